@@ -5,17 +5,32 @@
 //! - Channel throughput
 //! - Syscall overhead
 //! - Shared memory operations
+//! - Real-time channel round trip under load (validated against a p99 target)
 
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU64, AtomicU32, Ordering};
 use spin::RwLock;
 
+use helix_dis::ipc::{
+    ChannelId, Message as DisMessage, MessagePayload, MessageType, RtChannel, RtRecv,
+};
+use helix_dis::{Nanoseconds, TaskId};
+
 use crate::{
-    BenchmarkCategory, BenchmarkDef, BenchmarkId, BenchmarkSuite,
+    BenchmarkCategory, BenchmarkDef, BenchmarkId, BenchmarkResults, BenchmarkSuite,
     benchmark, timing,
 };
 
+/// p99 real-time channel round-trip target
+pub const RT_ROUNDTRIP_P99_TARGET_NS: u64 = 20_000;
+
+/// Real-time channel depth used by the round-trip benchmark
+const RT_DEPTH: usize = 16;
+
+/// Background messages kept queued ahead of each request
+const RT_LOAD: usize = RT_DEPTH / 2;
+
 // =============================================================================
 // Benchmark Registration
 // =============================================================================
@@ -72,6 +87,12 @@ pub fn register_benchmarks(suite: &BenchmarkSuite) {
         bench_channel_recv
     ));
     
+    suite.register(benchmark!(
+        "ipc.rt_channel.roundtrip",
+        BenchmarkCategory::Ipc,
+        bench_rt_channel_roundtrip
+    ));
+    
     // Syscall overhead
     suite.register(benchmark!(
         "ipc.syscall.null",
@@ -898,6 +919,69 @@ fn bench_semaphore_signal() -> u64 {
     end - start
 }
 
+// =============================================================================
+// Real-Time Channel Benchmarks
+// =============================================================================
+
+/// Request/reply round trip over real-time channels with a loaded queue
+///
+/// The client blocks on the reply channel (donating its priority to the
+/// server), sends a request behind `RT_LOAD` background messages, and
+/// wakes on the reply once the server has drained the backlog.
+fn bench_rt_channel_roundtrip() -> u64 {
+    const CLIENT: TaskId = TaskId::new(1);
+    const SERVER: TaskId = TaskId::new(2);
+    const CLIENT_PRIORITY: i16 = 100;
+    static REQUESTS: RtChannel = RtChannel::new(ChannelId::new(1), CLIENT, SERVER, RT_DEPTH);
+    static REPLIES: RtChannel = RtChannel::new(ChannelId::new(2), SERVER, CLIENT, RT_DEPTH);
+    
+    // Load: refill the background backlog
+    while REQUESTS.queued() < RT_LOAD {
+        let msg = DisMessage::signal(CLIENT, SERVER, 0);
+        if REQUESTS.send(CLIENT, msg, Nanoseconds(timing::read_tsc())).is_err() {
+            break;
+        }
+    }
+    
+    let start = timing::read_tsc();
+    
+    // Client blocks for the reply
+    let _ = REPLIES.receive(CLIENT, CLIENT_PRIORITY, Nanoseconds(start));
+    
+    // Request
+    let request = DisMessage::request(CLIENT, SERVER, MessagePayload::Integer(1));
+    let _ = REQUESTS.send(CLIENT, request, Nanoseconds(timing::read_tsc()));
+    
+    // Server drains the backlog up to the request
+    while let Ok(RtRecv::Message { msg, .. }) = REQUESTS.receive(SERVER, 0, Nanoseconds(timing::read_tsc())) {
+        if msg.msg_type == MessageType::Request {
+            break;
+        }
+        core::hint::black_box(msg);
+    }
+    
+    // Reply completes the rendezvous and wakes the client
+    let reply = DisMessage::new(SERVER, Some(CLIENT), MessageType::Response, MessagePayload::Empty);
+    let _ = REPLIES.send(SERVER, reply, Nanoseconds(timing::read_tsc()));
+    let woken = REPLIES.receive(CLIENT, CLIENT_PRIORITY, Nanoseconds(timing::read_tsc()));
+    
+    let end = timing::read_tsc();
+    core::hint::black_box(woken.is_ok());
+    end - start
+}
+
+/// Check `ipc.rt_channel.roundtrip` results against [`RT_ROUNDTRIP_P99_TARGET_NS`]
+///
+/// Marks the results failed if the p99 misses the target.
+pub fn validate_rt_roundtrip(results: &mut BenchmarkResults, cpu_freq_mhz: u64) -> bool {
+    let p99_ns = timing::cycles_to_ns(results.stats.p99, cpu_freq_mhz);
+    if p99_ns > RT_ROUNDTRIP_P99_TARGET_NS {
+        results.mark_failed("p99 real-time channel round trip above target");
+        return false;
+    }
+    true
+}
+
 // =============================================================================
 // Helper Types
 // =============================================================================
@@ -5,12 +5,21 @@
 //! - Page mapping/unmapping
 //! - Memory fragmentation
 //! - Cache effects
+//! - Last-level cache isolation under CAT partitioning
+//! - Heap hardening overhead (`hardening` feature)
 
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use alloc::vec;
+use core::alloc::Layout;
 use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
+use helix_memory::allocator::HeapAllocator;
+use helix_memory::allocator::slab::SlabAllocator;
+#[cfg(feature = "hardening")]
+use helix_memory::allocator::hardening::HardenedHeap;
+
 use crate::{
     BenchmarkCategory, BenchmarkDef, BenchmarkId, BenchmarkSuite,
     benchmark, timing,
@@ -129,6 +138,19 @@ pub fn register_benchmarks(suite: &BenchmarkSuite) {
         bench_stride_access
     ));
     
+    // LLC isolation: compare shared against partitioned
+    suite.register(benchmark!(
+        "mem.llc.shared",
+        BenchmarkCategory::Memory,
+        bench_llc_shared
+    ));
+    
+    suite.register(benchmark!(
+        "mem.llc.partitioned",
+        BenchmarkCategory::Memory,
+        bench_llc_partitioned
+    ));
+    
     // Allocator specific
     suite.register(benchmark!(
         "mem.allocator.buddy_split",
@@ -148,6 +170,20 @@ pub fn register_benchmarks(suite: &BenchmarkSuite) {
         bench_slab_alloc
     ));
     
+    // Real slab allocator: compare builds with and without `hardening`
+    suite.register(benchmark!(
+        "mem.allocator.slab_cycle",
+        BenchmarkCategory::Memory,
+        bench_slab_cycle
+    ));
+    
+    #[cfg(feature = "hardening")]
+    suite.register(benchmark!(
+        "mem.hardening.canary_cycle",
+        BenchmarkCategory::Memory,
+        bench_hardened_cycle
+    ));
+    
     // Region operations
     suite.register(benchmark!(
         "mem.region.create",
@@ -623,6 +659,112 @@ fn bench_stride_access() -> u64 {
     end - start
 }
 
+// =============================================================================
+// LLC Isolation Benchmarks
+// =============================================================================
+
+/// Latency-critical working set, small enough for half of any LLC
+const LLC_HOT_SET: usize = 256 * 1024;
+
+/// Batch working set streamed through the LLC
+const LLC_BATCH_SET: usize = 16 * 1024 * 1024;
+
+/// Cache line size assumed when touching buffers
+const LLC_LINE: usize = 64;
+
+/// Class of service of the latency-critical side
+const LLC_LATENCY_COS: u16 = 1;
+
+/// Class of service of the batch side
+const LLC_BATCH_COS: u16 = 2;
+
+/// Hot and batch working sets, allocated on first use
+static LLC_BUFFERS: spin::Once<(Vec<u8>, Vec<u8>)> = spin::Once::new();
+
+/// Read one byte per cache line
+fn touch_lines(buf: &[u8]) -> u64 {
+    let mut sum = 0u64;
+    let mut i = 0;
+    while i < buf.len() {
+        sum += unsafe { core::ptr::read_volatile(&buf[i]) } as u64;
+        i += LLC_LINE;
+    }
+    sum
+}
+
+/// Program disjoint halves of the LLC for the two classes of service
+///
+/// Returns false when the CPU cannot partition its LLC.
+#[cfg(target_arch = "x86_64")]
+fn llc_partition() -> bool {
+    use helix_hal::arch::x86_64::rdt;
+
+    let Some(cat) = helix_hal::cache::caches().cat() else {
+        return false;
+    };
+    if cat.max_cos < LLC_BATCH_COS || cat.cbm_len < 2 {
+        return false;
+    }
+    let batch_ways = cat.cbm_len / 2;
+    let batch_mask = (1u32 << batch_ways) - 1;
+    let latency_mask = cat.full_mask() & !batch_mask;
+    rdt::set_cos_mask(LLC_LATENCY_COS, latency_mask).is_ok()
+        && rdt::set_cos_mask(LLC_BATCH_COS, batch_mask).is_ok()
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn llc_partition() -> bool {
+    false
+}
+
+/// Switch the current CPU to a class of service
+fn llc_assign(cos: u16) {
+    #[cfg(target_arch = "x86_64")]
+    let _ = helix_hal::arch::x86_64::rdt::assign_cos(cos);
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = cos;
+}
+
+/// Time hot set accesses after a batch sweep over the LLC
+///
+/// With `partitioned`, the hot set and the sweep run in classes of service
+/// owning disjoint ways, so the sweep cannot evict the hot set and the
+/// measured time stays at LLC hit latency. Without CAT both runs share the
+/// cache and report the same miss-dominated time.
+fn llc_hot_after_sweep(partitioned: bool) -> u64 {
+    let (hot, batch) = LLC_BUFFERS.call_once(|| (vec![1u8; LLC_HOT_SET], vec![1u8; LLC_BATCH_SET]));
+    let partitioned = partitioned && llc_partition();
+    let (latency_cos, batch_cos) = if partitioned {
+        (LLC_LATENCY_COS, LLC_BATCH_COS)
+    } else {
+        (0, 0)
+    };
+
+    llc_assign(latency_cos);
+    core::hint::black_box(touch_lines(hot));
+    llc_assign(batch_cos);
+    core::hint::black_box(touch_lines(batch));
+    llc_assign(latency_cos);
+
+    let start = timing::read_tsc();
+    let sum = touch_lines(hot);
+    let end = timing::read_tsc();
+
+    llc_assign(0);
+    core::hint::black_box(sum);
+    end - start
+}
+
+/// Hot set latency with the batch sweep sharing the whole LLC
+fn bench_llc_shared() -> u64 {
+    llc_hot_after_sweep(false)
+}
+
+/// Hot set latency with the batch sweep confined to its own LLC ways
+fn bench_llc_partitioned() -> u64 {
+    llc_hot_after_sweep(true)
+}
+
 // =============================================================================
 // Allocator-Specific Benchmarks
 // =============================================================================
@@ -712,6 +854,68 @@ fn bench_slab_alloc() -> u64 {
     end - start
 }
 
+/// Size of the slab benchmark arena
+const SLAB_ARENA_SIZE: usize = 64 * 1024;
+
+/// Page-aligned backing memory for the slab benchmarks
+#[repr(align(4096))]
+struct SlabArena([u8; SLAB_ARENA_SIZE]);
+
+static mut SLAB_ARENA: SlabArena = SlabArena([0; SLAB_ARENA_SIZE]);
+
+/// Slab allocator over the benchmark arena, set up on first use
+fn bench_slab() -> Arc<SlabAllocator> {
+    static SLAB: spin::Once<Arc<SlabAllocator>> = spin::Once::new();
+    
+    SLAB.call_once(|| {
+        let slab = SlabAllocator::new();
+        // SAFETY: The arena is handed to this allocator only, once
+        let arena = unsafe { core::ptr::addr_of_mut!(SLAB_ARENA.0) };
+        slab.init(arena as *mut u8, SLAB_ARENA_SIZE);
+        Arc::new(slab)
+    }).clone()
+}
+
+/// Allocate and free a 64-byte object through the slab allocator
+///
+/// With `hardening` this includes freelist decoding and the double-free
+/// bitmap, so comparing builds gives the slab hardening overhead.
+fn bench_slab_cycle() -> u64 {
+    let slab = bench_slab();
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    
+    let start = timing::read_tsc();
+    
+    let ptr = slab.allocate(layout);
+    if !ptr.is_null() {
+        slab.deallocate(ptr, layout);
+    }
+    
+    let end = timing::read_tsc();
+    end - start
+}
+
+/// Allocate and free a 64-byte object through the hardened heap
+///
+/// The difference to `mem.allocator.slab_cycle` is the cost of the
+/// header, canaries and free checks.
+#[cfg(feature = "hardening")]
+fn bench_hardened_cycle() -> u64 {
+    static HEAP: spin::Once<HardenedHeap> = spin::Once::new();
+    let heap = HEAP.call_once(|| HardenedHeap::new(bench_slab()));
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    
+    let start = timing::read_tsc();
+    
+    let ptr = heap.allocate(layout);
+    if !ptr.is_null() {
+        heap.deallocate(ptr, layout);
+    }
+    
+    let end = timing::read_tsc();
+    end - start
+}
+
 // =============================================================================
 // Region Benchmarks
 // =============================================================================
@@ -6,16 +6,23 @@
 //! - Priority scheduling
 //! - Preemption latency
 //! - Multi-core load balancing
+//! - Wakeup-to-run latency (validated against a p99 target)
 
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU64, AtomicU32, Ordering};
 
+use helix_execution::scheduler::trace::{SchedStatsSummary, SchedTrace};
+use helix_execution::ThreadId;
+
 use crate::{
-    BenchmarkCategory, BenchmarkDef, BenchmarkId, BenchmarkSuite,
+    BenchmarkCategory, BenchmarkDef, BenchmarkId, BenchmarkResults, BenchmarkSuite,
     benchmark, measure, timing,
 };
 
+/// p99 wakeup-to-run latency target
+pub const WAKEUP_P99_TARGET_NS: u64 = 50_000;
+
 // =============================================================================
 // Benchmark Registration
 // =============================================================================
@@ -99,6 +106,19 @@ pub fn register_benchmarks(suite: &BenchmarkSuite) {
         bench_preemption_latency
     ));
     
+    // Wakeup latency
+    suite.register(benchmark!(
+        "sched.wakeup.latency",
+        BenchmarkCategory::Scheduler,
+        bench_wakeup_latency
+    ));
+
+    suite.register(benchmark!(
+        "sched.trace.record",
+        BenchmarkCategory::Scheduler,
+        bench_trace_record
+    ));
+    
     // Tick handling
     suite.register(benchmark!(
         "sched.tick.handler",
@@ -493,6 +513,74 @@ fn bench_preemption_latency() -> u64 {
     end - start
 }
 
+// =============================================================================
+// Wakeup Latency Benchmarks
+// =============================================================================
+
+/// Trace collector private to the benchmarks (clock in cycles)
+static BENCH_TRACE: SchedTrace = SchedTrace::new();
+
+/// Wakeup to run: mark ready, enqueue, pick, dequeue
+fn bench_wakeup_latency() -> u64 {
+    static QUEUE: spin::Mutex<[u64; 64]> = spin::Mutex::new([0; 64]);
+    static DEPTH: AtomicU32 = AtomicU32::new(0);
+    BENCH_TRACE.set_clock(timing::read_tsc);
+
+    let id = ThreadId::idle();
+    let start = timing::read_tsc();
+
+    // Wakeup: record and enqueue
+    BENCH_TRACE.record_wakeup(id);
+    let pos = DEPTH.fetch_add(1, Ordering::SeqCst) as usize % 64;
+    QUEUE.lock()[pos] = id.as_u64();
+
+    // Pick: dequeue and record
+    let depth = DEPTH.fetch_sub(1, Ordering::SeqCst) as usize - 1;
+    let next = QUEUE.lock()[depth % 64];
+    BENCH_TRACE.record_pick(0, id, Some(depth));
+
+    let end = timing::read_tsc();
+    core::hint::black_box(next);
+    end - start
+}
+
+/// Tracing overhead on the wakeup and pick paths
+fn bench_trace_record() -> u64 {
+    static TRACE: SchedTrace = SchedTrace::new();
+    TRACE.set_clock(timing::read_tsc);
+
+    let id = ThreadId::idle();
+    measure!({
+        TRACE.record_wakeup(id);
+        TRACE.record_pick(0, id, Some(1));
+    })
+}
+
+/// Check `sched.wakeup.latency` results against [`WAKEUP_P99_TARGET_NS`]
+///
+/// Marks the results failed if the p99 misses the target.
+pub fn validate_wakeup_latency(results: &mut BenchmarkResults, cpu_freq_mhz: u64) -> bool {
+    let p99_ns = timing::cycles_to_ns(results.stats.p99, cpu_freq_mhz);
+    if p99_ns > WAKEUP_P99_TARGET_NS {
+        results.mark_failed("p99 wakeup latency above target");
+        return false;
+    }
+    true
+}
+
+/// Check live scheduler tracing (`/proc/sched_stats`) against `target_ns`
+///
+/// Returns the summary, as `Err` if its p99 wakeup latency misses the
+/// target.
+pub fn validate_kernel_wakeup_latency(target_ns: u64) -> Result<SchedStatsSummary, SchedStatsSummary> {
+    let summary = helix_execution::scheduler::framework().trace().summary();
+    if summary.wakeup_p99_ns > target_ns {
+        Err(summary)
+    } else {
+        Ok(summary)
+    }
+}
+
 // =============================================================================
 // Tick Handler Benchmarks
 // =============================================================================
@@ -298,6 +298,20 @@ pub fn boot_timestamp(&self) -> Option<i64> {
         self.boot_time.map(|t| t.timestamp())
     }
 
+    /// Get the kernel command line, validated against the standard
+    /// kernel parameters
+    #[cfg(feature = "cmdline")]
+    pub fn parsed_cmdline(&self) -> Result<helix_cmdline::Cmdline<'static>, helix_cmdline::CmdlineError> {
+        let raw = self.kernel_file
+            .and_then(|kf| kf.file())
+            .map(|file| file.cmdline_static())
+            .unwrap_or("");
+
+        let cmdline = helix_cmdline::Cmdline::new(raw);
+        helix_cmdline::boot_registry().validate(&cmdline)?;
+        Ok(cmdline)
+    }
+
     // =========================================================================
     // Validation
     // =========================================================================
@@ -271,6 +271,15 @@ pub fn cmdline(&self) -> Option<&str> {
         }
     }
 
+    /// Get the command line, validated against the standard kernel
+    /// parameters
+    #[cfg(feature = "cmdline")]
+    pub fn parsed_cmdline(&self) -> Result<helix_cmdline::Cmdline<'_>, helix_cmdline::CmdlineError> {
+        let cmdline = helix_cmdline::Cmdline::new(self.cmdline().unwrap_or(""));
+        helix_cmdline::boot_registry().validate(&cmdline)?;
+        Ok(cmdline)
+    }
+
     /// Get the bootloader name
     #[must_use]
     pub fn bootloader_name(&self) -> Option<&str> {
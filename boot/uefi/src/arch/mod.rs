@@ -141,16 +141,40 @@ pub trait ArchOperations {
 /// Common CPU feature flags
 #[derive(Debug, Clone, Copy, Default)]
 pub struct CpuFeatures {
+    /// x87 FPU
+    pub fpu: bool,
+    /// Long mode (64-bit)
+    pub long_mode: bool,
     /// SSE support
     pub sse: bool,
     /// SSE2 support
     pub sse2: bool,
     /// SSE3 support
     pub sse3: bool,
+    /// SSSE3 support
+    pub ssse3: bool,
     /// SSE4.1 support
     pub sse4_1: bool,
     /// SSE4.2 support
     pub sse4_2: bool,
+    /// POPCNT instruction
+    pub popcnt: bool,
+    /// CMPXCHG16B instruction
+    pub cx16: bool,
+    /// LAHF/SAHF in long mode
+    pub lahf_lm: bool,
+    /// FMA3 support
+    pub fma: bool,
+    /// BMI1 support
+    pub bmi1: bool,
+    /// BMI2 support
+    pub bmi2: bool,
+    /// LZCNT instruction
+    pub lzcnt: bool,
+    /// MOVBE instruction
+    pub movbe: bool,
+    /// F16C (half-precision conversion)
+    pub f16c: bool,
     /// AVX support
     pub avx: bool,
     /// AVX2 support
@@ -213,6 +237,51 @@ pub fn detect() -> Self {
     pub fn detect() -> Self {
         Self::default()
     }
+
+    /// Convert to the feature set used by system information and validation
+    pub fn flags(&self) -> crate::sysinfo::CpuFeatures {
+        use crate::sysinfo::CpuFeatures as F;
+
+        let mut flags = F::NONE;
+        for (present, flag) in [
+            (self.fpu, F::FPU),
+            (self.long_mode, F::LONG_MODE),
+            (self.sse, F::SSE),
+            (self.sse2, F::SSE2),
+            (self.sse3, F::SSE3),
+            (self.ssse3, F::SSSE3),
+            (self.sse4_1, F::SSE4_1),
+            (self.sse4_2, F::SSE4_2),
+            (self.popcnt, F::POPCNT),
+            (self.cx16, F::CX16),
+            (self.lahf_lm, F::LAHF_LM),
+            (self.avx, F::AVX),
+            (self.avx2, F::AVX2),
+            (self.avx512, F::AVX512F),
+            (self.fma, F::FMA),
+            (self.bmi1, F::BMI1),
+            (self.bmi2, F::BMI2),
+            (self.lzcnt, F::LZCNT),
+            (self.movbe, F::MOVBE),
+            (self.f16c, F::F16C),
+            (self.aes, F::AES_NI),
+            (self.sha, F::SHA),
+            (self.rdrand, F::RDRAND),
+            (self.rdseed, F::RDSEED),
+            (self.tsc, F::TSC),
+            (self.tsc_invariant, F::INVARIANT_TSC),
+            (self.nx, F::NX),
+            (self.smep, F::SMEP),
+            (self.smap, F::SMAP),
+            (self.xsave, F::XSAVE),
+            (self.x2apic, F::X2APIC),
+        ] {
+            if present {
+                flags.set(flag);
+            }
+        }
+        flags
+    }
 }
 
 // =============================================================================
@@ -300,6 +300,12 @@ mod feature7_edx {
     pub const SSBD: u32 = 1 << 31;
 }
 
+/// Feature bits in CPUID.0x80000001.ECX
+mod ext_feature_ecx {
+    pub const LAHF_LM: u32 = 1 << 0;
+    pub const LZCNT: u32 = 1 << 5;
+}
+
 /// Feature bits in CPUID.0x80000001.EDX
 mod ext_feature_edx {
     pub const SYSCALL: u32 = 1 << 11;
@@ -328,8 +334,14 @@ pub fn detect_features() -> CpuFeatures {
 
         // ECX features
         features.sse3 = (result.ecx & feature_ecx::SSE3) != 0;
+        features.ssse3 = (result.ecx & feature_ecx::SSSE3) != 0;
         features.sse4_1 = (result.ecx & feature_ecx::SSE4_1) != 0;
         features.sse4_2 = (result.ecx & feature_ecx::SSE4_2) != 0;
+        features.popcnt = (result.ecx & feature_ecx::POPCNT) != 0;
+        features.cx16 = (result.ecx & feature_ecx::CMPXCHG16B) != 0;
+        features.fma = (result.ecx & feature_ecx::FMA) != 0;
+        features.movbe = (result.ecx & feature_ecx::MOVBE) != 0;
+        features.f16c = (result.ecx & feature_ecx::F16C) != 0;
         features.aes = (result.ecx & feature_ecx::AES) != 0;
         features.xsave = (result.ecx & feature_ecx::XSAVE) != 0;
         features.avx = (result.ecx & feature_ecx::AVX) != 0;
@@ -338,6 +350,7 @@ pub fn detect_features() -> CpuFeatures {
         features.pcid = (result.ecx & feature_ecx::PCID) != 0;
 
         // EDX features
+        features.fpu = (result.edx & feature_edx::FPU) != 0;
         features.tsc = (result.edx & feature_edx::TSC) != 0;
         features.sse = (result.edx & feature_edx::SSE) != 0;
         features.sse2 = (result.edx & feature_edx::SSE2) != 0;
@@ -350,6 +363,8 @@ pub fn detect_features() -> CpuFeatures {
         // EBX features
         features.fsgsbase = (result.ebx & feature7_ebx::FSGSBASE) != 0;
         features.avx2 = (result.ebx & feature7_ebx::AVX2) != 0;
+        features.bmi1 = (result.ebx & feature7_ebx::BMI1) != 0;
+        features.bmi2 = (result.ebx & feature7_ebx::BMI2) != 0;
         features.smep = (result.ebx & feature7_ebx::SMEP) != 0;
         features.smap = (result.ebx & feature7_ebx::SMAP) != 0;
         features.avx512 = (result.ebx & feature7_ebx::AVX512F) != 0;
@@ -370,6 +385,9 @@ pub fn detect_features() -> CpuFeatures {
     if max_extended >= cpuid_leaf::EXTENDED_INFO {
         let result = cpuid(cpuid_leaf::EXTENDED_INFO, 0);
         features.nx = (result.edx & ext_feature_edx::NX) != 0;
+        features.long_mode = (result.edx & ext_feature_edx::LM) != 0;
+        features.lahf_lm = (result.ecx & ext_feature_ecx::LAHF_LM) != 0;
+        features.lzcnt = (result.ecx & ext_feature_ecx::LZCNT) != 0;
         features.page_1gb = (result.edx & ext_feature_edx::PAGE1GB) != 0;
     }
 
@@ -487,6 +487,48 @@ pub trait PageTableAllocator {
     fn deallocate(&mut self, addr: PhysicalAddress);
 }
 
+/// Page table allocator handing out pages from a reserved range
+///
+/// Needs no firmware services, so tables can still be built after
+/// ExitBootServices. Pages are never returned to the pool.
+pub struct PageTablePool {
+    /// Next free page
+    next: PhysicalAddress,
+    /// End of the range
+    end: PhysicalAddress,
+    /// Start of the range
+    base: PhysicalAddress,
+}
+
+impl PageTablePool {
+    /// Pool over `pages` pages at page-aligned `base`
+    pub fn new(base: PhysicalAddress, pages: u64) -> Self {
+        Self {
+            next: base,
+            end: base + pages * PAGE_SIZE,
+            base,
+        }
+    }
+
+    /// Pages handed out so far
+    pub fn used(&self) -> u64 {
+        (self.next.0 - self.base.0) / PAGE_SIZE
+    }
+}
+
+impl PageTableAllocator for PageTablePool {
+    fn allocate(&mut self) -> Result<PhysicalAddress> {
+        if self.next >= self.end {
+            return Err(Error::OutOfResources);
+        }
+        let page = self.next;
+        self.next += PAGE_SIZE;
+        Ok(page)
+    }
+
+    fn deallocate(&mut self, _addr: PhysicalAddress) {}
+}
+
 /// Page table builder
 pub struct PageTableBuilder<A: PageTableAllocator> {
     /// Page table allocator
@@ -497,6 +539,8 @@ pub struct PageTableBuilder<A: PageTableAllocator> {
     root: PhysicalAddress,
     /// Use 5-level paging
     level5: bool,
+    /// Let `map_range` use 1GB pages
+    huge_pages: bool,
 }
 
 impl<A: PageTableAllocator> PageTableBuilder<A> {
@@ -515,9 +559,15 @@ pub fn new(mut allocator: A, phys_offset: u64) -> Result<Self> {
             phys_offset,
             root,
             level5: false,
+            huge_pages: true,
         })
     }
 
+    /// Allow `map_range` to use 1GB pages (CPUs without PDPE1GB fault on them)
+    pub fn set_huge_pages(&mut self, enabled: bool) {
+        self.huge_pages = enabled;
+    }
+
     /// Get root table address
     pub fn root(&self) -> PhysicalAddress {
         self.root
@@ -616,7 +666,8 @@ pub fn map_range(&mut self, virt: VirtualAddress, phys: PhysicalAddress, size: u
 
         while remaining > 0 {
             // Try 1GB page
-            if remaining >= HUGE_PAGE_SIZE &&
+            if self.huge_pages &&
+               remaining >= HUGE_PAGE_SIZE &&
                (virt & (HUGE_PAGE_SIZE - 1)) == 0 &&
                (phys & (HUGE_PAGE_SIZE - 1)) == 0 {
                 self.map_1g(virt, phys, entry_flags)?;
@@ -698,4 +749,27 @@ fn test_virtual_address_decomposition() {
         let reconstructed = components.to_address();
         assert_eq!(reconstructed & 0xFFFF_FFFF_FFFF_F000, addr & 0xFFFF_FFFF_FFFF_F000);
     }
+
+    #[test]
+    fn test_pool_builder() {
+        let tables: alloc::vec::Vec<PageTable> = (0..5).map(|_| PageTable::default()).collect();
+        let pool = PageTablePool::new(PhysicalAddress(tables.as_ptr() as u64), 5);
+        let mut builder = PageTableBuilder::new(pool, 0).unwrap();
+        builder.set_huge_pages(false);
+
+        // 2MB pages only: PML4, PDPT and one PD
+        let virt = VirtualAddress(0xFFFF_8000_0000_0000);
+        builder.map_range(virt, PhysicalAddress(0), HUGE_PAGE_SIZE, flags::KERNEL_DATA).unwrap();
+        assert_eq!(builder.allocator.used(), 3);
+
+        let walker = PageTableWalker::new(0);
+        let phys = walker.translate(builder.root(), virt + 0x1234_5678);
+        assert_eq!(phys, Some(PhysicalAddress(0x1234_5678)));
+
+        // A 4KB page in the next 1GB needs a PD and a PT, exhausting the pool
+        builder.map_4k(virt + HUGE_PAGE_SIZE, PhysicalAddress(0x5000), flags::KERNEL_CODE).unwrap();
+        assert_eq!(builder.allocator.used(), 5);
+        let result = builder.map_4k(VirtualAddress(0x1000), PhysicalAddress(0x1000), flags::KERNEL_CODE);
+        assert!(matches!(result, Err(Error::OutOfResources)));
+    }
 }
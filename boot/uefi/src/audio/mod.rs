@@ -28,6 +28,7 @@
 //! - Tone generation
 //! - BEEP code patterns
 //! - Boot chime sequences
+//! - Menu navigation sounds
 
 #![no_std]
 
@@ -618,6 +619,24 @@ fn noise_sample(&mut self) -> f32 {
         ((self.noise_state & 0xFFFF) as f32 / 32768.0) - 1.0
     }
 
+    /// Generate a tone sequence back to back; returns samples written
+    pub fn generate_sequence(&mut self, tones: &[Tone], buffer: &mut [i16]) -> usize {
+        let mut written = 0;
+
+        for tone in tones {
+            let num_samples = ((self.sample_rate as u64 * tone.duration_ms as u64) / 1000) as usize;
+            let end = buffer.len().min(written + num_samples);
+            if tone.is_rest() {
+                buffer[written..end].fill(0);
+                written = end;
+            } else {
+                written += self.generate(tone, &mut buffer[written..end]);
+            }
+        }
+
+        written
+    }
+
     /// Reset phase
     pub fn reset(&mut self) {
         self.phase = 0.0;
@@ -785,6 +804,70 @@ pub fn duration_ms(&self) -> u32 {
     }
 }
 
+/// Menu navigation feedback sounds
+///
+/// Kept short so they never lag behind key repeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavSound {
+    /// Selection moved
+    Move,
+    /// Selection already at the first or last entry
+    Boundary,
+    /// Entry chosen
+    Select,
+    /// Dialog or editor left
+    Back,
+    /// Option switched on
+    On,
+    /// Option switched off
+    Off,
+}
+
+static NAV_MOVE: [Tone; 1] = [Tone::new(notes::C5, 30)];
+static NAV_BOUNDARY: [Tone; 1] = [Tone::new(notes::C4, 80)];
+static NAV_SELECT: [Tone; 2] = [
+    Tone::new(notes::C5, 40),
+    Tone::new(notes::E5, 60),
+];
+static NAV_BACK: [Tone; 2] = [
+    Tone::new(notes::E5, 40),
+    Tone::new(notes::C5, 60),
+];
+static NAV_ON: [Tone; 3] = [
+    Tone::new(notes::G4, 40),
+    Tone::rest(20),
+    Tone::new(notes::C5, 40),
+];
+static NAV_OFF: [Tone; 3] = [
+    Tone::new(notes::C5, 40),
+    Tone::rest(20),
+    Tone::new(notes::G4, 40),
+];
+
+impl NavSound {
+    /// Get tone sequence for the sound
+    pub fn tones(&self) -> &'static [Tone] {
+        match self {
+            NavSound::Move => &NAV_MOVE,
+            NavSound::Boundary => &NAV_BOUNDARY,
+            NavSound::Select => &NAV_SELECT,
+            NavSound::Back => &NAV_BACK,
+            NavSound::On => &NAV_ON,
+            NavSound::Off => &NAV_OFF,
+        }
+    }
+
+    /// Sound for an option that was just switched
+    pub const fn toggle(enabled: bool) -> Self {
+        if enabled { NavSound::On } else { NavSound::Off }
+    }
+
+    /// Get total duration in milliseconds
+    pub fn duration_ms(&self) -> u32 {
+        self.tones().iter().map(|t| t.duration_ms).sum()
+    }
+}
+
 // =============================================================================
 // HD AUDIO CONTROLLER
 // =============================================================================
@@ -1260,6 +1343,30 @@ fn test_beep_code_duration() {
         assert_eq!(warning.duration_ms(), 300); // 100 + 100 + 100
     }
 
+    #[test]
+    fn test_nav_sounds() {
+        assert_eq!(NavSound::Move.duration_ms(), 30);
+        assert_eq!(NavSound::toggle(true), NavSound::On);
+        assert_eq!(NavSound::toggle(false), NavSound::Off);
+        assert!(NavSound::On.tones()[0].frequency < NavSound::On.tones()[2].frequency);
+    }
+
+    #[test]
+    fn test_generate_sequence() {
+        let mut generator = ToneGenerator::new(8000);
+        let mut buffer = [1i16; 1200];
+
+        // 40 ms + 20 ms rest + 40 ms at 8 kHz
+        let written = generator.generate_sequence(NavSound::On.tones(), &mut buffer);
+        assert_eq!(written, 800);
+        assert!(buffer[320..480].iter().all(|&s| s == 0));
+        assert_eq!(buffer[800], 1);
+
+        // Truncated to the buffer
+        let mut short = [0i16; 100];
+        assert_eq!(generator.generate_sequence(NavSound::On.tones(), &mut short), 100);
+    }
+
     #[test]
     fn test_frequency_to_divisor() {
         // 440 Hz should give approximately 2712
@@ -14,6 +14,7 @@
 
 extern crate alloc;
 
+use core::fmt::{self, Write};
 use core::panic::PanicInfo;
 use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
@@ -23,22 +24,48 @@
 use helix_uefi::error::{Error, Result};
 use helix_uefi::services::boot::BootServices;
 use helix_uefi::services::runtime::RuntimeServices;
-use helix_uefi::services::console::Console;
+use helix_uefi::console::{Console, SimpleTextInput, SimpleTextOutput};
 use helix_uefi::protocols::file::{FileSystem, File, FileMode, FileAttribute};
 use helix_uefi::protocols::graphics::GraphicsOutput;
 use helix_uefi::memory::allocator::UefiAllocator;
 use helix_uefi::memory::map::{MemoryMap, MemoryDescriptor};
 use helix_uefi::loader::elf::ElfLoader;
 use helix_uefi::loader::image::KernelImage;
-use helix_uefi::handoff::bootinfo::{BootInfo, BootInfoHeader, TlsTemplate};
-use helix_uefi::handoff::framebuffer::{FramebufferInfo, PixelFormat};
-use helix_uefi::handoff::memory_map::{MemoryMap as HandoffMemoryMap, MemoryType as HandoffMemoryType};
-use helix_uefi::handoff::modules::{ModuleList, ModuleInfo, ModuleType};
+use helix_uefi::loader::{KernelLoader, KernelSegment};
+use helix_uefi::handoff::bootinfo::{BootInfo, BootInfoHeader, MeasurementLog, TlsTemplate};
+use helix_uefi::handoff::framebuffer::{DisplayInfo, FramebufferInfo, PixelFormat};
+use helix_uefi::handoff::memory_map::{
+    sanitize, MemoryAttributes, MemoryMap as HandoffMemoryMap, MemoryMapEntry, MemoryMapEntryRaw,
+    MemoryType as HandoffMemoryType, Reservation,
+};
+use helix_uefi::handoff::modules::{ModuleBuilder, ModuleFlags, ModuleList, ModuleInfo, ModuleType};
 use helix_uefi::handoff::rsdp::{RsdpInfo, AcpiTableFinder};
-use helix_uefi::tables::acpi::AcpiTables;
+use helix_uefi::handoff::smp::{SmpInfo, TRAMPOLINE_LIMIT, TRAMPOLINE_SIZE};
+use helix_uefi::tables::acpi::{AcpiParser, AcpiTables};
 use helix_uefi::tables::smbios::SmbiosTables;
 use helix_uefi::tables::config::ConfigurationTable;
+#[cfg(feature = "dtb")]
+use helix_uefi::tables::dtb::{self, DeviceTree};
 use helix_uefi::arch::{Architecture, CpuFeatures, MemoryModel, PlatformInit};
+#[cfg(target_arch = "x86_64")]
+use helix_uefi::arch::x86_64::paging::{flags, PageTableBuilder, PageTablePool, HUGE_PAGE_SIZE, PAGE_SIZE};
+use helix_uefi::raw::memory::MemoryType;
+use helix_uefi::protocols::tcg2::Tcg2;
+use helix_uefi::security::secureboot::{self, SecureBootResult, SecureBootState};
+use helix_uefi::config::{BootConfig as ConfigFile, BootEntry as MenuEntry};
+use helix_uefi::cmdline;
+use helix_uefi::crypto::RandomSource;
+use helix_uefi::crypto::selftest::{self, ReadEntropy};
+use helix_uefi::locale::Language;
+use helix_uefi::protocols::rng::Rng;
+use helix_uefi::recovery::{codes, ErrorScreen};
+use helix_uefi::debug::boot_log::{self, BootLog};
+use helix_uefi::debug::{self, LogLevel, LoggerConfig};
+use helix_uefi::{log_error, log_fatal, log_info, log_warn};
+use helix_uefi::menu::{BootMenu, MenuResult};
+use helix_uefi::security::tpm::{BootComponents, MeasuredBoot};
+use helix_uefi::sysinfo::{FirmwareTables, SystemSummary};
+use helix_uefi::validate::{HardwareRequirements, MicroarchLevel};
 
 // =============================================================================
 // CONSTANTS
@@ -73,6 +100,9 @@
 /// Physical memory map base
 pub const PHYS_MAP_BASE: u64 = 0xFFFF_8000_0000_0000;
 
+/// Framebuffer mapping, above the KASLR window
+pub const FRAMEBUFFER_VIRT: u64 = 0xFFFF_FFFF_E000_0000;
+
 /// Stack size (256 KiB)
 pub const KERNEL_STACK_SIZE: u64 = 256 * 1024;
 
@@ -101,6 +131,8 @@ pub extern "efiapi" fn efi_main(
 ) -> EfiStatus {
     // Store system table
     unsafe { SYSTEM_TABLE = Some(system_table); }
+    // Protocol enumeration (disk discovery) goes through the services layer
+    let _ = unsafe { helix_uefi::services::initialize(image_handle, system_table) };
 
     // Record boot timestamp
     #[cfg(target_arch = "x86_64")]
@@ -109,20 +141,22 @@ pub extern "efiapi" fn efi_main(
         BOOT_TIMESTAMP.store(tsc, Ordering::Relaxed);
     }
 
+    // Log to serial, the firmware console and the ring handed to the kernel
+    unsafe {
+        debug::init(LoggerConfig::new(), timestamp_frequency());
+        if !system_table.is_null() && !(*system_table).con_out.is_null() {
+            debug::attach_console((*system_table).con_out as *mut SimpleTextOutput);
+        }
+    }
+
     // Initialize and run boot process
     match boot_main(image_handle, system_table) {
         Ok(()) => EFI_SUCCESS,
         Err(e) => {
-            // Try to print error if console is available
-            unsafe {
-                if let Some(st) = SYSTEM_TABLE {
-                    let st = &*st;
-                    if !st.con_out.is_null() {
-                        // Print error (simplified)
-                        let _ = print_error(st, e);
-                    }
-                }
-            }
+            // Boot services are still up if the log was not finished yet;
+            // the console was detached otherwise
+            log_fatal!("boot failed: {:?}", e);
+            let _ = boot_log::finish();
             e.to_status()
         }
     }
@@ -143,59 +177,119 @@ fn boot_main(image_handle: EfiHandle, system_table: *mut EfiSystemTable) -> Resu
     }
     let _bs = unsafe { &*st.boot_services };
 
-    // Clear screen and print banner
-    print_banner(st)?;
+    // Clear screen and announce the loader
+    let _ = unsafe { ((*st.con_out).clear_screen)(st.con_out) };
+    log_info!("{} {}.{}.{}", BOOTLOADER_NAME, VERSION_MAJOR, VERSION_MINOR, VERSION_PATCH);
 
     // Early hardware initialization
     early_init()?;
 
+    // Nothing is verified, measured or randomized before the crypto passes
+    let _ = boot_log::phase("self-test");
+    crypto_self_test(st)?;
+
     // Load configuration
-    let config = load_config(image_handle, st)?;
+    let mut config = load_config(image_handle, st)?;
+    debug::set_console_level(if config.verbose { LogLevel::Trace } else { LogLevel::Warn });
+    let lockdown = apply_lockdown(st, &mut config);
+    log_info!(
+        "kernel {} cmdline \"{}\"",
+        core::str::from_utf8(&config.kernel_path[..config.kernel_path_len]).unwrap_or("?"),
+        core::str::from_utf8(&config.cmdline[..config.cmdline_len]).unwrap_or("?"),
+    );
 
     // Detect CPU features
+    let _ = boot_log::phase("hardware");
     let cpu_features = detect_cpu_features();
     print_cpu_info(st, &cpu_features)?;
 
     // Initialize graphics
-    let framebuffer = init_graphics(st)?;
+    let (framebuffer, mut display) = init_graphics(image_handle, st)?;
 
     // Find ACPI tables
     let acpi_info = find_acpi_tables(st)?;
     print_acpi_info(st, &acpi_info)?;
+    let acpi_tables = parse_acpi_tables(&acpi_info);
+    if let Some(serial) = acpi_tables.as_ref().and_then(|acpi| acpi.spcr()).and_then(|spcr| spcr.serial()) {
+        // The UART firmware redirects its console to (the PL011 on AArch64)
+        debug::set_serial(serial);
+    }
+    apply_panel_orientation(acpi_tables.as_ref(), &mut display);
+
+    // Enumerate CPUs and reserve the AP trampoline
+    let smp = find_cpus(st, acpi_tables.as_ref());
 
     // Find SMBIOS tables
     let smbios_addr = find_smbios_tables(st);
 
+    // Refuse to boot on hardware the kernel cannot run on
+    check_compatibility(st, &cpu_features, smbios_addr)?;
+
     // Load kernel
+    let _ = boot_log::phase("kernel");
     let kernel = load_kernel(image_handle, st, &config)?;
     print_kernel_info(st, &kernel)?;
 
     // Load modules (initrd, etc.)
     let modules = load_modules(image_handle, st, &config)?;
 
+    // Device tree for the kernel (AArch64)
+    let device_tree = load_device_tree(image_handle, st, &config)?;
+
+    // Measure what is about to run into PCRs 8 and 9
+    let _ = boot_log::phase("measure");
+    let measurements = measure_boot(st, &kernel, &modules, &config, lockdown)?;
+
+    // The ESP and the firmware console go away with boot services
+    let _ = boot_log::phase("exit boot services");
+    let _ = boot_log::finish();
+    debug::detach_console();
+
     // Get memory map and exit boot services
-    let (memory_map, runtime_services) = exit_boot_services(image_handle, st)?;
+    let (mut memory_map, runtime_services) = exit_boot_services(image_handle, st)?;
+
+    // Room for the boot info and final memory map, carved before paging so
+    // it is identity mapped with the rest of the loader's data
+    let handoff = reserve_handoff_region(&mut memory_map, &kernel, &modules)?;
 
     // Set up page tables
-    let page_tables = setup_paging(&kernel, &memory_map, &framebuffer)?;
+    let page_tables = setup_paging(&kernel, &mut memory_map, &framebuffer, &cpu_features)?;
 
     // Allocate kernel stack
     let kernel_stack = allocate_kernel_stack(&memory_map)?;
 
+    // Final memory map, with everything the loader placed kept out of it
+    let reservations = loader_reservations(
+        &memory_map,
+        &kernel,
+        &modules,
+        &page_tables,
+        &handoff,
+        smp.as_ref(),
+        &measurements,
+    );
+    let memory_map = sanitized_memory_map(&memory_map, &reservations);
+
     // Build boot info structure
     let boot_info = build_boot_info(
         &framebuffer,
         &memory_map,
+        &handoff,
         &acpi_info,
         smbios_addr,
         &kernel,
         &modules,
         kernel_stack,
         &config,
+        measurements,
+        device_tree,
+        display,
+        smp,
     )?;
 
     // Hand off to kernel
-    handoff_to_kernel(&kernel, &boot_info, kernel_stack, page_tables)?;
+    let boot_info = handoff.place(boot_info);
+    handoff_to_kernel(&kernel, boot_info, kernel_stack, page_tables)?;
 
     // Should never reach here
     unreachable!()
@@ -220,6 +314,56 @@ fn early_init() -> Result<()> {
     Ok(())
 }
 
+/// Run the crypto power-on self-tests
+///
+/// The entropy sources tested are the firmware RNG and RDRAND, where
+/// present. A failure is shown on the recovery screen and stops the boot.
+fn crypto_self_test(st: &EfiSystemTable) -> Result<()> {
+    let firmware = unsafe { Rng::locate(&*(st.boot_services as *const _)) }.ok();
+    let mut read_firmware =
+        |buf: &mut [u8]| firmware.as_ref().is_some_and(|rng| rng.get_bytes(buf).is_ok());
+    #[cfg(target_arch = "x86_64")]
+    let mut read_cpu = read_rdrand;
+
+    let mut sources: Vec<(RandomSource, ReadEntropy<'_>)> = Vec::new();
+    if firmware.is_some() {
+        sources.push((RandomSource::UefiProtocol, &mut read_firmware));
+    }
+    #[cfg(target_arch = "x86_64")]
+    if detect_cpu_features().rdrand {
+        sources.push((RandomSource::Hardware, &mut read_cpu));
+    }
+
+    let Err(error) = selftest::power_on_self_test(&mut sources) else {
+        return Ok(());
+    };
+
+    log_fatal!("{}", error);
+    let mut screen = ErrorScreen {
+        error_code: codes::CRYPTO_SELF_TEST_FAILED,
+        show_options: false,
+        ..Default::default()
+    };
+    screen.set_title("Cryptographic self-test failed");
+    let mut out = ConOut(st);
+    let _ = screen.render(Language::default(), &mut out);
+    let _ = write!(out, "\n  {}\n", error);
+
+    Err(Error::SecurityViolation)
+}
+
+/// Fill `buf` from RDRAND, retrying a transient underflow
+#[cfg(target_arch = "x86_64")]
+fn read_rdrand(buf: &mut [u8]) -> bool {
+    for chunk in buf.chunks_mut(8) {
+        let Some(value) = (0..10).find_map(|_| helix_uefi::arch::x86_64::rdrand()) else {
+            return false;
+        };
+        chunk.copy_from_slice(&value.to_le_bytes()[..chunk.len()]);
+    }
+    true
+}
+
 /// Detect CPU features
 fn detect_cpu_features() -> CpuFeatures {
     #[cfg(target_arch = "x86_64")]
@@ -254,6 +398,13 @@ pub struct BootConfig {
     /// Command line
     pub cmdline: [u8; 1024],
     pub cmdline_len: usize,
+    /// Extra modules, `path [args]`
+    pub modules: Vec<alloc::string::String>,
+    /// Device tree overlays, applied in order
+    pub dt_overlays: Vec<alloc::string::String>,
+    /// File system holding the kernel, initrd and modules (`None`: the ESP
+    /// this loader was started from)
+    pub volume: Option<EfiHandle>,
     /// Verbose boot
     pub verbose: bool,
     /// Debug mode
@@ -271,6 +422,9 @@ fn default() -> Self {
             initrd_path_len: 0,
             cmdline: [0; 1024],
             cmdline_len: 0,
+            modules: Vec::new(),
+            dt_overlays: Vec::new(),
+            volume: None,
             verbose: false,
             debug: false,
             timeout: 3,
@@ -289,11 +443,289 @@ fn default() -> Self {
     }
 }
 
+impl BootConfig {
+    /// Take paths and command line from a config file entry
+    ///
+    /// Paths are made ESP-relative with backslashes.
+    fn apply_entry(&mut self, entry: &MenuEntry, cmdline: &str) {
+        fn copy(dest: &mut [u8], src: &str, map: fn(u8) -> u8) -> usize {
+            let len = src.len().min(dest.len());
+            for (d, &b) in dest.iter_mut().zip(&src.as_bytes()[..len]) {
+                *d = map(b);
+            }
+            len
+        }
+        let backslash = |b| if b == b'/' { b'\\' } else { b };
+
+        if !entry.kernel.is_empty() {
+            self.kernel_path_len = copy(&mut self.kernel_path, &entry.kernel, backslash);
+        }
+        self.initrd_path_len = match &entry.initrd {
+            Some(initrd) => copy(&mut self.initrd_path, initrd, backslash),
+            None => 0,
+        };
+        self.cmdline_len = copy(&mut self.cmdline, cmdline, |b| b);
+        self.modules = entry.modules.iter()
+            .map(|module| match module.split_once(char::is_whitespace) {
+                Some((path, args)) => alloc::format!("{} {}", path.replace('/', "\\"), args.trim()),
+                None => module.replace('/', "\\"),
+            })
+            .collect();
+        self.dt_overlays = entry.dt_overlays.iter().map(|path| path.replace('/', "\\")).collect();
+    }
+}
+
 /// Load boot configuration
-fn load_config(_image_handle: EfiHandle, _st: &EfiSystemTable) -> Result<BootConfig> {
-    // Try to load config file, fall back to defaults
-    // TODO: Implement config file parsing
-    Ok(BootConfig::default())
+///
+/// Reads [`DEFAULT_CONFIG_PATH`] from the ESP and lets the user pick one
+/// of its entries from the boot menu; without the file the best Helix
+/// installation on the disks is booted, and without entries the defaults.
+fn load_config(image_handle: EfiHandle, st: &EfiSystemTable) -> Result<BootConfig> {
+    let mut config = BootConfig::default();
+
+    let file = match read_esp_file(image_handle, st, DEFAULT_CONFIG_PATH) {
+        Ok(data) => data,
+        Err(Error::NotFound) => {
+            discover_system(&mut config);
+            return Ok(config);
+        }
+        Err(e) => return Err(e),
+    };
+    let file = match ConfigFile::parse(&alloc::string::String::from_utf8_lossy(&file)) {
+        Ok(file) => file,
+        Err(e) => {
+            log_warn!("{}: {:?}, using defaults", DEFAULT_CONFIG_PATH, e);
+            return Ok(config);
+        }
+    };
+    config.timeout = file.timeout;
+    config.verbose = file.verbose;
+    config.debug = file.debug;
+    debug::set_level(file.log_level.into());
+    if file.log_file {
+        start_boot_log(image_handle, st, &file);
+    }
+    if file.visible_entries().next().is_none() {
+        return Ok(config);
+    }
+
+    let console = Console::new(
+        st.con_out as *mut SimpleTextOutput,
+        st.con_in as *mut SimpleTextInput,
+    );
+    let mut menu = BootMenu::new(&console, &file).with_stall(stall_ms);
+    match menu.run() {
+        MenuResult::Reboot => unsafe { (*st.runtime_services).reset_cold() },
+        MenuResult::Shutdown => unsafe { (*st.runtime_services).shutdown() },
+        // Boot, timeout, or no menu: the selection starts at the default
+        _ => {}
+    }
+
+    let entry = menu.selected_entry().ok_or(Error::NotFound)?;
+    let cmdline = menu.edited_cmdline().unwrap_or(&entry.cmdline);
+    config.apply_entry(entry, cmdline);
+    Ok(config)
+}
+
+/// Boot the best Helix installation found on the disks
+///
+/// Takes the default paths from the ESP next to the highest ranked Helix
+/// system partition, which becomes the root. Without one the defaults on
+/// this loader's ESP stay.
+fn discover_system(config: &mut BootConfig) {
+    let candidates = helix_uefi::partition::discover();
+    let Some((candidate, volume, entry)) = candidates.iter().find_map(|candidate| {
+        let entry = helix_uefi::bootmgr::BootEntry::from_candidate(candidate)?;
+        Some((candidate, candidate.esp_volume?, entry))
+    }) else {
+        return;
+    };
+
+    let mut menu_entry = MenuEntry::new("discovered");
+    menu_entry.kernel = entry.path().into();
+    menu_entry.initrd = Some(entry.initrd().into());
+    config.apply_entry(&menu_entry, entry.args());
+    config.volume = Some(volume);
+    log_info!(
+        "found {} on disk {} ({} candidates)",
+        entry.title(), candidate.disk, candidates.len(),
+    );
+}
+
+/// Start logging to the ESP as configured in boot.cfg
+///
+/// A read-only or full ESP only costs the log, not the boot.
+fn start_boot_log(image_handle: EfiHandle, st: &EfiSystemTable, file: &ConfigFile) {
+    let bs = unsafe { &*st.boot_services };
+    let log = unsafe { helix_uefi::protocols::filesystem::FileSystem::boot_volume(bs, image_handle) }
+        .and_then(|fs| BootLog::open(fs, file.log_level.into(), file.log_keep));
+    match log {
+        Ok(log) => {
+            unsafe { boot_log::init(log) };
+            let _ = boot_log::phase("config");
+        }
+        Err(e) => {
+            log_warn!("{}: {:?}, not logging to the ESP", boot_log::LOG_PATH, e);
+        }
+    }
+}
+
+/// Ticks per second of the counter log timestamps are read from
+///
+/// The TSC is timed against a 10 ms `Stall`; the AArch64 generic timer
+/// reports its own frequency.
+fn timestamp_frequency() -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let start = helix_uefi::time::read_tsc();
+        stall_ms(10);
+        helix_uefi::time::read_tsc().wrapping_sub(start) * 100
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        helix_uefi::time::estimate_tsc_frequency()
+    }
+}
+
+/// Wait `ms` milliseconds with boot services `Stall`
+fn stall_ms(ms: u64) {
+    unsafe {
+        if let Some(st) = SYSTEM_TABLE {
+            let _ = ((*(*st).boot_services).stall)((ms * 1000) as usize);
+        }
+    }
+}
+
+/// Read a whole file from the ESP this loader was started from
+fn read_esp_file(image_handle: EfiHandle, st: &EfiSystemTable, path: &str) -> Result<Vec<u8>> {
+    let (file, size) = open_esp_file(image_handle, st, None, path)?;
+    let mut data = alloc::vec![0u8; size];
+    let size = read_file(file, &mut data)?;
+    data.truncate(size);
+    Ok(data)
+}
+
+/// Read a whole file from `volume` into page-aligned `EfiLoaderData` memory
+///
+/// The pages outlive boot services; returns their address and the file
+/// size.
+fn read_esp_file_pages(
+    image_handle: EfiHandle,
+    st: &EfiSystemTable,
+    volume: Option<EfiHandle>,
+    path: &str,
+) -> Result<(PhysicalAddress, usize)> {
+    let bs = unsafe { &*st.boot_services };
+    let (file, size) = open_esp_file(image_handle, st, volume, path)?;
+
+    let mut buffer: *mut core::ffi::c_void = core::ptr::null_mut();
+    let pages = size.max(1).div_ceil(4096);
+    let status = unsafe {
+        (bs.allocate_pages)(
+            0, // AllocateAnyPages
+            2, // EfiLoaderData
+            pages,
+            &mut buffer as *mut _ as *mut PhysicalAddress,
+        )
+    };
+    if status != EFI_SUCCESS {
+        unsafe { ((*file).close)(file) };
+        return Err(Error::OutOfResources);
+    }
+
+    let data = unsafe { core::slice::from_raw_parts_mut(buffer as *mut u8, size) };
+    match read_file(file, data) {
+        Ok(size) => Ok((PhysicalAddress(buffer as u64), size)),
+        Err(e) => {
+            let _ = unsafe { (bs.free_pages)(PhysicalAddress(buffer as u64), pages) };
+            Err(e)
+        }
+    }
+}
+
+/// Read `file` into `data` and close it
+fn read_file(file: *mut EfiFileProtocol, data: &mut [u8]) -> Result<usize> {
+    let mut size = data.len();
+    let status = unsafe { ((*file).read)(file, &mut size, data.as_mut_ptr() as *mut core::ffi::c_void) };
+    unsafe { ((*file).close)(file) };
+    if status != EFI_SUCCESS {
+        return Err(Error::from_status(status));
+    }
+    Ok(size)
+}
+
+/// Open a file on `volume`, by default the ESP this loader was started
+/// from, with its size
+fn open_esp_file(
+    image_handle: EfiHandle,
+    st: &EfiSystemTable,
+    volume: Option<EfiHandle>,
+    path: &str,
+) -> Result<(*mut EfiFileProtocol, usize)> {
+    let bs = unsafe { &*st.boot_services };
+
+    let mut loaded_image: *mut EfiLoadedImageProtocol = core::ptr::null_mut();
+    let status = unsafe {
+        (bs.open_protocol)(
+            image_handle,
+            &EFI_LOADED_IMAGE_PROTOCOL_GUID as *const _,
+            &mut loaded_image as *mut _ as *mut *mut core::ffi::c_void,
+            image_handle,
+            core::ptr::null_mut(),
+            EFI_OPEN_PROTOCOL_BY_HANDLE_PROTOCOL,
+        )
+    };
+    if status != EFI_SUCCESS {
+        return Err(Error::from_status(status));
+    }
+
+    let mut fs_protocol: *mut EfiSimpleFileSystemProtocol = core::ptr::null_mut();
+    let status = unsafe {
+        (bs.open_protocol)(
+            volume.unwrap_or((*loaded_image).device_handle),
+            &EFI_SIMPLE_FILE_SYSTEM_PROTOCOL_GUID as *const _,
+            &mut fs_protocol as *mut _ as *mut core::ffi::c_void,
+            image_handle,
+            core::ptr::null_mut(),
+            EFI_OPEN_PROTOCOL_BY_HANDLE_PROTOCOL,
+        )
+    };
+    if status != EFI_SUCCESS {
+        return Err(Error::from_status(status));
+    }
+
+    let mut root: *mut EfiFileProtocol = core::ptr::null_mut();
+    let status = unsafe { ((*fs_protocol).open_volume)(fs_protocol, &mut root) };
+    if status != EFI_SUCCESS {
+        return Err(Error::from_status(status));
+    }
+
+    let mut path16: Vec<u16> = path.encode_utf16().collect();
+    path16.push(0);
+    let mut file: *mut EfiFileProtocol = core::ptr::null_mut();
+    let status = unsafe { ((*root).open)(root, &mut file, path16.as_ptr(), EFI_FILE_MODE_READ, 0) };
+    if status != EFI_SUCCESS {
+        return Err(Error::NotFound);
+    }
+
+    // EFI_FILE_INFO: Size (u64), then FileSize (u64)
+    let file_info_guid = EfiGuid {
+        data1: 0x09576e92,
+        data2: 0x6d3f,
+        data3: 0x11d2,
+        data4: [0x8e, 0x39, 0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b],
+    };
+    let mut info = [0u64; 64];
+    let mut info_size = core::mem::size_of_val(&info);
+    let status = unsafe {
+        ((*file).get_info)(file, &file_info_guid, &mut info_size, info.as_mut_ptr() as *mut core::ffi::c_void)
+    };
+    if status != EFI_SUCCESS {
+        unsafe { ((*file).close)(file) };
+        return Err(Error::from_status(status));
+    }
+
+    Ok((file, info[1] as usize))
 }
 
 // =============================================================================
@@ -301,9 +733,28 @@ fn load_config(_image_handle: EfiHandle, _st: &EfiSystemTable) -> Result<BootCon
 // =============================================================================
 
 /// Initialize graphics and get framebuffer info
-fn init_graphics(st: &EfiSystemTable) -> Result<FramebufferInfo> {
+fn init_graphics(image_handle: EfiHandle, st: &EfiSystemTable) -> Result<(FramebufferInfo, Option<DisplayInfo>)> {
     let bs = unsafe { &*st.boot_services };
 
+    // Switch to the display's native mode before the framebuffer is read
+    let display = match unsafe { GraphicsOutput::acquire(bs, image_handle, st.console_out_handle) } {
+        Ok(gop) => {
+            let edid = unsafe { gop.edid(bs, image_handle) }.ok();
+            match gop.set_native_mode(edid.as_ref()) {
+                Ok(mode) => log_info!("graphics mode {}x{}", mode.width, mode.height),
+                Err(e) => log_warn!("native mode: {:?}", e),
+            }
+            edid.map(|edid| {
+                log_info!(
+                    "display {} {:04x} {}x{} mm",
+                    edid.manufacturer_id(), edid.product, edid.size_mm.0, edid.size_mm.1,
+                );
+                edid.display_info()
+            })
+        }
+        Err(_) => None,
+    };
+
     // Locate GOP
     let mut gop: *mut EfiGraphicsOutputProtocol = core::ptr::null_mut();
     let guid = EFI_GRAPHICS_OUTPUT_PROTOCOL_GUID;
@@ -318,7 +769,7 @@ fn init_graphics(st: &EfiSystemTable) -> Result<FramebufferInfo> {
 
     if status != EFI_SUCCESS || gop.is_null() {
         // No GOP, return empty framebuffer
-        return Ok(FramebufferInfo {
+        return Ok((FramebufferInfo {
             address: 0,
             size: 0,
             width: 0,
@@ -327,7 +778,7 @@ fn init_graphics(st: &EfiSystemTable) -> Result<FramebufferInfo> {
             format: PixelFormat::Rgb32,
             bitmask: None,
             bpp: 0,
-        });
+        }, display));
     }
 
     let gop = unsafe { &*gop };
@@ -343,7 +794,7 @@ fn init_graphics(st: &EfiSystemTable) -> Result<FramebufferInfo> {
     let fb = unsafe { (*gop.mode).frame_buffer_base };
     let fb_size = unsafe { (*gop.mode).frame_buffer_size };
 
-    Ok(FramebufferInfo {
+    Ok((FramebufferInfo {
         address: fb,
         size: fb_size as u64,
         width: mode_info.horizontal_resolution,
@@ -352,7 +803,7 @@ fn init_graphics(st: &EfiSystemTable) -> Result<FramebufferInfo> {
         format,
         bitmask: None,
         bpp: 32,
-    })
+    }, display))
 }
 
 // =============================================================================
@@ -439,6 +890,87 @@ fn find_acpi_tables(st: &EfiSystemTable) -> Result<AcpiInfo> {
     })
 }
 
+/// Parse the tables under the RSDP; `None` if they are unreadable
+fn parse_acpi_tables(acpi_info: &AcpiInfo) -> Option<AcpiParser> {
+    let mut parser = AcpiParser::new();
+    match unsafe { parser.init(acpi_info.rsdp_addr) } {
+        Ok(()) => Some(parser),
+        Err(e) => {
+            log_warn!("ACPI tables: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Record how the panel is mounted, from the BGRT orientation bits, so the
+/// kernel console can rotate to match
+fn apply_panel_orientation(acpi: Option<&AcpiParser>, display: &mut Option<DisplayInfo>) {
+    let orientation = match acpi.and_then(|acpi| acpi.bgrt()) {
+        Some(bgrt) if bgrt.orientation() != 0 => bgrt.orientation(),
+        _ => return,
+    };
+    log_info!("panel rotated {} degrees", orientation);
+    display.get_or_insert_with(DisplayInfo::default).orientation = orientation;
+}
+
+// =============================================================================
+// SMP
+// =============================================================================
+
+/// APIC ID of the CPU running the loader
+#[cfg(target_arch = "x86_64")]
+fn current_apic_id() -> u32 {
+    use helix_uefi::arch::x86_64::cpuid;
+
+    // Leaf 0xB has the full x2APIC ID, leaf 1 only its low 8 bits
+    if cpuid(0, 0).eax >= 0xB && cpuid(0xB, 0).ebx != 0 {
+        cpuid(0xB, 0).edx
+    } else {
+        cpuid(1, 0).ebx >> 24
+    }
+}
+
+/// CPUs from the MADT, with a page below 1 MiB reserved for the AP trampoline
+///
+/// Without a MADT the kernel runs on the BSP alone; without a trampoline
+/// page it gets the topology but has to find low memory itself.
+#[cfg(target_arch = "x86_64")]
+fn find_cpus(st: &EfiSystemTable, acpi: Option<&AcpiParser>) -> Option<SmpInfo> {
+    let madt = acpi.and_then(|acpi| acpi.madt())?;
+    let mut smp = SmpInfo::from_madt(madt, current_apic_id());
+
+    if smp.application_processors().next().is_some() {
+        let bs = unsafe { &*st.boot_services };
+        // AllocateMaxAddress takes the highest address the pages may end at
+        let mut address = PhysicalAddress(TRAMPOLINE_LIMIT - 1);
+        let status = unsafe {
+            (bs.allocate_pages)(
+                1, // AllocateMaxAddress
+                2, // EfiLoaderData
+                (TRAMPOLINE_SIZE / 4096) as usize,
+                &mut address,
+            )
+        };
+        if status != EFI_SUCCESS || smp.set_trampoline(address).is_err() {
+            log_warn!("no low memory for the AP trampoline");
+        }
+    }
+
+    log_info!(
+        "{} CPUs, BSP APIC {}, trampoline {:#x}",
+        smp.enabled_count(),
+        smp.bsp_apic_id,
+        smp.trampoline.map_or(0, |t| t.0),
+    );
+    Some(smp)
+}
+
+/// CPU topology for the kernel; none off x86, where there is no LAPIC
+#[cfg(not(target_arch = "x86_64"))]
+fn find_cpus(_st: &EfiSystemTable, _acpi: Option<&AcpiParser>) -> Option<SmpInfo> {
+    None
+}
+
 // =============================================================================
 // SMBIOS
 // =============================================================================
@@ -475,6 +1007,124 @@ fn find_smbios_tables(st: &EfiSystemTable) -> Option<PhysicalAddress> {
     None
 }
 
+// =============================================================================
+// DEVICE TREE
+// =============================================================================
+
+/// Copy the firmware device tree, with the boot entry's overlays applied,
+/// into `EfiLoaderData` pages for the kernel
+///
+/// Returns `None` when the firmware installed no device tree (ACPI-only
+/// machines). A corrupt tree or an overlay that does not apply fails the
+/// boot rather than handing the kernel a half-described machine.
+#[cfg(feature = "dtb")]
+fn load_device_tree(
+    image_handle: EfiHandle,
+    st: &EfiSystemTable,
+    config: &BootConfig,
+) -> Result<Option<PhysicalAddress>> {
+    // EFI_DTB_TABLE_GUID
+    let dtb_guid = EfiGuid {
+        data1: 0xb1b621d5,
+        data2: 0xf19c,
+        data3: 0x41a5,
+        data4: [0x83, 0x0b, 0xd9, 0x15, 0x2c, 0x69, 0xaa, 0xe0],
+    };
+
+    let config_tables = st.configuration_table;
+    let firmware_dtb = (0..st.number_of_table_entries)
+        .map(|i| unsafe { &*config_tables.add(i) })
+        .find(|entry| entry.vendor_guid == dtb_guid)
+        .map(|entry| PhysicalAddress(entry.vendor_table as u64));
+    let Some(firmware_dtb) = firmware_dtb else {
+        if !config.dt_overlays.is_empty() {
+            log_warn!("no firmware device tree, overlays ignored");
+        }
+        return Ok(None);
+    };
+
+    let mut tree = DeviceTree::parse(unsafe { dtb::blob_at(firmware_dtb)? })?;
+    for path in &config.dt_overlays {
+        let overlay = DeviceTree::parse(&read_esp_file(image_handle, st, path)?)?;
+        tree.apply_overlay(&overlay)?;
+        log_info!("device tree overlay {}", path);
+    }
+
+    // The firmware copy may live in boot services memory
+    let blob = tree.to_bytes();
+    let bs = unsafe { &*st.boot_services };
+    let mut buffer: *mut core::ffi::c_void = core::ptr::null_mut();
+    let status = unsafe {
+        (bs.allocate_pages)(
+            0, // AllocateAnyPages
+            2, // EfiLoaderData
+            blob.len().div_ceil(4096),
+            &mut buffer as *mut _ as *mut PhysicalAddress,
+        )
+    };
+    if status != EFI_SUCCESS {
+        return Err(Error::OutOfResources);
+    }
+    unsafe { core::ptr::copy_nonoverlapping(blob.as_ptr(), buffer as *mut u8, blob.len()) };
+
+    log_info!("device tree at {:#x}, {} bytes", buffer as u64, blob.len());
+    Ok(Some(PhysicalAddress(buffer as u64)))
+}
+
+/// Device tree for the kernel; none without DTB support
+#[cfg(not(feature = "dtb"))]
+fn load_device_tree(
+    _image_handle: EfiHandle,
+    _st: &EfiSystemTable,
+    _config: &BootConfig,
+) -> Result<Option<PhysicalAddress>> {
+    Ok(None)
+}
+
+// =============================================================================
+// COMPATIBILITY
+// =============================================================================
+
+/// Requirements of the kernel this loader boots
+fn kernel_requirements() -> HardwareRequirements {
+    #[cfg(target_arch = "x86_64")]
+    {
+        HardwareRequirements::for_level(MicroarchLevel::V2)
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        HardwareRequirements::default()
+    }
+}
+
+/// Check the hardware against the kernel requirements, print the report
+/// if anything was found and fail if the kernel cannot run
+fn check_compatibility(
+    st: &EfiSystemTable,
+    cpu_features: &CpuFeatures,
+    smbios_addr: Option<PhysicalAddress>,
+) -> Result<()> {
+    let mut system = SystemSummary::default();
+    system.cpu.features = cpu_features.flags();
+    // find_acpi_tables has already failed the boot if there is no RSDP
+    system.firmware.tables.set(FirmwareTables::ACPI);
+    if smbios_addr.is_some() {
+        system.firmware.tables.set(FirmwareTables::SMBIOS);
+    }
+
+    let report = kernel_requirements().check(&system);
+    if !report.issues().is_empty() {
+        let _ = write!(ConOut(st), "{}", report);
+    }
+
+    if report.can_boot() {
+        Ok(())
+    } else {
+        Err(Error::Unsupported)
+    }
+}
+
 // =============================================================================
 // KERNEL LOADING
 // =============================================================================
@@ -492,6 +1142,10 @@ pub struct LoadedKernel {
     pub entry_point: VirtualAddress,
     /// TLS template
     pub tls_template: Option<TlsTemplate>,
+    /// Segments as placed in memory
+    pub segments: Vec<KernelSegment>,
+    /// Kernel file as read, for measurement
+    pub file: &'static [u8],
 }
 
 /// Load kernel from filesystem
@@ -522,7 +1176,7 @@ fn load_kernel(
     }
 
     let loaded_image = unsafe { &*loaded_image };
-    let device_handle = loaded_image.device_handle;
+    let device_handle = config.volume.unwrap_or(loaded_image.device_handle);
 
     // Get file system protocol
     let fs_guid = EFI_SIMPLE_FILE_SYSTEM_PROTOCOL_GUID;
@@ -640,31 +1294,74 @@ fn load_kernel(
         core::slice::from_raw_parts(kernel_buffer as *const u8, bytes_read)
     };
 
-    // Verify ELF magic
-    if bytes_read < 4 || &kernel_data[0..4] != b"\x7fELF" {
-        return Err(Error::InvalidParameter);
-    }
+    // Refuse kernels not signed for this machine while Secure Boot enforces
+    verify_kernel(st, kernel_data)?;
 
-    // Parse ELF header (simplified)
-    let entry_point = if kernel_data[4] == 2 {
-        // 64-bit ELF
-        u64::from_le_bytes([
-            kernel_data[24], kernel_data[25], kernel_data[26], kernel_data[27],
-            kernel_data[28], kernel_data[29], kernel_data[30], kernel_data[31],
-        ])
-    } else {
-        return Err(Error::Unsupported);
-    };
+    // Place each PT_LOAD segment in its own pages; setup_paging maps them
+    let mut allocator = unsafe { BootServices::from_ptr(st.boot_services) }
+        .ok_or(Error::NotReady)?;
+    let placed = KernelLoader::new().load_kernel(kernel_data, &mut allocator)?;
+    let phys_base = placed.segments.first()
+        .map(|segment| segment.phys)
+        .ok_or(Error::InvalidParameter)?;
 
     Ok(LoadedKernel {
-        phys_base: PhysicalAddress(kernel_buffer as u64),
-        virt_base: KERNEL_LOAD_VIRT,
-        size: bytes_read as u64,
-        entry_point,
+        phys_base,
+        virt_base: placed.virt_base(),
+        size: placed.image_size(),
+        entry_point: placed.entry_point,
         tls_template: None,
+        segments: placed.segments,
+        file: kernel_data,
     })
 }
 
+/// Settle the kernel lockdown level; returns it for measurement
+///
+/// Under enforcing Secure Boot the kernel locks itself down to at least
+/// `integrity`. The level is written onto the command line when it is
+/// not what the command line already asks for, so the measured command
+/// line matches what the kernel applies.
+fn apply_lockdown(st: &EfiSystemTable, config: &mut BootConfig) -> &'static str {
+    let secure_boot = unsafe { RuntimeServices::from_ptr(st.runtime_services) }
+        .is_some_and(|rs| SecureBootState::read(&rs).mode.is_enforcing());
+    let current = core::str::from_utf8(&config.cmdline[..config.cmdline_len]).unwrap_or("");
+    let level = cmdline::lockdown_level(current, secure_boot);
+    if level == cmdline::lockdown_level(current, false) {
+        return level;
+    }
+
+    let arg = alloc::format!("{}lockdown={}", if current.is_empty() { "" } else { " " }, level);
+    let end = config.cmdline_len + arg.len();
+    if end > config.cmdline.len() {
+        log_warn!("Lockdown: command line full, kernel applies {} itself", level);
+        return level;
+    }
+    config.cmdline[config.cmdline_len..end].copy_from_slice(arg.as_bytes());
+    config.cmdline_len = end;
+    log_info!("Secure Boot: kernel lockdown {}", level);
+    level
+}
+
+/// Check the kernel signature against db, dbx and the vendor certificate
+///
+/// Only a user or deployed mode denial stops the boot; in audit mode it
+/// is reported and the kernel runs.
+fn verify_kernel(st: &EfiSystemTable, kernel: &[u8]) -> Result<()> {
+    let rs = unsafe { RuntimeServices::from_ptr(st.runtime_services) }.ok_or(Error::NotReady)?;
+    match secureboot::verify_kernel(&rs, kernel) {
+        Ok(SecureBootResult::Denied { reason }) => {
+            log_warn!("Secure Boot (audit): kernel would be denied: {:?}", reason);
+            Ok(())
+        }
+        Ok(SecureBootResult::Allowed { .. }) => Ok(()),
+        Err(e) => {
+            log_error!("Secure Boot: kernel refused: {:?}", e);
+            Err(Error::SecurityViolation)
+        }
+    }
+}
+
 // =============================================================================
 // MODULE LOADING
 // =============================================================================
@@ -678,19 +1375,127 @@ pub struct LoadedModules {
     pub initrd_size: u64,
     /// Module count
     pub count: usize,
+    /// Modules in load order, initrd first
+    pub list: ModuleList,
+    /// [`ModuleList::to_bytes`] in `EfiLoaderData` pages, for the kernel
+    pub table_addr: PhysicalAddress,
+    /// Size of the module table in bytes
+    pub table_size: u64,
 }
 
-/// Load additional modules
+/// Load the initrd and the extra modules of the boot entry
+///
+/// Each file gets its own page-aligned `EfiLoaderData` allocation that
+/// the kernel takes over. The default initrd may be missing; a
+/// configured one or any module may not.
 fn load_modules(
-    _image_handle: EfiHandle,
-    _st: &EfiSystemTable,
-    _config: &BootConfig,
+    image_handle: EfiHandle,
+    st: &EfiSystemTable,
+    config: &BootConfig,
 ) -> Result<LoadedModules> {
-    // TODO: Load initrd and other modules
+    let mut list = ModuleList::new();
+    let mut initrd = None;
+
+    if config.initrd_path_len > 0 {
+        let path = core::str::from_utf8(&config.initrd_path[..config.initrd_path_len])
+            .map_err(|_| Error::InvalidParameter)?;
+        match read_esp_file_pages(image_handle, st, config.volume, path) {
+            Ok((addr, size)) => {
+                list.add(ModuleBuilder::new(path)
+                    .physical_address(addr)
+                    .size(size as u64)
+                    .module_type(ModuleType::Initrd)
+                    .add_flag(ModuleFlags::PAGE_ALIGNED)
+                    .build());
+                initrd = Some((addr, size as u64));
+            }
+            Err(Error::NotFound) if path == DEFAULT_INITRD_PATH => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    for module in &config.modules {
+        let (path, args) = module.split_once(' ').unwrap_or((module, ""));
+        let (addr, size) = read_esp_file_pages(image_handle, st, config.volume, path)?;
+        let data = unsafe { core::slice::from_raw_parts(addr.0 as *const u8, size) };
+        list.add_auto(path.into(), addr, size as u64, data);
+        if let Some(info) = list.get_mut(list.len() - 1) {
+            info.cmdline = args.into();
+            info.flags.insert(ModuleFlags::PAGE_ALIGNED);
+        }
+        log_info!("module {} at {:#x}, {} bytes", path, addr.0, size);
+    }
+
+    let table = list.to_bytes();
+    let table_addr = if list.is_empty() {
+        PhysicalAddress(0)
+    } else {
+        let bs = unsafe { &*st.boot_services };
+        let mut buffer: *mut core::ffi::c_void = core::ptr::null_mut();
+        let status = unsafe {
+            (bs.allocate_pages)(
+                0, // AllocateAnyPages
+                2, // EfiLoaderData
+                table.len().div_ceil(4096),
+                &mut buffer as *mut _ as *mut PhysicalAddress,
+            )
+        };
+        if status != EFI_SUCCESS {
+            return Err(Error::OutOfResources);
+        }
+        unsafe { core::ptr::copy_nonoverlapping(table.as_ptr(), buffer as *mut u8, table.len()) };
+        PhysicalAddress(buffer as u64)
+    };
+
     Ok(LoadedModules {
-        initrd_addr: None,
-        initrd_size: 0,
-        count: 0,
+        initrd_addr: initrd.map(|(addr, _)| addr),
+        initrd_size: initrd.map_or(0, |(_, size)| size),
+        count: list.len(),
+        table_size: if list.is_empty() { 0 } else { table.len() as u64 },
+        list,
+        table_addr,
+    })
+}
+
+// =============================================================================
+// MEASURED BOOT
+// =============================================================================
+
+/// Measure the kernel, initrd, command line and lockdown level into the TPM
+///
+/// The firmware extends the TPM's active banks and logs each event; the
+/// same events are logged for the kernel. Without a TPM the log is still
+/// handed over, marked as not extended.
+fn measure_boot(
+    st: &EfiSystemTable,
+    kernel: &LoadedKernel,
+    modules: &LoadedModules,
+    config: &BootConfig,
+    lockdown: &str,
+) -> Result<MeasurementLog> {
+    let tpm = unsafe { Tcg2::locate(&*(st.boot_services as *const _)) }.ok();
+    let mut measured = MeasuredBoot::from_hash_bitmap(tpm.as_ref().map_or(0, Tcg2::active_banks), 24);
+
+    let initrd = modules.initrd_addr.map(|addr| unsafe {
+        core::slice::from_raw_parts(addr.0 as *const u8, modules.initrd_size as usize)
+    });
+    let components = BootComponents {
+        kernel: kernel.file,
+        initrd,
+        cmdline: core::str::from_utf8(&config.cmdline[..config.cmdline_len]).unwrap_or(""),
+        config: None,
+        lockdown: Some(lockdown),
+    };
+    measured.measure_boot(&components, |pcr, event_type, data, description| match &tpm {
+        Some(tpm) => tpm.measure(pcr, event_type, data, description),
+        None => Ok(()),
+    })?;
+
+    Ok(MeasurementLog {
+        event_log: measured.get_event_log_bytes(),
+        event_count: measured.event_log().events().len() as u32,
+        hash_algorithms: measured.hash_bitmap(),
+        tpm_extended: tpm.is_some(),
     })
 }
 
@@ -787,7 +1592,8 @@ fn exit_boot_services(
 
     // Parse memory map into our format
     let entry_count = map_size / desc_size;
-    let mut descriptors = Vec::with_capacity(entry_count);
+    // Spare entries for the handoff region and page table pool
+    let mut descriptors = Vec::with_capacity(entry_count + 2);
 
     for i in 0..entry_count {
         let desc_ptr = unsafe {
@@ -821,30 +1627,325 @@ pub struct MemoryDescriptor {
 extern crate alloc;
 use alloc::vec::Vec;
 
+/// Reservations besides the kernel segments and modules: the module
+/// table, page table pool, boot stack, handoff region, AP trampoline and
+/// TPM event log
+const LOADER_RESERVATIONS: usize = 6;
+
+/// Lowest address loader data is carved at, leaving real-mode memory to
+/// the kernel for AP startup
+const LOADER_CARVE_MIN: u64 = 0x10_0000;
+
+/// Split `pages` off the first conventional descriptor above 1 MiB
+///
+/// The pages are marked LoaderData so the kernel does not hand them out
+/// while they are in use. `exit_boot_services` left room for the new
+/// descriptor, so nothing is allocated.
+fn carve_loader_pages(memory_map: &mut Vec<MemoryDescriptor>, pages: u64) -> Result<PhysicalAddress> {
+    let index = memory_map.iter()
+        .position(|desc| {
+            desc.memory_type == MemoryType::ConventionalMemory as u32
+                && desc.physical_start.0 >= LOADER_CARVE_MIN
+                && desc.number_of_pages >= pages
+        })
+        .ok_or(Error::OutOfResources)?;
+
+    let desc = &mut memory_map[index];
+    let carved = MemoryDescriptor {
+        memory_type: MemoryType::LoaderData as u32,
+        number_of_pages: pages,
+        ..desc.clone()
+    };
+    desc.physical_start += pages * 4096;
+    desc.virtual_start += pages * 4096;
+    desc.number_of_pages -= pages;
+
+    let base = carved.physical_start;
+    memory_map.insert(index, carved);
+    Ok(base)
+}
+
+/// The descriptor holding the stack the loader runs on, which the kernel
+/// is entered on too
+fn boot_stack(memory_map: &[MemoryDescriptor]) -> Option<(PhysicalAddress, u64)> {
+    // Firmware memory is identity mapped, so a local's address is physical
+    let marker = 0u8;
+    let sp = core::hint::black_box(&marker) as *const u8 as u64;
+    memory_map.iter()
+        .find(|desc| {
+            desc.physical_start.0 <= sp
+                && sp - desc.physical_start.0 < desc.number_of_pages * 4096
+        })
+        .map(|desc| (desc.physical_start, desc.number_of_pages * 4096))
+}
+
+/// Everything the loader placed that the kernel must not allocate over
+///
+/// The kernel segments and modules are the kernel's to keep. The page
+/// tables, boot stack, handoff region, AP trampoline and TPM event log
+/// are reclaimable once the kernel is done with them.
+fn loader_reservations(
+    memory_map: &[MemoryDescriptor],
+    kernel: &LoadedKernel,
+    modules: &LoadedModules,
+    page_tables: &PageTableSetup,
+    handoff: &HandoffRegion,
+    smp: Option<&SmpInfo>,
+    measurements: &MeasurementLog,
+) -> Vec<Reservation> {
+    let kept = HandoffMemoryType::KernelAndModules;
+    let reclaimable = HandoffMemoryType::BootloaderReclaimable;
+    let mut reservations =
+        Vec::with_capacity(kernel.segments.len() + modules.count + LOADER_RESERVATIONS);
+
+    for segment in &kernel.segments {
+        reservations.push(Reservation::new(segment.phys, segment.pages as u64 * 4096, kept));
+    }
+    for module in modules.list.iter() {
+        reservations.push(Reservation::new(module.physical_address, module.size, kept));
+    }
+    reservations.push(Reservation::new(modules.table_addr, modules.table_size, kept));
+
+    reservations.push(Reservation::new(page_tables.pool, page_tables.pool_size, reclaimable));
+    if let Some((base, size)) = boot_stack(memory_map) {
+        reservations.push(Reservation::new(base, size, reclaimable));
+    }
+    reservations.push(Reservation::new(handoff.base, handoff.size, reclaimable));
+    if let Some(trampoline) = smp.and_then(|smp| smp.trampoline) {
+        reservations.push(Reservation::new(trampoline, TRAMPOLINE_SIZE, reclaimable));
+    }
+    let event_log = &measurements.event_log;
+    reservations.push(Reservation::new(
+        PhysicalAddress(event_log.as_ptr() as u64),
+        event_log.len() as u64,
+        reclaimable,
+    ));
+
+    reservations.retain(|reservation| reservation.size != 0);
+    reservations
+}
+
+/// The map handed to the kernel: the firmware descriptors, sanitized with
+/// `reservations` laid over them
+fn sanitized_memory_map(
+    memory_map: &[MemoryDescriptor],
+    reservations: &[Reservation],
+) -> HandoffMemoryMap {
+    let mut map = HandoffMemoryMap::with_capacity(memory_map.len());
+    for desc in memory_map {
+        let mut entry = MemoryMapEntry::new(
+            desc.physical_start,
+            desc.number_of_pages,
+            HandoffMemoryType::from_uefi(desc.memory_type),
+        );
+        entry.attributes = MemoryAttributes(desc.attribute);
+        map.add(entry);
+    }
+
+    let report = sanitize(&mut map, reservations);
+    log_info!(
+        "memory map: {} entries, {} MiB usable, {} overlaps, {} fragments dropped",
+        report.entries,
+        report.usable >> 20,
+        report.overlaps,
+        report.fragments,
+    );
+    map
+}
+
+// =============================================================================
+// HANDOFF REGION
+// =============================================================================
+
+/// Bytes kept for [`BootInfo`] at the start of the handoff region
+const BOOT_INFO_SPACE: u64 = (core::mem::size_of::<BootInfo>() as u64 + 63) & !63;
+
+/// Loader data pages holding the boot info and the memory map, which the
+/// kernel reads after it has left the boot stack
+#[derive(Debug)]
+pub struct HandoffRegion {
+    /// Physical base, identity mapped
+    pub base: PhysicalAddress,
+    /// Size in bytes
+    pub size: u64,
+}
+
+impl HandoffRegion {
+    /// Copy `map` in after the boot info; returns its address and size
+    fn write_memory_map(&self, map: &HandoffMemoryMap) -> Result<(u64, u64)> {
+        let addr = self.base.0 + BOOT_INFO_SPACE;
+        let size = (map.entries.len() * MemoryMapEntryRaw::SIZE) as u64;
+        if BOOT_INFO_SPACE + size > self.size {
+            return Err(Error::BufferTooSmall);
+        }
+
+        let raw = addr as *mut MemoryMapEntryRaw;
+        for (i, entry) in map.entries.iter().enumerate() {
+            unsafe { raw.add(i).write(MemoryMapEntryRaw::from_entry(entry)) };
+        }
+        Ok((addr, size))
+    }
+
+    /// Move the boot info to the start of the region
+    fn place(&self, boot_info: BootInfo) -> &'static BootInfo {
+        let ptr = self.base.0 as *mut BootInfo;
+        unsafe {
+            ptr.write(boot_info);
+            &*ptr
+        }
+    }
+}
+
+/// Carve the handoff region out of conventional memory
+///
+/// Sanitizing splits a map into at most two entries per descriptor and
+/// reservation, which bounds the size of the final map.
+fn reserve_handoff_region(
+    memory_map: &mut Vec<MemoryDescriptor>,
+    kernel: &LoadedKernel,
+    modules: &LoadedModules,
+) -> Result<HandoffRegion> {
+    // This region and the page table pool each add a descriptor
+    let pieces = memory_map.len() + 2 + kernel.segments.len() + modules.count + LOADER_RESERVATIONS;
+    let size = BOOT_INFO_SPACE + (2 * pieces * MemoryMapEntryRaw::SIZE) as u64;
+    let pages = size.div_ceil(4096);
+
+    let base = carve_loader_pages(memory_map, pages)?;
+    Ok(HandoffRegion { base, size: pages * 4096 })
+}
+
 // =============================================================================
 // PAGING
 // =============================================================================
 
+/// Pages reserved for boot page tables (2 MiB)
+///
+/// With 2 MiB pages the physical map takes one table per GiB, so this
+/// covers several hundred GiB even on CPUs without 1 GiB pages.
+const PAGE_TABLE_POOL_PAGES: u64 = 512;
+
 /// Page table setup result
 #[derive(Debug)]
 pub struct PageTableSetup {
     /// Root page table physical address
     pub root: PhysicalAddress,
+    /// Mappings use the NX bit (EFER.NXE must be set before the switch)
+    pub no_execute: bool,
+    /// Pages the tables were built in
+    pub pool: PhysicalAddress,
+    /// Size of the pool in bytes
+    pub pool_size: u64,
+}
+
+/// Set up page tables for kernel
+///
+/// Builds a 4-level hierarchy with:
+/// - loader and boot services memory identity mapped, so the code, stack
+///   and boot info survive the CR3 switch in `handoff_to_kernel`
+/// - each kernel segment at its higher-half address with its own
+///   permissions, refusing segments that are both writable and executable
+/// - all physical memory at `PHYS_MAP_BASE`
+/// - the framebuffer at `FRAMEBUFFER_VIRT`
+#[cfg(target_arch = "x86_64")]
+fn setup_paging(
+    kernel: &LoadedKernel,
+    memory_map: &mut Vec<MemoryDescriptor>,
+    framebuffer: &FramebufferInfo,
+    cpu_features: &CpuFeatures,
+) -> Result<PageTableSetup> {
+    // The pool is kept out of the kernel's usable memory
+    let pool_base = carve_loader_pages(memory_map, PAGE_TABLE_POOL_PAGES)?;
+    let pool = PageTablePool::new(pool_base, PAGE_TABLE_POOL_PAGES);
+
+    // Firmware paging is still live and identity maps all memory
+    let mut builder = PageTableBuilder::new(pool, 0)?;
+    builder.set_huge_pages(cpu_features.page_1gb);
+    let nx = if cpu_features.nx { flags::NO_EXECUTE } else { 0 };
+
+    // Identity map for the trampoline
+    for desc in memory_map.iter() {
+        let entry_flags = match MemoryType::from_u32(desc.memory_type) {
+            Some(MemoryType::LoaderCode | MemoryType::BootServicesCode) => {
+                flags::PRESENT | flags::WRITABLE
+            }
+            Some(MemoryType::LoaderData | MemoryType::BootServicesData) => {
+                flags::PRESENT | flags::WRITABLE | nx
+            }
+            _ => continue,
+        };
+        builder.identity_map(desc.physical_start, desc.number_of_pages * PAGE_SIZE, entry_flags)?;
+    }
+
+    // Higher-half kernel, W^X per segment
+    for segment in &kernel.segments {
+        let permissions = segment.permissions;
+        if permissions.is_wx() {
+            return Err(Error::SecurityViolation);
+        }
+        let mut entry_flags = flags::PRESENT | flags::GLOBAL;
+        if permissions.write {
+            entry_flags |= flags::WRITABLE;
+        }
+        if !permissions.execute {
+            entry_flags |= nx;
+        }
+        builder.map_range(segment.virt, segment.phys, segment.size(), entry_flags)?;
+    }
+
+    // Physical memory map, covering at least the low 4 GiB of MMIO
+    let phys_end = memory_map.iter()
+        .map(|desc| desc.physical_start.0 + desc.number_of_pages * PAGE_SIZE)
+        .max()
+        .unwrap_or(0)
+        .max(4 * HUGE_PAGE_SIZE);
+    builder.map_range(
+        VirtualAddress(PHYS_MAP_BASE),
+        PhysicalAddress(0),
+        phys_end,
+        flags::PRESENT | flags::WRITABLE | flags::GLOBAL | nx,
+    )?;
+
+    // Framebuffer
+    if framebuffer.address.0 != 0 {
+        let offset = framebuffer.address.0 & (PAGE_SIZE - 1);
+        let size = (offset + framebuffer.size + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+        if size > FRAMEBUFFER_VIRT.wrapping_neg() {
+            return Err(Error::InvalidParameter);
+        }
+        builder.map_range(
+            VirtualAddress(FRAMEBUFFER_VIRT),
+            PhysicalAddress(framebuffer.address.0 - offset),
+            size,
+            flags::PRESENT | flags::WRITABLE | flags::WRITE_THROUGH | flags::GLOBAL | nx,
+        )?;
+    }
+
+    Ok(PageTableSetup {
+        root: builder.root(),
+        no_execute: cpu_features.nx,
+        pool: pool_base,
+        pool_size: PAGE_TABLE_POOL_PAGES * PAGE_SIZE,
+    })
 }
 
 /// Set up page tables for kernel
+///
+/// Only x86_64 builds kernel page tables so far; elsewhere the kernel is
+/// entered on the firmware's identity map and `root` stays zero.
+#[cfg(not(target_arch = "x86_64"))]
 fn setup_paging(
     _kernel: &LoadedKernel,
-    _memory_map: &[MemoryDescriptor],
+    _memory_map: &mut Vec<MemoryDescriptor>,
     _framebuffer: &FramebufferInfo,
+    _cpu_features: &CpuFeatures,
 ) -> Result<PageTableSetup> {
-    // TODO: Build page tables for kernel
-    // - Identity map for transition
-    // - Higher half mapping for kernel
-    // - Physical memory map
-    // - Framebuffer mapping
-
-    Ok(PageTableSetup { root: 0 })
+    // TODO: Build translation tables for aarch64
+    Ok(PageTableSetup {
+        root: PhysicalAddress(0),
+        no_execute: false,
+        pool: PhysicalAddress(0),
+        pool_size: 0,
+    })
 }
 
 // =============================================================================
@@ -864,15 +1965,21 @@ fn allocate_kernel_stack(_memory_map: &[MemoryDescriptor]) -> Result<VirtualAddr
 /// Build boot info structure
 fn build_boot_info(
     framebuffer: &FramebufferInfo,
-    _memory_map: &[MemoryDescriptor],
+    memory_map: &HandoffMemoryMap,
+    handoff: &HandoffRegion,
     acpi: &AcpiInfo,
     smbios: Option<PhysicalAddress>,
     kernel: &LoadedKernel,
-    _modules: &LoadedModules,
+    modules: &LoadedModules,
     stack_top: VirtualAddress,
     config: &BootConfig,
+    measurements: MeasurementLog,
+    device_tree: Option<PhysicalAddress>,
+    display: Option<DisplayInfo>,
+    smp: Option<SmpInfo>,
 ) -> Result<BootInfo> {
     let boot_timestamp = BOOT_TIMESTAMP.load(Ordering::Relaxed);
+    let (memory_map_addr, memory_map_size) = handoff.write_memory_map(memory_map)?;
 
     let mut boot_info = BootInfo {
         magic: BOOT_INFO_MAGIC,
@@ -880,25 +1987,29 @@ fn build_boot_info(
         bootloader_name: [0; 64],
         bootloader_version: [0; 32],
         command_line: [0; 1024],
-        memory_map_addr: 0,
-        memory_map_size: 0,
-        memory_map_entry_size: 0,
+        memory_map_addr,
+        memory_map_size,
+        memory_map_entry_size: MemoryMapEntryRaw::SIZE as u64,
         framebuffer: framebuffer.clone(),
+        display,
         rsdp_addr: acpi.rsdp_addr,
         smbios_addr: smbios.unwrap_or(0),
         efi_system_table: unsafe { SYSTEM_TABLE.unwrap_or(core::ptr::null_mut()) as u64 },
         efi_runtime_services: 0,
-        module_count: 0,
-        modules_addr: 0,
+        module_count: modules.count as u32,
+        modules_addr: modules.table_addr.0,
         kernel_phys_base: kernel.phys_base,
         kernel_virt_base: kernel.virt_base,
         kernel_size: kernel.size,
         kernel_entry: kernel.entry_point,
         tls_template: kernel.tls_template,
         boot_timestamp,
-        cpu_count: 1,
-        bsp_apic_id: 0,
-        dtb_addr: None,
+        cpu_count: smp.as_ref().map_or(1, |smp| smp.enabled_count() as u32),
+        bsp_apic_id: smp.as_ref().map_or(0, |smp| smp.bsp_apic_id),
+        smp,
+        dtb_addr: device_tree,
+        measurements: Some(measurements),
+        loader_log: Some(debug::handoff()),
     };
 
     // Copy bootloader name
@@ -921,7 +2032,7 @@ fn handoff_to_kernel(
     kernel: &LoadedKernel,
     boot_info: &BootInfo,
     stack_top: VirtualAddress,
-    _page_tables: PageTableSetup,
+    page_tables: PageTableSetup,
 ) -> Result<()> {
     // Entry point function type
     type KernelEntry = extern "sysv64" fn(*const BootInfo) -> !;
@@ -939,7 +2050,17 @@ fn handoff_to_kernel(
         core::arch::asm!("msr DAIFSet, #3", options(nomem, nostack));
     }
 
-    // TODO: Switch to kernel page tables
+    // Switch to kernel page tables; the trampoline is identity mapped
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        use helix_uefi::arch::x86_64::{efer, msr, rdmsr, wrmsr, write_cr3};
+
+        if page_tables.no_execute {
+            wrmsr(msr::IA32_EFER, rdmsr(msr::IA32_EFER) | efer::NXE);
+        }
+        write_cr3(page_tables.root.0);
+    }
+
     // TODO: Switch to kernel stack
 
     // Jump to kernel
@@ -950,27 +2071,6 @@ fn handoff_to_kernel(
 // OUTPUT
 // =============================================================================
 
-/// Print banner
-fn print_banner(st: &EfiSystemTable) -> Result<()> {
-    let con_out = unsafe { &*st.con_out };
-
-    // Clear screen
-    let _ = unsafe { (con_out.clear_screen)(st.con_out) };
-
-    // Print banner
-    let banner: &[u16] = &[
-        'H' as u16, 'e' as u16, 'l' as u16, 'i' as u16, 'x' as u16, ' ' as u16,
-        'U' as u16, 'E' as u16, 'F' as u16, 'I' as u16, ' ' as u16,
-        'B' as u16, 'o' as u16, 'o' as u16, 't' as u16, 'l' as u16,
-        'o' as u16, 'a' as u16, 'd' as u16, 'e' as u16, 'r' as u16,
-        '\r' as u16, '\n' as u16, 0,
-    ];
-
-    let _ = unsafe { (con_out.output_string)(st.con_out, banner.as_ptr()) };
-
-    Ok(())
-}
-
 /// Print CPU information
 fn print_cpu_info(_st: &EfiSystemTable, _features: &CpuFeatures) -> Result<()> {
     // TODO: Print CPU features
@@ -989,21 +2089,34 @@ fn print_kernel_info(_st: &EfiSystemTable, _kernel: &LoadedKernel) -> Result<()>
     Ok(())
 }
 
-/// Print error
-unsafe fn print_error(st: &EfiSystemTable, error: Error) -> Result<()> {
-    let con_out = &*st.con_out;
+/// Text output on the firmware console
+struct ConOut<'a>(&'a EfiSystemTable);
 
-    let msg: &[u16] = &[
-        'E' as u16, 'r' as u16, 'r' as u16, 'o' as u16, 'r' as u16,
-        ':' as u16, ' ' as u16,
-        '0' as u16 + (error as u16 / 10),
-        '0' as u16 + (error as u16 % 10),
-        '\r' as u16, '\n' as u16, 0,
-    ];
+impl fmt::Write for ConOut<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let con_out = unsafe { &*self.0.con_out };
+        let mut buf = [0u16; 128];
+        let mut len = 0;
 
-    let _ = (con_out.output_string)(st.con_out, msg.as_ptr());
+        for c in s.chars() {
+            if c == '\n' {
+                buf[len] = '\r' as u16;
+                len += 1;
+            }
+            buf[len] = if (c as u32) < 0x10000 { c as u16 } else { '?' as u16 };
+            len += 1;
 
-    Ok(())
+            if len >= buf.len() - 3 {
+                buf[len] = 0;
+                let _ = unsafe { (con_out.output_string)(self.0.con_out, buf.as_ptr()) };
+                len = 0;
+            }
+        }
+
+        buf[len] = 0;
+        let _ = unsafe { (con_out.output_string)(self.0.con_out, buf.as_ptr()) };
+        Ok(())
+    }
 }
 
 // =============================================================================
@@ -1012,24 +2125,8 @@ unsafe fn print_error(st: &EfiSystemTable, error: Error) -> Result<()> {
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
-    // Try to print panic message if possible
-    unsafe {
-        if let Some(st) = SYSTEM_TABLE {
-            if !BOOT_SERVICES_EXITED.load(Ordering::Acquire) {
-                let st = &*st;
-                if !st.con_out.is_null() {
-                    let con_out = &*st.con_out;
-
-                    let msg: &[u16] = &[
-                        'P' as u16, 'A' as u16, 'N' as u16, 'I' as u16, 'C' as u16,
-                        '!' as u16, '\r' as u16, '\n' as u16, 0,
-                    ];
-
-                    let _ = (con_out.output_string)(st.con_out, msg.as_ptr());
-                }
-            }
-        }
-    }
+    // Serial and the ring always, the firmware console until it is detached
+    log_fatal!("{}", info);
 
     // Halt
     loop {
@@ -44,6 +44,8 @@
 
 #![no_std]
 
+use alloc::vec;
+use alloc::vec::Vec;
 use core::fmt;
 
 // =============================================================================
@@ -253,6 +255,24 @@ pub fn model_str(&self) -> &str {
     pub fn serial_str(&self) -> &str {
         core::str::from_utf8(&self.serial[..self.serial_len]).unwrap_or("")
     }
+
+    /// Check a block request against the device geometry
+    ///
+    /// Returns the number of blocks `len` bytes at `lba` cover.
+    pub fn check_request(&self, lba: u64, len: usize) -> Result<u64, BlockError> {
+        if !self.media_present {
+            return Err(BlockError::MediaNotPresent);
+        }
+        let block_size = self.block_size as usize;
+        if len % block_size != 0 {
+            return Err(BlockError::BufferTooSmall);
+        }
+        let count = (len / block_size) as u64;
+        match lba.checked_add(count) {
+            Some(end) if end <= self.total_blocks => Ok(count),
+            _ => Err(BlockError::InvalidLba),
+        }
+    }
 }
 
 impl Default for BlockDeviceInfo {
@@ -928,6 +948,242 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
+// =============================================================================
+// BLOCK DEVICE INTERFACE
+// =============================================================================
+
+/// A device addressed in fixed-size logical blocks
+///
+/// Implemented by the storage drivers so partitions and filesystems can sit
+/// on top of any of them. Buffers passed to the block methods must be a
+/// whole number of blocks long.
+pub trait BlockDevice {
+    /// Device geometry and identity
+    fn info(&self) -> &BlockDeviceInfo;
+
+    /// Read `buffer.len() / block_size` blocks starting at `lba`
+    fn read_blocks(&self, lba: u64, buffer: &mut [u8]) -> Result<(), BlockError>;
+
+    /// Write `buffer.len() / block_size` blocks starting at `lba`
+    fn write_blocks(&self, _lba: u64, _buffer: &[u8]) -> Result<(), BlockError> {
+        Err(BlockError::WriteProtected)
+    }
+
+    /// Flush volatile caches to the medium
+    fn flush(&self) -> Result<(), BlockError> {
+        Ok(())
+    }
+
+    /// Read bytes at an arbitrary offset
+    ///
+    /// Partial blocks at either end go through a one-block bounce buffer;
+    /// the aligned middle is read in place.
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), BlockError> {
+        let info = self.info();
+        if !info.media_present {
+            return Err(BlockError::MediaNotPresent);
+        }
+        let block_size = info.block_size as u64;
+        let end = offset.checked_add(buffer.len() as u64).ok_or(BlockError::InvalidLba)?;
+        if end > info.capacity_bytes() {
+            return Err(BlockError::InvalidLba);
+        }
+
+        let mut lba = offset / block_size;
+        let head = (offset % block_size) as usize;
+        let mut done = 0;
+        let mut bounce = Vec::new();
+
+        if head != 0 {
+            bounce.resize(block_size as usize, 0);
+            self.read_blocks(lba, &mut bounce)?;
+            let len = buffer.len().min(block_size as usize - head);
+            buffer[..len].copy_from_slice(&bounce[head..head + len]);
+            done = len;
+            lba += 1;
+        }
+
+        let whole = (buffer.len() - done) / block_size as usize * block_size as usize;
+        if whole != 0 {
+            self.read_blocks(lba, &mut buffer[done..done + whole])?;
+            done += whole;
+            lba += (whole as u64) / block_size;
+        }
+
+        if done < buffer.len() {
+            bounce.resize(block_size as usize, 0);
+            self.read_blocks(lba, &mut bounce)?;
+            let len = buffer.len() - done;
+            buffer[done..].copy_from_slice(&bounce[..len]);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: BlockDevice + ?Sized> BlockDevice for &T {
+    fn info(&self) -> &BlockDeviceInfo {
+        (**self).info()
+    }
+
+    fn read_blocks(&self, lba: u64, buffer: &mut [u8]) -> Result<(), BlockError> {
+        (**self).read_blocks(lba, buffer)
+    }
+
+    fn write_blocks(&self, lba: u64, buffer: &[u8]) -> Result<(), BlockError> {
+        (**self).write_blocks(lba, buffer)
+    }
+
+    fn flush(&self) -> Result<(), BlockError> {
+        (**self).flush()
+    }
+}
+
+/// A partition of a block device, addressed from its first block
+#[derive(Debug)]
+pub struct BlockSlice<D: BlockDevice> {
+    device: D,
+    start_lba: u64,
+    info: BlockDeviceInfo,
+}
+
+impl<D: BlockDevice> BlockSlice<D> {
+    /// View `partition` of `device`
+    pub fn new(device: D, partition: &PartitionInfo) -> Result<Self, BlockError> {
+        let end = partition
+            .start_lba
+            .checked_add(partition.size_sectors)
+            .ok_or(BlockError::InvalidPartitionTable)?;
+        if partition.size_sectors == 0 || end > device.info().total_blocks {
+            return Err(BlockError::InvalidPartitionTable);
+        }
+
+        let mut info = device.info().clone();
+        info.total_blocks = partition.size_sectors;
+        Ok(Self {
+            device,
+            start_lba: partition.start_lba,
+            info,
+        })
+    }
+
+    /// First block of the partition on the device
+    pub const fn start_lba(&self) -> u64 {
+        self.start_lba
+    }
+
+    /// The underlying device
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+}
+
+impl<D: BlockDevice> BlockDevice for BlockSlice<D> {
+    fn info(&self) -> &BlockDeviceInfo {
+        &self.info
+    }
+
+    fn read_blocks(&self, lba: u64, buffer: &mut [u8]) -> Result<(), BlockError> {
+        self.info.check_request(lba, buffer.len())?;
+        self.device.read_blocks(self.start_lba + lba, buffer)
+    }
+
+    fn write_blocks(&self, lba: u64, buffer: &[u8]) -> Result<(), BlockError> {
+        self.info.check_request(lba, buffer.len())?;
+        self.device.write_blocks(self.start_lba + lba, buffer)
+    }
+
+    fn flush(&self) -> Result<(), BlockError> {
+        self.device.flush()
+    }
+}
+
+// =============================================================================
+// PARTITION DISCOVERY
+// =============================================================================
+
+/// Upper bound on GPT entries read, as in the UEFI default table
+const MAX_GPT_ENTRIES: u32 = 128;
+
+/// Read the partition table of `device`
+///
+/// GPT disks are recognised by their protective MBR; anything else with a
+/// valid boot signature yields its primary MBR partitions.
+pub fn read_partitions<D: BlockDevice + ?Sized>(device: &D) -> Result<Vec<PartitionInfo>, BlockError> {
+    let mut sector = [0u8; 512];
+    device.read_at(0, &mut sector)?;
+    // SAFETY: MbrBootSector is 512 bytes of plain integers
+    let mbr: MbrBootSector = unsafe { core::ptr::read_unaligned(sector.as_ptr().cast()) };
+    if !mbr.is_valid() {
+        return Err(BlockError::InvalidPartitionTable);
+    }
+    if mbr.is_gpt_protective() {
+        return read_gpt(device);
+    }
+
+    let partitions = mbr.partitions;
+    Ok(partitions
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| !entry.is_empty() && !entry.is_extended())
+        .map(|(i, entry)| {
+            let mut part = PartitionInfo::new(i as u32 + 1, PartitionScheme::Mbr);
+            part.start_lba = entry.start_lba as u64;
+            part.size_sectors = entry.size_sectors as u64;
+            part.end_lba = part.start_lba + part.size_sectors.saturating_sub(1);
+            part.bootable = entry.is_bootable();
+            part.mbr_type = entry.partition_type;
+            part
+        })
+        .collect())
+}
+
+fn read_gpt<D: BlockDevice + ?Sized>(device: &D) -> Result<Vec<PartitionInfo>, BlockError> {
+    let block_size = device.info().block_size as u64;
+    let mut data = [0u8; core::mem::size_of::<GptHeader>()];
+    device.read_at(block_size, &mut data)?;
+    // SAFETY: GptHeader is plain integers and byte arrays
+    let header: GptHeader = unsafe { core::ptr::read_unaligned(data.as_ptr().cast()) };
+    let entry_size = header.partition_entry_size as usize;
+    if !header.is_valid_signature() || entry_size < core::mem::size_of::<GptPartitionEntry>() {
+        return Err(BlockError::InvalidPartitionTable);
+    }
+
+    let count = header.num_partition_entries.min(MAX_GPT_ENTRIES) as usize;
+    let mut entries = vec![0u8; count * entry_size];
+    device.read_at(header.partition_entry_lba * block_size, &mut entries)?;
+
+    let mut partitions = Vec::new();
+    for (i, raw) in entries.chunks_exact(entry_size).enumerate() {
+        // SAFETY: Each chunk holds at least one entry of plain data
+        let entry: GptPartitionEntry = unsafe { core::ptr::read_unaligned(raw.as_ptr().cast()) };
+        if entry.is_empty() || entry.size_blocks() == 0 {
+            continue;
+        }
+
+        let mut part = PartitionInfo::new(i as u32 + 1, PartitionScheme::Gpt);
+        part.start_lba = entry.starting_lba;
+        part.end_lba = entry.ending_lba;
+        part.size_sectors = entry.size_blocks();
+        part.bootable = entry.is_legacy_bootable();
+        part.type_guid = entry.partition_type_guid;
+        part.unique_guid = entry.unique_partition_guid;
+
+        // Names are UTF-16; keep the ASCII subset
+        let mut name = [0u8; 36];
+        let mut len = 0;
+        let units = entry.partition_name;
+        for unit in units.iter().take_while(|&&unit| unit != 0) {
+            name[len] = if *unit < 0x80 { *unit as u8 } else { b'?' };
+            len += 1;
+        }
+        part.set_name(&name[..len]);
+        partitions.push(part);
+    }
+
+    Ok(partitions)
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -980,6 +1236,95 @@ fn test_gpt_partition_entry() {
         assert_eq!(entry.size_blocks(), 204800);
     }
 
+    struct MemDisk {
+        info: BlockDeviceInfo,
+        data: Vec<u8>,
+    }
+
+    impl MemDisk {
+        fn new(blocks: u64) -> Self {
+            let mut info = BlockDeviceInfo::new(BlockDeviceType::Nvme);
+            info.total_blocks = blocks;
+            let data = (0..blocks as usize * 512).map(|i| (i % 251) as u8).collect();
+            Self { info, data }
+        }
+    }
+
+    impl BlockDevice for MemDisk {
+        fn info(&self) -> &BlockDeviceInfo {
+            &self.info
+        }
+
+        fn read_blocks(&self, lba: u64, buffer: &mut [u8]) -> Result<(), BlockError> {
+            self.info.check_request(lba, buffer.len())?;
+            let start = lba as usize * 512;
+            buffer.copy_from_slice(&self.data[start..start + buffer.len()]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_read_at_unaligned() {
+        let disk = MemDisk::new(8);
+        let mut buf = [0u8; 1100];
+        disk.read_at(300, &mut buf).unwrap();
+        assert_eq!(&buf[..], &disk.data[300..1400]);
+
+        let mut small = [0u8; 10];
+        disk.read_at(510, &mut small).unwrap();
+        assert_eq!(&small[..], &disk.data[510..520]);
+
+        assert_eq!(disk.read_at(4000, &mut buf), Err(BlockError::InvalidLba));
+        assert_eq!(disk.read_blocks(0, &mut small), Err(BlockError::BufferTooSmall));
+    }
+
+    #[test]
+    fn test_block_slice() {
+        let disk = MemDisk::new(8);
+        let mut part = PartitionInfo::new(1, PartitionScheme::Gpt);
+        part.start_lba = 2;
+        part.size_sectors = 4;
+
+        let slice = BlockSlice::new(&disk, &part).unwrap();
+        assert_eq!(slice.info().total_blocks, 4);
+        let mut buf = [0u8; 512];
+        slice.read_blocks(3, &mut buf).unwrap();
+        assert_eq!(&buf[..], &disk.data[5 * 512..6 * 512]);
+        assert_eq!(slice.read_blocks(4, &mut buf), Err(BlockError::InvalidLba));
+        assert_eq!(slice.write_blocks(0, &buf), Err(BlockError::WriteProtected));
+
+        part.size_sectors = 7;
+        assert!(BlockSlice::new(&disk, &part).is_err());
+    }
+
+    #[test]
+    fn test_read_partitions_gpt() {
+        let mut disk = MemDisk::new(64);
+        disk.data.fill(0);
+        disk.data[446 + 4] = 0xEE;
+        disk.data[510..512].copy_from_slice(&[0x55, 0xAA]);
+
+        let header = &mut disk.data[512..1024];
+        header[..8].copy_from_slice(b"EFI PART");
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+
+        let entry = &mut disk.data[1024 + 128..1024 + 256];
+        entry[..16].copy_from_slice(&gpt_types::LINUX_FILESYSTEM);
+        entry[32..40].copy_from_slice(&34u64.to_le_bytes());
+        entry[40..48].copy_from_slice(&63u64.to_le_bytes());
+        entry[56..62].copy_from_slice(&[b'r', 0, b'o', 0, b'o', 0]);
+        entry[62] = b't';
+
+        let parts = read_partitions(&disk).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].number, 2);
+        assert!(parts[0].is_linux());
+        assert_eq!(parts[0].size_sectors, 30);
+        assert_eq!(&parts[0].name[..parts[0].name_len], b"root");
+    }
+
     #[test]
     fn test_smart_status() {
         let status = SmartStatus::Healthy;
@@ -10,11 +10,14 @@
 //! - Configuration loading
 //! - State machine management
 //! - Error handling integration
+//! - Discovered Helix installations
 
 #![no_std]
 
 use core::fmt;
 
+use crate::partition::BootCandidate;
+
 // =============================================================================
 // BOOT MANAGER STATE
 // =============================================================================
@@ -420,6 +423,39 @@ pub const fn is_bootable(&self) -> bool {
     }
 }
 
+/// Kernel path on the ESP next to a discovered system partition
+pub const DISCOVERED_KERNEL_PATH: &str = "\\EFI\\helix\\kernel";
+
+/// Initrd path on the ESP next to a discovered system partition
+pub const DISCOVERED_INITRD_PATH: &str = "\\EFI\\helix\\initrd";
+
+/// Priority of the best discovered entry; configured entries come first
+pub const DISCOVERED_PRIORITY: u8 = 192;
+
+impl BootEntry {
+    /// Entry booting a discovered Helix installation
+    ///
+    /// Paths are on the ESP of the candidate's disk, which is
+    /// `partition_id`; the system partition is passed as the root.
+    /// Returns `None` when the disk has no ESP to load the kernel from.
+    pub fn from_candidate(candidate: &BootCandidate) -> Option<Self> {
+        let esp = candidate.esp?;
+        let mut entry = Self::new();
+        entry.entry_type = EntryType::HelixKernel;
+        entry.flags.set(EntryFlags::AUTO_DETECTED);
+        match candidate.superblock.label() {
+            "" => entry.set_title("Helix OS"),
+            label => entry.set_title(&alloc::format!("Helix OS ({})", label)),
+        }
+        entry.set_path(DISCOVERED_KERNEL_PATH);
+        entry.set_initrd(DISCOVERED_INITRD_PATH);
+        entry.set_args(&alloc::format!("root=PARTUUID={:x}", candidate.partition.partition_guid));
+        entry.device_id = candidate.disk;
+        entry.partition_id = esp.index;
+        Some(entry)
+    }
+}
+
 // =============================================================================
 // BOOT ENTRY LIST
 // =============================================================================
@@ -905,6 +941,33 @@ pub fn update(&mut self, elapsed_ms: u32) {
         }
     }
 
+    /// Add entries for discovered Helix installations
+    ///
+    /// `candidates` are ranked best first, as returned by
+    /// [`crate::partition::discover`], and keep that order after any
+    /// configured entries. The best becomes the default when no configured
+    /// entry is. Returns the number of entries added.
+    pub fn add_discovered(&mut self, candidates: &[BootCandidate]) -> usize {
+        let has_default = (0..self.entries.len())
+            .filter_map(|i| self.entries.get(i))
+            .any(BootEntry::is_default);
+
+        let mut added = 0;
+        for candidate in candidates {
+            let Some(mut entry) = BootEntry::from_candidate(candidate) else { continue };
+            entry.priority = DISCOVERED_PRIORITY.saturating_add(added as u8);
+            if !has_default && added == 0 {
+                entry.flags.set(EntryFlags::DEFAULT);
+            }
+            if self.entries.add(entry).is_none() {
+                break;
+            }
+            added += 1;
+        }
+        self.entries.sort_by_priority();
+        added
+    }
+
     /// Get selected entry
     pub fn selected_entry(&self) -> Option<&BootEntry> {
         self.entries.selected()
@@ -1014,4 +1077,53 @@ fn test_boot_manager_flow() {
         mgr.advance();
         assert_eq!(mgr.state, BootState::DiscoveringEntries);
     }
+
+    #[test]
+    fn test_add_discovered() {
+        use crate::partition::{Guid, HelixSuperblock, PartitionInfo};
+
+        let system = PartitionInfo {
+            index: 1,
+            partition_guid: Guid::new(0xA1B2C3D4, 0xE5F6, 0x0718, [0x29, 0x3A, 0, 0, 0, 0, 0, 1]),
+            ..Default::default()
+        };
+        let esp = PartitionInfo::default();
+        let mut label = [0u8; 32];
+        label[..4].copy_from_slice(b"main");
+        let candidate = BootCandidate {
+            disk: 2,
+            partition: system,
+            attributes: 0,
+            superblock: HelixSuperblock {
+                uuid: Guid::NULL,
+                label,
+                label_len: 4,
+                block_size: 4096,
+                total_blocks: 1,
+                write_time: 0,
+                clean: true,
+            },
+            esp: Some(esp),
+            esp_volume: None,
+        };
+        let no_esp = BootCandidate { esp: None, ..candidate };
+
+        let mut mgr = BootManager::new();
+        let mut configured = BootEntry::new();
+        configured.set_title("Configured");
+        mgr.entries.add(configured);
+
+        assert_eq!(mgr.add_discovered(&[no_esp, candidate, candidate]), 2);
+        assert_eq!(mgr.entries.len(), 3);
+        assert_eq!(mgr.entries.get(0).unwrap().title(), "Configured");
+
+        let entry = mgr.entries.get(1).unwrap();
+        assert_eq!(entry.title(), "Helix OS (main)");
+        assert_eq!(entry.path(), DISCOVERED_KERNEL_PATH);
+        assert_eq!(entry.args(), "root=PARTUUID=a1b2c3d4-e5f6-0718-293a-000000000001");
+        assert_eq!((entry.device_id, entry.partition_id), (2, 0));
+        assert!(entry.flags.has(EntryFlags::AUTO_DETECTED));
+        assert_eq!(mgr.entries.default_index(), 1);
+        assert!(!mgr.entries.get(2).unwrap().is_default());
+    }
 }
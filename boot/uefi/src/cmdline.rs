@@ -0,0 +1,85 @@
+//! # Kernel Command Line for UEFI
+//!
+//! Integration of the helix-cmdline subsystem with the UEFI boot menu.
+//!
+//! Edits made in the boot menu are validated against the standard kernel
+//! parameters before the kernel is started, so mistakes are reported while
+//! the user can still correct them.
+
+#[cfg(feature = "cmdline")]
+pub use helix_cmdline::{
+    Cmdline, CmdlineError, ParamSpec, Registry, Value,
+    boot_registry, BOOT_PARAMS,
+};
+
+#[cfg(feature = "cmdline")]
+use helix_cmdline::ParamFlags;
+
+/// Validate an edited kernel command line
+///
+/// Only boot-relevant parameters are checked; unknown arguments pass
+/// through untouched. Always succeeds without the `cmdline` feature.
+pub fn validate_edit(cmdline: &str) -> Result<(), alloc::string::String> {
+    #[cfg(feature = "cmdline")]
+    {
+        boot_registry()
+            .validate_with(&Cmdline::new(cmdline), ParamFlags::BOOT)
+            .map_err(|e| alloc::format!("{}", e))
+    }
+
+    #[cfg(not(feature = "cmdline"))]
+    {
+        let _ = cmdline;
+        Ok(())
+    }
+}
+
+/// `lockdown=` levels, least restrictive first
+pub const LOCKDOWN_LEVELS: [&str; 3] = ["none", "integrity", "confidentiality"];
+
+/// Lockdown level a kernel started with `cmdline` runs under
+///
+/// The last `lockdown=` value, raised to `integrity` under Secure Boot as
+/// the kernel does. An unknown value counts as `none`.
+pub fn lockdown_level(cmdline: &str, secure_boot: bool) -> &'static str {
+    let requested = cmdline
+        .split_whitespace()
+        .filter_map(|arg| arg.strip_prefix("lockdown="))
+        .next_back()
+        .and_then(|value| LOCKDOWN_LEVELS.iter().position(|level| *level == value))
+        .unwrap_or(0);
+    LOCKDOWN_LEVELS[if secure_boot { requested.max(1) } else { requested }]
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_edit_accepts_valid() {
+        assert!(validate_edit("quiet root=/dev/sda2 loglevel=3").is_ok());
+        assert!(validate_edit("").is_ok());
+    }
+
+    #[test]
+    fn test_lockdown_level() {
+        assert_eq!(lockdown_level("quiet", false), "none");
+        assert_eq!(lockdown_level("quiet", true), "integrity");
+        assert_eq!(lockdown_level("lockdown=none", true), "integrity");
+        assert_eq!(lockdown_level("lockdown=integrity lockdown=confidentiality", false), "confidentiality");
+        assert_eq!(lockdown_level("lockdown=confidentiality", true), "confidentiality");
+        assert_eq!(lockdown_level("lockdown=bogus", false), "none");
+    }
+
+    #[cfg(feature = "cmdline")]
+    #[test]
+    fn test_validate_edit_rejects_invalid() {
+        assert!(validate_edit("loglevel=12").is_err());
+        assert!(validate_edit("nokaslr=1").is_err());
+        assert!(validate_edit("console=\"ttyS0").is_err());
+    }
+}
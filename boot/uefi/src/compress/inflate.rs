@@ -0,0 +1,292 @@
+//! DEFLATE Decompression
+//!
+//! Inflate for raw DEFLATE streams (RFC 1951) and the gzip (RFC 1952) and
+//! zlib (RFC 1950) containers around them. Codes are decoded canonically,
+//! one bit at a time, which is plenty for boot payloads.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{BitReader, CompressionError, GzipHeader};
+use crate::diag::{adler32, crc32};
+
+/// Longest code, in bits
+const MAX_BITS: usize = 15;
+
+/// Literal/length symbols
+const MAX_LITLEN: usize = 288;
+
+/// Distance symbols
+const MAX_DIST: usize = 30;
+
+/// Base lengths of length symbols 257..285
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+
+/// Extra bits of length symbols 257..285
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distances of distance symbols 0..29
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// Extra bits of distance symbols 0..29
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+/// Order code length code lengths are sent in
+const CLEN_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Canonical Huffman code
+struct Huffman {
+    /// Codes of each length
+    counts: [u16; MAX_BITS + 1],
+    /// Symbols ordered by code
+    symbols: [u16; MAX_LITLEN],
+}
+
+impl Huffman {
+    /// Build from per-symbol code lengths (0 = unused)
+    ///
+    /// Incomplete codes are allowed; a stream using a missing code fails
+    /// when decoded.
+    fn new(lengths: &[u8]) -> Result<Self, CompressionError> {
+        let mut code = Self { counts: [0; MAX_BITS + 1], symbols: [0; MAX_LITLEN] };
+        for &len in lengths {
+            code.counts[len as usize] += 1;
+        }
+
+        let mut left = 1i32;
+        for len in 1..=MAX_BITS {
+            left = (left << 1) - code.counts[len] as i32;
+            if left < 0 {
+                return Err(CompressionError::InvalidData);
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + code.counts[len];
+        }
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                code.symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(code)
+    }
+
+    /// Decode one symbol
+    fn decode(&self, bits: &mut BitReader<'_>) -> Result<u16, CompressionError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= bit(bits)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(CompressionError::InvalidData)
+    }
+}
+
+fn bits(reader: &mut BitReader<'_>, count: u8) -> Result<u32, CompressionError> {
+    reader.read_bits(count).ok_or(CompressionError::IncompleteData)
+}
+
+fn bit(reader: &mut BitReader<'_>) -> Result<u32, CompressionError> {
+    bits(reader, 1)
+}
+
+/// Inflate the raw DEFLATE stream at the start of `input`, appending to
+/// `output`; returns the number of input bytes consumed
+pub fn inflate(input: &[u8], output: &mut Vec<u8>) -> Result<usize, CompressionError> {
+    let mut reader = BitReader::new(input);
+    let start = output.len();
+
+    loop {
+        let last = bit(&mut reader)? != 0;
+        match bits(&mut reader, 2)? {
+            0 => stored(&mut reader, output)?,
+            1 => {
+                let (litlen, dist) = fixed_codes()?;
+                codes(&mut reader, output, start, &litlen, &dist)?;
+            }
+            2 => {
+                let (litlen, dist) = dynamic_codes(&mut reader)?;
+                codes(&mut reader, output, start, &litlen, &dist)?;
+            }
+            _ => return Err(CompressionError::InvalidData),
+        }
+        if last {
+            break;
+        }
+    }
+
+    reader.align();
+    Ok(reader.byte_position())
+}
+
+/// Stored block: byte aligned length, its complement, then the bytes
+fn stored(reader: &mut BitReader<'_>, output: &mut Vec<u8>) -> Result<(), CompressionError> {
+    reader.align();
+    let len = bits(reader, 16)?;
+    if bits(reader, 16)? != !len & 0xFFFF {
+        return Err(CompressionError::InvalidData);
+    }
+    output.reserve(len as usize);
+    for _ in 0..len {
+        output.push(bits(reader, 8)? as u8);
+    }
+    Ok(())
+}
+
+/// Codes of fixed Huffman blocks
+fn fixed_codes() -> Result<(Huffman, Huffman), CompressionError> {
+    let mut lengths = [0u8; MAX_LITLEN];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; MAX_DIST])?))
+}
+
+/// Codes sent at the start of a dynamic Huffman block
+fn dynamic_codes(reader: &mut BitReader<'_>) -> Result<(Huffman, Huffman), CompressionError> {
+    let nlen = bits(reader, 5)? as usize + 257;
+    let ndist = bits(reader, 5)? as usize + 1;
+    let ncode = bits(reader, 4)? as usize + 4;
+    if nlen > 286 || ndist > MAX_DIST {
+        return Err(CompressionError::InvalidData);
+    }
+
+    let mut clens = [0u8; 19];
+    for &index in &CLEN_ORDER[..ncode] {
+        clens[index] = bits(reader, 3)? as u8;
+    }
+    let clen_code = Huffman::new(&clens)?;
+
+    // Literal/length and distance code lengths form one run-length coded list
+    let mut lengths = [0u8; 286 + MAX_DIST];
+    let mut index = 0;
+    while index < nlen + ndist {
+        let symbol = clen_code.decode(reader)?;
+        if symbol < 16 {
+            lengths[index] = symbol as u8;
+            index += 1;
+            continue;
+        }
+        let (value, repeat) = match symbol {
+            16 if index > 0 => (lengths[index - 1], 3 + bits(reader, 2)?),
+            17 => (0, 3 + bits(reader, 3)?),
+            18 => (0, 11 + bits(reader, 7)?),
+            _ => return Err(CompressionError::InvalidData),
+        };
+        let repeat = repeat as usize;
+        if index + repeat > nlen + ndist {
+            return Err(CompressionError::InvalidData);
+        }
+        lengths[index..index + repeat].fill(value);
+        index += repeat;
+    }
+
+    // A block without an end-of-block code could never end
+    if lengths[256] == 0 {
+        return Err(CompressionError::InvalidData);
+    }
+    Ok((Huffman::new(&lengths[..nlen])?, Huffman::new(&lengths[nlen..nlen + ndist])?))
+}
+
+/// Decode a Huffman coded block
+fn codes(
+    reader: &mut BitReader<'_>,
+    output: &mut Vec<u8>,
+    start: usize,
+    litlen: &Huffman,
+    dist: &Huffman,
+) -> Result<(), CompressionError> {
+    loop {
+        let symbol = litlen.decode(reader)? as usize;
+        if symbol < 256 {
+            output.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+
+        let symbol = symbol - 257;
+        if symbol >= LENGTH_BASE.len() {
+            return Err(CompressionError::InvalidData);
+        }
+        let len = LENGTH_BASE[symbol] as usize + bits(reader, LENGTH_EXTRA[symbol])? as usize;
+
+        let symbol = dist.decode(reader)? as usize;
+        if symbol >= DIST_BASE.len() {
+            return Err(CompressionError::InvalidData);
+        }
+        let distance = DIST_BASE[symbol] as usize + bits(reader, DIST_EXTRA[symbol])? as usize;
+        if distance > output.len() - start {
+            return Err(CompressionError::InvalidData);
+        }
+
+        // Byte by byte: the match may overlap what it produces
+        let from = output.len() - distance;
+        output.reserve(len);
+        for i in 0..len {
+            output.push(output[from + i]);
+        }
+    }
+}
+
+/// Decompress a gzip member, checking its CRC-32 and length
+pub fn gunzip(data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    let (header, offset) = GzipHeader::parse(data).ok_or(CompressionError::InvalidData)?;
+    if header.method != 8 {
+        return Err(CompressionError::UnsupportedFormat);
+    }
+
+    let mut output = Vec::new();
+    let used = inflate(data.get(offset..).ok_or(CompressionError::IncompleteData)?, &mut output)?;
+
+    let trailer = data.get(offset + used..offset + used + 8).ok_or(CompressionError::IncompleteData)?;
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if crc != crc32(&output) || size != output.len() as u32 {
+        return Err(CompressionError::ChecksumMismatch);
+    }
+    Ok(output)
+}
+
+/// Decompress a zlib stream, checking its Adler-32
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    let (&cmf, &flg) = (data.first().ok_or(CompressionError::IncompleteData)?, data.get(1).ok_or(CompressionError::IncompleteData)?);
+    if ((u16::from(cmf) << 8) | u16::from(flg)) % 31 != 0 {
+        return Err(CompressionError::InvalidData);
+    }
+    // Deflate only, no preset dictionary
+    if cmf & 0x0F != 8 || flg & 0x20 != 0 {
+        return Err(CompressionError::UnsupportedFormat);
+    }
+
+    let mut output = Vec::new();
+    let used = inflate(&data[2..], &mut output)?;
+    let trailer = data.get(2 + used..2 + used + 4).ok_or(CompressionError::IncompleteData)?;
+    if u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) != adler32(&output) {
+        return Err(CompressionError::ChecksumMismatch);
+    }
+    Ok(output)
+}
@@ -1,9 +1,20 @@
 //! Compression and Decompression
 //!
 //! Compression algorithms for boot payloads and initrd decompression.
+//!
+//! gzip, zlib and Zstandard payloads are decompressed by [`inflate`] and
+//! [`zstd`]; [`decompress`] picks the decoder from the magic bytes.
 
+extern crate alloc;
+use alloc::vec::Vec;
 use core::fmt;
 
+pub mod inflate;
+pub mod zstd;
+
+pub use inflate::{gunzip, zlib_decompress};
+pub use zstd::zstd_decompress;
+
 // =============================================================================
 // COMPRESSION TYPES
 // =============================================================================
@@ -63,6 +74,16 @@ pub fn detect(data: &[u8]) -> Self {
     }
 }
 
+/// Decompress a gzip, zlib or Zstandard payload
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    match CompressionType::detect(data) {
+        CompressionType::Deflate if data[0] == 0x1F => gunzip(data),
+        CompressionType::Deflate => zlib_decompress(data),
+        CompressionType::Zstd => zstd_decompress(data),
+        _ => Err(CompressionError::UnsupportedFormat),
+    }
+}
+
 // =============================================================================
 // RLE COMPRESSION (SIMPLE)
 // =============================================================================
@@ -535,6 +556,11 @@ pub fn align(&mut self) {
             self.bits_in_buffer -= discard;
         }
     }
+
+    /// Offset of the next whole byte not yet read
+    pub fn byte_position(&self) -> usize {
+        self.pos - (self.bits_in_buffer / 8) as usize
+    }
 }
 
 /// Bit writer for compression
@@ -766,6 +792,17 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
+impl From<CompressionError> for crate::error::Error {
+    fn from(err: CompressionError) -> Self {
+        match err {
+            CompressionError::BufferTooSmall => Self::BufferTooSmall,
+            CompressionError::UnsupportedFormat => Self::UnsupportedFormat,
+            CompressionError::ChecksumMismatch => Self::CrcError,
+            CompressionError::InvalidData | CompressionError::IncompleteData => Self::InvalidData,
+        }
+    }
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -816,4 +853,55 @@ fn test_bit_writer() {
 
         assert_eq!(buffer[0], 0b11001010);
     }
+
+    /// `gzip -9` and `zstd -19 --check` of [`payload`]
+    const PAYLOAD_GZ: [u8; 98] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xf3, 0x48, 0xcd, 0xc9, 0xac, 0x50,
+        0xc8, 0x4e, 0x2d, 0xca, 0x4b, 0xcd, 0x51, 0xc8, 0xcc, 0x4d, 0x4c, 0x4f, 0xd5, 0x51, 0x28, 0x4e,
+        0x4d, 0x2e, 0xc9, 0xcc, 0xcf, 0x53, 0x30, 0xb0, 0x52, 0xd0, 0x2b, 0x49, 0xad, 0x28, 0x51, 0xd0,
+        0x2b, 0xca, 0x4f, 0x49, 0x2c, 0x49, 0x54, 0xd0, 0x83, 0x90, 0x49, 0xc5, 0xc5, 0x5c, 0x1e, 0x78,
+        0xf4, 0x19, 0x92, 0xa9, 0xcf, 0x88, 0x4c, 0x7d, 0xc6, 0x64, 0xea, 0x33, 0x21, 0x53, 0x9f, 0x29,
+        0x99, 0xfa, 0xcc, 0xc8, 0xd4, 0x67, 0x8e, 0x47, 0x1f, 0x00, 0x47, 0x4e, 0x73, 0xf8, 0xc0, 0x01,
+        0x00, 0x00,
+    ];
+    const PAYLOAD_ZST: [u8; 92] = [
+        0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x68, 0x7d, 0x02, 0x00, 0xa2, 0x43, 0x0d, 0x11, 0x90, 0x7d, 0x0c,
+        0x05, 0x1a, 0x4d, 0x61, 0x99, 0xc7, 0x0a, 0x16, 0xd6, 0xf3, 0x72, 0x7d, 0xc5, 0x19, 0x4b, 0x25,
+        0x74, 0x4c, 0x21, 0x20, 0x67, 0x27, 0xbc, 0xe9, 0x06, 0x2f, 0x16, 0xae, 0xf7, 0x6b, 0xe1, 0x6b,
+        0xb8, 0xbd, 0x52, 0xbf, 0xcf, 0x1e, 0x3e, 0xb6, 0x2a, 0x9d, 0xbe, 0xc5, 0x3f, 0x7d, 0x4b, 0xfa,
+        0x2c, 0x09, 0x20, 0x90, 0x53, 0xe3, 0x36, 0xf2, 0x64, 0x7c, 0x0a, 0xcf, 0xe3, 0x93, 0xf1, 0x14,
+        0xee, 0xbc, 0x3b, 0x8c, 0x38, 0x43, 0x2b, 0x03, 0xfc, 0xa8, 0xdb, 0xa5,
+    ];
+
+    fn payload() -> Vec<u8> {
+        (0..8).flat_map(|i| alloc::format!("Helix kernel image, section {}: .text .rodata .data .bss\n", i).into_bytes()).collect()
+    }
+
+    #[test]
+    fn test_gunzip() {
+        assert_eq!(decompress(&PAYLOAD_GZ).unwrap(), payload());
+
+        let mut corrupt = PAYLOAD_GZ;
+        corrupt[PAYLOAD_GZ.len() - 8] ^= 1;
+        assert!(matches!(gunzip(&corrupt), Err(CompressionError::ChecksumMismatch)));
+        assert!(matches!(gunzip(&PAYLOAD_GZ[..40]), Err(CompressionError::IncompleteData)));
+    }
+
+    #[test]
+    fn test_zstd() {
+        assert_eq!(decompress(&PAYLOAD_ZST).unwrap(), payload());
+
+        let mut corrupt = PAYLOAD_ZST;
+        corrupt[PAYLOAD_ZST.len() - 1] ^= 1;
+        assert!(matches!(zstd_decompress(&corrupt), Err(CompressionError::ChecksumMismatch)));
+
+        // Raw and RLE blocks, after a skippable frame
+        let mut frames = alloc::vec![0x50, 0x2a, 0x4d, 0x18, 0x02, 0x00, 0x00, 0x00, 0xaa, 0xbb];
+        frames.extend_from_slice(&[0x28, 0xb5, 0x2f, 0xfd, 0x20, 0x08]);
+        frames.extend_from_slice(&[0x10, 0x00, 0x00, b'b', b'o']);
+        frames.extend_from_slice(&[0x2a, 0x00, 0x00, b'o']);
+        frames.extend_from_slice(&[0x09, 0x00, 0x00, b't']);
+        assert_eq!(zstd_decompress(&frames).unwrap(), b"boooooot");
+        assert!(matches!(decompress(&[0x04, 0x22, 0x4d, 0x18]), Err(CompressionError::UnsupportedFormat)));
+    }
 }
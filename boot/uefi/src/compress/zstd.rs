@@ -0,0 +1,796 @@
+//! Zstandard Decompression
+//!
+//! Decoder for Zstandard frames (RFC 8878): raw, RLE and compressed blocks,
+//! Huffman coded literals and FSE coded sequences. Frames are decoded
+//! whole into memory, so the window size is not enforced. Dictionaries are
+//! not supported; content checksums are verified when present.
+
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::CompressionError;
+
+/// Frame magic
+const MAGIC: u32 = 0xFD2F_B528;
+
+/// Skippable frame magic; the low nibble is free
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+
+/// Largest block
+const MAX_BLOCK_SIZE: usize = 128 << 10;
+
+/// Largest Huffman code, in bits
+const MAX_HUFFMAN_BITS: u32 = 11;
+
+/// Literal length codes: baseline and extra bits
+const LL_BASE: [u32; 36] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+    16, 18, 20, 22, 24, 28, 32, 40, 48, 64, 128, 256, 512, 1024, 2048, 4096,
+    8192, 16384, 32768, 65536,
+];
+const LL_BITS: [u8; 36] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 1, 1, 1, 2, 2, 3, 3, 4, 6, 7, 8, 9, 10, 11, 12,
+    13, 14, 15, 16,
+];
+
+/// Match length codes: baseline and extra bits
+const ML_BASE: [u32; 53] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18,
+    19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34,
+    35, 37, 39, 41, 43, 47, 51, 59, 67, 83, 99, 131, 259, 515, 1027, 2051,
+    4099, 8195, 16387, 32771, 65539,
+];
+const ML_BITS: [u8; 53] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 1, 1, 1, 2, 2, 3, 3, 4, 4, 5, 7, 8, 9, 10, 11,
+    12, 13, 14, 15, 16,
+];
+
+/// Predefined distributions (accuracy logs 6, 6 and 5)
+const LL_DEFAULT: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1,
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+const ML_DEFAULT: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1,
+    -1, -1, -1, -1, -1,
+];
+const OF_DEFAULT: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+/// Sequence symbol kinds: predefined distribution, its accuracy log, the
+/// largest accuracy log and symbol a stream may use
+struct SymbolKind {
+    default: &'static [i16],
+    default_log: u32,
+    max_log: u32,
+    max_symbol: usize,
+}
+
+const LITERAL_LENGTHS: SymbolKind = SymbolKind { default: &LL_DEFAULT, default_log: 6, max_log: 9, max_symbol: 35 };
+const MATCH_LENGTHS: SymbolKind = SymbolKind { default: &ML_DEFAULT, default_log: 6, max_log: 9, max_symbol: 52 };
+const OFFSETS: SymbolKind = SymbolKind { default: &OF_DEFAULT, default_log: 5, max_log: 8, max_symbol: 31 };
+
+fn slice(data: &[u8], start: usize, len: usize) -> Result<&[u8], CompressionError> {
+    data.get(start..start.checked_add(len).ok_or(CompressionError::InvalidData)?).ok_or(CompressionError::IncompleteData)
+}
+
+/// Little-endian integer of `len` (at most 8) bytes at `start`
+fn le(data: &[u8], start: usize, len: usize) -> Result<u64, CompressionError> {
+    Ok(slice(data, start, len)?.iter().rev().fold(0, |value, &b| (value << 8) | u64::from(b)))
+}
+
+// =============================================================================
+// BITSTREAMS
+// =============================================================================
+
+/// Forward little-endian bitstream (FSE table descriptions)
+struct ForwardBits<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ForwardBits<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Next `count` bits; past the end reads as zeros
+    fn peek(&self, count: u32) -> u32 {
+        (0..count as usize).fold(0, |value, i| {
+            let bit = self.pos + i;
+            let set = self.data.get(bit / 8).is_some_and(|b| (b >> (bit % 8)) & 1 != 0);
+            value | (u32::from(set) << i)
+        })
+    }
+
+    fn skip(&mut self, count: u32) {
+        self.pos += count as usize;
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = self.peek(count);
+        self.skip(count);
+        value
+    }
+
+    /// Whole bytes used, if none were read past the end
+    fn bytes_used(&self) -> Result<usize, CompressionError> {
+        let used = self.pos.div_ceil(8);
+        if used > self.data.len() {
+            return Err(CompressionError::IncompleteData);
+        }
+        Ok(used)
+    }
+}
+
+/// Backward bitstream (Huffman and FSE payloads)
+///
+/// Written forwards and read from the end: the highest set bit of the last
+/// byte marks where the stream starts, and reads take the highest bits
+/// first. Reading past the beginning yields zeros and leaves the position
+/// negative, which decoders use to spot the end.
+struct BackwardBits<'a> {
+    data: &'a [u8],
+    pos: isize,
+}
+
+impl<'a> BackwardBits<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, CompressionError> {
+        let last = *data.last().ok_or(CompressionError::IncompleteData)?;
+        if last == 0 {
+            return Err(CompressionError::InvalidData);
+        }
+        Ok(Self { data, pos: (data.len() as isize - 1) * 8 + (7 - last.leading_zeros() as isize) })
+    }
+
+    /// Read `count` (at most 32) bits
+    fn read(&mut self, count: u32) -> u64 {
+        let end = self.pos;
+        self.pos -= count as isize;
+        if count == 0 || end <= 0 {
+            return 0;
+        }
+
+        let low = self.pos.max(0) as usize;
+        let high = end as usize;
+        let value = self.data[low / 8..=(high - 1) / 8].iter().rev().fold(0u64, |value, &b| (value << 8) | u64::from(b));
+        let value = (value >> (low % 8)) & ((1u64 << (high - low)) - 1);
+        // Bits before the start of the stream read as zeros
+        value << (low as isize - self.pos)
+    }
+
+    /// Next `count` bits, without consuming them
+    fn peek(&mut self, count: u32) -> u64 {
+        let pos = self.pos;
+        let value = self.read(count);
+        self.pos = pos;
+        value
+    }
+
+    fn skip(&mut self, count: u32) {
+        self.pos -= count as isize;
+    }
+
+    /// Read past the start of the stream?
+    fn overflowed(&self) -> bool {
+        self.pos < 0
+    }
+
+    /// Exactly consumed?
+    fn finished(&self) -> bool {
+        self.pos == 0
+    }
+}
+
+// =============================================================================
+// FSE
+// =============================================================================
+
+/// FSE decoding table entry
+#[derive(Debug, Clone, Copy, Default)]
+struct FseEntry {
+    symbol: u8,
+    bits: u8,
+    base: u16,
+}
+
+/// FSE decoding table
+#[derive(Debug, Clone)]
+struct FseTable {
+    log: u32,
+    entries: Vec<FseEntry>,
+}
+
+impl FseTable {
+    /// Build from normalized probabilities (-1 = "less than one")
+    fn from_distribution(log: u32, distribution: &[i16]) -> Result<Self, CompressionError> {
+        let size = 1usize << log;
+        let total: usize = distribution.iter().map(|&p| p.unsigned_abs() as usize).sum();
+        if total != size || distribution.len() > 256 {
+            return Err(CompressionError::InvalidData);
+        }
+
+        let mut entries = vec![FseEntry::default(); size];
+        let mut next = vec![0u32; distribution.len()];
+
+        // "Less than one" symbols take the top of the table
+        let mut high = size;
+        for (symbol, &p) in distribution.iter().enumerate() {
+            if p == -1 {
+                high -= 1;
+                entries[high].symbol = symbol as u8;
+                next[symbol] = 1;
+            } else {
+                next[symbol] = p.max(0) as u32;
+            }
+        }
+
+        // Spread the others over the rest
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut pos = 0;
+        for (symbol, &p) in distribution.iter().enumerate() {
+            for _ in 0..p.max(0) {
+                entries[pos].symbol = symbol as u8;
+                pos = (pos + step) & (size - 1);
+                while pos >= high {
+                    pos = (pos + step) & (size - 1);
+                }
+            }
+        }
+        if pos != 0 {
+            return Err(CompressionError::InvalidData);
+        }
+
+        for entry in &mut entries {
+            let state = next[entry.symbol as usize];
+            next[entry.symbol as usize] += 1;
+            let bits = log - (31 - state.leading_zeros());
+            entry.bits = bits as u8;
+            entry.base = ((state << bits) - size as u32) as u16;
+        }
+        Ok(Self { log, entries })
+    }
+
+    /// Table repeating `symbol`
+    fn rle(symbol: u8) -> Self {
+        Self { log: 0, entries: vec![FseEntry { symbol, bits: 0, base: 0 }] }
+    }
+
+    /// Read a table description; returns the table and the bytes used
+    fn read(data: &[u8], max_log: u32, max_symbol: usize) -> Result<(Self, usize), CompressionError> {
+        let mut bits = ForwardBits::new(data);
+        let log = bits.read(4) + 5;
+        if log > max_log {
+            return Err(CompressionError::InvalidData);
+        }
+
+        let mut distribution: Vec<i16> = Vec::new();
+        let mut remaining = (1i32 << log) + 1;
+        let mut threshold = 1i32 << log;
+        let mut width = log + 1;
+        let mut previous_zero = false;
+        while remaining > 1 {
+            // A zero probability is followed by a count of more zeros
+            if previous_zero {
+                loop {
+                    let repeat = bits.read(2);
+                    distribution.resize(distribution.len() + repeat as usize, 0);
+                    if repeat != 3 {
+                        break;
+                    }
+                }
+            }
+            if distribution.len() > max_symbol {
+                return Err(CompressionError::InvalidData);
+            }
+
+            // Small values take one bit less
+            let max = 2 * threshold - 1 - remaining;
+            let low = bits.peek(width - 1) as i32;
+            let value = if low < max {
+                bits.skip(width - 1);
+                low
+            } else {
+                let value = bits.read(width) as i32;
+                if value >= threshold { value - max } else { value }
+            };
+
+            let probability = value - 1;
+            remaining -= probability.abs();
+            if remaining < 1 {
+                return Err(CompressionError::InvalidData);
+            }
+            distribution.push(probability as i16);
+            previous_zero = probability == 0;
+            while remaining < threshold {
+                width -= 1;
+                threshold >>= 1;
+            }
+        }
+
+        let used = bits.bytes_used()?;
+        Ok((Self::from_distribution(log, &distribution)?, used))
+    }
+
+    fn symbol(&self, state: usize) -> u8 {
+        self.entries[state].symbol
+    }
+
+    fn initial_state(&self, bits: &mut BackwardBits<'_>) -> usize {
+        bits.read(self.log) as usize
+    }
+
+    fn next_state(&self, state: usize, bits: &mut BackwardBits<'_>) -> usize {
+        let entry = self.entries[state];
+        entry.base as usize + bits.read(u32::from(entry.bits)) as usize
+    }
+}
+
+// =============================================================================
+// HUFFMAN
+// =============================================================================
+
+/// Huffman decoding table entry
+#[derive(Debug, Clone, Copy, Default)]
+struct HuffmanEntry {
+    symbol: u8,
+    bits: u8,
+}
+
+/// Huffman decoding table, indexed by the next `max_bits` bits
+#[derive(Debug, Clone)]
+struct HuffmanTable {
+    max_bits: u32,
+    entries: Vec<HuffmanEntry>,
+}
+
+impl HuffmanTable {
+    /// Read a tree description; returns the table and the bytes used
+    fn read(data: &[u8]) -> Result<(Self, usize), CompressionError> {
+        let header = *data.first().ok_or(CompressionError::IncompleteData)? as usize;
+
+        if header >= 128 {
+            // Weights sent directly, 4 bits each
+            let count = header - 127;
+            let packed = slice(data, 1, count.div_ceil(2))?;
+            let weights: Vec<u8> = (0..count).map(|i| if i % 2 == 0 { packed[i / 2] >> 4 } else { packed[i / 2] & 0xF }).collect();
+            return Ok((Self::from_weights(&weights)?, 1 + packed.len()));
+        }
+
+        // Weights FSE coded with two interleaved states
+        let packed = slice(data, 1, header)?;
+        let (table, used) = FseTable::read(packed, 6, 255)?;
+        let mut bits = BackwardBits::new(&packed[used..])?;
+        let mut states = [table.initial_state(&mut bits), table.initial_state(&mut bits)];
+        let mut weights = Vec::new();
+        'decode: loop {
+            for i in 0..2 {
+                weights.push(table.symbol(states[i]));
+                states[i] = table.next_state(states[i], &mut bits);
+                if bits.overflowed() {
+                    weights.push(table.symbol(states[1 - i]));
+                    break 'decode;
+                }
+                if weights.len() > 255 {
+                    return Err(CompressionError::InvalidData);
+                }
+            }
+        }
+        Ok((Self::from_weights(&weights)?, 1 + header))
+    }
+
+    /// Build from symbol weights; the last symbol's weight is implied
+    fn from_weights(weights: &[u8]) -> Result<Self, CompressionError> {
+        if weights.len() > 255 || weights.iter().any(|&w| w > MAX_HUFFMAN_BITS as u8) {
+            return Err(CompressionError::InvalidData);
+        }
+        let sum: u32 = weights.iter().filter(|&&w| w > 0).map(|&w| 1 << (w - 1)).sum();
+        if sum == 0 {
+            return Err(CompressionError::InvalidData);
+        }
+
+        // The weights of all symbols sum to a power of two
+        let max_bits = 32 - sum.leading_zeros();
+        let rest = (1 << max_bits) - sum;
+        if max_bits > MAX_HUFFMAN_BITS || !rest.is_power_of_two() {
+            return Err(CompressionError::InvalidData);
+        }
+        let mut weights = weights.to_vec();
+        weights.push(rest.trailing_zeros() as u8 + 1);
+
+        // Symbols of each weight take consecutive runs, lightest first
+        let mut start = [0usize; MAX_HUFFMAN_BITS as usize + 2];
+        let mut pos = 0;
+        for (weight, start) in start.iter_mut().enumerate().skip(1).take(max_bits as usize) {
+            *start = pos;
+            pos += weights.iter().filter(|&&w| w as usize == weight).count() << (weight - 1);
+        }
+
+        let mut entries = vec![HuffmanEntry::default(); 1 << max_bits];
+        for (symbol, &weight) in weights.iter().enumerate().filter(|(_, &w)| w > 0) {
+            let len = 1 << (weight - 1);
+            let entry = HuffmanEntry { symbol: symbol as u8, bits: (max_bits + 1 - u32::from(weight)) as u8 };
+            entries[start[weight as usize]..start[weight as usize] + len].fill(entry);
+            start[weight as usize] += len;
+        }
+        Ok(Self { max_bits, entries })
+    }
+
+    /// Decode `count` literals from one stream
+    fn decode_stream(&self, data: &[u8], count: usize, output: &mut Vec<u8>) -> Result<(), CompressionError> {
+        let mut bits = BackwardBits::new(data)?;
+        for _ in 0..count {
+            let entry = self.entries[bits.peek(self.max_bits) as usize];
+            bits.skip(u32::from(entry.bits));
+            output.push(entry.symbol);
+        }
+        if !bits.finished() {
+            return Err(CompressionError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// DECODER
+// =============================================================================
+
+/// State carried from block to block within a frame
+struct Decoder {
+    /// Repeat offsets, most recent first
+    offsets: [usize; 3],
+    /// Tables for the repeat modes
+    literal_lengths: Option<FseTable>,
+    match_lengths: Option<FseTable>,
+    offset_codes: Option<FseTable>,
+    huffman: Option<HuffmanTable>,
+}
+
+impl Decoder {
+    fn new() -> Self {
+        Self { offsets: [1, 4, 8], literal_lengths: None, match_lengths: None, offset_codes: None, huffman: None }
+    }
+
+    /// Decode the frame at the start of `input`, appending to `output`;
+    /// returns the bytes used
+    fn frame(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<usize, CompressionError> {
+        let descriptor = le(input, 4, 1)? as u8;
+        let single_segment = descriptor & 0x20 != 0;
+        let checksum = descriptor & 0x04 != 0;
+        if descriptor & 0x08 != 0 {
+            return Err(CompressionError::InvalidData);
+        }
+
+        let mut pos = 5;
+        if !single_segment {
+            // Window descriptor
+            pos += 1;
+        }
+        let dictionary_len = [0, 1, 2, 4][(descriptor & 3) as usize];
+        if le(input, pos, dictionary_len)? != 0 {
+            return Err(CompressionError::UnsupportedFormat);
+        }
+        pos += dictionary_len;
+        let content_len = match descriptor >> 6 {
+            0 if single_segment => Some(le(input, pos, 1)?),
+            0 => None,
+            1 => Some(le(input, pos, 2)? + 256),
+            2 => Some(le(input, pos, 4)?),
+            _ => Some(le(input, pos, 8)?),
+        };
+        pos += match descriptor >> 6 {
+            0 => usize::from(single_segment),
+            n => 1 << n,
+        };
+
+        let start = output.len();
+        loop {
+            let header = le(input, pos, 3)? as usize;
+            pos += 3;
+            let size = header >> 3;
+            match (header >> 1) & 3 {
+                0 => {
+                    output.extend_from_slice(slice(input, pos, size)?);
+                    pos += size;
+                }
+                1 => {
+                    let byte = slice(input, pos, 1)?[0];
+                    output.resize(output.len() + size, byte);
+                    pos += 1;
+                }
+                2 if size <= MAX_BLOCK_SIZE => {
+                    self.block(slice(input, pos, size)?, output, start)?;
+                    pos += size;
+                }
+                _ => return Err(CompressionError::InvalidData),
+            }
+            if header & 1 != 0 {
+                break;
+            }
+        }
+
+        if checksum {
+            if le(input, pos, 4)? as u32 != xxh64(&output[start..], 0) as u32 {
+                return Err(CompressionError::ChecksumMismatch);
+            }
+            pos += 4;
+        }
+        if content_len.is_some_and(|len| len != (output.len() - start) as u64) {
+            return Err(CompressionError::InvalidData);
+        }
+        Ok(pos)
+    }
+
+    /// Decode a compressed block
+    fn block(&mut self, block: &[u8], output: &mut Vec<u8>, start: usize) -> Result<(), CompressionError> {
+        let (literals, used) = self.literals(block)?;
+        self.sequences(&block[used..], &literals, output, start)
+    }
+
+    /// Decode the literals section; returns the literals and the bytes used
+    fn literals(&mut self, block: &[u8]) -> Result<(Vec<u8>, usize), CompressionError> {
+        let header = *block.first().ok_or(CompressionError::IncompleteData)?;
+        let size_format = (header >> 2) & 3;
+
+        if header & 3 < 2 {
+            // Raw or RLE
+            let (len, header_len) = match size_format {
+                0 | 2 => (usize::from(header >> 3), 1),
+                1 => ((le(block, 0, 2)? >> 4) as usize, 2),
+                _ => ((le(block, 0, 3)? >> 4) as usize, 3),
+            };
+            return if header & 3 == 0 {
+                Ok((slice(block, header_len, len)?.to_vec(), header_len + len))
+            } else {
+                Ok((vec![slice(block, header_len, 1)?[0]; len], header_len + 1))
+            };
+        }
+
+        // Huffman coded, with a new tree or the previous one
+        let (header_len, width, streams) = match size_format {
+            0 => (3, 10, 1),
+            1 => (3, 10, 4),
+            2 => (4, 14, 4),
+            _ => (5, 18, 4),
+        };
+        let sizes = le(block, 0, header_len)? >> 4;
+        let len = (sizes & ((1 << width) - 1)) as usize;
+        let compressed_len = ((sizes >> width) & ((1 << width) - 1)) as usize;
+        let mut data = slice(block, header_len, compressed_len)?;
+
+        if header & 3 == 2 {
+            let (table, used) = HuffmanTable::read(data)?;
+            self.huffman = Some(table);
+            data = &data[used..];
+        }
+        let table = self.huffman.as_ref().ok_or(CompressionError::InvalidData)?;
+
+        let mut literals = Vec::with_capacity(len);
+        if streams == 1 {
+            table.decode_stream(data, len, &mut literals)?;
+        } else {
+            // A jump table gives the sizes of the first three streams
+            let mut lens = [le(data, 0, 2)? as usize, le(data, 2, 2)? as usize, le(data, 4, 2)? as usize, 0];
+            lens[3] = data.len().checked_sub(6 + lens[..3].iter().sum::<usize>()).ok_or(CompressionError::InvalidData)?;
+            let segment = len.div_ceil(4);
+            let last = len.checked_sub(3 * segment).ok_or(CompressionError::InvalidData)?;
+            let mut pos = 6;
+            for (i, &stream_len) in lens.iter().enumerate() {
+                let count = if i == 3 { last } else { segment };
+                table.decode_stream(slice(data, pos, stream_len)?, count, &mut literals)?;
+                pos += stream_len;
+            }
+        }
+        Ok((literals, header_len + compressed_len))
+    }
+
+    /// Update the table for one symbol kind; returns the bytes used
+    fn update_table(slot: &mut Option<FseTable>, kind: &SymbolKind, mode: u8, data: &[u8]) -> Result<usize, CompressionError> {
+        match mode {
+            0 => {
+                *slot = Some(FseTable::from_distribution(kind.default_log, kind.default)?);
+                Ok(0)
+            }
+            1 => {
+                *slot = Some(FseTable::rle(*data.first().ok_or(CompressionError::IncompleteData)?));
+                Ok(1)
+            }
+            2 => {
+                let (table, used) = FseTable::read(data, kind.max_log, kind.max_symbol)?;
+                *slot = Some(table);
+                Ok(used)
+            }
+            _ => slot.as_ref().map(|_| 0).ok_or(CompressionError::InvalidData),
+        }
+    }
+
+    /// Decode the sequences section and execute it against `literals`
+    fn sequences(&mut self, data: &[u8], literals: &[u8], output: &mut Vec<u8>, start: usize) -> Result<(), CompressionError> {
+        let first = *data.first().ok_or(CompressionError::IncompleteData)? as usize;
+        let (count, mut pos) = match first {
+            0..=127 => (first, 1),
+            128..=254 => (((first - 128) << 8) + le(data, 1, 1)? as usize, 2),
+            _ => (le(data, 1, 2)? as usize + 0x7F00, 3),
+        };
+        if count == 0 {
+            output.extend_from_slice(literals);
+            return Ok(());
+        }
+
+        let modes = le(data, pos, 1)? as u8;
+        if modes & 3 != 0 {
+            return Err(CompressionError::InvalidData);
+        }
+        pos += 1;
+        pos += Self::update_table(&mut self.literal_lengths, &LITERAL_LENGTHS, modes >> 6, &data[pos..])?;
+        pos += Self::update_table(&mut self.offset_codes, &OFFSETS, (modes >> 4) & 3, &data[pos..])?;
+        pos += Self::update_table(&mut self.match_lengths, &MATCH_LENGTHS, (modes >> 2) & 3, &data[pos..])?;
+        let (Some(ll_table), Some(of_table), Some(ml_table)) = (&self.literal_lengths, &self.offset_codes, &self.match_lengths) else {
+            return Err(CompressionError::InvalidData);
+        };
+
+        let mut bits = BackwardBits::new(&data[pos..])?;
+        let mut ll_state = ll_table.initial_state(&mut bits);
+        let mut of_state = of_table.initial_state(&mut bits);
+        let mut ml_state = ml_table.initial_state(&mut bits);
+        let mut literal = 0;
+
+        for i in 0..count {
+            let ll_code = ll_table.symbol(ll_state) as usize;
+            let of_code = u32::from(of_table.symbol(of_state));
+            let ml_code = ml_table.symbol(ml_state) as usize;
+            if ll_code >= LL_BASE.len() || ml_code >= ML_BASE.len() || of_code > 31 {
+                return Err(CompressionError::InvalidData);
+            }
+
+            let offset_value = (1usize << of_code) + bits.read(of_code) as usize;
+            let match_len = ML_BASE[ml_code] as usize + bits.read(u32::from(ML_BITS[ml_code])) as usize;
+            let literal_len = LL_BASE[ll_code] as usize + bits.read(u32::from(LL_BITS[ll_code])) as usize;
+            if i + 1 < count {
+                ll_state = ll_table.next_state(ll_state, &mut bits);
+                ml_state = ml_table.next_state(ml_state, &mut bits);
+                of_state = of_table.next_state(of_state, &mut bits);
+            }
+
+            // Values 1-3 pick a repeat offset, shifted by one without literals
+            let offset = if offset_value > 3 {
+                let offset = offset_value - 3;
+                self.offsets = [offset, self.offsets[0], self.offsets[1]];
+                offset
+            } else {
+                let [first, second, third] = self.offsets;
+                match offset_value + usize::from(literal_len == 0) {
+                    1 => first,
+                    2 => {
+                        self.offsets = [second, first, third];
+                        second
+                    }
+                    3 => {
+                        self.offsets = [third, first, second];
+                        third
+                    }
+                    _ => {
+                        let offset = first.wrapping_sub(1);
+                        self.offsets = [offset, first, second];
+                        offset
+                    }
+                }
+            };
+
+            output.extend_from_slice(slice(literals, literal, literal_len)?);
+            literal += literal_len;
+
+            if offset == 0 || offset > output.len() - start {
+                return Err(CompressionError::InvalidData);
+            }
+            // Byte by byte: the match may overlap what it produces
+            let from = output.len() - offset;
+            output.reserve(match_len);
+            for i in 0..match_len {
+                output.push(output[from + i]);
+            }
+        }
+
+        if !bits.finished() {
+            return Err(CompressionError::InvalidData);
+        }
+        output.extend_from_slice(&literals[literal..]);
+        Ok(())
+    }
+}
+
+/// Decompress one or more Zstandard frames; skippable frames are ignored
+pub fn zstd_decompress(data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    let mut output = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let magic = le(data, pos, 4)? as u32;
+        if magic & 0xFFFF_FFF0 == SKIPPABLE_MAGIC {
+            pos += 8 + le(data, pos + 4, 4)? as usize;
+            continue;
+        }
+        if magic != MAGIC {
+            return Err(CompressionError::InvalidData);
+        }
+        pos += Decoder::new().frame(&data[pos..], &mut output)?;
+    }
+    if pos > data.len() {
+        return Err(CompressionError::IncompleteData);
+    }
+    Ok(output)
+}
+
+// =============================================================================
+// XXH64
+// =============================================================================
+
+const XXH_PRIME1: u64 = 0x9E37_79B1_85EB_CA87;
+const XXH_PRIME2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const XXH_PRIME3: u64 = 0x1656_67B1_9E37_79F9;
+const XXH_PRIME4: u64 = 0x85EB_CA77_C2B2_AE63;
+const XXH_PRIME5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn xxh_round(acc: u64, lane: u64) -> u64 {
+    acc.wrapping_add(lane.wrapping_mul(XXH_PRIME2)).rotate_left(31).wrapping_mul(XXH_PRIME1)
+}
+
+fn xxh_merge(acc: u64, value: u64) -> u64 {
+    (acc ^ xxh_round(0, value)).wrapping_mul(XXH_PRIME1).wrapping_add(XXH_PRIME4)
+}
+
+/// XXH64 of `data`, the frame content checksum (low 32 bits)
+fn xxh64(data: &[u8], seed: u64) -> u64 {
+    let lane = |bytes: &[u8]| u64::from_le_bytes(bytes[..8].try_into().unwrap_or([0; 8]));
+    let mut stripes = data.chunks_exact(32);
+
+    let mut hash = if data.len() >= 32 {
+        let mut acc = [
+            seed.wrapping_add(XXH_PRIME1).wrapping_add(XXH_PRIME2),
+            seed.wrapping_add(XXH_PRIME2),
+            seed,
+            seed.wrapping_sub(XXH_PRIME1),
+        ];
+        for stripe in &mut stripes {
+            for (i, acc) in acc.iter_mut().enumerate() {
+                *acc = xxh_round(*acc, lane(&stripe[i * 8..]));
+            }
+        }
+        let hash = acc[0].rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18));
+        acc.iter().fold(hash, |hash, &acc| xxh_merge(hash, acc))
+    } else {
+        seed.wrapping_add(XXH_PRIME5)
+    };
+    hash = hash.wrapping_add(data.len() as u64);
+
+    let mut tail = stripes.remainder();
+    while tail.len() >= 8 {
+        hash = (hash ^ xxh_round(0, lane(tail))).rotate_left(27).wrapping_mul(XXH_PRIME1).wrapping_add(XXH_PRIME4);
+        tail = &tail[8..];
+    }
+    if tail.len() >= 4 {
+        let word = u64::from(u32::from_le_bytes([tail[0], tail[1], tail[2], tail[3]]));
+        hash = (hash ^ word.wrapping_mul(XXH_PRIME1)).rotate_left(23).wrapping_mul(XXH_PRIME2).wrapping_add(XXH_PRIME3);
+        tail = &tail[4..];
+    }
+    for &byte in tail {
+        hash = (hash ^ u64::from(byte).wrapping_mul(XXH_PRIME5)).rotate_left(11).wrapping_mul(XXH_PRIME1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(XXH_PRIME2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(XXH_PRIME3);
+    hash ^ (hash >> 32)
+}
@@ -17,12 +17,18 @@ pub struct BootConfig {
     pub timeout: u32,
     /// Default entry index
     pub default_entry: usize,
+    /// Default entry id (`default = helix`), preferred over the index
+    pub default_id: Option<String>,
     /// Enable verbose mode
     pub verbose: bool,
     /// Enable debug mode
     pub debug: bool,
     /// Log level
     pub log_level: LogLevel,
+    /// Also log to `\EFI\HELIX\boot.log` on the ESP (`log_file = on`)
+    pub log_file: bool,
+    /// Previous ESP logs kept (`log_keep`)
+    pub log_keep: usize,
     /// Boot entries
     pub entries: Vec<BootEntry>,
     /// Global kernel parameters
@@ -31,6 +37,8 @@ pub struct BootConfig {
     pub graphics: GraphicsConfig,
     /// Security settings
     pub security: SecurityConfig,
+    /// Drive the boot menu over serial (`serialctl = on`)
+    pub serial_control: bool,
 }
 
 impl BootConfig {
@@ -39,13 +47,17 @@ pub fn new() -> Self {
         Self {
             timeout: 5,
             default_entry: 0,
+            default_id: None,
             verbose: false,
             debug: false,
             log_level: LogLevel::Info,
+            log_file: false,
+            log_keep: 3,
             entries: Vec::new(),
             kernel_params: Vec::new(),
             graphics: GraphicsConfig::default(),
             security: SecurityConfig::default(),
+            serial_control: false,
         }
     }
 
@@ -95,10 +107,16 @@ fn apply_setting(
             None | Some("boot") => {
                 match key {
                     "timeout" => self.timeout = parse_u32(value)?,
-                    "default" => self.default_entry = parse_usize(value)?,
+                    "default" => match parse_usize(value) {
+                        Ok(index) => self.default_entry = index,
+                        Err(_) => self.default_id = Some(String::from(value)),
+                    },
                     "verbose" => self.verbose = parse_bool(value)?,
                     "debug" => self.debug = parse_bool(value)?,
                     "log_level" | "loglevel" => self.log_level = LogLevel::from_str(value)?,
+                    "log_file" => self.log_file = parse_bool(value)?,
+                    "log_keep" => self.log_keep = parse_usize(value)?,
+                    "serialctl" => self.serial_control = parse_bool(value)?,
                     _ => {}
                 }
             }
@@ -129,9 +147,17 @@ fn apply_setting(
                 if let Some(entry) = self.entries.last_mut() {
                     match key {
                         "title" | "name" => entry.title = String::from(value),
-                        "kernel" | "linux" => entry.kernel = String::from(value),
+                        "kernel" => entry.kernel = String::from(value),
+                        "linux" => {
+                            entry.kernel = String::from(value);
+                            entry.linux = true;
+                        }
                         "initrd" | "initramfs" => entry.initrd = Some(String::from(value)),
+                        "module" => entry.modules.push(String::from(value)),
+                        "devicetree-overlay" | "dtoverlay" => entry.dt_overlays.push(String::from(value)),
                         "cmdline" | "options" | "append" => entry.cmdline = String::from(value),
+                        "efi" | "chainload" => entry.efi = Some(String::from(value)),
+                        "uki" => entry.uki = Some(String::from(value)),
                         "icon" => entry.icon = Some(String::from(value)),
                         "default" => entry.is_default = parse_bool(value)?,
                         "hidden" => entry.hidden = parse_bool(value)?,
@@ -154,10 +180,27 @@ pub fn default_entry(&self) -> Option<&BootEntry> {
             }
         }
 
+        // Then the entry named by `default`
+        if let Some(id) = &self.default_id {
+            if let Some(entry) = self.entries.iter().find(|e| &e.id == id) {
+                return Some(entry);
+            }
+        }
+
         // Otherwise use index
         self.entries.get(self.default_entry)
     }
 
+    /// Position of the default entry among the visible entries (0 if hidden)
+    pub fn default_index(&self) -> usize {
+        let Some(default) = self.default_entry() else {
+            return 0;
+        };
+        self.visible_entries()
+            .position(|entry| core::ptr::eq(entry, default))
+            .unwrap_or(0)
+    }
+
     /// Get visible entries
     pub fn visible_entries(&self) -> impl Iterator<Item = &BootEntry> {
         self.entries.iter().filter(|e| !e.hidden)
@@ -181,8 +224,18 @@ pub struct BootEntry {
     pub kernel: String,
     /// Initrd path
     pub initrd: Option<String>,
-    /// Kernel command line
+    /// Extra modules (`module = path [args]`, repeatable)
+    pub modules: Vec<String>,
+    /// Device tree overlays applied in order (`dtoverlay = path`, repeatable)
+    pub dt_overlays: Vec<String>,
+    /// Kernel command line (load options for EFI applications)
     pub cmdline: String,
+    /// EFI application to chainload instead of a kernel
+    pub efi: Option<String>,
+    /// Kernel is a Linux bzImage (`linux = ...`)
+    pub linux: bool,
+    /// Unified kernel image holding kernel, initrd and command line
+    pub uki: Option<String>,
     /// Icon path
     pub icon: Option<String>,
     /// Is default entry
@@ -199,12 +252,32 @@ pub fn new(id: &str) -> Self {
             title: String::from(id),
             kernel: String::new(),
             initrd: None,
+            modules: Vec::new(),
+            dt_overlays: Vec::new(),
             cmdline: String::new(),
+            efi: None,
+            linux: false,
+            uki: None,
             icon: None,
             is_default: false,
             hidden: false,
         }
     }
+
+    /// Check if the entry chainloads an EFI application
+    pub fn is_chainload(&self) -> bool {
+        self.efi.is_some()
+    }
+
+    /// Check if the entry boots a Linux kernel
+    pub fn is_linux(&self) -> bool {
+        (self.linux || self.is_uki()) && !self.is_chainload()
+    }
+
+    /// Check if the entry boots a unified kernel image
+    pub fn is_uki(&self) -> bool {
+        self.uki.is_some()
+    }
 }
 
 /// Log level
@@ -430,6 +503,9 @@ mod tests {
 title = "Helix OS"
 kernel = \EFI\HELIX\KERNEL
 initrd = \EFI\HELIX\INITRD.IMG
+module = \EFI\HELIX\NET.KO debug=1
+module = \EFI\HELIX\BOARD.DTB
+dtoverlay = \EFI\HELIX\UART.DTBO
 cmdline = root=/dev/sda1 quiet
 
 [entry.helix-debug]
@@ -466,4 +542,66 @@ fn test_log_level() {
         assert_eq!(LogLevel::from_str("info"), Ok(LogLevel::Info));
         assert_eq!(LogLevel::from_str("error"), Ok(LogLevel::Error));
     }
+
+    #[test]
+    fn test_chainload_entry() {
+        let config = BootConfig::parse(
+            "[entry.windows]\ntitle = Windows\nchainload = /EFI/Microsoft/Boot/bootmgfw.efi",
+        ).unwrap();
+        assert!(config.entries[0].is_chainload());
+        assert_eq!(config.entries[0].efi.as_deref(), Some("/EFI/Microsoft/Boot/bootmgfw.efi"));
+    }
+
+    #[test]
+    fn test_linux_entry() {
+        let config = BootConfig::parse(
+            "[entry.arch]\nlinux = /vmlinuz-linux\ninitrd = /initramfs-linux.img\noptions = root=/dev/sda2 rw",
+        ).unwrap();
+        let entry = &config.entries[0];
+        assert!(entry.is_linux());
+        assert_eq!(entry.kernel, "/vmlinuz-linux");
+        assert_eq!(entry.initrd.as_deref(), Some("/initramfs-linux.img"));
+        assert_eq!(entry.cmdline, "root=/dev/sda2 rw");
+
+        let config = BootConfig::parse("[entry.uki]\nuki = /EFI/Linux/helix.efi").unwrap();
+        assert!(config.entries[0].is_uki());
+        assert!(config.entries[0].is_linux());
+    }
+
+    #[test]
+    fn test_default_entry() {
+        let config = BootConfig::parse(SAMPLE_CONFIG).unwrap();
+        assert_eq!(config.entries.len(), 2);
+        assert_eq!(config.timeout, 5);
+        assert_eq!(config.default_index(), 0);
+        assert_eq!(config.entries[0].modules, ["\\EFI\\HELIX\\NET.KO debug=1", "\\EFI\\HELIX\\BOARD.DTB"]);
+        assert_eq!(config.entries[0].dt_overlays, ["\\EFI\\HELIX\\UART.DTBO"]);
+
+        let config = BootConfig::parse(
+            "default = helix-debug\n[entry.rescue]\nhidden = yes\n[entry.helix]\n[entry.helix-debug]",
+        ).unwrap();
+        assert_eq!(config.default_entry().unwrap().id, "helix-debug");
+        assert_eq!(config.default_index(), 1);
+
+        let config = BootConfig::parse("default = 1\n[entry.a]\n[entry.b]\ndefault = no\n[entry.c]\ndefault = yes").unwrap();
+        assert_eq!(config.default_index(), 2);
+    }
+
+    #[test]
+    fn test_serial_control() {
+        assert!(!BootConfig::parse("timeout = 5").unwrap().serial_control);
+        assert!(BootConfig::parse("[boot]\nserialctl=on").unwrap().serial_control);
+    }
+
+    #[test]
+    fn test_log_file() {
+        let config = BootConfig::parse("timeout = 5").unwrap();
+        assert!(!config.log_file);
+        assert_eq!(config.log_keep, 3);
+
+        let config = BootConfig::parse("log_file = on\nlog_keep = 5\nlog_level = debug").unwrap();
+        assert!(config.log_file);
+        assert_eq!(config.log_keep, 5);
+        assert_eq!(config.log_level, LogLevel::Debug);
+    }
 }
@@ -1,8 +1,17 @@
 //! Console and Text Output
 //!
 //! Console abstraction for UEFI text and graphics output.
+//!
+//! Text is Unicode throughout: the firmware console receives UCS-2 with
+//! characters it cannot represent substituted, and the framebuffer console
+//! lays out wide and combining characters and draws them from a chain of
+//! PSF fonts.
 
 use super::config::LogLevel;
+use super::framebuffer::PsfFont;
+use super::locale;
+use super::resources::{self, ResourceId, FONT_FALLBACK_CHAIN};
+use super::terminal::{Utf8Decoder, REPLACEMENT_CHAR};
 
 // =============================================================================
 // CONSOLE
@@ -95,24 +104,11 @@ pub fn set_attribute(&self, fg: Color, bg: Color) {
 
     /// Output string
     pub fn output_string(&self, s: &str) {
-        // Convert to UCS-2
-        let mut buffer = [0u16; 256];
-        let mut i = 0;
-
-        for c in s.chars() {
-            if i >= buffer.len() - 1 {
-                break;
-            }
-            buffer[i] = c as u16;
-            i += 1;
-        }
-        buffer[i] = 0; // Null terminate
-
-        unsafe {
+        encode_ucs2(s, |chunk| unsafe {
             if let Some(output) = (*self.stdout).output_string {
-                output(self.stdout, buffer.as_ptr());
+                output(self.stdout, chunk.as_ptr());
             }
-        }
+        });
     }
 
     /// Print string
@@ -279,6 +275,18 @@ pub fn set_mode(&self, mode: usize) -> bool {
         false
     }
 
+    /// Current text mode
+    pub fn current_mode(&self) -> Option<usize> {
+        unsafe {
+            let mode = (*self.stdout).mode;
+            if mode.is_null() {
+                None
+            } else {
+                Some((*mode).mode as usize)
+            }
+        }
+    }
+
     /// Get cursor position
     pub fn cursor_position(&self) -> (usize, usize) {
         unsafe {
@@ -472,6 +480,48 @@ pub fn as_char(&self) -> Option<char> {
     }
 }
 
+/// Encode `s` as null-terminated UCS-2 chunks for `OutputString`
+///
+/// Combining marks are composed into the preceding character where a
+/// precomposed form exists. Characters outside the BMP are substituted, or
+/// replaced with U+FFFD.
+pub fn encode_ucs2(s: &str, mut emit: impl FnMut(&[u16])) {
+    let mut buffer = [0u16; 256];
+    let mut i = 0;
+
+    for c in s.chars() {
+        if i > 0 && locale::is_combining(c) {
+            let composed = char::from_u32(buffer[i - 1] as u32)
+                .and_then(|base| locale::compose(base, c));
+            if let Some(composed) = composed {
+                buffer[i - 1] = composed as u16;
+                continue;
+            }
+        }
+
+        let c = if c as u32 > 0xFFFF {
+            resources::substitute_char(c)
+                .filter(|&s| s as u32 <= 0xFFFF)
+                .unwrap_or(REPLACEMENT_CHAR)
+        } else {
+            c
+        };
+
+        if i == buffer.len() - 1 {
+            buffer[i] = 0;
+            emit(&buffer[..=i]);
+            i = 0;
+        }
+        buffer[i] = c as u16;
+        i += 1;
+    }
+
+    if i > 0 {
+        buffer[i] = 0; // Null terminate
+        emit(&buffer[..=i]);
+    }
+}
+
 // =============================================================================
 // FRAMEBUFFER CONSOLE
 // =============================================================================
@@ -502,6 +552,12 @@ pub struct FramebufferConsole {
     max_cols: u32,
     /// Maximum rows
     max_rows: u32,
+    /// Loaded fonts, in `FONT_FALLBACK_CHAIN` order
+    fonts: [Option<PsfFont<'static>>; FONT_FALLBACK_CHAIN.len()],
+    /// Decoder for byte output
+    decoder: Utf8Decoder,
+    /// Last drawn character and its cell, for combining marks
+    last: Option<(char, u32, u32)>,
 }
 
 impl FramebufferConsole {
@@ -528,7 +584,41 @@ pub fn new(
             bg_color: 0xFF_00_00_00, // Black
             max_cols: width / char_width,
             max_rows: height / char_height,
+            fonts: [None; FONT_FALLBACK_CHAIN.len()],
+            decoder: Utf8Decoder::new(),
+            last: None,
+        }
+    }
+
+    /// Load a font from the resource bundle
+    ///
+    /// The console font sets the cell size; fallback fonts must match its
+    /// height. Returns false if `id` is not in the fallback chain or the
+    /// font is unusable.
+    pub fn load_font(&mut self, id: ResourceId, data: &'static [u8]) -> bool {
+        let Some(slot) = FONT_FALLBACK_CHAIN.iter().position(|&f| f == id) else {
+            return false;
+        };
+        let Some(font) = PsfFont::parse(data) else {
+            return false;
+        };
+
+        if slot == 0 {
+            if font.width() > self.width || font.height() > self.height {
+                return false;
+            }
+            self.char_width = font.width();
+            self.char_height = font.height();
+            self.max_cols = self.width / self.char_width;
+            self.max_rows = self.height / self.char_height;
+            self.cursor_col = self.cursor_col.min(self.max_cols - 1);
+            self.cursor_row = self.cursor_row.min(self.max_rows - 1);
+        } else if font.height() != self.char_height {
+            return false;
         }
+
+        self.fonts[slot] = Some(font);
+        true
     }
 
     /// Clear screen
@@ -553,18 +643,44 @@ fn put_pixel(&self, x: u32, y: u32, color: u32) {
         }
     }
 
-    /// Draw character
-    fn draw_char(&self, c: char, x: u32, y: u32) {
+    /// Draw character across `cells` cells
+    fn draw_char(&self, c: char, x: u32, y: u32, cells: u32) {
+        let width = cells * self.char_width;
+        let resolved = resources::resolve_glyph(c, self.fonts.len(), |slot, ch| {
+            self.fonts[slot].is_some_and(|f| f.has_glyph(ch))
+        });
+
+        if let Some((slot, ch)) = resolved {
+            let font = self.fonts[slot].unwrap();
+            let bitmap = font.glyph(ch).unwrap_or(&[]);
+            let bytes_per_row = font.bytes_per_row();
+            // Center narrow glyphs in wide cells
+            let x_offset = width.saturating_sub(font.width()) / 2;
+
+            for row in 0..self.char_height {
+                for col in 0..width {
+                    let gx = col.wrapping_sub(x_offset);
+                    let set = gx < font.width()
+                        && bitmap
+                            .get((row * bytes_per_row + gx / 8) as usize)
+                            .is_some_and(|b| (b >> (7 - gx % 8)) & 1 != 0);
+                    let color = if set { self.fg_color } else { self.bg_color };
+                    self.put_pixel(x + col, y + row, color);
+                }
+            }
+            return;
+        }
+
         let glyph = get_glyph(c);
 
         for row in 0..self.char_height {
             let glyph_row = if row < 16 { glyph[row as usize] } else { 0 };
 
-            for col in 0..self.char_width {
+            for col in 0..width {
                 let pixel_x = x + col;
                 let pixel_y = y + row;
 
-                let color = if (glyph_row >> (7 - col)) & 1 != 0 {
+                let color = if (glyph_row >> (7 - col % 8)) & 1 != 0 {
                     self.fg_color
                 } else {
                     self.bg_color
@@ -576,20 +692,44 @@ fn draw_char(&self, c: char, x: u32, y: u32) {
     }
 
     /// Print character
+    ///
+    /// Wide characters take two cells. Combining marks are composed into
+    /// the previous character, which is redrawn; other zero-width
+    /// characters are dropped.
     pub fn putc(&mut self, c: char) {
         match c {
             '\n' => {
                 self.cursor_col = 0;
                 self.cursor_row += 1;
+                self.last = None;
             }
             '\r' => {
                 self.cursor_col = 0;
+                self.last = None;
             }
             '\t' => {
                 self.cursor_col = (self.cursor_col + 4) & !3;
+                self.last = None;
             }
             _ => {
-                if self.cursor_col >= self.max_cols {
+                let cells = locale::char_width(c) as u32;
+                if cells == 0 {
+                    if let Some((base, col, row)) = self.last {
+                        if let Some(composed) = locale::compose(base, c) {
+                            let cells = (locale::char_width(composed) as u32).max(1);
+                            self.draw_char(
+                                composed,
+                                col * self.char_width,
+                                row * self.char_height,
+                                cells,
+                            );
+                            self.last = Some((composed, col, row));
+                        }
+                    }
+                    return;
+                }
+
+                if self.cursor_col + cells > self.max_cols {
                     self.cursor_col = 0;
                     self.cursor_row += 1;
                 }
@@ -602,12 +742,20 @@ pub fn putc(&mut self, c: char) {
                 let x = self.cursor_col * self.char_width;
                 let y = self.cursor_row * self.char_height;
 
-                self.draw_char(c, x, y);
-                self.cursor_col += 1;
+                self.draw_char(c, x, y, cells.min(self.max_cols));
+                self.last = Some((c, self.cursor_col, self.cursor_row));
+                self.cursor_col += cells;
             }
         }
     }
 
+    /// Print UTF-8 bytes, which may split characters across calls
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        let mut decoder = core::mem::replace(&mut self.decoder, Utf8Decoder::new());
+        decoder.feed(bytes, |c| self.putc(c));
+        self.decoder = decoder;
+    }
+
     /// Print string
     pub fn print(&mut self, s: &str) {
         for c in s.chars() {
@@ -623,6 +771,7 @@ pub fn println(&mut self, s: &str) {
 
     /// Scroll screen up
     fn scroll(&mut self) {
+        self.last = None;
         let line_size = self.char_height * self.stride;
 
         unsafe {
@@ -651,6 +800,7 @@ pub fn set_colors(&mut self, fg: u32, bg: u32) {
 
     /// Set cursor position
     pub fn set_cursor(&mut self, col: u32, row: u32) {
+        self.last = None;
         self.cursor_col = col.min(self.max_cols - 1);
         self.cursor_row = row.min(self.max_rows - 1);
     }
@@ -694,6 +844,28 @@ pub fn set_cursor(&mut self, col: u32, row: u32) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_encode_ucs2() {
+        let mut out = [0u16; 8];
+        let mut chunks = 0;
+        encode_ucs2("e\u{301}\u{1F600}\u{1D400}界", |chunk| {
+            out[..chunk.len()].copy_from_slice(chunk);
+            chunks += 1;
+        });
+        assert_eq!(chunks, 1);
+        assert_eq!(out[..5], [0xE9, 0xFFFD, 0xFFFD, 0x754C, 0]);
+
+        let long = "x".repeat(300);
+        let mut lengths = [0usize; 2];
+        let mut n = 0;
+        encode_ucs2(&long, |chunk| {
+            assert_eq!(chunk.last(), Some(&0));
+            lengths[n] = chunk.len() - 1;
+            n += 1;
+        });
+        assert_eq!(lengths, [255, 45]);
+    }
+
     #[test]
     fn test_key_from_input() {
         let key = InputKey {
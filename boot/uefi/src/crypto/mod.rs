@@ -37,6 +37,9 @@
 
 use core::fmt;
 
+#[cfg(feature = "security")]
+pub mod selftest;
+
 // =============================================================================
 // HASH ALGORITHMS
 // =============================================================================
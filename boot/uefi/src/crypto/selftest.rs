@@ -0,0 +1,399 @@
+//! Power-On Self-Tests
+//!
+//! Known-answer tests of the primitives the loader makes security decisions
+//! with, run once before the first of those decisions. A miscompiled or
+//! faulty SHA-2 or RSA could otherwise let a tampered kernel verify, or be
+//! measured, as the genuine one. Every test compares against a published or
+//! precomputed answer; the signature tests also corrupt the signature and
+//! expect a rejection, so a verifier that accepts everything fails as well.
+//!
+//! Entropy sources get the SP 800-90B start-up health tests (repetition
+//! count and adaptive proportion) plus a repeated-block check on
+//! [`RNG_SAMPLE_SIZE`] bytes before their output is used.
+//!
+//! The loader has no block cipher, so there is no AES test. A failure is
+//! final: [`passed`] stays false and the loader stops on the recovery
+//! screen instead of booting on a primitive it cannot trust.
+
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::RandomSource;
+use crate::security::hash::{HashAlgorithm, HmacSha256, Sha256, Sha512};
+use crate::security::keys::RsaPublicKey;
+
+/// Bytes drawn from each entropy source for the health tests
+pub const RNG_SAMPLE_SIZE: usize = 1024;
+
+/// Repetition count cutoff: identical bytes in a row that fail the source
+///
+/// `1 + ceil(20 / H)` for a false-positive rate of 2^-20, with a
+/// conservative min-entropy `H` of 1 bit per byte.
+pub const REPETITION_CUTOFF: usize = 21;
+
+/// Adaptive proportion window, in bytes
+pub const PROPORTION_WINDOW: usize = 512;
+
+/// Adaptive proportion cutoff: occurrences of the window's first byte that
+/// fail the source (H = 1, false-positive rate 2^-20)
+pub const PROPORTION_CUTOFF: usize = 410;
+
+/// Repeated-block check granularity, in bytes
+pub const BLOCK_SIZE: usize = 8;
+
+/// Fills a buffer from an entropy source; false if the source could not
+pub type ReadEntropy<'a> = &'a mut dyn FnMut(&mut [u8]) -> bool;
+
+/// A self-test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTest {
+    /// SHA-256
+    Sha256,
+    /// SHA-384
+    Sha384,
+    /// SHA-512
+    Sha512,
+    /// HMAC-SHA256
+    HmacSha256,
+    /// RSA PKCS#1 v1.5 signature verification
+    RsaPkcs1v15,
+    /// RSA-PSS signature verification
+    RsaPss,
+    /// Health of an entropy source
+    Rng(RandomSource),
+}
+
+impl SelfTest {
+    /// Every known-answer test, in the order they run
+    pub const KNOWN_ANSWER: [Self; 6] = [
+        Self::Sha256,
+        Self::Sha384,
+        Self::Sha512,
+        Self::HmacSha256,
+        Self::RsaPkcs1v15,
+        Self::RsaPss,
+    ];
+
+    /// Name for messages
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "SHA-256",
+            Self::Sha384 => "SHA-384",
+            Self::Sha512 => "SHA-512",
+            Self::HmacSha256 => "HMAC-SHA256",
+            Self::RsaPkcs1v15 => "RSA PKCS#1 v1.5",
+            Self::RsaPss => "RSA-PSS",
+            Self::Rng(RandomSource::Hardware) => "hardware RNG",
+            Self::Rng(RandomSource::Tpm) => "TPM RNG",
+            Self::Rng(RandomSource::UefiProtocol) => "firmware RNG",
+            Self::Rng(RandomSource::Software) => "software RNG",
+        }
+    }
+}
+
+/// How a self-test failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// Output differs from the known answer
+    WrongAnswer,
+    /// A corrupted signature was accepted
+    AcceptedCorrupt,
+    /// The same byte [`REPETITION_CUTOFF`] times in a row
+    Repetition,
+    /// One byte value [`PROPORTION_CUTOFF`] times in a window
+    Proportion,
+    /// Two identical consecutive [`BLOCK_SIZE`] blocks
+    RepeatedBlock,
+    /// The source returned no data
+    NoOutput,
+}
+
+impl Failure {
+    /// Description for messages
+    pub const fn description(&self) -> &'static str {
+        match self {
+            Self::WrongAnswer => "wrong answer",
+            Self::AcceptedCorrupt => "accepted a corrupted signature",
+            Self::Repetition => "repetition count test failed",
+            Self::Proportion => "adaptive proportion test failed",
+            Self::RepeatedBlock => "repeated output block",
+            Self::NoOutput => "no output",
+        }
+    }
+}
+
+/// A failed self-test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfTestError {
+    /// Test that failed
+    pub test: SelfTest,
+    /// How it failed
+    pub failure: Failure,
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} self-test failed: {}",
+            self.test.name(),
+            self.failure.description()
+        )
+    }
+}
+
+/// Set once every self-test has passed
+static PASSED: AtomicBool = AtomicBool::new(false);
+
+/// Have the power-on self-tests passed?
+pub fn passed() -> bool {
+    PASSED.load(Ordering::Acquire)
+}
+
+/// Run the power-on self-tests
+///
+/// Every known-answer test, then the health tests on each entropy source
+/// in `sources`.
+pub fn power_on_self_test(
+    sources: &mut [(RandomSource, ReadEntropy<'_>)],
+) -> Result<(), SelfTestError> {
+    for test in SelfTest::KNOWN_ANSWER {
+        known_answer(test).map_err(|failure| SelfTestError { test, failure })?;
+    }
+
+    for (source, read) in sources.iter_mut() {
+        test_source(*source, read)?;
+    }
+
+    PASSED.store(true, Ordering::Release);
+    Ok(())
+}
+
+/// Run one known-answer test; [`SelfTest::Rng`] has none and passes
+pub fn known_answer(test: SelfTest) -> Result<(), Failure> {
+    let correct = match test {
+        SelfTest::Sha256 => Sha256::digest(b"abc") == SHA256_ABC,
+        SelfTest::Sha384 => Sha512::digest_384(b"abc") == SHA384_ABC,
+        SelfTest::Sha512 => Sha512::digest_512(b"abc") == SHA512_ABC,
+        SelfTest::HmacSha256 => HmacSha256::mac(b"Jefe", HMAC_DATA) == HMAC_SHA256_JEFE,
+        SelfTest::RsaPkcs1v15 | SelfTest::RsaPss => return rsa(test),
+        SelfTest::Rng(_) => true,
+    };
+
+    if correct {
+        Ok(())
+    } else {
+        Err(Failure::WrongAnswer)
+    }
+}
+
+/// Verify the good signature, then the same signature with one bit flipped
+fn rsa(test: SelfTest) -> Result<(), Failure> {
+    let key = RsaPublicKey::new(&RSA_MODULUS, &RSA_EXPONENT);
+    let verify = |signature: &[u8]| {
+        let result = if test == SelfTest::RsaPss {
+            key.verify_pss(HashAlgorithm::Sha256, &SHA256_ABC, signature, Some(32))
+        } else {
+            key.verify_pkcs1_v15(HashAlgorithm::Sha256, &SHA256_ABC, signature)
+        };
+        result == Ok(true)
+    };
+
+    let mut signature = if test == SelfTest::RsaPss {
+        RSA_PSS_SIGNATURE
+    } else {
+        RSA_PKCS1_SIGNATURE
+    };
+    if !verify(&signature) {
+        return Err(Failure::WrongAnswer);
+    }
+
+    signature[signature.len() - 1] ^= 1;
+    if verify(&signature) {
+        return Err(Failure::AcceptedCorrupt);
+    }
+
+    Ok(())
+}
+
+/// Draw [`RNG_SAMPLE_SIZE`] bytes from `source` and health-test them
+pub fn test_source(source: RandomSource, read: ReadEntropy<'_>) -> Result<(), SelfTestError> {
+    let mut sample = [0u8; RNG_SAMPLE_SIZE];
+    let result = if read(&mut sample) {
+        health_test(&sample)
+    } else {
+        Err(Failure::NoOutput)
+    };
+
+    result.map_err(|failure| SelfTestError {
+        test: SelfTest::Rng(source),
+        failure,
+    })
+}
+
+/// Health-test an entropy sample
+pub fn health_test(sample: &[u8]) -> Result<(), Failure> {
+    // Repetition count
+    let mut run = 1;
+    for pair in sample.windows(2) {
+        run = if pair[0] == pair[1] { run + 1 } else { 1 };
+        if run >= REPETITION_CUTOFF {
+            return Err(Failure::Repetition);
+        }
+    }
+
+    // Adaptive proportion
+    for window in sample.chunks_exact(PROPORTION_WINDOW) {
+        let count = window.iter().filter(|&&b| b == window[0]).count();
+        if count >= PROPORTION_CUTOFF {
+            return Err(Failure::Proportion);
+        }
+    }
+
+    // A stuck source that repeats a whole word passes both of the above
+    let mut blocks = sample.chunks_exact(BLOCK_SIZE);
+    if let Some(mut previous) = blocks.next() {
+        for block in blocks {
+            if block == previous {
+                return Err(Failure::RepeatedBlock);
+            }
+            previous = block;
+        }
+    }
+
+    Ok(())
+}
+
+// =============================================================================
+// Known answers
+// =============================================================================
+
+/// Decode a hex string at compile time
+const fn hex<const N: usize>(s: &str) -> [u8; N] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("invalid hex digit"),
+        }
+    }
+
+    let s = s.as_bytes();
+    assert!(s.len() == 2 * N);
+
+    let mut out = [0; N];
+    let mut i = 0;
+    while i < N {
+        out[i] = (nibble(s[2 * i]) << 4) | nibble(s[2 * i + 1]);
+        i += 1;
+    }
+    out
+}
+
+/// SHA-256("abc"), FIPS 180-4 example
+const SHA256_ABC: [u8; 32] =
+    hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+/// SHA-384("abc"), FIPS 180-4 example
+const SHA384_ABC: [u8; 48] = hex(concat!(
+    "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed",
+    "8086072ba1e7cc2358baeca134c825a7",
+));
+
+/// SHA-512("abc"), FIPS 180-4 example
+const SHA512_ABC: [u8; 64] = hex(concat!(
+    "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a",
+    "2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+));
+
+/// RFC 4231 test case 2 message (key "Jefe")
+const HMAC_DATA: &[u8] = b"what do ya want for nothing?";
+
+/// RFC 4231 test case 2 HMAC-SHA256
+const HMAC_SHA256_JEFE: [u8; 32] =
+    hex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+
+/// Test key: RSA-2048 modulus, e = 65537
+const RSA_MODULUS: [u8; 256] = hex(concat!(
+    "b859f187cfa042260490d7de7ec4af2d97a1f176177efab90910db389c8b0640",
+    "6570040eeeeb2affe48ad1510baa0f48262e48d905d90df91846730944cf3a9a",
+    "3f414312a4a1e85d1e5b934fe19c3aed1be8c7171dc5e56563653cbdd9ac6dcb",
+    "803e6df50bd2ed607836629408a2a9389db2de37b4f0ee1f65260d0030dca706",
+    "12f35f3be2b9df18e726916893e0216c992e1dd4d0e89cb2d7753bd95e93810c",
+    "e139f630acef11765bf1217ee0ac83e8ecfdcbab48ccfc7953b11a6ab4e668ed",
+    "51b71195b97bbafcd10f0ae6e4073b2149222b8babc72c06c5b978c25833bc09",
+    "f31517394975b15fe539873982bee5ed8fc53649d1d55f34143864a774be2a65",
+));
+
+const RSA_EXPONENT: [u8; 3] = [0x01, 0x00, 0x01];
+
+/// PKCS#1 v1.5 signature of "abc" with SHA-256
+const RSA_PKCS1_SIGNATURE: [u8; 256] = hex(concat!(
+    "0a2757405f028322cd7fe1880acacf45d3f0689b8fe31988d9e9ae139a0265fe",
+    "f681fb7fab12ed71ef9a3c1a730556a85a005c24f4fef28cb24e8306c07b55ab",
+    "c9a470a134da4ae8591ed6503564b9d7d0dc2697595706db2bf6a125f097dd28",
+    "ae367949710185b91f7dedb88f372cf0bbe42e2082f384d7a161ed6ea4d26090",
+    "36af1040300b2def910e270f85d97bd6d6e0580d264eb776e513c4d8cd3f3e51",
+    "e203c293f0f6dbfbe22ab1f1d2ce389bb095d55f4c7ac1329cecd6915da5eefc",
+    "dbaaa4b1ea38a8a46055aabf48f3a6cedfd82cec3192795a732a20669e73962a",
+    "291102caa20d383440a54a8345ecc2046824bd4d264056761d9f21169e446fab",
+));
+
+/// PSS signature of "abc" with SHA-256, MGF1-SHA256 and a 32-byte salt
+const RSA_PSS_SIGNATURE: [u8; 256] = hex(concat!(
+    "1d008f859cd6f6a475916182bea22477aa9e85dc7ca8d416bba0817073e0d701",
+    "7f055200f3a68b084b92b4e4bd56a6d73dff76fc084f63797e97b0ab338b94c6",
+    "e8ede4753fc63abdf6afbd7702337370e87d7921d536488995d30e02d0e5dd86",
+    "dce2649abe049c1bbdf7af3bf9b95395a4299823ae6a82e5ae0ea5c69986fe51",
+    "45a545e35a12a8957771c85356036627faf5dbc66996c7b7d6f1dec70805ef28",
+    "718169c3971871aa3becd1dd3e332afde536134ad9f28d48ac256d53cb17042f",
+    "12ff9fd7a34842b9faf0dc8ddfbf6ebfa96414f3aa751af922fda7b4d7affbe5",
+    "27c9a3be00d7a8aa19725fe2d51b7baebfed9ab9be4568b2f8941ec9b99e2781",
+));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_answers() {
+        for test in SelfTest::KNOWN_ANSWER {
+            assert_eq!(known_answer(test), Ok(()), "{}", test.name());
+        }
+    }
+
+    #[test]
+    fn test_health() {
+        // A counter never repeats a byte or a block
+        let mut sample = [0u8; RNG_SAMPLE_SIZE];
+        for (i, b) in sample.iter_mut().enumerate() {
+            *b = (i * 7 + i / 256) as u8;
+        }
+        assert_eq!(health_test(&sample), Ok(()));
+
+        assert_eq!(
+            health_test(&[0xff; RNG_SAMPLE_SIZE]),
+            Err(Failure::Repetition)
+        );
+
+        // Mostly zeros, in runs short enough to pass the repetition count
+        let mut biased = sample;
+        for (i, b) in biased.iter_mut().enumerate().take(PROPORTION_WINDOW) {
+            if i % 5 != 4 {
+                *b = 0;
+            }
+        }
+        assert_eq!(health_test(&biased), Err(Failure::Proportion));
+
+        // Stuck 64-bit word
+        let mut stuck = sample;
+        stuck[8..16].copy_from_slice(&sample[..8]);
+        assert_eq!(health_test(&stuck), Err(Failure::RepeatedBlock));
+
+        let error = test_source(RandomSource::Hardware, &mut |_| false).unwrap_err();
+        assert_eq!(
+            alloc::format!("{}", error),
+            "hardware RNG self-test failed: no output"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! ESP Boot Log
+//!
+//! Writes the boot log to `\EFI\HELIX\boot.log` on the ESP for machines
+//! without a serial port. Lines are buffered in memory and written out at
+//! phase boundaries ([`phase`]) and by [`finish`], which has to run before
+//! `ExitBootServices` takes the file system away.
+//!
+//! The logs of previous boots are kept as `boot.1.log` (newest) up to
+//! `boot.N.log`, with N from `log_keep` in boot.cfg.
+
+use super::LogLevel;
+use crate::config;
+use crate::error::Result;
+use crate::protocols::filesystem::{File, FileMode, FileSystem};
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+// =============================================================================
+// PATHS
+// =============================================================================
+
+/// Log directory on the ESP
+pub const LOG_DIR: &str = "\\EFI\\HELIX";
+
+/// Current boot log
+pub const LOG_PATH: &str = "\\EFI\\HELIX\\boot.log";
+
+/// Previous logs kept by default
+pub const DEFAULT_KEEP: usize = 3;
+
+/// Buffered bytes that force a write before the next phase boundary
+const FLUSH_THRESHOLD: usize = 4096;
+
+/// Path of the log `n` boots ago (0 is the current one)
+pub fn rotated_path(n: usize) -> String {
+    if n == 0 {
+        String::from(LOG_PATH)
+    } else {
+        format!("{}\\boot.{}.log", LOG_DIR, n)
+    }
+}
+
+/// Shift `boot.log` .. `boot.{keep - 1}.log` up by one, dropping the oldest
+///
+/// Best effort: a log that cannot be moved is lost, not fatal.
+fn rotate(fs: &mut FileSystem, keep: usize) {
+    let _ = fs.delete(&rotated_path(keep));
+    for n in (0..keep).rev() {
+        let from = rotated_path(n);
+        if let Ok(data) = fs.read(&from) {
+            let _ = fs.write(&rotated_path(n + 1), &data);
+        }
+        let _ = fs.delete(&from);
+    }
+}
+
+impl From<config::LogLevel> for LogLevel {
+    fn from(level: config::LogLevel) -> Self {
+        match level {
+            config::LogLevel::Trace => Self::Trace,
+            config::LogLevel::Debug => Self::Debug,
+            config::LogLevel::Info => Self::Info,
+            config::LogLevel::Warn => Self::Warn,
+            config::LogLevel::Error => Self::Error,
+            config::LogLevel::Silent => Self::Off,
+        }
+    }
+}
+
+/// Append one log line to `out`
+fn write_line(out: &mut String, level: LogLevel, args: fmt::Arguments<'_>) {
+    let _ = writeln!(out, "[{}] {}", level.name(), args);
+}
+
+// =============================================================================
+// BOOT LOG
+// =============================================================================
+
+/// Buffered log file on the ESP
+pub struct BootLog {
+    /// `boot.log`, closed before the file system
+    file: File,
+    /// Volume holding the log
+    _fs: FileSystem,
+    /// Minimum level written
+    level: LogLevel,
+    /// Lines not yet written
+    buffer: String,
+}
+
+impl BootLog {
+    /// Rotate the previous logs and start a new `boot.log` on `fs`
+    pub fn open(mut fs: FileSystem, level: LogLevel, keep: usize) -> Result<Self> {
+        if !fs.is_dir(LOG_DIR) {
+            fs.create_dir(LOG_DIR)?;
+        }
+        rotate(&mut fs, keep);
+        let file = fs.open(LOG_PATH, FileMode::CreateReadWrite)?;
+
+        Ok(Self {
+            file,
+            _fs: fs,
+            level,
+            buffer: String::with_capacity(FLUSH_THRESHOLD),
+        })
+    }
+
+    /// Minimum level written
+    pub fn level(&self) -> LogLevel {
+        self.level
+    }
+
+    /// Buffer a line if `level` is enabled
+    pub fn log(&mut self, level: LogLevel, args: fmt::Arguments<'_>) {
+        if level < self.level || level == LogLevel::Off {
+            return;
+        }
+        write_line(&mut self.buffer, level, args);
+        if self.buffer.len() >= FLUSH_THRESHOLD {
+            let _ = self.flush();
+        }
+    }
+
+    /// Mark the start of boot phase `name` and write out the buffer
+    pub fn phase(&mut self, name: &str) -> Result<()> {
+        let _ = writeln!(self.buffer, "== {} ==", name);
+        self.flush()
+    }
+
+    /// Write buffered lines to the file
+    pub fn flush(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.file.write_all(self.buffer.as_bytes())?;
+        self.buffer.clear();
+        self.file.flush()
+    }
+
+    /// Flush and close the log
+    pub fn finish(mut self) -> Result<()> {
+        self.flush()
+    }
+}
+
+// =============================================================================
+// GLOBAL LOG
+// =============================================================================
+
+/// Boot log used by the free functions below
+static mut BOOT_LOG: Option<BootLog> = None;
+
+/// Install the global boot log
+///
+/// # Safety
+/// Must be called from the boot thread, before boot services are exited.
+pub unsafe fn init(log: BootLog) {
+    *core::ptr::addr_of_mut!(BOOT_LOG) = Some(log);
+}
+
+/// Is a boot log installed?
+pub fn is_active() -> bool {
+    unsafe { (*core::ptr::addr_of!(BOOT_LOG)).is_some() }
+}
+
+/// Log to the global boot log, if any
+pub fn log(level: LogLevel, args: fmt::Arguments<'_>) {
+    unsafe {
+        if let Some(log) = &mut *core::ptr::addr_of_mut!(BOOT_LOG) {
+            log.log(level, args);
+        }
+    }
+}
+
+/// Mark a phase boundary in the global boot log, if any
+pub fn phase(name: &str) -> Result<()> {
+    unsafe {
+        match &mut *core::ptr::addr_of_mut!(BOOT_LOG) {
+            Some(log) => log.phase(name),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Flush and close the global boot log; call before `ExitBootServices`
+pub fn finish() -> Result<()> {
+    match unsafe { (*core::ptr::addr_of_mut!(BOOT_LOG)).take() } {
+        Some(log) => log.finish(),
+        None => Ok(()),
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotated_path() {
+        assert_eq!(rotated_path(0), LOG_PATH);
+        assert_eq!(rotated_path(2), "\\EFI\\HELIX\\boot.2.log");
+    }
+
+    #[test]
+    fn test_write_line() {
+        let mut out = String::new();
+        write_line(&mut out, LogLevel::Warn, format_args!("stage {}", 3));
+        assert_eq!(out, "[WARN] stage 3\n");
+    }
+
+    #[test]
+    fn test_config_level() {
+        assert_eq!(LogLevel::from(config::LogLevel::Silent), LogLevel::Off);
+        assert_eq!(LogLevel::from(config::LogLevel::Debug), LogLevel::Debug);
+    }
+}
@@ -1,8 +1,21 @@
 //! Debugging and Logging Infrastructure
 //!
 //! Comprehensive debug output, serial port, and logging for UEFI bootloader.
+//!
+//! The global logger ([`init`], [`log`] and the `log_*!` macros) writes
+//! each line to the serial console (16550 or PL011), the firmware console
+//! and an in-memory ring that is handed to the kernel in `BootInfo`, and
+//! forwards it to the ESP log when that is open.
 
 use core::fmt::{self, Write};
+use core::ptr::{addr_of, addr_of_mut};
+
+use crate::console::{encode_ucs2, SimpleTextOutput};
+use crate::handoff::bootinfo::LoaderLog;
+use crate::raw::types::PhysicalAddress;
+
+#[cfg(feature = "filesystem")]
+pub mod boot_log;
 
 // =============================================================================
 // LOG LEVELS
@@ -352,23 +365,210 @@ fn write_str(&mut self, s: &str) -> fmt::Result {
     }
 }
 
+// =============================================================================
+// PL011 UART
+// =============================================================================
+
+/// PL011 registers (byte offsets)
+pub mod pl011_reg {
+    pub const DATA: usize = 0x00;
+    pub const FLAG: usize = 0x18;
+    pub const CONTROL: usize = 0x30;
+}
+
+/// PL011 flag register bits
+pub mod pl011_flag {
+    pub const BUSY: u32 = 1 << 3;
+    pub const TX_FULL: u32 = 1 << 5;
+}
+
+/// PL011 control register bits
+pub mod pl011_ctrl {
+    pub const ENABLE: u32 = 1 << 0;
+    pub const TX_ENABLE: u32 = 1 << 8;
+}
+
+/// UART of the QEMU `virt` machine
+pub const QEMU_VIRT_PL011: u64 = 0x0900_0000;
+
+/// PL011 UART writer
+///
+/// Firmware leaves the UART clocked at the baud rate SPCR reports, so only
+/// the transmitter is switched on; the divisors are left alone.
+pub struct Pl011 {
+    base: u64,
+    initialized: bool,
+}
+
+impl Pl011 {
+    /// Create a PL011 at MMIO address `base`
+    pub const fn new(base: u64) -> Self {
+        Self {
+            base,
+            initialized: false,
+        }
+    }
+
+    /// Base address
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Enable the UART and its transmitter
+    pub fn init(&mut self) {
+        if self.base == 0 {
+            return;
+        }
+        unsafe {
+            let control = self.read_reg(pl011_reg::CONTROL);
+            self.write_reg(pl011_reg::CONTROL, control | pl011_ctrl::ENABLE | pl011_ctrl::TX_ENABLE);
+        }
+        self.initialized = true;
+    }
+
+    /// Read register
+    unsafe fn read_reg(&self, reg: usize) -> u32 {
+        core::ptr::read_volatile((self.base as usize + reg) as *const u32)
+    }
+
+    /// Write register
+    unsafe fn write_reg(&self, reg: usize, value: u32) {
+        core::ptr::write_volatile((self.base as usize + reg) as *mut u32, value);
+    }
+
+    /// Write byte
+    pub fn write_byte(&mut self, byte: u8) {
+        if !self.initialized {
+            return;
+        }
+
+        // Wait for room in the transmit FIFO
+        while unsafe { self.read_reg(pl011_reg::FLAG) } & pl011_flag::TX_FULL != 0 {
+            core::hint::spin_loop();
+        }
+
+        unsafe {
+            self.write_reg(pl011_reg::DATA, byte as u32);
+        }
+    }
+
+    /// Write string
+    pub fn write_str(&mut self, s: &str) {
+        for byte in s.bytes() {
+            if byte == b'\n' {
+                self.write_byte(b'\r');
+            }
+            self.write_byte(byte);
+        }
+    }
+
+    /// Wait until the last byte has left the UART
+    pub fn flush(&self) {
+        if !self.initialized {
+            return;
+        }
+        while unsafe { self.read_reg(pl011_reg::FLAG) } & pl011_flag::BUSY != 0 {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+impl Write for Pl011 {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_str(s);
+        Ok(())
+    }
+}
+
+// =============================================================================
+// EARLY SERIAL CONSOLE
+// =============================================================================
+
+/// Serial console of the logger
+pub enum EarlySerial {
+    /// No serial console
+    None,
+    /// 16550 compatible UART on an I/O port
+    Uart16550(SerialPort),
+    /// PL011 UART in MMIO space
+    Pl011(Pl011),
+}
+
+impl EarlySerial {
+    /// COM1 on x86_64; elsewhere nothing until SPCR names a UART, since
+    /// probing an unknown MMIO address can abort
+    pub const fn platform_default() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            Self::Uart16550(SerialPort::com1())
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            Self::None
+        }
+    }
+
+    /// Bring up the UART
+    pub fn init(&mut self) {
+        match self {
+            Self::None => {}
+            Self::Uart16550(port) => port.init(BaudRate::B115200),
+            Self::Pl011(uart) => uart.init(),
+        }
+    }
+
+    /// Write string, translating `\n` to `\r\n`
+    pub fn write_str(&mut self, s: &str) {
+        match self {
+            Self::None => {}
+            Self::Uart16550(port) => port.write_str(s),
+            Self::Pl011(uart) => uart.write_str(s),
+        }
+    }
+}
+
 // =============================================================================
 // DEBUG OUTPUT
 // =============================================================================
 
-/// Debug output target
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DebugTarget {
-    /// Serial port
-    Serial,
-    /// Console (SimpleTextOutput)
-    Console,
-    /// Both serial and console
-    Both,
+/// Log outputs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogOutputs(pub u8);
+
+impl LogOutputs {
+    /// Serial console
+    pub const SERIAL: Self = Self(1 << 0);
+    /// Firmware console (SimpleTextOutput), until boot services are exited
+    pub const CONSOLE: Self = Self(1 << 1);
+    /// In-memory ring handed to the kernel
+    pub const BUFFER: Self = Self(1 << 2);
     /// Debug port (port 0xE9)
-    DebugPort,
-    /// Memory buffer
-    Buffer,
+    pub const DEBUG_PORT: Self = Self(1 << 3);
+
+    /// Empty flags
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Check if flag is set
+    pub fn contains(&self, other: Self) -> bool {
+        (self.0 & other.0) == other.0
+    }
+
+    /// Set flag
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    /// Clear flag
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    /// Combine flags
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
 }
 
 /// Debug port writer (port 0xE9, QEMU debug)
@@ -415,109 +615,118 @@ fn write_str(&mut self, s: &str) -> fmt::Result {
 /// Maximum log buffer size
 pub const LOG_BUFFER_SIZE: usize = 64 * 1024; // 64KB
 
-/// Log entry
-#[derive(Clone)]
-pub struct LogEntry {
-    /// Log level
-    pub level: LogLevel,
-    /// Timestamp (if available)
-    pub timestamp: u64,
-    /// Message offset in buffer
-    pub message_offset: usize,
-    /// Message length
-    pub message_len: usize,
-    /// Module/source
-    pub module: Option<&'static str>,
-    /// Line number
-    pub line: Option<u32>,
-}
-
-/// Circular log buffer
+/// Longest log line, longer messages are cut
+pub const MAX_LINE: usize = 512;
+
+/// Circular buffer of log lines
+///
+/// Holds whole lines only: when a new line does not fit, the oldest lines
+/// are dropped until it does.
 pub struct LogBuffer {
     /// Text buffer
     buffer: [u8; LOG_BUFFER_SIZE],
-    /// Current write position
-    write_pos: usize,
-    /// Entries
-    entries: [Option<LogEntry>; 256],
-    /// Entry write index
-    entry_index: usize,
-    /// Total bytes written
-    total_bytes: usize,
+    /// Offset of the oldest byte
+    head: usize,
+    /// Bytes held
+    len: usize,
+    /// Lines written since the start
+    lines: u64,
+    /// Bytes of dropped lines
+    dropped: u64,
 }
 
 impl LogBuffer {
     /// Create new log buffer
     pub const fn new() -> Self {
-        const NONE_ENTRY: Option<LogEntry> = None;
-
         Self {
             buffer: [0; LOG_BUFFER_SIZE],
-            write_pos: 0,
-            entries: [NONE_ENTRY; 256],
-            entry_index: 0,
-            total_bytes: 0,
+            head: 0,
+            len: 0,
+            lines: 0,
+            dropped: 0,
         }
     }
 
-    /// Add log entry
-    pub fn push(&mut self, level: LogLevel, message: &str, module: Option<&'static str>, line: Option<u32>) {
-        let message_bytes = message.as_bytes();
-        let len = message_bytes.len().min(LOG_BUFFER_SIZE);
+    /// Append `line` and a newline
+    pub fn push_line(&mut self, line: &str) {
+        let bytes = line.as_bytes();
+        let bytes = &bytes[..bytes.len().min(LOG_BUFFER_SIZE - 1)];
 
-        // Write message to buffer
-        let start = self.write_pos;
-        for (i, &byte) in message_bytes.iter().take(len).enumerate() {
-            self.buffer[(start + i) % LOG_BUFFER_SIZE] = byte;
+        while LOG_BUFFER_SIZE - self.len < bytes.len() + 1 {
+            self.drop_line();
         }
-        self.write_pos = (start + len) % LOG_BUFFER_SIZE;
-        self.total_bytes += len;
 
-        // Add entry
-        self.entries[self.entry_index] = Some(LogEntry {
-            level,
-            timestamp: 0, // TODO: Add timestamp support
-            message_offset: start,
-            message_len: len,
-            module,
-            line,
-        });
-        self.entry_index = (self.entry_index + 1) % 256;
+        let mut tail = (self.head + self.len) % LOG_BUFFER_SIZE;
+        for &byte in bytes.iter().chain(b"\n") {
+            self.buffer[tail] = byte;
+            tail = (tail + 1) % LOG_BUFFER_SIZE;
+        }
+        self.len += bytes.len() + 1;
+        self.lines += 1;
     }
 
-    /// Get entry count
-    pub fn entry_count(&self) -> usize {
-        self.entries.iter().filter(|e| e.is_some()).count()
+    /// Drop the oldest line
+    fn drop_line(&mut self) {
+        while self.len > 0 {
+            let byte = self.buffer[self.head];
+            self.head = (self.head + 1) % LOG_BUFFER_SIZE;
+            self.len -= 1;
+            self.dropped += 1;
+            if byte == b'\n' {
+                break;
+            }
+        }
     }
 
-    /// Get message text for entry
-    pub fn message(&self, entry: &LogEntry) -> &str {
-        let start = entry.message_offset;
-        let end = start + entry.message_len;
-
-        if end <= LOG_BUFFER_SIZE && end > start {
-            core::str::from_utf8(&self.buffer[start..end]).unwrap_or("")
+    /// Buffered text, oldest first, as two slices
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        let end = self.head + self.len;
+        if end <= LOG_BUFFER_SIZE {
+            (&self.buffer[self.head..end], &[])
         } else {
-            ""
+            (&self.buffer[self.head..], &self.buffer[..end - LOG_BUFFER_SIZE])
         }
     }
 
+    /// Move the text to the start of the buffer and return it
+    pub fn make_contiguous(&mut self) -> &[u8] {
+        self.buffer.rotate_left(self.head);
+        self.head = 0;
+        &self.buffer[..self.len]
+    }
+
+    /// Bytes held
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Lines written since the start, including dropped ones
+    pub fn line_count(&self) -> u64 {
+        self.lines
+    }
+
+    /// Bytes of lines dropped to make room
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
     /// Clear buffer
     pub fn clear(&mut self) {
-        self.write_pos = 0;
-        self.entry_index = 0;
-        self.total_bytes = 0;
-        for entry in &mut self.entries {
-            *entry = None;
-        }
+        self.head = 0;
+        self.len = 0;
+        self.lines = 0;
+        self.dropped = 0;
     }
+}
 
-    /// Get recent entries
-    pub fn recent_entries(&self, count: usize) -> impl Iterator<Item = &LogEntry> {
-        self.entries.iter()
-            .filter_map(|e| e.as_ref())
-            .rev()
-            .take(count)
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -529,8 +738,10 @@ pub fn recent_entries(&self, count: usize) -> impl Iterator<Item = &LogEntry> {
 pub struct LoggerConfig {
     /// Minimum log level
     pub min_level: LogLevel,
-    /// Output target
-    pub target: DebugTarget,
+    /// Minimum level shown on the firmware console
+    pub console_level: LogLevel,
+    /// Output targets
+    pub outputs: LogOutputs,
     /// Enable colors
     pub colors: bool,
     /// Show timestamps
@@ -539,26 +750,60 @@ pub struct LoggerConfig {
     pub show_source: bool,
 }
 
-impl Default for LoggerConfig {
-    fn default() -> Self {
+impl LoggerConfig {
+    /// Default configuration: everything from `Info` up, on all outputs
+    /// but the debug port
+    pub const fn new() -> Self {
         Self {
             min_level: LogLevel::Info,
-            target: DebugTarget::Serial,
+            console_level: LogLevel::Info,
+            outputs: LogOutputs::SERIAL.union(LogOutputs::CONSOLE).union(LogOutputs::BUFFER),
             colors: true,
-            timestamps: false,
+            timestamps: true,
             show_source: true,
         }
     }
 }
 
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write the `[seconds] [LEVEL] module:line: ` prefix of a log line
+pub fn write_prefix(
+    out: &mut impl Write,
+    level: LogLevel,
+    micros: Option<u64>,
+    module: Option<&str>,
+    line: Option<u32>,
+) -> fmt::Result {
+    if let Some(us) = micros {
+        write!(out, "[{:5}.{:06}] ", us / 1_000_000, us % 1_000_000)?;
+    }
+    write!(out, "[{:<5}] ", level.name())?;
+    match (module, line) {
+        (Some(m), Some(l)) => write!(out, "{}:{}: ", m, l),
+        (Some(m), None) => write!(out, "{}: ", m),
+        _ => Ok(()),
+    }
+}
+
 /// Global logger
 pub struct Logger {
     /// Configuration
     config: LoggerConfig,
-    /// Serial port
-    serial: SerialPort,
+    /// Serial console
+    serial: EarlySerial,
+    /// Firmware console, null when detached
+    console: *mut SimpleTextOutput,
     /// Log buffer
     buffer: LogBuffer,
+    /// Timer tick at [`Logger::init`]
+    start_tick: u64,
+    /// Timer ticks per second, 0 if unknown
+    tick_hz: u64,
     /// Initialized flag
     initialized: bool,
 }
@@ -567,27 +812,29 @@ impl Logger {
     /// Create new logger
     pub const fn new() -> Self {
         Self {
-            config: LoggerConfig {
-                min_level: LogLevel::Info,
-                target: DebugTarget::Serial,
-                colors: true,
-                timestamps: false,
-                show_source: true,
-            },
-            serial: SerialPort::com1(),
+            config: LoggerConfig::new(),
+            serial: EarlySerial::platform_default(),
+            console: core::ptr::null_mut(),
             buffer: LogBuffer::new(),
+            start_tick: 0,
+            tick_hz: 0,
             initialized: false,
         }
     }
 
     /// Initialize logger
-    pub fn init(&mut self, config: LoggerConfig) {
+    ///
+    /// Timestamps count from here, in units of `tick_hz` ticks of
+    /// [`crate::time::read_tsc`] per second.
+    pub fn init(&mut self, config: LoggerConfig, tick_hz: u64) {
         self.config = config;
 
-        if matches!(self.config.target, DebugTarget::Serial | DebugTarget::Both) {
-            self.serial.init(BaudRate::B115200);
+        if self.config.outputs.contains(LogOutputs::SERIAL) {
+            self.serial.init();
         }
 
+        self.start_tick = crate::time::read_tsc();
+        self.tick_hz = tick_hz;
         self.initialized = true;
     }
 
@@ -596,6 +843,46 @@ pub fn set_level(&mut self, level: LogLevel) {
         self.config.min_level = level;
     }
 
+    /// Minimum log level
+    pub fn level(&self) -> LogLevel {
+        self.config.min_level
+    }
+
+    /// Set minimum level shown on the firmware console
+    pub fn set_console_level(&mut self, level: LogLevel) {
+        self.config.console_level = level;
+    }
+
+    /// Replace the serial console, e.g. by the UART SPCR names
+    pub fn set_serial(&mut self, mut serial: EarlySerial) {
+        if self.initialized && self.config.outputs.contains(LogOutputs::SERIAL) {
+            serial.init();
+        }
+        self.serial = serial;
+    }
+
+    /// Log to the firmware console `out`
+    ///
+    /// # Safety
+    /// `out` must stay valid until [`Logger::detach_console`].
+    pub unsafe fn attach_console(&mut self, out: *mut SimpleTextOutput) {
+        self.console = out;
+    }
+
+    /// Stop using the firmware console; call before `ExitBootServices`
+    pub fn detach_console(&mut self) {
+        self.console = core::ptr::null_mut();
+    }
+
+    /// Microseconds since [`Logger::init`], if timestamps are on
+    fn micros(&self) -> Option<u64> {
+        if !self.config.timestamps || self.tick_hz == 0 {
+            return None;
+        }
+        let ticks = crate::time::read_tsc().wrapping_sub(self.start_tick);
+        Some((ticks as u128 * 1_000_000 / self.tick_hz as u128) as u64)
+    }
+
     /// Log message
     pub fn log(
         &mut self,
@@ -604,104 +891,202 @@ pub fn log(
         line: Option<u32>,
         args: fmt::Arguments<'_>,
     ) {
-        if level < self.config.min_level || !self.initialized {
+        if level < self.config.min_level || level == LogLevel::Off || !self.initialized {
             return;
         }
 
-        // Format message
-        let mut message_buf = [0u8; 512];
-        let mut writer = ArrayWriter::new(&mut message_buf);
+        // Format line
+        let (module, line) = if self.config.show_source { (module, line) } else { (None, None) };
+        let mut line_buf = [0u8; MAX_LINE];
+        let mut writer = ArrayWriter::new(&mut line_buf);
+        let _ = write_prefix(&mut writer, level, self.micros(), module, line);
+        let prefix_len = writer.len();
         let _ = write!(writer, "{}", args);
-        let message = writer.as_str();
-
-        // Store in buffer
-        self.buffer.push(level, message, module, line);
+        let text = writer.as_str();
+        let message = &text[prefix_len..];
 
-        // Output based on target
-        match self.config.target {
-            DebugTarget::Serial | DebugTarget::Both => {
-                self.output_serial(level, module, line, message);
-            }
-            DebugTarget::DebugPort => {
-                self.output_debug_port(level, module, line, message);
-            }
-            DebugTarget::Console => {
-                // Console output would need EFI handle
-            }
-            DebugTarget::Buffer => {
-                // Already stored
-            }
+        let outputs = self.config.outputs;
+        if outputs.contains(LogOutputs::BUFFER) {
+            self.buffer.push_line(text);
+        }
+        if outputs.contains(LogOutputs::SERIAL) {
+            self.output_serial(level, text);
+        }
+        if outputs.contains(LogOutputs::DEBUG_PORT) {
+            DebugPort::write_str(text);
+            DebugPort::write_str("\n");
+        }
+        if outputs.contains(LogOutputs::CONSOLE) && level >= self.config.console_level {
+            self.output_console(level, message);
         }
     }
 
     /// Output to serial port
-    fn output_serial(
-        &mut self,
-        level: LogLevel,
-        module: Option<&'static str>,
-        line: Option<u32>,
-        message: &str,
-    ) {
-        // Color prefix
+    fn output_serial(&mut self, level: LogLevel, text: &str) {
         if self.config.colors {
             self.serial.write_str(level.color().ansi_fg());
-        }
-
-        // Level
-        self.serial.write_str("[");
-        self.serial.write_str(level.name());
-        self.serial.write_str("]");
-
-        // Source
-        if self.config.show_source {
-            if let Some(m) = module {
-                self.serial.write_str(" ");
-                self.serial.write_str(m);
-                if let Some(l) = line {
-                    self.serial.write_str(":");
-                    let mut num_buf = [0u8; 10];
-                    let num_str = format_u32(l, &mut num_buf);
-                    self.serial.write_str(num_str);
-                }
-            }
-        }
-
-        // Reset color and message
-        if self.config.colors {
+            self.serial.write_str(text);
             self.serial.write_str(Color::ansi_reset());
+        } else {
+            self.serial.write_str(text);
         }
-
-        self.serial.write_str(" ");
-        self.serial.write_str(message);
         self.serial.write_str("\n");
     }
 
-    /// Output to debug port
-    fn output_debug_port(
-        &mut self,
-        level: LogLevel,
-        module: Option<&'static str>,
-        _line: Option<u32>,
-        message: &str,
-    ) {
-        DebugPort::write_str("[");
-        DebugPort::write_str(level.short_name());
-        DebugPort::write_str("]");
-
-        if let Some(m) = module {
-            DebugPort::write_str(" ");
-            DebugPort::write_str(m);
+    /// Output to the firmware console: the message alone, with the level
+    /// in front unless it is `Info`
+    fn output_console(&mut self, level: LogLevel, message: &str) {
+        let out = self.console;
+        if out.is_null() {
+            return;
         }
-
-        DebugPort::write_str(": ");
-        DebugPort::write_str(message);
-        DebugPort::write_str("\n");
+        let emit = |s: &str| {
+            encode_ucs2(s, |chunk| unsafe {
+                if let Some(output) = (*out).output_string {
+                    output(out, chunk.as_ptr());
+                }
+            })
+        };
+        if level != LogLevel::Info {
+            emit(level.name());
+            emit(": ");
+        }
+        emit(message);
+        emit("\r\n");
     }
 
     /// Get log buffer
     pub fn buffer(&self) -> &LogBuffer {
         &self.buffer
     }
+
+    /// Stop buffering and describe the buffer for the kernel
+    ///
+    /// The text is moved to the start of the buffer so the kernel sees one
+    /// block, oldest line first; it lives in the loader image's data.
+    pub fn handoff(&mut self) -> LoaderLog {
+        self.config.outputs.remove(LogOutputs::BUFFER);
+        let dropped = self.buffer.dropped();
+        let lines = self.buffer.line_count();
+        let text = self.buffer.make_contiguous();
+        LoaderLog {
+            address: PhysicalAddress(text.as_ptr() as u64),
+            size: text.len() as u64,
+            lines,
+            dropped,
+        }
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// GLOBAL LOGGER
+// =============================================================================
+
+/// Logger used by the free functions and macros below
+static mut LOGGER: Logger = Logger::new();
+
+/// Initialize the global logger
+///
+/// # Safety
+/// Must be called from the boot thread before any other logging function.
+pub unsafe fn init(config: LoggerConfig, tick_hz: u64) {
+    (*addr_of_mut!(LOGGER)).init(config, tick_hz);
+}
+
+/// Set the minimum level of the global logger
+pub fn set_level(level: LogLevel) {
+    unsafe { (*addr_of_mut!(LOGGER)).set_level(level) }
+}
+
+/// Set the minimum level the global logger shows on the firmware console
+pub fn set_console_level(level: LogLevel) {
+    unsafe { (*addr_of_mut!(LOGGER)).set_console_level(level) }
+}
+
+/// Replace the serial console of the global logger
+pub fn set_serial(serial: EarlySerial) {
+    unsafe { (*addr_of_mut!(LOGGER)).set_serial(serial) }
+}
+
+/// Log to the firmware console `out`
+///
+/// # Safety
+/// `out` must stay valid until [`detach_console`].
+pub unsafe fn attach_console(out: *mut SimpleTextOutput) {
+    (*addr_of_mut!(LOGGER)).attach_console(out);
+}
+
+/// Stop logging to the firmware console; call before `ExitBootServices`
+pub fn detach_console() {
+    unsafe { (*addr_of_mut!(LOGGER)).detach_console() }
+}
+
+/// Is `level` logged at all?
+pub fn enabled(level: LogLevel) -> bool {
+    unsafe { level >= (*addr_of!(LOGGER)).level() && level != LogLevel::Off }
+}
+
+/// Log through the global logger, and to the ESP log if one is open
+pub fn log(level: LogLevel, module: Option<&'static str>, line: Option<u32>, args: fmt::Arguments<'_>) {
+    unsafe { (*addr_of_mut!(LOGGER)).log(level, module, line, args) };
+
+    #[cfg(feature = "filesystem")]
+    boot_log::log(level, args);
+}
+
+/// Stop buffering and describe the ring buffer for `BootInfo`
+pub fn handoff() -> LoaderLog {
+    unsafe { (*addr_of_mut!(LOGGER)).handoff() }
+}
+
+/// Log at `$level` through the global logger
+#[macro_export]
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)*) => {
+        $crate::debug::log($level, Some(module_path!()), Some(line!()), format_args!($($arg)*))
+    };
+}
+
+/// Log at trace level
+#[macro_export]
+macro_rules! log_trace {
+    ($($arg:tt)*) => ($crate::log_at!($crate::debug::LogLevel::Trace, $($arg)*));
+}
+
+/// Log at debug level
+#[macro_export]
+macro_rules! log_debug {
+    ($($arg:tt)*) => ($crate::log_at!($crate::debug::LogLevel::Debug, $($arg)*));
+}
+
+/// Log at info level
+#[macro_export]
+macro_rules! log_info {
+    ($($arg:tt)*) => ($crate::log_at!($crate::debug::LogLevel::Info, $($arg)*));
+}
+
+/// Log at warning level
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)*) => ($crate::log_at!($crate::debug::LogLevel::Warn, $($arg)*));
+}
+
+/// Log at error level
+#[macro_export]
+macro_rules! log_error {
+    ($($arg:tt)*) => ($crate::log_at!($crate::debug::LogLevel::Error, $($arg)*));
+}
+
+/// Log at fatal level
+#[macro_export]
+macro_rules! log_fatal {
+    ($($arg:tt)*) => ($crate::log_at!($crate::debug::LogLevel::Fatal, $($arg)*));
 }
 
 // =============================================================================
@@ -740,7 +1125,12 @@ impl<'a> Write for ArrayWriter<'a> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
         let bytes = s.as_bytes();
         let space = self.buf.len() - self.pos;
-        let to_write = bytes.len().min(space);
+        let mut to_write = bytes.len().min(space);
+
+        // Cut at a character boundary so the contents stay UTF-8
+        while !s.is_char_boundary(to_write) {
+            to_write -= 1;
+        }
 
         self.buf[self.pos..self.pos + to_write].copy_from_slice(&bytes[..to_write]);
         self.pos += to_write;
@@ -1027,9 +1417,89 @@ fn test_array_writer() {
         assert_eq!(writer.as_str(), "Hello, world!");
     }
 
+    #[test]
+    fn test_array_writer_truncates_at_char() {
+        let mut buf = [0u8; 4];
+        let mut writer = ArrayWriter::new(&mut buf);
+
+        write!(writer, "abcé").unwrap();
+        assert_eq!(writer.as_str(), "abc");
+    }
+
     #[test]
     fn test_baud_rate() {
         assert_eq!(BaudRate::B115200.divisor(), 1);
         assert_eq!(BaudRate::B9600.divisor(), 12);
     }
+
+    #[test]
+    fn test_log_buffer_drops_whole_lines() {
+        let mut buffer = LogBuffer::new();
+        let line = "x".repeat(LOG_BUFFER_SIZE / 4);
+
+        buffer.push_line("first");
+        for _ in 0..3 {
+            buffer.push_line(&line);
+        }
+        assert_eq!(buffer.dropped(), 0);
+
+        // No room for another long line: "first" and one long line go
+        buffer.push_line(&line);
+        assert_eq!(buffer.dropped(), 6 + line.len() as u64 + 1);
+        assert_eq!(buffer.line_count(), 5);
+
+        let (a, b) = buffer.as_slices();
+        assert_eq!(a.len() + b.len(), buffer.len());
+        assert_eq!(a[0], b'x');
+
+        let text = buffer.make_contiguous();
+        assert_eq!(text.len(), 3 * (line.len() + 1));
+        assert!(text.ends_with(b"x\n"));
+    }
+
+    #[test]
+    fn test_write_prefix() {
+        let mut buf = [0u8; 64];
+        let mut writer = ArrayWriter::new(&mut buf);
+        write_prefix(&mut writer, LogLevel::Warn, Some(1_234_567), Some("boot"), Some(42)).unwrap();
+        assert_eq!(writer.as_str(), "[    1.234567] [WARN ] boot:42: ");
+
+        let mut buf = [0u8; 64];
+        let mut writer = ArrayWriter::new(&mut buf);
+        write_prefix(&mut writer, LogLevel::Info, None, None, None).unwrap();
+        assert_eq!(writer.as_str(), "[INFO ] ");
+    }
+
+    #[test]
+    fn test_logger_levels() {
+        let mut logger = Logger::new();
+        let mut config = LoggerConfig::new();
+        config.outputs = LogOutputs::BUFFER;
+        config.timestamps = false;
+        config.show_source = false;
+        logger.init(config, 0);
+
+        logger.log(LogLevel::Debug, None, None, format_args!("hidden"));
+        logger.log(LogLevel::Warn, None, None, format_args!("disk {}", 2));
+        logger.set_level(LogLevel::Trace);
+        logger.log(LogLevel::Debug, None, None, format_args!("shown"));
+
+        let (text, _) = logger.buffer().as_slices();
+        assert_eq!(text, b"[WARN ] disk 2\n[DEBUG] shown\n");
+
+        let log = logger.handoff();
+        assert_eq!(log.size, 29);
+        assert_eq!(log.lines, 2);
+        logger.log(LogLevel::Info, None, None, format_args!("after handoff"));
+        assert_eq!(logger.buffer().line_count(), 2);
+    }
+
+    #[test]
+    fn test_log_outputs() {
+        let mut outputs = LogOutputs::SERIAL.union(LogOutputs::BUFFER);
+        assert!(outputs.contains(LogOutputs::BUFFER));
+        outputs.remove(LogOutputs::BUFFER);
+        assert!(!outputs.contains(LogOutputs::BUFFER));
+        assert!(!LoggerConfig::new().outputs.contains(LogOutputs::DEBUG_PORT));
+    }
 }
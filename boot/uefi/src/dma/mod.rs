@@ -0,0 +1,404 @@
+//! DMA Buffer Lifetime Guards for Helix UEFI Bootloader
+//!
+//! Wraps memory handed to bus-mastering devices and tracks who owns it.
+//! The NVMe driver maps its data buffer through a [`DmaBuffer`] for each
+//! command, so a controller writing past the buffer or after completion
+//! fails the transfer instead of corrupting the loader.
+//!
+//! # Ownership
+//!
+//! ```text
+//!            map()                 unmap()
+//!   ┌─────┐ ──────► ┌────────┐ ──────────► ┌─────┐ ── release() ──► poisoned
+//!   │ CPU │         │ Device │             │ CPU │
+//!   └─────┘ ◄────── └────────┘             └─────┘
+//! ```
+//!
+//! # Canaries
+//!
+//! - A guard area after the buffer is filled with [`DMA_GUARD_BYTE`] and
+//!   checked on unmap, catching device overruns.
+//! - On unmap the contents are fingerprinted; the fingerprint is checked
+//!   before the CPU touches the buffer again and on the next map, catching
+//!   devices that keep writing after unmap.
+//! - On release the buffer is filled with [`DMA_POISON_BYTE`], so stale
+//!   device addresses read recognizable garbage, and [`DmaQuarantine`]
+//!   detects late device writes into released buffers.
+
+use core::fmt;
+use core::ptr;
+use core::slice;
+
+// =============================================================================
+// DMA CONSTANTS
+// =============================================================================
+
+/// Size of the guard area after each buffer
+pub const DMA_GUARD_SIZE: usize = 64;
+
+/// Guard area fill byte
+pub const DMA_GUARD_BYTE: u8 = 0xDB;
+
+/// Fill byte for released buffers
+pub const DMA_POISON_BYTE: u8 = 0x6B;
+
+/// Number of released buffers watched for late device writes
+pub const DMA_QUARANTINE_SLOTS: usize = 16;
+
+// =============================================================================
+// DMA ERRORS
+// =============================================================================
+
+/// DMA guard violations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmaError {
+    /// Buffer is owned by the device
+    DeviceOwned,
+    /// Buffer is already owned by the CPU
+    NotMapped,
+    /// Device wrote past the end of the buffer
+    Overrun {
+        /// Device address of the buffer
+        device_addr: u64,
+    },
+    /// Device wrote to the buffer after it was unmapped
+    WriteAfterUnmap {
+        /// Device address of the buffer
+        device_addr: u64,
+    },
+    /// Device wrote to the buffer after it was released
+    WriteAfterFree {
+        /// Device address of the buffer
+        device_addr: u64,
+    },
+    /// Region too small to hold the guard area
+    TooSmall,
+}
+
+impl fmt::Display for DmaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DmaError::DeviceOwned => write!(f, "DMA buffer is owned by the device"),
+            DmaError::NotMapped => write!(f, "DMA buffer is not mapped"),
+            DmaError::Overrun { device_addr } => {
+                write!(f, "Device overran DMA buffer {:#x}", device_addr)
+            }
+            DmaError::WriteAfterUnmap { device_addr } => {
+                write!(f, "Device wrote DMA buffer {:#x} after unmap", device_addr)
+            }
+            DmaError::WriteAfterFree { device_addr } => {
+                write!(f, "Device wrote DMA buffer {:#x} after free", device_addr)
+            }
+            DmaError::TooSmall => write!(f, "DMA region too small"),
+        }
+    }
+}
+
+/// DMA result type
+pub type DmaResult<T> = core::result::Result<T, DmaError>;
+
+// =============================================================================
+// DMA REGION
+// =============================================================================
+
+/// Raw DMA-capable memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmaRegion {
+    /// CPU virtual address
+    pub virt: usize,
+    /// Device-visible address
+    pub device_addr: u64,
+    /// Size in bytes
+    pub size: usize,
+}
+
+impl DmaRegion {
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: Region validity is guaranteed by `DmaBuffer::new`
+        unsafe { slice::from_raw_parts(self.virt as *const u8, self.size) }
+    }
+
+    /// FNV-1a fingerprint of the contents (volatile reads)
+    fn fingerprint(&self) -> u64 {
+        let mut hash = 0xCBF2_9CE4_8422_2325u64;
+        for i in 0..self.size {
+            // SAFETY: In bounds of the region
+            let byte = unsafe { ptr::read_volatile((self.virt + i) as *const u8) };
+            hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3);
+        }
+        hash
+    }
+
+    fn fill(&self, byte: u8) {
+        // SAFETY: Region validity is guaranteed by `DmaBuffer::new`
+        unsafe { ptr::write_bytes(self.virt as *mut u8, byte, self.size) };
+    }
+}
+
+// =============================================================================
+// DMA BUFFER
+// =============================================================================
+
+/// Buffer ownership state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Owner {
+    /// CPU owns the buffer; fingerprint of the contents at unmap, if sealed
+    Cpu(Option<u64>),
+    /// Device may access the buffer
+    Device,
+}
+
+/// A guarded DMA buffer
+#[derive(Debug)]
+pub struct DmaBuffer {
+    /// Usable part of the region
+    data: DmaRegion,
+    /// Guard area after the data
+    guard: DmaRegion,
+    owner: Owner,
+}
+
+impl DmaBuffer {
+    /// Wrap a DMA region; the last [`DMA_GUARD_SIZE`] bytes become the guard
+    ///
+    /// # Safety
+    ///
+    /// `region` must be valid, exclusively owned memory that stays
+    /// allocated until [`DmaBuffer::release`].
+    pub unsafe fn new(region: DmaRegion) -> DmaResult<Self> {
+        let size = region
+            .size
+            .checked_sub(DMA_GUARD_SIZE)
+            .filter(|&size| size > 0)
+            .ok_or(DmaError::TooSmall)?;
+
+        let data = DmaRegion { size, ..region };
+        let guard = DmaRegion {
+            virt: region.virt + size,
+            device_addr: region.device_addr + size as u64,
+            size: DMA_GUARD_SIZE,
+        };
+        guard.fill(DMA_GUARD_BYTE);
+
+        Ok(Self {
+            data,
+            guard,
+            owner: Owner::Cpu(None),
+        })
+    }
+
+    /// Usable size in bytes
+    pub const fn len(&self) -> usize {
+        self.data.size
+    }
+
+    /// Check if the buffer has no usable bytes
+    pub const fn is_empty(&self) -> bool {
+        self.data.size == 0
+    }
+
+    /// Device-visible address
+    pub const fn device_addr(&self) -> u64 {
+        self.data.device_addr
+    }
+
+    /// Check if the device currently owns the buffer
+    pub fn is_mapped(&self) -> bool {
+        self.owner == Owner::Device
+    }
+
+    /// Verify no device wrote since unmap
+    fn verify_sealed(&self) -> DmaResult<()> {
+        match self.owner {
+            Owner::Device => Err(DmaError::DeviceOwned),
+            Owner::Cpu(Some(fingerprint)) if fingerprint != self.data.fingerprint() => {
+                Err(DmaError::WriteAfterUnmap {
+                    device_addr: self.data.device_addr,
+                })
+            }
+            Owner::Cpu(_) => Ok(()),
+        }
+    }
+
+    /// Hand the buffer to the device, returning its device address
+    pub fn map(&mut self) -> DmaResult<u64> {
+        self.verify_sealed()?;
+        self.owner = Owner::Device;
+        Ok(self.data.device_addr)
+    }
+
+    /// Take the buffer back from the device
+    pub fn unmap(&mut self) -> DmaResult<()> {
+        if self.owner != Owner::Device {
+            return Err(DmaError::NotMapped);
+        }
+
+        let overrun = self.guard.bytes().iter().any(|&b| b != DMA_GUARD_BYTE);
+        self.owner = Owner::Cpu(Some(self.data.fingerprint()));
+
+        if overrun {
+            self.guard.fill(DMA_GUARD_BYTE);
+            return Err(DmaError::Overrun {
+                device_addr: self.data.device_addr,
+            });
+        }
+        Ok(())
+    }
+
+    /// Read access for the CPU
+    pub fn as_slice(&self) -> DmaResult<&[u8]> {
+        self.verify_sealed()?;
+        Ok(self.data.bytes())
+    }
+
+    /// Write access for the CPU
+    ///
+    /// Drops the unmap fingerprint, since the CPU is about to change the
+    /// contents.
+    pub fn as_mut_slice(&mut self) -> DmaResult<&mut [u8]> {
+        self.verify_sealed()?;
+        self.owner = Owner::Cpu(None);
+        // SAFETY: CPU owns the buffer; exclusive through `&mut self`
+        Ok(unsafe { slice::from_raw_parts_mut(self.data.virt as *mut u8, self.data.size) })
+    }
+
+    /// Poison the buffer and return its memory (guard included)
+    ///
+    /// Fails without releasing if the device still owns the buffer. If the
+    /// device wrote after unmap the buffer is poisoned but its memory is
+    /// leaked, since the device may still be using it.
+    pub fn release(self) -> DmaResult<DmaRegion> {
+        let result = self.verify_sealed();
+        if result == Err(DmaError::DeviceOwned) {
+            return Err(DmaError::DeviceOwned);
+        }
+
+        let region = DmaRegion {
+            size: self.data.size + self.guard.size,
+            ..self.data
+        };
+        region.fill(DMA_POISON_BYTE);
+        result.map(|()| region)
+    }
+}
+
+// =============================================================================
+// DMA QUARANTINE
+// =============================================================================
+
+/// Released regions watched for late device writes
+#[derive(Debug)]
+pub struct DmaQuarantine {
+    slots: [Option<DmaRegion>; DMA_QUARANTINE_SLOTS],
+    next: usize,
+}
+
+impl DmaQuarantine {
+    /// Create an empty quarantine
+    pub const fn new() -> Self {
+        Self {
+            slots: [None; DMA_QUARANTINE_SLOTS],
+            next: 0,
+        }
+    }
+
+    /// Watch a released region; returns the evicted region to free
+    ///
+    /// The evicted region is checked one last time before it is returned.
+    pub fn push(&mut self, region: DmaRegion) -> Option<DmaResult<DmaRegion>> {
+        let evicted = self.slots[self.next].replace(region);
+        self.next = (self.next + 1) % DMA_QUARANTINE_SLOTS;
+        evicted.map(|old| Self::check_region(&old).map(|()| old))
+    }
+
+    /// Scan all watched regions for bytes other than the poison
+    pub fn check(&self) -> DmaResult<()> {
+        self.slots.iter().flatten().try_for_each(Self::check_region)
+    }
+
+    fn check_region(region: &DmaRegion) -> DmaResult<()> {
+        if region.bytes().iter().all(|&b| b == DMA_POISON_BYTE) {
+            Ok(())
+        } else {
+            Err(DmaError::WriteAfterFree {
+                device_addr: region.device_addr,
+            })
+        }
+    }
+}
+
+impl Default for DmaQuarantine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(mem: &mut [u8]) -> DmaRegion {
+        DmaRegion {
+            virt: mem.as_mut_ptr() as usize,
+            device_addr: 0x1000,
+            size: mem.len(),
+        }
+    }
+
+    #[test]
+    fn test_map_unmap() {
+        let mut mem = [0u8; 128];
+        let mut buf = unsafe { DmaBuffer::new(region(&mut mem)) }.unwrap();
+        assert_eq!(buf.len(), 64);
+
+        buf.as_mut_slice().unwrap()[0] = 0xAA;
+        assert_eq!(buf.map(), Ok(0x1000));
+        assert_eq!(buf.as_slice(), Err(DmaError::DeviceOwned));
+        assert_eq!(buf.unmap(), Ok(()));
+        assert_eq!(buf.as_slice().unwrap()[0], 0xAA);
+        assert_eq!(buf.unmap(), Err(DmaError::NotMapped));
+    }
+
+    #[test]
+    fn test_overrun_and_late_write() {
+        let mut mem = [0u8; 128];
+        let base = mem.as_mut_ptr();
+        let mut buf = unsafe { DmaBuffer::new(region(&mut mem)) }.unwrap();
+
+        buf.map().unwrap();
+        unsafe { base.add(70).write_volatile(1) };
+        assert_eq!(buf.unmap(), Err(DmaError::Overrun { device_addr: 0x1000 }));
+
+        unsafe { base.add(3).write_volatile(2) };
+        assert_eq!(
+            buf.as_slice(),
+            Err(DmaError::WriteAfterUnmap { device_addr: 0x1000 })
+        );
+    }
+
+    #[test]
+    fn test_release_and_quarantine() {
+        let mut mem = [0u8; 128];
+        let base = mem.as_mut_ptr();
+        let mut buf = unsafe { DmaBuffer::new(region(&mut mem)) }.unwrap();
+
+        buf.map().unwrap();
+        buf.unmap().unwrap();
+        let released = buf.release().unwrap();
+        assert_eq!(released.size, 128);
+
+        let mut quarantine = DmaQuarantine::new();
+        assert!(quarantine.push(released).is_none());
+        assert_eq!(quarantine.check(), Ok(()));
+
+        unsafe { base.add(5).write_volatile(0) };
+        assert_eq!(
+            quarantine.check(),
+            Err(DmaError::WriteAfterFree { device_addr: 0x1000 })
+        );
+    }
+}
@@ -1263,6 +1263,12 @@ pub struct DetectionHint {
         entry_type: BootEntryType::Windows,
         default_title: "Windows Boot Manager",
     },
+    DetectionHint {
+        path_pattern: "\\EFI\\systemd\\systemd-bootx64.efi",
+        os_type: DetectedOs::Linux,
+        entry_type: BootEntryType::Chainload,
+        default_title: "Linux Boot Manager",
+    },
     DetectionHint {
         path_pattern: "\\EFI\\BOOT\\BOOTX64.EFI",
         os_type: DetectedOs::OtherEfi,
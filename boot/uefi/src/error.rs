@@ -244,7 +244,7 @@ pub enum Error {
 impl Error {
     /// Create error from UEFI status code
     pub fn from_status(status: Status) -> Self {
-        match status.0 {
+        match status.code() {
             0 => panic!("Cannot create error from success status"),
             1 => Self::LoadError,
             2 => Self::InvalidParameter,
@@ -546,5 +546,7 @@ fn test_error_categories() {
     fn test_status_conversion() {
         let status: Status = Error::NotFound.into();
         assert!(!status.is_success());
+        assert_eq!(Error::from_status(Status::NOT_READY), Error::NotReady);
+        assert_eq!(Error::from_status(Status::UNSUPPORTED), Error::Unsupported);
     }
 }
@@ -246,6 +246,23 @@ fn default() -> Self {
 }
 
 impl ProgressEvent {
+    /// Create step progress for `operation` (truncated to 64 bytes)
+    pub fn steps(current: u64, total: u64, operation: &str) -> Self {
+        let mut event = Self {
+            progress_type: ProgressType::Steps,
+            current,
+            total,
+            ..Default::default()
+        };
+        let mut len = operation.len().min(event.operation.len());
+        while !operation.is_char_boundary(len) {
+            len -= 1;
+        }
+        event.operation[..len].copy_from_slice(&operation.as_bytes()[..len]);
+        event.operation_len = len;
+        event
+    }
+
     /// Calculate percentage (0-100)
     pub fn percentage(&self) -> u8 {
         if self.total == 0 {
@@ -718,6 +735,19 @@ pub fn boot_phase(id: EventId, from: BootPhase, to: BootPhase, timestamp_us: u64
         }
     }
 
+    /// Create progress event
+    pub fn progress(id: EventId, progress: ProgressEvent, timestamp_us: u64) -> Self {
+        Self {
+            id,
+            category: EventCategory::Progress,
+            priority: EventPriority::Normal,
+            timestamp_us,
+            data: EventData::Progress(progress),
+            handled: false,
+            propagate: true,
+        }
+    }
+
     /// Create error event
     pub fn error(id: EventId, severity: ErrorSeverity, code: u32, timestamp_us: u64) -> Self {
         let priority = match severity {
@@ -1069,6 +1099,21 @@ fn test_progress_event() {
         assert!(!progress.is_complete());
     }
 
+    #[test]
+    fn test_progress_steps() {
+        let event = Event::progress(0, ProgressEvent::steps(3, 4, "Kernel Loading"), 1000);
+        assert_eq!(event.category, EventCategory::Progress);
+        let EventData::Progress(progress) = event.data else {
+            panic!("not a progress event");
+        };
+        assert_eq!(progress.progress_type, ProgressType::Steps);
+        assert_eq!(progress.percentage(), 75);
+        assert_eq!(progress.operation_str(), "Kernel Loading");
+
+        let long = "é".repeat(40);
+        assert_eq!(ProgressEvent::steps(0, 1, &long).operation_len, 64);
+    }
+
     #[test]
     fn test_handler_registration() {
         let handler = HandlerRegistration::default();
@@ -0,0 +1,720 @@
+//! ext4 File System Support
+//!
+//! Read-only ext2/3/4 access, so kernels and initrds can be loaded from a
+//! Linux root partition instead of the ESP.
+//!
+//! # Features
+//!
+//! - Superblock and group descriptor parsing (32 and 64-bit)
+//! - Extent trees and legacy indirect block maps
+//! - Linear directory lookup (hashed directories included)
+//! - Symbolic links in paths
+//!
+//! Volumes using features that change the on-disk layout this reader
+//! relies on (inline data, encryption, `meta_bg`) are refused.
+
+use super::FileError;
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Superblock offset from the start of the volume
+pub const SUPERBLOCK_OFFSET: u64 = 1024;
+
+/// Superblock magic
+pub const EXT4_MAGIC: u16 = 0xEF53;
+
+/// Root directory inode
+pub const ROOT_INODE: u32 = 2;
+
+/// Extent header magic
+const EXTENT_MAGIC: u16 = 0xF30A;
+
+/// Symbolic links followed while resolving one path
+const MAX_SYMLINKS: usize = 8;
+
+/// Incompatible features this reader handles
+pub mod incompat {
+    /// Directory entries record the file type
+    pub const FILETYPE: u32 = 0x0002;
+    /// Journal needs recovery (reads may see stale data)
+    pub const RECOVER: u32 = 0x0004;
+    /// Files use extent trees
+    pub const EXTENTS: u32 = 0x0040;
+    /// 64-bit block numbers
+    pub const BIT64: u32 = 0x0080;
+    /// Multiple mount protection
+    pub const MMP: u32 = 0x0100;
+    /// Flexible block groups
+    pub const FLEX_BG: u32 = 0x0200;
+    /// Extended attributes in inodes
+    pub const EA_INODE: u32 = 0x0400;
+    /// Checksum seed in the superblock
+    pub const CSUM_SEED: u32 = 0x2000;
+    /// Directories larger than 2 GiB or three-level htrees
+    pub const LARGEDIR: u32 = 0x4000;
+
+    /// All of the above
+    pub const SUPPORTED: u32 = FILETYPE | RECOVER | EXTENTS | BIT64 | MMP | FLEX_BG | EA_INODE | CSUM_SEED | LARGEDIR;
+}
+
+/// Inode uses an extent tree
+const EXTENTS_FL: u32 = 0x0008_0000;
+
+/// Inode stores its data inline
+const INLINE_DATA_FL: u32 = 0x1000_0000;
+
+// =============================================================================
+// BLOCK READER
+// =============================================================================
+
+/// Byte-addressed read access to a volume
+pub trait BlockReader {
+    /// Fill `buffer` from byte `offset` of the volume
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), FileError>;
+}
+
+impl<T: BlockReader + ?Sized> BlockReader for &T {
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), FileError> {
+        (**self).read_at(offset, buffer)
+    }
+}
+
+impl BlockReader for [u8] {
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), FileError> {
+        let start = usize::try_from(offset).map_err(|_| FileError::DeviceError)?;
+        let data = self.get(start..start + buffer.len()).ok_or(FileError::DeviceError)?;
+        buffer.copy_from_slice(data);
+        Ok(())
+    }
+}
+
+impl BlockReader for crate::protocols::block::BlockDevice {
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), FileError> {
+        crate::protocols::block::BlockDevice::read_at(self, offset, buffer).map_err(|_| FileError::DeviceError)
+    }
+}
+
+impl<D: crate::block::BlockDevice> BlockReader for crate::block::BlockSlice<D> {
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), FileError> {
+        crate::block::BlockDevice::read_at(self, offset, buffer).map_err(|_| FileError::DeviceError)
+    }
+}
+
+// =============================================================================
+// SUPERBLOCK
+// =============================================================================
+
+/// The fields of the superblock this reader uses
+#[derive(Debug, Clone)]
+pub struct Superblock {
+    /// Total inodes
+    pub inodes_count: u32,
+    /// Total blocks
+    pub blocks_count: u64,
+    /// Block holding the superblock (1 for 1 KiB blocks, else 0)
+    pub first_data_block: u32,
+    /// Block size in bytes
+    pub block_size: u32,
+    /// Inodes per block group
+    pub inodes_per_group: u32,
+    /// On-disk inode size
+    pub inode_size: u16,
+    /// Group descriptor size
+    pub desc_size: u16,
+    /// Incompatible feature flags
+    pub feature_incompat: u32,
+    /// Volume UUID
+    pub uuid: [u8; 16],
+    /// Volume label, NUL padded
+    pub volume_name: [u8; 16],
+}
+
+impl Superblock {
+    /// Parse the 1024-byte superblock
+    pub fn parse(data: &[u8]) -> Result<Self, FileError> {
+        if data.len() < 1024 || le16(data, 56) != EXT4_MAGIC {
+            return Err(FileError::InvalidFormat);
+        }
+
+        let log_block_size = le32(data, 24);
+        if log_block_size > 6 {
+            return Err(FileError::InvalidFormat);
+        }
+        let rev_level = le32(data, 76);
+        let feature_incompat = le32(data, 96);
+        let bit64 = feature_incompat & incompat::BIT64 != 0;
+
+        let mut uuid = [0; 16];
+        uuid.copy_from_slice(&data[104..120]);
+        let mut volume_name = [0; 16];
+        volume_name.copy_from_slice(&data[120..136]);
+
+        let sb = Self {
+            inodes_count: le32(data, 0),
+            blocks_count: u64::from(le32(data, 4)) | if bit64 { u64::from(le32(data, 0x150)) << 32 } else { 0 },
+            first_data_block: le32(data, 20),
+            block_size: 1024 << log_block_size,
+            inodes_per_group: le32(data, 40),
+            inode_size: if rev_level == 0 { 128 } else { le16(data, 88) },
+            desc_size: if bit64 { le16(data, 0xFE).max(32) } else { 32 },
+            feature_incompat,
+            uuid,
+            volume_name,
+        };
+
+        if sb.inodes_per_group == 0 || sb.inode_size < 128 || sb.desc_size < 32 {
+            return Err(FileError::InvalidFormat);
+        }
+        if sb.feature_incompat & !incompat::SUPPORTED != 0 {
+            return Err(FileError::NotSupported);
+        }
+        Ok(sb)
+    }
+
+    /// Volume label
+    pub fn volume_label(&self) -> &str {
+        let len = self.volume_name.iter().position(|&b| b == 0).unwrap_or(16);
+        core::str::from_utf8(&self.volume_name[..len]).unwrap_or("")
+    }
+}
+
+// =============================================================================
+// INODE
+// =============================================================================
+
+/// File type, from the inode mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    /// Regular file
+    Regular,
+    /// Directory
+    Directory,
+    /// Symbolic link
+    Symlink,
+    /// Device, FIFO or socket
+    Other,
+}
+
+/// An inode
+#[derive(Debug, Clone)]
+pub struct Inode {
+    /// Inode number
+    pub number: u32,
+    /// File type
+    pub file_type: FileType,
+    /// Size in bytes
+    pub size: u64,
+    /// Inode flags
+    pub flags: u32,
+    /// Block map or extent tree root
+    pub block: [u8; 60],
+}
+
+impl Inode {
+    /// Parse inode `number` from its on-disk bytes
+    pub fn parse(number: u32, data: &[u8]) -> Result<Self, FileError> {
+        if data.len() < 128 {
+            return Err(FileError::InvalidFormat);
+        }
+        let mode = le16(data, 0);
+        let file_type = match mode & 0xF000 {
+            0x8000 => FileType::Regular,
+            0x4000 => FileType::Directory,
+            0xA000 => FileType::Symlink,
+            _ => FileType::Other,
+        };
+        let mut block = [0; 60];
+        block.copy_from_slice(&data[0x28..0x64]);
+        Ok(Self {
+            number,
+            file_type,
+            size: u64::from(le32(data, 4)) | (u64::from(le32(data, 0x6C)) << 32),
+            flags: le32(data, 0x20),
+            block,
+        })
+    }
+
+    /// Is this a directory?
+    pub fn is_directory(&self) -> bool {
+        self.file_type == FileType::Directory
+    }
+}
+
+/// Run of contiguous file blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    /// First file block
+    pub logical: u32,
+    /// First volume block
+    pub physical: u64,
+    /// Number of blocks
+    pub len: u32,
+    /// Allocated but unwritten (reads as zeros)
+    pub uninit: bool,
+}
+
+/// Directory entry
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    /// Inode number
+    pub inode: u32,
+    /// Name
+    pub name: String,
+}
+
+// =============================================================================
+// FILE SYSTEM
+// =============================================================================
+
+/// A mounted ext4 volume
+pub struct Ext4Filesystem<R: BlockReader> {
+    reader: R,
+    superblock: Superblock,
+}
+
+impl<R: BlockReader> Ext4Filesystem<R> {
+    /// Mount the volume `reader` reads
+    pub fn mount(reader: R) -> Result<Self, FileError> {
+        let mut data = [0u8; 1024];
+        reader.read_at(SUPERBLOCK_OFFSET, &mut data)?;
+        let superblock = Superblock::parse(&data)?;
+        Ok(Self { reader, superblock })
+    }
+
+    /// The superblock
+    pub fn superblock(&self) -> &Superblock {
+        &self.superblock
+    }
+
+    /// Volume label
+    pub fn volume_label(&self) -> &str {
+        self.superblock.volume_label()
+    }
+
+    /// Read whole block `block` into `buffer`
+    fn read_block(&self, block: u64, buffer: &mut [u8]) -> Result<(), FileError> {
+        if block >= self.superblock.blocks_count {
+            return Err(FileError::InvalidFormat);
+        }
+        self.reader.read_at(block * u64::from(self.superblock.block_size), buffer)
+    }
+
+    /// Read inode `number`
+    pub fn inode(&self, number: u32) -> Result<Inode, FileError> {
+        let sb = &self.superblock;
+        if number == 0 || number > sb.inodes_count {
+            return Err(FileError::NotFound);
+        }
+        let group = u64::from((number - 1) / sb.inodes_per_group);
+        let index = u64::from((number - 1) % sb.inodes_per_group);
+
+        // Group descriptors follow the superblock's block
+        let mut desc = [0u8; 64];
+        let desc = &mut desc[..usize::from(sb.desc_size).min(64)];
+        let table = u64::from(sb.first_data_block + 1) * u64::from(sb.block_size);
+        self.reader.read_at(table + group * u64::from(sb.desc_size), desc)?;
+        let mut inode_table = u64::from(le32(desc, 8));
+        if desc.len() >= 64 {
+            inode_table |= u64::from(le32(desc, 0x28)) << 32;
+        }
+
+        let mut data = [0u8; 256];
+        let data = &mut data[..usize::from(sb.inode_size).min(256)];
+        let offset = inode_table * u64::from(sb.block_size) + index * u64::from(sb.inode_size);
+        self.reader.read_at(offset, data)?;
+        Inode::parse(number, data)
+    }
+
+    /// Block runs of `inode`, in file order
+    pub fn extents(&self, inode: &Inode) -> Result<Vec<Extent>, FileError> {
+        if inode.flags & INLINE_DATA_FL != 0 {
+            return Err(FileError::NotSupported);
+        }
+        let mut extents = Vec::new();
+        if inode.flags & EXTENTS_FL != 0 {
+            self.walk_extent_node(&inode.block, 5, &mut extents)?;
+        } else {
+            self.walk_block_map(inode, &mut extents)?;
+        }
+        Ok(extents)
+    }
+
+    /// Collect the leaves of the extent tree node `node`
+    fn walk_extent_node(&self, node: &[u8], depth_limit: u16, extents: &mut Vec<Extent>) -> Result<(), FileError> {
+        if node.len() < 12 || le16(node, 0) != EXTENT_MAGIC {
+            return Err(FileError::InvalidFormat);
+        }
+        let entries = usize::from(le16(node, 2));
+        let depth = le16(node, 6);
+        if depth > depth_limit || 12 + entries * 12 > node.len() {
+            return Err(FileError::InvalidFormat);
+        }
+
+        let mut child = alloc::vec![0u8; self.superblock.block_size as usize];
+        for entry in node[12..12 + entries * 12].chunks_exact(12) {
+            if depth == 0 {
+                let len = le16(entry, 4);
+                extents.push(Extent {
+                    logical: le32(entry, 0),
+                    physical: (u64::from(le16(entry, 6)) << 32) | u64::from(le32(entry, 8)),
+                    len: u32::from(if len > 32768 { len - 32768 } else { len }),
+                    uninit: len > 32768,
+                });
+            } else {
+                let leaf = (u64::from(le16(entry, 8)) << 32) | u64::from(le32(entry, 4));
+                self.read_block(leaf, &mut child)?;
+                self.walk_extent_node(&child, depth - 1, extents)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Collect the blocks of an indirect block map, merging contiguous runs
+    fn walk_block_map(&self, inode: &Inode, extents: &mut Vec<Extent>) -> Result<(), FileError> {
+        let block_size = u64::from(self.superblock.block_size);
+        let blocks = u32::try_from(inode.size.div_ceil(block_size)).map_err(|_| FileError::InvalidFormat)?;
+        let mut logical = 0u32;
+
+        let mut push = |physical: u32, logical: &mut u32| {
+            if physical != 0 {
+                match extents.last_mut() {
+                    Some(e) if e.logical + e.len == *logical && e.physical + u64::from(e.len) == u64::from(physical) => e.len += 1,
+                    _ => extents.push(Extent { logical: *logical, physical: u64::from(physical), len: 1, uninit: false }),
+                }
+            }
+            *logical += 1;
+        };
+
+        // 12 direct blocks, then single, double and triple indirect
+        for i in 0..12 {
+            if logical >= blocks {
+                return Ok(());
+            }
+            push(le32(&inode.block, i * 4), &mut logical);
+        }
+        for (i, level) in (12..15).zip(1..) {
+            if logical >= blocks {
+                break;
+            }
+            self.walk_indirect(le32(&inode.block, i * 4), level, blocks, &mut logical, &mut push)?;
+        }
+        Ok(())
+    }
+
+    /// Walk an indirect block `level` levels above the data
+    fn walk_indirect(
+        &self,
+        block: u32,
+        level: u32,
+        blocks: u32,
+        logical: &mut u32,
+        push: &mut impl FnMut(u32, &mut u32),
+    ) -> Result<(), FileError> {
+        let per_block = self.superblock.block_size / 4;
+        if block == 0 {
+            // Hole: skip every block this subtree covers
+            *logical = logical.saturating_add(per_block.saturating_pow(level)).min(blocks);
+            return Ok(());
+        }
+        let mut data = alloc::vec![0u8; self.superblock.block_size as usize];
+        self.read_block(u64::from(block), &mut data)?;
+        for entry in data.chunks_exact(4) {
+            if *logical >= blocks {
+                break;
+            }
+            let child = le32(entry, 0);
+            if level == 1 {
+                push(child, logical);
+            } else {
+                self.walk_indirect(child, level - 1, blocks, logical, push)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read `inode`'s contents into `buffer`; returns the size
+    pub fn read_inode(&self, inode: &Inode, buffer: &mut [u8]) -> Result<usize, FileError> {
+        let size = usize::try_from(inode.size).map_err(|_| FileError::BufferTooSmall)?;
+        if size > buffer.len() {
+            return Err(FileError::BufferTooSmall);
+        }
+        let buffer = &mut buffer[..size];
+        buffer.fill(0);
+
+        let block_size = self.superblock.block_size as usize;
+        for extent in self.extents(inode)? {
+            let start = extent.logical as usize * block_size;
+            if extent.uninit || start >= size {
+                continue;
+            }
+            let end = (start + extent.len as usize * block_size).min(size);
+            if extent.physical + u64::from(extent.len) > self.superblock.blocks_count {
+                return Err(FileError::InvalidFormat);
+            }
+            self.reader.read_at(extent.physical * block_size as u64, &mut buffer[start..end])?;
+        }
+        Ok(size)
+    }
+
+    /// Contents of `inode`
+    fn read_inode_to_vec(&self, inode: &Inode) -> Result<Vec<u8>, FileError> {
+        let size = usize::try_from(inode.size).map_err(|_| FileError::BufferTooSmall)?;
+        let mut data = alloc::vec![0u8; size];
+        self.read_inode(inode, &mut data)?;
+        Ok(data)
+    }
+
+    /// Entries of directory `dir`, `.` and `..` included
+    pub fn dir_entries(&self, dir: &Inode) -> Result<Vec<DirEntry>, FileError> {
+        if !dir.is_directory() {
+            return Err(FileError::NotDirectory);
+        }
+        let data = self.read_inode_to_vec(dir)?;
+        let block_size = self.superblock.block_size as usize;
+
+        // Entries never cross a block; hashed directories keep their index
+        // inside entries that a linear scan skips
+        let mut entries = Vec::new();
+        for block in data.chunks(block_size) {
+            let mut pos = 0;
+            while pos + 8 <= block.len() {
+                let inode = le32(block, pos);
+                let rec_len = usize::from(le16(block, pos + 4));
+                let name_len = usize::from(block[pos + 6]);
+                if rec_len < 8 || pos + rec_len > block.len() || 8 + name_len > rec_len {
+                    return Err(FileError::InvalidFormat);
+                }
+                if inode != 0 {
+                    let name = &block[pos + 8..pos + 8 + name_len];
+                    entries.push(DirEntry { inode, name: String::from_utf8_lossy(name).into_owned() });
+                }
+                pos += rec_len;
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Resolve `path` (`/` or `\` separated, from the root), following
+    /// symbolic links
+    pub fn lookup(&self, path: &str) -> Result<Inode, FileError> {
+        let mut links = 0;
+        self.resolve(self.inode(ROOT_INODE)?, path, &mut links, true)
+    }
+
+    /// Resolve `path` relative to directory `dir`
+    fn resolve(&self, dir: Inode, path: &str, links: &mut usize, follow_last: bool) -> Result<Inode, FileError> {
+        let mut current = if path.starts_with(['/', '\\']) { self.inode(ROOT_INODE)? } else { dir };
+        let mut components = path.split(['/', '\\']).filter(|c| !c.is_empty() && *c != ".").peekable();
+
+        while let Some(name) = components.next() {
+            let parent = current.clone();
+            let entry = self
+                .dir_entries(&parent)?
+                .into_iter()
+                .find(|e| e.name == name)
+                .ok_or(FileError::NotFound)?;
+            current = self.inode(entry.inode)?;
+
+            if current.file_type == FileType::Symlink && (components.peek().is_some() || follow_last) {
+                *links += 1;
+                if *links > MAX_SYMLINKS {
+                    return Err(FileError::InvalidPath);
+                }
+                let target = self.symlink_target(&current)?;
+                current = self.resolve(parent, &target, links, true)?;
+            }
+        }
+        Ok(current)
+    }
+
+    /// Target of symbolic link `link`
+    pub fn symlink_target(&self, link: &Inode) -> Result<String, FileError> {
+        // Short targets live in the block map itself
+        let data = if link.size < 60 && link.flags & (EXTENTS_FL | INLINE_DATA_FL) == 0 {
+            link.block[..link.size as usize].to_vec()
+        } else {
+            self.read_inode_to_vec(link)?
+        };
+        String::from_utf8(data).map_err(|_| FileError::InvalidFormat)
+    }
+
+    /// Entries of the directory at `path`
+    pub fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, FileError> {
+        self.dir_entries(&self.lookup(path)?)
+    }
+
+    /// Size of the file at `path`
+    pub fn file_size(&self, path: &str) -> Result<u64, FileError> {
+        Ok(self.lookup(path)?.size)
+    }
+
+    /// Read the file at `path` into `buffer`; returns its size
+    pub fn read_file(&self, path: &str, buffer: &mut [u8]) -> Result<usize, FileError> {
+        let inode = self.lookup(path)?;
+        if inode.is_directory() {
+            return Err(FileError::IsDirectory);
+        }
+        self.read_inode(&inode, buffer)
+    }
+
+    /// Contents of the file at `path`
+    pub fn read_to_vec(&self, path: &str) -> Result<Vec<u8>, FileError> {
+        let inode = self.lookup(path)?;
+        if inode.is_directory() {
+            return Err(FileError::IsDirectory);
+        }
+        self.read_inode_to_vec(&inode)
+    }
+}
+
+// =============================================================================
+// HELPERS
+// =============================================================================
+
+fn le16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn le32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: usize = 1024;
+
+    fn put16(image: &mut [u8], offset: usize, value: u16) {
+        image[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put32(image: &mut [u8], offset: usize, value: u32) {
+        image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Write inode `number` (inode table at block 4, 128-byte inodes)
+    fn put_inode(image: &mut [u8], number: u32, mode: u16, size: u32, flags: u32, block: &[u8]) {
+        let base = 4 * BLOCK + (number as usize - 1) * 128;
+        put16(image, base, mode);
+        put32(image, base + 4, size);
+        put32(image, base + 0x20, flags);
+        image[base + 0x28..base + 0x28 + block.len()].copy_from_slice(block);
+    }
+
+    /// Write a directory block holding `entries`
+    fn put_dir(image: &mut [u8], block: usize, entries: &[(u32, &str)]) {
+        let mut pos = block * BLOCK;
+        for (i, (inode, name)) in entries.iter().enumerate() {
+            let rec_len = if i + 1 == entries.len() { block * BLOCK + BLOCK - pos } else { (8 + name.len() + 3) & !3 };
+            put32(image, pos, *inode);
+            put16(image, pos + 4, rec_len as u16);
+            image[pos + 6] = name.len() as u8;
+            image[pos + 8..pos + 8 + name.len()].copy_from_slice(name.as_bytes());
+            pos += rec_len;
+        }
+    }
+
+    /// Extent tree root with one leaf extent
+    fn extent_root(logical: u32, len: u16, physical: u32) -> Vec<u8> {
+        let mut root = alloc::vec![0u8; 24];
+        put16(&mut root, 0, EXTENT_MAGIC);
+        put16(&mut root, 2, 1);
+        put16(&mut root, 4, 4);
+        put32(&mut root, 12, logical);
+        put16(&mut root, 16, len);
+        put32(&mut root, 20, physical);
+        root
+    }
+
+    /// 64 KiB volume: `/boot/vmlinuz` (extents, with a hole), `/initrd`
+    /// (block map) and `/vmlinuz -> boot/vmlinuz`
+    fn image() -> Vec<u8> {
+        let mut image = alloc::vec![0u8; 64 * BLOCK];
+        let sb = 1024;
+        put32(&mut image, sb, 32);
+        put32(&mut image, sb + 4, 64);
+        put32(&mut image, sb + 20, 1);
+        put32(&mut image, sb + 40, 32);
+        put16(&mut image, sb + 56, EXT4_MAGIC);
+        put32(&mut image, sb + 96, incompat::FILETYPE | incompat::EXTENTS);
+        image[sb + 120..sb + 124].copy_from_slice(b"root");
+        // Group descriptor at block 2: inode table at block 4
+        put32(&mut image, 2 * BLOCK + 8, 4);
+
+        put_inode(&mut image, ROOT_INODE, 0x41ED, BLOCK as u32, EXTENTS_FL, &extent_root(0, 1, 10));
+        put_dir(&mut image, 10, &[(2, "."), (2, ".."), (11, "boot"), (13, "initrd"), (14, "vmlinuz")]);
+        put_inode(&mut image, 11, 0x41ED, BLOCK as u32, EXTENTS_FL, &extent_root(0, 1, 11));
+        put_dir(&mut image, 11, &[(11, "."), (2, ".."), (12, "vmlinuz")]);
+
+        // Blocks 0 and 2 of the kernel are data, block 1 is a hole
+        let mut kernel = extent_root(0, 1, 20);
+        put16(&mut kernel, 2, 2);
+        kernel.resize(36, 0);
+        put32(&mut kernel, 24, 2);
+        put16(&mut kernel, 28, 1);
+        put32(&mut kernel, 32, 21);
+        put_inode(&mut image, 12, 0x81A4, (2 * BLOCK + 100) as u32, EXTENTS_FL, &kernel);
+        image[20 * BLOCK..21 * BLOCK].fill(0x7F);
+        image[21 * BLOCK..22 * BLOCK].fill(0x4B);
+
+        let mut map = [0u8; 8];
+        map[..4].copy_from_slice(&30u32.to_le_bytes());
+        map[4..].copy_from_slice(&31u32.to_le_bytes());
+        put_inode(&mut image, 13, 0x81A4, (BLOCK + 10) as u32, 0, &map);
+        image[30 * BLOCK..31 * BLOCK].fill(0x11);
+        image[31 * BLOCK..32 * BLOCK].fill(0x22);
+
+        put_inode(&mut image, 14, 0xA1FF, 12, 0, b"boot/vmlinuz");
+        image
+    }
+
+    #[test]
+    fn test_superblock() {
+        let image = image();
+        let fs = Ext4Filesystem::mount(image.as_slice()).unwrap();
+        assert_eq!(fs.superblock().block_size, 1024);
+        assert_eq!(fs.volume_label(), "root");
+
+        let mut unsupported = image.clone();
+        put32(&mut unsupported, 1024 + 96, incompat::EXTENTS | 0x8000);
+        assert!(matches!(Ext4Filesystem::mount(unsupported.as_slice()), Err(FileError::NotSupported)));
+        assert!(matches!(Ext4Filesystem::mount(&[0u8; 4096][..]), Err(FileError::InvalidFormat)));
+    }
+
+    #[test]
+    fn test_read_files() {
+        let image = image();
+        let fs = Ext4Filesystem::mount(image.as_slice()).unwrap();
+
+        let kernel = fs.read_to_vec("/boot/vmlinuz").unwrap();
+        assert_eq!(kernel.len(), 2 * BLOCK + 100);
+        assert!(kernel[..BLOCK].iter().all(|&b| b == 0x7F));
+        assert!(kernel[BLOCK..2 * BLOCK].iter().all(|&b| b == 0));
+        assert!(kernel[2 * BLOCK..].iter().all(|&b| b == 0x4B));
+
+        // Backslash paths and symlinks resolve to the same file
+        assert_eq!(fs.read_to_vec("\\vmlinuz").unwrap(), kernel);
+        assert_eq!(fs.lookup("/vmlinuz").unwrap().number, 12);
+
+        let mut initrd = [0u8; 2 * BLOCK];
+        assert_eq!(fs.read_file("/initrd", &mut initrd).unwrap(), BLOCK + 10);
+        assert_eq!((initrd[0], initrd[BLOCK + 9], initrd[BLOCK + 10]), (0x11, 0x22, 0));
+        assert!(matches!(fs.read_file("/initrd", &mut [0u8; 16]), Err(FileError::BufferTooSmall)));
+
+        let names: Vec<String> = fs.read_dir("/boot").unwrap().into_iter().map(|e| e.name).collect();
+        assert_eq!(names, [".", "..", "vmlinuz"]);
+        assert!(matches!(fs.lookup("/boot/missing"), Err(FileError::NotFound)));
+        assert!(matches!(fs.read_file("/boot", &mut initrd), Err(FileError::IsDirectory)));
+    }
+}
@@ -1,9 +1,12 @@
 //! UEFI File System Support
 //!
-//! FAT file system access for boot file loading.
+//! FAT file system access for boot file loading, and read-only ext4 for
+//! loading from Linux partitions.
 
 use core::fmt;
 
+pub mod ext4;
+
 // =============================================================================
 // FILE SYSTEM PROTOCOL GUIDs
 // =============================================================================
@@ -1042,6 +1045,19 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
+impl From<FileError> for crate::error::Error {
+    fn from(err: FileError) -> Self {
+        match err {
+            FileError::NotFound => Self::FileNotFound,
+            FileError::InvalidPath => Self::InvalidPath,
+            FileError::DeviceError => Self::DeviceError,
+            FileError::BufferTooSmall => Self::BufferTooSmall,
+            FileError::NotSupported => Self::Unsupported,
+            _ => Self::FileSystemError,
+        }
+    }
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -711,6 +711,138 @@ pub const fn has_unicode_table(&self) -> bool {
     }
 }
 
+/// A PSF1 or PSF2 bitmap font
+///
+/// Glyphs are looked up by character through the font's Unicode table;
+/// in a font without one, glyph `n` is character `n`.
+#[derive(Debug, Clone, Copy)]
+pub struct PsfFont<'a> {
+    /// Glyph bitmaps, rows padded to whole bytes
+    glyphs: &'a [u8],
+    /// Number of glyphs
+    glyph_count: u32,
+    /// Bytes per glyph
+    bytes_per_glyph: u32,
+    /// Glyph width in pixels
+    width: u32,
+    /// Glyph height in pixels
+    height: u32,
+    /// Unicode table, empty if none
+    unicode: &'a [u8],
+    /// PSF1 (UCS-2 table) rather than PSF2 (UTF-8 table)
+    psf1: bool,
+}
+
+impl<'a> PsfFont<'a> {
+    /// Parse a PSF1 or PSF2 font file
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        let u32_at = |offset: usize| {
+            data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        if data.starts_with(&Psf2Header::MAGIC) {
+            let header_size = u32_at(8)? as usize;
+            let flags = u32_at(12)?;
+            let glyph_count = u32_at(16)?;
+            let bytes_per_glyph = u32_at(20)?;
+            let height = u32_at(24)?;
+            let width = u32_at(28)?;
+            if width == 0 || height == 0 || bytes_per_glyph < height * width.div_ceil(8) {
+                return None;
+            }
+            let end = header_size.checked_add(glyph_count as usize * bytes_per_glyph as usize)?;
+            Some(Self {
+                glyphs: data.get(header_size..end)?,
+                glyph_count,
+                bytes_per_glyph,
+                width,
+                height,
+                unicode: if flags & 0x01 != 0 { &data[end..] } else { &[] },
+                psf1: false,
+            })
+        } else if data.starts_with(&Psf1Header::MAGIC) {
+            let mode = *data.get(2)?;
+            let height = *data.get(3)? as u32;
+            let glyph_count = if mode & 0x01 != 0 { 512 } else { 256 };
+            let end = 4 + (glyph_count * height) as usize;
+            Some(Self {
+                glyphs: data.get(4..end)?,
+                glyph_count,
+                bytes_per_glyph: height,
+                width: 8,
+                height,
+                unicode: if mode & 0x06 != 0 { &data[end..] } else { &[] },
+                psf1: true,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Glyph width in pixels
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Glyph height in pixels
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Bytes per bitmap row
+    pub const fn bytes_per_row(&self) -> u32 {
+        self.width.div_ceil(8)
+    }
+
+    /// Index of the glyph for `c`
+    ///
+    /// Multi-character sequences in the Unicode table are skipped; only
+    /// single characters are matched.
+    pub fn glyph_index(&self, c: char) -> Option<u32> {
+        if self.unicode.is_empty() {
+            return ((c as u32) < self.glyph_count).then_some(c as u32);
+        }
+
+        if self.psf1 {
+            let mut glyph = 0;
+            let mut in_sequence = false;
+            for entry in self.unicode.chunks_exact(2) {
+                match u16::from_le_bytes([entry[0], entry[1]]) {
+                    0xFFFF => {
+                        glyph += 1;
+                        in_sequence = false;
+                    }
+                    0xFFFE => in_sequence = true,
+                    cp if !in_sequence && cp as u32 == c as u32 => return Some(glyph),
+                    _ => {}
+                }
+            }
+            return None;
+        }
+
+        self.unicode
+            .split(|&b| b == 0xFF)
+            .take(self.glyph_count as usize)
+            .position(|entry| {
+                let singles = entry.split(|&b| b == 0xFE).next().unwrap_or(&[]);
+                core::str::from_utf8(singles).is_ok_and(|s| s.chars().any(|ch| ch == c))
+            })
+            .map(|i| i as u32)
+    }
+
+    /// Check if the font can draw `c`
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.glyph_index(c).is_some()
+    }
+
+    /// Bitmap of the glyph for `c`, `bytes_per_row` bytes per row
+    pub fn glyph(&self, c: char) -> Option<&'a [u8]> {
+        let index = self.glyph_index(c)? as usize;
+        let size = self.bytes_per_glyph as usize;
+        self.glyphs.get(index * size..(index + 1) * size)
+    }
+}
+
 // =============================================================================
 // IMAGE FORMATS
 // =============================================================================
@@ -1172,6 +1304,41 @@ fn test_display_mode() {
         assert_eq!(mode.stride(), 1920 * 4);
     }
 
+    #[test]
+    fn test_psf2_unicode_table() {
+        let mut font = [0u8; 32 + 3 * 2 + 13];
+        font[..4].copy_from_slice(&Psf2Header::MAGIC);
+        for (i, value) in [0, 32, 1, 3, 2, 2, 8].iter().enumerate() {
+            font[4 + i * 4..8 + i * 4].copy_from_slice(&(*value as u32).to_le_bytes());
+        }
+        font[32..38].copy_from_slice(&[0x18, 0x3C, 0x66, 0x7E, 0xFF, 0x00]);
+        // "A"; "é" plus the sequence "e\u{301}"; "─━"
+        let table = b"A\xFF\xC3\xA9\xFEe\xCC\x81\xFF\xE2\x94\x80\xFF";
+        font[38..38 + table.len()].copy_from_slice(table);
+
+        let psf = PsfFont::parse(&font[..38 + table.len()]).unwrap();
+        assert_eq!((psf.width(), psf.height(), psf.bytes_per_row()), (8, 2, 1));
+        assert_eq!(psf.glyph_index('A'), Some(0));
+        assert_eq!(psf.glyph_index('é'), Some(1));
+        assert_eq!(psf.glyph_index('─'), Some(2));
+        assert!(!psf.has_glyph('e'));
+        assert_eq!(psf.glyph('é'), Some(&[0x66, 0x7E][..]));
+        assert!(PsfFont::parse(&font[..36]).is_none());
+    }
+
+    #[test]
+    fn test_psf1_without_table() {
+        let mut font = [0u8; 4 + 256];
+        font[..2].copy_from_slice(&Psf1Header::MAGIC);
+        font[3] = 1;
+        font[4 + 0xE9] = 0xAA;
+
+        let psf = PsfFont::parse(&font).unwrap();
+        assert_eq!(psf.glyph_index('A'), Some(0x41));
+        assert_eq!(psf.glyph('é'), Some(&[0xAA][..]));
+        assert!(!psf.has_glyph('界'));
+    }
+
     #[test]
     fn test_progress_bar() {
         let mut bar = ProgressBar::new(Rect::new(100, 500, 400, 20));
@@ -395,6 +395,12 @@ fn parse_hex_u8(s: &str) -> Option<u8> {
     [0x8C, 0x14, 0xF5, 0x85, 0x17, 0xA6, 0x25, 0xAA]
 );
 
+/// Absolute Pointer Protocol GUID
+pub const ABSOLUTE_POINTER_PROTOCOL_GUID: Guid = Guid::new(
+    0x8D59D32B, 0xC655, 0x4AE9,
+    [0x9B, 0x15, 0xF2, 0x59, 0x04, 0x99, 0x2A, 0x43]
+);
+
 /// Simple Text Output Protocol GUID
 pub const SIMPLE_TEXT_OUTPUT_PROTOCOL_GUID: Guid = Guid::new(
     0x387477C2, 0x69C7, 0x11D2,
@@ -573,6 +579,7 @@ pub fn guid_name(guid: &Guid) -> Option<&'static str> {
     GuidEntry { guid: BLOCK_IO_PROTOCOL_GUID, name: "Block I/O Protocol" },
     GuidEntry { guid: SIMPLE_TEXT_INPUT_PROTOCOL_GUID, name: "Simple Text Input Protocol" },
     GuidEntry { guid: SIMPLE_TEXT_OUTPUT_PROTOCOL_GUID, name: "Simple Text Output Protocol" },
+    GuidEntry { guid: ABSOLUTE_POINTER_PROTOCOL_GUID, name: "Absolute Pointer Protocol" },
     GuidEntry { guid: GRAPHICS_OUTPUT_PROTOCOL_GUID, name: "Graphics Output Protocol" },
     GuidEntry { guid: SERIAL_IO_PROTOCOL_GUID, name: "Serial I/O Protocol" },
     GuidEntry { guid: PCI_IO_PROTOCOL_GUID, name: "PCI I/O Protocol" },
@@ -3,7 +3,7 @@
 //! The main boot information structure passed to the kernel.
 
 use crate::raw::types::*;
-use crate::handoff::{FramebufferInfo, MemoryMap, ModuleInfo};
+use crate::handoff::{ConsoleHandover, ConsolePalette, DisplayInfo, FramebufferInfo, MemoryMap, ModuleInfo, SmpInfo};
 
 extern crate alloc;
 use alloc::vec::Vec;
@@ -40,6 +40,12 @@ pub struct BootInfo {
     /// Framebuffer information
     pub framebuffer: Option<FramebufferInfo>,
 
+    /// Display behind the framebuffer (from EDID)
+    pub display: Option<DisplayInfo>,
+
+    /// Framebuffer console colors from the boot theme
+    pub console_palette: Option<ConsolePalette>,
+
     /// RSDP address (ACPI root)
     pub rsdp_address: Option<PhysicalAddress>,
 
@@ -49,6 +55,12 @@ pub struct BootInfo {
     /// EFI system table address
     pub efi_system_table: Option<PhysicalAddress>,
 
+    /// Boot console in `earlycon=` syntax (from the ACPI SPCR)
+    pub earlycon: Option<String>,
+
+    /// Console state for the kernel to continue from
+    pub console_handover: Option<ConsoleHandover>,
+
     /// EFI memory map address
     pub efi_memory_map: Option<PhysicalAddress>,
 
@@ -94,6 +106,12 @@ pub struct BootInfo {
     // END KASLR / RELOCATION FIELDS
     // =========================================================================
 
+    /// Measurements of the kernel, initrd, command line and boot config
+    pub measurements: Option<MeasurementLog>,
+
+    /// The bootloader's log, for post-mortem analysis
+    pub loader_log: Option<LoaderLog>,
+
     /// Physical memory offset (for identity mapping)
     pub physical_memory_offset: Option<u64>,
 
@@ -112,6 +130,9 @@ pub struct BootInfo {
     /// BSP (Bootstrap Processor) APIC ID
     pub bsp_apic_id: u32,
 
+    /// CPU topology and AP trampoline, from the MADT
+    pub smp: Option<SmpInfo>,
+
     /// Device tree blob address (for ARM/RISC-V)
     pub dtb_address: Option<PhysicalAddress>,
 
@@ -130,9 +151,13 @@ pub fn new() -> Self {
             command_line: String::new(),
             memory_map: MemoryMap::new(),
             framebuffer: None,
+            display: None,
+            console_palette: None,
             rsdp_address: None,
             smbios_address: None,
             efi_system_table: None,
+            earlycon: None,
+            console_handover: None,
             efi_memory_map: None,
             efi_memory_map_size: 0,
             efi_memory_descriptor_size: 0,
@@ -147,12 +172,15 @@ pub fn new() -> Self {
             kaslr_entropy_quality: 0,
             relocation_count: 0,
             // End KASLR fields
+            measurements: None,
+            loader_log: None,
             physical_memory_offset: None,
             recursive_index: None,
             tls_template: None,
             boot_timestamp: 0,
             cpu_count: 1,
             bsp_apic_id: 0,
+            smp: None,
             dtb_address: None,
             dtb_size: 0,
         }
@@ -274,6 +302,47 @@ fn default() -> Self {
     }
 }
 
+// =============================================================================
+// MEASUREMENT LOG
+// =============================================================================
+
+/// TCG event log of the boot measurements
+///
+/// Replaying the events over zeroed PCRs gives the values of PCRs 8 and 9
+/// the bootloader left in the TPM.
+#[derive(Debug, Clone, Default)]
+pub struct MeasurementLog {
+    /// Crypto-agile (TCG2) event log: the Spec ID event, then the events
+    pub event_log: Vec<u8>,
+    /// Number of events after the Spec ID event
+    pub event_count: u32,
+    /// Banks the events carry digests for (`EFI_TCG2_BOOT_HASH_ALG_*`)
+    pub hash_algorithms: u32,
+    /// Whether the events were extended into a TPM, or only logged
+    pub tpm_extended: bool,
+}
+
+// =============================================================================
+// LOADER LOG
+// =============================================================================
+
+/// The bootloader's log ring, handed over as text
+///
+/// Lines look like `[    0.012345] [INFO ] module:line: message`, oldest
+/// first. The text stays in the loader image's data, so the kernel has to
+/// copy it before reclaiming loader memory.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoaderLog {
+    /// Start of the text
+    pub address: PhysicalAddress,
+    /// Length of the text in bytes
+    pub size: u64,
+    /// Lines logged, including dropped ones
+    pub lines: u64,
+    /// Bytes of the oldest lines dropped when the ring filled up
+    pub dropped: u64,
+}
+
 // =============================================================================
 // TLS TEMPLATE
 // =============================================================================
@@ -407,6 +476,8 @@ pub fn from_boot_info(info: &BootInfo) -> Self {
         if info.rsdp_address.is_some() { flags |= 1 << 1; }
         if info.smbios_address.is_some() { flags |= 1 << 2; }
         if info.efi_system_table.is_some() { flags |= 1 << 3; }
+        if info.earlycon.is_some() { flags |= 1 << 4; }
+        if info.console_handover.is_some() { flags |= 1 << 5; }
 
         Self {
             magic: info.magic,
@@ -678,6 +678,132 @@ pub mod font {
     };
 }
 
+/// Console colors chosen by the boot theme, passed on to the kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct ConsolePalette {
+    /// Normal text
+    pub foreground: (u8, u8, u8),
+    /// Screen background
+    pub background: (u8, u8, u8),
+    /// Headings and highlights
+    pub accent: (u8, u8, u8),
+    /// Secondary text
+    pub dim: (u8, u8, u8),
+    /// Selected item text
+    pub selected_fg: (u8, u8, u8),
+    /// Selected item background
+    pub selected_bg: (u8, u8, u8),
+    /// Warnings
+    pub warning: (u8, u8, u8),
+    /// Errors
+    pub error: (u8, u8, u8),
+}
+
+impl Default for ConsolePalette {
+    fn default() -> Self {
+        Self {
+            foreground: (255, 255, 255),
+            background: (0, 0, 0),
+            accent: (0, 255, 255),
+            dim: (128, 128, 128),
+            selected_fg: (0, 0, 0),
+            selected_bg: (255, 255, 255),
+            warning: (255, 255, 0),
+            error: (255, 0, 0),
+        }
+    }
+}
+
+/// Splash screen left on the display when the kernel is entered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(C)]
+pub struct SplashHandover {
+    /// Splash is on screen; the kernel keeps it until the display server starts
+    pub visible: bool,
+    /// Progress shown in the bar (0-100)
+    pub progress: u8,
+    /// Screen background (0x00RRGGBB)
+    pub background: u32,
+    /// Logo rectangle (x, y, width, height)
+    pub logo: (u32, u32, u32, u32),
+    /// Progress bar rectangle (x, y, width, height)
+    pub progress_bar: (u32, u32, u32, u32),
+}
+
+/// Serial console line settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct SerialHandover {
+    /// Baud rate
+    pub baud: u32,
+    /// Data bits (5-8)
+    pub data_bits: u8,
+    /// Parity (0 none, 1 odd, 2 even, 3 mark, 4 space)
+    pub parity: u8,
+    /// Stop bits (1 or 2)
+    pub stop_bits: u8,
+}
+
+impl Default for SerialHandover {
+    fn default() -> Self {
+        Self { baud: 115200, data_bits: 8, parity: 0, stop_bits: 1 }
+    }
+}
+
+/// Console state at kernel entry
+///
+/// Lets the kernel carry on where the bootloader stopped instead of
+/// setting a mode and clearing the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(C)]
+pub struct ConsoleHandover {
+    /// Graphics mode the framebuffer was left in
+    pub mode: u32,
+    /// Next text column
+    pub cursor_column: u32,
+    /// Next text row
+    pub cursor_row: u32,
+    /// Glyph cell (width, height) in pixels
+    pub cell: (u8, u8),
+    /// Splash state
+    pub splash: SplashHandover,
+    /// Serial console, if one is in use
+    pub serial: Option<SerialHandover>,
+}
+
+/// Display attached to the framebuffer, as described by its EDID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(C)]
+pub struct DisplayInfo {
+    /// PNP manufacturer ID (e.g. `DEL`)
+    pub manufacturer: [u8; 3],
+    /// Manufacturer product code
+    pub product: u16,
+    /// Native (preferred) resolution (width, height), zero if unknown
+    pub native: (u32, u32),
+    /// Refresh rate of the native mode in Hz, zero if unknown
+    pub refresh_hz: u32,
+    /// Physical size (width, height) in millimetres, zero if unknown
+    pub size_mm: (u16, u16),
+    /// Display accepts HDR (PQ or HLG) signals
+    pub hdr: bool,
+    /// Desired content max luminance in cd/m², zero if not reported
+    pub max_luminance: u16,
+    /// Panel mounting, degrees clockwise (from the ACPI BGRT), zero if upright
+    pub orientation: u16,
+}
+
+impl DisplayInfo {
+    /// Horizontal pixel density in dots per inch, if the size is known
+    pub fn dpi(&self) -> Option<u32> {
+        if self.size_mm.0 == 0 || self.native.0 == 0 {
+            return None;
+        }
+        Some(self.native.0 * 254 / (self.size_mm.0 as u32 * 10))
+    }
+}
+
 /// Console for text rendering on framebuffer
 pub struct FramebufferConsole<'a> {
     writer: FramebufferWriter<'a>,
@@ -719,6 +845,12 @@ pub fn set_bg(&mut self, r: u8, g: u8, b: u8) {
         self.bg_color = (r, g, b);
     }
 
+    /// Use a palette's text colors; takes effect for new output
+    pub fn set_palette(&mut self, palette: &ConsolePalette) {
+        self.fg_color = palette.foreground;
+        self.bg_color = palette.background;
+    }
+
     /// Clear console
     pub fn clear(&mut self) {
         self.writer.clear(self.bg_color.0, self.bg_color.1, self.bg_color.2);
@@ -794,6 +926,17 @@ fn scroll(&mut self) {
         // A real implementation would copy framebuffer contents
         self.cursor_y = self.rows() - 1;
     }
+
+    /// Console state for the kernel to continue from
+    pub fn handover(&self, mode: u32) -> ConsoleHandover {
+        ConsoleHandover {
+            mode,
+            cursor_column: self.cursor_x,
+            cursor_row: self.cursor_y,
+            cell: (font::FONT_WIDTH as u8, font::FONT_HEIGHT as u8),
+            ..ConsoleHandover::default()
+        }
+    }
 }
 
 // =============================================================================
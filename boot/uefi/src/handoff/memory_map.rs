@@ -1,6 +1,9 @@
 //! Memory Map
 //!
 //! Boot-time memory map structures for passing to the kernel.
+//!
+//! [`sanitize`] turns the firmware map, which may be unsorted and
+//! overlapping, into the deterministic map the kernel allocator starts from.
 
 use crate::raw::types::*;
 use crate::error::Result;
@@ -662,6 +665,192 @@ pub fn statistics(&self) -> MemoryMapStats {
     }
 }
 
+// =============================================================================
+// SANITIZATION
+// =============================================================================
+
+/// Page size of the memory map
+const PAGE_SIZE: u64 = 4096;
+
+/// A range the loader placed something in, kept out of usable memory
+#[derive(Debug, Clone, Copy)]
+pub struct Reservation {
+    /// Physical start address
+    pub start: PhysicalAddress,
+    /// Size in bytes
+    pub size: u64,
+    /// Type the range is reported as
+    pub memory_type: MemoryType,
+}
+
+impl Reservation {
+    /// Create new reservation
+    pub fn new(start: PhysicalAddress, size: u64, memory_type: MemoryType) -> Self {
+        Self { start, size, memory_type }
+    }
+}
+
+/// What [`sanitize`] did to a map
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SanitizeReport {
+    /// Usable RAM after sanitization, in bytes
+    pub usable: u64,
+    /// Ranges claimed by more than one entry
+    pub overlaps: usize,
+    /// Entries dropped for covering less than a page
+    pub fragments: usize,
+    /// Entries in the sanitized map
+    pub entries: usize,
+}
+
+/// Sanitize a firmware memory map for the kernel
+///
+/// The result is sorted, free of overlaps and made of whole pages, with
+/// adjacent entries of the same type and attributes merged, so the same
+/// firmware map always gives the kernel allocator the same map:
+///
+/// - Usable entries shrink to the pages they fully cover; everything else
+///   grows to the pages it touches. What is left of less than a page is
+///   dropped.
+/// - Where entries overlap, the more restrictive type wins (bad memory over
+///   reserved over in-use over reclaimable over usable).
+/// - `reservations` (the kernel, modules and boot structures) are laid over
+///   the firmware map the same way, never handed out as usable.
+pub fn sanitize(map: &mut MemoryMap, reservations: &[Reservation]) -> SanitizeReport {
+    let mut report = SanitizeReport::default();
+
+    let mut pieces = Vec::with_capacity(map.entries.len() + reservations.len());
+    for entry in &map.entries {
+        let usable = entry.memory_type.is_usable();
+        match page_align(*entry, entry.physical_start.0, entry.end().0, usable) {
+            Some(piece) => pieces.push(piece),
+            None => report.fragments += 1,
+        }
+    }
+    for reservation in reservations {
+        let entry = MemoryMapEntry::new(reservation.start, 0, reservation.memory_type);
+        let end = reservation.start.0 + reservation.size;
+        match page_align(entry, reservation.start.0, end, false) {
+            Some(piece) => pieces.push(piece),
+            None => report.fragments += 1,
+        }
+    }
+    pieces.sort_by_key(|e| {
+        (e.physical_start.0, e.page_count, e.memory_type as u32, e.attributes.0)
+    });
+
+    let mut bounds: Vec<u64> =
+        pieces.iter().flat_map(|e| [e.physical_start.0, e.end().0]).collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    // Each range between two consecutive bounds goes to the strongest
+    // entry covering it
+    let mut entries: Vec<MemoryMapEntry> = Vec::with_capacity(pieces.len());
+    for range in bounds.windows(2) {
+        let (start, end) = (range[0], range[1]);
+        let mut covering = pieces
+            .iter()
+            .filter(|e| e.physical_start.0 <= start && e.end().0 >= end);
+        let Some(mut winner) = covering.next() else {
+            continue;
+        };
+        let mut overlap = false;
+        for entry in covering {
+            overlap = true;
+            if sanitize_rank(entry.memory_type) > sanitize_rank(winner.memory_type) {
+                winner = entry;
+            }
+        }
+        if overlap {
+            report.overlaps += 1;
+        }
+
+        let offset = start - winner.physical_start.0;
+        let piece = MemoryMapEntry {
+            physical_start: PhysicalAddress(start),
+            virtual_start: if winner.virtual_start.0 != 0 {
+                VirtualAddress(winner.virtual_start.0 + offset)
+            } else {
+                VirtualAddress(0)
+            },
+            page_count: (end - start) / PAGE_SIZE,
+            memory_type: winner.memory_type,
+            attributes: winner.attributes,
+        };
+
+        match entries.last_mut() {
+            Some(last) if continues(last, &piece) => last.page_count += piece.page_count,
+            _ => entries.push(piece),
+        }
+    }
+
+    map.entries = entries;
+    report.usable = map.total_usable();
+    report.entries = map.entries.len();
+    report
+}
+
+/// Page-align `[start, end)`, shrinking it if `inward`, growing it if not
+fn page_align(
+    entry: MemoryMapEntry,
+    start: u64,
+    end: u64,
+    inward: bool,
+) -> Option<MemoryMapEntry> {
+    let (aligned_start, aligned_end) = if inward {
+        (start.checked_add(PAGE_SIZE - 1)? & !(PAGE_SIZE - 1), end & !(PAGE_SIZE - 1))
+    } else {
+        (start & !(PAGE_SIZE - 1), end.checked_add(PAGE_SIZE - 1)? & !(PAGE_SIZE - 1))
+    };
+    if aligned_end <= aligned_start {
+        return None;
+    }
+
+    let virtual_start = match entry.virtual_start.0 {
+        0 => 0,
+        virt => (virt + aligned_start).wrapping_sub(entry.physical_start.0),
+    };
+    Some(MemoryMapEntry {
+        physical_start: PhysicalAddress(aligned_start),
+        virtual_start: VirtualAddress(virtual_start),
+        page_count: (aligned_end - aligned_start) / PAGE_SIZE,
+        ..entry
+    })
+}
+
+/// Does `next` extend `last`, physically and virtually?
+fn continues(last: &MemoryMapEntry, next: &MemoryMapEntry) -> bool {
+    let virtual_contiguous = match (last.virtual_start.0, next.virtual_start.0) {
+        (0, 0) => true,
+        (0, _) | (_, 0) => false,
+        (last_virt, next_virt) => last_virt + last.size() == next_virt,
+    };
+    last.end() == next.physical_start && last.can_merge(next) && virtual_contiguous
+}
+
+/// Which type wins an overlap: the higher rank, then the higher type
+fn sanitize_rank(memory_type: MemoryType) -> (u8, u32) {
+    let rank = match memory_type {
+        MemoryType::Usable => 0,
+        MemoryType::BootloaderReclaimable => 1,
+        MemoryType::AcpiReclaimable
+        | MemoryType::KernelAndModules
+        | MemoryType::Framebuffer
+        | MemoryType::EfiRuntimeCode
+        | MemoryType::EfiRuntimeData
+        | MemoryType::PalCode
+        | MemoryType::PersistentMemory => 2,
+        MemoryType::Reserved
+        | MemoryType::AcpiNvs
+        | MemoryType::Mmio
+        | MemoryType::MmioPortSpace
+        | MemoryType::Unknown => 3,
+        MemoryType::BadMemory => 4,
+    };
+    (rank, memory_type as u32)
+}
+
 // =============================================================================
 // MEMORY MAP STATISTICS
 // =============================================================================
@@ -926,4 +1115,56 @@ fn test_find_free() {
         assert!(addr.is_some());
         assert_eq!(addr.unwrap(), 0x100000);
     }
+
+    #[test]
+    fn test_sanitize() {
+        let entry = |start: u64, pages: u64, memory_type| {
+            MemoryMapEntry::new(PhysicalAddress(start), pages, memory_type)
+        };
+        let mut map = MemoryMap::new();
+        map.add(entry(0x10_0000, 0x100, MemoryType::Usable));
+        map.add(entry(0x0, 0x9f, MemoryType::Usable));
+        // Overlaps the usable range above
+        map.add(entry(0x18_0000, 0x10, MemoryType::Reserved));
+        map.add(entry(0x20_0000, 0x100, MemoryType::Usable));
+        // Adjacent to, and merged with, the one above
+        map.add(entry(0x30_0000, 0x10, MemoryType::Usable));
+        // Unaligned, less than a page once shrunk
+        map.add(entry(0x9f_800, 1, MemoryType::Usable));
+
+        let kernel =
+            Reservation::new(PhysicalAddress(0x20_0800), 0x1000, MemoryType::KernelAndModules);
+        let report = sanitize(&mut map, &[kernel]);
+
+        let layout: Vec<_> = map
+            .entries
+            .iter()
+            .map(|e| (e.physical_start.0, e.page_count, e.memory_type))
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                (0x0, 0x9f, MemoryType::Usable),
+                (0x10_0000, 0x80, MemoryType::Usable),
+                (0x18_0000, 0x10, MemoryType::Reserved),
+                (0x19_0000, 0x70, MemoryType::Usable),
+                (0x20_0000, 0x2, MemoryType::KernelAndModules),
+                (0x20_2000, 0x10e, MemoryType::Usable),
+            ]
+        );
+        assert_eq!(
+            report,
+            SanitizeReport {
+                usable: (0x9f + 0x80 + 0x70 + 0x10e) * 4096,
+                overlaps: 2,
+                fragments: 1,
+                entries: 6,
+            }
+        );
+
+        // Sanitizing again changes nothing
+        let entries = map.entries.clone();
+        assert_eq!(sanitize(&mut map, &[]).overlaps, 0);
+        assert_eq!(map.entries.len(), entries.len());
+    }
 }
@@ -17,7 +17,9 @@
 //! - SMBIOS information
 //! - Kernel command line
 //! - Module/initrd information
+//! - TPM measurement log
 //! - RSDP location
+//! - CPU topology and AP trampoline for SMP startup
 //! - EFI runtime services
 
 #![allow(dead_code)]
@@ -27,15 +29,18 @@
 pub mod memory_map;
 pub mod modules;
 pub mod rsdp;
+pub mod smp;
 
 pub use bootinfo::*;
 pub use framebuffer::*;
 pub use memory_map::*;
 pub use modules::*;
 pub use rsdp::*;
+pub use smp::*;
 
 use crate::raw::types::*;
 use crate::error::{Error, Result};
+use crate::loader::LoadedKernel;
 
 extern crate alloc;
 use alloc::vec::Vec;
@@ -51,6 +56,8 @@ pub struct HandoffBuilder {
     boot_info: BootInfo,
     /// Memory map entries
     memory_entries: Vec<MemoryMapEntry>,
+    /// Ranges reserved over the memory map
+    reservations: Vec<Reservation>,
     /// Modules
     modules: Vec<ModuleInfo>,
     /// Command line
@@ -63,6 +70,7 @@ pub fn new() -> Self {
         Self {
             boot_info: BootInfo::new(),
             memory_entries: Vec::new(),
+            reservations: Vec::new(),
             modules: Vec::new(),
             command_line: String::new(),
         }
@@ -121,12 +129,27 @@ pub fn add_memory_region_with_attrs(
         self
     }
 
+    /// Reserve a range the loader placed a boot structure in
+    ///
+    /// Reserved ranges are laid over the memory map when it is sanitized
+    /// by [`build`](Self::build).
+    pub fn reserve(mut self, base: PhysicalAddress, size: u64, memory_type: MemoryType) -> Self {
+        self.reservations.push(Reservation::new(base, size, memory_type));
+        self
+    }
+
     /// Set framebuffer info
     pub fn framebuffer(mut self, fb: FramebufferInfo) -> Self {
         self.boot_info.framebuffer = Some(fb);
         self
     }
 
+    /// Set framebuffer console colors
+    pub fn console_palette(mut self, palette: ConsolePalette) -> Self {
+        self.boot_info.console_palette = Some(palette);
+        self
+    }
+
     /// Set RSDP address
     pub fn rsdp(mut self, address: PhysicalAddress) -> Self {
         self.boot_info.rsdp_address = Some(address);
@@ -145,6 +168,18 @@ pub fn efi_system_table(mut self, address: PhysicalAddress) -> Self {
         self
     }
 
+    /// Set boot console, in `earlycon=` syntax
+    pub fn earlycon(mut self, spec: &str) -> Self {
+        self.boot_info.earlycon = Some(String::from(spec));
+        self
+    }
+
+    /// Set console state for the kernel to continue from
+    pub fn console_handover(mut self, handover: ConsoleHandover) -> Self {
+        self.boot_info.console_handover = Some(handover);
+        self
+    }
+
     /// Set EFI memory map address
     pub fn efi_memory_map(mut self, address: PhysicalAddress, size: u64, desc_size: u64) -> Self {
         self.boot_info.efi_memory_map = Some(address);
@@ -153,8 +188,21 @@ pub fn efi_memory_map(mut self, address: PhysicalAddress, size: u64, desc_size:
         self
     }
 
-    /// Add module
+    /// Set CPU topology and AP trampoline; also sets the CPU count and BSP
+    pub fn smp(mut self, smp: SmpInfo) -> Self {
+        self.boot_info.cpu_count = smp.enabled_count() as u32;
+        self.boot_info.bsp_apic_id = smp.bsp_apic_id;
+        self.boot_info.smp = Some(smp);
+        self
+    }
+
+    /// Add module; its memory is reserved
     pub fn add_module(mut self, module: ModuleInfo) -> Self {
+        self.reservations.push(Reservation::new(
+            module.physical_address,
+            module.size,
+            MemoryType::KernelAndModules,
+        ));
         self.modules.push(module);
         self
     }
@@ -177,6 +225,43 @@ pub fn kernel_size(mut self, size: u64) -> Self {
         self
     }
 
+    /// Record a placed kernel: its addresses, size and KASLR slide
+    ///
+    /// `entropy_quality` grades the randomness behind the slide, as in
+    /// [`BootInfo::kaslr_entropy_description`]. The kernel's pages are
+    /// reserved.
+    pub fn kernel(mut self, kernel: &LoadedKernel, entropy_quality: u8) -> Self {
+        for segment in &kernel.segments {
+            self.reservations.push(Reservation::new(
+                segment.phys,
+                segment.size(),
+                MemoryType::KernelAndModules,
+            ));
+        }
+        self.boot_info.kernel_physical_address = kernel.segments.first().map(|s| s.phys);
+        self.boot_info.kernel_virtual_address = Some(kernel.virt_base());
+        self.boot_info.kernel_size = kernel.image_size();
+        self.boot_info.set_kaslr_info(
+            kernel.slide as i64,
+            kernel.link_base.0,
+            entropy_quality,
+            kernel.relocations as u64,
+        );
+        self
+    }
+
+    /// Set the boot measurement log
+    pub fn measurements(mut self, log: MeasurementLog) -> Self {
+        self.boot_info.measurements = Some(log);
+        self
+    }
+
+    /// Set the bootloader's log
+    pub fn loader_log(mut self, log: LoaderLog) -> Self {
+        self.boot_info.loader_log = Some(log);
+        self
+    }
+
     /// Set physical memory offset
     pub fn physical_memory_offset(mut self, offset: u64) -> Self {
         self.boot_info.physical_memory_offset = Some(offset);
@@ -205,9 +290,11 @@ pub fn build(mut self) -> BootInfo {
         self.boot_info.command_line = self.command_line;
 
         // Build memory map
-        self.boot_info.memory_map = MemoryMap {
+        let mut memory_map = MemoryMap {
             entries: self.memory_entries,
         };
+        memory_map::sanitize(&mut memory_map, &self.reservations);
+        self.boot_info.memory_map = memory_map;
 
         // Set modules
         self.boot_info.modules = self.modules;
@@ -272,6 +359,10 @@ pub fn serialize(&mut self, info: &BootInfo) -> Result<&[u8]> {
         if info.rsdp_address.is_some() { flags |= 1 << 1; }
         if info.smbios_address.is_some() { flags |= 1 << 2; }
         if info.efi_system_table.is_some() { flags |= 1 << 3; }
+        if info.earlycon.is_some() { flags |= 1 << 4; }
+        if info.console_handover.is_some() { flags |= 1 << 5; }
+        if info.kaslr_enabled { flags |= 1 << 6; }
+        if info.measurements.is_some() { flags |= 1 << 7; }
         self.write_u64(flags)?;
 
         // Write command line
@@ -312,6 +403,56 @@ pub fn serialize(&mut self, info: &BootInfo) -> Result<&[u8]> {
             self.write_u64(addr.0)?;
         }
 
+        // Write boot console if present
+        if let Some(ref spec) = info.earlycon {
+            self.write_string(spec)?;
+        }
+
+        // Write console handover if present
+        if let Some(ref console) = info.console_handover {
+            self.write_u32(console.mode)?;
+            self.write_u32(console.cursor_column)?;
+            self.write_u32(console.cursor_row)?;
+            self.write_u8(console.cell.0)?;
+            self.write_u8(console.cell.1)?;
+            self.write_u8(console.splash.visible as u8)?;
+            self.write_u8(console.splash.progress)?;
+            self.write_u32(console.splash.background)?;
+            for rect in [console.splash.logo, console.splash.progress_bar] {
+                self.write_u32(rect.0)?;
+                self.write_u32(rect.1)?;
+                self.write_u32(rect.2)?;
+                self.write_u32(rect.3)?;
+            }
+            // Baud 0: no serial console
+            let serial = console.serial.unwrap_or(SerialHandover { baud: 0, data_bits: 0, parity: 0, stop_bits: 0 });
+            self.write_u32(serial.baud)?;
+            self.write_u8(serial.data_bits)?;
+            self.write_u8(serial.parity)?;
+            self.write_u8(serial.stop_bits)?;
+            self.write_u8(0)?; // Reserved
+        }
+
+        // Write KASLR slide if the kernel was relocated
+        if info.kaslr_enabled {
+            self.write_u64(info.kernel_slide as u64)?;
+            self.write_u64(info.kernel_link_address)?;
+            self.write_u64(info.relocation_count)?;
+            self.write_u8(info.kaslr_entropy_quality)?;
+            self.write_u8(0)?; // Reserved
+            self.write_u16(0)?; // Reserved
+        }
+
+        // Write measurement log if present
+        if let Some(ref log) = info.measurements {
+            self.write_u32(log.event_count)?;
+            self.write_u32(log.hash_algorithms)?;
+            self.write_u8(log.tpm_extended as u8)?;
+            self.write_u8(0)?; // Reserved
+            self.write_u16(0)?; // Reserved
+            self.write_bytes(&log.event_log)?;
+        }
+
         // Write modules
         self.write_u64(info.modules.len() as u64)?;
         for module in &info.modules {
@@ -357,7 +498,11 @@ fn write_u64(&mut self, value: u64) -> Result<()> {
 
     /// Write string
     fn write_string(&mut self, s: &str) -> Result<()> {
-        let bytes = s.as_bytes();
+        self.write_bytes(s.as_bytes())
+    }
+
+    /// Write length-prefixed bytes
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
         self.write_u32(bytes.len() as u32)?;
         self.buffer.extend_from_slice(bytes);
         self.offset += bytes.len();
@@ -476,6 +621,22 @@ fn test_handoff_builder() {
         assert_eq!(info.memory_map.entries.len(), 1);
     }
 
+    #[test]
+    fn test_handoff_reserves_modules() {
+        let initrd = ModuleInfo::new(String::from("initrd"), PhysicalAddress(0x40_0000), 0x1800);
+        let info = HandoffBuilder::new()
+            .add_memory_region(PhysicalAddress(0x10_0000), 0x100_0000, MemoryType::Usable)
+            .add_module(initrd)
+            .build();
+
+        let entries = &info.memory_map.entries;
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].physical_start, PhysicalAddress(0x40_0000));
+        assert_eq!(entries[1].page_count, 2);
+        assert_eq!(entries[1].memory_type, MemoryType::KernelAndModules);
+        assert_eq!(info.memory_map.total_usable(), 0x100_0000 - 0x2000);
+    }
+
     #[test]
     fn test_serializer() {
         let info = HandoffBuilder::new()
@@ -491,4 +652,93 @@ fn test_serializer() {
         // Check version
         assert_eq!(u32::from_le_bytes([data[4], data[5], data[6], data[7]]), BOOT_INFO_VERSION);
     }
+
+    #[test]
+    fn test_serialize_earlycon() {
+        let info = HandoffBuilder::new()
+            .command_line("test")
+            .earlycon("pl011,0x9000000")
+            .build();
+
+        let mut serializer = HandoffSerializer::new();
+        let data = serializer.serialize(&info).unwrap();
+
+        // Flag set, string last before the (empty) module list
+        assert_eq!(data[12] & (1 << 4), 1 << 4);
+        let tail = &data[data.len() - 8 - 20..data.len() - 8];
+        assert_eq!(u32::from_le_bytes([tail[0], tail[1], tail[2], tail[3]]), 15);
+        assert_eq!(&tail[4..19], b"pl011,0x9000000");
+    }
+
+    #[test]
+    fn test_serialize_console_handover() {
+        let handover = ConsoleHandover {
+            mode: 2,
+            cursor_column: 0,
+            cursor_row: 7,
+            cell: (8, 16),
+            splash: SplashHandover { visible: true, progress: 30, ..SplashHandover::default() },
+            serial: Some(SerialHandover::default()),
+        };
+        let info = HandoffBuilder::new()
+            .command_line("test")
+            .console_handover(handover)
+            .build();
+
+        let mut serializer = HandoffSerializer::new();
+        let data = serializer.serialize(&info).unwrap();
+
+        // 60 byte record last before the (empty) module list
+        assert_eq!(data[12] & (1 << 5), 1 << 5);
+        let record = &data[data.len() - 8 - 60..data.len() - 8];
+        assert_eq!(u32::from_le_bytes([record[0], record[1], record[2], record[3]]), 2);
+        assert_eq!(u32::from_le_bytes([record[8], record[9], record[10], record[11]]), 7);
+        assert_eq!(&record[12..16], &[8, 16, 1, 30]);
+        assert_eq!(u32::from_le_bytes([record[52], record[53], record[54], record[55]]), 115200);
+        assert_eq!(&record[56..59], &[8, 0, 1]);
+    }
+
+    #[test]
+    fn test_serialize_kaslr() {
+        let mut info = HandoffBuilder::new()
+            .command_line("test")
+            .build();
+        info.set_kaslr_info(0x4000_0000, 0xFFFF_FFFF_8000_0000, 3, 12);
+
+        let mut serializer = HandoffSerializer::new();
+        let data = serializer.serialize(&info).unwrap();
+
+        // 28 byte record last before the (empty) module list
+        assert_eq!(data[12] & (1 << 6), 1 << 6);
+        let record = &data[data.len() - 8 - 28..data.len() - 8];
+        assert_eq!(u64::from_le_bytes(record[0..8].try_into().unwrap()), 0x4000_0000);
+        assert_eq!(u64::from_le_bytes(record[8..16].try_into().unwrap()), 0xFFFF_FFFF_8000_0000);
+        assert_eq!(u64::from_le_bytes(record[16..24].try_into().unwrap()), 12);
+        assert_eq!(record[24], 3);
+    }
+
+    #[test]
+    fn test_serialize_measurements() {
+        let info = HandoffBuilder::new()
+            .command_line("test")
+            .measurements(MeasurementLog {
+                event_log: alloc::vec![0xAA; 6],
+                event_count: 2,
+                hash_algorithms: 0x2,
+                tpm_extended: true,
+            })
+            .build();
+
+        let mut serializer = HandoffSerializer::new();
+        let data = serializer.serialize(&info).unwrap();
+
+        // 12 byte record and the log padded to 4, before the module list
+        assert_eq!(data[12] & (1 << 7), 1 << 7);
+        let record = &data[data.len() - 8 - 24..data.len() - 8];
+        assert_eq!(u32::from_le_bytes(record[0..4].try_into().unwrap()), 2);
+        assert_eq!(u32::from_le_bytes(record[4..8].try_into().unwrap()), 0x2);
+        assert_eq!(record[8], 1);
+        assert_eq!(u32::from_le_bytes(record[12..16].try_into().unwrap()), 6);
+        assert_eq!(&record[16..24], &[0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0, 0]);
+    }
 }
@@ -41,6 +41,8 @@ pub enum ModuleType {
     DebugInfo = 10,
     /// Splash screen image
     SplashScreen = 11,
+    /// Boot time history report (served as `/proc/boot_history`)
+    BootHistory = 12,
     /// Custom module type
     Custom = 0xFFFF,
 }
@@ -116,6 +118,7 @@ pub fn name(&self) -> &'static str {
             ModuleType::SymbolTable => "Symbol Table",
             ModuleType::DebugInfo => "Debug Info",
             ModuleType::SplashScreen => "Splash Screen",
+            ModuleType::BootHistory => "Boot History",
             ModuleType::Custom => "Custom",
         }
     }
@@ -452,6 +455,50 @@ pub fn sort_by_load_order(&mut self) {
         self.modules.sort_by_key(|m| m.load_order);
     }
 
+    /// Serialize for the kernel
+    ///
+    /// Layout: one [`ModuleInfoRaw`] per module in load order, followed by
+    /// the string table holding names and command lines. String offsets
+    /// are relative to the start of the returned buffer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let table_start = self.modules.len() * ModuleInfoRaw::SIZE;
+        let mut raw = Vec::with_capacity(self.modules.len());
+        let mut strings = Vec::new();
+
+        for module in &self.modules {
+            let name_offset = table_start + strings.len();
+            strings.extend_from_slice(module.name.as_bytes());
+            let cmdline_offset = table_start + strings.len();
+            strings.extend_from_slice(module.cmdline.as_bytes());
+
+            raw.push(ModuleInfoRaw {
+                physical_address: module.physical_address.0,
+                virtual_address: module.virtual_address.0,
+                size: module.size,
+                module_type: module.module_type as u32,
+                flags: module.flags.0,
+                name_offset: name_offset as u32,
+                name_length: module.name.len() as u32,
+                cmdline_offset: cmdline_offset as u32,
+                cmdline_length: module.cmdline.len() as u32,
+                load_order: module.load_order,
+                reserved: 0,
+                hash: module.hash,
+            });
+        }
+
+        let mut bytes = Vec::with_capacity(table_start + strings.len());
+        for entry in &raw {
+            // repr(C) without padding
+            let entry = unsafe {
+                core::slice::from_raw_parts(entry as *const ModuleInfoRaw as *const u8, ModuleInfoRaw::SIZE)
+            };
+            bytes.extend_from_slice(entry);
+        }
+        bytes.extend_from_slice(&strings);
+        bytes
+    }
+
     /// Sort by type (initrd first, then kernel modules, etc.)
     pub fn sort_by_priority(&mut self) {
         self.modules.sort_by_key(|m| {
@@ -914,6 +961,20 @@ fn test_module_list() {
         assert_eq!(found.unwrap().physical_address, 0x1000);
     }
 
+    #[test]
+    fn test_module_list_bytes() {
+        let mut list = ModuleList::new();
+        list.add(ModuleBuilder::new("initrd").physical_address(PhysicalAddress(0x2000)).size(0x100).build());
+        list.add(ModuleBuilder::new("net.ko").size(0x10).cmdline("debug=1").build());
+
+        let bytes = list.to_bytes();
+        assert_eq!(bytes.len(), 2 * ModuleInfoRaw::SIZE + "initrdnet.kodebug=1".len());
+        let second = unsafe { (bytes.as_ptr() as *const ModuleInfoRaw).add(1).read_unaligned() };
+        assert_eq!(second.load_order, 1);
+        let cmdline = second.cmdline_offset as usize..(second.cmdline_offset + second.cmdline_length) as usize;
+        assert_eq!(&bytes[cmdline], b"debug=1");
+    }
+
     #[test]
     fn test_module_builder() {
         let module = ModuleBuilder::new("initrd.img")
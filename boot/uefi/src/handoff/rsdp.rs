@@ -616,6 +616,8 @@ pub struct CpuInfo {
     pub is_bsp: bool,
     /// Is enabled
     pub enabled: bool,
+    /// Can be brought online later (hotplug)
+    pub online_capable: bool,
     /// Is x2APIC
     pub x2apic: bool,
 }
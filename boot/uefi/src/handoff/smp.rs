@@ -0,0 +1,168 @@
+//! SMP Startup Information
+//!
+//! CPU topology from the ACPI MADT and the low-memory trampoline page the
+//! kernel starts application processors (APs) from, so it can bring up
+//! secondary CPUs without parsing ACPI again.
+
+use crate::raw::types::*;
+use crate::error::{Error, Result};
+use crate::handoff::CpuInfo;
+use crate::tables::acpi::MadtInfo;
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+// =============================================================================
+// TRAMPOLINE
+// =============================================================================
+
+/// The trampoline must end below this address: APs start in real mode at
+/// the page named by the 8-bit SIPI vector
+pub const TRAMPOLINE_LIMIT: u64 = 0x10_0000;
+
+/// Size of the AP trampoline
+pub const TRAMPOLINE_SIZE: u64 = 0x1000;
+
+/// Lowest trampoline address; the first page holds the real-mode IVT
+pub const TRAMPOLINE_MIN: u64 = 0x1000;
+
+// =============================================================================
+// SMP INFO
+// =============================================================================
+
+/// CPU topology and AP startup information
+#[derive(Debug, Clone, Default)]
+pub struct SmpInfo {
+    /// Usable CPUs in MADT order, the BSP included
+    pub cpus: Vec<CpuInfo>,
+    /// APIC ID of the bootstrap processor
+    pub bsp_apic_id: u32,
+    /// Local APIC base address
+    pub local_apic_address: PhysicalAddress,
+    /// Page reserved for the AP startup code
+    pub trampoline: Option<PhysicalAddress>,
+}
+
+impl SmpInfo {
+    /// Collect the CPUs the MADT lists as usable
+    ///
+    /// CPUs that are neither enabled nor online capable are left out, as
+    /// are x2APIC entries repeating an APIC ID already seen.
+    pub fn from_madt(madt: &MadtInfo, bsp_apic_id: u32) -> Self {
+        let xapics = madt.local_apics.iter()
+            .filter(|a| a.apic_id != 0xFF)
+            .map(|a| (a.processor_uid, a.apic_id, a.flags, false));
+        let x2apics = madt.local_x2apics.iter()
+            .map(|a| (a.processor_uid, a.x2apic_id, a.flags, true));
+
+        let mut cpus: Vec<CpuInfo> = Vec::new();
+        for (acpi_uid, apic_id, flags, x2apic) in xapics.chain(x2apics) {
+            let enabled = flags & 1 != 0;
+            let online_capable = flags & 2 != 0;
+            if !(enabled || online_capable) || cpus.iter().any(|c| c.apic_id == apic_id) {
+                continue;
+            }
+            cpus.push(CpuInfo {
+                apic_id,
+                acpi_uid,
+                is_bsp: apic_id == bsp_apic_id,
+                enabled,
+                online_capable,
+                x2apic,
+            });
+        }
+
+        Self {
+            cpus,
+            bsp_apic_id,
+            local_apic_address: PhysicalAddress(madt.local_apic_address),
+            trampoline: None,
+        }
+    }
+
+    /// Number of CPUs enabled at boot, at least the BSP
+    pub fn enabled_count(&self) -> usize {
+        self.cpus.iter().filter(|c| c.enabled).count().max(1)
+    }
+
+    /// Enabled CPUs other than the BSP
+    pub fn application_processors(&self) -> impl Iterator<Item = &CpuInfo> {
+        self.cpus.iter().filter(|c| c.enabled && !c.is_bsp)
+    }
+
+    /// Record the trampoline page, which must be page aligned and lie below 1 MiB
+    pub fn set_trampoline(&mut self, address: PhysicalAddress) -> Result<()> {
+        if address.0 % TRAMPOLINE_SIZE != 0
+            || address.0 < TRAMPOLINE_MIN
+            || address.0 + TRAMPOLINE_SIZE > TRAMPOLINE_LIMIT
+        {
+            return Err(Error::InvalidParameter);
+        }
+        self.trampoline = Some(address);
+        Ok(())
+    }
+
+    /// SIPI vector starting APs at the trampoline
+    pub fn sipi_vector(&self) -> Option<u8> {
+        self.trampoline.map(|t| (t.0 >> 12) as u8)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tables::acpi::{LocalApic, LocalX2Apic};
+    use alloc::vec;
+
+    fn madt() -> MadtInfo {
+        let lapic = |uid, apic_id, flags| LocalApic { processor_uid: uid, apic_id, flags };
+        MadtInfo {
+            local_apic_address: 0xFEE0_0000,
+            flags: 1,
+            local_apics: vec![
+                lapic(0, 0, 1),
+                lapic(1, 2, 1),
+                lapic(2, 4, 2),
+                lapic(3, 6, 0),
+                lapic(4, 0xFF, 1),
+            ],
+            io_apics: Vec::new(),
+            overrides: Vec::new(),
+            nmis: Vec::new(),
+            local_apic_nmis: Vec::new(),
+            local_x2apics: vec![
+                LocalX2Apic { processor_uid: 1, x2apic_id: 2, flags: 1 },
+                LocalX2Apic { processor_uid: 5, x2apic_id: 300, flags: 1 },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_from_madt() {
+        let smp = SmpInfo::from_madt(&madt(), 2);
+        let ids: Vec<u32> = smp.cpus.iter().map(|c| c.apic_id).collect();
+        assert_eq!(ids, [0, 2, 4, 300]);
+        assert_eq!(smp.enabled_count(), 3);
+        assert!(smp.cpus[1].is_bsp);
+        assert!(smp.cpus[2].online_capable && !smp.cpus[2].enabled);
+        assert!(smp.cpus[3].x2apic);
+
+        let aps: Vec<u32> = smp.application_processors().map(|c| c.apic_id).collect();
+        assert_eq!(aps, [0, 300]);
+    }
+
+    #[test]
+    fn test_trampoline() {
+        let mut smp = SmpInfo::from_madt(&madt(), 0);
+        assert_eq!(smp.sipi_vector(), None);
+        assert_eq!(smp.set_trampoline(PhysicalAddress(0x8800)), Err(Error::InvalidParameter));
+        assert_eq!(smp.set_trampoline(PhysicalAddress(0)), Err(Error::InvalidParameter));
+        assert_eq!(smp.set_trampoline(PhysicalAddress(0x10_0000)), Err(Error::InvalidParameter));
+        smp.set_trampoline(PhysicalAddress(0x8000)).unwrap();
+        assert_eq!(smp.sipi_vector(), Some(0x08));
+    }
+}
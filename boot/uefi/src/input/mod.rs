@@ -23,9 +23,10 @@
 //!
 //! - Keyboard input with scancode translation
 //! - Mouse/pointer support
-//! - Touch screen support
+//! - Touch screen support (EFI Absolute Pointer)
 //! - Key combination handling
 //! - Hotkey support
+//! - Configurable key repeat
 //! - International keyboard layouts
 
 #![no_std]
@@ -1002,6 +1003,122 @@ fn default() -> Self {
     }
 }
 
+// =============================================================================
+// ABSOLUTE POINTER
+// =============================================================================
+
+/// Absolute pointer axis ranges (from the protocol mode)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AbsoluteAxes {
+    /// Minimum X
+    pub min_x: u64,
+    /// Maximum X
+    pub max_x: u64,
+    /// Minimum Y
+    pub min_y: u64,
+    /// Maximum Y
+    pub max_y: u64,
+    /// Maximum Z (pressure); 0 if not reported
+    pub max_z: u64,
+}
+
+/// One absolute pointer reading
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AbsoluteSample {
+    /// X position
+    pub x: u64,
+    /// Y position
+    pub y: u64,
+    /// Z position or pressure
+    pub z: u64,
+    /// Surface is being touched
+    pub touching: bool,
+}
+
+/// Turns absolute pointer readings into touch points
+///
+/// Coordinates are scaled to a target surface: pixels for the framebuffer,
+/// character cells for the text console.
+#[derive(Debug, Clone, Copy)]
+pub struct AbsolutePointer {
+    /// Device axis ranges
+    axes: AbsoluteAxes,
+    /// Target width
+    width: i32,
+    /// Target height
+    height: i32,
+    /// Touch in progress
+    down: bool,
+    /// Last reported position
+    last: (i32, i32),
+}
+
+impl AbsolutePointer {
+    /// Create pointer scaling `axes` to a `width` x `height` surface
+    pub const fn new(axes: AbsoluteAxes, width: i32, height: i32) -> Self {
+        Self {
+            axes,
+            width,
+            height,
+            down: false,
+            last: (0, 0),
+        }
+    }
+
+    /// Change the target surface (e.g. after a mode switch)
+    pub fn set_target(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Scale device coordinates to the target surface
+    pub fn scale(&self, x: u64, y: u64) -> (i32, i32) {
+        fn axis(value: u64, min: u64, max: u64, size: i32) -> i32 {
+            if max <= min || size <= 0 {
+                return 0;
+            }
+            let offset = value.clamp(min, max) - min;
+            let scaled = (offset as u128 * size as u128 / (max - min + 1) as u128) as i32;
+            scaled.min(size - 1)
+        }
+
+        (
+            axis(x, self.axes.min_x, self.axes.max_x, self.width),
+            axis(y, self.axes.min_y, self.axes.max_y, self.height),
+        )
+    }
+
+    /// Feed a reading; returns the touch point if something changed
+    pub fn update(&mut self, sample: AbsoluteSample) -> Option<TouchPoint> {
+        let state = match (sample.touching, self.down) {
+            (true, false) => TouchState::Down,
+            (true, true) => TouchState::Move,
+            (false, true) => TouchState::Up,
+            (false, false) => return None,
+        };
+
+        // Many digitizers report zero coordinates on release
+        let (x, y) = if sample.touching { self.scale(sample.x, sample.y) } else { self.last };
+        if state == TouchState::Move && (x, y) == self.last {
+            return None;
+        }
+
+        self.down = sample.touching;
+        self.last = (x, y);
+
+        let mut point = TouchPoint::new(0, x, y, state);
+        if self.axes.max_z > 0 {
+            point.pressure = (sample.z.min(self.axes.max_z) * 255 / self.axes.max_z) as u8;
+        }
+        Some(point)
+    }
+
+    /// Check if a touch is in progress
+    pub const fn is_down(&self) -> bool {
+        self.down
+    }
+}
+
 // =============================================================================
 // KEYBOARD LAYOUTS
 // =============================================================================
@@ -1251,6 +1368,84 @@ fn default() -> Self {
     }
 }
 
+// =============================================================================
+// KEY REPEAT
+// =============================================================================
+
+/// Longest gap between strokes of a key that is still being held (ms)
+///
+/// Firmware only reports strokes, not releases; it auto-repeats a held key
+/// well inside this gap, while separate taps are rarely this close.
+pub const KEY_RELEASE_GAP_MS: u64 = 150;
+
+/// Key repeat filter with its own delay and interval
+///
+/// Replaces the firmware's typematic timing: the first stroke of a key
+/// passes as a press, further strokes of the held key pass as repeats
+/// once `delay_ms` has elapsed, then at most every `rate_ms`.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyRepeat<K> {
+    /// Hold time before repeating (ms)
+    delay_ms: u64,
+    /// Interval between repeats (ms)
+    rate_ms: u64,
+    /// Key being held
+    held: Option<K>,
+    /// When the held key was pressed
+    pressed_at: u64,
+    /// Last stroke of the held key
+    last_stroke: u64,
+    /// Last stroke let through
+    last_pass: u64,
+}
+
+impl<K: Copy + PartialEq> KeyRepeat<K> {
+    /// Create filter
+    pub const fn new(delay_ms: u16, rate_ms: u16) -> Self {
+        Self {
+            delay_ms: delay_ms as u64,
+            rate_ms: rate_ms as u64,
+            held: None,
+            pressed_at: 0,
+            last_stroke: 0,
+            last_pass: 0,
+        }
+    }
+
+    /// Change delay and interval
+    pub fn set_timing(&mut self, delay_ms: u16, rate_ms: u16) {
+        self.delay_ms = delay_ms as u64;
+        self.rate_ms = rate_ms as u64;
+    }
+
+    /// Filter a stroke at `now_ms`; `None` drops it
+    pub fn filter(&mut self, key: K, now_ms: u64) -> Option<KeyEventType> {
+        let held = self.held == Some(key)
+            && now_ms.saturating_sub(self.last_stroke) <= KEY_RELEASE_GAP_MS;
+        self.last_stroke = now_ms;
+
+        if !held {
+            self.held = Some(key);
+            self.pressed_at = now_ms;
+            self.last_pass = now_ms;
+            return Some(KeyEventType::Press);
+        }
+
+        let held_for = now_ms - self.pressed_at;
+        if held_for >= self.delay_ms && now_ms - self.last_pass >= self.rate_ms {
+            self.last_pass = now_ms;
+            Some(KeyEventType::Repeat)
+        } else {
+            None
+        }
+    }
+
+    /// Forget the held key
+    pub fn reset(&mut self) {
+        self.held = None;
+    }
+}
+
 // =============================================================================
 // ERROR TYPES
 // =============================================================================
@@ -1333,6 +1528,26 @@ fn test_touch_event() {
         assert!(event.primary().is_some());
     }
 
+    #[test]
+    fn test_absolute_pointer() {
+        let axes = AbsoluteAxes { min_x: 0, max_x: 4095, min_y: 0, max_y: 4095, max_z: 0 };
+        let mut pointer = AbsolutePointer::new(axes, 80, 25);
+        assert_eq!(pointer.scale(0, 0), (0, 0));
+        assert_eq!(pointer.scale(4095, 4095), (79, 24));
+        assert_eq!(pointer.scale(2048, 9999), (40, 24));
+
+        let touch = |x, y| AbsoluteSample { x, y, z: 0, touching: true };
+        let down = pointer.update(touch(1024, 1024)).unwrap();
+        assert_eq!((down.x, down.y, down.state), (20, 6, TouchState::Down));
+        assert!(pointer.update(touch(1030, 1030)).is_none()); // Same cell
+        assert_eq!(pointer.update(touch(2048, 1024)).unwrap().state, TouchState::Move);
+
+        let up = pointer.update(AbsoluteSample::default()).unwrap();
+        assert_eq!((up.x, up.y, up.state), (40, 6, TouchState::Up));
+        assert!(pointer.update(AbsoluteSample::default()).is_none());
+        assert!(!pointer.is_down());
+    }
+
     #[test]
     fn test_key_buffer() {
         let mut buffer = KeyBuffer::<16>::new();
@@ -1346,6 +1561,29 @@ fn test_key_buffer() {
         assert!(buffer.is_empty());
     }
 
+    #[test]
+    fn test_key_repeat() {
+        let mut repeat = KeyRepeat::new(500, 200);
+
+        // Firmware repeating every 50 ms while the key is held
+        let passed = (0..=20).filter_map(|i| repeat.filter('a', i * 50).map(|t| (i * 50, t)));
+        assert!(passed.eq([
+            (0, KeyEventType::Press),
+            (500, KeyEventType::Repeat),
+            (700, KeyEventType::Repeat),
+            (900, KeyEventType::Repeat),
+        ]));
+
+        // Released, then tapped again
+        assert_eq!(repeat.filter('a', 1500), Some(KeyEventType::Press));
+        // Another key
+        assert_eq!(repeat.filter('b', 1550), Some(KeyEventType::Press));
+        assert_eq!(repeat.filter('b', 1600), None);
+
+        repeat.reset();
+        assert_eq!(repeat.filter('b', 1650), Some(KeyEventType::Press));
+    }
+
     #[test]
     fn test_keyboard_layout() {
         let layout = KeyboardLayout::French;
@@ -18,6 +18,7 @@
 //! │  ┌─────────────────────────────────────────────────────────────────┐   │
 //! │  │                   Widgets                                        │   │
 //! │  │  Text │ Button │ List │ Input │ Progress │ Image │ Container    │   │
+//! │  │  On-screen keyboard │ Touch hit testing                         │   │
 //! │  └─────────────────────────────────────────────────────────────────┘   │
 //! │                                                                         │
 //! │  ┌─────────────────────────────────────────────────────────────────┐   │
@@ -32,6 +33,8 @@
 
 use core::fmt;
 
+use super::input::{scancode, KeyEvent, TouchPoint, TouchState};
+
 // =============================================================================
 // UNITS AND DIMENSIONS
 // =============================================================================
@@ -425,6 +428,8 @@ pub enum WidgetType {
     Radio,
     /// Scroll view
     ScrollView,
+    /// On-screen keyboard
+    Keyboard,
     /// Custom
     Custom,
 }
@@ -452,6 +457,7 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             WidgetType::Checkbox => write!(f, "Checkbox"),
             WidgetType::Radio => write!(f, "Radio"),
             WidgetType::ScrollView => write!(f, "ScrollView"),
+            WidgetType::Keyboard => write!(f, "Keyboard"),
             WidgetType::Custom => write!(f, "Custom"),
         }
     }
@@ -989,6 +995,329 @@ pub fn set_text(&mut self, text: &str) {
     }
 }
 
+// =============================================================================
+// HIT TESTING
+// =============================================================================
+
+/// Topmost visible, enabled, clickable widget at a point
+///
+/// Higher `z_index` wins; on a tie, the widget later in the slice.
+pub fn hit_test(widgets: &[Widget], x: i32, y: i32) -> Option<WidgetId> {
+    widgets
+        .iter()
+        .filter(|w| {
+            w.is_visible()
+                && w.is_enabled()
+                && w.flags.contains(WidgetFlags::CLICKABLE)
+                && w.bounds.contains(x, y)
+        })
+        .max_by_key(|w| w.layout.z_index)
+        .map(|w| w.id)
+}
+
+/// Tracks the widget under a touch from press to release
+///
+/// A widget is activated when the touch is released over the widget it
+/// started on; sliding off and releasing elsewhere cancels.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PointerCapture {
+    /// Widget the touch started on
+    pressed: Option<WidgetId>,
+}
+
+impl PointerCapture {
+    /// Create new capture
+    pub const fn new() -> Self {
+        Self { pressed: None }
+    }
+
+    /// Widget currently held down
+    pub const fn pressed(&self) -> Option<WidgetId> {
+        self.pressed
+    }
+
+    /// Handle a touch point; returns the activated widget
+    pub fn handle(&mut self, widgets: &mut [Widget], point: &TouchPoint) -> Option<WidgetId> {
+        let hit = hit_test(widgets, point.x, point.y);
+
+        match point.state {
+            TouchState::Down => {
+                self.pressed = hit;
+                Self::mark(widgets, self.pressed, true);
+                None
+            }
+            TouchState::Move => {
+                Self::mark(widgets, self.pressed, hit == self.pressed);
+                None
+            }
+            TouchState::Up | TouchState::Cancel => {
+                let pressed = self.pressed.take();
+                Self::mark(widgets, pressed, false);
+                if point.state == TouchState::Up && hit == pressed {
+                    pressed
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Show or clear the pressed state
+    fn mark(widgets: &mut [Widget], id: Option<WidgetId>, pressed: bool) {
+        let Some(widget) = widgets.iter_mut().find(|w| Some(w.id) == id) else {
+            return;
+        };
+        if pressed == widget.flags.contains(WidgetFlags::PRESSED) {
+            return;
+        }
+
+        if pressed {
+            widget.flags.set(WidgetFlags::PRESSED);
+            widget.state = WidgetState::Pressed;
+        } else {
+            widget.flags.clear(WidgetFlags::PRESSED);
+            widget.state = WidgetState::Normal;
+        }
+        widget.flags.set(WidgetFlags::DIRTY);
+    }
+}
+
+// =============================================================================
+// ON-SCREEN KEYBOARD
+// =============================================================================
+
+/// On-screen keyboard layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardLayer {
+    /// Lowercase letters
+    Lower,
+    /// Uppercase letters (one character)
+    Upper,
+    /// Digits and punctuation
+    Symbols,
+}
+
+/// On-screen keyboard key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OskKey {
+    /// Character key
+    Char(char),
+    /// Shift (uppercase for one character)
+    Shift,
+    /// Switch between letters and symbols
+    Layer,
+    /// Delete the previous character
+    Backspace,
+    /// Space
+    Space,
+    /// Cursor left
+    Left,
+    /// Cursor right
+    Right,
+    /// Accept
+    Enter,
+    /// Cancel
+    Escape,
+}
+
+impl OskKey {
+    /// Label for non-character keys
+    pub const fn label(&self, layer: KeyboardLayer) -> &'static str {
+        match self {
+            OskKey::Char(_) => "",
+            OskKey::Shift => "Shift",
+            OskKey::Layer => match layer {
+                KeyboardLayer::Symbols => "abc",
+                _ => "?123",
+            },
+            OskKey::Backspace => "Bksp",
+            OskKey::Space => "Space",
+            OskKey::Left => "<",
+            OskKey::Right => ">",
+            OskKey::Enter => "Enter",
+            OskKey::Escape => "Esc",
+        }
+    }
+}
+
+/// Character rows per layer (the last row sits between Shift and Backspace)
+const OSK_LOWER: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+const OSK_UPPER: [&str; 4] = ["1234567890", "QWERTYUIOP", "ASDFGHJKL", "ZXCVBNM"];
+const OSK_SYMBOLS: [&str; 4] = ["1234567890", "-_=+/\\:;.,", "\"'()[]<>|~", "!@#$%&*"];
+
+/// Bottom row with widths in half keys
+const OSK_BOTTOM: [(OskKey, i32); 6] = [
+    (OskKey::Escape, 3),
+    (OskKey::Layer, 3),
+    (OskKey::Left, 2),
+    (OskKey::Space, 6),
+    (OskKey::Right, 2),
+    (OskKey::Enter, 4),
+];
+
+/// Keyboard rows
+const OSK_ROWS: i32 = 5;
+
+/// Keyboard width in half keys
+const OSK_HALF_KEYS: i32 = 20;
+
+/// On-screen keyboard for touch-only devices
+///
+/// Laid out inside `bounds` (pixels or character cells); key presses come
+/// out as [`KeyEvent`]s, so a text field handles them like typed keys.
+#[derive(Debug, Clone, Copy)]
+pub struct OnScreenKeyboard {
+    /// Keyboard area
+    pub bounds: Rect,
+    /// Active layer
+    layer: KeyboardLayer,
+    /// Key under the current touch
+    pressed: Option<OskKey>,
+}
+
+impl OnScreenKeyboard {
+    /// Create keyboard in `bounds`
+    pub const fn new(bounds: Rect) -> Self {
+        Self {
+            bounds,
+            layer: KeyboardLayer::Lower,
+            pressed: None,
+        }
+    }
+
+    /// Active layer
+    pub const fn layer(&self) -> KeyboardLayer {
+        self.layer
+    }
+
+    /// Key under the current touch (draw it highlighted)
+    pub const fn pressed(&self) -> Option<OskKey> {
+        self.pressed
+    }
+
+    /// Visit every key with its bounds
+    pub fn for_each_key(&self, mut f: impl FnMut(OskKey, Rect)) {
+        for row in 0..OSK_ROWS {
+            self.row_keys(row, |key, rect| {
+                f(key, rect);
+                false
+            });
+        }
+    }
+
+    /// Key at a point
+    pub fn key_at(&self, x: i32, y: i32) -> Option<OskKey> {
+        if !self.bounds.contains(x, y) {
+            return None;
+        }
+        let row = ((y - self.bounds.y) * OSK_ROWS / self.bounds.height).min(OSK_ROWS - 1);
+
+        let mut found = None;
+        self.row_keys(row, |key, rect| {
+            if rect.contains(x, y) {
+                found = Some(key);
+            }
+            found.is_some()
+        });
+        found
+    }
+
+    /// Handle a touch point; returns the key event for a completed tap
+    ///
+    /// Sliding across keys follows the finger; the key under it on
+    /// release is typed.
+    pub fn touch(&mut self, point: &TouchPoint) -> Option<KeyEvent> {
+        match point.state {
+            TouchState::Down | TouchState::Move => {
+                self.pressed = self.key_at(point.x, point.y);
+                None
+            }
+            TouchState::Up => {
+                let key = self.pressed.take()?;
+                self.press(key)
+            }
+            TouchState::Cancel => {
+                self.pressed = None;
+                None
+            }
+        }
+    }
+
+    /// Press a key; returns its key event (layer keys return none)
+    pub fn press(&mut self, key: OskKey) -> Option<KeyEvent> {
+        let event = match key {
+            OskKey::Char(c) => {
+                if self.layer == KeyboardLayer::Upper {
+                    self.layer = KeyboardLayer::Lower;
+                }
+                KeyEvent::new(0, c as u16)
+            }
+            OskKey::Shift => {
+                self.layer = match self.layer {
+                    KeyboardLayer::Lower => KeyboardLayer::Upper,
+                    _ => KeyboardLayer::Lower,
+                };
+                return None;
+            }
+            OskKey::Layer => {
+                self.layer = match self.layer {
+                    KeyboardLayer::Symbols => KeyboardLayer::Lower,
+                    _ => KeyboardLayer::Symbols,
+                };
+                return None;
+            }
+            OskKey::Backspace => KeyEvent::new(0, 0x08),
+            OskKey::Space => KeyEvent::new(0, 0x20),
+            OskKey::Left => KeyEvent::new(scancode::LEFT, 0),
+            OskKey::Right => KeyEvent::new(scancode::RIGHT, 0),
+            OskKey::Enter => KeyEvent::new(0, 0x0D),
+            OskKey::Escape => KeyEvent::new(scancode::ESCAPE, 0),
+        };
+        Some(event)
+    }
+
+    /// Walk the keys of a row until `f` returns true
+    fn row_keys(&self, row: i32, mut f: impl FnMut(OskKey, Rect) -> bool) {
+        let rows = match self.layer {
+            KeyboardLayer::Lower => &OSK_LOWER,
+            KeyboardLayer::Upper => &OSK_UPPER,
+            KeyboardLayer::Symbols => &OSK_SYMBOLS,
+        };
+
+        let mut keys = [(OskKey::Space, 0i32); OSK_HALF_KEYS as usize / 2 + 2];
+        let mut count = 0;
+        let mut push = |key, width| {
+            keys[count] = (key, width);
+            count += 1;
+        };
+
+        match row {
+            0..=2 => rows[row as usize].chars().for_each(|c| push(OskKey::Char(c), 2)),
+            3 => {
+                push(OskKey::Shift, 3);
+                rows[3].chars().for_each(|c| push(OskKey::Char(c), 2));
+                push(OskKey::Backspace, 3);
+            }
+            _ => OSK_BOTTOM.iter().for_each(|&(key, width)| push(key, width)),
+        }
+
+        // Center rows narrower than the keyboard
+        let used: i32 = keys[..count].iter().map(|&(_, w)| w).sum();
+        let y0 = self.bounds.y + row * self.bounds.height / OSK_ROWS;
+        let y1 = self.bounds.y + (row + 1) * self.bounds.height / OSK_ROWS;
+        let mut half = (OSK_HALF_KEYS - used) / 2;
+
+        for &(key, width) in &keys[..count] {
+            let x0 = self.bounds.x + half * self.bounds.width / OSK_HALF_KEYS;
+            let x1 = self.bounds.x + (half + width) * self.bounds.width / OSK_HALF_KEYS;
+            if f(key, Rect::new(x0, y0, x1 - x0, y1 - y0)) {
+                return;
+            }
+            half += width;
+        }
+    }
+}
+
 // =============================================================================
 // SCREEN LAYOUT
 // =============================================================================
@@ -1150,6 +1479,78 @@ fn test_widget() {
         assert!(widget.is_enabled());
     }
 
+    #[test]
+    fn test_hit_test() {
+        let mut back = Widget::new(1, WidgetType::Container);
+        back.bounds = Rect::new(0, 0, 100, 100);
+        back.flags.set(WidgetFlags::CLICKABLE);
+        let mut button = Widget::new(2, WidgetType::Button);
+        button.bounds = Rect::new(10, 10, 20, 10);
+        button.flags.set(WidgetFlags::CLICKABLE);
+        let mut label = Widget::new(3, WidgetType::Text);
+        label.bounds = Rect::new(10, 10, 20, 10);
+        let mut widgets = [back, button, label];
+
+        assert_eq!(hit_test(&widgets, 15, 15), Some(2));
+        assert_eq!(hit_test(&widgets, 50, 50), Some(1));
+        assert_eq!(hit_test(&widgets, 150, 50), None);
+
+        // Press on the button, slide off and back, release on it
+        let mut capture = PointerCapture::new();
+        let at = |x, y, state| TouchPoint::new(0, x, y, state);
+        assert_eq!(capture.handle(&mut widgets, &at(15, 15, TouchState::Down)), None);
+        assert_eq!(widgets[1].state, WidgetState::Pressed);
+        capture.handle(&mut widgets, &at(50, 50, TouchState::Move));
+        assert_eq!(widgets[1].state, WidgetState::Normal);
+        capture.handle(&mut widgets, &at(16, 16, TouchState::Move));
+        assert_eq!(capture.handle(&mut widgets, &at(16, 16, TouchState::Up)), Some(2));
+        assert_eq!(widgets[1].state, WidgetState::Normal);
+
+        // Released elsewhere: cancelled
+        capture.handle(&mut widgets, &at(15, 15, TouchState::Down));
+        assert_eq!(capture.handle(&mut widgets, &at(50, 50, TouchState::Up)), None);
+    }
+
+    #[test]
+    fn test_on_screen_keyboard() {
+        // 10 keys of 6 cells, 5 rows of 2 cells
+        let mut osk = OnScreenKeyboard::new(Rect::new(0, 0, 60, 10));
+        assert_eq!(osk.key_at(0, 0), Some(OskKey::Char('1')));
+        assert_eq!(osk.key_at(59, 2), Some(OskKey::Char('p')));
+        assert_eq!(osk.key_at(1, 4), None); // Row 2 is centered
+        assert_eq!(osk.key_at(3, 4), Some(OskKey::Char('a')));
+        assert_eq!(osk.key_at(0, 6), Some(OskKey::Shift));
+        assert_eq!(osk.key_at(30, 9), Some(OskKey::Space));
+
+        let mut count = 0;
+        osk.for_each_key(|_, rect| {
+            assert!(rect.width > 0 && rect.x + rect.width <= 60);
+            count += 1;
+        });
+        assert_eq!(count, 10 + 10 + 9 + 9 + 6);
+
+        // Shift applies to one character
+        let tap = |osk: &mut OnScreenKeyboard, x, y| {
+            osk.touch(&TouchPoint::new(0, x, y, TouchState::Down));
+            osk.touch(&TouchPoint::new(0, x, y, TouchState::Up))
+        };
+        assert!(tap(&mut osk, 0, 6).is_none());
+        assert_eq!(osk.layer(), KeyboardLayer::Upper);
+        assert_eq!(tap(&mut osk, 10, 6).unwrap().as_char(), Some('Z'));
+        assert_eq!(tap(&mut osk, 10, 6).unwrap().as_char(), Some('z'));
+
+        // Symbols, slide from '-' to '_' before lifting
+        tap(&mut osk, 12, 8);
+        assert_eq!(osk.layer(), KeyboardLayer::Symbols);
+        osk.touch(&TouchPoint::new(0, 0, 2, TouchState::Down));
+        assert_eq!(osk.pressed(), Some(OskKey::Char('-')));
+        osk.touch(&TouchPoint::new(0, 7, 2, TouchState::Move));
+        assert_eq!(osk.touch(&TouchPoint::new(0, 7, 2, TouchState::Up)).unwrap().as_char(), Some('_'));
+
+        assert!(tap(&mut osk, 58, 9).unwrap().is_enter());
+        assert!(tap(&mut osk, 0, 9).unwrap().is_escape());
+    }
+
     #[test]
     fn test_screen_layout() {
         let layout = ScreenLayout::default();
@@ -103,6 +103,11 @@
 /// PIE kernel relocation and KASLR integration.
 pub mod relocation;
 
+/// Kernel command line
+///
+/// Validation of boot menu edits against registered kernel parameters.
+pub mod cmdline;
+
 /// Kernel loading
 ///
 /// ELF/PE loaders and kernel preparation.
@@ -205,13 +210,23 @@
 /// Compression utilities
 ///
 /// Compression and decompression for boot payloads.
-/// Supports RLE, LZ77, Huffman, GZIP, and more.
+/// Supports RLE, LZ77, Huffman, gzip/zlib and Zstandard.
 pub mod compress;
 
 // =============================================================================
 // DEVICE AND PROTOCOL MODULES
 // =============================================================================
 
+/// Typed MMIO register access
+///
+/// Volatile, bounds-checked register maps generated by `register_block!`.
+pub mod mmio;
+
+/// DMA buffer guards
+///
+/// Ownership tracking, overrun guards and poisoning for device buffers.
+pub mod dma;
+
 /// USB protocol support
 ///
 /// Complete USB device enumeration, HID, mass storage, and hub support.
@@ -455,7 +470,7 @@
 /// Theme and appearance
 ///
 /// Visual theming, color schemes, and UI styling.
-/// Includes color management, fonts, component styles, and theme switching.
+/// Includes color management, fonts, component styles, theme files, and runtime switching.
 pub mod theme;
 
 /// Performance monitoring
@@ -497,7 +512,8 @@
 /// Partition table support
 ///
 /// GPT and MBR partition table parsing.
-/// Includes GUID handling, partition type identification, and ESP detection.
+/// Includes GUID handling, partition type identification, ESP detection
+/// and discovery of Helix system partitions.
 pub mod partition;
 
 // =============================================================================
@@ -614,7 +630,7 @@ pub mod prelude {
     pub use crate::diag::{BootProgress, BootStage};
 
     // Compression
-    pub use crate::compress::{CompressionType, rle_compress, rle_decompress};
+    pub use crate::compress::{CompressionType, decompress, rle_compress, rle_decompress};
 
     // ELF parsing
     pub use crate::elf::{Elf64Header, Elf64ProgramHeader};
@@ -729,11 +745,27 @@ pub fn boot_services(&self) -> Option<&raw::boot_services::EfiBootServices> {
         unsafe { self.system_table.boot_services() }
     }
 
+    /// Get Boot Services
+    ///
+    /// Fails with `NotReady` after `exit_boot_services()` or when the
+    /// firmware provides none.
+    pub fn try_boot_services(&self) -> Result<&raw::boot_services::EfiBootServices> {
+        if BOOT_SERVICES_EXITED.load(Ordering::Acquire) {
+            return Err(Error::NotReady);
+        }
+        unsafe { self.system_table.boot_services() }.ok_or(Error::NotReady)
+    }
+
     /// Get Runtime Services
     pub fn runtime_services(&self) -> Option<&raw::runtime_services::EfiRuntimeServices> {
         unsafe { self.system_table.runtime_services() }
     }
 
+    /// Get Runtime Services, failing with `NotReady` when there are none
+    pub fn try_runtime_services(&self) -> Result<&raw::runtime_services::EfiRuntimeServices> {
+        self.runtime_services().ok_or(Error::NotReady)
+    }
+
     /// Get console for text I/O
     #[cfg(feature = "simple_text")]
     pub fn console(&self) -> Result<protocols::console::Console<'_>> {
@@ -742,16 +774,36 @@ pub fn console(&self) -> Result<protocols::console::Console<'_>> {
 
     /// Get graphics output
     #[cfg(feature = "gop")]
+    ///
+    /// Prefers the GOP driving the console so the kernel inherits the
+    /// screen the user is looking at.
     pub fn graphics(&self) -> Result<protocols::graphics::GraphicsOutput> {
-        // TODO: Implement properly via boot services protocol location
-        Err(Error::Unsupported)
+        let bs = self.try_boot_services()?;
+        unsafe {
+            protocols::graphics::GraphicsOutput::acquire(
+                bs,
+                self.image_handle,
+                self.system_table.console_out_handle,
+            )
+        }
     }
 
-    /// Get file system access
+    /// Get the output for the boot splash
+    ///
+    /// The GOP when there is one, otherwise the text console.
+    pub fn splash_output(&self) -> Result<splash::render::SplashOutput> {
+        #[cfg(feature = "gop")]
+        if let Ok(gop) = self.graphics() {
+            return Ok(splash::render::SplashOutput::Graphics(gop));
+        }
+        Ok(splash::render::SplashOutput::Text(protocols::console::Console::get()?))
+    }
+
+    /// Get the file system this image was loaded from (normally the ESP)
     #[cfg(feature = "filesystem")]
     pub fn filesystem(&self) -> Result<protocols::filesystem::FileSystem> {
-        // TODO: Implement properly via boot services protocol location
-        Err(Error::Unsupported)
+        let bs = self.try_boot_services()?;
+        unsafe { protocols::filesystem::FileSystem::boot_volume(bs, self.image_handle) }
     }
 
     /// Get ACPI tables
@@ -766,12 +818,200 @@ pub fn smbios(&self) -> Result<protocols::smbios::SmbiosTables> {
         Ok(protocols::smbios::SmbiosTables::new(self.image_handle))
     }
 
+    /// Mount the first block device holding an ext4 volume
+    ///
+    /// Firmware exposes every partition as a block device of its own.
+    #[cfg(feature = "filesystem")]
+    pub fn linux_volume(&self) -> Result<filesystem::ext4::Ext4Filesystem<protocols::block::BlockDevice>> {
+        use protocols::EnumerableProtocol;
+
+        protocols::block::BlockDevice::enumerate()?
+            .into_iter()
+            .find_map(|device| filesystem::ext4::Ext4Filesystem::mount(device).ok())
+            .ok_or(Error::NotFound)
+    }
+
+    /// Mount the first ext4 Linux partition on an NVMe drive
+    ///
+    /// Drives the controllers directly instead of through firmware
+    /// `BlockIo`, which resets them.
+    #[cfg(feature = "filesystem")]
+    pub fn nvme_linux_volume(
+        &self,
+    ) -> Result<filesystem::ext4::Ext4Filesystem<block::BlockSlice<nvme::controller::NvmeNamespace>>> {
+        for namespace in nvme::controller::probe() {
+            let Ok(partitions) = block::read_partitions(&namespace) else { continue };
+            for partition in partitions.iter().filter(|partition| partition.is_linux()) {
+                let Ok(slice) = block::BlockSlice::new(namespace.clone(), partition) else { continue };
+                if let Ok(volume) = filesystem::ext4::Ext4Filesystem::mount(slice) {
+                    return Ok(volume);
+                }
+            }
+        }
+        Err(Error::NotFound)
+    }
+
+    /// Read a file from the Linux volume
+    ///
+    /// Falls back to NVMe drives when no firmware block device holds an
+    /// ext4 volume.
+    #[cfg(feature = "filesystem")]
+    fn read_linux_file(&self, path: &str) -> Result<alloc::vec::Vec<u8>> {
+        match self.linux_volume() {
+            Ok(volume) => Ok(volume.read_to_vec(path)?),
+            Err(_) => Ok(self.nvme_linux_volume()?.read_to_vec(path)?),
+        }
+    }
+
     /// Load a kernel from the file system
+    ///
+    /// Paths missing from the ESP are looked up on the Linux volume.
     #[cfg(feature = "filesystem")]
     pub fn load_kernel(&self, path: &str) -> Result<loader::LoadedImage> {
         let mut kernel_loader = loader::KernelLoader::new();
-        let _ = kernel_loader.load_file(path)?;
-        kernel_loader.image().cloned().ok_or(Error::NotFound)
+        if let Ok(image) = kernel_loader.load_file(path) {
+            return Ok(image.clone());
+        }
+        let data = self.read_linux_file(path)?;
+        kernel_loader.load(&data).cloned()
+    }
+
+    /// Get the firmware random number generator
+    pub fn rng(&self) -> Result<protocols::rng::Rng> {
+        let bs = self.try_boot_services()?;
+        unsafe { protocols::rng::Rng::locate(bs) }
+    }
+
+    /// Collect entropy for KASLR and its quality
+    ///
+    /// Mixes the firmware RNG, if present, with the CPU sources.
+    pub fn kaslr_entropy(&self) -> (u64, u8) {
+        let firmware = self.rng().and_then(|rng| rng.u64()).ok();
+        loader::relocation::collect_entropy(firmware)
+    }
+
+    /// Place an ELF kernel in memory, randomizing its base if allowed
+    ///
+    /// KASLR is used when enabled in the security settings and not
+    /// disabled with `nokaslr` on the command line. Returns the kernel and
+    /// the entropy quality to record with [`handoff::HandoffBuilder::kernel`].
+    pub fn place_kernel(
+        &self,
+        data: &[u8],
+        security: &settings::SecuritySettings,
+        cmdline: &str,
+    ) -> Result<(loader::LoadedKernel, u8)> {
+        let bs = self.try_boot_services()?;
+        let mut allocator = unsafe {
+            services::boot::BootServices::from_ptr(bs as *const _ as *mut _)
+        }
+        .ok_or(Error::NotReady)?;
+
+        let mut kernel_loader = loader::KernelLoader::new();
+        let mut quality = 0;
+        if security.kaslr && loader::RelocationConfig::from_cmdline(cmdline).kaslr_enabled {
+            let (seed, entropy_quality) = self.kaslr_entropy();
+            kernel_loader.enable_kaslr(seed);
+            quality = entropy_quality;
+        }
+
+        let kernel = kernel_loader.load_kernel(data, &mut allocator)?;
+        Ok((kernel, quality))
+    }
+
+    /// Get the firmware TPM 2.0 (TCG2) protocol
+    pub fn tcg2(&self) -> Result<protocols::tcg2::Tcg2> {
+        let bs = self.try_boot_services()?;
+        unsafe { protocols::tcg2::Tcg2::locate(bs) }
+    }
+
+    /// Verify a kernel against the firmware Secure Boot policy
+    ///
+    /// Fails with `SecurityViolation` when Secure Boot is enforcing and
+    /// the kernel is not signed by a db entry or the vendor certificate.
+    /// Verify the kernel as read, before it is placed.
+    #[cfg(feature = "security")]
+    pub fn verify_kernel(&self, data: &[u8]) -> Result<security::SecureBootResult> {
+        let rs = self.try_runtime_services()?;
+        let rs = unsafe {
+            services::runtime::RuntimeServices::from_ptr(rs as *const _ as *mut _)
+        }
+        .ok_or(Error::NotReady)?;
+        security::secureboot::verify_kernel(&rs, data).map_err(|_| Error::SecurityViolation)
+    }
+
+    /// Measure the boot components into PCRs 8 and 9
+    ///
+    /// Each component is extended into the TPM's active banks through the
+    /// firmware, which logs it too, and recorded in the returned log for
+    /// [`handoff::HandoffBuilder::measurements`]. Without a TPM the log
+    /// is still built, with `tpm_extended` cleared. Measure the kernel as
+    /// read, before it is placed: the slide must not change its digest.
+    #[cfg(feature = "security")]
+    pub fn measure_boot(
+        &self,
+        components: &security::tpm::BootComponents<'_>,
+    ) -> Result<handoff::MeasurementLog> {
+        let tpm = self.tcg2().ok();
+        let banks = tpm.as_ref().map_or(0, |tpm| tpm.active_banks());
+        let mut measured = security::tpm::MeasuredBoot::from_hash_bitmap(banks, 24);
+
+        measured.measure_boot(components, |pcr, event_type, data, description| match &tpm {
+            Some(tpm) => tpm.measure(pcr, event_type, data, description),
+            None => Ok(()),
+        })?;
+
+        Ok(handoff::MeasurementLog {
+            event_log: measured.get_event_log_bytes(),
+            event_count: measured.event_log().events().len() as u32,
+            hash_algorithms: measured.hash_bitmap(),
+            tpm_extended: tpm.is_some(),
+        })
+    }
+
+    /// Download and load a kernel over HTTP(S)
+    ///
+    /// HTTPS servers must chain to a root CA pinned in the network settings.
+    #[cfg(feature = "security")]
+    pub fn load_kernel_url(
+        &self,
+        url: &str,
+        network: &settings::NetworkSettings,
+    ) -> Result<loader::LoadedImage> {
+        let data = netboot::https::HttpsClient::new(network).get(url)?;
+        loader::KernelLoader::new().load(&data).cloned()
+    }
+
+    /// Download and load a kernel and initrd over PXE
+    ///
+    /// The kernel defaults to the boot file named by DHCP. The images are
+    /// returned as [`load_kernel_url`](Self::load_kernel_url) and
+    /// [`load_initrd`](Self::load_initrd) return them, for the same
+    /// handoff as a disk boot.
+    #[cfg(feature = "network")]
+    pub fn load_kernel_pxe(
+        &self,
+        kernel: Option<&str>,
+        initrd: Option<&str>,
+        network: &settings::NetworkSettings,
+        splash: Option<&mut splash::SplashScreen>,
+    ) -> Result<(loader::LoadedImage, Option<alloc::vec::Vec<u8>>)> {
+        let net = netboot::snp::SnpDatagram::open(self.image_handle())?;
+        let mut pxe = netboot::pxe::PxeBoot::new(net)
+            .with_limits(network.retry_count, u32::from(network.dhcp_timeout) * 1000);
+        if let Some(splash) = splash {
+            pxe = pxe.with_splash(splash);
+        }
+
+        let images = pxe.boot(kernel, initrd)?;
+        let image = loader::KernelLoader::new().load(&images.kernel).cloned()?;
+        Ok((image, images.initrd))
+    }
+
+    /// Read an initrd from the Linux volume
+    #[cfg(feature = "filesystem")]
+    pub fn load_initrd(&self, path: &str) -> Result<alloc::vec::Vec<u8>> {
+        self.read_linux_file(path)
     }
 
     /// Exit boot services and prepare kernel handoff
@@ -0,0 +1,262 @@
+//! Chainloading
+//!
+//! Starts other EFI applications (Windows Boot Manager, GRUB, systemd-boot,
+//! the UEFI shell) from the ESP through LoadImage/StartImage, and returns
+//! to the boot menu when they exit.
+//!
+//! The image is loaded from a buffer together with a full device path
+//! (ESP device + file path node), so the started application sees the
+//! same `LoadedImage` device and path as when the firmware starts it.
+//!
+//! With Secure Boot enforcing, the image is checked against db/dbx before
+//! LoadImage, and a firmware refusal is reported as a denial rather than
+//! a load error.
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::device_path::{DevicePath, DevicePathBuilder};
+use crate::loader::ImageFormat;
+use crate::raw::protocols::loaded_image::{EfiDevicePathProtocol, EfiLoadedImageProtocol};
+use crate::raw::types::*;
+use crate::security::secureboot::{DenialReason, SecureBootMode, SecureBootResult, SecureBootVerifier};
+use crate::services::boot::BootServices;
+
+// =============================================================================
+// PATHS
+// =============================================================================
+
+/// Normalize a file path to ESP form (`/efi/x.efi` → `\efi\x.efi`)
+pub fn esp_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len() + 1);
+    for c in path.chars() {
+        let c = if c == '/' { '\\' } else { c };
+        if c == '\\' && out.ends_with('\\') {
+            continue;
+        }
+        if out.is_empty() && c != '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Device path of a file: the device's path followed by a file path node
+///
+/// `device` is the serialized device path of the volume (e.g. the ESP
+/// partition). Returns `None` if it cannot be parsed or the file path is
+/// empty.
+pub fn file_device_path(device: &[u8], path: &str) -> Option<Vec<u8>> {
+    let path = esp_path(path);
+    if path.len() <= 1 {
+        return None;
+    }
+
+    let mut full = DevicePath::from_bytes(device)?;
+    let file = DevicePathBuilder::new().file_path(&path).build();
+    if full.node_count() + file.node_count() > DevicePath::MAX_NODES {
+        return None;
+    }
+    full.append(&file);
+
+    let mut bytes = vec![0u8; full.length()];
+    let len = full.to_bytes(&mut bytes);
+    bytes.truncate(len);
+    Some(bytes)
+}
+
+/// Load options as a NUL-terminated UCS-2 string
+pub fn load_options(options: &str) -> Vec<u16> {
+    options
+        .chars()
+        .map(|c| if (c as u32) < 0x10000 { c as u16 } else { b'?' as u16 })
+        .chain(core::iter::once(0))
+        .collect()
+}
+
+// =============================================================================
+// ERRORS
+// =============================================================================
+
+/// Chainload failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainloadError {
+    /// Empty path, or the device path could not be built
+    InvalidPath,
+    /// Not a PE32+ EFI application
+    NotEfiImage,
+    /// Rejected by the db/dbx check
+    Denied(DenialReason),
+    /// Refused by the firmware's Secure Boot check
+    SecurityViolation(Status),
+    /// LoadImage failed
+    LoadFailed(Status),
+}
+
+impl fmt::Display for ChainloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPath => write!(f, "invalid path"),
+            Self::NotEfiImage => write!(f, "not an EFI application"),
+            Self::Denied(reason) => write!(f, "Secure Boot denied the image ({:?})", reason),
+            Self::SecurityViolation(status) => write!(f, "firmware refused the image ({})", status),
+            Self::LoadFailed(status) => write!(f, "load failed ({})", status),
+        }
+    }
+}
+
+/// How the chainloaded application returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainloadExit {
+    /// Exit status passed to `Exit()` (or the StartImage error)
+    pub status: Status,
+}
+
+impl ChainloadExit {
+    /// Check if the application exited successfully
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+}
+
+impl fmt::Display for ChainloadExit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exited with {}", self.status)
+    }
+}
+
+// =============================================================================
+// CHAINLOADER
+// =============================================================================
+
+/// Starts EFI applications on behalf of the boot menu
+pub struct Chainloader<'a> {
+    /// Boot services
+    bs: &'a BootServices,
+    /// Helix's own image handle
+    parent: Handle,
+    /// Secure Boot mode
+    secure_boot: SecureBootMode,
+    /// db/dbx checker, when the databases were read
+    verifier: Option<&'a SecureBootVerifier>,
+}
+
+impl<'a> Chainloader<'a> {
+    /// Create chainloader for images started by `parent`
+    pub fn new(bs: &'a BootServices, parent: Handle, secure_boot: SecureBootMode) -> Self {
+        Self { bs, parent, secure_boot, verifier: None }
+    }
+
+    /// Check images against db/dbx before loading them
+    pub fn with_verifier(mut self, verifier: &'a SecureBootVerifier) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Check if Secure Boot blocks unauthorized images
+    pub fn enforcing(&self) -> bool {
+        matches!(self.secure_boot, SecureBootMode::UserMode | SecureBootMode::DeployedMode)
+    }
+
+    /// Check an image before loading it
+    pub fn check(&self, image: &[u8]) -> Result<(), ChainloadError> {
+        if !matches!(ImageFormat::detect(image), Ok(ImageFormat::Pe32Plus)) {
+            return Err(ChainloadError::NotEfiImage);
+        }
+        if !self.enforcing() {
+            return Ok(());
+        }
+        match self.verifier.map(|verifier| verifier.verify_image(image)) {
+            Some(SecureBootResult::Denied { reason }) => Err(ChainloadError::Denied(reason)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Load and start `image`, read from `path` on the volume `device`
+    /// (whose device path is `device_path`), passing `options` as its load
+    /// options; returns when the application exits
+    pub fn start(
+        &self,
+        device: Handle,
+        device_path: &[u8],
+        path: &str,
+        image: &[u8],
+        options: &str,
+    ) -> Result<ChainloadExit, ChainloadError> {
+        self.check(image)?;
+
+        let mut file_path = file_device_path(device_path, path).ok_or(ChainloadError::InvalidPath)?;
+        let handle = self
+            .bs
+            .load_image(false, self.parent, file_path.as_mut_ptr() as *mut EfiDevicePathProtocol, Some(image))
+            .map_err(|status| match status {
+                Status::SECURITY_VIOLATION | Status::ACCESS_DENIED => ChainloadError::SecurityViolation(status),
+                _ => ChainloadError::LoadFailed(status),
+            })?;
+
+        // Must outlive StartImage
+        let options = load_options(options);
+        if let Ok(loaded) = self.bs.handle_protocol::<EfiLoadedImageProtocol>(handle, &EfiLoadedImageProtocol::GUID) {
+            // SAFETY: the firmware returned a valid protocol for the new image
+            let loaded = unsafe { &mut *loaded };
+            if loaded.device_handle.is_null() {
+                loaded.device_handle = device;
+            }
+            if options.len() > 1 {
+                loaded.load_options = options.as_ptr() as *mut core::ffi::c_void;
+                loaded.load_options_size = (options.len() * 2) as u32;
+            }
+        }
+
+        let status = match self.bs.start_image(handle) {
+            Ok(()) => Status::SUCCESS,
+            Err(status) => status,
+        };
+
+        // Applications are unloaded by Exit(); this covers those that fail to start
+        let _ = self.bs.unload_image(handle);
+
+        Ok(ChainloadExit { status })
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_esp_path() {
+        assert_eq!(esp_path("/EFI/Microsoft/Boot/bootmgfw.efi"), "\\EFI\\Microsoft\\Boot\\bootmgfw.efi");
+        assert_eq!(esp_path("EFI//BOOT\\BOOTX64.EFI"), "\\EFI\\BOOT\\BOOTX64.EFI");
+        assert_eq!(esp_path(""), "");
+    }
+
+    #[test]
+    fn test_file_device_path() {
+        let disk = DevicePathBuilder::new().pci(0x1F, 2).sata(0, 0xFFFF, 0).build();
+        let mut device = vec![0u8; disk.length()];
+        disk.to_bytes(&mut device);
+
+        let bytes = file_device_path(&device, "/EFI/x.efi").unwrap();
+        let path = DevicePath::from_bytes(&bytes).unwrap();
+        assert_eq!(path.node_count(), 3);
+        assert_eq!(bytes.len(), disk.length() + 4 + 2 * "\\EFI\\x.efi".len() + 2);
+        assert_eq!(&bytes[bytes.len() - 4..], &[0x7F, 0xFF, 4, 0]);
+
+        assert!(file_device_path(&device, "/").is_none());
+    }
+
+    #[test]
+    fn test_load_options() {
+        assert_eq!(load_options("a b"), [b'a' as u16, b' ' as u16, b'b' as u16, 0]);
+        assert_eq!(load_options(""), [0]);
+    }
+}
@@ -0,0 +1,505 @@
+//! Kernel Segment Loading
+//!
+//! Places an ELF kernel in memory from its program headers: each `PT_LOAD`
+//! segment gets its own page allocation (so it can be mapped with its own
+//! permissions), file contents are copied, BSS is zeroed, and
+//! `R_X86_64_RELATIVE` relocations are applied for the KASLR slide.
+//!
+//! Relocations are taken from the dynamic table (`DT_RELA`) when present,
+//! which also covers kernels stripped of section headers, and otherwise
+//! from the `SHT_RELA` sections.
+
+use crate::raw::types::*;
+use crate::raw::memory::MemoryType;
+use crate::error::{Error, Result};
+use crate::loader::elf::{dt, machine, r_x86_64, Elf64ProgramHeader, Elf64Rela, ElfLoader};
+use crate::services::boot::BootServices;
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+/// Page size used for segment allocations
+const PAGE_SIZE: u64 = 4096;
+
+// =============================================================================
+// SEGMENT ALLOCATOR
+// =============================================================================
+
+/// Page allocation for kernel segments
+///
+/// Allocations must be identity mapped while the loader writes to them.
+pub trait SegmentAllocator {
+    /// Allocate `pages` contiguous pages of `memory_type`
+    fn allocate(&mut self, pages: usize, memory_type: MemoryType) -> Result<PhysicalAddress>;
+
+    /// Free pages returned by [`allocate`](Self::allocate)
+    fn free(&mut self, base: PhysicalAddress, pages: usize);
+}
+
+impl SegmentAllocator for BootServices {
+    fn allocate(&mut self, pages: usize, memory_type: MemoryType) -> Result<PhysicalAddress> {
+        let mut address = PhysicalAddress(0);
+        self.allocate_pages(AllocateType::AllocateAnyPages, memory_type, pages, &mut address)
+            .map_err(Error::from_status)?;
+        Ok(address)
+    }
+
+    fn free(&mut self, base: PhysicalAddress, pages: usize) {
+        let _ = self.free_pages(base, pages);
+    }
+}
+
+// =============================================================================
+// LOADED KERNEL
+// =============================================================================
+
+/// Segment permissions, from the program header flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SegmentPermissions {
+    /// Readable
+    pub read: bool,
+    /// Writable
+    pub write: bool,
+    /// Executable
+    pub execute: bool,
+}
+
+impl SegmentPermissions {
+    /// Permissions of a program header
+    pub fn from_phdr(phdr: &Elf64ProgramHeader) -> Self {
+        Self {
+            read: phdr.is_readable(),
+            write: phdr.is_writable(),
+            execute: phdr.is_executable(),
+        }
+    }
+
+    /// Both writable and executable
+    pub fn is_wx(&self) -> bool {
+        self.write && self.execute
+    }
+}
+
+/// Kernel segment placed in memory
+///
+/// `virt` and `phys` are page aligned; the segment's first byte sits at
+/// the same offset into the first page in both.
+#[derive(Debug, Clone)]
+pub struct KernelSegment {
+    /// Virtual address of the first page (after the slide)
+    pub virt: VirtualAddress,
+    /// Physical address of the first page
+    pub phys: PhysicalAddress,
+    /// Pages allocated
+    pub pages: usize,
+    /// Offset of the segment into its first page
+    pub page_offset: u64,
+    /// Size in memory
+    pub mem_size: u64,
+    /// Size in the file (the rest is BSS)
+    pub file_size: u64,
+    /// Permissions
+    pub permissions: SegmentPermissions,
+}
+
+impl KernelSegment {
+    /// Size of the page range
+    pub fn size(&self) -> u64 {
+        self.pages as u64 * PAGE_SIZE
+    }
+
+    /// Check if the page range contains `len` bytes at `virt`
+    pub fn contains(&self, virt: VirtualAddress, len: u64) -> bool {
+        virt.0 >= self.virt.0 && virt.0.saturating_add(len) <= self.virt.0 + self.size()
+    }
+
+    /// Physical address of a virtual address in the segment
+    pub fn translate(&self, virt: VirtualAddress) -> Option<PhysicalAddress> {
+        self.contains(virt, 1).then(|| PhysicalAddress(self.phys.0 + (virt.0 - self.virt.0)))
+    }
+}
+
+/// Kernel placed in memory by [`KernelLoader::load_kernel`](super::KernelLoader::load_kernel)
+#[derive(Debug, Clone)]
+pub struct LoadedKernel {
+    /// Entry point (after the slide)
+    pub entry_point: VirtualAddress,
+    /// Page-aligned link-time base
+    pub link_base: VirtualAddress,
+    /// Offset added to every link-time virtual address (0 without KASLR)
+    pub slide: u64,
+    /// Segments, in program header order
+    pub segments: Vec<KernelSegment>,
+    /// Relocations applied
+    pub relocations: usize,
+    /// BSS bytes zeroed
+    pub bss_size: u64,
+}
+
+impl LoadedKernel {
+    /// Virtual base the kernel runs at
+    pub fn virt_base(&self) -> VirtualAddress {
+        VirtualAddress(self.link_base.0.wrapping_add(self.slide))
+    }
+
+    /// Size of the virtual range spanned by the segments
+    pub fn image_size(&self) -> u64 {
+        self.segments.iter()
+            .map(|s| s.virt.0 + s.size())
+            .max()
+            .map_or(0, |end| end - self.virt_base().0)
+    }
+
+    /// Segment containing a virtual address
+    pub fn segment_at(&self, virt: VirtualAddress) -> Option<&KernelSegment> {
+        self.segments.iter().find(|s| s.contains(virt, 1))
+    }
+
+    /// Physical address of a virtual address in the kernel
+    pub fn virt_to_phys(&self, virt: VirtualAddress) -> Option<PhysicalAddress> {
+        self.segment_at(virt).and_then(|s| s.translate(virt))
+    }
+
+    /// Free every segment
+    pub fn free(self, allocator: &mut impl SegmentAllocator) {
+        for segment in &self.segments {
+            allocator.free(segment.phys, segment.pages);
+        }
+    }
+}
+
+// =============================================================================
+// LOADING
+// =============================================================================
+
+/// Place the segments of a parsed ELF at `link + slide`
+///
+/// Everything allocated is freed again on failure.
+pub fn load_segments(
+    elf: &ElfLoader,
+    data: &[u8],
+    slide: u64,
+    allocator: &mut impl SegmentAllocator,
+) -> Result<LoadedKernel> {
+    let header = elf.header().ok_or(Error::NotLoaded)?;
+
+    let link_base = elf.loadable_segments().iter()
+        .map(|p| p.p_vaddr & !(PAGE_SIZE - 1))
+        .min()
+        .ok_or(Error::InvalidData)?;
+
+    let mut kernel = LoadedKernel {
+        entry_point: VirtualAddress(header.e_entry.wrapping_add(slide)),
+        link_base: VirtualAddress(link_base),
+        slide,
+        segments: Vec::new(),
+        relocations: 0,
+        bss_size: 0,
+    };
+
+    let result = place_segments(elf, data, &mut kernel, allocator)
+        .and_then(|()| apply_relocations(elf, &mut kernel));
+
+    match result {
+        Ok(()) => Ok(kernel),
+        Err(e) => {
+            kernel.free(allocator);
+            Err(e)
+        }
+    }
+}
+
+/// Allocate, copy and zero each `PT_LOAD` segment
+fn place_segments(
+    elf: &ElfLoader,
+    data: &[u8],
+    kernel: &mut LoadedKernel,
+    allocator: &mut impl SegmentAllocator,
+) -> Result<()> {
+    for phdr in elf.loadable_segments() {
+        if phdr.p_memsz == 0 {
+            continue;
+        }
+        if phdr.p_filesz > phdr.p_memsz {
+            return Err(Error::InvalidData);
+        }
+
+        let file_start = usize::try_from(phdr.p_offset).map_err(|_| Error::InvalidData)?;
+        let file_end = file_start
+            .checked_add(usize::try_from(phdr.p_filesz).map_err(|_| Error::InvalidData)?)
+            .filter(|&end| end <= data.len())
+            .ok_or(Error::InvalidData)?;
+
+        let virt = VirtualAddress((phdr.p_vaddr & !(PAGE_SIZE - 1)).wrapping_add(kernel.slide));
+        let page_offset = phdr.p_vaddr & (PAGE_SIZE - 1);
+        let pages = (page_offset + phdr.p_memsz).div_ceil(PAGE_SIZE);
+
+        // A page shared by two segments can't carry both permissions
+        let overlaps = kernel.segments.iter()
+            .any(|s| virt.0 < s.virt.0 + s.size() && s.virt.0 < virt.0 + pages * PAGE_SIZE);
+        if overlaps {
+            return Err(Error::InvalidData);
+        }
+
+        let permissions = SegmentPermissions::from_phdr(phdr);
+        let memory_type = if permissions.execute {
+            MemoryType::LoaderCode
+        } else {
+            MemoryType::LoaderData
+        };
+        let pages = usize::try_from(pages).map_err(|_| Error::OutOfResources)?;
+        let phys = allocator.allocate(pages, memory_type)?;
+
+        kernel.segments.push(KernelSegment {
+            virt,
+            phys,
+            pages,
+            page_offset,
+            mem_size: phdr.p_memsz,
+            file_size: phdr.p_filesz,
+            permissions,
+        });
+
+        // Zero the whole range (BSS and page slack), then copy the file part
+        unsafe {
+            let base = phys.0 as *mut u8;
+            core::ptr::write_bytes(base, 0, pages * PAGE_SIZE as usize);
+            core::ptr::copy_nonoverlapping(
+                data[file_start..file_end].as_ptr(),
+                base.add(page_offset as usize),
+                file_end - file_start,
+            );
+        }
+        kernel.bss_size += phdr.bss_size();
+    }
+
+    if kernel.segments.is_empty() {
+        return Err(Error::InvalidData);
+    }
+
+    Ok(())
+}
+
+/// Collect the `RELA` entries, from `DT_RELA` or else the section headers
+fn rela_entries(elf: &ElfLoader, kernel: &LoadedKernel) -> Result<Vec<Elf64Rela>> {
+    let tag = |tag: i64| elf.dynamic().iter().find(|d| d.d_tag == tag).map(|d| d.d_val);
+
+    let Some(table) = tag(dt::DT_RELA) else {
+        return Ok(elf.relocations().iter()
+            .map(|r| Elf64Rela {
+                r_offset: r.offset,
+                r_info: (u64::from(r.symbol_index) << 32) | u64::from(r.reloc_type),
+                r_addend: r.addend,
+            })
+            .collect());
+    };
+
+    let size = tag(dt::DT_RELASZ).unwrap_or(0);
+    let entry = tag(dt::DT_RELAENT).unwrap_or(core::mem::size_of::<Elf64Rela>() as u64);
+    if entry < core::mem::size_of::<Elf64Rela>() as u64 {
+        return Err(Error::InvalidData);
+    }
+
+    // The table is part of a loaded segment
+    let table = VirtualAddress(table.wrapping_add(kernel.slide));
+    let segment = kernel.segment_at(table)
+        .filter(|s| s.contains(table, size))
+        .ok_or(Error::InvalidData)?;
+    let phys = segment.translate(table).ok_or(Error::InvalidData)?;
+
+    Ok((0..size / entry)
+        .map(|i| unsafe {
+            core::ptr::read_unaligned((phys.0 + i * entry) as *const Elf64Rela)
+        })
+        .collect())
+}
+
+/// Apply `R_X86_64_RELATIVE` relocations for the slide
+fn apply_relocations(elf: &ElfLoader, kernel: &mut LoadedKernel) -> Result<()> {
+    let entries = rela_entries(elf, kernel)?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let header = elf.header().ok_or(Error::NotLoaded)?;
+    if header.e_machine != machine::EM_X86_64 {
+        return Err(Error::UnsupportedRelocation);
+    }
+
+    for rela in &entries {
+        match rela.reloc_type() {
+            r_x86_64::R_X86_64_NONE => {}
+            r_x86_64::R_X86_64_RELATIVE => {
+                let target = VirtualAddress(rela.r_offset.wrapping_add(kernel.slide));
+                let phys = kernel.segment_at(target)
+                    .filter(|s| s.contains(target, 8))
+                    .and_then(|s| s.translate(target))
+                    .ok_or(Error::InvalidData)?;
+
+                let value = (rela.r_addend as u64).wrapping_add(kernel.slide);
+                unsafe { core::ptr::write_unaligned(phys.0 as *mut u64, value) };
+                kernel.relocations += 1;
+            }
+            _ => return Err(Error::UnsupportedRelocation),
+        }
+    }
+
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::elf::{class, data as elf_data, elf_type, pf, pt, Elf64Dyn, Elf64Header, ELF_MAGIC};
+
+    /// Allocator handing out heap pages
+    #[derive(Default)]
+    struct HeapPages {
+        blocks: Vec<Vec<u64>>,
+        freed: usize,
+    }
+
+    impl SegmentAllocator for HeapPages {
+        fn allocate(&mut self, pages: usize, _memory_type: MemoryType) -> Result<PhysicalAddress> {
+            // Over-allocate so the base can be page aligned
+            let mut block = alloc::vec![0xAAAA_AAAA_AAAA_AAAAu64; (pages + 1) * 512];
+            let base = (block.as_mut_ptr() as u64).next_multiple_of(PAGE_SIZE);
+            self.blocks.push(block);
+            Ok(PhysicalAddress(base))
+        }
+
+        fn free(&mut self, _base: PhysicalAddress, _pages: usize) {
+            self.freed += 1;
+        }
+    }
+
+    fn bytes<T: Copy>(value: &T) -> &[u8] {
+        unsafe { core::slice::from_raw_parts((value as *const T).cast(), core::mem::size_of::<T>()) }
+    }
+
+    /// PIE kernel linked at 0: RX text at 0x0, RW data at 0x1000 holding
+    /// a pointer to the entry, a RELATIVE relocation for it, and BSS
+    fn build_kernel() -> Vec<u8> {
+        const DATA: u64 = 0x1000;
+        const DYN: u64 = DATA + 0x40;
+        const RELA: u64 = DATA + 0x80;
+
+        let mut image = alloc::vec![0u8; 0x1100];
+        let header = Elf64Header {
+            e_ident: {
+                let mut ident = [0u8; 16];
+                ident[..4].copy_from_slice(&ELF_MAGIC);
+                ident[4] = class::ELFCLASS64;
+                ident[5] = elf_data::ELFDATA2LSB;
+                ident[6] = 1;
+                ident
+            },
+            e_type: elf_type::ET_DYN,
+            e_machine: machine::EM_X86_64,
+            e_version: 1,
+            e_entry: 0x200,
+            e_phoff: 64,
+            e_shoff: 0,
+            e_flags: 0,
+            e_ehsize: 64,
+            e_phentsize: core::mem::size_of::<Elf64ProgramHeader>() as u16,
+            e_phnum: 3,
+            e_shentsize: 0,
+            e_shnum: 0,
+            e_shstrndx: 0,
+        };
+        let phdrs = [
+            Elf64ProgramHeader {
+                p_type: pt::PT_LOAD, p_flags: pf::PF_R | pf::PF_X, p_offset: 0,
+                p_vaddr: 0, p_paddr: 0, p_filesz: 0x1000, p_memsz: 0x1000, p_align: PAGE_SIZE,
+            },
+            Elf64ProgramHeader {
+                p_type: pt::PT_LOAD, p_flags: pf::PF_R | pf::PF_W, p_offset: DATA,
+                p_vaddr: DATA, p_paddr: DATA, p_filesz: 0x100, p_memsz: 0x3000, p_align: PAGE_SIZE,
+            },
+            Elf64ProgramHeader {
+                p_type: pt::PT_DYNAMIC, p_flags: pf::PF_R | pf::PF_W, p_offset: DYN,
+                p_vaddr: DYN, p_paddr: DYN, p_filesz: 0x40, p_memsz: 0x40, p_align: 8,
+            },
+        ];
+        let dynamic = [
+            Elf64Dyn { d_tag: dt::DT_RELA, d_val: RELA },
+            Elf64Dyn { d_tag: dt::DT_RELASZ, d_val: core::mem::size_of::<Elf64Rela>() as u64 },
+            Elf64Dyn { d_tag: dt::DT_RELAENT, d_val: core::mem::size_of::<Elf64Rela>() as u64 },
+            Elf64Dyn { d_tag: dt::DT_NULL, d_val: 0 },
+        ];
+        let rela = Elf64Rela {
+            r_offset: DATA,
+            r_info: u64::from(r_x86_64::R_X86_64_RELATIVE),
+            r_addend: 0x200,
+        };
+
+        image[..64].copy_from_slice(bytes(&header));
+        for (i, phdr) in phdrs.iter().enumerate() {
+            let at = 64 + i * core::mem::size_of::<Elf64ProgramHeader>();
+            image[at..at + core::mem::size_of::<Elf64ProgramHeader>()].copy_from_slice(bytes(phdr));
+        }
+        for (i, entry) in dynamic.iter().enumerate() {
+            let at = DYN as usize + i * 16;
+            image[at..at + 16].copy_from_slice(bytes(entry));
+        }
+        image[RELA as usize..RELA as usize + 24].copy_from_slice(bytes(&rela));
+        image
+    }
+
+    #[test]
+    fn test_load_segments_relocated() {
+        let data = build_kernel();
+        let mut elf = ElfLoader::new();
+        elf.load(&data).unwrap();
+
+        let slide = 0xFFFF_FFFF_8000_0000;
+        let mut pages = HeapPages::default();
+        let kernel = load_segments(&elf, &data, slide, &mut pages).unwrap();
+
+        assert_eq!(kernel.entry_point, VirtualAddress(slide + 0x200));
+        assert_eq!(kernel.segments.len(), 2);
+        assert_eq!(kernel.relocations, 1);
+        assert_eq!(kernel.bss_size, 0x2F00);
+        assert_eq!(kernel.image_size(), 0x4000);
+
+        let text = &kernel.segments[0];
+        assert!(text.permissions.execute && !text.permissions.write);
+        let data_seg = &kernel.segments[1];
+        assert_eq!(data_seg.permissions, SegmentPermissions { read: true, write: true, execute: false });
+        assert_eq!(data_seg.pages, 3);
+
+        // The pointer was slid and the BSS zeroed
+        let ptr = kernel.virt_to_phys(VirtualAddress(slide + 0x1000)).unwrap();
+        assert_eq!(unsafe { *(ptr.0 as *const u64) }, slide + 0x200);
+        let bss = kernel.virt_to_phys(VirtualAddress(slide + 0x3FF8)).unwrap();
+        assert_eq!(unsafe { *(bss.0 as *const u64) }, 0);
+    }
+
+    #[test]
+    fn test_load_segments_rejects_bad_relocation() {
+        let mut data = build_kernel();
+        // Point the relocation past the data segment
+        data[0x1080..0x1088].copy_from_slice(&0x8000u64.to_le_bytes());
+
+        let mut elf = ElfLoader::new();
+        elf.load(&data).unwrap();
+
+        let mut pages = HeapPages::default();
+        assert!(matches!(load_segments(&elf, &data, 0, &mut pages), Err(Error::InvalidData)));
+        assert_eq!(pages.freed, 2);
+    }
+
+    #[test]
+    fn test_segment_permissions() {
+        let phdr = Elf64ProgramHeader {
+            p_type: pt::PT_LOAD, p_flags: pf::PF_R | pf::PF_W | pf::PF_X, p_offset: 0,
+            p_vaddr: 0, p_paddr: 0, p_filesz: 0, p_memsz: 0, p_align: 0,
+        };
+        assert!(SegmentPermissions::from_phdr(&phdr).is_wx());
+    }
+}
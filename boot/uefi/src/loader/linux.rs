@@ -0,0 +1,620 @@
+//! Linux Boot Protocol
+//!
+//! Boots Linux bzImages directly, following the x86 boot protocol
+//! (Documentation/arch/x86/boot.rst):
+//!
+//! - [`BzImage`] validates the setup header and splits off the
+//!   protected-mode kernel
+//! - [`ZeroPage`] is the `boot_params` page: setup header, command line,
+//!   initrd, E820 map, framebuffer, EFI and ACPI pointers
+//! - [`initrd_address`] places the initrd above the kernel, below the
+//!   kernel's limit
+//! - [`efi_handover`] enters the kernel's EFI stub with boot services still
+//!   running; kernels without it are entered at the 64-bit entry point after
+//!   `ExitBootServices`
+//!
+//! Menu entries select this path with `linux = <path>`, `initrd = <path>` and
+//! `options = <cmdline>`.
+
+use core::fmt;
+
+use crate::handoff::framebuffer::{FramebufferInfo, PixelFormat};
+use crate::raw::memory::MemoryType;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// `boot_params` size
+pub const ZERO_PAGE_SIZE: usize = 4096;
+
+/// Boot sector signature at 0x1FE
+pub const BOOT_FLAG: u16 = 0xAA55;
+
+/// Setup header magic at 0x202 ("HdrS")
+pub const HEADER_MAGIC: u32 = 0x5372_6448;
+
+/// Oldest supported boot protocol (2.11: relocatable, EFI handover)
+pub const MIN_PROTOCOL: u16 = 0x020B;
+
+/// Loader type reported to the kernel (undefined bootloader)
+pub const TYPE_OF_LOADER: u8 = 0xFF;
+
+/// Maximum E820 entries in the zero page
+pub const E820_MAX: usize = 128;
+
+/// Offset of the 64-bit entry point from the protected-mode kernel start
+pub const ENTRY_64_OFFSET: u64 = 0x200;
+
+/// Setup header `loadflags`
+pub mod loadflags {
+    /// Protected-mode code is loaded at 0x100000
+    pub const LOADED_HIGH: u8 = 1 << 0;
+    /// KASLR enabled (set by the kernel)
+    pub const KASLR: u8 = 1 << 1;
+    /// Suppress early messages
+    pub const QUIET: u8 = 1 << 5;
+    /// `heap_end_ptr` is valid
+    pub const CAN_USE_HEAP: u8 = 1 << 7;
+}
+
+/// Setup header `xloadflags`
+pub mod xloadflags {
+    /// Has a 64-bit entry point at 0x200
+    pub const KERNEL_64: u16 = 1 << 0;
+    /// Kernel, command line and initrd may live above 4 GiB
+    pub const CAN_BE_LOADED_ABOVE_4G: u16 = 1 << 1;
+    /// 32-bit EFI handover entry
+    pub const EFI_HANDOVER_32: u16 = 1 << 2;
+    /// 64-bit EFI handover entry
+    pub const EFI_HANDOVER_64: u16 = 1 << 3;
+}
+
+/// Zero page field offsets
+mod offset {
+    pub const ACPI_RSDP_ADDR: usize = 0x070;
+    pub const EXT_RAMDISK_IMAGE: usize = 0x0C0;
+    pub const EXT_RAMDISK_SIZE: usize = 0x0C4;
+    pub const EXT_CMD_LINE_PTR: usize = 0x0C8;
+    pub const EFI_INFO: usize = 0x1C0;
+    pub const E820_ENTRIES: usize = 0x1E8;
+    pub const SETUP_SECTS: usize = 0x1F1;
+    pub const BOOT_FLAG: usize = 0x1FE;
+    pub const JUMP: usize = 0x200;
+    pub const HEADER: usize = 0x202;
+    pub const VERSION: usize = 0x206;
+    pub const TYPE_OF_LOADER: usize = 0x210;
+    pub const LOADFLAGS: usize = 0x211;
+    pub const CODE32_START: usize = 0x214;
+    pub const RAMDISK_IMAGE: usize = 0x218;
+    pub const RAMDISK_SIZE: usize = 0x21C;
+    pub const HEAP_END_PTR: usize = 0x224;
+    pub const CMD_LINE_PTR: usize = 0x228;
+    pub const INITRD_ADDR_MAX: usize = 0x22C;
+    pub const KERNEL_ALIGNMENT: usize = 0x230;
+    pub const RELOCATABLE_KERNEL: usize = 0x234;
+    pub const XLOADFLAGS: usize = 0x236;
+    pub const CMDLINE_SIZE: usize = 0x238;
+    pub const PREF_ADDRESS: usize = 0x258;
+    pub const INIT_SIZE: usize = 0x260;
+    pub const HANDOVER_OFFSET: usize = 0x264;
+    pub const E820_TABLE: usize = 0x2D0;
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    read_u32(bytes, at) as u64 | ((read_u32(bytes, at + 4) as u64) << 32)
+}
+
+// =============================================================================
+// ERRORS
+// =============================================================================
+
+/// Linux boot error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinuxError {
+    /// Image shorter than its setup code
+    Truncated,
+    /// No `HdrS` setup header
+    NotBzImage,
+    /// Boot protocol older than [`MIN_PROTOCOL`]
+    ProtocolTooOld(u16),
+    /// Kernel has no 64-bit entry point
+    Not64Bit,
+    /// Command line longer than the kernel accepts
+    CmdlineTooLong,
+    /// Initrd does not fit below the kernel's limit
+    InitrdTooHigh,
+    /// More than [`E820_MAX`] memory regions
+    TooManyRegions,
+}
+
+impl fmt::Display for LinuxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "kernel image is truncated"),
+            Self::NotBzImage => write!(f, "not a bzImage"),
+            Self::ProtocolTooOld(version) => {
+                write!(f, "boot protocol {}.{:02} is too old", version >> 8, version & 0xFF)
+            }
+            Self::Not64Bit => write!(f, "kernel has no 64-bit entry point"),
+            Self::CmdlineTooLong => write!(f, "command line too long"),
+            Self::InitrdTooHigh => write!(f, "initrd does not fit below the kernel's limit"),
+            Self::TooManyRegions => write!(f, "too many memory regions"),
+        }
+    }
+}
+
+// =============================================================================
+// BZIMAGE
+// =============================================================================
+
+/// Check for a bzImage setup header
+pub fn is_bzimage(data: &[u8]) -> bool {
+    data.len() > offset::HEADER + 4
+        && read_u16(data, offset::BOOT_FLAG) == BOOT_FLAG
+        && read_u32(data, offset::HEADER) == HEADER_MAGIC
+}
+
+/// Setup header fields used to load the kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetupHeader {
+    /// Real-mode setup size in 512-byte sectors
+    pub setup_sects: u8,
+    /// Boot protocol version (major << 8 | minor)
+    pub version: u16,
+    /// `loadflags`
+    pub loadflags: u8,
+    /// `xloadflags`
+    pub xloadflags: u16,
+    /// Kernel can be loaded at any aligned address
+    pub relocatable: bool,
+    /// Required alignment of a relocated kernel
+    pub kernel_alignment: u32,
+    /// Preferred load address
+    pub pref_address: u64,
+    /// Memory needed by the kernel while it decompresses itself
+    pub init_size: u32,
+    /// Highest address the initrd may end at (below 4 GiB)
+    pub initrd_addr_max: u32,
+    /// Longest command line, excluding the NUL
+    pub cmdline_size: u32,
+    /// EFI handover entry, relative to the protected-mode kernel
+    pub handover_offset: u32,
+}
+
+impl SetupHeader {
+    /// Parse the header of a bzImage
+    pub fn parse(data: &[u8]) -> Result<Self, LinuxError> {
+        if !is_bzimage(data) {
+            return Err(if data.len() < 0x270 { LinuxError::Truncated } else { LinuxError::NotBzImage });
+        }
+        if data.len() < 0x270 {
+            return Err(LinuxError::Truncated);
+        }
+
+        let version = read_u16(data, offset::VERSION);
+        if version < MIN_PROTOCOL {
+            return Err(LinuxError::ProtocolTooOld(version));
+        }
+
+        Ok(Self {
+            setup_sects: data[offset::SETUP_SECTS],
+            version,
+            loadflags: data[offset::LOADFLAGS],
+            xloadflags: if version >= 0x020C { read_u16(data, offset::XLOADFLAGS) } else { 0 },
+            relocatable: data[offset::RELOCATABLE_KERNEL] != 0,
+            kernel_alignment: read_u32(data, offset::KERNEL_ALIGNMENT),
+            pref_address: read_u64(data, offset::PREF_ADDRESS),
+            init_size: read_u32(data, offset::INIT_SIZE),
+            initrd_addr_max: read_u32(data, offset::INITRD_ADDR_MAX),
+            cmdline_size: read_u32(data, offset::CMDLINE_SIZE),
+            handover_offset: read_u32(data, offset::HANDOVER_OFFSET),
+        })
+    }
+
+    /// Size of the boot sector and real-mode setup code
+    pub fn setup_size(&self) -> usize {
+        // 0 means 4 for ancient kernels
+        let sects = if self.setup_sects == 0 { 4 } else { self.setup_sects as usize };
+        (sects + 1) * 512
+    }
+
+    /// Check an `xloadflags` bit
+    pub fn has(&self, flag: u16) -> bool {
+        self.xloadflags & flag != 0
+    }
+
+    /// Highest usable address for the initrd and command line
+    pub fn address_limit(&self) -> u64 {
+        if self.has(xloadflags::CAN_BE_LOADED_ABOVE_4G) {
+            u64::MAX
+        } else {
+            self.initrd_addr_max as u64
+        }
+    }
+}
+
+/// Parsed bzImage
+#[derive(Debug, Clone, Copy)]
+pub struct BzImage<'a> {
+    /// Whole image
+    data: &'a [u8],
+    /// Setup header
+    pub header: SetupHeader,
+}
+
+impl<'a> BzImage<'a> {
+    /// Parse a bzImage; requires a 64-bit kernel
+    pub fn parse(data: &'a [u8]) -> Result<Self, LinuxError> {
+        let header = SetupHeader::parse(data)?;
+        if data.len() <= header.setup_size() {
+            return Err(LinuxError::Truncated);
+        }
+        if !header.has(xloadflags::KERNEL_64) {
+            return Err(LinuxError::Not64Bit);
+        }
+        Ok(Self { data, header })
+    }
+
+    /// Setup sector (copied into the zero page)
+    pub fn setup(&self) -> &'a [u8] {
+        &self.data[..self.header.setup_size()]
+    }
+
+    /// Protected-mode kernel, loaded at [`BzImage::load_address`]
+    pub fn kernel(&self) -> &'a [u8] {
+        &self.data[self.header.setup_size()..]
+    }
+
+    /// Memory to reserve at the load address
+    pub fn load_size(&self) -> u64 {
+        (self.header.init_size as u64).max(self.kernel().len() as u64)
+    }
+
+    /// Address the kernel prefers to run at
+    pub fn load_address(&self) -> u64 {
+        self.header.pref_address
+    }
+
+    /// Alignment of a relocated kernel (if the preferred address is taken)
+    pub fn alignment(&self) -> Option<u64> {
+        self.header.relocatable.then_some(self.header.kernel_alignment.max(4096) as u64)
+    }
+
+    /// 64-bit EFI handover entry for a kernel loaded at `load_address`
+    pub fn handover_entry(&self, load_address: u64) -> Option<u64> {
+        (self.header.has(xloadflags::EFI_HANDOVER_64) && self.header.handover_offset != 0)
+            .then(|| load_address + ENTRY_64_OFFSET + self.header.handover_offset as u64)
+    }
+
+    /// 64-bit entry after `ExitBootServices` for a kernel loaded at `load_address`
+    pub fn entry_64(&self, load_address: u64) -> u64 {
+        load_address + ENTRY_64_OFFSET
+    }
+}
+
+// =============================================================================
+// MEMORY LAYOUT
+// =============================================================================
+
+/// Place an initrd of `size` bytes above the kernel ending at `kernel_end`
+pub fn initrd_address(header: &SetupHeader, kernel_end: u64, size: u64) -> Result<u64, LinuxError> {
+    let address = (kernel_end + 0xFFF) & !0xFFF;
+    match address.checked_add(size) {
+        Some(end) if end - 1 <= header.address_limit() => Ok(address),
+        _ => Err(LinuxError::InitrdTooHigh),
+    }
+}
+
+/// E820 region type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum E820Type {
+    /// Usable RAM
+    Ram = 1,
+    /// Reserved
+    Reserved = 2,
+    /// ACPI tables (reclaimable)
+    Acpi = 3,
+    /// ACPI non-volatile storage
+    Nvs = 4,
+    /// Defective RAM
+    Unusable = 5,
+    /// Persistent memory
+    Pmem = 7,
+}
+
+impl E820Type {
+    /// Type of a UEFI memory region once boot services have exited
+    pub fn from_uefi(memory_type: MemoryType) -> Self {
+        match memory_type {
+            MemoryType::LoaderCode
+            | MemoryType::LoaderData
+            | MemoryType::BootServicesCode
+            | MemoryType::BootServicesData
+            | MemoryType::ConventionalMemory => E820Type::Ram,
+            MemoryType::AcpiReclaimMemory => E820Type::Acpi,
+            MemoryType::AcpiNvsMemory => E820Type::Nvs,
+            MemoryType::UnusableMemory => E820Type::Unusable,
+            MemoryType::PersistentMemory => E820Type::Pmem,
+            _ => E820Type::Reserved,
+        }
+    }
+}
+
+// =============================================================================
+// ZERO PAGE
+// =============================================================================
+
+/// `boot_params` handed to the kernel
+#[repr(C, align(4096))]
+pub struct ZeroPage {
+    bytes: [u8; ZERO_PAGE_SIZE],
+}
+
+impl ZeroPage {
+    /// Start from the image's setup header
+    pub fn new(image: &BzImage<'_>) -> Self {
+        let mut page = Self { bytes: [0; ZERO_PAGE_SIZE] };
+
+        // The header runs to the end of the jump at 0x200
+        let setup = image.setup();
+        let end = (offset::HEADER + setup[offset::JUMP + 1] as usize).min(setup.len());
+        page.bytes[offset::SETUP_SECTS..end].copy_from_slice(&setup[offset::SETUP_SECTS..end]);
+
+        page.bytes[offset::TYPE_OF_LOADER] = TYPE_OF_LOADER;
+        page.bytes[offset::LOADFLAGS] |= loadflags::CAN_USE_HEAP;
+        page.write_u16(offset::HEAP_END_PTR, 0xFE00 - 0x200);
+        page
+    }
+
+    fn write_u16(&mut self, at: usize, value: u16) {
+        self.bytes[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, at: usize, value: u32) {
+        self.bytes[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, at: usize, value: u64) {
+        self.bytes[at..at + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Raw page
+    pub fn as_bytes(&self) -> &[u8; ZERO_PAGE_SIZE] {
+        &self.bytes
+    }
+
+    /// Pointer passed to the kernel
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.bytes.as_mut_ptr()
+    }
+
+    /// Where the protected-mode kernel was loaded
+    pub fn set_kernel(&mut self, address: u64) {
+        self.write_u32(offset::CODE32_START, address as u32);
+    }
+
+    /// NUL-terminated command line of `len` bytes (excluding the NUL) at `address`
+    pub fn set_cmdline(&mut self, address: u64, len: usize) -> Result<(), LinuxError> {
+        if len as u64 > read_u32(&self.bytes, offset::CMDLINE_SIZE) as u64 {
+            return Err(LinuxError::CmdlineTooLong);
+        }
+        self.write_u32(offset::CMD_LINE_PTR, address as u32);
+        self.write_u32(offset::EXT_CMD_LINE_PTR, (address >> 32) as u32);
+        Ok(())
+    }
+
+    /// Initrd of `size` bytes at `address`
+    pub fn set_initrd(&mut self, address: u64, size: u64) {
+        self.write_u32(offset::RAMDISK_IMAGE, address as u32);
+        self.write_u32(offset::EXT_RAMDISK_IMAGE, (address >> 32) as u32);
+        self.write_u32(offset::RAMDISK_SIZE, size as u32);
+        self.write_u32(offset::EXT_RAMDISK_SIZE, (size >> 32) as u32);
+    }
+
+    /// ACPI RSDP address
+    pub fn set_acpi_rsdp(&mut self, address: u64) {
+        self.write_u64(offset::ACPI_RSDP_ADDR, address);
+    }
+
+    /// EFI system table and final memory map (after `ExitBootServices`)
+    pub fn set_efi(
+        &mut self,
+        system_table: u64,
+        memory_map: u64,
+        memory_map_size: u32,
+        descriptor_size: u32,
+        descriptor_version: u32,
+    ) {
+        let at = offset::EFI_INFO;
+        self.bytes[at..at + 4].copy_from_slice(b"EL64");
+        self.write_u32(at + 0x04, system_table as u32);
+        self.write_u32(at + 0x08, descriptor_size);
+        self.write_u32(at + 0x0C, descriptor_version);
+        self.write_u32(at + 0x10, memory_map as u32);
+        self.write_u32(at + 0x14, memory_map_size);
+        self.write_u32(at + 0x18, (system_table >> 32) as u32);
+        self.write_u32(at + 0x1C, (memory_map >> 32) as u32);
+    }
+
+    /// GOP framebuffer as an EFI `screen_info`
+    pub fn set_framebuffer(&mut self, fb: &FramebufferInfo) {
+        const VIDEO_TYPE_EFI: u8 = 0x70;
+        const VIDEO_CAPABILITY_64BIT_BASE: u32 = 1 << 1;
+
+        let address = fb.address.as_u64();
+        if address == 0 || fb.format == PixelFormat::Text {
+            return;
+        }
+        self.bytes[0x0F] = VIDEO_TYPE_EFI;
+        self.write_u16(0x12, fb.width as u16);
+        self.write_u16(0x14, fb.height as u16);
+        self.write_u16(0x16, fb.bpp as u16);
+        self.write_u32(0x18, address as u32);
+        self.write_u32(0x1C, fb.size as u32);
+        self.write_u16(0x24, fb.stride as u16);
+
+        let mask = &fb.bitmask;
+        let channels = [
+            (mask.red_width(), mask.red_shift()),
+            (mask.green_width(), mask.green_shift()),
+            (mask.blue_width(), mask.blue_shift()),
+            (mask.alpha_width(), mask.alpha_shift()),
+        ];
+        for (i, (size, pos)) in channels.into_iter().enumerate() {
+            self.bytes[0x26 + i * 2] = size;
+            self.bytes[0x27 + i * 2] = pos;
+        }
+
+        if address >> 32 != 0 {
+            self.write_u32(0x36, VIDEO_CAPABILITY_64BIT_BASE);
+            self.write_u32(0x3A, (address >> 32) as u32);
+        }
+    }
+
+    /// Number of E820 entries
+    pub fn e820_count(&self) -> usize {
+        self.bytes[offset::E820_ENTRIES] as usize
+    }
+
+    /// E820 entry (address, size, type)
+    pub fn e820(&self, index: usize) -> Option<(u64, u64, u32)> {
+        if index >= self.e820_count() {
+            return None;
+        }
+        let at = offset::E820_TABLE + index * 20;
+        Some((read_u64(&self.bytes, at), read_u64(&self.bytes, at + 8), read_u32(&self.bytes, at + 16)))
+    }
+
+    /// Add a region, merging it into the previous one when contiguous and
+    /// of the same type
+    pub fn add_e820(&mut self, address: u64, size: u64, kind: E820Type) -> Result<(), LinuxError> {
+        let count = self.e820_count();
+        if let Some((last, last_size, last_kind)) = count.checked_sub(1).and_then(|i| self.e820(i)) {
+            if last_kind == kind as u32 && last + last_size == address {
+                let at = offset::E820_TABLE + (count - 1) * 20;
+                self.write_u64(at + 8, last_size + size);
+                return Ok(());
+            }
+        }
+        if count == E820_MAX {
+            return Err(LinuxError::TooManyRegions);
+        }
+
+        let at = offset::E820_TABLE + count * 20;
+        self.write_u64(at, address);
+        self.write_u64(at + 8, size);
+        self.write_u32(at + 16, kind as u32);
+        self.bytes[offset::E820_ENTRIES] = count as u8 + 1;
+        Ok(())
+    }
+}
+
+// =============================================================================
+// ENTRY
+// =============================================================================
+
+/// Enter the kernel's EFI stub through the handover protocol
+///
+/// # Safety
+/// `entry` must come from [`BzImage::handover_entry`] for a kernel copied to
+/// its load address, boot services must still be running, and `boot_params`
+/// must stay valid; the kernel never returns.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn efi_handover(
+    entry: u64,
+    image_handle: crate::raw::types::Handle,
+    system_table: *mut core::ffi::c_void,
+    boot_params: &mut ZeroPage,
+) -> ! {
+    type Handover = extern "sysv64" fn(crate::raw::types::Handle, *mut core::ffi::c_void, *mut u8) -> !;
+
+    // SAFETY: the caller guarantees `entry` is the kernel's handover entry
+    let handover: Handover = unsafe { core::mem::transmute(entry as usize) };
+    handover(image_handle, system_table, boot_params.as_mut_ptr())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal bzImage: one setup sector plus a 512-byte kernel
+    fn image() -> [u8; 1536] {
+        let mut data = [0u8; 1536];
+        data[offset::SETUP_SECTS] = 1;
+        data[offset::BOOT_FLAG..offset::BOOT_FLAG + 2].copy_from_slice(&BOOT_FLAG.to_le_bytes());
+        data[offset::JUMP + 1] = 0x66; // Header runs to 0x268
+        data[offset::HEADER..offset::HEADER + 4].copy_from_slice(&HEADER_MAGIC.to_le_bytes());
+        data[offset::VERSION..offset::VERSION + 2].copy_from_slice(&0x020Fu16.to_le_bytes());
+        data[offset::INITRD_ADDR_MAX..offset::INITRD_ADDR_MAX + 4].copy_from_slice(&0x7FFF_FFFFu32.to_le_bytes());
+        data[offset::RELOCATABLE_KERNEL] = 1;
+        let flags = xloadflags::KERNEL_64 | xloadflags::EFI_HANDOVER_64;
+        data[offset::XLOADFLAGS..offset::XLOADFLAGS + 2].copy_from_slice(&flags.to_le_bytes());
+        data[offset::CMDLINE_SIZE..offset::CMDLINE_SIZE + 4].copy_from_slice(&2047u32.to_le_bytes());
+        data[offset::PREF_ADDRESS..offset::PREF_ADDRESS + 8].copy_from_slice(&0x100_0000u64.to_le_bytes());
+        data[offset::INIT_SIZE..offset::INIT_SIZE + 4].copy_from_slice(&0x40_0000u32.to_le_bytes());
+        data[offset::HANDOVER_OFFSET..offset::HANDOVER_OFFSET + 4].copy_from_slice(&0x190u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_bzimage() {
+        let data = image();
+        let image = BzImage::parse(&data).unwrap();
+        assert_eq!(image.header.setup_size(), 1024);
+        assert_eq!(image.kernel().len(), 512);
+        assert_eq!(image.load_size(), 0x40_0000);
+        assert_eq!(image.handover_entry(0x100_0000), Some(0x100_0390));
+        assert_eq!(image.header.address_limit(), 0x7FFF_FFFF);
+
+        let mut old = data;
+        old[offset::VERSION] = 0x08;
+        assert_eq!(BzImage::parse(&old).unwrap_err(), LinuxError::ProtocolTooOld(0x0208));
+        assert!(!is_bzimage(&[0u8; 1024]));
+    }
+
+    #[test]
+    fn test_zero_page() {
+        let data = image();
+        let image = BzImage::parse(&data).unwrap();
+        let mut page = ZeroPage::new(&image);
+        assert_eq!(read_u32(page.as_bytes(), offset::HEADER), HEADER_MAGIC);
+        assert_eq!(page.as_bytes()[offset::TYPE_OF_LOADER], TYPE_OF_LOADER);
+
+        assert_eq!(page.set_cmdline(0x1_2345_6000, 2048), Err(LinuxError::CmdlineTooLong));
+        page.set_cmdline(0x1_2345_6000, 12).unwrap();
+        assert_eq!(read_u32(page.as_bytes(), offset::CMD_LINE_PTR), 0x2345_6000);
+        assert_eq!(read_u32(page.as_bytes(), offset::EXT_CMD_LINE_PTR), 1);
+
+        let initrd = initrd_address(&image.header, 0x100_0000 + image.load_size() + 1, 0x10_0000).unwrap();
+        assert_eq!(initrd, 0x140_1000);
+        assert_eq!(initrd_address(&image.header, 0x7FF0_0000, 0x20_0000), Err(LinuxError::InitrdTooHigh));
+    }
+
+    #[test]
+    fn test_e820() {
+        let data = image();
+        let mut page = ZeroPage::new(&BzImage::parse(&data).unwrap());
+        page.add_e820(0, 0x9F000, E820Type::Ram).unwrap();
+        page.add_e820(0x100000, 0x100000, E820Type::from_uefi(MemoryType::BootServicesData)).unwrap();
+        page.add_e820(0x200000, 0x100000, E820Type::from_uefi(MemoryType::ConventionalMemory)).unwrap();
+        page.add_e820(0x300000, 0x1000, E820Type::from_uefi(MemoryType::AcpiNvsMemory)).unwrap();
+
+        assert_eq!(page.e820_count(), 3);
+        assert_eq!(page.e820(1), Some((0x100000, 0x200000, E820Type::Ram as u32)));
+        assert_eq!(page.e820(2), Some((0x300000, 0x1000, E820Type::Nvs as u32)));
+    }
+}
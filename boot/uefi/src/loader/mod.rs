@@ -12,6 +12,10 @@
 //! - Image verification (signatures, hashes)
 //! - Relocation engine
 //! - Module loading
+//! - Chainloading of other EFI applications
+//! - Linux bzImage boot (boot params, initrd, EFI handover)
+//! - Multiboot2 kernel loading
+//! - Unified kernel images (kernel, initrd and cmdline in one signed PE)
 //!
 //! # Features
 //!
@@ -21,6 +25,7 @@
 //! - Dynamic relocation for KASLR
 //! - Module dependency resolution
 //! - Symbol table management
+//! - gzip and zstd compressed kernel images
 //!
 //! # KASLR Support
 //!
@@ -35,20 +40,31 @@
 pub mod elf;
 pub mod pe;
 pub mod image;
+pub mod kernel;
 pub mod relocate;
 pub mod relocation;
 pub mod verify;
 pub mod symbols;
+pub mod chainload;
+pub mod linux;
+pub mod multiboot2;
+pub mod uki;
 
 pub use elf::*;
 pub use pe::*;
 pub use image::*;
+pub use kernel::{KernelSegment, LoadedKernel, SegmentAllocator, SegmentPermissions};
 pub use relocate::*;
 pub use relocation::{RelocationConfig, RelocationStats};
 pub use verify::*;
 pub use symbols::*;
+pub use chainload::{Chainloader, ChainloadError, ChainloadExit};
+pub use linux::{BzImage, LinuxError, ZeroPage};
+pub use multiboot2::{InfoBuilder, Multiboot2Error, Multiboot2Header};
+pub use uki::{UkiBuilder, UkiError, UnifiedKernelImage};
 
 use crate::raw::types::*;
+use crate::compress::{self, CompressionType};
 use crate::error::{Error, Result};
 
 extern crate alloc;
@@ -59,6 +75,16 @@
 // KERNEL LOADER
 // =============================================================================
 
+/// Decompress a gzip or zstd compressed kernel image
+///
+/// Returns `None` for images that are not compressed.
+pub fn decompress_image(data: &[u8]) -> Result<Option<Vec<u8>>> {
+    match CompressionType::detect(data) {
+        CompressionType::Deflate | CompressionType::Zstd => Ok(Some(compress::decompress(data)?)),
+        _ => Ok(None),
+    }
+}
+
 /// Main kernel loader
 pub struct KernelLoader {
     /// ELF loader
@@ -99,7 +125,12 @@ pub fn with_config(config: LoaderConfig) -> Self {
     }
 
     /// Load kernel from buffer
+    ///
+    /// gzip and zstd compressed images are decompressed first.
     pub fn load(&mut self, data: &[u8]) -> Result<&LoadedImage> {
+        let decompressed = decompress_image(data)?;
+        let data = decompressed.as_deref().unwrap_or(data);
+
         // Detect format
         let format = ImageFormat::detect(data)?;
 
@@ -129,6 +160,69 @@ pub fn load(&mut self, data: &[u8]) -> Result<&LoadedImage> {
         Ok(self.loaded_image.as_ref().unwrap())
     }
 
+    /// Place an ELF kernel in memory from its program headers
+    ///
+    /// Each `PT_LOAD` segment gets its own pages and BSS is zeroed. PIE
+    /// kernels are slid to `base_address` if set, or to a random 2 MiB
+    /// aligned slot in the higher half if KASLR is enabled, with their
+    /// `R_X86_64_RELATIVE` relocations applied.
+    pub fn load_kernel(
+        &mut self,
+        data: &[u8],
+        allocator: &mut impl SegmentAllocator,
+    ) -> Result<LoadedKernel> {
+        let decompressed = decompress_image(data)?;
+        let data = decompressed.as_deref().unwrap_or(data);
+
+        if ImageFormat::detect(data)? != ImageFormat::Elf64 {
+            return Err(Error::UnsupportedFormat);
+        }
+        let image = self.elf_loader.load(data)?;
+
+        if self.config.verify_signature {
+            self.verifier.verify_image(&image)?;
+        }
+
+        let segments = self.elf_loader.loadable_segments();
+        let link_base = segments.iter()
+            .map(|p| p.p_vaddr & !0xFFF)
+            .min()
+            .ok_or(Error::InvalidData)?;
+        let link_end = segments.iter()
+            .map(|p| p.p_vaddr + p.p_memsz)
+            .max()
+            .unwrap_or(link_base);
+        let slide = match self.config.base_address {
+            Some(base) => base.0.wrapping_sub(link_base),
+            None if self.config.kaslr_enabled => relocation::kaslr_slide(
+                &RelocationConfig::default(),
+                link_base,
+                link_end - link_base,
+                self.config.kaslr_seed,
+            )?,
+            None => 0,
+        };
+
+        let is_pie = self.elf_loader.header().is_some_and(|h| h.is_pie());
+        if slide != 0 && !is_pie {
+            return Err(Error::UnsupportedRelocation);
+        }
+
+        let kernel = kernel::load_segments(&self.elf_loader, data, slide, allocator)?;
+
+        if self.config.load_symbols {
+            self.symbols.load_from_image(&image)?;
+        }
+
+        self.loaded_image = Some(LoadedImage {
+            entry_point: kernel.entry_point,
+            load_address: kernel.virt_base(),
+            image_size: kernel.image_size(),
+            ..image
+        });
+        Ok(kernel)
+    }
+
     /// Load kernel from file path
     pub fn load_file(&mut self, path: &str) -> Result<&LoadedImage> {
         // This would use filesystem protocol
@@ -659,6 +753,28 @@ fn test_image_format_detection() {
         assert_eq!(ImageFormat::detect(&elf32).unwrap(), ImageFormat::Elf32);
     }
 
+    #[test]
+    fn test_compressed_image() {
+        // `gzip -9` and `zstd -19` of an ELF64 header
+        let gz = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xab, 0x77, 0xf5, 0x71, 0x63, 0x62,
+            0x64, 0x64, 0x20, 0x17, 0x00, 0x00, 0x07, 0xbb, 0x3f, 0xd7, 0x40, 0x00, 0x00, 0x00,
+        ];
+        let zst = [
+            0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x68, 0x7d, 0x00, 0x00, 0x48, 0x7f, 0x45, 0x4c, 0x46, 0x02, 0x01,
+            0x01, 0x00, 0x00, 0x01, 0x00, 0x3c, 0xc1, 0x11, 0x3e, 0xef, 0x4d, 0x9f,
+        ];
+        for image in [&gz[..], &zst[..]] {
+            let elf = decompress_image(image).unwrap().unwrap();
+            assert_eq!(elf.len(), 64);
+            assert_eq!(ImageFormat::detect(&elf).unwrap(), ImageFormat::Elf64);
+        }
+
+        let elf64 = [0x7F, b'E', b'L', b'F', 2, 1, 1, 0];
+        assert!(decompress_image(&elf64).unwrap().is_none());
+        assert_eq!(decompress_image(&gz[..20]), Err(Error::InvalidData));
+    }
+
     #[test]
     fn test_loader_config_default() {
         let config = LoaderConfig::default();
@@ -0,0 +1,793 @@
+//! Multiboot2 Loading
+//!
+//! Loads Multiboot2-compliant kernels, the bootloader side of
+//! `helix-multiboot2`:
+//!
+//! - [`Multiboot2Header`] finds and validates the header in the first
+//!   32 KiB of the image and decodes its tags (information requests,
+//!   address/entry overrides, framebuffer request, module alignment,
+//!   EFI entries, relocation range)
+//! - [`InfoBuilder`] assembles the boot information structure passed in EBX
+//! - [`enter_i386`] switches from long mode to 32-bit protected mode with
+//!   paging off and jumps to the kernel; [`enter_efi_amd64`] jumps to the
+//!   64-bit EFI entry with boot services still running
+//!
+//! ELF kernels are laid out by [`crate::loader::ElfLoader`]; the address tag
+//! covers a.out-kludge images.
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::linux::E820Type;
+use crate::handoff::framebuffer::FramebufferInfo;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Header magic in the kernel image
+pub const HEADER_MAGIC: u32 = 0xE852_50D6;
+
+/// Value in EAX when entering the kernel
+pub const BOOTLOADER_MAGIC: u32 = 0x36D7_6289;
+
+/// The header must lie within this many bytes of the image start
+pub const SEARCH_LIMIT: usize = 32768;
+
+/// i386 architecture (also used by x86_64 kernels)
+pub const ARCHITECTURE_I386: u32 = 0;
+
+/// Header tag types
+pub mod header_tag {
+    /// Last tag
+    pub const END: u16 = 0;
+    /// Boot information tags the kernel needs
+    pub const INFORMATION_REQUEST: u16 = 1;
+    /// Load addresses (a.out kludge)
+    pub const ADDRESS: u16 = 2;
+    /// 32-bit entry point override
+    pub const ENTRY_ADDRESS: u16 = 3;
+    /// Console requirements
+    pub const CONSOLE_FLAGS: u16 = 4;
+    /// Preferred framebuffer mode
+    pub const FRAMEBUFFER: u16 = 5;
+    /// Page-align modules
+    pub const MODULE_ALIGN: u16 = 6;
+    /// Keep boot services running
+    pub const EFI_BS: u16 = 7;
+    /// 32-bit EFI entry point
+    pub const ENTRY_ADDRESS_EFI32: u16 = 8;
+    /// 64-bit EFI entry point
+    pub const ENTRY_ADDRESS_EFI64: u16 = 9;
+    /// Kernel may be loaded anywhere in a range
+    pub const RELOCATABLE: u16 = 10;
+    /// Tag may be ignored if unsupported
+    pub const FLAG_OPTIONAL: u16 = 1;
+}
+
+/// Boot information tag types
+pub mod info_tag {
+    /// Last tag
+    pub const END: u32 = 0;
+    /// Command line
+    pub const CMDLINE: u32 = 1;
+    /// Bootloader name
+    pub const BOOTLOADER_NAME: u32 = 2;
+    /// Boot module
+    pub const MODULE: u32 = 3;
+    /// Lower/upper memory size
+    pub const BASIC_MEMINFO: u32 = 4;
+    /// Memory map
+    pub const MEMORY_MAP: u32 = 6;
+    /// Framebuffer
+    pub const FRAMEBUFFER: u32 = 8;
+    /// EFI system table (64-bit)
+    pub const EFI64: u32 = 12;
+    /// ACPI 1.0 RSDP
+    pub const ACPI_OLD: u32 = 14;
+    /// ACPI 2.0+ RSDP
+    pub const ACPI_NEW: u32 = 15;
+    /// EFI memory map
+    pub const EFI_MMAP: u32 = 17;
+    /// Boot services not exited
+    pub const EFI_BS: u32 = 18;
+    /// EFI image handle (64-bit)
+    pub const EFI64_IH: u32 = 20;
+    /// Load base address
+    pub const LOAD_BASE_ADDR: u32 = 21;
+
+    /// Tags [`super::InfoBuilder`] can provide
+    pub const SUPPORTED: [u32; 14] = [
+        END, CMDLINE, BOOTLOADER_NAME, MODULE, BASIC_MEMINFO, MEMORY_MAP, FRAMEBUFFER,
+        EFI64, ACPI_OLD, ACPI_NEW, EFI_MMAP, EFI_BS, EFI64_IH, LOAD_BASE_ADDR,
+    ];
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+const fn align_up(value: u64, align: u64) -> u64 {
+    (value + align - 1) & !(align - 1)
+}
+
+// =============================================================================
+// ERRORS
+// =============================================================================
+
+/// Multiboot2 load error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiboot2Error {
+    /// No header in the first 32 KiB
+    NoHeader,
+    /// Header checksum does not match
+    BadChecksum,
+    /// Header is for another architecture
+    UnsupportedArchitecture(u32),
+    /// Malformed header tag
+    BadTag(u16),
+    /// Required header tag we do not implement
+    UnsupportedTag(u16),
+    /// Required boot information we cannot provide
+    UnsupportedRequest(u32),
+    /// Address tag does not match the image
+    BadAddress,
+    /// No load address in the relocation range
+    NoLoadAddress,
+    /// Kernel needs a console we cannot give it
+    NoConsole,
+    /// Entry code is above 4 GiB
+    TrampolineTooHigh,
+}
+
+impl fmt::Display for Multiboot2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoHeader => write!(f, "no Multiboot2 header"),
+            Self::BadChecksum => write!(f, "bad Multiboot2 header checksum"),
+            Self::UnsupportedArchitecture(arch) => write!(f, "unsupported architecture {}", arch),
+            Self::BadTag(tag) => write!(f, "malformed header tag {}", tag),
+            Self::UnsupportedTag(tag) => write!(f, "unsupported header tag {}", tag),
+            Self::UnsupportedRequest(tag) => write!(f, "cannot provide boot information tag {}", tag),
+            Self::BadAddress => write!(f, "address tag does not match the image"),
+            Self::NoLoadAddress => write!(f, "no load address in the relocation range"),
+            Self::NoConsole => write!(f, "kernel requires a text console"),
+            Self::TrampolineTooHigh => write!(f, "entry trampoline is above 4 GiB"),
+        }
+    }
+}
+
+// =============================================================================
+// HEADER
+// =============================================================================
+
+/// Address tag (a.out kludge)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressTag {
+    /// Address the header is loaded at
+    pub header_addr: u32,
+    /// Start of the text segment
+    pub load_addr: u32,
+    /// End of the data segment (0: end of file)
+    pub load_end_addr: u32,
+    /// End of bss (0: no bss)
+    pub bss_end_addr: u32,
+}
+
+/// Image segment described by the address tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    /// Offset in the image file
+    pub file_offset: usize,
+    /// Bytes copied from the file
+    pub file_size: usize,
+    /// Physical load address
+    pub load_addr: u64,
+    /// Bytes occupied in memory, including bss
+    pub mem_size: u64,
+}
+
+/// Relocation preference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// No preference
+    None,
+    /// Lowest suitable address
+    Lowest,
+    /// Highest suitable address
+    Highest,
+}
+
+/// Relocatable tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelocatableTag {
+    /// Lowest load address
+    pub min_addr: u32,
+    /// Highest address the image may end at
+    pub max_addr: u32,
+    /// Load address alignment
+    pub align: u32,
+    /// Placement preference
+    pub preference: Placement,
+}
+
+impl RelocatableTag {
+    /// Pick a load address for `size` bytes from the free regions
+    /// `(base, length)`
+    pub fn place(&self, size: u64, free: &[(u64, u64)]) -> Option<u64> {
+        let align = (self.align as u64).max(1).next_power_of_two();
+        let candidates = free.iter().filter_map(|&(base, len)| {
+            let start = align_up(base.max(self.min_addr as u64), align);
+            let end = (base + len).min(self.max_addr as u64 + 1);
+            if start + size > end {
+                return None;
+            }
+            Some(match self.preference {
+                Placement::Highest => (end - size) & !(align - 1),
+                _ => start,
+            })
+        });
+        match self.preference {
+            Placement::Highest => candidates.max(),
+            _ => candidates.min(),
+        }
+    }
+}
+
+/// Decoded Multiboot2 header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multiboot2Header {
+    /// Offset of the header in the image
+    pub offset: usize,
+    /// Boot information tags the kernel cannot run without
+    pub required_info: Vec<u32>,
+    /// Load addresses, for non-ELF images
+    pub address: Option<AddressTag>,
+    /// 32-bit entry point override
+    pub entry: Option<u32>,
+    /// 64-bit EFI entry point
+    pub efi64_entry: Option<u32>,
+    /// Console flags (bit 0: console required, bit 1: EGA text supported)
+    pub console_flags: Option<u32>,
+    /// Preferred framebuffer mode (width, height, depth; 0 = no preference)
+    pub framebuffer: Option<(u32, u32, u32)>,
+    /// Modules must be page aligned
+    pub module_align: bool,
+    /// Keep boot services running
+    pub efi_boot_services: bool,
+    /// Relocation range
+    pub relocatable: Option<RelocatableTag>,
+}
+
+impl Multiboot2Header {
+    /// Find and decode the header in a kernel image
+    pub fn parse(image: &[u8]) -> Result<Self, Multiboot2Error> {
+        let limit = image.len().min(SEARCH_LIMIT);
+        let offset = (0..limit.saturating_sub(15))
+            .step_by(8)
+            .find(|&at| read_u32(image, at) == HEADER_MAGIC)
+            .ok_or(Multiboot2Error::NoHeader)?;
+
+        let architecture = read_u32(image, offset + 4);
+        let length = read_u32(image, offset + 8);
+        let checksum = read_u32(image, offset + 12);
+        if HEADER_MAGIC.wrapping_add(architecture).wrapping_add(length).wrapping_add(checksum) != 0 {
+            return Err(Multiboot2Error::BadChecksum);
+        }
+        if architecture != ARCHITECTURE_I386 {
+            return Err(Multiboot2Error::UnsupportedArchitecture(architecture));
+        }
+        let end = offset
+            .checked_add(length as usize)
+            .filter(|&end| end <= image.len())
+            .ok_or(Multiboot2Error::BadTag(header_tag::END))?;
+
+        let mut header = Self {
+            offset,
+            required_info: Vec::new(),
+            address: None,
+            entry: None,
+            efi64_entry: None,
+            console_flags: None,
+            framebuffer: None,
+            module_align: false,
+            efi_boot_services: false,
+            relocatable: None,
+        };
+
+        let mut at = offset + 16;
+        while at + 8 <= end {
+            let kind = read_u16(image, at);
+            let flags = read_u16(image, at + 2);
+            let size = read_u32(image, at + 4) as usize;
+            if size < 8 || at + size > end {
+                return Err(Multiboot2Error::BadTag(kind));
+            }
+            let body = &image[at + 8..at + size];
+            let field = |index: usize| {
+                (body.len() >= index * 4 + 4)
+                    .then(|| read_u32(body, index * 4))
+                    .ok_or(Multiboot2Error::BadTag(kind))
+            };
+
+            match kind {
+                header_tag::END => return Ok(header),
+                header_tag::INFORMATION_REQUEST => {
+                    let optional = flags & header_tag::FLAG_OPTIONAL != 0;
+                    for request in body.chunks_exact(4).map(|c| read_u32(c, 0)) {
+                        if !info_tag::SUPPORTED.contains(&request) && !optional {
+                            return Err(Multiboot2Error::UnsupportedRequest(request));
+                        }
+                        header.required_info.push(request);
+                    }
+                }
+                header_tag::ADDRESS => {
+                    header.address = Some(AddressTag {
+                        header_addr: field(0)?,
+                        load_addr: field(1)?,
+                        load_end_addr: field(2)?,
+                        bss_end_addr: field(3)?,
+                    });
+                }
+                header_tag::ENTRY_ADDRESS => header.entry = Some(field(0)?),
+                header_tag::ENTRY_ADDRESS_EFI64 => header.efi64_entry = Some(field(0)?),
+                header_tag::CONSOLE_FLAGS => header.console_flags = Some(field(0)?),
+                header_tag::FRAMEBUFFER => header.framebuffer = Some((field(0)?, field(1)?, field(2)?)),
+                header_tag::MODULE_ALIGN => header.module_align = true,
+                header_tag::EFI_BS => header.efi_boot_services = true,
+                header_tag::RELOCATABLE => {
+                    header.relocatable = Some(RelocatableTag {
+                        min_addr: field(0)?,
+                        max_addr: field(1)?,
+                        align: field(2)?,
+                        preference: match field(3)? {
+                            1 => Placement::Lowest,
+                            2 => Placement::Highest,
+                            _ => Placement::None,
+                        },
+                    });
+                }
+                _ if flags & header_tag::FLAG_OPTIONAL != 0 => {}
+                // 32-bit EFI entry, or a tag from a newer revision
+                _ => return Err(Multiboot2Error::UnsupportedTag(kind)),
+            }
+
+            at += align_up(size as u64, 8) as usize;
+        }
+
+        Err(Multiboot2Error::BadTag(header_tag::END))
+    }
+
+    /// Segment to load when the image carries an address tag
+    pub fn segment(&self, image_len: usize) -> Result<Option<Segment>, Multiboot2Error> {
+        let Some(tag) = self.address else {
+            return Ok(None);
+        };
+
+        let file_offset = (self.offset as u64)
+            .checked_sub(tag.header_addr.wrapping_sub(tag.load_addr) as u64)
+            .filter(|_| tag.load_addr <= tag.header_addr)
+            .ok_or(Multiboot2Error::BadAddress)? as usize;
+        let file_size = match tag.load_end_addr {
+            0 => image_len - file_offset,
+            end => end.checked_sub(tag.load_addr).ok_or(Multiboot2Error::BadAddress)? as usize,
+        };
+        if file_offset + file_size > image_len {
+            return Err(Multiboot2Error::BadAddress);
+        }
+        let mem_size = match tag.bss_end_addr {
+            0 => file_size as u64,
+            end => ((end as u64).checked_sub(tag.load_addr as u64).ok_or(Multiboot2Error::BadAddress)?)
+                .max(file_size as u64),
+        };
+
+        Ok(Some(Segment { file_offset, file_size, load_addr: tag.load_addr as u64, mem_size }))
+    }
+
+    /// Load address for a relocatable kernel of `size` bytes (`None` if it
+    /// must be loaded where it was linked)
+    pub fn relocate(&self, size: u64, free: &[(u64, u64)]) -> Result<Option<u64>, Multiboot2Error> {
+        match self.relocatable {
+            Some(tag) => tag.place(size, free).map(Some).ok_or(Multiboot2Error::NoLoadAddress),
+            None => Ok(None),
+        }
+    }
+
+    /// Alignment for module load addresses
+    pub fn module_alignment(&self) -> u64 {
+        if self.module_align { 4096 } else { 8 }
+    }
+
+    /// Check that we can give the kernel the console it asks for
+    pub fn check_console(&self, framebuffer: Option<&FramebufferInfo>) -> Result<(), Multiboot2Error> {
+        const CONSOLE_REQUIRED: u32 = 1 << 0;
+
+        match self.console_flags {
+            // We only hand over graphics; a required console needs a framebuffer
+            Some(flags) if flags & CONSOLE_REQUIRED != 0 && framebuffer.is_none() => Err(Multiboot2Error::NoConsole),
+            _ => Ok(()),
+        }
+    }
+
+    /// Enter through the 64-bit EFI entry with boot services running
+    pub fn uses_efi_entry(&self) -> bool {
+        self.efi_boot_services && self.efi64_entry.is_some()
+    }
+}
+
+/// Check for a Multiboot2 header
+pub fn is_multiboot2(image: &[u8]) -> bool {
+    Multiboot2Header::parse(image).is_ok()
+}
+
+// =============================================================================
+// BOOT INFORMATION
+// =============================================================================
+
+/// Builds the boot information structure
+#[derive(Debug, Clone)]
+pub struct InfoBuilder {
+    /// Structure so far (`total_size` is filled in by [`InfoBuilder::finish`])
+    bytes: Vec<u8>,
+}
+
+impl InfoBuilder {
+    /// Start an empty structure
+    pub fn new() -> Self {
+        Self { bytes: alloc::vec![0; 8] }
+    }
+
+    fn tag(&mut self, kind: u32, body: &[&[u8]]) {
+        let size = 8 + body.iter().map(|part| part.len()).sum::<usize>();
+        self.bytes.extend_from_slice(&kind.to_le_bytes());
+        self.bytes.extend_from_slice(&(size as u32).to_le_bytes());
+        for part in body {
+            self.bytes.extend_from_slice(part);
+        }
+        self.bytes.resize(align_up(self.bytes.len() as u64, 8) as usize, 0);
+    }
+
+    /// Kernel command line
+    pub fn cmdline(&mut self, cmdline: &str) -> &mut Self {
+        self.tag(info_tag::CMDLINE, &[cmdline.as_bytes(), &[0]]);
+        self
+    }
+
+    /// Bootloader name
+    pub fn bootloader_name(&mut self, name: &str) -> &mut Self {
+        self.tag(info_tag::BOOTLOADER_NAME, &[name.as_bytes(), &[0]]);
+        self
+    }
+
+    /// Module loaded at `start..end`
+    pub fn module(&mut self, start: u32, end: u32, cmdline: &str) -> &mut Self {
+        self.tag(info_tag::MODULE, &[&start.to_le_bytes(), &end.to_le_bytes(), cmdline.as_bytes(), &[0]]);
+        self
+    }
+
+    /// Memory map entries `(address, length, type)`; also adds the basic
+    /// memory information derived from it
+    pub fn memory_map(&mut self, regions: &[(u64, u64, E820Type)]) -> &mut Self {
+        let ram = |from: u64| {
+            let mut end = from;
+            while let Some(&(base, len, _)) = regions
+                .iter()
+                .find(|&&(base, len, kind)| kind == E820Type::Ram && base <= end && base + len > end)
+            {
+                end = base + len;
+            }
+            end - from
+        };
+        let lower = ram(0).min(640 * 1024) / 1024;
+        let upper = ram(0x10_0000) / 1024;
+        self.tag(info_tag::BASIC_MEMINFO, &[&(lower as u32).to_le_bytes(), &(upper.min(u32::MAX as u64) as u32).to_le_bytes()]);
+
+        let mut entries = Vec::with_capacity(regions.len() * 24);
+        for &(base, len, kind) in regions {
+            // Multiboot2 shares the E820 numbering but has no persistent type
+            let kind = if kind == E820Type::Pmem { E820Type::Reserved } else { kind };
+            entries.extend_from_slice(&base.to_le_bytes());
+            entries.extend_from_slice(&len.to_le_bytes());
+            entries.extend_from_slice(&(kind as u32).to_le_bytes());
+            entries.extend_from_slice(&0u32.to_le_bytes());
+        }
+        self.tag(info_tag::MEMORY_MAP, &[&24u32.to_le_bytes(), &0u32.to_le_bytes(), &entries]);
+        self
+    }
+
+    /// Linear framebuffer
+    pub fn framebuffer(&mut self, fb: &FramebufferInfo) -> &mut Self {
+        const TYPE_RGB: u8 = 1;
+
+        let mask = &fb.bitmask;
+        self.tag(
+            info_tag::FRAMEBUFFER,
+            &[
+                &fb.address.as_u64().to_le_bytes(),
+                &fb.stride.to_le_bytes(),
+                &fb.width.to_le_bytes(),
+                &fb.height.to_le_bytes(),
+                &[fb.bpp, TYPE_RGB, 0, 0],
+                &[
+                    mask.red_shift(), mask.red_width(),
+                    mask.green_shift(), mask.green_width(),
+                    mask.blue_shift(), mask.blue_width(),
+                ],
+            ],
+        );
+        self
+    }
+
+    /// EFI system table and image handle
+    pub fn efi(&mut self, system_table: u64, image_handle: u64) -> &mut Self {
+        self.tag(info_tag::EFI64, &[&system_table.to_le_bytes()]);
+        self.tag(info_tag::EFI64_IH, &[&image_handle.to_le_bytes()]);
+        self
+    }
+
+    /// Final EFI memory map
+    pub fn efi_memory_map(&mut self, map: &[u8], descriptor_size: u32, descriptor_version: u32) -> &mut Self {
+        self.tag(info_tag::EFI_MMAP, &[&descriptor_size.to_le_bytes(), &descriptor_version.to_le_bytes(), map]);
+        self
+    }
+
+    /// Boot services were left running
+    pub fn efi_boot_services(&mut self) -> &mut Self {
+        self.tag(info_tag::EFI_BS, &[]);
+        self
+    }
+
+    /// ACPI RSDP (20 bytes: 1.0, 36 bytes: 2.0+)
+    pub fn acpi_rsdp(&mut self, rsdp: &[u8]) -> &mut Self {
+        let kind = if rsdp.len() > 20 { info_tag::ACPI_NEW } else { info_tag::ACPI_OLD };
+        self.tag(kind, &[rsdp]);
+        self
+    }
+
+    /// Physical address a relocatable kernel was loaded at
+    pub fn load_base_addr(&mut self, address: u32) -> &mut Self {
+        self.tag(info_tag::LOAD_BASE_ADDR, &[&address.to_le_bytes()]);
+        self
+    }
+
+    /// Terminate the structure; copy it to an 8-byte aligned address below
+    /// 4 GiB and pass that address in EBX
+    pub fn finish(mut self) -> Vec<u8> {
+        self.tag(info_tag::END, &[]);
+        let total = self.bytes.len() as u32;
+        self.bytes[..4].copy_from_slice(&total.to_le_bytes());
+        self.bytes
+    }
+}
+
+impl Default for InfoBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// ENTRY
+// =============================================================================
+
+/// GDT for the protected-mode switch: flat 32-bit code (0x08) and data (0x10)
+#[cfg(target_arch = "x86_64")]
+static ENTRY_GDT: [u64; 3] = [0, 0x00CF_9A00_0000_FFFF, 0x00CF_9200_0000_FFFF];
+
+#[cfg(target_arch = "x86_64")]
+#[repr(C, packed)]
+struct GdtPointer {
+    limit: u16,
+    base: u64,
+}
+
+#[cfg(target_arch = "x86_64")]
+core::arch::global_asm!(
+    ".pushsection .text.helix_multiboot2_enter32, \"ax\"",
+    ".global helix_multiboot2_enter32",
+    ".code64",
+    // rdi = entry, rsi = boot information, rdx = GDT pointer
+    "helix_multiboot2_enter32:",
+    "cli",
+    "lgdt [rdx]",
+    "lea rax, [rip + 2f]",
+    "push 0x08",
+    "push rax",
+    "retfq",
+    ".code32",
+    "2:",
+    "mov ax, 0x10",
+    "mov ds, ax",
+    "mov es, ax",
+    "mov fs, ax",
+    "mov gs, ax",
+    "mov ss, ax",
+    // Paging off, then leave long mode
+    "mov eax, cr0",
+    "and eax, 0x7FFFFFFF",
+    "mov cr0, eax",
+    "mov ecx, 0xC0000080",
+    "rdmsr",
+    "and eax, 0xFFFFFEFF",
+    "wrmsr",
+    "mov eax, cr4",
+    "and eax, 0xFFFFFFDF",
+    "mov cr4, eax",
+    "mov eax, 0x36D76289",
+    "mov ebx, esi",
+    "jmp edi",
+    ".code64",
+    ".popsection",
+);
+
+#[cfg(target_arch = "x86_64")]
+extern "sysv64" {
+    fn helix_multiboot2_enter32(entry: u64, info: u64, gdt: *const GdtPointer) -> !;
+}
+
+/// Check that [`enter_i386`] can run from where the loader was placed
+#[cfg(target_arch = "x86_64")]
+pub fn can_enter_i386() -> Result<(), Multiboot2Error> {
+    let code = helix_multiboot2_enter32 as usize as u64;
+    let gdt = ENTRY_GDT.as_ptr() as u64;
+    if code >= 1 << 32 || gdt >= 1 << 32 {
+        return Err(Multiboot2Error::TrampolineTooHigh);
+    }
+    Ok(())
+}
+
+/// Enter a kernel at its 32-bit entry point: protected mode, paging off,
+/// flat segments, interrupts off, EAX = [`BOOTLOADER_MAGIC`], EBX = `info`
+///
+/// # Safety
+/// Boot services must have exited, the kernel and boot information must be
+/// in place at their physical addresses, and [`can_enter_i386`] must have
+/// succeeded; never returns.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn enter_i386(entry: u32, info: u32) -> ! {
+    let gdt = GdtPointer {
+        limit: (core::mem::size_of_val(&ENTRY_GDT) - 1) as u16,
+        base: ENTRY_GDT.as_ptr() as u64,
+    };
+    // SAFETY: the caller guarantees the machine is ready to leave long mode
+    unsafe { helix_multiboot2_enter32(entry as u64, info as u64, &gdt) }
+}
+
+/// Enter a kernel at its 64-bit EFI entry point with boot services running:
+/// EAX = [`BOOTLOADER_MAGIC`], RBX = `info`
+///
+/// # Safety
+/// `entry` must be the kernel's EFI amd64 entry and `info` must hold the
+/// boot information (with the EFI boot services tag); never returns.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn enter_efi_amd64(entry: u64, info: u64) -> ! {
+    // SAFETY: the caller guarantees the entry point and boot information
+    unsafe {
+        core::arch::asm!(
+            "mov rbx, {info}",
+            "jmp {entry}",
+            info = in(reg) info,
+            entry = in(reg) entry,
+            in("eax") BOOTLOADER_MAGIC,
+            options(noreturn),
+        )
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Image with a header at offset 8 built from `tags` (type, flags, body)
+    fn image(tags: &[(u16, u16, &[u32])]) -> Vec<u8> {
+        let mut header = Vec::new();
+        for &(kind, flags, body) in tags.iter().chain([(header_tag::END, 0, &[][..])].iter()) {
+            header.extend_from_slice(&kind.to_le_bytes());
+            header.extend_from_slice(&flags.to_le_bytes());
+            header.extend_from_slice(&(8 + body.len() as u32 * 4).to_le_bytes());
+            for value in body {
+                header.extend_from_slice(&value.to_le_bytes());
+            }
+            header.resize(align_up(header.len() as u64, 8) as usize, 0);
+        }
+
+        let length = 16 + header.len() as u32;
+        let mut data = alloc::vec![0u8; 8];
+        for value in [HEADER_MAGIC, 0, length, 0u32.wrapping_sub(HEADER_MAGIC).wrapping_sub(length)] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&header);
+        data.resize(4096, 0x90);
+        data
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let data = image(&[
+            (header_tag::INFORMATION_REQUEST, 0, &[info_tag::MEMORY_MAP, info_tag::FRAMEBUFFER]),
+            (header_tag::ADDRESS, 0, &[0x10_0008, 0x10_0000, 0x10_0800, 0x10_2000]),
+            (header_tag::ENTRY_ADDRESS, 0, &[0x10_0040]),
+            (header_tag::FRAMEBUFFER, header_tag::FLAG_OPTIONAL, &[1024, 768, 32]),
+            (header_tag::MODULE_ALIGN, 0, &[]),
+        ]);
+        let header = Multiboot2Header::parse(&data).unwrap();
+        assert_eq!(header.offset, 8);
+        assert_eq!(header.required_info, [info_tag::MEMORY_MAP, info_tag::FRAMEBUFFER]);
+        assert_eq!(header.entry, Some(0x10_0040));
+        assert_eq!(header.framebuffer, Some((1024, 768, 32)));
+        assert_eq!(header.module_alignment(), 4096);
+        assert!(!header.uses_efi_entry());
+        assert_eq!(
+            header.segment(data.len()),
+            Ok(Some(Segment { file_offset: 0, file_size: 0x800, load_addr: 0x10_0000, mem_size: 0x2000 }))
+        );
+
+        let mut bad = data.clone();
+        bad[20] ^= 1;
+        assert_eq!(Multiboot2Header::parse(&bad), Err(Multiboot2Error::BadChecksum));
+        assert_eq!(
+            Multiboot2Header::parse(&image(&[(header_tag::INFORMATION_REQUEST, 0, &[9])])),
+            Err(Multiboot2Error::UnsupportedRequest(9))
+        );
+        assert!(is_multiboot2(&image(&[(header_tag::INFORMATION_REQUEST, header_tag::FLAG_OPTIONAL, &[9])])));
+        assert_eq!(
+            Multiboot2Header::parse(&image(&[(header_tag::ENTRY_ADDRESS_EFI32, 0, &[0])])),
+            Err(Multiboot2Error::UnsupportedTag(header_tag::ENTRY_ADDRESS_EFI32))
+        );
+        assert_eq!(Multiboot2Header::parse(&[0u8; 64]), Err(Multiboot2Error::NoHeader));
+    }
+
+    #[test]
+    fn test_relocation() {
+        let free = [(0x1000, 0x9_0000), (0x10_0000, 0x100_0000), (0x200_0000, 0x40_0000)];
+        let mut tag = RelocatableTag { min_addr: 0x20_0000, max_addr: 0xFFFF_FFFF, align: 0x20_0000, preference: Placement::Lowest };
+        assert_eq!(tag.place(0x30_0000, &free), Some(0x20_0000));
+
+        tag.preference = Placement::Highest;
+        assert_eq!(tag.place(0x30_0000, &free), Some(0x200_0000));
+
+        tag.max_addr = 0x3F_FFFF;
+        assert_eq!(tag.place(0x30_0000, &free), None);
+    }
+
+    #[test]
+    fn test_info_builder() {
+        let mut info = InfoBuilder::new();
+        info.cmdline("console=ttyS0")
+            .bootloader_name("Helix")
+            .module(0x20_0000, 0x20_1000, "initrd")
+            .memory_map(&[(0, 0x9_F000, E820Type::Ram), (0x10_0000, 0x700_0000, E820Type::Ram)])
+            .efi_boot_services();
+        let bytes = info.finish();
+
+        assert_eq!(read_u32(&bytes, 0) as usize, bytes.len());
+        assert_eq!(bytes.len() % 8, 0);
+
+        let mut tags = Vec::new();
+        let mut at = 8;
+        while at < bytes.len() {
+            let (kind, size) = (read_u32(&bytes, at), read_u32(&bytes, at + 4));
+            if kind == info_tag::BASIC_MEMINFO {
+                assert_eq!((read_u32(&bytes, at + 8), read_u32(&bytes, at + 12)), (636, 0x700_0000 / 1024));
+            }
+            tags.push(kind);
+            at += align_up(size as u64, 8) as usize;
+        }
+        assert_eq!(
+            tags,
+            [
+                info_tag::CMDLINE, info_tag::BOOTLOADER_NAME, info_tag::MODULE, info_tag::BASIC_MEMINFO,
+                info_tag::MEMORY_MAP, info_tag::EFI_BS, info_tag::END,
+            ]
+        );
+    }
+}
@@ -1045,6 +1045,105 @@ pub fn type_name(&self) -> &'static str {
 // TESTS
 // =============================================================================
 
+// =============================================================================
+// SECTION READER
+// =============================================================================
+
+/// Reads the section table of a PE32+ file in place, without loading it
+#[derive(Debug, Clone)]
+pub struct PeSectionReader<'a> {
+    /// Whole file
+    data: &'a [u8],
+    /// COFF header
+    coff: CoffHeader,
+    /// Optional header
+    optional: OptionalHeader64,
+    /// File offset of the optional header
+    optional_offset: usize,
+    /// Section headers
+    sections: Vec<PeSectionHeader>,
+}
+
+impl<'a> PeSectionReader<'a> {
+    /// Parse headers and section table
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        let dos = DosHeader::parse(data)?;
+        let pe_offset = dos.pe_offset();
+        let coff = CoffHeader::parse(data, pe_offset)?;
+        let optional_offset = pe_offset + 4 + core::mem::size_of::<CoffHeader>();
+        let optional = OptionalHeader64::parse(data, optional_offset)?;
+
+        let table = optional_offset + coff.size_of_optional_header as usize;
+        let size = core::mem::size_of::<PeSectionHeader>();
+        let mut sections = Vec::with_capacity(coff.number_of_sections as usize);
+        for i in 0..coff.number_of_sections as usize {
+            let offset = table + i * size;
+            if data.len() < offset + size {
+                return Err(Error::InvalidData);
+            }
+            // SAFETY: bounds checked above; the header is plain old data
+            sections.push(unsafe { core::ptr::read_unaligned(data[offset..].as_ptr() as *const PeSectionHeader) });
+        }
+
+        Ok(Self { data, coff, optional, optional_offset, sections })
+    }
+
+    /// Whole file
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// COFF header
+    pub fn coff_header(&self) -> &CoffHeader {
+        &self.coff
+    }
+
+    /// Optional header
+    pub fn optional_header(&self) -> &OptionalHeader64 {
+        &self.optional
+    }
+
+    /// File offset of the optional header
+    pub fn optional_header_offset(&self) -> usize {
+        self.optional_offset
+    }
+
+    /// File offset of the section table
+    pub fn section_table_offset(&self) -> usize {
+        self.optional_offset + self.coff.size_of_optional_header as usize
+    }
+
+    /// Section headers
+    pub fn sections(&self) -> &[PeSectionHeader] {
+        &self.sections
+    }
+
+    /// Find a section by name
+    pub fn find(&self, name: &str) -> Option<&PeSectionHeader> {
+        self.sections.iter().find(|section| section.name_str() == name)
+    }
+
+    /// Contents of a section in the file (trailing file alignment padding
+    /// excluded); `None` if absent or outside the file
+    pub fn section_data(&self, name: &str) -> Option<&'a [u8]> {
+        let section = self.find(name)?;
+        let start = section.pointer_to_raw_data as usize;
+        let raw = section.size_of_raw_data as usize;
+        let len = match section.virtual_size as usize {
+            0 => raw,
+            virtual_size => virtual_size.min(raw),
+        };
+        self.data.get(start..start.checked_add(len)?)
+    }
+
+    /// File offset and size of the Authenticode certificate table
+    pub fn certificate_table(&self) -> Option<(usize, usize)> {
+        // The security directory holds a file offset, not an RVA
+        let dir = self.optional.data_directory(directory::IMAGE_DIRECTORY_ENTRY_SECURITY)?;
+        (dir.virtual_address != 0 && dir.size != 0).then_some((dir.virtual_address as usize, dir.size as usize))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,7 +147,7 @@ pub fn generate_kaslr_address(
     }
 
     // Get entropy
-    let (random, quality) = collect_entropy();
+    let (random, quality) = collect_entropy(None);
 
     // Select slot
     let slot = random % num_slots;
@@ -156,20 +156,57 @@ pub fn generate_kaslr_address(
     Ok((load_address, quality))
 }
 
+/// Pick a KASLR slide for an image linked at `link_base`
+///
+/// The slid image lies within `[min_address, max_address)`, and the slide
+/// is a multiple of `alignment` so large-page mappings stay possible.
+/// Returns 0 when KASLR is disabled.
+pub fn kaslr_slide(
+    config: &RelocationConfig,
+    link_base: u64,
+    image_size: u64,
+    random: u64,
+) -> Result<u64> {
+    if !config.kaslr_enabled {
+        return Ok(0);
+    }
+
+    let mask = config.alignment - 1;
+    let first = ((config.min_address + mask) & !mask) | (link_base & mask);
+    let room = config.max_address
+        .checked_sub(first)
+        .and_then(|room| room.checked_sub(image_size))
+        .ok_or(Error::BufferTooSmall)?;
+
+    let slots = room / config.alignment + 1;
+    let load_address = first + (random % slots) * config.alignment;
+    Ok(load_address.wrapping_sub(link_base))
+}
+
 /// Collect entropy from available sources
-fn collect_entropy() -> (u64, u8) {
-    // Try RDSEED first (best quality)
-    if let Some(val) = rdseed64() {
-        return (val, 4); // Cryptographic quality
+///
+/// `firmware` is a value from the UEFI RNG protocol, if there is one. The
+/// sources are mixed; the quality is that of the best one.
+pub fn collect_entropy(firmware: Option<u64>) -> (u64, u8) {
+    let mut value = rdtsc();
+    let mut quality = 1; // Weak quality
+
+    if let Some(val) = firmware {
+        value ^= val;
+        quality = 2; // Moderate quality
     }
 
-    // Try RDRAND
     if let Some(val) = rdrand64() {
-        return (val, 3); // Strong quality
+        value ^= val;
+        quality = 3; // Strong quality
+    }
+
+    if let Some(val) = rdseed64() {
+        value ^= val;
+        quality = 4; // Cryptographic quality
     }
 
-    // Fall back to TSC
-    (rdtsc(), 1) // Weak quality
+    (value, quality)
 }
 
 // Hardware RNG wrappers
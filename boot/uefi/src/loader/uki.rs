@@ -0,0 +1,405 @@
+//! Unified Kernel Images
+//!
+//! A UKI is an EFI stub with a complete boot entry in PE sections: kernel
+//! (`.linux`), initrd, command line, os-release and splash. One signed file
+//! then covers everything that is booted, since the Authenticode hash spans
+//! every section.
+//!
+//! - [`UnifiedKernelImage`] reads the sections of an image from the ESP,
+//!   verifies the signature over the whole file and turns it into a boot
+//!   entry; the kernel is booted through [`crate::loader::linux`]
+//! - [`UkiBuilder`] appends the sections to a stub; the result is signed
+//!   afterwards (the stub's own signature is dropped)
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::linux::{BzImage, LinuxError};
+use super::pe::{section_characteristics, CoffHeader, PeSectionReader};
+use crate::config::BootEntry;
+use crate::security::secureboot::{DenialReason, SecureBootResult, SecureBootVerifier};
+
+/// UKI section names
+pub mod section {
+    /// Linux kernel (bzImage)
+    pub const LINUX: &str = ".linux";
+    /// Initrd
+    pub const INITRD: &str = ".initrd";
+    /// Kernel command line
+    pub const CMDLINE: &str = ".cmdline";
+    /// os-release of the image
+    pub const OSREL: &str = ".osrel";
+    /// Kernel release (`uname -r`)
+    pub const UNAME: &str = ".uname";
+    /// Boot splash (BMP)
+    pub const SPLASH: &str = ".splash";
+}
+
+/// Size of a PE section header
+const SECTION_HEADER_SIZE: usize = 40;
+
+// =============================================================================
+// ERRORS
+// =============================================================================
+
+/// UKI error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UkiError {
+    /// Not a PE32+ image
+    NotPe,
+    /// No `.linux` section
+    NoKernel,
+    /// `.linux` is not a bootable kernel
+    BadKernel(LinuxError),
+    /// A UKI section overlaps the certificate table, outside the signature
+    UnsignedSection(&'static str),
+    /// Rejected by the db/dbx check
+    Denied(DenialReason),
+    /// No room in the stub's headers for more sections
+    HeadersFull,
+}
+
+impl fmt::Display for UkiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotPe => write!(f, "not a PE image"),
+            Self::NoKernel => write!(f, "no .linux section"),
+            Self::BadKernel(err) => write!(f, "bad kernel: {}", err),
+            Self::UnsignedSection(name) => write!(f, "section {} is outside the signature", name),
+            Self::Denied(reason) => write!(f, "Secure Boot denied the image ({:?})", reason),
+            Self::HeadersFull => write!(f, "no room for more section headers"),
+        }
+    }
+}
+
+// =============================================================================
+// IMAGE
+// =============================================================================
+
+/// Parsed unified kernel image
+#[derive(Debug, Clone)]
+pub struct UnifiedKernelImage<'a> {
+    /// PE section table
+    reader: PeSectionReader<'a>,
+    /// Kernel
+    linux: &'a [u8],
+}
+
+impl<'a> UnifiedKernelImage<'a> {
+    /// Parse the PE sections of an image
+    pub fn parse(data: &'a [u8]) -> Result<Self, UkiError> {
+        let reader = PeSectionReader::parse(data).map_err(|_| UkiError::NotPe)?;
+        let linux = reader.section_data(section::LINUX).ok_or(UkiError::NoKernel)?;
+        Ok(Self { reader, linux })
+    }
+
+    /// Check for a `.linux` section
+    pub fn is_uki(data: &[u8]) -> bool {
+        UnifiedKernelImage::parse(data).is_ok()
+    }
+
+    /// Kernel
+    pub fn kernel(&self) -> Result<BzImage<'a>, UkiError> {
+        BzImage::parse(self.linux).map_err(UkiError::BadKernel)
+    }
+
+    /// Initrd
+    pub fn initrd(&self) -> Option<&'a [u8]> {
+        self.reader.section_data(section::INITRD)
+    }
+
+    /// Embedded command line
+    pub fn cmdline(&self) -> Option<&'a str> {
+        self.text(section::CMDLINE)
+    }
+
+    /// Command line to boot with: edits are ignored under Secure Boot, since
+    /// they are not covered by the signature
+    pub fn effective_cmdline<'b>(&self, edited: Option<&'b str>, enforcing: bool) -> &'b str
+    where
+        'a: 'b,
+    {
+        let edited = if enforcing { None } else { edited };
+        edited.or(self.cmdline()).unwrap_or("")
+    }
+
+    /// Embedded os-release
+    pub fn os_release(&self) -> Option<&'a str> {
+        self.text(section::OSREL)
+    }
+
+    /// Value of an os-release field, unquoted
+    pub fn os_release_field(&self, key: &str) -> Option<&'a str> {
+        self.os_release()?.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            (name.trim() == key).then(|| value.trim().trim_matches(|c| c == '"' || c == '\''))
+        })
+    }
+
+    /// Kernel release
+    pub fn uname(&self) -> Option<&'a str> {
+        self.text(section::UNAME)
+    }
+
+    /// Boot splash
+    pub fn splash(&self) -> Option<&'a [u8]> {
+        self.reader.section_data(section::SPLASH)
+    }
+
+    fn text(&self, name: &str) -> Option<&'a str> {
+        let bytes = self.reader.section_data(name)?;
+        let text = core::str::from_utf8(bytes).ok()?;
+        Some(text.trim_end_matches(['\0', '\n', ' ']))
+    }
+
+    /// Verify the signature over the whole image
+    ///
+    /// Every UKI section must lie outside the certificate table, the only
+    /// part of the file Authenticode does not hash.
+    pub fn verify(&self, verifier: &SecureBootVerifier) -> Result<(), UkiError> {
+        if let Some((start, size)) = self.reader.certificate_table() {
+            for name in [section::LINUX, section::INITRD, section::CMDLINE, section::OSREL, section::UNAME, section::SPLASH] {
+                let Some(header) = self.reader.find(name) else { continue };
+                let from = header.pointer_to_raw_data as usize;
+                let to = from + header.size_of_raw_data as usize;
+                if from < start + size && start < to {
+                    return Err(UkiError::UnsignedSection(name));
+                }
+            }
+        }
+
+        match verifier.verify_image(self.reader.data()) {
+            SecureBootResult::Denied { reason } => Err(UkiError::Denied(reason)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Boot entry for the image at `path` on the ESP
+    pub fn to_entry(&self, id: &str, path: &str) -> BootEntry {
+        let mut entry = BootEntry::new(id);
+        if let Some(title) = self.os_release_field("PRETTY_NAME").or_else(|| self.os_release_field("NAME")) {
+            entry.title = String::from(title);
+        }
+        entry.kernel = String::from(path);
+        entry.cmdline = String::from(self.cmdline().unwrap_or(""));
+        entry.uki = Some(String::from(path));
+        entry.linux = true;
+        entry
+    }
+}
+
+// =============================================================================
+// BUILDER
+// =============================================================================
+
+/// Assembles a UKI from a stub and its sections
+#[derive(Debug, Clone)]
+pub struct UkiBuilder<'a> {
+    /// EFI stub
+    stub: &'a [u8],
+    /// Sections to append, in order
+    sections: Vec<(&'static str, &'a [u8])>,
+}
+
+impl<'a> UkiBuilder<'a> {
+    /// Start from an EFI stub
+    pub fn new(stub: &'a [u8]) -> Self {
+        Self { stub, sections: Vec::new() }
+    }
+
+    /// Add (or replace) a section
+    pub fn section(mut self, name: &'static str, data: &'a [u8]) -> Self {
+        self.sections.retain(|(existing, _)| *existing != name);
+        self.sections.push((name, data));
+        self
+    }
+
+    /// Kernel
+    pub fn linux(self, data: &'a [u8]) -> Self {
+        self.section(section::LINUX, data)
+    }
+
+    /// Initrd
+    pub fn initrd(self, data: &'a [u8]) -> Self {
+        self.section(section::INITRD, data)
+    }
+
+    /// Command line
+    pub fn cmdline(self, cmdline: &'a str) -> Self {
+        self.section(section::CMDLINE, cmdline.as_bytes())
+    }
+
+    /// os-release
+    pub fn os_release(self, osrel: &'a str) -> Self {
+        self.section(section::OSREL, osrel.as_bytes())
+    }
+
+    /// Boot splash
+    pub fn splash(self, bmp: &'a [u8]) -> Self {
+        self.section(section::SPLASH, bmp)
+    }
+
+    /// Build the unsigned image
+    pub fn build(&self) -> Result<Vec<u8>, UkiError> {
+        let reader = PeSectionReader::parse(self.stub).map_err(|_| UkiError::NotPe)?;
+        if !self.sections.iter().any(|(name, _)| *name == section::LINUX) {
+            return Err(UkiError::NoKernel);
+        }
+
+        let optional = *reader.optional_header();
+        let file_align = optional.file_alignment.max(1) as usize;
+        let section_align = optional.section_alignment.max(1) as u64;
+        let align = |value: usize, to: usize| value.div_ceil(to) * to;
+
+        let count = reader.sections().len();
+        let table = reader.section_table_offset() + count * SECTION_HEADER_SIZE;
+        if table + self.sections.len() * SECTION_HEADER_SIZE > optional.size_of_headers as usize {
+            return Err(UkiError::HeadersFull);
+        }
+
+        // Drop the stub's signature; it no longer matches
+        let end = reader.certificate_table().map_or(self.stub.len(), |(start, _)| start.min(self.stub.len()));
+        let mut out = self.stub[..end].to_vec();
+        out.resize(align(out.len(), file_align), 0);
+
+        let mut next_va = reader
+            .sections()
+            .iter()
+            .map(|s| s.virtual_address as u64 + s.virtual_size.max(s.size_of_raw_data) as u64)
+            .max()
+            .unwrap_or(section_align);
+        let mut initialized = 0u32;
+
+        for (i, (name, data)) in self.sections.iter().enumerate() {
+            let virtual_address = next_va.div_ceil(section_align) * section_align;
+            let raw_size = align(data.len(), file_align);
+            let pointer = out.len();
+            out.extend_from_slice(data);
+            out.resize(pointer + raw_size, 0);
+
+            let mut header = [0u8; SECTION_HEADER_SIZE];
+            let len = name.len().min(8);
+            header[..len].copy_from_slice(&name.as_bytes()[..len]);
+            header[8..12].copy_from_slice(&(data.len() as u32).to_le_bytes());
+            header[12..16].copy_from_slice(&(virtual_address as u32).to_le_bytes());
+            header[16..20].copy_from_slice(&(raw_size as u32).to_le_bytes());
+            header[20..24].copy_from_slice(&(pointer as u32).to_le_bytes());
+            let flags = section_characteristics::IMAGE_SCN_CNT_INITIALIZED_DATA | section_characteristics::IMAGE_SCN_MEM_READ;
+            header[36..40].copy_from_slice(&flags.to_le_bytes());
+            let at = table + i * SECTION_HEADER_SIZE;
+            out[at..at + SECTION_HEADER_SIZE].copy_from_slice(&header);
+
+            initialized = initialized.wrapping_add(raw_size as u32);
+            next_va = virtual_address + data.len() as u64;
+        }
+
+        let size_of_image = next_va.div_ceil(section_align) * section_align;
+
+        // Header fields (offsets within the PE32+ optional header)
+        let coff = reader.optional_header_offset() - core::mem::size_of::<CoffHeader>();
+        let opt = reader.optional_header_offset();
+        let sections = (count + self.sections.len()) as u16;
+        out[coff + 2..coff + 4].copy_from_slice(&sections.to_le_bytes());
+        let initialized = optional.size_of_initialized_data.wrapping_add(initialized);
+        out[opt + 8..opt + 12].copy_from_slice(&initialized.to_le_bytes());
+        out[opt + 56..opt + 60].copy_from_slice(&(size_of_image as u32).to_le_bytes());
+        out[opt + 64..opt + 68].copy_from_slice(&0u32.to_le_bytes()); // CheckSum
+        out[opt + 144..opt + 152].copy_from_slice(&[0; 8]); // Certificate table
+        Ok(out)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// PE32+ stub with a `.text` section and a stale signature
+    fn stub() -> Vec<u8> {
+        let mut data = alloc::vec![0u8; 0x800];
+        data[0..2].copy_from_slice(b"MZ");
+        data[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        data[0x40..0x44].copy_from_slice(b"PE\0\0");
+        data[0x44..0x46].copy_from_slice(&0x8664u16.to_le_bytes());
+        data[0x46..0x48].copy_from_slice(&1u16.to_le_bytes());
+        data[0x54..0x56].copy_from_slice(&240u16.to_le_bytes());
+
+        let opt = 0x58;
+        data[opt..opt + 2].copy_from_slice(&0x20Bu16.to_le_bytes());
+        data[opt + 32..opt + 36].copy_from_slice(&0x1000u32.to_le_bytes());
+        data[opt + 36..opt + 40].copy_from_slice(&0x200u32.to_le_bytes());
+        data[opt + 56..opt + 60].copy_from_slice(&0x2000u32.to_le_bytes());
+        data[opt + 60..opt + 64].copy_from_slice(&0x400u32.to_le_bytes());
+        data[opt + 108..opt + 112].copy_from_slice(&16u32.to_le_bytes());
+
+        let text = opt + 240;
+        data[text..text + 5].copy_from_slice(b".text");
+        data[text + 8..text + 12].copy_from_slice(&0x100u32.to_le_bytes());
+        data[text + 12..text + 16].copy_from_slice(&0x1000u32.to_le_bytes());
+        data[text + 16..text + 20].copy_from_slice(&0x200u32.to_le_bytes());
+        data[text + 20..text + 24].copy_from_slice(&0x400u32.to_le_bytes());
+
+        // Stale signature in the last 0x200 bytes
+        data[opt + 144..opt + 148].copy_from_slice(&0x600u32.to_le_bytes());
+        data[opt + 148..opt + 152].copy_from_slice(&0x200u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_build_and_parse() {
+        let stub = stub();
+        let image = UkiBuilder::new(&stub)
+            .os_release("NAME=Helix\nPRETTY_NAME=\"Helix OS 1.0\"\n")
+            .cmdline("root=/dev/sda2 quiet\n")
+            .initrd(b"initrd")
+            .linux(b"not a bzImage")
+            .build()
+            .unwrap();
+
+        let reader = PeSectionReader::parse(&image).unwrap();
+        assert_eq!(reader.sections().len(), 5);
+        assert_eq!(reader.certificate_table(), None);
+        let osrel = *reader.find(section::OSREL).unwrap();
+        assert_eq!({ osrel.virtual_address }, 0x2000);
+
+        let uki = UnifiedKernelImage::parse(&image).unwrap();
+        assert_eq!(uki.cmdline(), Some("root=/dev/sda2 quiet"));
+        assert_eq!(uki.initrd(), Some(&b"initrd"[..]));
+        assert_eq!(uki.os_release_field("PRETTY_NAME"), Some("Helix OS 1.0"));
+        assert_eq!(uki.kernel().unwrap_err(), UkiError::BadKernel(LinuxError::Truncated));
+
+        let entry = uki.to_entry("helix", "\\EFI\\Linux\\helix.efi");
+        assert_eq!(entry.title, "Helix OS 1.0");
+        assert_eq!(entry.cmdline, "root=/dev/sda2 quiet");
+        assert!(entry.is_uki());
+
+        assert_eq!(UkiBuilder::new(&stub).cmdline("x").build(), Err(UkiError::NoKernel));
+        assert_eq!(UnifiedKernelImage::parse(&stub).unwrap_err(), UkiError::NoKernel);
+    }
+
+    #[test]
+    fn test_headers_full() {
+        // Room for three section headers after `.text`
+        let mut stub = stub();
+        stub[0x58 + 60..0x58 + 64].copy_from_slice(&0x200u32.to_le_bytes());
+
+        let builder = UkiBuilder::new(&stub).linux(b"k").initrd(b"i").cmdline("c");
+        assert!(builder.build().is_ok());
+        assert_eq!(builder.os_release("NAME=Helix").build(), Err(UkiError::HeadersFull));
+    }
+
+    #[test]
+    fn test_effective_cmdline() {
+        let stub = stub();
+        let image = UkiBuilder::new(&stub).linux(b"k").cmdline("quiet").build().unwrap();
+        let uki = UnifiedKernelImage::parse(&image).unwrap();
+        assert_eq!(uki.effective_cmdline(Some("single"), false), "single");
+        assert_eq!(uki.effective_cmdline(Some("single"), true), "quiet");
+        assert_eq!(uki.effective_cmdline(None, true), "quiet");
+    }
+}
@@ -235,6 +235,70 @@ pub const fn script(&self) -> Script {
             Language::Hi => Script::Devanagari,
         }
     }
+
+    /// Parse an ISO 639-1 code (`zh` maps to Simplified Chinese)
+    pub fn from_code(code: &str) -> Option<Self> {
+        const ALL: [Language; 29] = [
+            Language::En, Language::Fr, Language::De, Language::Es, Language::It,
+            Language::Pt, Language::Nl, Language::Ru, Language::Ja, Language::ZhCn,
+            Language::ZhTw, Language::Ko, Language::Ar, Language::He, Language::Pl,
+            Language::Cs, Language::Hu, Language::Tr, Language::El, Language::Th,
+            Language::Vi, Language::Id, Language::Hi, Language::Sv, Language::No,
+            Language::Da, Language::Fi, Language::Uk, Language::Ro,
+        ];
+
+        if code.eq_ignore_ascii_case("zh") {
+            return Some(Language::ZhCn);
+        }
+        ALL.iter().copied().find(|lang| lang.code().eq_ignore_ascii_case(code))
+    }
+
+    /// Plural category of `n` (CLDR cardinal rules, integers only)
+    pub const fn plural(&self, n: u64) -> PluralCategory {
+        match self {
+            Language::Ja | Language::ZhCn | Language::ZhTw | Language::Ko |
+            Language::Th | Language::Vi | Language::Id => PluralCategory::Other,
+            Language::Fr | Language::Pt | Language::Hi => {
+                if n <= 1 { PluralCategory::One } else { PluralCategory::Other }
+            }
+            Language::Ru | Language::Uk => {
+                let (n10, n100) = (n % 10, n % 100);
+                if n10 == 1 && n100 != 11 {
+                    PluralCategory::One
+                } else if n10 >= 2 && n10 <= 4 && !(n100 >= 12 && n100 <= 14) {
+                    PluralCategory::Few
+                } else {
+                    PluralCategory::Many
+                }
+            }
+            Language::Pl => {
+                let (n10, n100) = (n % 10, n % 100);
+                if n == 1 {
+                    PluralCategory::One
+                } else if n10 >= 2 && n10 <= 4 && !(n100 >= 12 && n100 <= 14) {
+                    PluralCategory::Few
+                } else {
+                    PluralCategory::Many
+                }
+            }
+            Language::Cs => match n {
+                1 => PluralCategory::One,
+                2..=4 => PluralCategory::Few,
+                _ => PluralCategory::Other,
+            },
+            Language::Ro => {
+                let n100 = n % 100;
+                if n == 1 {
+                    PluralCategory::One
+                } else if n == 0 || (n100 >= 2 && n100 <= 19) {
+                    PluralCategory::Few
+                } else {
+                    PluralCategory::Other
+                }
+            }
+            _ => if n == 1 { PluralCategory::One } else { PluralCategory::Other },
+        }
+    }
 }
 
 impl Default for Language {
@@ -243,6 +307,31 @@ fn default() -> Self {
     }
 }
 
+/// Plural category
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    /// Singular ("1 second")
+    One,
+    /// Paucal ("2-4" in Slavic languages)
+    Few,
+    /// Genitive plural in Slavic languages
+    Many,
+    /// Everything else
+    Other,
+}
+
+impl PluralCategory {
+    /// Keyword used in message patterns
+    pub const fn keyword(&self) -> &'static str {
+        match self {
+            PluralCategory::One => "one",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
 /// Script type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Script {
@@ -967,6 +1056,15 @@ pub const fn is_cjk(c: char) -> bool {
     (cp >= 0x3000 && cp < 0x303F)     // CJK Symbols
 }
 
+/// Check if character is an emoji with wide (pictograph) presentation
+pub const fn is_emoji(c: char) -> bool {
+    let cp = c as u32;
+    (cp >= 0x1F300 && cp <= 0x1F64F) ||  // Pictographs, emoticons
+    (cp >= 0x1F680 && cp <= 0x1F6FF) ||  // Transport and map
+    (cp >= 0x1F900 && cp <= 0x1F9FF) ||  // Supplemental pictographs
+    (cp >= 0x1FA70 && cp <= 0x1FAFF)     // Pictographs extended-A
+}
+
 /// Check if character is wide (takes 2 cells)
 pub const fn is_wide(c: char) -> bool {
     let cp = c as u32;
@@ -976,7 +1074,78 @@ pub const fn is_wide(c: char) -> bool {
     (cp >= 0xAC00 && cp <= 0xD7A3) ||  // Hangul Syllables
     (cp >= 0xF900 && cp <= 0xFAFF) ||  // CJK Compat
     (cp >= 0xFE10 && cp <= 0xFE1F) ||  // Vertical forms
-    (cp >= 0xFF00 && cp <= 0xFF60)     // Fullwidth forms
+    (cp >= 0xFF00 && cp <= 0xFF60) ||  // Fullwidth forms
+    (cp >= 0xFFE0 && cp <= 0xFFE6) ||  // Fullwidth signs
+    (cp >= 0x20000 && cp <= 0x3FFFD) || // CJK Extension B onwards
+    is_emoji(c)
+}
+
+/// Check if character is a combining mark, drawn over the one before it
+pub const fn is_combining(c: char) -> bool {
+    let cp = c as u32;
+    (cp >= 0x0300 && cp <= 0x036F) ||  // Combining diacritical marks
+    (cp >= 0x1AB0 && cp <= 0x1AFF) ||  // Extended
+    (cp >= 0x1DC0 && cp <= 0x1DFF) ||  // Supplement
+    (cp >= 0x20D0 && cp <= 0x20FF) ||  // For symbols
+    (cp >= 0xFE20 && cp <= 0xFE2F)     // Half marks
+}
+
+/// Check if character takes no cell: combining marks, zero-width spaces
+/// and joiners, variation selectors
+pub const fn is_zero_width(c: char) -> bool {
+    let cp = c as u32;
+    is_combining(c) ||
+    (cp >= 0x200B && cp <= 0x200F) ||  // ZWSP, ZWNJ, ZWJ, direction marks
+    cp == 0x2060 || cp == 0xFEFF ||     // Word joiner, BOM
+    (cp >= 0xFE00 && cp <= 0xFE0F)     // Variation selectors
+}
+
+/// Terminal cells taken by a character: 0, 1 or 2
+pub const fn char_width(c: char) -> usize {
+    if is_zero_width(c) {
+        0
+    } else if is_wide(c) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Canonical compositions of Latin letters with one combining mark:
+/// (base, mark, precomposed)
+const COMPOSITIONS: &[(char, char, char)] = &[
+    ('A', '\u{300}', 'À'), ('A', '\u{301}', 'Á'), ('A', '\u{302}', 'Â'), ('A', '\u{303}', 'Ã'),
+    ('A', '\u{308}', 'Ä'), ('A', '\u{30A}', 'Å'), ('C', '\u{327}', 'Ç'), ('C', '\u{30C}', 'Č'),
+    ('E', '\u{300}', 'È'), ('E', '\u{301}', 'É'), ('E', '\u{302}', 'Ê'), ('E', '\u{308}', 'Ë'),
+    ('E', '\u{30C}', 'Ě'), ('I', '\u{300}', 'Ì'), ('I', '\u{301}', 'Í'), ('I', '\u{302}', 'Î'),
+    ('I', '\u{308}', 'Ï'), ('N', '\u{303}', 'Ñ'), ('N', '\u{30C}', 'Ň'), ('O', '\u{300}', 'Ò'),
+    ('O', '\u{301}', 'Ó'), ('O', '\u{302}', 'Ô'), ('O', '\u{303}', 'Õ'), ('O', '\u{308}', 'Ö'),
+    ('R', '\u{30C}', 'Ř'), ('S', '\u{301}', 'Ś'), ('S', '\u{30C}', 'Š'), ('U', '\u{300}', 'Ù'),
+    ('U', '\u{301}', 'Ú'), ('U', '\u{302}', 'Û'), ('U', '\u{308}', 'Ü'), ('U', '\u{30A}', 'Ů'),
+    ('Y', '\u{301}', 'Ý'), ('Z', '\u{301}', 'Ź'), ('Z', '\u{30C}', 'Ž'),
+    ('a', '\u{300}', 'à'), ('a', '\u{301}', 'á'), ('a', '\u{302}', 'â'), ('a', '\u{303}', 'ã'),
+    ('a', '\u{308}', 'ä'), ('a', '\u{30A}', 'å'), ('c', '\u{327}', 'ç'), ('c', '\u{30C}', 'č'),
+    ('e', '\u{300}', 'è'), ('e', '\u{301}', 'é'), ('e', '\u{302}', 'ê'), ('e', '\u{308}', 'ë'),
+    ('e', '\u{30C}', 'ě'), ('i', '\u{300}', 'ì'), ('i', '\u{301}', 'í'), ('i', '\u{302}', 'î'),
+    ('i', '\u{308}', 'ï'), ('n', '\u{303}', 'ñ'), ('n', '\u{30C}', 'ň'), ('o', '\u{300}', 'ò'),
+    ('o', '\u{301}', 'ó'), ('o', '\u{302}', 'ô'), ('o', '\u{303}', 'õ'), ('o', '\u{308}', 'ö'),
+    ('r', '\u{30C}', 'ř'), ('s', '\u{301}', 'ś'), ('s', '\u{30C}', 'š'), ('u', '\u{300}', 'ù'),
+    ('u', '\u{301}', 'ú'), ('u', '\u{302}', 'û'), ('u', '\u{308}', 'ü'), ('u', '\u{30A}', 'ů'),
+    ('y', '\u{301}', 'ý'), ('y', '\u{308}', 'ÿ'), ('z', '\u{301}', 'ź'), ('z', '\u{30C}', 'ž'),
+];
+
+/// Precomposed form of `base` followed by combining `mark` (NFC), if any
+pub fn compose(base: char, mark: char) -> Option<char> {
+    COMPOSITIONS.iter()
+        .find(|&&(b, m, _)| b == base && m == mark)
+        .map(|&(_, _, composed)| composed)
+}
+
+/// Base letter and combining mark of a precomposed Latin letter
+pub fn decompose(c: char) -> Option<(char, char)> {
+    COMPOSITIONS.iter()
+        .find(|&&(_, _, composed)| composed == c)
+        .map(|&(base, mark, _)| (base, mark))
 }
 
 /// Character direction
@@ -1038,6 +1207,25 @@ fn test_boot_strings() {
         assert_eq!(BootString::Loading.de(), "Wird geladen...");
     }
 
+    #[test]
+    fn test_language_from_code() {
+        assert_eq!(Language::from_code("fr"), Some(Language::Fr));
+        assert_eq!(Language::from_code("DE"), Some(Language::De));
+        assert_eq!(Language::from_code("zh"), Some(Language::ZhCn));
+        assert_eq!(Language::from_code("xx"), None);
+    }
+
+    #[test]
+    fn test_plural_rules() {
+        assert_eq!(Language::En.plural(1), PluralCategory::One);
+        assert_eq!(Language::En.plural(0), PluralCategory::Other);
+        assert_eq!(Language::Fr.plural(0), PluralCategory::One);
+        assert_eq!(Language::Ru.plural(21), PluralCategory::One);
+        assert_eq!(Language::Ru.plural(3), PluralCategory::Few);
+        assert_eq!(Language::Ru.plural(12), PluralCategory::Many);
+        assert_eq!(Language::Ja.plural(1), PluralCategory::Other);
+    }
+
     #[test]
     fn test_locale_default() {
         let locale = Locale::default();
@@ -1053,6 +1241,27 @@ fn test_unicode() {
         assert!(!is_wide('A'));
     }
 
+    #[test]
+    fn test_char_width() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('─'), 1);
+        assert_eq!(char_width('界'), 2);
+        assert_eq!(char_width('🚀'), 2);
+        assert_eq!(char_width('\u{301}'), 0);
+        assert_eq!(char_width('\u{200D}'), 0);
+        assert!(is_combining('\u{308}'));
+        assert!(!is_combining('\u{200D}'));
+    }
+
+    #[test]
+    fn test_compose() {
+        assert_eq!(compose('e', '\u{301}'), Some('é'));
+        assert_eq!(compose('U', '\u{308}'), Some('Ü'));
+        assert_eq!(compose('q', '\u{301}'), None);
+        assert_eq!(decompose('ñ'), Some(('n', '\u{303}')));
+        assert_eq!(decompose('n'), None);
+    }
+
     #[test]
     fn test_char_direction() {
         assert_eq!(char_direction('A'), CharDirection::LeftToRight);
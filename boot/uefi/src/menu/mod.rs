@@ -1,9 +1,49 @@
 //! Boot Menu Interface
 //!
 //! Text and graphical boot menu for selecting boot entries.
+//!
+//! All text comes from the resources message catalog; the language is
+//! taken from the locale settings and can be cycled live with `l`.
+//!
+//! Accessibility: F2 toggles high contrast with large text, F3 toggles
+//! navigation beeps, and held keys repeat at the delay and rate from the
+//! accessibility settings.
+//!
+//! Boot profile: the automatically selected preset is shown in the help
+//! and `p` cycles through the presets as a manual override.
+//!
+//! Diagnostics: `d` shows the recorded boot times with regressed stages
+//! flagged.
+//!
+//! Machine mode: with `serialctl = on`, the menu takes commands over a
+//! serial line instead of the keyboard (see [`serialctl`]).
+//!
+//! Chainloading: when a chainloaded application exits, the caller returns
+//! to the menu with [`BootMenu::return_from_chainload`], which shows how it
+//! ended and stops the auto-boot countdown.
+//!
+//! Touch: with an absolute pointer, tapping an entry selects it and tapping
+//! it again boots it; the command line editor shows an on-screen keyboard.
 
+pub mod serialctl;
+
+use self::serialctl::{ControlCommand, ControlLink, LineReader, PROTOCOL_VERSION};
+use super::audio::{NavSound, Tone};
 use super::config::{BootConfig, BootEntry};
-use super::console::{Color, Console, FramebufferConsole, Key};
+use super::console::{Color, Console, FramebufferConsole, InputKey, Key};
+use super::input::{AbsoluteAxes, AbsolutePointer, AbsoluteSample, KeyRepeat, TouchPoint, TouchState};
+use super::layout::{
+    OnScreenKeyboard, OskKey, PointerCapture, Rect, Widget, WidgetFlags, WidgetId, WidgetType,
+};
+use super::loader::chainload::{ChainloadError, ChainloadExit};
+use super::locale::Language;
+use super::monitor::BootHistory;
+use super::profiles::{ProfileSelection, SelectionSource};
+use super::raw::protocols::EfiAbsolutePointerProtocol;
+use super::resources::{self, MessageArg, MessageId};
+use super::settings::{AccessibilitySettings, LocaleSettings};
+use super::theme::{ColorScheme, Theme};
+use alloc::string::String;
 use alloc::vec::Vec;
 
 // =============================================================================
@@ -29,6 +69,62 @@ pub enum MenuResult {
     Timeout,
 }
 
+/// Menu poll interval (ms); the timeout counts these ticks
+const TICK_MS: u64 = 100;
+
+/// Largest text mode (80x25, guaranteed by UEFI)
+const LARGE_TEXT_MODE: usize = 0;
+
+/// Touch target of the edit button
+const TOUCH_EDIT: WidgetId = 1;
+
+/// Touch target of the first entry (entry `i` is `TOUCH_ENTRY_BASE + i`)
+const TOUCH_ENTRY_BASE: WidgetId = 16;
+
+/// On-screen keyboard size in character cells
+const KEYBOARD_COLS: usize = 60;
+const KEYBOARD_ROWS: usize = 10;
+
+/// Text menu colors
+#[derive(Debug, Clone, Copy)]
+struct MenuColors {
+    frame: Color,
+    title: Color,
+    text: Color,
+    background: Color,
+    key: Color,
+    rule: Color,
+    selected_fg: Color,
+    selected_bg: Color,
+    error: Color,
+}
+
+impl MenuColors {
+    const NORMAL: Self = Self {
+        frame: Color::Cyan,
+        title: Color::White,
+        text: Color::White,
+        background: Color::Black,
+        key: Color::Yellow,
+        rule: Color::DarkGray,
+        selected_fg: Color::Black,
+        selected_bg: Color::White,
+        error: Color::Red,
+    };
+
+    const HIGH_CONTRAST: Self = Self {
+        frame: Color::White,
+        title: Color::Yellow,
+        text: Color::White,
+        background: Color::Black,
+        key: Color::Yellow,
+        rule: Color::White,
+        selected_fg: Color::Black,
+        selected_bg: Color::Yellow,
+        error: Color::LightRed,
+    };
+}
+
 /// Boot menu state
 pub struct BootMenu<'a> {
     /// Console
@@ -47,6 +143,44 @@ pub struct BootMenu<'a> {
     cmdline_buffer: [u8; 256],
     /// Command line length
     cmdline_len: usize,
+    /// Validation error for the edited command line
+    edit_error: Option<String>,
+    /// UI language
+    language: Language,
+    /// Accessibility options (changed with F2/F3)
+    accessibility: AccessibilitySettings,
+    /// Colors for the contrast mode
+    colors: MenuColors,
+    /// Text mode to restore when large text is turned off
+    saved_mode: Option<usize>,
+    /// Plays navigation sounds
+    beeper: Option<fn(&[Tone])>,
+    /// Waits out a tick (ms), for a countdown in real time
+    stall: Option<fn(u64)>,
+    /// Held key filter
+    repeat: KeyRepeat<Key>,
+    /// Time since the menu started (ms)
+    clock_ms: u64,
+    /// Touch screen, if any
+    pointer: Option<*mut EfiAbsolutePointerProtocol>,
+    /// Touch readings scaled to character cells
+    touch: AbsolutePointer,
+    /// Widget under the current touch
+    capture: PointerCapture,
+    /// Touch targets of the last draw
+    targets: Vec<Widget>,
+    /// On-screen keyboard for the editor
+    keyboard: OnScreenKeyboard,
+    /// Boot profile (changed with `p`)
+    profile: Option<ProfileSelection>,
+    /// Boot time history (shown with `d`)
+    history: Option<&'a BootHistory>,
+    /// Serial control link, replacing keyboard input
+    control: Option<&'a mut dyn ControlLink>,
+    /// Partial command received over the control link
+    control_input: LineReader,
+    /// Outcome of the last chainloaded application
+    notice: Option<String>,
 }
 
 impl<'a> BootMenu<'a> {
@@ -55,28 +189,170 @@ pub fn new(console: &'a Console, config: &'a BootConfig) -> Self {
         Self {
             console,
             config,
-            selected: config.default_entry,
+            selected: config.default_index(),
             timeout: config.timeout * 10, // Convert to deciseconds
             visible: config.timeout > 0,
             editor_active: false,
             cmdline_buffer: [0u8; 256],
             cmdline_len: 0,
+            edit_error: None,
+            language: Language::default(),
+            accessibility: AccessibilitySettings::default(),
+            colors: MenuColors::NORMAL,
+            saved_mode: None,
+            beeper: None,
+            stall: None,
+            repeat: KeyRepeat::new(500, 100),
+            clock_ms: 0,
+            pointer: None,
+            touch: AbsolutePointer::new(AbsoluteAxes { min_x: 0, max_x: 0, min_y: 0, max_y: 0, max_z: 0 }, 80, 25),
+            capture: PointerCapture::new(),
+            targets: Vec::new(),
+            keyboard: OnScreenKeyboard::new(Rect::new(0, 0, 0, 0)),
+            profile: None,
+            history: None,
+            control: None,
+            control_input: LineReader::new(),
+            notice: None,
         }
     }
 
+    /// Use the language from the locale settings
+    pub fn with_locale(mut self, locale: &LocaleSettings) -> Self {
+        self.language = locale.language.language();
+        self
+    }
+
+    /// Use the accessibility settings
+    pub fn with_accessibility(mut self, accessibility: &AccessibilitySettings) -> Self {
+        let (delay, rate) = accessibility.key_repeat();
+        self.repeat.set_timing(delay, rate);
+        self.accessibility = *accessibility;
+        self
+    }
+
+    /// Play navigation sounds through `beeper` (PC speaker or HDA)
+    pub fn with_beeper(mut self, beeper: fn(&[Tone])) -> Self {
+        self.beeper = Some(beeper);
+        self
+    }
+
+    /// Wait between polls with `stall` (boot services `Stall`) instead of
+    /// spinning, so the countdown runs in seconds
+    pub fn with_stall(mut self, stall: fn(u64)) -> Self {
+        self.stall = Some(stall);
+        self
+    }
+
+    /// Accept touch input from an absolute pointer
+    ///
+    /// # Safety
+    /// `pointer` must be a valid protocol instance for as long as the menu runs.
+    pub unsafe fn with_touch(mut self, pointer: *mut EfiAbsolutePointerProtocol) -> Self {
+        let Some(mode) = (unsafe { (*pointer).get_mode() }) else {
+            return self;
+        };
+
+        let axes = AbsoluteAxes {
+            min_x: mode.absolute_min_x,
+            max_x: mode.absolute_max_x,
+            min_y: mode.absolute_min_y,
+            max_y: mode.absolute_max_y,
+            max_z: if mode.has_pressure() { mode.absolute_max_z } else { 0 },
+        };
+        self.touch = AbsolutePointer::new(axes, 80, 25);
+        self.pointer = Some(pointer);
+        self
+    }
+
+    /// Show the automatically selected boot profile and allow overriding it
+    pub fn with_profile(mut self, selection: ProfileSelection) -> Self {
+        self.profile = Some(selection);
+        self
+    }
+
+    /// Offer the boot time history as a diagnostics screen
+    pub fn with_history(mut self, history: &'a BootHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Take commands from `link` instead of the keyboard (`serialctl = on`)
+    pub fn with_serial_control(mut self, link: &'a mut dyn ControlLink) -> Self {
+        self.visible = true;
+        self.control = Some(link);
+        self
+    }
+
+    /// Come back to the menu after a chainloaded application returned
+    ///
+    /// The countdown is cancelled so the same entry is not started again.
+    pub fn return_from_chainload(&mut self, title: &str, result: &Result<ChainloadExit, ChainloadError>) {
+        self.notice = Some(match result {
+            Ok(exit) => self.format(
+                MessageId::ChainloadReturned,
+                &[MessageArg::Text(&title), MessageArg::Text(&exit.status)],
+            ),
+            Err(error) => self.format(
+                MessageId::ChainloadFailed,
+                &[MessageArg::Text(&title), MessageArg::Text(error)],
+            ),
+        });
+        self.timeout = 0;
+        self.visible = true;
+        self.editor_active = false;
+        self.cmdline_len = 0;
+    }
+
+    /// Current boot profile (changed with `p`; a manual choice goes to
+    /// `ProfileManager::manual`)
+    pub fn profile(&self) -> Option<ProfileSelection> {
+        self.profile
+    }
+
+    /// Current UI language (changed with `l`; persist it on exit)
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Current accessibility options (changed with F2/F3; persist them on exit)
+    pub fn accessibility(&self) -> AccessibilitySettings {
+        self.accessibility
+    }
+
     /// Run boot menu
     pub fn run(&mut self) -> MenuResult {
         if !self.visible {
             return MenuResult::Continue;
         }
 
+        self.apply_accessibility();
         self.draw();
 
+        if let Some(link) = self.control.as_deref_mut() {
+            link.write_str(&alloc::format!("helix-serialctl {} ready\r\n", PROTOCOL_VERSION));
+        }
+
         loop {
-            // Check for key press
-            if let Some(key) = self.console.read_key() {
+            if self.control.is_some() {
+                if let Some(result) = self.poll_control() {
+                    return result;
+                }
+            } else if let Some(result) = self.poll_touch() {
+                return result;
+            }
+
+            // Drain pending keys; firmware auto-repeat is re-timed by the filter
+            while self.control.is_none() {
+                let Some(key) = self.console.read_key() else {
+                    break;
+                };
                 self.timeout = 0; // Cancel timeout on any key
 
+                if !self.editor_active && self.repeat.filter(key, self.clock_ms).is_none() {
+                    continue;
+                }
+
                 match self.handle_key(key) {
                     Some(result) => return result,
                     None => self.draw(),
@@ -97,9 +373,15 @@ pub fn run(&mut self) -> MenuResult {
             }
 
             // Small delay
-            for _ in 0..10000 {
-                core::hint::spin_loop();
+            match self.stall {
+                Some(stall) => stall(TICK_MS),
+                None => {
+                    for _ in 0..10000 {
+                        core::hint::spin_loop();
+                    }
+                }
             }
+            self.clock_ms += TICK_MS;
         }
     }
 
@@ -113,15 +395,22 @@ fn handle_key(&mut self, key: Key) -> Option<MenuResult> {
             Key::Up => {
                 if self.selected > 0 {
                     self.selected -= 1;
+                    self.beep(NavSound::Move);
+                } else {
+                    self.beep(NavSound::Boundary);
                 }
             }
             Key::Down => {
                 let visible_count = self.config.visible_entries().count();
                 if self.selected < visible_count.saturating_sub(1) {
                     self.selected += 1;
+                    self.beep(NavSound::Move);
+                } else {
+                    self.beep(NavSound::Boundary);
                 }
             }
             Key::Enter => {
+                self.beep(NavSound::Select);
                 return Some(MenuResult::Boot(self.selected));
             }
             Key::Char('e') | Key::Char('E') => {
@@ -136,7 +425,37 @@ fn handle_key(&mut self, key: Key) -> Option<MenuResult> {
             Key::Char('s') | Key::Char('S') => {
                 return Some(MenuResult::Shutdown);
             }
+            Key::Char('l') | Key::Char('L') => {
+                self.language = resources::next_language(self.language);
+            }
+            Key::Char('p') | Key::Char('P') => {
+                if let Some(profile) = self.profile.as_mut() {
+                    profile.preset = profile.preset.next();
+                    profile.source = SelectionSource::Manual;
+                    self.beep(NavSound::Move);
+                }
+            }
+            Key::Char('d') | Key::Char('D') => {
+                if self.history.is_some() {
+                    self.beep(NavSound::Select);
+                    self.show_boot_times();
+                }
+            }
+            Key::Function(2) => {
+                let on = !self.accessibility.high_contrast;
+                self.accessibility.high_contrast = on;
+                self.accessibility.large_text = on;
+                self.apply_accessibility();
+                self.beep(NavSound::toggle(on));
+            }
+            Key::Function(3) => {
+                // Always audible, so switching beeps off is confirmed too
+                let on = !self.accessibility.nav_beeps;
+                self.accessibility.nav_beeps = on;
+                self.play(NavSound::toggle(on));
+            }
             Key::Escape => {
+                self.beep(NavSound::Back);
                 return Some(MenuResult::Continue);
             }
             _ => {}
@@ -145,14 +464,237 @@ fn handle_key(&mut self, key: Key) -> Option<MenuResult> {
         None
     }
 
+    /// Run the next command received over the control link
+    fn poll_control(&mut self) -> Option<MenuResult> {
+        let line = self.control_input.poll(self.control.as_deref_mut()?)?;
+        self.timeout = 0;
+
+        let mut reply = String::new();
+        let result = match line.and_then(|line| ControlCommand::parse(&line)) {
+            Ok(command) => self.execute(command, &mut reply),
+            Err(reason) => Err(reason),
+        };
+        match result {
+            Ok(_) => reply.push_str("ok\r\n"),
+            Err(reason) => {
+                reply.push_str("err ");
+                reply.push_str(reason);
+                reply.push_str("\r\n");
+            }
+        }
+
+        if let Some(link) = self.control.as_deref_mut() {
+            link.write_str(&reply);
+        }
+        match result {
+            Ok(Some(result)) => Some(result),
+            _ => {
+                self.draw();
+                None
+            }
+        }
+    }
+
+    /// Run a control command, adding its data lines to `reply`
+    fn execute(&mut self, command: ControlCommand, reply: &mut String) -> Result<Option<MenuResult>, &'static str> {
+        use core::fmt::Write;
+
+        match command {
+            ControlCommand::Ping => {
+                let _ = write!(reply, "helix-serialctl {}\r\n", PROTOCOL_VERSION);
+            }
+            ControlCommand::Help => {
+                for name in ControlCommand::NAMES {
+                    let _ = write!(reply, "{}\r\n", name);
+                }
+            }
+            ControlCommand::List => {
+                for (i, entry) in self.config.visible_entries().enumerate() {
+                    let mark = if i == self.selected { '*' } else { '-' };
+                    let _ = write!(reply, "entry {} {} {}\r\n", i, mark, entry.title);
+                }
+            }
+            ControlCommand::Select(index) => {
+                if index >= self.config.visible_entries().count() {
+                    return Err("no such entry");
+                }
+                self.selected = index;
+                self.cmdline_len = 0;
+            }
+            ControlCommand::Cmdline(cmdline) => {
+                if cmdline.len() >= self.cmdline_buffer.len() {
+                    return Err("command line too long");
+                }
+                if let Err(e) = super::cmdline::validate_edit(&cmdline) {
+                    self.edit_error = Some(e);
+                    return Err("invalid command line");
+                }
+                self.cmdline_buffer[..cmdline.len()].copy_from_slice(cmdline.as_bytes());
+                self.cmdline_len = cmdline.len();
+                self.edit_error = None;
+            }
+            ControlCommand::Diag => self.write_diagnostics(reply),
+            ControlCommand::Boot => return Ok(Some(MenuResult::Boot(self.selected))),
+            ControlCommand::Continue => return Ok(Some(MenuResult::Continue)),
+            ControlCommand::Shell => return Ok(Some(MenuResult::Shell)),
+            ControlCommand::Reboot => return Ok(Some(MenuResult::Reboot)),
+            ControlCommand::Shutdown => return Ok(Some(MenuResult::Shutdown)),
+        }
+        Ok(None)
+    }
+
+    /// Menu state and boot diagnostics as `key value` lines
+    fn write_diagnostics(&self, reply: &mut String) {
+        use core::fmt::Write;
+
+        let _ = write!(reply, "entries {}\r\n", self.config.visible_entries().count());
+        let _ = write!(reply, "selected {}\r\n", self.selected);
+        if let Some(entry) = self.selected_entry() {
+            let _ = write!(reply, "title {}\r\n", entry.title);
+            let cmdline = self.edited_cmdline().unwrap_or(entry.cmdline.as_str());
+            let _ = write!(reply, "cmdline {}\r\n", cmdline);
+        }
+        if let Some(error) = &self.edit_error {
+            let _ = write!(reply, "cmdline_error {}\r\n", error);
+        }
+        if let Some(notice) = &self.notice {
+            let _ = write!(reply, "notice {}\r\n", notice);
+        }
+        let _ = write!(reply, "language {}\r\n", self.language.code());
+        if let Some(profile) = self.profile {
+            let _ = write!(reply, "profile {}\r\n", profile_label(profile));
+        }
+        if let Some(latest) = self.history.and_then(BootHistory::latest) {
+            let _ = write!(reply, "last_boot {}\r\n", latest.sequence);
+            let _ = write!(reply, "last_boot_ms {}\r\n", latest.total_us() / 1000);
+            let _ = write!(reply, "last_boot_regressed {}\r\n", latest.is_regressed());
+        }
+    }
+
+    /// Apply contrast colors and text size
+    fn apply_accessibility(&mut self) {
+        self.colors = if self.accessibility.high_contrast {
+            MenuColors::HIGH_CONTRAST
+        } else {
+            MenuColors::NORMAL
+        };
+
+        if self.accessibility.large_text {
+            if self.saved_mode.is_none() {
+                self.saved_mode = self.console.current_mode();
+                self.console.set_mode(LARGE_TEXT_MODE);
+            }
+        } else if let Some(mode) = self.saved_mode.take() {
+            self.console.set_mode(mode);
+        }
+        self.console.set_attribute(self.colors.text, self.colors.background);
+
+        let (cols, rows) = self.text_size();
+        self.touch.set_target(cols as i32, rows as i32);
+    }
+
+    /// Text mode size in character cells
+    fn text_size(&self) -> (usize, usize) {
+        self.console
+            .current_mode()
+            .and_then(|mode| self.console.query_mode(mode))
+            .unwrap_or((80, 25))
+    }
+
+    /// Read the touch screen and act on completed taps
+    fn poll_touch(&mut self) -> Option<MenuResult> {
+        let pointer = self.pointer?;
+        let state = unsafe { (*pointer).read_state() }.ok()?;
+        let point = self.touch.update(AbsoluteSample {
+            x: state.current_x,
+            y: state.current_y,
+            z: state.current_z,
+            touching: state.is_touching(),
+        })?;
+        self.timeout = 0;
+
+        if self.editor_active {
+            return self.touch_keyboard(&point);
+        }
+
+        let id = self.capture.handle(&mut self.targets, &point)?;
+        if id == TOUCH_EDIT {
+            self.enter_editor();
+        } else {
+            let index = (id - TOUCH_ENTRY_BASE) as usize;
+            if index == self.selected {
+                return self.handle_key(Key::Enter);
+            }
+            self.selected = index;
+            self.beep(NavSound::Move);
+        }
+
+        self.draw();
+        None
+    }
+
+    /// Type on the on-screen keyboard
+    fn touch_keyboard(&mut self, point: &TouchPoint) -> Option<MenuResult> {
+        let event = self.keyboard.touch(point);
+        if point.state != TouchState::Up {
+            return None;
+        }
+
+        if let Some(event) = event {
+            let key = Key::from_input_key(&InputKey {
+                scan_code: event.scancode,
+                unicode_char: event.unicode,
+            });
+            if let Some(result) = self.handle_editor_key(key) {
+                return Some(result);
+            }
+        }
+
+        // Redraw even for layer keys
+        self.draw();
+        None
+    }
+
+    /// Register a touch target
+    fn add_target(&mut self, id: WidgetId, widget_type: WidgetType, bounds: Rect) {
+        let mut widget = Widget::new(id, widget_type);
+        widget.bounds = bounds;
+        widget.flags.set(WidgetFlags::CLICKABLE);
+        self.targets.push(widget);
+    }
+
+    /// Play a navigation sound if beeps are on
+    fn beep(&self, sound: NavSound) {
+        if self.accessibility.nav_beeps {
+            self.play(sound);
+        }
+    }
+
+    /// Play a sound
+    fn play(&self, sound: NavSound) {
+        if let Some(beeper) = self.beeper {
+            beeper(sound.tones());
+        }
+    }
+
     /// Handle editor key press
     fn handle_editor_key(&mut self, key: Key) -> Option<MenuResult> {
         match key {
             Key::Escape => {
                 self.editor_active = false;
+                self.beep(NavSound::Back);
             }
             Key::Enter => {
+                let edited = core::str::from_utf8(&self.cmdline_buffer[..self.cmdline_len])
+                    .unwrap_or("");
+                if let Err(e) = super::cmdline::validate_edit(edited) {
+                    self.edit_error = Some(e);
+                    self.beep(NavSound::Boundary);
+                    return None;
+                }
+                self.edit_error = None;
                 self.editor_active = false;
+                self.beep(NavSound::Select);
                 return Some(MenuResult::Boot(self.selected));
             }
             Key::Backspace => {
@@ -175,6 +717,7 @@ fn handle_editor_key(&mut self, key: Key) -> Option<MenuResult> {
     /// Enter command line editor
     fn enter_editor(&mut self) {
         self.editor_active = true;
+        self.edit_error = None;
 
         // Copy current entry's cmdline to buffer
         if let Some(entry) = self.config.entries.get(self.selected) {
@@ -186,7 +729,7 @@ fn enter_editor(&mut self) {
     }
 
     /// Draw menu
-    fn draw(&self) {
+    fn draw(&mut self) {
         self.console.clear();
 
         // Banner
@@ -195,6 +738,12 @@ fn draw(&self) {
         // Entries
         self.draw_entries();
 
+        // Last chainload
+        if let Some(notice) = &self.notice {
+            self.console.print("  ");
+            self.console.println(notice);
+        }
+
         // Help text
         self.draw_help();
 
@@ -209,33 +758,53 @@ fn draw(&self) {
         }
     }
 
+    /// Message in the current language
+    fn text(&self, id: MessageId) -> &'static str {
+        resources::message(self.language, id)
+    }
+
+    /// Formatted message in the current language
+    fn format(&self, id: MessageId, args: &[MessageArg<'_>]) -> String {
+        let mut text = String::new();
+        let _ = resources::format_message(&mut text, self.language, id, args);
+        text
+    }
+
     /// Draw banner
     fn draw_banner(&self) {
+        let c = self.colors;
         self.console.println("");
-        self.console.print_colored("  ╔═══════════════════════════════════════════════════════╗\r\n", Color::Cyan);
-        self.console.print_colored("  ║", Color::Cyan);
-        self.console.print_colored("           Helix UEFI Boot Manager v1.0           ", Color::White);
-        self.console.print_colored("║\r\n", Color::Cyan);
-        self.console.print_colored("  ╚═══════════════════════════════════════════════════════╝\r\n", Color::Cyan);
+        self.console.print_colored("  ╔═══════════════════════════════════════════════════════╗\r\n", c.frame);
+        self.console.print_colored("  ║", c.frame);
+        self.console.print_colored(&center(self.text(MessageId::MenuTitle), 55), c.title);
+        self.console.print_colored("║\r\n", c.frame);
+        self.console.print_colored("  ╚═══════════════════════════════════════════════════════╝\r\n", c.frame);
         self.console.println("");
     }
 
     /// Draw entries
-    fn draw_entries(&self) {
-        let visible: Vec<_> = self.config.entries.iter()
+    fn draw_entries(&mut self) {
+        let config = self.config;
+        let visible: Vec<_> = config.entries.iter()
             .filter(|e| !e.hidden)
             .collect();
 
+        let c = self.colors;
+        let (cols, _) = self.text_size();
+        self.targets.clear();
+
         for (i, entry) in visible.iter().enumerate() {
             let is_selected = i == self.selected;
+            let row = self.console.cursor_position().1 as i32;
+            self.add_target(TOUCH_ENTRY_BASE + i as WidgetId, WidgetType::ListItem, Rect::new(0, row, cols as i32, 1));
 
             if is_selected {
-                self.console.print_colored("  ► ", Color::Yellow);
-                self.console.set_attribute(Color::Black, Color::White);
+                self.console.print_colored("  ► ", c.key);
+                self.console.set_attribute(c.selected_fg, c.selected_bg);
                 self.console.print(" ");
                 self.console.print(entry.title.as_str());
                 self.console.print(" ");
-                self.console.set_attribute(Color::White, Color::Black);
+                self.console.set_attribute(c.text, c.background);
             } else {
                 self.console.print("    ");
                 self.console.print(entry.title.as_str());
@@ -245,49 +814,159 @@ fn draw_entries(&self) {
         }
 
         self.console.println("");
+
+        // Touch has no `e` key
+        if self.pointer.is_some() && !self.editor_active {
+            let label = self.text(MessageId::KeyEdit);
+            let row = self.console.cursor_position().1 as i32;
+            self.console.print("  ");
+            self.console.set_attribute(c.selected_fg, c.selected_bg);
+            self.console.print(" ");
+            self.console.print(label);
+            self.console.print(" ");
+            self.console.set_attribute(c.text, c.background);
+            self.console.println("");
+            self.add_target(TOUCH_EDIT, WidgetType::Button, Rect::new(2, row, label.chars().count() as i32 + 2, 1));
+        }
     }
 
     /// Draw help text
     fn draw_help(&self) {
+        let key = self.colors.key;
         self.console.println("");
-        self.console.print_colored("  ─────────────────────────────────────────────────────────\r\n", Color::DarkGray);
-        self.console.print_colored("  ↑↓ ", Color::Yellow);
-        self.console.print("Select   ");
-        self.console.print_colored("Enter ", Color::Yellow);
-        self.console.print("Boot   ");
-        self.console.print_colored("e ", Color::Yellow);
-        self.console.print("Edit   ");
-        self.console.print_colored("c ", Color::Yellow);
-        self.console.println("Shell");
-        self.console.print_colored("  r ", Color::Yellow);
-        self.console.print("Reboot   ");
-        self.console.print_colored("s ", Color::Yellow);
-        self.console.print("Shutdown   ");
-        self.console.print_colored("Esc ", Color::Yellow);
-        self.console.println("Continue");
+        self.console.print_colored("  ─────────────────────────────────────────────────────────\r\n", self.colors.rule);
+        self.console.print_colored("  ↑↓ ", key);
+        self.console.print(self.text(MessageId::KeySelect));
+        self.console.print_colored("   Enter ", key);
+        self.console.print(self.text(MessageId::KeyBoot));
+        self.console.print_colored("   e ", key);
+        self.console.print(self.text(MessageId::KeyEdit));
+        self.console.print_colored("   c ", key);
+        self.console.println(self.text(MessageId::KeyShell));
+        self.console.print_colored("  r ", key);
+        self.console.print(self.text(MessageId::KeyReboot));
+        self.console.print_colored("   s ", key);
+        self.console.print(self.text(MessageId::KeyShutdown));
+        self.console.print_colored("   Esc ", key);
+        self.console.println(self.text(MessageId::KeyContinue));
+        self.console.print_colored("  l ", key);
+        self.console.print(self.text(MessageId::KeyLanguage));
+        self.console.print(": ");
+        self.console.println(self.language.native_name());
+        self.console.print_colored("  F2 ", key);
+        self.console.print(self.text(MessageId::KeyHighContrast));
+        self.console.print_colored("   F3 ", key);
+        self.console.println(self.text(MessageId::KeyBeeps));
+
+        if let Some(profile) = self.profile {
+            self.console.print_colored("  p ", key);
+            self.console.print(self.text(MessageId::KeyProfile));
+            self.console.print(": ");
+            self.console.println(&profile_label(profile));
+        }
+
+        if self.history.is_some() {
+            self.console.print_colored("  d ", key);
+            self.console.println(self.text(MessageId::KeyBootTimes));
+        }
+    }
+
+    /// Show the boot time history until a key is pressed
+    fn show_boot_times(&self) {
+        let Some(history) = self.history else {
+            return;
+        };
+
+        self.console.clear();
+        self.console.println("");
+        self.console.print_colored("  ", self.colors.title);
+        self.console.print_colored(self.text(MessageId::KeyBootTimes), self.colors.title);
+        self.console.println("");
+        self.console.print_colored("  ─────────────────────────────────────────────────────────\r\n", self.colors.rule);
+
+        if history.is_empty() {
+            self.console.print("  ");
+            self.console.println(self.text(MessageId::BootTimesEmpty));
+        } else {
+            for line in alloc::format!("{}", history).lines() {
+                self.console.print("  ");
+                self.console.println(line);
+            }
+        }
+
+        self.console.println("");
+        self.console.print("  ");
+        self.console.println(self.text(MessageId::PressAnyKey));
+        self.console.wait_for_key();
     }
 
     /// Draw timeout
     fn draw_timeout(&self) {
         let seconds = self.timeout / 10;
         self.console.println("");
-        self.console.print("  Booting in ");
-        self.console.print_colored(&format_number(seconds), Color::Yellow);
-        self.console.println(" seconds...");
+        self.console.print("  ");
+        self.console.println(&self.format(MessageId::BootCountdown, &[MessageArg::Num(seconds as u64)]));
     }
 
     /// Draw command line editor
-    fn draw_editor(&self) {
+    fn draw_editor(&mut self) {
+        self.console.println("");
+        self.console.print("  ");
+        self.console.print_colored(self.text(MessageId::EditorTitle), self.colors.frame);
         self.console.println("");
-        self.console.print_colored("  Command Line Editor:\r\n", Color::Cyan);
         self.console.print("  > ");
 
         if let Ok(s) = core::str::from_utf8(&self.cmdline_buffer[..self.cmdline_len]) {
             self.console.print(s);
         }
 
-        self.console.print_colored("█", Color::Yellow);
+        self.console.print_colored("█", self.colors.key);
         self.console.println("");
+
+        if let Some(error) = &self.edit_error {
+            self.console.print("  ");
+            self.console.print_colored(
+                &self.format(MessageId::InvalidCmdline, &[MessageArg::Text(error)]),
+                self.colors.error,
+            );
+            self.console.println("");
+        }
+
+        if self.pointer.is_some() {
+            self.console.println("");
+            let (cols, _) = self.text_size();
+            let row = self.console.cursor_position().1 as i32;
+            let width = KEYBOARD_COLS.min(cols.saturating_sub(4)) as i32;
+            self.keyboard.bounds = Rect::new(2, row, width, KEYBOARD_ROWS as i32);
+            self.draw_keyboard();
+        }
+    }
+
+    /// Draw on-screen keyboard
+    fn draw_keyboard(&self) {
+        let c = self.colors;
+        let layer = self.keyboard.layer();
+        let pressed = self.keyboard.pressed();
+
+        self.keyboard.for_each_key(|key, rect| {
+            let mut buf = [0u8; 4];
+            let label = match key {
+                OskKey::Char(ch) => ch.encode_utf8(&mut buf),
+                _ => key.label(layer),
+            };
+
+            if pressed == Some(key) {
+                self.console.set_attribute(c.selected_fg, c.selected_bg);
+            } else {
+                self.console.set_attribute(c.key, c.background);
+            }
+            self.console.set_cursor(rect.x as usize, rect.y as usize);
+            self.console.print(&center(label, rect.width.max(1) as usize - 1));
+        });
+
+        self.console.set_attribute(c.text, c.background);
+        let bottom = self.keyboard.bounds.bottom() as usize;
+        self.console.set_cursor(0, bottom);
     }
 
     /// Get selected entry
@@ -328,6 +1007,10 @@ pub struct GraphicalMenu<'a> {
     box_y: u32,
     box_width: u32,
     box_height: u32,
+    /// UI language
+    language: Language,
+    /// Colors of the active theme
+    colors: ColorScheme,
 }
 
 impl<'a> GraphicalMenu<'a> {
@@ -348,7 +1031,33 @@ pub fn new(fb: &'a mut FramebufferConsole, config: &'a BootConfig) -> Self {
             box_y,
             box_width,
             box_height,
+            language: Language::default(),
+            colors: ColorScheme::dark(),
+        }
+    }
+
+    /// Use the language from the locale settings
+    pub fn with_locale(mut self, locale: &LocaleSettings) -> Self {
+        self.language = locale.language.language();
+        self
+    }
+
+    /// Switch the UI language; takes effect on the next draw
+    pub fn set_language(&mut self, language: Language) {
+        self.language = language;
+    }
+
+    /// Switch the theme; takes effect on the next draw
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.colors = theme.colors;
+    }
+
+    /// Use the high contrast large theme if the accessibility settings ask for it
+    pub fn with_accessibility(mut self, accessibility: &AccessibilitySettings) -> Self {
+        if accessibility.high_contrast {
+            self.set_theme(&Theme::high_contrast_large());
         }
+        self
     }
 
     /// Draw menu
@@ -369,23 +1078,53 @@ pub fn draw(&mut self) {
         self.draw_footer();
     }
 
+    /// Handle a touch point (framebuffer pixels): tap selects, tapping the
+    /// selected entry boots it
+    pub fn handle_touch(&mut self, point: &TouchPoint) -> Option<MenuResult> {
+        if point.state != TouchState::Up {
+            return None;
+        }
+        let index = self.entry_at(point.x, point.y)?;
+        self.timeout = 0;
+
+        if index == self.selected {
+            return Some(MenuResult::Boot(index));
+        }
+        self.selected = index;
+        self.draw();
+        None
+    }
+
+    /// Entry under a point
+    fn entry_at(&self, x: i32, y: i32) -> Option<usize> {
+        let bounds = Rect::new(self.box_x as i32, self.box_y as i32, self.box_width as i32, self.box_height as i32);
+        if !bounds.contains(x, y) {
+            return None;
+        }
+
+        // Entries are drawn one per 16-pixel text row
+        let index = (y as u32 / 16).checked_sub(self.box_y / 16 + 4)? as usize;
+        self.config.entries.get(index).filter(|e| !e.hidden).map(|_| index)
+    }
+
     /// Draw menu box
     fn draw_box(&mut self) {
         // Draw border and background
         // This would use pixel drawing in real implementation
-        self.fb.set_colors(0xFF_40_40_40, 0xFF_20_20_20);
+        self.fb.set_colors(self.colors.border.to_argb(), self.colors.surface.to_argb());
     }
 
     /// Draw title
     fn draw_title(&mut self) {
         self.fb.set_cursor(self.box_x / 8 + 2, self.box_y / 16 + 1);
-        self.fb.set_colors(0xFF_00_FF_FF, 0xFF_20_20_20);
-        self.fb.print("Helix Boot Manager");
+        self.fb.set_colors(self.colors.primary.to_argb(), self.colors.surface.to_argb());
+        self.fb.print(resources::message(self.language, MessageId::GraphicalTitle));
     }
 
     /// Draw entries
     fn draw_entries(&mut self) {
         let y_start = self.box_y / 16 + 4;
+        self.fb.set_colors(self.colors.text_primary.to_argb(), self.colors.surface.to_argb());
 
         for (i, entry) in self.config.entries.iter().enumerate() {
             if entry.hidden {
@@ -396,11 +1135,11 @@ fn draw_entries(&mut self) {
             self.fb.set_cursor(self.box_x / 8 + 4, y);
 
             if i == self.selected {
-                self.fb.set_colors(0xFF_00_00_00, 0xFF_FF_FF_FF);
+                self.fb.set_colors(self.colors.selected_text.to_argb(), self.colors.selected_bg.to_argb());
                 self.fb.print(" ");
                 self.fb.print(entry.title.as_str());
                 self.fb.print(" ");
-                self.fb.set_colors(0xFF_FF_FF_FF, 0xFF_20_20_20);
+                self.fb.set_colors(self.colors.text_primary.to_argb(), self.colors.surface.to_argb());
             } else {
                 self.fb.print("  ");
                 self.fb.print(entry.title.as_str());
@@ -412,15 +1151,15 @@ fn draw_entries(&mut self) {
     fn draw_footer(&mut self) {
         let y = self.box_y / 16 + self.box_height / 16 - 2;
         self.fb.set_cursor(self.box_x / 8 + 2, y);
-        self.fb.set_colors(0xFF_80_80_80, 0xFF_20_20_20);
-        self.fb.print("Use arrows to select, Enter to boot");
+        self.fb.set_colors(self.colors.text_secondary.to_argb(), self.colors.surface.to_argb());
+        self.fb.print(resources::message(self.language, MessageId::GraphicalHelp));
 
         if self.timeout > 0 {
             self.fb.set_cursor(self.box_x / 8 + 2, y + 1);
-            self.fb.print("Auto-boot in ");
-            let seconds = self.timeout / 10;
-            self.fb.print(&format_number(seconds));
-            self.fb.print("s");
+            let seconds = (self.timeout / 10) as u64;
+            let mut text = String::new();
+            let _ = resources::format_message(&mut text, self.language, MessageId::AutoBoot, &[MessageArg::Num(seconds)]);
+            self.fb.print(&text);
         }
     }
 }
@@ -645,6 +1384,26 @@ fn format_number(n: u32) -> &'static str {
     }
 }
 
+/// Preset name with the selection reason, e.g. "Safe Mode (fallback)"
+fn profile_label(profile: ProfileSelection) -> String {
+    use core::fmt::Write;
+
+    let mut label = String::new();
+    let _ = write!(label, "{} ({})", profile.preset, profile.source);
+    label
+}
+
+/// Center `text` in a field of `width` columns
+fn center(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    let pad = width.saturating_sub(len);
+    let mut line = String::with_capacity(text.len() + pad);
+    line.extend(core::iter::repeat(' ').take(pad / 2));
+    line.push_str(text);
+    line.extend(core::iter::repeat(' ').take(pad - pad / 2));
+    line
+}
+
 /// Convert char to static str
 fn char_to_str(c: char) -> &'static str {
     match c {
@@ -677,9 +1436,24 @@ fn test_format_number() {
         assert_eq!(format_number(10), "10");
     }
 
+    #[test]
+    fn test_center() {
+        assert_eq!(center("ab", 6), "  ab  ");
+        assert_eq!(center("Démarrer", 9), "Démarrer ");
+        assert_eq!(center("too long", 4), "too long");
+    }
+
     #[test]
     fn test_menu_result() {
         let result = MenuResult::Boot(0);
         assert!(matches!(result, MenuResult::Boot(0)));
     }
+
+    #[test]
+    fn test_profile_label() {
+        use crate::profiles::PresetType;
+
+        let profile = ProfileSelection { preset: PresetType::Safe, source: SelectionSource::Fallback };
+        assert_eq!(profile_label(profile), "Safe Mode (fallback)");
+    }
 }
@@ -0,0 +1,228 @@
+//! Serial Control Protocol
+//!
+//! Line protocol that drives the boot menu over a serial port when
+//! `serialctl = on` is set in the boot configuration, so test farms can run
+//! boot matrices without scraping the screen.
+//!
+//! Each command is one line; the reply is zero or more data lines followed
+//! by `ok` or `err <reason>`. The menu announces itself with
+//! `helix-serialctl <version> ready` when it starts listening.
+//!
+//! ```text
+//! > list
+//! < entry 0 * Helix
+//! < entry 1 - Helix (recovery)
+//! < ok
+//! > select 1
+//! < ok
+//! > cmdline quiet loglevel=3
+//! < ok
+//! > boot
+//! < ok
+//! ```
+
+use crate::protocols::serial::SerialPort;
+use alloc::string::String;
+
+/// Protocol version reported in the greeting and by `ping`
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Longest accepted command line (bytes)
+pub const MAX_LINE: usize = 512;
+
+// =============================================================================
+// LINK
+// =============================================================================
+
+/// Byte transport for the control protocol
+pub trait ControlLink {
+    /// Next received byte, if any (never blocks)
+    fn read_byte(&mut self) -> Option<u8>;
+
+    /// Send text; errors are dropped since the peer retries on timeout
+    fn write_str(&mut self, s: &str);
+}
+
+impl ControlLink for SerialPort {
+    fn read_byte(&mut self) -> Option<u8> {
+        SerialPort::read_byte(self).ok().flatten()
+    }
+
+    fn write_str(&mut self, s: &str) {
+        let _ = SerialPort::write_str(self, s);
+    }
+}
+
+// =============================================================================
+// COMMANDS
+// =============================================================================
+
+/// Control command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// Check the link
+    Ping,
+    /// List commands
+    Help,
+    /// List the visible entries
+    List,
+    /// Select a visible entry by index
+    Select(usize),
+    /// Replace the selected entry's command line (empty restores it)
+    Cmdline(String),
+    /// Report menu state and boot diagnostics
+    Diag,
+    /// Boot the selected entry
+    Boot,
+    /// Continue with the default boot
+    Continue,
+    /// Enter the shell
+    Shell,
+    /// Reboot
+    Reboot,
+    /// Shut down
+    Shutdown,
+}
+
+impl ControlCommand {
+    /// Command names, as listed by `help`
+    pub const NAMES: [&'static str; 11] = [
+        "ping", "help", "list", "select <n>", "cmdline [args]", "diag",
+        "boot", "continue", "shell", "reboot", "shutdown",
+    ];
+
+    /// Parse a command line
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let line = line.trim();
+        let (name, rest) = match line.split_once(' ') {
+            Some((name, rest)) => (name, rest.trim()),
+            None => (line, ""),
+        };
+
+        let command = match name.to_ascii_lowercase().as_str() {
+            "ping" => Self::Ping,
+            "help" => Self::Help,
+            "list" => Self::List,
+            "select" => Self::Select(rest.parse().map_err(|_| "expected entry index")?),
+            "cmdline" => return Ok(Self::Cmdline(String::from(rest))),
+            "diag" => Self::Diag,
+            "boot" => Self::Boot,
+            "continue" => Self::Continue,
+            "shell" => Self::Shell,
+            "reboot" => Self::Reboot,
+            "shutdown" => Self::Shutdown,
+            "" => return Err("empty command"),
+            _ => return Err("unknown command"),
+        };
+
+        if !rest.is_empty() && !matches!(command, Self::Select(_)) {
+            return Err("unexpected argument");
+        }
+        Ok(command)
+    }
+}
+
+// =============================================================================
+// LINE INPUT
+// =============================================================================
+
+/// Assembles received bytes into command lines
+#[derive(Debug, Clone, Default)]
+pub struct LineReader {
+    /// Partial line
+    line: String,
+    /// Current line exceeded [`MAX_LINE`] and is being discarded
+    overflow: bool,
+}
+
+impl LineReader {
+    /// Create empty reader
+    pub const fn new() -> Self {
+        Self { line: String::new(), overflow: false }
+    }
+
+    /// Add a byte; returns the line when it ends (`Err` if it was too long)
+    pub fn push(&mut self, byte: u8) -> Option<Result<String, &'static str>> {
+        match byte {
+            b'\r' | b'\n' => {
+                if core::mem::take(&mut self.overflow) {
+                    self.line.clear();
+                    return Some(Err("line too long"));
+                }
+                if self.line.is_empty() {
+                    return None; // CR LF, or a blank line
+                }
+                Some(Ok(core::mem::take(&mut self.line)))
+            }
+            _ if self.overflow => None,
+            0x20..=0x7E => {
+                if self.line.len() == MAX_LINE {
+                    self.overflow = true;
+                } else {
+                    self.line.push(byte as char);
+                }
+                None
+            }
+            _ => None, // Control characters and non-ASCII
+        }
+    }
+
+    /// Read what the link has buffered; returns the first complete line
+    pub fn poll(&mut self, link: &mut dyn ControlLink) -> Option<Result<String, &'static str>> {
+        while let Some(byte) = link.read_byte() {
+            if let Some(line) = self.push(byte) {
+                return Some(line);
+            }
+        }
+        None
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ControlCommand::parse("LIST"), Ok(ControlCommand::List));
+        assert_eq!(ControlCommand::parse(" select 2 "), Ok(ControlCommand::Select(2)));
+        assert_eq!(
+            ControlCommand::parse("cmdline quiet  root=/dev/sda2"),
+            Ok(ControlCommand::Cmdline(String::from("quiet  root=/dev/sda2")))
+        );
+        assert_eq!(ControlCommand::parse("cmdline"), Ok(ControlCommand::Cmdline(String::new())));
+        assert_eq!(ControlCommand::parse("select x"), Err("expected entry index"));
+        assert_eq!(ControlCommand::parse("boot now"), Err("unexpected argument"));
+        assert_eq!(ControlCommand::parse("format c:"), Err("unknown command"));
+    }
+
+    #[test]
+    fn test_line_reader() {
+        struct Script(&'static [u8]);
+        impl ControlLink for Script {
+            fn read_byte(&mut self) -> Option<u8> {
+                let (&byte, rest) = self.0.split_first()?;
+                self.0 = rest;
+                Some(byte)
+            }
+            fn write_str(&mut self, _s: &str) {}
+        }
+
+        let mut link = Script(b"list\r\n\x1bdiag\n");
+        let mut reader = LineReader::new();
+        assert_eq!(reader.poll(&mut link), Some(Ok(String::from("list"))));
+        assert_eq!(reader.poll(&mut link), Some(Ok(String::from("diag"))));
+        assert_eq!(reader.poll(&mut link), None);
+
+        for _ in 0..MAX_LINE + 1 {
+            assert!(reader.push(b'x').is_none());
+        }
+        assert_eq!(reader.push(b'\n'), Some(Err("line too long")));
+        assert!(reader.push(b'a').is_none());
+        assert_eq!(reader.push(b'\n'), Some(Ok(String::from("a"))));
+    }
+}
@@ -0,0 +1,341 @@
+//! Typed MMIO Register Access for Helix UEFI Bootloader
+//!
+//! Volatile, bounds-checked access to memory-mapped device registers.
+//! Drivers describe their register layout once with [`register_block!`]
+//! instead of adding offsets to raw base pointers.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! register_block! {
+//!     /// Example device registers
+//!     pub struct ExampleRegs, size = 0x10 {
+//!         /// Status register
+//!         0x00 => status: u32, ReadOnly;
+//!         /// Control register
+//!         0x04 => control: u32, ReadWrite;
+//!         /// Doorbell
+//!         0x08 => doorbell: u32, WriteOnly;
+//!     }
+//! }
+//!
+//! let regs = unsafe { ExampleRegs::new(bar0) };
+//! regs.control().modify(|v| v | 1);
+//! while regs.status().read() & 1 == 0 {}
+//! ```
+//!
+//! # Canaries
+//!
+//! - Register offsets are checked for natural alignment at compile time.
+//! - Dynamic accesses ([`MmioRegion::read`] / [`MmioRegion::write`]) are
+//!   checked against the region size and alignment; violations return
+//!   [`MmioError`] instead of touching memory.
+//! - Access permissions are part of the type: a read-only register has no
+//!   `write` method.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::ptr;
+
+// =============================================================================
+// ACCESS MARKERS
+// =============================================================================
+
+/// Read-only register marker
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOnly;
+
+/// Write-only register marker
+#[derive(Debug, Clone, Copy)]
+pub struct WriteOnly;
+
+/// Read-write register marker
+#[derive(Debug, Clone, Copy)]
+pub struct ReadWrite;
+
+/// Registers that can be read
+pub trait Readable {}
+
+/// Registers that can be written
+pub trait Writable {}
+
+impl Readable for ReadOnly {}
+impl Readable for ReadWrite {}
+impl Writable for WriteOnly {}
+impl Writable for ReadWrite {}
+
+/// Integer types usable as register values
+pub trait RegisterValue: Copy {}
+
+impl RegisterValue for u8 {}
+impl RegisterValue for u16 {}
+impl RegisterValue for u32 {}
+impl RegisterValue for u64 {}
+
+// =============================================================================
+// MMIO ERRORS
+// =============================================================================
+
+/// MMIO access errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MmioError {
+    /// Access outside the mapped region
+    OutOfBounds {
+        /// Offset of the access
+        offset: usize,
+        /// Region size
+        size: usize,
+    },
+    /// Offset not naturally aligned for the access width
+    Misaligned {
+        /// Offset of the access
+        offset: usize,
+        /// Access width
+        width: usize,
+    },
+}
+
+impl fmt::Display for MmioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MmioError::OutOfBounds { offset, size } => {
+                write!(f, "MMIO access at {:#x} outside {:#x}-byte region", offset, size)
+            }
+            MmioError::Misaligned { offset, width } => {
+                write!(f, "Misaligned {}-byte MMIO access at {:#x}", width, offset)
+            }
+        }
+    }
+}
+
+// =============================================================================
+// MMIO REGION
+// =============================================================================
+
+/// A mapped MMIO window
+#[derive(Debug, Clone, Copy)]
+pub struct MmioRegion {
+    base: usize,
+    size: usize,
+}
+
+impl MmioRegion {
+    /// Create a region
+    ///
+    /// # Safety
+    ///
+    /// `base..base + size` must be mapped device memory for as long as the
+    /// region (or any register block built on it) is used.
+    pub const unsafe fn new(base: usize, size: usize) -> Self {
+        Self { base, size }
+    }
+
+    /// Base address
+    pub const fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Region size
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Check an access of `T` at `offset`
+    fn check<T>(&self, offset: usize) -> Result<usize, MmioError> {
+        let width = size_of::<T>();
+        if offset % width != 0 {
+            return Err(MmioError::Misaligned { offset, width });
+        }
+        match offset.checked_add(width) {
+            Some(end) if end <= self.size => Ok(self.base + offset),
+            _ => Err(MmioError::OutOfBounds { offset, size: self.size }),
+        }
+    }
+
+    /// Volatile read at a byte offset
+    pub fn read<T: RegisterValue>(&self, offset: usize) -> Result<T, MmioError> {
+        let addr = self.check::<T>(offset)?;
+        // SAFETY: In bounds and aligned; mapping guaranteed by `new`
+        Ok(unsafe { ptr::read_volatile(addr as *const T) })
+    }
+
+    /// Volatile write at a byte offset
+    pub fn write<T: RegisterValue>(&self, offset: usize, value: T) -> Result<(), MmioError> {
+        let addr = self.check::<T>(offset)?;
+        // SAFETY: In bounds and aligned; mapping guaranteed by `new`
+        unsafe { ptr::write_volatile(addr as *mut T, value) };
+        Ok(())
+    }
+
+    /// Typed register at a fixed offset
+    ///
+    /// # Safety
+    ///
+    /// `offset` must be in bounds and aligned for `T` (checked at compile
+    /// time by [`register_block!`]).
+    pub const unsafe fn reg<T: RegisterValue, A>(&self, offset: usize) -> Reg<'_, T, A> {
+        Reg {
+            addr: self.base + offset,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// =============================================================================
+// TYPED REGISTER
+// =============================================================================
+
+/// A typed register within an [`MmioRegion`]
+pub struct Reg<'a, T, A> {
+    addr: usize,
+    _marker: PhantomData<(&'a MmioRegion, T, A)>,
+}
+
+impl<T: RegisterValue, A> Reg<'_, T, A> {
+    /// Register address
+    pub const fn addr(&self) -> usize {
+        self.addr
+    }
+}
+
+impl<T: RegisterValue, A: Readable> Reg<'_, T, A> {
+    /// Volatile read
+    #[inline]
+    pub fn read(&self) -> T {
+        // SAFETY: Offset validated when the register was created
+        unsafe { ptr::read_volatile(self.addr as *const T) }
+    }
+}
+
+impl<T: RegisterValue, A: Writable> Reg<'_, T, A> {
+    /// Volatile write
+    #[inline]
+    pub fn write(&self, value: T) {
+        // SAFETY: Offset validated when the register was created
+        unsafe { ptr::write_volatile(self.addr as *mut T, value) }
+    }
+}
+
+impl<T: RegisterValue> Reg<'_, T, ReadWrite> {
+    /// Read-modify-write
+    #[inline]
+    pub fn modify(&self, f: impl FnOnce(T) -> T) {
+        self.write(f(self.read()));
+    }
+}
+
+// =============================================================================
+// REGISTER BLOCK MACRO
+// =============================================================================
+
+/// Generate a typed register map over an [`MmioRegion`]
+///
+/// Each entry is `offset => name: type, Access;`. The generated struct has
+/// an unsafe `new(base)` constructor, a `region()` accessor for dynamic
+/// offsets, and one method per register.
+#[macro_export]
+macro_rules! register_block {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident, size = $size:literal {
+            $(
+                $(#[$reg_meta:meta])*
+                $offset:literal => $reg:ident : $ty:ty, $access:ident;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        $vis struct $name {
+            region: $crate::mmio::MmioRegion,
+        }
+
+        impl $name {
+            /// Size of the register block in bytes
+            pub const SIZE: usize = $size;
+
+            /// Create the register map at `base`
+            ///
+            /// # Safety
+            ///
+            /// `base` must point to mapped registers of this device, at
+            /// least [`Self::SIZE`] bytes long.
+            pub const unsafe fn new(base: usize) -> Self {
+                Self {
+                    // SAFETY: Forwarded from the caller
+                    region: unsafe { $crate::mmio::MmioRegion::new(base, $size) },
+                }
+            }
+
+            /// Underlying region, for bounds-checked dynamic offsets
+            pub const fn region(&self) -> &$crate::mmio::MmioRegion {
+                &self.region
+            }
+
+            $(
+                $(#[$reg_meta])*
+                #[inline]
+                pub fn $reg(&self) -> $crate::mmio::Reg<'_, $ty, $crate::mmio::$access> {
+                    const _: () = {
+                        assert!($offset % core::mem::size_of::<$ty>() == 0, "misaligned register");
+                        assert!($offset + core::mem::size_of::<$ty>() <= $size, "register outside block");
+                    };
+                    // SAFETY: Offset checked at compile time above
+                    unsafe { self.region.reg($offset) }
+                }
+            )*
+        }
+    };
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    register_block! {
+        /// Test registers
+        struct TestRegs, size = 0x10 {
+            /// Status
+            0x00 => status: u32, ReadOnly;
+            /// Control
+            0x04 => control: u32, ReadWrite;
+            /// Address
+            0x08 => addr: u64, WriteOnly;
+        }
+    }
+
+    #[test]
+    fn test_register_block() {
+        let mut mem = [0u64; 2];
+        mem[0] = 0x1234;
+        let regs = unsafe { TestRegs::new(mem.as_mut_ptr() as usize) };
+
+        assert_eq!(regs.status().read(), 0x1234);
+        regs.control().modify(|v| v | 0x8000_0000);
+        regs.addr().write(0xDEAD_BEEF_0000);
+        assert_eq!(regs.control().read(), 0x8000_0000);
+        assert_eq!(mem[1], 0xDEAD_BEEF_0000);
+    }
+
+    #[test]
+    fn test_region_checks() {
+        let mut mem = [0u32; 4];
+        let region = unsafe { MmioRegion::new(mem.as_mut_ptr() as usize, 16) };
+
+        assert_eq!(region.write::<u32>(12, 7), Ok(()));
+        assert_eq!(region.read::<u32>(12), Ok(7));
+        assert_eq!(
+            region.read::<u32>(16),
+            Err(MmioError::OutOfBounds { offset: 16, size: 16 })
+        );
+        assert_eq!(
+            region.read::<u64>(4),
+            Err(MmioError::Misaligned { offset: 4, width: 8 })
+        );
+    }
+}
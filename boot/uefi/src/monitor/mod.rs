@@ -39,6 +39,7 @@
 //! - Error and warning collection
 //! - Health status monitoring
 //! - Performance metrics
+//! - Persistent per-stage boot time history with regression flagging
 
 #![no_std]
 
@@ -146,6 +147,35 @@ pub enum BootStage {
 }
 
 impl BootStage {
+    /// Number of stages
+    pub const COUNT: usize = 17;
+
+    /// All stages in boot order
+    pub const ALL: [BootStage; Self::COUNT] = [
+        BootStage::FirmwareInit,
+        BootStage::MemoryDetect,
+        BootStage::MemoryInit,
+        BootStage::CpuInit,
+        BootStage::ChipsetInit,
+        BootStage::BoardInit,
+        BootStage::PciEnumerate,
+        BootStage::ConsoleInit,
+        BootStage::DriverLoad,
+        BootStage::ProtocolInstall,
+        BootStage::BootOptionEnum,
+        BootStage::BootOptionSelect,
+        BootStage::BootLoaderLoad,
+        BootStage::BootLoaderStart,
+        BootStage::KernelLoad,
+        BootStage::ExitBootServices,
+        BootStage::KernelStart,
+    ];
+
+    /// Position in [`BootStage::ALL`]
+    pub const fn index(&self) -> usize {
+        *self as usize
+    }
+
     /// Get stage name
     pub const fn name(&self) -> &'static str {
         match self {
@@ -468,6 +498,335 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
+// =============================================================================
+// BOOT HISTORY
+// =============================================================================
+
+/// Boots kept in the history ring
+pub const HISTORY_LEN: usize = 16;
+
+/// Most recent boots the baseline is the median of
+pub const BASELINE_WINDOW: usize = 8;
+
+/// Boots needed before regressions are flagged
+pub const BASELINE_MIN_BOOTS: usize = 3;
+
+/// Slowdown over the baseline flagged as a regression (percent)
+pub const REGRESSION_PERCENT: u64 = 25;
+
+/// Smallest slowdown flagged, so jitter in short stages is ignored (µs)
+pub const REGRESSION_MIN_US: u64 = 50_000;
+
+/// Serialized history magic ("HBHI")
+pub const HISTORY_MAGIC: u32 = 0x4948_4248;
+
+/// Serialized history version
+pub const HISTORY_VERSION: u16 = 1;
+
+/// NV variable holding the history
+pub const HISTORY_VARIABLE: &str = "HelixBootHistory";
+
+/// ESP file holding the history when variables are unavailable
+pub const HISTORY_FILE: &str = "\\EFI\\HELIX\\boothist.bin";
+
+/// Stage timings of one boot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootRecord {
+    /// Boot number
+    pub sequence: u32,
+    /// Duration per stage in [`BootStage::ALL`] order (µs, 0 = not timed)
+    pub stage_us: [u32; BootStage::COUNT],
+    /// Regressed stages (bit = stage index, [`BootRecord::TOTAL_BIT`] = total)
+    pub regressed: u32,
+}
+
+impl BootRecord {
+    /// Regression bit of the total boot time
+    pub const TOTAL_BIT: u32 = 1 << 31;
+
+    /// Serialized size
+    pub const SIZE: usize = 8 + 4 * BootStage::COUNT;
+
+    /// Create empty record
+    pub const fn new() -> Self {
+        Self { sequence: 0, stage_us: [0; BootStage::COUNT], regressed: 0 }
+    }
+
+    /// Record completed timings
+    pub fn from_timings(timings: &[BootTiming]) -> Self {
+        let mut record = Self::new();
+        for timing in timings {
+            if let Some(duration) = timing.duration() {
+                record.set(timing.stage, duration);
+            }
+        }
+        record
+    }
+
+    /// Set a stage duration
+    pub fn set(&mut self, stage: BootStage, duration: Duration) {
+        self.stage_us[stage.index()] = duration.as_micros().min(u32::MAX as u64) as u32;
+    }
+
+    /// Stage duration (µs) if timed
+    pub const fn stage(&self, stage: BootStage) -> Option<u64> {
+        match self.stage_us[stage.index()] {
+            0 => None,
+            us => Some(us as u64),
+        }
+    }
+
+    /// Sum of all stage durations (µs)
+    pub fn total_us(&self) -> u64 {
+        self.stage_us.iter().map(|&us| us as u64).sum()
+    }
+
+    /// Stage duration, or the total for `None`
+    fn value(&self, stage: Option<BootStage>) -> Option<u64> {
+        match stage {
+            Some(stage) => self.stage(stage),
+            None => Some(self.total_us()).filter(|&us| us > 0),
+        }
+    }
+
+    /// Check if any stage or the total regressed
+    pub const fn is_regressed(&self) -> bool {
+        self.regressed != 0
+    }
+
+    /// Check if a stage regressed
+    pub const fn stage_regressed(&self, stage: BootStage) -> bool {
+        self.regressed & (1 << stage.index()) != 0
+    }
+
+    fn write(&self, out: &mut [u8]) {
+        out[0..4].copy_from_slice(&self.sequence.to_le_bytes());
+        out[4..8].copy_from_slice(&self.regressed.to_le_bytes());
+        for (i, us) in self.stage_us.iter().enumerate() {
+            out[8 + i * 4..12 + i * 4].copy_from_slice(&us.to_le_bytes());
+        }
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        let word = |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+        let mut record = Self { sequence: word(0), regressed: word(4), ..Self::new() };
+        for i in 0..BootStage::COUNT {
+            record.stage_us[i] = word(8 + i * 4);
+        }
+        record
+    }
+}
+
+impl Default for BootRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Ring of recent boot records with rolling baselines
+///
+/// Persisted as bytes in [`HISTORY_VARIABLE`] (or [`HISTORY_FILE`]) and
+/// handed to the kernel, which serves [`fmt::Display`]'s report as
+/// `/proc/boot_history`.
+#[derive(Debug, Clone)]
+pub struct BootHistory {
+    /// Records, oldest overwritten first
+    records: [BootRecord; HISTORY_LEN],
+    /// Slot of the next record
+    next: usize,
+    /// Record count
+    len: usize,
+    /// Sequence number of the next record
+    next_sequence: u32,
+}
+
+impl BootHistory {
+    /// Header size when serialized
+    const HEADER_SIZE: usize = 12;
+
+    /// Largest serialized size
+    pub const MAX_SIZE: usize = Self::HEADER_SIZE + HISTORY_LEN * BootRecord::SIZE;
+
+    /// Create empty history
+    pub const fn new() -> Self {
+        Self {
+            records: [BootRecord::new(); HISTORY_LEN],
+            next: 0,
+            len: 0,
+            next_sequence: 1,
+        }
+    }
+
+    /// Record count
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if empty
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Record by age (0 = latest)
+    pub fn get(&self, age: usize) -> Option<&BootRecord> {
+        if age >= self.len {
+            return None;
+        }
+        Some(&self.records[(self.next + HISTORY_LEN - 1 - age) % HISTORY_LEN])
+    }
+
+    /// Records, latest first
+    pub fn iter(&self) -> impl Iterator<Item = &BootRecord> {
+        (0..self.len).filter_map(move |age| self.get(age))
+    }
+
+    /// Latest record
+    pub fn latest(&self) -> Option<&BootRecord> {
+        self.get(0)
+    }
+
+    /// Median of a stage (or the total for `None`) over the last
+    /// [`BASELINE_WINDOW`] boots; `None` until [`BASELINE_MIN_BOOTS`] have it
+    pub fn baseline(&self, stage: Option<BootStage>) -> Option<u64> {
+        let mut samples = [0u64; BASELINE_WINDOW];
+        let mut count = 0;
+        for record in self.iter().take(BASELINE_WINDOW) {
+            if let Some(us) = record.value(stage) {
+                samples[count] = us;
+                count += 1;
+            }
+        }
+        if count < BASELINE_MIN_BOOTS {
+            return None;
+        }
+        let samples = &mut samples[..count];
+        samples.sort_unstable();
+        Some(samples[count / 2])
+    }
+
+    /// Check a duration against a baseline
+    fn regressed(duration: u64, baseline: u64) -> bool {
+        let slower = duration.saturating_sub(baseline);
+        slower >= REGRESSION_MIN_US && slower * 100 > baseline * REGRESSION_PERCENT
+    }
+
+    /// Flag regressions against the current baselines, number the record
+    /// and add it; returns the stored record
+    pub fn push(&mut self, mut record: BootRecord) -> BootRecord {
+        record.sequence = self.next_sequence;
+        record.regressed = 0;
+
+        for stage in BootStage::ALL {
+            if let (Some(us), Some(baseline)) = (record.stage(stage), self.baseline(Some(stage))) {
+                if Self::regressed(us, baseline) {
+                    record.regressed |= 1 << stage.index();
+                }
+            }
+        }
+        if let Some(baseline) = self.baseline(None) {
+            if Self::regressed(record.total_us(), baseline) {
+                record.regressed |= BootRecord::TOTAL_BIT;
+            }
+        }
+
+        self.records[self.next] = record;
+        self.next = (self.next + 1) % HISTORY_LEN;
+        self.len = (self.len + 1).min(HISTORY_LEN);
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        record
+    }
+
+    /// Serialize into `out`; returns the size, or `None` if `out` is too small
+    pub fn to_bytes(&self, out: &mut [u8]) -> Option<usize> {
+        let size = Self::HEADER_SIZE + self.len * BootRecord::SIZE;
+        if out.len() < size {
+            return None;
+        }
+        out[0..4].copy_from_slice(&HISTORY_MAGIC.to_le_bytes());
+        out[4..6].copy_from_slice(&HISTORY_VERSION.to_le_bytes());
+        out[6] = self.len as u8;
+        out[7] = BootStage::COUNT as u8;
+        out[8..12].copy_from_slice(&self.next_sequence.to_le_bytes());
+
+        // Oldest first, so reading back replays them in order
+        for (i, age) in (0..self.len).rev().enumerate() {
+            let at = Self::HEADER_SIZE + i * BootRecord::SIZE;
+            self.records[(self.next + HISTORY_LEN - 1 - age) % HISTORY_LEN].write(&mut out[at..at + BootRecord::SIZE]);
+        }
+        Some(size)
+    }
+
+    /// Deserialize; `None` if the data is not a compatible history
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < Self::HEADER_SIZE
+            || bytes[0..4] != HISTORY_MAGIC.to_le_bytes()
+            || bytes[4..6] != HISTORY_VERSION.to_le_bytes()
+            || bytes[7] as usize != BootStage::COUNT
+        {
+            return None;
+        }
+        let len = bytes[6] as usize;
+        if len > HISTORY_LEN || bytes.len() < Self::HEADER_SIZE + len * BootRecord::SIZE {
+            return None;
+        }
+
+        let mut history = Self::new();
+        for i in 0..len {
+            let at = Self::HEADER_SIZE + i * BootRecord::SIZE;
+            history.records[i] = BootRecord::read(&bytes[at..at + BootRecord::SIZE]);
+        }
+        history.len = len;
+        history.next = len % HISTORY_LEN;
+        history.next_sequence = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        Some(history)
+    }
+}
+
+impl Default for BootHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for BootHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "boot     total_ms  regressed")?;
+        for record in self.iter() {
+            write!(f, "#{:<7} {:>8}  ", record.sequence, record.total_us() / 1000)?;
+            if record.regressed & BootRecord::TOTAL_BIT != 0 {
+                write!(f, "total ")?;
+            }
+            for stage in BootStage::ALL {
+                if record.stage_regressed(stage) {
+                    write!(f, "{} ", stage)?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        let Some(latest) = self.latest() else {
+            return Ok(());
+        };
+        writeln!(f)?;
+        writeln!(f, "stage              last_ms  baseline_ms")?;
+        for stage in BootStage::ALL {
+            let Some(us) = latest.stage(stage) else {
+                continue;
+            };
+            write!(f, "{:<18} {:>7}", stage.name(), us / 1000)?;
+            match self.baseline(Some(stage)) {
+                Some(baseline) => write!(f, "  {:>11}", baseline / 1000)?,
+                None => write!(f, "  {:>11}", "-")?,
+            }
+            if latest.stage_regressed(stage) {
+                write!(f, "  !")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
 // =============================================================================
 // MEMORY TRACKING
 // =============================================================================
@@ -1082,6 +1441,59 @@ fn test_perf_counter() {
         assert_eq!(counter.average(), 116); // (100+200+50)/3 = 116
     }
 
+    #[test]
+    fn test_boot_history() {
+        let boot = |kernel_ms: u64| {
+            let mut record = BootRecord::new();
+            record.set(BootStage::BootLoaderStart, Duration::from_millis(200));
+            record.set(BootStage::KernelLoad, Duration::from_millis(kernel_ms));
+            record
+        };
+
+        let mut history = BootHistory::new();
+        for ms in [300, 310, 290] {
+            assert!(!history.push(boot(ms)).is_regressed());
+        }
+        assert_eq!(history.baseline(Some(BootStage::KernelLoad)), Some(300_000));
+        assert_eq!(history.baseline(None), Some(500_000));
+
+        // +20% is tolerated, +100% is flagged
+        assert!(!history.push(boot(360)).is_regressed());
+        let slow = history.push(boot(600));
+        assert!(slow.stage_regressed(BootStage::KernelLoad));
+        assert!(!slow.stage_regressed(BootStage::BootLoaderStart));
+        assert!(slow.regressed & BootRecord::TOTAL_BIT != 0);
+        assert_eq!(slow.sequence, 5);
+        assert_eq!(history.latest(), Some(&slow));
+
+        // Ring keeps the newest HISTORY_LEN boots
+        for _ in 0..HISTORY_LEN {
+            history.push(boot(300));
+        }
+        assert_eq!(history.len(), HISTORY_LEN);
+        assert_eq!(history.get(HISTORY_LEN - 1).unwrap().sequence, 6);
+    }
+
+    #[test]
+    fn test_boot_history_bytes() {
+        let mut history = BootHistory::new();
+        let mut record = BootRecord::new();
+        record.set(BootStage::KernelLoad, Duration::from_millis(42));
+        history.push(record);
+        history.push(record);
+
+        let mut bytes = [0u8; BootHistory::MAX_SIZE];
+        let size = history.to_bytes(&mut bytes).unwrap();
+        let restored = BootHistory::from_bytes(&bytes[..size]).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.latest().unwrap().sequence, 2);
+        assert_eq!(restored.latest().unwrap().stage(BootStage::KernelLoad), Some(42_000));
+        assert_eq!(restored.clone().push(record).sequence, 3);
+
+        assert!(BootHistory::from_bytes(&bytes[..8]).is_none());
+        assert!(history.to_bytes(&mut [0u8; 16]).is_none());
+    }
+
     #[test]
     fn test_memory_usage() {
         let usage = MemoryUsage {
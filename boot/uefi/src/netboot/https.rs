@@ -0,0 +1,431 @@
+//! HTTPS Boot Client
+//!
+//! Downloads boot images through the firmware `EFI_HTTP_PROTOCOL`. TLS is
+//! terminated by the firmware, which validates the server against the
+//! `TlsCaCertificate` variable; when the boot settings pin root CAs the
+//! variable is narrowed to those roots for the duration of the transfer.
+
+use alloc::vec::Vec;
+
+use super::{HttpStatus, HttpUrl};
+use crate::error::{Error, Result};
+use crate::raw::protocols::http::*;
+use crate::raw::types::*;
+use crate::security::hash::Sha256;
+use crate::security::secureboot::{SignatureDatabase, SignatureEntryType, SignatureList};
+use crate::services::boot::try_boot_services;
+use crate::services::events::Timeout;
+use crate::services::variables::Variable;
+use crate::settings::NetworkSettings;
+
+/// Firmware variable holding the TLS trust anchors
+pub const TLS_CA_VARIABLE: &str = "TlsCaCertificate";
+
+/// Receive chunk size
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Default transfer timeout (milliseconds)
+const DEFAULT_TIMEOUT_MS: u32 = 30_000;
+
+// =============================================================================
+// TRUST ANCHORS
+// =============================================================================
+
+/// Narrow a `TlsCaCertificate` database to the pinned roots
+///
+/// Only X.509 entries whose SHA-256 fingerprint is pinned are kept, one
+/// signature list per certificate since their sizes differ.
+pub fn pin_ca_database(data: &[u8], pins: &[[u8; 32]]) -> Result<Vec<u8>> {
+    let database = SignatureDatabase::parse(data).map_err(|_| Error::InvalidParameter)?;
+
+    let mut pinned = SignatureDatabase::new();
+    for entry in database.lists.iter().flat_map(|list| list.entries.iter()) {
+        if entry.entry_type != SignatureEntryType::X509 {
+            continue;
+        }
+        if !pins.contains(&Sha256::digest(&entry.data)) {
+            continue;
+        }
+
+        let mut list = SignatureList::new(SignatureEntryType::X509);
+        list.add_entry(entry.clone());
+        pinned.add_list(list);
+    }
+
+    if pinned.lists.is_empty() {
+        return Err(Error::SecurityViolation);
+    }
+
+    Ok(pinned.to_bytes())
+}
+
+/// Pinned trust anchors, restored on drop
+struct TrustAnchors {
+    original: Option<Variable>,
+}
+
+impl TrustAnchors {
+    /// Install the pinned subset of the firmware trust anchors
+    fn install(pins: &[[u8; 32]]) -> Result<Self> {
+        // Without trust anchors the firmware skips server verification
+        let original = Variable::read(TLS_CA_VARIABLE, &guids::TLS_CA_CERTIFICATE)
+            .map_err(|_| Error::SecurityViolation)?;
+
+        if pins.is_empty() {
+            return Ok(Self { original: None });
+        }
+
+        let data = pin_ca_database(&original.data, pins)?;
+        if data == original.data {
+            return Ok(Self { original: None });
+        }
+
+        let mut pinned = original.clone();
+        pinned.data = data;
+        pinned.write().map_err(Error::from_status)?;
+
+        Ok(Self { original: Some(original) })
+    }
+}
+
+impl Drop for TrustAnchors {
+    fn drop(&mut self) {
+        if let Some(original) = &self.original {
+            let _ = original.write();
+        }
+    }
+}
+
+// =============================================================================
+// CONNECTION
+// =============================================================================
+
+/// HTTP child instance
+struct HttpConnection {
+    binding: *mut EfiServiceBindingProtocol,
+    child: Handle,
+    http: *mut EfiHttpProtocol,
+    timeout_ms: u32,
+}
+
+impl HttpConnection {
+    /// Create and configure a child on the first HTTP-capable NIC
+    fn open(timeout_ms: u32) -> Result<Self> {
+        let bs = try_boot_services().map_err(Error::from_status)?;
+
+        let handles = bs
+            .locate_handle_buffer(
+                LocateSearchType::ByProtocol,
+                Some(&EfiServiceBindingProtocol::HTTP_GUID),
+            )
+            .map_err(|_| Error::Unsupported)?;
+        let handle = *handles.handles().first().ok_or(Error::Unsupported)?;
+
+        let binding = bs
+            .handle_protocol::<EfiServiceBindingProtocol>(handle, &EfiServiceBindingProtocol::HTTP_GUID)
+            .map_err(Error::from_status)?;
+
+        let mut child = Handle::null();
+        let status = unsafe { ((*binding).create_child)(binding, &mut child) };
+        if status != Status::SUCCESS {
+            return Err(Error::from_status(status));
+        }
+
+        let http = match bs.handle_protocol::<EfiHttpProtocol>(child, &EfiHttpProtocol::GUID) {
+            Ok(http) => http,
+            Err(status) => {
+                unsafe { ((*binding).destroy_child)(binding, child) };
+                return Err(Error::from_status(status));
+            }
+        };
+
+        let connection = Self { binding, child, http, timeout_ms };
+
+        let mut access_point = EfiHttpv4AccessPoint {
+            use_default_address: 1,
+            ..Default::default()
+        };
+        let config = EfiHttpConfigData {
+            http_version: EfiHttpVersion::Http11,
+            timeout_ms,
+            local_address_is_ipv6: 0,
+            access_point: core::ptr::addr_of_mut!(access_point).cast(),
+        };
+        let status = unsafe { ((*http).configure)(http, &config) };
+        if status != Status::SUCCESS {
+            return Err(Error::from_status(status));
+        }
+
+        Ok(connection)
+    }
+
+    /// Queue a token and poll it to completion
+    fn complete(
+        &self,
+        token: &mut EfiHttpToken,
+        submit: unsafe extern "efiapi" fn(*mut EfiHttpProtocol, *mut EfiHttpToken) -> Status,
+    ) -> Result<()> {
+        let bs = try_boot_services().map_err(Error::from_status)?;
+
+        token.event = bs
+            .create_event(0, TPL_CALLBACK, None, core::ptr::null_mut())
+            .map_err(Error::from_status)?;
+        token.status = Status::NOT_READY;
+
+        let result = self.wait(token, submit);
+        let _ = bs.close_event(token.event);
+        result
+    }
+
+    fn wait(
+        &self,
+        token: &mut EfiHttpToken,
+        submit: unsafe extern "efiapi" fn(*mut EfiHttpProtocol, *mut EfiHttpToken) -> Status,
+    ) -> Result<()> {
+        let bs = try_boot_services().map_err(Error::from_status)?;
+
+        let status = unsafe { submit(self.http, token) };
+        if status != Status::SUCCESS {
+            return Err(Error::from_status(status));
+        }
+
+        let mut timeout = Timeout::new(u64::from(self.timeout_ms)).map_err(Error::from_status)?;
+        loop {
+            unsafe { ((*self.http).poll)(self.http) };
+
+            if bs.check_event(token.event).map_err(Error::from_status)? {
+                break;
+            }
+            if timeout.is_expired().map_err(Error::from_status)? {
+                unsafe { ((*self.http).cancel)(self.http, token) };
+                return Err(Error::Timeout);
+            }
+        }
+
+        if token.status != Status::SUCCESS {
+            return Err(Error::from_status(token.status));
+        }
+
+        Ok(())
+    }
+
+    /// GET a resource into memory
+    fn get(&self, url: &HttpUrl) -> Result<Vec<u8>> {
+        let bs = try_boot_services().map_err(Error::from_status)?;
+
+        // Request
+        let url_utf16: Vec<u16> = url.url().encode_utf16().chain(core::iter::once(0)).collect();
+        let mut request = EfiHttpRequestData {
+            method: EfiHttpMethod::Get,
+            url: url_utf16.as_ptr(),
+        };
+
+        let mut host: Vec<u8> = Vec::from(url.host().as_bytes());
+        let default_port = if url.is_https { 443 } else { 80 };
+        if url.port != default_port {
+            host.extend_from_slice(alloc::format!(":{}", url.port).as_bytes());
+        }
+        host.push(0);
+
+        let mut headers = [
+            EfiHttpHeader { field_name: c"Host".as_ptr().cast(), field_value: host.as_ptr() },
+            EfiHttpHeader { field_name: c"Accept".as_ptr().cast(), field_value: c"*/*".as_ptr().cast() },
+            EfiHttpHeader { field_name: c"User-Agent".as_ptr().cast(), field_value: c"Helix-UEFI".as_ptr().cast() },
+        ];
+
+        let mut message = EfiHttpMessage {
+            data: core::ptr::addr_of_mut!(request).cast(),
+            header_count: headers.len(),
+            headers: headers.as_mut_ptr(),
+            body_length: 0,
+            body: core::ptr::null_mut(),
+        };
+        let mut token = EfiHttpToken {
+            event: Event::null(),
+            status: Status::SUCCESS,
+            message: &mut message,
+        };
+        self.complete(&mut token, unsafe { (*self.http).request })?;
+
+        // Status line, headers and the first chunk of the body
+        let mut response = EfiHttpResponseData { status_code: EfiHttpStatusCode(0) };
+        let mut chunk = alloc::vec![0u8; CHUNK_SIZE];
+        let mut message = EfiHttpMessage {
+            data: core::ptr::addr_of_mut!(response).cast(),
+            header_count: 0,
+            headers: core::ptr::null_mut(),
+            body_length: chunk.len(),
+            body: chunk.as_mut_ptr().cast(),
+        };
+        let mut token = EfiHttpToken {
+            event: Event::null(),
+            status: Status::SUCCESS,
+            message: &mut message,
+        };
+        self.complete(&mut token, unsafe { (*self.http).response })?;
+
+        let content_length = unsafe { content_length(message.headers, message.header_count) };
+        let _ = bs.free_pool(message.headers.cast());
+
+        let status = HttpStatus(response.status_code.code());
+        if status != HttpStatus::OK {
+            return Err(if status == HttpStatus::NOT_FOUND { Error::NotFound } else { Error::HttpError });
+        }
+
+        let mut body = Vec::with_capacity(content_length.unwrap_or(CHUNK_SIZE));
+        body.extend_from_slice(&chunk[..message.body_length]);
+
+        // Remaining body
+        while content_length.is_none_or(|length| body.len() < length) {
+            let mut message = EfiHttpMessage {
+                data: core::ptr::null_mut(),
+                header_count: 0,
+                headers: core::ptr::null_mut(),
+                body_length: chunk.len(),
+                body: chunk.as_mut_ptr().cast(),
+            };
+            let mut token = EfiHttpToken {
+                event: Event::null(),
+                status: Status::SUCCESS,
+                message: &mut message,
+            };
+
+            match self.complete(&mut token, unsafe { (*self.http).response }) {
+                Ok(()) if message.body_length == 0 => break,
+                Ok(()) => body.extend_from_slice(&chunk[..message.body_length]),
+                // Without a length the server closing the connection ends the body
+                Err(_) if content_length.is_none() => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        if content_length.is_some_and(|length| body.len() != length) {
+            return Err(Error::HttpError);
+        }
+
+        Ok(body)
+    }
+}
+
+impl Drop for HttpConnection {
+    fn drop(&mut self) {
+        unsafe {
+            ((*self.http).configure)(self.http, core::ptr::null());
+            ((*self.binding).destroy_child)(self.binding, self.child);
+        }
+    }
+}
+
+/// Find `Content-Length` in a firmware header array
+unsafe fn content_length(headers: *const EfiHttpHeader, count: usize) -> Option<usize> {
+    if headers.is_null() {
+        return None;
+    }
+
+    let headers = core::slice::from_raw_parts(headers, count);
+    headers.iter().find_map(|header| {
+        let name = core::ffi::CStr::from_ptr(header.field_name.cast()).to_bytes();
+        if !name.eq_ignore_ascii_case(b"content-length") {
+            return None;
+        }
+        let value = core::ffi::CStr::from_ptr(header.field_value.cast()).to_str().ok()?;
+        value.trim().parse().ok()
+    })
+}
+
+// =============================================================================
+// CLIENT
+// =============================================================================
+
+/// HTTPS boot client
+#[derive(Debug, Clone)]
+pub struct HttpsClient {
+    /// Refuse plain HTTP
+    pub https_only: bool,
+    /// Pinned root CA fingerprints
+    pub pinned_ca: Vec<[u8; 32]>,
+    /// Transfer timeout (milliseconds)
+    pub timeout_ms: u32,
+}
+
+impl HttpsClient {
+    /// Create a client from the network boot settings
+    pub fn new(settings: &NetworkSettings) -> Self {
+        Self {
+            https_only: settings.https_only,
+            pinned_ca: settings.pinned_cas().to_vec(),
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+        }
+    }
+
+    /// Download a resource
+    pub fn get(&self, url: &str) -> Result<Vec<u8>> {
+        let url = HttpUrl::parse(url).ok_or(Error::InvalidParameter)?;
+        if !url.is_https && self.https_only {
+            return Err(Error::SecurityViolation);
+        }
+
+        let _anchors = if url.is_https {
+            Some(TrustAnchors::install(&self.pinned_ca)?)
+        } else {
+            None
+        };
+
+        HttpConnection::open(self.timeout_ms)?.get(&url)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::secureboot::SignatureEntry;
+
+    fn ca_database(certs: &[&[u8]]) -> Vec<u8> {
+        let mut database = SignatureDatabase::new();
+        for cert in certs {
+            let mut list = SignatureList::new(SignatureEntryType::X509);
+            list.add_entry(SignatureEntry::x509([0x11; 16], cert.to_vec()));
+            database.add_list(list);
+        }
+        database.to_bytes()
+    }
+
+    #[test]
+    fn test_pin_ca_database() {
+        let first: &[u8] = &[0x30, 0x82, 0x01, 0x0a, 0xaa];
+        let second: &[u8] = &[0x30, 0x82, 0x02, 0x0b, 0xbb, 0xcc];
+        let data = ca_database(&[first, second]);
+
+        let pinned = pin_ca_database(&data, &[Sha256::digest(second)]).unwrap();
+        let database = SignatureDatabase::parse(&pinned).unwrap();
+        assert_eq!(database.lists.len(), 1);
+        assert_eq!(database.lists[0].entries[0].data, second);
+
+        assert!(matches!(
+            pin_ca_database(&data, &[[0; 32]]),
+            Err(Error::SecurityViolation)
+        ));
+    }
+
+    #[test]
+    fn test_https_only() {
+        let settings = NetworkSettings {
+            https_only: true,
+            ..Default::default()
+        };
+        let client = HttpsClient::new(&settings);
+        assert!(matches!(
+            client.get("http://boot.example.com/kernel.efi"),
+            Err(Error::SecurityViolation)
+        ));
+    }
+
+    #[test]
+    fn test_without_boot_services() {
+        assert!(matches!(HttpConnection::open(1000), Err(Error::NotReady)));
+    }
+}
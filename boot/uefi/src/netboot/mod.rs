@@ -5,9 +5,9 @@
 //!
 //! # Features
 //!
-//! - PXE boot support
+//! - PXE boot support (DHCP with PXE options, then TFTP; see [`pxe`])
 //! - HTTP/HTTPS boot support
-//! - TFTP client
+//! - TFTP client with option negotiation and retransmit
 //! - DHCP integration
 //! - Network configuration
 
@@ -15,6 +15,12 @@
 
 use core::fmt;
 
+#[cfg(feature = "security")]
+pub mod https;
+pub mod pxe;
+#[cfg(feature = "network")]
+pub mod snp;
+
 // =============================================================================
 // NETWORK BOOT TYPES
 // =============================================================================
@@ -436,6 +442,23 @@ pub enum TftpError {
     OptionNegotiation = 8,
 }
 
+impl TftpError {
+    /// Error from its wire code
+    pub const fn from_code(code: u16) -> Self {
+        match code {
+            1 => Self::FileNotFound,
+            2 => Self::AccessViolation,
+            3 => Self::DiskFull,
+            4 => Self::IllegalOperation,
+            5 => Self::UnknownTid,
+            6 => Self::FileExists,
+            7 => Self::NoSuchUser,
+            8 => Self::OptionNegotiation,
+            _ => Self::NotDefined,
+        }
+    }
+}
+
 /// TFTP transfer mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum TftpMode {
@@ -944,10 +967,10 @@ pub fn progress(&self) -> u8 {
             NetBootState::Downloading => {
                 40 + match self.config.protocol {
                     NetBootProtocol::Pxe | NetBootProtocol::Tftp => {
-                        (self.tftp.progress() * 55) / 100
+                        (self.tftp.progress() as u16 * 55 / 100) as u8
                     }
                     NetBootProtocol::Http | NetBootProtocol::Https => {
-                        (self.http.progress() * 55) / 100
+                        (self.http.progress() as u16 * 55 / 100) as u8
                     }
                     _ => 0,
                 }
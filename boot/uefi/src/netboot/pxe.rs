@@ -0,0 +1,1023 @@
+//! PXE Boot
+//!
+//! DHCP discovery with the PXE client options (RFC 4578), then TFTP
+//! download of the kernel and initrd with block size, transfer size and
+//! timeout negotiation (RFC 2347-2349). The protocol engine runs over any
+//! [`Datagram`] transport; the firmware one is
+//! [`SnpDatagram`](super::snp::SnpDatagram).
+
+use alloc::vec::Vec;
+
+use super::{
+    DhcpConfig, DhcpMessageType, Ipv4Addr, MacAddr, NetBootConfig, NetBootManager,
+    NetBootProtocol, NetBootState, TftpError, TftpOpcode, TftpOptions, TftpTransfer,
+};
+use crate::error::{Error, Result};
+use crate::netstack::{dhcp_options, DhcpHeader, PxeBootMode};
+use crate::splash::SplashScreen;
+
+/// DHCP server port
+pub const DHCP_SERVER_PORT: u16 = 67;
+
+/// DHCP client port
+pub const DHCP_CLIENT_PORT: u16 = 68;
+
+/// TFTP server port
+pub const TFTP_PORT: u16 = 69;
+
+/// DHCP retransmit timeouts (milliseconds), as in the PXE specification
+const DHCP_TIMEOUTS_MS: [u32; 4] = [4_000, 8_000, 16_000, 32_000];
+
+/// Largest DHCP message we accept (option 57)
+const DHCP_MAX_MESSAGE: usize = 1472;
+
+/// Block size requested from TFTP servers (fits a 1500 byte MTU)
+pub const TFTP_BLOCK_SIZE: u16 = 1468;
+
+/// Unrelated datagrams tolerated while waiting for one reply
+const MAX_STRAY: usize = 64;
+
+/// Boot phases shown on the splash screen
+const PHASES: u8 = 3;
+
+// =============================================================================
+// TRANSPORT
+// =============================================================================
+
+/// Received datagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Received {
+    /// Source address
+    pub src: Ipv4Addr,
+    /// Source port
+    pub src_port: u16,
+    /// Payload length
+    pub len: usize,
+}
+
+/// UDP transport used by the PXE client
+pub trait Datagram {
+    /// Hardware address of the interface
+    fn mac(&self) -> MacAddr;
+
+    /// Apply the leased address
+    fn configure(&mut self, config: &DhcpConfig) -> Result<()>;
+
+    /// Send a datagram
+    fn send(&mut self, dst: Ipv4Addr, dst_port: u16, src_port: u16, data: &[u8]) -> Result<()>;
+
+    /// Wait up to `timeout_ms` for a datagram to `port`
+    ///
+    /// Returns `None` on timeout; datagrams to other ports are dropped.
+    fn recv(&mut self, port: u16, buf: &mut [u8], timeout_ms: u32) -> Result<Option<Received>>;
+}
+
+impl<T: Datagram + ?Sized> Datagram for &mut T {
+    fn mac(&self) -> MacAddr {
+        (**self).mac()
+    }
+
+    fn configure(&mut self, config: &DhcpConfig) -> Result<()> {
+        (**self).configure(config)
+    }
+
+    fn send(&mut self, dst: Ipv4Addr, dst_port: u16, src_port: u16, data: &[u8]) -> Result<()> {
+        (**self).send(dst, dst_port, src_port, data)
+    }
+
+    fn recv(&mut self, port: u16, buf: &mut [u8], timeout_ms: u32) -> Result<Option<Received>> {
+        (**self).recv(port, buf, timeout_ms)
+    }
+}
+
+// =============================================================================
+// DHCP
+// =============================================================================
+
+/// Parsed DHCP reply
+#[derive(Debug, Clone, Copy)]
+struct DhcpReply {
+    /// Message type
+    kind: u8,
+    /// Server identifier
+    server_id: Ipv4Addr,
+    /// Offered configuration
+    config: DhcpConfig,
+    /// Vendor class is "PXEClient"
+    pxe: bool,
+}
+
+impl DhcpReply {
+    /// Offer carrying an address (not a proxy offer)
+    fn has_address(&self) -> bool {
+        !self.config.client_ip.is_unspecified()
+    }
+
+    /// Offer naming something to boot
+    fn has_boot_file(&self) -> bool {
+        self.config.boot_file_len != 0
+    }
+}
+
+/// DHCP client sending the PXE options
+#[derive(Debug, Clone, Copy)]
+pub struct DhcpClient {
+    /// Client hardware address
+    pub mac: MacAddr,
+    /// Transaction ID
+    pub xid: u32,
+    /// Client system architecture (option 93)
+    pub arch: PxeBootMode,
+    /// Client machine identifier (option 97)
+    pub uuid: [u8; 16],
+    /// Retransmissions before giving up
+    pub retries: u8,
+    /// Longest retransmit timeout (milliseconds)
+    pub max_timeout_ms: u32,
+}
+
+impl DhcpClient {
+    /// Create a client for `mac`
+    pub fn new(mac: MacAddr) -> Self {
+        let o = mac.octets;
+        Self {
+            mac,
+            xid: u32::from_be_bytes([o[2], o[3], o[4], o[5]]) ^ 0x4845_4C58,
+            arch: if cfg!(target_arch = "aarch64") { PxeBootMode::UefiArm64 } else { PxeBootMode::UefiX64 },
+            uuid: [0; 16],
+            retries: DHCP_TIMEOUTS_MS.len() as u8,
+            max_timeout_ms: DHCP_TIMEOUTS_MS[DHCP_TIMEOUTS_MS.len() - 1],
+        }
+    }
+
+    /// Vendor class identifier (option 60)
+    fn vendor_class(&self) -> [u8; 32] {
+        let mut class = *b"PXEClient:Arch:00000:UNDI:003016";
+        let mut arch = self.arch.arch_id();
+        for digit in class[15..20].iter_mut().rev() {
+            *digit = b'0' + (arch % 10) as u8;
+            arch /= 10;
+        }
+        class
+    }
+
+    /// Build a DISCOVER or a REQUEST for `offer`, returning its length
+    fn build(&self, buf: &mut [u8], secs: u16, offer: Option<&DhcpReply>) -> usize {
+        buf.fill(0);
+        buf[0] = DhcpHeader::BOOTREQUEST;
+        buf[1] = 1; // Ethernet
+        buf[2] = 6;
+        buf[4..8].copy_from_slice(&self.xid.to_be_bytes());
+        buf[8..10].copy_from_slice(&secs.to_be_bytes());
+        buf[10..12].copy_from_slice(&0x8000u16.to_be_bytes()); // Broadcast replies
+        buf[28..34].copy_from_slice(&self.mac.octets);
+        buf[DhcpHeader::SIZE..DhcpHeader::SIZE + 4].copy_from_slice(&DhcpHeader::MAGIC_COOKIE);
+
+        let mut options = Options { buf, len: DhcpHeader::SIZE + 4 };
+        let kind = if offer.is_some() { DhcpMessageType::Request } else { DhcpMessageType::Discover };
+        options.push(dhcp_options::MESSAGE_TYPE, &[kind as u8]);
+        if let Some(offer) = offer {
+            options.push(dhcp_options::REQUESTED_IP, &offer.config.client_ip.octets);
+            options.push(dhcp_options::SERVER_ID, &offer.server_id.octets);
+        }
+        options.push(dhcp_options::MAX_MESSAGE_SIZE, &(DHCP_MAX_MESSAGE as u16).to_be_bytes());
+        options.push(
+            dhcp_options::PARAM_REQUEST,
+            &[
+                dhcp_options::SUBNET_MASK,
+                dhcp_options::ROUTER,
+                dhcp_options::DNS_SERVER,
+                dhcp_options::LEASE_TIME,
+                dhcp_options::SERVER_ID,
+                dhcp_options::VENDOR_SPECIFIC,
+                dhcp_options::VENDOR_CLASS_ID,
+                dhcp_options::TFTP_SERVER,
+                dhcp_options::BOOTFILE_NAME,
+            ],
+        );
+        options.push(dhcp_options::CLIENT_ARCH, &self.arch.arch_id().to_be_bytes());
+        options.push(dhcp_options::CLIENT_NDI, &[1, 3, 16]); // UNDI 3.16
+        let mut uuid = [0u8; 17];
+        uuid[1..].copy_from_slice(&self.uuid);
+        options.push(dhcp_options::CLIENT_UUID, &uuid);
+        options.push(dhcp_options::VENDOR_CLASS_ID, &self.vendor_class());
+        options.end()
+    }
+
+    /// Parse a reply to this client
+    fn parse(&self, data: &[u8]) -> Option<DhcpReply> {
+        let options_start = DhcpHeader::SIZE + 4;
+        if data.len() < options_start
+            || data[0] != DhcpHeader::BOOTREPLY
+            || data[4..8] != self.xid.to_be_bytes()
+            || data[28..34] != self.mac.octets
+            || data[DhcpHeader::SIZE..options_start] != DhcpHeader::MAGIC_COOKIE
+        {
+            return None;
+        }
+
+        let ip = |at: usize| Ipv4Addr { octets: [data[at], data[at + 1], data[at + 2], data[at + 3]] };
+        let mut reply = DhcpReply {
+            kind: 0,
+            server_id: Ipv4Addr::ANY,
+            config: DhcpConfig {
+                client_ip: ip(16),
+                tftp_server: ip(20),
+                ..DhcpConfig::default()
+            },
+            pxe: false,
+        };
+        let file = cstr(&data[108..236]);
+        if let Ok(file) = core::str::from_utf8(file) {
+            reply.config.set_boot_file(file);
+        }
+
+        let mut options = &data[options_start..];
+        while let [code, rest @ ..] = options {
+            match *code {
+                dhcp_options::PAD => {
+                    options = rest;
+                    continue;
+                }
+                dhcp_options::END => break,
+                _ => {}
+            }
+            let [len, rest @ ..] = rest else { break };
+            let Some(value) = rest.get(..usize::from(*len)) else { break };
+            options = &rest[value.len()..];
+
+            let addr = value.get(..4).map(|v| Ipv4Addr { octets: [v[0], v[1], v[2], v[3]] });
+            match *code {
+                dhcp_options::MESSAGE_TYPE => reply.kind = value.first().copied().unwrap_or(0),
+                dhcp_options::SERVER_ID => reply.server_id = addr.unwrap_or(Ipv4Addr::ANY),
+                dhcp_options::SUBNET_MASK => reply.config.subnet_mask = addr.unwrap_or(Ipv4Addr::ANY),
+                dhcp_options::ROUTER => reply.config.gateway = addr.unwrap_or(Ipv4Addr::ANY),
+                dhcp_options::DNS_SERVER => reply.config.dns_server = addr.unwrap_or(Ipv4Addr::ANY),
+                dhcp_options::LEASE_TIME if value.len() == 4 => {
+                    reply.config.lease_time = u32::from_be_bytes([value[0], value[1], value[2], value[3]]);
+                }
+                dhcp_options::VENDOR_CLASS_ID => reply.pxe = value.starts_with(b"PXEClient"),
+                dhcp_options::TFTP_SERVER => {
+                    if let Some(server) = core::str::from_utf8(cstr(value)).ok().and_then(parse_ipv4) {
+                        reply.config.tftp_server = server;
+                    }
+                }
+                dhcp_options::BOOTFILE_NAME => {
+                    if let Ok(file) = core::str::from_utf8(cstr(value)) {
+                        reply.config.set_boot_file(file);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        reply.config.dhcp_server = reply.server_id;
+        if reply.config.tftp_server.is_unspecified() {
+            reply.config.tftp_server = reply.server_id;
+        }
+        Some(reply)
+    }
+
+    /// Wait for replies of `kind`
+    ///
+    /// Offers are gathered until one names a boot file or an address and
+    /// a proxy offer have both arrived; the reply carrying the address is
+    /// returned with any proxy offer seen.
+    fn collect<T: Datagram>(
+        &self,
+        net: &mut T,
+        kind: DhcpMessageType,
+        timeout_ms: u32,
+    ) -> Result<(Option<DhcpReply>, Option<DhcpReply>)> {
+        let mut buf = [0u8; DHCP_MAX_MESSAGE];
+        let (mut lease, mut proxy) = (None::<DhcpReply>, None::<DhcpReply>);
+
+        for _ in 0..MAX_STRAY {
+            let Some(received) = net.recv(DHCP_CLIENT_PORT, &mut buf, timeout_ms)? else { break };
+            let Some(reply) = self.parse(&buf[..received.len]) else { continue };
+
+            if reply.kind == DhcpMessageType::Nak as u8 && kind == DhcpMessageType::Request {
+                return Ok((None, proxy));
+            }
+            if reply.kind != kind as u8 && !(kind == DhcpMessageType::Request && reply.kind == DhcpMessageType::Ack as u8) {
+                continue;
+            }
+
+            if reply.has_address() {
+                lease.get_or_insert(reply);
+            } else if reply.pxe && reply.has_boot_file() {
+                proxy.get_or_insert(reply);
+            }
+
+            match (&lease, &proxy) {
+                (Some(lease), _) if lease.has_boot_file() || kind != DhcpMessageType::Offer => break,
+                (Some(_), Some(_)) => break,
+                _ => {}
+            }
+        }
+
+        Ok((lease, proxy))
+    }
+
+    /// Run DISCOVER/OFFER/REQUEST/ACK and return the lease
+    ///
+    /// Boot file and server missing from the lease are taken from a proxy
+    /// DHCP offer.
+    pub fn run<T: Datagram>(&self, net: &mut T) -> Result<DhcpConfig> {
+        let mut buf = [0u8; DHCP_MAX_MESSAGE];
+        let mut secs = 0u16;
+
+        for attempt in 0..usize::from(self.retries) {
+            let timeout_ms = DHCP_TIMEOUTS_MS[attempt.min(DHCP_TIMEOUTS_MS.len() - 1)].min(self.max_timeout_ms);
+
+            let len = self.build(&mut buf, secs, None);
+            net.send(Ipv4Addr::BROADCAST, DHCP_SERVER_PORT, DHCP_CLIENT_PORT, &buf[..len])?;
+            secs = secs.saturating_add((timeout_ms / 1000) as u16);
+
+            let (Some(offer), proxy) = self.collect(net, DhcpMessageType::Offer, timeout_ms)? else {
+                continue;
+            };
+
+            let len = self.build(&mut buf, secs, Some(&offer));
+            net.send(Ipv4Addr::BROADCAST, DHCP_SERVER_PORT, DHCP_CLIENT_PORT, &buf[..len])?;
+
+            let (Some(ack), _) = self.collect(net, DhcpMessageType::Request, timeout_ms)? else {
+                continue;
+            };
+
+            let mut config = ack.config;
+            if config.boot_file_len == 0 && offer.has_boot_file() {
+                config.boot_file = offer.config.boot_file;
+                config.boot_file_len = offer.config.boot_file_len;
+            }
+            if let Some(proxy) = proxy.filter(|_| config.boot_file_len == 0) {
+                config.boot_file = proxy.config.boot_file;
+                config.boot_file_len = proxy.config.boot_file_len;
+                config.tftp_server = proxy.config.tftp_server;
+            }
+            return Ok(config);
+        }
+
+        Err(Error::NoResponse)
+    }
+}
+
+/// DHCP option writer
+struct Options<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Options<'_> {
+    fn push(&mut self, code: u8, value: &[u8]) {
+        self.buf[self.len] = code;
+        self.buf[self.len + 1] = value.len() as u8;
+        self.buf[self.len + 2..self.len + 2 + value.len()].copy_from_slice(value);
+        self.len += 2 + value.len();
+    }
+
+    fn end(self) -> usize {
+        self.buf[self.len] = dhcp_options::END;
+        // BOOTP relays expect at least 300 bytes
+        (self.len + 1).max(300)
+    }
+}
+
+/// Bytes up to the first NUL
+fn cstr(data: &[u8]) -> &[u8] {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    &data[..end]
+}
+
+/// Parse a dotted-quad address
+fn parse_ipv4(s: &str) -> Option<Ipv4Addr> {
+    let mut octets = [0u8; 4];
+    let mut parts = s.split('.');
+    for octet in &mut octets {
+        *octet = parts.next()?.parse().ok()?;
+    }
+    parts.next().is_none().then_some(Ipv4Addr { octets })
+}
+
+// =============================================================================
+// TFTP
+// =============================================================================
+
+/// TFTP client for read requests in octet mode
+#[derive(Debug, Clone, Copy)]
+pub struct TftpClient {
+    /// Local port (transfer ID)
+    pub local_port: u16,
+    /// Requested block size
+    pub block_size: u16,
+    /// Requested retransmit timeout (seconds)
+    pub timeout: u8,
+    /// Retransmissions of one packet before giving up
+    pub retries: u8,
+}
+
+impl TftpClient {
+    /// Create a client on `local_port`
+    pub const fn new(local_port: u16) -> Self {
+        Self {
+            local_port,
+            block_size: TFTP_BLOCK_SIZE,
+            timeout: 4,
+            retries: 5,
+        }
+    }
+
+    /// Build a read request, with or without options
+    fn request(&self, file: &str, options: bool) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(file.len() + 64);
+        packet.extend_from_slice(&(TftpOpcode::Rrq as u16).to_be_bytes());
+        for field in [file.as_bytes(), b"octet"] {
+            packet.extend_from_slice(field);
+            packet.push(0);
+        }
+        if options {
+            push_option(&mut packet, b"blksize", u64::from(self.block_size));
+            push_option(&mut packet, b"tsize", 0);
+            push_option(&mut packet, b"timeout", u64::from(self.timeout));
+        }
+        packet
+    }
+
+    /// Download `file` from `transfer.server`
+    ///
+    /// `transfer` tracks the negotiated options and progress; `progress`
+    /// is called after every block.
+    pub fn get<T: Datagram>(
+        &self,
+        net: &mut T,
+        file: &str,
+        transfer: &mut TftpTransfer,
+        mut progress: impl FnMut(&TftpTransfer),
+    ) -> Result<Vec<u8>> {
+        transfer.local_port = self.local_port;
+        transfer.server_port = TFTP_PORT;
+        transfer.block_num = 0;
+        transfer.bytes_transferred = 0;
+        transfer.total_size = 0;
+        transfer.options.block_size = TftpOptions::DEFAULT_BLOCK_SIZE;
+        transfer.options.timeout = self.timeout;
+        transfer.complete = false;
+        transfer.error = None;
+
+        let mut data = Vec::new();
+        let mut buf = alloc::vec![0u8; usize::from(self.block_size.max(TftpOptions::DEFAULT_BLOCK_SIZE)) + 4];
+        let mut with_options = true;
+        let mut packet = self.request(file, with_options);
+        let mut locked = false;
+        let mut attempts = 0u8;
+
+        'send: loop {
+            net.send(transfer.server, transfer.server_port, self.local_port, &packet)?;
+            let timeout_ms = u32::from(transfer.options.timeout.max(1)) * 1000;
+
+            for _ in 0..MAX_STRAY {
+                let Some(received) = net.recv(self.local_port, &mut buf, timeout_ms)? else { break };
+                if received.src != transfer.server || received.len < 4 {
+                    continue;
+                }
+                if locked && received.src_port != transfer.server_port {
+                    // Not our transfer: tell the sender, keep going
+                    let error = error_packet(TftpError::UnknownTid, "Unknown transfer ID");
+                    net.send(received.src, received.src_port, self.local_port, &error)?;
+                    continue;
+                }
+
+                let reply = &buf[..received.len];
+                let opcode = u16::from_be_bytes([reply[0], reply[1]]);
+                let block = u16::from_be_bytes([reply[2], reply[3]]);
+
+                match opcode {
+                    op if op == TftpOpcode::Oack as u16 && transfer.block_num == 0 && with_options => {
+                        transfer.server_port = received.src_port;
+                        locked = true;
+                        if !self.accept_options(&reply[2..], transfer) {
+                            let error = error_packet(TftpError::OptionNegotiation, "Bad options");
+                            net.send(transfer.server, transfer.server_port, self.local_port, &error)?;
+                            transfer.error = Some(TftpError::OptionNegotiation);
+                            return Err(Error::TftpError);
+                        }
+                        if transfer.total_size != 0 {
+                            data.reserve(transfer.total_size as usize);
+                        }
+                        packet = ack_packet(0);
+                        attempts = 0;
+                        continue 'send;
+                    }
+                    op if op == TftpOpcode::Data as u16 => {
+                        if !locked {
+                            // The server ignored our options
+                            transfer.server_port = received.src_port;
+                            locked = true;
+                        }
+
+                        let payload = &reply[4..];
+                        if block == transfer.block_num.wrapping_add(1) {
+                            if payload.len() > usize::from(transfer.options.block_size) {
+                                return Err(Error::ProtocolError);
+                            }
+                            data.extend_from_slice(payload);
+                            transfer.block_num = block;
+                            transfer.bytes_transferred += payload.len() as u64;
+                            packet = ack_packet(block);
+                            attempts = 0;
+                            progress(transfer);
+
+                            if transfer.is_last_block(payload.len()) {
+                                net.send(transfer.server, transfer.server_port, self.local_port, &packet)?;
+                                if transfer.total_size != 0 && transfer.total_size != transfer.bytes_transferred {
+                                    return Err(Error::ProtocolError);
+                                }
+                                transfer.complete = true;
+                                return Ok(data);
+                            }
+                            continue 'send;
+                        }
+                        if block == transfer.block_num {
+                            // Our ACK was lost: acknowledge the duplicate
+                            continue 'send;
+                        }
+                    }
+                    op if op == TftpOpcode::Error as u16 => {
+                        let error = TftpError::from_code(block);
+                        if error == TftpError::OptionNegotiation && !locked && with_options {
+                            // Retry without options for servers that refuse them
+                            with_options = false;
+                            packet = self.request(file, with_options);
+                            continue 'send;
+                        }
+                        transfer.error = Some(error);
+                        return Err(match error {
+                            TftpError::FileNotFound => Error::NotFound,
+                            TftpError::AccessViolation => Error::AccessDenied,
+                            _ => Error::TftpError,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+
+            attempts += 1;
+            if attempts > self.retries {
+                return Err(Error::Timeout);
+            }
+        }
+    }
+
+    /// Apply an OACK; unknown or out of range values are refused
+    fn accept_options(&self, mut options: &[u8], transfer: &mut TftpTransfer) -> bool {
+        while !options.is_empty() {
+            let name = cstr(options);
+            let Some(rest) = options.get(name.len() + 1..) else { return false };
+            let value = cstr(rest);
+            options = rest.get(value.len() + 1..).unwrap_or(&[]);
+
+            let Some(value) = core::str::from_utf8(value).ok().and_then(|v| v.parse::<u64>().ok()) else {
+                return false;
+            };
+            if name.eq_ignore_ascii_case(b"blksize") {
+                if !(8..=u64::from(self.block_size)).contains(&value) {
+                    return false;
+                }
+                transfer.options.block_size = value as u16;
+            } else if name.eq_ignore_ascii_case(b"tsize") {
+                transfer.total_size = value;
+                transfer.options.transfer_size = value;
+            } else if name.eq_ignore_ascii_case(b"timeout") {
+                if !(1..=255).contains(&value) {
+                    return false;
+                }
+                transfer.options.timeout = value as u8;
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Append a `name\0value\0` option
+fn push_option(packet: &mut Vec<u8>, name: &[u8], value: u64) {
+    packet.extend_from_slice(name);
+    packet.push(0);
+
+    let mut digits = [0u8; 20];
+    let mut start = digits.len();
+    let mut value = value;
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(&digits[start..]);
+    packet.push(0);
+}
+
+/// Build an ACK
+fn ack_packet(block: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(4);
+    packet.extend_from_slice(&(TftpOpcode::Ack as u16).to_be_bytes());
+    packet.extend_from_slice(&block.to_be_bytes());
+    packet
+}
+
+/// Build an ERROR
+fn error_packet(error: TftpError, message: &str) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(5 + message.len());
+    packet.extend_from_slice(&(TftpOpcode::Error as u16).to_be_bytes());
+    packet.extend_from_slice(&(error as u16).to_be_bytes());
+    packet.extend_from_slice(message.as_bytes());
+    packet.push(0);
+    packet
+}
+
+// =============================================================================
+// PXE BOOT
+// =============================================================================
+
+/// Images downloaded by [`PxeBoot::boot`]
+#[derive(Debug, Clone)]
+pub struct PxeImages {
+    /// Lease the images were fetched with
+    pub config: DhcpConfig,
+    /// Kernel image
+    pub kernel: Vec<u8>,
+    /// Initial ramdisk
+    pub initrd: Option<Vec<u8>>,
+}
+
+/// PXE boot flow
+///
+/// Drives a [`NetBootManager`] through DHCP and the downloads and mirrors
+/// its progress on the splash screen, if one is attached.
+pub struct PxeBoot<'a, T: Datagram> {
+    net: T,
+    manager: NetBootManager,
+    dhcp: DhcpClient,
+    tftp: TftpClient,
+    splash: Option<&'a mut SplashScreen>,
+}
+
+impl<'a, T: Datagram> PxeBoot<'a, T> {
+    /// Create the flow over `net`
+    pub fn new(net: T) -> Self {
+        let mac = net.mac();
+        let dhcp = DhcpClient::new(mac);
+        let mut manager = NetBootManager::new();
+        manager.init(NetBootConfig {
+            protocol: NetBootProtocol::Pxe,
+            mac,
+            ..NetBootConfig::new()
+        });
+
+        Self {
+            net,
+            manager,
+            tftp: TftpClient::new(49152 | (dhcp.xid as u16 & 0x3FFF)),
+            dhcp,
+            splash: None,
+        }
+    }
+
+    /// Report progress on `splash`
+    pub fn with_splash(mut self, splash: &'a mut SplashScreen) -> Self {
+        self.splash = Some(splash);
+        self
+    }
+
+    /// Set the retry budget and longest DHCP timeout
+    pub fn with_limits(mut self, retries: u8, dhcp_timeout_ms: u32) -> Self {
+        self.dhcp.retries = retries.max(1);
+        self.dhcp.max_timeout_ms = dhcp_timeout_ms.max(1000);
+        self.tftp.retries = retries.max(1);
+        self.manager.config.retry_count = retries;
+        self
+    }
+
+    /// Boot state
+    pub fn manager(&self) -> &NetBootManager {
+        &self.manager
+    }
+
+    fn phase(&mut self, phase: u8, status: &str) {
+        if let Some(splash) = self.splash.as_deref_mut() {
+            splash.set_phase(phase, PHASES, status);
+            splash.set_progress(self.manager.progress());
+        }
+    }
+
+    fn fail<R>(&mut self, error: Error, msg: &str) -> Result<R> {
+        self.manager.set_error(msg);
+        if let Some(splash) = self.splash.as_deref_mut() {
+            splash.set_status(msg);
+        }
+        Err(error)
+    }
+
+    /// Acquire a lease and configure the transport
+    pub fn discover(&mut self) -> Result<DhcpConfig> {
+        self.manager.start_dhcp();
+        self.phase(1, "Configuring network (DHCP)");
+
+        let config = match self.dhcp.run(&mut self.net) {
+            Ok(config) => config,
+            Err(error) => return self.fail(error, "No DHCP offer received"),
+        };
+        if let Err(error) = self.net.configure(&config) {
+            return self.fail(error, "Network configuration failed");
+        }
+
+        self.manager.dhcp_complete(config);
+        self.manager.tftp = TftpTransfer::new(config.tftp_server);
+        Ok(config)
+    }
+
+    /// Download `file` from the TFTP server of the lease
+    pub fn download(&mut self, phase: u8, file: &str) -> Result<Vec<u8>> {
+        self.manager.start_download();
+        self.phase(phase, file);
+        self.manager.state = NetBootState::Downloading;
+
+        let Self { net, manager, tftp, splash, .. } = self;
+        let mut transfer = manager.tftp;
+        let result = tftp.get(net, file, &mut transfer, |transfer| {
+            manager.tftp = *transfer;
+            if let Some(splash) = splash.as_deref_mut() {
+                splash.set_progress(manager.progress());
+            }
+        });
+        self.manager.tftp = transfer;
+
+        match result {
+            Ok(data) => Ok(data),
+            Err(Error::NotFound) => self.fail(Error::NotFound, "Boot file not found on TFTP server"),
+            Err(error) => self.fail(error, "TFTP transfer failed"),
+        }
+    }
+
+    /// Fetch the kernel and optional initrd
+    ///
+    /// The kernel defaults to the boot file named by DHCP.
+    pub fn boot(&mut self, kernel: Option<&str>, initrd: Option<&str>) -> Result<PxeImages> {
+        let config = self.discover()?;
+
+        let kernel_path = match kernel {
+            Some(path) => path,
+            None if config.boot_file_len != 0 => config.boot_file(),
+            None => return self.fail(Error::NotFound, "No boot file offered"),
+        };
+        let kernel = self.download(2, kernel_path)?;
+        let initrd = match initrd {
+            Some(path) => Some(self.download(3, path)?),
+            None => None,
+        };
+
+        self.manager.state = NetBootState::Complete;
+        self.phase(PHASES, "Network boot images loaded");
+        Ok(PxeImages { config, kernel, initrd })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::VecDeque;
+    use alloc::vec;
+
+    const SERVER: Ipv4Addr = Ipv4Addr::new(10, 0, 2, 2);
+    const CLIENT: Ipv4Addr = Ipv4Addr::new(10, 0, 2, 15);
+    const MAC: MacAddr = MacAddr::new(0x52, 0x54, 0, 0x12, 0x34, 0x56);
+
+    /// Scripted server: `respond` sees every sent datagram
+    struct Mock<F: FnMut(u16, &[u8]) -> Vec<(u16, Vec<u8>)>> {
+        respond: F,
+        queue: VecDeque<(u16, u16, Vec<u8>)>,
+        sent: Vec<(u16, Vec<u8>)>,
+        configured: Option<DhcpConfig>,
+    }
+
+    fn mock<F: FnMut(u16, &[u8]) -> Vec<(u16, Vec<u8>)>>(respond: F) -> Mock<F> {
+        Mock { respond, queue: VecDeque::new(), sent: Vec::new(), configured: None }
+    }
+
+    impl<F: FnMut(u16, &[u8]) -> Vec<(u16, Vec<u8>)>> Datagram for Mock<F> {
+        fn mac(&self) -> MacAddr {
+            MAC
+        }
+
+        fn configure(&mut self, config: &DhcpConfig) -> Result<()> {
+            self.configured = Some(*config);
+            Ok(())
+        }
+
+        fn send(&mut self, _dst: Ipv4Addr, dst_port: u16, src_port: u16, data: &[u8]) -> Result<()> {
+            self.sent.push((dst_port, data.to_vec()));
+            for (port, reply) in (self.respond)(dst_port, data) {
+                let dst = if dst_port == DHCP_SERVER_PORT { DHCP_CLIENT_PORT } else { src_port };
+                self.queue.push_back((port, dst, reply));
+            }
+            Ok(())
+        }
+
+        fn recv(&mut self, port: u16, buf: &mut [u8], _timeout_ms: u32) -> Result<Option<Received>> {
+            while let Some((src_port, dst, data)) = self.queue.pop_front() {
+                if dst == port {
+                    buf[..data.len()].copy_from_slice(&data);
+                    return Ok(Some(Received { src: SERVER, src_port, len: data.len() }));
+                }
+            }
+            Ok(None)
+        }
+    }
+
+    fn dhcp_reply(request: &[u8], kind: DhcpMessageType, yiaddr: Ipv4Addr, file: &str, pxe: bool) -> Vec<u8> {
+        let mut reply = vec![0u8; DhcpHeader::SIZE + 4];
+        reply[0] = DhcpHeader::BOOTREPLY;
+        reply[4..8].copy_from_slice(&request[4..8]);
+        reply[16..20].copy_from_slice(&yiaddr.octets);
+        reply[20..24].copy_from_slice(&SERVER.octets);
+        reply[28..34].copy_from_slice(&request[28..34]);
+        reply[108..108 + file.len()].copy_from_slice(file.as_bytes());
+        reply[DhcpHeader::SIZE..].copy_from_slice(&DhcpHeader::MAGIC_COOKIE);
+        let mut option = |code: u8, value: &[u8]| {
+            reply.extend_from_slice(&[code, value.len() as u8]);
+            reply.extend_from_slice(value);
+        };
+        option(dhcp_options::MESSAGE_TYPE, &[kind as u8]);
+        option(dhcp_options::SERVER_ID, &SERVER.octets);
+        option(dhcp_options::SUBNET_MASK, &[255, 255, 255, 0]);
+        if pxe {
+            option(dhcp_options::VENDOR_CLASS_ID, b"PXEClient");
+        }
+        reply.push(dhcp_options::END);
+        reply
+    }
+
+    fn message_type(request: &[u8]) -> u8 {
+        request[DhcpHeader::SIZE + 6]
+    }
+
+    fn option(request: &[u8], code: u8) -> Option<&[u8]> {
+        let mut at = DhcpHeader::SIZE + 4;
+        while request[at] != dhcp_options::END {
+            let len = usize::from(request[at + 1]);
+            if request[at] == code {
+                return Some(&request[at + 2..at + 2 + len]);
+            }
+            at += 2 + len;
+        }
+        None
+    }
+
+    #[test]
+    fn test_dhcp_with_proxy_offer() {
+        let mut net = mock(|_, request| match message_type(request) {
+            1 => vec![
+                (DHCP_SERVER_PORT, dhcp_reply(request, DhcpMessageType::Offer, CLIENT, "", false)),
+                (DHCP_SERVER_PORT, dhcp_reply(request, DhcpMessageType::Offer, Ipv4Addr::ANY, "helix/kernel", true)),
+            ],
+            3 => vec![(DHCP_SERVER_PORT, dhcp_reply(request, DhcpMessageType::Ack, CLIENT, "", false))],
+            _ => vec![],
+        });
+
+        let client = DhcpClient::new(MAC);
+        let config = client.run(&mut net).unwrap();
+        assert_eq!(config.client_ip, CLIENT);
+        assert_eq!(config.tftp_server, SERVER);
+        assert_eq!(config.boot_file(), "helix/kernel");
+        assert_eq!(config.subnet_mask, Ipv4Addr::new(255, 255, 255, 0));
+
+        let discover = &net.sent[0].1;
+        assert_eq!(option(discover, dhcp_options::VENDOR_CLASS_ID).unwrap(), b"PXEClient:Arch:00007:UNDI:003016".as_slice());
+        assert_eq!(option(discover, dhcp_options::CLIENT_ARCH).unwrap(), &[0, 7]);
+        let request = &net.sent[1].1;
+        assert_eq!(option(request, dhcp_options::REQUESTED_IP).unwrap(), &CLIENT.octets);
+    }
+
+    #[test]
+    fn test_dhcp_retransmits() {
+        let mut discovers = 0;
+        let mut net = mock(|_, request| match message_type(request) {
+            1 => {
+                discovers += 1;
+                if discovers < 3 {
+                    return vec![];
+                }
+                vec![(DHCP_SERVER_PORT, dhcp_reply(request, DhcpMessageType::Offer, CLIENT, "kernel", false))]
+            }
+            3 => vec![(DHCP_SERVER_PORT, dhcp_reply(request, DhcpMessageType::Ack, CLIENT, "kernel", false))],
+            _ => vec![],
+        });
+
+        let config = DhcpClient::new(MAC).run(&mut net).unwrap();
+        assert_eq!(config.boot_file(), "kernel");
+        assert_eq!(net.sent.len(), 4);
+
+        let mut silent = mock(|_, _| vec![]);
+        assert_eq!(DhcpClient::new(MAC).run(&mut silent).unwrap_err(), Error::NoResponse);
+    }
+
+    fn data(block: u16, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0, 3];
+        packet.extend_from_slice(&block.to_be_bytes());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    #[test]
+    fn test_tftp_negotiated_download() {
+        let image: Vec<u8> = (0..2500u32).map(|i| i as u8).collect();
+        let mut dropped = false;
+        let mut net = mock(|_, packet| {
+            let block = u16::from_be_bytes([packet[2], packet[3]]);
+            match packet[1] {
+                1 => {
+                    assert!(packet.windows(8).any(|w| w == b"blksize\0"));
+                    vec![(2000, b"\0\x06blksize\x001024\0tsize\x002500\0".to_vec())]
+                }
+                4 if block == 1 && !dropped => {
+                    // Lose block 2 once, and inject a stray sender
+                    dropped = true;
+                    vec![(3000, data(2, &[0; 4]))]
+                }
+                4 => {
+                    let start = usize::from(block) * 1024;
+                    if start > image.len() {
+                        return vec![];
+                    }
+                    let end = (start + 1024).min(image.len());
+                    vec![(2000, data(block + 1, &image[start..end]))]
+                }
+                5 => vec![],
+                _ => panic!("unexpected opcode"),
+            }
+        });
+
+        let mut transfer = TftpTransfer::new(SERVER);
+        let mut reports = 0;
+        let file = TftpClient::new(50000).get(&mut net, "kernel", &mut transfer, |_| reports += 1).unwrap();
+        assert_eq!(file, image);
+        assert_eq!(transfer.options.block_size, 1024);
+        assert_eq!(transfer.total_size, 2500);
+        assert_eq!(transfer.progress(), 100);
+        assert_eq!(reports, 3);
+        // Unknown TID answered with error 5
+        assert!(net.sent.iter().any(|(port, p)| *port == 3000 && p[..4] == [0, 5, 0, 5]));
+    }
+
+    #[test]
+    fn test_tftp_without_options() {
+        let mut net = mock(|_, packet| match packet[1] {
+            1 if packet.len() > 20 => vec![(2000, b"\0\x05\0\x08no options\0".to_vec())],
+            1 => vec![(2000, data(1, &[0xAB; 512]))],
+            4 if packet[3] == 1 => vec![(2000, data(2, &[0xCD; 10]))],
+            _ => vec![],
+        });
+
+        let mut transfer = TftpTransfer::new(SERVER);
+        let file = TftpClient::new(50000).get(&mut net, "initrd", &mut transfer, |_| {}).unwrap();
+        assert_eq!(file.len(), 522);
+        assert_eq!(transfer.options.block_size, TftpOptions::DEFAULT_BLOCK_SIZE);
+
+        let mut missing = mock(|_, _| vec![(2000, b"\0\x05\0\x01not found\0".to_vec())]);
+        let error = TftpClient::new(50000).get(&mut missing, "nope", &mut transfer, |_| {}).unwrap_err();
+        assert_eq!(error, Error::NotFound);
+        assert_eq!(transfer.error, Some(TftpError::FileNotFound));
+    }
+
+    #[test]
+    fn test_pxe_boot_flow() {
+        let kernel = vec![0x7Fu8; 1500];
+        let mut net = mock(|port, packet| {
+            if port == DHCP_SERVER_PORT {
+                let kind = if message_type(packet) == 1 { DhcpMessageType::Offer } else { DhcpMessageType::Ack };
+                return vec![(DHCP_SERVER_PORT, dhcp_reply(packet, kind, CLIENT, "helix.elf", false))];
+            }
+            let block = u16::from_be_bytes([packet[2], packet[3]]);
+            match packet[1] {
+                1 => vec![(2000, b"\0\x06blksize\x001024\0".to_vec())],
+                4 if block == 0 => vec![(2000, data(1, &kernel[..1024]))],
+                4 if block == 1 => vec![(2000, data(2, &kernel[1024..]))],
+                _ => vec![],
+            }
+        });
+
+        let mut splash = SplashScreen::new();
+        {
+            let mut pxe = PxeBoot::new(&mut net).with_splash(&mut splash);
+            let images = pxe.boot(None, None).unwrap();
+            assert_eq!(images.kernel.len(), 1500);
+            assert!(images.initrd.is_none());
+            assert!(pxe.manager().is_complete());
+        }
+        assert_eq!(splash.status(), "Network boot images loaded");
+        assert_eq!(net.configured.unwrap().client_ip, CLIENT);
+    }
+}
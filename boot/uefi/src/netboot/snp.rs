@@ -0,0 +1,320 @@
+//! Simple Network Transport
+//!
+//! UDP over the firmware `EFI_SIMPLE_NETWORK_PROTOCOL` for the PXE
+//! client: Ethernet, IPv4 and UDP framing from [`crate::netstack`], with
+//! a small ARP cache. The interface is opened exclusively so the firmware
+//! network stack stops consuming its frames, and handed back on drop.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::pxe::{Datagram, Received};
+use super::{DhcpConfig, Ipv4Addr, MacAddr};
+use crate::error::{Error, Result};
+use crate::netstack::{ArpPacket, Ipv4Address, MacAddress, UdpDatagram};
+use crate::raw::protocols::snp::*;
+use crate::raw::types::*;
+use crate::services::boot::try_boot_services;
+use crate::services::events::Timeout;
+
+/// ARP cache entries
+const ARP_CACHE_SIZE: usize = 8;
+
+/// ARP requests sent before giving up on a neighbour
+const ARP_RETRIES: u32 = 4;
+
+/// ARP reply timeout (milliseconds)
+const ARP_TIMEOUT_MS: u32 = 500;
+
+/// Polls for a transmitted buffer to be recycled
+const TX_POLLS: usize = 100_000;
+
+/// Frame buffer size
+const FRAME_SIZE: usize = 1536;
+
+fn to_ip(addr: Ipv4Addr) -> Ipv4Address {
+    Ipv4Address { octets: addr.octets }
+}
+
+/// Simple Network Protocol transport
+pub struct SnpDatagram {
+    handle: Handle,
+    agent: Handle,
+    snp: *mut EfiSimpleNetworkProtocol,
+    mac: MacAddress,
+    ip: Ipv4Address,
+    subnet_mask: Ipv4Address,
+    gateway: Ipv4Address,
+    arp: [(Ipv4Address, MacAddress); ARP_CACHE_SIZE],
+    arp_next: usize,
+    identification: u16,
+    tx: Vec<u8>,
+    rx: Vec<u8>,
+}
+
+impl SnpDatagram {
+    /// Open the first interface with media present
+    ///
+    /// `agent` is the image handle opening the protocol.
+    pub fn open(agent: Handle) -> Result<Self> {
+        let bs = try_boot_services().map_err(Error::from_status)?;
+
+        let handles = bs
+            .locate_handle_buffer(LocateSearchType::ByProtocol, Some(&EfiSimpleNetworkProtocol::GUID))
+            .map_err(|_| Error::Unsupported)?;
+
+        for &handle in handles.handles() {
+            let Ok(snp) = bs.open_protocol::<EfiSimpleNetworkProtocol>(
+                handle,
+                &EfiSimpleNetworkProtocol::GUID,
+                agent,
+                Handle::null(),
+                open_protocol::EXCLUSIVE,
+            ) else {
+                continue;
+            };
+
+            let mut net = Self {
+                handle,
+                agent,
+                snp,
+                mac: MacAddress::ZERO,
+                ip: Ipv4Address::ANY,
+                subnet_mask: Ipv4Address::ANY,
+                gateway: Ipv4Address::ANY,
+                arp: [(Ipv4Address::ANY, MacAddress::ZERO); ARP_CACHE_SIZE],
+                arp_next: 0,
+                identification: 1,
+                tx: vec![0; FRAME_SIZE],
+                rx: vec![0; FRAME_SIZE],
+            };
+            if net.start().is_ok() {
+                return Ok(net);
+            }
+        }
+
+        Err(Error::NoMedia)
+    }
+
+    /// Bring the interface to the initialized state
+    fn start(&mut self) -> Result<()> {
+        let snp = self.snp;
+        let check = |status: Status| {
+            if status == Status::SUCCESS || status == Status::ALREADY_STARTED {
+                Ok(())
+            } else {
+                Err(Error::from_status(status))
+            }
+        };
+
+        unsafe {
+            if (*(*snp).mode).state == SNP_STATE_STOPPED {
+                check(((*snp).start)(snp))?;
+            }
+            if (*(*snp).mode).state == SNP_STATE_STARTED {
+                check(((*snp).initialize)(snp, 0, 0))?;
+            }
+
+            let mode = &*(*snp).mode;
+            if mode.media_present_supported != 0 && mode.media_present == 0 {
+                return Err(Error::NoMedia);
+            }
+            if mode.if_type != 1 || mode.hw_address_size != 6 {
+                return Err(Error::Unsupported);
+            }
+            self.mac = MacAddress::new(mode.current_address.ethernet());
+
+            let filters = (SNP_RECEIVE_UNICAST | SNP_RECEIVE_BROADCAST) & mode.receive_filter_mask;
+            check(((*snp).receive_filters)(snp, filters, 0, 0, 0, core::ptr::null()))?;
+        }
+        Ok(())
+    }
+
+    /// Queue `len` bytes of the transmit buffer and wait for it to be recycled
+    fn transmit(&mut self, len: usize) -> Result<()> {
+        let snp = self.snp;
+        let status = unsafe {
+            ((*snp).transmit)(
+                snp,
+                0,
+                len,
+                self.tx.as_ptr().cast(),
+                core::ptr::null(),
+                core::ptr::null(),
+                core::ptr::null(),
+            )
+        };
+        if status != Status::SUCCESS {
+            return Err(Error::from_status(status));
+        }
+
+        for _ in 0..TX_POLLS {
+            let mut interrupts = 0u32;
+            let mut recycled: *mut core::ffi::c_void = core::ptr::null_mut();
+            let status = unsafe { ((*snp).get_status)(snp, &mut interrupts, &mut recycled) };
+            if status != Status::SUCCESS {
+                return Err(Error::from_status(status));
+            }
+            if !recycled.is_null() {
+                return Ok(());
+            }
+        }
+        Err(Error::DeviceError)
+    }
+
+    /// Receive one frame into the receive buffer
+    fn receive(&mut self) -> Result<Option<usize>> {
+        let snp = self.snp;
+        let mut len = self.rx.len();
+        let status = unsafe {
+            ((*snp).receive)(
+                snp,
+                core::ptr::null_mut(),
+                &mut len,
+                self.rx.as_mut_ptr().cast(),
+                core::ptr::null_mut(),
+                core::ptr::null_mut(),
+                core::ptr::null_mut(),
+            )
+        };
+        match status {
+            Status::SUCCESS => Ok(Some(len)),
+            Status::NOT_READY => Ok(None),
+            // Oversized frames are dropped
+            Status::BUFFER_TOO_SMALL => Ok(None),
+            status => Err(Error::from_status(status)),
+        }
+    }
+
+    /// Learn from an ARP frame and answer requests for our address
+    fn handle_arp(&mut self, arp: ArpPacket) -> Result<()> {
+        if arp.sender_ip != Ipv4Address::ANY {
+            self.learn(arp.sender_ip, arp.sender_mac);
+        }
+        if arp.operation == ArpPacket::REQUEST && self.ip != Ipv4Address::ANY && arp.target_ip == self.ip {
+            if let Some(len) = arp.reply(self.mac).build_frame(&mut self.tx) {
+                self.transmit(len)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn learn(&mut self, ip: Ipv4Address, mac: MacAddress) {
+        if let Some(entry) = self.arp.iter_mut().find(|(cached, _)| *cached == ip) {
+            entry.1 = mac;
+            return;
+        }
+        self.arp[self.arp_next] = (ip, mac);
+        self.arp_next = (self.arp_next + 1) % ARP_CACHE_SIZE;
+    }
+
+    /// Hardware address to send `dst` to
+    fn resolve(&mut self, dst: Ipv4Address) -> Result<MacAddress> {
+        if dst == Ipv4Address::BROADCAST {
+            return Ok(MacAddress::BROADCAST);
+        }
+
+        let on_link = dst.apply_mask(&self.subnet_mask) == self.ip.apply_mask(&self.subnet_mask);
+        let hop = if on_link || self.gateway == Ipv4Address::ANY { dst } else { self.gateway };
+        if let Some(&(_, mac)) = self.arp.iter().find(|(cached, _)| *cached == hop) {
+            return Ok(mac);
+        }
+
+        for _ in 0..ARP_RETRIES {
+            let request = ArpPacket::request(self.mac, self.ip, hop);
+            let len = request.build_frame(&mut self.tx).ok_or(Error::BufferTooSmall)?;
+            self.transmit(len)?;
+
+            let mut timeout = Timeout::new(u64::from(ARP_TIMEOUT_MS)).map_err(Error::from_status)?;
+            while !timeout.is_expired().map_err(Error::from_status)? {
+                let Some(len) = self.receive()? else { continue };
+                if let Some(arp) = ArpPacket::parse_frame(&self.rx[..len]) {
+                    self.handle_arp(arp)?;
+                    if arp.operation == ArpPacket::REPLY && arp.sender_ip == hop {
+                        return Ok(arp.sender_mac);
+                    }
+                }
+            }
+        }
+
+        Err(Error::NoResponse)
+    }
+}
+
+impl Datagram for SnpDatagram {
+    fn mac(&self) -> MacAddr {
+        MacAddr { octets: self.mac.octets }
+    }
+
+    fn configure(&mut self, config: &DhcpConfig) -> Result<()> {
+        self.ip = to_ip(config.client_ip);
+        self.subnet_mask = to_ip(config.subnet_mask);
+        self.gateway = to_ip(config.gateway);
+        Ok(())
+    }
+
+    fn send(&mut self, dst: Ipv4Addr, dst_port: u16, src_port: u16, data: &[u8]) -> Result<()> {
+        let dst_ip = to_ip(dst);
+        let dst_mac = self.resolve(dst_ip)?;
+        let datagram = UdpDatagram {
+            src_mac: self.mac,
+            dst_mac,
+            src_ip: self.ip,
+            dst_ip,
+            src_port,
+            dst_port,
+            payload: data,
+        };
+
+        let len = datagram
+            .build_frame(&mut self.tx, self.identification)
+            .ok_or(Error::BufferTooSmall)?;
+        self.identification = self.identification.wrapping_add(1);
+        self.transmit(len)
+    }
+
+    fn recv(&mut self, port: u16, buf: &mut [u8], timeout_ms: u32) -> Result<Option<Received>> {
+        let mut timeout = Timeout::new(u64::from(timeout_ms)).map_err(Error::from_status)?;
+
+        while !timeout.is_expired().map_err(Error::from_status)? {
+            let Some(len) = self.receive()? else { continue };
+
+            if let Some(arp) = ArpPacket::parse_frame(&self.rx[..len]) {
+                self.handle_arp(arp)?;
+                continue;
+            }
+
+            let Some(datagram) = UdpDatagram::parse_frame(&self.rx[..len]) else { continue };
+            let ours = self.ip == Ipv4Address::ANY
+                || datagram.dst_ip == self.ip
+                || datagram.dst_ip == Ipv4Address::BROADCAST;
+            if datagram.dst_port != port || !ours || datagram.payload.len() > buf.len() {
+                continue;
+            }
+
+            buf[..datagram.payload.len()].copy_from_slice(datagram.payload);
+            let received = Received {
+                src: Ipv4Addr { octets: datagram.src_ip.octets },
+                src_port: datagram.src_port,
+                len: datagram.payload.len(),
+            };
+            // Replies come back the way this datagram arrived
+            let (src_ip, src_mac) = (datagram.src_ip, datagram.src_mac);
+            if src_ip != Ipv4Address::ANY {
+                self.learn(src_ip, src_mac);
+            }
+            return Ok(Some(received));
+        }
+
+        Ok(None)
+    }
+}
+
+impl Drop for SnpDatagram {
+    fn drop(&mut self) {
+        // Reconnects the drivers the exclusive open displaced
+        if let Ok(bs) = try_boot_services() {
+            let _ = bs.close_protocol(self.handle, &EfiSimpleNetworkProtocol::GUID, self.agent, Handle::null());
+        }
+    }
+}
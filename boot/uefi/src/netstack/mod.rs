@@ -344,6 +344,103 @@ pub const fn pcp(&self) -> u8 {
     }
 }
 
+// =============================================================================
+// ARP
+// =============================================================================
+
+/// ARP packet for IPv4 over Ethernet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpPacket {
+    /// Operation (`REQUEST` or `REPLY`)
+    pub operation: u16,
+    /// Sender hardware address
+    pub sender_mac: MacAddress,
+    /// Sender protocol address
+    pub sender_ip: Ipv4Address,
+    /// Target hardware address (zero in requests)
+    pub target_mac: MacAddress,
+    /// Target protocol address
+    pub target_ip: Ipv4Address,
+}
+
+impl ArpPacket {
+    /// ARP request
+    pub const REQUEST: u16 = 1;
+    /// ARP reply
+    pub const REPLY: u16 = 2;
+
+    /// Packet size
+    pub const SIZE: usize = 28;
+
+    /// Request the hardware address of `target_ip`
+    pub const fn request(mac: MacAddress, ip: Ipv4Address, target_ip: Ipv4Address) -> Self {
+        Self {
+            operation: Self::REQUEST,
+            sender_mac: mac,
+            sender_ip: ip,
+            target_mac: MacAddress::ZERO,
+            target_ip,
+        }
+    }
+
+    /// Reply to this request with our hardware address
+    pub const fn reply(&self, mac: MacAddress) -> Self {
+        Self {
+            operation: Self::REPLY,
+            sender_mac: mac,
+            sender_ip: self.target_ip,
+            target_mac: self.sender_mac,
+            target_ip: self.sender_ip,
+        }
+    }
+
+    /// Parse an Ethernet frame carrying ARP
+    pub fn parse_frame(frame: &[u8]) -> Option<Self> {
+        let packet = frame.get(EthernetHeader::SIZE..EthernetHeader::SIZE + Self::SIZE)?;
+        if be16(frame, 12) != EtherType::Arp as u16
+            || be16(packet, 0) != 1
+            || be16(packet, 2) != EtherType::Ipv4 as u16
+            || packet[4] != 6
+            || packet[5] != 4
+        {
+            return None;
+        }
+
+        let mac = |at: usize| MacAddress::new(packet[at..at + 6].try_into().unwrap());
+        let ip = |at: usize| Ipv4Address { octets: packet[at..at + 4].try_into().unwrap() };
+        Some(Self {
+            operation: be16(packet, 6),
+            sender_mac: mac(8),
+            sender_ip: ip(14),
+            target_mac: mac(18),
+            target_ip: ip(24),
+        })
+    }
+
+    /// Build the Ethernet frame into `buf`, returning its length
+    ///
+    /// Requests are broadcast, replies go to the target.
+    pub fn build_frame(&self, buf: &mut [u8]) -> Option<usize> {
+        let len = EthernetHeader::SIZE + Self::SIZE;
+        let frame = buf.get_mut(..len)?;
+
+        let dst = if self.operation == Self::REQUEST { MacAddress::BROADCAST } else { self.target_mac };
+        write_ethernet(frame, dst, self.sender_mac, EtherType::Arp);
+
+        let packet = &mut frame[EthernetHeader::SIZE..];
+        packet[0..2].copy_from_slice(&1u16.to_be_bytes());
+        packet[2..4].copy_from_slice(&(EtherType::Ipv4 as u16).to_be_bytes());
+        packet[4] = 6;
+        packet[5] = 4;
+        packet[6..8].copy_from_slice(&self.operation.to_be_bytes());
+        packet[8..14].copy_from_slice(&self.sender_mac.octets);
+        packet[14..18].copy_from_slice(&self.sender_ip.octets);
+        packet[18..24].copy_from_slice(&self.target_mac.octets);
+        packet[24..28].copy_from_slice(&self.target_ip.octets);
+        Some(len)
+    }
+}
+
 // =============================================================================
 // IPv4
 // =============================================================================
@@ -515,6 +612,159 @@ pub const fn get_length(&self) -> u16 {
     }
 }
 
+// =============================================================================
+// UDP FRAMES
+// =============================================================================
+
+/// Time to live of frames we send
+const DEFAULT_TTL: u8 = 64;
+
+/// Read a big-endian u16
+fn be16(data: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([data[at], data[at + 1]])
+}
+
+/// Write an Ethernet header
+fn write_ethernet(frame: &mut [u8], dst: MacAddress, src: MacAddress, ether_type: EtherType) {
+    frame[0..6].copy_from_slice(&dst.octets);
+    frame[6..12].copy_from_slice(&src.octets);
+    frame[12..14].copy_from_slice(&(ether_type as u16).to_be_bytes());
+}
+
+/// Internet checksum (RFC 1071), with `sum` carrying a partial sum
+pub fn checksum(data: &[u8], mut sum: u32) -> u16 {
+    let mut chunks = data.chunks_exact(2);
+    for pair in &mut chunks {
+        sum += u32::from(u16::from_be_bytes([pair[0], pair[1]]));
+    }
+    if let [last] = chunks.remainder() {
+        sum += u32::from(*last) << 8;
+    }
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Partial sum of the UDP pseudo header
+fn pseudo_header_sum(src: Ipv4Address, dst: Ipv4Address, udp_len: usize) -> u32 {
+    let word = |ip: Ipv4Address, i: usize| u32::from(u16::from_be_bytes([ip.octets[i], ip.octets[i + 1]]));
+    word(src, 0) + word(src, 2) + word(dst, 0) + word(dst, 2) + 17 + udp_len as u32
+}
+
+/// UDP datagram in an Ethernet frame
+#[derive(Debug, Clone, Copy)]
+pub struct UdpDatagram<'a> {
+    /// Source hardware address
+    pub src_mac: MacAddress,
+    /// Destination hardware address
+    pub dst_mac: MacAddress,
+    /// Source address
+    pub src_ip: Ipv4Address,
+    /// Destination address
+    pub dst_ip: Ipv4Address,
+    /// Source port
+    pub src_port: u16,
+    /// Destination port
+    pub dst_port: u16,
+    /// Payload
+    pub payload: &'a [u8],
+}
+
+impl<'a> UdpDatagram<'a> {
+    /// Frame size for a payload
+    pub const fn frame_size(payload_len: usize) -> usize {
+        EthernetHeader::SIZE + Ipv4Header::MIN_SIZE + UdpHeader::SIZE + payload_len
+    }
+
+    /// Build the Ethernet/IPv4/UDP frame into `buf`, returning its length
+    pub fn build_frame(&self, buf: &mut [u8], identification: u16) -> Option<usize> {
+        let len = Self::frame_size(self.payload.len());
+        let udp_len = UdpHeader::SIZE + self.payload.len();
+        let ip_len = Ipv4Header::MIN_SIZE + udp_len;
+        if ip_len > usize::from(u16::MAX) {
+            return None;
+        }
+        let frame = buf.get_mut(..len)?;
+
+        write_ethernet(frame, self.dst_mac, self.src_mac, EtherType::Ipv4);
+
+        let ip = &mut frame[EthernetHeader::SIZE..];
+        ip[0] = 0x45;
+        ip[1] = 0;
+        ip[2..4].copy_from_slice(&(ip_len as u16).to_be_bytes());
+        ip[4..6].copy_from_slice(&identification.to_be_bytes());
+        ip[6..8].copy_from_slice(&0x4000u16.to_be_bytes()); // Don't fragment
+        ip[8] = DEFAULT_TTL;
+        ip[9] = IpProtocol::Udp.to_u8();
+        ip[10..12].fill(0);
+        ip[12..16].copy_from_slice(&self.src_ip.octets);
+        ip[16..20].copy_from_slice(&self.dst_ip.octets);
+        let sum = checksum(&ip[..Ipv4Header::MIN_SIZE], 0);
+        ip[10..12].copy_from_slice(&sum.to_be_bytes());
+
+        let udp = &mut ip[Ipv4Header::MIN_SIZE..];
+        udp[0..2].copy_from_slice(&self.src_port.to_be_bytes());
+        udp[2..4].copy_from_slice(&self.dst_port.to_be_bytes());
+        udp[4..6].copy_from_slice(&(udp_len as u16).to_be_bytes());
+        udp[6..8].fill(0);
+        udp[UdpHeader::SIZE..].copy_from_slice(self.payload);
+        let sum = match checksum(udp, pseudo_header_sum(self.src_ip, self.dst_ip, udp_len)) {
+            0 => 0xFFFF,
+            sum => sum,
+        };
+        udp[6..8].copy_from_slice(&sum.to_be_bytes());
+
+        Some(len)
+    }
+
+    /// Parse an Ethernet frame carrying an unfragmented IPv4 UDP datagram
+    ///
+    /// Frames with a bad IP or UDP checksum are rejected.
+    pub fn parse_frame(frame: &'a [u8]) -> Option<Self> {
+        if frame.len() < Self::frame_size(0) || be16(frame, 12) != EtherType::Ipv4 as u16 {
+            return None;
+        }
+
+        let ip = &frame[EthernetHeader::SIZE..];
+        let header_len = usize::from(ip[0] & 0x0F) * 4;
+        let total_len = usize::from(be16(ip, 2));
+        if ip[0] >> 4 != 4
+            || header_len < Ipv4Header::MIN_SIZE
+            || total_len < header_len + UdpHeader::SIZE
+            || total_len > ip.len()
+            || be16(ip, 6) & 0x3FFF != 0
+            || ip[9] != IpProtocol::Udp.to_u8()
+            || checksum(&ip[..header_len], 0) != 0
+        {
+            return None;
+        }
+
+        let src_ip = Ipv4Address { octets: ip[12..16].try_into().ok()? };
+        let dst_ip = Ipv4Address { octets: ip[16..20].try_into().ok()? };
+
+        let udp = &ip[header_len..total_len];
+        let udp_len = usize::from(be16(udp, 4));
+        if udp_len < UdpHeader::SIZE || udp_len > udp.len() {
+            return None;
+        }
+        let udp = &udp[..udp_len];
+        if be16(udp, 6) != 0 && checksum(udp, pseudo_header_sum(src_ip, dst_ip, udp_len)) != 0 {
+            return None;
+        }
+
+        Some(Self {
+            src_mac: MacAddress::new(frame[6..12].try_into().ok()?),
+            dst_mac: MacAddress::new(frame[0..6].try_into().ok()?),
+            src_ip,
+            dst_ip,
+            src_port: be16(udp, 0),
+            dst_port: be16(udp, 2),
+            payload: &udp[UdpHeader::SIZE..],
+        })
+    }
+}
+
 // =============================================================================
 // TCP
 // =============================================================================
@@ -645,6 +895,13 @@ pub mod dhcp_options {
     pub const CLIENT_ID: u8 = 61;
     pub const TFTP_SERVER: u8 = 66;
     pub const BOOTFILE_NAME: u8 = 67;
+    pub const VENDOR_SPECIFIC: u8 = 43;
+    pub const MAX_MESSAGE_SIZE: u8 = 57;
+    pub const VENDOR_CLASS_ID: u8 = 60;
+    pub const CLIENT_ARCH: u8 = 93;
+    pub const CLIENT_NDI: u8 = 94;
+    pub const CLIENT_UUID: u8 = 97;
+    pub const PAD: u8 = 0;
     pub const END: u8 = 255;
 }
 
@@ -1205,6 +1462,49 @@ fn test_http_status() {
         assert!(HttpStatus::MovedPermanently.is_redirect());
     }
 
+    #[test]
+    fn test_udp_frame_roundtrip() {
+        let datagram = UdpDatagram {
+            src_mac: MacAddress::new([0x52, 0x54, 0, 0x12, 0x34, 0x56]),
+            dst_mac: MacAddress::BROADCAST,
+            src_ip: Ipv4Address::new(10, 0, 2, 15),
+            dst_ip: Ipv4Address::new(10, 0, 2, 2),
+            src_port: 1024,
+            dst_port: 69,
+            payload: b"\x00\x01kernel\x00octet\x00",
+        };
+        let mut buf = [0u8; 128];
+        let len = datagram.build_frame(&mut buf, 7).unwrap();
+        assert_eq!(len, UdpDatagram::frame_size(datagram.payload.len()));
+
+        let parsed = UdpDatagram::parse_frame(&buf[..len]).unwrap();
+        assert_eq!(parsed.src_ip, datagram.src_ip);
+        assert_eq!(parsed.dst_port, 69);
+        assert_eq!(parsed.payload, datagram.payload);
+
+        // Corrupted payload fails the UDP checksum
+        buf[len - 1] ^= 0xFF;
+        assert!(UdpDatagram::parse_frame(&buf[..len]).is_none());
+    }
+
+    #[test]
+    fn test_arp_reply() {
+        let ours = MacAddress::new([0x52, 0x54, 0, 0x12, 0x34, 0x56]);
+        let theirs = MacAddress::new([0x52, 0x54, 0, 0xAA, 0xBB, 0xCC]);
+        let request = ArpPacket::request(theirs, Ipv4Address::new(10, 0, 2, 2), Ipv4Address::new(10, 0, 2, 15));
+
+        let mut buf = [0u8; 64];
+        let len = request.build_frame(&mut buf).unwrap();
+        assert_eq!(&buf[..6], &[0xFF; 6]);
+        let parsed = ArpPacket::parse_frame(&buf[..len]).unwrap();
+        assert_eq!(parsed, request);
+
+        let reply = parsed.reply(ours);
+        assert_eq!(reply.operation, ArpPacket::REPLY);
+        assert_eq!(reply.target_mac, theirs);
+        assert_eq!(reply.sender_ip, Ipv4Address::new(10, 0, 2, 15));
+    }
+
     #[test]
     fn test_tcp_flags() {
         let header = TcpHeader {
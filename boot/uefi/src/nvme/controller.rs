@@ -0,0 +1,578 @@
+//! NVMe Controller Driver
+//!
+//! Polled admin and I/O queue pairs over BAR0: enough to identify the
+//! controller, discover its namespaces and read from them without the
+//! firmware `EFI_BLOCK_IO_PROTOCOL`. Namespaces implement
+//! [`crate::block::BlockDevice`].
+//!
+//! Bringing the controller up resets it, so any firmware driver bound to
+//! it stops working; use this only once the firmware path has failed.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::sync::atomic::{fence, Ordering};
+
+use super::*;
+use crate::block::{BlockDevice, BlockDeviceInfo, BlockDeviceType, BlockError};
+use crate::dma::{DmaBuffer, DmaError, DmaRegion, DMA_GUARD_SIZE};
+use crate::protocols::pci::{self, PciDevice};
+use crate::raw::memory::MemoryType;
+use crate::raw::types::*;
+use crate::services::boot::try_boot_services;
+
+/// Controller memory page size (CC.MPS = 0)
+const PAGE_SIZE: usize = 4096;
+
+/// Queue depth; one page holds the submission queue
+const QUEUE_ENTRIES: u16 = 64;
+
+/// The single polled I/O queue pair
+const IO_QUEUE_ID: u16 = 1;
+
+/// Largest read issued as one command, bounded by the PRP list page
+const MAX_TRANSFER: usize = 128 * 1024;
+
+/// Busy-wait granularity (microseconds)
+const POLL_INTERVAL_US: usize = 10;
+
+/// Namespaces probed when the active list is unsupported
+const MAX_NAMESPACES: u32 = 1024;
+
+/// PCI class of NVMe controllers (mass storage, NVM, NVMe)
+const PCI_CLASS: (u8, u8, u8) = (0x01, 0x08, 0x02);
+
+/// Controller Configuration for a polled NVM controller with 4 KB pages
+const fn controller_config() -> u32 {
+    cc::EN | (6 << cc::IOSQES_SHIFT) | (4 << cc::IOCQES_SHIFT)
+}
+
+/// Largest transfer the controller accepts, capped at [`MAX_TRANSFER`]
+const fn transfer_limit(mdts: u8) -> usize {
+    // MDTS is a power of two in units of the minimum page size
+    if mdts == 0 || mdts as u32 > (MAX_TRANSFER / PAGE_SIZE).ilog2() {
+        MAX_TRANSFER
+    } else {
+        PAGE_SIZE << mdts
+    }
+}
+
+/// Physical address of BAR0 from the raw BAR registers
+fn bar0_address(bars: &[u32; 6]) -> Option<u64> {
+    let low = bars[0];
+    if low & 1 != 0 {
+        // I/O space
+        return None;
+    }
+    let base = match (low >> 1) & 0x3 {
+        0 => u64::from(low & !0xF),
+        2 => u64::from(low & !0xF) | (u64::from(bars[1]) << 32),
+        _ => return None,
+    };
+    (base != 0).then_some(base)
+}
+
+/// PRP entries for a physically contiguous, page-aligned buffer
+///
+/// Transfers over two pages spill into `list`, which the controller finds
+/// at `list_addr`.
+fn fill_prps(addr: u64, len: usize, list: &mut [u64], list_addr: u64) -> (u64, u64) {
+    match prp_entries_needed(0, len, PAGE_SIZE) {
+        0 => (addr, 0),
+        1 => (addr, addr + PAGE_SIZE as u64),
+        n => {
+            for (i, entry) in list.iter_mut().take(n).enumerate() {
+                *entry = addr + ((i + 1) * PAGE_SIZE) as u64;
+            }
+            (addr, list_addr)
+        }
+    }
+}
+
+// =============================================================================
+// DMA MEMORY
+// =============================================================================
+
+/// Zeroed boot-services pages shared with the controller
+///
+/// Boot services identity-map memory, so the address is both the CPU
+/// pointer and the bus address.
+struct DmaPages {
+    addr: u64,
+    pages: usize,
+}
+
+impl DmaPages {
+    fn new(bytes: usize) -> Result<Self, NvmeError> {
+        let pages = bytes.div_ceil(PAGE_SIZE);
+        let mut address = PhysicalAddress(0);
+        try_boot_services()
+            .map_err(|_| NvmeError::OutOfMemory)?
+            .allocate_pages(AllocateType::AllocateAnyPages, MemoryType::LoaderData, pages, &mut address)
+            .map_err(|_| NvmeError::OutOfMemory)?;
+        unsafe { core::ptr::write_bytes(address.0 as *mut u8, 0, pages * PAGE_SIZE) };
+        Ok(Self { addr: address.0, pages })
+    }
+
+    fn ptr<T>(&self) -> *mut T {
+        self.addr as *mut T
+    }
+}
+
+impl Drop for DmaPages {
+    fn drop(&mut self) {
+        if let Ok(bs) = try_boot_services() {
+            let _ = bs.free_pages(PhysicalAddress(self.addr), self.pages);
+        }
+    }
+}
+
+/// A guard violation on the data buffer fails the transfer
+fn dma_error(_: DmaError) -> NvmeError {
+    NvmeError::DataTransferError
+}
+
+/// A submission/completion queue pair
+struct QueuePair {
+    state: QueueState,
+    sq: DmaPages,
+    cq: DmaPages,
+}
+
+impl QueuePair {
+    fn new(qid: u16, size: u16) -> Result<Self, NvmeError> {
+        Ok(Self {
+            state: QueueState::new(qid, size),
+            sq: DmaPages::new(size as usize * core::mem::size_of::<NvmeCommand>())?,
+            cq: DmaPages::new(size as usize * core::mem::size_of::<NvmeCompletion>())?,
+        })
+    }
+}
+
+// =============================================================================
+// CONTROLLER
+// =============================================================================
+
+/// An NVMe controller driven by polling
+pub struct NvmeController {
+    regs: NvmeRegisters,
+    doorbell_stride: u32,
+    ready_polls: usize,
+    queue_size: u16,
+    admin: QueuePair,
+    io: Option<QueuePair>,
+    max_transfer: usize,
+    namespace_count: u32,
+    model: [u8; 40],
+    serial: [u8; 20],
+    /// Data buffer memory, guard area included; freed on drop
+    _buffer: DmaPages,
+    /// The data buffer, mapped to the device only while a command runs
+    data: DmaBuffer,
+    prp_list: DmaPages,
+}
+
+impl NvmeController {
+    /// Bring up the controller behind a PCI function
+    pub fn new(device: &PciDevice) -> Result<Self, NvmeError> {
+        let class = device.class();
+        if (class.base, class.sub, class.interface) != PCI_CLASS {
+            return Err(NvmeError::ControllerNotFound);
+        }
+        let base = bar0_address(device.bars()).ok_or(NvmeError::ControllerNotFound)?;
+        device.enable_memory().map_err(|_| NvmeError::ControllerNotFound)?;
+        device.enable_bus_master().map_err(|_| NvmeError::ControllerNotFound)?;
+
+        // SAFETY: BAR0 of an NVMe function maps its registers, and boot
+        // services identity-map MMIO
+        unsafe { Self::from_registers(NvmeRegisters::new(base as usize)) }
+    }
+
+    /// Reset and enable the controller at `regs`
+    ///
+    /// # Safety
+    ///
+    /// `regs` must map an NVMe controller nothing else is driving.
+    pub unsafe fn from_registers(regs: NvmeRegisters) -> Result<Self, NvmeError> {
+        let cap = regs.cap().read();
+        if cap & cap::CSS_NVM == 0 || (cap >> cap::MPSMIN_SHIFT) & cap::MPSMIN_MASK != 0 {
+            return Err(NvmeError::NotSupported);
+        }
+        let max_entries = ((cap >> cap::MQES_SHIFT) & cap::MQES_MASK) + 1;
+        // CAP.TO is in 500 ms units
+        let timeout_us = ((cap >> cap::TO_SHIFT) & cap::TO_MASK).max(1) as usize * 500_000;
+
+        let queue_size = max_entries.min(u64::from(QUEUE_ENTRIES)) as u16;
+        let buffer = DmaPages::new(MAX_TRANSFER + DMA_GUARD_SIZE)?;
+        let region = DmaRegion {
+            virt: buffer.addr as usize,
+            device_addr: buffer.addr,
+            size: MAX_TRANSFER + DMA_GUARD_SIZE,
+        };
+        // SAFETY: The pages are ours and outlive the controller's use of them
+        let data = unsafe { DmaBuffer::new(region) }.map_err(dma_error)?;
+        let mut controller = Self {
+            regs,
+            doorbell_stride: ((cap >> cap::DSTRD_SHIFT) & cap::DSTRD_MASK) as u32,
+            ready_polls: timeout_us / POLL_INTERVAL_US,
+            queue_size,
+            admin: QueuePair::new(NVME_ADMIN_QUEUE_ID, queue_size)?,
+            io: None,
+            max_transfer: MAX_TRANSFER,
+            namespace_count: 0,
+            model: [0; 40],
+            serial: [0; 20],
+            _buffer: buffer,
+            data,
+            prp_list: DmaPages::new(PAGE_SIZE)?,
+        };
+
+        controller.disable()?;
+        let entries = u32::from(queue_size - 1);
+        controller.regs.aqa().write((entries << 16) | entries);
+        controller.regs.asq().write(controller.admin.sq.addr);
+        controller.regs.acq().write(controller.admin.cq.addr);
+        controller.regs.cc().write(controller_config());
+        controller.wait_ready(true)?;
+
+        controller.identify()?;
+        controller.create_io_queues()?;
+        Ok(controller)
+    }
+
+    /// Clear CC.EN and wait for the controller to go idle
+    fn disable(&self) -> Result<(), NvmeError> {
+        let cc = self.regs.cc().read();
+        if cc & cc::EN != 0 {
+            self.regs.cc().write(cc & !cc::EN);
+        }
+        self.wait_ready(false)
+    }
+
+    fn wait_ready(&self, ready: bool) -> Result<(), NvmeError> {
+        let bs = try_boot_services().map_err(|_| NvmeError::ControllerNotReady)?;
+        for _ in 0..self.ready_polls {
+            let csts = self.regs.csts().read();
+            if ready && csts & csts::CFS != 0 {
+                return Err(NvmeError::ControllerNotReady);
+            }
+            if (csts & csts::RDY != 0) == ready {
+                return Ok(());
+            }
+            let _ = bs.stall(POLL_INTERVAL_US);
+        }
+        Err(NvmeError::ControllerNotReady)
+    }
+
+    /// Hand the data buffer to the device for one command
+    fn map_data(&mut self) -> Result<u64, NvmeError> {
+        self.data.map().map_err(dma_error)
+    }
+
+    /// Take the data buffer back after a command; the first `len` bytes
+    /// if the command succeeded and the device stayed inside the buffer
+    fn unmap_data(&mut self, result: Result<NvmeCompletion, NvmeError>, len: usize) -> Result<&[u8], NvmeError> {
+        self.data.unmap().map_err(dma_error)?;
+        result?;
+        Ok(&self.data.as_slice().map_err(dma_error)?[..len])
+    }
+
+    /// Identify the controller: transfer limit, namespace count, model
+    fn identify(&mut self) -> Result<(), NvmeError> {
+        let addr = self.map_data()?;
+        let result = self.admin_command(|cid| NvmeCommand::identify_controller(cid, addr));
+        let page = self.unmap_data(result, PAGE_SIZE)?;
+        // SAFETY: The page holds the 4 KB Identify Controller data
+        let data: IdentifyController = unsafe { core::ptr::read_unaligned(page.as_ptr().cast()) };
+
+        self.max_transfer = transfer_limit(data.mdts);
+        self.namespace_count = data.nn;
+        let model = data.model_number();
+        self.model[..model.len()].copy_from_slice(model);
+        let serial = data.serial_number();
+        self.serial[..serial.len()].copy_from_slice(serial);
+        Ok(())
+    }
+
+    fn create_io_queues(&mut self) -> Result<(), NvmeError> {
+        let io = QueuePair::new(IO_QUEUE_ID, self.queue_size)?;
+        let (size, cq, sq) = (self.queue_size, io.cq.addr, io.sq.addr);
+        self.admin_command(|cid| NvmeCommand::create_io_cq(cid, IO_QUEUE_ID, size, cq, 0))?;
+        self.admin_command(|cid| NvmeCommand::create_io_sq(cid, IO_QUEUE_ID, size, sq, IO_QUEUE_ID))?;
+        self.io = Some(io);
+        Ok(())
+    }
+
+    fn admin_command(&mut self, build: impl FnOnce(u16) -> NvmeCommand) -> Result<NvmeCompletion, NvmeError> {
+        let polls = self.ready_polls;
+        execute(&self.regs, self.doorbell_stride, &mut self.admin, polls, build)
+    }
+
+    fn io_command(&mut self, build: impl FnOnce(u16) -> NvmeCommand) -> Result<NvmeCompletion, NvmeError> {
+        let polls = NVME_COMMAND_TIMEOUT_MS as usize * 1000 / POLL_INTERVAL_US;
+        let queue = self.io.as_mut().ok_or(NvmeError::InvalidQueue)?;
+        execute(&self.regs, self.doorbell_stride, queue, polls, build)
+    }
+
+    /// Model number reported by Identify Controller
+    pub fn model(&self) -> &[u8] {
+        let len = self.model.iter().position(|&c| c == 0).unwrap_or(self.model.len());
+        &self.model[..len]
+    }
+
+    /// Serial number reported by Identify Controller
+    pub fn serial(&self) -> &[u8] {
+        let len = self.serial.iter().position(|&c| c == 0).unwrap_or(self.serial.len());
+        &self.serial[..len]
+    }
+
+    /// Largest single read in bytes
+    pub const fn max_transfer(&self) -> usize {
+        self.max_transfer
+    }
+
+    /// IDs of the active namespaces
+    ///
+    /// Controllers older than NVMe 1.1 lack the active list; every ID up
+    /// to NN is returned for them and inactive ones fail to identify.
+    pub fn namespace_ids(&mut self) -> Result<Vec<u32>, NvmeError> {
+        let addr = self.map_data()?;
+        let result = self.admin_command(|cid| NvmeCommand::identify_active_ns_list(cid, 0, addr));
+        if let Ok(list) = self.unmap_data(result, PAGE_SIZE) {
+            return Ok(list
+                .chunks_exact(4)
+                .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+                .take_while(|&id| id != 0)
+                .collect());
+        }
+        Ok((1..=self.namespace_count.min(MAX_NAMESPACES)).collect())
+    }
+
+    /// Identify namespace `nsid`
+    pub fn identify_namespace(&mut self, nsid: u32) -> Result<IdentifyNamespace, NvmeError> {
+        let addr = self.map_data()?;
+        let result = self.admin_command(|cid| NvmeCommand::identify_namespace(cid, nsid, addr));
+        let page = self.unmap_data(result, PAGE_SIZE)?;
+        // SAFETY: The page holds the 4 KB Identify Namespace data
+        Ok(unsafe { core::ptr::read_unaligned(page.as_ptr().cast()) })
+    }
+
+    /// Read whole blocks of `block_size` bytes from namespace `nsid`
+    pub fn read(&mut self, nsid: u32, lba: u64, block_size: usize, buffer: &mut [u8]) -> Result<(), NvmeError> {
+        if block_size == 0 || buffer.len() % block_size != 0 {
+            return Err(NvmeError::InvalidParameter);
+        }
+
+        let chunk_size = self.max_transfer / block_size * block_size;
+        let mut lba = lba;
+        for chunk in buffer.chunks_mut(chunk_size) {
+            let blocks = (chunk.len() / block_size) as u16;
+            let addr = self.map_data()?;
+            let list = unsafe { core::slice::from_raw_parts_mut(self.prp_list.ptr::<u64>(), PAGE_SIZE / 8) };
+            let (prp1, prp2) = fill_prps(addr, chunk.len(), list, self.prp_list.addr);
+
+            let result = self.io_command(|cid| NvmeCommand::read(cid, nsid, lba, blocks, prp1, prp2));
+            chunk.copy_from_slice(self.unmap_data(result, chunk.len())?);
+            lba += u64::from(blocks);
+        }
+        Ok(())
+    }
+
+    /// Wrap every usable namespace as a block device
+    ///
+    /// Namespaces with interleaved metadata or block sizes above a page
+    /// are skipped.
+    pub fn into_namespaces(mut self) -> Result<Vec<NvmeNamespace>, NvmeError> {
+        let mut found = Vec::new();
+        for nsid in self.namespace_ids()? {
+            let Ok(ns) = self.identify_namespace(nsid) else { continue };
+            let block_size = ns.block_size();
+            if ns.nsze == 0
+                || !(512..=PAGE_SIZE).contains(&block_size)
+                || (ns.metadata_extended_lba() && ns.current_lba_format().ms != 0)
+            {
+                continue;
+            }
+
+            let mut info = BlockDeviceInfo::new(BlockDeviceType::Nvme);
+            info.block_size = block_size as u32;
+            info.physical_block_size = block_size as u32;
+            info.total_blocks = ns.nsze;
+            info.optimal_transfer_blocks = (self.max_transfer / block_size) as u32;
+            info.read_only = true;
+            info.set_model(self.model());
+            info.set_serial(self.serial());
+            found.push((nsid, info));
+        }
+
+        let controller = Rc::new(RefCell::new(self));
+        Ok(found
+            .into_iter()
+            .map(|(nsid, info)| NvmeNamespace { controller: controller.clone(), nsid, info })
+            .collect())
+    }
+}
+
+impl Drop for NvmeController {
+    fn drop(&mut self) {
+        // Stop the controller before its queues are freed
+        let _ = self.disable();
+    }
+}
+
+/// Submit one command and poll for its completion
+fn execute(
+    regs: &NvmeRegisters,
+    stride: u32,
+    queue: &mut QueuePair,
+    polls: usize,
+    build: impl FnOnce(u16) -> NvmeCommand,
+) -> Result<NvmeCompletion, NvmeError> {
+    let bs = try_boot_services().map_err(|_| NvmeError::ControllerNotReady)?;
+    let cid = queue.state.next_cid();
+    let qid = queue.state.qid;
+    let command = build(cid);
+
+    unsafe {
+        core::ptr::write_volatile(queue.sq.ptr::<NvmeCommand>().add(queue.state.sq_tail as usize), command);
+    }
+    queue.state.advance_sq_tail();
+    fence(Ordering::SeqCst);
+    regs.ring_doorbell(qid, stride, false, u32::from(queue.state.sq_tail))
+        .map_err(|_| NvmeError::InvalidQueue)?;
+
+    for _ in 0..polls {
+        let cqe = unsafe {
+            core::ptr::read_volatile(queue.cq.ptr::<NvmeCompletion>().add(queue.state.cq_head as usize))
+        };
+        if cqe.phase() != queue.state.phase {
+            let _ = bs.stall(POLL_INTERVAL_US);
+            continue;
+        }
+
+        fence(Ordering::SeqCst);
+        queue.state.advance_cq_head();
+        regs.ring_doorbell(qid, stride, true, u32::from(queue.state.cq_head))
+            .map_err(|_| NvmeError::InvalidQueue)?;
+        if cqe.cid != cid {
+            // Completion of an earlier command that timed out
+            continue;
+        }
+        return if cqe.is_success() {
+            Ok(cqe)
+        } else {
+            Err(NvmeError::CommandFailed(cqe.status()))
+        };
+    }
+
+    Err(NvmeError::Timeout)
+}
+
+/// Bring up every NVMe controller on the PCI bus and collect its namespaces
+///
+/// Controllers that fail to initialize are skipped.
+pub fn probe() -> Vec<NvmeNamespace> {
+    let Ok(devices) = pci::find_by_class(PCI_CLASS.0, Some(PCI_CLASS.1)) else {
+        return Vec::new();
+    };
+    devices
+        .iter()
+        .filter_map(|device| NvmeController::new(device).and_then(NvmeController::into_namespaces).ok())
+        .flatten()
+        .collect()
+}
+
+// =============================================================================
+// NAMESPACE BLOCK DEVICE
+// =============================================================================
+
+/// An NVMe namespace as a read-only block device
+///
+/// Namespaces of one controller share its I/O queue.
+#[derive(Clone)]
+pub struct NvmeNamespace {
+    controller: Rc<RefCell<NvmeController>>,
+    nsid: u32,
+    info: BlockDeviceInfo,
+}
+
+impl NvmeNamespace {
+    /// Namespace ID
+    pub const fn nsid(&self) -> u32 {
+        self.nsid
+    }
+}
+
+impl BlockDevice for NvmeNamespace {
+    fn info(&self) -> &BlockDeviceInfo {
+        &self.info
+    }
+
+    fn read_blocks(&self, lba: u64, buffer: &mut [u8]) -> Result<(), BlockError> {
+        self.info.check_request(lba, buffer.len())?;
+        self.controller
+            .try_borrow_mut()
+            .map_err(|_| BlockError::DeviceBusy)?
+            .read(self.nsid, lba, self.info.block_size as usize, buffer)
+            .map_err(BlockError::from)
+    }
+}
+
+impl From<NvmeError> for BlockError {
+    fn from(error: NvmeError) -> Self {
+        match error {
+            NvmeError::Timeout => BlockError::Timeout,
+            NvmeError::ControllerNotFound | NvmeError::InvalidNamespace => BlockError::DeviceNotFound,
+            _ => BlockError::ReadError,
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dma_without_boot_services() {
+        assert_eq!(DmaPages::new(PAGE_SIZE).err(), Some(NvmeError::OutOfMemory));
+    }
+
+    #[test]
+    fn test_controller_config() {
+        let config = controller_config();
+        assert_eq!(config & cc::EN, cc::EN);
+        assert_eq!((config >> cc::IOSQES_SHIFT) & cc::IOSQES_MASK, 6);
+        assert_eq!((config >> cc::IOCQES_SHIFT) & cc::IOCQES_MASK, 4);
+        assert_eq!((config >> cc::MPS_SHIFT) & cc::MPS_MASK, 0);
+    }
+
+    #[test]
+    fn test_transfer_limit() {
+        assert_eq!(transfer_limit(0), MAX_TRANSFER);
+        assert_eq!(transfer_limit(1), 8192);
+        assert_eq!(transfer_limit(5), MAX_TRANSFER);
+        assert_eq!(transfer_limit(255), MAX_TRANSFER);
+    }
+
+    #[test]
+    fn test_bar0_address() {
+        assert_eq!(bar0_address(&[0xFEB0_0004, 0x1, 0, 0, 0, 0]), Some(0x1_FEB0_0000));
+        assert_eq!(bar0_address(&[0xFEB0_0000, 0x1, 0, 0, 0, 0]), Some(0xFEB0_0000));
+        assert_eq!(bar0_address(&[0xC001, 0, 0, 0, 0, 0]), None);
+        assert_eq!(bar0_address(&[0; 6]), None);
+    }
+
+    #[test]
+    fn test_fill_prps() {
+        let mut list = [0u64; 512];
+        assert_eq!(fill_prps(0x10000, 4096, &mut list, 0x90000), (0x10000, 0));
+        assert_eq!(fill_prps(0x10000, 8192, &mut list, 0x90000), (0x10000, 0x11000));
+
+        assert_eq!(fill_prps(0x10000, 4 * 4096, &mut list, 0x90000), (0x10000, 0x90000));
+        assert_eq!(&list[..3], &[0x11000, 0x12000, 0x13000]);
+    }
+}
@@ -28,11 +28,14 @@
 //! - Dataset management (TRIM/deallocate)
 //! - Power state management
 //! - Health monitoring (SMART)
+//! - Polled controller driver exposing namespaces as block devices
 
 #![no_std]
 
 use core::fmt;
 
+pub mod controller;
+
 // =============================================================================
 // NVME CONSTANTS
 // =============================================================================
@@ -107,6 +110,53 @@ pub mod regs {
     pub const SQ0TDBL: usize = 0x1000;
 }
 
+crate::register_block! {
+    /// NVMe controller register map (BAR0)
+    ///
+    /// Covers the property registers and the first 4 KB of doorbells; use
+    /// [`NvmeRegisters::ring_doorbell`] for queue doorbells.
+    pub struct NvmeRegisters, size = 0x2000 {
+        /// Controller Capabilities (CAP)
+        0x0000 => cap: u64, ReadOnly;
+        /// Version (VS)
+        0x0008 => vs: u32, ReadOnly;
+        /// Interrupt Mask Set (INTMS)
+        0x000C => intms: u32, ReadWrite;
+        /// Interrupt Mask Clear (INTMC)
+        0x0010 => intmc: u32, ReadWrite;
+        /// Controller Configuration (CC)
+        0x0014 => cc: u32, ReadWrite;
+        /// Controller Status (CSTS)
+        0x001C => csts: u32, ReadOnly;
+        /// NVM Subsystem Reset (NSSR)
+        0x0020 => nssr: u32, ReadWrite;
+        /// Admin Queue Attributes (AQA)
+        0x0024 => aqa: u32, ReadWrite;
+        /// Admin Submission Queue Base Address (ASQ)
+        0x0028 => asq: u64, ReadWrite;
+        /// Admin Completion Queue Base Address (ACQ)
+        0x0030 => acq: u64, ReadWrite;
+        /// Controller Memory Buffer Location (CMBLOC)
+        0x0038 => cmbloc: u32, ReadOnly;
+        /// Controller Memory Buffer Size (CMBSZ)
+        0x003C => cmbsz: u32, ReadOnly;
+    }
+}
+
+impl NvmeRegisters {
+    /// Write a submission tail or completion head doorbell
+    pub fn ring_doorbell(
+        &self,
+        qid: u16,
+        doorbell_stride: u32,
+        is_completion: bool,
+        value: u32,
+    ) -> Result<(), crate::mmio::MmioError> {
+        self.region()
+            .write(doorbell_offset(qid, doorbell_stride, is_completion), value)
+    }
+}
+
 /// Controller Capabilities (CAP) register bits
 pub mod cap {
     /// Maximum Queue Entries Supported (bits 0-15)
@@ -299,6 +349,57 @@ pub enum NvmOpcode {
     Copy = 0x19,
 }
 
+// =============================================================================
+// DATASET MANAGEMENT
+// =============================================================================
+
+/// Maximum ranges per Dataset Management command
+pub const DSM_MAX_RANGES: usize = 256;
+
+/// Dataset Management attributes (CDW11)
+pub mod dsm_attr {
+    /// Integral dataset for read
+    pub const INTEGRAL_READ: u32 = 1 << 0;
+    /// Integral dataset for write
+    pub const INTEGRAL_WRITE: u32 = 1 << 1;
+    /// Deallocate (TRIM)
+    pub const DEALLOCATE: u32 = 1 << 2;
+}
+
+/// Dataset Management range (16 bytes)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct DsmRange {
+    /// Context attributes
+    pub context: u32,
+    /// Length in logical blocks
+    pub length: u32,
+    /// Starting LBA
+    pub slba: u64,
+}
+
+impl DsmRange {
+    /// Create a range
+    pub const fn new(slba: u64, length: u32) -> Self {
+        Self { context: 0, length, slba }
+    }
+
+    /// Fill `out` with ranges covering `count` blocks from `slba`,
+    /// splitting at the 32-bit length limit; returns ranges used
+    pub fn split(slba: u64, count: u64, out: &mut [DsmRange]) -> usize {
+        let mut used = 0;
+        let mut lba = slba;
+        let end = slba + count;
+        while lba < end && used < out.len() {
+            let length = (end - lba).min(u32::MAX as u64);
+            out[used] = Self::new(lba, length as u32);
+            lba += length;
+            used += 1;
+        }
+        used
+    }
+}
+
 // =============================================================================
 // NVME COMMAND STRUCTURES
 // =============================================================================
@@ -469,6 +570,23 @@ pub fn flush(cid: u16, nsid: u32) -> Self {
         cmd
     }
 
+    /// Create Dataset Management command over `num_ranges` [`DsmRange`]s
+    /// at `prp1` (the range list must fit in one page)
+    pub fn dataset_management(cid: u16, nsid: u32, num_ranges: u16, attributes: u32, prp1: u64) -> Self {
+        let mut cmd = Self::new();
+        cmd.set_opcode(NvmOpcode::DatasetManagement as u8, cid);
+        cmd.nsid = nsid;
+        cmd.prp1 = prp1;
+        cmd.cdw10 = (num_ranges.clamp(1, DSM_MAX_RANGES as u16) - 1) as u32;
+        cmd.cdw11 = attributes;
+        cmd
+    }
+
+    /// Create Dataset Management command deallocating `num_ranges` ranges
+    pub fn deallocate(cid: u16, nsid: u32, num_ranges: u16, prp1: u64) -> Self {
+        Self::dataset_management(cid, nsid, num_ranges, dsm_attr::DEALLOCATE, prp1)
+    }
+
     /// Create Get Log Page command
     pub fn get_log_page(cid: u16, lid: u8, buffer: u64, size: u32) -> Self {
         let mut cmd = Self::new();
@@ -1087,6 +1205,16 @@ pub const fn supports_security(&self) -> bool {
     pub const fn has_volatile_write_cache(&self) -> bool {
         (self.vwc & 1) != 0
     }
+
+    /// Check if Dataset Management (deallocate) is supported
+    pub const fn supports_dsm(&self) -> bool {
+        (self.oncs & (1 << 2)) != 0
+    }
+
+    /// Check if Write Zeroes is supported
+    pub const fn supports_write_zeroes(&self) -> bool {
+        (self.oncs & (1 << 3)) != 0
+    }
 }
 
 /// Power State Descriptor
@@ -1254,6 +1382,11 @@ pub const fn thin_provisioning(&self) -> bool {
         (self.nsfeat & (1 << 0)) != 0
     }
 
+    /// Check if deallocated blocks read back as zeroes
+    pub const fn deallocate_reads_zeroes(&self) -> bool {
+        (self.dlfeat & 0x07) == 0x01
+    }
+
     /// Check if namespace supports NAWUN/NAWUPF/NACWU
     pub const fn supports_atomic_writes(&self) -> bool {
         (self.nsfeat & (1 << 1)) != 0
@@ -1726,6 +1859,19 @@ fn test_prp_entries() {
         assert_eq!(prp_entries_needed(512, 4096, page_size), 1);
     }
 
+    #[test]
+    fn test_dataset_management() {
+        let cmd = NvmeCommand::deallocate(7, 1, 3, 0x1000);
+        assert_eq!(cmd.opcode(), NvmOpcode::DatasetManagement as u8);
+        assert_eq!(cmd.cdw10, 2);
+        assert_eq!(cmd.cdw11, dsm_attr::DEALLOCATE);
+
+        let mut ranges = [DsmRange::default(); 4];
+        assert_eq!(DsmRange::split(10, u32::MAX as u64 + 5, &mut ranges), 2);
+        assert_eq!(ranges[1], DsmRange::new(10 + u32::MAX as u64, 5));
+        assert_eq!(core::mem::size_of::<DsmRange>(), 16);
+    }
+
     #[test]
     fn test_lba_format() {
         let format = LbaFormat {
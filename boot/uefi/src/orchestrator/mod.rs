@@ -34,6 +34,8 @@
 
 use core::fmt;
 
+use super::events::{self, ErrorSeverity, Event, EventQueue, ProgressEvent};
+
 // =============================================================================
 // BOOT PHASES
 // =============================================================================
@@ -90,33 +92,74 @@ fn default() -> Self {
     }
 }
 
-impl fmt::Display for BootPhase {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl BootPhase {
+    /// Human-readable phase name
+    pub const fn name(&self) -> &'static str {
+        match self {
+            BootPhase::NotStarted => "Not Started",
+            BootPhase::FirmwareEntry => "Firmware Entry",
+            BootPhase::EarlyInit => "Early Initialization",
+            BootPhase::ConsoleInit => "Console Initialization",
+            BootPhase::MemoryInit => "Memory Initialization",
+            BootPhase::ConfigLoad => "Configuration Loading",
+            BootPhase::DeviceDiscovery => "Device Discovery",
+            BootPhase::EntryDetection => "Entry Detection",
+            BootPhase::SecurityValidation => "Security Validation",
+            BootPhase::MenuDisplay => "Menu Display",
+            BootPhase::UserSelection => "User Selection",
+            BootPhase::EntryPreparation => "Entry Preparation",
+            BootPhase::KernelLoad => "Kernel Loading",
+            BootPhase::InitrdLoad => "Initrd Loading",
+            BootPhase::PreBootHooks => "Pre-boot Hooks",
+            BootPhase::ExitBootServices => "Exit Boot Services",
+            BootPhase::HandoffPrep => "Handoff Preparation",
+            BootPhase::KernelHandoff => "Kernel Handoff",
+            BootPhase::BootComplete => "Boot Complete",
+            BootPhase::BootFailed => "Boot Failed",
+        }
+    }
+
+    /// Position in the boot sequence, out of [`BOOT_STEPS`]
+    pub const fn step(&self) -> u8 {
         match self {
-            BootPhase::NotStarted => write!(f, "Not Started"),
-            BootPhase::FirmwareEntry => write!(f, "Firmware Entry"),
-            BootPhase::EarlyInit => write!(f, "Early Initialization"),
-            BootPhase::ConsoleInit => write!(f, "Console Initialization"),
-            BootPhase::MemoryInit => write!(f, "Memory Initialization"),
-            BootPhase::ConfigLoad => write!(f, "Configuration Loading"),
-            BootPhase::DeviceDiscovery => write!(f, "Device Discovery"),
-            BootPhase::EntryDetection => write!(f, "Entry Detection"),
-            BootPhase::SecurityValidation => write!(f, "Security Validation"),
-            BootPhase::MenuDisplay => write!(f, "Menu Display"),
-            BootPhase::UserSelection => write!(f, "User Selection"),
-            BootPhase::EntryPreparation => write!(f, "Entry Preparation"),
-            BootPhase::KernelLoad => write!(f, "Kernel Loading"),
-            BootPhase::InitrdLoad => write!(f, "Initrd Loading"),
-            BootPhase::PreBootHooks => write!(f, "Pre-boot Hooks"),
-            BootPhase::ExitBootServices => write!(f, "Exit Boot Services"),
-            BootPhase::HandoffPrep => write!(f, "Handoff Preparation"),
-            BootPhase::KernelHandoff => write!(f, "Kernel Handoff"),
-            BootPhase::BootComplete => write!(f, "Boot Complete"),
-            BootPhase::BootFailed => write!(f, "Boot Failed"),
+            BootPhase::BootFailed => 0,
+            phase => *phase as u8,
+        }
+    }
+
+    /// Lifecycle milestone reported when this phase is entered
+    ///
+    /// Phases without a matching milestone only report progress.
+    pub const fn lifecycle(&self) -> Option<events::BootPhase> {
+        match self {
+            BootPhase::FirmwareEntry => Some(events::BootPhase::FirmwareEntry),
+            BootPhase::EarlyInit => Some(events::BootPhase::EarlyInit),
+            BootPhase::ConsoleInit => Some(events::BootPhase::ConsoleReady),
+            BootPhase::MemoryInit => Some(events::BootPhase::MemoryMapReady),
+            BootPhase::ConfigLoad => Some(events::BootPhase::ConfigLoaded),
+            BootPhase::DeviceDiscovery => Some(events::BootPhase::DevicesEnumerated),
+            BootPhase::EntryDetection => Some(events::BootPhase::FilesystemsMounted),
+            BootPhase::MenuDisplay => Some(events::BootPhase::BootMenuShown),
+            BootPhase::EntryPreparation => Some(events::BootPhase::EntrySelected),
+            BootPhase::KernelLoad => Some(events::BootPhase::KernelLoading),
+            BootPhase::InitrdLoad => Some(events::BootPhase::KernelLoaded),
+            BootPhase::ExitBootServices => Some(events::BootPhase::ExitBootServices),
+            BootPhase::KernelHandoff => Some(events::BootPhase::KernelEntry),
+            BootPhase::BootComplete => Some(events::BootPhase::BootComplete),
+            _ => None,
         }
     }
 }
 
+/// Number of steps in a full boot, for progress display
+pub const BOOT_STEPS: u8 = BootPhase::BootComplete as u8;
+
+impl fmt::Display for BootPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// Phase timing information
 #[derive(Debug, Clone, Copy, Default)]
 pub struct PhaseTiming {
@@ -1038,12 +1081,22 @@ pub const fn is_empty(&self) -> bool {
 // =============================================================================
 
 /// Boot orchestrator
+///
+/// Phase transitions and errors are posted to [`events`](Self::events):
+/// a lifecycle event for each milestone, a step progress event for every
+/// phase, and an error event from [`handle_error`](Self::handle_error).
+/// The splash screen drains them to show boot progress. Events posted
+/// while the queue is full are dropped and counted.
 #[derive(Debug)]
 pub struct BootOrchestrator {
     /// Boot context
     pub context: BootContext,
     /// Hook manager
     pub hooks: HookManager,
+    /// Boot events for the splash screen and other consumers
+    pub events: EventQueue,
+    /// Last lifecycle milestone reported
+    milestone: Option<events::BootPhase>,
     /// Initialized
     initialized: bool,
 }
@@ -1060,6 +1113,8 @@ pub fn new() -> Self {
         Self {
             context: BootContext::new(),
             hooks: HookManager::new(),
+            events: EventQueue::new(),
+            milestone: None,
             initialized: false,
         }
     }
@@ -1071,8 +1126,10 @@ pub fn initialize(&mut self, timestamp_us: u64) -> Result<(), BootError> {
         }
 
         self.context.phases.start(timestamp_us);
+        self.context.state.phase = BootPhase::FirmwareEntry;
         self.context.state.flags.set(BootStateFlags::INITIALIZED);
         self.initialized = true;
+        self.post_phase(BootPhase::FirmwareEntry, timestamp_us);
         Ok(())
     }
 
@@ -1090,9 +1147,24 @@ pub const fn current_phase(&self) -> BootPhase {
     pub fn advance(&mut self, timestamp_us: u64) -> Result<BootPhase, BootError> {
         let next_phase = self.next_phase();
         self.context.transition(next_phase, timestamp_us)?;
+        self.post_phase(next_phase, timestamp_us);
         Ok(next_phase)
     }
 
+    /// Post the events for entering `phase`
+    fn post_phase(&mut self, phase: BootPhase, timestamp_us: u64) {
+        if let Some(milestone) = phase.lifecycle() {
+            if self.milestone != Some(milestone) {
+                let from = self.milestone.unwrap_or(milestone);
+                self.events.push(Event::boot_phase(0, from, milestone, timestamp_us));
+                self.milestone = Some(milestone);
+            }
+        }
+
+        let progress = ProgressEvent::steps(u64::from(phase.step()), u64::from(BOOT_STEPS), phase.name());
+        self.events.push(Event::progress(0, progress, timestamp_us));
+    }
+
     /// Determine next phase
     fn next_phase(&self) -> BootPhase {
         match self.context.state.phase {
@@ -1133,6 +1205,7 @@ pub fn handle_error(&mut self, error_code: u32, timestamp_us: u64) {
             timestamp_us,
             message: LogMessage::Error(error_code),
         });
+        self.events.push(Event::error(0, ErrorSeverity::Error, error_code, timestamp_us));
     }
 }
 
@@ -1195,4 +1268,44 @@ fn test_boot_orchestrator() {
         assert!(orchestrator.is_initialized());
         assert_eq!(orchestrator.current_phase(), BootPhase::FirmwareEntry);
     }
+
+    #[test]
+    fn test_orchestrator_events() {
+        let mut orchestrator = BootOrchestrator::new();
+        orchestrator.initialize(0).unwrap();
+        orchestrator.advance(100).unwrap();
+        assert_eq!(orchestrator.events.len(), 4);
+
+        let boot = orchestrator.events.pop().unwrap();
+        let events::EventData::Boot(boot) = boot.data else {
+            panic!("expected lifecycle event");
+        };
+        assert_eq!(boot.to, events::BootPhase::FirmwareEntry);
+
+        let events::EventData::Progress(progress) = orchestrator.events.pop().unwrap().data else {
+            panic!("expected progress event");
+        };
+        assert_eq!(progress.current, 1);
+        assert_eq!(progress.total, BOOT_STEPS as u64);
+        assert_eq!(progress.operation_str(), "Firmware Entry");
+
+        let events::EventData::Boot(boot) = orchestrator.events.pop().unwrap().data else {
+            panic!("expected lifecycle event");
+        };
+        assert_eq!((boot.from, boot.to), (events::BootPhase::FirmwareEntry, events::BootPhase::EarlyInit));
+        assert_eq!(boot.timestamp_us, 100);
+        orchestrator.events.clear();
+
+        // Security validation has no milestone of its own
+        while orchestrator.current_phase() < BootPhase::EntryDetection {
+            orchestrator.advance(200).unwrap();
+        }
+        orchestrator.events.clear();
+        orchestrator.advance(300).unwrap();
+        assert_eq!(orchestrator.events.len(), 1);
+
+        orchestrator.handle_error(7, 400);
+        let error = orchestrator.events.pop().and_then(|_| orchestrator.events.pop()).unwrap();
+        assert!(matches!(error.data, events::EventData::Error(e) if e.code == 7));
+    }
 }
@@ -10,11 +10,18 @@
 //! - Protective MBR detection
 //! - Partition type identification
 //! - GUID handling
+//! - Helix system partition discovery
 
 #![no_std]
 
 use core::fmt;
 
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::block::{BlockDevice, BlockDeviceInfo, BlockDeviceType, BlockError};
+use crate::raw::types::Handle;
+
 // =============================================================================
 // CONSTANTS
 // =============================================================================
@@ -104,6 +111,16 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
+impl fmt::LowerHex for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+            self.data1, self.data2, self.data3,
+            self.data4[0], self.data4[1],
+            self.data4[2], self.data4[3], self.data4[4],
+            self.data4[5], self.data4[6], self.data4[7])
+    }
+}
+
 // =============================================================================
 // KNOWN GUIDS
 // =============================================================================
@@ -174,6 +191,12 @@ pub mod partition_types {
         0x21686148, 0x6449, 0x6E6F,
         [0x74, 0x4E, 0x65, 0x65, 0x64, 0x45, 0x46, 0x49]
     );
+
+    /// Helix system partition (HelixFS root)
+    pub const HELIX_SYSTEM: Guid = Guid::new(
+        0x48454C58, 0x4653, 0x4F53,
+        [0x9A, 0x1D, 0x48, 0x45, 0x4C, 0x49, 0x58, 0x01]
+    );
 }
 
 /// Partition type identification
@@ -202,6 +225,8 @@ pub enum PartitionType {
     AppleApfs,
     /// BIOS Boot
     BiosBoot,
+    /// Helix system partition
+    HelixSystem,
     /// Other
     Other,
 }
@@ -231,6 +256,8 @@ pub fn from_guid(guid: &Guid) -> Self {
             PartitionType::AppleApfs
         } else if *guid == partition_types::BIOS_BOOT {
             PartitionType::BiosBoot
+        } else if *guid == partition_types::HELIX_SYSTEM {
+            PartitionType::HelixSystem
         } else {
             PartitionType::Other
         }
@@ -266,6 +293,7 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             PartitionType::AppleHfs => write!(f, "Apple HFS+"),
             PartitionType::AppleApfs => write!(f, "Apple APFS"),
             PartitionType::BiosBoot => write!(f, "BIOS Boot"),
+            PartitionType::HelixSystem => write!(f, "Helix System"),
             PartitionType::Other => write!(f, "Other"),
         }
     }
@@ -870,6 +898,315 @@ pub const fn is_empty(&self) -> bool {
     }
 }
 
+// =============================================================================
+// HELIXFS SUPERBLOCK
+// =============================================================================
+
+/// HelixFS superblock magic ("HELIXFS1")
+pub const HELIXFS_MAGIC: u64 = 0x3153_4658_494C_4548;
+
+/// HelixFS secondary magic ("HFS!"), the last field of the superblock
+pub const HELIXFS_MAGIC2: u32 = 0x4846_5321;
+
+/// Newest HelixFS on-disk format understood
+pub const HELIXFS_VERSION: u32 = 1;
+
+/// HelixFS superblock size
+pub const HELIXFS_SUPERBLOCK_SIZE: usize = 512;
+
+/// Superblock replicas filling the first HelixFS block
+pub const HELIXFS_SUPERBLOCK_REPLICAS: usize = 8;
+
+/// CRC32C (Castagnoli), as used by HelixFS metadata
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// HelixFS volume, as described by its superblock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HelixSuperblock {
+    /// Filesystem UUID
+    pub uuid: Guid,
+    /// Volume label
+    pub label: [u8; 32],
+    /// Label length
+    pub label_len: usize,
+    /// Block size in bytes
+    pub block_size: u32,
+    /// Total blocks in the filesystem
+    pub total_blocks: u64,
+    /// Last write time
+    pub write_time: u64,
+    /// Cleanly unmounted
+    pub clean: bool,
+}
+
+impl HelixSuperblock {
+    /// Parse and validate one superblock copy
+    ///
+    /// Checks both magics, the format version, the CRC32C and the block
+    /// geometry, as the filesystem does before mounting.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < HELIXFS_SUPERBLOCK_SIZE {
+            return None;
+        }
+        let u16_at = |off: usize| u16::from_le_bytes([data[off], data[off + 1]]);
+        let u32_at = |off: usize| u32::from_le_bytes(data[off..off + 4].try_into().unwrap());
+        let u64_at = |off: usize| u64::from_le_bytes(data[off..off + 8].try_into().unwrap());
+
+        if u64_at(0x000) != HELIXFS_MAGIC || u32_at(0x1FC) != HELIXFS_MAGIC2 {
+            return None;
+        }
+        if u32_at(0x008) > HELIXFS_VERSION {
+            return None;
+        }
+        // The checksum covers everything before itself
+        if u32_at(0x1F8) != crc32c(&data[..HELIXFS_SUPERBLOCK_SIZE - 8]) {
+            return None;
+        }
+
+        let block_size = u32_at(0x048);
+        let total_blocks = u64_at(0x050);
+        if !block_size.is_power_of_two() || block_size < 512
+            || 1u32.checked_shl(data[0x04C] as u32) != Some(block_size)
+            || u64_at(0x058) > total_blocks
+        {
+            return None;
+        }
+
+        let mut label = [0u8; 32];
+        label.copy_from_slice(&data[0x028..0x048]);
+        let label_len = label.iter().position(|&b| b == 0).unwrap_or(label.len());
+
+        Some(Self {
+            uuid: Guid::from_bytes(&data[0x018..0x028])?,
+            label,
+            label_len,
+            block_size,
+            total_blocks,
+            write_time: u64_at(0x108),
+            clean: u16_at(0x0F8) == 0,
+        })
+    }
+
+    /// First valid replica in the first block of a volume
+    pub fn find(block: &[u8]) -> Option<Self> {
+        block
+            .chunks_exact(HELIXFS_SUPERBLOCK_SIZE)
+            .take(HELIXFS_SUPERBLOCK_REPLICAS)
+            .find_map(Self::parse)
+    }
+
+    /// Volume label
+    pub fn label(&self) -> &str {
+        core::str::from_utf8(&self.label[..self.label_len]).unwrap_or("")
+    }
+
+    /// Size of the filesystem in bytes
+    pub fn size_bytes(&self) -> u64 {
+        self.total_blocks.saturating_mul(self.block_size as u64)
+    }
+}
+
+// =============================================================================
+// SYSTEM PARTITION DISCOVERY
+// =============================================================================
+
+/// GPT attribute: never boot this system partition automatically
+pub const HELIX_ATTR_NO_AUTO: u64 = 1 << 63;
+
+/// GPT attributes: boot priority (bits 48-51, higher first)
+pub const HELIX_ATTR_PRIORITY_SHIFT: u32 = 48;
+
+/// A bootable Helix installation
+///
+/// A Helix system partition holding a valid HelixFS volume. The kernel is
+/// installed on the EFI System Partition of the same disk.
+#[derive(Debug, Clone, Copy)]
+pub struct BootCandidate {
+    /// Disk, in scan order
+    pub disk: u8,
+    /// The system partition
+    pub partition: PartitionInfo,
+    /// GPT attributes of the system partition
+    pub attributes: u64,
+    /// Its filesystem
+    pub superblock: HelixSuperblock,
+    /// EFI System Partition on the same disk
+    pub esp: Option<PartitionInfo>,
+    /// Firmware handle of the ESP's file system, once matched
+    pub esp_volume: Option<Handle>,
+}
+
+impl BootCandidate {
+    /// Boot priority from the GPT attributes
+    pub const fn priority(&self) -> u8 {
+        ((self.attributes >> HELIX_ATTR_PRIORITY_SHIFT) & 0xF) as u8
+    }
+}
+
+/// Order candidates best first
+///
+/// Higher GPT priority wins, then a cleanly unmounted filesystem, then the
+/// most recently written one; ties keep disk and partition order.
+pub fn rank_candidates(candidates: &mut [BootCandidate]) {
+    candidates.sort_by(|a, b| {
+        b.priority().cmp(&a.priority())
+            .then(b.superblock.clean.cmp(&a.superblock.clean))
+            .then(b.superblock.write_time.cmp(&a.superblock.write_time))
+            .then(a.disk.cmp(&b.disk))
+            .then(a.partition.index.cmp(&b.partition.index))
+    });
+}
+
+/// Find the Helix system partitions of a disk
+///
+/// Reads the GPT behind the protective MBR and validates the HelixFS
+/// superblock of every partition with the Helix type GUID, skipping those
+/// marked [`HELIX_ATTR_NO_AUTO`]. MBR disks cannot carry the type and yield
+/// nothing.
+pub fn scan_disk<D: BlockDevice + ?Sized>(disk: u8, device: &D) -> Result<Vec<BootCandidate>, BlockError> {
+    let block_size = device.info().block_size as u64;
+    let mut sector = vec![0u8; block_size as usize];
+    device.read_blocks(0, &mut sector)?;
+    match Mbr::parse(&sector) {
+        Some(mbr) if mbr.is_protective() => {}
+        _ => return Ok(Vec::new()),
+    }
+
+    device.read_blocks(1, &mut sector)?;
+    let header = GptHeader::parse(&sector)
+        .filter(GptHeader::is_valid)
+        .ok_or(BlockError::InvalidPartitionTable)?;
+    let entry_size = header.partition_entry_size as usize;
+    let count = (header.num_partition_entries as usize).min(MAX_GPT_PARTITIONS);
+    let mut entries = vec![0u8; count * entry_size];
+    device.read_at(header.partition_entries_lba * block_size, &mut entries)?;
+
+    let mut table = PartitionTable::new();
+    let mut systems = Vec::new();
+    for raw in entries.chunks_exact(entry_size) {
+        let Some(entry) = GptPartition::parse(raw) else { continue };
+        if !table.add_gpt_partition(&entry) {
+            continue;
+        }
+        if entry.partition_type() == PartitionType::HelixSystem
+            && entry.attributes & HELIX_ATTR_NO_AUTO == 0
+        {
+            systems.push((table.partitions[table.count - 1], entry.attributes));
+        }
+    }
+
+    let mut block = [0u8; HELIXFS_SUPERBLOCK_SIZE * HELIXFS_SUPERBLOCK_REPLICAS];
+    let mut candidates = Vec::new();
+    for (partition, attributes) in systems {
+        let size = partition.size_sectors.saturating_mul(block_size);
+        let len = block.len().min(size as usize);
+        if device.read_at(partition.start_lba * block_size, &mut block[..len]).is_err() {
+            continue;
+        }
+        let Some(superblock) = HelixSuperblock::find(&block[..len]) else { continue };
+        if superblock.size_bytes() > size {
+            continue;
+        }
+        candidates.push(BootCandidate {
+            disk,
+            partition,
+            attributes,
+            superblock,
+            esp: table.find_esp().copied(),
+            esp_volume: None,
+        });
+    }
+    Ok(candidates)
+}
+
+/// Firmware block device seen through [`BlockDevice`]
+struct FirmwareDisk {
+    device: crate::protocols::block::BlockDevice,
+    info: BlockDeviceInfo,
+}
+
+impl FirmwareDisk {
+    fn new(device: crate::protocols::block::BlockDevice) -> Self {
+        let media = device.media();
+        let mut info = BlockDeviceInfo::new(BlockDeviceType::Unknown);
+        info.block_size = media.block_size;
+        info.total_blocks = device.block_count();
+        info.read_only = media.readonly;
+        info.removable = media.removable;
+        info.media_present = media.present;
+        Self { device, info }
+    }
+
+    /// Whether this is `partition` of `disk`, which the firmware also
+    /// publishes as a device of its own
+    fn is_partition_of(&self, disk: &FirmwareDisk, partition: &PartitionInfo) -> bool {
+        if !self.device.media().logical
+            || self.info.block_size != disk.info.block_size
+            || self.info.total_blocks != partition.size_sectors
+        {
+            return false;
+        }
+        let mut ours = vec![0u8; self.info.block_size as usize];
+        let mut theirs = ours.clone();
+        self.read_blocks(0, &mut ours).is_ok()
+            && disk.read_blocks(partition.start_lba, &mut theirs).is_ok()
+            && ours == theirs
+    }
+}
+
+impl BlockDevice for FirmwareDisk {
+    fn info(&self) -> &BlockDeviceInfo {
+        &self.info
+    }
+
+    fn read_blocks(&self, lba: u64, buffer: &mut [u8]) -> Result<(), BlockError> {
+        self.info.check_request(lba, buffer.len())?;
+        self.device.read_blocks(lba, buffer).map_err(|_| BlockError::ReadError)
+    }
+}
+
+/// Find the Helix installations on all disks, best first
+///
+/// Scans every whole disk the firmware exposes and matches each ESP to the
+/// firmware device published for it, so the kernel can be read from there.
+pub fn discover() -> Vec<BootCandidate> {
+    use crate::protocols::EnumerableProtocol;
+
+    let Ok(devices) = crate::protocols::block::BlockDevice::enumerate() else {
+        return Vec::new();
+    };
+    let (partitions, disks): (Vec<_>, Vec<_>) = devices
+        .into_iter()
+        .filter(|device| device.media_present())
+        .map(FirmwareDisk::new)
+        .partition(|device| device.device.media().logical);
+
+    let mut candidates = Vec::new();
+    for (index, disk) in disks.iter().enumerate() {
+        let Ok(found) = scan_disk(index as u8, disk) else { continue };
+        for mut candidate in found {
+            if let Some(esp) = &candidate.esp {
+                candidate.esp_volume = partitions
+                    .iter()
+                    .find(|device| device.is_partition_of(disk, esp))
+                    .map(|device| device.device.handle());
+            }
+            candidates.push(candidate);
+        }
+    }
+    rank_candidates(&mut candidates);
+    candidates
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -940,4 +1277,133 @@ fn test_gpt_partition() {
         let part = GptPartition::parse(&data).unwrap();
         assert_eq!(part.partition_type(), PartitionType::EfiSystem);
     }
+
+    #[test]
+    fn test_crc32c() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+    }
+
+    struct MemDisk {
+        info: BlockDeviceInfo,
+        data: Vec<u8>,
+    }
+
+    impl BlockDevice for MemDisk {
+        fn info(&self) -> &BlockDeviceInfo {
+            &self.info
+        }
+
+        fn read_blocks(&self, lba: u64, buffer: &mut [u8]) -> Result<(), BlockError> {
+            self.info.check_request(lba, buffer.len())?;
+            let start = lba as usize * 512;
+            buffer.copy_from_slice(&self.data[start..start + buffer.len()]);
+            Ok(())
+        }
+    }
+
+    /// GPT disk of 256 sectors with the given (type, start, end, attributes)
+    fn gpt_disk(parts: &[(Guid, u64, u64, u64)]) -> MemDisk {
+        let mut info = BlockDeviceInfo::new(BlockDeviceType::Ssd);
+        info.total_blocks = 256;
+        let mut data = vec![0u8; 256 * 512];
+        data[446 + 4] = 0xEE;
+        data[510..512].copy_from_slice(&MBR_SIGNATURE.to_le_bytes());
+
+        let header = &mut data[512..1024];
+        header[..8].copy_from_slice(&GPT_SIGNATURE.to_le_bytes());
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+
+        for (i, &(type_guid, start, end, attributes)) in parts.iter().enumerate() {
+            let entry = &mut data[1024 + i * 128..1024 + (i + 1) * 128];
+            entry[..16].copy_from_slice(&type_guid.to_bytes());
+            entry[16..32].copy_from_slice(&Guid::new(i as u32 + 1, 0, 0, [0; 8]).to_bytes());
+            entry[32..40].copy_from_slice(&start.to_le_bytes());
+            entry[40..48].copy_from_slice(&end.to_le_bytes());
+            entry[48..56].copy_from_slice(&attributes.to_le_bytes());
+        }
+        MemDisk { info, data }
+    }
+
+    /// Write a HelixFS superblock of `blocks` 512-byte blocks at `lba`
+    fn format_helixfs(disk: &mut MemDisk, lba: u64, blocks: u64, write_time: u64, clean: bool) {
+        let sb = &mut disk.data[lba as usize * 512..][..HELIXFS_SUPERBLOCK_SIZE];
+        sb[0x000..0x008].copy_from_slice(&HELIXFS_MAGIC.to_le_bytes());
+        sb[0x008..0x00C].copy_from_slice(&1u32.to_le_bytes());
+        sb[0x028..0x02E].copy_from_slice(b"helix\0");
+        sb[0x048..0x04C].copy_from_slice(&512u32.to_le_bytes());
+        sb[0x04C] = 9;
+        sb[0x050..0x058].copy_from_slice(&blocks.to_le_bytes());
+        sb[0x0F8..0x0FA].copy_from_slice(&u16::from(!clean).to_le_bytes());
+        sb[0x108..0x110].copy_from_slice(&write_time.to_le_bytes());
+        sb[0x1FC..0x200].copy_from_slice(&HELIXFS_MAGIC2.to_le_bytes());
+        let crc = crc32c(&sb[..HELIXFS_SUPERBLOCK_SIZE - 8]);
+        sb[0x1F8..0x1FC].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    #[test]
+    fn test_helix_superblock() {
+        let mut disk = gpt_disk(&[]);
+        format_helixfs(&mut disk, 0, 64, 7, true);
+        let sb = HelixSuperblock::parse(&disk.data[..512]).unwrap();
+        assert_eq!(sb.label(), "helix");
+        assert_eq!(sb.size_bytes(), 64 * 512);
+        assert!(sb.clean);
+
+        // A corrupt primary falls back to the next replica
+        disk.data.copy_within(0..512, 512);
+        disk.data[0x050] ^= 1;
+        assert!(HelixSuperblock::parse(&disk.data[..512]).is_none());
+        assert_eq!(HelixSuperblock::find(&disk.data[..4096]), Some(sb));
+    }
+
+    #[test]
+    fn test_scan_disk() {
+        let priority = |p: u64| p << HELIX_ATTR_PRIORITY_SHIFT;
+        let mut disk = gpt_disk(&[
+            (partition_types::EFI_SYSTEM, 34, 63, 0),
+            (partition_types::HELIX_SYSTEM, 64, 127, 0),
+            (partition_types::HELIX_SYSTEM, 128, 191, priority(2)),
+            (partition_types::HELIX_SYSTEM, 192, 255, HELIX_ATTR_NO_AUTO | priority(15)),
+        ]);
+        format_helixfs(&mut disk, 64, 64, 100, true);
+        format_helixfs(&mut disk, 128, 64, 50, false);
+        format_helixfs(&mut disk, 192, 64, 100, true);
+
+        let mut candidates = scan_disk(0, &disk).unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].esp.map(|esp| esp.start_lba), Some(34));
+
+        rank_candidates(&mut candidates);
+        assert_eq!(candidates[0].partition.start_lba, 128);
+        assert_eq!(candidates[0].priority(), 2);
+        assert_eq!(candidates[1].partition.start_lba, 64);
+
+        // A filesystem larger than its partition is not trusted
+        format_helixfs(&mut disk, 128, 65, 50, false);
+        let candidates = scan_disk(0, &disk).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(
+            alloc::format!("{:x}", candidates[0].partition.partition_guid),
+            "00000002-0000-0000-0000-000000000000"
+        );
+    }
+
+    #[test]
+    fn test_rank_candidates() {
+        let mut disk = gpt_disk(&[
+            (partition_types::HELIX_SYSTEM, 64, 127, 0),
+            (partition_types::HELIX_SYSTEM, 128, 191, 0),
+        ]);
+        format_helixfs(&mut disk, 64, 64, 100, false);
+        format_helixfs(&mut disk, 128, 64, 50, true);
+
+        // Same priority: the clean filesystem wins over the newer one
+        let mut candidates = scan_disk(0, &disk).unwrap();
+        assert!(candidates[0].esp.is_none());
+        rank_candidates(&mut candidates);
+        assert_eq!(candidates[0].partition.start_lba, 128);
+    }
 }
@@ -27,6 +27,11 @@
 //! │                                                                         │
 //! └─────────────────────────────────────────────────────────────────────────┘
 //! ```
+//!
+//! The boot preset is chosen per machine: a fingerprint of the SMBIOS
+//! model, CPU and GPU is matched against hardware rules, the last preset
+//! that booted on this machine is reused, and a boot that never completed
+//! falls back to the previous good preset. The boot menu can override it.
 
 #![no_std]
 
@@ -276,6 +281,10 @@ pub struct HardwareProfile {
     pub product_name: [u8; 64],
     /// Product name length
     pub product_name_len: u8,
+    /// CPUID signature (family, model, stepping)
+    pub cpu_signature: u32,
+    /// Primary GPU PCI ID (vendor << 16 | device, 0 = none)
+    pub gpu_id: u32,
 }
 
 impl HardwareProfile {
@@ -310,9 +319,42 @@ pub const fn new() -> Self {
             system_vendor_len: 0,
             product_name: [0u8; 64],
             product_name_len: 0,
+            cpu_signature: 0,
+            gpu_id: 0,
         }
     }
 
+    /// Set system vendor and product (SMBIOS type 1), truncating long names
+    pub fn set_system(&mut self, vendor: &str, product: &str) {
+        let vendor = &vendor.as_bytes()[..vendor.len().min(self.system_vendor.len())];
+        self.system_vendor[..vendor.len()].copy_from_slice(vendor);
+        self.system_vendor_len = vendor.len() as u8;
+
+        let product = &product.as_bytes()[..product.len().min(self.product_name.len())];
+        self.product_name[..product.len()].copy_from_slice(product);
+        self.product_name_len = product.len() as u8;
+    }
+
+    /// Get system vendor as str
+    pub fn system_vendor_str(&self) -> &str {
+        core::str::from_utf8(&self.system_vendor[..self.system_vendor_len as usize]).unwrap_or("")
+    }
+
+    /// Get product name as str
+    pub fn product_name_str(&self) -> &str {
+        core::str::from_utf8(&self.product_name[..self.product_name_len as usize]).unwrap_or("")
+    }
+
+    /// Fingerprint of the machine (SMBIOS model, CPU and GPU)
+    pub fn fingerprint(&self) -> HardwareFingerprint {
+        HardwareFingerprint::new()
+            .add(self.system_vendor_str().as_bytes())
+            .add(self.product_name_str().as_bytes())
+            .add(&[self.vendor as u8, self.arch as u8])
+            .add(&self.cpu_signature.to_le_bytes())
+            .add(&self.gpu_id.to_le_bytes())
+    }
+
     /// Check if mobile platform
     pub const fn is_mobile(&self) -> bool {
         matches!(self.platform, HardwarePlatform::Laptop) || self.caps.battery
@@ -330,6 +372,46 @@ fn default() -> Self {
     }
 }
 
+// =============================================================================
+// HARDWARE FINGERPRINT
+// =============================================================================
+
+/// Hash identifying a machine (FNV-1a over its identity fields)
+///
+/// Changes when the board, CPU or GPU changes, so per-machine choices
+/// are not carried over to different hardware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HardwareFingerprint(pub u64);
+
+impl HardwareFingerprint {
+    const OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01B3;
+
+    /// Create empty fingerprint
+    pub const fn new() -> Self {
+        Self(Self::OFFSET)
+    }
+
+    /// Mix in a field; fields are separated so ("ab", "c") != ("a", "bc")
+    pub const fn add(self, bytes: &[u8]) -> Self {
+        let mut hash = self.0;
+        let mut i = 0;
+        while i < bytes.len() {
+            hash ^= bytes[i] as u64;
+            hash = hash.wrapping_mul(Self::PRIME);
+            i += 1;
+        }
+        hash ^= 0xFF;
+        Self(hash.wrapping_mul(Self::PRIME))
+    }
+}
+
+impl fmt::Display for HardwareFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
 // =============================================================================
 // BOOT PRESET
 // =============================================================================
@@ -365,6 +447,46 @@ fn default() -> Self {
     }
 }
 
+impl PresetType {
+    /// Presets offered in the boot menu, in cycling order
+    pub const SELECTABLE: [PresetType; 6] = [
+        PresetType::Normal,
+        PresetType::Quick,
+        PresetType::Safe,
+        PresetType::Debug,
+        PresetType::Recovery,
+        PresetType::Minimal,
+    ];
+
+    /// Next selectable preset (wraps)
+    pub fn next(self) -> Self {
+        let index = Self::SELECTABLE.iter().position(|&p| p == self).map_or(0, |i| i + 1);
+        Self::SELECTABLE[index % Self::SELECTABLE.len()]
+    }
+
+    /// Stable code for persistence
+    pub const fn code(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Preset from persisted code
+    pub const fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            0 => PresetType::Quick,
+            1 => PresetType::Normal,
+            2 => PresetType::Full,
+            3 => PresetType::Debug,
+            4 => PresetType::Safe,
+            5 => PresetType::Recovery,
+            6 => PresetType::Minimal,
+            7 => PresetType::Gaming,
+            8 => PresetType::PowerSave,
+            9 => PresetType::Custom,
+            _ => return None,
+        })
+    }
+}
+
 impl fmt::Display for PresetType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -868,6 +990,157 @@ pub struct ParamSet {
 /// Maximum user profiles
 pub const MAX_PROFILES: usize = 16;
 
+/// Maximum hardware rules
+pub const MAX_RULES: usize = 16;
+
+/// Hardware a rule applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareMatch {
+    /// One machine
+    Fingerprint(HardwareFingerprint),
+    /// SMBIOS product name starting with the prefix
+    Product([u8; 32], u8),
+    /// CPU vendor
+    CpuVendor(CpuVendor),
+    /// Primary GPU PCI vendor
+    GpuVendor(u16),
+    /// Platform type
+    Platform(HardwarePlatform),
+    /// Any virtual machine
+    Virtual,
+}
+
+impl HardwareMatch {
+    /// Match SMBIOS product names starting with `prefix` (up to 32 bytes)
+    pub fn product(prefix: &str) -> Self {
+        let mut name = [0u8; 32];
+        let len = prefix.len().min(name.len());
+        name[..len].copy_from_slice(&prefix.as_bytes()[..len]);
+        HardwareMatch::Product(name, len as u8)
+    }
+
+    /// Check against a machine
+    pub fn matches(&self, hardware: &HardwareProfile) -> bool {
+        match *self {
+            HardwareMatch::Fingerprint(fingerprint) => hardware.fingerprint() == fingerprint,
+            HardwareMatch::Product(ref name, len) => {
+                hardware.product_name[..hardware.product_name_len as usize].starts_with(&name[..len as usize])
+            }
+            HardwareMatch::CpuVendor(vendor) => hardware.vendor == vendor,
+            HardwareMatch::GpuVendor(vendor) => hardware.gpu_id != 0 && (hardware.gpu_id >> 16) as u16 == vendor,
+            HardwareMatch::Platform(platform) => hardware.platform == platform,
+            HardwareMatch::Virtual => hardware.is_virtual(),
+        }
+    }
+}
+
+/// Hardware to preset mapping; the first matching rule wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileRule {
+    /// Hardware the rule applies to
+    pub hardware: HardwareMatch,
+    /// Preset to use
+    pub preset: PresetType,
+}
+
+/// Why a preset was chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionSource {
+    /// Chosen in the boot menu
+    Manual,
+    /// Last boot with another preset did not complete
+    Fallback,
+    /// Last preset that booted on this machine
+    LastGood,
+    /// Matching hardware rule
+    Rule,
+    /// Recommended for the platform
+    Hardware,
+}
+
+impl fmt::Display for SelectionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionSource::Manual => write!(f, "manual"),
+            SelectionSource::Fallback => write!(f, "fallback"),
+            SelectionSource::LastGood => write!(f, "last good"),
+            SelectionSource::Rule => write!(f, "rule"),
+            SelectionSource::Hardware => write!(f, "auto"),
+        }
+    }
+}
+
+/// Selected preset and the reason
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileSelection {
+    /// Preset
+    pub preset: PresetType,
+    /// Reason
+    pub source: SelectionSource,
+}
+
+/// Persisted record magic ("HPRF")
+pub const LAST_GOOD_MAGIC: u32 = 0x4650_5248;
+
+/// Persisted last-good record size
+pub const LAST_GOOD_SIZE: usize = 16;
+
+/// Last-good profile, persisted across boots (e.g. in an NV variable)
+///
+/// [`LastGoodProfile::attempt`] is recorded before the kernel starts and
+/// [`LastGoodProfile::confirm`] once the boot completed. An attempt still
+/// pending on the next boot did not complete and is not reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LastGoodProfile {
+    /// Machine the record belongs to
+    pub fingerprint: HardwareFingerprint,
+    /// Last preset that booted
+    pub last_good: Option<PresetType>,
+    /// Preset of a boot that has not been confirmed
+    pub pending: Option<PresetType>,
+}
+
+impl LastGoodProfile {
+    /// Record a boot attempt
+    pub fn attempt(&mut self, fingerprint: HardwareFingerprint, preset: PresetType) {
+        if self.fingerprint != fingerprint {
+            *self = Self { fingerprint, ..Self::default() };
+        }
+        self.pending = Some(preset);
+    }
+
+    /// Mark the pending attempt as good
+    pub fn confirm(&mut self) {
+        if let Some(preset) = self.pending.take() {
+            self.last_good = Some(preset);
+        }
+    }
+
+    /// Serialize
+    pub fn to_bytes(&self) -> [u8; LAST_GOOD_SIZE] {
+        let mut bytes = [0u8; LAST_GOOD_SIZE];
+        bytes[0..4].copy_from_slice(&LAST_GOOD_MAGIC.to_le_bytes());
+        bytes[4..12].copy_from_slice(&self.fingerprint.0.to_le_bytes());
+        bytes[12] = self.last_good.map_or(0xFF, |p| p.code());
+        bytes[13] = self.pending.map_or(0xFF, |p| p.code());
+        bytes
+    }
+
+    /// Deserialize; `None` if the data is not a record
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < LAST_GOOD_SIZE || bytes[0..4] != LAST_GOOD_MAGIC.to_le_bytes() {
+            return None;
+        }
+        let mut fingerprint = [0u8; 8];
+        fingerprint.copy_from_slice(&bytes[4..12]);
+        Some(Self {
+            fingerprint: HardwareFingerprint(u64::from_le_bytes(fingerprint)),
+            last_good: PresetType::from_code(bytes[12]),
+            pending: PresetType::from_code(bytes[13]),
+        })
+    }
+}
+
 /// Profile manager
 #[derive(Debug, Clone)]
 pub struct ProfileManager {
@@ -883,6 +1156,14 @@ pub struct ProfileManager {
     pub profile_count: usize,
     /// Active profile index
     pub active_profile: usize,
+    /// Hardware rules
+    pub rules: [ProfileRule; MAX_RULES],
+    /// Rule count
+    pub rule_count: usize,
+    /// Persisted last-good record
+    pub last_good: LastGoodProfile,
+    /// Preset chosen in the boot menu
+    pub manual: Option<PresetType>,
 }
 
 impl Default for ProfileManager {
@@ -894,6 +1175,10 @@ fn default() -> Self {
             user_profiles: core::array::from_fn(|_| BootPreset::normal()),
             profile_count: 0,
             active_profile: 0,
+            rules: [ProfileManager::NO_RULE; MAX_RULES],
+            rule_count: 0,
+            last_good: LastGoodProfile { fingerprint: HardwareFingerprint(0), last_good: None, pending: None },
+            manual: None,
         }
     }
 }
@@ -908,9 +1193,18 @@ pub const fn new() -> Self {
             user_profiles: [BootPreset::normal(); MAX_PROFILES],
             profile_count: 0,
             active_profile: 0,
+            rules: [Self::NO_RULE; MAX_RULES],
+            rule_count: 0,
+            last_good: LastGoodProfile { fingerprint: HardwareFingerprint(0), last_good: None, pending: None },
+            manual: None,
         }
     }
 
+    const NO_RULE: ProfileRule = ProfileRule {
+        hardware: HardwareMatch::Virtual,
+        preset: PresetType::Normal,
+    };
+
     /// Add user profile
     pub fn add_profile(&mut self, profile: BootPreset) -> bool {
         if self.profile_count < MAX_PROFILES {
@@ -954,6 +1248,65 @@ pub fn recommended_preset(&self) -> PresetType {
             _ => PresetType::Normal,
         }
     }
+
+    /// Add hardware rule
+    pub fn add_rule(&mut self, rule: ProfileRule) -> bool {
+        if self.rule_count < MAX_RULES {
+            self.rules[self.rule_count] = rule;
+            self.rule_count += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Load the persisted last-good record (ignored if invalid)
+    pub fn restore(&mut self, bytes: &[u8]) {
+        if let Some(record) = LastGoodProfile::from_bytes(bytes) {
+            self.last_good = record;
+        }
+    }
+
+    /// Choose the preset for this boot
+    ///
+    /// Order: menu override, fallback after an unconfirmed boot, last good
+    /// preset, first matching rule, platform recommendation. Records from
+    /// other hardware are ignored.
+    pub fn auto_select(&self) -> ProfileSelection {
+        let select = |preset, source| ProfileSelection { preset, source };
+
+        if let Some(preset) = self.manual {
+            return select(preset, SelectionSource::Manual);
+        }
+
+        if self.last_good.fingerprint == self.hardware.fingerprint() {
+            match (self.last_good.pending, self.last_good.last_good) {
+                (Some(failed), last_good) if last_good != Some(failed) => {
+                    let preset = last_good.unwrap_or(PresetType::Safe);
+                    return select(preset, SelectionSource::Fallback);
+                }
+                (_, Some(preset)) => return select(preset, SelectionSource::LastGood),
+                _ => {}
+            }
+        }
+
+        let rules = &self.rules[..self.rule_count];
+        if let Some(rule) = rules.iter().find(|r| r.hardware.matches(&self.hardware)) {
+            return select(rule.preset, SelectionSource::Rule);
+        }
+
+        select(self.recommended_preset(), SelectionSource::Hardware)
+    }
+
+    /// Select the automatic (or overridden) preset and record the attempt;
+    /// persist [`ProfileManager::last_good`] before starting the kernel
+    pub fn begin_boot(&mut self) -> ProfileSelection {
+        let selection = self.auto_select();
+        self.select_preset(selection.preset);
+        let fingerprint = self.hardware.fingerprint();
+        self.last_good.attempt(fingerprint, selection.preset);
+        selection
+    }
 }
 
 // =============================================================================
@@ -1015,6 +1368,74 @@ fn test_profile_manager() {
         assert_eq!(manager.profile_count, 1);
     }
 
+    #[test]
+    fn test_hardware_fingerprint() {
+        let mut a = HardwareProfile::new();
+        a.set_system("LENOVO", "20XW0026GE");
+        a.cpu_signature = 0x806C1;
+        let mut b = a;
+        assert_eq!(a.fingerprint(), b.fingerprint());
+
+        b.gpu_id = 0x10DE_2484;
+        assert_ne!(a.fingerprint(), b.fingerprint());
+
+        assert_ne!(
+            HardwareFingerprint::new().add(b"ab").add(b"c"),
+            HardwareFingerprint::new().add(b"a").add(b"bc"),
+        );
+    }
+
+    #[test]
+    fn test_auto_select() {
+        let mut manager = ProfileManager::new();
+        manager.hardware.set_system("QEMU", "Standard PC (Q35 + ICH9, 2009)");
+        manager.hardware.gpu_id = 0x1234_1111;
+
+        assert_eq!(manager.auto_select().source, SelectionSource::Hardware);
+
+        manager.add_rule(ProfileRule { hardware: HardwareMatch::GpuVendor(0x10DE), preset: PresetType::Safe });
+        manager.add_rule(ProfileRule { hardware: HardwareMatch::product("Standard PC"), preset: PresetType::Quick });
+        let selection = manager.auto_select();
+        assert_eq!(selection, ProfileSelection { preset: PresetType::Quick, source: SelectionSource::Rule });
+
+        manager.manual = Some(PresetType::Debug);
+        assert_eq!(manager.auto_select().source, SelectionSource::Manual);
+    }
+
+    #[test]
+    fn test_last_good_profile() {
+        let mut manager = ProfileManager::new();
+        manager.hardware.set_system("Dell Inc.", "XPS 13 9310");
+        manager.add_rule(ProfileRule { hardware: HardwareMatch::product("XPS"), preset: PresetType::Quick });
+
+        // First boot completes: Quick becomes last good
+        assert_eq!(manager.begin_boot().preset, PresetType::Quick);
+        manager.last_good.confirm();
+        let saved = manager.last_good.to_bytes();
+
+        let mut next = ProfileManager::new();
+        next.hardware = manager.hardware;
+        next.restore(&saved);
+        assert_eq!(next.auto_select().source, SelectionSource::LastGood);
+
+        // A manual Debug boot that never completes falls back to Quick
+        next.manual = Some(PresetType::Debug);
+        next.begin_boot();
+        let saved = next.last_good.to_bytes();
+
+        let mut next = ProfileManager::new();
+        next.hardware = manager.hardware;
+        next.restore(&saved);
+        let selection = next.auto_select();
+        assert_eq!(selection, ProfileSelection { preset: PresetType::Quick, source: SelectionSource::Fallback });
+
+        // Records from other hardware are ignored
+        next.hardware.set_system("Dell Inc.", "XPS 15 9500");
+        assert_eq!(next.auto_select().source, SelectionSource::Hardware);
+
+        assert_eq!(LastGoodProfile::from_bytes(&[0u8; 4]), None);
+    }
+
     #[test]
     fn test_vm_type() {
         assert!(!VmType::None.is_vm());
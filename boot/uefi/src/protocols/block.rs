@@ -44,6 +44,11 @@ pub unsafe fn from_raw(protocol: *mut EfiBlockIoProtocol, handle: Handle) -> Sel
         Self { protocol, handle, media }
     }
 
+    /// Get the handle the protocol is installed on
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
     /// Get media information
     pub fn media(&self) -> &MediaInfo {
         &self.media
@@ -329,9 +334,9 @@ impl Protocol for BlockDevice {
     const GUID: Guid = BLOCK_IO_PROTOCOL_GUID;
 
     fn open(handle: Handle) -> Result<Self> {
-        use crate::services::boot_services;
+        use crate::services::try_boot_services;
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services().map_err(Error::from_status)?;
         let image = crate::services::image_handle().ok_or(Error::NotReady)?;
 
         let mut protocol: *mut core::ffi::c_void = core::ptr::null_mut();
@@ -31,7 +31,7 @@ pub unsafe fn new(system_table: *mut crate::raw::system_table::EfiSystemTable) -
 
     /// Get from global state
     pub fn get() -> Result<Self> {
-        let st = unsafe { crate::services::system_table() };
+        let st = crate::services::try_system_table().map_err(Error::from_status)?;
         Ok(Self { system_table: st as *const _ as *mut _ })
     }
 
@@ -291,7 +291,7 @@ pub fn wait_for_key(&self) -> Result<InputKey> {
         let events = [event];
         let mut index = 0usize;
 
-        let bs = unsafe { crate::services::boot_services() };
+        let bs = crate::services::try_boot_services().map_err(Error::from_status)?;
         let result = unsafe {
             ((*bs).wait_for_event)(1, events.as_ptr(), &mut index)
         };
@@ -0,0 +1,421 @@
+//! EDID
+//!
+//! Parser for the Extended Display Identification Data a display reports
+//! through the EFI EDID Active/Discovered protocols: identity, physical
+//! size, preferred timing, supported modes and, from the CTA-861
+//! extension, HDR capabilities.
+
+use crate::error::{Error, Result};
+use crate::handoff::DisplayInfo;
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// EDID block size
+pub const BLOCK_SIZE: usize = 128;
+
+/// Fixed header of the base block
+const HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+
+/// CTA-861 extension block tag
+const CTA_EXTENSION: u8 = 0x02;
+
+/// Established timings, byte 35 bit 7 first
+const ESTABLISHED: [(u32, u32, u32); 17] = [
+    (720, 400, 70), (720, 400, 88), (640, 480, 60), (640, 480, 67),
+    (640, 480, 72), (640, 480, 75), (800, 600, 56), (800, 600, 60),
+    (800, 600, 72), (800, 600, 75), (832, 624, 75), (1024, 768, 87),
+    (1024, 768, 60), (1024, 768, 70), (1024, 768, 75), (1280, 1024, 75),
+    (1152, 870, 75),
+];
+
+// =============================================================================
+// TIMINGS
+// =============================================================================
+
+/// Video mode a display accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoMode {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Refresh rate in Hz
+    pub refresh_hz: u32,
+}
+
+/// Detailed timing descriptor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetailedTiming {
+    /// Pixel clock in kHz
+    pub pixel_clock_khz: u32,
+    /// Horizontal active pixels
+    pub h_active: u32,
+    /// Horizontal blanking pixels
+    pub h_blank: u32,
+    /// Vertical active lines
+    pub v_active: u32,
+    /// Vertical blanking lines
+    pub v_blank: u32,
+    /// Image size (width, height) in millimetres
+    pub size_mm: (u16, u16),
+    /// Interlaced mode
+    pub interlaced: bool,
+}
+
+impl DetailedTiming {
+    /// Parse an 18-byte descriptor; `None` for display descriptors
+    fn parse(d: &[u8]) -> Option<Self> {
+        let clock = u16::from_le_bytes([d[0], d[1]]) as u32;
+        if clock == 0 {
+            return None;
+        }
+        let wide = |low: u8, high: u8| low as u32 | ((high as u32) << 8);
+
+        Some(Self {
+            pixel_clock_khz: clock * 10,
+            h_active: wide(d[2], d[4] >> 4),
+            h_blank: wide(d[3], d[4] & 0x0F),
+            v_active: wide(d[5], d[7] >> 4),
+            v_blank: wide(d[6], d[7] & 0x0F),
+            size_mm: (wide(d[12], d[14] >> 4) as u16, wide(d[13], d[14] & 0x0F) as u16),
+            interlaced: d[17] & 0x80 != 0,
+        })
+    }
+
+    /// Refresh rate in Hz, rounded
+    pub fn refresh_hz(&self) -> u32 {
+        let total = (self.h_active + self.h_blank) as u64 * (self.v_active + self.v_blank) as u64;
+        if total == 0 {
+            return 0;
+        }
+        ((self.pixel_clock_khz as u64 * 1000 + total / 2) / total) as u32
+    }
+
+    /// The mode this timing describes
+    pub fn mode(&self) -> VideoMode {
+        VideoMode { width: self.h_active, height: self.v_active, refresh_hz: self.refresh_hz() }
+    }
+}
+
+// =============================================================================
+// HDR
+// =============================================================================
+
+/// HDR static metadata (CTA-861.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HdrCapabilities {
+    /// SMPTE ST 2084 (PQ) transfer function
+    pub pq: bool,
+    /// Hybrid Log-Gamma transfer function
+    pub hlg: bool,
+    /// Desired content max luminance in cd/m²
+    pub max_luminance: Option<u16>,
+}
+
+impl HdrCapabilities {
+    /// Accepts an HDR transfer function
+    pub fn is_hdr(&self) -> bool {
+        self.pq || self.hlg
+    }
+}
+
+/// Decode a CTA-861.3 luminance code: 50 * 2^(code / 32) cd/m²
+fn luminance(code: u8) -> u16 {
+    // 2^(1/32) in 16.16 fixed point
+    let mut value = (50u64 << (code / 32)) << 16;
+    for _ in 0..code % 32 {
+        value = (value * 66_971) >> 16;
+    }
+    (value >> 16).min(u16::MAX as u64) as u16
+}
+
+// =============================================================================
+// EDID
+// =============================================================================
+
+/// Parsed EDID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edid {
+    /// PNP manufacturer ID
+    pub manufacturer: [u8; 3],
+    /// Product code
+    pub product: u16,
+    /// Serial number
+    pub serial: u32,
+    /// Year of manufacture
+    pub year: u16,
+    /// EDID version (major, minor)
+    pub version: (u8, u8),
+    /// Digital input
+    pub digital: bool,
+    /// Bits per colour channel (EDID 1.4 digital displays)
+    pub bits_per_color: Option<u8>,
+    /// Physical size (width, height) in millimetres, zero if unknown
+    pub size_mm: (u16, u16),
+    /// Monitor name descriptor
+    pub name: Option<String>,
+    /// Preferred (native) timing
+    pub preferred: Option<DetailedTiming>,
+    /// Every mode the display reports
+    pub modes: Vec<VideoMode>,
+    /// HDR capabilities from the CTA-861 extension
+    pub hdr: Option<HdrCapabilities>,
+}
+
+impl Edid {
+    /// Parse the base block and any extension blocks present in `data`
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < BLOCK_SIZE {
+            return Err(Error::BufferTooSmall);
+        }
+        if data[..8] != HEADER {
+            return Err(Error::InvalidMagic);
+        }
+        let base = &data[..BLOCK_SIZE];
+        if !checksum_ok(base) {
+            return Err(Error::CrcError);
+        }
+
+        let id = u16::from_be_bytes([base[8], base[9]]);
+        let letter = |shift: u16| b'@' + ((id >> shift) & 0x1F) as u8;
+        let digital = base[20] & 0x80 != 0;
+        let version = (base[18], base[19]);
+        let bits_per_color = match (digital && version >= (1, 4), (base[20] >> 4) & 0x07) {
+            (true, depth @ 1..=6) => Some(4 + 2 * depth),
+            _ => None,
+        };
+
+        let mut edid = Self {
+            manufacturer: [letter(10), letter(5), letter(0)],
+            product: u16::from_le_bytes([base[10], base[11]]),
+            serial: u32::from_le_bytes([base[12], base[13], base[14], base[15]]),
+            year: 1990 + base[17] as u16,
+            version,
+            digital,
+            bits_per_color,
+            size_mm: (base[21] as u16 * 10, base[22] as u16 * 10),
+            name: None,
+            preferred: None,
+            modes: Vec::new(),
+            hdr: None,
+        };
+
+        // Established timings
+        let established = u32::from_be_bytes([0, base[35], base[36], base[37]]);
+        for (bit, &(width, height, refresh_hz)) in ESTABLISHED.iter().enumerate() {
+            if established & (1 << (23 - bit)) != 0 {
+                edid.add_mode(VideoMode { width, height, refresh_hz });
+            }
+        }
+
+        // Standard timings
+        for timing in base[38..54].chunks_exact(2) {
+            if timing == [0x01, 0x01] || timing[0] == 0 {
+                continue;
+            }
+            let width = (timing[0] as u32 + 31) * 8;
+            let height = match timing[1] >> 6 {
+                0 if version < (1, 3) => width,
+                0 => width * 10 / 16,
+                1 => width * 3 / 4,
+                2 => width * 4 / 5,
+                _ => width * 9 / 16,
+            };
+            edid.add_mode(VideoMode { width, height, refresh_hz: (timing[1] & 0x3F) as u32 + 60 });
+        }
+
+        // Detailed timings and display descriptors; the first timing is
+        // the preferred one
+        for descriptor in base[54..126].chunks_exact(18) {
+            match DetailedTiming::parse(descriptor) {
+                Some(timing) => {
+                    if edid.preferred.is_none() {
+                        edid.preferred = Some(timing);
+                        if timing.size_mm.0 != 0 && timing.size_mm.1 != 0 {
+                            edid.size_mm = timing.size_mm;
+                        }
+                    }
+                    edid.add_mode(timing.mode());
+                }
+                None if descriptor[3] == 0xFC => {
+                    let text = &descriptor[5..18];
+                    let end = text.iter().position(|&b| b == b'\n').unwrap_or(text.len());
+                    edid.name = Some(String::from_utf8_lossy(&text[..end]).trim_end().into());
+                }
+                None => {}
+            }
+        }
+
+        // Extension blocks (a truncated or corrupt one only loses its data)
+        for block in data[BLOCK_SIZE..].chunks_exact(BLOCK_SIZE).take(base[126] as usize) {
+            if block[0] == CTA_EXTENSION && checksum_ok(block) {
+                edid.parse_cta(block);
+            }
+        }
+
+        Ok(edid)
+    }
+
+    /// HDR metadata and detailed timings of a CTA-861 extension block
+    fn parse_cta(&mut self, block: &[u8]) {
+        let dtd_start = (block[2] as usize).min(127);
+        let mut offset = 4;
+        while offset < dtd_start {
+            let tag = block[offset] >> 5;
+            let len = (block[offset] & 0x1F) as usize;
+            let payload = block.get(offset + 1..(offset + 1 + len).min(dtd_start)).unwrap_or(&[]);
+            // Extended tag 6: HDR static metadata
+            if tag == 7 && payload.len() >= 3 && payload[0] == 0x06 {
+                self.hdr = Some(HdrCapabilities {
+                    pq: payload[1] & 0x04 != 0,
+                    hlg: payload[1] & 0x08 != 0,
+                    max_luminance: payload.get(3).filter(|&&code| code != 0).map(|&code| luminance(code)),
+                });
+            }
+            offset += 1 + len;
+        }
+
+        if dtd_start >= 4 {
+            for descriptor in block[dtd_start..127].chunks_exact(18) {
+                if let Some(timing) = DetailedTiming::parse(descriptor) {
+                    self.add_mode(timing.mode());
+                }
+            }
+        }
+    }
+
+    fn add_mode(&mut self, mode: VideoMode) {
+        if !self.modes.contains(&mode) {
+            self.modes.push(mode);
+        }
+    }
+
+    /// PNP manufacturer ID as text
+    pub fn manufacturer_id(&self) -> &str {
+        core::str::from_utf8(&self.manufacturer).unwrap_or("???")
+    }
+
+    /// Native resolution (width, height)
+    pub fn native_resolution(&self) -> Option<(u32, u32)> {
+        self.preferred.map(|t| (t.h_active, t.v_active))
+    }
+
+    /// Does the display accept `width` x `height` at any refresh rate?
+    pub fn supports(&self, width: u32, height: u32) -> bool {
+        self.modes.iter().any(|m| m.width == width && m.height == height)
+    }
+
+    /// Summary handed to the kernel
+    pub fn display_info(&self) -> DisplayInfo {
+        let hdr = self.hdr.unwrap_or_default();
+        DisplayInfo {
+            manufacturer: self.manufacturer,
+            product: self.product,
+            native: self.native_resolution().unwrap_or((0, 0)),
+            refresh_hz: self.preferred.map_or(0, |t| t.refresh_hz()),
+            size_mm: self.size_mm,
+            hdr: hdr.is_hdr(),
+            max_luminance: hdr.max_luminance.unwrap_or(0),
+            orientation: 0,
+        }
+    }
+}
+
+/// Bytes of an EDID block sum to zero
+fn checksum_ok(block: &[u8]) -> bool {
+    block.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) == 0
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seal(block: &mut [u8]) {
+        let sum = block[..127].iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+        block[127] = 0u8.wrapping_sub(sum);
+    }
+
+    /// 2560x1440@60 27" panel from "DEL" with an HDR10 CTA block
+    fn sample() -> Vec<u8> {
+        let mut edid = alloc::vec![0u8; 2 * BLOCK_SIZE];
+        let (base, cta) = edid.split_at_mut(BLOCK_SIZE);
+
+        base[..8].copy_from_slice(&HEADER);
+        base[8..10].copy_from_slice(&0x10ACu16.to_be_bytes());
+        base[10..12].copy_from_slice(&0xA0C3u16.to_le_bytes());
+        base[17] = 30;
+        base[18..20].copy_from_slice(&[1, 4]);
+        base[20] = 0x80 | (3 << 4);
+        base[21..23].copy_from_slice(&[60, 34]);
+        base[35] = 0x20; // 640x480@60
+        base[38..54].fill(0x01);
+        base[38..40].copy_from_slice(&[(1920 / 8 - 31) as u8, 0xC0]); // 1920x1080@60
+
+        // 2560x1440, 160/41 blanking, 241.5 MHz, 597x336 mm
+        base[54..72].copy_from_slice(&[
+            0x56, 0x5E, 0x00, 0xA0, 0xA0, 0xA0, 0x29, 0x50, 0x30, 0x20,
+            0x35, 0x00, 0x55, 0x50, 0x21, 0x00, 0x00, 0x1A,
+        ]);
+        base[72..90].copy_from_slice(&[
+            0, 0, 0, 0xFC, 0, b'D', b'E', b'L', b'L', b' ', b'U', b'2', b'7', b'\n', b' ', b' ', b' ', b' ',
+        ]);
+        base[126] = 1;
+        seal(base);
+
+        cta[..4].copy_from_slice(&[CTA_EXTENSION, 3, 10, 0]);
+        cta[4..10].copy_from_slice(&[0xE5, 0x06, 0x05, 0x01, 0x60, 0x00]);
+        seal(cta);
+        edid
+    }
+
+    #[test]
+    fn test_parse() {
+        let edid = Edid::parse(&sample()).unwrap();
+        assert_eq!(edid.manufacturer_id(), "DEL");
+        assert_eq!(edid.year, 2020);
+        assert_eq!(edid.bits_per_color, Some(10));
+        assert_eq!(edid.name.as_deref(), Some("DELL U27"));
+        assert_eq!(edid.native_resolution(), Some((2560, 1440)));
+        assert_eq!(edid.preferred.unwrap().refresh_hz(), 60);
+        assert_eq!(edid.size_mm, (597, 336));
+        assert!(edid.supports(640, 480) && edid.supports(1920, 1080));
+        assert!(!edid.supports(3840, 2160));
+
+        let hdr = edid.hdr.unwrap();
+        assert!(hdr.pq && !hdr.hlg);
+        assert_eq!(hdr.max_luminance, Some(400));
+
+        let display = edid.display_info();
+        assert_eq!(display.native, (2560, 1440));
+        assert!(display.hdr);
+        assert_eq!(display.dpi(), Some(108));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut edid = sample();
+        assert_eq!(Edid::parse(&edid[..64]), Err(Error::BufferTooSmall));
+        edid[20] ^= 1;
+        assert_eq!(Edid::parse(&edid), Err(Error::CrcError));
+        edid[0] = 1;
+        assert_eq!(Edid::parse(&edid), Err(Error::InvalidMagic));
+    }
+
+    #[test]
+    fn test_luminance() {
+        assert_eq!(luminance(0), 50);
+        assert_eq!(luminance(32), 100);
+        assert_eq!(luminance(96), 400);
+        assert_eq!(luminance(16), 70);
+    }
+}
@@ -4,6 +4,8 @@
 
 use crate::raw::types::*;
 use crate::raw::protocols::file::*;
+use crate::raw::protocols::loaded_image::EfiLoadedImageProtocol;
+use crate::raw::boot_services::EfiBootServices;
 use crate::error::{Error, Result};
 use super::{Protocol, EnumerableProtocol, DevicePath};
 
@@ -11,6 +13,10 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
+/// Path length (UCS-2 units, with the terminator) beyond which paths are
+/// opened one component at a time; EDK2's FAT driver rejects longer ones
+const FIRMWARE_PATH_LIMIT: usize = 260;
+
 // =============================================================================
 // FILE SYSTEM
 // =============================================================================
@@ -34,6 +40,67 @@ pub unsafe fn from_raw(protocol: *mut EfiSimpleFileSystemProtocol, handle: Handl
         Self { protocol, handle, root: None }
     }
 
+    /// Open the file system the image was loaded from (normally the ESP)
+    ///
+    /// # Safety
+    /// `bs` must be the firmware boot services table, before `ExitBootServices`
+    pub unsafe fn boot_volume(bs: &EfiBootServices, image: Handle) -> Result<Self> {
+        let mut loaded: *mut core::ffi::c_void = core::ptr::null_mut();
+        let result = (bs.open_protocol)(
+            image,
+            &EfiLoadedImageProtocol::GUID,
+            &mut loaded,
+            image,
+            Handle::null(),
+            open_protocol::GET_PROTOCOL,
+        );
+
+        if result != Status::SUCCESS {
+            return Err(Error::from_status(result));
+        }
+        if loaded.is_null() {
+            return Err(Error::NotFound);
+        }
+
+        // Images loaded from memory or the network have no device
+        let device = (*loaded.cast::<EfiLoadedImageProtocol>()).device_handle;
+        if device.is_null() {
+            return Err(Error::NotFound);
+        }
+
+        Self::open_on(bs, device, image)
+    }
+
+    /// Open the file system on a handle
+    ///
+    /// # Safety
+    /// `bs` must be the firmware boot services table, before `ExitBootServices`
+    pub unsafe fn open_on(bs: &EfiBootServices, handle: Handle, agent: Handle) -> Result<Self> {
+        let mut interface: *mut core::ffi::c_void = core::ptr::null_mut();
+        let result = (bs.open_protocol)(
+            handle,
+            &SIMPLE_FILE_SYSTEM_PROTOCOL_GUID,
+            &mut interface,
+            agent,
+            Handle::null(),
+            open_protocol::GET_PROTOCOL,
+        );
+
+        if result != Status::SUCCESS {
+            return Err(Error::from_status(result));
+        }
+        if interface.is_null() {
+            return Err(Error::NotFound);
+        }
+
+        Ok(Self::from_raw(interface.cast(), handle))
+    }
+
+    /// Handle the file system was opened on
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
     /// Open root directory
     fn open_root(&mut self) -> Result<*mut EfiFileProtocol> {
         if let Some(root) = self.root {
@@ -56,24 +123,7 @@ fn open_root(&mut self) -> Result<*mut EfiFileProtocol> {
     /// Open file by path
     pub fn open(&mut self, path: &str, mode: FileMode) -> Result<File> {
         let root = self.open_root()?;
-
-        // Convert path to UCS-2
-        let path_ucs2 = to_ucs2(path);
-
-        let mut file: *mut EfiFileProtocol = core::ptr::null_mut();
-        let result = unsafe {
-            ((*root).open)(
-                root,
-                &mut file,
-                path_ucs2.as_ptr(),
-                mode.to_raw(),
-                0,
-            )
-        };
-
-        if result != Status::SUCCESS {
-            return Err(Error::from_status(result));
-        }
+        let file = open_path(root, path, mode.to_raw(), 0)?;
 
         Ok(File { protocol: file, path: path.into() })
     }
@@ -81,22 +131,7 @@ pub fn open(&mut self, path: &str, mode: FileMode) -> Result<File> {
     /// Open directory
     pub fn open_dir(&mut self, path: &str) -> Result<Directory> {
         let root = self.open_root()?;
-        let path_ucs2 = to_ucs2(path);
-
-        let mut dir: *mut EfiFileProtocol = core::ptr::null_mut();
-        let result = unsafe {
-            ((*root).open)(
-                root,
-                &mut dir,
-                path_ucs2.as_ptr(),
-                FILE_MODE_READ,
-                FILE_ATTRIBUTE_DIRECTORY,
-            )
-        };
-
-        if result != Status::SUCCESS {
-            return Err(Error::from_status(result));
-        }
+        let dir = open_path(root, path, FILE_MODE_READ, FILE_ATTRIBUTE_DIRECTORY)?;
 
         Ok(Directory { protocol: dir, path: path.into() })
     }
@@ -142,22 +177,12 @@ pub fn write_string(&mut self, path: &str, content: &str) -> Result<()> {
     /// Create directory
     pub fn create_dir(&mut self, path: &str) -> Result<()> {
         let root = self.open_root()?;
-        let path_ucs2 = to_ucs2(path);
-
-        let mut dir: *mut EfiFileProtocol = core::ptr::null_mut();
-        let result = unsafe {
-            ((*root).open)(
-                root,
-                &mut dir,
-                path_ucs2.as_ptr(),
-                FILE_MODE_READ | FILE_MODE_WRITE | FILE_MODE_CREATE,
-                FILE_ATTRIBUTE_DIRECTORY,
-            )
-        };
-
-        if result != Status::SUCCESS {
-            return Err(Error::from_status(result));
-        }
+        let dir = open_path(
+            root,
+            path,
+            FILE_MODE_READ | FILE_MODE_WRITE | FILE_MODE_CREATE,
+            FILE_ATTRIBUTE_DIRECTORY,
+        )?;
 
         // Close the directory
         unsafe { ((*dir).close)(dir) };
@@ -210,28 +235,18 @@ pub fn volume_label(&mut self) -> Result<String> {
         let root = self.open_root()?;
 
         // Query volume info
-        let mut buffer = [0u8; 512];
-        let mut size = buffer.len();
-
-        let result = unsafe {
-            ((*root).get_info)(
-                root,
-                &EFI_FILE_SYSTEM_INFO_GUID,
-                &mut size,
-                buffer.as_mut_ptr(),
-            )
-        };
-
-        if result != Status::SUCCESS {
-            return Err(Error::from_status(result));
-        }
+        let (buffer, size) = query_info(root, &EFI_FILE_SYSTEM_INFO_GUID)?;
 
         // Parse volume label from info
         // The label starts at offset 36 as UCS-2
         if size > 36 {
-            let label_ptr = unsafe { buffer.as_ptr().add(36) as *const u16 };
-            let label = from_ucs2(label_ptr);
-            Ok(label)
+            let label = unsafe {
+                core::slice::from_raw_parts(
+                    buffer.as_ptr().cast::<u8>().add(36).cast::<u16>(),
+                    (size - 36) / 2,
+                )
+            };
+            Ok(from_ucs2(label))
         } else {
             Ok(String::new())
         }
@@ -242,9 +257,9 @@ impl Protocol for FileSystem {
     const GUID: Guid = SIMPLE_FILE_SYSTEM_PROTOCOL_GUID;
 
     fn open(handle: Handle) -> Result<Self> {
-        use crate::services::boot_services;
+        use crate::services::try_boot_services;
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services().map_err(Error::from_status)?;
         let image = crate::services::image_handle().ok_or(Error::NotReady)?;
 
         let mut protocol: *mut core::ffi::c_void = core::ptr::null_mut();
@@ -420,42 +435,49 @@ pub fn seek_end(&mut self) -> Result<u64> {
         self.position()
     }
 
+    /// Seek relative to the start, end or current position
+    ///
+    /// Returns the new position.
+    pub fn seek_from(&mut self, pos: SeekFrom) -> Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size()?.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position()?.checked_add_signed(offset),
+        }
+        .ok_or(Error::InvalidParameter)?;
+
+        self.seek(target)?;
+        Ok(target)
+    }
+
     /// Get file info
     pub fn info(&self) -> Result<FileInfo> {
-        let mut buffer = [0u8; 512];
-        let mut size = buffer.len();
+        let (buffer, size) = query_info(self.protocol, &EFI_FILE_INFO_GUID)?;
+        FileInfo::from_raw(&buffer, size)
+    }
+
+    /// Set file info
+    ///
+    /// Applies the size, attributes and name (renaming within the
+    /// directory); the times are left as they are.
+    pub fn set_info(&mut self, info: &FileInfo) -> Result<()> {
+        let (current, _) = query_info(self.protocol, &EFI_FILE_INFO_GUID)?;
+        let buffer = info.to_raw(&current);
 
         let result = unsafe {
-            ((*self.protocol).get_info)(
+            ((*self.protocol).set_info)(
                 self.protocol,
                 &EFI_FILE_INFO_GUID,
-                &mut size,
-                buffer.as_mut_ptr(),
+                buffer.len() * 8,
+                buffer.as_ptr().cast(),
             )
         };
 
-        if result != Status::SUCCESS {
-            return Err(Error::from_status(result));
+        if result == Status::SUCCESS {
+            Ok(())
+        } else {
+            Err(Error::from_status(result))
         }
-
-        // Parse EfiFileInfo
-        let raw = unsafe { &*(buffer.as_ptr() as *const EfiFileInfo) };
-
-        Ok(FileInfo {
-            size: raw.file_size,
-            physical_size: raw.physical_size,
-            create_time: time_from_raw(&raw.create_time),
-            modify_time: time_from_raw(&raw.last_access_time),
-            access_time: time_from_raw(&raw.modification_time),
-            attributes: FileAttributes(raw.attribute.0),
-            name: from_ucs2(raw.file_name.as_ptr()),
-        })
-    }
-
-    /// Set file info
-    pub fn set_info(&mut self, info: &FileInfo) -> Result<()> {
-        // TODO: Implement set_info
-        Ok(())
     }
 
     /// Flush to disk
@@ -521,59 +543,39 @@ pub struct Directory {
 impl Directory {
     /// Read next entry
     pub fn read_entry(&mut self) -> Result<Option<FileInfo>> {
-        let mut buffer = [0u8; 512];
-        let mut size = buffer.len();
-
-        let result = unsafe {
-            ((*self.protocol).read)(
-                self.protocol,
-                &mut size,
-                buffer.as_mut_ptr(),
-            )
-        };
-
-        if result != Status::SUCCESS {
-            return Err(Error::from_status(result));
-        }
+        let protocol = self.protocol;
+        let (buffer, size) = fill_buffer(|size, buffer| unsafe {
+            ((*protocol).read)(protocol, size, buffer)
+        })?;
 
         if size == 0 {
             return Ok(None);
         }
 
-        // Parse EfiFileInfo
-        let raw = unsafe { &*(buffer.as_ptr() as *const EfiFileInfo) };
+        FileInfo::from_raw(&buffer, size).map(Some)
+    }
 
-        Ok(Some(FileInfo {
-            size: raw.file_size,
-            physical_size: raw.physical_size,
-            create_time: time_from_raw(&raw.create_time),
-            modify_time: time_from_raw(&raw.last_access_time),
-            access_time: time_from_raw(&raw.modification_time),
-            attributes: FileAttributes(raw.attribute.0),
-            name: from_ucs2(raw.file_name.as_ptr()),
-        }))
+    /// Iterate over the entries from the start, skipping `.` and `..`
+    pub fn iter_entries(&mut self) -> Result<DirEntries<'_>> {
+        self.rewind()?;
+        Ok(DirEntries { dir: self, done: false })
     }
 
     /// Get all entries
     pub fn entries(&mut self) -> Result<Vec<FileInfo>> {
-        let mut entries = Vec::new();
-
-        // Rewind first
-        let result = unsafe {
-            ((*self.protocol).set_position)(self.protocol, 0)
-        };
-        if result != Status::SUCCESS {
-            return Err(Error::from_status(result));
-        }
+        self.iter_entries()?.collect()
+    }
 
-        while let Some(entry) = self.read_entry()? {
-            // Skip . and ..
-            if entry.name != "." && entry.name != ".." {
-                entries.push(entry);
-            }
-        }
+    /// Open a file relative to this directory
+    pub fn open(&self, path: &str, mode: FileMode) -> Result<File> {
+        let file = open_path(self.protocol, path, mode.to_raw(), 0)?;
+        Ok(File { protocol: file, path: Path::join(&self.path, path) })
+    }
 
-        Ok(entries)
+    /// Open a subdirectory relative to this directory
+    pub fn open_dir(&self, path: &str) -> Result<Directory> {
+        let dir = open_path(self.protocol, path, FILE_MODE_READ, FILE_ATTRIBUTE_DIRECTORY)?;
+        Ok(Directory { protocol: dir, path: Path::join(&self.path, path) })
     }
 
     /// Get files only
@@ -625,6 +627,31 @@ fn drop(&mut self) {
     }
 }
 
+/// Iterator over directory entries, from [`Directory::iter_entries`]
+pub struct DirEntries<'a> {
+    dir: &'a mut Directory,
+    done: bool,
+}
+
+impl Iterator for DirEntries<'_> {
+    type Item = Result<FileInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.dir.read_entry() {
+                Ok(Some(entry)) if entry.name == "." || entry.name == ".." => {}
+                Ok(Some(entry)) => return Some(Ok(entry)),
+                Ok(None) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
 // =============================================================================
 // FILE INFO
 // =============================================================================
@@ -649,6 +676,59 @@ pub struct FileInfo {
 }
 
 impl FileInfo {
+    /// Parse an `EFI_FILE_INFO` of `size` bytes
+    fn from_raw(buffer: &[u64], size: usize) -> Result<Self> {
+        let name_offset = core::mem::offset_of!(EfiFileInfo, file_name);
+        let size = size.min(buffer.len() * 8);
+        if size < name_offset {
+            return Err(Error::VolumeCorrupted);
+        }
+
+        let raw = unsafe { &*buffer.as_ptr().cast::<EfiFileInfo>() };
+        let end = (raw.size as usize).clamp(name_offset, size);
+        let name = unsafe {
+            core::slice::from_raw_parts(
+                buffer.as_ptr().cast::<u8>().add(name_offset).cast::<u16>(),
+                (end - name_offset) / 2,
+            )
+        };
+
+        Ok(Self {
+            size: raw.file_size,
+            physical_size: raw.physical_size,
+            create_time: time_from_raw(&raw.create_time),
+            modify_time: time_from_raw(&raw.modification_time),
+            access_time: time_from_raw(&raw.last_access_time),
+            attributes: FileAttributes(raw.attribute.0),
+            name: from_ucs2(name),
+        })
+    }
+
+    /// Build an `EFI_FILE_INFO` from this, keeping the times of `current`
+    fn to_raw(&self, current: &[u64]) -> Vec<u64> {
+        let name_offset = core::mem::offset_of!(EfiFileInfo, file_name);
+        let name: Vec<u16> = self.name.encode_utf16().chain(Some(0)).collect();
+        let size = name_offset + name.len() * 2;
+
+        let mut buffer = alloc::vec![0u64; size.div_ceil(8)];
+        let header = name_offset / 8;
+        buffer[..header].copy_from_slice(&current[..header]);
+
+        unsafe {
+            let raw = buffer.as_mut_ptr().cast::<EfiFileInfo>();
+            (*raw).size = size as u64;
+            (*raw).file_size = self.size;
+            (*raw).attribute = FileAttribute(self.attributes.0);
+            core::ptr::copy_nonoverlapping(
+                name.as_ptr(),
+                buffer.as_mut_ptr().cast::<u8>().add(name_offset).cast::<u16>(),
+                name.len(),
+            );
+        }
+
+        buffer
+    }
+
     /// Check if directory
     pub fn is_directory(&self) -> bool {
         self.attributes.is_directory()
@@ -740,6 +820,21 @@ fn to_raw(self) -> u64 {
     }
 }
 
+// =============================================================================
+// SEEK
+// =============================================================================
+
+/// Seek origin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekFrom {
+    /// Offset from the start
+    Start(u64),
+    /// Offset from the end
+    End(i64),
+    /// Offset from the current position
+    Current(i64),
+}
+
 // =============================================================================
 // FILE ATTRIBUTES
 // =============================================================================
@@ -782,40 +877,109 @@ pub fn is_archive(&self) -> bool {
 
 /// Convert string to UCS-2
 fn to_ucs2(s: &str) -> Vec<u16> {
-    let mut buffer: Vec<u16> = s.chars()
+    s.encode_utf16()
         .map(|c| {
             // Replace forward slash with backslash for UEFI
-            if c == '/' { '\\' as u16 } else { c as u16 }
+            if c == '/' as u16 { '\\' as u16 } else { c }
         })
-        .collect();
-    buffer.push(0);
-    buffer
+        .chain(Some(0))
+        .collect()
 }
 
-/// Convert UCS-2 to string
-fn from_ucs2(ptr: *const u16) -> String {
-    let mut s = String::new();
-    let mut i = 0;
+/// Convert UCS-2 to string, stopping at the first NUL
+fn from_ucs2(units: &[u16]) -> String {
+    let len = units.iter().position(|&c| c == 0).unwrap_or(units.len());
+    char::decode_utf16(units[..len].iter().copied())
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
 
-    unsafe {
-        loop {
-            let c = *ptr.add(i);
-            if c == 0 {
-                break;
-            }
-            if let Some(ch) = char::from_u32(c as u32) {
-                s.push(ch);
-            }
-            i += 1;
+/// Split a NUL-terminated UCS-2 path into NUL-terminated components
+///
+/// A leading separator becomes a `\` component, which opens the root.
+fn path_components(path: &[u16]) -> Vec<Vec<u16>> {
+    let sep = '\\' as u16;
+    let path = path.strip_suffix(&[0]).unwrap_or(path);
 
-            // Safety limit
-            if i > 1024 {
-                break;
-            }
+    let mut components = Vec::new();
+    if path.first() == Some(&sep) {
+        components.push(alloc::vec![sep, 0]);
+    }
+    for name in path.split(|&c| c == sep).filter(|name| !name.is_empty()) {
+        let mut component = name.to_vec();
+        component.push(0);
+        components.push(component);
+    }
+    components
+}
+
+/// Open a NUL-terminated UCS-2 name relative to `base`
+fn open_raw(base: *mut EfiFileProtocol, name: &[u16], mode: u64, attributes: u64) -> Result<*mut EfiFileProtocol> {
+    let mut file: *mut EfiFileProtocol = core::ptr::null_mut();
+    let result = unsafe { ((*base).open)(base, &mut file, name.as_ptr(), mode, attributes) };
+
+    if result == Status::SUCCESS {
+        Ok(file)
+    } else {
+        Err(Error::from_status(result))
+    }
+}
+
+/// Open `path` relative to `base`
+///
+/// Paths over [`FIRMWARE_PATH_LIMIT`] are walked one directory at a time.
+fn open_path(base: *mut EfiFileProtocol, path: &str, mode: u64, attributes: u64) -> Result<*mut EfiFileProtocol> {
+    let path = to_ucs2(path);
+    if path.len() <= FIRMWARE_PATH_LIMIT {
+        return open_raw(base, &path, mode, attributes);
+    }
+
+    let components = path_components(&path);
+    let (last, dirs) = components.split_last().ok_or(Error::InvalidParameter)?;
+
+    let mut current = base;
+    for dir in dirs {
+        let next = open_raw(current, dir, FILE_MODE_READ, 0);
+        if current != base {
+            unsafe { ((*current).close)(current) };
+        }
+        current = next?;
+    }
+
+    let file = open_raw(current, last, mode, attributes);
+    if current != base {
+        unsafe { ((*current).close)(current) };
+    }
+    file
+}
+
+/// Run a firmware call that fills a buffer, growing it on `BUFFER_TOO_SMALL`
+///
+/// The buffer is `u64`-backed so structures read from it are aligned.
+/// Returns it with the byte count the firmware reported.
+fn fill_buffer(mut call: impl FnMut(&mut usize, *mut u8) -> Status) -> Result<(Vec<u64>, usize)> {
+    let mut capacity = 512;
+    loop {
+        let mut buffer = alloc::vec![0u64; capacity / 8];
+        let mut size = capacity;
+        let result = call(&mut size, buffer.as_mut_ptr().cast());
+
+        if result == Status::BUFFER_TOO_SMALL && size > capacity {
+            capacity = size.next_multiple_of(8);
+            continue;
+        }
+        if result != Status::SUCCESS {
+            return Err(Error::from_status(result));
         }
+        return Ok((buffer, size));
     }
+}
 
-    s
+/// Query an information type of an open file
+fn query_info(protocol: *mut EfiFileProtocol, info_type: &Guid) -> Result<(Vec<u64>, usize)> {
+    fill_buffer(|size, buffer| unsafe {
+        ((*protocol).get_info)(protocol, info_type, size, buffer)
+    })
 }
 
 /// Simple pattern matching
@@ -908,7 +1072,63 @@ fn test_ucs2_conversion() {
         let ucs2 = to_ucs2(s);
         assert_eq!(ucs2.len(), 5); // 4 chars + null
 
-        let back = from_ucs2(ucs2.as_ptr());
+        let back = from_ucs2(&ucs2);
         assert_eq!(back, "test");
+
+        let wide = to_ucs2("a/\u{1F600}");
+        assert_eq!(wide.len(), 5); // surrogate pair
+        assert_eq!(from_ucs2(&wide), "a\\\u{1F600}");
+    }
+
+    #[test]
+    fn test_path_components() {
+        let names = |path: &str| -> Vec<String> {
+            path_components(&to_ucs2(path)).iter().map(|c| from_ucs2(c)).collect()
+        };
+
+        assert_eq!(names("/EFI//BOOT/bootx64.efi"), ["\\", "EFI", "BOOT", "bootx64.efi"]);
+        assert_eq!(names("helix\\kernel"), ["helix", "kernel"]);
+    }
+
+    #[test]
+    fn test_fill_buffer_grows() {
+        let mut calls = 0;
+        let (buffer, size) = fill_buffer(|size, _| {
+            calls += 1;
+            if *size < 2000 {
+                *size = 2000;
+                Status::BUFFER_TOO_SMALL
+            } else {
+                *size = 1999;
+                Status::SUCCESS
+            }
+        })
+        .unwrap();
+
+        assert_eq!(calls, 2);
+        assert_eq!(size, 1999);
+        assert_eq!(buffer.len(), 250);
+    }
+
+    #[test]
+    fn test_file_info_long_name() {
+        let long = "x".repeat(1500);
+        let info = FileInfo {
+            size: 42,
+            physical_size: 0,
+            create_time: None,
+            modify_time: None,
+            access_time: None,
+            attributes: FileAttributes::ARCHIVE,
+            name: long.clone(),
+        };
+
+        let current = alloc::vec![0u64; 64];
+        let raw = info.to_raw(&current);
+        let parsed = FileInfo::from_raw(&raw, raw.len() * 8).unwrap();
+
+        assert_eq!(parsed.size, 42);
+        assert!(parsed.is_archive());
+        assert_eq!(parsed.name, long);
     }
 }
@@ -4,8 +4,11 @@
 
 use crate::raw::types::*;
 use crate::raw::protocols::gop::*;
+use crate::raw::boot_services::EfiBootServices;
 use crate::error::{Error, Result};
+use crate::handoff::{FramebufferInfo, PixelBitmask, PixelFormat as HandoffPixelFormat};
 use super::{Protocol, EnumerableProtocol};
+use super::edid::Edid;
 
 extern crate alloc;
 use alloc::vec::Vec;
@@ -34,6 +37,74 @@ pub unsafe fn from_raw(protocol: *mut EfiGraphicsOutputProtocol, handle: Handle)
         Self { protocol, handle }
     }
 
+    /// Acquire the GOP behind the console, or else the first one present
+    ///
+    /// # Safety
+    /// `bs` must be the firmware boot services table, before `ExitBootServices`
+    pub unsafe fn acquire(bs: &EfiBootServices, agent: Handle, console_out: Handle) -> Result<Self> {
+        if !console_out.is_null() {
+            if let Some(gop) = Self::open_on(bs, console_out, agent) {
+                return Ok(gop);
+            }
+        }
+
+        let mut count = 0usize;
+        let mut buffer: *mut Handle = core::ptr::null_mut();
+        let result = (bs.locate_handle_buffer)(
+            LocateSearchType::ByProtocol,
+            &GOP_GUID,
+            core::ptr::null_mut(),
+            &mut count,
+            &mut buffer,
+        );
+        if result == Status::SUCCESS && !buffer.is_null() {
+            let found = core::slice::from_raw_parts(buffer, count)
+                .iter()
+                .find_map(|&handle| Self::open_on(bs, handle, agent));
+            (bs.free_pool)(buffer.cast());
+
+            if let Some(gop) = found {
+                return Ok(gop);
+            }
+        }
+
+        // Handle-less instance (some firmware installs GOP this way)
+        let mut interface: *mut core::ffi::c_void = core::ptr::null_mut();
+        let result = (bs.locate_protocol)(&GOP_GUID, core::ptr::null_mut(), &mut interface);
+        if result != Status::SUCCESS {
+            return Err(Error::from_status(result));
+        }
+        if interface.is_null() {
+            return Err(Error::NotFound);
+        }
+
+        Ok(Self::from_raw(interface.cast(), Handle::null()))
+    }
+
+    /// Open GOP on a handle
+    unsafe fn open_on(bs: &EfiBootServices, handle: Handle, agent: Handle) -> Option<Self> {
+        let mut interface: *mut core::ffi::c_void = core::ptr::null_mut();
+        let result = (bs.open_protocol)(
+            handle,
+            &GOP_GUID,
+            &mut interface,
+            agent,
+            Handle::null(),
+            open_protocol::GET_PROTOCOL,
+        );
+
+        if result == Status::SUCCESS && !interface.is_null() {
+            Some(Self::from_raw(interface.cast(), handle))
+        } else {
+            None
+        }
+    }
+
+    /// Handle the protocol was opened on (null if located handle-less)
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
     /// Get current mode info
     pub fn mode_info(&self) -> Result<ModeInfo> {
         let mode = unsafe { &*(*self.protocol).mode };
@@ -144,6 +215,91 @@ pub fn set_resolution(&self, width: u32, height: u32) -> Result<()> {
         self.set_mode(mode)
     }
 
+    /// Switch to a resolution, or the largest mode that fits within it
+    pub fn set_preferred_mode(&self, width: u32, height: u32) -> Result<ModeInfo> {
+        let modes = self.available_modes()?;
+        let mode = select_mode(&modes, width, height).cloned().ok_or(Error::NotFound)?;
+
+        if mode.mode_number != self.mode_info()?.mode_number {
+            self.set_mode(mode.mode_number)?;
+        }
+        Ok(mode)
+    }
+
+    /// EDID of the display behind this GOP
+    ///
+    /// Prefers EDID Active (what the firmware drives, overrides included)
+    /// over EDID Discovered (what the display reported).
+    ///
+    /// # Safety
+    /// `bs` must be the firmware boot services table, before `ExitBootServices`
+    pub unsafe fn edid(&self, bs: &EfiBootServices, agent: Handle) -> Result<Edid> {
+        if self.handle.is_null() {
+            return Err(Error::NotFound);
+        }
+
+        for guid in [EfiEdidActiveProtocol::GUID, EfiEdidDiscoveredProtocol::GUID] {
+            let mut interface: *mut core::ffi::c_void = core::ptr::null_mut();
+            let result = (bs.open_protocol)(
+                self.handle,
+                &guid,
+                &mut interface,
+                agent,
+                Handle::null(),
+                open_protocol::GET_PROTOCOL,
+            );
+            if result != Status::SUCCESS || interface.is_null() {
+                continue;
+            }
+
+            // Both protocols share one layout
+            let protocol = &*(interface as *const EfiEdidDiscoveredProtocol);
+            if let Some(data) = protocol.edid_data() {
+                return Edid::parse(data);
+            }
+        }
+
+        Err(Error::NotFound)
+    }
+
+    /// Switch to the display's native mode
+    ///
+    /// Uses the EDID preferred timing, else the largest mode the display
+    /// reports; without EDID the firmware's choice is kept.
+    pub fn set_native_mode(&self, edid: Option<&Edid>) -> Result<ModeInfo> {
+        let current = self.mode_info()?;
+        let Some(edid) = edid else {
+            return Ok(current);
+        };
+
+        let modes = self.available_modes()?;
+        let mode = select_native_mode(&modes, edid).cloned().ok_or(Error::NotFound)?;
+        if mode.mode_number != current.mode_number {
+            self.set_mode(mode.mode_number)?;
+        }
+        Ok(mode)
+    }
+
+    /// Describe the current framebuffer for the kernel handoff
+    pub fn handoff_info(&self) -> Result<FramebufferInfo> {
+        let mode = unsafe { &*(*self.protocol).mode };
+        let info = unsafe { &*mode.info };
+
+        let (format, bitmask, bpp) = handoff_format(info.pixel_format, &info.pixel_information)
+            .ok_or(Error::Unsupported)?;
+
+        Ok(FramebufferInfo {
+            address: mode.frame_buffer_base,
+            size: mode.frame_buffer_size as u64,
+            width: info.horizontal_resolution,
+            height: info.vertical_resolution,
+            stride: info.pixels_per_scan_line * u32::from(bpp / 8),
+            format,
+            bitmask,
+            bpp,
+        })
+    }
+
     // =========================================================================
     // DRAWING OPERATIONS
     // =========================================================================
@@ -460,9 +616,9 @@ impl Protocol for GraphicsOutput {
     const GUID: Guid = GOP_GUID;
 
     fn open(handle: Handle) -> Result<Self> {
-        use crate::services::boot_services;
+        use crate::services::try_boot_services;
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services().map_err(Error::from_status)?;
         let image = crate::services::image_handle().ok_or(Error::NotReady)?;
 
         let mut protocol: *mut core::ffi::c_void = core::ptr::null_mut();
@@ -492,6 +648,62 @@ fn enumerate() -> Result<Vec<Self>> {
     }
 }
 
+/// Pick a mode for a resolution: exact match first, else the largest that fits
+fn select_mode(modes: &[ModeInfo], width: u32, height: u32) -> Option<&ModeInfo> {
+    let usable = || modes.iter().filter(|m| m.pixel_format.has_framebuffer());
+
+    usable()
+        .find(|m| m.width == width && m.height == height)
+        .or_else(|| {
+            usable()
+                .filter(|m| m.width <= width && m.height <= height)
+                .max_by_key(|m| m.resolution().pixels())
+        })
+}
+
+/// Pick the mode for a display: its preferred timing, else the largest
+/// mode it reports
+fn select_native_mode<'a>(modes: &'a [ModeInfo], edid: &Edid) -> Option<&'a ModeInfo> {
+    let usable = || modes.iter().filter(|m| m.pixel_format.has_framebuffer());
+
+    edid.native_resolution()
+        .and_then(|(width, height)| usable().find(|m| m.width == width && m.height == height))
+        .or_else(|| {
+            usable()
+                .filter(|m| edid.supports(m.width, m.height))
+                .max_by_key(|m| m.resolution().pixels())
+        })
+}
+
+/// Map a GOP pixel format to the handoff format, bitmask and bits per pixel
+///
+/// BLT-only modes have no linear framebuffer to hand over.
+fn handoff_format(
+    format: EfiGraphicsPixelFormat,
+    mask: &EfiPixelBitmask,
+) -> Option<(HandoffPixelFormat, PixelBitmask, u8)> {
+    match format {
+        EfiGraphicsPixelFormat::PixelRedGreenBlueReserved8BitPerColor => {
+            Some((HandoffPixelFormat::Rgb32, PixelBitmask::RGB32, 32))
+        }
+        EfiGraphicsPixelFormat::PixelBlueGreenRedReserved8BitPerColor => {
+            Some((HandoffPixelFormat::Bgr32, PixelBitmask::BGR32, 32))
+        }
+        EfiGraphicsPixelFormat::PixelBitMask => {
+            let bitmask = PixelBitmask {
+                red_mask: mask.red_mask,
+                green_mask: mask.green_mask,
+                blue_mask: mask.blue_mask,
+                alpha_mask: mask.reserved_mask,
+            };
+            let used = mask.red_mask | mask.green_mask | mask.blue_mask | mask.reserved_mask;
+            let bits = (32 - used.leading_zeros()).div_ceil(8) * 8;
+            Some((HandoffPixelFormat::Bitmask, bitmask, bits.max(8) as u8))
+        }
+        EfiGraphicsPixelFormat::PixelBltOnly => None,
+    }
+}
+
 // =============================================================================
 // MODE INFO
 // =============================================================================
@@ -896,6 +1108,7 @@ pub fn bytes_per_row(&self) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocols::edid::{DetailedTiming, VideoMode};
 
     #[test]
     fn test_pixel_creation() {
@@ -925,4 +1138,94 @@ fn test_resolution() {
         assert!(Resolution::FULL_HD.is_16_9());
         assert!(Resolution::XGA.is_4_3());
     }
+
+    fn mode(mode_number: u32, width: u32, height: u32, pixel_format: PixelFormat) -> ModeInfo {
+        ModeInfo {
+            width,
+            height,
+            pixel_format,
+            pixels_per_scan_line: width,
+            mode_number,
+            max_mode: 4,
+        }
+    }
+
+    #[test]
+    fn test_select_mode() {
+        let modes = [
+            mode(0, 640, 480, PixelFormat::Bgr),
+            mode(1, 1024, 768, PixelFormat::Bgr),
+            mode(2, 1920, 1080, PixelFormat::BltOnly),
+            mode(3, 1280, 1024, PixelFormat::Bgr),
+        ];
+
+        assert_eq!(select_mode(&modes, 1024, 768).unwrap().mode_number, 1);
+        assert_eq!(select_mode(&modes, 1920, 1080).unwrap().mode_number, 3);
+        assert_eq!(select_mode(&modes, 800, 600).unwrap().mode_number, 0);
+        assert!(select_mode(&modes, 320, 200).is_none());
+    }
+
+    #[test]
+    fn test_select_native_mode() {
+        let modes = [
+            mode(0, 640, 480, PixelFormat::Bgr),
+            mode(1, 1024, 768, PixelFormat::Bgr),
+            mode(2, 1920, 1080, PixelFormat::Bgr),
+            mode(3, 2560, 1440, PixelFormat::Bgr),
+        ];
+        let mut edid = Edid {
+            manufacturer: *b"DEL",
+            product: 0,
+            serial: 0,
+            year: 2020,
+            version: (1, 4),
+            digital: true,
+            bits_per_color: None,
+            size_mm: (0, 0),
+            name: None,
+            preferred: None,
+            modes: alloc::vec![
+                VideoMode { width: 640, height: 480, refresh_hz: 60 },
+                VideoMode { width: 1920, height: 1080, refresh_hz: 60 },
+            ],
+            hdr: None,
+        };
+        assert_eq!(select_native_mode(&modes, &edid).unwrap().mode_number, 2);
+
+        edid.preferred = Some(DetailedTiming {
+            pixel_clock_khz: 241_500,
+            h_active: 2560,
+            h_blank: 160,
+            v_active: 1440,
+            v_blank: 41,
+            size_mm: (597, 336),
+            interlaced: false,
+        });
+        assert_eq!(select_native_mode(&modes, &edid).unwrap().mode_number, 3);
+    }
+
+    #[test]
+    fn test_handoff_format() {
+        let (format, _, bpp) = handoff_format(
+            EfiGraphicsPixelFormat::PixelBlueGreenRedReserved8BitPerColor,
+            &EfiPixelBitmask::default(),
+        )
+        .unwrap();
+        assert_eq!(format, HandoffPixelFormat::Bgr32);
+        assert_eq!(bpp, 32);
+
+        let rgb565 = EfiPixelBitmask {
+            red_mask: 0xF800,
+            green_mask: 0x07E0,
+            blue_mask: 0x001F,
+            reserved_mask: 0,
+        };
+        let (format, bitmask, bpp) =
+            handoff_format(EfiGraphicsPixelFormat::PixelBitMask, &rgb565).unwrap();
+        assert_eq!(format, HandoffPixelFormat::Bitmask);
+        assert_eq!(bitmask.red_mask, 0xF800);
+        assert_eq!(bpp, 16);
+
+        assert!(handoff_format(EfiGraphicsPixelFormat::PixelBltOnly, &rgb565).is_none());
+    }
 }
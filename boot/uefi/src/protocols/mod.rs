@@ -31,6 +31,7 @@
 //!
 //! - **Console**: Text input/output with full keyboard support
 //! - **Graphics**: Framebuffer access with pixel drawing
+//! - **EDID**: Display identification and capabilities
 //! - **FileSystem**: File operations with path abstraction
 //! - **Block**: Raw disk access with partition support
 //! - **Serial**: Debug output and communication
@@ -41,9 +42,11 @@
 //! - **SMBIOS**: System information
 //! - **Security**: Secure Boot and authentication
 //! - **RNG**: Cryptographic random numbers
+//! - **TCG2**: TPM 2.0 measurements
 
 pub mod console;
 pub mod graphics;
+pub mod edid;
 pub mod filesystem;
 pub mod block;
 pub mod serial;
@@ -54,11 +57,12 @@
 pub mod smbios;
 pub mod security;
 pub mod rng;
+pub mod tcg2;
 
 // Re-exports
 pub use console::{Console, InputKey, KeyModifiers, ScanCode};
 pub use graphics::{GraphicsOutput, Framebuffer, Pixel, PixelFormat, Resolution};
-pub use filesystem::{FileSystem, File, Directory, FileInfo, FileMode, FileAttributes};
+pub use filesystem::{FileSystem, File, Directory, DirEntries, FileInfo, FileMode, FileAttributes, SeekFrom};
 pub use block::{BlockDevice, Partition, DiskInfo};
 pub use serial::{SerialPort, SerialConfig, Parity, StopBits};
 pub use pci::{PciDevice, PciConfig, PciClass, PciLocation};
@@ -208,9 +212,9 @@ pub fn locate_all<P: Protocol>() -> Result<alloc::vec::Vec<ProtocolHandle<P>>> {
 
     /// Locate single handle for protocol GUID
     fn locate_handle(guid: &Guid) -> Result<Handle> {
-        use crate::services::boot_services;
+        use crate::services::try_boot_services;
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services().map_err(Error::from_status)?;
 
         // LocateProtocol
         let mut interface: *mut core::ffi::c_void = core::ptr::null_mut();
@@ -233,10 +237,10 @@ fn locate_handle(guid: &Guid) -> Result<Handle> {
 
     /// Locate all handles for protocol GUID
     fn locate_handles(guid: &Guid) -> Result<alloc::vec::Vec<Handle>> {
-        use crate::services::boot_services;
+        use crate::services::try_boot_services;
         use crate::raw::types::LocateSearchType;
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services().map_err(Error::from_status)?;
 
         let mut buffer_size: usize = 0;
         let mut buffer: *mut Handle = core::ptr::null_mut();
@@ -460,10 +464,10 @@ pub struct ProtocolNotification {
 impl ProtocolNotification {
     /// Register for protocol notification
     pub fn register<P: Protocol>() -> Result<Self> {
-        use crate::services::boot_services;
+        use crate::services::try_boot_services;
         use crate::event::EventType;
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services().map_err(Error::from_status)?;
 
         let mut event = Event(core::ptr::null_mut());
         let mut registration = core::ptr::null_mut();
@@ -511,9 +515,9 @@ pub fn check(&self) -> bool {
 
     /// Wait for protocol to appear
     pub fn wait(&self) -> Result<Handle> {
-        use crate::services::boot_services;
+        use crate::services::try_boot_services;
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services().map_err(Error::from_status)?;
 
         loop {
             // Try to locate handle
@@ -546,10 +550,11 @@ pub fn wait(&self) -> Result<Handle> {
 
 impl Drop for ProtocolNotification {
     fn drop(&mut self) {
-        use crate::services::boot_services;
+        use crate::services::try_boot_services;
 
-        let bs = unsafe { boot_services() };
-        unsafe { ((*bs).close_event)(self.event) };
+        if let Ok(bs) = try_boot_services() {
+            unsafe { (bs.close_event)(self.event) };
+        }
     }
 }
 
@@ -590,4 +595,12 @@ fn test_device_path_end() {
         assert!(node.is_end());
         assert_eq!(node.type_name(), "End");
     }
+
+    #[test]
+    fn test_without_boot_services() {
+        use block::BlockDevice;
+
+        assert_eq!(ProtocolLocator::locate::<BlockDevice>().err(), Some(Error::NotReady));
+        assert_eq!(BlockDevice::open(Handle::NULL).err(), Some(Error::NotReady));
+    }
 }
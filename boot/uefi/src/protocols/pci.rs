@@ -496,9 +496,9 @@ impl Protocol for PciDevice {
     const GUID: Guid = PCI_IO_PROTOCOL_GUID;
 
     fn open(handle: Handle) -> Result<Self> {
-        use crate::services::boot_services;
+        use crate::services::try_boot_services;
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services().map_err(Error::from_status)?;
         let image = crate::services::image_handle().ok_or(Error::NotReady)?;
 
         let mut protocol: *mut core::ffi::c_void = core::ptr::null_mut();
@@ -3,6 +3,8 @@
 //! High-level random number generation abstraction.
 
 use crate::raw::types::*;
+use crate::raw::boot_services::EfiBootServices;
+use crate::raw::protocols::rng::EfiRngProtocol;
 use crate::error::{Error, Result};
 use super::Protocol;
 
@@ -17,6 +19,8 @@
 pub struct Rng {
     /// Handle
     handle: Handle,
+    /// Firmware protocol (null until located)
+    protocol: *mut EfiRngProtocol,
     /// Supported algorithms
     algorithms: Vec<RngAlgorithm>,
     /// Default algorithm
@@ -28,11 +32,38 @@ impl Rng {
     pub fn new(handle: Handle) -> Self {
         Self {
             handle,
+            protocol: core::ptr::null_mut(),
             algorithms: Vec::new(),
             default_algorithm: RngAlgorithm::Raw,
         }
     }
 
+    /// Locate the firmware RNG and query its algorithms
+    ///
+    /// # Safety
+    /// `bs` must be the firmware boot services table, before `ExitBootServices`
+    pub unsafe fn locate(bs: &EfiBootServices) -> Result<Self> {
+        let protocol = bs.locate_protocol::<EfiRngProtocol>(&EfiRngProtocol::GUID)
+            .map_err(Error::from_status)?;
+        if protocol.is_null() {
+            return Err(Error::NotFound);
+        }
+
+        let mut guids = [Guid::NULL; 8];
+        let count = (*protocol).get_supported_algorithms(&mut guids).unwrap_or(0);
+        let algorithms: Vec<RngAlgorithm> = guids[..count.min(guids.len())]
+            .iter()
+            .map(|&guid| RngAlgorithm::from_guid(guid))
+            .collect();
+
+        Ok(Self {
+            handle: Handle::null(),
+            protocol,
+            default_algorithm: algorithms.first().copied().unwrap_or(RngAlgorithm::Raw),
+            algorithms,
+        })
+    }
+
     /// Get supported algorithms
     pub fn algorithms(&self) -> &[RngAlgorithm] {
         &self.algorithms
@@ -48,24 +79,26 @@ pub fn supports(&self, algorithm: RngAlgorithm) -> bool {
         self.algorithms.contains(&algorithm)
     }
 
-    /// Get random bytes with default algorithm
+    /// Get random bytes with the firmware's default algorithm
     pub fn get_bytes(&self, buffer: &mut [u8]) -> Result<()> {
-        self.get_bytes_with(self.default_algorithm, buffer)
+        let protocol = self.protocol()?;
+        unsafe { (*protocol).get_random_bytes(buffer) }.map_err(Error::from_status)
     }
 
     /// Get random bytes with specific algorithm
-    pub fn get_bytes_with(&self, _algorithm: RngAlgorithm, buffer: &mut [u8]) -> Result<()> {
-        // TODO: Implement actual RNG call
-        // For now, use a simple LFSR-based fallback
-        let mut state = 0xDEADBEEFu64;
-
-        for byte in buffer.iter_mut() {
-            state = state.wrapping_mul(6364136223846793005)
-                .wrapping_add(1442695040888963407);
-            *byte = (state >> 33) as u8;
-        }
+    pub fn get_bytes_with(&self, algorithm: RngAlgorithm, buffer: &mut [u8]) -> Result<()> {
+        let protocol = self.protocol()?;
+        unsafe { (*protocol).get_random_bytes_with_algorithm(&algorithm.guid(), buffer) }
+            .map_err(Error::from_status)
+    }
 
-        Ok(())
+    /// Firmware protocol, if located
+    fn protocol(&self) -> Result<*mut EfiRngProtocol> {
+        if self.protocol.is_null() {
+            Err(Error::NotReady)
+        } else {
+            Ok(self.protocol)
+        }
     }
 
     /// Get single random u8
@@ -0,0 +1,61 @@
+//! TCG2 Protocol
+//!
+//! Measurements into a TPM 2.0 through the firmware.
+
+use crate::raw::boot_services::EfiBootServices;
+use crate::raw::protocols::tcg2::{self, EfiTcg2Protocol};
+use crate::error::{Error, Result};
+
+extern crate alloc;
+use alloc::vec;
+
+// =============================================================================
+// TCG2 PROTOCOL
+// =============================================================================
+
+/// Firmware TPM 2.0 access
+pub struct Tcg2 {
+    /// Firmware protocol
+    protocol: *mut EfiTcg2Protocol,
+    /// Active PCR banks (`EFI_TCG2_BOOT_HASH_ALG_*`)
+    active_banks: u32,
+}
+
+impl Tcg2 {
+    /// Locate the TCG2 protocol
+    ///
+    /// Fails with `NotFound` when there is no protocol or it reports no
+    /// TPM.
+    ///
+    /// # Safety
+    /// `bs` must be the firmware boot services table, before `ExitBootServices`
+    pub unsafe fn locate(bs: &EfiBootServices) -> Result<Self> {
+        let protocol = bs.locate_protocol::<EfiTcg2Protocol>(&EfiTcg2Protocol::GUID)
+            .map_err(Error::from_status)?;
+        if protocol.is_null() {
+            return Err(Error::NotFound);
+        }
+
+        let mut capability = [0u8; EfiTcg2Protocol::CAPABILITY_SIZE];
+        (*protocol).capability(&mut capability).map_err(Error::from_status)?;
+        // TPMPresentFlag
+        if capability[13] == 0 {
+            return Err(Error::NotFound);
+        }
+        let active_banks = (*protocol).active_pcr_banks().map_err(Error::from_status)?;
+
+        Ok(Self { protocol, active_banks })
+    }
+
+    /// Active PCR banks (`EFI_TCG2_BOOT_HASH_ALG_*` bitmap)
+    pub fn active_banks(&self) -> u32 {
+        self.active_banks
+    }
+
+    /// Hash `data` into `pcr` and add `event_data` to the firmware log
+    pub fn measure(&self, pcr: u32, event_type: u32, data: &[u8], event_data: &[u8]) -> Result<()> {
+        let mut event = vec![0u8; 4 + tcg2::EVENT_HEADER_SIZE + event_data.len()];
+        tcg2::write_event(&mut event, pcr, event_type, event_data).ok_or(Error::BufferTooSmall)?;
+        unsafe { (*self.protocol).extend(data, &event) }.map_err(Error::from_status)
+    }
+}
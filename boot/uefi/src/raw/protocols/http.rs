@@ -0,0 +1,233 @@
+//! HTTP Protocol
+//!
+//! Firmware HTTP(S) client, one child per connection. HTTPS URLs go
+//! through the firmware TLS stack, which validates the server against the
+//! `TlsCaCertificate` variable.
+
+use crate::raw::types::*;
+
+// =============================================================================
+// SERVICE BINDING
+// =============================================================================
+
+/// Service Binding Protocol
+///
+/// Creates and destroys protocol children on a network device handle.
+#[repr(C)]
+pub struct EfiServiceBindingProtocol {
+    /// Create a child handle carrying the protocol
+    pub create_child: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        child_handle: *mut Handle,
+    ) -> Status,
+
+    /// Destroy a child handle
+    pub destroy_child: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        child_handle: Handle,
+    ) -> Status,
+}
+
+impl EfiServiceBindingProtocol {
+    /// HTTP service binding GUID
+    pub const HTTP_GUID: Guid = guids::HTTP_SERVICE_BINDING_PROTOCOL;
+}
+
+// =============================================================================
+// HTTP PROTOCOL
+// =============================================================================
+
+/// HTTP Protocol
+#[repr(C)]
+pub struct EfiHttpProtocol {
+    /// Get current configuration
+    pub get_mode_data: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        config: *mut EfiHttpConfigData,
+    ) -> Status,
+
+    /// Configure (or reset, with a null config) the instance
+    pub configure: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        config: *const EfiHttpConfigData,
+    ) -> Status,
+
+    /// Queue a request
+    pub request: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        token: *mut EfiHttpToken,
+    ) -> Status,
+
+    /// Abort a queued request or response
+    pub cancel: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        token: *mut EfiHttpToken,
+    ) -> Status,
+
+    /// Queue a receive of response headers and/or body
+    pub response: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        token: *mut EfiHttpToken,
+    ) -> Status,
+
+    /// Drive the network stack
+    pub poll: unsafe extern "efiapi" fn(this: *mut Self) -> Status,
+}
+
+impl EfiHttpProtocol {
+    /// Protocol GUID
+    pub const GUID: Guid = guids::HTTP_PROTOCOL;
+}
+
+/// HTTP version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum EfiHttpVersion {
+    /// HTTP/1.0
+    Http10 = 0,
+    /// HTTP/1.1
+    Http11 = 1,
+}
+
+/// HTTP method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum EfiHttpMethod {
+    /// GET
+    Get = 0,
+    /// POST
+    Post = 1,
+    /// PATCH
+    Patch = 2,
+    /// OPTIONS
+    Options = 3,
+    /// CONNECT
+    Connect = 4,
+    /// HEAD
+    Head = 5,
+    /// PUT
+    Put = 6,
+    /// DELETE
+    Delete = 7,
+    /// TRACE
+    Trace = 8,
+}
+
+/// IPv4 local endpoint
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct EfiHttpv4AccessPoint {
+    /// Use the address configured on the interface
+    pub use_default_address: Boolean,
+    /// Local address
+    pub local_address: [u8; 4],
+    /// Local subnet
+    pub local_subnet: [u8; 4],
+    /// Local port (0 = any)
+    pub local_port: u16,
+}
+
+/// HTTP instance configuration
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct EfiHttpConfigData {
+    /// Protocol version
+    pub http_version: EfiHttpVersion,
+    /// Timeout in milliseconds (0 = none)
+    pub timeout_ms: u32,
+    /// Access point is IPv6
+    pub local_address_is_ipv6: Boolean,
+    /// Access point (`EFI_HTTPv4_ACCESS_POINT` or `EFI_HTTPv6_ACCESS_POINT`)
+    pub access_point: *mut core::ffi::c_void,
+}
+
+/// Request line
+#[repr(C)]
+pub struct EfiHttpRequestData {
+    /// Method
+    pub method: EfiHttpMethod,
+    /// Absolute URL (UCS-2, NUL terminated)
+    pub url: *const Char16,
+}
+
+/// Status line
+#[repr(C)]
+pub struct EfiHttpResponseData {
+    /// Status code
+    pub status_code: EfiHttpStatusCode,
+}
+
+/// Header field
+#[repr(C)]
+pub struct EfiHttpHeader {
+    /// Field name (ASCII, NUL terminated)
+    pub field_name: *const Char8,
+    /// Field value (ASCII, NUL terminated)
+    pub field_value: *const Char8,
+}
+
+/// Request or response message
+#[repr(C)]
+pub struct EfiHttpMessage {
+    /// `EfiHttpRequestData` for requests, `EfiHttpResponseData` for
+    /// responses; null to send or receive body only
+    pub data: *mut core::ffi::c_void,
+    /// Number of headers
+    pub header_count: usize,
+    /// Headers (allocated by the firmware on receive)
+    pub headers: *mut EfiHttpHeader,
+    /// Body length; updated with the bytes received
+    pub body_length: usize,
+    /// Body
+    pub body: *mut core::ffi::c_void,
+}
+
+/// Completion token
+#[repr(C)]
+pub struct EfiHttpToken {
+    /// Signaled on completion
+    pub event: Event,
+    /// Completion status
+    pub status: Status,
+    /// Message
+    pub message: *mut EfiHttpMessage,
+}
+
+/// HTTP status code, as enumerated by the UEFI specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct EfiHttpStatusCode(pub u32);
+
+impl EfiHttpStatusCode {
+    /// Numeric codes in enumeration order
+    const CODES: [u16; 43] = [
+        0, 100, 101, 200, 201, 202, 203, 204, 205, 206,
+        300, 301, 302, 303, 304, 305, 307,
+        400, 401, 402, 403, 404, 405, 406, 407, 408, 409, 410, 411, 412, 413, 414, 415, 416, 417,
+        500, 501, 502, 503, 504, 505,
+        308, 429,
+    ];
+
+    /// Numeric status code (0 if unsupported)
+    pub fn code(self) -> u16 {
+        Self::CODES.get(self.0 as usize).copied().unwrap_or(0)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_code() {
+        assert_eq!(EfiHttpStatusCode(3).code(), 200);
+        assert_eq!(EfiHttpStatusCode(12).code(), 302);
+        assert_eq!(EfiHttpStatusCode(21).code(), 404);
+        assert_eq!(EfiHttpStatusCode(41).code(), 308);
+        assert_eq!(EfiHttpStatusCode(99).code(), 0);
+    }
+}
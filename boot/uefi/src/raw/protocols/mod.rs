@@ -11,6 +11,10 @@
 pub mod loaded_image;
 pub mod device_path;
 pub mod rng;
+pub mod pointer;
+pub mod http;
+pub mod snp;
+pub mod tcg2;
 
 // Re-export commonly used protocols
 pub use gop::*;
@@ -21,3 +25,7 @@
 pub use loaded_image::*;
 pub use device_path::*;
 pub use rng::*;
+pub use pointer::*;
+pub use http::*;
+pub use snp::*;
+pub use tcg2::*;
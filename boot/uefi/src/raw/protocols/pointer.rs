@@ -0,0 +1,131 @@
+//! Absolute Pointer Protocol
+//!
+//! Touch screens and digitizers reporting absolute coordinates.
+
+use crate::raw::types::*;
+use core::fmt;
+
+// =============================================================================
+// ABSOLUTE POINTER PROTOCOL
+// =============================================================================
+
+/// Absolute Pointer Protocol
+#[repr(C)]
+pub struct EfiAbsolutePointerProtocol {
+    /// Reset the pointer device
+    pub reset: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        extended_verification: Boolean,
+    ) -> Status,
+
+    /// Read the current pointer state
+    pub get_state: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        state: *mut EfiAbsolutePointerState,
+    ) -> Status,
+
+    /// Event to wait for input
+    pub wait_for_input: Event,
+
+    /// Mode
+    pub mode: *mut EfiAbsolutePointerMode,
+}
+
+impl EfiAbsolutePointerProtocol {
+    /// Protocol GUID
+    pub const GUID: Guid = guids::ABSOLUTE_POINTER_PROTOCOL;
+
+    /// Reset the pointer device
+    ///
+    /// # Safety
+    /// The caller must ensure the protocol pointer is valid.
+    pub unsafe fn reset_device(&mut self, extended_verification: bool) -> Result<(), Status> {
+        let status = (self.reset)(self, extended_verification as Boolean);
+        status.to_status_result()
+    }
+
+    /// Read the pointer state; `NOT_READY` if it has not changed
+    ///
+    /// # Safety
+    /// The caller must ensure the protocol pointer is valid.
+    pub unsafe fn read_state(&mut self) -> Result<EfiAbsolutePointerState, Status> {
+        let mut state = EfiAbsolutePointerState::default();
+        let status = (self.get_state)(self, &mut state);
+        status.to_status_result_with(state)
+    }
+
+    /// Get the axis ranges
+    ///
+    /// # Safety
+    /// The caller must ensure the protocol pointer is valid.
+    pub unsafe fn get_mode(&self) -> Option<&EfiAbsolutePointerMode> {
+        self.mode.as_ref()
+    }
+}
+
+impl fmt::Debug for EfiAbsolutePointerProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EfiAbsolutePointerProtocol")
+            .field("wait_for_input", &self.wait_for_input)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+/// Absolute pointer mode (axis ranges)
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct EfiAbsolutePointerMode {
+    /// Minimum X value
+    pub absolute_min_x: u64,
+    /// Minimum Y value
+    pub absolute_min_y: u64,
+    /// Minimum Z value
+    pub absolute_min_z: u64,
+    /// Maximum X value (0 = axis not supported)
+    pub absolute_max_x: u64,
+    /// Maximum Y value (0 = axis not supported)
+    pub absolute_max_y: u64,
+    /// Maximum Z value (0 = axis not supported)
+    pub absolute_max_z: u64,
+    /// Device attributes
+    pub attributes: u32,
+}
+
+impl EfiAbsolutePointerMode {
+    /// Device has an alternate (pen barrel) button
+    pub const SUPPORTS_ALT_ACTIVE: u32 = 0x00000001;
+    /// Z axis reports pressure
+    pub const SUPPORTS_PRESSURE_AS_Z: u32 = 0x00000002;
+
+    /// Check if Z reports pressure
+    pub const fn has_pressure(&self) -> bool {
+        self.attributes & Self::SUPPORTS_PRESSURE_AS_Z != 0 && self.absolute_max_z > 0
+    }
+}
+
+/// Absolute pointer state
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct EfiAbsolutePointerState {
+    /// X position
+    pub current_x: u64,
+    /// Y position
+    pub current_y: u64,
+    /// Z position or pressure
+    pub current_z: u64,
+    /// Active buttons
+    pub active_buttons: u32,
+}
+
+impl EfiAbsolutePointerState {
+    /// Touch surface is being touched
+    pub const TOUCH_ACTIVE: u32 = 0x00000001;
+    /// Alternate button is pressed
+    pub const ALT_ACTIVE: u32 = 0x00000002;
+
+    /// Check if touching
+    pub const fn is_touching(&self) -> bool {
+        self.active_buttons & Self::TOUCH_ACTIVE != 0
+    }
+}
@@ -0,0 +1,209 @@
+//! Simple Network Protocol
+//!
+//! Raw frame transmit and receive on a network interface, below any
+//! firmware IP stack.
+
+use crate::raw::types::*;
+
+// =============================================================================
+// SIMPLE NETWORK PROTOCOL
+// =============================================================================
+
+/// Simple Network Protocol
+#[repr(C)]
+pub struct EfiSimpleNetworkProtocol {
+    /// Protocol revision
+    pub revision: u64,
+
+    /// Change the state from stopped to started
+    pub start: unsafe extern "efiapi" fn(this: *mut Self) -> Status,
+
+    /// Change the state from started to stopped
+    pub stop: unsafe extern "efiapi" fn(this: *mut Self) -> Status,
+
+    /// Allocate transmit and receive buffers and initialize the interface
+    pub initialize: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        extra_rx_buffer_size: usize,
+        extra_tx_buffer_size: usize,
+    ) -> Status,
+
+    /// Reset the interface
+    pub reset: unsafe extern "efiapi" fn(this: *mut Self, extended_verification: Boolean) -> Status,
+
+    /// Release the buffers and leave the interface started
+    pub shutdown: unsafe extern "efiapi" fn(this: *mut Self) -> Status,
+
+    /// Enable and disable receive filters
+    pub receive_filters: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        enable: u32,
+        disable: u32,
+        reset_mcast_filter: Boolean,
+        mcast_filter_count: usize,
+        mcast_filter: *const EfiMacAddress,
+    ) -> Status,
+
+    /// Change or reset the station address
+    pub station_address: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        reset: Boolean,
+        new: *const EfiMacAddress,
+    ) -> Status,
+
+    /// Read or reset the statistics
+    pub statistics: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        reset: Boolean,
+        statistics_size: *mut usize,
+        statistics_table: *mut core::ffi::c_void,
+    ) -> Status,
+
+    /// Map a multicast IP address to a multicast MAC address
+    pub mcast_ip_to_mac: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        ipv6: Boolean,
+        ip: *const core::ffi::c_void,
+        mac: *mut EfiMacAddress,
+    ) -> Status,
+
+    /// Read or write the NVRAM
+    pub nv_data: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        read_write: Boolean,
+        offset: usize,
+        buffer_size: usize,
+        buffer: *mut core::ffi::c_void,
+    ) -> Status,
+
+    /// Read the interrupt status and recycled transmit buffers
+    pub get_status: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        interrupt_status: *mut u32,
+        tx_buf: *mut *mut core::ffi::c_void,
+    ) -> Status,
+
+    /// Queue a frame for transmission
+    ///
+    /// With a non-zero `header_size` the media header is filled in from
+    /// the addresses and protocol; otherwise `buffer` is a complete frame.
+    pub transmit: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        header_size: usize,
+        buffer_size: usize,
+        buffer: *const core::ffi::c_void,
+        src_addr: *const EfiMacAddress,
+        dest_addr: *const EfiMacAddress,
+        protocol: *const u16,
+    ) -> Status,
+
+    /// Receive a frame (`NOT_READY` when none is pending)
+    pub receive: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        header_size: *mut usize,
+        buffer_size: *mut usize,
+        buffer: *mut core::ffi::c_void,
+        src_addr: *mut EfiMacAddress,
+        dest_addr: *mut EfiMacAddress,
+        protocol: *mut u16,
+    ) -> Status,
+
+    /// Signaled when a frame is available
+    pub wait_for_packet: Event,
+
+    /// Current mode
+    pub mode: *const EfiSimpleNetworkMode,
+}
+
+impl EfiSimpleNetworkProtocol {
+    /// Protocol GUID
+    pub const GUID: Guid = guids::SIMPLE_NETWORK_PROTOCOL;
+}
+
+/// MAC address (padded to 32 bytes)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EfiMacAddress {
+    /// Address bytes
+    pub addr: [u8; 32],
+}
+
+impl EfiMacAddress {
+    /// Pad an Ethernet address
+    pub const fn from_ethernet(mac: [u8; 6]) -> Self {
+        let mut addr = [0u8; 32];
+        let mut i = 0;
+        while i < 6 {
+            addr[i] = mac[i];
+            i += 1;
+        }
+        Self { addr }
+    }
+
+    /// Ethernet address
+    pub fn ethernet(&self) -> [u8; 6] {
+        let mut mac = [0u8; 6];
+        mac.copy_from_slice(&self.addr[..6]);
+        mac
+    }
+}
+
+/// Simple Network Protocol mode
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EfiSimpleNetworkMode {
+    /// Interface state (`SNP_STATE_*`)
+    pub state: u32,
+    /// Hardware address size
+    pub hw_address_size: u32,
+    /// Media header size
+    pub media_header_size: u32,
+    /// Maximum packet size, excluding the media header
+    pub max_packet_size: u32,
+    /// NVRAM size
+    pub nv_ram_size: u32,
+    /// NVRAM access size
+    pub nv_ram_access_size: u32,
+    /// Supported receive filters
+    pub receive_filter_mask: u32,
+    /// Enabled receive filters
+    pub receive_filter_setting: u32,
+    /// Maximum multicast filter entries
+    pub max_mcast_filter_count: u32,
+    /// Current multicast filter entries
+    pub mcast_filter_count: u32,
+    /// Multicast filter
+    pub mcast_filter: [EfiMacAddress; 16],
+    /// Current station address
+    pub current_address: EfiMacAddress,
+    /// Broadcast address
+    pub broadcast_address: EfiMacAddress,
+    /// Permanent station address
+    pub permanent_address: EfiMacAddress,
+    /// Interface type (IANA hardware type)
+    pub if_type: u8,
+    /// Station address can be changed
+    pub mac_address_changeable: Boolean,
+    /// Several frames can be queued for transmission
+    pub multiple_tx_supported: Boolean,
+    /// Media presence is reported
+    pub media_present_supported: Boolean,
+    /// Media is present
+    pub media_present: Boolean,
+}
+
+/// Stopped
+pub const SNP_STATE_STOPPED: u32 = 0;
+/// Started
+pub const SNP_STATE_STARTED: u32 = 1;
+/// Initialized
+pub const SNP_STATE_INITIALIZED: u32 = 2;
+
+/// Receive frames addressed to the station
+pub const SNP_RECEIVE_UNICAST: u32 = 0x01;
+/// Receive multicast frames
+pub const SNP_RECEIVE_MULTICAST: u32 = 0x02;
+/// Receive broadcast frames
+pub const SNP_RECEIVE_BROADCAST: u32 = 0x04;
+/// Receive all frames
+pub const SNP_RECEIVE_PROMISCUOUS: u32 = 0x08;
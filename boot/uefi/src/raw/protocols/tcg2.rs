@@ -0,0 +1,144 @@
+//! TCG2 Protocol
+//!
+//! Firmware access to a TPM 2.0: capability queries, the firmware event
+//! log and measurements that are extended and logged in one call.
+
+use crate::raw::types::*;
+use core::fmt;
+
+// =============================================================================
+// TCG2 PROTOCOL
+// =============================================================================
+
+/// EFI_TCG2_PROTOCOL
+#[repr(C)]
+pub struct EfiTcg2Protocol {
+    /// Get capability (`EFI_TCG2_BOOT_SERVICE_CAPABILITY`)
+    pub get_capability: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        capability: *mut u8,
+    ) -> Status,
+
+    /// Get the firmware event log
+    pub get_event_log: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        format: u32,
+        location: *mut PhysicalAddress,
+        last_entry: *mut PhysicalAddress,
+        truncated: *mut Boolean,
+    ) -> Status,
+
+    /// Hash data, extend a PCR and log the event
+    pub hash_log_extend_event: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        flags: u64,
+        data_to_hash: PhysicalAddress,
+        data_to_hash_len: u64,
+        event: *const u8,
+    ) -> Status,
+
+    /// Submit a raw TPM command
+    pub submit_command: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        input_size: u32,
+        input: *const u8,
+        output_size: u32,
+        output: *mut u8,
+    ) -> Status,
+
+    /// Get active PCR banks
+    pub get_active_pcr_banks: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        active_pcr_banks: *mut u32,
+    ) -> Status,
+
+    /// Set active PCR banks (takes effect after reset)
+    pub set_active_pcr_banks: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        active_pcr_banks: u32,
+    ) -> Status,
+
+    /// Result of the last bank change
+    pub get_result_of_set_active_pcr_banks: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        operation_present: *mut u32,
+        response: *mut u32,
+    ) -> Status,
+}
+
+impl EfiTcg2Protocol {
+    /// Protocol GUID
+    pub const GUID: Guid = guids::TCG2_PROTOCOL;
+
+    /// Size of `EFI_TCG2_BOOT_SERVICE_CAPABILITY` (1.1)
+    pub const CAPABILITY_SIZE: usize = 36;
+
+    /// Query the capability structure into `buffer`
+    ///
+    /// # Safety
+    /// The caller must ensure the protocol pointer is valid.
+    pub unsafe fn capability(&self, buffer: &mut [u8; Self::CAPABILITY_SIZE]) -> Result<(), Status> {
+        buffer.fill(0);
+        buffer[0] = Self::CAPABILITY_SIZE as u8;
+        (self.get_capability)(self as *const _ as *mut _, buffer.as_mut_ptr()).to_status_result()
+    }
+
+    /// Active PCR banks (`EFI_TCG2_BOOT_HASH_ALG_*` bitmap)
+    ///
+    /// # Safety
+    /// The caller must ensure the protocol pointer is valid.
+    pub unsafe fn active_pcr_banks(&self) -> Result<u32, Status> {
+        let mut banks = 0;
+        (self.get_active_pcr_banks)(self as *const _ as *mut _, &mut banks).to_status_result_with(banks)
+    }
+
+    /// Measure `data` with the event in `event` (see [`write_event`])
+    ///
+    /// # Safety
+    /// The caller must ensure the protocol pointer is valid and `event`
+    /// holds a complete `EFI_TCG2_EVENT`.
+    pub unsafe fn extend(&self, data: &[u8], event: &[u8]) -> Result<(), Status> {
+        (self.hash_log_extend_event)(
+            self as *const _ as *mut _,
+            0,
+            PhysicalAddress(data.as_ptr() as u64),
+            data.len() as u64,
+            event.as_ptr(),
+        )
+        .to_status_result()
+    }
+}
+
+impl fmt::Debug for EfiTcg2Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EfiTcg2Protocol").finish()
+    }
+}
+
+// =============================================================================
+// TCG2 EVENT
+// =============================================================================
+
+/// `EFI_TCG2_EVENT_LOG_FORMAT_TCG_2` (crypto-agile)
+pub const EVENT_LOG_FORMAT_TCG_2: u32 = 0x0000_0002;
+
+/// Size of `EFI_TCG2_EVENT_HEADER`
+pub const EVENT_HEADER_SIZE: usize = 14;
+
+/// `EFI_TCG2_EVENT_HEADER` version
+pub const EVENT_HEADER_VERSION: u16 = 1;
+
+/// Write an `EFI_TCG2_EVENT` into `buffer`
+///
+/// Returns the event size, or `None` if `buffer` is too small.
+pub fn write_event(buffer: &mut [u8], pcr: u32, event_type: u32, data: &[u8]) -> Option<usize> {
+    let size = 4 + EVENT_HEADER_SIZE + data.len();
+    let event = buffer.get_mut(..size)?;
+    event[0..4].copy_from_slice(&(size as u32).to_le_bytes());
+    event[4..8].copy_from_slice(&(EVENT_HEADER_SIZE as u32).to_le_bytes());
+    event[8..10].copy_from_slice(&EVENT_HEADER_VERSION.to_le_bytes());
+    event[10..14].copy_from_slice(&pcr.to_le_bytes());
+    event[14..18].copy_from_slice(&event_type.to_le_bytes());
+    event[18..].copy_from_slice(data);
+    Some(size)
+}
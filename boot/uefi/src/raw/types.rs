@@ -758,6 +758,12 @@ pub mod guids {
         [0x8E, 0x39, 0x00, 0xA0, 0xC9, 0x69, 0x72, 0x3B]
     );
 
+    /// EFI_ABSOLUTE_POINTER_PROTOCOL_GUID
+    pub const ABSOLUTE_POINTER_PROTOCOL: Guid = Guid::new(
+        0x8D59D32B, 0xC655, 0x4AE9,
+        [0x9B, 0x15, 0xF2, 0x59, 0x04, 0x99, 0x2A, 0x43]
+    );
+
     /// EFI_GRAPHICS_OUTPUT_PROTOCOL_GUID
     pub const GRAPHICS_OUTPUT_PROTOCOL: Guid = Guid::new(
         0x9042A9DE, 0x23DC, 0x4A38,
@@ -932,6 +938,24 @@ pub mod guids {
         [0xB0, 0x10, 0x5A, 0xAD, 0xC7, 0xEC, 0x2B, 0x62]
     );
 
+    /// EFI_HTTP_SERVICE_BINDING_PROTOCOL_GUID
+    pub const HTTP_SERVICE_BINDING_PROTOCOL: Guid = Guid::new(
+        0xBDC8E6AF, 0xD9BC, 0x4379,
+        [0xA7, 0x2A, 0xE0, 0xC4, 0xE7, 0x5D, 0xAE, 0x1C]
+    );
+
+    /// EFI_HTTP_PROTOCOL_GUID
+    pub const HTTP_PROTOCOL: Guid = Guid::new(
+        0x7A59B29B, 0x910B, 0x4171,
+        [0x82, 0x42, 0xA8, 0x5A, 0x0D, 0xF2, 0x5B, 0x5B]
+    );
+
+    /// EFI_TLS_CA_CERTIFICATE_GUID (vendor of the `TlsCaCertificate` variable)
+    pub const TLS_CA_CERTIFICATE: Guid = Guid::new(
+        0xFD2340D0, 0x3DAB, 0x4349,
+        [0xA6, 0xC7, 0x3B, 0x4F, 0x12, 0xB4, 0x8E, 0xAE]
+    );
+
     /// EFI_ACPI_TABLE_GUID (ACPI 1.0)
     pub const ACPI_TABLE: Guid = Guid::new(
         0xEB9D2D30, 0x2D88, 0x11D3,
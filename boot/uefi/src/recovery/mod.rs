@@ -32,6 +32,9 @@
 
 use core::fmt;
 
+use super::locale::Language;
+use super::resources::{self, MessageArg, MessageId};
+
 // =============================================================================
 // ERROR CATEGORIES
 // =============================================================================
@@ -221,6 +224,7 @@ pub mod codes {
     pub const PASSWORD_REQUIRED: ErrorCode = ErrorCode::new(ErrorCategory::Security, 0x0020);
     pub const PASSWORD_INCORRECT: ErrorCode = ErrorCode::new(ErrorCategory::Security, 0x0021);
     pub const LOCKOUT_ACTIVE: ErrorCode = ErrorCode::new(ErrorCategory::Security, 0x0022);
+    pub const CRYPTO_SELF_TEST_FAILED: ErrorCode = ErrorCode::new(ErrorCategory::Security, 0x0030);
 
     // Graphics errors (0x0500-0x05FF)
     pub const NO_DISPLAY: ErrorCode = ErrorCode::new(ErrorCategory::Graphics, 0x0001);
@@ -386,6 +390,31 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
+impl RecoveryStrategy {
+    /// Message ID of the strategy's label
+    pub const fn message_id(&self) -> MessageId {
+        match self {
+            RecoveryStrategy::None => MessageId::StrategyNone,
+            RecoveryStrategy::Retry => MessageId::StrategyRetry,
+            RecoveryStrategy::Fallback => MessageId::StrategyFallback,
+            RecoveryStrategy::SafeMode => MessageId::StrategySafeMode,
+            RecoveryStrategy::RecoveryMode => MessageId::StrategyRecoveryMode,
+            RecoveryStrategy::LastKnownGood => MessageId::StrategyLastKnownGood,
+            RecoveryStrategy::ResetDefaults => MessageId::StrategyResetDefaults,
+            RecoveryStrategy::Manual => MessageId::StrategyManual,
+            RecoveryStrategy::Reboot => MessageId::StrategyReboot,
+            RecoveryStrategy::Shutdown => MessageId::StrategyShutdown,
+            RecoveryStrategy::UefiShell => MessageId::StrategyUefiShell,
+            RecoveryStrategy::FirmwareSetup => MessageId::StrategyFirmwareSetup,
+        }
+    }
+
+    /// Label in the given language
+    pub fn label(&self, language: Language) -> &'static str {
+        resources::message(language, self.message_id())
+    }
+}
+
 /// Recovery action
 #[derive(Debug, Clone, Copy)]
 pub struct RecoveryAction {
@@ -435,7 +464,9 @@ pub fn recommended_recovery(code: ErrorCode) -> RecoveryAction {
             auto_execute: true,
             ..Default::default()
         },
-        codes::SECURE_BOOT_VIOLATION | codes::SIGNATURE_INVALID => RecoveryAction {
+        codes::SECURE_BOOT_VIOLATION
+        | codes::SIGNATURE_INVALID
+        | codes::CRYPTO_SELF_TEST_FAILED => RecoveryAction {
             strategy: RecoveryStrategy::Manual,
             timeout_secs: 0,
             ..Default::default()
@@ -792,6 +823,58 @@ fn default() -> Self {
     }
 }
 
+impl ErrorScreen {
+    /// Get title as string
+    pub fn title_str(&self) -> &str {
+        core::str::from_utf8(&self.title[..self.title_len]).unwrap_or("")
+    }
+
+    /// Set title
+    pub fn set_title(&mut self, title: &str) {
+        let bytes = title.as_bytes();
+        let len = bytes.len().min(self.title.len());
+        self.title[..len].copy_from_slice(&bytes[..len]);
+        self.title_len = len;
+    }
+
+    /// Render the screen text in the given language
+    pub fn render(&self, language: Language, out: &mut dyn fmt::Write) -> fmt::Result {
+        if self.title_len > 0 {
+            out.write_str(self.title_str())?;
+        } else {
+            out.write_str(resources::message(language, MessageId::ErrorTitle))?;
+        }
+        out.write_str("\r\n\r\n  ")?;
+        resources::format_message(out, language, MessageId::ErrorCode, &[MessageArg::Text(&self.error_code)])?;
+        out.write_str("\r\n")?;
+
+        let options = &self.options[..self.option_count.min(self.options.len())];
+        if self.show_options && !options.is_empty() {
+            out.write_str("\r\n  ")?;
+            out.write_str(resources::message(language, MessageId::RecoveryOptions))?;
+            out.write_str("\r\n")?;
+            for (i, option) in options.iter().enumerate() {
+                let marker = if i == self.default_option { '>' } else { ' ' };
+                write!(out, "  {} {}. {}\r\n", marker, i + 1, option.label(language))?;
+            }
+
+            if self.countdown_secs > 0 {
+                let default = options.get(self.default_option).copied().unwrap_or_default();
+                out.write_str("\r\n  ")?;
+                resources::format_message(
+                    out,
+                    language,
+                    MessageId::RecoveryCountdown,
+                    &[MessageArg::Text(&default.label(language)), MessageArg::Num(self.countdown_secs as u64)],
+                )?;
+                out.write_str("\r\n")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -829,6 +912,44 @@ fn test_recovery_action() {
         assert_eq!(action.max_retries, 3);
     }
 
+    struct Buf {
+        data: [u8; 512],
+        len: usize,
+    }
+
+    impl fmt::Write for Buf {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let bytes = s.as_bytes();
+            self.data[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_error_screen_localized() {
+        let mut options = [RecoveryStrategy::None; 4];
+        options[0] = RecoveryStrategy::Retry;
+        options[1] = RecoveryStrategy::Reboot;
+        let screen = ErrorScreen {
+            error_code: codes::KERNEL_NOT_FOUND,
+            options,
+            option_count: 2,
+            default_option: 1,
+            countdown_secs: 1,
+            ..Default::default()
+        };
+
+        let mut buf = Buf { data: [0; 512], len: 0 };
+        screen.render(Language::Fr, &mut buf).unwrap();
+        let text = core::str::from_utf8(&buf.data[..buf.len]).unwrap();
+        assert!(text.starts_with("Échec du démarrage"));
+        assert!(text.contains("  1. Réessayer"));
+        assert!(text.contains("> 2. Redémarrer"));
+        assert!(text.contains("Redémarrer dans 1 seconde"));
+        assert_eq!(RecoveryStrategy::UefiShell.label(Language::En), "UEFI Shell");
+    }
+
     #[test]
     fn test_error_log() {
         let mut log = ErrorLog::new();
@@ -32,6 +32,8 @@
 
 use core::fmt;
 
+use super::locale::{self, Language, PluralCategory};
+
 // =============================================================================
 // RESOURCE TYPES
 // =============================================================================
@@ -145,6 +147,8 @@ pub mod resource_ids {
     pub const FONT_LARGE: u32 = 0x1011;
     pub const FONT_HUGE: u32 = 0x1012;
     pub const FONT_CONSOLE: u32 = 0x1020;
+    pub const FONT_UNICODE: u32 = 0x1021;
+    pub const FONT_EMOJI: u32 = 0x1022;
 
     // Icons (0x2000 - 0x2FFF)
     pub const ICON_HELIX: u32 = 0x2000;
@@ -329,6 +333,63 @@ fn default() -> Self {
     }
 }
 
+/// Console fonts in the order glyphs are looked up: the console font,
+/// then wide-coverage (CJK, symbols) and emoji fonts for what it lacks
+pub const FONT_FALLBACK_CHAIN: &[ResourceId] = &[
+    resource_ids::FONT_CONSOLE,
+    resource_ids::FONT_UNICODE,
+    resource_ids::FONT_EMOJI,
+];
+
+/// Look-alike for a character no font has: ASCII punctuation for
+/// typographic marks, line drawing and fullwidth forms, the base letter
+/// for accented ones
+pub fn substitute_char(c: char) -> Option<char> {
+    let sub = match c {
+        '\u{A0}' | '\u{2000}'..='\u{200A}' | '\u{3000}' => ' ',
+        '‘' | '’' | '‚' | '‛' | '′' => '\'',
+        '“' | '”' | '„' | '‟' | '″' | '«' | '»' => '"',
+        '‐'..='―' | '−' => '-',
+        '…' | '·' => '.',
+        '•' | '●' | '◆' | '★' | '■' => '*',
+        '○' | '□' => 'o',
+        '←' => '<',
+        '→' => '>',
+        '↑' => '^',
+        '↓' => 'v',
+        '✓' | '✔' => 'v',
+        '✗' | '✘' | '×' => 'x',
+        '─' | '━' | '═' | '╌' => '-',
+        '│' | '┃' | '║' | '╎' => '|',
+        '┌'..='╋' | '╒'..='╰' => '+',
+        '░' | '▒' | '▓' | '█' | '▀' | '▄' | '▌' | '▐' => '#',
+        // Fullwidth ASCII
+        '\u{FF01}'..='\u{FF5E}' => return char::from_u32(c as u32 - 0xFEE0),
+        _ => return locale::decompose(c).map(|(base, _)| base),
+    };
+    Some(sub)
+}
+
+/// Pick the font (index into the loaded fallback chain) and character to
+/// draw `c` with
+///
+/// Tries `c` in each of `fonts` fonts, then its look-alike, then U+FFFD,
+/// then `?`; `None` if no font has any of them.
+pub fn resolve_glyph(
+    c: char,
+    fonts: usize,
+    has_glyph: impl Fn(usize, char) -> bool,
+) -> Option<(usize, char)> {
+    [Some(c), substitute_char(c), Some('\u{FFFD}'), Some('?')]
+        .into_iter()
+        .flatten()
+        .find_map(|candidate| {
+            (0..fonts)
+                .find(|&font| has_glyph(font, candidate))
+                .map(|font| (font, candidate))
+        })
+}
+
 // =============================================================================
 // ICON RESOURCES
 // =============================================================================
@@ -743,6 +804,426 @@ fn default() -> Self {
     }
 }
 
+// =============================================================================
+// MESSAGE CATALOG
+// =============================================================================
+
+/// UI message identifiers
+///
+/// Patterns may contain `{N}` (argument N) and
+/// `{N,plural,one{# item}other{# items}}`, where the form is picked by the
+/// language's plural rules and `#` is replaced by the number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum MessageId {
+    /// Boot menu banner
+    MenuTitle,
+    /// Help: move selection
+    KeySelect,
+    /// Help: boot entry
+    KeyBoot,
+    /// Help: edit command line
+    KeyEdit,
+    /// Help: UEFI shell
+    KeyShell,
+    /// Help: reboot
+    KeyReboot,
+    /// Help: shutdown
+    KeyShutdown,
+    /// Help: continue default boot
+    KeyContinue,
+    /// Help: switch language
+    KeyLanguage,
+    /// Help: toggle high contrast
+    KeyHighContrast,
+    /// Help: toggle navigation beeps
+    KeyBeeps,
+    /// Help: cycle boot profile
+    KeyProfile,
+    /// Help: show boot time history
+    KeyBootTimes,
+    /// Boot time history with no records
+    BootTimesEmpty,
+    /// Prompt to leave an information screen
+    PressAnyKey,
+    /// Chainloaded application returned (`{0}` = title, `{1}` = status)
+    ChainloadReturned,
+    /// Chainloaded application could not start (`{0}` = title, `{1}` = reason)
+    ChainloadFailed,
+    /// Auto-boot countdown (`{0}` = seconds)
+    BootCountdown,
+    /// Command line editor heading
+    EditorTitle,
+    /// Rejected command line (`{0}` = reason)
+    InvalidCmdline,
+    /// Graphical menu title
+    GraphicalTitle,
+    /// Graphical menu footer
+    GraphicalHelp,
+    /// Graphical auto-boot countdown (`{0}` = seconds)
+    AutoBoot,
+    /// Default error screen title
+    ErrorTitle,
+    /// Error code line (`{0}` = code)
+    ErrorCode,
+    /// Recovery options heading
+    RecoveryOptions,
+    /// Recovery countdown (`{0}` = option, `{1}` = seconds)
+    RecoveryCountdown,
+    /// Recovery strategy: none
+    StrategyNone,
+    /// Recovery strategy: retry
+    StrategyRetry,
+    /// Recovery strategy: fallback
+    StrategyFallback,
+    /// Recovery strategy: safe mode
+    StrategySafeMode,
+    /// Recovery strategy: recovery mode
+    StrategyRecoveryMode,
+    /// Recovery strategy: last known good
+    StrategyLastKnownGood,
+    /// Recovery strategy: reset defaults
+    StrategyResetDefaults,
+    /// Recovery strategy: manual
+    StrategyManual,
+    /// Recovery strategy: reboot
+    StrategyReboot,
+    /// Recovery strategy: shutdown
+    StrategyShutdown,
+    /// Recovery strategy: UEFI shell
+    StrategyUefiShell,
+    /// Recovery strategy: firmware setup
+    StrategyFirmwareSetup,
+}
+
+impl MessageId {
+    /// Number of message IDs
+    pub const COUNT: usize = MessageId::StrategyFirmwareSetup as usize + 1;
+}
+
+/// Argument substituted into a message pattern
+#[derive(Clone, Copy)]
+pub enum MessageArg<'a> {
+    /// Number; selects plural forms
+    Num(u64),
+    /// Any displayable value
+    Text(&'a dyn fmt::Display),
+}
+
+impl MessageArg<'_> {
+    fn write(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match self {
+            MessageArg::Num(n) => write!(out, "{}", n),
+            MessageArg::Text(text) => write!(out, "{}", text),
+        }
+    }
+}
+
+/// Embedded string table for one language
+#[derive(Debug, Clone, Copy)]
+pub struct StringTable {
+    /// Resource ID (`STRINGS_*`)
+    pub id: ResourceId,
+    /// Language
+    pub language: Language,
+    /// Message patterns; missing IDs fall back to English
+    pub messages: &'static [(MessageId, &'static str)],
+}
+
+impl StringTable {
+    /// Pattern for a message, if translated
+    pub fn get(&self, id: MessageId) -> Option<&'static str> {
+        self.messages.iter().find(|(mid, _)| *mid == id).map(|(_, text)| *text)
+    }
+}
+
+/// String tables embedded in the bootloader; English first
+pub const STRING_TABLES: &[StringTable] = &[
+    StringTable { id: resource_ids::STRINGS_EN, language: Language::En, messages: STRINGS_EN },
+    StringTable { id: resource_ids::STRINGS_FR, language: Language::Fr, messages: STRINGS_FR },
+    StringTable { id: resource_ids::STRINGS_DE, language: Language::De, messages: STRINGS_DE },
+    StringTable { id: resource_ids::STRINGS_ES, language: Language::Es, messages: STRINGS_ES },
+];
+
+const STRINGS_EN: &[(MessageId, &str)] = &[
+    (MessageId::MenuTitle, "Helix UEFI Boot Manager v1.0"),
+    (MessageId::KeySelect, "Select"),
+    (MessageId::KeyBoot, "Boot"),
+    (MessageId::KeyEdit, "Edit"),
+    (MessageId::KeyShell, "Shell"),
+    (MessageId::KeyReboot, "Reboot"),
+    (MessageId::KeyShutdown, "Shutdown"),
+    (MessageId::KeyContinue, "Continue"),
+    (MessageId::KeyLanguage, "Language"),
+    (MessageId::KeyHighContrast, "High contrast"),
+    (MessageId::KeyBeeps, "Beeps"),
+    (MessageId::KeyProfile, "Profile"),
+    (MessageId::KeyBootTimes, "Boot times"),
+    (MessageId::BootTimesEmpty, "No boot times recorded yet"),
+    (MessageId::PressAnyKey, "Press any key to return"),
+    (MessageId::ChainloadReturned, "{0} returned ({1})"),
+    (MessageId::ChainloadFailed, "Cannot start {0}: {1}"),
+    (MessageId::BootCountdown, "Booting in {0,plural,one{# second}other{# seconds}}..."),
+    (MessageId::EditorTitle, "Command Line Editor:"),
+    (MessageId::InvalidCmdline, "Invalid command line: {0}"),
+    (MessageId::GraphicalTitle, "Helix Boot Manager"),
+    (MessageId::GraphicalHelp, "Use arrows to select, Enter to boot"),
+    (MessageId::AutoBoot, "Auto-boot in {0}s"),
+    (MessageId::ErrorTitle, "Boot failed"),
+    (MessageId::ErrorCode, "Error code: {0}"),
+    (MessageId::RecoveryOptions, "Recovery options:"),
+    (MessageId::RecoveryCountdown, "{0} in {1,plural,one{# second}other{# seconds}}"),
+    (MessageId::StrategyNone, "None"),
+    (MessageId::StrategyRetry, "Retry"),
+    (MessageId::StrategyFallback, "Fallback"),
+    (MessageId::StrategySafeMode, "Safe Mode"),
+    (MessageId::StrategyRecoveryMode, "Recovery Mode"),
+    (MessageId::StrategyLastKnownGood, "Last Known Good"),
+    (MessageId::StrategyResetDefaults, "Reset Defaults"),
+    (MessageId::StrategyManual, "Manual"),
+    (MessageId::StrategyReboot, "Reboot"),
+    (MessageId::StrategyShutdown, "Shutdown"),
+    (MessageId::StrategyUefiShell, "UEFI Shell"),
+    (MessageId::StrategyFirmwareSetup, "Firmware Setup"),
+];
+
+const STRINGS_FR: &[(MessageId, &str)] = &[
+    (MessageId::MenuTitle, "Gestionnaire de démarrage Helix UEFI v1.0"),
+    (MessageId::KeySelect, "Choisir"),
+    (MessageId::KeyBoot, "Démarrer"),
+    (MessageId::KeyEdit, "Modifier"),
+    (MessageId::KeyShell, "Shell"),
+    (MessageId::KeyReboot, "Redémarrer"),
+    (MessageId::KeyShutdown, "Arrêter"),
+    (MessageId::KeyContinue, "Continuer"),
+    (MessageId::KeyLanguage, "Langue"),
+    (MessageId::KeyHighContrast, "Contraste élevé"),
+    (MessageId::KeyBeeps, "Bips"),
+    (MessageId::KeyProfile, "Profil"),
+    (MessageId::KeyBootTimes, "Temps de démarrage"),
+    (MessageId::BootTimesEmpty, "Aucun temps de démarrage enregistré"),
+    (MessageId::PressAnyKey, "Appuyez sur une touche pour revenir"),
+    (MessageId::ChainloadReturned, "{0} s'est terminé ({1})"),
+    (MessageId::ChainloadFailed, "Impossible de démarrer {0} : {1}"),
+    (MessageId::BootCountdown, "Démarrage dans {0,plural,one{# seconde}other{# secondes}}..."),
+    (MessageId::EditorTitle, "Éditeur de ligne de commande :"),
+    (MessageId::InvalidCmdline, "Ligne de commande invalide : {0}"),
+    (MessageId::GraphicalTitle, "Gestionnaire de démarrage Helix"),
+    (MessageId::GraphicalHelp, "Flèches pour choisir, Entrée pour démarrer"),
+    (MessageId::AutoBoot, "Démarrage auto dans {0} s"),
+    (MessageId::ErrorTitle, "Échec du démarrage"),
+    (MessageId::ErrorCode, "Code d'erreur : {0}"),
+    (MessageId::RecoveryOptions, "Options de récupération :"),
+    (MessageId::RecoveryCountdown, "{0} dans {1,plural,one{# seconde}other{# secondes}}"),
+    (MessageId::StrategyNone, "Aucune"),
+    (MessageId::StrategyRetry, "Réessayer"),
+    (MessageId::StrategyFallback, "Solution de repli"),
+    (MessageId::StrategySafeMode, "Mode sans échec"),
+    (MessageId::StrategyRecoveryMode, "Mode de récupération"),
+    (MessageId::StrategyLastKnownGood, "Dernière configuration valide"),
+    (MessageId::StrategyResetDefaults, "Rétablir les valeurs par défaut"),
+    (MessageId::StrategyManual, "Intervention manuelle"),
+    (MessageId::StrategyReboot, "Redémarrer"),
+    (MessageId::StrategyShutdown, "Arrêter"),
+    (MessageId::StrategyUefiShell, "Shell UEFI"),
+    (MessageId::StrategyFirmwareSetup, "Configuration du micrologiciel"),
+];
+
+const STRINGS_DE: &[(MessageId, &str)] = &[
+    (MessageId::MenuTitle, "Helix UEFI-Bootmanager v1.0"),
+    (MessageId::KeySelect, "Auswählen"),
+    (MessageId::KeyBoot, "Starten"),
+    (MessageId::KeyEdit, "Bearbeiten"),
+    (MessageId::KeyShell, "Shell"),
+    (MessageId::KeyReboot, "Neustart"),
+    (MessageId::KeyShutdown, "Ausschalten"),
+    (MessageId::KeyContinue, "Weiter"),
+    (MessageId::KeyLanguage, "Sprache"),
+    (MessageId::KeyHighContrast, "Hoher Kontrast"),
+    (MessageId::KeyBeeps, "Töne"),
+    (MessageId::KeyProfile, "Profil"),
+    (MessageId::KeyBootTimes, "Startzeiten"),
+    (MessageId::BootTimesEmpty, "Noch keine Startzeiten aufgezeichnet"),
+    (MessageId::PressAnyKey, "Beliebige Taste zum Zurückkehren"),
+    (MessageId::ChainloadReturned, "{0} wurde beendet ({1})"),
+    (MessageId::ChainloadFailed, "{0} kann nicht gestartet werden: {1}"),
+    (MessageId::BootCountdown, "Start in {0,plural,one{# Sekunde}other{# Sekunden}}..."),
+    (MessageId::EditorTitle, "Befehlszeilen-Editor:"),
+    (MessageId::InvalidCmdline, "Ungültige Befehlszeile: {0}"),
+    (MessageId::GraphicalTitle, "Helix-Bootmanager"),
+    (MessageId::GraphicalHelp, "Pfeiltasten zum Auswählen, Eingabe zum Starten"),
+    (MessageId::AutoBoot, "Autostart in {0} s"),
+    (MessageId::ErrorTitle, "Start fehlgeschlagen"),
+    (MessageId::ErrorCode, "Fehlercode: {0}"),
+    (MessageId::RecoveryOptions, "Wiederherstellungsoptionen:"),
+    (MessageId::RecoveryCountdown, "{0} in {1,plural,one{# Sekunde}other{# Sekunden}}"),
+    (MessageId::StrategyNone, "Keine"),
+    (MessageId::StrategyRetry, "Wiederholen"),
+    (MessageId::StrategyFallback, "Ausweichoption"),
+    (MessageId::StrategySafeMode, "Abgesicherter Modus"),
+    (MessageId::StrategyRecoveryMode, "Wiederherstellungsmodus"),
+    (MessageId::StrategyLastKnownGood, "Letzte funktionierende Konfiguration"),
+    (MessageId::StrategyResetDefaults, "Standardwerte wiederherstellen"),
+    (MessageId::StrategyManual, "Manuelle Behebung"),
+    (MessageId::StrategyReboot, "Neustart"),
+    (MessageId::StrategyShutdown, "Ausschalten"),
+    (MessageId::StrategyUefiShell, "UEFI-Shell"),
+    (MessageId::StrategyFirmwareSetup, "Firmware-Einrichtung"),
+];
+
+const STRINGS_ES: &[(MessageId, &str)] = &[
+    (MessageId::MenuTitle, "Gestor de arranque Helix UEFI v1.0"),
+    (MessageId::KeySelect, "Elegir"),
+    (MessageId::KeyBoot, "Arrancar"),
+    (MessageId::KeyEdit, "Editar"),
+    (MessageId::KeyShell, "Shell"),
+    (MessageId::KeyReboot, "Reiniciar"),
+    (MessageId::KeyShutdown, "Apagar"),
+    (MessageId::KeyContinue, "Continuar"),
+    (MessageId::KeyLanguage, "Idioma"),
+    (MessageId::KeyHighContrast, "Alto contraste"),
+    (MessageId::KeyBeeps, "Pitidos"),
+    (MessageId::KeyProfile, "Perfil"),
+    (MessageId::KeyBootTimes, "Tiempos de arranque"),
+    (MessageId::BootTimesEmpty, "Aún no hay tiempos de arranque registrados"),
+    (MessageId::PressAnyKey, "Pulse una tecla para volver"),
+    (MessageId::ChainloadReturned, "{0} ha terminado ({1})"),
+    (MessageId::ChainloadFailed, "No se puede iniciar {0}: {1}"),
+    (MessageId::BootCountdown, "Arrancando en {0,plural,one{# segundo}other{# segundos}}..."),
+    (MessageId::EditorTitle, "Editor de línea de comandos:"),
+    (MessageId::InvalidCmdline, "Línea de comandos no válida: {0}"),
+    (MessageId::GraphicalTitle, "Gestor de arranque Helix"),
+    (MessageId::GraphicalHelp, "Flechas para elegir, Intro para arrancar"),
+    (MessageId::AutoBoot, "Arranque automático en {0} s"),
+    (MessageId::ErrorTitle, "Error de arranque"),
+    (MessageId::ErrorCode, "Código de error: {0}"),
+    (MessageId::RecoveryOptions, "Opciones de recuperación:"),
+    (MessageId::RecoveryCountdown, "{0} en {1,plural,one{# segundo}other{# segundos}}"),
+    (MessageId::StrategyNone, "Ninguna"),
+    (MessageId::StrategyRetry, "Reintentar"),
+    (MessageId::StrategyFallback, "Alternativa"),
+    (MessageId::StrategySafeMode, "Modo seguro"),
+    (MessageId::StrategyRecoveryMode, "Modo de recuperación"),
+    (MessageId::StrategyLastKnownGood, "Última configuración válida"),
+    (MessageId::StrategyResetDefaults, "Restablecer valores predeterminados"),
+    (MessageId::StrategyManual, "Intervención manual"),
+    (MessageId::StrategyReboot, "Reiniciar"),
+    (MessageId::StrategyShutdown, "Apagar"),
+    (MessageId::StrategyUefiShell, "Shell UEFI"),
+    (MessageId::StrategyFirmwareSetup, "Configuración del firmware"),
+];
+
+/// String table for a language, if one is embedded
+pub fn string_table(language: Language) -> Option<&'static StringTable> {
+    STRING_TABLES.iter().find(|table| table.language == language)
+}
+
+/// Next language with a string table, wrapping around
+pub fn next_language(language: Language) -> Language {
+    let index = STRING_TABLES.iter().position(|table| table.language == language);
+    let next = index.map_or(0, |i| (i + 1) % STRING_TABLES.len());
+    STRING_TABLES[next].language
+}
+
+/// Message pattern in `language`, falling back to English
+pub fn message(language: Language, id: MessageId) -> &'static str {
+    string_table(language)
+        .and_then(|table| table.get(id))
+        .or_else(|| STRING_TABLES[0].get(id))
+        .unwrap_or("")
+}
+
+/// Write a message with its arguments substituted
+pub fn format_message(
+    out: &mut dyn fmt::Write,
+    language: Language,
+    id: MessageId,
+    args: &[MessageArg<'_>],
+) -> fmt::Result {
+    format_pattern(out, language, message(language, id), args)
+}
+
+/// Write a message pattern with its arguments substituted
+pub fn format_pattern(
+    out: &mut dyn fmt::Write,
+    language: Language,
+    pattern: &str,
+    args: &[MessageArg<'_>],
+) -> fmt::Result {
+    let mut rest = pattern;
+
+    while let Some(open) = rest.find('{') {
+        out.write_str(&rest[..open])?;
+        rest = &rest[open + 1..];
+
+        let end = closing_brace(rest);
+        let body = &rest[..end];
+        rest = &rest[(end + 1).min(rest.len())..];
+
+        let (index, forms) = match body.split_once(',') {
+            Some((index, forms)) => (index, Some(forms)),
+            None => (body, None),
+        };
+        let arg = index.trim().parse::<usize>().ok().and_then(|i| args.get(i));
+
+        match (arg, forms.and_then(|f| f.trim_start().strip_prefix("plural,"))) {
+            (Some(MessageArg::Num(n)), Some(forms)) => write_plural(out, language, *n, forms)?,
+            (Some(arg), _) => arg.write(out)?,
+            (None, _) => {
+                // Unknown placeholder: leave it visible
+                out.write_char('{')?;
+                out.write_str(body)?;
+                out.write_char('}')?;
+            }
+        }
+    }
+
+    out.write_str(rest)
+}
+
+/// Offset of the `}` closing a placeholder whose `{` was just consumed
+fn closing_brace(s: &str) -> usize {
+    let mut depth = 1;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+/// Write the plural form matching `n` from `one{..}other{..}`
+fn write_plural(out: &mut dyn fmt::Write, language: Language, n: u64, forms: &str) -> fmt::Result {
+    let wanted = language.plural(n).keyword();
+    let mut chosen = None;
+    let mut rest = forms;
+
+    while let Some(open) = rest.find('{') {
+        let keyword = rest[..open].trim();
+        let end = open + 1 + closing_brace(&rest[open + 1..]);
+        let text = &rest[open + 1..end.min(rest.len())];
+        if keyword == wanted || (keyword == PluralCategory::Other.keyword() && chosen.is_none()) {
+            chosen = Some(text);
+        }
+        rest = &rest[(end + 1).min(rest.len())..];
+    }
+
+    for (i, part) in chosen.unwrap_or("").split('#').enumerate() {
+        if i > 0 {
+            write!(out, "{}", n)?;
+        }
+        out.write_str(part)?;
+    }
+    Ok(())
+}
+
 // =============================================================================
 // RESOURCE BUNDLE
 // =============================================================================
@@ -983,12 +1464,109 @@ fn test_pixel_format() {
         assert!(!PixelFormat::Rgb24.has_alpha());
     }
 
+    #[test]
+    fn test_substitute_char() {
+        assert_eq!(substitute_char('“'), Some('"'));
+        assert_eq!(substitute_char('—'), Some('-'));
+        assert_eq!(substitute_char('╭'), Some('+'));
+        assert_eq!(substitute_char('Ａ'), Some('A'));
+        assert_eq!(substitute_char('é'), Some('e'));
+        assert_eq!(substitute_char('界'), None);
+    }
+
+    #[test]
+    fn test_resolve_glyph() {
+        // Font 0 is ASCII only, font 1 adds CJK
+        let has_glyph = |font: usize, c: char| c.is_ascii() || (font == 1 && c == '界');
+        assert_eq!(resolve_glyph('a', 2, has_glyph), Some((0, 'a')));
+        assert_eq!(resolve_glyph('界', 2, has_glyph), Some((1, '界')));
+        assert_eq!(resolve_glyph('é', 2, has_glyph), Some((0, 'e')));
+        assert_eq!(resolve_glyph('🚀', 2, has_glyph), Some((0, '?')));
+        assert_eq!(resolve_glyph('a', 0, has_glyph), None);
+    }
+
     #[test]
     fn test_bundle_header() {
         let header = ResourceBundleHeader::default();
         assert!(header.is_valid());
     }
 
+    #[test]
+    fn test_english_catalog_complete() {
+        let english = string_table(Language::En).unwrap();
+        assert_eq!(english.messages.len(), MessageId::COUNT);
+        for (i, (id, _)) in english.messages.iter().enumerate() {
+            assert_eq!(*id as usize, i);
+        }
+        for table in STRING_TABLES {
+            assert!(table.messages.len() <= MessageId::COUNT);
+        }
+    }
+
+    #[test]
+    fn test_message_fallback() {
+        assert_eq!(message(Language::Fr, MessageId::KeyLanguage), "Langue");
+        assert_eq!(message(Language::Ja, MessageId::KeyLanguage), "Language");
+        assert_eq!(next_language(Language::En), Language::Fr);
+        assert_eq!(next_language(Language::Es), Language::En);
+        assert_eq!(next_language(Language::Ja), Language::En);
+    }
+
+    struct Buf {
+        data: [u8; 128],
+        len: usize,
+    }
+
+    impl Buf {
+        fn new() -> Self {
+            Self { data: [0; 128], len: 0 }
+        }
+
+        fn as_str(&self) -> &str {
+            core::str::from_utf8(&self.data[..self.len]).unwrap()
+        }
+    }
+
+    impl fmt::Write for Buf {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let bytes = s.as_bytes();
+            self.data[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_format_plural() {
+        let mut buf = Buf::new();
+        format_message(&mut buf, Language::En, MessageId::BootCountdown, &[MessageArg::Num(1)]).unwrap();
+        assert_eq!(buf.as_str(), "Booting in 1 second...");
+
+        let mut buf = Buf::new();
+        format_message(&mut buf, Language::En, MessageId::BootCountdown, &[MessageArg::Num(5)]).unwrap();
+        assert_eq!(buf.as_str(), "Booting in 5 seconds...");
+
+        let mut buf = Buf::new();
+        format_message(&mut buf, Language::Fr, MessageId::BootCountdown, &[MessageArg::Num(0)]).unwrap();
+        assert_eq!(buf.as_str(), "Démarrage dans 0 seconde...");
+    }
+
+    #[test]
+    fn test_format_args() {
+        let mut buf = Buf::new();
+        format_message(
+            &mut buf,
+            Language::De,
+            MessageId::RecoveryCountdown,
+            &[MessageArg::Text(&"Neustart"), MessageArg::Num(3)],
+        ).unwrap();
+        assert_eq!(buf.as_str(), "Neustart in 3 Sekunden");
+
+        let mut buf = Buf::new();
+        format_pattern(&mut buf, Language::En, "{0} and {1}", &[MessageArg::Num(7)]).unwrap();
+        assert_eq!(buf.as_str(), "7 and {1}");
+    }
+
     #[test]
     fn test_resource_cache() {
         let cache = ResourceCache::new(1024 * 1024);
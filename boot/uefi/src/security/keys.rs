@@ -716,14 +716,33 @@ pub fn mul(&self, other: &BigUint) -> BigUint {
         BigUint { limbs: result }
     }
 
-    /// Modular reduction (simplified)
+    /// Modular reduction: self mod modulus
+    ///
+    /// Binary long division, one bit of `self` at a time from the top.
     pub fn mod_reduce(&self, modulus: &BigUint) -> BigUint {
-        // Simple reduction by repeated subtraction
-        // In production, use Barrett or Montgomery reduction
-        let mut result = self.clone();
+        if self.compare(modulus) < 0 {
+            return self.clone();
+        }
+
+        let mut result = BigUint::zero();
+
+        for i in (0..self.limbs.len()).rev() {
+            for bit in (0..64).rev() {
+                // result = (result << 1) | bit
+                let mut carry = (self.limbs[i] >> bit) & 1;
+                for limb in result.limbs.iter_mut() {
+                    let next = *limb >> 63;
+                    *limb = (*limb << 1) | carry;
+                    carry = next;
+                }
+                if carry != 0 {
+                    result.limbs.push(carry);
+                }
 
-        while result.compare(modulus) >= 0 {
-            result = result.sub(modulus);
+                if result.compare(modulus) >= 0 {
+                    result = result.sub(modulus);
+                }
+            }
         }
 
         result
@@ -1349,4 +1368,15 @@ fn test_biguint_mul() {
         let c = a.mul(&b);
         assert_eq!(c.to_be_bytes(), vec![0x06]);
     }
+
+    #[test]
+    fn test_biguint_mod_reduce() {
+        // (2^128 + 5) mod (2^64 + 1) = 6
+        let a = BigUint::from_be_bytes(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5]);
+        let m = BigUint::from_be_bytes(&[1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(a.mod_reduce(&m).to_be_bytes(), vec![0x06]);
+
+        let small = BigUint::from_be_bytes(&[0x07]);
+        assert_eq!(small.mod_reduce(&m).to_be_bytes(), vec![0x07]);
+    }
 }
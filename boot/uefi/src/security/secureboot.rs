@@ -3,11 +3,14 @@
 //! Interface with UEFI Secure Boot variables, certificate databases, and verification.
 
 extern crate alloc;
+use alloc::vec;
 use alloc::vec::Vec;
 
 use super::hash::Sha256;
 use super::keys::{KeyError, X509Certificate};
-use super::signature::{AuthenticodeVerifier, SignatureError};
+use super::signature::{AuthenticodeVerifier, Pkcs7, SignatureError, SignatureVerificationResult};
+use crate::raw::types::{guids, Guid, Status};
+use crate::services::runtime::RuntimeServices;
 
 // =============================================================================
 // SECURE BOOT GUIDS
@@ -103,6 +106,13 @@ pub enum SecureBootMode {
     DeployedMode,
 }
 
+impl SecureBootMode {
+    /// Are unverified images refused?
+    pub fn is_enforcing(&self) -> bool {
+        matches!(self, Self::UserMode | Self::DeployedMode)
+    }
+}
+
 /// Secure Boot state
 #[derive(Debug, Clone)]
 pub struct SecureBootState {
@@ -135,6 +145,48 @@ pub fn determine_mode(&mut self) {
             SecureBootMode::UserMode
         };
     }
+
+    /// Read the mode variables from firmware
+    ///
+    /// Missing variables read as cleared: firmware without Secure Boot
+    /// has none of them.
+    pub fn read(rs: &RuntimeServices) -> Self {
+        let flag = |name| {
+            read_variable(rs, name, &guids::GLOBAL_VARIABLE)
+                .is_some_and(|data| data.first() == Some(&1))
+        };
+        let mut state = Self {
+            mode: SecureBootMode::Disabled,
+            secure_boot_enabled: flag(var_name::SECURE_BOOT),
+            setup_mode: flag(var_name::SETUP_MODE),
+            audit_mode: flag(var_name::AUDIT_MODE),
+            deployed_mode: flag(var_name::DEPLOYED_MODE),
+            vendor_keys: flag(var_name::VENDOR_KEYS),
+        };
+        state.determine_mode();
+        state
+    }
+}
+
+/// Largest variable read (dbx grows with every revocation)
+const MAX_VARIABLE_SIZE: usize = 1024 * 1024;
+
+/// Read a whole variable; `None` if it is missing
+fn read_variable(rs: &RuntimeServices, name: &str, guid: &Guid) -> Option<Vec<u8>> {
+    let name: Vec<u16> = name.encode_utf16().chain(core::iter::once(0)).collect();
+    let mut buffer = vec![0u8; 4096];
+    loop {
+        match rs.get_variable(&name, guid, &mut buffer) {
+            Ok((_, size)) => {
+                buffer.truncate(size);
+                return Some(buffer);
+            }
+            Err(Status::BUFFER_TOO_SMALL) if buffer.len() < MAX_VARIABLE_SIZE => {
+                buffer.resize(buffer.len() * 4, 0);
+            }
+            Err(_) => return None,
+        }
+    }
 }
 
 // =============================================================================
@@ -584,6 +636,29 @@ pub fn load_dbt(&mut self, data: &[u8]) -> Result<(), SecureBootError> {
         Ok(())
     }
 
+    /// Trust a certificate not enrolled in db (the Helix vendor certificate)
+    pub fn add_vendor_cert(&mut self, cert: X509Certificate) {
+        self.authenticode.add_trusted_cert(cert);
+    }
+
+    /// Verifier for db and dbx as enrolled in firmware
+    ///
+    /// The vendor certificate patched into this loader, if any, is
+    /// trusted as well.
+    pub fn from_firmware(rs: &RuntimeServices) -> Result<Self, SecureBootError> {
+        let mut verifier = Self::new();
+        if let Some(db) = read_variable(rs, var_name::DB, &guids::IMAGE_SECURITY_DATABASE) {
+            verifier.load_db(&db)?;
+        }
+        if let Some(dbx) = read_variable(rs, var_name::DBX, &guids::IMAGE_SECURITY_DATABASE) {
+            verifier.load_dbx(&dbx)?;
+        }
+        if let Some(cert) = vendor_cert() {
+            verifier.add_vendor_cert(cert);
+        }
+        Ok(verifier)
+    }
+
     /// Set current time
     pub fn set_time(&mut self, time: u64) {
         self.current_time = Some(time);
@@ -610,7 +685,49 @@ pub fn verify_image(&self, image: &[u8]) -> SecureBootResult {
         }
 
         // Try to verify Authenticode signature
-        match self.authenticode.verify_pe(image) {
+        self.check_signature(self.authenticode.verify_pe(image))
+    }
+
+    /// Verify a kernel image
+    ///
+    /// PE kernels carry an Authenticode signature. Other kernels (ELF)
+    /// carry a detached PKCS#7 signature over the rest of the file,
+    /// appended as laid out by [`split_appended_signature`].
+    pub fn verify_kernel(&self, image: &[u8]) -> SecureBootResult {
+        if image.starts_with(b"MZ") {
+            return self.verify_image(image);
+        }
+
+        let Some((payload, signature)) = split_appended_signature(image) else {
+            return SecureBootResult::Denied {
+                reason: DenialReason::NoSignature,
+            };
+        };
+
+        let payload_hash = Sha256::digest(payload);
+        if self.dbx.contains_hash(&payload_hash) {
+            return SecureBootResult::Denied {
+                reason: DenialReason::HashRevoked,
+            };
+        }
+        if self.db.contains_hash(&payload_hash) {
+            return SecureBootResult::Allowed {
+                method: AllowMethod::HashInDb,
+                signer: None,
+            };
+        }
+
+        self.check_signature(
+            Pkcs7::parse(signature).and_then(|pkcs7| self.authenticode.verify_pkcs7(&pkcs7, &payload_hash)),
+        )
+    }
+
+    /// Check a verified signature's signer and chain against dbx
+    fn check_signature(
+        &self,
+        result: Result<SignatureVerificationResult, SignatureError>,
+    ) -> SecureBootResult {
+        match result {
             Ok(result) => {
                 if result.valid {
                     // Check if signer is in dbx
@@ -743,6 +860,79 @@ pub enum DenialReason {
     CertificateExpired,
 }
 
+// =============================================================================
+// KERNEL SIGNATURES
+// =============================================================================
+
+/// Trailer of a signature appended to a non-PE kernel
+///
+/// ```text
+/// kernel | PKCS#7 (DER) | signature length (u32 LE) | magic
+/// ```
+pub const APPENDED_SIGNATURE_MAGIC: &[u8] = b"~Helix signature appended~\n";
+
+/// Split `image` into the signed payload and its appended PKCS#7 signature
+pub fn split_appended_signature(image: &[u8]) -> Option<(&[u8], &[u8])> {
+    let rest = image.strip_suffix(APPENDED_SIGNATURE_MAGIC)?;
+    let (rest, len) = rest.split_at(rest.len().checked_sub(4)?);
+    let len = u32::from_le_bytes(len.try_into().ok()?) as usize;
+    let payload = rest.len().checked_sub(len)?;
+    Some(rest.split_at(payload))
+}
+
+/// Space reserved for the vendor certificate
+pub const VENDOR_CERT_SPACE: usize = 4096;
+
+/// Helix vendor certificate
+///
+/// Its own section so release tooling can patch a DER certificate into a
+/// built loader, shim style: a `u32` LE length, then the certificate.
+/// Length zero means none.
+#[used]
+#[link_section = ".vendor_cert"]
+static VENDOR_CERT: [u8; VENDOR_CERT_SPACE] = [0; VENDOR_CERT_SPACE];
+
+/// The vendor certificate patched into this loader, if any
+pub fn vendor_cert() -> Option<X509Certificate> {
+    // Patched after the build: the zeros must not be constant-folded
+    let section: &[u8; VENDOR_CERT_SPACE] = core::hint::black_box(&VENDOR_CERT);
+    let len = u32::from_le_bytes(section[..4].try_into().ok()?) as usize;
+    let der = section.get(4..4 + len).filter(|der| !der.is_empty())?;
+    X509Certificate::from_der(der).ok()
+}
+
+/// Verify a kernel under the firmware Secure Boot policy
+///
+/// Kernels are checked against db, dbx and the vendor certificate. A
+/// denied kernel is an error only when the mode is enforcing; in setup
+/// and audit mode the result is returned for the caller to report.
+pub fn verify_kernel(rs: &RuntimeServices, image: &[u8]) -> Result<SecureBootResult, SecureBootError> {
+    let state = SecureBootState::read(rs);
+    match state.mode {
+        SecureBootMode::Disabled => {
+            return Ok(SecureBootResult::Allowed {
+                method: AllowMethod::SecureBootDisabled,
+                signer: None,
+            })
+        }
+        SecureBootMode::SetupMode => {
+            return Ok(SecureBootResult::Allowed {
+                method: AllowMethod::SetupMode,
+                signer: None,
+            })
+        }
+        _ => {}
+    }
+
+    let result = SecureBootVerifier::from_firmware(rs)?.verify_kernel(image);
+    match result {
+        SecureBootResult::Denied { reason } if state.mode.is_enforcing() => {
+            Err(SecureBootError::KernelDenied(reason))
+        }
+        result => Ok(result),
+    }
+}
+
 // =============================================================================
 // AUTHENTICATED VARIABLE
 // =============================================================================
@@ -944,6 +1134,8 @@ pub enum SecureBootError {
     AccessDenied,
     /// Signature verification failed
     VerificationFailed,
+    /// Kernel refused while Secure Boot is enforcing
+    KernelDenied(DenialReason),
 }
 
 // =============================================================================
@@ -994,6 +1186,43 @@ fn test_secure_boot_result() {
         assert!(!denied.is_allowed());
     }
 
+    #[test]
+    fn test_verify_kernel_appended() {
+        let payload = b"\x7fELF kernel";
+        let signature = [0x30, 0x03, 0x02, 0x01, 0x00];
+        let mut image = payload.to_vec();
+        image.extend_from_slice(&signature);
+        image.extend_from_slice(&(signature.len() as u32).to_le_bytes());
+        image.extend_from_slice(APPENDED_SIGNATURE_MAGIC);
+
+        let (signed, appended) = split_appended_signature(&image).unwrap();
+        assert_eq!(signed, payload);
+        assert_eq!(appended, signature);
+        assert!(split_appended_signature(payload).is_none());
+
+        let mut verifier = SecureBootVerifier::new();
+        assert!(matches!(
+            verifier.verify_kernel(payload),
+            SecureBootResult::Denied { reason: DenialReason::NoSignature }
+        ));
+        assert!(matches!(
+            verifier.verify_kernel(&image),
+            SecureBootResult::Denied { reason: DenialReason::SignatureError }
+        ));
+
+        // The hash in db covers the payload, not the signature
+        let mut list = SignatureList::new(SignatureEntryType::Sha256);
+        list.add_entry(SignatureEntry::sha256([0; 16], Sha256::digest(payload)));
+        verifier.db.add_list(list.clone());
+        assert!(verifier.verify_kernel(&image).is_allowed());
+
+        verifier.dbx.add_list(list);
+        assert!(matches!(
+            verifier.verify_kernel(&image),
+            SecureBootResult::Denied { reason: DenialReason::HashRevoked }
+        ));
+    }
+
     #[test]
     fn test_efi_time() {
         let time = EfiTime {
@@ -122,6 +122,42 @@ pub mod pcr {
     pub const PCR9_CMDLINE: u32 = 9;
 }
 
+/// Event data of the boot component measurements
+pub mod boot_event {
+    /// Kernel image
+    pub const KERNEL: &[u8] = b"helix kernel";
+    /// Initial ramdisk
+    pub const INITRD: &[u8] = b"helix initrd";
+    /// Kernel command line
+    pub const CMDLINE: &[u8] = b"helix cmdline";
+    /// Boot configuration file
+    pub const CONFIG: &[u8] = b"helix boot config";
+    /// Kernel lockdown level
+    pub const LOCKDOWN: &[u8] = b"helix lockdown";
+}
+
+/// `EFI_TCG2_BOOT_HASH_ALG_*` bits of the banks [`MeasuredBoot`] computes
+const HASH_ALG_BITS: [(u32, u16); 3] = [
+    (0x0000_0002, algorithm::TPM_ALG_SHA256),
+    (0x0000_0004, algorithm::TPM_ALG_SHA384),
+    (0x0000_0008, algorithm::TPM_ALG_SHA512),
+];
+
+/// What a boot measures, see [`MeasuredBoot::measure_boot`]
+#[derive(Debug, Clone, Copy)]
+pub struct BootComponents<'a> {
+    /// Kernel image as read
+    pub kernel: &'a [u8],
+    /// Initrd
+    pub initrd: Option<&'a [u8]>,
+    /// Kernel command line
+    pub cmdline: &'a str,
+    /// Boot configuration file
+    pub config: Option<&'a [u8]>,
+    /// Kernel lockdown level (`none`, `integrity`, `confidentiality`)
+    pub lockdown: Option<&'a str>,
+}
+
 // =============================================================================
 // TCG2 EVENT TYPES
 // =============================================================================
@@ -444,11 +480,18 @@ pub fn new_multi_hash(pcr_index: u32, event_type: u32, data: &[u8], algorithms:
     }
 
     /// Serialize to bytes
+    ///
+    /// Event log structures are little-endian, unlike the big-endian
+    /// [`TpmlDigestValues::to_bytes`] used in TPM commands.
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut result = Vec::new();
         result.extend_from_slice(&self.pcr_index.to_le_bytes());
         result.extend_from_slice(&self.event_type.to_le_bytes());
-        result.extend_from_slice(&self.digests.to_bytes());
+        result.extend_from_slice(&self.digests.count.to_le_bytes());
+        for digest in &self.digests.digests {
+            result.extend_from_slice(&digest.hash_alg.to_le_bytes());
+            result.extend_from_slice(&digest.digest);
+        }
         result.extend_from_slice(&self.event_size.to_le_bytes());
         result.extend_from_slice(&self.event);
         result
@@ -479,7 +522,8 @@ pub fn new_tcg2(algorithms: &[(u16, u16)]) -> Self {
             spec_version_minor: 0,
             spec_version_major: 2,
             spec_errata: 0,
-            uintn_size: core::mem::size_of::<usize>() as u8,
+            // In UINT32s: 1 for 32-bit, 2 for 64-bit firmware
+            uintn_size: (core::mem::size_of::<usize>() / 4) as u8,
             number_of_algorithms: algorithms.len() as u32,
             digest_sizes: algorithms.to_vec(),
             vendor_info_size: 0,
@@ -967,6 +1011,79 @@ pub fn measure_variable(
         self.extend_digest(pcr, event_type::EV_EFI_BOOT_SERVICES_APPLICATION, image_hash, &event_data);
     }
 
+    /// Measure `data` into `pcr`, logging `description` as the event
+    ///
+    /// Unlike [`Self::extend`], the digests cover `data` rather than the
+    /// logged event, as `HashLogExtendEvent` does.
+    pub fn measure(&mut self, pcr: u32, event_type: u32, data: &[u8], description: &[u8]) {
+        let algorithms: Vec<u16> = self.banks.iter().map(|b| b.algorithm).collect();
+        let mut event = TcgPcrEvent2::new_multi_hash(pcr, event_type, data, &algorithms);
+        event.event_size = description.len() as u32;
+        event.event = description.to_vec();
+
+        for (bank, digest) in self.banks.iter_mut().zip(&event.digests.digests) {
+            bank.extend(pcr, &digest.digest);
+        }
+        self.event_log.add_event(event);
+    }
+
+    /// Measure the components of a boot into PCRs 8 and 9
+    ///
+    /// The kernel image (as read, before relocation) and initrd go to
+    /// [`pcr::PCR8_KERNEL`], the command line, boot configuration and
+    /// lockdown level to [`pcr::PCR9_CMDLINE`], as `EV_IPL` events described by
+    /// [`boot_event`]. `tpm` is called with each (pcr, event type, data,
+    /// description) to extend the TPM as well; its first error stops the
+    /// measurement with the log covering only what the TPM saw.
+    pub fn measure_boot<E>(
+        &mut self,
+        components: &BootComponents<'_>,
+        mut tpm: impl FnMut(u32, u32, &[u8], &[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut measurements = vec![(pcr::PCR8_KERNEL, boot_event::KERNEL, components.kernel)];
+        if let Some(initrd) = components.initrd {
+            measurements.push((pcr::PCR8_KERNEL, boot_event::INITRD, initrd));
+        }
+        measurements.push((pcr::PCR9_CMDLINE, boot_event::CMDLINE, components.cmdline.as_bytes()));
+        if let Some(config) = components.config {
+            measurements.push((pcr::PCR9_CMDLINE, boot_event::CONFIG, config));
+        }
+        if let Some(lockdown) = components.lockdown {
+            measurements.push((pcr::PCR9_CMDLINE, boot_event::LOCKDOWN, lockdown.as_bytes()));
+        }
+
+        for (pcr, description, data) in measurements {
+            tpm(pcr, event_type::EV_IPL, data, description)?;
+            self.measure(pcr, event_type::EV_IPL, data, description);
+        }
+        Ok(())
+    }
+
+    /// Create with the banks in an `EFI_TCG2_BOOT_HASH_ALG_*` bitmap
+    ///
+    /// Banks that cannot be computed here are skipped; SHA-256 is used if
+    /// none is left.
+    pub fn from_hash_bitmap(bitmap: u32, num_pcrs: usize) -> Self {
+        let algorithms: Vec<u16> = HASH_ALG_BITS
+            .iter()
+            .filter(|(bit, _)| bitmap & bit != 0)
+            .map(|&(_, alg)| alg)
+            .collect();
+        if algorithms.is_empty() {
+            Self::new_sha256(num_pcrs)
+        } else {
+            Self::new(&algorithms, num_pcrs)
+        }
+    }
+
+    /// Banks as an `EFI_TCG2_BOOT_HASH_ALG_*` bitmap
+    pub fn hash_bitmap(&self) -> u32 {
+        HASH_ALG_BITS
+            .iter()
+            .filter(|(_, alg)| self.banks.iter().any(|b| b.algorithm == *alg))
+            .fold(0, |bitmap, (bit, _)| bitmap | bit)
+    }
+
     /// Get PCR value
     pub fn get_pcr(&self, algorithm: u16, pcr: u32) -> Option<&[u8]> {
         for bank in &self.banks {
@@ -1089,6 +1206,48 @@ fn test_measured_boot() {
         assert_eq!(mb.event_log().events().len(), 1);
     }
 
+    #[test]
+    fn test_measure_boot() {
+        let mut mb = MeasuredBoot::from_hash_bitmap(0x2 | 0x4, 24);
+        assert_eq!(mb.hash_bitmap(), 0x6);
+
+        let components = BootComponents {
+            kernel: b"\x7fELF",
+            initrd: None,
+            cmdline: "root=/dev/sda1",
+            config: Some(b"timeout=5"),
+            lockdown: Some("integrity"),
+        };
+        let mut extended = Vec::new();
+        mb.measure_boot(&components, |pcr, _, data, _| {
+            extended.push((pcr, data.to_vec()));
+            Ok::<(), ()>(())
+        })
+        .unwrap();
+        assert_eq!(extended.len(), 4);
+        assert_eq!(extended[3], (pcr::PCR9_CMDLINE, b"integrity".to_vec()));
+
+        // PCR 8 = extend(0, sha256(kernel)), event data is the description
+        let events = mb.event_log().events_for_pcr(pcr::PCR8_KERNEL);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, boot_event::KERNEL);
+        let mut expected = PcrBank::new(algorithm::TPM_ALG_SHA256, 24);
+        expected.extend(8, &Sha256::digest(b"\x7fELF"));
+        assert_eq!(mb.get_pcr(algorithm::TPM_ALG_SHA256, 8), expected.get(8));
+        assert_eq!(mb.event_log().events_for_pcr(pcr::PCR9_CMDLINE).len(), 3);
+
+        // Little-endian log: count, then SHA-256 and SHA-384 digests
+        let bytes = events[0].to_bytes();
+        assert_eq!(&bytes[0..12], &[8, 0, 0, 0, 0x0D, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(&bytes[12..14], &algorithm::TPM_ALG_SHA256.to_le_bytes());
+        assert_eq!(bytes.len(), 12 + 2 + 32 + 2 + 48 + 4 + boot_event::KERNEL.len());
+
+        // A TPM failure stops before anything more is logged
+        let mut mb = MeasuredBoot::default();
+        assert_eq!(mb.measure_boot(&components, |_, _, _, _| Err(1)), Err(1));
+        assert!(mb.event_log().events().is_empty());
+    }
+
     #[test]
     fn test_tpm2_command_build() {
         let cmd = Tpm2Command::new(command::TPM_CC_STARTUP)
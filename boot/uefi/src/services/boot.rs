@@ -672,6 +672,14 @@ pub fn calculate_crc32(&self, data: &[u8]) -> Result<u32, Status> {
 /// # Safety
 /// Must only be called after initialization and before ExitBootServices.
 pub unsafe fn boot_services() -> BootServices {
-    BootServices::from_ptr(super::boot_services() as *const _ as *mut _)
-        .expect("Boot services not available")
+    try_boot_services().expect("Boot services not available")
+}
+
+/// Get boot services from global state
+///
+/// Fails instead of panicking before initialization or after
+/// ExitBootServices (see [`super::try_boot_services`]).
+pub fn try_boot_services() -> Result<BootServices, Status> {
+    let bs = super::try_boot_services()?;
+    unsafe { BootServices::from_ptr(bs as *const _ as *mut _) }.ok_or(Status::UNSUPPORTED)
 }
@@ -3,7 +3,7 @@
 //! Safe wrappers for UEFI capsule update functionality.
 
 use crate::raw::types::*;
-use super::runtime::runtime_services;
+use super::runtime::try_runtime_services;
 
 // =============================================================================
 // CAPSULE
@@ -55,7 +55,7 @@ pub fn query_capabilities(&self) -> Result<CapsuleCapabilities, Status> {
         let header_ptr = &header as *const CapsuleHeader as *mut CapsuleHeader;
         let headers = [header_ptr];
 
-        let rs = unsafe { runtime_services() };
+        let rs = try_runtime_services()?;
         unsafe { rs.query_capsule_capabilities(&headers) }
     }
 
@@ -193,7 +193,7 @@ pub unsafe fn submit_capsule(capsule: &Capsule) -> Result<(), Status> {
     // Create header pointer array
     let headers = [buffer as *mut CapsuleHeader];
 
-    let rs = runtime_services();
+    let rs = try_runtime_services()?;
     rs.update_capsule(&headers, PhysicalAddress(0))
 }
 
@@ -240,7 +240,7 @@ pub unsafe fn submit_capsules(capsules: &[Capsule]) -> Result<(), Status> {
         headers.push(buffer as *mut CapsuleHeader);
     }
 
-    let rs = runtime_services();
+    let rs = try_runtime_services()?;
     rs.update_capsule(&headers, PhysicalAddress(0))
 }
 
@@ -3,7 +3,7 @@
 //! Safe wrappers for UEFI event and timer management.
 
 use crate::raw::types::*;
-use super::boot::{boot_services, EventType, TPL_CALLBACK};
+use super::boot::{try_boot_services, EventType, TPL_CALLBACK};
 
 // =============================================================================
 // EVENT WRAPPER
@@ -37,26 +37,26 @@ pub fn take(mut self) -> Event {
 
     /// Signal the event
     pub fn signal(&self) -> Result<(), Status> {
-        unsafe { boot_services().signal_event(self.event) }
+        try_boot_services()?.signal_event(self.event)
     }
 
     /// Check if event is signaled
     pub fn check(&self) -> Result<bool, Status> {
-        unsafe { boot_services().check_event(self.event) }
+        try_boot_services()?.check_event(self.event)
     }
 
     /// Close the event
     pub fn close(mut self) -> Result<(), Status> {
         self.auto_close = false;
-        unsafe { boot_services().close_event(self.event) }
+        try_boot_services()?.close_event(self.event)
     }
 }
 
 impl Drop for EventGuard {
     fn drop(&mut self) {
         if self.auto_close && !self.event.is_null() {
-            unsafe {
-                let _ = boot_services().close_event(self.event);
+            if let Ok(bs) = try_boot_services() {
+                let _ = bs.close_event(self.event);
             }
         }
     }
@@ -74,7 +74,7 @@ pub struct Timer {
 impl Timer {
     /// Create a new timer
     pub fn new() -> Result<Self, Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         let event = bs.create_timer_event()?;
 
         Ok(Self {
@@ -89,19 +89,19 @@ pub fn event(&self) -> Event {
 
     /// Set timer to trigger periodically
     pub fn set_periodic(&self, interval_100ns: u64) -> Result<(), Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         bs.set_timer(self.event.handle(), TimerDelay::Periodic, interval_100ns)
     }
 
     /// Set timer to trigger once
     pub fn set_relative(&self, delay_100ns: u64) -> Result<(), Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         bs.set_timer(self.event.handle(), TimerDelay::Relative, delay_100ns)
     }
 
     /// Cancel timer
     pub fn cancel(&self) -> Result<(), Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         bs.set_timer(self.event.handle(), TimerDelay::Cancel, 0)
     }
 
@@ -112,7 +112,7 @@ pub fn check(&self) -> Result<bool, Status> {
 
     /// Wait for timer to trigger
     pub fn wait(&self) -> Result<(), Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         let events = [self.event.handle()];
         bs.wait_for_event(&events)?;
         Ok(())
@@ -185,7 +185,7 @@ pub unsafe fn new<F>(
         let callback_box = Box::new(callback);
         let callback_ptr = Box::into_raw(callback_box);
 
-        let bs = boot_services();
+        let bs = try_boot_services()?;
         let event = bs.create_event(
             EventType::TIMER | EventType::NOTIFY_SIGNAL,
             TPL_CALLBACK,
@@ -208,7 +208,7 @@ pub fn stop(&mut self) -> Result<(), Status> {
             return Ok(());
         }
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         bs.set_timer(self.event, TimerDelay::Cancel, 0)?;
         self.active = false;
 
@@ -225,8 +225,8 @@ impl Drop for PeriodicCallback {
     fn drop(&mut self) {
         let _ = self.stop();
         if !self.event.is_null() {
-            unsafe {
-                let _ = boot_services().close_event(self.event);
+            if let Ok(bs) = try_boot_services() {
+                let _ = bs.close_event(self.event);
             }
         }
     }
@@ -276,13 +276,13 @@ pub fn wait_any(&self) -> Result<usize, Status> {
             return Err(Status::INVALID_PARAMETER);
         }
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         bs.wait_for_event(&self.events)
     }
 
     /// Check which events are signaled
     pub fn check_all(&self) -> Result<alloc::vec::Vec<bool>, Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         let mut results = alloc::vec::Vec::with_capacity(self.events.len());
 
         for &event in &self.events {
@@ -320,13 +320,13 @@ fn default() -> Self {
 
 /// Sleep for specified milliseconds
 pub fn sleep_ms(milliseconds: u64) -> Result<(), Status> {
-    let bs = unsafe { boot_services() };
+    let bs = try_boot_services()?;
     bs.stall((milliseconds * 1000) as usize)
 }
 
 /// Sleep for specified microseconds
 pub fn sleep_us(microseconds: usize) -> Result<(), Status> {
-    let bs = unsafe { boot_services() };
+    let bs = try_boot_services()?;
     bs.stall(microseconds)
 }
 
@@ -354,7 +354,7 @@ pub unsafe fn create_notification_event<F>(
     let callback_box = Box::new(Some(callback));
     let callback_ptr = Box::into_raw(callback_box);
 
-    let bs = boot_services();
+    let bs = try_boot_services()?;
     let event = bs.create_event(
         EventType::NOTIFY_SIGNAL,
         TPL_CALLBACK,
@@ -393,7 +393,7 @@ pub unsafe fn on_exit_boot_services<F>(callback: F) -> Result<EventGuard, Status
     let callback_box = Box::new(Some(callback));
     let callback_ptr = Box::into_raw(callback_box);
 
-    let bs = boot_services();
+    let bs = try_boot_services()?;
     let event = bs.create_event(
         EventType::SIGNAL_EXIT_BOOT_SERVICES,
         TPL_CALLBACK,
@@ -415,7 +415,7 @@ pub unsafe fn on_virtual_address_change<F>(callback: F) -> Result<EventGuard, St
     let callback_box = Box::new(Some(callback));
     let callback_ptr = Box::into_raw(callback_box);
 
-    let bs = boot_services();
+    let bs = try_boot_services()?;
     let event = bs.create_event(
         EventType::SIGNAL_VIRTUAL_ADDRESS_CHANGE,
         TPL_CALLBACK,
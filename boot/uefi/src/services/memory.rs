@@ -19,7 +19,7 @@ pub unsafe fn allocate_pool(
     memory_type: MemoryType,
     size: usize,
 ) -> Result<*mut u8, Status> {
-    let bs = super::boot_services();
+    let bs = super::try_boot_services()?;
     let mut buffer: *mut u8 = core::ptr::null_mut();
 
     let status = (bs.allocate_pool)(memory_type, size, &mut buffer);
@@ -36,7 +36,7 @@ pub unsafe fn free_pool(buffer: *mut u8) -> Result<(), Status> {
         return Ok(());
     }
 
-    let bs = super::boot_services();
+    let bs = super::try_boot_services()?;
     let status = (bs.free_pool)(buffer);
     status.to_status_result()
 }
@@ -51,7 +51,7 @@ pub unsafe fn allocate_pages(
     pages: usize,
     address: &mut PhysicalAddress,
 ) -> Result<(), Status> {
-    let bs = super::boot_services();
+    let bs = super::try_boot_services()?;
     let status = (bs.allocate_pages)(alloc_type, memory_type, pages, address);
     status.to_status_result()
 }
@@ -61,7 +61,7 @@ pub unsafe fn allocate_pages(
 /// # Safety
 /// Must only be called with addresses returned by allocate_pages.
 pub unsafe fn free_pages(address: PhysicalAddress, pages: usize) -> Result<(), Status> {
-    let bs = super::boot_services();
+    let bs = super::try_boot_services()?;
     let status = (bs.free_pages)(address, pages);
     status.to_status_result()
 }
@@ -92,7 +92,7 @@ impl MemoryMap {
     /// # Safety
     /// Must only be called while boot services are available.
     pub unsafe fn get() -> Result<Self, Status> {
-        let bs = super::boot_services();
+        let bs = super::try_boot_services()?;
 
         // First call to get size
         let mut map_size = 0;
@@ -591,6 +591,19 @@ pub unsafe fn free(address: PhysicalAddress, pages: usize) -> Result<(), Status>
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_without_boot_services() {
+        let mut address = PhysicalAddress(0);
+        unsafe {
+            assert_eq!(allocate_pool(MemoryType::LoaderData, 64).err(), Some(Status::NOT_READY));
+            assert_eq!(
+                allocate_pages(AllocateType::AllocateAnyPages, MemoryType::LoaderData, 1, &mut address),
+                Err(Status::NOT_READY)
+            );
+            assert_eq!(MemoryMap::get().err(), Some(Status::NOT_READY));
+        }
+    }
+
     #[test]
     fn test_memory_region() {
         let region = MemoryRegion::new(0x1000, 0x5000, MemoryRegionType::Usable);
@@ -119,9 +119,18 @@ pub fn image_handle() -> Option<Handle> {
 /// # Safety
 /// Must only be called after initialize() succeeds.
 pub unsafe fn system_table() -> &'static EfiSystemTable {
+    try_system_table().expect("UEFI services not initialized")
+}
+
+/// Get a reference to the system table, failing with `NOT_READY` before
+/// initialize()
+pub fn try_system_table() -> Result<&'static EfiSystemTable, Status> {
     let ptr = SYSTEM_TABLE.load(Ordering::Acquire);
-    assert!(!ptr.is_null(), "UEFI services not initialized");
-    &*ptr
+    if ptr.is_null() {
+        return Err(Status::NOT_READY);
+    }
+    // initialize() validated the table and firmware keeps it alive
+    Ok(unsafe { &*ptr })
 }
 
 /// Get a mutable reference to the system table
@@ -140,10 +149,23 @@ pub unsafe fn system_table_mut() -> &'static mut EfiSystemTable {
 /// # Safety
 /// Must only be called after initialize() and before ExitBootServices.
 pub unsafe fn boot_services() -> &'static EfiBootServices {
-    assert!(boot_services_available(), "Boot services not available");
-    let st = system_table();
-    assert!(!st.boot_services.is_null(), "Boot services pointer is null");
-    &*st.boot_services
+    try_boot_services().expect("Boot services not available")
+}
+
+/// Get boot services
+///
+/// Fails with `NOT_READY` before initialize() and `UNSUPPORTED` once boot
+/// services have been exited.
+pub fn try_boot_services() -> Result<&'static EfiBootServices, Status> {
+    boot_services_in(try_system_table()?)
+}
+
+/// Boot services of `st`, unless they have been exited
+fn boot_services_in(st: &'static EfiSystemTable) -> Result<&'static EfiBootServices, Status> {
+    if BOOT_SERVICES_EXITED.load(Ordering::Acquire) || st.boot_services.is_null() {
+        return Err(Status::UNSUPPORTED);
+    }
+    Ok(unsafe { &*st.boot_services })
 }
 
 /// Get runtime services
@@ -151,9 +173,16 @@ pub unsafe fn boot_services() -> &'static EfiBootServices {
 /// # Safety
 /// Must only be called after initialize().
 pub unsafe fn runtime_services() -> &'static EfiRuntimeServices {
-    let st = system_table();
-    assert!(!st.runtime_services.is_null(), "Runtime services pointer is null");
-    &*st.runtime_services
+    try_runtime_services().expect("Runtime services not available")
+}
+
+/// Get runtime services, failing with `NOT_READY` before initialize()
+pub fn try_runtime_services() -> Result<&'static EfiRuntimeServices, Status> {
+    let st = try_system_table()?;
+    if st.runtime_services.is_null() {
+        return Err(Status::UNSUPPORTED);
+    }
+    Ok(unsafe { &*st.runtime_services })
 }
 
 // =============================================================================
@@ -406,3 +435,29 @@ pub unsafe fn reboot() -> ! {
 // =============================================================================
 
 extern crate alloc;
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_ready() {
+        assert!(!boot_services_available());
+        assert_eq!(try_system_table().err(), Some(Status::NOT_READY));
+        assert_eq!(try_boot_services().err(), Some(Status::NOT_READY));
+        assert_eq!(try_runtime_services().err(), Some(Status::NOT_READY));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let st: &'static EfiSystemTable = alloc::boxed::Box::leak(alloc::boxed::Box::new(
+            // SAFETY: the table is plain integers and raw pointers
+            unsafe { core::mem::zeroed() },
+        ));
+        assert_eq!(boot_services_in(st).err(), Some(Status::UNSUPPORTED));
+    }
+}
@@ -3,7 +3,7 @@
 //! Safe wrappers for locating and using UEFI protocols.
 
 use crate::raw::types::*;
-use super::boot::boot_services;
+use super::boot::try_boot_services;
 use core::marker::PhantomData;
 
 // =============================================================================
@@ -92,8 +92,8 @@ fn deref_mut(&mut self) -> &mut Self::Target {
 impl<'a, P: Protocol> Drop for ProtocolHandle<'a, P> {
     fn drop(&mut self) {
         if self.close_on_drop && !self.interface.is_null() {
-            unsafe {
-                let _ = boot_services().close_protocol(
+            if let Ok(bs) = try_boot_services() {
+                let _ = bs.close_protocol(
                     self.handle,
                     &P::GUID,
                     self.agent,
@@ -114,7 +114,7 @@ fn drop(&mut self) {
 impl ProtocolLocator {
     /// Locate a single protocol instance
     pub fn locate<P: Protocol>() -> Result<*mut P, Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         bs.locate_protocol::<P>(&P::GUID)
     }
 
@@ -123,7 +123,7 @@ pub fn open_on_handle<P: Protocol>(
         handle: Handle,
         agent: Handle,
     ) -> Result<ProtocolHandle<'static, P>, Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
 
         // Open with EXCLUSIVE access
         let interface = bs.open_protocol::<P>(
@@ -141,7 +141,7 @@ pub fn open_on_handle<P: Protocol>(
 
     /// Find all handles supporting a protocol
     pub fn find_handles<P: Protocol>() -> Result<alloc::vec::Vec<Handle>, Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         let buffer = bs.locate_handle_buffer(LocateSearchType::ByProtocol, Some(&P::GUID))?;
         Ok(buffer.handles().to_vec())
     }
@@ -151,7 +151,7 @@ pub fn open_exclusive<P: Protocol>(
         handle: Handle,
         agent: Handle,
     ) -> Result<ProtocolHandle<'static, P>, Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
 
         let interface = bs.open_protocol::<P>(
             handle,
@@ -168,7 +168,7 @@ pub fn open_exclusive<P: Protocol>(
 
     /// Get protocol without opening (simple lookup)
     pub fn get<P: Protocol>(handle: Handle) -> Result<*mut P, Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
         bs.handle_protocol::<P>(handle, &P::GUID)
     }
 }
@@ -361,7 +361,7 @@ pub unsafe fn new<P: Protocol>(
         callback: super::boot::EventNotify,
         context: *mut core::ffi::c_void,
     ) -> Result<Self, Status> {
-        let bs = boot_services();
+        let bs = try_boot_services()?;
 
         let event = bs.create_event(
             super::boot::EventType::NOTIFY_SIGNAL,
@@ -396,8 +396,8 @@ pub fn registration(&self) -> *mut core::ffi::c_void {
 impl Drop for ProtocolNotification {
     fn drop(&mut self) {
         if !self.event.is_null() {
-            unsafe {
-                let _ = boot_services().close_event(self.event);
+            if let Ok(bs) = try_boot_services() {
+                let _ = bs.close_event(self.event);
             }
         }
     }
@@ -346,6 +346,14 @@ pub unsafe fn warm_reboot(&self) -> ! {
 /// # Safety
 /// Must only be called after initialization.
 pub unsafe fn runtime_services() -> RuntimeServices {
-    RuntimeServices::from_ptr(super::runtime_services() as *const _ as *mut _)
-        .expect("Runtime services not available")
+    try_runtime_services().expect("Runtime services not available")
+}
+
+/// Get runtime services from global state
+///
+/// Fails instead of panicking before initialization (see
+/// [`super::try_runtime_services`]).
+pub fn try_runtime_services() -> Result<RuntimeServices, Status> {
+    let rs = super::try_runtime_services()?;
+    unsafe { RuntimeServices::from_ptr(rs as *const _ as *mut _) }.ok_or(Status::UNSUPPORTED)
 }
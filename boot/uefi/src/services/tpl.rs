@@ -2,7 +2,7 @@
 //!
 //! Safe wrappers for UEFI Task Priority Level management.
 
-use super::boot_services;
+use super::try_boot_services;
 
 // =============================================================================
 // TPL LEVELS
@@ -84,18 +84,26 @@ fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
 pub struct TplGuard {
     /// Previous TPL to restore
     previous: Tpl,
+    /// Whether the TPL was actually raised
+    raised: bool,
 }
 
 impl TplGuard {
     /// Raise TPL to the specified level
     ///
     /// Returns a guard that will restore the previous TPL when dropped.
+    /// Without boot services there is no TPL to raise and the guard does
+    /// nothing.
     pub fn raise(new_tpl: Tpl) -> Self {
-        let bs = unsafe { boot_services() };
-        let previous = unsafe { ((*bs).raise_tpl)(new_tpl.0) };
-
-        Self {
-            previous: Tpl(previous),
+        match try_boot_services() {
+            Ok(bs) => Self {
+                previous: Tpl(unsafe { (bs.raise_tpl)(new_tpl.0) }),
+                raised: true,
+            },
+            Err(_) => Self {
+                previous: Tpl::APPLICATION,
+                raised: false,
+            },
         }
     }
 
@@ -132,8 +140,12 @@ pub fn restore(self) {
 
 impl Drop for TplGuard {
     fn drop(&mut self) {
-        let bs = unsafe { boot_services() };
-        unsafe { ((*bs).restore_tpl)(self.previous.0) };
+        if !self.raised {
+            return;
+        }
+        if let Ok(bs) = try_boot_services() {
+            unsafe { (bs.restore_tpl)(self.previous.0) };
+        }
     }
 }
 
@@ -145,14 +157,17 @@ fn drop(&mut self) {
 ///
 /// Note: There's no direct UEFI call to get the current TPL.
 /// This raises to TPL_HIGH_LEVEL and immediately restores to detect current.
+/// Without boot services this is `TPL_APPLICATION`.
 pub fn get_current_tpl() -> Tpl {
-    let bs = unsafe { boot_services() };
+    let Ok(bs) = try_boot_services() else {
+        return Tpl::APPLICATION;
+    };
 
     // Raise to high level to get current
-    let current = unsafe { ((*bs).raise_tpl)(Tpl::HIGH_LEVEL.0) };
+    let current = unsafe { (bs.raise_tpl)(Tpl::HIGH_LEVEL.0) };
 
     // Immediately restore
-    unsafe { ((*bs).restore_tpl)(current) };
+    unsafe { (bs.restore_tpl)(current) };
 
     Tpl(current)
 }
@@ -521,6 +536,14 @@ fn test_tpl_names() {
         assert_eq!(Tpl::HIGH_LEVEL.name(), "TPL_HIGH_LEVEL");
     }
 
+    #[test]
+    fn test_raise_without_boot_services() {
+        let guard = TplGuard::raise(Tpl::NOTIFY);
+        assert_eq!(guard.previous(), Tpl::APPLICATION);
+        assert_eq!(get_current_tpl(), Tpl::APPLICATION);
+        guard.restore();
+    }
+
     #[test]
     fn test_tpl_lock() {
         let lock = TplLock::new(42);
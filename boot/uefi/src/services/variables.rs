@@ -4,7 +4,7 @@
 
 use crate::raw::types::*;
 use crate::raw::runtime_services::variable_attributes;
-use super::runtime::runtime_services;
+use super::runtime::try_runtime_services;
 
 // =============================================================================
 // VARIABLE
@@ -52,7 +52,7 @@ pub fn read(name: &str, vendor_guid: &Guid) -> Result<Self, Status> {
 
     /// Read variable from UEFI (UTF-16 name)
     pub fn read_utf16(name: &[u16], vendor_guid: &Guid) -> Result<Self, Status> {
-        let rs = unsafe { runtime_services() };
+        let rs = try_runtime_services()?;
 
         // First, try with a small buffer to get size
         let mut buffer = alloc::vec![0u8; 256];
@@ -89,7 +89,7 @@ pub fn read_utf16(name: &[u16], vendor_guid: &Guid) -> Result<Self, Status> {
 
     /// Write variable to UEFI
     pub fn write(&self) -> Result<(), Status> {
-        let rs = unsafe { runtime_services() };
+        let rs = try_runtime_services()?;
         rs.set_variable(
             &self.name,
             &self.vendor_guid,
@@ -100,7 +100,7 @@ pub fn write(&self) -> Result<(), Status> {
 
     /// Delete the variable
     pub fn delete(&self) -> Result<(), Status> {
-        let rs = unsafe { runtime_services() };
+        let rs = try_runtime_services()?;
         rs.delete_variable(&self.name, &self.vendor_guid)
     }
 }
@@ -248,7 +248,7 @@ fn next(&mut self) -> Option<Self::Item> {
             return None;
         }
 
-        let rs = unsafe { runtime_services() };
+        let rs = try_runtime_services().ok()?;
 
         loop {
             let result = rs.get_next_variable_name(&mut self.name_buffer, &mut self.vendor_guid);
@@ -307,7 +307,7 @@ pub fn write_global_variable(
 /// Delete a global variable
 pub fn delete_global_variable(name: &str) -> Result<(), Status> {
     let name_utf16 = string_to_utf16(name);
-    let rs = unsafe { runtime_services() };
+    let rs = try_runtime_services()?;
     rs.delete_variable(&name_utf16, &GLOBAL_VARIABLE_GUID)
 }
 
@@ -350,6 +350,12 @@ pub fn read_boot_option(num: u16) -> Result<BootOption, Status> {
     BootOption::parse(&var.data)
 }
 
+/// Write a boot option
+pub fn write_boot_option(num: u16, option: &BootOption) -> Result<(), Status> {
+    let name = alloc::format!("Boot{:04X}", num);
+    write_global_variable(&name, VariableAttributes::NV_BS_RT, &option.to_bytes())
+}
+
 /// Boot option structure
 #[derive(Debug, Clone)]
 pub struct BootOption {
@@ -427,6 +433,24 @@ pub fn parse(data: &[u8]) -> Result<Self, Status> {
         })
     }
 
+    /// Serialize to EFI_LOAD_OPTION layout
+    pub fn to_bytes(&self) -> alloc::vec::Vec<u8> {
+        let description = string_to_utf16(&self.description);
+        let mut data = alloc::vec::Vec::with_capacity(
+            6 + description.len() * 2 + self.device_path.len() + self.optional_data.len(),
+        );
+
+        data.extend_from_slice(&self.attributes.to_le_bytes());
+        data.extend_from_slice(&(self.device_path.len() as u16).to_le_bytes());
+        for ch in &description {
+            data.extend_from_slice(&ch.to_le_bytes());
+        }
+        data.extend_from_slice(&self.device_path);
+        data.extend_from_slice(&self.optional_data);
+
+        data
+    }
+
     /// Check if active
     pub fn is_active(&self) -> bool {
         (self.attributes & Self::ACTIVE) != 0
@@ -503,7 +527,7 @@ pub fn utf16_to_string(s: &[u16]) -> alloc::string::String {
 pub fn get_variable_storage_info(
     attributes: VariableAttributes,
 ) -> Result<VariableStorageInfo, Status> {
-    let rs = unsafe { runtime_services() };
+    let rs = try_runtime_services()?;
 
     let info = rs.query_variable_info(attributes.bits())?;
 
@@ -571,4 +595,20 @@ fn test_string_conversion() {
         let back = utf16_to_string(&utf16);
         assert_eq!(back, "Hello");
     }
+
+    #[test]
+    fn test_boot_option_roundtrip() {
+        let option = BootOption {
+            attributes: BootOption::ACTIVE,
+            description: alloc::string::String::from("Helix OS"),
+            device_path: alloc::vec![0x7F, 0xFF, 0x04, 0x00],
+            optional_data: alloc::vec![1, 2, 3],
+        };
+
+        let parsed = BootOption::parse(&option.to_bytes()).unwrap();
+        assert!(parsed.is_active());
+        assert_eq!(parsed.description, "Helix OS");
+        assert_eq!(parsed.device_path, option.device_path);
+        assert_eq!(parsed.optional_data, option.optional_data);
+    }
 }
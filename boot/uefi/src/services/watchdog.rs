@@ -3,7 +3,7 @@
 //! Safe wrappers for UEFI watchdog timer functionality.
 
 use crate::raw::types::*;
-use super::boot_services;
+use super::try_boot_services;
 
 // =============================================================================
 // WATCHDOG TIMER
@@ -30,7 +30,7 @@ pub fn new() -> Self {
     ///
     /// A timeout of 0 disables the watchdog.
     pub fn set_timeout(&mut self, seconds: u64) -> Result<(), Status> {
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
 
         // Convert to 100ns units
         let timeout = seconds;
@@ -66,7 +66,7 @@ pub fn kick(&self) -> Result<(), Status> {
             return Ok(());
         }
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
 
         // Re-set the timer to reset the countdown
         let result = unsafe {
@@ -117,7 +117,7 @@ pub fn disable() -> Result<Self, Status> {
             was_disabled: false,
         };
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
 
         let result = unsafe {
             ((*bs).set_watchdog_timer)(0, 0, 0, core::ptr::null())
@@ -137,7 +137,7 @@ pub fn with_timeout(seconds: u64) -> Result<Self, Status> {
             was_disabled: false,
         };
 
-        let bs = unsafe { boot_services() };
+        let bs = try_boot_services()?;
 
         let result = unsafe {
             ((*bs).set_watchdog_timer)(seconds as usize, 0x10000, 0, core::ptr::null())
@@ -153,8 +153,10 @@ pub fn with_timeout(seconds: u64) -> Result<Self, Status> {
 
 impl Drop for WatchdogGuard {
     fn drop(&mut self) {
-        if !self.was_disabled {
-            let bs = unsafe { boot_services() };
+        if self.was_disabled {
+            return;
+        }
+        if let Ok(bs) = try_boot_services() {
             unsafe {
                 let _ = ((*bs).set_watchdog_timer)(
                     self.previous_timeout as usize,
@@ -211,7 +213,7 @@ pub const fn user_defined(code: u64) -> Self {
 
 /// Set watchdog timeout
 pub fn set_watchdog_timeout(seconds: u64) -> Result<(), Status> {
-    let bs = unsafe { boot_services() };
+    let bs = try_boot_services()?;
 
     let result = unsafe {
         ((*bs).set_watchdog_timer)(seconds as usize, 0x10000, 0, core::ptr::null())
@@ -231,7 +233,7 @@ pub fn disable_watchdog() -> Result<(), Status> {
 
 /// Set watchdog with message
 pub fn set_watchdog_with_message(seconds: u64, code: WatchdogCode, message: &str) -> Result<(), Status> {
-    let bs = unsafe { boot_services() };
+    let bs = try_boot_services()?;
 
     // Convert message to UCS-2
     let mut buffer = [0u16; 256];
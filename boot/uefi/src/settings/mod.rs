@@ -31,6 +31,8 @@
 
 use core::fmt;
 
+use super::locale::Language;
+
 // =============================================================================
 // SETTING TYPES
 // =============================================================================
@@ -473,6 +475,8 @@ pub struct SecuritySettings {
     pub lockout_duration: u16,
     /// Auto-lock timeout (seconds, 0 = disabled)
     pub auto_lock_secs: u16,
+    /// Randomize the kernel load address (KASLR)
+    pub kaslr: bool,
 }
 
 impl Default for SecuritySettings {
@@ -487,6 +491,7 @@ fn default() -> Self {
             lockout_threshold: 3,
             lockout_duration: 30,
             auto_lock_secs: 0,
+            kaslr: true,
         }
     }
 }
@@ -560,6 +565,22 @@ pub struct NetworkSettings {
     pub dns_server: [u8; 4],
     /// VLAN ID (0 = disabled)
     pub vlan_id: u16,
+    /// Refuse plain HTTP boot URLs
+    pub https_only: bool,
+    /// SHA-256 fingerprints of the root CAs trusted for HTTPS boot
+    pub pinned_ca: [[u8; 32]; MAX_PINNED_CA],
+    /// Number of pinned root CAs (0 = any CA the firmware trusts)
+    pub pinned_ca_count: u8,
+}
+
+/// Maximum pinned HTTPS root CAs
+pub const MAX_PINNED_CA: usize = 4;
+
+impl NetworkSettings {
+    /// Pinned root CA fingerprints
+    pub fn pinned_cas(&self) -> &[[u8; 32]] {
+        &self.pinned_ca[..(self.pinned_ca_count as usize).min(MAX_PINNED_CA)]
+    }
 }
 
 impl Default for NetworkSettings {
@@ -575,6 +596,9 @@ fn default() -> Self {
             gateway: [0, 0, 0, 0],
             dns_server: [8, 8, 8, 8],
             vlan_id: 0,
+            https_only: false,
+            pinned_ca: [[0; 32]; MAX_PINNED_CA],
+            pinned_ca_count: 0,
         }
     }
 }
@@ -912,6 +936,50 @@ pub const fn iso_code(&self) -> &'static str {
             LanguageCode::Custom(_) => "xx",
         }
     }
+
+    /// UI language for this setting (English if unknown)
+    pub fn language(&self) -> Language {
+        match self {
+            LanguageCode::English => Language::En,
+            LanguageCode::French => Language::Fr,
+            LanguageCode::German => Language::De,
+            LanguageCode::Spanish => Language::Es,
+            LanguageCode::Italian => Language::It,
+            LanguageCode::Portuguese => Language::Pt,
+            LanguageCode::Russian => Language::Ru,
+            LanguageCode::Chinese => Language::ZhCn,
+            LanguageCode::Japanese => Language::Ja,
+            LanguageCode::Korean => Language::Ko,
+            LanguageCode::Arabic => Language::Ar,
+            LanguageCode::Hebrew => Language::He,
+            LanguageCode::Custom(code) => core::str::from_utf8(code)
+                .ok()
+                .and_then(Language::from_code)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Setting that selects a UI language
+    pub fn from_language(language: Language) -> Self {
+        match language {
+            Language::En => LanguageCode::English,
+            Language::Fr => LanguageCode::French,
+            Language::De => LanguageCode::German,
+            Language::Es => LanguageCode::Spanish,
+            Language::It => LanguageCode::Italian,
+            Language::Pt => LanguageCode::Portuguese,
+            Language::Ru => LanguageCode::Russian,
+            Language::ZhCn => LanguageCode::Chinese,
+            Language::Ja => LanguageCode::Japanese,
+            Language::Ko => LanguageCode::Korean,
+            Language::Ar => LanguageCode::Arabic,
+            Language::He => LanguageCode::Hebrew,
+            other => {
+                let code = other.code().as_bytes();
+                LanguageCode::Custom([code[0], code[1]])
+            }
+        }
+    }
 }
 
 /// Keyboard layout
@@ -962,6 +1030,53 @@ fn default() -> Self {
     }
 }
 
+// =============================================================================
+// ACCESSIBILITY SETTINGS
+// =============================================================================
+
+/// Shortest key repeat delay or interval accepted (ms)
+pub const KEY_REPEAT_MIN_MS: u16 = 20;
+
+/// Longest key repeat delay or interval accepted (ms)
+pub const KEY_REPEAT_MAX_MS: u16 = 2000;
+
+/// Accessibility settings structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessibilitySettings {
+    /// High-contrast colors
+    pub high_contrast: bool,
+    /// Large text (largest text mode, XLarge font)
+    pub large_text: bool,
+    /// Beep on menu navigation and selection
+    pub nav_beeps: bool,
+    /// Hold time before a key starts repeating (ms)
+    pub key_repeat_delay_ms: u16,
+    /// Interval between repeats of a held key (ms)
+    pub key_repeat_rate_ms: u16,
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            high_contrast: false,
+            large_text: false,
+            nav_beeps: false,
+            key_repeat_delay_ms: 500,
+            key_repeat_rate_ms: 100,
+        }
+    }
+}
+
+impl AccessibilitySettings {
+    /// Key repeat delay and interval, clamped to the accepted range
+    pub fn key_repeat(&self) -> (u16, u16) {
+        (
+            self.key_repeat_delay_ms.clamp(KEY_REPEAT_MIN_MS, KEY_REPEAT_MAX_MS),
+            self.key_repeat_rate_ms.clamp(KEY_REPEAT_MIN_MS, KEY_REPEAT_MAX_MS),
+        )
+    }
+}
+
 // =============================================================================
 // MASTER SETTINGS
 // =============================================================================
@@ -1030,6 +1145,8 @@ pub struct Settings {
     pub power: PowerSettings,
     /// Locale settings
     pub locale: LocaleSettings,
+    /// Accessibility settings
+    pub accessibility: AccessibilitySettings,
 }
 
 impl Default for Settings {
@@ -1044,6 +1161,7 @@ fn default() -> Self {
             debug: DebugSettings::default(),
             power: PowerSettings::default(),
             locale: LocaleSettings::default(),
+            accessibility: AccessibilitySettings::default(),
         }
     }
 }
@@ -1094,6 +1212,7 @@ pub const fn new() -> Self {
                 lockout_threshold: 3,
                 lockout_duration: 30,
                 auto_lock_secs: 0,
+                kaslr: true,
             },
             network: NetworkSettings {
                 mode: NetworkMode::Dhcp,
@@ -1106,6 +1225,9 @@ pub const fn new() -> Self {
                 gateway: [0, 0, 0, 0],
                 dns_server: [8, 8, 8, 8],
                 vlan_id: 0,
+                https_only: false,
+                pinned_ca: [[0; 32]; MAX_PINNED_CA],
+                pinned_ca_count: 0,
             },
             storage: StorageSettings {
                 scan_mode: StorageScanMode::All,
@@ -1159,6 +1281,13 @@ pub const fn new() -> Self {
                 date_format: 2,
                 timezone_offset: 0,
             },
+            accessibility: AccessibilitySettings {
+                high_contrast: false,
+                large_text: false,
+                nav_beeps: false,
+                key_repeat_delay_ms: 500,
+                key_repeat_rate_ms: 100,
+            },
         }
     }
 
@@ -1201,6 +1330,7 @@ pub enum SettingCategory {
     Debug,
     Power,
     Locale,
+    Accessibility,
 }
 
 // =============================================================================
@@ -1222,6 +1352,17 @@ fn test_settings_default() {
     fn test_settings_new() {
         let settings = Settings::new();
         assert!(settings.header.is_valid());
+        assert_eq!(settings.accessibility, AccessibilitySettings::default());
+    }
+
+    #[test]
+    fn test_key_repeat_clamped() {
+        let mut a11y = AccessibilitySettings::default();
+        assert_eq!(a11y.key_repeat(), (500, 100));
+
+        a11y.key_repeat_delay_ms = 0;
+        a11y.key_repeat_rate_ms = 60000;
+        assert_eq!(a11y.key_repeat(), (KEY_REPEAT_MIN_MS, KEY_REPEAT_MAX_MS));
     }
 
     #[test]
@@ -1234,6 +1375,10 @@ fn test_font_size() {
     fn test_language_code() {
         assert_eq!(LanguageCode::French.iso_code(), "fr");
         assert_eq!(LanguageCode::German.iso_code(), "de");
+        assert_eq!(LanguageCode::French.language(), Language::Fr);
+        assert_eq!(LanguageCode::Custom(*b"pl").language(), Language::Pl);
+        assert_eq!(LanguageCode::Custom(*b"??").language(), Language::En);
+        assert_eq!(LanguageCode::from_language(Language::Nl), LanguageCode::Custom(*b"nl"));
     }
 
     #[test]
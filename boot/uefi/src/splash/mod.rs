@@ -11,9 +11,15 @@
 //! - Animation system
 //! - Transition effects
 //! - Brand identity
+//! - Boot event consumption ([`SplashScreen::pump`]) and rendering to the
+//!   GOP framebuffer or the text console ([`render`])
 
 #![no_std]
 
+pub mod render;
+
+use super::events::{BootPhase, ErrorSeverity, Event, EventData, EventQueue, ProgressType};
+use super::handoff::SplashHandover;
 
 // =============================================================================
 // COLORS
@@ -553,6 +559,8 @@ pub struct SplashScreen {
     pub logo_size: Size,
     /// Logo data (if embedded)
     logo_data: Option<&'static [u8]>,
+    /// Activity spinner, next to the progress bar
+    pub spinner: Spinner,
 }
 
 impl Default for SplashScreen {
@@ -591,6 +599,7 @@ pub const fn new() -> Self {
             screen_size: Size::zero(),
             logo_size: Size::new(128, 128),
             logo_data: None,
+            spinner: Spinner::new(SpinnerStyle::CircleDots, 24),
         }
     }
 
@@ -599,6 +608,7 @@ pub fn init(&mut self, screen_size: Size) {
         self.screen_size = screen_size;
         self.state = SplashState::default();
         self.state.animating = true;
+        self.spinner.start();
     }
 
     /// Set configuration
@@ -638,6 +648,7 @@ pub fn update(&mut self, delta_ms: u64) {
             let frames_per_ms = delta_ms / self.config.animation_speed_ms as u64;
             for _ in 0..frames_per_ms {
                 self.state.advance_frame();
+                self.spinner.update();
             }
         }
     }
@@ -699,8 +710,69 @@ pub fn status_position(&self) -> Point {
     pub fn complete(&mut self) {
         self.state.progress = 100;
         self.state.animating = false;
+        self.spinner.stop();
         self.set_status("Boot complete");
     }
+
+    /// Apply a boot event
+    ///
+    /// Step progress sets the phase, progress bar and status text,
+    /// percentage progress only the bar; completing the boot or a critical
+    /// error stops the animation. Returns whether the event was relevant.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match &event.data {
+            EventData::Progress(progress) => {
+                match progress.progress_type {
+                    ProgressType::Steps => {
+                        let total = progress.total.min(u8::MAX as u64) as u8;
+                        let phase = progress.current.min(total as u64) as u8;
+                        self.set_phase(phase, total, progress.operation_str());
+                    }
+                    ProgressType::Percentage | ProgressType::Bytes => {
+                        self.set_progress(progress.percentage());
+                    }
+                    ProgressType::Indeterminate => {}
+                }
+                true
+            }
+            EventData::Boot(boot) if boot.to == BootPhase::BootComplete => {
+                self.complete();
+                true
+            }
+            EventData::Error(error) if error.severity >= ErrorSeverity::Critical => {
+                self.state.animating = false;
+                self.spinner.stop();
+                self.set_status("Boot failed");
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Apply every queued event, emptying `queue`
+    ///
+    /// Returns the number of events that changed the splash.
+    pub fn pump(&mut self, queue: &mut EventQueue) -> usize {
+        let mut applied = 0;
+        while let Some(event) = queue.pop() {
+            if self.handle_event(&event) {
+                applied += 1;
+            }
+        }
+        applied
+    }
+
+    /// Splash state for the kernel, which keeps it on screen
+    pub fn handover(&self) -> SplashHandover {
+        let rect = |r: Rect| (r.x.max(0) as u32, r.y.max(0) as u32, r.width, r.height);
+        SplashHandover {
+            visible: self.screen_size.width != 0,
+            progress: self.state.progress,
+            background: self.config.background.to_rgb32(),
+            logo: rect(self.logo_rect()),
+            progress_bar: if self.config.show_progress { rect(self.progress_rect()) } else { (0, 0, 0, 0) },
+        }
+    }
 }
 
 // =============================================================================
@@ -1203,4 +1275,46 @@ fn test_splash() {
         splash.set_status("Loading kernel...");
         assert_eq!(splash.status(), "Loading kernel...");
     }
+
+    #[test]
+    fn test_splash_handover() {
+        let mut splash = SplashScreen::new();
+        assert!(!splash.handover().visible);
+
+        splash.init(Size::new(1920, 1080));
+        splash.set_progress(40);
+        let handover = splash.handover();
+        assert!(handover.visible);
+        assert_eq!(handover.progress, 40);
+        assert_eq!(handover.background, brand::BACKGROUND.to_rgb32());
+        assert_eq!(handover.logo, (896, 476, 128, 128));
+        assert_eq!(handover.progress_bar, (576, 810, 768, 8));
+    }
+
+    #[test]
+    fn test_splash_events() {
+        use crate::events::ProgressEvent;
+
+        let mut splash = SplashScreen::new();
+        splash.init(Size::new(1920, 1080));
+        assert!(splash.spinner.active);
+
+        let mut queue = EventQueue::new();
+        queue.push(Event::boot_phase(0, BootPhase::FirmwareEntry, BootPhase::KernelLoading, 0));
+        queue.push(Event::progress(0, ProgressEvent::steps(12, 18, "Kernel Loading"), 0));
+        queue.push(Event::default());
+        assert_eq!(splash.pump(&mut queue), 1);
+        assert!(queue.is_empty());
+        assert_eq!((splash.state.phase, splash.state.total_phases), (12, 18));
+        assert_eq!(splash.state.progress, 66);
+        assert_eq!(splash.status(), "Kernel Loading");
+
+        splash.update(32);
+        assert_eq!(splash.spinner.frame, 2);
+
+        queue.push(Event::boot_phase(0, BootPhase::KernelEntry, BootPhase::BootComplete, 0));
+        assert_eq!(splash.pump(&mut queue), 1);
+        assert_eq!(splash.state.progress, 100);
+        assert!(!splash.spinner.active);
+    }
 }
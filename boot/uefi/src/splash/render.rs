@@ -0,0 +1,413 @@
+//! Splash Rendering
+//!
+//! Draws a [`SplashScreen`] on the GOP framebuffer, or as a single status
+//! line on the firmware text console when no GOP is available.
+//!
+//! On the framebuffer the background is painted once; each frame then
+//! redraws the progress bar, the spinner to its right and the phase text
+//! below it, so nothing else on screen flickers. Text uses the built-in
+//! 8x16 console font.
+//!
+//! The splash is driven by boot events: [`SplashRenderer::tick`] drains
+//! the orchestrator's event queue into the splash, advances the animation
+//! and presents a frame.
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use super::{Color, Point, ProgressStyle, Rect, Size, SplashScreen};
+use crate::error::Result;
+use crate::events::EventQueue;
+use crate::handoff::framebuffer::font::{get_glyph, FONT_HEIGHT, FONT_WIDTH};
+use crate::protocols::console::Console;
+use crate::protocols::graphics::{GraphicsOutput, Pixel};
+
+/// Bands in a gradient progress bar
+const GRADIENT_BANDS: u32 = 16;
+/// Stripe width in a striped progress bar
+const STRIPE_WIDTH: u32 = 8;
+/// Segments in a dotted progress bar
+const DOT_SEGMENTS: u32 = 20;
+/// Gap between the progress bar and the spinner
+const SPINNER_GAP: u32 = 16;
+/// Dots in the spinner ring
+const SPINNER_DOTS: usize = 8;
+/// Spinner dot positions on the unit circle, in thousandths, clockwise from
+/// the top
+const SPINNER_RING: [(i32, i32); SPINNER_DOTS] = [
+    (0, -1000), (707, -707), (1000, 0), (707, 707),
+    (0, 1000), (-707, 707), (-1000, 0), (-707, -707),
+];
+/// Width of the text console progress bar, in characters
+const TEXT_BAR_WIDTH: usize = 30;
+/// Width of the text console status field, in characters
+const TEXT_STATUS_WIDTH: usize = 40;
+
+// =============================================================================
+// CANVAS
+// =============================================================================
+
+/// Surface the graphical splash is drawn on
+pub trait SplashCanvas {
+    /// Fill `rect` with `color`
+    fn fill_rect(&mut self, rect: Rect, color: Color) -> Result<()>;
+}
+
+impl SplashCanvas for GraphicsOutput {
+    fn fill_rect(&mut self, rect: Rect, color: Color) -> Result<()> {
+        GraphicsOutput::fill_rect(
+            self,
+            rect.x.max(0) as u32,
+            rect.y.max(0) as u32,
+            rect.width,
+            rect.height,
+            Pixel::rgb(color.r, color.g, color.b),
+        )
+    }
+}
+
+/// Fill `rect`, skipping empty rectangles (which GOP rejects)
+fn fill(canvas: &mut dyn SplashCanvas, rect: Rect, color: Color) -> Result<()> {
+    if rect.width == 0 || rect.height == 0 {
+        return Ok(());
+    }
+    canvas.fill_rect(rect, color)
+}
+
+// =============================================================================
+// GRAPHICAL SPLASH
+// =============================================================================
+
+/// Draw the progress bar
+pub fn draw_progress(canvas: &mut dyn SplashCanvas, splash: &SplashScreen) -> Result<()> {
+    let config = &splash.config;
+    if !config.show_progress || config.progress_style == ProgressStyle::Spinner {
+        return Ok(());
+    }
+
+    let bar = splash.progress_rect();
+    let filled = bar.width * splash.state.progress as u32 / 100;
+    let rest = Rect::new(bar.x + filled as i32, bar.y, bar.width - filled, bar.height);
+    let span = |from: u32, to: u32| Rect::new(bar.x + from as i32, bar.y, to - from, bar.height);
+
+    match config.progress_style {
+        ProgressStyle::Gradient => {
+            // Lighten towards the leading edge
+            for band in 0..GRADIENT_BANDS {
+                let from = filled * band / GRADIENT_BANDS;
+                let to = filled * (band + 1) / GRADIENT_BANDS;
+                let color = config.progress_color.lighten((band * 40 / GRADIENT_BANDS) as u8);
+                fill(canvas, span(from, to), color)?;
+            }
+        }
+        ProgressStyle::Striped => {
+            // Stripes scroll with the animation frame
+            let period = 2 * STRIPE_WIDTH;
+            let shift = splash.state.frame % period;
+            let mut x = 0;
+            while x < filled {
+                let phase = (x + period - shift) % period;
+                let to = (x + STRIPE_WIDTH - phase % STRIPE_WIDTH).min(filled);
+                let color = if phase < STRIPE_WIDTH {
+                    config.progress_color
+                } else {
+                    config.progress_color.darken(25)
+                };
+                fill(canvas, span(x, to), color)?;
+                x = to;
+            }
+        }
+        ProgressStyle::Dots => {
+            fill(canvas, span(0, filled), config.progress_bg)?;
+            let lit = DOT_SEGMENTS * splash.state.progress as u32 / 100;
+            for segment in 0..lit {
+                let from = bar.width * segment / DOT_SEGMENTS;
+                let to = bar.width * (segment + 1) / DOT_SEGMENTS;
+                fill(canvas, span(from, to.saturating_sub(2).max(from)), config.progress_color)?;
+            }
+        }
+        ProgressStyle::Solid | ProgressStyle::Spinner => {
+            fill(canvas, span(0, filled), config.progress_color)?;
+        }
+    }
+
+    fill(canvas, rest, config.progress_bg)
+}
+
+/// Area of the spinner, right of the progress bar
+pub fn spinner_rect(splash: &SplashScreen) -> Rect {
+    let bar = splash.progress_rect();
+    let size = splash.spinner.size;
+    let y = bar.y + bar.height as i32 / 2 - size as i32 / 2;
+    Rect::new(bar.right() + SPINNER_GAP as i32, y, size, size)
+}
+
+/// Draw the spinner as a ring of dots, the head bright and its tail fading
+pub fn draw_spinner(canvas: &mut dyn SplashCanvas, splash: &SplashScreen) -> Result<()> {
+    let area = spinner_rect(splash);
+    let background = splash.config.background;
+    if !splash.spinner.active {
+        return fill(canvas, area, background);
+    }
+
+    let dot = (area.width / 6).max(2);
+    let radius = (area.width - dot) as i32 / 2;
+    let center = area.center();
+    let head = splash.spinner.rotation() as usize * SPINNER_DOTS / 360;
+
+    for (i, &(dx, dy)) in SPINNER_RING.iter().enumerate() {
+        // Dots behind the head fade towards the background
+        let behind = (head + SPINNER_DOTS - i) % SPINNER_DOTS;
+        let color = splash.spinner.color.blend(background, (behind * 255 / SPINNER_DOTS) as u8);
+        let x = center.x + dx * radius / 1000 - dot as i32 / 2;
+        let y = center.y + dy * radius / 1000 - dot as i32 / 2;
+        fill(canvas, Rect::new(x, y, dot, dot), color)?;
+    }
+    Ok(())
+}
+
+/// Draw `text` at `at` in the console font, clipped to `max_width` pixels
+pub fn draw_text(
+    canvas: &mut dyn SplashCanvas,
+    at: Point,
+    text: &str,
+    color: Color,
+    max_width: u32,
+) -> Result<()> {
+    let columns = (max_width / FONT_WIDTH) as usize;
+    for (i, c) in text.chars().take(columns).enumerate() {
+        let x = at.x + (i as u32 * FONT_WIDTH) as i32;
+        for (row, &bits) in get_glyph(c).iter().enumerate() {
+            // One rectangle per run of set pixels
+            let y = at.y + row as i32;
+            let mut col = 0;
+            while col < FONT_WIDTH {
+                if bits & (0x80 >> col) == 0 {
+                    col += 1;
+                    continue;
+                }
+                let start = col;
+                while col < FONT_WIDTH && bits & (0x80 >> col) != 0 {
+                    col += 1;
+                }
+                fill(canvas, Rect::new(x + start as i32, y, col - start, 1), color)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Draw the phase text below the progress bar
+pub fn draw_status(canvas: &mut dyn SplashCanvas, splash: &SplashScreen) -> Result<()> {
+    if !splash.config.show_status {
+        return Ok(());
+    }
+
+    let at = splash.status_position();
+    let width = splash.progress_rect().width;
+    fill(canvas, Rect::new(at.x, at.y, width, FONT_HEIGHT), splash.config.background)?;
+    draw_text(canvas, at, splash.status(), splash.config.status_color, width)
+}
+
+/// Draw one frame: progress bar, spinner and phase text
+pub fn draw_frame(canvas: &mut dyn SplashCanvas, splash: &SplashScreen) -> Result<()> {
+    draw_progress(canvas, splash)?;
+    draw_spinner(canvas, splash)?;
+    draw_status(canvas, splash)
+}
+
+// =============================================================================
+// TEXT SPLASH
+// =============================================================================
+
+/// Status line for the text console: spinner, bar, percentage and phase
+///
+/// Padded to a fixed width so a shorter phase name overwrites a longer
+/// one when the line is redrawn in place.
+pub fn text_line(splash: &SplashScreen) -> String {
+    const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+    let spinner = if splash.spinner.active {
+        FRAMES[splash.spinner.rotation() as usize * FRAMES.len() / 360]
+    } else {
+        ' '
+    };
+
+    let progress = splash.state.progress as usize;
+    let filled = TEXT_BAR_WIDTH * progress / 100;
+    let mut bar = String::with_capacity(TEXT_BAR_WIDTH);
+    for i in 0..TEXT_BAR_WIDTH {
+        bar.push(match i.cmp(&filled) {
+            core::cmp::Ordering::Less => '=',
+            core::cmp::Ordering::Equal => '>',
+            core::cmp::Ordering::Greater => ' ',
+        });
+    }
+
+    alloc::format!(
+        "{} [{}] {:>3}% {:<width$.width$}",
+        spinner,
+        bar,
+        progress,
+        splash.status(),
+        width = TEXT_STATUS_WIDTH,
+    )
+}
+
+// =============================================================================
+// RENDERER
+// =============================================================================
+
+/// Where the splash is shown
+pub enum SplashOutput {
+    /// GOP framebuffer
+    Graphics(GraphicsOutput),
+    /// Firmware text console, for systems without a GOP
+    Text(Console),
+}
+
+/// Presents a [`SplashScreen`] on a [`SplashOutput`]
+pub struct SplashRenderer {
+    /// Output
+    output: SplashOutput,
+    /// Background painted since the last attach
+    background_drawn: bool,
+}
+
+impl SplashRenderer {
+    /// Create renderer for `output`
+    pub const fn new(output: SplashOutput) -> Self {
+        Self {
+            output,
+            background_drawn: false,
+        }
+    }
+
+    /// Whether the splash is drawn on the framebuffer
+    pub const fn is_graphical(&self) -> bool {
+        matches!(self.output, SplashOutput::Graphics(_))
+    }
+
+    /// Start showing `splash`
+    ///
+    /// Sizes it to the current video mode. On the text console it is left
+    /// without a screen size, so the kernel is not told a splash is up.
+    pub fn attach(&mut self, splash: &mut SplashScreen) -> Result<()> {
+        let size = match &self.output {
+            SplashOutput::Graphics(gop) => {
+                let mode = gop.mode_info()?;
+                Size::new(mode.width, mode.height)
+            }
+            SplashOutput::Text(_) => Size::zero(),
+        };
+        splash.init(size);
+        self.background_drawn = false;
+        Ok(())
+    }
+
+    /// Draw the current state of `splash`
+    pub fn present(&mut self, splash: &SplashScreen) -> Result<()> {
+        match &mut self.output {
+            SplashOutput::Graphics(gop) => {
+                if !self.background_drawn {
+                    let screen = Rect::from_pos_size(Point::origin(), splash.screen_size);
+                    fill(gop, screen, splash.config.background)?;
+                    self.background_drawn = true;
+                }
+                draw_frame(gop, splash)
+            }
+            SplashOutput::Text(console) => {
+                console.write("\r")?;
+                console.write(&text_line(splash))
+            }
+        }
+    }
+
+    /// Apply queued boot events to `splash`, advance its animation by
+    /// `delta_ms` and present a frame
+    pub fn tick(&mut self, splash: &mut SplashScreen, events: &mut EventQueue, delta_ms: u64) -> Result<()> {
+        splash.pump(events);
+        splash.update(delta_ms);
+        self.present(splash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// Canvas recording every fill
+    #[derive(Default)]
+    struct Recorder(Vec<(Rect, Color)>);
+
+    impl SplashCanvas for Recorder {
+        fn fill_rect(&mut self, rect: Rect, color: Color) -> Result<()> {
+            self.0.push((rect, color));
+            Ok(())
+        }
+    }
+
+    fn splash(progress: u8) -> SplashScreen {
+        let mut splash = SplashScreen::new();
+        splash.init(Size::new(1920, 1080));
+        splash.config.progress_style = ProgressStyle::Solid;
+        splash.set_phase(0, 1, "Kernel Loading");
+        splash.set_progress(progress);
+        splash
+    }
+
+    #[test]
+    fn test_draw_progress() {
+        let splash = splash(25);
+        let mut canvas = Recorder::default();
+        draw_progress(&mut canvas, &splash).unwrap();
+        // Bar is 768x8 at (576, 810)
+        assert_eq!(canvas.0, [
+            (Rect::new(576, 810, 192, 8), splash.config.progress_color),
+            (Rect::new(768, 810, 576, 8), splash.config.progress_bg),
+        ]);
+
+        let mut canvas = Recorder::default();
+        draw_progress(&mut canvas, &self::splash(0)).unwrap();
+        assert_eq!(canvas.0.len(), 1);
+    }
+
+    #[test]
+    fn test_draw_spinner() {
+        let mut splash = splash(50);
+        let mut canvas = Recorder::default();
+        draw_spinner(&mut canvas, &splash).unwrap();
+        assert_eq!(canvas.0.len(), SPINNER_DOTS);
+        // The head is at the top and drawn in the spinner color
+        assert_eq!(canvas.0[0].1, splash.spinner.color);
+        assert!(canvas.0.iter().all(|(r, _)| spinner_rect(&splash).intersect(r).is_some()));
+
+        splash.complete();
+        let mut canvas = Recorder::default();
+        draw_spinner(&mut canvas, &splash).unwrap();
+        assert_eq!(canvas.0, [(spinner_rect(&splash), splash.config.background)]);
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let mut canvas = Recorder::default();
+        draw_text(&mut canvas, Point::new(0, 0), "  ", Color::WHITE, 100).unwrap();
+        assert!(canvas.0.is_empty());
+
+        draw_text(&mut canvas, Point::new(8, 0), "1234", Color::WHITE, 16).unwrap();
+        assert!(!canvas.0.is_empty());
+        assert!(canvas.0.iter().all(|(r, _)| r.x >= 8 && r.right() <= 24));
+    }
+
+    #[test]
+    fn test_text_line() {
+        let mut splash = splash(50);
+        let line = text_line(&splash);
+        assert!(line.starts_with("| [===============>              ]  50% Kernel Loading"));
+        assert_eq!(line.len(), TEXT_BAR_WIDTH + TEXT_STATUS_WIDTH + 10);
+
+        splash.complete();
+        assert!(text_line(&splash).starts_with("  [==============================] 100% Boot complete"));
+    }
+}
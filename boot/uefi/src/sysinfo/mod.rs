@@ -26,6 +26,7 @@
 #![no_std]
 
 use core::fmt;
+use crate::handoff::DisplayInfo;
 
 // =============================================================================
 // SYSTEM SUMMARY
@@ -169,6 +170,15 @@ impl CpuFeatures {
     pub const LONG_MODE: CpuFeatures = CpuFeatures(1 << 24);
     pub const PAE: CpuFeatures = CpuFeatures(1 << 25);
     pub const HYPERVISOR: CpuFeatures = CpuFeatures(1 << 26);
+    pub const POPCNT: CpuFeatures = CpuFeatures(1 << 27);
+    pub const CX16: CpuFeatures = CpuFeatures(1 << 28);
+    pub const LAHF_LM: CpuFeatures = CpuFeatures(1 << 29);
+    pub const FMA: CpuFeatures = CpuFeatures(1 << 30);
+    pub const BMI1: CpuFeatures = CpuFeatures(1 << 31);
+    pub const BMI2: CpuFeatures = CpuFeatures(1 << 32);
+    pub const LZCNT: CpuFeatures = CpuFeatures(1 << 33);
+    pub const MOVBE: CpuFeatures = CpuFeatures(1 << 34);
+    pub const F16C: CpuFeatures = CpuFeatures(1 << 35);
 
     /// Get raw value
     pub const fn raw(&self) -> u64 {
@@ -180,6 +190,21 @@ pub const fn has(&self, feature: CpuFeatures) -> bool {
         self.0 & feature.0 != 0
     }
 
+    /// Combine with another set
+    pub const fn with(self, other: CpuFeatures) -> Self {
+        CpuFeatures(self.0 | other.0)
+    }
+
+    /// Features in this set that `other` lacks
+    pub const fn missing_from(self, other: CpuFeatures) -> Self {
+        CpuFeatures(self.0 & !other.0)
+    }
+
+    /// Check if no feature is set
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
     /// Set feature
     pub fn set(&mut self, feature: CpuFeatures) {
         self.0 |= feature.0;
@@ -509,6 +534,8 @@ pub struct StorageSummary {
     pub boot_device_found: bool,
     /// Boot device index
     pub boot_device_index: u8,
+    /// Boot device type
+    pub boot_device_type: StorageType,
 }
 
 // =============================================================================
@@ -537,6 +564,61 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
+/// Firmware tables found in the configuration table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FirmwareTables(u16);
+
+impl FirmwareTables {
+    pub const NONE: FirmwareTables = FirmwareTables(0);
+    pub const ACPI: FirmwareTables = FirmwareTables(1 << 0);
+    pub const SMBIOS: FirmwareTables = FirmwareTables(1 << 1);
+    pub const MADT: FirmwareTables = FirmwareTables(1 << 2);
+    pub const FADT: FirmwareTables = FirmwareTables(1 << 3);
+    pub const HPET: FirmwareTables = FirmwareTables(1 << 4);
+    pub const MCFG: FirmwareTables = FirmwareTables(1 << 5);
+    pub const DEVICE_TREE: FirmwareTables = FirmwareTables(1 << 6);
+
+    /// All tables, in display order
+    pub const ALL: [FirmwareTables; 7] = [
+        Self::ACPI, Self::SMBIOS, Self::MADT, Self::FADT,
+        Self::HPET, Self::MCFG, Self::DEVICE_TREE,
+    ];
+
+    /// Get raw value
+    pub const fn raw(&self) -> u16 {
+        self.0
+    }
+
+    /// Combine with another set
+    pub const fn with(self, other: FirmwareTables) -> Self {
+        FirmwareTables(self.0 | other.0)
+    }
+
+    /// Check that every table in `tables` is present
+    pub const fn has(&self, tables: FirmwareTables) -> bool {
+        self.0 & tables.0 == tables.0
+    }
+
+    /// Set table
+    pub fn set(&mut self, tables: FirmwareTables) {
+        self.0 |= tables.0;
+    }
+
+    /// Name of a single table
+    pub const fn name(&self) -> &'static str {
+        match self.0 {
+            0x01 => "ACPI",
+            0x02 => "SMBIOS",
+            0x04 => "MADT",
+            0x08 => "FADT",
+            0x10 => "HPET",
+            0x20 => "MCFG",
+            0x40 => "Device Tree",
+            _ => "?",
+        }
+    }
+}
+
 /// UEFI version
 #[derive(Debug, Clone, Copy, Default)]
 pub struct UefiVersion {
@@ -597,6 +679,8 @@ pub struct FirmwareSummary {
     pub smbios_major: u8,
     /// SMBIOS version minor
     pub smbios_minor: u8,
+    /// Tables found
+    pub tables: FirmwareTables,
     /// Runtime services available
     pub runtime_services: bool,
     /// Boot services available
@@ -617,6 +701,7 @@ fn default() -> Self {
             acpi_version: 0,
             smbios_major: 0,
             smbios_minor: 0,
+            tables: FirmwareTables::NONE,
             runtime_services: false,
             boot_services: true,
         }
@@ -689,6 +774,8 @@ pub struct GraphicsSummary {
     pub console_cols: u32,
     /// Console rows
     pub console_rows: u32,
+    /// Attached display, if it reported EDID
+    pub display: Option<DisplayInfo>,
 }
 
 impl GraphicsSummary {
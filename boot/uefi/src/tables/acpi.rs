@@ -4,8 +4,11 @@
 
 use crate::raw::types::*;
 use crate::error::{Error, Result};
+use crate::debug::{EarlySerial, Pl011, SerialPort};
 
 extern crate alloc;
+use alloc::format;
+use alloc::string::String;
 use alloc::vec::Vec;
 use core::ptr;
 
@@ -35,6 +38,8 @@ pub struct AcpiParser {
     mcfg: Option<McfgInfo>,
     /// BGRT info
     bgrt: Option<BgrtInfo>,
+    /// SPCR info
+    spcr: Option<SpcrInfo>,
 }
 
 impl AcpiParser {
@@ -51,6 +56,7 @@ pub fn new() -> Self {
             hpet: None,
             mcfg: None,
             bgrt: None,
+            spcr: None,
         }
     }
 
@@ -97,6 +103,7 @@ pub unsafe fn init(&mut self, rsdp_address: PhysicalAddress) -> Result<()> {
         self.parse_hpet()?;
         self.parse_mcfg()?;
         self.parse_bgrt()?;
+        self.parse_spcr()?;
 
         Ok(())
     }
@@ -431,6 +438,31 @@ unsafe fn parse_bgrt(&mut self) -> Result<()> {
         Ok(())
     }
 
+    /// Parse SPCR (Serial Port Console Redirection)
+    unsafe fn parse_spcr(&mut self) -> Result<()> {
+        let spcr_entry = match self.find_table(b"SPCR") {
+            Some(e) => e.clone(),
+            None => return Ok(()),
+        };
+
+        // Too short to say where the port is
+        if (spcr_entry.length as usize) < core::mem::size_of::<SpcrTable>() {
+            return Ok(());
+        }
+        let spcr = &*(spcr_entry.address.0 as *const SpcrTable);
+
+        self.spcr = Some(SpcrInfo {
+            interface_type: spcr.interface_type,
+            base_address: spcr.base_address,
+            interrupt_type: spcr.interrupt_type,
+            irq: spcr.irq,
+            gsi: spcr.gsi,
+            baud_rate: spcr.baud_rate,
+        });
+
+        Ok(())
+    }
+
     /// Get FADT info
     pub fn fadt(&self) -> Option<&FadtInfo> {
         self.fadt.as_ref()
@@ -456,6 +488,11 @@ pub fn bgrt(&self) -> Option<&BgrtInfo> {
         self.bgrt.as_ref()
     }
 
+    /// Get SPCR info
+    pub fn spcr(&self) -> Option<&SpcrInfo> {
+        self.spcr.as_ref()
+    }
+
     /// Get CPU count from MADT
     pub fn cpu_count(&self) -> usize {
         self.madt.as_ref().map_or(0, |m| {
@@ -734,6 +771,19 @@ struct BgrtTable {
     image_offset_y: u32,
 }
 
+/// SPCR
+#[repr(C, packed)]
+struct SpcrTable {
+    header: SdtHeader,
+    interface_type: u8,
+    reserved: [u8; 3],
+    base_address: GenericAddress,
+    interrupt_type: u8,
+    irq: u8,
+    gsi: u32,
+    baud_rate: u8,
+}
+
 // =============================================================================
 // PARSED STRUCTURES
 // =============================================================================
@@ -1148,6 +1198,81 @@ pub struct BgrtInfo {
     pub image_offset_y: u32,
 }
 
+/// SPCR info (Serial Port Console Redirection)
+#[derive(Debug, Clone)]
+pub struct SpcrInfo {
+    /// Interface type (0 = 16550, 3 = PL011, 0x12 = 16550 per GAS, ...)
+    pub interface_type: u8,
+    /// Register block
+    pub base_address: GenericAddress,
+    /// Interrupt type bits (1 = PC-AT IRQ, 2 = I/O APIC, 8 = GIC, ...)
+    pub interrupt_type: u8,
+    /// PC-AT IRQ
+    pub irq: u8,
+    /// Global system interrupt
+    pub gsi: u32,
+    /// Configured baud rate code (0 = as is)
+    pub baud_rate: u8,
+}
+
+impl SpcrInfo {
+    /// 16550 compatible interface types
+    const NS16550: [u8; 3] = [0x00, 0x01, 0x12];
+    /// PL011 and its SBSA subsets
+    const PL011: [u8; 3] = [0x03, 0x0D, 0x0E];
+    /// RISC-V SBI console
+    const SBI: u8 = 0x15;
+
+    /// Baud rate firmware configured, if it says
+    pub fn baud(&self) -> Option<u32> {
+        match self.baud_rate {
+            3 => Some(9600),
+            4 => Some(19200),
+            6 => Some(57600),
+            7 => Some(115200),
+            _ => None,
+        }
+    }
+
+    /// The console as an `earlycon=` value
+    pub fn earlycon(&self) -> Option<String> {
+        let address = self.base_address.address;
+        let baud = self.baud().map(|b| format!(",{}", b)).unwrap_or_default();
+
+        if Self::NS16550.contains(&self.interface_type) {
+            match self.base_address.address_space {
+                0 if self.base_address.access_size == 3 => Some(format!("uart8250,mmio32,{:#x}{}", address, baud)),
+                0 => Some(format!("uart8250,mmio,{:#x}{}", address, baud)),
+                1 => Some(format!("uart8250,io,{:#x}{}", address, baud)),
+                _ => None,
+            }
+        } else if Self::PL011.contains(&self.interface_type) {
+            (self.base_address.address_space == 0).then(|| format!("pl011,{:#x}", address))
+        } else if self.interface_type == Self::SBI {
+            Some(String::from("sbi"))
+        } else {
+            None
+        }
+    }
+
+    /// The console as a serial port the loader's logger can drive
+    ///
+    /// Only I/O port 16550s and MMIO PL011s; MMIO 16550s are left to the
+    /// kernel.
+    pub fn serial(&self) -> Option<EarlySerial> {
+        let address = self.base_address.address;
+
+        if Self::NS16550.contains(&self.interface_type) && self.base_address.address_space == 1 {
+            let port = u16::try_from(address).ok()?;
+            Some(EarlySerial::Uart16550(SerialPort::new(port)))
+        } else if Self::PL011.contains(&self.interface_type) && self.base_address.address_space == 0 {
+            Some(EarlySerial::Pl011(Pl011::new(address)))
+        } else {
+            None
+        }
+    }
+}
+
 impl BgrtInfo {
     /// Check if image is displayed
     pub fn is_displayed(&self) -> bool {
@@ -1158,6 +1283,10 @@ pub fn is_displayed(&self) -> bool {
     pub fn is_bmp(&self) -> bool {
         self.image_type == 0
     }
+    /// Screen orientation offset, degrees clockwise (status bits 1-2, ACPI 6.2+)
+    pub fn orientation(&self) -> u16 {
+        ((self.status >> 1) & 0x3) as u16 * 90
+    }
 }
 
 // =============================================================================
@@ -1179,6 +1308,20 @@ fn test_local_apic() {
         assert!(!apic.is_online_capable());
     }
 
+    #[test]
+    fn test_bgrt_orientation() {
+        let bgrt = BgrtInfo {
+            version: 1,
+            status: 0b011,
+            image_type: 0,
+            image_address: 0,
+            image_offset_x: 0,
+            image_offset_y: 0,
+        };
+        assert!(bgrt.is_displayed());
+        assert_eq!(bgrt.orientation(), 90);
+    }
+
     #[test]
     fn test_interrupt_override() {
         let ovr = InterruptOverride {
@@ -1201,4 +1344,66 @@ fn test_pcie_segment() {
         };
         assert_eq!(seg.config_address(0, 0, 0, 0), 0xE000_0000);
     }
+
+    #[test]
+    fn test_spcr_earlycon() {
+        let address = |space, access_size, address| GenericAddress {
+            address_space: space,
+            bit_width: 8,
+            bit_offset: 0,
+            access_size,
+            address,
+        };
+        let mut spcr = SpcrInfo {
+            interface_type: 0,
+            base_address: address(1, 1, 0x3F8),
+            interrupt_type: 1,
+            irq: 4,
+            gsi: 0,
+            baud_rate: 7,
+        };
+        assert_eq!(spcr.earlycon().as_deref(), Some("uart8250,io,0x3f8,115200"));
+
+        spcr.interface_type = 0x12;
+        spcr.base_address = address(0, 3, 0xFE21_5040);
+        spcr.baud_rate = 0;
+        assert_eq!(spcr.earlycon().as_deref(), Some("uart8250,mmio32,0xfe215040"));
+
+        spcr.interface_type = 3;
+        spcr.base_address = address(0, 3, 0x0900_0000);
+        assert_eq!(spcr.earlycon().as_deref(), Some("pl011,0x9000000"));
+
+        spcr.interface_type = 0x15;
+        assert_eq!(spcr.earlycon().as_deref(), Some("sbi"));
+
+        spcr.interface_type = 0x02;
+        assert_eq!(spcr.earlycon(), None);
+    }
+
+    #[test]
+    fn test_spcr_serial() {
+        let mut spcr = SpcrInfo {
+            interface_type: 3,
+            base_address: GenericAddress {
+                address_space: 0,
+                bit_width: 32,
+                bit_offset: 0,
+                access_size: 3,
+                address: 0x0900_0000,
+            },
+            interrupt_type: 8,
+            irq: 0,
+            gsi: 33,
+            baud_rate: 0,
+        };
+        assert!(matches!(spcr.serial(), Some(EarlySerial::Pl011(uart)) if uart.base() == 0x0900_0000));
+
+        // MMIO 16550s are not driven by the loader
+        spcr.interface_type = 0x12;
+        assert!(spcr.serial().is_none());
+
+        spcr.base_address.address_space = 1;
+        spcr.base_address.address = 0x2F8;
+        assert!(matches!(spcr.serial(), Some(EarlySerial::Uart16550(_))));
+    }
 }
@@ -0,0 +1,706 @@
+//! Flattened Device Tree
+//!
+//! Validation, parsing and serialization of FDT blobs (DTB), and
+//! application of device tree overlays (DTBO) so the kernel receives one
+//! merged tree.
+//!
+//! Overlays follow the dtc `-@` format: `fragment@N` nodes carrying a
+//! `target` phandle or `target-path` and an `__overlay__` node to merge,
+//! `__fixups__` for references to labels of the base tree and
+//! `__local_fixups__` for references within the overlay.
+
+use crate::error::{Error, Result};
+use crate::raw::types::PhysicalAddress;
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// FDT magic
+pub const FDT_MAGIC: u32 = 0xD00D_FEED;
+
+/// Version written by [`DeviceTree::to_bytes`]
+pub const FDT_VERSION: u32 = 17;
+
+/// Oldest version that can read what we write
+pub const FDT_LAST_COMP_VERSION: u32 = 16;
+
+/// Largest blob accepted from firmware
+pub const MAX_DTB_SIZE: usize = 64 * 1024 * 1024;
+
+/// Deepest node nesting accepted
+const MAX_DEPTH: usize = 64;
+
+/// Structure block tokens
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+const FDT_END: u32 = 9;
+
+fn be32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn be64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(((be32(data, offset)? as u64) << 32) | be32(data, offset + 4)? as u64)
+}
+
+fn align4(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+// =============================================================================
+// HEADER
+// =============================================================================
+
+/// FDT header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FdtHeader {
+    /// Size of the whole blob
+    pub totalsize: u32,
+    /// Structure block offset
+    pub off_dt_struct: u32,
+    /// Strings block offset
+    pub off_dt_strings: u32,
+    /// Memory reservation block offset
+    pub off_mem_rsvmap: u32,
+    /// Format version
+    pub version: u32,
+    /// Oldest compatible version
+    pub last_comp_version: u32,
+    /// Boot CPU
+    pub boot_cpuid_phys: u32,
+    /// Strings block size
+    pub size_dt_strings: u32,
+    /// Structure block size
+    pub size_dt_struct: u32,
+}
+
+impl FdtHeader {
+    /// Header size (version 17)
+    pub const SIZE: usize = 40;
+
+    /// Parse and validate the header at the start of `data`
+    ///
+    /// `data` may hold only the header; the block offsets are checked
+    /// against `totalsize`.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let field = |index: usize| be32(data, index * 4).ok_or(Error::BufferTooSmall);
+        if field(0)? != FDT_MAGIC {
+            return Err(Error::InvalidMagic);
+        }
+
+        let mut header = Self {
+            totalsize: field(1)?,
+            off_dt_struct: field(2)?,
+            off_dt_strings: field(3)?,
+            off_mem_rsvmap: field(4)?,
+            version: field(5)?,
+            last_comp_version: field(6)?,
+            boot_cpuid_phys: field(7)?,
+            size_dt_strings: field(8)?,
+            size_dt_struct: 0,
+        };
+        if header.version < FDT_LAST_COMP_VERSION || header.last_comp_version > FDT_VERSION {
+            return Err(Error::IncompatibleVersion);
+        }
+        // Version 16 has no structure block size
+        header.size_dt_struct = if header.version >= 17 {
+            field(9)?
+        } else {
+            header.totalsize.saturating_sub(header.off_dt_struct)
+        };
+
+        let total = header.totalsize as u64;
+        let within = |offset: u32, size: u32| {
+            offset as usize >= 36 && offset as u64 + size as u64 <= total
+        };
+        if total as usize > MAX_DTB_SIZE
+            || !within(header.off_mem_rsvmap, 16)
+            || !within(header.off_dt_struct, header.size_dt_struct)
+            || !within(header.off_dt_strings, header.size_dt_strings)
+            || header.off_mem_rsvmap % 8 != 0
+            || header.off_dt_struct % 4 != 0
+        {
+            return Err(Error::InvalidData);
+        }
+        Ok(header)
+    }
+}
+
+/// The blob firmware installed at `address`
+///
+/// Only the header is validated; parse the result with
+/// [`DeviceTree::parse`] before trusting it.
+///
+/// # Safety
+/// `address` must point to readable memory holding an FDT header, and
+/// `totalsize` bytes must be readable if the header is valid.
+pub unsafe fn blob_at(address: PhysicalAddress) -> Result<&'static [u8]> {
+    let header = core::slice::from_raw_parts(address.0 as *const u8, FdtHeader::SIZE);
+    let header = FdtHeader::parse(header)?;
+    Ok(core::slice::from_raw_parts(address.0 as *const u8, header.totalsize as usize))
+}
+
+// =============================================================================
+// NODES
+// =============================================================================
+
+/// Device tree property
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    /// Name
+    pub name: String,
+    /// Raw (big-endian) value
+    pub value: Vec<u8>,
+}
+
+/// Device tree node
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Node {
+    /// Name with unit address (empty for the root)
+    pub name: String,
+    /// Properties
+    pub properties: Vec<Property>,
+    /// Child nodes
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    /// Create an empty node
+    pub fn new(name: &str) -> Self {
+        Self { name: String::from(name), ..Self::default() }
+    }
+
+    /// Raw property value
+    pub fn property(&self, name: &str) -> Option<&[u8]> {
+        self.properties.iter().find(|p| p.name == name).map(|p| p.value.as_slice())
+    }
+
+    /// Raw property value (mutable)
+    pub fn property_mut(&mut self, name: &str) -> Option<&mut Vec<u8>> {
+        self.properties.iter_mut().find(|p| p.name == name).map(|p| &mut p.value)
+    }
+
+    /// Property holding a single cell
+    pub fn property_u32(&self, name: &str) -> Option<u32> {
+        self.property(name).filter(|v| v.len() == 4).and_then(|v| be32(v, 0))
+    }
+
+    /// Property holding a string
+    pub fn property_str(&self, name: &str) -> Option<&str> {
+        let value = self.property(name)?;
+        let value = value.strip_suffix(&[0]).unwrap_or(value);
+        core::str::from_utf8(value).ok()
+    }
+
+    /// Add or replace a property
+    pub fn set_property(&mut self, name: &str, value: Vec<u8>) {
+        match self.property_mut(name) {
+            Some(existing) => *existing = value,
+            None => self.properties.push(Property { name: String::from(name), value }),
+        }
+    }
+
+    /// Child by name; without a unit address in `name` the first node
+    /// of that name matches
+    pub fn child(&self, name: &str) -> Option<&Node> {
+        self.children.iter().find(|c| c.name == name).or_else(|| {
+            self.children.iter().find(|c| !name.contains('@') && c.base_name() == name)
+        })
+    }
+
+    /// Child by name (mutable)
+    pub fn child_mut(&mut self, name: &str) -> Option<&mut Node> {
+        let index = self.children.iter().position(|c| c.name == name).or_else(|| {
+            self.children.iter().position(|c| !name.contains('@') && c.base_name() == name)
+        })?;
+        Some(&mut self.children[index])
+    }
+
+    /// Name without the unit address
+    pub fn base_name(&self) -> &str {
+        self.name.split('@').next().unwrap_or("")
+    }
+
+    /// The node's phandle
+    pub fn phandle(&self) -> Option<u32> {
+        self.property_u32("phandle").or_else(|| self.property_u32("linux,phandle"))
+    }
+
+    /// Largest phandle in this subtree
+    fn max_phandle(&self) -> u32 {
+        self.children.iter().map(Node::max_phandle).fold(self.phandle().unwrap_or(0), u32::max)
+    }
+
+    /// Path (below `prefix`) of the node with `phandle`
+    fn path_of(&self, phandle: u32, prefix: &str) -> Option<String> {
+        for child in &self.children {
+            let path = alloc::format!("{}/{}", prefix, child.name);
+            if child.phandle() == Some(phandle) {
+                return Some(path);
+            }
+            if let Some(path) = child.path_of(phandle, &path) {
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    /// Add `delta` to every phandle in this subtree
+    fn shift_phandles(&mut self, delta: u32) {
+        for property in &mut self.properties {
+            if (property.name == "phandle" || property.name == "linux,phandle") && property.value.len() == 4 {
+                let phandle = be32(&property.value, 0).unwrap_or(0);
+                property.value = phandle.wrapping_add(delta).to_be_bytes().to_vec();
+            }
+        }
+        for child in &mut self.children {
+            child.shift_phandles(delta);
+        }
+    }
+
+    /// Add `delta` to the cells listed by the mirroring `__local_fixups__`
+    /// node `fixups`
+    fn apply_local_fixups(&mut self, fixups: &Node, delta: u32) -> Result<()> {
+        for fixup in &fixups.properties {
+            let value = self.property_mut(&fixup.name).ok_or(Error::NotFound)?;
+            for offset in fixup.value.chunks_exact(4) {
+                let offset = u32::from_be_bytes([offset[0], offset[1], offset[2], offset[3]]) as usize;
+                let cell = be32(value, offset).ok_or(Error::OutOfBounds)?;
+                value[offset..offset + 4].copy_from_slice(&cell.wrapping_add(delta).to_be_bytes());
+            }
+        }
+        for child in &fixups.children {
+            self.child_mut(&child.name).ok_or(Error::NotFound)?.apply_local_fixups(child, delta)?;
+        }
+        Ok(())
+    }
+
+    /// Merge `other` into this node: its properties replace ours, its
+    /// children are merged into ours of the same name
+    pub fn merge(&mut self, other: &Node) {
+        for property in &other.properties {
+            self.set_property(&property.name, property.value.clone());
+        }
+        for child in &other.children {
+            match self.children.iter_mut().find(|c| c.name == child.name) {
+                Some(existing) => existing.merge(child),
+                None => self.children.push(child.clone()),
+            }
+        }
+    }
+}
+
+// =============================================================================
+// DEVICE TREE
+// =============================================================================
+
+/// Parsed device tree
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceTree {
+    /// Memory reservations (address, size)
+    pub reserved: Vec<(u64, u64)>,
+    /// Boot CPU
+    pub boot_cpuid: u32,
+    /// Root node
+    pub root: Node,
+}
+
+/// Structure block reader
+struct Cursor<'a> {
+    data: &'a [u8],
+    strings: &'a [u8],
+    offset: usize,
+}
+
+impl Cursor<'_> {
+    fn token(&mut self) -> Result<u32> {
+        loop {
+            let token = be32(self.data, self.offset).ok_or(Error::InvalidData)?;
+            self.offset += 4;
+            if token != FDT_NOP {
+                return Ok(token);
+            }
+        }
+    }
+
+    fn cstr(data: &[u8], offset: usize) -> Result<&str> {
+        let rest = data.get(offset..).ok_or(Error::InvalidData)?;
+        let len = rest.iter().position(|&b| b == 0).ok_or(Error::InvalidData)?;
+        core::str::from_utf8(&rest[..len]).map_err(|_| Error::InvalidData)
+    }
+
+    /// Node body after its `FDT_BEGIN_NODE`
+    fn node(&mut self, depth: usize) -> Result<Node> {
+        if depth > MAX_DEPTH {
+            return Err(Error::InvalidData);
+        }
+        let name = Self::cstr(self.data, self.offset)?;
+        self.offset = align4(self.offset + name.len() + 1);
+        let mut node = Node::new(name);
+
+        loop {
+            match self.token()? {
+                FDT_PROP => {
+                    let len = be32(self.data, self.offset).ok_or(Error::InvalidData)? as usize;
+                    let name_offset = be32(self.data, self.offset + 4).ok_or(Error::InvalidData)? as usize;
+                    let start = self.offset + 8;
+                    let value = self.data.get(start..start + len).ok_or(Error::InvalidData)?;
+                    node.properties.push(Property {
+                        name: String::from(Self::cstr(self.strings, name_offset)?),
+                        value: value.to_vec(),
+                    });
+                    self.offset = align4(start + len);
+                }
+                FDT_BEGIN_NODE => node.children.push(self.node(depth + 1)?),
+                FDT_END_NODE => return Ok(node),
+                _ => return Err(Error::InvalidData),
+            }
+        }
+    }
+}
+
+impl DeviceTree {
+    /// Parse and validate a blob
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let header = FdtHeader::parse(data)?;
+        if data.len() < header.totalsize as usize {
+            return Err(Error::BufferTooSmall);
+        }
+
+        let mut reserved = Vec::new();
+        let mut offset = header.off_mem_rsvmap as usize;
+        loop {
+            let address = be64(data, offset).ok_or(Error::InvalidData)?;
+            let size = be64(data, offset + 8).ok_or(Error::InvalidData)?;
+            if address == 0 && size == 0 {
+                break;
+            }
+            reserved.push((address, size));
+            offset += 16;
+        }
+
+        let structure = header.off_dt_struct as usize;
+        let strings = header.off_dt_strings as usize;
+        let mut cursor = Cursor {
+            data: &data[structure..structure + header.size_dt_struct as usize],
+            strings: &data[strings..strings + header.size_dt_strings as usize],
+            offset: 0,
+        };
+        if cursor.token()? != FDT_BEGIN_NODE {
+            return Err(Error::InvalidData);
+        }
+        let root = cursor.node(0)?;
+        if cursor.token()? != FDT_END {
+            return Err(Error::InvalidData);
+        }
+
+        Ok(Self { reserved, boot_cpuid: header.boot_cpuid_phys, root })
+    }
+
+    /// Serialize to a version 17 blob
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut rsvmap = Vec::new();
+        for &(address, size) in self.reserved.iter().chain(core::iter::once(&(0, 0))) {
+            rsvmap.extend_from_slice(&address.to_be_bytes());
+            rsvmap.extend_from_slice(&size.to_be_bytes());
+        }
+
+        let mut structure = Vec::new();
+        let mut strings = Vec::new();
+        write_node(&self.root, &mut structure, &mut strings);
+        structure.extend_from_slice(&FDT_END.to_be_bytes());
+
+        let off_mem_rsvmap = FdtHeader::SIZE;
+        let off_dt_struct = off_mem_rsvmap + rsvmap.len();
+        let off_dt_strings = off_dt_struct + structure.len();
+        let totalsize = off_dt_strings + strings.len();
+
+        let mut blob = Vec::with_capacity(totalsize);
+        for field in [
+            FDT_MAGIC,
+            totalsize as u32,
+            off_dt_struct as u32,
+            off_dt_strings as u32,
+            off_mem_rsvmap as u32,
+            FDT_VERSION,
+            FDT_LAST_COMP_VERSION,
+            self.boot_cpuid,
+            strings.len() as u32,
+            structure.len() as u32,
+        ] {
+            blob.extend_from_slice(&field.to_be_bytes());
+        }
+        blob.extend_from_slice(&rsvmap);
+        blob.extend_from_slice(&structure);
+        blob.extend_from_slice(&strings);
+        blob
+    }
+
+    /// Node at `path`; a path not starting with `/` begins with an alias
+    pub fn node(&self, path: &str) -> Option<&Node> {
+        let path = self.resolve_alias(path)?;
+        path.split('/').filter(|c| !c.is_empty()).try_fold(&self.root, |node, name| node.child(name))
+    }
+
+    /// Node at `path` (mutable)
+    pub fn node_mut(&mut self, path: &str) -> Option<&mut Node> {
+        let path = self.resolve_alias(path)?;
+        path.split('/').filter(|c| !c.is_empty()).try_fold(&mut self.root, |node, name| node.child_mut(name))
+    }
+
+    /// Expand a leading alias in `path`
+    fn resolve_alias(&self, path: &str) -> Option<String> {
+        if path.starts_with('/') {
+            return Some(String::from(path));
+        }
+        let (alias, rest) = path.split_once('/').unwrap_or((path, ""));
+        let target = self.root.child("aliases")?.property_str(alias)?;
+        Some(alloc::format!("{}/{}", target, rest))
+    }
+
+    /// Path of the node labelled `label` (from `__symbols__`)
+    pub fn symbol(&self, label: &str) -> Option<&str> {
+        self.root.child("__symbols__")?.property_str(label)
+    }
+
+    /// Path of the node with `phandle`
+    pub fn path_of_phandle(&self, phandle: u32) -> Option<String> {
+        if self.root.phandle() == Some(phandle) {
+            return Some(String::from("/"));
+        }
+        self.root.path_of(phandle, "")
+    }
+
+    /// Apply an overlay compiled with `dtc -@`
+    pub fn apply_overlay(&mut self, overlay: &DeviceTree) -> Result<()> {
+        let mut overlay = overlay.clone();
+
+        // Move the overlay's own phandles past ours
+        let delta = self.root.max_phandle();
+        overlay.root.shift_phandles(delta);
+        if let Some(fixups) = overlay.root.child("__local_fixups__").cloned() {
+            overlay.root.apply_local_fixups(&fixups, delta)?;
+        }
+
+        // Point references to our labels at our phandles
+        let mut next_phandle = self.root.max_phandle().max(overlay.root.max_phandle()) + 1;
+        if let Some(fixups) = overlay.root.child("__fixups__").cloned() {
+            for label in &fixups.properties {
+                let path = String::from(self.symbol(&label.name).ok_or(Error::NotFound)?);
+                let target = self.node_mut(&path).ok_or(Error::NotFound)?;
+                let phandle = match target.phandle() {
+                    Some(phandle) => phandle,
+                    None => {
+                        target.set_property("phandle", next_phandle.to_be_bytes().to_vec());
+                        next_phandle += 1;
+                        next_phandle - 1
+                    }
+                };
+
+                for fixup in label.value.split(|&b| b == 0).filter(|f| !f.is_empty()) {
+                    let fixup = core::str::from_utf8(fixup).map_err(|_| Error::InvalidData)?;
+                    let mut parts = fixup.rsplitn(3, ':');
+                    let (offset, property, path) = match (parts.next(), parts.next(), parts.next()) {
+                        (Some(offset), Some(property), Some(path)) => (offset, property, path),
+                        _ => return Err(Error::InvalidData),
+                    };
+                    let offset: usize = offset.parse().map_err(|_| Error::InvalidData)?;
+                    let value = overlay.node_mut(path)
+                        .and_then(|node| node.property_mut(property))
+                        .ok_or(Error::NotFound)?;
+                    value.get_mut(offset..offset + 4)
+                        .ok_or(Error::OutOfBounds)?
+                        .copy_from_slice(&phandle.to_be_bytes());
+                }
+            }
+        }
+
+        // Merge the fragments
+        let mut targets = Vec::new();
+        for fragment in &overlay.root.children {
+            let Some(content) = fragment.child("__overlay__") else {
+                continue;
+            };
+            let target = match (fragment.property_u32("target"), fragment.property_str("target-path")) {
+                (Some(phandle), _) => self.path_of_phandle(phandle).ok_or(Error::NotFound)?,
+                (None, Some(path)) => self.resolve_alias(path).ok_or(Error::NotFound)?,
+                (None, None) => return Err(Error::InvalidData),
+            };
+            self.node_mut(&target).ok_or(Error::NotFound)?.merge(content);
+            targets.push((fragment.name.as_str(), target));
+        }
+
+        // Labels defined by the overlay now live under the targets
+        if let Some(symbols) = overlay.root.child("__symbols__") {
+            for symbol in &symbols.properties {
+                let Some(path) = symbol.value.strip_suffix(&[0]).and_then(|v| core::str::from_utf8(v).ok()) else {
+                    continue;
+                };
+                let mut parts = path.trim_start_matches('/').splitn(3, '/');
+                let (Some(fragment), Some("__overlay__")) = (parts.next(), parts.next()) else {
+                    continue;
+                };
+                let Some((_, target)) = targets.iter().find(|(name, _)| *name == fragment) else {
+                    continue;
+                };
+                let mut path = String::from(target.trim_end_matches('/'));
+                if let Some(rest) = parts.next() {
+                    path.push('/');
+                    path.push_str(rest);
+                }
+                path.push('\0');
+
+                if self.root.child("__symbols__").is_none() {
+                    self.root.children.push(Node::new("__symbols__"));
+                }
+                if let Some(ours) = self.root.child_mut("__symbols__") {
+                    ours.set_property(&symbol.name, path.into_bytes());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Append `node` to the structure block, interning property names
+fn write_node(node: &Node, structure: &mut Vec<u8>, strings: &mut Vec<u8>) {
+    structure.extend_from_slice(&FDT_BEGIN_NODE.to_be_bytes());
+    structure.extend_from_slice(node.name.as_bytes());
+    structure.push(0);
+    structure.resize(align4(structure.len()), 0);
+
+    for property in &node.properties {
+        let name_offset = intern(strings, &property.name);
+        structure.extend_from_slice(&FDT_PROP.to_be_bytes());
+        structure.extend_from_slice(&(property.value.len() as u32).to_be_bytes());
+        structure.extend_from_slice(&(name_offset as u32).to_be_bytes());
+        structure.extend_from_slice(&property.value);
+        structure.resize(align4(structure.len()), 0);
+    }
+    for child in &node.children {
+        write_node(child, structure, strings);
+    }
+    structure.extend_from_slice(&FDT_END_NODE.to_be_bytes());
+}
+
+/// Offset of `name` in the strings block, adding it if missing
+fn intern(strings: &mut Vec<u8>, name: &str) -> usize {
+    let mut offset = 0;
+    for existing in strings.split(|&b| b == 0) {
+        if existing == name.as_bytes() && offset < strings.len() {
+            return offset;
+        }
+        offset += existing.len() + 1;
+    }
+    let offset = strings.len();
+    strings.extend_from_slice(name.as_bytes());
+    strings.push(0);
+    offset
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut value = s.as_bytes().to_vec();
+        value.push(0);
+        value
+    }
+
+    fn base() -> DeviceTree {
+        let mut uart = Node::new("serial@9000000");
+        uart.set_property("compatible", string("arm,pl011"));
+        uart.set_property("phandle", cells(&[1]));
+        let mut soc = Node::new("soc");
+        soc.children.push(uart);
+        let mut symbols = Node::new("__symbols__");
+        symbols.set_property("uart0", string("/soc/serial@9000000"));
+
+        let mut root = Node::new("");
+        root.set_property("#address-cells", cells(&[2]));
+        root.children.push(soc);
+        root.children.push(symbols);
+        DeviceTree { reserved: alloc::vec![(0x4000_0000, 0x1000)], boot_cpuid: 0, root }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tree = base();
+        let blob = tree.to_bytes();
+        let header = FdtHeader::parse(&blob).unwrap();
+        assert_eq!(header.totalsize as usize, blob.len());
+        assert_eq!(DeviceTree::parse(&blob).unwrap(), tree);
+        assert_eq!(tree.node("/soc/serial").unwrap().property_str("compatible"), Some("arm,pl011"));
+    }
+
+    #[test]
+    fn test_invalid_blob() {
+        let mut blob = base().to_bytes();
+        assert_eq!(DeviceTree::parse(&blob[..blob.len() - 1]), Err(Error::BufferTooSmall));
+        blob[0] = 0;
+        assert_eq!(FdtHeader::parse(&blob), Err(Error::InvalidMagic));
+    }
+
+    #[test]
+    fn test_apply_overlay() {
+        // &uart0 { status = "okay"; }; / { led: led { gpios = <&led>; }; };
+        let mut fragment0 = Node::new("fragment@0");
+        fragment0.set_property("target", cells(&[0xFFFF_FFFF]));
+        let mut content = Node::new("__overlay__");
+        content.set_property("status", string("okay"));
+        fragment0.children.push(content);
+
+        let mut fragment1 = Node::new("fragment@1");
+        fragment1.set_property("target-path", string("/"));
+        let mut led = Node::new("led");
+        led.set_property("phandle", cells(&[1]));
+        led.set_property("gpios", cells(&[1, 7]));
+        let mut content = Node::new("__overlay__");
+        content.children.push(led);
+        fragment1.children.push(content);
+
+        let mut fixups = Node::new("__fixups__");
+        fixups.set_property("uart0", string("/fragment@0:target:0"));
+        let mut local = Node::new("__local_fixups__");
+        let mut led_fixup = Node::new("led");
+        led_fixup.set_property("gpios", cells(&[0]));
+        let mut overlay_fixup = Node::new("__overlay__");
+        overlay_fixup.children.push(led_fixup);
+        let mut fragment_fixup = Node::new("fragment@1");
+        fragment_fixup.children.push(overlay_fixup);
+        local.children.push(fragment_fixup);
+        let mut symbols = Node::new("__symbols__");
+        symbols.set_property("led", string("/fragment@1/__overlay__/led"));
+
+        let mut root = Node::new("");
+        root.children.extend([fragment0, fragment1, fixups, local, symbols]);
+        let overlay = DeviceTree::parse(&DeviceTree { root, ..DeviceTree::default() }.to_bytes()).unwrap();
+
+        let mut tree = base();
+        tree.apply_overlay(&overlay).unwrap();
+        assert_eq!(tree.node("/soc/serial@9000000").unwrap().property_str("status"), Some("okay"));
+        let led = tree.node("/led").unwrap();
+        assert_eq!(led.phandle(), Some(2));
+        assert_eq!(led.property("gpios"), Some(cells(&[2, 7]).as_slice()));
+        assert_eq!(tree.symbol("led"), Some("/led"));
+        assert!(DeviceTree::parse(&tree.to_bytes()).is_ok());
+    }
+}
@@ -3,6 +3,10 @@
 //! This module provides comprehensive terminal emulation support with
 //! ANSI/VT100 escape sequences, Unicode rendering, and advanced console features.
 //!
+//! Output is decoded as UTF-8 ([`Utf8Decoder`]) and laid out by
+//! [`put_char`]: wide (CJK, emoji) characters take two cells, combining
+//! marks are composed into the character before them.
+//!
 //! # Architecture
 //!
 //! ```text
@@ -35,6 +39,8 @@
 
 #![no_std]
 
+use crate::locale;
+
 
 // =============================================================================
 // ANSI ESCAPE SEQUENCES
@@ -304,7 +310,8 @@ pub struct Cell {
     pub ch: char,
     /// Text attributes
     pub attr: TextAttributes,
-    /// Character width (1 for normal, 2 for wide)
+    /// Character width (1 for normal, 2 for wide, 0 for the right half
+    /// of a wide character)
     pub width: u8,
 }
 
@@ -316,6 +323,18 @@ impl Cell {
         width: 1,
     };
 
+    /// Right half of a wide character
+    pub const CONTINUATION: Self = Self {
+        ch: ' ',
+        attr: TextAttributes::DEFAULT,
+        width: 0,
+    };
+
+    /// Check if this is the right half of a wide character
+    pub const fn is_continuation(&self) -> bool {
+        self.width == 0
+    }
+
     /// Create new cell with character
     pub const fn new(ch: char) -> Self {
         Self {
@@ -332,6 +351,144 @@ fn default() -> Self {
     }
 }
 
+/// Write `ch` into `row` at column `col`, returning the column after it
+///
+/// Wide characters take two cells, the second a continuation; halves of a
+/// wide character that get overwritten are blanked. Combining marks are
+/// composed into the character before `col` when a precomposed form
+/// exists and dropped otherwise, as are other zero-width characters.
+/// Returns `None` if the character does not fit before the end of the
+/// row, for the caller to wrap.
+pub fn put_char(row: &mut [Cell], col: usize, ch: char, attr: TextAttributes) -> Option<usize> {
+    let width = locale::char_width(ch);
+    if width == 0 {
+        if locale::is_combining(ch) {
+            let base = match col.checked_sub(1) {
+                Some(prev) if row[prev].is_continuation() => prev.checked_sub(1),
+                prev => prev,
+            };
+            if let Some(cell) = base.and_then(|base| row.get_mut(base)) {
+                cell.ch = locale::compose(cell.ch, ch).unwrap_or(cell.ch);
+            }
+        }
+        return Some(col);
+    }
+    if col + width > row.len() {
+        return None;
+    }
+
+    // Don't leave half of a wide character behind
+    if row[col].is_continuation() && col > 0 {
+        row[col - 1] = Cell { attr: row[col - 1].attr, ..Cell::EMPTY };
+    }
+    let end = col + width;
+    if row.get(end).is_some_and(Cell::is_continuation) {
+        row[end] = Cell { attr: row[end].attr, ..Cell::EMPTY };
+    }
+
+    row[col] = Cell { ch, attr, width: width as u8 };
+    if width == 2 {
+        row[col + 1] = Cell { attr, ..Cell::CONTINUATION };
+    }
+    Some(end)
+}
+
+// =============================================================================
+// UTF-8 DECODER
+// =============================================================================
+
+/// Shown for malformed input
+pub const REPLACEMENT_CHAR: char = '\u{FFFD}';
+
+/// Incremental UTF-8 decoder for terminal output
+///
+/// Sequences may be split across writes. Malformed input (stray
+/// continuation bytes, overlong forms, surrogates, truncated sequences)
+/// decodes to [`REPLACEMENT_CHAR`], one per maximal invalid subpart.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Utf8Decoder {
+    /// Code point bits so far
+    code: u32,
+    /// Lead byte of the sequence in progress
+    lead: u8,
+    /// Continuation bytes still expected
+    need: u8,
+    /// Continuation bytes seen
+    seen: u8,
+}
+
+impl Utf8Decoder {
+    /// Create a decoder
+    pub const fn new() -> Self {
+        Self { code: 0, lead: 0, need: 0, seen: 0 }
+    }
+
+    /// Check if a sequence is incomplete
+    pub const fn is_pending(&self) -> bool {
+        self.need != 0
+    }
+
+    /// Decode `bytes`, passing each character to `emit`
+    pub fn feed(&mut self, bytes: &[u8], mut emit: impl FnMut(char)) {
+        for &byte in bytes {
+            if self.need != 0 {
+                if self.accepts(byte) {
+                    self.code = (self.code << 6) | (byte & 0x3F) as u32;
+                    self.seen += 1;
+                    self.need -= 1;
+                    if self.need == 0 {
+                        emit(char::from_u32(self.code).unwrap_or(REPLACEMENT_CHAR));
+                    }
+                    continue;
+                }
+                // Broken sequence; the byte starts afresh
+                self.need = 0;
+                emit(REPLACEMENT_CHAR);
+            }
+
+            let (bits, need) = match byte {
+                0x00..=0x7F => {
+                    emit(byte as char);
+                    continue;
+                }
+                0xC2..=0xDF => (byte & 0x1F, 1),
+                0xE0..=0xEF => (byte & 0x0F, 2),
+                0xF0..=0xF4 => (byte & 0x07, 3),
+                _ => {
+                    emit(REPLACEMENT_CHAR);
+                    continue;
+                }
+            };
+            self.code = bits as u32;
+            self.lead = byte;
+            self.need = need;
+            self.seen = 0;
+        }
+    }
+
+    /// End of input: a truncated sequence decodes to [`REPLACEMENT_CHAR`]
+    pub fn finish(&mut self, mut emit: impl FnMut(char)) {
+        if self.need != 0 {
+            self.need = 0;
+            emit(REPLACEMENT_CHAR);
+        }
+    }
+
+    /// Check `byte` continues the sequence; the second byte after some
+    /// leads is restricted to rule out overlong forms, surrogates and
+    /// code points past U+10FFFF
+    fn accepts(&self, byte: u8) -> bool {
+        let range = match (self.seen, self.lead) {
+            (0, 0xE0) => 0xA0..=0xBF,
+            (0, 0xED) => 0x80..=0x9F,
+            (0, 0xF0) => 0x90..=0xBF,
+            (0, 0xF4) => 0x80..=0x8F,
+            _ => 0x80..=0xBF,
+        };
+        range.contains(&byte)
+    }
+}
+
 // =============================================================================
 // CURSOR
 // =============================================================================
@@ -1169,6 +1326,47 @@ fn test_terminal_size() {
         assert_eq!(size.total_cells(), 2000);
     }
 
+    #[test]
+    fn test_utf8_decoder() {
+        const R: char = REPLACEMENT_CHAR;
+        let mut decoder = Utf8Decoder::new();
+        let mut out = [' '; 16];
+        let mut n = 0;
+        let mut push = |c| {
+            out[n] = c;
+            n += 1;
+        };
+
+        // "é界" split mid-sequence
+        decoder.feed(b"\xC3", &mut push);
+        assert!(decoder.is_pending());
+        decoder.feed(b"\xA9\xE7\x95", &mut push);
+        decoder.feed(b"\x8C", &mut push);
+        // Stray continuation, overlong '/', surrogate, truncated emoji
+        decoder.feed(b"\x80\xC0\xAF\xED\xA0A\xF0\x9F", &mut push);
+        decoder.finish(&mut push);
+
+        assert_eq!(out[..n], ['é', '界', R, R, R, R, R, 'A', R]);
+    }
+
+    #[test]
+    fn test_put_char() {
+        let attr = TextAttributes::DEFAULT;
+        let mut row = [Cell::EMPTY; 4];
+
+        assert_eq!(put_char(&mut row, 0, '界', attr), Some(2));
+        assert!(row[1].is_continuation());
+        assert_eq!(put_char(&mut row, 2, 'e', attr), Some(3));
+        assert_eq!(put_char(&mut row, 3, '\u{301}', attr), Some(3));
+        assert_eq!(row[2].ch, 'é');
+        assert_eq!(put_char(&mut row, 3, '🚀', attr), None);
+
+        // Overwriting the right half blanks the left
+        assert_eq!(put_char(&mut row, 1, 'x', attr), Some(2));
+        assert_eq!((row[0].ch, row[0].width), (' ', 1));
+        assert_eq!(row[1].ch, 'x');
+    }
+
     #[test]
     fn test_efi_color() {
         let attr = EfiColor::make_attr(EfiColor::White, EfiColor::Blue);
@@ -24,6 +24,11 @@
 //! │  │                   Component Styles                               │   │
 //! │  │  Menu │ Text │ Buttons │ Progress │ Dialogs                     │   │
 //! │  └─────────────────────────────────────────────────────────────────┘   │
+//! │                              │                                         │
+//! │  ┌─────────────────────────────────────────────────────────────────┐   │
+//! │  │                   Theme Files                                    │   │
+//! │  │  ESP │ HelixFS │ Validation │ Hot Switching                     │   │
+//! │  └─────────────────────────────────────────────────────────────────┘   │
 //! │                                                                         │
 //! └─────────────────────────────────────────────────────────────────────────┘
 //! ```
@@ -32,6 +37,9 @@
 
 use core::fmt;
 
+use super::handoff::ConsolePalette;
+use super::protocols::filesystem::{FileMode, FileSystem};
+
 // =============================================================================
 // COLOR SYSTEM
 // =============================================================================
@@ -143,6 +151,41 @@ pub fn contrast_text(&self) -> Color {
             colors::BLACK
         }
     }
+
+    /// Convert to 0xAARRGGBB (framebuffer console format)
+    pub const fn to_argb(&self) -> u32 {
+        ((self.a as u32) << 24) | self.to_rgb()
+    }
+
+    /// Contrast ratio against another color, in hundredths (100 = 1:1)
+    ///
+    /// WCAG formula with gamma 2.0 instead of the sRGB curve.
+    pub fn contrast_ratio(&self, other: Color) -> u32 {
+        fn luminance(c: Color) -> u32 {
+            let (r, g, b) = (c.r as u32, c.g as u32, c.b as u32);
+            (2126 * r * r + 7152 * g * g + 722 * b * b) / 10000
+        }
+        // 0.05 on the 0..=65025 scale
+        const FLARE: u32 = 3251;
+
+        let (a, b) = (luminance(*self), luminance(other));
+        let (hi, lo) = if a > b { (a, b) } else { (b, a) };
+        (hi + FLARE) * 100 / (lo + FLARE)
+    }
+
+    /// Parse `#RRGGBB` or `#RRGGBBAA`
+    pub fn parse_hex(s: &str) -> Option<Color> {
+        let hex = s.strip_prefix('#')?;
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        match hex.len() {
+            6 => Some(Color::from_rgb(value)),
+            8 => Some(Color::from_rgba(value)),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Color {
@@ -857,6 +900,10 @@ pub struct Theme {
     pub animations: bool,
     /// Animation speed (1-10, 5 = normal)
     pub animation_speed: u8,
+    /// Splash image path (empty = built-in splash)
+    pub splash: [u8; MAX_SPLASH_PATH],
+    /// Splash path length
+    pub splash_len: usize,
 }
 
 impl Default for Theme {
@@ -883,6 +930,8 @@ pub fn dark() -> Self {
             },
             animations: true,
             animation_speed: 5,
+            splash: [0u8; MAX_SPLASH_PATH],
+            splash_len: 0,
         }
     }
 
@@ -922,10 +971,427 @@ pub fn high_contrast_dark() -> Self {
         theme
     }
 
+    /// High contrast theme with large text (accessibility)
+    pub fn high_contrast_large() -> Self {
+        let mut theme = Self::high_contrast_dark();
+        theme.name = *b"High Contrast Large\0\0\0\0\0\0\0\0\0\0\0\0\0";
+        theme.name_len = 19;
+        theme.font.size = FontSizePreset::XXLarge.size_px();
+        theme.font.weight = FontWeight::Bold;
+        theme.font_mono.size = FontSizePreset::XXLarge.size_px();
+        theme.menu_item.height = 64;
+        theme.menu_item.padding = SpacingPreset::Large;
+        theme.sync_components();
+        theme
+    }
+
     /// Get theme name
     pub fn name_str(&self) -> &str {
         core::str::from_utf8(&self.name[..self.name_len]).unwrap_or("")
     }
+
+    /// Get splash image path
+    pub fn splash_str(&self) -> &str {
+        core::str::from_utf8(&self.splash[..self.splash_len]).unwrap_or("")
+    }
+
+    /// Palette for the framebuffer console (boot menu and kernel)
+    pub fn console_palette(&self) -> ConsolePalette {
+        let rgb = |c: Color| (c.r, c.g, c.b);
+        ConsolePalette {
+            foreground: rgb(self.colors.text_primary),
+            background: rgb(self.colors.background),
+            accent: rgb(self.colors.primary),
+            dim: rgb(self.colors.text_secondary),
+            selected_fg: rgb(self.colors.selected_text),
+            selected_bg: rgb(self.colors.selected_bg),
+            warning: rgb(self.colors.warning),
+            error: rgb(self.colors.error),
+        }
+    }
+
+    /// Re-derive component colors from the color scheme
+    fn sync_components(&mut self) {
+        let c = self.colors;
+
+        self.button.background = c.primary;
+        self.button.background_hover = c.primary_hover;
+        self.button.background_active = c.primary_active;
+        self.button.text = c.primary.contrast_text();
+        self.button.text_disabled = c.text_disabled;
+
+        self.menu_item.background_selected = c.selected_bg;
+        self.menu_item.text = c.text_primary;
+        self.menu_item.text_selected = c.selected_text;
+        self.menu_item.text_secondary = c.text_secondary;
+        self.menu_item.icon = c.text_secondary;
+        self.menu_item.icon_selected = c.selected_text;
+        self.menu_item.separator_color = c.border;
+
+        self.progress.background = c.background_tertiary;
+        self.progress.fill = c.primary;
+        self.progress.fill_success = c.success;
+        self.progress.fill_error = c.error;
+
+        self.dialog.background = c.surface;
+        self.dialog.border = c.border;
+        self.dialog.overlay = c.overlay;
+        self.dialog.header_bg = c.background_secondary;
+        self.dialog.header_text = c.text_primary;
+    }
+
+    /// Check that the theme is usable
+    pub fn validate(&self) -> Result<(), ThemeError> {
+        if self.name_len == 0 {
+            return Err(ThemeError::MissingName);
+        }
+        for font in [&self.font, &self.font_mono] {
+            if !(MIN_FONT_SIZE..=MAX_FONT_SIZE).contains(&font.size) {
+                return Err(ThemeError::FontSize);
+            }
+        }
+        if self.menu_item.height < self.font.effective_line_height() {
+            return Err(ThemeError::MenuItemTooShort);
+        }
+        if !(1..=10).contains(&self.animation_speed) {
+            return Err(ThemeError::AnimationSpeed);
+        }
+
+        let c = &self.colors;
+        if c.text_primary.contrast_ratio(c.background) < MIN_CONTRAST
+            || c.selected_text.contrast_ratio(c.selected_bg) < MIN_CONTRAST
+        {
+            return Err(ThemeError::LowContrast);
+        }
+
+        Ok(())
+    }
+
+    /// Parse a theme file
+    ///
+    /// Keys not set by the file keep the values of its `base` theme
+    /// (dark by default). The result still needs [`Theme::validate`].
+    pub fn parse(text: &str) -> Result<Theme, ThemeError> {
+        let mut theme = Theme::dark();
+        theme.name_len = 0;
+        let mut section = "";
+        let mut colors_set = false;
+
+        for (index, raw) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                section = name.strip_suffix(']').ok_or(ThemeError::Syntax(line_no))?.trim();
+                if !matches!(section, "theme" | "colors" | "font" | "spacing") {
+                    return Err(ThemeError::UnknownSection(line_no));
+                }
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or(ThemeError::Syntax(line_no))?;
+            let (key, value) = (key.trim(), strip_comment(value.trim()));
+            let invalid = ThemeError::InvalidValue(line_no);
+
+            match section {
+                "theme" => match key {
+                    "name" => {
+                        let len = copy_str(&mut theme.name, value).ok_or(ThemeError::NameTooLong)?;
+                        theme.name_len = len;
+                    }
+                    "base" => {
+                        let name = (theme.name, theme.name_len);
+                        let splash = (theme.splash, theme.splash_len);
+                        theme = match value {
+                            "dark" => Theme::dark(),
+                            "light" => Theme::light(),
+                            "high-contrast" => Theme::high_contrast_dark(),
+                            _ => return Err(invalid),
+                        };
+                        (theme.name, theme.name_len) = name;
+                        (theme.splash, theme.splash_len) = splash;
+                    }
+                    "splash" => {
+                        let len = copy_str(&mut theme.splash, value).ok_or(ThemeError::PathTooLong)?;
+                        theme.splash_len = len;
+                    }
+                    "animations" => theme.animations = parse_bool(value).ok_or(invalid)?,
+                    "animation_speed" => theme.animation_speed = value.parse().map_err(|_| invalid)?,
+                    _ => return Err(ThemeError::UnknownKey(line_no)),
+                },
+                "colors" => {
+                    let color = Color::parse_hex(value).ok_or(invalid)?;
+                    *color_slot(&mut theme.colors, key).ok_or(ThemeError::UnknownKey(line_no))? = color;
+                    theme.colors.scheme_type = ColorSchemeType::Custom;
+                    colors_set = true;
+                }
+                "font" => match key {
+                    "size" => theme.font.size = value.parse().map_err(|_| invalid)?,
+                    "line_height" => theme.font.line_height = value.parse().map_err(|_| invalid)?,
+                    "weight" => theme.font.weight = parse_weight(value).ok_or(invalid)?,
+                    "mono_size" => theme.font_mono.size = value.parse().map_err(|_| invalid)?,
+                    _ => return Err(ThemeError::UnknownKey(line_no)),
+                },
+                "spacing" => match key {
+                    "menu_item_height" => theme.menu_item.height = value.parse().map_err(|_| invalid)?,
+                    "padding" => {
+                        let padding = parse_spacing(value).ok_or(invalid)?;
+                        theme.menu_item.padding = padding;
+                        theme.button.padding_x = padding;
+                    }
+                    "dialog_padding" => theme.dialog.padding = parse_spacing(value).ok_or(invalid)?,
+                    "radius" => {
+                        let radius = parse_radius(value).ok_or(invalid)?;
+                        theme.menu_item.radius = radius;
+                        theme.button.radius = radius;
+                        theme.dialog.radius = radius;
+                    }
+                    _ => return Err(ThemeError::UnknownKey(line_no)),
+                },
+                _ => return Err(ThemeError::Syntax(line_no)),
+            }
+        }
+
+        if theme.name_len == 0 {
+            return Err(ThemeError::MissingName);
+        }
+        if colors_set {
+            theme.sync_components();
+        }
+        Ok(theme)
+    }
+}
+
+// =============================================================================
+// THEME FILES
+// =============================================================================
+//
+// Theme files are INI-style text, one per theme:
+//
+//     [theme]
+//     name = Ocean
+//     base = dark                  ; dark | light | high-contrast
+//     splash = \EFI\helix\splash\ocean.bmp
+//     animations = true
+//
+//     [colors]                     ; any ColorScheme field
+//     background = #0B1D2A
+//     text_primary = #E0F2FE
+//     selected_bg = #0369A1
+//
+//     [font]
+//     size = 16                    ; also line_height, weight, mono_size
+//
+//     [spacing]
+//     menu_item_height = 40        ; also padding, dialog_padding, radius
+
+/// Theme directory on the ESP
+pub const ESP_THEME_DIR: &str = "\\EFI\\helix\\themes";
+
+/// Theme directory on HelixFS
+pub const HELIXFS_THEME_DIR: &str = "/etc/helix/themes";
+
+/// Theme file extension
+pub const THEME_EXTENSION: &str = ".theme";
+
+/// Maximum theme file size
+pub const MAX_THEME_FILE: usize = 8192;
+
+/// Maximum splash image path length
+pub const MAX_SPLASH_PATH: usize = 128;
+
+/// Smallest allowed font size
+pub const MIN_FONT_SIZE: u16 = 8;
+
+/// Largest allowed font size
+pub const MAX_FONT_SIZE: u16 = 64;
+
+/// Minimum text/background contrast (hundredths, 300 = 3:1)
+pub const MIN_CONTRAST: u32 = 300;
+
+/// Theme file or switching error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeError {
+    /// Malformed line
+    Syntax(usize),
+    /// Unknown `[section]`
+    UnknownSection(usize),
+    /// Unknown key in a section
+    UnknownKey(usize),
+    /// Value cannot be parsed
+    InvalidValue(usize),
+    /// No `name` in `[theme]`
+    MissingName,
+    /// Name longer than 32 bytes
+    NameTooLong,
+    /// Splash path longer than [`MAX_SPLASH_PATH`]
+    PathTooLong,
+    /// Font size outside [`MIN_FONT_SIZE`]..=[`MAX_FONT_SIZE`]
+    FontSize,
+    /// Menu items shorter than a line of text
+    MenuItemTooShort,
+    /// Animation speed outside 1..=10
+    AnimationSpeed,
+    /// Text would be unreadable on its background
+    LowContrast,
+    /// All [`MAX_THEMES`] slots are used
+    TooManyThemes,
+    /// No theme file or loaded theme with that name
+    NotFound,
+    /// Theme file is not UTF-8 or too large
+    BadFile,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Syntax(line) => write!(f, "line {}: syntax error", line),
+            ThemeError::UnknownSection(line) => write!(f, "line {}: unknown section", line),
+            ThemeError::UnknownKey(line) => write!(f, "line {}: unknown key", line),
+            ThemeError::InvalidValue(line) => write!(f, "line {}: invalid value", line),
+            ThemeError::MissingName => write!(f, "theme has no name"),
+            ThemeError::NameTooLong => write!(f, "theme name too long"),
+            ThemeError::PathTooLong => write!(f, "splash path too long"),
+            ThemeError::FontSize => write!(f, "font size out of range"),
+            ThemeError::MenuItemTooShort => write!(f, "menu item height below line height"),
+            ThemeError::AnimationSpeed => write!(f, "animation speed out of range"),
+            ThemeError::LowContrast => write!(f, "insufficient text contrast"),
+            ThemeError::TooManyThemes => write!(f, "too many themes"),
+            ThemeError::NotFound => write!(f, "theme not found"),
+            ThemeError::BadFile => write!(f, "unreadable theme file"),
+        }
+    }
+}
+
+/// Storage that theme files are read from
+pub trait ThemeSource {
+    /// Read theme file `name` (without extension) into `buf`; returns its length
+    fn read_theme(&mut self, name: &str, buf: &mut [u8]) -> Option<usize>;
+}
+
+/// Theme files in [`ESP_THEME_DIR`]
+impl ThemeSource for FileSystem {
+    fn read_theme(&mut self, name: &str, buf: &mut [u8]) -> Option<usize> {
+        let mut path = [0u8; 128];
+        let path = theme_path(ESP_THEME_DIR, '\\', name, &mut path)?;
+        let mut file = self.open(path, FileMode::Read).ok()?;
+        let mut len = 0;
+        while len < buf.len() {
+            match file.read(&mut buf[len..]).ok()? {
+                0 => return Some(len),
+                n => len += n,
+            }
+        }
+        // File larger than the buffer
+        None
+    }
+}
+
+/// Build `<dir><sep><name>.theme` in `buf`
+pub fn theme_path<'a>(dir: &str, separator: char, name: &str, buf: &'a mut [u8]) -> Option<&'a str> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return None;
+    }
+    let mut sep = [0u8; 4];
+    let mut len = 0;
+    for part in [dir, separator.encode_utf8(&mut sep), name, THEME_EXTENSION] {
+        let end = len + part.len();
+        buf.get_mut(len..end)?.copy_from_slice(part.as_bytes());
+        len = end;
+    }
+    core::str::from_utf8(&buf[..len]).ok()
+}
+
+/// Strip a trailing `;` comment from a value
+fn strip_comment(value: &str) -> &str {
+    value.split(';').next().unwrap_or("").trim_end()
+}
+
+/// Copy `value` into a fixed buffer; `None` if it does not fit
+fn copy_str(buf: &mut [u8], value: &str) -> Option<usize> {
+    let bytes = value.as_bytes();
+    buf.get_mut(..bytes.len())?.copy_from_slice(bytes);
+    Some(bytes.len())
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_weight(value: &str) -> Option<FontWeight> {
+    Some(match value {
+        "thin" | "100" => FontWeight::Thin,
+        "extralight" | "200" => FontWeight::ExtraLight,
+        "light" | "300" => FontWeight::Light,
+        "normal" | "regular" | "400" => FontWeight::Normal,
+        "medium" | "500" => FontWeight::Medium,
+        "semibold" | "600" => FontWeight::Semibold,
+        "bold" | "700" => FontWeight::Bold,
+        "extrabold" | "800" => FontWeight::ExtraBold,
+        "black" | "900" => FontWeight::Black,
+        _ => return None,
+    })
+}
+
+fn parse_spacing(value: &str) -> Option<SpacingPreset> {
+    Some(match value {
+        "none" => SpacingPreset::None,
+        "xsmall" => SpacingPreset::XSmall,
+        "small" => SpacingPreset::Small,
+        "medium" => SpacingPreset::Medium,
+        "large" => SpacingPreset::Large,
+        "xlarge" => SpacingPreset::XLarge,
+        "xxlarge" => SpacingPreset::XXLarge,
+        _ => return None,
+    })
+}
+
+fn parse_radius(value: &str) -> Option<RadiusPreset> {
+    Some(match value {
+        "none" => RadiusPreset::None,
+        "small" => RadiusPreset::Small,
+        "medium" => RadiusPreset::Medium,
+        "large" => RadiusPreset::Large,
+        "xlarge" => RadiusPreset::XLarge,
+        "full" => RadiusPreset::Full,
+        _ => return None,
+    })
+}
+
+/// Color scheme field named `key`
+fn color_slot<'a>(scheme: &'a mut ColorScheme, key: &str) -> Option<&'a mut Color> {
+    Some(match key {
+        "background" => &mut scheme.background,
+        "background_secondary" => &mut scheme.background_secondary,
+        "background_tertiary" => &mut scheme.background_tertiary,
+        "surface" => &mut scheme.surface,
+        "overlay" => &mut scheme.overlay,
+        "text_primary" => &mut scheme.text_primary,
+        "text_secondary" => &mut scheme.text_secondary,
+        "text_disabled" => &mut scheme.text_disabled,
+        "text_inverse" => &mut scheme.text_inverse,
+        "primary" => &mut scheme.primary,
+        "primary_hover" => &mut scheme.primary_hover,
+        "primary_active" => &mut scheme.primary_active,
+        "secondary" => &mut scheme.secondary,
+        "success" => &mut scheme.success,
+        "warning" => &mut scheme.warning,
+        "error" => &mut scheme.error,
+        "info" => &mut scheme.info,
+        "border" => &mut scheme.border,
+        "border_focus" => &mut scheme.border_focus,
+        "selected_bg" => &mut scheme.selected_bg,
+        "selected_text" => &mut scheme.selected_text,
+        "highlight" => &mut scheme.highlight,
+        _ => return None,
+    })
 }
 
 // =============================================================================
@@ -960,12 +1426,13 @@ pub fn new() -> Self {
         let mut manager = Self {
             current: Theme::dark(),
             themes: [Theme::dark(); MAX_THEMES],
-            count: 3,
+            count: 4,
             current_index: 0,
         };
         manager.themes[0] = Theme::dark();
         manager.themes[1] = Theme::light();
         manager.themes[2] = Theme::high_contrast_dark();
+        manager.themes[3] = Theme::high_contrast_large();
         manager
     }
 
@@ -1019,6 +1486,56 @@ pub fn previous(&mut self) {
         };
         self.select(prev);
     }
+
+    /// Find theme by name
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.themes[..self.count].iter().position(|t| t.name_str() == name)
+    }
+
+    /// Select theme by name
+    pub fn select_by_name(&mut self, name: &str) -> Result<(), ThemeError> {
+        let index = self.find(name).ok_or(ThemeError::NotFound)?;
+        self.select(index);
+        Ok(())
+    }
+
+    /// Parse, validate and add a theme file; a theme with the same name is
+    /// replaced (and re-applied if current). Returns the theme's index.
+    pub fn load(&mut self, text: &str) -> Result<usize, ThemeError> {
+        let theme = Theme::parse(text)?;
+        theme.validate()?;
+
+        match self.find(theme.name_str()) {
+            Some(index) => {
+                self.themes[index] = theme;
+                if index == self.current_index {
+                    self.current = theme;
+                }
+                Ok(index)
+            }
+            None if self.add_theme(theme) => Ok(self.count - 1),
+            None => Err(ThemeError::TooManyThemes),
+        }
+    }
+
+    /// Load theme file `name` from a source
+    pub fn load_from(&mut self, source: &mut dyn ThemeSource, name: &str) -> Result<usize, ThemeError> {
+        let mut buf = [0u8; MAX_THEME_FILE];
+        let len = source.read_theme(name, &mut buf).ok_or(ThemeError::NotFound)?;
+        let text = core::str::from_utf8(&buf[..len]).map_err(|_| ThemeError::BadFile)?;
+        self.load(text)
+    }
+
+    /// Switch to theme `name` at runtime, loading it from `source` if it
+    /// is not loaded yet. On error the current theme stays in effect.
+    pub fn switch(&mut self, source: &mut dyn ThemeSource, name: &str) -> Result<&Theme, ThemeError> {
+        let index = match self.find(name) {
+            Some(index) => index,
+            None => self.load_from(source, name)?,
+        };
+        self.select(index);
+        Ok(&self.current)
+    }
 }
 
 // =============================================================================
@@ -1069,10 +1586,113 @@ fn test_color_scheme() {
         assert_eq!(light.scheme_type, ColorSchemeType::Light);
     }
 
+    const OCEAN: &str = "\
+# Ocean theme
+[theme]
+name = Ocean
+base = dark
+splash = \\EFI\\helix\\splash\\ocean.bmp   ; shown during boot
+
+[colors]
+background = #0B1D2A
+text_primary = #E0F2FE
+selected_bg = #0369A1
+
+[font]
+size = 16
+weight = bold
+
+[spacing]
+menu_item_height = 40
+radius = large
+";
+
+    struct Files(&'static [(&'static str, &'static str)]);
+
+    impl ThemeSource for Files {
+        fn read_theme(&mut self, name: &str, buf: &mut [u8]) -> Option<usize> {
+            let (_, text) = self.0.iter().find(|(n, _)| *n == name)?;
+            buf[..text.len()].copy_from_slice(text.as_bytes());
+            Some(text.len())
+        }
+    }
+
+    #[test]
+    fn test_parse_theme_file() {
+        let theme = Theme::parse(OCEAN).unwrap();
+        assert_eq!(theme.name_str(), "Ocean");
+        assert_eq!(theme.splash_str(), "\\EFI\\helix\\splash\\ocean.bmp");
+        assert_eq!(theme.colors.background, Color::from_rgb(0x0B1D2A));
+        assert_eq!(theme.colors.scheme_type, ColorSchemeType::Custom);
+        assert_eq!(theme.menu_item.background_selected, Color::from_rgb(0x0369A1));
+        assert_eq!(theme.font.size, 16);
+        assert_eq!(theme.font.weight, FontWeight::Bold);
+        assert_eq!(theme.dialog.radius, RadiusPreset::Large);
+        assert_eq!(theme.validate(), Ok(()));
+        assert_eq!(theme.console_palette().background, (0x0B, 0x1D, 0x2A));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Theme::parse("[colors]\nbackground = #000000\n").err(), Some(ThemeError::MissingName));
+        assert_eq!(Theme::parse("[theme]\nname = x\n[sound]\n").err(), Some(ThemeError::UnknownSection(3)));
+        assert_eq!(Theme::parse("[theme]\nname = x\ncolour = 1\n").err(), Some(ThemeError::UnknownKey(3)));
+        assert_eq!(Theme::parse("[theme]\nname = x\n[colors]\nerror = red\n").err(), Some(ThemeError::InvalidValue(4)));
+        assert_eq!(Theme::parse("[theme]\nname x\n").err(), Some(ThemeError::Syntax(2)));
+    }
+
+    #[test]
+    fn test_validate_theme() {
+        let unreadable = Theme::parse("[theme]\nname = Gray\n[colors]\ntext_primary = #303030\nbackground = #202020\n").unwrap();
+        assert_eq!(unreadable.validate(), Err(ThemeError::LowContrast));
+
+        let tiny = Theme::parse("[theme]\nname = Tiny\n[font]\nsize = 4\n").unwrap();
+        assert_eq!(tiny.validate(), Err(ThemeError::FontSize));
+
+        assert_eq!(Theme::light().validate(), Ok(()));
+        assert_eq!(Theme::high_contrast_dark().validate(), Ok(()));
+        assert_eq!(Theme::high_contrast_large().validate(), Ok(()));
+        assert_eq!(Theme::high_contrast_large().name_str(), "High Contrast Large");
+        assert!(colors::WHITE.contrast_ratio(colors::BLACK) > 2000);
+        assert_eq!(colors::WHITE.contrast_ratio(colors::WHITE), 100);
+    }
+
+    #[test]
+    fn test_theme_switching() {
+        let mut files = Files(&[
+            ("ocean", OCEAN),
+            ("broken", "[theme]\nname = Broken\n[font]\nsize = 200\n"),
+        ]);
+        let mut manager = ThemeManager::new();
+
+        assert_eq!(manager.switch(&mut files, "ocean").unwrap().name_str(), "Ocean");
+        assert_eq!(manager.len(), 5);
+
+        // Broken or missing themes leave the current theme in place
+        assert_eq!(manager.switch(&mut files, "broken").err(), Some(ThemeError::FontSize));
+        assert_eq!(manager.switch(&mut files, "missing").err(), Some(ThemeError::NotFound));
+        assert_eq!(manager.current.name_str(), "Ocean");
+
+        // Reloading replaces the loaded copy
+        assert_eq!(manager.load(OCEAN), Ok(4));
+        assert_eq!(manager.len(), 5);
+
+        assert_eq!(manager.select_by_name("Helix Light"), Ok(()));
+        assert_eq!(manager.current_index(), 1);
+    }
+
+    #[test]
+    fn test_theme_path() {
+        let mut buf = [0u8; 64];
+        assert_eq!(theme_path(HELIXFS_THEME_DIR, '/', "ocean", &mut buf), Some("/etc/helix/themes/ocean.theme"));
+        assert_eq!(theme_path(ESP_THEME_DIR, '\\', "../x", &mut buf), None);
+        assert_eq!(theme_path(ESP_THEME_DIR, '\\', "ocean", &mut [0u8; 8]), None);
+    }
+
     #[test]
     fn test_theme_manager() {
         let mut manager = ThemeManager::new();
-        assert_eq!(manager.len(), 3);
+        assert_eq!(manager.len(), 4);
 
         manager.next();
         assert_eq!(manager.current_index(), 1);
@@ -771,6 +771,16 @@ pub const fn set_interface(interface: u8, alternate: u8) -> Self {
         }
     }
 
+    /// Setup packet as the little-endian quadword used in Setup Stage TRBs
+    pub const fn to_u64(&self) -> u64 {
+        let (value, index, length) = (self.value, self.index, self.length);
+        (self.request_type as u64)
+            | ((self.request as u64) << 8)
+            | ((value as u64) << 16)
+            | ((index as u64) << 32)
+            | ((length as u64) << 48)
+    }
+
     /// Check if direction is IN (device to host)
     pub const fn is_in(&self) -> bool {
         (self.request_type & 0x80) != 0
@@ -1798,6 +1808,64 @@ pub mod xhci_op {
     pub const CONFIG: usize = 0x38;
 }
 
+crate::register_block! {
+    /// xHCI capability register map
+    pub struct XhciCapRegisters, size = 0x20 {
+        /// Capability register length
+        0x00 => cap_length: u8, ReadOnly;
+        /// Interface version number
+        0x02 => hci_version: u16, ReadOnly;
+        /// Structural parameters 1
+        0x04 => hcsparams1: u32, ReadOnly;
+        /// Structural parameters 2
+        0x08 => hcsparams2: u32, ReadOnly;
+        /// Structural parameters 3
+        0x0C => hcsparams3: u32, ReadOnly;
+        /// Capability parameters 1
+        0x10 => hccparams1: u32, ReadOnly;
+        /// Doorbell offset
+        0x14 => dboff: u32, ReadOnly;
+        /// Runtime register space offset
+        0x18 => rtsoff: u32, ReadOnly;
+        /// Capability parameters 2
+        0x1C => hccparams2: u32, ReadOnly;
+    }
+}
+
+crate::register_block! {
+    /// xHCI operational register map (relative to operational base)
+    pub struct XhciOpRegisters, size = 0x40 {
+        /// USB command register
+        0x00 => usbcmd: u32, ReadWrite;
+        /// USB status register
+        0x04 => usbsts: u32, ReadWrite;
+        /// Page size register
+        0x08 => pagesize: u32, ReadOnly;
+        /// Device notification control
+        0x14 => dnctrl: u32, ReadWrite;
+        /// Command ring control register
+        0x18 => crcr: u64, ReadWrite;
+        /// Device context base address array pointer
+        0x30 => dcbaap: u64, ReadWrite;
+        /// Configure register
+        0x38 => config: u32, ReadWrite;
+    }
+}
+
+impl XhciCapRegisters {
+    /// Operational registers, located `CAPLENGTH` bytes after the capabilities
+    ///
+    /// # Safety
+    ///
+    /// The capability registers must belong to a mapped xHCI BAR covering
+    /// the operational registers.
+    pub unsafe fn operational(&self) -> XhciOpRegisters {
+        let base = self.region().base() + self.cap_length().read() as usize;
+        // SAFETY: Forwarded from the caller
+        unsafe { XhciOpRegisters::new(base) }
+    }
+}
+
 /// xHCI USB command register bits
 pub mod xhci_usbcmd {
     /// Run/Stop
@@ -2016,13 +2084,8 @@ pub const fn configure_endpoint(input_context_ptr: u64, slot_id: u8, dc: bool) -
 
     /// Create a Setup Stage TRB
     pub fn setup_stage(setup: &SetupPacket, trt: u8) -> Self {
-        let param = unsafe {
-            let ptr = setup as *const SetupPacket as *const u64;
-            *ptr
-        };
-
         Self {
-            parameter: param,
+            parameter: setup.to_u64(),
             status: 8, // TRB transfer length = 8
             control: ((TrbType::SetupStage as u32) << 10)
                 | ((trt as u32) << 16) // Transfer Type
@@ -11,12 +11,16 @@
 //! - Boot entry validation
 //! - Pre-boot checks
 //! - Hardware capability validation
+//! - Pre-boot compatibility report (CPU level, memory, firmware tables,
+//!   root device driver) that refuses to boot with actionable messages
 //! - Security policy validation
 
 #![no_std]
 
 use core::fmt;
 
+use super::sysinfo::{CpuFeatures, FirmwareTables, StorageType, SystemSummary};
+
 // =============================================================================
 // VALIDATION RESULT
 // =============================================================================
@@ -861,6 +865,24 @@ pub enum CpuFeature {
     Apic,
     /// X2APIC
     X2apic,
+    /// POPCNT
+    Popcnt,
+    /// CMPXCHG16B
+    Cx16,
+    /// LAHF/SAHF in long mode
+    LahfLm,
+    /// FMA3
+    Fma,
+    /// BMI1
+    Bmi1,
+    /// BMI2
+    Bmi2,
+    /// LZCNT
+    Lzcnt,
+    /// MOVBE
+    Movbe,
+    /// F16C
+    F16c,
 }
 
 impl Default for CpuFeature {
@@ -869,6 +891,208 @@ fn default() -> Self {
     }
 }
 
+impl CpuFeature {
+    /// All features, in report order
+    pub const ALL: [CpuFeature; 32] = [
+        CpuFeature::LongMode, CpuFeature::Pae, CpuFeature::Fpu, CpuFeature::Tsc,
+        CpuFeature::Apic, CpuFeature::X2apic, CpuFeature::Nx, CpuFeature::Smep,
+        CpuFeature::Smap, CpuFeature::Sse, CpuFeature::Sse2, CpuFeature::Sse3,
+        CpuFeature::Ssse3, CpuFeature::Sse41, CpuFeature::Sse42, CpuFeature::Popcnt,
+        CpuFeature::Cx16, CpuFeature::LahfLm, CpuFeature::Avx, CpuFeature::Avx2,
+        CpuFeature::Fma, CpuFeature::Bmi1, CpuFeature::Bmi2, CpuFeature::Lzcnt,
+        CpuFeature::Movbe, CpuFeature::F16c, CpuFeature::Xsave, CpuFeature::Avx512f,
+        CpuFeature::AesNi, CpuFeature::Sha, CpuFeature::Rdrand, CpuFeature::Rdseed,
+    ];
+
+    /// Corresponding detected feature flag
+    pub const fn flag(&self) -> CpuFeatures {
+        match self {
+            CpuFeature::LongMode => CpuFeatures::LONG_MODE,
+            CpuFeature::Pae => CpuFeatures::PAE,
+            CpuFeature::Sse => CpuFeatures::SSE,
+            CpuFeature::Sse2 => CpuFeatures::SSE2,
+            CpuFeature::Sse3 => CpuFeatures::SSE3,
+            CpuFeature::Ssse3 => CpuFeatures::SSSE3,
+            CpuFeature::Sse41 => CpuFeatures::SSE4_1,
+            CpuFeature::Sse42 => CpuFeatures::SSE4_2,
+            CpuFeature::Avx => CpuFeatures::AVX,
+            CpuFeature::Avx2 => CpuFeatures::AVX2,
+            CpuFeature::Avx512f => CpuFeatures::AVX512F,
+            CpuFeature::AesNi => CpuFeatures::AES_NI,
+            CpuFeature::Sha => CpuFeatures::SHA,
+            CpuFeature::Fpu => CpuFeatures::FPU,
+            CpuFeature::Nx => CpuFeatures::NX,
+            CpuFeature::Smep => CpuFeatures::SMEP,
+            CpuFeature::Smap => CpuFeatures::SMAP,
+            CpuFeature::Xsave => CpuFeatures::XSAVE,
+            CpuFeature::Rdrand => CpuFeatures::RDRAND,
+            CpuFeature::Rdseed => CpuFeatures::RDSEED,
+            CpuFeature::Tsc => CpuFeatures::TSC,
+            CpuFeature::Apic => CpuFeatures::APIC,
+            CpuFeature::X2apic => CpuFeatures::X2APIC,
+            CpuFeature::Popcnt => CpuFeatures::POPCNT,
+            CpuFeature::Cx16 => CpuFeatures::CX16,
+            CpuFeature::LahfLm => CpuFeatures::LAHF_LM,
+            CpuFeature::Fma => CpuFeatures::FMA,
+            CpuFeature::Bmi1 => CpuFeatures::BMI1,
+            CpuFeature::Bmi2 => CpuFeatures::BMI2,
+            CpuFeature::Lzcnt => CpuFeatures::LZCNT,
+            CpuFeature::Movbe => CpuFeatures::MOVBE,
+            CpuFeature::F16c => CpuFeatures::F16C,
+        }
+    }
+
+    /// Short name as shown in CPU documentation
+    pub const fn name(&self) -> &'static str {
+        match self {
+            CpuFeature::LongMode => "LM",
+            CpuFeature::Pae => "PAE",
+            CpuFeature::Sse => "SSE",
+            CpuFeature::Sse2 => "SSE2",
+            CpuFeature::Sse3 => "SSE3",
+            CpuFeature::Ssse3 => "SSSE3",
+            CpuFeature::Sse41 => "SSE4.1",
+            CpuFeature::Sse42 => "SSE4.2",
+            CpuFeature::Avx => "AVX",
+            CpuFeature::Avx2 => "AVX2",
+            CpuFeature::Avx512f => "AVX512F",
+            CpuFeature::AesNi => "AES-NI",
+            CpuFeature::Sha => "SHA",
+            CpuFeature::Fpu => "FPU",
+            CpuFeature::Nx => "NX",
+            CpuFeature::Smep => "SMEP",
+            CpuFeature::Smap => "SMAP",
+            CpuFeature::Xsave => "XSAVE",
+            CpuFeature::Rdrand => "RDRAND",
+            CpuFeature::Rdseed => "RDSEED",
+            CpuFeature::Tsc => "TSC",
+            CpuFeature::Apic => "APIC",
+            CpuFeature::X2apic => "X2APIC",
+            CpuFeature::Popcnt => "POPCNT",
+            CpuFeature::Cx16 => "CX16",
+            CpuFeature::LahfLm => "LAHF_LM",
+            CpuFeature::Fma => "FMA",
+            CpuFeature::Bmi1 => "BMI1",
+            CpuFeature::Bmi2 => "BMI2",
+            CpuFeature::Lzcnt => "LZCNT",
+            CpuFeature::Movbe => "MOVBE",
+            CpuFeature::F16c => "F16C",
+        }
+    }
+}
+
+/// x86-64 microarchitecture level a kernel is built for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MicroarchLevel {
+    /// Baseline x86-64
+    Baseline,
+    /// x86-64-v2 (SSE4.2, POPCNT, CX16)
+    V2,
+    /// x86-64-v3 (AVX2, BMI, FMA)
+    V3,
+    /// x86-64-v4 (AVX-512)
+    V4,
+}
+
+impl MicroarchLevel {
+    /// Features the level requires, including those of lower levels
+    pub const fn features(&self) -> CpuFeatures {
+        let baseline = CpuFeatures::LONG_MODE
+            .with(CpuFeatures::FPU)
+            .with(CpuFeatures::SSE)
+            .with(CpuFeatures::SSE2);
+        let v2 = baseline
+            .with(CpuFeatures::SSE3)
+            .with(CpuFeatures::SSSE3)
+            .with(CpuFeatures::SSE4_1)
+            .with(CpuFeatures::SSE4_2)
+            .with(CpuFeatures::POPCNT)
+            .with(CpuFeatures::CX16)
+            .with(CpuFeatures::LAHF_LM);
+        let v3 = v2
+            .with(CpuFeatures::AVX)
+            .with(CpuFeatures::AVX2)
+            .with(CpuFeatures::BMI1)
+            .with(CpuFeatures::BMI2)
+            .with(CpuFeatures::F16C)
+            .with(CpuFeatures::FMA)
+            .with(CpuFeatures::LZCNT)
+            .with(CpuFeatures::MOVBE)
+            .with(CpuFeatures::XSAVE);
+
+        match self {
+            MicroarchLevel::Baseline => baseline,
+            MicroarchLevel::V2 => v2,
+            MicroarchLevel::V3 => v3,
+            MicroarchLevel::V4 => v3.with(CpuFeatures::AVX512F),
+        }
+    }
+
+    /// Highest level a feature set satisfies
+    pub const fn detect(features: CpuFeatures) -> Option<Self> {
+        let levels = [MicroarchLevel::V4, MicroarchLevel::V3, MicroarchLevel::V2, MicroarchLevel::Baseline];
+        let mut i = 0;
+        while i < levels.len() {
+            if levels[i].features().missing_from(features).is_empty() {
+                return Some(levels[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+}
+
+impl fmt::Display for MicroarchLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MicroarchLevel::Baseline => write!(f, "x86-64"),
+            MicroarchLevel::V2 => write!(f, "x86-64-v2"),
+            MicroarchLevel::V3 => write!(f, "x86-64-v3"),
+            MicroarchLevel::V4 => write!(f, "x86-64-v4"),
+        }
+    }
+}
+
+/// Storage drivers built into the kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageDrivers(u16);
+
+impl StorageDrivers {
+    pub const NONE: StorageDrivers = StorageDrivers(0);
+    pub const AHCI: StorageDrivers = StorageDrivers(1 << 0);
+    pub const NVME: StorageDrivers = StorageDrivers(1 << 1);
+    pub const USB_STORAGE: StorageDrivers = StorageDrivers(1 << 2);
+    pub const SDHCI: StorageDrivers = StorageDrivers(1 << 3);
+    pub const ATAPI: StorageDrivers = StorageDrivers(1 << 4);
+    pub const NETWORK: StorageDrivers = StorageDrivers(1 << 5);
+    pub const RAMDISK: StorageDrivers = StorageDrivers(1 << 6);
+    pub const ALL: StorageDrivers = StorageDrivers(0x7F);
+
+    /// Combine with another set
+    pub const fn with(self, other: StorageDrivers) -> Self {
+        StorageDrivers(self.0 | other.0)
+    }
+
+    /// Check that every driver in `drivers` is present
+    pub const fn has(&self, drivers: StorageDrivers) -> bool {
+        self.0 & drivers.0 == drivers.0
+    }
+
+    /// Driver needed for a device type (`None` if the type is unknown)
+    pub const fn for_device(device: StorageType) -> Option<Self> {
+        match device {
+            StorageType::Hdd | StorageType::Ssd => Some(Self::AHCI),
+            StorageType::Nvme => Some(Self::NVME),
+            StorageType::UsbFlash => Some(Self::USB_STORAGE),
+            StorageType::MemoryCard => Some(Self::SDHCI),
+            StorageType::Optical => Some(Self::ATAPI),
+            StorageType::Network => Some(Self::NETWORK),
+            StorageType::RamDisk => Some(Self::RAMDISK),
+            StorageType::Unknown => None,
+        }
+    }
+}
+
 /// Hardware requirements
 #[derive(Debug, Clone, Copy)]
 pub struct HardwareRequirements {
@@ -898,6 +1122,12 @@ pub struct HardwareRequirements {
     pub tpm_required: bool,
     /// Secure boot required
     pub secure_boot_required: bool,
+    /// Microarchitecture level the kernel is built for
+    pub cpu_level: Option<MicroarchLevel>,
+    /// Required firmware tables
+    pub firmware_tables: FirmwareTables,
+    /// Storage drivers available for the root device
+    pub storage_drivers: StorageDrivers,
 }
 
 impl Default for HardwareRequirements {
@@ -916,7 +1146,115 @@ fn default() -> Self {
             storage_required: 0,
             tpm_required: false,
             secure_boot_required: false,
+            cpu_level: None,
+            firmware_tables: FirmwareTables::NONE,
+            storage_drivers: StorageDrivers::ALL,
+        }
+    }
+}
+
+impl HardwareRequirements {
+    /// Requirements of a kernel built for `level`: the level's
+    /// instructions, NX and ACPI
+    pub fn for_level(level: MicroarchLevel) -> Self {
+        let mut requirements = Self {
+            cpu_level: Some(level),
+            firmware_tables: FirmwareTables::ACPI,
+            ..Self::default()
+        };
+        requirements.require(CpuFeature::Nx);
+        requirements
+    }
+
+    /// Add a required CPU feature
+    pub fn require(&mut self, feature: CpuFeature) -> bool {
+        if self.feature_count >= self.cpu_features.len() {
+            return false;
+        }
+        self.cpu_features[self.feature_count] = feature;
+        self.feature_count += 1;
+        true
+    }
+
+    /// All required CPU features
+    pub fn required_cpu_features(&self) -> CpuFeatures {
+        let mut required = match self.cpu_level {
+            Some(level) => level.features(),
+            None => CpuFeatures::NONE,
+        };
+        for feature in &self.cpu_features[..self.feature_count] {
+            required.set(feature.flag());
+        }
+        required
+    }
+
+    /// Check a system against the requirements
+    ///
+    /// Memory and core counts of zero, and a storage summary without
+    /// devices, mean "not measured" and are skipped.
+    pub fn check(&self, system: &SystemSummary) -> CompatibilityReport {
+        let mut report = CompatibilityReport::new(self.cpu_level);
+
+        // NX gets its own entry: it is usually a firmware setting, not a
+        // missing instruction
+        let missing = self.required_cpu_features().missing_from(system.cpu.features);
+        if missing.has(CpuFeatures::NX) {
+            report.push(CompatibilityIssue::NxDisabled);
+        }
+        let missing = missing.missing_from(CpuFeatures::NX);
+        if !missing.is_empty() {
+            report.push(CompatibilityIssue::MissingCpuFeatures(missing));
+        }
+
+        let cores = system.cpu.logical_cpus;
+        if cores != 0 && cores < self.min_cores as u16 {
+            report.push(CompatibilityIssue::TooFewCores {
+                actual: cores,
+                required: self.min_cores as u16,
+            });
+        }
+
+        let memory = system.memory.total_physical;
+        if memory != 0 && memory < self.min_memory {
+            report.push(CompatibilityIssue::InsufficientMemory {
+                actual_mb: memory / (1024 * 1024),
+                required_mb: self.min_memory / (1024 * 1024),
+            });
+        } else if memory != 0 && memory < self.recommended_memory {
+            report.push(CompatibilityIssue::LowMemory {
+                actual_mb: memory / (1024 * 1024),
+                recommended_mb: self.recommended_memory / (1024 * 1024),
+            });
+        }
+
+        for table in FirmwareTables::ALL {
+            if self.firmware_tables.has(table) && !system.firmware.tables.has(table) {
+                report.push(CompatibilityIssue::MissingFirmwareTable(table));
+            }
+        }
+
+        if system.storage.device_count == 0 {
+            // Storage not enumerated yet
+        } else if !system.storage.boot_device_found {
+            report.push(CompatibilityIssue::NoRootDevice);
+        } else {
+            let device = system.storage.boot_device_type;
+            match StorageDrivers::for_device(device) {
+                Some(driver) if !self.storage_drivers.has(driver) => {
+                    report.push(CompatibilityIssue::UnsupportedRootDevice(device));
+                }
+                Some(_) => {}
+                None => {
+                    report.push(CompatibilityIssue::UnknownRootDevice);
+                }
+            }
+        }
+
+        if self.secure_boot_required && !system.firmware.secure_boot {
+            report.push(CompatibilityIssue::SecureBootDisabled);
         }
+
+        report
     }
 }
 
@@ -951,6 +1289,219 @@ pub struct HardwareValidationResult {
     pub secure_boot_ok: bool,
 }
 
+// =============================================================================
+// COMPATIBILITY CHECK
+// =============================================================================
+
+/// Reason a system cannot (or should not) boot the kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompatibilityIssue {
+    /// CPU lacks instructions the kernel is compiled for
+    MissingCpuFeatures(CpuFeatures),
+    /// NX is missing or disabled in firmware
+    NxDisabled,
+    /// Fewer logical CPUs than required
+    TooFewCores {
+        /// Detected
+        actual: u16,
+        /// Required
+        required: u16,
+    },
+    /// Less memory than required
+    InsufficientMemory {
+        /// Installed (MB)
+        actual_mb: u64,
+        /// Required (MB)
+        required_mb: u64,
+    },
+    /// Less memory than recommended (warning)
+    LowMemory {
+        /// Installed (MB)
+        actual_mb: u64,
+        /// Recommended (MB)
+        recommended_mb: u64,
+    },
+    /// Required firmware table not published
+    MissingFirmwareTable(FirmwareTables),
+    /// Root device not found
+    NoRootDevice,
+    /// Kernel has no driver for the root device
+    UnsupportedRootDevice(StorageType),
+    /// Root device type unknown (warning)
+    UnknownRootDevice,
+    /// Secure Boot required but disabled
+    SecureBootDisabled,
+}
+
+impl CompatibilityIssue {
+    /// Check if the issue prevents booting
+    pub const fn is_fatal(&self) -> bool {
+        !matches!(self, CompatibilityIssue::LowMemory { .. } | CompatibilityIssue::UnknownRootDevice)
+    }
+
+    /// What the user can do about it
+    pub const fn advice(&self) -> &'static str {
+        match self {
+            CompatibilityIssue::MissingCpuFeatures(_) => {
+                "Boot a kernel built for an older x86-64 level, or pass the host CPU model through to the virtual machine"
+            }
+            CompatibilityIssue::NxDisabled => {
+                "Enable \"NX\", \"XD\" or \"Execute Disable\" in firmware setup"
+            }
+            CompatibilityIssue::TooFewCores { .. } => {
+                "Enable the disabled cores in firmware setup or give the virtual machine more CPUs"
+            }
+            CompatibilityIssue::InsufficientMemory { .. } => {
+                "Install more memory or give the virtual machine more RAM"
+            }
+            CompatibilityIssue::LowMemory { .. } => {
+                "The system will boot but may run slowly"
+            }
+            CompatibilityIssue::MissingFirmwareTable(_) => {
+                "Enable the table in firmware setup or update the firmware"
+            }
+            CompatibilityIssue::NoRootDevice => {
+                "Check the root= option and that the disk is connected and visible in firmware setup"
+            }
+            CompatibilityIssue::UnsupportedRootDevice(_) => {
+                "Boot a kernel with a driver for this controller, or switch the controller mode (e.g. RAID to AHCI) in firmware setup"
+            }
+            CompatibilityIssue::UnknownRootDevice => {
+                "Boot may fail later if the kernel has no driver for this device"
+            }
+            CompatibilityIssue::SecureBootDisabled => {
+                "Enable Secure Boot in firmware setup"
+            }
+        }
+    }
+}
+
+impl fmt::Display for CompatibilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompatibilityIssue::MissingCpuFeatures(missing) => {
+                write!(f, "CPU lacks required instructions:")?;
+                for feature in CpuFeature::ALL {
+                    if missing.has(feature.flag()) {
+                        write!(f, " {}", feature.name())?;
+                    }
+                }
+                Ok(())
+            }
+            CompatibilityIssue::NxDisabled => {
+                write!(f, "No-execute (NX) protection is unavailable")
+            }
+            CompatibilityIssue::TooFewCores { actual, required } => {
+                write!(f, "{} CPU(s) found, {} required", actual, required)
+            }
+            CompatibilityIssue::InsufficientMemory { actual_mb, required_mb } => {
+                write!(f, "{} MB of memory, {} MB required", actual_mb, required_mb)
+            }
+            CompatibilityIssue::LowMemory { actual_mb, recommended_mb } => {
+                write!(f, "{} MB of memory, {} MB recommended", actual_mb, recommended_mb)
+            }
+            CompatibilityIssue::MissingFirmwareTable(table) => {
+                write!(f, "Firmware does not provide the {} table", table.name())
+            }
+            CompatibilityIssue::NoRootDevice => write!(f, "Root device not found"),
+            CompatibilityIssue::UnsupportedRootDevice(device) => {
+                write!(f, "Kernel has no driver for the {} root device", device)
+            }
+            CompatibilityIssue::UnknownRootDevice => {
+                write!(f, "Root device type could not be identified")
+            }
+            CompatibilityIssue::SecureBootDisabled => {
+                write!(f, "Secure Boot is required but disabled")
+            }
+        }
+    }
+}
+
+/// Maximum compatibility issues
+pub const MAX_COMPAT_ISSUES: usize = 16;
+
+/// Result of a pre-boot compatibility check
+#[derive(Debug, Clone, Copy)]
+pub struct CompatibilityReport {
+    /// Level the kernel is built for
+    pub cpu_level: Option<MicroarchLevel>,
+    /// Issues found
+    issues: [CompatibilityIssue; MAX_COMPAT_ISSUES],
+    /// Issue count
+    issue_count: usize,
+}
+
+impl CompatibilityReport {
+    /// Create empty report
+    pub const fn new(cpu_level: Option<MicroarchLevel>) -> Self {
+        Self {
+            cpu_level,
+            issues: [CompatibilityIssue::NoRootDevice; MAX_COMPAT_ISSUES],
+            issue_count: 0,
+        }
+    }
+
+    /// Add issue
+    pub fn push(&mut self, issue: CompatibilityIssue) -> bool {
+        if self.issue_count >= MAX_COMPAT_ISSUES {
+            return false;
+        }
+        self.issues[self.issue_count] = issue;
+        self.issue_count += 1;
+        true
+    }
+
+    /// Issues found
+    pub fn issues(&self) -> &[CompatibilityIssue] {
+        &self.issues[..self.issue_count]
+    }
+
+    /// Count issues that prevent booting
+    pub fn fatal_count(&self) -> usize {
+        self.issues().iter().filter(|i| i.is_fatal()).count()
+    }
+
+    /// Count warnings
+    pub fn warning_count(&self) -> usize {
+        self.issue_count - self.fatal_count()
+    }
+
+    /// Check if the kernel may be started
+    pub fn can_boot(&self) -> bool {
+        self.fatal_count() == 0
+    }
+
+    /// Summarize as a validation result
+    pub fn result(&self) -> ValidationResult {
+        if !self.can_boot() {
+            ValidationResult::invalid(self.fatal_count() as u32)
+        } else if self.issue_count > 0 {
+            ValidationResult::warning(self.issue_count as u16)
+        } else {
+            ValidationResult::valid()
+        }
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cpu_level {
+            Some(level) => writeln!(f, "Hardware compatibility (kernel built for {})", level)?,
+            None => writeln!(f, "Hardware compatibility")?,
+        }
+        for issue in self.issues() {
+            let tag = if issue.is_fatal() { "FAIL" } else { "WARN" };
+            writeln!(f, "  {}  {}", tag, issue)?;
+            writeln!(f, "        {}", issue.advice())?;
+        }
+        if self.can_boot() {
+            writeln!(f, "Compatible ({} warning(s))", self.warning_count())
+        } else {
+            writeln!(f, "Boot refused: {} problem(s) must be fixed", self.fatal_count())
+        }
+    }
+}
+
 // =============================================================================
 // VALIDATION SUITE
 // =============================================================================
@@ -1116,6 +1667,73 @@ fn test_pre_boot_validation() {
         assert!(validation.can_continue());
     }
 
+    #[test]
+    fn test_microarch_level() {
+        let v2 = MicroarchLevel::V2.features();
+        assert!(v2.has(CpuFeatures::POPCNT));
+        assert!(!v2.has(CpuFeatures::AVX2));
+        assert!(MicroarchLevel::V3.features().has(CpuFeatures::SSE4_2));
+
+        assert_eq!(MicroarchLevel::detect(v2), Some(MicroarchLevel::V2));
+        assert_eq!(MicroarchLevel::detect(CpuFeatures::SSE), None);
+    }
+
+    #[test]
+    fn test_compatibility_check() {
+        let requirements = HardwareRequirements::for_level(MicroarchLevel::V2);
+
+        let mut system = SystemSummary::default();
+        system.cpu.features = MicroarchLevel::V2.features().with(CpuFeatures::NX);
+        system.cpu.logical_cpus = 4;
+        system.memory.total_physical = 1024 * 1024 * 1024;
+        system.firmware.tables = FirmwareTables::ACPI.with(FirmwareTables::SMBIOS);
+        system.storage.device_count = 1;
+        system.storage.boot_device_found = true;
+        system.storage.boot_device_type = StorageType::Nvme;
+
+        let report = requirements.check(&system);
+        assert!(report.can_boot());
+        assert!(report.issues().is_empty());
+        assert!(report.result().passed());
+
+        // Baseline CPU with NX disabled, no ACPI, small RAM
+        system.cpu.features = MicroarchLevel::Baseline.features();
+        system.memory.total_physical = 128 * 1024 * 1024;
+        system.firmware.tables = FirmwareTables::SMBIOS;
+        let report = requirements.check(&system);
+        assert!(!report.can_boot());
+        assert_eq!(report.issues()[0], CompatibilityIssue::NxDisabled);
+        assert!(matches!(
+            report.issues()[1],
+            CompatibilityIssue::MissingCpuFeatures(missing)
+                if missing.has(CpuFeatures::POPCNT) && !missing.has(CpuFeatures::NX)
+        ));
+        assert_eq!(report.fatal_count(), 3);
+        assert_eq!(report.warning_count(), 1);
+        assert!(report.result().failed());
+    }
+
+    #[test]
+    fn test_root_device_driver() {
+        let mut requirements = HardwareRequirements::default();
+        requirements.storage_drivers = StorageDrivers::AHCI.with(StorageDrivers::USB_STORAGE);
+
+        let mut system = SystemSummary::default();
+        assert!(requirements.check(&system).issues().is_empty());
+
+        system.storage.device_count = 2;
+        let report = requirements.check(&system);
+        assert_eq!(report.issues(), &[CompatibilityIssue::NoRootDevice]);
+
+        system.storage.boot_device_found = true;
+        system.storage.boot_device_type = StorageType::Nvme;
+        let report = requirements.check(&system);
+        assert_eq!(report.issues(), &[CompatibilityIssue::UnsupportedRootDevice(StorageType::Nvme)]);
+
+        system.storage.boot_device_type = StorageType::Unknown;
+        assert!(requirements.check(&system).can_boot());
+    }
+
     #[test]
     fn test_validation_suite() {
         let mut suite = ValidationSuite::new();
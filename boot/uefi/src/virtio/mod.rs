@@ -802,6 +802,91 @@ pub mod mmio {
     pub const CONFIG: usize = 0x100;
 }
 
+crate::register_block! {
+    /// VirtIO MMIO transport register map
+    ///
+    /// Device-specific configuration follows at [`mmio::CONFIG`]; read it
+    /// with [`VirtioMmioRegisters::config`].
+    pub struct VirtioMmioRegisters, size = 0x200 {
+        /// Magic value (0x74726976, "virt")
+        0x000 => magic_value: u32, ReadOnly;
+        /// Version (1 for legacy, 2 for modern)
+        0x004 => version: u32, ReadOnly;
+        /// Device type
+        0x008 => device_id: u32, ReadOnly;
+        /// Vendor ID
+        0x00C => vendor_id: u32, ReadOnly;
+        /// Device features
+        0x010 => device_features: u32, ReadOnly;
+        /// Device features select
+        0x014 => device_features_sel: u32, WriteOnly;
+        /// Driver features
+        0x020 => driver_features: u32, WriteOnly;
+        /// Driver features select
+        0x024 => driver_features_sel: u32, WriteOnly;
+        /// Queue select
+        0x030 => queue_sel: u32, WriteOnly;
+        /// Maximum queue size
+        0x034 => queue_num_max: u32, ReadOnly;
+        /// Queue size
+        0x038 => queue_num: u32, WriteOnly;
+        /// Queue ready
+        0x044 => queue_ready: u32, ReadWrite;
+        /// Queue notify
+        0x050 => queue_notify: u32, WriteOnly;
+        /// Interrupt status
+        0x060 => interrupt_status: u32, ReadOnly;
+        /// Interrupt acknowledge
+        0x064 => interrupt_ack: u32, WriteOnly;
+        /// Device status
+        0x070 => status: u32, ReadWrite;
+        /// Queue descriptor low
+        0x080 => queue_desc_low: u32, WriteOnly;
+        /// Queue descriptor high
+        0x084 => queue_desc_high: u32, WriteOnly;
+        /// Queue driver low
+        0x090 => queue_driver_low: u32, WriteOnly;
+        /// Queue driver high
+        0x094 => queue_driver_high: u32, WriteOnly;
+        /// Queue device low
+        0x0A0 => queue_device_low: u32, WriteOnly;
+        /// Queue device high
+        0x0A4 => queue_device_high: u32, WriteOnly;
+        /// Queue reset
+        0x0C0 => queue_reset: u32, ReadWrite;
+    }
+}
+
+impl VirtioMmioRegisters {
+    /// Check the magic value and version
+    pub fn probe(&self) -> Result<u32, VirtioError> {
+        if self.magic_value().read() != VIRTIO_MMIO_MAGIC {
+            return Err(VirtioError::InvalidMagic);
+        }
+        match self.version().read() {
+            version @ 1..=2 => Ok(version),
+            _ => Err(VirtioError::UnsupportedVersion),
+        }
+    }
+
+    /// Read device-specific configuration at `offset`
+    pub fn config<T: crate::mmio::RegisterValue>(&self, offset: usize) -> Result<T, VirtioError> {
+        self.region()
+            .read(mmio::CONFIG + offset)
+            .map_err(|_| VirtioError::InvalidParameter)
+    }
+
+    /// Program the addresses of the selected queue
+    pub fn set_queue_addresses(&self, desc: u64, driver: u64, device: u64) {
+        self.queue_desc_low().write(desc as u32);
+        self.queue_desc_high().write((desc >> 32) as u32);
+        self.queue_driver_low().write(driver as u32);
+        self.queue_driver_high().write((driver >> 32) as u32);
+        self.queue_device_low().write(device as u32);
+        self.queue_device_high().write((device >> 32) as u32);
+    }
+}
+
 /// VirtIO MMIO interrupt status bits
 pub mod mmio_int {
     /// Used buffer notification
@@ -993,6 +1078,31 @@ pub const fn capacity_mb(&self) -> u64 {
     pub const fn capacity_gb(&self) -> u64 {
         self.capacity_bytes() / (1024 * 1024 * 1024)
     }
+
+    /// Fill `out` with discard segments covering `count` sectors from
+    /// `sector`, honoring max_discard_sectors, max_discard_seg and the
+    /// discard alignment; returns segments used
+    ///
+    /// Unaligned head and tail sectors are skipped, as discard is a hint.
+    pub fn discard_segments(&self, sector: u64, count: u64, out: &mut [VirtioBlkDiscardSegment]) -> usize {
+        let align = self.discard_sector_alignment.max(1) as u64;
+        let max = match self.max_discard_sectors {
+            0 => u32::MAX as u64,
+            n => n as u64,
+        };
+        let limit = (self.max_discard_seg.max(1) as usize).min(out.len());
+
+        let mut start = sector.next_multiple_of(align);
+        let end = (sector + count) / align * align;
+        let mut used = 0;
+        while start < end && used < limit {
+            let len = (end - start).min(max / align * align).max(align);
+            out[used] = VirtioBlkDiscardSegment::new(start, len as u32);
+            start += len;
+            used += 1;
+        }
+        used
+    }
 }
 
 /// VirtIO block request types
@@ -1056,6 +1166,41 @@ pub const fn flush() -> Self {
             sector: 0,
         }
     }
+
+    /// Create discard request (segments follow the header)
+    pub const fn discard() -> Self {
+        Self {
+            req_type: VirtioBlkReqType::Discard as u32,
+            reserved: 0,
+            sector: 0,
+        }
+    }
+}
+
+/// VirtIO block discard / write zeroes segment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct VirtioBlkDiscardSegment {
+    /// First sector (512-byte units)
+    pub sector: u64,
+    /// Number of sectors
+    pub num_sectors: u32,
+    /// Flags (write zeroes only)
+    pub flags: u32,
+}
+
+impl VirtioBlkDiscardSegment {
+    /// Write zeroes may unmap
+    pub const FLAG_UNMAP: u32 = 1 << 0;
+
+    /// Create segment
+    pub const fn new(sector: u64, num_sectors: u32) -> Self {
+        Self {
+            sector,
+            num_sectors,
+            flags: 0,
+        }
+    }
 }
 
 /// VirtIO block status codes
@@ -1693,6 +1838,25 @@ fn test_block_config() {
         assert_eq!(config.capacity_gb(), 1);
     }
 
+    #[test]
+    fn test_discard_segments() {
+        let mut config: VirtioBlkConfig = unsafe { core::mem::zeroed() };
+        config.max_discard_sectors = 64;
+        config.max_discard_seg = 2;
+        config.discard_sector_alignment = 8;
+
+        let mut segs = [VirtioBlkDiscardSegment::default(); 4];
+        let n = config.discard_segments(3, 200, &mut segs);
+        assert_eq!(n, 2);
+        assert_eq!(segs[0], VirtioBlkDiscardSegment::new(8, 64));
+        assert_eq!(segs[1], VirtioBlkDiscardSegment::new(72, 64));
+
+        // Fully unaligned range discards nothing
+        assert_eq!(config.discard_segments(1, 5, &mut segs), 0);
+        assert_eq!(core::mem::size_of::<VirtioBlkDiscardSegment>(), 16);
+        assert_eq!(VirtioBlkReqHeader::discard().req_type, VirtioBlkReqType::Discard as u32);
+    }
+
     #[test]
     fn test_net_mac_string() {
         let config = VirtioNetConfig {
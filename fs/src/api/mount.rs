@@ -146,6 +146,8 @@ pub struct MountOptions {
     pub cache_mode: CacheMode,
     /// Error behavior
     pub errors: ErrorBehavior,
+    /// Discard of freed extents
+    pub discard: DiscardMode,
     /// Default permissions mode
     pub default_mode: u32,
     /// Default UID
@@ -170,6 +172,7 @@ pub const fn new() -> Self {
             dedup: false,
             cache_mode: CacheMode::Normal,
             errors: ErrorBehavior::Continue,
+            discard: DiscardMode::Off,
             default_mode: 0o755,
             default_uid: 0,
             default_gid: 0,
@@ -201,6 +204,12 @@ pub fn commit_interval(mut self, secs: u32) -> Self {
         self.commit_interval = secs;
         self
     }
+    
+    /// Set discard mode
+    pub fn with_discard(mut self, mode: DiscardMode) -> Self {
+        self.discard = mode;
+        self
+    }
 }
 
 impl Default for MountOptions {
@@ -267,6 +276,37 @@ fn default() -> Self {
     }
 }
 
+/// Discard (TRIM) behavior for freed extents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DiscardMode {
+    /// Never discard
+    Off = 0,
+    /// Discard batched extents at each commit
+    Sync = 1,
+    /// Discard batched extents from a background worker
+    Async = 2,
+}
+
+impl DiscardMode {
+    /// Parse a `discard`, `discard=sync`, `discard=async` or `nodiscard`
+    /// mount option
+    pub fn from_option(opt: &[u8]) -> Option<Self> {
+        match opt {
+            b"nodiscard" => Some(Self::Off),
+            b"discard" | b"discard=async" => Some(Self::Async),
+            b"discard=sync" => Some(Self::Sync),
+            _ => None,
+        }
+    }
+}
+
+impl Default for DiscardMode {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
 // ============================================================================
 // Mount Point
 // ============================================================================
@@ -716,6 +756,18 @@ fn test_mount_options() {
         assert!(opts.compress);
         assert_eq!(opts.compress_algo, CompressionAlgo::Zstd);
         assert_eq!(opts.commit_interval, 10);
+        assert_eq!(opts.discard, DiscardMode::Off);
+        
+        let opts = MountOptions::new().with_discard(DiscardMode::Async);
+        assert_eq!(opts.discard, DiscardMode::Async);
+    }
+    
+    #[test]
+    fn test_discard_option() {
+        assert_eq!(DiscardMode::from_option(b"discard"), Some(DiscardMode::Async));
+        assert_eq!(DiscardMode::from_option(b"discard=sync"), Some(DiscardMode::Sync));
+        assert_eq!(DiscardMode::from_option(b"nodiscard"), Some(DiscardMode::Off));
+        assert_eq!(DiscardMode::from_option(b"discard=bogus"), None);
     }
     
     #[test]
@@ -653,6 +653,20 @@ pub fn write(
         }
     }
     
+    /// Create discard request (no data buffer)
+    pub fn discard(start_block: BlockNum, block_count: u32, request_id: u64) -> Self {
+        Self {
+            req_type: IoRequestType::Discard,
+            priority: IoRequestPriority::Background,
+            start_block,
+            block_count,
+            buffer_ptr: 0,
+            callback_data: 0,
+            request_id,
+            flags: 0,
+        }
+    }
+    
     /// Set priority
     pub fn with_priority(mut self, priority: IoRequestPriority) -> Self {
         self.priority = priority;
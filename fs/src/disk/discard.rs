@@ -0,0 +1,426 @@
+//! Discard (TRIM) of freed extents.
+//!
+//! Freed extents are batched and sent to the device as discards so that
+//! SSDs and thin-provisioned devices can reclaim the space. Extents are
+//! held back from the allocator until their discard has been issued;
+//! otherwise a block could be reallocated and written before a late
+//! discard wipes it.
+//!
+//! Behavior follows the `discard` mount option:
+//!
+//! - `Off`: extents go straight back to the allocator
+//! - `Sync`: the batch is discarded at every transaction commit
+//! - `Async`: a background worker calls [`Discarder::flush`]; a full batch
+//!   is flushed inline
+//!
+//! Extent freeing and snapshot garbage collection (which frees the
+//! exclusive extents of a deleted snapshot) both go through
+//! [`Discarder::free_extent`].
+
+use crate::core::types::*;
+use crate::core::error::HfsResult;
+use crate::alloc::BlockAllocator;
+use crate::api::mount::DiscardMode;
+use crate::disk::device::BlockDiscard;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Maximum distinct ranges held in a batch
+pub const MAX_DISCARD_RANGES: usize = 64;
+
+// ============================================================================
+// Discard Batch
+// ============================================================================
+
+/// A range of blocks to discard.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiscardRange {
+    /// First block
+    pub start: u64,
+    /// Block count
+    pub count: u64,
+}
+
+impl DiscardRange {
+    /// Create range
+    pub const fn new(start: u64, count: u64) -> Self {
+        Self { start, count }
+    }
+
+    /// End block (exclusive)
+    #[inline]
+    pub const fn end(&self) -> u64 {
+        self.start + self.count
+    }
+}
+
+/// Sorted set of pending discard ranges; adjacent ranges are merged.
+pub struct DiscardBatch {
+    /// Ranges, sorted by start
+    ranges: [DiscardRange; MAX_DISCARD_RANGES],
+    /// Ranges in use
+    len: usize,
+}
+
+impl DiscardBatch {
+    /// Create empty batch
+    pub const fn new() -> Self {
+        Self {
+            ranges: [DiscardRange::new(0, 0); MAX_DISCARD_RANGES],
+            len: 0,
+        }
+    }
+
+    /// Pending ranges
+    pub fn ranges(&self) -> &[DiscardRange] {
+        &self.ranges[..self.len]
+    }
+
+    /// Number of ranges
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Check if no further range can be added without merging
+    pub fn is_full(&self) -> bool {
+        self.len == MAX_DISCARD_RANGES
+    }
+
+    /// Total pending blocks
+    pub fn blocks(&self) -> u64 {
+        self.ranges().iter().map(|r| r.count).sum()
+    }
+
+    /// Add a range; returns false if the batch is full and the range
+    /// cannot be merged into a neighbor
+    pub fn insert(&mut self, start: u64, count: u64) -> bool {
+        if count == 0 {
+            return true;
+        }
+        let end = start + count;
+        let pos = self.ranges().partition_point(|r| r.end() < start);
+
+        // Merge with every range that overlaps or touches [start, end)
+        let mut last = pos;
+        while last < self.len && self.ranges[last].start <= end {
+            last += 1;
+        }
+        if last > pos {
+            let first = self.ranges[pos].start.min(start);
+            let merged_end = self.ranges[last - 1].end().max(end);
+            self.ranges[pos] = DiscardRange::new(first, merged_end - first);
+            self.ranges.copy_within(last..self.len, pos + 1);
+            self.len -= last - pos - 1;
+            return true;
+        }
+
+        if self.is_full() {
+            return false;
+        }
+        self.ranges.copy_within(pos..self.len, pos + 1);
+        self.ranges[pos] = DiscardRange::new(start, count);
+        self.len += 1;
+        true
+    }
+
+    /// Drop all ranges
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl Default for DiscardBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// Discarder
+// ============================================================================
+
+/// Discard statistics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DiscardStats {
+    /// Discard commands issued
+    pub discards: u64,
+    /// Blocks discarded
+    pub blocks: u64,
+    /// Discards the device rejected
+    pub errors: u64,
+    /// Batch flushes
+    pub flushes: u64,
+}
+
+/// Batches freed extents and discards them before release.
+pub struct Discarder {
+    /// Mount option in effect
+    mode: DiscardMode,
+    /// Extents waiting for discard
+    batch: DiscardBatch,
+    /// Statistics
+    stats: DiscardStats,
+}
+
+impl Discarder {
+    /// Create discarder for a mount
+    pub const fn new(mode: DiscardMode) -> Self {
+        Self {
+            mode,
+            batch: DiscardBatch::new(),
+            stats: DiscardStats {
+                discards: 0,
+                blocks: 0,
+                errors: 0,
+                flushes: 0,
+            },
+        }
+    }
+
+    /// Current mode
+    pub fn mode(&self) -> DiscardMode {
+        self.mode
+    }
+
+    /// Change mode (remount); pending extents are released first
+    pub fn set_mode<A, D>(&mut self, mode: DiscardMode, alloc: &A, device: &D) -> HfsResult<()>
+    where
+        A: BlockAllocator + ?Sized,
+        D: BlockDiscard + ?Sized,
+    {
+        self.flush(alloc, device)?;
+        self.mode = mode;
+        Ok(())
+    }
+
+    /// Statistics
+    pub fn stats(&self) -> DiscardStats {
+        self.stats
+    }
+
+    /// Blocks freed but not yet released to the allocator
+    pub fn pending_blocks(&self) -> u64 {
+        self.batch.blocks()
+    }
+
+    /// Free an extent, discarding it first unless discard is off
+    pub fn free_extent<A, D>(&mut self, alloc: &A, device: &D, start: BlockNum, count: u64) -> HfsResult<()>
+    where
+        A: BlockAllocator + ?Sized,
+        D: BlockDiscard + ?Sized,
+    {
+        if self.mode == DiscardMode::Off {
+            return release(alloc, start.get(), count);
+        }
+        if !self.batch.insert(start.get(), count) {
+            self.flush(alloc, device)?;
+            self.batch.insert(start.get(), count);
+        }
+        Ok(())
+    }
+
+    /// Transaction commit hook; discards the batch in `Sync` mode
+    pub fn commit<A, D>(&mut self, alloc: &A, device: &D) -> HfsResult<()>
+    where
+        A: BlockAllocator + ?Sized,
+        D: BlockDiscard + ?Sized,
+    {
+        match self.mode {
+            DiscardMode::Sync => self.flush(alloc, device),
+            DiscardMode::Off | DiscardMode::Async => Ok(()),
+        }
+    }
+
+    /// Discard every pending range and release it to the allocator
+    ///
+    /// A failed discard is counted but not fatal: discard is only a hint,
+    /// and the blocks are released either way.
+    pub fn flush<A, D>(&mut self, alloc: &A, device: &D) -> HfsResult<()>
+    where
+        A: BlockAllocator + ?Sized,
+        D: BlockDiscard + ?Sized,
+    {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        self.stats.flushes += 1;
+
+        let mut result = Ok(());
+        for range in self.batch.ranges() {
+            match device.discard(BlockNum::new(range.start), range.count) {
+                Ok(()) => {
+                    self.stats.discards += 1;
+                    self.stats.blocks += range.count;
+                }
+                Err(_) => self.stats.errors += 1,
+            }
+            if let Err(e) = release(alloc, range.start, range.count) {
+                result = result.and(Err(e));
+            }
+        }
+        self.batch.clear();
+        result
+    }
+}
+
+/// Return an extent to the allocator in `u32`-sized pieces
+fn release<A: BlockAllocator + ?Sized>(alloc: &A, start: u64, count: u64) -> HfsResult<()> {
+    let mut done = 0;
+    while done < count {
+        let chunk = (count - done).min(u32::MAX as u64);
+        alloc.free(BlockNum::new(start + done), chunk as u32)?;
+        done += chunk;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::{AllocRequest, AllocResult};
+    use crate::core::error::HfsError;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    /// Allocator that counts freed blocks
+    struct CountingAlloc {
+        freed: AtomicU64,
+    }
+
+    impl BlockAllocator for CountingAlloc {
+        fn allocate(&self, _request: &AllocRequest) -> HfsResult<AllocResult> {
+            Err(HfsError::NoSpace)
+        }
+
+        fn free(&self, _start: BlockNum, count: u32) -> HfsResult<()> {
+            self.freed.fetch_add(count as u64, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn free_blocks(&self) -> u64 {
+            self.freed.load(Ordering::Relaxed)
+        }
+
+        fn total_blocks(&self) -> u64 {
+            1 << 20
+        }
+
+        fn is_free(&self, _block: BlockNum) -> bool {
+            false
+        }
+
+        fn reserve(&self, _start: BlockNum, _count: u32) -> HfsResult<()> {
+            Ok(())
+        }
+
+        fn unreserve(&self, _start: BlockNum, _count: u32) -> HfsResult<()> {
+            Ok(())
+        }
+
+        fn sync(&self) -> HfsResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Device that counts discarded blocks
+    struct TrimDevice {
+        discarded: AtomicU64,
+        commands: AtomicU64,
+    }
+
+    impl BlockDiscard for TrimDevice {
+        fn discard(&self, _start: BlockNum, count: u64) -> HfsResult<()> {
+            self.commands.fetch_add(1, Ordering::Relaxed);
+            self.discarded.fetch_add(count, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn secure_erase(&self, _start: BlockNum, _count: u64) -> HfsResult<()> {
+            Err(HfsError::NotSupported)
+        }
+    }
+
+    fn fixtures() -> (CountingAlloc, TrimDevice) {
+        let alloc = CountingAlloc { freed: AtomicU64::new(0) };
+        let dev = TrimDevice {
+            discarded: AtomicU64::new(0),
+            commands: AtomicU64::new(0),
+        };
+        (alloc, dev)
+    }
+
+    #[test]
+    fn test_batch_merge() {
+        let mut batch = DiscardBatch::new();
+        assert!(batch.insert(10, 5));
+        assert!(batch.insert(30, 5));
+        assert!(batch.insert(15, 5)); // touches 10..15
+        assert!(batch.insert(0, 2));
+        assert_eq!(batch.ranges(), &[
+            DiscardRange::new(0, 2),
+            DiscardRange::new(10, 10),
+            DiscardRange::new(30, 5),
+        ]);
+
+        // Bridges the last two ranges
+        assert!(batch.insert(18, 14));
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.ranges()[1], DiscardRange::new(10, 25));
+        assert_eq!(batch.blocks(), 27);
+
+        let mut full = DiscardBatch::new();
+        for i in 0..MAX_DISCARD_RANGES as u64 {
+            assert!(full.insert(i * 10, 1));
+        }
+        assert!(full.is_full());
+        assert!(full.insert(1, 1)); // still mergeable
+        assert!(!full.insert(5, 1));
+    }
+
+    #[test]
+    fn test_discarder_modes() {
+        // Off: freed immediately, never discarded
+        let (alloc, dev) = fixtures();
+        let mut d = Discarder::new(DiscardMode::Off);
+        d.free_extent(&alloc, &dev, BlockNum::new(100), 8).unwrap();
+        assert_eq!(alloc.freed.load(Ordering::Relaxed), 8);
+        assert_eq!(dev.commands.load(Ordering::Relaxed), 0);
+
+        // Sync: held until commit, then discarded as one merged range
+        let (alloc, dev) = fixtures();
+        let mut d = Discarder::new(DiscardMode::Sync);
+        d.free_extent(&alloc, &dev, BlockNum::new(100), 8).unwrap();
+        d.free_extent(&alloc, &dev, BlockNum::new(108), 8).unwrap();
+        assert_eq!(alloc.freed.load(Ordering::Relaxed), 0);
+        assert_eq!(d.pending_blocks(), 16);
+        d.commit(&alloc, &dev).unwrap();
+        assert_eq!(dev.commands.load(Ordering::Relaxed), 1);
+        assert_eq!(dev.discarded.load(Ordering::Relaxed), 16);
+        assert_eq!(alloc.freed.load(Ordering::Relaxed), 16);
+
+        // Async: commit leaves the batch to the worker; a full batch flushes
+        let (alloc, dev) = fixtures();
+        let mut d = Discarder::new(DiscardMode::Async);
+        d.free_extent(&alloc, &dev, BlockNum::new(0), 1).unwrap();
+        d.commit(&alloc, &dev).unwrap();
+        assert_eq!(d.pending_blocks(), 1);
+        for i in 1..=MAX_DISCARD_RANGES as u64 {
+            d.free_extent(&alloc, &dev, BlockNum::new(i * 10), 1).unwrap();
+        }
+        assert_eq!(dev.commands.load(Ordering::Relaxed), MAX_DISCARD_RANGES as u64);
+        assert_eq!(d.pending_blocks(), 1);
+        d.flush(&alloc, &dev).unwrap();
+        assert_eq!(alloc.freed.load(Ordering::Relaxed), MAX_DISCARD_RANGES as u64 + 1);
+        assert_eq!(d.stats().flushes, 2);
+    }
+}
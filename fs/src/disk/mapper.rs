@@ -0,0 +1,784 @@
+//! Device mapper.
+//!
+//! Builds virtual block devices out of ranges of other block devices. A
+//! mapped device is described by a table; each line maps a contiguous range
+//! of its blocks onto a target:
+//!
+//! ```text
+//! <start> <length> linear <device> <offset>
+//! <start> <length> crypt chacha20-plain64 <key hex> <iv offset> <device> <offset>
+//! <start> <length> verity <data device> <hash device> <root hash hex>
+//! ```
+//!
+//! Unlike Linux, starts, lengths and offsets are in 4 KiB blocks rather than
+//! 512-byte sectors. Mapped devices are registered like any other device, so
+//! they can be used by further tables: an encrypted and verified root is a
+//! `verity` table over `crypt` devices over `linear` partitions.
+//!
+//! The crypt key is the volume key, as recovered from a keyslot by the caller.
+
+use crate::core::types::*;
+use crate::core::error::{HfsError, HfsResult};
+use crate::crypto::cipher::ChaCha20Context;
+use crate::crypto::CHACHA20_NONCE_SIZE;
+use crate::disk::device::{BlockRead, BlockWrite, BlockDeviceInfo, BlockDevice};
+use crate::disk::verity::{VerityDevice, RootHash, parse_hex_digest};
+
+use alloc_crate::boxed::Box;
+use alloc_crate::format;
+use alloc_crate::string::{String, ToString};
+use alloc_crate::sync::Arc;
+use alloc_crate::vec::Vec;
+use core::fmt::Write;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Block size of mapped devices
+pub const DM_BLOCK_SIZE: usize = 4096;
+
+/// Maximum device name length
+pub const DM_NAME_MAX: usize = 32;
+
+/// Crypt target cipher
+pub const DM_CRYPT_CIPHER: &str = "chacha20-plain64";
+
+// ============================================================================
+// Shared Devices
+// ============================================================================
+
+/// A named device that tables can refer to.
+#[derive(Clone)]
+pub struct SharedDevice {
+    /// Registered name
+    name: String,
+    /// Underlying device
+    device: Arc<dyn BlockDevice>,
+}
+
+impl SharedDevice {
+    /// Wrap a device under `name`
+    pub fn new(name: &str, device: Arc<dyn BlockDevice>) -> Self {
+        Self { name: name.to_string(), device }
+    }
+
+    /// Registered name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Read `buffer.len()` bytes starting at `block`, failing on short reads
+    fn read_exact(&self, block: u64, buffer: &mut [u8]) -> HfsResult<()> {
+        let count = buffer.len() / DM_BLOCK_SIZE;
+        if self.device.read_blocks(BlockNum::new(block), buffer)? != count {
+            return Err(HfsError::IoReadError);
+        }
+        Ok(())
+    }
+
+    /// Write `buffer` starting at `block`, failing on short writes
+    fn write_exact(&self, block: u64, buffer: &[u8]) -> HfsResult<()> {
+        let count = buffer.len() / DM_BLOCK_SIZE;
+        if self.device.write_blocks(BlockNum::new(block), buffer)? != count {
+            return Err(HfsError::IoWriteError);
+        }
+        Ok(())
+    }
+}
+
+impl BlockRead for SharedDevice {
+    fn read_blocks(&self, start: BlockNum, buffer: &mut [u8]) -> HfsResult<usize> {
+        self.device.read_blocks(start, buffer)
+    }
+}
+
+impl BlockWrite for SharedDevice {
+    fn write_blocks(&self, start: BlockNum, buffer: &[u8]) -> HfsResult<usize> {
+        self.device.write_blocks(start, buffer)
+    }
+
+    fn sync(&self) -> HfsResult<()> {
+        self.device.sync()
+    }
+}
+
+impl BlockDeviceInfo for SharedDevice {
+    fn block_size(&self) -> u32 {
+        self.device.block_size()
+    }
+
+    fn block_count(&self) -> u64 {
+        self.device.block_count()
+    }
+
+    fn is_readonly(&self) -> bool {
+        self.device.is_readonly()
+    }
+
+    fn device_name(&self) -> &[u8] {
+        self.name.as_bytes()
+    }
+}
+
+// ============================================================================
+// Targets
+// ============================================================================
+
+/// A mapping from a range of a mapped device onto underlying storage.
+///
+/// Blocks are relative to the start of the range.
+pub trait DmTarget: Send + Sync {
+    /// Target type as written in tables
+    fn target_type(&self) -> &'static str;
+
+    /// Read whole blocks
+    fn read(&self, block: u64, buffer: &mut [u8]) -> HfsResult<()>;
+
+    /// Write whole blocks
+    fn write(&self, block: u64, buffer: &[u8]) -> HfsResult<()>;
+
+    /// Flush underlying devices
+    fn sync(&self) -> HfsResult<()>;
+
+    /// Target never accepts writes
+    fn is_readonly(&self) -> bool {
+        false
+    }
+
+    /// Target arguments as written in tables
+    fn params(&self) -> String;
+}
+
+/// Maps a range linearly onto another device.
+pub struct LinearTarget {
+    /// Underlying device
+    device: SharedDevice,
+    /// First block on the underlying device
+    offset: u64,
+}
+
+impl LinearTarget {
+    /// Create a linear target
+    pub fn new(device: SharedDevice, offset: u64) -> Self {
+        Self { device, offset }
+    }
+}
+
+impl DmTarget for LinearTarget {
+    fn target_type(&self) -> &'static str {
+        "linear"
+    }
+
+    fn read(&self, block: u64, buffer: &mut [u8]) -> HfsResult<()> {
+        self.device.read_exact(self.offset + block, buffer)
+    }
+
+    fn write(&self, block: u64, buffer: &[u8]) -> HfsResult<()> {
+        self.device.write_exact(self.offset + block, buffer)
+    }
+
+    fn sync(&self) -> HfsResult<()> {
+        self.device.sync()
+    }
+
+    fn is_readonly(&self) -> bool {
+        self.device.is_readonly()
+    }
+
+    fn params(&self) -> String {
+        format!("{} {}", self.device.name(), self.offset)
+    }
+}
+
+/// Encrypts a range with ChaCha20, one keystream per block.
+///
+/// The nonce is the little-endian block number plus `iv_offset`
+/// (`plain64`), so blocks can be read and written independently.
+pub struct CryptTarget {
+    /// Underlying device
+    device: SharedDevice,
+    /// First block on the underlying device
+    offset: u64,
+    /// Added to the block number to form the IV
+    iv_offset: u64,
+    /// Volume key
+    key: [u8; 32],
+}
+
+impl CryptTarget {
+    /// Create a crypt target
+    pub fn new(device: SharedDevice, offset: u64, key: [u8; 32], iv_offset: u64) -> Self {
+        Self { device, offset, iv_offset, key }
+    }
+
+    /// Encrypt or decrypt one block in place
+    fn crypt_block(&self, block: u64, data: &mut [u8]) -> HfsResult<()> {
+        let mut nonce = [0u8; CHACHA20_NONCE_SIZE];
+        nonce[..8].copy_from_slice(&(self.iv_offset + block).to_le_bytes());
+
+        let mut ctx = ChaCha20Context::new();
+        ctx.init(&self.key, &nonce).map_err(|_| HfsError::InvalidKey)?;
+        ctx.crypt(data);
+        Ok(())
+    }
+}
+
+impl DmTarget for CryptTarget {
+    fn target_type(&self) -> &'static str {
+        "crypt"
+    }
+
+    fn read(&self, block: u64, buffer: &mut [u8]) -> HfsResult<()> {
+        self.device.read_exact(self.offset + block, buffer)?;
+        for (i, data) in buffer.chunks_exact_mut(DM_BLOCK_SIZE).enumerate() {
+            self.crypt_block(block + i as u64, data)?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, block: u64, buffer: &[u8]) -> HfsResult<()> {
+        // Never let plaintext reach the device, not even transiently
+        let mut sealed = [0u8; DM_BLOCK_SIZE];
+        for (i, data) in buffer.chunks_exact(DM_BLOCK_SIZE).enumerate() {
+            let block = block + i as u64;
+            sealed.copy_from_slice(data);
+            self.crypt_block(block, &mut sealed)?;
+            self.device.write_exact(self.offset + block, &sealed)?;
+        }
+        sealed.fill(0);
+        Ok(())
+    }
+
+    fn sync(&self) -> HfsResult<()> {
+        self.device.sync()
+    }
+
+    fn is_readonly(&self) -> bool {
+        self.device.is_readonly()
+    }
+
+    fn params(&self) -> String {
+        // The key is never echoed back
+        format!("{} - {} {} {}", DM_CRYPT_CIPHER, self.iv_offset, self.device.name(), self.offset)
+    }
+}
+
+impl Drop for CryptTarget {
+    fn drop(&mut self) {
+        for b in self.key.iter_mut() {
+            // SAFETY: b is a valid, aligned reference
+            unsafe { core::ptr::write_volatile(b, 0) };
+        }
+    }
+}
+
+/// Verifies a range against a hash tree (see [`VerityDevice`]).
+pub struct VerityTarget {
+    /// Verified device
+    device: VerityDevice<SharedDevice, SharedDevice>,
+    /// Data device name
+    data_name: String,
+    /// Hash device name
+    hash_name: String,
+    /// Trusted root hash
+    root: RootHash,
+}
+
+impl VerityTarget {
+    /// Open a verity target; fails if the tree does not match `root`
+    pub fn new(data: SharedDevice, hash: SharedDevice, root: RootHash) -> HfsResult<Self> {
+        let data_name = data.name().to_string();
+        let hash_name = hash.name().to_string();
+        let device = VerityDevice::open(data, hash, &root)?;
+        Ok(Self { device, data_name, hash_name, root })
+    }
+}
+
+impl DmTarget for VerityTarget {
+    fn target_type(&self) -> &'static str {
+        "verity"
+    }
+
+    fn read(&self, block: u64, buffer: &mut [u8]) -> HfsResult<()> {
+        self.device.read_blocks(BlockNum::new(block), buffer).map(|_| ())
+    }
+
+    fn write(&self, _block: u64, _buffer: &[u8]) -> HfsResult<()> {
+        Err(HfsError::ReadOnlyFilesystem)
+    }
+
+    fn sync(&self) -> HfsResult<()> {
+        Ok(())
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn params(&self) -> String {
+        let mut root = String::with_capacity(64);
+        for b in self.root {
+            let _ = write!(root, "{:02x}", b);
+        }
+        format!("{} {} {}", self.data_name, self.hash_name, root)
+    }
+}
+
+// ============================================================================
+// Tables
+// ============================================================================
+
+/// One table line.
+pub struct DmTableEntry {
+    /// First block of the range
+    pub start: u64,
+    /// Length of the range in blocks
+    pub length: u64,
+    /// Target backing the range
+    pub target: Box<dyn DmTarget>,
+}
+
+/// Ordered, gap-free list of target ranges.
+#[derive(Default)]
+pub struct DmTable {
+    /// Entries sorted by start block
+    entries: Vec<DmTableEntry>,
+}
+
+impl DmTable {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a range; it must start where the previous one ended
+    pub fn push(&mut self, start: u64, length: u64, target: Box<dyn DmTarget>) -> HfsResult<()> {
+        if length == 0 || start != self.block_count() {
+            return Err(HfsError::InvalidArgument);
+        }
+        self.entries.push(DmTableEntry { start, length, target });
+        Ok(())
+    }
+
+    /// Total blocks covered
+    pub fn block_count(&self) -> u64 {
+        self.entries.last().map_or(0, |e| e.start + e.length)
+    }
+
+    /// Entries
+    pub fn entries(&self) -> &[DmTableEntry] {
+        &self.entries
+    }
+
+    /// Entry covering `block`
+    fn lookup(&self, block: u64) -> Option<&DmTableEntry> {
+        let index = self.entries.partition_point(|e| e.start + e.length <= block);
+        self.entries.get(index)
+    }
+
+    /// Parse a table, resolving device names in `mapper`; lines are
+    /// separated by newlines or `;`
+    pub fn parse(text: &str, mapper: &DeviceMapper) -> HfsResult<Self> {
+        let mut table = Self::new();
+        for line in text.split(['\n', ';']).map(str::trim).filter(|l| !l.is_empty()) {
+            let mut args = line.split_whitespace();
+            let start = parse_u64(args.next())?;
+            let length = parse_u64(args.next())?;
+            let target_type = args.next().ok_or(HfsError::InvalidArgument)?;
+            let args: Vec<&str> = args.collect();
+
+            let target: Box<dyn DmTarget> = match (target_type, args.as_slice()) {
+                ("linear", [device, offset]) => {
+                    let device = mapper.get(device)?;
+                    let offset = parse_u64(Some(offset))?;
+                    check_range(&device, offset, length)?;
+                    Box::new(LinearTarget::new(device, offset))
+                }
+                ("crypt", [cipher, key, iv_offset, device, offset]) => {
+                    if *cipher != DM_CRYPT_CIPHER {
+                        return Err(HfsError::NotSupported);
+                    }
+                    let key = parse_hex_digest(key.as_bytes()).ok_or(HfsError::InvalidKey)?;
+                    let device = mapper.get(device)?;
+                    let offset = parse_u64(Some(offset))?;
+                    check_range(&device, offset, length)?;
+                    Box::new(CryptTarget::new(device, offset, key, parse_u64(Some(iv_offset))?))
+                }
+                ("verity", [data, hash, root]) => {
+                    let root = parse_hex_digest(root.as_bytes()).ok_or(HfsError::InvalidArgument)?;
+                    let target = VerityTarget::new(mapper.get(data)?, mapper.get(hash)?, root)?;
+                    if target.device.block_count() < length {
+                        return Err(HfsError::InvalidBlockNumber);
+                    }
+                    Box::new(target)
+                }
+                ("linear" | "crypt" | "verity", _) => return Err(HfsError::InvalidArgument),
+                _ => return Err(HfsError::NotSupported),
+            };
+            table.push(start, length, target)?;
+        }
+
+        if table.entries.is_empty() {
+            return Err(HfsError::InvalidArgument);
+        }
+        Ok(table)
+    }
+
+    /// Render the table in the format accepted by [`DmTable::parse`]
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for entry in &self.entries {
+            let _ = writeln!(
+                text,
+                "{} {} {} {}",
+                entry.start,
+                entry.length,
+                entry.target.target_type(),
+                entry.target.params()
+            );
+        }
+        text
+    }
+}
+
+/// Parse a decimal table field
+fn parse_u64(field: Option<&str>) -> HfsResult<u64> {
+    field.and_then(|f| f.parse().ok()).ok_or(HfsError::InvalidArgument)
+}
+
+/// Check that `length` blocks at `offset` fit on `device`
+fn check_range(device: &SharedDevice, offset: u64, length: u64) -> HfsResult<()> {
+    if device.block_size() as usize != DM_BLOCK_SIZE {
+        return Err(HfsError::NotSupported);
+    }
+    match offset.checked_add(length) {
+        Some(end) if end <= device.block_count() => Ok(()),
+        _ => Err(HfsError::InvalidBlockNumber),
+    }
+}
+
+// ============================================================================
+// Mapped Devices
+// ============================================================================
+
+/// Block device assembled from a table.
+pub struct MappedDevice {
+    /// Device name
+    name: String,
+    /// Mapping table
+    table: DmTable,
+}
+
+impl MappedDevice {
+    /// Create a mapped device
+    pub fn new(name: &str, table: DmTable) -> Self {
+        Self { name: name.to_string(), table }
+    }
+
+    /// Mapping table
+    pub fn table(&self) -> &DmTable {
+        &self.table
+    }
+
+    /// Split a request at target boundaries
+    fn for_each_range(
+        &self,
+        start: BlockNum,
+        len: usize,
+        mut f: impl FnMut(&DmTableEntry, u64, core::ops::Range<usize>) -> HfsResult<()>,
+    ) -> HfsResult<usize> {
+        let count = (len / DM_BLOCK_SIZE) as u64;
+        if start.get() + count > self.table.block_count() {
+            return Err(HfsError::InvalidBlockNumber);
+        }
+
+        let mut block = start.get();
+        let end = block + count;
+        while block < end {
+            let entry = self.table.lookup(block).ok_or(HfsError::InvalidBlockNumber)?;
+            let run = (entry.start + entry.length).min(end) - block;
+            let offset = ((block - start.get()) as usize) * DM_BLOCK_SIZE;
+            f(entry, block - entry.start, offset..offset + run as usize * DM_BLOCK_SIZE)?;
+            block += run;
+        }
+        Ok(count as usize)
+    }
+}
+
+impl BlockRead for MappedDevice {
+    fn read_blocks(&self, start: BlockNum, buffer: &mut [u8]) -> HfsResult<usize> {
+        self.for_each_range(start, buffer.len(), |entry, block, range| {
+            entry.target.read(block, &mut buffer[range])
+        })
+    }
+}
+
+impl BlockWrite for MappedDevice {
+    fn write_blocks(&self, start: BlockNum, buffer: &[u8]) -> HfsResult<usize> {
+        if self.is_readonly() {
+            return Err(HfsError::ReadOnlyFilesystem);
+        }
+        self.for_each_range(start, buffer.len(), |entry, block, range| {
+            entry.target.write(block, &buffer[range])
+        })
+    }
+
+    fn sync(&self) -> HfsResult<()> {
+        self.table.entries.iter().try_for_each(|e| e.target.sync())
+    }
+}
+
+impl BlockDeviceInfo for MappedDevice {
+    fn block_size(&self) -> u32 {
+        DM_BLOCK_SIZE as u32
+    }
+
+    fn block_count(&self) -> u64 {
+        self.table.block_count()
+    }
+
+    fn is_readonly(&self) -> bool {
+        self.table.entries.iter().any(|e| e.target.is_readonly())
+    }
+
+    fn device_name(&self) -> &[u8] {
+        self.name.as_bytes()
+    }
+}
+
+impl BlockDevice for MappedDevice {}
+
+// ============================================================================
+// Device Mapper
+// ============================================================================
+
+/// Registered device.
+struct DmEntry {
+    /// Device handle
+    device: SharedDevice,
+    /// Set for devices created from a table
+    mapped: Option<Arc<MappedDevice>>,
+}
+
+/// Registry of devices that tables can refer to.
+#[derive(Default)]
+pub struct DeviceMapper {
+    /// Devices by name, in registration order
+    devices: Vec<DmEntry>,
+}
+
+impl DeviceMapper {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.devices.iter().position(|d| d.device.name() == name)
+    }
+
+    fn check_name(&self, name: &str) -> HfsResult<()> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(HfsError::InvalidArgument);
+        }
+        if name.len() > DM_NAME_MAX {
+            return Err(HfsError::NameTooLong);
+        }
+        if self.find(name).is_some() {
+            return Err(HfsError::Busy);
+        }
+        Ok(())
+    }
+
+    /// Make a disk, partition or other device available to tables
+    pub fn register(&mut self, name: &str, device: Arc<dyn BlockDevice>) -> HfsResult<()> {
+        self.check_name(name)?;
+        self.devices.push(DmEntry { device: SharedDevice::new(name, device), mapped: None });
+        Ok(())
+    }
+
+    /// Look up a device by name
+    pub fn get(&self, name: &str) -> HfsResult<SharedDevice> {
+        self.find(name).map(|i| self.devices[i].device.clone()).ok_or(HfsError::NotFound)
+    }
+
+    /// Create a mapped device from table text
+    pub fn create(&mut self, name: &str, table: &str) -> HfsResult<Arc<MappedDevice>> {
+        self.check_name(name)?;
+        let table = DmTable::parse(table, self)?;
+        let mapped = Arc::new(MappedDevice::new(name, table));
+        let device = SharedDevice::new(name, mapped.clone());
+        self.devices.push(DmEntry { device, mapped: Some(mapped.clone()) });
+        Ok(mapped)
+    }
+
+    /// Remove a device; fails while a table or other user still holds it
+    pub fn remove(&mut self, name: &str) -> HfsResult<()> {
+        let index = self.find(name).ok_or(HfsError::NotFound)?;
+        let entry = &self.devices[index];
+        // One reference from the registry, one more for mapped devices
+        let owned = 1 + entry.mapped.is_some() as usize;
+        if Arc::strong_count(&entry.device.device) > owned {
+            return Err(HfsError::Busy);
+        }
+        self.devices.remove(index);
+        Ok(())
+    }
+
+    /// Mapped device by name
+    pub fn mapped(&self, name: &str) -> HfsResult<Arc<MappedDevice>> {
+        let index = self.find(name).ok_or(HfsError::NotFound)?;
+        self.devices[index].mapped.clone().ok_or(HfsError::InvalidArgument)
+    }
+
+    /// Names of mapped devices
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.devices.iter().filter(|d| d.mapped.is_some()).map(|d| d.device.name())
+    }
+}
+
+// ============================================================================
+// dmsetup
+// ============================================================================
+
+/// Usage of [`dmsetup`]
+pub const DMSETUP_USAGE: &str = "usage: dmsetup create <name> <table>\n\
+    \x20      dmsetup remove <name>\n\
+    \x20      dmsetup table <name>\n\
+    \x20      dmsetup ls";
+
+/// Run a `dmsetup`-style command and return its output.
+///
+/// Table lines given on the command line are separated by `;`. Shells
+/// register this as their `dmsetup` command.
+pub fn dmsetup(mapper: &mut DeviceMapper, args: &[&str]) -> Result<String, String> {
+    let describe = |err: HfsError| format!("dmsetup: {:?}", err);
+    match args {
+        ["create", name, table @ ..] if !table.is_empty() => {
+            let device = mapper.create(name, &table.join(" ")).map_err(describe)?;
+            Ok(format!("{}: {} blocks\n", name, device.block_count()))
+        }
+        ["remove", name] => mapper.remove(name).map(|_| String::new()).map_err(describe),
+        ["table", name] => mapper.mapped(name).map(|d| d.table().to_text()).map_err(describe),
+        ["ls"] => {
+            let mut out = String::new();
+            for name in mapper.names() {
+                let _ = writeln!(out, "{}", name);
+            }
+            Ok(out)
+        }
+        _ => Err(DMSETUP_USAGE.to_string()),
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disk::device::MemoryBlockDevice;
+    use crate::disk::verity::VerityBuilder;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    /// Memory device backed by a leaked buffer
+    fn memory(blocks: usize) -> Arc<dyn BlockDevice> {
+        let buffer = Box::leak(alloc_crate::vec![0u8; blocks * DM_BLOCK_SIZE].into_boxed_slice());
+        // SAFETY: the buffer is leaked and lives forever
+        Arc::new(unsafe { MemoryBlockDevice::from_buffer(buffer.as_mut_ptr(), buffer.len(), 4096) })
+    }
+
+    #[test]
+    fn test_linear_concat() {
+        let mut dm = DeviceMapper::new();
+        dm.register("sda", memory(4)).unwrap();
+        dm.register("sdb", memory(4)).unwrap();
+        let dev = dm.create("vol", "0 3 linear sda 1; 3 2 linear sdb 0").unwrap();
+        assert_eq!(dev.block_count(), 5);
+
+        let mut data = [0u8; 5 * DM_BLOCK_SIZE];
+        for (i, block) in data.chunks_exact_mut(DM_BLOCK_SIZE).enumerate() {
+            block.fill(i as u8 + 1);
+        }
+        assert_eq!(dev.write_blocks(BlockNum::new(0), &data).unwrap(), 5);
+
+        let mut block = [0u8; DM_BLOCK_SIZE];
+        dm.get("sdb").unwrap().read_block(BlockNum::new(1), &mut block).unwrap();
+        assert_eq!(block[0], 5);
+
+        let mut back = [0u8; 2 * DM_BLOCK_SIZE];
+        dev.read_blocks(BlockNum::new(2), &mut back).unwrap();
+        assert_eq!((back[0], back[DM_BLOCK_SIZE]), (3, 4));
+
+        assert!(dm.create("bad", "0 4 linear sda 1").is_err());
+        assert!(dm.create("gap", "0 1 linear sda 0; 2 1 linear sdb 0").is_err());
+    }
+
+    #[test]
+    fn test_crypt_stacked_on_linear() {
+        let mut dm = DeviceMapper::new();
+        dm.register("sda", memory(8)).unwrap();
+        dm.create("part", "0 4 linear sda 4").unwrap();
+        let table = format!("0 4 crypt chacha20-plain64 {} 0 part 0", KEY);
+        let dev = dm.create("root", &table).unwrap();
+
+        let plain = [0x5Au8; DM_BLOCK_SIZE];
+        dev.write_block(BlockNum::new(1), &plain).unwrap();
+
+        let mut raw = [0u8; DM_BLOCK_SIZE];
+        dm.get("sda").unwrap().read_block(BlockNum::new(5), &mut raw).unwrap();
+        assert_ne!(raw, plain);
+
+        let mut back = [0u8; DM_BLOCK_SIZE];
+        dev.read_block(BlockNum::new(1), &mut back).unwrap();
+        assert_eq!(back, plain);
+
+        // Layers in use cannot go away
+        assert_eq!(dm.remove("part"), Err(HfsError::Busy));
+        drop(dev);
+        dm.remove("root").unwrap();
+        dm.remove("part").unwrap();
+    }
+
+    #[test]
+    fn test_verity_target() {
+        let mut dm = DeviceMapper::new();
+        dm.register("data", memory(4)).unwrap();
+        dm.register("hash", memory(2)).unwrap();
+        let (data, hash) = (dm.get("data").unwrap(), dm.get("hash").unwrap());
+        data.write_block(BlockNum::new(2), &[7u8; DM_BLOCK_SIZE]).unwrap();
+        let root = VerityBuilder::new(b"").unwrap().build(&data, &hash).unwrap();
+        drop((data, hash));
+
+        let mut hex = String::new();
+        for b in root {
+            write!(hex, "{:02x}", b).unwrap();
+        }
+        let dev = dm.create("verified", &format!("0 4 verity data hash {}", hex)).unwrap();
+        assert!(dev.is_readonly());
+
+        let mut block = [0u8; DM_BLOCK_SIZE];
+        dev.read_block(BlockNum::new(2), &mut block).unwrap();
+        assert_eq!(block[0], 7);
+        assert!(dev.write_block(BlockNum::new(2), &block).is_err());
+        assert!(dm.mapped("verified").unwrap().table().to_text().contains(&hex));
+    }
+
+    #[test]
+    fn test_dmsetup() {
+        let mut dm = DeviceMapper::new();
+        dm.register("sda", memory(4)).unwrap();
+
+        let out = dmsetup(&mut dm, &["create", "vol", "0", "4", "linear", "sda", "0"]).unwrap();
+        assert_eq!(out, "vol: 4 blocks\n");
+        assert_eq!(dmsetup(&mut dm, &["ls"]).unwrap(), "vol\n");
+        assert_eq!(dmsetup(&mut dm, &["table", "vol"]).unwrap(), "0 4 linear sda 0\n");
+        assert!(dmsetup(&mut dm, &["create", "vol", "0", "1", "linear", "sda", "0"]).is_err());
+        assert!(dmsetup(&mut dm, &["frobnicate"]).is_err());
+        assert_eq!(dmsetup(&mut dm, &["remove", "vol"]).unwrap(), "");
+        assert_eq!(dmsetup(&mut dm, &["ls"]).unwrap(), "");
+    }
+}
@@ -7,9 +7,21 @@
 pub mod extent;
 pub mod layout;
 pub mod device;
+pub mod verity;
+pub mod discard;
+#[cfg(feature = "alloc")]
+pub mod mapper;
+#[cfg(feature = "alloc")]
+pub mod raid;
 
 pub use superblock::*;
 pub use inode::*;
 pub use extent::*;
 pub use layout::*;
 pub use device::*;
+pub use verity::*;
+pub use discard::*;
+#[cfg(feature = "alloc")]
+pub use mapper::*;
+#[cfg(feature = "alloc")]
+pub use raid::*;
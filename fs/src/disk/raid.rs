@@ -0,0 +1,1066 @@
+//! Software RAID.
+//!
+//! Striping (RAID0) and mirroring (RAID1) over whole block devices. Every
+//! member starts with a superblock describing the array, so arrays are
+//! assembled at boot from whatever devices were found, without a config
+//! file.
+//!
+//! ## Member Layout
+//!
+//! ```text
+//! Block 0:        Superblock (array UUID, level, member states, event count)
+//! Block 1..:      Data
+//! ```
+//!
+//! A RAID1 array keeps running while one member is in sync. Failed members
+//! are marked faulty on the survivors and reported through a
+//! [`RaidEventSink`], which the kernel forwards to the event bus and the
+//! healer. A replacement is added with [`RaidArray::add_member`] and brought
+//! in sync by repeatedly calling [`RaidArray::resync_step`] from a
+//! background task, throttled whenever foreground I/O is seen.
+
+use crate::core::types::*;
+use crate::core::error::{HfsError, HfsResult};
+use crate::crypto::integrity::crc32c;
+use crate::disk::device::{BlockRead, BlockWrite, BlockDeviceInfo, BlockDevice};
+
+use alloc_crate::format;
+use alloc_crate::string::String;
+use alloc_crate::sync::Arc;
+use alloc_crate::vec;
+use alloc_crate::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Superblock magic
+pub const RAID_MAGIC: [u8; 8] = *b"HelixMD\0";
+
+/// Superblock format version
+pub const RAID_VERSION: u32 = 1;
+
+/// Array and member block size
+pub const RAID_BLOCK_SIZE: usize = 4096;
+
+/// First data block on each member
+pub const RAID_DATA_OFFSET: u64 = 1;
+
+/// Maximum members per array
+pub const RAID_MAX_MEMBERS: usize = 16;
+
+/// Default RAID0 chunk size in blocks (64 KiB)
+pub const RAID_DEFAULT_CHUNK: u32 = 16;
+
+/// Resync offset of an array that is in sync
+pub const RESYNC_DONE: u64 = u64::MAX;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// RAID level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RaidLevel {
+    /// Striping
+    Raid0 = 0,
+    /// Mirroring
+    Raid1 = 1,
+}
+
+impl RaidLevel {
+    /// From superblock value
+    pub fn from_raw(raw: u8) -> Option<Self> {
+        match raw {
+            0 => Some(Self::Raid0),
+            1 => Some(Self::Raid1),
+            _ => None,
+        }
+    }
+}
+
+/// Member state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MemberState {
+    /// In sync, serving reads and writes
+    Active = 0,
+    /// Failed or missing
+    Faulty = 1,
+    /// Receiving writes, not yet in sync
+    Rebuilding = 2,
+}
+
+impl MemberState {
+    /// From superblock value
+    pub fn from_raw(raw: u8) -> Self {
+        match raw {
+            0 => Self::Active,
+            2 => Self::Rebuilding,
+            _ => Self::Faulty,
+        }
+    }
+}
+
+/// Array health.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArrayState {
+    /// All members active
+    Clean,
+    /// Running with faulty or rebuilding members
+    Degraded,
+    /// Not enough members to serve I/O
+    Failed,
+}
+
+/// Array events, for the healer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaidEvent {
+    /// A member failed and was kicked out
+    MemberFailed {
+        /// Member slot
+        member: usize,
+    },
+    /// The array lost redundancy
+    Degraded {
+        /// Members still active
+        active: usize,
+    },
+    /// The array can no longer serve I/O
+    Failed,
+    /// A member was added to a faulty slot
+    MemberAdded {
+        /// Member slot
+        member: usize,
+    },
+    /// Rebuilding members are in sync again
+    ResyncComplete,
+}
+
+/// Receiver of array events.
+pub trait RaidEventSink: Send + Sync {
+    /// Called on every state change of array `name`
+    fn raid_event(&self, name: &str, event: RaidEvent);
+}
+
+// ============================================================================
+// Superblock
+// ============================================================================
+
+/// Per-member superblock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RaidSuperblock {
+    /// Array UUID
+    pub uuid: [u8; 16],
+    /// RAID level
+    pub level: RaidLevel,
+    /// Number of member slots
+    pub member_count: usize,
+    /// Slot of the member holding this copy
+    pub index: usize,
+    /// RAID0 chunk size in blocks
+    pub chunk_blocks: u32,
+    /// Data blocks used on each member
+    pub member_blocks: u64,
+    /// Update counter; the highest wins at assembly
+    pub events: u64,
+    /// First block not yet resynced ([`RESYNC_DONE`] if in sync)
+    pub resync_offset: u64,
+    /// State of every slot
+    pub states: [MemberState; RAID_MAX_MEMBERS],
+}
+
+impl RaidSuperblock {
+    /// Serialize into a member block
+    pub fn write_to(&self, block: &mut [u8; RAID_BLOCK_SIZE]) {
+        block.fill(0);
+        block[0..8].copy_from_slice(&RAID_MAGIC);
+        block[8..12].copy_from_slice(&RAID_VERSION.to_le_bytes());
+        block[12..28].copy_from_slice(&self.uuid);
+        block[28] = self.level as u8;
+        block[29] = self.member_count as u8;
+        block[30] = self.index as u8;
+        block[32..36].copy_from_slice(&self.chunk_blocks.to_le_bytes());
+        block[36..44].copy_from_slice(&self.member_blocks.to_le_bytes());
+        block[44..52].copy_from_slice(&self.events.to_le_bytes());
+        block[52..60].copy_from_slice(&self.resync_offset.to_le_bytes());
+        for (i, state) in self.states.iter().enumerate() {
+            block[64 + i] = *state as u8;
+        }
+        let crc = crc32c(&block[..RAID_BLOCK_SIZE - 4]);
+        block[RAID_BLOCK_SIZE - 4..].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Parse from a member block
+    pub fn read_from(block: &[u8; RAID_BLOCK_SIZE]) -> HfsResult<Self> {
+        if block[0..8] != RAID_MAGIC {
+            return Err(HfsError::BadMagic);
+        }
+        let crc = u32::from_le_bytes(block[RAID_BLOCK_SIZE - 4..].try_into().unwrap());
+        if crc != crc32c(&block[..RAID_BLOCK_SIZE - 4]) {
+            return Err(HfsError::ChecksumMismatch);
+        }
+        if u32::from_le_bytes(block[8..12].try_into().unwrap()) != RAID_VERSION {
+            return Err(HfsError::InvalidVersion);
+        }
+
+        let level = RaidLevel::from_raw(block[28]).ok_or(HfsError::NotSupported)?;
+        let member_count = block[29] as usize;
+        let index = block[30] as usize;
+        if member_count == 0 || member_count > RAID_MAX_MEMBERS || index >= member_count {
+            return Err(HfsError::CorruptedData);
+        }
+
+        let mut uuid = [0u8; 16];
+        uuid.copy_from_slice(&block[12..28]);
+        let mut states = [MemberState::Faulty; RAID_MAX_MEMBERS];
+        for (i, state) in states.iter_mut().enumerate().take(member_count) {
+            *state = MemberState::from_raw(block[64 + i]);
+        }
+
+        let u64_at = |at: usize| u64::from_le_bytes(block[at..at + 8].try_into().unwrap());
+        Ok(Self {
+            uuid,
+            level,
+            member_count,
+            index,
+            chunk_blocks: u32::from_le_bytes(block[32..36].try_into().unwrap()),
+            member_blocks: u64_at(36),
+            events: u64_at(44),
+            resync_offset: u64_at(52),
+            states,
+        })
+    }
+
+    /// Read the superblock of `device`, if it is a member of some array
+    pub fn probe(device: &dyn BlockDevice) -> HfsResult<Self> {
+        if device.block_size() as usize != RAID_BLOCK_SIZE {
+            return Err(HfsError::NotSupported);
+        }
+        let mut block = [0u8; RAID_BLOCK_SIZE];
+        device.read_block(BlockNum::new(0), &mut block)?;
+        Self::read_from(&block)
+    }
+}
+
+// ============================================================================
+// Resync Throttling
+// ============================================================================
+
+/// Resync speed limits, in blocks per [`RaidArray::resync_step`].
+#[derive(Clone, Copy, Debug)]
+pub struct ResyncThrottle {
+    /// Budget when foreground I/O happened since the last step
+    pub min_blocks: u64,
+    /// Budget when the array was idle
+    pub max_blocks: u64,
+}
+
+impl Default for ResyncThrottle {
+    fn default() -> Self {
+        Self { min_blocks: 16, max_blocks: 256 }
+    }
+}
+
+/// Outcome of a resync step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResyncProgress {
+    /// Nothing to resync
+    Idle,
+    /// Copied up to `done` of `total` blocks
+    Running {
+        /// Blocks in sync
+        done: u64,
+        /// Blocks to resync
+        total: u64,
+    },
+    /// The last blocks were copied; all members are active
+    Complete,
+}
+
+// ============================================================================
+// Array
+// ============================================================================
+
+/// Array member slot.
+struct Member {
+    /// Device, if present
+    device: Option<Arc<dyn BlockDevice>>,
+    /// [`MemberState`]
+    state: AtomicU8,
+}
+
+impl Member {
+    fn state(&self) -> MemberState {
+        MemberState::from_raw(self.state.load(Ordering::Acquire))
+    }
+
+    fn set_state(&self, state: MemberState) {
+        self.state.store(state as u8, Ordering::Release);
+    }
+}
+
+/// An assembled RAID array, usable as a block device.
+pub struct RaidArray {
+    /// Array name (`md0`, ...)
+    name: String,
+    /// Array UUID
+    uuid: [u8; 16],
+    /// RAID level
+    level: RaidLevel,
+    /// RAID0 chunk size in blocks
+    chunk_blocks: u32,
+    /// Data blocks on each member
+    member_blocks: u64,
+    /// Member slots
+    members: Vec<Member>,
+    /// Superblock update counter
+    events: AtomicU64,
+    /// Resync cursor ([`RESYNC_DONE`] if in sync)
+    resync_offset: AtomicU64,
+    /// Set once too many members failed
+    failed: AtomicBool,
+    /// Foreground I/O counter, for throttling
+    io_count: AtomicU64,
+    /// I/O count seen by the last resync step
+    io_seen: AtomicU64,
+    /// Serializes writes with resync copies
+    write_lock: AtomicBool,
+    /// Event receiver
+    sink: Option<Arc<dyn RaidEventSink>>,
+}
+
+impl RaidArray {
+    /// Create a new array over `devices`, writing fresh superblocks.
+    ///
+    /// Mirrors start with a full resync from the first member.
+    pub fn create(
+        name: &str,
+        uuid: [u8; 16],
+        level: RaidLevel,
+        devices: &[Arc<dyn BlockDevice>],
+        chunk_blocks: u32,
+    ) -> HfsResult<Self> {
+        let min_members = match level {
+            RaidLevel::Raid0 => 1,
+            RaidLevel::Raid1 => 2,
+        };
+        if devices.len() < min_members || devices.len() > RAID_MAX_MEMBERS || chunk_blocks == 0 {
+            return Err(HfsError::InvalidArgument);
+        }
+        if devices.iter().any(|d| d.is_readonly() || d.block_size() as usize != RAID_BLOCK_SIZE) {
+            return Err(HfsError::NotSupported);
+        }
+
+        let smallest = devices.iter().map(|d| d.block_count()).min().unwrap_or(0);
+        let mut member_blocks = smallest.saturating_sub(RAID_DATA_OFFSET);
+        if level == RaidLevel::Raid0 {
+            member_blocks -= member_blocks % chunk_blocks as u64;
+        }
+        if member_blocks == 0 {
+            return Err(HfsError::NoSpace);
+        }
+
+        let members = devices
+            .iter()
+            .enumerate()
+            .map(|(i, device)| {
+                let state = match (level, i) {
+                    (RaidLevel::Raid1, 1..) => MemberState::Rebuilding,
+                    _ => MemberState::Active,
+                };
+                Member { device: Some(device.clone()), state: AtomicU8::new(state as u8) }
+            })
+            .collect();
+        let sb = RaidSuperblock {
+            uuid,
+            level,
+            member_count: devices.len(),
+            index: 0,
+            chunk_blocks,
+            member_blocks,
+            events: 0,
+            resync_offset: match level {
+                RaidLevel::Raid0 => RESYNC_DONE,
+                RaidLevel::Raid1 => 0,
+            },
+            states: [MemberState::Faulty; RAID_MAX_MEMBERS],
+        };
+
+        let array = Self::new(name, &sb, members);
+        array.write_superblocks()?;
+        Ok(array)
+    }
+
+    /// Assemble an array from devices carrying its superblock.
+    ///
+    /// The copy with the highest event count describes the array; members
+    /// with older copies missed updates and are rebuilt.
+    pub fn assemble(name: &str, devices: &[Arc<dyn BlockDevice>]) -> HfsResult<Self> {
+        let mut found: Vec<(RaidSuperblock, Arc<dyn BlockDevice>)> = Vec::new();
+        for device in devices {
+            found.push((RaidSuperblock::probe(device.as_ref())?, device.clone()));
+        }
+        let mut newest = found.iter().map(|(sb, _)| *sb).max_by_key(|sb| sb.events).ok_or(HfsError::NotFound)?;
+        if found.iter().any(|(sb, _)| sb.uuid != newest.uuid) {
+            return Err(HfsError::InvalidArgument);
+        }
+
+        let mut members: Vec<Member> = (0..newest.member_count)
+            .map(|_| Member { device: None, state: AtomicU8::new(MemberState::Faulty as u8) })
+            .collect();
+        for (sb, device) in found {
+            let slot = &mut members[sb.index];
+            if slot.device.is_some() {
+                return Err(HfsError::Busy);
+            }
+            let state = match newest.states[sb.index] {
+                MemberState::Faulty => continue,
+                _ if sb.events < newest.events && newest.level == RaidLevel::Raid1 => {
+                    newest.resync_offset = 0;
+                    MemberState::Rebuilding
+                }
+                _ if sb.events < newest.events => continue,
+                state => state,
+            };
+            slot.device = Some(device);
+            slot.set_state(state);
+        }
+
+        let array = Self::new(name, &newest, members);
+        if array.state() == ArrayState::Failed {
+            return Err(HfsError::DeviceNotReady);
+        }
+        array.write_superblocks()?;
+        Ok(array)
+    }
+
+    /// Array described by `sb`, over `members`
+    fn new(name: &str, sb: &RaidSuperblock, members: Vec<Member>) -> Self {
+        Self {
+            name: String::from(name),
+            uuid: sb.uuid,
+            level: sb.level,
+            chunk_blocks: sb.chunk_blocks,
+            member_blocks: sb.member_blocks,
+            members,
+            events: AtomicU64::new(sb.events),
+            resync_offset: AtomicU64::new(sb.resync_offset),
+            failed: AtomicBool::new(false),
+            io_count: AtomicU64::new(0),
+            io_seen: AtomicU64::new(0),
+            write_lock: AtomicBool::new(false),
+            sink: None,
+        }
+    }
+
+    /// Report events to `sink`
+    pub fn set_event_sink(&mut self, sink: Arc<dyn RaidEventSink>) {
+        self.sink = Some(sink);
+    }
+
+    /// Array name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Array UUID
+    pub fn uuid(&self) -> &[u8; 16] {
+        &self.uuid
+    }
+
+    /// RAID level
+    pub fn level(&self) -> RaidLevel {
+        self.level
+    }
+
+    /// State of member slot `index`
+    pub fn member_state(&self, index: usize) -> Option<MemberState> {
+        self.members.get(index).map(Member::state)
+    }
+
+    /// Number of members in sync
+    pub fn active_members(&self) -> usize {
+        self.members.iter().filter(|m| m.state() == MemberState::Active).count()
+    }
+
+    /// Array health
+    pub fn state(&self) -> ArrayState {
+        let active = self.active_members();
+        let needed = match self.level {
+            RaidLevel::Raid0 => self.members.len(),
+            RaidLevel::Raid1 => 1,
+        };
+        if self.failed.load(Ordering::Acquire) || active < needed {
+            ArrayState::Failed
+        } else if active < self.members.len() {
+            ArrayState::Degraded
+        } else {
+            ArrayState::Clean
+        }
+    }
+
+    fn emit(&self, event: RaidEvent) {
+        if let Some(sink) = &self.sink {
+            sink.raid_event(&self.name, event);
+        }
+    }
+
+    /// Write the current state to every present member
+    fn write_superblocks(&self) -> HfsResult<()> {
+        let events = self.events.fetch_add(1, Ordering::AcqRel) + 1;
+        let mut states = [MemberState::Faulty; RAID_MAX_MEMBERS];
+        for (state, member) in states.iter_mut().zip(&self.members) {
+            *state = member.state();
+        }
+
+        let mut block = [0u8; RAID_BLOCK_SIZE];
+        let mut written = 0;
+        for (index, member) in self.members.iter().enumerate() {
+            let Some(device) = member.device.as_ref().filter(|_| member.state() != MemberState::Faulty) else {
+                continue;
+            };
+            let sb = RaidSuperblock {
+                uuid: self.uuid,
+                level: self.level,
+                member_count: self.members.len(),
+                index,
+                chunk_blocks: self.chunk_blocks,
+                member_blocks: self.member_blocks,
+                events,
+                resync_offset: self.resync_offset.load(Ordering::Acquire),
+                states,
+            };
+            sb.write_to(&mut block);
+            // A member that cannot take its superblock is left for the
+            // next I/O to fail
+            if device.write_block(BlockNum::new(0), &block).is_ok() {
+                written += 1;
+            }
+        }
+        match written {
+            0 => Err(HfsError::IoWriteError),
+            _ => Ok(()),
+        }
+    }
+
+    /// Kick out a member after an I/O error
+    fn fail_member(&self, index: usize) {
+        let member = &self.members[index];
+        if member.state() == MemberState::Faulty {
+            return;
+        }
+        member.set_state(MemberState::Faulty);
+        self.emit(RaidEvent::MemberFailed { member: index });
+
+        match self.state() {
+            ArrayState::Failed => {
+                self.failed.store(true, Ordering::Release);
+                self.emit(RaidEvent::Failed);
+            }
+            _ => {
+                let _ = self.write_superblocks();
+                self.emit(RaidEvent::Degraded { active: self.active_members() });
+            }
+        }
+    }
+
+    /// Put `device` into faulty slot `index` and start rebuilding it
+    pub fn add_member(&mut self, index: usize, device: Arc<dyn BlockDevice>) -> HfsResult<()> {
+        if self.level != RaidLevel::Raid1 {
+            return Err(HfsError::NotSupported);
+        }
+        let member = self.members.get_mut(index).ok_or(HfsError::InvalidArgument)?;
+        if member.state() != MemberState::Faulty {
+            return Err(HfsError::Busy);
+        }
+        if device.block_size() as usize != RAID_BLOCK_SIZE
+            || device.block_count() < self.member_blocks + RAID_DATA_OFFSET
+        {
+            return Err(HfsError::NoSpace);
+        }
+
+        member.device = Some(device);
+        member.set_state(MemberState::Rebuilding);
+        self.resync_offset.store(0, Ordering::Release);
+
+        self.write_superblocks()?;
+        self.emit(RaidEvent::MemberAdded { member: index });
+        Ok(())
+    }
+
+    fn lock(&self) {
+        while self
+            .write_lock
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+    }
+
+    fn unlock(&self) {
+        self.write_lock.store(false, Ordering::Release);
+    }
+
+    /// Copy the next stretch of blocks to rebuilding members
+    pub fn resync_step(&self, throttle: &ResyncThrottle) -> HfsResult<ResyncProgress> {
+        let cursor = self.resync_offset.load(Ordering::Acquire);
+        if cursor == RESYNC_DONE || self.level != RaidLevel::Raid1 {
+            return Ok(ResyncProgress::Idle);
+        }
+        if self.state() == ArrayState::Failed {
+            return Err(HfsError::DeviceNotReady);
+        }
+
+        let io = self.io_count.load(Ordering::Relaxed);
+        let budget = match self.io_seen.swap(io, Ordering::Relaxed) == io {
+            true => throttle.max_blocks,
+            false => throttle.min_blocks,
+        };
+        let count = budget.max(1).min(self.member_blocks - cursor);
+        let mut buffer = vec![0u8; count as usize * RAID_BLOCK_SIZE];
+
+        self.lock();
+        let result = self.copy_to_rebuilding(cursor, &mut buffer);
+        self.unlock();
+        result?;
+
+        let done = cursor + count;
+        if done < self.member_blocks {
+            self.resync_offset.store(done, Ordering::Release);
+            return Ok(ResyncProgress::Running { done, total: self.member_blocks });
+        }
+
+        for member in &self.members {
+            if member.state() == MemberState::Rebuilding {
+                member.set_state(MemberState::Active);
+            }
+        }
+        self.resync_offset.store(RESYNC_DONE, Ordering::Release);
+        self.write_superblocks()?;
+        self.emit(RaidEvent::ResyncComplete);
+        Ok(ResyncProgress::Complete)
+    }
+
+    /// Read `buffer` at `block` from an active member, write it to the
+    /// rebuilding ones
+    fn copy_to_rebuilding(&self, block: u64, buffer: &mut [u8]) -> HfsResult<()> {
+        self.mirror_read(block, buffer)?;
+        let start = BlockNum::new(RAID_DATA_OFFSET + block);
+        for (index, member) in self.members.iter().enumerate() {
+            if member.state() != MemberState::Rebuilding {
+                continue;
+            }
+            let device = member.device.as_ref().ok_or(HfsError::DeviceNotReady)?;
+            if device.write_blocks(start, buffer).is_err() {
+                self.fail_member(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read from the first active member that succeeds
+    fn mirror_read(&self, block: u64, buffer: &mut [u8]) -> HfsResult<usize> {
+        let start = BlockNum::new(RAID_DATA_OFFSET + block);
+        for (index, member) in self.members.iter().enumerate() {
+            if member.state() != MemberState::Active {
+                continue;
+            }
+            let Some(device) = &member.device else { continue };
+            match device.read_blocks(start, buffer) {
+                Ok(read) => return Ok(read),
+                Err(_) => self.fail_member(index),
+            }
+        }
+        Err(HfsError::IoReadError)
+    }
+
+    /// Write to every active and rebuilding member; succeeds if an active
+    /// member took the data
+    fn mirror_write(&self, block: u64, buffer: &[u8]) -> HfsResult<usize> {
+        let start = BlockNum::new(RAID_DATA_OFFSET + block);
+        let mut written = None;
+        self.lock();
+        for (index, member) in self.members.iter().enumerate() {
+            let state = member.state();
+            if state == MemberState::Faulty {
+                continue;
+            }
+            let Some(device) = &member.device else { continue };
+            match device.write_blocks(start, buffer) {
+                Ok(count) if state == MemberState::Active => written = Some(count),
+                Ok(_) => {}
+                Err(_) => self.fail_member(index),
+            }
+        }
+        self.unlock();
+        written.ok_or(HfsError::IoWriteError)
+    }
+
+    /// Member and member block holding array block `block` (RAID0)
+    fn stripe(&self, block: u64) -> (usize, u64) {
+        let chunk_blocks = self.chunk_blocks as u64;
+        let width = self.members.len() as u64;
+        let chunk = block / chunk_blocks;
+        let member = (chunk % width) as usize;
+        let member_block = (chunk / width) * chunk_blocks + block % chunk_blocks;
+        (member, RAID_DATA_OFFSET + member_block)
+    }
+
+    /// Run `f` on each piece of a request split at chunk boundaries (RAID0)
+    fn for_each_chunk(
+        &self,
+        start: u64,
+        len: usize,
+        mut f: impl FnMut(&dyn BlockDevice, u64, core::ops::Range<usize>) -> HfsResult<()>,
+    ) -> HfsResult<()> {
+        let chunk_blocks = self.chunk_blocks as u64;
+        let end = start + (len / RAID_BLOCK_SIZE) as u64;
+        let mut block = start;
+        while block < end {
+            let run = (chunk_blocks - block % chunk_blocks).min(end - block);
+            let (index, member_block) = self.stripe(block);
+            let offset = (block - start) as usize * RAID_BLOCK_SIZE;
+            let range = offset..offset + run as usize * RAID_BLOCK_SIZE;
+            let device = self.members[index].device.as_ref().ok_or(HfsError::DeviceNotReady)?;
+            if let Err(err) = f(device.as_ref(), member_block, range) {
+                self.fail_member(index);
+                return Err(err);
+            }
+            block += run;
+        }
+        Ok(())
+    }
+
+    /// Check that a request fits and the array can serve it
+    fn check_io(&self, start: BlockNum, len: usize) -> HfsResult<u64> {
+        if self.state() == ArrayState::Failed {
+            return Err(HfsError::DeviceNotReady);
+        }
+        let count = (len / RAID_BLOCK_SIZE) as u64;
+        if start.get() + count > self.block_count() {
+            return Err(HfsError::InvalidBlockNumber);
+        }
+        self.io_count.fetch_add(1, Ordering::Relaxed);
+        Ok(count)
+    }
+}
+
+impl BlockRead for RaidArray {
+    fn read_blocks(&self, start: BlockNum, buffer: &mut [u8]) -> HfsResult<usize> {
+        let count = self.check_io(start, buffer.len())?;
+        match self.level {
+            RaidLevel::Raid1 => self.mirror_read(start.get(), buffer),
+            RaidLevel::Raid0 => {
+                self.for_each_chunk(start.get(), buffer.len(), |device, block, range| {
+                    device.read_blocks(BlockNum::new(block), &mut buffer[range]).map(|_| ())
+                })?;
+                Ok(count as usize)
+            }
+        }
+    }
+}
+
+impl BlockWrite for RaidArray {
+    fn write_blocks(&self, start: BlockNum, buffer: &[u8]) -> HfsResult<usize> {
+        let count = self.check_io(start, buffer.len())?;
+        match self.level {
+            RaidLevel::Raid1 => self.mirror_write(start.get(), buffer),
+            RaidLevel::Raid0 => {
+                self.for_each_chunk(start.get(), buffer.len(), |device, block, range| {
+                    device.write_blocks(BlockNum::new(block), &buffer[range]).map(|_| ())
+                })?;
+                Ok(count as usize)
+            }
+        }
+    }
+
+    fn sync(&self) -> HfsResult<()> {
+        for (index, member) in self.members.iter().enumerate() {
+            if let (Some(device), MemberState::Active | MemberState::Rebuilding) = (&member.device, member.state()) {
+                if device.sync().is_err() {
+                    self.fail_member(index);
+                }
+            }
+        }
+        match self.state() {
+            ArrayState::Failed => Err(HfsError::IoWriteError),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl BlockDeviceInfo for RaidArray {
+    fn block_size(&self) -> u32 {
+        RAID_BLOCK_SIZE as u32
+    }
+
+    fn block_count(&self) -> u64 {
+        match self.level {
+            RaidLevel::Raid0 => self.member_blocks * self.members.len() as u64,
+            RaidLevel::Raid1 => self.member_blocks,
+        }
+    }
+
+    fn is_readonly(&self) -> bool {
+        false
+    }
+
+    fn device_name(&self) -> &[u8] {
+        self.name.as_bytes()
+    }
+}
+
+impl BlockDevice for RaidArray {}
+
+// ============================================================================
+// Boot Assembly
+// ============================================================================
+
+/// Assemble every array found on `devices`, naming them `md0`, `md1`, ...
+///
+/// Devices without a valid superblock are skipped; arrays that cannot run
+/// are reported through `sink` and left out.
+pub fn assemble_all(devices: &[Arc<dyn BlockDevice>], sink: Option<Arc<dyn RaidEventSink>>) -> Vec<RaidArray> {
+    let probed: Vec<(RaidSuperblock, &Arc<dyn BlockDevice>)> = devices
+        .iter()
+        .filter_map(|d| RaidSuperblock::probe(d.as_ref()).ok().map(|sb| (sb, d)))
+        .collect();
+    let mut uuids: Vec<[u8; 16]> = Vec::new();
+    for (sb, _) in &probed {
+        if !uuids.contains(&sb.uuid) {
+            uuids.push(sb.uuid);
+        }
+    }
+
+    let mut arrays = Vec::new();
+    for (n, uuid) in uuids.iter().enumerate() {
+        let name = format!("md{}", n);
+        let members: Vec<Arc<dyn BlockDevice>> =
+            probed.iter().filter(|(sb, _)| sb.uuid == *uuid).map(|(_, d)| (*d).clone()).collect();
+        match RaidArray::assemble(&name, &members) {
+            Ok(mut array) => {
+                if let Some(sink) = &sink {
+                    array.set_event_sink(sink.clone());
+                    if array.state() == ArrayState::Degraded {
+                        sink.raid_event(&name, RaidEvent::Degraded { active: array.active_members() });
+                    }
+                }
+                arrays.push(array);
+            }
+            Err(_) => {
+                if let Some(sink) = &sink {
+                    sink.raid_event(&name, RaidEvent::Failed);
+                }
+            }
+        }
+    }
+    arrays
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disk::device::MemoryBlockDevice;
+    use alloc_crate::boxed::Box;
+    use core::sync::atomic::AtomicUsize;
+
+    /// Memory device that can be made to fail
+    struct FlakyDevice {
+        inner: MemoryBlockDevice,
+        broken: AtomicBool,
+    }
+
+    impl FlakyDevice {
+        fn check(&self) -> HfsResult<()> {
+            match self.broken.load(Ordering::Relaxed) {
+                true => Err(HfsError::IoError),
+                false => Ok(()),
+            }
+        }
+    }
+
+    impl BlockRead for FlakyDevice {
+        fn read_blocks(&self, start: BlockNum, buffer: &mut [u8]) -> HfsResult<usize> {
+            self.check()?;
+            self.inner.read_blocks(start, buffer)
+        }
+    }
+
+    impl BlockWrite for FlakyDevice {
+        fn write_blocks(&self, start: BlockNum, buffer: &[u8]) -> HfsResult<usize> {
+            self.check()?;
+            self.inner.write_blocks(start, buffer)
+        }
+
+        fn sync(&self) -> HfsResult<()> {
+            self.check()
+        }
+    }
+
+    impl BlockDeviceInfo for FlakyDevice {
+        fn block_size(&self) -> u32 {
+            self.inner.block_size()
+        }
+
+        fn block_count(&self) -> u64 {
+            self.inner.block_count()
+        }
+
+        fn is_readonly(&self) -> bool {
+            false
+        }
+
+        fn device_name(&self) -> &[u8] {
+            b"flaky"
+        }
+    }
+
+    impl BlockDevice for FlakyDevice {}
+
+    fn flaky(blocks: usize) -> Arc<FlakyDevice> {
+        let buffer = Box::leak(vec![0u8; blocks * RAID_BLOCK_SIZE].into_boxed_slice());
+        // SAFETY: the buffer is leaked and lives forever
+        let inner = unsafe { MemoryBlockDevice::from_buffer(buffer.as_mut_ptr(), buffer.len(), 4096) };
+        Arc::new(FlakyDevice { inner, broken: AtomicBool::new(false) })
+    }
+
+    /// Counts events
+    #[derive(Default)]
+    struct Log {
+        failed: AtomicUsize,
+        degraded: AtomicUsize,
+        resynced: AtomicUsize,
+    }
+
+    impl RaidEventSink for Log {
+        fn raid_event(&self, _name: &str, event: RaidEvent) {
+            let counter = match event {
+                RaidEvent::MemberFailed { .. } => &self.failed,
+                RaidEvent::Degraded { .. } => &self.degraded,
+                RaidEvent::ResyncComplete => &self.resynced,
+                _ => return,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn block(byte: u8) -> [u8; RAID_BLOCK_SIZE] {
+        [byte; RAID_BLOCK_SIZE]
+    }
+
+    #[test]
+    fn test_superblock_roundtrip() {
+        let sb = RaidSuperblock {
+            uuid: [7; 16],
+            level: RaidLevel::Raid1,
+            member_count: 2,
+            index: 1,
+            chunk_blocks: 16,
+            member_blocks: 100,
+            events: 42,
+            resync_offset: RESYNC_DONE,
+            states: [MemberState::Active; RAID_MAX_MEMBERS],
+        };
+        let mut raw = [0u8; RAID_BLOCK_SIZE];
+        sb.write_to(&mut raw);
+        assert_eq!(RaidSuperblock::read_from(&raw).unwrap().events, 42);
+
+        raw[40] ^= 1;
+        assert_eq!(RaidSuperblock::read_from(&raw), Err(HfsError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn test_raid0_striping() {
+        let (a, b) = (flaky(9), flaky(9));
+        let devices: [Arc<dyn BlockDevice>; 2] = [a.clone(), b.clone()];
+        let array = RaidArray::create("md0", [1; 16], RaidLevel::Raid0, &devices, 4).unwrap();
+        assert_eq!(array.block_count(), 16);
+
+        let mut data = vec![0u8; 8 * RAID_BLOCK_SIZE];
+        for (i, chunk) in data.chunks_exact_mut(RAID_BLOCK_SIZE).enumerate() {
+            chunk.fill(i as u8);
+        }
+        array.write_blocks(BlockNum::new(2), &data).unwrap();
+
+        // Array block 5 is block 1 of chunk 1, which lives on member 1
+        let mut raw = [0u8; RAID_BLOCK_SIZE];
+        b.read_block(BlockNum::new(RAID_DATA_OFFSET + 1), &mut raw).unwrap();
+        assert_eq!(raw[0], 3);
+
+        let mut back = vec![0u8; 8 * RAID_BLOCK_SIZE];
+        array.read_blocks(BlockNum::new(2), &mut back).unwrap();
+        assert_eq!(back, data);
+
+        a.broken.store(true, Ordering::Relaxed);
+        assert!(array.read_blocks(BlockNum::new(0), &mut back).is_err());
+        assert_eq!(array.state(), ArrayState::Failed);
+    }
+
+    #[test]
+    fn test_raid1_degraded_and_resync() {
+        let log = Arc::new(Log::default());
+        let (a, b) = (flaky(17), flaky(17));
+        let devices: [Arc<dyn BlockDevice>; 2] = [a.clone(), b.clone()];
+        let mut array = RaidArray::create("md0", [2; 16], RaidLevel::Raid1, &devices, 4).unwrap();
+        array.set_event_sink(log.clone());
+
+        let throttle = ResyncThrottle { min_blocks: 4, max_blocks: 8 };
+        assert_eq!(array.resync_step(&throttle).unwrap(), ResyncProgress::Running { done: 8, total: 16 });
+        array.write_block(BlockNum::new(3), &block(9)).unwrap();
+        // Foreground I/O slows the next step down
+        assert_eq!(array.resync_step(&throttle).unwrap(), ResyncProgress::Running { done: 12, total: 16 });
+        assert_eq!(array.resync_step(&throttle).unwrap(), ResyncProgress::Complete);
+        assert_eq!(array.state(), ArrayState::Clean);
+
+        // Lose a member: reads keep working from the survivor
+        a.broken.store(true, Ordering::Relaxed);
+        let mut buf = [0u8; RAID_BLOCK_SIZE];
+        array.read_block(BlockNum::new(3), &mut buf).unwrap();
+        assert_eq!(buf, block(9));
+        assert_eq!(array.state(), ArrayState::Degraded);
+        assert_eq!(log.failed.load(Ordering::Relaxed), 1);
+        assert_eq!(log.degraded.load(Ordering::Relaxed), 1);
+
+        // Replace it and rebuild
+        let c = flaky(17);
+        array.add_member(0, c.clone()).unwrap();
+        array.write_block(BlockNum::new(15), &block(4)).unwrap();
+        while array.resync_step(&throttle).unwrap() != ResyncProgress::Complete {}
+        assert_eq!(log.resynced.load(Ordering::Relaxed), 2);
+
+        b.broken.store(true, Ordering::Relaxed);
+        array.read_block(BlockNum::new(3), &mut buf).unwrap();
+        assert_eq!(buf, block(9));
+    }
+
+    #[test]
+    fn test_assemble_stale_member() {
+        let (a, b) = (flaky(9), flaky(9));
+        let devices: [Arc<dyn BlockDevice>; 2] = [a.clone(), b.clone()];
+        let array = RaidArray::create("md0", [3; 16], RaidLevel::Raid1, &devices, 4).unwrap();
+        while array.resync_step(&ResyncThrottle::default()).unwrap() != ResyncProgress::Complete {}
+
+        // b misses an update while the array runs degraded
+        b.broken.store(true, Ordering::Relaxed);
+        array.write_block(BlockNum::new(0), &block(1)).unwrap();
+        drop(array);
+        b.broken.store(false, Ordering::Relaxed);
+
+        let log = Arc::new(Log::default());
+        let mut arrays = assemble_all(&devices, Some(log.clone()));
+        assert_eq!(arrays.len(), 1);
+        let array = arrays.pop().unwrap();
+        assert_eq!(array.name(), "md0");
+        assert_eq!(array.member_state(0), Some(MemberState::Active));
+        assert_eq!(array.member_state(1), Some(MemberState::Faulty));
+        assert_eq!(log.degraded.load(Ordering::Relaxed), 1);
+    }
+}
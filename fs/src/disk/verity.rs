@@ -0,0 +1,551 @@
+//! Verified block devices (dm-verity style).
+//!
+//! A read-only target that checks every block read from a data device
+//! against a SHA-256 hash tree kept on a separate hash device. The tree is
+//! built once, when the image is produced, and the only trusted input at
+//! runtime is the root hash: taken from the kernel command line
+//! (`roothash=<hex>`) or unsealed from the TPM by the caller.
+//!
+//! ## Hash Device Layout
+//!
+//! ```text
+//! Block 0:        Superblock (magic, block sizes, data block count, salt)
+//! Block 1:        Top level (a single block, whose hash is the root hash)
+//! Block 2..:      Lower levels, down to level 0 (hashes of data blocks)
+//! ```
+//!
+//! Each hash block holds 128 salted SHA-256 digests of the blocks one level
+//! down. A block whose chain of digests does not lead to the root hash is
+//! never returned; the read fails with an I/O error instead.
+
+use crate::core::types::*;
+use crate::core::error::{HfsError, HfsResult};
+use crate::crypto::integrity::Sha256;
+use crate::disk::device::{BlockRead, BlockWrite, BlockDeviceInfo, BlockDeviceRO};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Superblock magic
+pub const VERITY_MAGIC: [u8; 8] = *b"verity\0\0";
+
+/// On-disk format version
+pub const VERITY_VERSION: u32 = 1;
+
+/// Data and hash block size
+pub const VERITY_BLOCK_SIZE: usize = 4096;
+
+/// SHA-256 digest size
+pub const VERITY_DIGEST_SIZE: usize = 32;
+
+/// Digests per hash block
+pub const HASHES_PER_BLOCK: u64 = (VERITY_BLOCK_SIZE / VERITY_DIGEST_SIZE) as u64;
+
+/// Maximum salt length
+pub const VERITY_MAX_SALT: usize = 32;
+
+/// Maximum tree depth (128^8 blocks is far beyond any device)
+pub const VERITY_MAX_LEVELS: usize = 8;
+
+/// Root hash of a hash tree
+pub type RootHash = [u8; VERITY_DIGEST_SIZE];
+
+/// Salted digest of one block
+fn digest(salt: &[u8], block: &[u8]) -> RootHash {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(block);
+    hasher.finish()
+}
+
+// ============================================================================
+// Geometry
+// ============================================================================
+
+/// Position of every hash tree level on the hash device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerityGeometry {
+    /// Number of data blocks covered
+    pub data_blocks: u64,
+    /// Number of levels (level 0 hashes data blocks)
+    pub levels: usize,
+    /// First hash block of each level
+    pub level_start: [u64; VERITY_MAX_LEVELS],
+    /// Hash blocks in each level
+    pub level_blocks: [u64; VERITY_MAX_LEVELS],
+}
+
+impl VerityGeometry {
+    /// Compute the tree shape for a device of `data_blocks` blocks
+    pub fn new(data_blocks: u64) -> HfsResult<Self> {
+        if data_blocks == 0 {
+            return Err(HfsError::InvalidArgument);
+        }
+
+        let mut level_blocks = [0u64; VERITY_MAX_LEVELS];
+        let mut levels = 0;
+        let mut entries = data_blocks;
+        loop {
+            if levels == VERITY_MAX_LEVELS {
+                return Err(HfsError::TooBig);
+            }
+            let blocks = entries.div_ceil(HASHES_PER_BLOCK);
+            level_blocks[levels] = blocks;
+            levels += 1;
+            if blocks == 1 {
+                break;
+            }
+            entries = blocks;
+        }
+
+        // Top level first, right after the superblock
+        let mut level_start = [0u64; VERITY_MAX_LEVELS];
+        let mut next = 1;
+        for level in (0..levels).rev() {
+            level_start[level] = next;
+            next += level_blocks[level];
+        }
+
+        Ok(Self { data_blocks, levels, level_start, level_blocks })
+    }
+
+    /// Total size of the hash device in blocks, superblock included
+    pub fn hash_device_blocks(&self) -> u64 {
+        1 + self.level_blocks[..self.levels].iter().sum::<u64>()
+    }
+
+    /// Hash block and byte offset holding the digest of entry `index` of
+    /// `level` (a data block for level 0, a level - 1 hash block otherwise)
+    #[inline]
+    pub fn entry(&self, level: usize, index: u64) -> (BlockNum, usize) {
+        let block = self.level_start[level] + index / HASHES_PER_BLOCK;
+        let offset = (index % HASHES_PER_BLOCK) as usize * VERITY_DIGEST_SIZE;
+        (BlockNum::new(block), offset)
+    }
+}
+
+// ============================================================================
+// Superblock
+// ============================================================================
+
+/// Hash device superblock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VeritySuperblock {
+    /// Number of data blocks covered
+    pub data_blocks: u64,
+    /// Salt length
+    pub salt_len: usize,
+    /// Salt prepended to every hashed block
+    pub salt: [u8; VERITY_MAX_SALT],
+}
+
+impl VeritySuperblock {
+    /// Salt bytes
+    pub fn salt(&self) -> &[u8] {
+        &self.salt[..self.salt_len]
+    }
+
+    /// Serialize into a hash block
+    pub fn write_to(&self, block: &mut [u8; VERITY_BLOCK_SIZE]) {
+        block.fill(0);
+        block[0..8].copy_from_slice(&VERITY_MAGIC);
+        block[8..12].copy_from_slice(&VERITY_VERSION.to_le_bytes());
+        block[12..16].copy_from_slice(&(VERITY_BLOCK_SIZE as u32).to_le_bytes());
+        block[16..20].copy_from_slice(&(VERITY_BLOCK_SIZE as u32).to_le_bytes());
+        block[20..28].copy_from_slice(&self.data_blocks.to_le_bytes());
+        block[28..30].copy_from_slice(&(self.salt_len as u16).to_le_bytes());
+        block[32..32 + VERITY_MAX_SALT].copy_from_slice(&self.salt);
+    }
+
+    /// Parse from a hash block
+    pub fn read_from(block: &[u8; VERITY_BLOCK_SIZE]) -> HfsResult<Self> {
+        if block[0..8] != VERITY_MAGIC {
+            return Err(HfsError::BadMagic);
+        }
+        let field = |at: usize| u32::from_le_bytes([block[at], block[at + 1], block[at + 2], block[at + 3]]);
+        if field(8) != VERITY_VERSION {
+            return Err(HfsError::InvalidVersion);
+        }
+        if field(12) as usize != VERITY_BLOCK_SIZE || field(16) as usize != VERITY_BLOCK_SIZE {
+            return Err(HfsError::NotSupported);
+        }
+
+        let salt_len = u16::from_le_bytes([block[28], block[29]]) as usize;
+        if salt_len > VERITY_MAX_SALT {
+            return Err(HfsError::CorruptedData);
+        }
+        let mut salt = [0u8; VERITY_MAX_SALT];
+        salt.copy_from_slice(&block[32..32 + VERITY_MAX_SALT]);
+
+        let mut data_blocks = [0u8; 8];
+        data_blocks.copy_from_slice(&block[20..28]);
+        Ok(Self { data_blocks: u64::from_le_bytes(data_blocks), salt_len, salt })
+    }
+}
+
+// ============================================================================
+// Tree Builder
+// ============================================================================
+
+/// Builds the hash tree of a data device at image build time.
+pub struct VerityBuilder {
+    /// Salt length
+    salt_len: usize,
+    /// Salt
+    salt: [u8; VERITY_MAX_SALT],
+}
+
+impl VerityBuilder {
+    /// Create a builder using `salt` (up to 32 bytes)
+    pub fn new(salt: &[u8]) -> HfsResult<Self> {
+        if salt.len() > VERITY_MAX_SALT {
+            return Err(HfsError::InvalidArgument);
+        }
+        let mut buf = [0u8; VERITY_MAX_SALT];
+        buf[..salt.len()].copy_from_slice(salt);
+        Ok(Self { salt_len: salt.len(), salt: buf })
+    }
+
+    /// Hash every block of `data` into a tree on `hash`; returns the root
+    /// hash to put on the kernel command line or seal in the TPM
+    pub fn build<D, H>(&self, data: &D, hash: &H) -> HfsResult<RootHash>
+    where
+        D: BlockRead + BlockDeviceInfo,
+        H: BlockRead + BlockWrite + BlockDeviceInfo,
+    {
+        if data.block_size() as usize != VERITY_BLOCK_SIZE || hash.block_size() as usize != VERITY_BLOCK_SIZE {
+            return Err(HfsError::NotSupported);
+        }
+        let geometry = VerityGeometry::new(data.block_count())?;
+        if hash.block_count() < geometry.hash_device_blocks() {
+            return Err(HfsError::NoSpace);
+        }
+        let salt = &self.salt[..self.salt_len];
+
+        let mut input = [0u8; VERITY_BLOCK_SIZE];
+        let mut output = [0u8; VERITY_BLOCK_SIZE];
+
+        // Level 0 from the data device, each further level from the one below
+        for level in 0..geometry.levels {
+            let entries = match level {
+                0 => geometry.data_blocks,
+                _ => geometry.level_blocks[level - 1],
+            };
+            output.fill(0);
+            for index in 0..entries {
+                match level {
+                    0 => data.read_block(BlockNum::new(index), &mut input)?,
+                    _ => hash.read_block(BlockNum::new(geometry.level_start[level - 1] + index), &mut input)?,
+                }
+                let (block, offset) = geometry.entry(level, index);
+                output[offset..offset + VERITY_DIGEST_SIZE].copy_from_slice(&digest(salt, &input));
+                if offset + VERITY_DIGEST_SIZE == VERITY_BLOCK_SIZE || index + 1 == entries {
+                    hash.write_block(block, &output)?;
+                    output.fill(0);
+                }
+            }
+        }
+
+        let superblock = VeritySuperblock { data_blocks: geometry.data_blocks, salt_len: self.salt_len, salt: self.salt };
+        superblock.write_to(&mut output);
+        hash.write_block(BlockNum::new(0), &output)?;
+        hash.sync()?;
+
+        let top = geometry.levels - 1;
+        hash.read_block(BlockNum::new(geometry.level_start[top]), &mut input)?;
+        Ok(digest(salt, &input))
+    }
+}
+
+// ============================================================================
+// Root Hash Sources
+// ============================================================================
+
+/// Where the trusted root hash comes from.
+///
+/// TPM-sealed root hashes are unsealed by the TPM driver, which implements
+/// this trait (or passes the unsealed [`RootHash`] directly).
+pub trait RootHashSource {
+    /// Get the root hash
+    fn root_hash(&self) -> HfsResult<RootHash>;
+}
+
+impl RootHashSource for RootHash {
+    fn root_hash(&self) -> HfsResult<RootHash> {
+        Ok(*self)
+    }
+}
+
+/// Root hash given as `roothash=<64 hex digits>` on the kernel command line.
+pub struct CmdlineRootHash<'a>(pub &'a [u8]);
+
+impl RootHashSource for CmdlineRootHash<'_> {
+    fn root_hash(&self) -> HfsResult<RootHash> {
+        parse_root_hash(self.0).ok_or(HfsError::NotFound)
+    }
+}
+
+/// Find and decode `roothash=` in a kernel command line
+pub fn parse_root_hash(cmdline: &[u8]) -> Option<RootHash> {
+    let value = cmdline
+        .split(|&b| b == b' ')
+        .find_map(|arg| arg.strip_prefix(b"roothash="))?;
+    parse_hex_digest(value)
+}
+
+/// Decode a 32-byte digest written as 64 hex digits
+pub fn parse_hex_digest(hex: &[u8]) -> Option<RootHash> {
+    if hex.len() != VERITY_DIGEST_SIZE * 2 {
+        return None;
+    }
+
+    let nibble = |c: u8| match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    };
+    let mut hash = [0u8; VERITY_DIGEST_SIZE];
+    for (i, pair) in hex.chunks_exact(2).enumerate() {
+        hash[i] = (nibble(pair[0])? << 4) | nibble(pair[1])?;
+    }
+    Some(hash)
+}
+
+// ============================================================================
+// Verified Device
+// ============================================================================
+
+/// Verification statistics.
+#[derive(Default)]
+pub struct VerityStats {
+    /// Blocks that passed verification
+    pub verified: AtomicU64,
+    /// Blocks that failed verification
+    pub failures: AtomicU64,
+    /// Last block that failed (u64::MAX if none)
+    pub last_failure: AtomicU64,
+}
+
+/// Read-only device returning only blocks that match the hash tree.
+pub struct VerityDevice<D, H> {
+    /// Data device
+    data: D,
+    /// Hash device
+    hash: H,
+    /// Tree layout
+    geometry: VerityGeometry,
+    /// Superblock (salt)
+    superblock: VeritySuperblock,
+    /// Trusted root hash
+    root: RootHash,
+    /// Statistics
+    stats: VerityStats,
+}
+
+impl<D, H> VerityDevice<D, H>
+where
+    D: BlockRead + BlockDeviceInfo,
+    H: BlockRead,
+{
+    /// Open a verified device; fails if the tree does not match `root`
+    pub fn open(data: D, hash: H, root: &dyn RootHashSource) -> HfsResult<Self> {
+        if data.block_size() as usize != VERITY_BLOCK_SIZE {
+            return Err(HfsError::NotSupported);
+        }
+
+        let mut block = [0u8; VERITY_BLOCK_SIZE];
+        hash.read_block(BlockNum::new(0), &mut block)?;
+        let superblock = VeritySuperblock::read_from(&block)?;
+        if superblock.data_blocks > data.block_count() {
+            return Err(HfsError::InvalidBlockNumber);
+        }
+        let geometry = VerityGeometry::new(superblock.data_blocks)?;
+        let root = root.root_hash()?;
+
+        let top = geometry.levels - 1;
+        hash.read_block(BlockNum::new(geometry.level_start[top]), &mut block)?;
+        if digest(superblock.salt(), &block) != root {
+            return Err(HfsError::ChecksumMismatch);
+        }
+
+        let stats = VerityStats::default();
+        stats.last_failure.store(u64::MAX, Ordering::Relaxed);
+        Ok(Self { data, hash, geometry, superblock, root, stats })
+    }
+
+    /// Tree layout
+    pub fn geometry(&self) -> &VerityGeometry {
+        &self.geometry
+    }
+
+    /// Verification statistics
+    pub fn stats(&self) -> &VerityStats {
+        &self.stats
+    }
+
+    /// Check the contents of data block `block` against the tree
+    pub fn verify_block(&self, block: u64, contents: &[u8]) -> HfsResult<()> {
+        let salt = self.superblock.salt();
+        let mut expected = digest(salt, contents);
+        let mut index = block;
+        let mut hash_block = [0u8; VERITY_BLOCK_SIZE];
+
+        // Walk up to the root, checking each digest against its parent
+        for level in 0..self.geometry.levels {
+            let (location, offset) = self.geometry.entry(level, index);
+            self.hash.read_block(location, &mut hash_block)?;
+            if hash_block[offset..offset + VERITY_DIGEST_SIZE] != expected {
+                return Err(HfsError::IoReadError);
+            }
+            expected = digest(salt, &hash_block);
+            index /= HASHES_PER_BLOCK;
+        }
+
+        if expected != self.root {
+            return Err(HfsError::IoReadError);
+        }
+        Ok(())
+    }
+}
+
+impl<D, H> BlockRead for VerityDevice<D, H>
+where
+    D: BlockRead + BlockDeviceInfo,
+    H: BlockRead,
+{
+    fn read_blocks(&self, start: BlockNum, buffer: &mut [u8]) -> HfsResult<usize> {
+        let count = (buffer.len() / VERITY_BLOCK_SIZE) as u64;
+        if start.get() + count > self.geometry.data_blocks {
+            return Err(HfsError::InvalidBlockNumber);
+        }
+
+        let read = self.data.read_blocks(start, buffer)?;
+        let failed = buffer
+            .chunks_exact(VERITY_BLOCK_SIZE)
+            .take(read)
+            .enumerate()
+            .map(|(i, contents)| (start.get() + i as u64, contents))
+            .find_map(|(block, contents)| self.verify_block(block, contents).err().map(|err| (block, err)));
+
+        if let Some((block, err)) = failed {
+            self.stats.failures.fetch_add(1, Ordering::Relaxed);
+            self.stats.last_failure.store(block, Ordering::Relaxed);
+            // Never hand out unverified data
+            buffer.fill(0);
+            return Err(err);
+        }
+        self.stats.verified.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+impl<D, H> BlockWrite for VerityDevice<D, H> {
+    fn write_blocks(&self, _start: BlockNum, _buffer: &[u8]) -> HfsResult<usize> {
+        Err(HfsError::ReadOnlyFilesystem)
+    }
+
+    fn sync(&self) -> HfsResult<()> {
+        Ok(())
+    }
+}
+
+impl<D, H> BlockDeviceInfo for VerityDevice<D, H> {
+    fn block_size(&self) -> u32 {
+        VERITY_BLOCK_SIZE as u32
+    }
+
+    fn block_count(&self) -> u64 {
+        self.geometry.data_blocks
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn device_name(&self) -> &[u8] {
+        b"verity"
+    }
+}
+
+impl<D, H> BlockDeviceRO for VerityDevice<D, H>
+where
+    D: BlockRead + BlockDeviceInfo + Send + Sync,
+    H: BlockRead + Send + Sync,
+{
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disk::device::MemoryBlockDevice;
+
+    const DATA_BLOCKS: usize = 130;
+
+    #[test]
+    fn test_geometry() {
+        let geometry = VerityGeometry::new(DATA_BLOCKS as u64).unwrap();
+        assert_eq!(geometry.levels, 2);
+        assert_eq!(geometry.level_start[1], 1);
+        assert_eq!(geometry.level_start[0], 2);
+        assert_eq!(geometry.hash_device_blocks(), 4);
+        assert_eq!(geometry.entry(0, 129), (BlockNum::new(3), 32));
+
+        assert_eq!(VerityGeometry::new(1).unwrap().levels, 1);
+        assert!(VerityGeometry::new(0).is_err());
+    }
+
+    #[test]
+    fn test_parse_root_hash() {
+        let hex = b"quiet roothash=00112233445566778899aabbccddeeff00112233445566778899AABBCCDDEEFF ro";
+        let hash = parse_root_hash(hex).unwrap();
+        assert_eq!(hash[..4], [0x00, 0x11, 0x22, 0x33]);
+        assert_eq!(hash[31], 0xFF);
+
+        assert!(parse_root_hash(b"roothash=0011").is_none());
+        assert!(parse_root_hash(b"quiet").is_none());
+    }
+
+    #[test]
+    fn test_verified_reads() {
+        let mut data = [0u8; DATA_BLOCKS * VERITY_BLOCK_SIZE];
+        for (i, block) in data.chunks_exact_mut(VERITY_BLOCK_SIZE).enumerate() {
+            block.fill(i as u8);
+        }
+        let mut tree = [0u8; 4 * VERITY_BLOCK_SIZE];
+
+        // SAFETY: both buffers outlive the devices
+        let devices = |data: &mut [u8], tree: &mut [u8]| unsafe {
+            (
+                MemoryBlockDevice::from_buffer(data.as_mut_ptr(), data.len(), 4096),
+                MemoryBlockDevice::from_buffer(tree.as_mut_ptr(), tree.len(), 4096),
+            )
+        };
+        let (data_dev, hash_dev) = devices(&mut data, &mut tree);
+        let root = VerityBuilder::new(b"salt").unwrap().build(&data_dev, &hash_dev).unwrap();
+
+        let mut wrong = root;
+        wrong[0] ^= 1;
+        assert!(matches!(VerityDevice::open(data_dev, hash_dev, &wrong), Err(HfsError::ChecksumMismatch)));
+
+        let (data_dev, hash_dev) = devices(&mut data, &mut tree);
+        let verity = VerityDevice::open(data_dev, hash_dev, &root).unwrap();
+        let mut buf = [0u8; 2 * VERITY_BLOCK_SIZE];
+        assert_eq!(verity.read_blocks(BlockNum::new(128), &mut buf).unwrap(), 2);
+        assert_eq!(buf[VERITY_BLOCK_SIZE], 129);
+
+        // Tamper with a data block behind the device's back
+        // SAFETY: the device only reads through its raw pointer
+        unsafe { *data.as_mut_ptr().add(129 * VERITY_BLOCK_SIZE) ^= 0xFF };
+        assert!(matches!(verity.read_blocks(BlockNum::new(128), &mut buf), Err(HfsError::IoReadError)));
+        assert_eq!(verity.stats().last_failure.load(Ordering::Relaxed), 129);
+        assert!(buf.iter().all(|&b| b == 0));
+        assert!(verity.write_blocks(BlockNum::new(0), &buf).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+//! Link flags for the host test binaries
+//!
+//! `uaccess` finds the exception table through the
+//! `__start_helix_extable` / `__stop_helix_extable` symbols. Kernel linker
+//! scripts `KEEP` the section; host test binaries are linked with
+//! `--gc-sections`, and lld drops the section unless those symbols retain
+//! it.
+
+fn main() {
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("linux") {
+        println!("cargo:rustc-link-arg=-Wl,-z,nostart-stop-gc");
+    }
+}
@@ -0,0 +1,200 @@
+//! # Branch Protection
+//!
+//! ARM pointer authentication (backward edge) and branch target
+//! identification (forward edge), the targets of `-Zbranch-protection`.
+//!
+//! - **PAC**: `PACIASP`/`AUTIASP` sign and check the link register with
+//!   the A instruction key. Enabled by `SCTLR_EL1.EnIA`/`EnIB`/`EnDA`/`EnDB`
+//!   for EL0 and EL1 alike; code built without PAC is unaffected. A failed
+//!   check traps with FPAC (EC 0x1C) where supported.
+//! - **BTI**: indirect branches must land on a `BTI` instruction in pages
+//!   mapped as guarded (GP). `SCTLR_EL1.BT0` controls EL0, `BT1` EL1;
+//!   violations trap with EC 0x0D.
+//!
+//! The keys are shared by EL0 and EL1: per-process keys require switching
+//! them on every exception entry and return.
+//!
+//! Kernel PAC and BTI are only enabled with the `cfi` feature, whose
+//! builds use `-Zbranch-protection=bti,pac-ret`.
+
+use core::arch::asm;
+
+use crate::cfi::{cfi, CfiFeatures};
+
+/// SCTLR_EL1.EnIA (instruction key A)
+const SCTLR_ENIA: u64 = 1 << 31;
+/// SCTLR_EL1.EnIB (instruction key B)
+const SCTLR_ENIB: u64 = 1 << 30;
+/// SCTLR_EL1.EnDA (data key A)
+const SCTLR_ENDA: u64 = 1 << 27;
+/// SCTLR_EL1.EnDB (data key B)
+const SCTLR_ENDB: u64 = 1 << 13;
+/// SCTLR_EL1.BT0 (BTI at EL0)
+const SCTLR_BT0: u64 = 1 << 35;
+/// SCTLR_EL1.BT1 (BTI at EL1)
+const SCTLR_BT1: u64 = 1 << 36;
+
+/// Read a system register by encoded name
+macro_rules! read_sysreg {
+    ($reg:literal) => {{
+        let value: u64;
+        unsafe { asm!(concat!("mrs {}, ", $reg), out(reg) value, options(nomem, nostack, preserves_flags)) };
+        value
+    }};
+}
+
+/// Write a system register by encoded name
+macro_rules! write_sysreg {
+    ($reg:literal, $value:expr) => {
+        asm!(concat!("msr ", $reg, ", {}"), in(reg) $value, options(nostack, preserves_flags))
+    };
+}
+
+// =============================================================================
+// Detection
+// =============================================================================
+
+/// Decode ID_AA64PFR1_EL1, ID_AA64ISAR1_EL1 and ID_AA64ISAR2_EL1
+///
+/// BTI is PFR1.BT; address authentication is any of ISAR1.APA (QARMA5),
+/// ISAR1.API (implementation defined) or ISAR2.APA3 (QARMA3).
+pub const fn features_from_id_regs(pfr1: u64, isar1: u64, isar2: u64) -> CfiFeatures {
+    let apa = (isar1 >> 4) & 0xF;
+    let api = (isar1 >> 8) & 0xF;
+    let apa3 = (isar2 >> 12) & 0xF;
+    CfiFeatures {
+        shadow_stack: false,
+        indirect_branch_tracking: false,
+        branch_target: pfr1 & 0xF != 0,
+        pointer_auth: apa != 0 || api != 0 || apa3 != 0,
+    }
+}
+
+/// Detect BTI and PAC support and record it in [`cfi`]
+pub fn detect() -> CfiFeatures {
+    let pfr1 = read_sysreg!("S3_0_C0_C4_1"); // ID_AA64PFR1_EL1
+    let isar1 = read_sysreg!("S3_0_C0_C6_1"); // ID_AA64ISAR1_EL1
+    let isar2 = read_sysreg!("S3_0_C0_C6_2"); // ID_AA64ISAR2_EL1
+    let features = features_from_id_regs(pfr1, isar1, isar2);
+    cfi().set_capability(features);
+    features
+}
+
+// =============================================================================
+// Keys
+// =============================================================================
+
+/// Pointer authentication keys (128-bit, as low/high halves)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacKeys {
+    /// Instruction key A (return addresses)
+    pub ia: (u64, u64),
+    /// Instruction key B
+    pub ib: (u64, u64),
+    /// Data key A
+    pub da: (u64, u64),
+    /// Data key B
+    pub db: (u64, u64),
+}
+
+impl PacKeys {
+    /// Derive keys from a random source
+    pub fn generate(mut random: impl FnMut() -> u64) -> Self {
+        Self {
+            ia: (random(), random()),
+            ib: (random(), random()),
+            da: (random(), random()),
+            db: (random(), random()),
+        }
+    }
+}
+
+/// Load the authentication keys
+///
+/// # Safety
+///
+/// No frame signed with the previous keys may be authenticated afterwards.
+pub unsafe fn set_keys(keys: &PacKeys) {
+    unsafe {
+        write_sysreg!("S3_0_C2_C1_0", keys.ia.0); // APIAKeyLo_EL1
+        write_sysreg!("S3_0_C2_C1_1", keys.ia.1); // APIAKeyHi_EL1
+        write_sysreg!("S3_0_C2_C1_2", keys.ib.0); // APIBKeyLo_EL1
+        write_sysreg!("S3_0_C2_C1_3", keys.ib.1); // APIBKeyHi_EL1
+        write_sysreg!("S3_0_C2_C2_0", keys.da.0); // APDAKeyLo_EL1
+        write_sysreg!("S3_0_C2_C2_1", keys.da.1); // APDAKeyHi_EL1
+        write_sysreg!("S3_0_C2_C2_2", keys.db.0); // APDBKeyLo_EL1
+        write_sysreg!("S3_0_C2_C2_3", keys.db.1); // APDBKeyHi_EL1
+        asm!("isb", options(nostack, preserves_flags));
+    }
+}
+
+// =============================================================================
+// Enabling
+// =============================================================================
+
+/// SCTLR_EL1 bits enabling `features` for user mode and, optionally, EL1
+pub const fn sctlr_bits(features: CfiFeatures, kernel: bool) -> u64 {
+    let mut bits = 0;
+    if features.pointer_auth {
+        bits |= SCTLR_ENIA | SCTLR_ENIB | SCTLR_ENDA | SCTLR_ENDB;
+    }
+    if features.branch_target {
+        bits |= SCTLR_BT0;
+        if kernel {
+            bits |= SCTLR_BT1;
+        }
+    }
+    bits
+}
+
+/// Enable BTI and PAC with `keys`
+///
+/// Returns the protection enabled for user tasks; call on every CPU.
+///
+/// # Safety
+///
+/// Must be called before any PAC-signed frame is live (from a function
+/// that never returns), with the same keys on every CPU.
+pub unsafe fn enable(keys: &PacKeys) -> CfiFeatures {
+    let capability = cfi().capability();
+    if capability.is_empty() {
+        return capability;
+    }
+
+    let kernel = cfg!(feature = "cfi");
+    let sctlr = read_sysreg!("SCTLR_EL1") | sctlr_bits(capability, kernel);
+    unsafe {
+        if capability.pointer_auth {
+            set_keys(keys);
+        }
+        write_sysreg!("SCTLR_EL1", sctlr);
+        asm!("isb", options(nostack, preserves_flags));
+    }
+
+    if kernel {
+        cfi().set_kernel(capability);
+    }
+    cfi().set_user(capability)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features_from_id_regs() {
+        let f = features_from_id_regs(0x1, 0x1 << 4, 0);
+        assert!(f.branch_target && f.pointer_auth);
+        assert!(features_from_id_regs(0, 0, 0x1 << 12).pointer_auth);
+        assert!(features_from_id_regs(0, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_sctlr_bits() {
+        let all = features_from_id_regs(1, 1 << 8, 0);
+        assert_eq!(sctlr_bits(all, false) & SCTLR_BT1, 0);
+        assert_ne!(sctlr_bits(all, true) & SCTLR_BT1, 0);
+        assert_ne!(sctlr_bits(all, false) & SCTLR_ENIA, 0);
+        assert_eq!(sctlr_bits(CfiFeatures::NONE, true), 0);
+    }
+}
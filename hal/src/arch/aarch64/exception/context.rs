@@ -105,7 +105,7 @@ pub fn for_kernel_thread(entry: u64, stack: u64) -> Self {
         let mut frame = Self::new();
         frame.elr = entry;
         frame.sp = stack;
-        frame.spsr = 0b0101; // EL1h (SP_EL1)
+        frame.spsr = 0b0100; // EL1t (SP_EL0; SP_EL1 is the exception stack)
         frame
     }
 
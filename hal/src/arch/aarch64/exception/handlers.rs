@@ -4,6 +4,8 @@
 //! for AArch64 exceptions.
 
 use super::context::TrapFrame;
+use super::stacks;
+use super::super::smp::percpu;
 use super::vectors::VectorOffset;
 
 // =============================================================================
@@ -555,7 +557,14 @@ pub extern "C" fn dispatch_exception(frame: &mut TrapFrame, vector_offset: u16)
     // Route to specific handler based on type
     match info.exception_type {
         ExceptionType::Synchronous => handle_sync_exception(frame, info),
-        ExceptionType::Irq => handle_irq(frame, info),
+        ExceptionType::Irq => {
+            let cpu = percpu::current_cpu_id() as usize;
+            crate::interrupts::irq_enter(cpu);
+            unsafe {
+                stacks::call_on_irq_stack(irq_trampoline, frame, &info as *const _ as usize);
+            }
+            crate::interrupts::irq_exit(cpu);
+        }
         ExceptionType::Fiq => handle_fiq(frame, info),
         ExceptionType::SError => handle_serror(frame, info),
     }
@@ -586,6 +595,10 @@ fn handle_sync_exception(frame: &mut TrapFrame, info: ExceptionInfo) {
             // Illegal state
             handle_illegal_state(frame, &info);
         }
+        ExceptionClass::Bti | ExceptionClass::Pac => {
+            // Control-flow violation
+            handle_cfi_violation(frame, &info);
+        }
         _ => {
             // Unhandled synchronous exception
             handle_unhandled(frame, &info);
@@ -593,6 +606,12 @@ fn handle_sync_exception(frame: &mut TrapFrame, info: ExceptionInfo) {
     }
 }
 
+/// IRQ entry on the IRQ stack (`info` points to the caller's ExceptionInfo)
+extern "C" fn irq_trampoline(frame: *mut TrapFrame, info: usize) {
+    let (frame, info) = unsafe { (&mut *frame, (*(info as *const ExceptionInfo)).clone()) };
+    handle_irq(frame, info);
+}
+
 /// Handle IRQ
 fn handle_irq(_frame: &mut TrapFrame, _info: ExceptionInfo) {
     // Placeholder - actual implementation will call GIC and dispatch
@@ -616,6 +635,24 @@ fn handle_syscall(_frame: &mut TrapFrame) {
 
 /// Handle page fault
 fn handle_page_fault(frame: &mut TrapFrame, info: &ExceptionInfo) {
+    // A user copy faulted: resume at its fixup, which reports EFAULT
+    if !info.from_lower_el {
+        if let Some(fixup) = crate::uaccess::fixup_exception(frame.elr) {
+            frame.elr = fixup;
+            return;
+        }
+    }
+
+    // Kernel stack overflow into a guard page: we are on SP_EL1, so report it
+    if !info.from_lower_el {
+        if let Some(hit) = stacks::locate_stack(info.far).filter(|hit| hit.in_guard) {
+            panic!(
+                "{}: FAR={:#x} ELR={:#x} SP={:#x}",
+                hit, info.far, frame.elr, frame.sp
+            );
+        }
+    }
+
     let _fault_addr = info.far;
     let _is_write = info.data_abort_info().map(|d| d.is_write()).unwrap_or(false);
     let _is_user = info.from_lower_el;
@@ -639,6 +676,27 @@ fn handle_illegal_state(frame: &mut TrapFrame, _info: &ExceptionInfo) {
     panic!("Illegal execution state at {:#x}", frame.elr);
 }
 
+/// Handle a branch target or pointer authentication failure
+fn handle_cfi_violation(frame: &mut TrapFrame, info: &ExceptionInfo) {
+    use crate::cfi::{report_violation, CfiViolation, CfiViolationKind};
+
+    let violation = CfiViolation {
+        kind: match info.class {
+            ExceptionClass::Bti => CfiViolationKind::BranchTarget,
+            _ => CfiViolationKind::PointerAuth,
+        },
+        ip: frame.elr,
+        ssp: 0,
+        user: info.from_lower_el,
+    };
+
+    if !report_violation(&violation) {
+        panic!("{}", violation);
+    }
+
+    // Placeholder - the violation handler terminates the offending process
+}
+
 /// Handle unhandled exception
 fn handle_unhandled(frame: &mut TrapFrame, info: &ExceptionInfo) {
     panic!(
@@ -6,6 +6,7 @@
 pub mod context;
 pub mod el;
 pub mod handlers;
+pub mod stacks;
 pub mod syscall;
 pub mod vectors;
 
@@ -13,5 +14,6 @@
 pub use context::{ExceptionContext, TrapFrame};
 pub use el::{ExceptionLevel, current_el, in_el1, in_el2};
 pub use handlers::{ExceptionHandler, ExceptionInfo, ExceptionType};
+pub use stacks::{call_on_irq_stack, locate_stack, protect_guard_pages};
 pub use syscall::{SyscallHandler, SyscallResult};
 pub use vectors::{install_vectors, ExceptionVectors};
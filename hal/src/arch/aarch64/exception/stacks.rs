@@ -0,0 +1,187 @@
+//! # Exception and IRQ Stacks
+//!
+//! Dedicated per-CPU stacks for exception and interrupt handling.
+//!
+//! ## SP_EL1 Switching
+//!
+//! Kernel code runs in EL1t, i.e. on SP_EL0. SP_EL1 is reserved for
+//! exception entry: the PE selects SP_EL1 on every exception taken to EL1,
+//! so the vector code always starts on a known-good per-CPU stack, even
+//! after the interrupted kernel stack has overflowed.
+//!
+//! ```text
+//!  EL1t kernel code ──exception──► SP_EL1 (exception stack)
+//!        (SP_EL0)                        │
+//!                                        ├── sync  ──► handled in place
+//!                                        └── IRQ   ──► IRQ stack
+//! ```
+//!
+//! Every stack, including the per-CPU boot/idle kernel stack, has a guard
+//! page below it (see [`crate::stack`]). Once the guards are unmapped, an
+//! overflow raises a data abort whose FAR falls in a guard page, and the
+//! abort handler reports which stack overflowed.
+
+use core::arch::asm;
+
+use super::super::smp::percpu::{self, PerCpuData, MAX_CPUS};
+use super::context::TrapFrame;
+use crate::stack::{StackHit, StackKind, StackLayout, StackSlot, IRQ_STACK_SIZE};
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Per-CPU boot/idle kernel stack size (16 KB)
+pub const KERNEL_STACK_SIZE: usize = 16 * 1024;
+
+/// Exception stack size (SP_EL1, 16 KB)
+pub const EXCEPTION_STACK_SIZE: usize = 16 * 1024;
+
+/// Per-CPU stack layout (lowest address first, guard page below each)
+pub const STACK_LAYOUT: StackLayout<3> = StackLayout::new([
+    StackSlot::new(StackKind::Kernel, KERNEL_STACK_SIZE),
+    StackSlot::new(StackKind::Exception, EXCEPTION_STACK_SIZE),
+    StackSlot::new(StackKind::Irq, IRQ_STACK_SIZE),
+]);
+
+/// Size of one CPU's stack area
+const PER_CPU_STACK_SIZE: usize = STACK_LAYOUT.size();
+
+// =============================================================================
+// Storage
+// =============================================================================
+
+#[repr(C, align(4096))]
+struct PerCpuStacks {
+    stacks: [[u8; PER_CPU_STACK_SIZE]; MAX_CPUS],
+}
+
+static mut STACKS: PerCpuStacks = PerCpuStacks {
+    stacks: [[0; PER_CPU_STACK_SIZE]; MAX_CPUS],
+};
+
+/// Base address of a CPU's stack area
+fn stack_area(cpu_id: usize) -> u64 {
+    assert!(cpu_id < MAX_CPUS);
+    unsafe { core::ptr::addr_of!(STACKS.stacks[cpu_id]) as u64 }
+}
+
+/// Stack top of a given kind for a CPU
+pub fn stack_top(cpu_id: usize, kind: StackKind) -> Option<u64> {
+    STACK_LAYOUT.top(stack_area(cpu_id), kind)
+}
+
+/// Boot/idle kernel stack top for a CPU (e.g. for AP startup)
+pub fn kernel_stack_top(cpu_id: usize) -> u64 {
+    stack_top(cpu_id, StackKind::Kernel).unwrap_or(0)
+}
+
+// =============================================================================
+// Installation
+// =============================================================================
+
+/// Move the current CPU to EL1t and point SP_EL1 at its exception stack
+///
+/// Execution continues on the current stack, now addressed through SP_EL0.
+/// Records the stack bounds in the per-CPU data if it is initialized.
+///
+/// # Safety
+///
+/// Must run at EL1 with interrupts masked, once per CPU, before the
+/// exception vectors are relied upon.
+pub unsafe fn init_cpu(cpu_id: usize) {
+    let exception_top = stack_top(cpu_id, StackKind::Exception).unwrap_or(0);
+
+    unsafe {
+        asm!(
+            "mov {tmp}, sp",
+            "msr spsel, #0",
+            "mov sp, {tmp}",
+            "msr spsel, #1",
+            "mov sp, {top}",
+            "msr spsel, #0",
+            tmp = out(reg) _,
+            top = in(reg) exception_top,
+            options(nomem),
+        );
+    }
+
+    if let Some(data) = PerCpuData::try_current() {
+        data.irq_stack_top = stack_top(cpu_id, StackKind::Irq).unwrap_or(0) as usize;
+        if data.stack_top == 0 {
+            data.stack_top = kernel_stack_top(cpu_id) as usize;
+            data.stack_bottom = data.stack_top - KERNEL_STACK_SIZE;
+        }
+    }
+
+    log::debug!("Exception stacks: CPU {} SP_EL1={:#x}", cpu_id, exception_top);
+}
+
+// =============================================================================
+// IRQ Stack
+// =============================================================================
+
+/// IRQ handler entry point: trap frame and an opaque argument
+pub type IrqStackFn = extern "C" fn(*mut TrapFrame, usize);
+
+/// Run an IRQ handler on the current CPU's IRQ stack
+///
+/// Nested calls (already on the IRQ stack) run in place.
+///
+/// # Safety
+///
+/// Must be called from exception context with IRQs masked.
+pub unsafe fn call_on_irq_stack(handler: IrqStackFn, frame: *mut TrapFrame, arg: usize) {
+    let cpu_id = percpu::current_cpu_id() as usize;
+    let top = match stack_top(cpu_id, StackKind::Irq) {
+        Some(top) => top,
+        None => return handler(frame, arg),
+    };
+
+    let sp: u64;
+    unsafe {
+        asm!("mov {}, sp", out(reg) sp, options(nomem, nostack, preserves_flags));
+    }
+    if sp <= top && sp > top - IRQ_STACK_SIZE as u64 {
+        return handler(frame, arg);
+    }
+
+    unsafe {
+        asm!(
+            "mov x20, sp",
+            "mov sp, {top}",
+            "blr {handler}",
+            "mov sp, x20",
+            top = in(reg) top,
+            handler = in(reg) handler,
+            in("x0") frame,
+            in("x1") arg,
+            out("x20") _,
+            clobber_abi("C"),
+        );
+    }
+}
+
+// =============================================================================
+// Guard Pages
+// =============================================================================
+
+/// Guard page addresses of a CPU's stacks
+pub fn guard_pages(cpu_id: usize) -> impl Iterator<Item = u64> {
+    STACK_LAYOUT.guard_pages(stack_area(cpu_id))
+}
+
+/// Unmap the guard pages of the first `cpu_count` CPUs
+///
+/// `unmap` receives the virtual address of each 4 KiB guard page and must
+/// remove its mapping (and invalidate the TLB entry).
+pub fn protect_guard_pages(cpu_count: usize, mut unmap: impl FnMut(u64)) {
+    for cpu_id in 0..cpu_count.min(MAX_CPUS) {
+        guard_pages(cpu_id).for_each(&mut unmap);
+    }
+}
+
+/// Find which per-CPU stack an address belongs to
+pub fn locate_stack(addr: u64) -> Option<StackHit> {
+    STACK_LAYOUT.locate(stack_area(0), MAX_CPUS, addr)
+}
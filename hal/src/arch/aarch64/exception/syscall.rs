@@ -542,9 +542,8 @@ pub fn validate_user_ptr<T>(ptr: *const T) -> Result<(), SyscallError> {
         return Err(SyscallError::Efault);
     }
 
-    // Check if in user space (below kernel address)
-    // Kernel addresses start at 0xFFFF_0000_0000_0000
-    if addr >= 0xFFFF_0000_0000_0000 {
+    // Check if in user space
+    if !crate::uaccess::access_ok(addr as u64, core::mem::size_of::<T>()) {
         return Err(SyscallError::Efault);
     }
 
@@ -558,19 +557,24 @@ pub fn validate_user_ptr_mut<T>(ptr: *mut T) -> Result<(), SyscallError> {
 
 /// Copy data from user space
 ///
+/// Faults are caught by the exception table and reported as `Efault`.
+///
 /// # Safety
-/// Pointer must be valid and readable
+/// Every bit pattern must be a valid `T`
 pub unsafe fn copy_from_user<T: Copy>(ptr: *const T) -> Result<T, SyscallError> {
     validate_user_ptr(ptr)?;
-    Ok(ptr.read_volatile())
+    // SAFETY: any bit pattern is a valid `T` per the caller
+    unsafe { crate::uaccess::read_user(ptr as u64) }.map_err(|_| SyscallError::Efault)
 }
 
 /// Copy data to user space
 ///
+/// Faults are caught by the exception table and reported as `Efault`.
+///
 /// # Safety
-/// Pointer must be valid and writable
+/// `T` must have no padding
 pub unsafe fn copy_to_user<T: Copy>(ptr: *mut T, value: T) -> Result<(), SyscallError> {
     validate_user_ptr_mut(ptr)?;
-    ptr.write_volatile(value);
-    Ok(())
+    // SAFETY: `T` has no padding per the caller
+    unsafe { crate::uaccess::write_user(ptr as u64, &value) }.map_err(|_| SyscallError::Efault)
 }
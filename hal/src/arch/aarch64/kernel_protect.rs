@@ -0,0 +1,97 @@
+//! # Kernel Page Protection
+//!
+//! Translation table side of [`crate::kernel_protect`]: the leaf
+//! descriptors mapping the kernel image get `AP[2]` (read-only at EL1) and
+//! `PXN` set or cleared, and those of guard pages `VALID`.
+//!
+//! The walk starts at TTBR1_EL1 for the upper half and TTBR0_EL1 for the
+//! lower half, assumes the 4 KiB granule with four levels, and relies on
+//! the tables being identity mapped.
+//!
+//! Blocks are changed in place when a region covers them entirely. A block
+//! straddling a region boundary is not split: replacing a live block by a
+//! table needs break-before-make, which cannot be done for the mapping the
+//! kernel is running from. The boot mapping must use pages (or
+//! region-aligned blocks) for the image.
+
+use super::mmu::asid::{read_ttbr0_el1, read_ttbr1_el1};
+use super::mmu::entries::{PTE_ADDR_MASK_4K, PTE_PXN, PTE_TABLE, PTE_VALID};
+use super::mmu::tlb::tlb_flush_page;
+use crate::kernel_protect::{ProtectError, Protection, Span};
+
+/// AP[2]: read-only
+const PTE_RDONLY: u64 = 1 << 7;
+
+/// Nothing to switch on: EL1 always honours `AP` and `PXN`
+///
+/// # Safety
+///
+/// None; kept for symmetry with the other architectures.
+pub unsafe fn enable() {}
+
+/// Leaf descriptor mapping `addr`, and the size it maps
+///
+/// # Safety
+///
+/// EL1, with the kernel's tables loaded.
+unsafe fn leaf(addr: u64, end: u64) -> Result<(*mut u64, u64), ProtectError> {
+    let ttbr = if addr >> 63 != 0 { read_ttbr1_el1() } else { read_ttbr0_el1() };
+    let mut table = (ttbr & PTE_ADDR_MASK_4K) as *mut u64;
+    for level in 0..=3u32 {
+        let shift = 39 - 9 * level;
+        let entry = unsafe { table.add(((addr >> shift) & 0x1FF) as usize) };
+        let value = unsafe { entry.read_volatile() };
+        let size = 1u64 << shift;
+        // A guard page keeps its output address with VALID cleared
+        if level == 3 && value & PTE_ADDR_MASK_4K != 0 {
+            return Ok((entry, size));
+        }
+        if value & PTE_VALID == 0 {
+            return Err(ProtectError::NotMapped(addr));
+        }
+        if value & PTE_TABLE == 0 {
+            if level == 0 {
+                return Err(ProtectError::NotMapped(addr));
+            }
+            if addr & (size - 1) == 0 && addr + size <= end {
+                return Ok((entry, size));
+            }
+            return Err(ProtectError::Unsplittable(addr));
+        }
+        table = (value & PTE_ADDR_MASK_4K) as *mut u64;
+    }
+    unreachable!()
+}
+
+/// Apply `prot` to every page of `span`
+///
+/// # Safety
+///
+/// `span` must be page aligned and mapped; see
+/// [`crate::kernel_protect::protect_kernel`].
+pub unsafe fn set_protection(span: Span, prot: Protection) -> Result<(), ProtectError> {
+    let mut addr = span.start;
+    while addr < span.end {
+        let (entry, size) = unsafe { leaf(addr, span.end)? };
+        let mut value = unsafe { entry.read_volatile() };
+        if prot.present() {
+            value |= PTE_VALID;
+        } else {
+            value &= !PTE_VALID;
+        }
+        if prot.writable() {
+            value &= !PTE_RDONLY;
+        } else {
+            value |= PTE_RDONLY;
+        }
+        if prot.executable() {
+            value &= !PTE_PXN;
+        } else {
+            value |= PTE_PXN;
+        }
+        unsafe { entry.write_volatile(value) };
+        tlb_flush_page(addr);
+        addr += size;
+    }
+    Ok(())
+}
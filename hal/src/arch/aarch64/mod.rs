@@ -88,6 +88,13 @@
 //!   - [`psci::functions`]: PSCI function calls
 //!   - [`psci::cpu_ops`]: CPU power operations
 //!
+//! ### Branch Protection
+//! - [`branch_protection`]: BTI and pointer authentication
+//!
+//! ### User Memory Access
+//! - [`uaccess`]: PAN/UAO and the fault-tolerant user copy routines
+//! - [`kernel_protect`]: the translation table side of kernel W^X
+//!
 //! ### Timer Framework
 //! - [`timers`]: ARM Timer support
 //!   - [`timers::generic_timer`]: ARM Generic Timer
@@ -133,6 +140,19 @@
 
 pub mod timers;
 
+// =============================================================================
+// BRANCH PROTECTION
+// =============================================================================
+
+pub mod branch_protection;
+
+// =============================================================================
+// USER MEMORY ACCESS
+// =============================================================================
+
+pub mod uaccess;
+pub mod kernel_protect;
+
 // NOTE: psci is part of the smp module
 
 // =============================================================================
@@ -253,6 +273,9 @@ pub unsafe fn init(&mut self) -> Result<(), &'static str> {
         // Log detected features (debug)
         let _ = features;
 
+        // Kernel may only reach user memory through crate::uaccess from here on
+        unsafe { uaccess::enable() };
+
         // Initialize timer subsystem
         self.timer_subsystem = Some(timers::TimerSubsystem::init());
 
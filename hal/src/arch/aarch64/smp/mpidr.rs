@@ -342,6 +342,16 @@ pub fn is_bsp() -> bool {
     Mpidr::current().flat_id() == 0
 }
 
+/// Read the current CPU's MIDR_EL1 (implementer and part number)
+#[inline]
+pub fn read_midr() -> u64 {
+    let value: u64;
+    unsafe {
+        asm!("mrs {}, midr_el1", out(reg) value, options(nomem, nostack, preserves_flags));
+    }
+    value
+}
+
 // ============================================================================
 // Platform-Specific Helpers
 // ============================================================================
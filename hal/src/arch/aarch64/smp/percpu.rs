@@ -505,6 +505,7 @@ pub fn init_percpu_bsp() {
         // Initialize BSP per-CPU data
         BSP_PERCPU.init(0, 0, 0); // Stack info will be set separately
         BSP_PERCPU.mpidr = Mpidr::current().value();
+        record_core_type(0);
 
         // Register and activate
         register_percpu(0, &mut BSP_PERCPU);
@@ -522,12 +523,20 @@ pub unsafe fn init_percpu_ap(cpu_id: u32) {
         // Set MPIDR now that we're running on this CPU
         data.mpidr = Mpidr::current().value();
         data.self_ptr = data as *mut PerCpuData;
+        record_core_type(cpu_id as usize);
 
         // Activate per-CPU data
         write_tpidr_el1(data as *const _ as u64);
     }
 }
 
+/// Record the core type of the current CPU from MIDR_EL1
+fn record_core_type(cpu_id: usize) {
+    if let Some(core_type) = crate::topology::core_type_from_midr(super::mpidr::read_midr()) {
+        crate::topology::topology().set_core_type(cpu_id, core_type);
+    }
+}
+
 // ============================================================================
 // Convenience Functions
 // ============================================================================
@@ -0,0 +1,184 @@
+//! # Privileged Access Never
+//!
+//! PAN and UAO, and the copy routines behind [`crate::uaccess`].
+//!
+//! - **PAN**: EL1 faults on data accesses to EL0-accessible pages.
+//!   `SCTLR_EL1.SPAN` is cleared so PAN is set again on every exception
+//!   taken to EL1.
+//! - **UAO**: kept clear, so the unprivileged `LDTR`/`STTR` used by the
+//!   copy routines check EL0 permissions: they reach user pages despite
+//!   PAN and fault on kernel pages even if `access_ok` were bypassed.
+//!
+//! Each unprivileged access has an exception table entry whose fixup
+//! leaves the loop with the number of bytes left.
+
+use core::arch::asm;
+
+/// End of the user address space (exclusive): the 48-bit TTBR0 range
+pub const USER_SPACE_END: u64 = 1 << 48;
+
+/// SCTLR_EL1.SPAN (0 = set PAN on exception entry to EL1)
+const SCTLR_SPAN: u64 = 1 << 23;
+/// PSTATE.PAN as read/written through the PAN register
+const PSTATE_PAN: u64 = 1 << 22;
+
+/// Read a system register by encoded name
+macro_rules! read_sysreg {
+    ($reg:literal) => {{
+        let value: u64;
+        unsafe { asm!(concat!("mrs {}, ", $reg), out(reg) value, options(nomem, nostack, preserves_flags)) };
+        value
+    }};
+}
+
+/// Write a system register by encoded name
+macro_rules! write_sysreg {
+    ($reg:literal, $value:expr) => {
+        asm!(concat!("msr ", $reg, ", {}"), in(reg) $value, options(nostack, preserves_flags))
+    };
+}
+
+/// Privileged access protections
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessProtection {
+    /// Privileged Access Never (ARMv8.1)
+    pub pan: bool,
+    /// User Access Override (ARMv8.2)
+    pub uao: bool,
+}
+
+// =============================================================================
+// Detection
+// =============================================================================
+
+/// Decode ID_AA64MMFR1_EL1.PAN (bits 23:20) and ID_AA64MMFR2_EL1.UAO
+/// (bits 7:4)
+pub const fn protection_from_id_regs(mmfr1: u64, mmfr2: u64) -> AccessProtection {
+    AccessProtection {
+        pan: (mmfr1 >> 20) & 0xF != 0,
+        uao: (mmfr2 >> 4) & 0xF != 0,
+    }
+}
+
+/// Detect the supported protections
+pub fn detect() -> AccessProtection {
+    let mmfr1 = read_sysreg!("S3_0_C0_C7_1"); // ID_AA64MMFR1_EL1
+    let mmfr2 = read_sysreg!("S3_0_C0_C7_2"); // ID_AA64MMFR2_EL1
+    protection_from_id_regs(mmfr1, mmfr2)
+}
+
+// =============================================================================
+// Enabling
+// =============================================================================
+
+/// Enable PAN and clear UAO on this CPU
+///
+/// Call on every CPU, before entering user mode.
+///
+/// # Safety
+///
+/// The kernel must not touch user memory other than through
+/// [`crate::uaccess`] afterwards.
+pub unsafe fn enable() -> AccessProtection {
+    let protection = detect();
+    let sctlr = read_sysreg!("SCTLR_EL1") & !SCTLR_SPAN;
+    unsafe {
+        if protection.uao {
+            write_sysreg!("S3_0_C4_C2_4", 0u64); // UAO
+        }
+        if protection.pan {
+            write_sysreg!("SCTLR_EL1", sctlr);
+            write_sysreg!("S3_0_C4_C2_3", PSTATE_PAN); // PAN
+        }
+        asm!("isb", options(nostack, preserves_flags));
+    }
+    protection
+}
+
+// =============================================================================
+// Copy
+// =============================================================================
+
+/// Copy from user memory with `LDTRB`; see
+/// [`crate::uaccess::copy_from_user`]
+///
+/// Returns the number of bytes not copied: nonzero only after a fault.
+///
+/// # Safety
+///
+/// `dst` must be valid for `len` bytes and `src` must have passed
+/// [`access_ok`](crate::uaccess::access_ok).
+#[inline(never)]
+pub unsafe fn raw_copy_from_user(dst: *mut u8, src: *const u8, len: usize) -> usize {
+    let left: usize;
+    unsafe {
+        asm!(
+            "cbz {len}, 3f",
+            "2: ldtrb {tmp:w}, [{src}]",
+            "strb {tmp:w}, [{dst}], #1",
+            "add {src}, {src}, #1",
+            "subs {len}, {len}, #1",
+            "b.ne 2b",
+            "3:",
+            ".pushsection helix_extable, \"a\"",
+            ".balign 4",
+            ".long 2b - .",
+            ".long 3b - .",
+            ".popsection",
+            dst = inout(reg) dst => _,
+            src = inout(reg) src => _,
+            len = inout(reg) len => left,
+            tmp = out(reg) _,
+            options(nostack),
+        );
+    }
+    left
+}
+
+/// Copy to user memory with `STTRB`; see [`crate::uaccess::copy_to_user`]
+///
+/// Returns the number of bytes not copied: nonzero only after a fault.
+///
+/// # Safety
+///
+/// `src` must be valid for `len` bytes and `dst` must have passed
+/// [`access_ok`](crate::uaccess::access_ok).
+#[inline(never)]
+pub unsafe fn raw_copy_to_user(dst: *mut u8, src: *const u8, len: usize) -> usize {
+    let left: usize;
+    unsafe {
+        asm!(
+            "cbz {len}, 3f",
+            "4: ldrb {tmp:w}, [{src}], #1",
+            "2: sttrb {tmp:w}, [{dst}]",
+            "add {dst}, {dst}, #1",
+            "subs {len}, {len}, #1",
+            "b.ne 4b",
+            "3:",
+            ".pushsection helix_extable, \"a\"",
+            ".balign 4",
+            ".long 2b - .",
+            ".long 3b - .",
+            ".popsection",
+            dst = inout(reg) dst => _,
+            src = inout(reg) src => _,
+            len = inout(reg) len => left,
+            tmp = out(reg) _,
+            options(nostack),
+        );
+    }
+    left
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protection_from_id_regs() {
+        let all = protection_from_id_regs(0x2 << 20, 0x1 << 4);
+        assert!(all.pan && all.uao);
+        assert_eq!(protection_from_id_regs(0x1 << 20, 0), AccessProtection { pan: true, uao: false });
+        assert_eq!(protection_from_id_regs(0xF << 16, 0xF), AccessProtection::default());
+    }
+}
@@ -670,3 +670,56 @@ pub fn unmask_gsi(gsi: u32) -> Result<(), ApicError> {
     }
     Err(ApicError::IoApicError)
 }
+
+/// Route a GSI to another destination APIC ID
+pub fn set_gsi_destination(gsi: u32, dest: u8) -> Result<(), ApicError> {
+    if let Some(ioapic) = find_ioapic_for_gsi(gsi) {
+        if let Some(index) = ioapic.gsi_to_index(gsi) {
+            if let Some(mut entry) = ioapic.read_entry(index) {
+                entry.set_destination(dest);
+                return ioapic.write_entry(index, entry);
+            }
+        }
+    }
+    Err(ApicError::IoApicError)
+}
+
+/// Move every physical-mode entry targeting `from` to `to`
+///
+/// Used to steer device IRQs off isolated CPUs. Returns the number of
+/// entries rewritten.
+pub fn migrate_destination(from: u8, to: u8) -> usize {
+    let mut moved = 0;
+    for i in 0..ioapic_count() {
+        let Some(ioapic) = get_ioapic(i) else {
+            continue;
+        };
+        for index in 0..ioapic.num_entries() {
+            let Some(mut entry) = ioapic.read_entry(index) else {
+                continue;
+            };
+            if entry.destination_mode() != DestinationMode::Physical || entry.destination() != from {
+                continue;
+            }
+            entry.set_destination(to);
+            if ioapic.write_entry(index, entry).is_ok() {
+                moved += 1;
+            }
+        }
+    }
+    moved
+}
+
+/// Route a GSI to a logical CPU
+pub fn route_gsi_to_cpu(gsi: u32, cpu: usize) -> Result<(), ApicError> {
+    let info = crate::arch::x86_64::smp::get_cpu_info(cpu).ok_or(ApicError::InvalidId)?;
+    let apic_id = u8::try_from(info.apic_id()).map_err(|_| ApicError::InvalidId)?;
+    set_gsi_destination(gsi, apic_id)
+}
+
+/// Get the destination APIC ID of a GSI
+pub fn gsi_destination(gsi: u32) -> Option<u8> {
+    let ioapic = find_ioapic_for_gsi(gsi)?;
+    let index = ioapic.gsi_to_index(gsi)?;
+    ioapic.read_entry(index).map(|entry| entry.destination())
+}
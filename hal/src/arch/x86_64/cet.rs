@@ -0,0 +1,310 @@
+//! # Control-flow Enforcement Technology
+//!
+//! Intel CET shadow stacks (backward edge) and indirect branch tracking
+//! (forward edge), for the kernel (`IA32_S_CET`) and user tasks
+//! (`IA32_U_CET`).
+//!
+//! ## Shadow stacks
+//!
+//! Shadow stack pages are mapped read-only and dirty; only `CALL`, `RET`
+//! and the shadow stack instructions write them. Each task has its own:
+//!
+//! - Kernel: a supervisor token at the top (loaded into `IA32_PL0_SSP`
+//!   for user-to-kernel transitions) and a restore token below it, used by
+//!   [`context_switch`](super::context::context_switch) to switch with
+//!   `RSTORSSP`/`SAVEPREVSSP`. Tokens are written by
+//!   [`init_kernel_shadow_stack`] before the pages are made shadow stack.
+//! - User: `IA32_PL3_SSP` points at the top; no token is needed to enter.
+//!
+//! ## Indirect branch tracking
+//!
+//! Every indirect branch target must start with `ENDBR64`. User IBT is up
+//! to the binaries; kernel IBT is only enabled with the `cfi` feature,
+//! whose builds use `-Zcf-protection=branch`.
+
+use core::arch::asm;
+
+use super::cpu::{cpuid_subleaf, read_msr, write_msr};
+use super::task::CpuContext;
+use crate::cfi::{cfi, CfiFeatures};
+use crate::{HalError, HalResult};
+
+/// IA32_U_CET MSR (user mode CET configuration)
+const IA32_U_CET: u32 = 0x6A0;
+
+/// IA32_S_CET MSR (supervisor mode CET configuration)
+const IA32_S_CET: u32 = 0x6A2;
+
+/// IA32_PL0_SSP MSR (shadow stack pointer on entry to ring 0)
+const IA32_PL0_SSP: u32 = 0x6A4;
+
+/// IA32_PL3_SSP MSR (user shadow stack pointer)
+const IA32_PL3_SSP: u32 = 0x6A7;
+
+/// IA32_INTERRUPT_SSP_TABLE_ADDR MSR (shadow stacks of the IST entries)
+const IA32_INTERRUPT_SSP_TABLE: u32 = 0x6A8;
+
+/// CR4.CET
+const CR4_CET: u64 = 1 << 23;
+
+/// CR0.WP (required by CR4.CET)
+const CR0_WP: u64 = 1 << 16;
+
+/// `IA32_x_CET` bits
+pub mod cet_flags {
+    /// Enable shadow stacks
+    pub const SH_STK_EN: u64 = 1 << 0;
+    /// Allow `WRSS`
+    pub const WR_SHSTK_EN: u64 = 1 << 1;
+    /// Enable indirect branch tracking
+    pub const ENDBR_EN: u64 = 1 << 2;
+    /// Allow `NOTRACK`-prefixed branches to skip `ENDBR64`
+    pub const NO_TRACK_EN: u64 = 1 << 4;
+}
+
+/// Shadow stack token mode bit (64-bit)
+const TOKEN_64BIT: u64 = 1;
+
+// =============================================================================
+// Detection
+// =============================================================================
+
+/// Decode CPUID leaf 7 subleaf 0 (CET_SS in ECX[7], CET_IBT in EDX[20])
+pub const fn features_from_cpuid(ecx: u32, edx: u32) -> CfiFeatures {
+    CfiFeatures {
+        shadow_stack: ecx & (1 << 7) != 0,
+        indirect_branch_tracking: edx & (1 << 20) != 0,
+        branch_target: false,
+        pointer_auth: false,
+    }
+}
+
+/// Detect CET support and record it in [`cfi`]
+pub fn detect() -> CfiFeatures {
+    let (max_leaf, _, _, _) = cpuid_subleaf(0, 0);
+    let features = if max_leaf >= 7 {
+        let (_, _, ecx, edx) = cpuid_subleaf(7, 0);
+        features_from_cpuid(ecx, edx)
+    } else {
+        CfiFeatures::NONE
+    };
+    cfi().set_capability(features);
+    features
+}
+
+// =============================================================================
+// Enabling
+// =============================================================================
+
+/// Set CR4.CET (and CR0.WP, which it requires)
+///
+/// # Safety
+///
+/// CET must be supported.
+unsafe fn enable_cr4_cet() {
+    unsafe {
+        let (mut cr0, mut cr4): (u64, u64);
+        asm!("mov {}, cr0", out(reg) cr0, options(nomem, nostack, preserves_flags));
+        asm!("mov {}, cr4", out(reg) cr4, options(nomem, nostack, preserves_flags));
+        cr0 |= CR0_WP;
+        cr4 |= CR4_CET;
+        asm!("mov cr0, {}", in(reg) cr0, options(nostack, preserves_flags));
+        asm!("mov cr4, {}", in(reg) cr4, options(nostack, preserves_flags));
+    }
+}
+
+/// Enable kernel IBT and allow user CET
+///
+/// Kernel shadow stacks are entered separately with
+/// [`enter_shadow_stack`], since the current call chain has no shadow
+/// stack to return through. Returns the protection enabled for user
+/// tasks; call on every CPU.
+///
+/// # Safety
+///
+/// With the `cfi` feature, every indirect branch target in the kernel
+/// must start with `ENDBR64`.
+pub unsafe fn enable() -> CfiFeatures {
+    let capability = cfi().capability();
+    if capability.is_empty() {
+        return capability;
+    }
+
+    unsafe { enable_cr4_cet() };
+
+    if cfg!(feature = "cfi") && capability.indirect_branch_tracking {
+        // SAFETY: IBT is supported and the kernel is built with ENDBR64
+        unsafe { write_msr(IA32_S_CET, read_msr(IA32_S_CET) | cet_flags::ENDBR_EN) };
+        cfi().set_kernel(CfiFeatures {
+            indirect_branch_tracking: true,
+            ..cfi().kernel()
+        });
+    }
+
+    cfi().set_user(capability)
+}
+
+/// Install the shadow stacks used by IST entries
+///
+/// `table[n]` holds the supervisor token address of the shadow stack for
+/// IST `n` (entry 0 is unused); each must be set up with
+/// [`init_kernel_shadow_stack`].
+///
+/// # Safety
+///
+/// `table` must stay valid while kernel shadow stacks are enabled.
+pub unsafe fn set_interrupt_ssp_table(table: &'static [u64; 8]) {
+    unsafe { write_msr(IA32_INTERRUPT_SSP_TABLE, table.as_ptr() as u64) };
+}
+
+/// Enable kernel shadow stacks and continue at `entry` on `ssp`
+///
+/// `ssp` comes from [`init_kernel_shadow_stack`]. The caller's frames are
+/// abandoned: they have no shadow stack entries to return through.
+///
+/// # Safety
+///
+/// [`enable`] must have been called, `ssp` must name a prepared shadow
+/// stack, and interrupts using IST must have shadow stacks installed with
+/// [`set_interrupt_ssp_table`].
+pub unsafe fn enter_shadow_stack(ssp: u64, entry: extern "C" fn() -> !) -> ! {
+    if !cfi().capability().shadow_stack {
+        entry();
+    }
+
+    cfi().set_kernel(CfiFeatures {
+        shadow_stack: true,
+        ..cfi().kernel()
+    });
+
+    unsafe {
+        write_msr(IA32_PL0_SSP, ssp);
+        write_msr(IA32_S_CET, read_msr(IA32_S_CET) | cet_flags::SH_STK_EN);
+        asm!(
+            // Claim the supervisor token at IA32_PL0_SSP and switch to it
+            "setssbsy",
+            "jmp {entry}",
+            entry = in(reg) entry,
+            options(noreturn),
+        );
+    }
+}
+
+// =============================================================================
+// Shadow Stacks
+// =============================================================================
+
+/// Restore token for switching to a shadow stack at `ssp` with `RSTORSSP`
+pub const fn restore_token(ssp: u64) -> u64 {
+    ssp | TOKEN_64BIT
+}
+
+/// Write the tokens of a kernel shadow stack ending at `top`
+///
+/// Returns the shadow stack pointer: the address of the supervisor token,
+/// to store in `CpuContext::ssp` and `CpuContext::pl0_ssp`.
+///
+/// # Safety
+///
+/// The 16 bytes below `top` must be writable: call before the pages are
+/// mapped as shadow stack.
+pub unsafe fn init_kernel_shadow_stack(top: u64) -> HalResult<u64> {
+    if top % 8 != 0 || top < 16 {
+        return Err(HalError::InvalidAddress);
+    }
+    let ssp = top - 8;
+    unsafe {
+        // Supervisor token (not busy)
+        (ssp as *mut u64).write_volatile(ssp);
+        // Restore token for the first switch to this stack
+        ((ssp - 8) as *mut u64).write_volatile(restore_token(ssp));
+    }
+    Ok(ssp)
+}
+
+/// Give a task its kernel shadow stack
+pub fn attach_kernel_shadow_stack(ctx: &mut CpuContext, ssp: u64) {
+    ctx.ssp = ssp;
+    ctx.pl0_ssp = ssp;
+}
+
+/// Enable user CET for a task
+///
+/// `user_ssp` is the top of a user shadow stack mapping, or 0 to leave
+/// shadow stacks off (for binaries not built for them).
+pub fn enable_user(ctx: &mut CpuContext, user_ssp: u64, ibt: bool) {
+    let user = cfi().user();
+    let mut u_cet = 0;
+    if user.shadow_stack && user_ssp != 0 {
+        u_cet |= cet_flags::SH_STK_EN;
+        ctx.pl3_ssp = user_ssp;
+    }
+    if user.indirect_branch_tracking && ibt {
+        u_cet |= cet_flags::ENDBR_EN;
+    }
+    ctx.u_cet = u_cet;
+}
+
+/// Current shadow stack pointer (0 if shadow stacks are off)
+#[inline]
+pub fn read_ssp() -> u64 {
+    let mut ssp: u64 = 0;
+    // SAFETY: RDSSPQ is a NOP when shadow stacks are disabled
+    unsafe { asm!("rdsspq {}", inout(reg) ssp, options(nomem, nostack, preserves_flags)) };
+    ssp
+}
+
+/// User shadow stack pointer of the current task
+pub fn user_ssp() -> u64 {
+    if cfi().user().shadow_stack {
+        // SAFETY: The MSR exists when CET is supported
+        unsafe { read_msr(IA32_PL3_SSP) }
+    } else {
+        0
+    }
+}
+
+/// Switch the per-task CET MSRs from `old` to `new`
+///
+/// The kernel shadow stack pointer itself is switched by `context_switch`.
+///
+/// # Safety
+///
+/// Call with interrupts disabled, right before `context_switch(old, new)`.
+pub unsafe fn switch_state(old: &mut CpuContext, new: &CpuContext) {
+    let user = cfi().user();
+    unsafe {
+        if !user.is_empty() {
+            old.u_cet = read_msr(IA32_U_CET);
+            old.pl3_ssp = read_msr(IA32_PL3_SSP);
+            write_msr(IA32_U_CET, new.u_cet);
+            write_msr(IA32_PL3_SSP, new.pl3_ssp);
+        }
+        if cfi().kernel().shadow_stack && new.pl0_ssp != 0 {
+            write_msr(IA32_PL0_SSP, new.pl0_ssp);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features_from_cpuid() {
+        let f = features_from_cpuid(1 << 7, 1 << 20);
+        assert!(f.shadow_stack && f.indirect_branch_tracking);
+        assert!(features_from_cpuid(0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_kernel_shadow_stack_tokens() {
+        let mut stack = [0u64; 4];
+        let top = stack.as_mut_ptr() as u64 + 32;
+        let ssp = unsafe { init_kernel_shadow_stack(top) }.unwrap();
+        assert_eq!(ssp, top - 8);
+        assert_eq!(stack[3], ssp);
+        assert_eq!(stack[2], ssp | 1);
+        assert_eq!(unsafe { init_kernel_shadow_stack(top + 4) }, Err(HalError::InvalidAddress));
+    }
+}
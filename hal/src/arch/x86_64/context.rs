@@ -40,6 +40,11 @@
         "pop rax",
         "mov [rdi + 0x40], rax",     // old->rflags
         
+        // Save shadow stack pointer (RDSSP leaves 0 when disabled)
+        "xor eax, eax",
+        "rdsspq rax",
+        "mov [rdi + 0x58], rax",     // old->ssp
+        
         // === Now restore from new context ===
         
         // Restore callee-saved registers from new context
@@ -50,6 +55,15 @@
         "mov rbx, [rsi + 0x20]",     // new->rbx
         "mov rbp, [rsi + 0x28]",     // new->rbp
         
+        // Switch shadow stacks: consume the restore token of the new
+        // one and leave a restore token on the old one
+        "mov rax, [rsi + 0x58]",     // new->ssp
+        "test rax, rax",
+        "jz 3f",
+        "rstorssp [rax - 8]",
+        "saveprevssp",
+        "3:",
+        
         // Restore stack pointer
         "mov rsp, [rsi + 0x38]",     // new->rsp
         
@@ -64,6 +78,7 @@
         
         // Return label (old context will return here when switched back)
         "2:",
+        "endbr64",
         "ret",
     ); }
 }
@@ -233,6 +233,14 @@ pub fn invlpg(addr: u64) {
     }
 }
 
+/// Execute CPUID for a leaf and subleaf, returning (EAX, EBX, ECX, EDX)
+#[inline]
+pub fn cpuid_subleaf(leaf: u32, subleaf: u32) -> (u32, u32, u32, u32) {
+    // RBX is reserved by LLVM, so use the intrinsic rather than inline asm
+    let r = unsafe { core::arch::x86_64::__cpuid_count(leaf, subleaf) };
+    (r.eax, r.ebx, r.ecx, r.edx)
+}
+
 /// x86_64 CPU implementation
 pub struct X86_64Cpu {
     /// CPU ID (for SMP)
@@ -0,0 +1,102 @@
+//! # ACPI Embedded Controller
+//!
+//! Register access over the EC's command and data ports (ACPI 6.5, 12.3):
+//! a command byte goes to the command port, then the address and value
+//! through the data port, waiting on the status flags between bytes.
+//! The ports come from the ECDT or the EC device's `_CRS`; most machines
+//! use the legacy pair.
+
+use super::cpu::{inb, outb};
+use crate::backlight::EmbeddedController;
+use crate::{HalError, HalResult};
+use spin::Mutex;
+
+/// Legacy EC data port
+pub const EC_DATA_PORT: u16 = 0x62;
+
+/// Legacy EC command/status port
+pub const EC_COMMAND_PORT: u16 = 0x66;
+
+/// Status: output buffer full (data to read)
+const EC_OBF: u8 = 1 << 0;
+
+/// Status: input buffer full (EC busy with the last byte)
+const EC_IBF: u8 = 1 << 1;
+
+/// Read register command
+const RD_EC: u8 = 0x80;
+
+/// Write register command
+const WR_EC: u8 = 0x81;
+
+/// Status polls before giving up
+const EC_TIMEOUT_POLLS: u32 = 100_000;
+
+/// Embedded controller at a pair of I/O ports
+pub struct AcpiEc {
+    data: u16,
+    command: u16,
+    /// Transactions must not interleave
+    lock: Mutex<()>,
+}
+
+impl AcpiEc {
+    /// EC at the given data and command ports
+    pub const fn new(data: u16, command: u16) -> Self {
+        Self {
+            data,
+            command,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// EC at the legacy ports
+    pub const fn legacy() -> Self {
+        Self::new(EC_DATA_PORT, EC_COMMAND_PORT)
+    }
+
+    /// Poll the status port until `(status & mask) == want`
+    fn wait(&self, mask: u8, want: u8) -> HalResult<()> {
+        for _ in 0..EC_TIMEOUT_POLLS {
+            // SAFETY: the status port belongs to the EC
+            if unsafe { inb(self.command) } & mask == want {
+                return Ok(());
+            }
+            core::hint::spin_loop();
+        }
+        Err(HalError::Timeout)
+    }
+
+    fn send_command(&self, command: u8) -> HalResult<()> {
+        self.wait(EC_IBF, 0)?;
+        // SAFETY: the command port belongs to the EC
+        unsafe { outb(self.command, command) };
+        Ok(())
+    }
+
+    fn send_data(&self, value: u8) -> HalResult<()> {
+        self.wait(EC_IBF, 0)?;
+        // SAFETY: the data port belongs to the EC
+        unsafe { outb(self.data, value) };
+        Ok(())
+    }
+}
+
+impl EmbeddedController for AcpiEc {
+    fn read(&self, register: u8) -> HalResult<u8> {
+        let _guard = self.lock.lock();
+        self.send_command(RD_EC)?;
+        self.send_data(register)?;
+        self.wait(EC_OBF, EC_OBF)?;
+        // SAFETY: the data port belongs to the EC
+        Ok(unsafe { inb(self.data) })
+    }
+
+    fn write(&self, register: u8, value: u8) -> HalResult<()> {
+        let _guard = self.lock.lock();
+        self.send_command(WR_EC)?;
+        self.send_data(register)?;
+        self.send_data(value)?;
+        self.wait(EC_IBF, 0)
+    }
+}
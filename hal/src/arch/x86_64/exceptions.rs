@@ -201,7 +201,15 @@ extern "C" fn general_protection_inner(frame: &InterruptStackFrame, error_code:
     panic!("General protection fault at {:#x}", frame.instruction_pointer);
 }
 
-extern "C" fn page_fault_inner(frame: &InterruptStackFrame, error_code: u64) {
+extern "C" fn page_fault_inner(frame: &mut InterruptStackFrame, error_code: u64) {
+    // A user copy faulted: resume at its fixup, which reports EFAULT
+    if error_code & page_fault_error::USER == 0 {
+        if let Some(fixup) = crate::uaccess::fixup_exception(frame.instruction_pointer) {
+            frame.instruction_pointer = fixup;
+            return;
+        }
+    }
+
     // Direct serial output for debugging
     let msg = b"\n### PAGE FAULT ###\n";
     for &c in msg {
@@ -279,6 +287,10 @@ extern "C" fn page_fault_inner(frame: &InterruptStackFrame, error_code: u64) {
     if error_code & page_fault_error::INSTRUCTION_FETCH != 0 {
         log::error!("    - Instruction fetch");
     }
+
+    if error_code & page_fault_error::USER == 0 && cr2 < crate::uaccess::USER_SPACE_END {
+        log::error!("    - Kernel access to user memory outside uaccess (SMEP/SMAP)");
+    }
     
     panic!("Page fault at {:#x} accessing {:#x}", frame.instruction_pointer, cr2);
 }
@@ -304,6 +316,35 @@ extern "C" fn simd_floating_point_inner(frame: &InterruptStackFrame) {
     panic!("SIMD error at {:#x}", frame.instruction_pointer);
 }
 
+/// Exit code of a task killed for a control-flow violation (SIGSEGV)
+const CFI_EXIT_CODE: i32 = -11;
+
+extern "C" fn control_protection_inner(frame: &InterruptStackFrame, error_code: u64) {
+    use crate::cfi::{report_violation, CfiViolation, CfiViolationKind};
+
+    let user = frame.code_segment & 3 == 3;
+    let violation = CfiViolation {
+        kind: CfiViolationKind::from_cp_error_code(error_code),
+        ip: frame.instruction_pointer,
+        ssp: if user { super::cet::user_ssp() } else { 0 },
+        user,
+    };
+
+    if !report_violation(&violation) {
+        frame.log("Control Protection Exception (#CP)");
+        panic!("{}", violation);
+    }
+
+    // Never return to the offending task: retire it and wait for the
+    // timer to switch away
+    if let Ok(sched) = super::task::try_scheduler() {
+        sched.exit(CFI_EXIT_CODE);
+    }
+    loop {
+        unsafe { asm!("sti; hlt", options(nomem, nostack)) };
+    }
+}
+
 // =============================================================================
 // Handler Wrappers using naked_asm! with sym operands
 // =============================================================================
@@ -452,3 +493,4 @@ pub extern "C" fn $name() {
 exception_handler_with_error!(alignment_check_handler, alignment_check_inner);
 exception_handler!(machine_check_handler, machine_check_inner);
 exception_handler!(simd_floating_point_handler, simd_floating_point_inner);
+exception_handler_with_error!(control_protection_handler, control_protection_inner);
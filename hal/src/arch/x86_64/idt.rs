@@ -27,7 +27,8 @@
 //!   0x12: Machine Check
 //!   0x13: SIMD Floating-Point Exception
 //!   0x14: Virtualization Exception
-//!   0x15-0x1F: Reserved
+//!   0x15: Control Protection Exception
+//!   0x16-0x1F: Reserved
 //!
 //! 0x20-0x2F: IRQs (remapped from legacy PIC)
 //! 0x30-0xFF: Software interrupts, syscalls, etc.
@@ -204,7 +205,8 @@ pub unsafe fn load(&self) {
 // Global IDT
 // =============================================================================
 
-/// Static IDT
+/// Static IDT, read-only after [`lockdown`](crate::kernel_protect::lockdown)
+#[link_section = ".data..ro_after_init"]
 static mut IDT: Idt = Idt::new();
 
 /// Exception vector numbers
@@ -228,6 +230,7 @@ pub mod vectors {
     pub const MACHINE_CHECK: u8 = 18;
     pub const SIMD_FLOATING_POINT: u8 = 19;
     pub const VIRTUALIZATION: u8 = 20;
+    pub const CONTROL_PROTECTION: u8 = 21;
     
     /// First IRQ vector (after remapping PIC)
     pub const IRQ_BASE: u8 = 32;
@@ -327,6 +330,10 @@ pub unsafe fn init() {
             exceptions::simd_floating_point_handler as u64,
             IdtEntryOptions::interrupt());
         
+        IDT.set_handler(vectors::CONTROL_PROTECTION,
+            exceptions::control_protection_handler as u64,
+            IdtEntryOptions::interrupt());
+        
         // Load the IDT
         IDT.load();
         
@@ -338,7 +345,8 @@ pub unsafe fn init() {
 /// 
 /// # Safety
 /// The handler must be a valid function pointer that follows the x86_64 interrupt
-/// calling convention.
+/// calling convention. Must run before [`lockdown`](crate::kernel_protect::lockdown),
+/// which makes the IDT read-only.
 pub unsafe fn set_handler(vector: u8, handler: u64, options: IdtEntryOptions) {
     unsafe { IDT.set_handler(vector, handler, options); }
 }
@@ -15,6 +15,7 @@
 
 use super::frame::{InterruptStackFrame, ExceptionStackFrame, InterruptContext, PageFaultErrorCode};
 use super::vectors::ExceptionVector;
+use super::segmentation;
 use core::sync::atomic::{AtomicPtr, Ordering};
 
 // =============================================================================
@@ -147,15 +148,37 @@ pub extern "x86-interrupt" fn device_not_available_handler(frame: InterruptStack
 /// Double Fault Handler (#DF)
 ///
 /// This is a diverging handler because a double fault cannot be recovered from.
+/// It runs on its own IST stack, so it can still report a kernel stack
+/// overflow: the interrupted RSP and CR2 are matched against the per-CPU
+/// stack layout to name the stack that overflowed.
 pub extern "x86-interrupt" fn double_fault_handler(frame: ExceptionStackFrame) -> ! {
+    let cr2: u64;
+    unsafe {
+        core::arch::asm!("mov {}, cr2", out(reg) cr2, options(nomem, nostack));
+    }
+
     log::error!("========================================");
     log::error!("FATAL: Double Fault (#DF)");
     log::error!("========================================");
+
+    // A fault while delivering a #PF on a guard page is the common cause
+    let overflow = segmentation::locate_stack(frame.rsp)
+        .filter(|hit| hit.in_guard)
+        .or_else(|| segmentation::locate_stack(cr2).filter(|hit| hit.in_guard));
+    match overflow {
+        Some(hit) => log::error!("Cause: {}", hit),
+        None => match segmentation::locate_stack(frame.rsp) {
+            Some(hit) => log::error!("Stack: {}", hit),
+            None => log::error!("Stack: RSP outside per-CPU stacks"),
+        },
+    }
+
     log::error!("Error Code: {:#x}", frame.error_code);
     log::error!("RIP: {:#x}", frame.rip);
     log::error!("CS:  {:#x}", frame.cs);
     log::error!("RSP: {:#x}", frame.rsp);
     log::error!("SS:  {:#x}", frame.ss);
+    log::error!("CR2: {:#x}", cr2);
     log::error!("RFLAGS: {:#x}", frame.rflags);
     log::error!("========================================");
 
@@ -214,7 +237,7 @@ pub extern "x86-interrupt" fn general_protection_handler(frame: ExceptionStackFr
 }
 
 /// Page Fault Handler (#PF)
-pub extern "x86-interrupt" fn page_fault_handler(frame: ExceptionStackFrame) {
+pub extern "x86-interrupt" fn page_fault_handler(mut frame: ExceptionStackFrame) {
     // Get the faulting address from CR2
     let faulting_address: u64;
     unsafe {
@@ -223,8 +246,20 @@ pub extern "x86-interrupt" fn page_fault_handler(frame: ExceptionStackFrame) {
 
     let error = PageFaultErrorCode::from_bits_truncate(frame.error_code);
 
+    // A user copy faulted: resume at its fixup, which reports EFAULT. The
+    // frame is the one IRETQ pops; write it volatile so the store is kept
+    if !error.contains(PageFaultErrorCode::USER_MODE) {
+        if let Some(fixup) = crate::uaccess::fixup_exception(frame.rip) {
+            unsafe { core::ptr::write_volatile(&mut frame.rip, fixup) };
+            return;
+        }
+    }
+
     log::error!("EXCEPTION: Page Fault (#PF)");
     log::error!("  Faulting Address: {:#018x}", faulting_address);
+    if let Some(hit) = segmentation::locate_stack(faulting_address).filter(|hit| hit.in_guard) {
+        log::error!("  Cause: {}", hit);
+    }
     log::error!("  Error: {}", error);
     log::error!("{:?}", frame.as_interrupt_frame());
 
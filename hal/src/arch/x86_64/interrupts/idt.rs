@@ -8,7 +8,7 @@
 //! making the total IDT size 4096 bytes (4KB).
 
 use super::entries::{IdtEntry, GateType, GateOptions, Dpl};
-use super::vectors::ExceptionVector;
+use super::vectors::{ExceptionVector, Vector};
 use super::handlers;
 use super::segmentation;
 use core::mem::size_of;
@@ -175,7 +175,9 @@ fn index_mut(&mut self, index: u8) -> &mut Self::Output {
 
 /// The global IDT instance
 ///
-/// This is shared across all CPUs.
+/// This is shared across all CPUs. Read-only after
+/// [`lockdown`](crate::kernel_protect::lockdown).
+#[link_section = ".data..ro_after_init"]
 static mut IDT: Idt = Idt::new();
 
 /// Get a reference to the global IDT
@@ -410,6 +412,10 @@ pub unsafe fn load_idt() {
 
 /// Set a handler for a specific vector
 ///
+/// Interrupt gates get the vector's IST entry (see [`Vector::ist`]), which
+/// is only set for critical exceptions; device handlers switch to the IRQ
+/// stack themselves.
+///
 /// # Safety
 ///
 /// The handler must be a valid interrupt handler function.
@@ -417,14 +423,14 @@ pub unsafe fn set_handler(vector: u8, handler: usize, gate_type: GateType) {
     let idt = unsafe { get_idt_mut() };
     let selector = segmentation::selectors::KERNEL_CS.0;
 
-    idt.set(
-        vector,
-        IdtEntry::new(
-            handler as u64,
-            selector,
-            GateOptions::from_type(gate_type),
-        ),
-    );
+    let options = match gate_type {
+        GateType::Interrupt => {
+            GateOptions::from_type(gate_type).with_ist(Vector::from_u8(vector).ist())
+        }
+        _ => GateOptions::from_type(gate_type),
+    };
+
+    idt.set(vector, IdtEntry::new(handler as u64, selector, options));
 }
 
 /// Set an exception handler with IST
@@ -281,6 +281,8 @@ pub unsafe fn end_of_interrupt(_vector: u8) {
 /// # Safety
 ///
 /// The handler must be a valid function that properly handles the interrupt.
+/// The IDT is read-only after [`lockdown`](crate::kernel_protect::lockdown):
+/// register before it, or through `stop_machine::patch_kernel`.
 pub unsafe fn register_handler(
     vector: u8,
     handler: usize,
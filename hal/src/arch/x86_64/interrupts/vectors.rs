@@ -15,6 +15,14 @@
 //! 0xF0-0xFE      Reserved                Future use
 //! 0xFF           Spurious Interrupt      APIC spurious
 //! ```
+//!
+//! ## Stacks
+//!
+//! Only the critical exceptions switch stacks through the IST (IST1-IST4).
+//! The IST resets RSP on every entry, so a nested delivery would overwrite
+//! the outer frame; device interrupts and IPIs therefore arrive on the
+//! interrupted stack and their entry stubs move onto the per-CPU IRQ stack
+//! (see `segmentation::call_on_irq_stack`).
 
 use core::fmt;
 
@@ -512,6 +520,14 @@ pub const fn from_u8(vector: u8) -> Self {
             _ => Vector::Device(vector),
         }
     }
+
+    /// IST entry used by this vector (0 = stay on the current stack)
+    pub const fn ist(&self) -> u8 {
+        match self {
+            Vector::Exception(e) => e.recommended_ist(),
+            _ => 0,
+        }
+    }
 }
 
 impl fmt::Debug for Vector {
@@ -585,4 +601,14 @@ fn test_vector_conversion() {
         assert_eq!(Vector::from_u8(0x20).as_u8(), 0x20);
         assert_eq!(Vector::from_u8(0xFF).as_u8(), 0xFF);
     }
+
+    #[test]
+    fn test_vector_ist() {
+        assert_eq!(Vector::from_u8(0x08).ist(), 1);
+        assert_eq!(Vector::from_u8(0x0E).ist(), 0);
+        assert_eq!(Vector::from_u8(0x20).ist(), 0);
+        assert_eq!(Vector::from_u8(0x40).ist(), 0);
+        assert_eq!(Vector::from_u8(0xFF).ist(), 0);
+        assert_eq!(Vector::from_u8(0x80).ist(), 0);
+    }
 }
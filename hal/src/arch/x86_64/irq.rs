@@ -1,11 +1,19 @@
 //! # IRQ Handlers
 //!
 //! Hardware interrupt handlers for timer, keyboard, etc.
+//!
+//! The entry stubs save the interrupted registers on the current stack and
+//! the handlers then move onto the per-CPU IRQ stack through
+//! [`segmentation::call_on_irq_stack`], bracketed by
+//! [`interrupts::irq_enter`] and [`interrupts::irq_exit`]. Rescheduling
+//! happens after the switch back, on the interrupted task's stack.
 
 use core::arch::{asm, naked_asm};
 use super::pic::{self, Irq};
 use super::pit;
 use super::task;
+use super::segmentation::{self, per_cpu::IrqStackFn};
+use crate::interrupts;
 
 /// Interrupt frame pushed by CPU on interrupt
 #[repr(C)]
@@ -40,9 +48,23 @@ pub struct SavedRegs {
 /// Timer tick counter for display
 static mut TIMER_TICKS: u64 = 0;
 
-/// Timer interrupt handler (IRQ 0 = vector 0x20)
-#[no_mangle]
-pub extern "C" fn timer_handler_inner() {
+/// Run a device IRQ handler on the IRQ stack in hard-IRQ context
+///
+/// Pending softirqs run in [`interrupts::irq_exit`], after the handler
+/// has sent its EOI.
+fn dispatch(handler: IrqStackFn, arg: usize) {
+    let cpu = segmentation::per_cpu::interrupted_cpu();
+    interrupts::irq_enter(cpu);
+    unsafe {
+        segmentation::call_on_irq_stack(handler, arg);
+    }
+    interrupts::irq_exit(cpu);
+}
+
+/// Timer tick, run on the IRQ stack
+///
+/// `arg` points to a `bool` that is set when the scheduler wants to switch.
+extern "C" fn timer_irq(arg: usize) {
     // Update PIT tick counter
     let ticks = pit::tick();
     
@@ -50,16 +72,36 @@ pub extern "C" fn timer_handler_inner() {
         TIMER_TICKS = ticks;
     }
     
-    // Check if we should preempt
-    let should_switch = task::scheduler().tick();
+    // Cross-check the TSC against HPET
+    super::timers::clocksource::watchdog();
+    
+    // Check if we should preempt (ticks before the scheduler exists are
+    // just counted)
+    let should_switch = task::try_scheduler().ok().is_some_and(|s| s.tick());
+    unsafe {
+        *(arg as *mut bool) = should_switch;
+    }
     
     // Send EOI before potentially switching (important!)
     pic::end_of_interrupt(Irq::Timer);
+}
+
+/// Timer interrupt handler (IRQ 0 = vector 0x20)
+#[no_mangle]
+pub extern "C" fn timer_handler_inner() {
+    let mut should_switch = false;
+    dispatch(timer_irq, &mut should_switch as *mut bool as usize);
     
-    // Perform context switch if needed
-    if should_switch {
-        if let Some((old_ctx, new_ctx)) = task::scheduler().schedule() {
+    // Perform context switch if needed (back on the task's stack, so the
+    // saved context does not point into the IRQ stack)
+    if !should_switch {
+        return;
+    }
+    if let Ok(sched) = task::try_scheduler() {
+        if let Some((old_ctx, new_ctx)) = sched.schedule() {
             unsafe {
+                super::cet::switch_state(&mut *old_ctx, &*new_ctx);
+                super::speculation::switch_state(&*old_ctx, &*new_ctx);
                 super::context::context_switch(old_ctx, new_ctx);
             }
         }
@@ -114,9 +156,8 @@ pub extern "C" fn timer_handler_inner() {
     ); }
 }
 
-/// Keyboard interrupt handler (IRQ 1 = vector 0x21)
-#[no_mangle]
-pub extern "C" fn keyboard_handler_inner() {
+/// Keyboard scancode read, run on the IRQ stack
+extern "C" fn keyboard_irq(_arg: usize) {
     // Read scancode from keyboard controller
     let scancode: u8;
     unsafe {
@@ -135,6 +176,12 @@ pub extern "C" fn keyboard_handler_inner() {
     pic::end_of_interrupt(Irq::Keyboard);
 }
 
+/// Keyboard interrupt handler (IRQ 1 = vector 0x21)
+#[no_mangle]
+pub extern "C" fn keyboard_handler_inner() {
+    dispatch(keyboard_irq, 0);
+}
+
 /// Keyboard interrupt entry point
 #[naked]
 pub unsafe extern "C" fn keyboard_handler() {
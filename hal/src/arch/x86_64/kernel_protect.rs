@@ -0,0 +1,176 @@
+//! # Kernel Page Protection
+//!
+//! Page table side of [`crate::kernel_protect`]: the leaf entries mapping
+//! the kernel image get `WRITABLE` and `NX` set or cleared, and those of
+//! guard pages `PRESENT`.
+//!
+//! Like [`super::paging`], this walks the live tables from CR3 and relies
+//! on the tables being identity mapped. Upper-level entries are left
+//! alone; only leaves change. A 2 MiB or 1 GiB page that straddles a
+//! region boundary is split into smaller pages with identical
+//! translations, using tables from a static pool.
+
+use core::arch::asm;
+use core::ptr::addr_of_mut;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use super::cpu::{read_msr, write_msr};
+use super::paging::{flags, get_cr3, invlpg};
+use crate::kernel_protect::{ProtectError, Protection, Span};
+
+/// Execute-disable (requires EFER.NXE)
+const NO_EXECUTE: u64 = 1 << 63;
+/// PAT bit of a 4 KiB entry
+const PAT_4K: u64 = 1 << 7;
+/// PAT bit of a 2 MiB or 1 GiB entry
+const PAT_LARGE: u64 = 1 << 12;
+/// Physical address bits of an entry
+const ADDR_MASK: u64 = 0x000F_FFFF_FFFF_F000;
+
+/// CR0.WP: supervisor writes honour read-only pages
+const CR0_WP: u64 = 1 << 16;
+/// IA32_EFER
+const MSR_EFER: u32 = 0xC000_0080;
+/// EFER.NXE: enable the NX bit
+const EFER_NXE: u64 = 1 << 11;
+
+/// Large pages that can be split before the pool runs out
+const SPLIT_TABLES: usize = 16;
+
+#[derive(Clone, Copy)]
+#[repr(C, align(4096))]
+struct TablePage([u64; 512]);
+
+/// Tables for split large pages; identity mapped like the boot tables
+static mut SPLIT_POOL: [TablePage; SPLIT_TABLES] = [TablePage([0; 512]); SPLIT_TABLES];
+static SPLIT_USED: AtomicUsize = AtomicUsize::new(0);
+
+/// NX is available and enabled
+static NX_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Enforce read-only pages in ring 0 and enable NX where supported
+///
+/// # Safety
+///
+/// Ring 0 only. Writes to read-only kernel pages fault from here on.
+pub unsafe fn enable() {
+    // SAFETY: CPUID is always available in long mode
+    let nx = unsafe { ::core::arch::x86_64::__cpuid(0x8000_0001) }.edx & (1 << 20) != 0;
+    unsafe {
+        if nx {
+            write_msr(MSR_EFER, read_msr(MSR_EFER) | EFER_NXE);
+        }
+        let mut cr0: u64;
+        asm!("mov {}, cr0", out(reg) cr0, options(nomem, nostack, preserves_flags));
+        cr0 |= CR0_WP;
+        asm!("mov cr0, {}", in(reg) cr0, options(nostack, preserves_flags));
+    }
+    NX_ENABLED.store(nx, Ordering::Relaxed);
+}
+
+/// Take a zeroed table from the pool
+fn alloc_table() -> Result<*mut u64, ProtectError> {
+    let index = SPLIT_USED.fetch_add(1, Ordering::Relaxed);
+    if index >= SPLIT_TABLES {
+        SPLIT_USED.store(SPLIT_TABLES, Ordering::Relaxed);
+        return Err(ProtectError::NoTables);
+    }
+    // SAFETY: each pool entry is handed out once
+    Ok(unsafe { addr_of_mut!(SPLIT_POOL[index].0) } as *mut u64)
+}
+
+/// Replace the large page `*entry` (mapping `size` bytes) by a table of
+/// 512 entries mapping the same memory with the same attributes
+///
+/// # Safety
+///
+/// `entry` must be a present large-page entry of the live tables.
+unsafe fn split(entry: *mut u64, size: u64) -> Result<u64, ProtectError> {
+    let table = alloc_table()?;
+    let value = unsafe { entry.read_volatile() };
+    let base = value & ADDR_MASK & !(size - 1);
+    let child_size = size / 512;
+    let pat = value & PAT_LARGE != 0;
+    let mut child_flags = value & !ADDR_MASK;
+    if child_size == 0x1000 {
+        child_flags &= !flags::HUGE_PAGE;
+        if pat {
+            child_flags |= PAT_4K;
+        }
+    } else if pat {
+        child_flags |= PAT_LARGE;
+    }
+    for i in 0..512 {
+        unsafe { table.add(i).write_volatile((base + i as u64 * child_size) | child_flags) };
+    }
+    let next = (table as u64) | flags::PRESENT | flags::WRITABLE | (value & flags::USER);
+    unsafe { entry.write_volatile(next) };
+    Ok(next)
+}
+
+/// Leaf entry mapping `addr`, and the size it maps
+///
+/// Large pages that do not fit in `[addr, end)` are split first.
+///
+/// # Safety
+///
+/// Ring 0, with the kernel's tables loaded in CR3.
+unsafe fn leaf(addr: u64, end: u64) -> Result<(*mut u64, u64), ProtectError> {
+    let mut table = (get_cr3() & ADDR_MASK) as *mut u64;
+    for level in (1..=4u32).rev() {
+        let shift = 12 + 9 * (level - 1);
+        let entry = unsafe { table.add(((addr >> shift) & 0x1FF) as usize) };
+        let mut value = unsafe { entry.read_volatile() };
+        let size = 1u64 << shift;
+        // A guard page keeps its translation with PRESENT cleared
+        if level == 1 && value & ADDR_MASK != 0 {
+            return Ok((entry, size));
+        }
+        if value & flags::PRESENT == 0 {
+            return Err(ProtectError::NotMapped(addr));
+        }
+        if value & flags::HUGE_PAGE != 0 {
+            if addr & (size - 1) == 0 && addr + size <= end {
+                return Ok((entry, size));
+            }
+            value = unsafe { split(entry, size)? };
+            invlpg(addr);
+        }
+        table = (value & ADDR_MASK) as *mut u64;
+    }
+    unreachable!()
+}
+
+/// Apply `prot` to every page of `span`
+///
+/// # Safety
+///
+/// `span` must be page aligned and mapped; see
+/// [`crate::kernel_protect::protect_kernel`].
+pub unsafe fn set_protection(span: Span, prot: Protection) -> Result<(), ProtectError> {
+    let nx = NX_ENABLED.load(Ordering::Relaxed);
+    let mut addr = span.start;
+    while addr < span.end {
+        let (entry, size) = unsafe { leaf(addr, span.end)? };
+        let mut value = unsafe { entry.read_volatile() };
+        if prot.present() {
+            value |= flags::PRESENT;
+        } else {
+            value &= !flags::PRESENT;
+        }
+        if prot.writable() {
+            value |= flags::WRITABLE;
+        } else {
+            value &= !flags::WRITABLE;
+        }
+        if prot.executable() {
+            value &= !NO_EXECUTE;
+        } else if nx {
+            value |= NO_EXECUTE;
+        }
+        unsafe { entry.write_volatile(value) };
+        invlpg(addr);
+        addr += size;
+    }
+    Ok(())
+}
@@ -53,6 +53,8 @@
 //!   - [`timers::apic_timer`]: Per-CPU APIC Timer
 //!   - [`timers::pit`]: Legacy PIT (for calibration)
 //!   - [`timers::calibration`]: Timer calibration routines
+//!   - [`timers::tsc_sync`]: Cross-CPU TSC synchronization check
+//!   - [`timers::clocksource`]: Clocksource selection and watchdog
 //!
 //! ### SMP Framework (NEW - Industrial Grade)
 //! - [`smp`]: Symmetric Multi-Processing support
@@ -61,6 +63,22 @@
 //!   - [`smp::per_cpu`]: Per-CPU data with GS base
 //!   - [`smp::barriers`]: Synchronization primitives
 //!
+//! ### Cache Allocation
+//! - [`rdt`]: L3 Cache Allocation Technology (classes of service)
+//!
+//! ### Control-flow Protection
+//! - [`cet`]: CET shadow stacks and indirect branch tracking
+//!
+//! ### Speculative Execution
+//! - [`speculation`]: Spectre/Meltdown detection, IBRS/STIBP/IBPB, PTI and buffer clearing
+//!
+//! ### User Memory Access
+//! - [`uaccess`]: SMEP/SMAP/UMIP and the fault-tolerant user copy routine
+//! - [`kernel_protect`]: CR0.WP, NX and the page table side of kernel W^X
+//!
+//! ### Platform Devices
+//! - [`ec`]: ACPI embedded controller (brightness and other vendor registers)
+//!
 //! ### Legacy Modules (Being Refactored)
 //! - [`gdt`]: Global Descriptor Table (DEPRECATED - use segmentation)
 //! - [`idt`]: Interrupt Descriptor Table (DEPRECATED - use interrupts)
@@ -79,6 +97,12 @@
 pub mod apic;
 pub mod timers;
 pub mod smp;
+pub mod rdt;
+pub mod cet;
+pub mod speculation;
+pub mod uaccess;
+pub mod kernel_protect;
+pub mod ec;
 
 // =============================================================================
 // EXISTING MODULES (Legacy - To Be Refactored)
@@ -171,5 +195,12 @@ pub unsafe fn init() {
         syscall::init();
     }
 
+    // Kernel may only reach user memory through crate::uaccess from here on
+    let protection = unsafe { uaccess::enable() };
+    log::info!(
+        "x86_64 HAL: SMEP={} SMAP={} UMIP={}",
+        protection.smep, protection.smap, protection.umip
+    );
+
     log::info!("x86_64 HAL fully initialized");
 }
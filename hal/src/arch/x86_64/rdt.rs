@@ -0,0 +1,63 @@
+//! # Resource Director Technology (Cache Allocation)
+//!
+//! Programs L3 Cache Allocation Technology: one capacity bitmask per class
+//! of service in `IA32_L3_QOS_MASK_n`, and the class of the current CPU in
+//! `IA32_PQR_ASSOC`. Capabilities are detected by
+//! [`super::smp::cpu_info::detect_caches`].
+
+use super::cpu::{read_msr, write_msr};
+use crate::cache::caches;
+use crate::{HalError, HalResult};
+
+/// IA32_PQR_ASSOC MSR (CLOS in bits 63:32)
+const IA32_PQR_ASSOC: u32 = 0xC8F;
+
+/// IA32_L3_QOS_MASK_0 MSR, one per class of service
+const IA32_L3_QOS_MASK_BASE: u32 = 0xC90;
+
+/// Set the L3 capacity bitmask of a class of service
+///
+/// Class 0 is the default of every CPU and should keep a wide mask.
+pub fn set_cos_mask(cos: u16, mask: u32) -> HalResult<()> {
+    let cat = caches().cat().ok_or(HalError::NotSupported)?;
+    if cos > cat.max_cos || !cat.is_valid_mask(mask) {
+        return Err(HalError::InvalidParameter);
+    }
+    // SAFETY: CAT is supported and the class is within range
+    unsafe { write_msr(IA32_L3_QOS_MASK_BASE + cos as u32, mask as u64) };
+    Ok(())
+}
+
+/// Get the L3 capacity bitmask of a class of service
+pub fn cos_mask(cos: u16) -> HalResult<u32> {
+    let cat = caches().cat().ok_or(HalError::NotSupported)?;
+    if cos > cat.max_cos {
+        return Err(HalError::InvalidParameter);
+    }
+    // SAFETY: CAT is supported and the class is within range
+    Ok(unsafe { read_msr(IA32_L3_QOS_MASK_BASE + cos as u32) } as u32)
+}
+
+/// Assign the current CPU to a class of service
+///
+/// Called on context switch to apply the incoming task's class.
+pub fn assign_cos(cos: u16) -> HalResult<()> {
+    let cat = caches().cat().ok_or(HalError::NotSupported)?;
+    if cos > cat.max_cos {
+        return Err(HalError::InvalidParameter);
+    }
+    // SAFETY: IA32_PQR_ASSOC exists when CAT is supported; the RMID in
+    // the low bits is preserved
+    unsafe {
+        let rmid = read_msr(IA32_PQR_ASSOC) & 0xFFFF_FFFF;
+        write_msr(IA32_PQR_ASSOC, ((cos as u64) << 32) | rmid);
+    }
+    Ok(())
+}
+
+/// Class of service of the current CPU
+pub fn current_cos() -> Option<u16> {
+    caches().cat()?;
+    // SAFETY: IA32_PQR_ASSOC exists when CAT is supported
+    Some((unsafe { read_msr(IA32_PQR_ASSOC) } >> 32) as u16)
+}
@@ -49,9 +49,15 @@
 //! | 2   | #NMI (Non-Maskable Int)  | 8 KB    |
 //! | 3   | #MC (Machine Check)      | 8 KB    |
 //! | 4   | #DB (Debug)              | 8 KB    |
-//! | 5   | Reserved                 | 8 KB    |
-//! | 6   | Reserved                 | 8 KB    |
-//! | 7   | Reserved                 | 8 KB    |
+//! | 5   | Unused                   | -       |
+//! | 6   | Unused                   | -       |
+//! | 7   | Unused                   | -       |
+//!
+//! Hardware interrupts do not use the IST: their entry stubs switch to a
+//! separate 16 KB per-CPU IRQ stack (see [`call_on_irq_stack`]), so a
+//! nested IRQ keeps running on the same stack instead of overwriting the
+//! outer frame. The kernel stack, every IST stack and the IRQ stack have a
+//! 4 KB guard page below them.
 
 pub mod gdt;
 pub mod tss;
@@ -78,7 +84,8 @@
 pub use per_cpu::{
     PerCpuSegmentation,
     init_bsp, init_ap,
-    MAX_CPUS,
+    call_on_irq_stack, guard_pages, locate_stack, protect_guard_pages,
+    MAX_CPUS, STACK_LAYOUT,
 };
 
 /// Initialize segmentation for the bootstrap processor
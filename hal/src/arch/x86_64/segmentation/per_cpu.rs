@@ -33,10 +33,18 @@
 //! │                                                                  │
 //! └─────────────────────────────────────────────────────────────────┘
 //! ```
+//!
+//! ## Stacks
+//!
+//! Each CPU owns a kernel stack and one stack per used IST entry, laid out
+//! by [`STACK_LAYOUT`] with a guard page below every stack. Once paging is
+//! up, [`protect_guard_pages`] unmaps the guards so an overflow faults
+//! instead of running into the neighbouring stack.
 
 use super::gdt::Gdt;
 use super::tss::{Tss, IstIndex, KERNEL_STACK_SIZE, IST_STACK_SIZE, IST_DOUBLE_FAULT_SIZE};
 use super::selectors::TSS_SELECTOR;
+use crate::stack::{StackHit, StackKind, StackLayout, StackSlot, IRQ_STACK_SIZE};
 use core::sync::atomic::{AtomicUsize, Ordering};
 
 // =============================================================================
@@ -46,10 +54,18 @@
 /// Maximum number of CPUs supported
 pub const MAX_CPUS: usize = 256;
 
-/// Size of per-CPU stack area (kernel stack + IST stacks)
-const PER_CPU_STACK_SIZE: usize = KERNEL_STACK_SIZE
-    + IST_DOUBLE_FAULT_SIZE  // IST1: Double Fault (larger)
-    + IST_STACK_SIZE * 6;    // IST2-IST7
+/// Per-CPU stack layout (lowest address first, guard page below each)
+pub const STACK_LAYOUT: StackLayout<6> = StackLayout::new([
+    StackSlot::new(StackKind::Kernel, KERNEL_STACK_SIZE),
+    StackSlot::new(StackKind::DoubleFault, IST_DOUBLE_FAULT_SIZE), // IST1
+    StackSlot::new(StackKind::Nmi, IST_STACK_SIZE),                // IST2
+    StackSlot::new(StackKind::MachineCheck, IST_STACK_SIZE),       // IST3
+    StackSlot::new(StackKind::Debug, IST_STACK_SIZE),              // IST4
+    StackSlot::new(StackKind::Irq, IRQ_STACK_SIZE),                // IRQ stack
+]);
+
+/// Size of per-CPU stack area (stacks + guard pages)
+const PER_CPU_STACK_SIZE: usize = STACK_LAYOUT.size();
 
 // =============================================================================
 // STATIC DATA
@@ -72,9 +88,7 @@
 
 /// Per-CPU stack storage
 ///
-/// Layout per CPU:
-/// - [0..KERNEL_STACK_SIZE): Kernel stack
-/// - [KERNEL_STACK_SIZE..]: IST stacks
+/// Layout per CPU is given by [`STACK_LAYOUT`].
 #[repr(C, align(4096))]
 struct PerCpuStacks {
     stacks: [[u8; PER_CPU_STACK_SIZE]; MAX_CPUS],
@@ -117,31 +131,14 @@ pub fn tss_mut(&mut self) -> &mut Tss {
 
     /// Get kernel stack top for this CPU
     pub fn kernel_stack_top(&self) -> u64 {
-        unsafe {
-            let base = STACKS.stacks[self.cpu_id].as_ptr() as u64;
-            // Stack top is at end of kernel stack region, 16-byte aligned
-            (base + KERNEL_STACK_SIZE as u64) & !0xF
-        }
+        stack_top(self.cpu_id, StackKind::Kernel).unwrap_or(0)
     }
 
-    /// Get IST stack top for this CPU
+    /// Get IST stack top for this CPU (0 if the IST entry is unused)
     pub fn ist_stack_top(&self, ist: IstIndex) -> u64 {
-        unsafe {
-            let base = STACKS.stacks[self.cpu_id].as_ptr() as u64;
-            let mut offset = KERNEL_STACK_SIZE;
-
-            // Add offsets for previous IST stacks
-            for i in 1..=(ist as usize) {
-                offset += if i == 1 {
-                    IST_DOUBLE_FAULT_SIZE
-                } else {
-                    IST_STACK_SIZE
-                };
-            }
-
-            // Stack top at end of this IST region, 16-byte aligned
-            (base + offset as u64) & !0xF
-        }
+        ist_stack_kind(ist)
+            .and_then(|kind| stack_top(self.cpu_id, kind))
+            .unwrap_or(0)
     }
 
     /// CPU ID
@@ -150,6 +147,110 @@ pub fn cpu_id(&self) -> usize {
     }
 }
 
+// =============================================================================
+// STACKS
+// =============================================================================
+
+/// Stack backing an IST entry
+const fn ist_stack_kind(ist: IstIndex) -> Option<StackKind> {
+    match ist {
+        IstIndex::DoubleFault => Some(StackKind::DoubleFault),
+        IstIndex::Nmi => Some(StackKind::Nmi),
+        IstIndex::MachineCheck => Some(StackKind::MachineCheck),
+        IstIndex::Debug => Some(StackKind::Debug),
+        IstIndex::Reserved5 | IstIndex::Reserved6 | IstIndex::Reserved7 => None,
+    }
+}
+
+/// Base address of a CPU's stack area
+fn stack_area(cpu_id: usize) -> u64 {
+    unsafe { core::ptr::addr_of!(STACKS.stacks[cpu_id]) as u64 }
+}
+
+/// Stack top of a given kind for a CPU
+fn stack_top(cpu_id: usize, kind: StackKind) -> Option<u64> {
+    STACK_LAYOUT.top(stack_area(cpu_id), kind)
+}
+
+/// Guard page addresses of a CPU's stacks
+pub fn guard_pages(cpu_id: usize) -> impl Iterator<Item = u64> {
+    assert!(cpu_id < MAX_CPUS);
+    STACK_LAYOUT.guard_pages(stack_area(cpu_id))
+}
+
+/// Unmap the guard pages of every initialized CPU
+///
+/// `unmap` receives the virtual address of each 4 KiB guard page and must
+/// remove its mapping (and flush the TLB entry).
+pub fn protect_guard_pages(mut unmap: impl FnMut(u64)) {
+    for cpu_id in 0..cpu_count() {
+        guard_pages(cpu_id).for_each(&mut unmap);
+    }
+    log::debug!("Segmentation: stack guard pages unmapped for {} CPUs", cpu_count());
+}
+
+/// Find which per-CPU stack an address belongs to
+///
+/// Used by fault handlers to report stack overflows.
+pub fn locate_stack(addr: u64) -> Option<StackHit> {
+    STACK_LAYOUT.locate(stack_area(0), cpu_count(), addr)
+}
+
+// =============================================================================
+// IRQ STACK
+// =============================================================================
+
+/// Index of the CPU taking an interrupt
+///
+/// Before per-CPU data is set up only the BSP takes interrupts.
+pub(crate) fn interrupted_cpu() -> usize {
+    crate::arch::x86_64::smp::per_cpu::current_percpu().map_or(0, |data| data.cpu_id as usize)
+}
+
+/// IRQ handler run on the IRQ stack, with an opaque argument
+pub type IrqStackFn = extern "C" fn(usize);
+
+/// Run an IRQ handler on the current CPU's IRQ stack
+///
+/// Device interrupt gates do not use the IST, so the CPU delivers them on
+/// the interrupted stack; the entry stub then calls this to move onto the
+/// per-CPU IRQ stack. Nested calls (already on the IRQ stack) run in place,
+/// so a nested IRQ pushes below the outer one instead of overwriting it.
+///
+/// # Safety
+///
+/// Must be called from interrupt context with interrupts disabled.
+pub unsafe fn call_on_irq_stack(handler: IrqStackFn, arg: usize) {
+    let top = match stack_top(interrupted_cpu(), StackKind::Irq) {
+        Some(top) => top,
+        None => return handler(arg),
+    };
+
+    let rsp: u64;
+    unsafe {
+        core::arch::asm!("mov {}, rsp", out(reg) rsp, options(nomem, nostack, preserves_flags));
+    }
+    if rsp <= top && rsp > top - IRQ_STACK_SIZE as u64 {
+        return handler(arg);
+    }
+
+    // rbx is callee-saved, so it survives the call and restores the old RSP
+    unsafe {
+        core::arch::asm!(
+            "push rbx",
+            "mov rbx, rsp",
+            "mov rsp, {top}",
+            "call {handler}",
+            "mov rsp, rbx",
+            "pop rbx",
+            top = in(reg) top,
+            handler = in(reg) handler,
+            in("rdi") arg,
+            clobber_abi("C"),
+        );
+    }
+}
+
 // =============================================================================
 // INITIALIZATION
 // =============================================================================
@@ -187,39 +288,22 @@ unsafe fn init_cpu(cpu_id: usize) {
     // Initialize TSS
     *tss = Tss::new();
 
-    // Calculate stack addresses
-    let base = STACKS.stacks[cpu_id].as_ptr() as u64;
-
     // Set kernel stack (RSP0)
-    let kernel_stack_top = (base + KERNEL_STACK_SIZE as u64) & !0xF;
-    tss.set_kernel_stack(kernel_stack_top);
-
-    // Set IST stacks
-    let mut offset = KERNEL_STACK_SIZE;
-
-    // IST1: Double Fault (larger stack)
-    offset += IST_DOUBLE_FAULT_SIZE;
-    tss.set_ist(IstIndex::DoubleFault, (base + offset as u64) & !0xF);
-
-    // IST2: NMI
-    offset += IST_STACK_SIZE;
-    tss.set_ist(IstIndex::Nmi, (base + offset as u64) & !0xF);
-
-    // IST3: Machine Check
-    offset += IST_STACK_SIZE;
-    tss.set_ist(IstIndex::MachineCheck, (base + offset as u64) & !0xF);
-
-    // IST4: Debug
-    offset += IST_STACK_SIZE;
-    tss.set_ist(IstIndex::Debug, (base + offset as u64) & !0xF);
+    if let Some(top) = stack_top(cpu_id, StackKind::Kernel) {
+        tss.set_kernel_stack(top);
+    }
 
-    // IST5-7: Reserved
-    offset += IST_STACK_SIZE;
-    tss.set_ist(IstIndex::Reserved5, (base + offset as u64) & !0xF);
-    offset += IST_STACK_SIZE;
-    tss.set_ist(IstIndex::Reserved6, (base + offset as u64) & !0xF);
-    offset += IST_STACK_SIZE;
-    tss.set_ist(IstIndex::Reserved7, (base + offset as u64) & !0xF);
+    // Set IST stacks (IST5-7 stay unused)
+    for ist in [
+        IstIndex::DoubleFault,
+        IstIndex::Nmi,
+        IstIndex::MachineCheck,
+        IstIndex::Debug,
+    ] {
+        if let Some(top) = ist_stack_kind(ist).and_then(|kind| stack_top(cpu_id, kind)) {
+            tss.set_ist(ist, top);
+        }
+    }
 
     // Link TSS to GDT
     gdt.set_tss(tss as *const Tss);
@@ -306,6 +390,7 @@ pub fn dump_info(cpu_id: usize) {
         log::debug!("IST2 (NMI): {:#018x}", tss.ist[1]);
         log::debug!("IST3 (MC): {:#018x}", tss.ist[2]);
         log::debug!("IST4 (DB): {:#018x}", tss.ist[3]);
+        log::debug!("IRQ stack: {:#018x}", stack_top(cpu_id, StackKind::Irq).unwrap_or(0));
     }
 }
 
@@ -323,6 +408,28 @@ fn test_per_cpu_stack_size() {
         assert!(PER_CPU_STACK_SIZE >= KERNEL_STACK_SIZE + IST_DOUBLE_FAULT_SIZE + 6 * IST_STACK_SIZE);
     }
 
+    #[test]
+    fn test_ist_stacks_guarded() {
+        let base = 0x100_0000;
+        let guards: [u64; 6] = {
+            let mut g = [0; 6];
+            for (slot, addr) in g.iter_mut().zip(STACK_LAYOUT.guard_pages(base)) {
+                *slot = addr;
+            }
+            g
+        };
+
+        // Every stack's guard page sits directly below its bottom
+        for (i, slot) in STACK_LAYOUT.slots().iter().enumerate() {
+            let bottom = STACK_LAYOUT.bottom(base, slot.kind).unwrap();
+            assert_eq!(bottom, guards[i] + crate::stack::GUARD_PAGE_SIZE as u64);
+        }
+
+        assert!(ist_stack_kind(IstIndex::Debug).is_some());
+        assert!(ist_stack_kind(IstIndex::Reserved5).is_none());
+        assert!(STACK_LAYOUT.bottom(base, StackKind::Irq).is_some());
+    }
+
     #[test]
     fn test_max_cpus() {
         assert!(MAX_CPUS >= 1);
@@ -6,6 +6,8 @@
 use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
 
 use super::{MAX_CPUS, SmpError};
+use crate::cache::{decode_cpuid_cache, CatCapability};
+use crate::topology::{core_type_from_cpuid, CoreType};
 
 // =============================================================================
 // CPU State
@@ -416,6 +418,60 @@ fn detect_topology_01(topology: &mut CpuTopology) {
     }
 }
 
+/// Detect the core type of the current CPU (hybrid parts only)
+///
+/// Uses CPUID leaf 0x1A when leaf 7 reports a hybrid part; `None` on
+/// non-hybrid CPUs.
+pub fn detect_core_type() -> Option<CoreType> {
+    let (max_leaf, _, _, _) = cpuid(0);
+    if max_leaf < 0x1A {
+        return None;
+    }
+    let (_, _, _, edx) = cpuid_subleaf(7, 0);
+    if edx & (1 << 15) == 0 {
+        return None;
+    }
+    let (eax, _, _, _) = cpuid_subleaf(0x1A, 0);
+    core_type_from_cpuid(eax)
+}
+
+/// Enumerate the cache hierarchy and LLC allocation support
+///
+/// Records every cache level from CPUID leaf 4 (Intel) or 0x8000001D (AMD
+/// with topology extensions), and the L3 CAT capabilities from leaf 0x10,
+/// in the global [`crate::cache::caches`] topology.
+pub fn detect_caches() {
+    let caches = crate::cache::caches();
+    let (max_leaf, _, _, _) = cpuid(0);
+    let (max_ext, _, _, _) = cpuid(0x8000_0000);
+
+    let leaf = if max_ext >= 0x8000_001D && cpuid(0x8000_0001).2 & (1 << 22) != 0 {
+        0x8000_001D
+    } else if max_leaf >= 4 {
+        4
+    } else {
+        return;
+    };
+
+    for subleaf in 0..crate::cache::MAX_CACHES as u32 {
+        let (eax, ebx, ecx, _) = cpuid_subleaf(leaf, subleaf);
+        match decode_cpuid_cache(eax, ebx, ecx) {
+            Some(info) => caches.add(info),
+            None => break,
+        }
+    }
+
+    if max_leaf >= 0x10 {
+        // Leaf 7 EBX bit 15: RDT allocation; leaf 0x10 EBX bit 1: L3 CAT
+        let (_, ebx7, _, _) = cpuid_subleaf(7, 0);
+        let (_, res, _, _) = cpuid_subleaf(0x10, 0);
+        if ebx7 & (1 << 15) != 0 && res & (1 << 1) != 0 {
+            let (eax, ebx, _, edx) = cpuid_subleaf(0x10, 1);
+            caches.set_cat(CatCapability::from_cpuid(eax, ebx, edx));
+        }
+    }
+}
+
 /// Extract topology IDs from APIC ID
 pub fn extract_topology_ids(apic_id: u32, topology: &CpuTopology) -> (u8, u8, u8) {
     let smt_mask = (1u32 << topology.smt_mask_width) - 1;
@@ -148,6 +148,9 @@ pub unsafe fn init() -> Result<(), SmpError> {
     let cpu_count = cpu_info::enumerate_cpus()?;
     CPU_COUNT.store(cpu_count, Ordering::SeqCst);
 
+    // All CPUs share the BSP's cache geometry
+    cpu_info::detect_caches();
+
     log::info!(
         "SMP: Initialized on BSP (APIC ID {}), {} CPU(s) detected",
         bsp_id,
@@ -263,6 +263,11 @@ fn init_percpu(cpu_id: usize, apic_id: u32) -> Result<(), SmpError> {
 
     PERCPU_INITIALIZED[cpu_id].store(1, Ordering::Release);
 
+    // Runs on the CPU itself, so CPUID describes this core
+    if let Some(core_type) = super::cpu_info::detect_core_type() {
+        crate::topology::topology().set_core_type(cpu_id, core_type);
+    }
+
     Ok(())
 }
 
@@ -18,6 +18,7 @@
 
 use super::{MAX_CPUS, CPU_STACK_SIZE, AP_TRAMPOLINE_ADDR, SmpError};
 use super::cpu_info::{CpuState, register_cpu, get_cpu_info};
+use super::super::timers::tsc_sync;
 
 // =============================================================================
 // Trampoline
@@ -186,6 +187,9 @@ pub fn start_ap(
 
     trampoline.aps_started.fetch_add(1, Ordering::SeqCst);
 
+    // The AP runs the target side right after signalling ready
+    tsc_sync::check_source(cpu_id);
+
     log::debug!("AP {} (CPU {}) started successfully", apic_id, cpu_id);
 
     Ok(())
@@ -393,6 +397,9 @@ pub extern "C" fn ap_entry_default(cpu_id: u32, apic_id: u32) -> ! {
         trampoline.ap_ready.store(apic_id, Ordering::SeqCst);
     }
 
+    // Pair with the BSP's TSC sync check
+    tsc_sync::check_target(cpu_id as usize);
+
     log::info!("AP {} (APIC {}) entered idle loop", cpu_id, apic_id);
 
     // Idle loop
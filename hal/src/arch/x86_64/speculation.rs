@@ -0,0 +1,431 @@
+//! # Speculation Control
+//!
+//! Detection of speculative execution vulnerabilities from CPUID and
+//! `IA32_ARCH_CAPABILITIES`, and the mitigations for them:
+//!
+//! - **Meltdown**: page table isolation. User mode runs on a shadow PML4
+//!   holding the user half and the entry trampoline only; see
+//!   [`build_user_root`] and [`user_cr3`].
+//! - **Spectre v2**: enhanced IBRS when available, otherwise retpolines
+//!   (with the `retpoline` feature, for builds using `-Zretpoline`) or
+//!   IBRS; STIBP against the sibling thread and an IBPB when switching to
+//!   another user task ([`switch_state`]).
+//! - **MDS/TAA**: `VERW` clears CPU buffers on return to user mode
+//!   ([`clear_cpu_buffers`], also done inline by the syscall exit path).
+//!
+//! [`apply`] runs on the boot CPU and records the outcome in
+//! [`mitigations`]; secondary CPUs call [`apply_secondary`].
+
+use core::arch::asm;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use super::cpu::{cpuid_subleaf, read_msr, write_msr};
+use super::paging_v2::{PageTable, PageTableIndex};
+use super::task::CpuContext;
+use crate::speculation::{
+    mitigations, IndirectBranchMode, Mitigation, MitigationPolicy, Vulnerability,
+    VulnerabilityState,
+};
+
+/// IA32_SPEC_CTRL MSR
+const IA32_SPEC_CTRL: u32 = 0x48;
+
+/// IA32_PRED_CMD MSR
+const IA32_PRED_CMD: u32 = 0x49;
+
+/// IA32_ARCH_CAPABILITIES MSR
+const IA32_ARCH_CAPABILITIES: u32 = 0x10A;
+
+/// `IA32_SPEC_CTRL` bits
+pub mod spec_ctrl {
+    /// Indirect branch restricted speculation
+    pub const IBRS: u64 = 1 << 0;
+    /// Single thread indirect branch predictors
+    pub const STIBP: u64 = 1 << 1;
+    /// Speculative store bypass disable
+    pub const SSBD: u64 = 1 << 2;
+}
+
+/// `IA32_PRED_CMD` indirect branch prediction barrier
+const PRED_CMD_IBPB: u64 = 1 << 0;
+
+/// `IA32_ARCH_CAPABILITIES` bits
+pub mod arch_caps {
+    /// Not susceptible to rogue data cache load (Meltdown)
+    pub const RDCL_NO: u64 = 1 << 0;
+    /// Enhanced IBRS
+    pub const IBRS_ALL: u64 = 1 << 1;
+    /// Not susceptible to speculative store bypass
+    pub const SSB_NO: u64 = 1 << 4;
+    /// Not susceptible to microarchitectural data sampling
+    pub const MDS_NO: u64 = 1 << 5;
+    /// Not susceptible to TSX asynchronous abort
+    pub const TAA_NO: u64 = 1 << 8;
+}
+
+/// PCID bit distinguishing the user half of a PTI page table pair
+pub const PTI_USER_PCID_BIT: u64 = 1 << 11;
+
+/// Offset of the user PML4 from the kernel PML4 in a PTI pair
+pub const PTI_USER_ROOT_OFFSET: u64 = 4096;
+
+/// First PML4 slot of the kernel half
+const KERNEL_HALF: u16 = 256;
+
+/// Selector `VERW` is issued with to clear CPU buffers
+pub static VERW_SELECTOR: u16 = super::gdt::KERNEL_DATA_SELECTOR;
+
+/// Clear CPU buffers on return to user mode (read by the syscall exit path)
+pub static CLEAR_CPU_BUFFERS: AtomicBool = AtomicBool::new(false);
+
+/// Issue IBPB when switching to another user task
+static IBPB_ON_SWITCH: AtomicBool = AtomicBool::new(false);
+
+/// Page table isolation is enabled
+static PTI: AtomicBool = AtomicBool::new(false);
+
+/// `IA32_SPEC_CTRL` value every CPU runs with
+static SPEC_CTRL: AtomicU64 = AtomicU64::new(0);
+
+// =============================================================================
+// Detection
+// =============================================================================
+
+/// Speculation-related CPU properties
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpeculationFeatures {
+    /// Intel CPU
+    pub intel: bool,
+    /// AMD or Hygon CPU
+    pub amd: bool,
+    /// `IA32_SPEC_CTRL.IBRS` and `IA32_PRED_CMD.IBPB` (CPUID.7.0:EDX[26])
+    pub ibrs_ibpb: bool,
+    /// `IA32_SPEC_CTRL.STIBP` (CPUID.7.0:EDX[27])
+    pub stibp: bool,
+    /// `IA32_SPEC_CTRL.SSBD` (CPUID.7.0:EDX[31])
+    pub ssbd: bool,
+    /// `VERW` clears CPU buffers (CPUID.7.0:EDX[10])
+    pub md_clear: bool,
+    /// TSX (CPUID.7.0:EBX[11])
+    pub rtm: bool,
+    /// `IA32_ARCH_CAPABILITIES`, 0 if absent
+    pub arch_capabilities: u64,
+}
+
+impl SpeculationFeatures {
+    /// Decode CPUID leaf 7 subleaf 0 and the architectural capabilities
+    pub const fn from_cpuid(vendor: &[u8; 12], ebx: u32, edx: u32, arch_capabilities: u64) -> Self {
+        Self {
+            intel: matches!(vendor, b"GenuineIntel"),
+            amd: matches!(vendor, b"AuthenticAMD" | b"HygonGenuine"),
+            ibrs_ibpb: edx & (1 << 26) != 0,
+            stibp: edx & (1 << 27) != 0,
+            ssbd: edx & (1 << 31) != 0,
+            md_clear: edx & (1 << 10) != 0,
+            rtm: ebx & (1 << 11) != 0,
+            arch_capabilities,
+        }
+    }
+
+    const fn has(&self, cap: u64) -> bool {
+        self.arch_capabilities & cap != 0
+    }
+
+    /// Whether the CPU is affected by `vulnerability`
+    pub const fn is_affected(&self, vulnerability: Vulnerability) -> bool {
+        match vulnerability {
+            Vulnerability::Meltdown => self.intel && !self.has(arch_caps::RDCL_NO),
+            Vulnerability::SpectreV1 | Vulnerability::SpectreV2 => true,
+            Vulnerability::SpecStoreBypass => {
+                (self.intel || self.amd) && !self.has(arch_caps::SSB_NO)
+            }
+            Vulnerability::Mds => self.intel && !self.has(arch_caps::MDS_NO),
+            Vulnerability::TsxAsyncAbort => {
+                self.intel && self.rtm && !self.has(arch_caps::TAA_NO)
+            }
+        }
+    }
+}
+
+/// Read the speculation-related CPU properties
+pub fn detect() -> SpeculationFeatures {
+    let (max_leaf, b, c, d) = cpuid_subleaf(0, 0);
+    let mut vendor = [0u8; 12];
+    vendor[0..4].copy_from_slice(&b.to_le_bytes());
+    vendor[4..8].copy_from_slice(&d.to_le_bytes());
+    vendor[8..12].copy_from_slice(&c.to_le_bytes());
+    if max_leaf < 7 {
+        return SpeculationFeatures::from_cpuid(&vendor, 0, 0, 0);
+    }
+
+    let (_, ebx, _, edx) = cpuid_subleaf(7, 0);
+    let arch_capabilities = if edx & (1 << 29) != 0 {
+        // SAFETY: CPUID.7.0:EDX[29] enumerates the MSR
+        unsafe { read_msr(IA32_ARCH_CAPABILITIES) }
+    } else {
+        0
+    };
+    SpeculationFeatures::from_cpuid(&vendor, ebx, edx, arch_capabilities)
+}
+
+// =============================================================================
+// Planning
+// =============================================================================
+
+/// Mitigations chosen for a CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MitigationPlan {
+    /// `IA32_SPEC_CTRL` value
+    pub spec_ctrl: u64,
+    /// IBPB when switching to another user task
+    pub ibpb_on_switch: bool,
+    /// `VERW` on return to user mode
+    pub clear_cpu_buffers: bool,
+    /// Page table isolation
+    pub pti: bool,
+    /// Resulting state of each vulnerability, in [`Vulnerability::ALL`] order
+    pub states: [VulnerabilityState; Vulnerability::COUNT],
+}
+
+/// Choose mitigations for `cpu` under `policy`
+///
+/// `retpoline` says whether the kernel was built with retpoline thunks.
+pub fn plan(cpu: &SpeculationFeatures, policy: MitigationPolicy, retpoline: bool) -> MitigationPlan {
+    let mut plan = MitigationPlan {
+        spec_ctrl: 0,
+        ibpb_on_switch: false,
+        clear_cpu_buffers: false,
+        pti: false,
+        states: [VulnerabilityState::NotAffected; Vulnerability::COUNT],
+    };
+
+    for (state, vulnerability) in plan.states.iter_mut().zip(Vulnerability::ALL) {
+        if cpu.is_affected(vulnerability) {
+            *state = VulnerabilityState::Vulnerable;
+        }
+    }
+    if !policy.is_enabled() {
+        return plan;
+    }
+
+    let mitigate = |plan: &mut MitigationPlan, vulnerability: Vulnerability, mitigation| {
+        let state = &mut plan.states[vulnerability as usize];
+        if *state == VulnerabilityState::Vulnerable {
+            *state = VulnerabilityState::Mitigated(mitigation);
+        }
+    };
+
+    if cpu.is_affected(Vulnerability::Meltdown) {
+        plan.pti = true;
+        mitigate(&mut plan, Vulnerability::Meltdown, Mitigation::Pti);
+    }
+
+    mitigate(&mut plan, Vulnerability::SpectreV1, Mitigation::PointerSanitization);
+
+    let mode = if cpu.ibrs_ibpb && cpu.has(arch_caps::IBRS_ALL) {
+        Some(IndirectBranchMode::EnhancedIbrs)
+    } else if retpoline {
+        Some(IndirectBranchMode::Retpoline)
+    } else if cpu.ibrs_ibpb {
+        Some(IndirectBranchMode::Ibrs)
+    } else {
+        None
+    };
+    if let Some(mode) = mode {
+        if mode != IndirectBranchMode::Retpoline {
+            plan.spec_ctrl |= spec_ctrl::IBRS;
+        }
+        // Enhanced IBRS already isolates the sibling thread
+        let stibp = cpu.stibp && mode != IndirectBranchMode::EnhancedIbrs;
+        if stibp {
+            plan.spec_ctrl |= spec_ctrl::STIBP;
+        }
+        plan.ibpb_on_switch = cpu.ibrs_ibpb;
+        let ibpb = plan.ibpb_on_switch;
+        mitigate(&mut plan, Vulnerability::SpectreV2, Mitigation::IndirectBranch { mode, ibpb, stibp });
+    }
+
+    if cpu.md_clear {
+        plan.clear_cpu_buffers = cpu.is_affected(Vulnerability::Mds)
+            || cpu.is_affected(Vulnerability::TsxAsyncAbort);
+        mitigate(&mut plan, Vulnerability::Mds, Mitigation::ClearCpuBuffers);
+        mitigate(&mut plan, Vulnerability::TsxAsyncAbort, Mitigation::ClearCpuBuffers);
+    }
+
+    plan
+}
+
+// =============================================================================
+// Applying
+// =============================================================================
+
+/// Detect vulnerabilities and apply mitigations on the boot CPU
+///
+/// Records the policy and outcome in [`mitigations`].
+pub fn apply(policy: MitigationPolicy) -> MitigationPlan {
+    let cpu = detect();
+    let plan = plan(&cpu, policy, cfg!(feature = "retpoline"));
+
+    SPEC_CTRL.store(plan.spec_ctrl, Ordering::Relaxed);
+    IBPB_ON_SWITCH.store(plan.ibpb_on_switch, Ordering::Relaxed);
+    CLEAR_CPU_BUFFERS.store(plan.clear_cpu_buffers, Ordering::Relaxed);
+    PTI.store(plan.pti, Ordering::Relaxed);
+    apply_secondary();
+
+    mitigations().set_policy(policy);
+    for (vulnerability, state) in Vulnerability::ALL.into_iter().zip(plan.states) {
+        mitigations().set_state(vulnerability, state);
+    }
+    plan
+}
+
+/// Apply the boot CPU's mitigations on this CPU
+pub fn apply_secondary() {
+    let value = SPEC_CTRL.load(Ordering::Relaxed);
+    if value != 0 {
+        // SAFETY: Bits are only planned when CPUID enumerates them
+        unsafe { write_msr(IA32_SPEC_CTRL, read_msr(IA32_SPEC_CTRL) | value) };
+    }
+}
+
+/// Indirect branch prediction barrier
+#[inline]
+pub fn ibpb() {
+    // SAFETY: Only reached once CPUID enumerated IBPB
+    unsafe { write_msr(IA32_PRED_CMD, PRED_CMD_IBPB) };
+}
+
+/// Clear CPU buffers before returning to user mode
+#[inline]
+pub fn clear_cpu_buffers() {
+    if CLEAR_CPU_BUFFERS.load(Ordering::Relaxed) {
+        // SAFETY: VERW with a valid selector only sets flags
+        unsafe { asm!("verw word ptr [{}]", in(reg) &VERW_SELECTOR, options(nostack)) };
+    }
+}
+
+/// Barrier between `old` and `new` on a context switch
+///
+/// Keeps a user task from steering the indirect branches of the next one.
+///
+/// # Safety
+///
+/// Call with interrupts disabled, right before `context_switch(old, new)`.
+pub unsafe fn switch_state(old: &CpuContext, new: &CpuContext) {
+    let to_user = new.cs & 3 == 3;
+    if IBPB_ON_SWITCH.load(Ordering::Relaxed) && to_user && !core::ptr::eq(old, new) {
+        ibpb();
+    }
+}
+
+// =============================================================================
+// Page Table Isolation
+// =============================================================================
+
+/// Page table isolation is enabled
+pub fn pti_enabled() -> bool {
+    PTI.load(Ordering::Relaxed)
+}
+
+/// CR3 for user mode from the kernel CR3 of the same address space
+///
+/// The PML4 pair is 8 KiB aligned with the user copy in the upper page;
+/// the user half also gets its own PCID so neither flushes the other.
+pub const fn user_cr3(kernel_cr3: u64) -> u64 {
+    kernel_cr3 | PTI_USER_ROOT_OFFSET | PTI_USER_PCID_BIT
+}
+
+/// Fill the user PML4 of a PTI pair from the kernel PML4
+///
+/// The user half is shared; of the kernel half only `trampoline` (the
+/// slots holding the entry code, its stacks and the GDT/IDT/TSS) is kept.
+pub fn build_user_root(kernel: &PageTable, user: &mut PageTable, trampoline: &[PageTableIndex]) {
+    user.zero();
+    user.copy_range(PageTableIndex::new(0), PageTableIndex::new(KERNEL_HALF - 1), kernel);
+    for &slot in trampoline {
+        if slot.as_u16() >= KERNEL_HALF {
+            user.copy_range(slot, slot, kernel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intel(edx: u32, arch_capabilities: u64) -> SpeculationFeatures {
+        SpeculationFeatures::from_cpuid(b"GenuineIntel", 1 << 11, edx, arch_capabilities)
+    }
+
+    #[test]
+    fn test_affected() {
+        let old = intel(0, 0);
+        assert!(old.is_affected(Vulnerability::Meltdown));
+        assert!(old.is_affected(Vulnerability::TsxAsyncAbort));
+
+        let new = intel(0, arch_caps::RDCL_NO | arch_caps::MDS_NO | arch_caps::TAA_NO);
+        assert!(!new.is_affected(Vulnerability::Meltdown));
+        assert!(!new.is_affected(Vulnerability::Mds));
+        assert!(new.is_affected(Vulnerability::SpectreV2));
+
+        let amd = SpeculationFeatures::from_cpuid(b"AuthenticAMD", 0, 0, 0);
+        assert!(!amd.is_affected(Vulnerability::Meltdown));
+        assert!(amd.is_affected(Vulnerability::SpecStoreBypass));
+    }
+
+    #[test]
+    fn test_plan_legacy_intel() {
+        // IBRS/IBPB, STIBP and MD_CLEAR without ARCH_CAPABILITIES
+        let cpu = intel((1 << 26) | (1 << 27) | (1 << 10), 0);
+        let plan = plan(&cpu, MitigationPolicy::Auto, false);
+        assert!(plan.pti && plan.ibpb_on_switch && plan.clear_cpu_buffers);
+        assert_eq!(plan.spec_ctrl, spec_ctrl::IBRS | spec_ctrl::STIBP);
+        assert_eq!(
+            plan.states[Vulnerability::Meltdown as usize],
+            VulnerabilityState::Mitigated(Mitigation::Pti)
+        );
+        assert_eq!(
+            plan.states[Vulnerability::SpecStoreBypass as usize],
+            VulnerabilityState::Vulnerable
+        );
+
+        let retpoline = super::plan(&cpu, MitigationPolicy::Auto, true);
+        assert_eq!(retpoline.spec_ctrl, spec_ctrl::STIBP);
+    }
+
+    #[test]
+    fn test_plan_enhanced_ibrs() {
+        let cpu = intel(
+            (1 << 26) | (1 << 27),
+            arch_caps::RDCL_NO | arch_caps::IBRS_ALL | arch_caps::MDS_NO | arch_caps::TAA_NO,
+        );
+        let plan = plan(&cpu, MitigationPolicy::Auto, true);
+        assert!(!plan.pti && !plan.clear_cpu_buffers);
+        assert_eq!(plan.spec_ctrl, spec_ctrl::IBRS);
+        assert_eq!(
+            plan.states[Vulnerability::SpectreV2 as usize],
+            VulnerabilityState::Mitigated(Mitigation::IndirectBranch {
+                mode: IndirectBranchMode::EnhancedIbrs,
+                ibpb: true,
+                stibp: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_plan_off() {
+        let cpu = intel((1 << 26) | (1 << 10), 0);
+        let plan = plan(&cpu, MitigationPolicy::Off, true);
+        assert_eq!(plan.spec_ctrl, 0);
+        assert!(!plan.pti && !plan.ibpb_on_switch && !plan.clear_cpu_buffers);
+        assert!(plan
+            .states
+            .iter()
+            .all(|s| matches!(s, VulnerabilityState::Vulnerable | VulnerabilityState::NotAffected)));
+    }
+
+    #[test]
+    fn test_user_cr3() {
+        assert_eq!(user_cr3(0x0010_2000 | 5), 0x0010_3000 | 0x800 | 5);
+    }
+}
@@ -93,8 +93,8 @@ pub unsafe fn init() {
     // Set compat mode entry (not used, but required)
     wrmsr(msr::CSTAR, 0);
     
-    // Set flags mask (clear IF and TF on syscall entry)
-    wrmsr(msr::SFMASK, 0x300); // Clear IF (0x200) and TF (0x100)
+    // Set flags mask (clear IF, TF and AC on syscall entry)
+    wrmsr(msr::SFMASK, 0x40300); // Clear IF (0x200), TF (0x100) and AC (0x40000)
     
     log::info!("Syscall/sysret initialized (STAR={:#x}, LSTAR={:#x})", 
                star, syscall_entry as u64);
@@ -157,13 +157,24 @@ pub unsafe fn init() {
         "pop r11",      // User RFLAGS
         "pop rcx",      // User RIP
         
+        // Clear CPU buffers (MDS/TAA); RFLAGS is restored from R11
+        "cmp byte ptr [rip + {clear_buffers}], 0",
+        "je 2f",
+        "verw word ptr [rip + {verw_selector}]",
+        "2:",
+        
         // Return to userspace (Ring 3)
         "sysretq",
         
         dispatcher = sym syscall_dispatcher,
+        clear_buffers = sym super::speculation::CLEAR_CPU_BUFFERS,
+        verw_selector = sym super::speculation::VERW_SELECTOR,
     ); }
 }
 
+/// `-EFAULT`: bad user buffer
+const EFAULT: u64 = -14i64 as u64;
+
 /// Syscall dispatcher (called from assembly)
 /// 
 /// Arguments are already set up by the assembly stub.
@@ -199,7 +210,9 @@ pub extern "C" fn syscall_dispatcher(
             }
             
             // Exit the task
-            super::task::scheduler().exit(code);
+            if let Ok(sched) = super::task::try_scheduler() {
+                sched.exit(code);
+            }
             
             // Should never return, but halt just in case
             loop {
@@ -207,23 +220,31 @@ pub extern "C" fn syscall_dispatcher(
             }
         }
         nr::WRITE => {
-            // write(fd, buf, len)
+            // write(fd, buf, len), copied in chunks: a bad buffer fails with
+            // EFAULT instead of faulting the kernel
             let _fd = arg0;
-            let buf = arg1 as *const u8;
             let len = arg2 as usize;
-            
+            let mut chunk = [0u8; 64];
+            let mut done = 0;
+
             // Write to serial port
-            unsafe {
-                for i in 0..len {
-                    let c = *buf.add(i);
-                    if c == 0 { break; } // Stop at null
-                    core::arch::asm!(
-                        "out dx, al",
-                        in("dx") 0x3F8u16,
-                        in("al") c,
-                        options(nomem, nostack)
-                    );
+            'copy: while done < len {
+                let n = chunk.len().min(len - done);
+                if crate::uaccess::copy_from_user(&mut chunk[..n], arg1 + done as u64).is_err() {
+                    return EFAULT;
+                }
+                for &c in &chunk[..n] {
+                    if c == 0 { break 'copy; } // Stop at null
+                    unsafe {
+                        core::arch::asm!(
+                            "out dx, al",
+                            in("dx") 0x3F8u16,
+                            in("al") c,
+                            options(nomem, nostack)
+                        );
+                    }
                 }
+                done += n;
             }
             
             len as u64
@@ -233,7 +254,8 @@ pub extern "C" fn syscall_dispatcher(
             0
         }
         nr::GETPID => {
-            super::task::scheduler().current_task_id().as_u64()
+            super::task::try_scheduler()
+                .map_or(super::task::TaskId::IDLE.as_u64(), |s| s.current_task_id().as_u64())
         }
         nr::DEBUG => {
             // Debug syscall - print a value
@@ -16,6 +16,8 @@
 use core::sync::atomic::{AtomicU64, AtomicU32, Ordering};
 use spin::RwLock;
 
+use crate::{HalError, HalResult};
+
 /// Task ID type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
@@ -91,6 +93,16 @@ pub struct CpuContext {
     // For userspace tasks
     pub cs: u64,
     pub ss: u64,
+
+    // CET state (see `cet`); zero when shadow stacks are off
+    /// Kernel shadow stack pointer, switched by `context_switch`
+    pub ssp: u64,
+    /// Kernel shadow stack loaded on entry from user mode
+    pub pl0_ssp: u64,
+    /// User shadow stack pointer
+    pub pl3_ssp: u64,
+    /// User CET configuration (`IA32_U_CET`)
+    pub u_cet: u64,
 }
 
 impl CpuContext {
@@ -108,6 +120,10 @@ pub fn new_kernel(entry_point: u64, stack_top: u64) -> Self {
             rflags: 0x202, // Interrupts enabled
             cs: 0x08, // Kernel code segment
             ss: 0x10, // Kernel data segment
+            ssp: 0,
+            pl0_ssp: 0,
+            pl3_ssp: 0,
+            u_cet: 0,
         }
     }
 
@@ -125,6 +141,10 @@ pub fn new_user(entry_point: u64, stack_top: u64, kernel_stack: u64) -> Self {
             rflags: 0x202, // Interrupts enabled
             cs: 0x1B, // User code segment (ring 3) | 0x18 | 3
             ss: 0x23, // User data segment (ring 3) | 0x20 | 3
+            ssp: 0,
+            pl0_ssp: 0,
+            pl3_ssp: 0,
+            u_cet: 0,
         }
     }
 }
@@ -464,6 +484,14 @@ pub fn scheduler() -> &'static Scheduler {
     })
 }
 
+/// Get the global scheduler if it has already been initialized
+///
+/// Interrupt and syscall paths use this instead of [`scheduler`] so they
+/// never allocate the idle task from interrupt context.
+pub fn try_scheduler() -> HalResult<&'static Scheduler> {
+    GLOBAL_SCHEDULER.get().ok_or(HalError::NotInitialized)
+}
+
 /// Spawn a new kernel task
 pub fn spawn(name: impl Into<String>, entry: extern "C" fn()) -> TaskId {
     let task = Task::new_kernel(name, entry);
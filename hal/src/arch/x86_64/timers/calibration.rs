@@ -10,6 +10,9 @@
 //! 3. **HPET**: Use HPET as reference (if available)
 //! 4. **ACPI PM Timer**: Use ACPI PM Timer (if available)
 //!
+//! CPUID.15h is trusted only when a measurement against HPET or PIT agrees
+//! with it; some firmware reports a crystal frequency that is slightly off.
+//!
 //! ## Calibration Process
 //!
 //! For TSC calibration:
@@ -53,26 +56,55 @@ pub struct CalibrationResult {
 // TSC Calibration
 // =============================================================================
 
+/// Maximum disagreement between CPUID.15h and a measurement (ppm)
+const CPUID_TOLERANCE_PPM: i64 = 500;
+
 /// Calibrate TSC using the best available method
+///
+/// Measures against HPET (or PIT without HPET) and prefers the exact
+/// CPUID.15h frequency when the measurement confirms it.
 pub fn calibrate_tsc() -> Result<CalibrationResult, TimerError> {
-    // Try CPUID first
-    if let Some(freq) = tsc::get_frequency_from_cpuid() {
-        return Ok(CalibrationResult {
-            frequency: freq,
-            method: CalibrationMethod::Cpuid,
-            error_ppm: 0, // CPUID is exact
-        });
-    }
+    let measured = if hpet::is_available() {
+        calibrate_tsc_with_hpet().or_else(|_| calibrate_tsc_with_pit())
+    } else {
+        calibrate_tsc_with_pit()
+    };
+
+    let Some(freq) = tsc::get_frequency_from_cpuid_15h() else {
+        return measured;
+    };
 
-    // Try HPET
-    if hpet::is_available() {
-        if let Ok(result) = calibrate_tsc_with_hpet() {
-            return Ok(result);
+    match measured {
+        Ok(reference) => {
+            let ppm = ppm_difference(freq, reference.frequency);
+            if ppm.abs() > CPUID_TOLERANCE_PPM {
+                log::warn!(
+                    "TSC: CPUID.15h reports {} Hz but {:?} measured {} Hz ({} ppm), using measurement",
+                    freq,
+                    reference.method,
+                    reference.frequency,
+                    ppm
+                );
+                return Ok(reference);
+            }
         }
+        Err(_) => log::warn!("TSC: no reference timer to verify CPUID.15h frequency"),
     }
 
-    // Fall back to PIT
-    calibrate_tsc_with_pit()
+    Ok(CalibrationResult {
+        frequency: freq,
+        method: CalibrationMethod::Cpuid,
+        error_ppm: 0, // CPUID is exact
+    })
+}
+
+/// Difference of `frequency` from `reference` in parts per million
+fn ppm_difference(frequency: u64, reference: u64) -> i64 {
+    if reference == 0 {
+        return i64::MAX;
+    }
+    let diff = frequency as i128 - reference as i128;
+    (diff * 1_000_000 / reference as i128) as i64
 }
 
 /// Calibrate TSC using PIT as reference
@@ -370,11 +402,7 @@ pub fn verify_calibration(frequency: u64, method: CalibrationMethod) -> Option<i
         }
     };
 
-    // Calculate difference in PPM
-    let diff = frequency as i64 - reference as i64;
-    let ppm = (diff * 1_000_000) / reference as i64;
-
-    Some(ppm)
+    Some(ppm_difference(frequency, reference))
 }
 
 // =============================================================================
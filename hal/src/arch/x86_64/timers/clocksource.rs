@@ -0,0 +1,310 @@
+//! # Clocksource Selection and Watchdog
+//!
+//! Chooses the primary clocksource at boot and demotes the TSC at runtime
+//! when it proves unreliable:
+//!
+//! - Not invariant/constant at boot
+//! - Warp or excessive skew between CPUs ([`super::tsc_sync`])
+//! - Drift against HPET detected by [`watchdog`]
+//!
+//! Demotion switches to HPET (PIT as a last resort) without making
+//! [`super::read_ns`] jump. The selection is reported at
+//! `/proc/clocksource`:
+//!
+//! ```text
+//! current: tsc
+//! available: tsc hpet pit
+//! tsc_khz: 2995200
+//! tsc_calibration: cpuid
+//! tsc_flags: invariant constant rdtscp tsc_deadline tsc_adjust
+//! tsc_stable: yes
+//! tsc_max_skew_cycles: 42
+//! tsc_warps: 0
+//! ```
+
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+
+use super::calibration::CalibrationMethod;
+use super::tsc::{self, TscFeatures};
+use super::{hpet, tsc_sync, TimerSource, NS_PER_US};
+
+/// File name under `/proc`
+pub const PROC_PATH: &str = "clocksource";
+
+/// Minimum interval between watchdog comparisons
+const WATCHDOG_INTERVAL_NS: u64 = 500_000_000;
+
+/// Tolerated drift against HPET, in parts per million
+const WATCHDOG_THRESHOLD_PPM: u64 = 1_000;
+
+/// Tolerated drift per interval regardless of length
+const WATCHDOG_MIN_SKEW_NS: u64 = 100 * NS_PER_US;
+
+// =============================================================================
+// State
+// =============================================================================
+
+/// Why the TSC was rejected as clocksource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Instability {
+    /// Rate changes with P-states or stops in C-states
+    NotInvariant = 1,
+    /// Skew between CPUs above threshold at bring-up
+    SyncFailed = 2,
+    /// A CPU observed the TSC going backwards
+    Warp = 3,
+    /// Drifted against HPET
+    WatchdogDrift = 4,
+    /// Frequency unknown
+    NotCalibrated = 5,
+}
+
+impl Instability {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::NotInvariant),
+            2 => Some(Self::SyncFailed),
+            3 => Some(Self::Warp),
+            4 => Some(Self::WatchdogDrift),
+            5 => Some(Self::NotCalibrated),
+            _ => None,
+        }
+    }
+
+    /// Short description
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotInvariant => "not invariant",
+            Self::SyncFailed => "cross-CPU skew",
+            Self::Warp => "warp",
+            Self::WatchdogDrift => "watchdog drift",
+            Self::NotCalibrated => "not calibrated",
+        }
+    }
+}
+
+/// First reason the TSC was marked unstable (0 = stable)
+static UNSTABLE: AtomicU8 = AtomicU8::new(0);
+
+/// Calibration method, as `CalibrationMethod as u8 + 1` (0 = none)
+static CALIBRATION: AtomicU8 = AtomicU8::new(0);
+
+/// TSC features detected at boot
+static FEATURES: spin::Once<TscFeatures> = spin::Once::new();
+
+/// Watchdog reference points
+static WATCHDOG_TSC: AtomicU64 = AtomicU64::new(0);
+static WATCHDOG_HPET_NS: AtomicU64 = AtomicU64::new(0);
+
+/// Serializes watchdog comparisons across CPUs
+static WATCHDOG_LOCK: AtomicBool = AtomicBool::new(false);
+
+// =============================================================================
+// Selection
+// =============================================================================
+
+/// Choose the primary clocksource after TSC calibration
+pub fn select(features: TscFeatures, calibration: Option<CalibrationMethod>) -> TimerSource {
+    FEATURES.call_once(|| features);
+    if let Some(method) = calibration {
+        CALIBRATION.store(method as u8 + 1, Ordering::Relaxed);
+    }
+
+    if super::tsc_frequency() == 0 {
+        mark_reason(Instability::NotCalibrated);
+    } else if !features.is_reliable() {
+        mark_reason(Instability::NotInvariant);
+    }
+
+    let source = preferred(is_tsc_stable(), hpet::is_available());
+    super::switch_clock_source(source);
+    source
+}
+
+/// Best clocksource given which of TSC and HPET are usable
+fn preferred(tsc_stable: bool, hpet_available: bool) -> TimerSource {
+    if tsc_stable {
+        TimerSource::Tsc
+    } else if hpet_available {
+        TimerSource::Hpet
+    } else {
+        TimerSource::Pit
+    }
+}
+
+/// Best non-TSC clocksource
+fn fallback() -> TimerSource {
+    preferred(false, hpet::is_available())
+}
+
+/// Record the first instability reason; returns `true` if newly marked
+fn mark_reason(reason: Instability) -> bool {
+    UNSTABLE
+        .compare_exchange(0, reason as u8, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+}
+
+/// Reject the TSC, switching away from it if it is the current clocksource
+pub fn mark_tsc_unstable(reason: Instability) {
+    if !mark_reason(reason) {
+        return;
+    }
+
+    if super::primary_clock_source() == TimerSource::Tsc {
+        let source = fallback();
+        super::switch_clock_source(source);
+        log::warn!(
+            "Timers: TSC unstable ({}), switching clocksource to {}",
+            reason.as_str(),
+            source.name()
+        );
+    } else {
+        log::warn!("Timers: TSC unstable ({})", reason.as_str());
+    }
+}
+
+/// Has the TSC passed every check so far?
+pub fn is_tsc_stable() -> bool {
+    UNSTABLE.load(Ordering::Acquire) == 0
+}
+
+/// Why the TSC was rejected, if it was
+pub fn instability() -> Option<Instability> {
+    Instability::from_u8(UNSTABLE.load(Ordering::Acquire))
+}
+
+// =============================================================================
+// Watchdog
+// =============================================================================
+
+/// Compare TSC progress against HPET and re-run the warp check
+///
+/// Called from the timer tick on every CPU; comparisons happen at most
+/// every 500 ms. Without HPET only the warp check runs.
+pub fn watchdog() {
+    if !is_tsc_stable() {
+        return;
+    }
+    if !tsc_sync::warp_check() || !hpet::is_available() {
+        return;
+    }
+    if WATCHDOG_LOCK.swap(true, Ordering::Acquire) {
+        return;
+    }
+
+    let now_tsc = tsc::read_serialized();
+    let now_hpet = hpet::read_ns();
+    let last_tsc = WATCHDOG_TSC.load(Ordering::Relaxed);
+    let last_hpet = WATCHDOG_HPET_NS.load(Ordering::Relaxed);
+
+    let hpet_delta = now_hpet.wrapping_sub(last_hpet);
+    if last_tsc == 0 {
+        WATCHDOG_TSC.store(now_tsc, Ordering::Relaxed);
+        WATCHDOG_HPET_NS.store(now_hpet, Ordering::Relaxed);
+    } else if hpet_delta >= WATCHDOG_INTERVAL_NS {
+        let tsc_delta = super::tsc_to_ns(now_tsc.wrapping_sub(last_tsc));
+
+        WATCHDOG_TSC.store(now_tsc, Ordering::Relaxed);
+        WATCHDOG_HPET_NS.store(now_hpet, Ordering::Relaxed);
+
+        if drifted(tsc_delta, hpet_delta) {
+            log::warn!(
+                "Timers: TSC advanced {} ns while HPET advanced {} ns",
+                tsc_delta,
+                hpet_delta
+            );
+            mark_tsc_unstable(Instability::WatchdogDrift);
+        }
+    }
+
+    WATCHDOG_LOCK.store(false, Ordering::Release);
+}
+
+/// Did the TSC drift past tolerance while HPET advanced `hpet_delta` ns?
+fn drifted(tsc_delta: u64, hpet_delta: u64) -> bool {
+    let threshold = (hpet_delta / 1_000_000 * WATCHDOG_THRESHOLD_PPM).max(WATCHDOG_MIN_SKEW_NS);
+    tsc_delta.abs_diff(hpet_delta) > threshold
+}
+
+// =============================================================================
+// /proc Reporting
+// =============================================================================
+
+/// Render `/proc/clocksource`
+pub fn render_proc(out: &mut dyn Write) -> fmt::Result {
+    writeln!(out, "current: {}", super::primary_clock_source().name())?;
+
+    write!(out, "available:")?;
+    if is_tsc_stable() && super::tsc_frequency() != 0 {
+        write!(out, " tsc")?;
+    }
+    if hpet::is_available() {
+        write!(out, " hpet")?;
+    }
+    writeln!(out, " pit")?;
+
+    writeln!(out, "tsc_khz: {}", super::tsc_frequency() / 1_000)?;
+
+    let calibration = match CALIBRATION.load(Ordering::Relaxed) {
+        1 => "cpuid",
+        2 => "pit",
+        3 => "hpet",
+        4 => "acpi_pm",
+        _ => "none",
+    };
+    writeln!(out, "tsc_calibration: {}", calibration)?;
+
+    write!(out, "tsc_flags:")?;
+    if let Some(features) = FEATURES.get() {
+        let flags = [
+            (features.invariant, "invariant"),
+            (features.constant, "constant"),
+            (features.rdtscp, "rdtscp"),
+            (features.tsc_deadline, "tsc_deadline"),
+            (features.tsc_adjust, "tsc_adjust"),
+        ];
+        for (set, name) in flags {
+            if set {
+                write!(out, " {}", name)?;
+            }
+        }
+    }
+    writeln!(out)?;
+
+    match instability() {
+        None => writeln!(out, "tsc_stable: yes")?,
+        Some(reason) => writeln!(out, "tsc_stable: no ({})", reason.as_str())?,
+    }
+    writeln!(out, "tsc_max_skew_cycles: {}", tsc_sync::max_skew())?;
+    writeln!(out, "tsc_warps: {}", tsc_sync::warps())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferred_source() {
+        assert_eq!(preferred(true, true), TimerSource::Tsc);
+        assert_eq!(preferred(true, false), TimerSource::Tsc);
+        assert_eq!(preferred(false, true), TimerSource::Hpet);
+        assert_eq!(preferred(false, false), TimerSource::Pit);
+    }
+
+    #[test]
+    fn test_drift_threshold() {
+        // 1000 ppm of one second
+        let second = 1_000_000_000;
+        assert!(!drifted(second + 1_000_000, second));
+        assert!(drifted(second + 1_000_001, second));
+        assert!(!drifted(second - 1_000_000, second));
+        assert!(drifted(second - 1_000_001, second));
+
+        // Short intervals tolerate the minimum skew
+        let short = 50_000_000;
+        assert!(!drifted(short + WATCHDOG_MIN_SKEW_NS, short));
+        assert!(drifted(short + WATCHDOG_MIN_SKEW_NS + 1, short));
+    }
+}
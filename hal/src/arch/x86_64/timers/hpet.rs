@@ -128,6 +128,9 @@ unsafe fn write_reg32(offset: u64, value: u32) {
 
 /// Initialize the HPET
 ///
+/// Machines with [`Quirks::HPET_BROKEN`](crate::quirks::Quirks::HPET_BROKEN)
+/// keep it disabled, so clocksource selection never falls back to it.
+///
 /// # Safety
 ///
 /// The base address must be a valid HPET MMIO mapping.
@@ -135,6 +138,10 @@ pub unsafe fn init(base: u64) -> Result<(), HpetError> {
     if HPET_AVAILABLE.load(Ordering::Acquire) {
         return Err(HpetError::AlreadyInitialized);
     }
+    if crate::quirks::has(crate::quirks::Quirks::HPET_BROKEN) {
+        log::warn!("HPET: Disabled by machine quirk");
+        return Err(HpetError::NotAvailable);
+    }
 
     HPET_BASE.store(base, Ordering::SeqCst);
 
@@ -70,7 +70,8 @@
 //! - Automatic timer source selection
 //! - TSC calibration using multiple methods
 //! - Invariant TSC detection
-//! - Per-CPU timer management
+//! - Cross-CPU TSC sync check and runtime watchdog with HPET fallback
+//! - Per-CPU timer management, with tick stop on nohz_full CPUs
 //! - High-precision delays (nanosecond resolution)
 //! - Periodic interrupt scheduling
 
@@ -81,6 +82,9 @@
 pub mod apic_timer;
 pub mod pit;
 pub mod calibration;
+pub mod clocksource;
+pub mod tsc_sync;
+pub mod nohz;
 
 pub use tsc::{Tsc, TscFeatures};
 pub use hpet::{Hpet, HpetTimer};
@@ -88,7 +92,7 @@
 pub use pit::{Pit, PitChannel};
 pub use calibration::{CalibrationMethod, CalibrationResult};
 
-use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 
 // =============================================================================
 // Constants
@@ -126,9 +130,25 @@ pub enum TimerSource {
     Pit,
 }
 
+impl TimerSource {
+    /// Clocksource name, as reported in `/proc/clocksource`
+    pub const fn name(&self) -> &'static str {
+        match self {
+            TimerSource::Tsc => "tsc",
+            TimerSource::Hpet => "hpet",
+            TimerSource::AcpiPm => "acpi_pm",
+            TimerSource::Pit => "pit",
+        }
+    }
+}
+
 /// Current primary clock source
 static PRIMARY_CLOCK: AtomicU64 = AtomicU64::new(0); // 0 = TSC, 1 = HPET, etc.
 
+/// Offset added to the primary source so `read_ns` stays continuous
+/// across clocksource switches
+static CLOCK_OFFSET_NS: AtomicI64 = AtomicI64::new(0);
+
 /// Timer subsystem initialized
 static TIMER_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
@@ -165,41 +185,24 @@ pub unsafe fn init() -> Result<(), TimerError> {
     let tsc_features = tsc::detect_features();
     TSC_INVARIANT.store(tsc_features.invariant, Ordering::SeqCst);
 
-    // Calibrate TSC
-    let tsc_freq = if tsc_features.invariant {
-        // Try to get TSC frequency from CPUID first
-        if let Some(freq) = tsc::get_frequency_from_cpuid() {
-            freq
-        } else {
-            // Fall back to PIT calibration
-            calibration::calibrate_tsc_with_pit()?
-        }
-    } else {
-        // Non-invariant TSC - still calibrate but mark it
-        calibration::calibrate_tsc_with_pit()?
-    };
-
+    // Calibrate TSC against CPUID.15h, HPET or PIT
+    let calibration = calibration::calibrate_tsc().ok();
+    let tsc_freq = calibration.map_or(0, |c| c.frequency);
     TSC_FREQUENCY.store(tsc_freq, Ordering::SeqCst);
 
     log::info!(
-        "Timers: TSC calibrated at {} MHz (invariant={})",
+        "Timers: TSC calibrated at {} MHz via {:?} (invariant={}, constant={})",
         tsc_freq / 1_000_000,
-        tsc_features.invariant
+        calibration.map(|c| c.method),
+        tsc_features.invariant,
+        tsc_features.constant
     );
 
     // Select primary clock source
-    if tsc_features.invariant && tsc_freq > 0 {
-        PRIMARY_CLOCK.store(TimerSource::Tsc as u64, Ordering::SeqCst);
-        log::info!("Timers: Using TSC as primary clock source");
-    } else {
-        // Try HPET
-        if hpet::is_available() {
-            PRIMARY_CLOCK.store(TimerSource::Hpet as u64, Ordering::SeqCst);
-            log::info!("Timers: Using HPET as primary clock source");
-        } else {
-            PRIMARY_CLOCK.store(TimerSource::Pit as u64, Ordering::SeqCst);
-            log::warn!("Timers: Falling back to PIT (low precision)");
-        }
+    let source = clocksource::select(tsc_features, calibration.map(|c| c.method));
+    match source {
+        TimerSource::Pit => log::warn!("Timers: Falling back to PIT (low precision)"),
+        _ => log::info!("Timers: Using {} as primary clock source", source.name()),
     }
 
     Ok(())
@@ -216,11 +219,19 @@ pub unsafe fn init_for_ap() -> Result<(), TimerError> {
     }
 
     // Each AP needs its own APIC timer configured
-    // The TSC should be synchronized on modern systems
+    // TSC synchronization is verified by tsc_sync at AP bring-up
 
     Ok(())
 }
 
+/// Switch the primary clock source, keeping `read_ns` continuous
+pub fn switch_clock_source(source: TimerSource) {
+    let now = read_ns() as i64;
+    let offset = now - raw_ns(source) as i64;
+    CLOCK_OFFSET_NS.store(offset, Ordering::SeqCst);
+    PRIMARY_CLOCK.store(source as u64, Ordering::SeqCst);
+}
+
 // =============================================================================
 // Error Type
 // =============================================================================
@@ -285,7 +296,14 @@ pub fn is_tsc_invariant() -> bool {
 /// Read the current timestamp (nanoseconds since boot)
 #[inline]
 pub fn read_ns() -> u64 {
-    match primary_clock_source() {
+    let raw = raw_ns(primary_clock_source());
+    (raw as i64).wrapping_add(CLOCK_OFFSET_NS.load(Ordering::Relaxed)) as u64
+}
+
+/// Read a clock source without the switch offset
+#[inline]
+fn raw_ns(source: TimerSource) -> u64 {
+    match source {
         TimerSource::Tsc => {
             let freq = tsc_frequency();
             if freq > 0 {
@@ -0,0 +1,118 @@
+//! # Tickless (nohz_full) CPUs
+//!
+//! Stops and restarts the periodic APIC timer tick of individual CPUs on
+//! behalf of the scheduler's CPU isolation layer. The local APIC timer can
+//! only be programmed from its own CPU, so a request for another CPU is
+//! recorded and that CPU is kicked with a reschedule IPI; its IPI handler
+//! calls [`apply_pending`].
+
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+
+use super::apic_timer;
+use crate::arch::x86_64::apic::ipi;
+use crate::arch::x86_64::smp::{self, MAX_CPUS};
+
+/// Periodic tick configuration of one CPU
+struct TickState {
+    /// Initial count of the periodic timer (0 = not registered)
+    ticks: AtomicU32,
+    /// Tick vector
+    vector: AtomicU8,
+    /// Tick currently stopped
+    stopped: AtomicBool,
+    /// Requested state not yet applied (0 = none, 1 = stop, 2 = start)
+    pending: AtomicU8,
+}
+
+impl TickState {
+    const fn new() -> Self {
+        Self {
+            ticks: AtomicU32::new(0),
+            vector: AtomicU8::new(0),
+            stopped: AtomicBool::new(false),
+            pending: AtomicU8::new(0),
+        }
+    }
+}
+
+const PENDING_STOP: u8 = 1;
+const PENDING_START: u8 = 2;
+
+static TICKS: [TickState; MAX_CPUS] = [const { TickState::new() }; MAX_CPUS];
+
+/// Remember the periodic tick of `cpu` so it can be restarted
+pub fn register_tick(cpu: usize, ticks: u32, vector: u8) {
+    if let Some(state) = TICKS.get(cpu) {
+        state.ticks.store(ticks, Ordering::Relaxed);
+        state.vector.store(vector, Ordering::Relaxed);
+        state.stopped.store(false, Ordering::Release);
+    }
+}
+
+/// Restart (`enabled`) or stop the periodic tick of `cpu`
+///
+/// Applied immediately on the current CPU, otherwise on the next
+/// reschedule IPI received by `cpu`.
+pub fn set_tick(cpu: usize, enabled: bool) {
+    let Some(state) = TICKS.get(cpu) else {
+        return;
+    };
+    if state.ticks.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+
+    if cpu == smp::current_cpu_id() as usize {
+        state.pending.store(0, Ordering::Relaxed);
+        apply(cpu, state, enabled);
+        return;
+    }
+
+    let request = if enabled { PENDING_START } else { PENDING_STOP };
+    state.pending.store(request, Ordering::Release);
+    if let Some(info) = smp::get_cpu_info(cpu) {
+        ipi::send_reschedule(info.apic_id());
+    }
+}
+
+/// Apply a tick change requested by another CPU
+///
+/// Called from the reschedule IPI handler.
+pub fn apply_pending() {
+    let cpu = smp::current_cpu_id() as usize;
+    let Some(state) = TICKS.get(cpu) else {
+        return;
+    };
+    match state.pending.swap(0, Ordering::Acquire) {
+        PENDING_STOP => apply(cpu, state, false),
+        PENDING_START => apply(cpu, state, true),
+        _ => {}
+    }
+}
+
+fn apply(cpu: usize, state: &TickState, enabled: bool) {
+    if state.stopped.load(Ordering::Relaxed) != enabled {
+        return;
+    }
+
+    // SAFETY: the tick was registered, so the timer of this CPU is
+    // initialized and calibrated
+    let result = unsafe {
+        if enabled {
+            apic_timer::start_periodic(
+                cpu,
+                state.ticks.load(Ordering::Relaxed),
+                state.vector.load(Ordering::Relaxed),
+            )
+        } else {
+            apic_timer::stop(cpu)
+        }
+    };
+    if result.is_ok() {
+        state.stopped.store(!enabled, Ordering::Release);
+    }
+}
+
+/// Is the tick of `cpu` stopped?
+pub fn is_tick_stopped(cpu: usize) -> bool {
+    TICKS.get(cpu).is_some_and(|state| state.stopped.load(Ordering::Acquire))
+}
@@ -26,6 +26,8 @@
 
 use core::sync::atomic::{AtomicU64, Ordering};
 
+use crate::arch::x86_64::cpu::cpuid_subleaf;
+
 // =============================================================================
 // Constants
 // =============================================================================
@@ -48,8 +50,10 @@
 pub struct TscFeatures {
     /// TSC is available
     pub available: bool,
-    /// TSC is invariant (constant rate)
+    /// TSC is invariant (constant rate, runs in all C-states)
     pub invariant: bool,
+    /// TSC runs at a constant rate across P-states
+    pub constant: bool,
     /// RDTSCP instruction available
     pub rdtscp: bool,
     /// TSC deadline mode available (for APIC)
@@ -64,6 +68,7 @@ pub fn detect() -> Self {
         let mut features = Self {
             available: false,
             invariant: false,
+            constant: false,
             rdtscp: false,
             tsc_deadline: false,
             tsc_adjust: false,
@@ -90,6 +95,9 @@ pub fn detect() -> Self {
             features.invariant = edx & (1 << 8) != 0;
         }
 
+        // Constant TSC is not enumerated; infer it from the CPU model
+        features.constant = features.invariant || has_constant_tsc(eax);
+
         // Check for TSC_ADJUST MSR (CPUID.07H:EBX[bit 1])
         if eax >= 7 {
             let (_, ebx, _, _) = cpuid_subleaf(7, 0);
@@ -98,6 +106,37 @@ pub fn detect() -> Self {
 
         features
     }
+
+    /// Is the TSC usable as the system clocksource?
+    ///
+    /// Requires a constant rate that keeps counting in deep C-states.
+    pub fn is_reliable(&self) -> bool {
+        self.available && self.invariant && self.constant
+    }
+}
+
+/// Constant TSC by model (CPUID.01H signature)
+///
+/// Intel family 0Fh model 03h+ and family 06h model 0Eh+ tick at a fixed
+/// rate; AMD enumerates it through the invariant bit.
+fn has_constant_tsc(max_leaf: u32) -> bool {
+    if max_leaf < 1 {
+        return false;
+    }
+    let (_, vendor_ebx, _, _) = cpuid(0);
+    // "Genu"ineIntel
+    if vendor_ebx != 0x756E_6547 {
+        return false;
+    }
+
+    let (signature, _, _, _) = cpuid(1);
+    let family = (signature >> 8) & 0xF;
+    let model = ((signature >> 4) & 0xF) | ((signature >> 12) & 0xF0);
+    match family {
+        0xF => model >= 0x03,
+        0x6 => model >= 0x0E,
+        _ => false,
+    }
 }
 
 /// Detect TSC features
@@ -110,33 +149,9 @@ pub fn detect_features() -> TscFeatures {
 // =============================================================================
 
 fn cpuid(leaf: u32) -> (u32, u32, u32, u32) {
-    let (eax, ebx, ecx, edx): (u32, u32, u32, u32);
-    unsafe {
-        core::arch::asm!(
-            "cpuid",
-            inout("eax") leaf => eax,
-            out("ebx") ebx,
-            out("ecx") ecx,
-            out("edx") edx,
-            options(nostack, preserves_flags),
-        );
-    }
-    (eax, ebx, ecx, edx)
-}
-
-fn cpuid_subleaf(leaf: u32, subleaf: u32) -> (u32, u32, u32, u32) {
-    let (eax, ebx, ecx, edx): (u32, u32, u32, u32);
-    unsafe {
-        core::arch::asm!(
-            "cpuid",
-            inout("eax") leaf => eax,
-            out("ebx") ebx,
-            inout("ecx") subleaf => ecx,
-            out("edx") edx,
-            options(nostack, preserves_flags),
-        );
-    }
-    (eax, ebx, ecx, edx)
+    // RBX is reserved by LLVM, so use the intrinsic rather than inline asm
+    let r = unsafe { core::arch::x86_64::__cpuid(leaf) };
+    (r.eax, r.ebx, r.ecx, r.edx)
 }
 
 fn cpuid_max_extended() -> u32 {
@@ -231,6 +246,32 @@ pub fn read_best() -> u64 {
 /// This uses CPUID leaf 0x15 (TSC/Core Crystal Clock)
 /// and optionally 0x16 (Processor Frequency Information).
 pub fn get_frequency_from_cpuid() -> Option<u64> {
+    if let Some(freq) = get_frequency_from_cpuid_15h() {
+        return Some(freq);
+    }
+
+    let (max_leaf, _, _, _) = cpuid(0);
+
+    // Try CPUID.16H for base frequency
+    if max_leaf >= CPUID_FREQ_INFO {
+        let (eax, _, _, _) = cpuid(CPUID_FREQ_INFO);
+
+        // EAX = Processor Base Frequency (in MHz)
+        if eax != 0 {
+            // Note: This is base frequency, not TSC frequency
+            // For invariant TSC, they're usually the same
+            return Some((eax as u64) * 1_000_000);
+        }
+    }
+
+    None
+}
+
+/// Get the exact TSC frequency from CPUID leaf 0x15
+///
+/// Unlike [`get_frequency_from_cpuid`], never falls back to the
+/// approximate base frequency of leaf 0x16.
+pub fn get_frequency_from_cpuid_15h() -> Option<u64> {
     let (max_leaf, _, _, _) = cpuid(0);
 
     if max_leaf >= CPUID_TSC_INFO {
@@ -257,18 +298,6 @@ pub fn get_frequency_from_cpuid() -> Option<u64> {
         }
     }
 
-    // Try CPUID.16H for base frequency
-    if max_leaf >= CPUID_FREQ_INFO {
-        let (eax, _, _, _) = cpuid(CPUID_FREQ_INFO);
-
-        // EAX = Processor Base Frequency (in MHz)
-        if eax != 0 {
-            // Note: This is base frequency, not TSC frequency
-            // For invariant TSC, they're usually the same
-            return Some((eax as u64) * 1_000_000);
-        }
-    }
-
     None
 }
 
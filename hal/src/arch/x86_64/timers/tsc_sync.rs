@@ -0,0 +1,253 @@
+//! # Cross-CPU TSC Synchronization
+//!
+//! Even with an invariant TSC, firmware can leave the counters of
+//! different sockets (or cores) out of step. When an AP comes online the
+//! BSP ([`check_source`]) and the AP ([`check_target`]) run two tests
+//! together:
+//!
+//! 1. **Warp test**: both CPUs repeatedly read the TSC under a shared lock
+//!    and compare it with the last value read by either CPU. Any read that
+//!    goes backwards is a warp.
+//! 2. **Skew estimate**: a ping-pong exchange estimates the AP's offset
+//!    from the BSP, keeping the round with the shortest round trip.
+//!
+//! A warp, or a skew above [`MAX_SKEW_NS`], marks the TSC unstable.
+//! After bring-up, [`warp_check`] repeats the warp test opportunistically
+//! from the clocksource watchdog.
+
+use core::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, Ordering};
+
+use super::clocksource::{self, Instability};
+use super::tsc;
+use crate::arch::x86_64::smp::MAX_CPUS;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Lock-protected reads per CPU in the warp test
+const WARP_ITERATIONS: u32 = 20_000;
+
+/// Ping-pong rounds in the skew estimate
+const SKEW_ROUNDS: u64 = 16;
+
+/// Largest tolerated skew between two CPUs
+pub const MAX_SKEW_NS: u64 = 1_000;
+
+/// How long an AP waits for the BSP to start the check
+const TARGET_WAIT_NS: u64 = 100_000_000;
+
+// =============================================================================
+// State
+// =============================================================================
+
+/// CPU being checked, plus one (0 = no check in progress)
+static ARMED: AtomicU32 = AtomicU32::new(0);
+
+/// Rendezvous counters
+static START: AtomicU32 = AtomicU32::new(0);
+static STOP: AtomicU32 = AtomicU32::new(0);
+
+/// Lock serializing warp test reads
+static WARP_LOCK: AtomicBool = AtomicBool::new(false);
+
+/// Last TSC value read under the lock
+static LAST_TSC: AtomicU64 = AtomicU64::new(0);
+
+/// Ping-pong round requested by the BSP / acknowledged by the AP
+static PING: AtomicU64 = AtomicU64::new(0);
+static PONG: AtomicU64 = AtomicU64::new(0);
+
+/// AP TSC value for the current round
+static REMOTE_TSC: AtomicU64 = AtomicU64::new(0);
+
+/// Warps observed (bring-up and periodic)
+static WARPS: AtomicU64 = AtomicU64::new(0);
+
+/// Largest backwards step observed, in cycles
+static MAX_WARP: AtomicU64 = AtomicU64::new(0);
+
+/// Largest absolute skew measured, in cycles
+static MAX_SKEW: AtomicU64 = AtomicU64::new(0);
+
+/// Measured skew per CPU relative to the BSP, in cycles
+static SKEW: [AtomicI64; MAX_CPUS] = [const { AtomicI64::new(0) }; MAX_CPUS];
+
+/// CPUs checked
+static CHECKED: AtomicU32 = AtomicU32::new(0);
+
+// =============================================================================
+// Bring-up Check
+// =============================================================================
+
+/// BSP side of the check for `cpu`
+///
+/// Call after the AP has signalled that it is running. Returns `true` if
+/// the AP's TSC is in sync.
+pub fn check_source(cpu: usize) -> bool {
+    if cpu >= MAX_CPUS {
+        return false;
+    }
+
+    START.store(0, Ordering::SeqCst);
+    STOP.store(0, Ordering::SeqCst);
+    LAST_TSC.store(0, Ordering::SeqCst);
+    PING.store(0, Ordering::SeqCst);
+    PONG.store(0, Ordering::SeqCst);
+    let warps_before = WARPS.load(Ordering::SeqCst);
+    ARMED.store(cpu as u32 + 1, Ordering::SeqCst);
+
+    rendezvous(&START);
+    warp_loop();
+    rendezvous(&STOP);
+
+    // Skew: keep the round with the shortest round trip
+    let mut best_rtt = u64::MAX;
+    let mut skew = 0i64;
+    for round in 1..=SKEW_ROUNDS {
+        let t0 = tsc::read_serialized();
+        PING.store(round, Ordering::SeqCst);
+        while PONG.load(Ordering::SeqCst) != round {
+            core::hint::spin_loop();
+        }
+        let t1 = tsc::read_serialized();
+        let remote = REMOTE_TSC.load(Ordering::SeqCst);
+
+        let rtt = t1.wrapping_sub(t0);
+        if rtt < best_rtt {
+            best_rtt = rtt;
+            skew = remote.wrapping_sub(t0 + rtt / 2) as i64;
+        }
+    }
+    ARMED.store(0, Ordering::SeqCst);
+
+    SKEW[cpu].store(skew, Ordering::Relaxed);
+    MAX_SKEW.fetch_max(skew.unsigned_abs(), Ordering::Relaxed);
+    CHECKED.fetch_add(1, Ordering::Relaxed);
+
+    let warped = WARPS.load(Ordering::SeqCst) != warps_before;
+    let skew_ns = super::tsc_to_ns(skew.unsigned_abs());
+    if warped {
+        log::warn!(
+            "TSC: warp of {} cycles between BSP and CPU {}",
+            MAX_WARP.load(Ordering::Relaxed),
+            cpu
+        );
+        clocksource::mark_tsc_unstable(Instability::Warp);
+        false
+    } else if skew_ns > MAX_SKEW_NS {
+        log::warn!("TSC: CPU {} skewed by {} cycles (~{} ns)", cpu, skew, skew_ns);
+        clocksource::mark_tsc_unstable(Instability::SyncFailed);
+        false
+    } else {
+        log::debug!("TSC: CPU {} in sync (skew {} cycles)", cpu, skew);
+        true
+    }
+}
+
+/// AP side of the check, run by `cpu` right after it signals ready
+///
+/// Returns without checking if the BSP does not start the check in time
+/// (e.g. the TSC is already known to be unstable).
+pub fn check_target(cpu: usize) {
+    let deadline = tsc::read().wrapping_add(super::ns_to_tsc(TARGET_WAIT_NS));
+    while ARMED.load(Ordering::SeqCst) != cpu as u32 + 1 {
+        if super::tsc_frequency() == 0 || tsc::read() > deadline {
+            return;
+        }
+        core::hint::spin_loop();
+    }
+
+    rendezvous(&START);
+    warp_loop();
+    rendezvous(&STOP);
+
+    for round in 1..=SKEW_ROUNDS {
+        while PING.load(Ordering::SeqCst) != round {
+            core::hint::spin_loop();
+        }
+        REMOTE_TSC.store(tsc::read_serialized(), Ordering::SeqCst);
+        PONG.store(round, Ordering::SeqCst);
+    }
+}
+
+/// Wait until both CPUs have reached `counter`
+fn rendezvous(counter: &AtomicU32) {
+    counter.fetch_add(1, Ordering::SeqCst);
+    while counter.load(Ordering::SeqCst) < 2 {
+        core::hint::spin_loop();
+    }
+}
+
+/// Warp test body, run concurrently on both CPUs
+fn warp_loop() {
+    for _ in 0..WARP_ITERATIONS {
+        locked_read();
+    }
+}
+
+/// Read the TSC under the warp lock, recording any backwards step
+fn locked_read() -> bool {
+    while WARP_LOCK
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        core::hint::spin_loop();
+    }
+    let prev = LAST_TSC.load(Ordering::Relaxed);
+    let now = tsc::read_serialized();
+    LAST_TSC.store(now, Ordering::Relaxed);
+    WARP_LOCK.store(false, Ordering::Release);
+
+    if now < prev {
+        WARPS.fetch_add(1, Ordering::Relaxed);
+        MAX_WARP.fetch_max(prev - now, Ordering::Relaxed);
+        return false;
+    }
+    true
+}
+
+// =============================================================================
+// Periodic Check
+// =============================================================================
+
+/// Check that this CPU's TSC has not fallen behind other CPUs
+///
+/// Cheap enough to run from every CPU's watchdog tick. Marks the TSC
+/// unstable and returns `false` on a warp.
+pub fn warp_check() -> bool {
+    if locked_read() {
+        return true;
+    }
+    clocksource::mark_tsc_unstable(Instability::Warp);
+    false
+}
+
+// =============================================================================
+// Statistics
+// =============================================================================
+
+/// Warps observed so far
+pub fn warps() -> u64 {
+    WARPS.load(Ordering::Relaxed)
+}
+
+/// Largest backwards step observed, in cycles
+pub fn max_warp() -> u64 {
+    MAX_WARP.load(Ordering::Relaxed)
+}
+
+/// Largest absolute skew measured at bring-up, in cycles
+pub fn max_skew() -> u64 {
+    MAX_SKEW.load(Ordering::Relaxed)
+}
+
+/// Measured skew of `cpu` relative to the BSP, in cycles
+pub fn skew(cpu: usize) -> Option<i64> {
+    SKEW.get(cpu).map(|s| s.load(Ordering::Relaxed))
+}
+
+/// CPUs checked at bring-up
+pub fn checked_cpus() -> u32 {
+    CHECKED.load(Ordering::Relaxed)
+}
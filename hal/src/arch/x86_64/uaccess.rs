@@ -0,0 +1,185 @@
+//! # Supervisor Mode Access Protections
+//!
+//! SMEP, SMAP and UMIP, and the copy routine behind
+//! [`crate::uaccess`].
+//!
+//! - **SMEP**: the kernel faults when fetching instructions from user pages.
+//! - **SMAP**: the kernel faults on any access to user pages unless
+//!   `RFLAGS.AC` is set. [`raw_copy_from_user`] and [`raw_copy_to_user`]
+//!   set it with `STAC` around a single `REP MOVSB` and clear it with
+//!   `CLAC`; the syscall entry clears it too (`SFMASK`).
+//! - **UMIP**: `SGDT`, `SIDT`, `SLDT`, `SMSW` and `STR` fault in user mode,
+//!   so descriptor table addresses do not leak.
+//!
+//! The `REP MOVSB` has an exception table entry whose fixup skips to the
+//! `CLAC`; `RCX` then holds the number of bytes left.
+
+use core::arch::asm;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::cpu::cpuid_subleaf;
+
+/// End of the user address space (exclusive): the lower canonical half
+pub const USER_SPACE_END: u64 = 1 << 47;
+
+/// CR4.UMIP
+const CR4_UMIP: u64 = 1 << 11;
+/// CR4.SMEP
+const CR4_SMEP: u64 = 1 << 20;
+/// CR4.SMAP
+const CR4_SMAP: u64 = 1 << 21;
+
+/// SMAP is enabled: `STAC`/`CLAC` are only valid when it is supported
+static SMAP_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Supervisor mode access protections
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessProtection {
+    /// Supervisor Mode Execution Prevention
+    pub smep: bool,
+    /// Supervisor Mode Access Prevention
+    pub smap: bool,
+    /// User Mode Instruction Prevention
+    pub umip: bool,
+}
+
+impl AccessProtection {
+    /// CR4 bits enabling these protections
+    pub const fn cr4_bits(&self) -> u64 {
+        let mut bits = 0;
+        if self.smep {
+            bits |= CR4_SMEP;
+        }
+        if self.smap {
+            bits |= CR4_SMAP;
+        }
+        if self.umip {
+            bits |= CR4_UMIP;
+        }
+        bits
+    }
+}
+
+// =============================================================================
+// Detection
+// =============================================================================
+
+/// Decode CPUID leaf 7 subleaf 0 (SMEP in EBX[7], SMAP in EBX[20], UMIP
+/// in ECX[2])
+pub const fn protection_from_cpuid(ebx: u32, ecx: u32) -> AccessProtection {
+    AccessProtection {
+        smep: ebx & (1 << 7) != 0,
+        smap: ebx & (1 << 20) != 0,
+        umip: ecx & (1 << 2) != 0,
+    }
+}
+
+/// Detect the supported protections
+pub fn detect() -> AccessProtection {
+    let (max_leaf, _, _, _) = cpuid_subleaf(0, 0);
+    if max_leaf < 7 {
+        return AccessProtection::default();
+    }
+    let (_, ebx, ecx, _) = cpuid_subleaf(7, 0);
+    protection_from_cpuid(ebx, ecx)
+}
+
+// =============================================================================
+// Enabling
+// =============================================================================
+
+/// Enable every supported protection on this CPU
+///
+/// Call on every CPU, before entering user mode.
+///
+/// # Safety
+///
+/// The kernel must not touch user memory other than through
+/// [`crate::uaccess`] afterwards.
+pub unsafe fn enable() -> AccessProtection {
+    let protection = detect();
+    unsafe {
+        let mut cr4: u64;
+        asm!("mov {}, cr4", out(reg) cr4, options(nomem, nostack, preserves_flags));
+        cr4 |= protection.cr4_bits();
+        asm!("mov cr4, {}", in(reg) cr4, options(nostack, preserves_flags));
+    }
+    SMAP_ENABLED.store(protection.smap, Ordering::Relaxed);
+    protection
+}
+
+// =============================================================================
+// Copy
+// =============================================================================
+
+/// Copy `len` bytes with user access open
+///
+/// Returns the number of bytes not copied: nonzero only after a fault.
+/// Never inlined, so the routine has exactly one exception table entry.
+///
+/// # Safety
+///
+/// The kernel side must be valid for `len` bytes and the user side must
+/// have passed [`access_ok`](crate::uaccess::access_ok).
+#[inline(never)]
+unsafe fn copy_user(dst: *mut u8, src: *const u8, len: usize) -> usize {
+    let smap = SMAP_ENABLED.load(Ordering::Relaxed) as u8;
+    let left: usize;
+    unsafe {
+        asm!(
+            "test {smap}, {smap}",
+            "jz 2f",
+            "stac",
+            "2:",
+            "3: rep movsb",
+            "4:",
+            "test {smap}, {smap}",
+            "jz 5f",
+            "clac",
+            "5:",
+            ".pushsection helix_extable, \"a\"",
+            ".balign 4",
+            ".long 3b - .",
+            ".long 4b - .",
+            ".popsection",
+            smap = in(reg_byte) smap,
+            inout("rdi") dst => _,
+            inout("rsi") src => _,
+            inout("rcx") len => left,
+            options(nostack),
+        );
+    }
+    left
+}
+
+/// Copy from user memory; see [`crate::uaccess::copy_from_user`]
+///
+/// # Safety
+///
+/// As for [`copy_user`].
+pub unsafe fn raw_copy_from_user(dst: *mut u8, src: *const u8, len: usize) -> usize {
+    unsafe { copy_user(dst, src, len) }
+}
+
+/// Copy to user memory; see [`crate::uaccess::copy_to_user`]
+///
+/// # Safety
+///
+/// As for [`copy_user`].
+pub unsafe fn raw_copy_to_user(dst: *mut u8, src: *const u8, len: usize) -> usize {
+    unsafe { copy_user(dst, src, len) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protection_from_cpuid() {
+        let all = protection_from_cpuid((1 << 7) | (1 << 20), 1 << 2);
+        assert!(all.smep && all.smap && all.umip);
+        assert_eq!(all.cr4_bits(), CR4_SMEP | CR4_SMAP | CR4_UMIP);
+        assert_eq!(protection_from_cpuid(1 << 7, 0).cr4_bits(), CR4_SMEP);
+        assert_eq!(protection_from_cpuid(0, 0), AccessProtection::default());
+    }
+}
@@ -274,6 +274,11 @@ pub unsafe fn run_hello_world() -> ! {
         let code_page_start = entry & !0xFFF;  // Page-align
         super::paging::make_user_accessible(code_page_start, 4096);
         
+        // The program lives in .data, which is NX once the kernel is
+        // protected: turn its page into code (read+execute, W^X)
+        let code_page = crate::kernel_protect::Span::new(code_page_start, code_page_start + 4096);
+        let _ = super::kernel_protect::set_protection(code_page, crate::kernel_protect::Protection::ReadExecute);
+        
         // Also make the user stack accessible from Ring 3
         let stack_start = USER_STACK.0.as_ptr() as u64;
         super::paging::make_user_accessible(stack_start, USER_STACK_SIZE);
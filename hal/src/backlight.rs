@@ -0,0 +1,468 @@
+//! # Display Backlight
+//!
+//! Brightness control for built-in panels, through whichever interface the
+//! platform offers. Each interface is a [`Backlight`] with its own level
+//! range; [`preferred`] picks the one to drive when several exist, favouring
+//! firmware, which knows the panel's usable range:
+//!
+//! - [`BacklightType::Firmware`]: ACPI video `_BCL` levels, set with `_BCM`
+//!   and read back with `_BQC` ([`AcpiBacklight`])
+//! - [`BacklightType::Platform`]: vendor embedded controller register
+//!   ([`EcBacklight`])
+//! - [`BacklightType::Raw`]: eDP panel PWM on the GPU ([`PwmBacklight`])
+//!
+//! UEFI GOP has no brightness control, but the GOP driver programs the
+//! panel PWM before handing over; [`PwmBacklight`] keeps the frequency it
+//! chose and only changes the duty cycle.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, Ordering};
+use spin::RwLock;
+
+use crate::{HalError, HalResult};
+
+/// Kind of backlight interface, as reported in sysfs `type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BacklightType {
+    /// Firmware method (ACPI video)
+    Firmware,
+    /// Platform-specific interface (embedded controller)
+    Platform,
+    /// Direct hardware register (GPU PWM)
+    Raw,
+}
+
+impl BacklightType {
+    /// sysfs name
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Firmware => "firmware",
+            Self::Platform => "platform",
+            Self::Raw => "raw",
+        }
+    }
+}
+
+/// A panel backlight
+///
+/// Levels run from 0 to [`max_brightness`](Backlight::max_brightness);
+/// their meaning is device specific, and 0 may or may not turn the panel
+/// off.
+pub trait Backlight: Send + Sync {
+    /// Device name (`acpi_video0`, `intel_backlight`, ...)
+    fn name(&self) -> &str;
+
+    /// Interface kind
+    fn backlight_type(&self) -> BacklightType;
+
+    /// Highest level
+    fn max_brightness(&self) -> u32;
+
+    /// Level currently applied by the hardware
+    fn brightness(&self) -> HalResult<u32>;
+
+    /// Apply a level, at most [`max_brightness`](Backlight::max_brightness)
+    fn set_brightness(&self, level: u32) -> HalResult<()>;
+}
+
+/// Level closest to `percent` of `max`
+pub fn percent_to_level(percent: u32, max: u32) -> u32 {
+    ((percent.min(100) as u64 * max as u64 + 50) / 100) as u32
+}
+
+/// Percentage of `max` that `level` represents
+pub fn level_to_percent(level: u32, max: u32) -> u32 {
+    match max {
+        0 => 0,
+        _ => ((level.min(max) as u64 * 100 + max as u64 / 2) / max as u64) as u32,
+    }
+}
+
+/// Set a backlight to `percent` of its range
+pub fn set_percent(backlight: &dyn Backlight, percent: u32) -> HalResult<()> {
+    backlight.set_brightness(percent_to_level(percent, backlight.max_brightness()))
+}
+
+// =============================================================================
+// ACPI video
+// =============================================================================
+
+/// Set a brightness level through `_BCM`
+pub type AcpiSetLevel = Box<dyn Fn(u32) -> HalResult<()> + Send + Sync>;
+
+/// Query the brightness level through `_BQC`
+pub type AcpiQueryLevel = Box<dyn Fn() -> HalResult<u32> + Send + Sync>;
+
+/// ACPI video output device brightness
+///
+/// `_BCL` returns the level on AC power, the level on battery, then the
+/// supported levels (in percent, in any order). Brightness is the index in
+/// the sorted list of supported levels. The methods are evaluated by the
+/// ACPI interpreter, which hands them in as closures.
+pub struct AcpiBacklight {
+    name: String,
+    levels: Vec<u32>,
+    ac_level: u32,
+    battery_level: u32,
+    set_level: AcpiSetLevel,
+    query_level: Option<AcpiQueryLevel>,
+    /// Last index set, when `_BQC` is missing
+    current: AtomicU32,
+}
+
+impl AcpiBacklight {
+    /// Create from a `_BCL` package; `None` if it lists no level
+    pub fn from_bcl(
+        name: &str,
+        bcl: &[u32],
+        set_level: AcpiSetLevel,
+        query_level: Option<AcpiQueryLevel>,
+    ) -> Option<Self> {
+        let mut levels = bcl.get(2..)?.to_vec();
+        levels.sort_unstable();
+        levels.dedup();
+        if levels.is_empty() {
+            return None;
+        }
+        let backlight = Self {
+            name: String::from(name),
+            ac_level: bcl[0],
+            battery_level: bcl[1],
+            current: AtomicU32::new(0),
+            levels,
+            set_level,
+            query_level,
+        };
+        backlight.current.store(backlight.index_of(bcl[0]), Ordering::Relaxed);
+        Some(backlight)
+    }
+
+    /// Index of the supported level closest to `level`
+    fn index_of(&self, level: u32) -> u32 {
+        let index = self.levels.iter().enumerate().min_by_key(|(_, l)| l.abs_diff(level));
+        index.map_or(0, |(i, _)| i as u32)
+    }
+
+    /// Supported levels, ascending
+    pub fn levels(&self) -> &[u32] {
+        &self.levels
+    }
+
+    /// Brightness firmware recommends on AC power
+    pub fn ac_brightness(&self) -> u32 {
+        self.index_of(self.ac_level)
+    }
+
+    /// Brightness firmware recommends on battery
+    pub fn battery_brightness(&self) -> u32 {
+        self.index_of(self.battery_level)
+    }
+}
+
+impl Backlight for AcpiBacklight {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn backlight_type(&self) -> BacklightType {
+        BacklightType::Firmware
+    }
+
+    fn max_brightness(&self) -> u32 {
+        self.levels.len() as u32 - 1
+    }
+
+    fn brightness(&self) -> HalResult<u32> {
+        match &self.query_level {
+            Some(query) => Ok(self.index_of(query()?)),
+            None => Ok(self.current.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn set_brightness(&self, level: u32) -> HalResult<()> {
+        let value = *self.levels.get(level as usize).ok_or(HalError::InvalidParameter)?;
+        (self.set_level)(value)?;
+        self.current.store(level, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Embedded controller
+// =============================================================================
+
+/// Embedded controller register access
+pub trait EmbeddedController: Send + Sync {
+    /// Read a register
+    fn read(&self, register: u8) -> HalResult<u8>;
+
+    /// Write a register
+    fn write(&self, register: u8, value: u8) -> HalResult<()>;
+}
+
+/// Brightness held in one embedded controller register
+///
+/// Vendors that do not route brightness through ACPI expose it as a raw
+/// EC register; its address and range come from a per-model quirk table.
+pub struct EcBacklight {
+    name: String,
+    ec: &'static dyn EmbeddedController,
+    register: u8,
+    max: u8,
+}
+
+impl EcBacklight {
+    /// Create over `register` of `ec`, holding levels up to `max`
+    pub fn new(name: &str, ec: &'static dyn EmbeddedController, register: u8, max: u8) -> Self {
+        Self {
+            name: String::from(name),
+            ec,
+            register,
+            max,
+        }
+    }
+}
+
+impl Backlight for EcBacklight {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn backlight_type(&self) -> BacklightType {
+        BacklightType::Platform
+    }
+
+    fn max_brightness(&self) -> u32 {
+        self.max as u32
+    }
+
+    fn brightness(&self) -> HalResult<u32> {
+        Ok(self.ec.read(self.register)?.min(self.max) as u32)
+    }
+
+    fn set_brightness(&self, level: u32) -> HalResult<()> {
+        if level > self.max as u32 {
+            return Err(HalError::InvalidParameter);
+        }
+        self.ec.write(self.register, level as u8)
+    }
+}
+
+// =============================================================================
+// eDP panel PWM
+// =============================================================================
+
+/// Intel south display `BLC_PWM_PCH_CTL1` offset (bit 31: PWM enable)
+pub const INTEL_BLC_PWM_PCH_CTL1: usize = 0xC8250;
+
+/// Intel south display `BLC_PWM_PCH_CTL2` offset (31:16 period, 15:0 duty)
+pub const INTEL_BLC_PWM_PCH_CTL2: usize = 0xC8254;
+
+/// PWM enable bit of `BLC_PWM_PCH_CTL1`
+const PWM_ENABLE: u32 = 1 << 31;
+
+/// Panel PWM driven by the GPU, as an eDP panel backlight
+///
+/// The period, set by the GOP driver or video BIOS, is the maximum
+/// brightness; the duty cycle is the level.
+pub struct PwmBacklight {
+    name: String,
+    ctl1: *mut u32,
+    ctl2: *mut u32,
+    max: u32,
+}
+
+// SAFETY: the registers are only accessed with volatile 32-bit operations
+unsafe impl Send for PwmBacklight {}
+// SAFETY: as above
+unsafe impl Sync for PwmBacklight {}
+
+impl PwmBacklight {
+    /// Create over the PCH backlight registers of an Intel GPU
+    ///
+    /// Returns `None` if firmware left the PWM unprogrammed.
+    ///
+    /// # Safety
+    ///
+    /// `mmio` must be the mapped register BAR (BAR 0) of an Intel GPU
+    /// driving an eDP panel, valid for the life of the backlight.
+    pub unsafe fn intel_pch(name: &str, mmio: *mut u8) -> Option<Self> {
+        // SAFETY: both registers lie within the BAR, per the caller
+        let (ctl1, ctl2) = unsafe {
+            (
+                mmio.add(INTEL_BLC_PWM_PCH_CTL1) as *mut u32,
+                mmio.add(INTEL_BLC_PWM_PCH_CTL2) as *mut u32,
+            )
+        };
+        // SAFETY: valid register, per the caller
+        let max = unsafe { ctl2.read_volatile() } >> 16;
+        (max != 0).then(|| Self {
+            name: String::from(name),
+            ctl1,
+            ctl2,
+            max,
+        })
+    }
+}
+
+impl Backlight for PwmBacklight {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn backlight_type(&self) -> BacklightType {
+        BacklightType::Raw
+    }
+
+    fn max_brightness(&self) -> u32 {
+        self.max
+    }
+
+    fn brightness(&self) -> HalResult<u32> {
+        // SAFETY: register validity is a construction invariant
+        let (ctl1, ctl2) = unsafe { (self.ctl1.read_volatile(), self.ctl2.read_volatile()) };
+        Ok(match ctl1 & PWM_ENABLE {
+            0 => 0,
+            _ => (ctl2 & 0xFFFF).min(self.max),
+        })
+    }
+
+    fn set_brightness(&self, level: u32) -> HalResult<()> {
+        if level > self.max {
+            return Err(HalError::InvalidParameter);
+        }
+        // SAFETY: register validity is a construction invariant
+        unsafe {
+            self.ctl2.write_volatile((self.max << 16) | level);
+            let ctl1 = self.ctl1.read_volatile();
+            self.ctl1.write_volatile(ctl1 | PWM_ENABLE);
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Registry
+// =============================================================================
+
+/// Registered backlights, in registration order
+static BACKLIGHTS: RwLock<Vec<&'static dyn Backlight>> = RwLock::new(Vec::new());
+
+/// Register a backlight; names must be unique
+pub fn register(backlight: &'static dyn Backlight) -> HalResult<()> {
+    let mut backlights = BACKLIGHTS.write();
+    if backlights.iter().any(|b| b.name() == backlight.name()) {
+        return Err(HalError::ResourceBusy);
+    }
+    backlights.push(backlight);
+    Ok(())
+}
+
+/// All registered backlights
+pub fn backlights() -> Vec<&'static dyn Backlight> {
+    BACKLIGHTS.read().clone()
+}
+
+/// Backlight named `name`
+pub fn find(name: &str) -> Option<&'static dyn Backlight> {
+    BACKLIGHTS.read().iter().find(|b| b.name() == name).copied()
+}
+
+/// Backlight to drive when a single one is wanted
+///
+/// Firmware interfaces first, then platform ones, then raw registers;
+/// the first registered among equals.
+pub fn preferred() -> Option<&'static dyn Backlight> {
+    BACKLIGHTS.read().iter().min_by_key(|b| b.backlight_type()).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent_to_level(50, 937), 469);
+        assert_eq!(percent_to_level(150, 937), 937);
+        assert_eq!(level_to_percent(469, 937), 50);
+        assert_eq!(level_to_percent(5, 0), 0);
+    }
+
+    #[test]
+    fn test_acpi_backlight() {
+        let applied = Arc::new(AtomicU32::new(0));
+        let sink = applied.clone();
+        let set: AcpiSetLevel = Box::new(move |level| {
+            sink.store(level, Ordering::Relaxed);
+            Ok(())
+        });
+        let backlight = AcpiBacklight::from_bcl("acpi_video0", &[80, 40, 100, 10, 40, 60, 80, 10], set, None).unwrap();
+
+        assert_eq!(backlight.levels(), [10, 40, 60, 80, 100]);
+        assert_eq!(backlight.max_brightness(), 4);
+        assert_eq!(backlight.ac_brightness(), 3);
+        assert_eq!(backlight.battery_brightness(), 1);
+        assert_eq!(backlight.brightness(), Ok(3));
+
+        backlight.set_brightness(4).unwrap();
+        assert_eq!(applied.load(Ordering::Relaxed), 100);
+        assert_eq!(backlight.brightness(), Ok(4));
+        assert_eq!(backlight.set_brightness(5), Err(HalError::InvalidParameter));
+        assert!(AcpiBacklight::from_bcl("empty", &[100, 50], Box::new(|_| Ok(())), None).is_none());
+    }
+
+    struct TestEc([AtomicU32; 4]);
+
+    impl EmbeddedController for TestEc {
+        fn read(&self, register: u8) -> HalResult<u8> {
+            Ok(self.0[register as usize].load(Ordering::Relaxed) as u8)
+        }
+
+        fn write(&self, register: u8, value: u8) -> HalResult<()> {
+            self.0[register as usize].store(value as u32, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    static EC: TestEc = TestEc([const { AtomicU32::new(0) }; 4]);
+
+    #[test]
+    fn test_registry_preference() {
+        let ec = Box::leak(Box::new(EcBacklight::new("vendor_ec", &EC, 2, 15)));
+        set_percent(ec, 60).unwrap();
+        assert_eq!(ec.brightness(), Ok(9));
+        assert_eq!(ec.set_brightness(16), Err(HalError::InvalidParameter));
+
+        register(ec).unwrap();
+        assert_eq!(register(ec), Err(HalError::ResourceBusy));
+        assert_eq!(preferred().map(|b| b.name()), Some("vendor_ec"));
+
+        let acpi = AcpiBacklight::from_bcl("acpi_video0", &[100, 50, 0, 50, 100], Box::new(|_| Ok(())), None);
+        register(Box::leak(Box::new(acpi.unwrap()))).unwrap();
+        assert_eq!(preferred().map(|b| b.name()), Some("acpi_video0"));
+        assert!(find("vendor_ec").is_some());
+        assert_eq!(backlights().len(), 2);
+    }
+
+    #[test]
+    fn test_pwm_backlight() {
+        let mut mmio = alloc::vec![0u32; INTEL_BLC_PWM_PCH_CTL2 / 4 + 1];
+        let base = mmio.as_mut_ptr() as *mut u8;
+        // SAFETY: the buffer covers both registers
+        assert!(unsafe { PwmBacklight::intel_pch("intel_backlight", base) }.is_none());
+
+        mmio[INTEL_BLC_PWM_PCH_CTL2 / 4] = 0x1D4C << 16;
+        // SAFETY: as above
+        let pwm = unsafe { PwmBacklight::intel_pch("intel_backlight", base) }.unwrap();
+        assert_eq!(pwm.max_brightness(), 0x1D4C);
+        assert_eq!(pwm.brightness(), Ok(0));
+
+        pwm.set_brightness(0x1000).unwrap();
+        assert_eq!(pwm.brightness(), Ok(0x1000));
+        assert_eq!(mmio[INTEL_BLC_PWM_PCH_CTL1 / 4], PWM_ENABLE);
+        assert_eq!(mmio[INTEL_BLC_PWM_PCH_CTL2 / 4], 0x1D4C_1000);
+    }
+}
@@ -0,0 +1,220 @@
+//! # Cache Topology and Partitioning
+//!
+//! Cache hierarchy as enumerated by the CPU, and the last-level cache
+//! allocation interface (Intel RDT Cache Allocation Technology).
+//!
+//! x86_64 enumerates caches with CPUID leaf 4 (Intel) or 0x8000001D (AMD);
+//! both use the same register layout, decoded by [`decode_cpuid_cache`].
+//!
+//! Cache allocation assigns each CPU a class of service (CLOS). Each class
+//! owns a capacity bitmask (CBM) of LLC ways; fills from a CPU only evict
+//! lines in the ways of its class. Masks must be contiguous.
+
+use spin::RwLock;
+
+/// Cache levels tracked by the topology
+pub const MAX_CACHES: usize = 8;
+
+/// Kind of cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheType {
+    /// Data cache
+    Data,
+    /// Instruction cache
+    Instruction,
+    /// Unified cache
+    Unified,
+}
+
+/// Description of one cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheInfo {
+    /// Cache level (1 = L1)
+    pub level: u8,
+    /// Kind of cache
+    pub cache_type: CacheType,
+    /// Size in bytes
+    pub size: u32,
+    /// Line size in bytes
+    pub line_size: u16,
+    /// Associativity
+    pub ways: u16,
+    /// Number of sets
+    pub sets: u32,
+    /// Logical CPUs sharing this cache
+    pub shared_by: u16,
+}
+
+/// Decode a deterministic cache parameters subleaf (CPUID 4 / 0x8000001D)
+///
+/// Returns `None` for the terminating subleaf.
+pub fn decode_cpuid_cache(eax: u32, ebx: u32, ecx: u32) -> Option<CacheInfo> {
+    let cache_type = match eax & 0x1F {
+        1 => CacheType::Data,
+        2 => CacheType::Instruction,
+        3 => CacheType::Unified,
+        _ => return None,
+    };
+    let line_size = (ebx & 0xFFF) + 1;
+    let partitions = ((ebx >> 12) & 0x3FF) + 1;
+    let ways = ((ebx >> 22) & 0x3FF) + 1;
+    let sets = ecx + 1;
+
+    Some(CacheInfo {
+        level: ((eax >> 5) & 0x7) as u8,
+        cache_type,
+        size: ways * partitions * line_size * sets,
+        line_size: line_size as u16,
+        ways: ways as u16,
+        sets,
+        shared_by: (((eax >> 14) & 0xFFF) + 1) as u16,
+    })
+}
+
+/// Cache allocation capabilities of the last-level cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatCapability {
+    /// Length of capacity bitmasks (LLC ways that can be allocated)
+    pub cbm_len: u8,
+    /// Ways that may also be used by other agents (e.g. I/O)
+    pub shareable: u32,
+    /// Highest class of service
+    pub max_cos: u16,
+}
+
+impl CatCapability {
+    /// Decode CPUID leaf 0x10 subleaf 1 (L3 CAT)
+    pub fn from_cpuid(eax: u32, ebx: u32, edx: u32) -> Self {
+        Self {
+            cbm_len: ((eax & 0x1F) + 1) as u8,
+            shareable: ebx,
+            max_cos: (edx & 0xFFFF) as u16,
+        }
+    }
+
+    /// Mask covering every allocatable way
+    pub fn full_mask(&self) -> u32 {
+        if self.cbm_len >= 32 {
+            u32::MAX
+        } else {
+            (1 << self.cbm_len) - 1
+        }
+    }
+
+    /// Can `mask` be programmed as a capacity bitmask?
+    ///
+    /// Masks must be non-empty, contiguous and within [`Self::full_mask`].
+    pub fn is_valid_mask(&self, mask: u32) -> bool {
+        if mask == 0 || mask & !self.full_mask() != 0 {
+            return false;
+        }
+        let shifted = mask >> mask.trailing_zeros();
+        shifted & (shifted + 1) == 0
+    }
+}
+
+/// Cache hierarchy of the system
+///
+/// Recorded once by the boot CPU; all CPUs are assumed identical.
+pub struct CacheTopology {
+    caches: RwLock<[Option<CacheInfo>; MAX_CACHES]>,
+    cat: RwLock<Option<CatCapability>>,
+}
+
+impl CacheTopology {
+    /// Create an empty topology
+    pub const fn new() -> Self {
+        Self {
+            caches: RwLock::new([None; MAX_CACHES]),
+            cat: RwLock::new(None),
+        }
+    }
+
+    /// Record a cache, ignored once [`MAX_CACHES`] are known
+    pub fn add(&self, info: CacheInfo) {
+        let mut caches = self.caches.write();
+        if let Some(slot) = caches.iter_mut().find(|c| c.is_none() || **c == Some(info)) {
+            *slot = Some(info);
+        }
+    }
+
+    /// Record the cache allocation capabilities
+    pub fn set_cat(&self, cat: CatCapability) {
+        *self.cat.write() = Some(cat);
+    }
+
+    /// Cache allocation capabilities, if supported
+    pub fn cat(&self) -> Option<CatCapability> {
+        *self.cat.read()
+    }
+
+    /// Visit all known caches, in enumeration order
+    pub fn for_each(&self, f: impl FnMut(&CacheInfo)) {
+        self.caches.read().iter().flatten().for_each(f);
+    }
+
+    /// Last-level cache
+    pub fn llc(&self) -> Option<CacheInfo> {
+        self.caches
+            .read()
+            .iter()
+            .flatten()
+            .filter(|c| c.cache_type != CacheType::Instruction)
+            .max_by_key(|c| c.level)
+            .copied()
+    }
+}
+
+impl Default for CacheTopology {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global cache topology
+static CACHES: CacheTopology = CacheTopology::new();
+
+/// Get the cache topology
+pub fn caches() -> &'static CacheTopology {
+    &CACHES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_cpuid_cache() {
+        // L3 unified, shared by 16 threads, 16 ways, 64-byte lines, 32768 sets
+        let l3 = decode_cpuid_cache(0x0003_C163, 0x03C0_003F, 0x7FFF).unwrap();
+        assert_eq!(l3.level, 3);
+        assert_eq!(l3.cache_type, CacheType::Unified);
+        assert_eq!(l3.shared_by, 16);
+        assert_eq!(l3.ways, 16);
+        assert_eq!(l3.size, 32 * 1024 * 1024);
+        assert_eq!(decode_cpuid_cache(0, 0, 0), None);
+
+        let topology = CacheTopology::new();
+        let l1d = decode_cpuid_cache(0x0000_4121, 0x02C0_003F, 0x3F).unwrap();
+        topology.add(l1d);
+        topology.add(l3);
+        topology.add(l3);
+        assert_eq!(topology.llc(), Some(l3));
+        let mut count = 0;
+        topology.for_each(|_| count += 1);
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_cat_masks() {
+        let cat = CatCapability::from_cpuid(10, 0x600, 15);
+        assert_eq!(cat.cbm_len, 11);
+        assert_eq!(cat.full_mask(), 0x7FF);
+        assert_eq!(cat.max_cos, 15);
+        assert!(cat.is_valid_mask(0x7F0));
+        assert!(cat.is_valid_mask(0x00F));
+        assert!(!cat.is_valid_mask(0));
+        assert!(!cat.is_valid_mask(0x0F0F));
+        assert!(!cat.is_valid_mask(0x800));
+    }
+}
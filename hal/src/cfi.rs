@@ -0,0 +1,331 @@
+//! # Control-Flow Integrity
+//!
+//! Hardware control-flow protection and violation reporting.
+//!
+//! - **Backward edge**: return addresses are checked against a shadow
+//!   stack (Intel CET shadow stacks) or signed in the link register
+//!   (ARM pointer authentication).
+//! - **Forward edge**: indirect branches must land on a marked target
+//!   (`ENDBR64` for Intel IBT, `BTI` for ARM BTI).
+//!
+//! The architecture code detects support, records it here with
+//! [`Cfi::set_capability`], and enables protection per privilege level.
+//! Violations trap (`#CP` on x86_64, branch target or PAC exceptions on
+//! AArch64) and are passed to [`report_violation`], which logs them and
+//! forwards them to the installed handler (typically feeding the security
+//! oracle and killing the offending process).
+
+use core::fmt;
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::RwLock;
+
+/// Control-flow protection features
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CfiFeatures {
+    /// Shadow stacks (CET_SS)
+    pub shadow_stack: bool,
+    /// Indirect branch tracking (CET_IBT)
+    pub indirect_branch_tracking: bool,
+    /// Branch target identification (ARM BTI)
+    pub branch_target: bool,
+    /// Pointer authentication (ARM PAC)
+    pub pointer_auth: bool,
+}
+
+impl CfiFeatures {
+    /// No protection
+    pub const NONE: Self = Self {
+        shadow_stack: false,
+        indirect_branch_tracking: false,
+        branch_target: false,
+        pointer_auth: false,
+    };
+
+    /// Features present in both sets
+    pub const fn intersect(self, other: Self) -> Self {
+        Self {
+            shadow_stack: self.shadow_stack && other.shadow_stack,
+            indirect_branch_tracking: self.indirect_branch_tracking
+                && other.indirect_branch_tracking,
+            branch_target: self.branch_target && other.branch_target,
+            pointer_auth: self.pointer_auth && other.pointer_auth,
+        }
+    }
+
+    /// Backward-edge protection is present
+    pub const fn protects_returns(&self) -> bool {
+        self.shadow_stack || self.pointer_auth
+    }
+
+    /// Forward-edge protection is present
+    pub const fn protects_branches(&self) -> bool {
+        self.indirect_branch_tracking || self.branch_target
+    }
+
+    /// Nothing is present
+    pub const fn is_empty(&self) -> bool {
+        !self.protects_returns() && !self.protects_branches()
+    }
+}
+
+// =============================================================================
+// Violations
+// =============================================================================
+
+/// Kind of control-flow violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CfiViolationKind {
+    /// `RET` target differs from the shadow stack
+    ReturnMismatch,
+    /// `IRET`/far `RET` target or CS differs from the shadow stack
+    FarReturnMismatch,
+    /// Indirect branch did not land on `ENDBR64`
+    MissingEndbranch,
+    /// `RSTORSSP` found no valid restore token
+    RestoreToken,
+    /// `SETSSBSY` found no valid supervisor token
+    SupervisorToken,
+    /// Indirect branch did not land on a `BTI` instruction
+    BranchTarget,
+    /// Pointer authentication failed
+    PointerAuth,
+    /// Unrecognized report
+    Unknown,
+}
+
+impl CfiViolationKind {
+    /// Decode the `#CP` (control protection) error code
+    pub const fn from_cp_error_code(code: u64) -> Self {
+        match code & 0x7FFF {
+            1 => Self::ReturnMismatch,
+            2 => Self::FarReturnMismatch,
+            3 => Self::MissingEndbranch,
+            4 => Self::RestoreToken,
+            5 => Self::SupervisorToken,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Violation of return (backward-edge) integrity
+    pub const fn is_backward_edge(&self) -> bool {
+        matches!(
+            self,
+            Self::ReturnMismatch
+                | Self::FarReturnMismatch
+                | Self::RestoreToken
+                | Self::SupervisorToken
+                | Self::PointerAuth
+        )
+    }
+
+    /// Short name for reports
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::ReturnMismatch => "shadow stack return mismatch",
+            Self::FarReturnMismatch => "shadow stack far return mismatch",
+            Self::MissingEndbranch => "missing ENDBR64 at branch target",
+            Self::RestoreToken => "invalid shadow stack restore token",
+            Self::SupervisorToken => "invalid supervisor shadow stack token",
+            Self::BranchTarget => "branch target exception",
+            Self::PointerAuth => "pointer authentication failure",
+            Self::Unknown => "unknown control-flow violation",
+        }
+    }
+}
+
+/// A trapped control-flow violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CfiViolation {
+    /// What was violated
+    pub kind: CfiViolationKind,
+    /// Faulting instruction
+    pub ip: u64,
+    /// Shadow stack pointer at the fault (0 if none)
+    pub ssp: u64,
+    /// Raised in user mode
+    pub user: bool,
+}
+
+impl fmt::Display for CfiViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CFI: {} in {} mode at {:#x}",
+            self.kind.name(),
+            if self.user { "user" } else { "kernel" },
+            self.ip
+        )?;
+        if self.ssp != 0 {
+            write!(f, " (SSP {:#x})", self.ssp)?;
+        }
+        Ok(())
+    }
+}
+
+/// Violation handler
+///
+/// Runs in the exception handler: it must not block, and should only
+/// record the violation and mark the offending task for termination.
+pub type ViolationHandler = fn(&CfiViolation);
+
+// =============================================================================
+// State
+// =============================================================================
+
+/// Control-flow protection state of the system
+///
+/// Recorded by the boot CPU; all CPUs are assumed identical.
+pub struct Cfi {
+    capability: RwLock<CfiFeatures>,
+    kernel: RwLock<CfiFeatures>,
+    user: RwLock<CfiFeatures>,
+    handler: RwLock<Option<ViolationHandler>>,
+    violations: AtomicU64,
+}
+
+impl Cfi {
+    /// Create with nothing supported
+    pub const fn new() -> Self {
+        Self {
+            capability: RwLock::new(CfiFeatures::NONE),
+            kernel: RwLock::new(CfiFeatures::NONE),
+            user: RwLock::new(CfiFeatures::NONE),
+            handler: RwLock::new(None),
+            violations: AtomicU64::new(0),
+        }
+    }
+
+    /// Record what the hardware supports
+    pub fn set_capability(&self, features: CfiFeatures) {
+        *self.capability.write() = features;
+    }
+
+    /// What the hardware supports
+    pub fn capability(&self) -> CfiFeatures {
+        *self.capability.read()
+    }
+
+    /// Record the protection enabled in the kernel, limited to the capability
+    pub fn set_kernel(&self, features: CfiFeatures) -> CfiFeatures {
+        let enabled = features.intersect(self.capability());
+        *self.kernel.write() = enabled;
+        enabled
+    }
+
+    /// Protection enabled in the kernel
+    pub fn kernel(&self) -> CfiFeatures {
+        *self.kernel.read()
+    }
+
+    /// Record the protection enabled for user tasks, limited to the capability
+    pub fn set_user(&self, features: CfiFeatures) -> CfiFeatures {
+        let enabled = features.intersect(self.capability());
+        *self.user.write() = enabled;
+        enabled
+    }
+
+    /// Protection enabled for user tasks
+    pub fn user(&self) -> CfiFeatures {
+        *self.user.read()
+    }
+
+    /// Install the violation handler
+    pub fn set_violation_handler(&self, handler: ViolationHandler) {
+        *self.handler.write() = Some(handler);
+    }
+
+    /// Number of violations reported
+    pub fn violation_count(&self) -> u64 {
+        self.violations.load(Ordering::Relaxed)
+    }
+
+    /// Count and log a violation, then pass it to the handler
+    pub fn report(&self, violation: &CfiViolation) {
+        self.violations.fetch_add(1, Ordering::Relaxed);
+        log::error!("{}", violation);
+        if let Some(handler) = *self.handler.read() {
+            handler(violation);
+        }
+    }
+}
+
+impl Default for Cfi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global control-flow protection state
+static CFI: Cfi = Cfi::new();
+
+/// Get the control-flow protection state
+pub fn cfi() -> &'static Cfi {
+    &CFI
+}
+
+/// Report a violation trapped by the architecture code
+///
+/// Returns whether execution may continue: user violations are left to
+/// the handler to terminate the task, kernel violations are fatal.
+pub fn report_violation(violation: &CfiViolation) -> bool {
+    CFI.report(violation);
+    violation.user
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cp_error_code() {
+        assert_eq!(CfiViolationKind::from_cp_error_code(1), CfiViolationKind::ReturnMismatch);
+        assert_eq!(CfiViolationKind::from_cp_error_code(3), CfiViolationKind::MissingEndbranch);
+        // Bit 15 flags an enclave access and does not change the cause
+        assert_eq!(
+            CfiViolationKind::from_cp_error_code(0x8005),
+            CfiViolationKind::SupervisorToken
+        );
+        assert_eq!(CfiViolationKind::from_cp_error_code(9), CfiViolationKind::Unknown);
+        assert!(CfiViolationKind::ReturnMismatch.is_backward_edge());
+        assert!(!CfiViolationKind::MissingEndbranch.is_backward_edge());
+    }
+
+    #[test]
+    fn test_enable_limited_to_capability() {
+        let cfi = Cfi::new();
+        cfi.set_capability(CfiFeatures {
+            shadow_stack: true,
+            ..CfiFeatures::NONE
+        });
+
+        let all = CfiFeatures {
+            shadow_stack: true,
+            indirect_branch_tracking: true,
+            branch_target: true,
+            pointer_auth: true,
+        };
+        let enabled = cfi.set_user(all);
+        assert!(enabled.shadow_stack && !enabled.indirect_branch_tracking);
+        assert!(enabled.protects_returns() && !enabled.protects_branches());
+        assert!(cfi.kernel().is_empty());
+    }
+
+    #[test]
+    fn test_report() {
+        use core::sync::atomic::AtomicBool;
+        static CALLED: AtomicBool = AtomicBool::new(false);
+
+        let cfi = Cfi::new();
+        cfi.set_violation_handler(|v| {
+            CALLED.store(v.kind == CfiViolationKind::BranchTarget, Ordering::Relaxed)
+        });
+        cfi.report(&CfiViolation {
+            kind: CfiViolationKind::BranchTarget,
+            ip: 0x4000,
+            ssp: 0,
+            user: true,
+        });
+        assert_eq!(cfi.violation_count(), 1);
+        assert!(CALLED.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,754 @@
+//! # Hardware Description
+//!
+//! One tree of device nodes, whichever firmware described the machine.
+//! [`DeviceTree::from_fdt`] builds it from a flattened device tree; ACPI
+//! enumeration adds each namespace device with
+//! [`DeviceTree::add_acpi_device`], which decodes its `_CRS`. Either way a
+//! node carries what drivers ask about:
+//!
+//! - `compatible`: the DT compatible strings, or the ACPI `_HID` followed by
+//!   the `_CID`s (a `PRP0001` device gets them from its `_DSD` through
+//!   [`DeviceTree::set_property`])
+//! - resources: MMIO and I/O port ranges, in CPU addresses
+//! - interrupts: global interrupt numbers with their trigger mode
+//! - I2C connection of ACPI devices on an I2C bus ([`I2cConnection`]; DT
+//!   describes those as children of the controller, see
+//!   [`i2c`](crate::i2c))
+//!
+//! Property values use the device tree encoding (big-endian 32-bit cells,
+//! NUL-terminated strings) whatever their source.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use spin::Once;
+
+use crate::{HalError, HalResult};
+
+/// Index of a node in its [`DeviceTree`]
+pub type NodeId = usize;
+
+/// Firmware a node was described by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Flattened device tree
+    DeviceTree,
+    /// ACPI namespace
+    Acpi,
+}
+
+/// Address range a device decodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// Memory-mapped registers
+    Memory {
+        /// CPU physical address
+        base: u64,
+        /// Length in bytes
+        size: u64,
+    },
+    /// I/O ports
+    Io {
+        /// First port
+        base: u16,
+        /// Number of ports
+        len: u16,
+    },
+}
+
+/// Interrupt trigger mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Edge triggered
+    Edge,
+    /// Level triggered
+    Level,
+}
+
+/// Interrupt a device raises
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interrupt {
+    /// Global interrupt number (GSI, GIC INTID, PLIC source)
+    pub number: u32,
+    /// Trigger mode
+    pub trigger: Trigger,
+    /// Falling edge or low level
+    pub active_low: bool,
+}
+
+/// ACPI `I2cSerialBus` connection of a device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct I2cConnection {
+    /// Address on the bus
+    pub address: u16,
+    /// 10-bit addressing
+    pub ten_bit: bool,
+    /// Bus speed in Hz
+    pub speed_hz: u32,
+    /// ACPI path of the controller (`\_SB.I2C1`)
+    pub controller: String,
+}
+
+/// Resources decoded from a `_CRS` buffer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Crs {
+    /// Address ranges
+    pub resources: Vec<Resource>,
+    /// Interrupts
+    pub interrupts: Vec<Interrupt>,
+    /// First I2C connection
+    pub i2c: Option<I2cConnection>,
+}
+
+/// One device (or bus) of the machine
+#[derive(Debug, Clone)]
+pub struct DeviceNode {
+    /// Node name (`uart@9000000`, ACPI name segment `UAR1`)
+    pub name: String,
+    /// Firmware that described the node
+    pub source: Source,
+    /// Parent node; `None` for the root
+    pub parent: Option<NodeId>,
+    /// Child nodes, in firmware order
+    pub children: Vec<NodeId>,
+    /// Compatible identifiers, most specific first
+    pub compatible: Vec<String>,
+    /// Address ranges
+    pub resources: Vec<Resource>,
+    /// Interrupts
+    pub interrupts: Vec<Interrupt>,
+    /// I2C connection (ACPI)
+    pub i2c: Option<I2cConnection>,
+    /// Raw properties
+    pub properties: Vec<(String, Vec<u8>)>,
+}
+
+impl DeviceNode {
+    fn new(name: &str, source: Source, parent: Option<NodeId>) -> Self {
+        Self {
+            name: name.to_string(),
+            source,
+            parent,
+            children: Vec::new(),
+            compatible: Vec::new(),
+            resources: Vec::new(),
+            interrupts: Vec::new(),
+            i2c: None,
+            properties: Vec::new(),
+        }
+    }
+
+    /// Raw value of property `name`
+    pub fn property(&self, name: &str) -> Option<&[u8]> {
+        self.properties.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_slice())
+    }
+
+    /// Property `name` as a single cell
+    pub fn property_u32(&self, name: &str) -> Option<u32> {
+        self.property(name).and_then(|v| cell(v, 0))
+    }
+
+    /// Property `name` as a string
+    pub fn property_str(&self, name: &str) -> Option<&str> {
+        self.property(name).and_then(|v| strings(v).next())
+    }
+
+    /// Is the node compatible with `compatible`?
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.compatible.iter().any(|c| c == compatible)
+    }
+
+    /// Did firmware leave the device enabled (`status` absent or `okay`)?
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.property_str("status"), None | Some("okay" | "ok"))
+    }
+}
+
+/// The machine's devices
+#[derive(Debug, Clone)]
+pub struct DeviceTree {
+    nodes: Vec<DeviceNode>,
+}
+
+/// Structure block tokens
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+const FDT_END: u32 = 9;
+
+/// Size of the flattened device tree header
+const FDT_HEADER_LEN: usize = 40;
+
+impl DeviceTree {
+    /// Tree holding only a root node described by `source`
+    pub fn new(source: Source) -> Self {
+        Self { nodes: alloc::vec![DeviceNode::new("", source, None)] }
+    }
+
+    /// The root node
+    pub const fn root(&self) -> NodeId {
+        0
+    }
+
+    /// Node `id`
+    pub fn node(&self, id: NodeId) -> Option<&DeviceNode> {
+        self.nodes.get(id)
+    }
+
+    /// All nodes, parents before their children
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &DeviceNode)> {
+        self.nodes.iter().enumerate()
+    }
+
+    /// Add a child of `parent`
+    pub fn add_node(&mut self, parent: NodeId, name: &str, source: Source) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(DeviceNode::new(name, source, Some(parent)));
+        self.nodes[parent].children.push(id);
+        id
+    }
+
+    /// Set property `name` of node `id`; `compatible` also replaces the
+    /// node's compatible list
+    pub fn set_property(&mut self, id: NodeId, name: &str, value: &[u8]) {
+        let node = &mut self.nodes[id];
+        if name == "compatible" {
+            node.compatible = strings(value).map(String::from).collect();
+        }
+        match node.properties.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_vec(),
+            None => node.properties.push((name.to_string(), value.to_vec())),
+        }
+    }
+
+    /// Path of node `id`, such as `/soc/uart@9000000` or `/_SB_/PCI0/UAR1`
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut node = id;
+        while let Some(parent) = self.nodes[node].parent {
+            names.push(self.nodes[node].name.as_str());
+            node = parent;
+        }
+        if names.is_empty() {
+            return String::from("/");
+        }
+        names.iter().rev().fold(String::new(), |mut path, name| {
+            path.push('/');
+            path.push_str(name);
+            path
+        })
+    }
+
+    /// Node at `path`
+    pub fn find_path(&self, path: &str) -> Option<NodeId> {
+        path.split('/').filter(|s| !s.is_empty()).try_fold(self.root(), |node, name| {
+            self.nodes[node].children.iter().copied().find(|&c| self.nodes[c].name == name)
+        })
+    }
+
+    /// Enabled nodes compatible with `compatible`
+    pub fn find_compatible<'a>(&'a self, compatible: &'a str) -> impl Iterator<Item = NodeId> + 'a {
+        self.nodes()
+            .filter(move |(_, node)| node.is_compatible(compatible) && node.is_enabled())
+            .map(|(id, _)| id)
+    }
+
+    /// Node at ACPI path `path` (`\_SB.PCI0.I2C1`); short name segments
+    /// are padded with `_`
+    pub fn find_acpi_path(&self, path: &str) -> Option<NodeId> {
+        path.trim_start_matches('\\').split('.').filter(|s| !s.is_empty()).try_fold(self.root(), |node, segment| {
+            let matches = |name: &str| name.len() == 4 && name.starts_with(segment) && name[segment.len()..].bytes().all(|b| b == b'_');
+            self.nodes[node].children.iter().copied().find(|&c| matches(&self.nodes[c].name))
+        })
+    }
+
+    /// Node with phandle `phandle`
+    pub fn find_phandle(&self, phandle: u32) -> Option<NodeId> {
+        self.nodes()
+            .find(|(_, node)| node.property_u32("phandle").or_else(|| node.property_u32("linux,phandle")) == Some(phandle))
+            .map(|(id, _)| id)
+    }
+
+    // -------------------------------------------------------------------------
+    // Flattened device tree
+    // -------------------------------------------------------------------------
+
+    /// Build the tree from a flattened device tree blob
+    ///
+    /// `reg` becomes memory resources translated through the parents'
+    /// `ranges`; nodes behind a bus without `ranges` (I2C, SPI) keep only
+    /// the raw property. `interrupts` and `interrupts-extended` are decoded
+    /// with the interrupt parent's `#interrupt-cells`.
+    pub fn from_fdt(blob: &[u8]) -> HalResult<Self> {
+        let header = |field: usize| cell(blob, field).ok_or(HalError::InvalidParameter);
+        if header(0)? != FDT_MAGIC || blob.len() < FDT_HEADER_LEN || header(6)? > 17 {
+            return Err(HalError::InvalidParameter);
+        }
+        let total = (header(1)? as usize).min(blob.len());
+        let structs = blob.get(header(2)? as usize..total).ok_or(HalError::InvalidParameter)?;
+        let strings_block = blob.get(header(3)? as usize..total).ok_or(HalError::InvalidParameter)?;
+
+        let mut tree = Self::new(Source::DeviceTree);
+        let mut stack: Vec<NodeId> = Vec::new();
+        let mut pos = 0;
+        loop {
+            let token = cell(structs, pos / 4).ok_or(HalError::InvalidParameter)?;
+            pos += 4;
+            match token {
+                FDT_BEGIN_NODE => {
+                    let name = c_str(structs.get(pos..).ok_or(HalError::InvalidParameter)?)?;
+                    pos = align4(pos + name.len() + 1);
+                    let id = match stack.last() {
+                        Some(&parent) => tree.add_node(parent, name, Source::DeviceTree),
+                        None => tree.root(),
+                    };
+                    stack.push(id);
+                }
+                FDT_END_NODE => {
+                    stack.pop().ok_or(HalError::InvalidParameter)?;
+                }
+                FDT_PROP => {
+                    let len = cell(structs, pos / 4).ok_or(HalError::InvalidParameter)? as usize;
+                    let name_off = cell(structs, pos / 4 + 1).ok_or(HalError::InvalidParameter)? as usize;
+                    let value = structs.get(pos + 8..pos + 8 + len).ok_or(HalError::InvalidParameter)?;
+                    let name = c_str(strings_block.get(name_off..).ok_or(HalError::InvalidParameter)?)?;
+                    let node = *stack.last().ok_or(HalError::InvalidParameter)?;
+                    tree.set_property(node, name, value);
+                    pos = align4(pos + 8 + len);
+                }
+                FDT_NOP => {}
+                FDT_END if stack.is_empty() => break,
+                _ => return Err(HalError::InvalidParameter),
+            }
+        }
+
+        for id in 1..tree.nodes.len() {
+            tree.nodes[id].resources = tree.fdt_resources(id);
+            tree.nodes[id].interrupts = tree.fdt_interrupts(id);
+        }
+        Ok(tree)
+    }
+
+    /// `#address-cells` or `#size-cells` of node `id`
+    fn cell_count(&self, id: NodeId, name: &str, default: u32) -> usize {
+        self.nodes[id].property_u32(name).unwrap_or(default) as usize
+    }
+
+    /// Memory resources from `reg`
+    fn fdt_resources(&self, id: NodeId) -> Vec<Resource> {
+        let (Some(parent), Some(reg)) = (self.nodes[id].parent, self.nodes[id].property("reg")) else {
+            return Vec::new();
+        };
+        let address_cells = self.cell_count(parent, "#address-cells", 2);
+        let size_cells = self.cell_count(parent, "#size-cells", 1);
+        let entry = (address_cells + size_cells) * 4;
+        if entry == 0 {
+            return Vec::new();
+        }
+        reg.chunks_exact(entry)
+            .filter_map(|e| {
+                let base = self.translate(parent, read_cells(e, address_cells))?;
+                Some(Resource::Memory { base, size: read_cells(&e[address_cells * 4..], size_cells) })
+            })
+            .collect()
+    }
+
+    /// CPU address of `address` on the bus of node `bus`
+    fn translate(&self, mut bus: NodeId, mut address: u64) -> Option<u64> {
+        while let Some(parent) = self.nodes[bus].parent {
+            let ranges = self.nodes[bus].property("ranges")?;
+            if !ranges.is_empty() {
+                let child_cells = self.cell_count(bus, "#address-cells", 2);
+                let parent_cells = self.cell_count(parent, "#address-cells", 2);
+                let size_cells = self.cell_count(bus, "#size-cells", 1);
+                let entry = (child_cells + parent_cells + size_cells) * 4;
+                address = ranges.chunks_exact(entry).find_map(|r| {
+                    let child = read_cells(r, child_cells);
+                    let size = read_cells(&r[(child_cells + parent_cells) * 4..], size_cells);
+                    let offset = address.checked_sub(child).filter(|&o| o < size)?;
+                    Some(read_cells(&r[child_cells * 4..], parent_cells) + offset)
+                })?;
+            }
+            bus = parent;
+        }
+        Some(address)
+    }
+
+    /// Interrupt controller of node `id`, from the nearest `interrupt-parent`
+    fn interrupt_parent(&self, id: NodeId) -> Option<NodeId> {
+        let mut node = Some(id);
+        while let Some(n) = node {
+            if let Some(phandle) = self.nodes[n].property_u32("interrupt-parent") {
+                return self.find_phandle(phandle);
+            }
+            node = self.nodes[n].parent;
+        }
+        None
+    }
+
+    /// Interrupts from `interrupts-extended` or `interrupts`
+    fn fdt_interrupts(&self, id: NodeId) -> Vec<Interrupt> {
+        let node = &self.nodes[id];
+        let mut interrupts = Vec::new();
+        if let Some(extended) = node.property("interrupts-extended") {
+            let mut pos = 0;
+            while let Some(controller) = cell(extended, pos).and_then(|p| self.find_phandle(p)) {
+                let cells = self.cell_count(controller, "#interrupt-cells", 1);
+                let Some(specifier) = extended.get((pos + 1) * 4..(pos + 1 + cells) * 4) else {
+                    break;
+                };
+                interrupts.extend(self.decode_interrupt(controller, specifier));
+                pos += 1 + cells;
+            }
+        } else if let (Some(raw), Some(controller)) = (node.property("interrupts"), self.interrupt_parent(id)) {
+            let cells = self.cell_count(controller, "#interrupt-cells", 1).max(1);
+            interrupts.extend(raw.chunks_exact(cells * 4).filter_map(|s| self.decode_interrupt(controller, s)));
+        }
+        interrupts
+    }
+
+    /// Decode one interrupt specifier of `controller`
+    ///
+    /// Three-cell GIC specifiers are `<type number flags>` with SPIs
+    /// numbered from 32 and PPIs from 16; other controllers use
+    /// `<number [flags]>`.
+    fn decode_interrupt(&self, controller: NodeId, specifier: &[u8]) -> Option<Interrupt> {
+        let cells = specifier.len() / 4;
+        let gic = self.nodes[controller].compatible.iter().any(|c| c.contains("gic"));
+        let (number, flags) = if gic && cells == 3 {
+            let base = if cell(specifier, 0)? == 0 { 32 } else { 16 };
+            (cell(specifier, 1)? + base, cell(specifier, 2)?)
+        } else {
+            (cell(specifier, 0)?, if cells >= 2 { cell(specifier, 1)? } else { 0 })
+        };
+        // IRQ_TYPE_EDGE_RISING 1, _FALLING 2, _LEVEL_HIGH 4, _LEVEL_LOW 8
+        Some(Interrupt {
+            number,
+            trigger: if flags & 0x3 != 0 { Trigger::Edge } else { Trigger::Level },
+            active_low: flags & 0xa != 0,
+        })
+    }
+
+    // -------------------------------------------------------------------------
+    // ACPI
+    // -------------------------------------------------------------------------
+
+    /// Add ACPI namespace device `name` (its name segment) under `parent`
+    ///
+    /// `hid` and `cids` become the compatible list and `crs`, the buffer
+    /// `_CRS` returned, the resources, interrupts and I2C connection.
+    pub fn add_acpi_device(&mut self, parent: NodeId, name: &str, hid: &str, cids: &[&str], crs: &[u8]) -> HalResult<NodeId> {
+        let crs = parse_crs(crs)?;
+        let id = self.add_node(parent, name, Source::Acpi);
+        let node = &mut self.nodes[id];
+        node.compatible = core::iter::once(hid).chain(cids.iter().copied()).map(String::from).collect();
+        node.resources = crs.resources;
+        node.interrupts = crs.interrupts;
+        node.i2c = crs.i2c;
+        Ok(id)
+    }
+}
+
+/// Decode an ACPI resource template (`_CRS`)
+///
+/// Understands IRQ, I/O, fixed I/O, 32-bit memory, word/dword/qword address
+/// space, extended interrupt and I2C serial bus descriptors; others are
+/// skipped.
+pub fn parse_crs(crs: &[u8]) -> HalResult<Crs> {
+    let mut resources = Vec::new();
+    let mut interrupts = Vec::new();
+    let mut i2c = None;
+    let mut pos = 0;
+    while pos < crs.len() {
+        let tag = crs[pos];
+        let (kind, data) = if tag & 0x80 == 0 {
+            let len = (tag & 0x7) as usize;
+            pos += 1 + len;
+            ((tag >> 3) & 0xf, crs.get(pos - len..pos))
+        } else {
+            let len = crs.get(pos + 1..pos + 3).map_or(0, |l| u16::from_le_bytes([l[0], l[1]])) as usize;
+            pos += 3 + len;
+            (tag, crs.get(pos - len..pos))
+        };
+        let data = data.ok_or(HalError::InvalidParameter)?;
+        let le = |offset: usize, width: usize| -> HalResult<u64> {
+            let bytes = data.get(offset..offset + width).ok_or(HalError::InvalidParameter)?;
+            Ok(bytes.iter().rev().fold(0, |v, &b| (v << 8) | b as u64))
+        };
+
+        match kind {
+            // IRQ
+            0x04 => {
+                let info = data.get(2).copied().unwrap_or(0x01);
+                let mask = le(0, 2)?;
+                interrupts.extend((0..16).filter(|bit| mask & (1 << bit) != 0).map(|number| Interrupt {
+                    number,
+                    trigger: if info & 0x01 != 0 { Trigger::Edge } else { Trigger::Level },
+                    active_low: info & 0x08 != 0,
+                }));
+            }
+            // I/O, fixed I/O
+            0x08 => resources.push(Resource::Io { base: le(1, 2)? as u16, len: le(6, 1)? as u16 }),
+            0x09 => resources.push(Resource::Io { base: le(0, 2)? as u16 & 0x3ff, len: le(2, 1)? as u16 }),
+            // End tag
+            0x0f => break,
+            // 32-bit memory range, 32-bit fixed memory
+            0x85 => resources.push(Resource::Memory { base: le(1, 4)?, size: le(13, 4)? }),
+            0x86 => resources.push(Resource::Memory { base: le(1, 4)?, size: le(5, 4)? }),
+            // DWord, word, qword address space
+            0x87 | 0x88 | 0x8a => {
+                let width = match kind {
+                    0x87 => 4,
+                    0x88 => 2,
+                    _ => 8,
+                };
+                let minimum = le(3 + width, width)?;
+                let translation = le(3 + 3 * width, width)?;
+                let size = le(3 + 4 * width, width)?;
+                match data[0] {
+                    0 => resources.push(Resource::Memory { base: minimum.wrapping_add(translation), size }),
+                    1 => resources.push(Resource::Io { base: minimum as u16, len: size as u16 }),
+                    _ => {}
+                }
+            }
+            // Extended interrupt
+            0x89 => {
+                let flags = le(0, 1)?;
+                for i in 0..le(1, 1)? as usize {
+                    interrupts.push(Interrupt {
+                        number: le(2 + 4 * i, 4)? as u32,
+                        trigger: if flags & 0x02 != 0 { Trigger::Edge } else { Trigger::Level },
+                        active_low: flags & 0x04 != 0,
+                    });
+                }
+            }
+            // Serial bus connection, I2C type
+            0x8e if le(2, 1)? == 1 => {
+                let type_len = le(7, 2)? as usize;
+                let source = data.get(9 + type_len..).ok_or(HalError::InvalidParameter)?;
+                let controller = String::from(c_str(source)?);
+                i2c.get_or_insert(I2cConnection {
+                    address: le(13, 2)? as u16,
+                    ten_bit: le(4, 2)? & 0x1 != 0,
+                    speed_hz: le(9, 4)? as u32,
+                    controller,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(Crs { resources, interrupts, i2c })
+}
+
+/// Big-endian cell `index` of `bytes`
+fn cell(bytes: &[u8], index: usize) -> Option<u32> {
+    let b = bytes.get(index * 4..index * 4 + 4)?;
+    Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Number made of the first `cells` cells of `bytes`, keeping the low
+/// 64 bits (PCI addresses have a leading flags cell)
+fn read_cells(bytes: &[u8], cells: usize) -> u64 {
+    (0..cells).filter_map(|i| cell(bytes, i)).fold(0, |v, c| (v << 32) | c as u64)
+}
+
+/// Strings of a string-list property
+fn strings(value: &[u8]) -> impl Iterator<Item = &str> {
+    value.split(|&b| b == 0).filter(|s| !s.is_empty()).filter_map(|s| core::str::from_utf8(s).ok())
+}
+
+/// NUL-terminated string at the start of `bytes`
+fn c_str(bytes: &[u8]) -> HalResult<&str> {
+    let end = bytes.iter().position(|&b| b == 0).ok_or(HalError::InvalidParameter)?;
+    core::str::from_utf8(&bytes[..end]).map_err(|_| HalError::InvalidParameter)
+}
+
+const fn align4(pos: usize) -> usize {
+    (pos + 3) & !3
+}
+
+/// The machine's hardware description, once built
+static TREE: Once<DeviceTree> = Once::new();
+
+/// Keep `tree` as the machine's hardware description; later calls keep the
+/// first
+pub fn init(tree: DeviceTree) -> &'static DeviceTree {
+    let mut first = false;
+    let tree = TREE.call_once(|| {
+        first = true;
+        tree
+    });
+    if first {
+        let source = tree.node(tree.root()).map(|root| root.source);
+        log::info!("Devices: {} nodes described by {:?}", tree.nodes.len() - 1, source);
+    }
+    tree
+}
+
+/// Build the description from the flattened device tree at `blob` and
+/// [`init`] from it
+///
+/// # Safety
+///
+/// `blob` must point to a readable flattened device tree whose header's
+/// total size is valid.
+pub unsafe fn init_from_fdt(blob: *const u8) -> HalResult<&'static DeviceTree> {
+    // SAFETY: the header is readable, per the caller
+    let header = unsafe { core::slice::from_raw_parts(blob, FDT_HEADER_LEN) };
+    let total = cell(header, 1).ok_or(HalError::InvalidParameter)? as usize;
+    // SAFETY: the whole blob is readable, per the caller
+    let blob = unsafe { core::slice::from_raw_parts(blob, total.max(FDT_HEADER_LEN)) };
+    Ok(init(DeviceTree::from_fdt(blob)?))
+}
+
+/// The machine's hardware description, if firmware provided one
+pub fn get() -> Option<&'static DeviceTree> {
+    TREE.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal flattened device tree writer
+    #[derive(Default)]
+    struct Fdt {
+        structs: Vec<u8>,
+        strings: Vec<u8>,
+    }
+
+    impl Fdt {
+        fn token(&mut self, token: u32) -> &mut Self {
+            self.structs.extend_from_slice(&token.to_be_bytes());
+            self
+        }
+
+        fn begin(&mut self, name: &str) -> &mut Self {
+            self.token(FDT_BEGIN_NODE);
+            self.structs.extend_from_slice(name.as_bytes());
+            self.structs.push(0);
+            self.structs.resize(align4(self.structs.len()), 0);
+            self
+        }
+
+        fn prop(&mut self, name: &str, value: &[u8]) -> &mut Self {
+            let name_off = self.strings.len() as u32;
+            self.token(FDT_PROP).token(value.len() as u32).token(name_off);
+            self.strings.extend_from_slice(name.as_bytes());
+            self.strings.push(0);
+            self.structs.extend_from_slice(value);
+            self.structs.resize(align4(self.structs.len()), 0);
+            self
+        }
+
+        fn cells(&mut self, name: &str, cells: &[u32]) -> &mut Self {
+            let value: Vec<u8> = cells.iter().flat_map(|c| c.to_be_bytes()).collect();
+            self.prop(name, &value)
+        }
+
+        fn finish(&mut self) -> Vec<u8> {
+            self.token(FDT_END);
+            let structs = FDT_HEADER_LEN as u32;
+            let strings = structs + self.structs.len() as u32;
+            let total = strings + self.strings.len() as u32;
+            let header = [FDT_MAGIC, total, structs, strings, 0, 17, 16, 0, self.strings.len() as u32, self.structs.len() as u32];
+            let mut blob: Vec<u8> = header.iter().flat_map(|c| c.to_be_bytes()).collect();
+            blob.extend_from_slice(&self.structs);
+            blob.extend_from_slice(&self.strings);
+            blob
+        }
+    }
+
+    #[test]
+    fn test_fdt() {
+        let blob = Fdt::default()
+            .begin("")
+            .cells("#address-cells", &[2])
+            .cells("#size-cells", &[2])
+            .cells("interrupt-parent", &[1])
+            .begin("intc@8000000")
+            .prop("compatible", b"arm,cortex-a15-gic\0")
+            .cells("#interrupt-cells", &[3])
+            .cells("phandle", &[1])
+            .cells("reg", &[0, 0x0800_0000, 0, 0x1_0000, 0, 0x0801_0000, 0, 0x1_0000])
+            .token(FDT_END_NODE)
+            .begin("soc")
+            .cells("#address-cells", &[1])
+            .cells("#size-cells", &[1])
+            .cells("ranges", &[0, 0, 0x4000_0000, 0x1000_0000])
+            .begin("uart@9000000")
+            .prop("compatible", b"arm,pl011\0arm,primecell\0")
+            .cells("reg", &[0x0900_0000, 0x1000])
+            .cells("interrupts", &[0, 1, 4])
+            .token(FDT_END_NODE)
+            .begin("i2c@a000000")
+            .prop("status", b"disabled\0")
+            .prop("compatible", b"arm,pl011\0")
+            .token(FDT_END_NODE)
+            .token(FDT_END_NODE)
+            .token(FDT_END_NODE)
+            .finish();
+
+        let tree = DeviceTree::from_fdt(&blob).unwrap();
+        let gic = tree.find_path("/intc@8000000").unwrap();
+        assert_eq!(tree.node(gic).unwrap().resources[1], Resource::Memory { base: 0x0801_0000, size: 0x1_0000 });
+
+        let uarts: Vec<NodeId> = tree.find_compatible("arm,primecell").collect();
+        assert_eq!(uarts.len(), 1);
+        assert_eq!(tree.path(uarts[0]), "/soc/uart@9000000");
+        let uart = tree.node(uarts[0]).unwrap();
+        assert_eq!(uart.resources, [Resource::Memory { base: 0x4900_0000, size: 0x1000 }]);
+        assert_eq!(uart.interrupts, [Interrupt { number: 33, trigger: Trigger::Level, active_low: false }]);
+        assert_eq!(tree.find_compatible("arm,pl011").count(), 1);
+
+        assert_eq!(DeviceTree::from_fdt(&blob[..40]).unwrap_err(), HalError::InvalidParameter);
+    }
+
+    #[test]
+    fn test_acpi() {
+        let crs = [
+            0x47, 0x01, 0xf8, 0x03, 0xf8, 0x03, 0x01, 0x08, // IO 0x3f8-0x3ff
+            0x22, 0x10, 0x00, // IRQ 4, edge, active high
+            0x86, 0x09, 0x00, 0x01, 0x00, 0x00, 0xd1, 0xfe, 0x00, 0x10, 0x00, 0x00, // Memory32Fixed 0xfed10000
+            0x89, 0x06, 0x00, 0x01, 0x01, 0x2a, 0x00, 0x00, 0x00, // Interrupt 42, level, active high
+            0x79, 0x00, // End tag
+        ];
+        let mut tree = DeviceTree::new(Source::Acpi);
+        let sb = tree.add_node(tree.root(), "_SB_", Source::Acpi);
+        let uart = tree.add_acpi_device(sb, "UAR1", "PNP0501", &["PNP0500"], &crs).unwrap();
+
+        let node = tree.node(uart).unwrap();
+        assert_eq!(tree.find_path("/_SB_/UAR1"), Some(uart));
+        assert!(node.is_compatible("PNP0500") && node.is_enabled());
+        assert_eq!(
+            node.resources,
+            [Resource::Io { base: 0x3f8, len: 8 }, Resource::Memory { base: 0xfed1_0000, size: 0x1000 }]
+        );
+        assert_eq!(
+            node.interrupts,
+            [
+                Interrupt { number: 4, trigger: Trigger::Edge, active_low: false },
+                Interrupt { number: 42, trigger: Trigger::Level, active_low: false },
+            ]
+        );
+
+        // PRP0001 devices take DT compatible strings from _DSD
+        let prp = tree.add_acpi_device(sb, "LED0", "PRP0001", &[], &[0x79, 0x00]).unwrap();
+        tree.set_property(prp, "compatible", b"gpio-leds\0");
+        assert_eq!(tree.find_compatible("gpio-leds").collect::<Vec<_>>(), [prp]);
+
+        assert_eq!(parse_crs(&[0x86, 0x09, 0x00, 0x01]), Err(HalError::InvalidParameter));
+
+        // I2cSerialBusV2 (0x1a, 400 kHz, "\_SB.I2C1")
+        let mut i2c = alloc::vec![0x8e, 0x19, 0x00, 0x02, 0x00, 0x01, 0x02, 0x00, 0x00, 0x01, 0x06, 0x00];
+        i2c.extend_from_slice(&[0x80, 0x1a, 0x06, 0x00, 0x1a, 0x00]);
+        i2c.extend_from_slice(b"\\_SB.I2C1\0");
+        i2c.extend_from_slice(&[0x79, 0x00]);
+        let i2c1 = tree.add_acpi_device(sb, "I2C1", "INT33C3", &[], &[0x79, 0x00]).unwrap();
+        let touch = tree.add_acpi_device(i2c1, "TPD0", "ELAN0001", &["PNP0C50"], &i2c).unwrap();
+        let connection = tree.node(touch).unwrap().i2c.clone().unwrap();
+        assert_eq!((connection.address, connection.speed_hz, connection.ten_bit), (0x1a, 400_000, false));
+        assert_eq!(tree.find_acpi_path(&connection.controller), Some(i2c1));
+    }
+}
@@ -0,0 +1,475 @@
+//! # SMBIOS / DMI
+//!
+//! Firmware describes the machine (vendor, model, board, BIOS) in SMBIOS
+//! structures. The bootloader hands over the entry point address;
+//! [`init_from_entry_point`] parses the structure table once and keeps the
+//! identification strings, which [`field`] returns for the rest of the
+//! kernel's life. [`quirks`](crate::quirks) matches them against its table
+//! at that point.
+//!
+//! Both entry point formats are understood: the 32-bit `_SM_` (SMBIOS 2.x)
+//! and the 64-bit `_SM3_` (SMBIOS 3.x).
+
+use alloc::string::{String, ToString};
+use core::fmt::Write;
+use spin::Once;
+
+/// Anchor of a 32-bit (SMBIOS 2.x) entry point
+const ANCHOR_32: &[u8] = b"_SM_";
+/// Anchor of the intermediate entry point inside a 32-bit one
+const ANCHOR_DMI: &[u8] = b"_DMI_";
+/// Anchor of a 64-bit (SMBIOS 3.x) entry point
+const ANCHOR_64: &[u8] = b"_SM3_";
+
+/// Largest entry point structure
+pub const ENTRY_POINT_MAX_LEN: usize = 0x20;
+
+/// Structure type that ends the table
+const END_OF_TABLE: u8 = 127;
+
+/// Location of the structure table, from the entry point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryPoint {
+    /// SMBIOS version (major, minor)
+    pub version: (u8, u8),
+    /// Physical address of the structure table
+    pub table_address: u64,
+    /// Table length (SMBIOS 3.x: maximum length)
+    pub table_length: u32,
+}
+
+fn checksum_ok(bytes: &[u8]) -> bool {
+    bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) == 0
+}
+
+fn le16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn le32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+impl EntryPoint {
+    /// Parse and checksum an entry point structure
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(ANCHOR_64) {
+            let len = *bytes.get(6)? as usize;
+            let entry = bytes.get(..len).filter(|e| e.len() >= 0x18 && checksum_ok(e))?;
+            return Some(Self {
+                version: (entry[7], entry[8]),
+                table_address: u64::from_le_bytes(entry[0x10..0x18].try_into().unwrap()),
+                table_length: le32(entry, 0x0C),
+            });
+        }
+        if bytes.starts_with(ANCHOR_32) {
+            let len = *bytes.get(5)? as usize;
+            let entry = bytes.get(..len).filter(|e| e.len() >= 0x1F && checksum_ok(e))?;
+            let intermediate = &entry[0x10..0x1F];
+            if !intermediate.starts_with(ANCHOR_DMI) || !checksum_ok(intermediate) {
+                return None;
+            }
+            return Some(Self {
+                version: (entry[6], entry[7]),
+                table_address: le32(entry, 0x18) as u64,
+                table_length: le16(entry, 0x16) as u32,
+            });
+        }
+        None
+    }
+}
+
+/// One SMBIOS structure
+#[derive(Debug, Clone, Copy)]
+pub struct Structure<'a> {
+    /// Structure type
+    pub kind: u8,
+    /// Structure handle
+    pub handle: u16,
+    /// Formatted area, header included
+    pub formatted: &'a [u8],
+    /// String set: NUL-terminated strings
+    strings: &'a [u8],
+}
+
+impl<'a> Structure<'a> {
+    /// Byte at `offset` of the formatted area
+    pub fn byte(&self, offset: usize) -> Option<u8> {
+        self.formatted.get(offset).copied()
+    }
+
+    /// String referenced by the string number at `offset`
+    ///
+    /// Number 0 means no string; trailing blanks are dropped, and empty
+    /// strings read as none.
+    pub fn string(&self, offset: usize) -> Option<&'a str> {
+        let number = self.byte(offset)? as usize;
+        let raw = self.strings.split(|b| *b == 0).nth(number.checked_sub(1)?)?;
+        let text = core::str::from_utf8(raw).ok()?.trim_end();
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+/// Iterator over the structures of a table
+pub struct Structures<'a> {
+    table: &'a [u8],
+}
+
+/// Structures of `table`, up to the end-of-table structure
+pub fn structures(table: &[u8]) -> Structures<'_> {
+    Structures { table }
+}
+
+impl<'a> Iterator for Structures<'a> {
+    type Item = Structure<'a>;
+
+    fn next(&mut self) -> Option<Structure<'a>> {
+        let table = self.table;
+        let len = *table.get(1)? as usize;
+        if len < 4 || table.len() < len {
+            self.table = &[];
+            return None;
+        }
+        // The string set ends with two NULs, even when empty
+        let end = match table[len..].windows(2).position(|w| w == [0, 0]) {
+            Some(end) => len + end + 2,
+            None => {
+                self.table = &[];
+                return None;
+            }
+        };
+        let structure = Structure {
+            kind: table[0],
+            handle: le16(table, 2),
+            formatted: &table[..len],
+            strings: &table[len..end],
+        };
+        self.table = if structure.kind == END_OF_TABLE { &[] } else { &table[end..] };
+        Some(structure)
+    }
+}
+
+/// Identification fields, named as in `/sys/class/dmi/id`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmiField {
+    /// BIOS vendor
+    BiosVendor,
+    /// BIOS version
+    BiosVersion,
+    /// BIOS release date
+    BiosDate,
+    /// System manufacturer
+    SysVendor,
+    /// System product name
+    ProductName,
+    /// System version
+    ProductVersion,
+    /// System serial number
+    ProductSerial,
+    /// System UUID
+    ProductUuid,
+    /// System SKU
+    ProductSku,
+    /// System family
+    ProductFamily,
+    /// Baseboard manufacturer
+    BoardVendor,
+    /// Baseboard product
+    BoardName,
+    /// Baseboard version
+    BoardVersion,
+    /// Baseboard serial number
+    BoardSerial,
+    /// Baseboard asset tag
+    BoardAssetTag,
+    /// Chassis manufacturer
+    ChassisVendor,
+    /// Chassis type (SMBIOS enumeration, in decimal)
+    ChassisType,
+    /// Chassis version
+    ChassisVersion,
+    /// Chassis serial number
+    ChassisSerial,
+    /// Chassis asset tag
+    ChassisAssetTag,
+}
+
+impl DmiField {
+    /// Every field
+    pub const ALL: [DmiField; 20] = [
+        Self::BiosVendor,
+        Self::BiosVersion,
+        Self::BiosDate,
+        Self::SysVendor,
+        Self::ProductName,
+        Self::ProductVersion,
+        Self::ProductSerial,
+        Self::ProductUuid,
+        Self::ProductSku,
+        Self::ProductFamily,
+        Self::BoardVendor,
+        Self::BoardName,
+        Self::BoardVersion,
+        Self::BoardSerial,
+        Self::BoardAssetTag,
+        Self::ChassisVendor,
+        Self::ChassisType,
+        Self::ChassisVersion,
+        Self::ChassisSerial,
+        Self::ChassisAssetTag,
+    ];
+
+    /// sysfs name
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::BiosVendor => "bios_vendor",
+            Self::BiosVersion => "bios_version",
+            Self::BiosDate => "bios_date",
+            Self::SysVendor => "sys_vendor",
+            Self::ProductName => "product_name",
+            Self::ProductVersion => "product_version",
+            Self::ProductSerial => "product_serial",
+            Self::ProductUuid => "product_uuid",
+            Self::ProductSku => "product_sku",
+            Self::ProductFamily => "product_family",
+            Self::BoardVendor => "board_vendor",
+            Self::BoardName => "board_name",
+            Self::BoardVersion => "board_version",
+            Self::BoardSerial => "board_serial",
+            Self::BoardAssetTag => "board_asset_tag",
+            Self::ChassisVendor => "chassis_vendor",
+            Self::ChassisType => "chassis_type",
+            Self::ChassisVersion => "chassis_version",
+            Self::ChassisSerial => "chassis_serial",
+            Self::ChassisAssetTag => "chassis_asset_tag",
+        }
+    }
+
+    /// Field named `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|f| f.name() == name)
+    }
+
+    /// Structure type holding the field, and its offset there
+    const fn location(self) -> (u8, usize) {
+        match self {
+            Self::BiosVendor => (0, 0x04),
+            Self::BiosVersion => (0, 0x05),
+            Self::BiosDate => (0, 0x08),
+            Self::SysVendor => (1, 0x04),
+            Self::ProductName => (1, 0x05),
+            Self::ProductVersion => (1, 0x06),
+            Self::ProductSerial => (1, 0x07),
+            Self::ProductUuid => (1, 0x08),
+            Self::ProductSku => (1, 0x19),
+            Self::ProductFamily => (1, 0x1A),
+            Self::BoardVendor => (2, 0x04),
+            Self::BoardName => (2, 0x05),
+            Self::BoardVersion => (2, 0x06),
+            Self::BoardSerial => (2, 0x07),
+            Self::BoardAssetTag => (2, 0x08),
+            Self::ChassisVendor => (3, 0x04),
+            Self::ChassisType => (3, 0x05),
+            Self::ChassisVersion => (3, 0x06),
+            Self::ChassisSerial => (3, 0x07),
+            Self::ChassisAssetTag => (3, 0x08),
+        }
+    }
+}
+
+/// Machine identification, parsed from the SMBIOS table
+#[derive(Debug, Clone, Default)]
+pub struct Dmi {
+    /// SMBIOS version (major, minor)
+    pub version: (u8, u8),
+    fields: [Option<String>; DmiField::ALL.len()],
+}
+
+impl Dmi {
+    /// Collect the identification fields of `table`; the first structure of
+    /// each type wins
+    pub fn from_table(version: (u8, u8), table: &[u8]) -> Self {
+        let mut dmi = Self { version, ..Self::default() };
+        for (index, field) in DmiField::ALL.iter().enumerate() {
+            let (kind, offset) = field.location();
+            let Some(structure) = structures(table).find(|s| s.kind == kind) else {
+                continue;
+            };
+            dmi.fields[index] = match field {
+                DmiField::ChassisType => structure.byte(offset).map(|t| (t & 0x7F).to_string()),
+                DmiField::ProductUuid => structure.formatted.get(offset..offset + 16).and_then(|uuid| format_uuid(uuid, version)),
+                _ => structure.string(offset).map(String::from),
+            };
+        }
+        dmi
+    }
+
+    /// Value of `field`, if firmware filled it in
+    pub fn get(&self, field: DmiField) -> Option<&str> {
+        let index = DmiField::ALL.iter().position(|f| *f == field)?;
+        self.fields[index].as_deref()
+    }
+}
+
+/// Text form of a system UUID; `None` if firmware left it unset
+///
+/// From SMBIOS 2.6 on, the first three fields are little-endian.
+fn format_uuid(uuid: &[u8], version: (u8, u8)) -> Option<String> {
+    if uuid.iter().all(|b| *b == 0) || uuid.iter().all(|b| *b == 0xFF) {
+        return None;
+    }
+    let mut bytes: [u8; 16] = uuid.try_into().ok()?;
+    if version >= (2, 6) {
+        bytes[..4].reverse();
+        bytes[4..6].reverse();
+        bytes[6..8].reverse();
+    }
+    let mut text = String::with_capacity(36);
+    for (index, byte) in bytes.iter().enumerate() {
+        if matches!(index, 4 | 6 | 8 | 10) {
+            text.push('-');
+        }
+        let _ = write!(text, "{:02x}", byte);
+    }
+    Some(text)
+}
+
+/// The machine's identification, once parsed
+static DMI: Once<Dmi> = Once::new();
+
+/// Keep `dmi` as the machine's identification and apply matching quirks;
+/// later calls keep the first
+pub fn init(dmi: Dmi) -> &'static Dmi {
+    let mut first = false;
+    let dmi = DMI.call_once(|| {
+        first = true;
+        dmi
+    });
+    if first {
+        log::info!(
+            "DMI: SMBIOS {}.{}: {} {}, BIOS {} {}",
+            dmi.version.0,
+            dmi.version.1,
+            dmi.get(DmiField::SysVendor).unwrap_or("?"),
+            dmi.get(DmiField::ProductName).unwrap_or("?"),
+            dmi.get(DmiField::BiosVersion).unwrap_or("?"),
+            dmi.get(DmiField::BiosDate).unwrap_or("?")
+        );
+        crate::quirks::apply(dmi);
+    }
+    dmi
+}
+
+/// Parse the SMBIOS table behind the entry point at physical address
+/// `entry_point` and [`init`] from it
+///
+/// Returns `None` if the entry point is invalid.
+///
+/// # Safety
+///
+/// `phys_to_virt` must return a readable mapping of the physical address
+/// it is given, valid for the entry point structure and for the whole
+/// structure table.
+pub unsafe fn init_from_entry_point(entry_point: u64, phys_to_virt: impl Fn(u64) -> *const u8) -> Option<&'static Dmi> {
+    // SAFETY: the entry point mapping is readable, per the caller
+    let entry = unsafe { core::slice::from_raw_parts(phys_to_virt(entry_point), ENTRY_POINT_MAX_LEN) };
+    let entry = EntryPoint::parse(entry)?;
+    // SAFETY: the table mapping is readable, per the caller
+    let table = unsafe { core::slice::from_raw_parts(phys_to_virt(entry.table_address), entry.table_length as usize) };
+    Some(init(Dmi::from_table(entry.version, table)))
+}
+
+/// The machine's identification, if SMBIOS was found
+pub fn get() -> Option<&'static Dmi> {
+    DMI.get()
+}
+
+/// Value of `field`, if SMBIOS was found and firmware filled it in
+pub fn field(field: DmiField) -> Option<&'static str> {
+    get()?.get(field)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// Append a structure with `formatted` (after the header) and `strings`
+    pub(crate) fn push_structure(table: &mut Vec<u8>, kind: u8, formatted: &[u8], strings: &[&str]) {
+        table.extend_from_slice(&[kind, 4 + formatted.len() as u8, 0, 0]);
+        table.extend_from_slice(formatted);
+        for string in strings {
+            table.extend_from_slice(string.as_bytes());
+            table.push(0);
+        }
+        if strings.is_empty() {
+            table.push(0);
+        }
+        table.push(0);
+    }
+
+    /// A table for a Dell XPS 15 9550
+    pub(crate) fn xps_table() -> Vec<u8> {
+        let mut table = Vec::new();
+        push_structure(&mut table, 0, &[1, 2, 0, 0, 3], &["Dell Inc.", "1.2.3", "09/01/2016"]);
+        let mut system = alloc::vec![1, 2, 0, 0];
+        system.extend_from_slice(&[0x44, 0x33, 0x22, 0x11, 0x66, 0x55, 0x88, 0x77, 1, 2, 3, 4, 5, 6, 7, 8]);
+        system.extend_from_slice(&[6, 0, 0]);
+        push_structure(&mut table, 1, &system, &["Dell Inc.", "XPS 15 9550   "]);
+        push_structure(&mut table, 3, &[1, 0x89], &["Dell Inc."]);
+        push_structure(&mut table, END_OF_TABLE, &[], &[]);
+        table
+    }
+
+    #[test]
+    fn test_entry_points() {
+        let mut smbios3 = alloc::vec![0u8; 0x18];
+        smbios3[..5].copy_from_slice(ANCHOR_64);
+        smbios3[6] = 0x18;
+        smbios3[7] = 3;
+        smbios3[8] = 2;
+        smbios3[0x0C..0x10].copy_from_slice(&0x1234u32.to_le_bytes());
+        smbios3[0x10..0x18].copy_from_slice(&0x7FF0_0000u64.to_le_bytes());
+        smbios3[5] = 0u8.wrapping_sub(smbios3.iter().fold(0u8, |s, b| s.wrapping_add(*b)));
+        assert_eq!(
+            EntryPoint::parse(&smbios3),
+            Some(EntryPoint { version: (3, 2), table_address: 0x7FF0_0000, table_length: 0x1234 })
+        );
+        smbios3[8] = 3;
+        assert_eq!(EntryPoint::parse(&smbios3), None);
+
+        let mut smbios2 = alloc::vec![0u8; 0x1F];
+        smbios2[..4].copy_from_slice(ANCHOR_32);
+        smbios2[5] = 0x1F;
+        smbios2[6] = 2;
+        smbios2[7] = 8;
+        smbios2[0x10..0x15].copy_from_slice(ANCHOR_DMI);
+        smbios2[0x16..0x18].copy_from_slice(&0x400u16.to_le_bytes());
+        smbios2[0x18..0x1C].copy_from_slice(&0xF0000u32.to_le_bytes());
+        smbios2[0x15] = 0u8.wrapping_sub(smbios2[0x10..].iter().fold(0u8, |s, b| s.wrapping_add(*b)));
+        smbios2[4] = 0u8.wrapping_sub(smbios2.iter().fold(0u8, |s, b| s.wrapping_add(*b)));
+        assert_eq!(
+            EntryPoint::parse(&smbios2),
+            Some(EntryPoint { version: (2, 8), table_address: 0xF0000, table_length: 0x400 })
+        );
+    }
+
+    #[test]
+    fn test_identification() {
+        let table = xps_table();
+        assert_eq!(structures(&table).count(), 4);
+
+        let dmi = Dmi::from_table((3, 0), &table);
+        assert_eq!(dmi.get(DmiField::BiosVendor), Some("Dell Inc."));
+        assert_eq!(dmi.get(DmiField::BiosDate), Some("09/01/2016"));
+        assert_eq!(dmi.get(DmiField::ProductName), Some("XPS 15 9550"));
+        assert_eq!(dmi.get(DmiField::ProductVersion), None);
+        assert_eq!(dmi.get(DmiField::ProductUuid), Some("11223344-5566-7788-0102-030405060708"));
+        assert_eq!(dmi.get(DmiField::ChassisType), Some("9"));
+        assert_eq!(dmi.get(DmiField::BoardName), None);
+        assert_eq!(DmiField::from_name("chassis_type"), Some(DmiField::ChassisType));
+
+        // Truncated tables end the walk instead of reading past them
+        assert_eq!(structures(&table[..table.len() - 1]).count(), 3);
+    }
+}
@@ -0,0 +1,391 @@
+//! # GPIO
+//!
+//! General purpose I/O lines. Each controller is a [`GpioController`]
+//! numbering its lines from 0; consumers [`request`] a line and get a
+//! [`GpioLine`], which applies the line's polarity so that `true` always
+//! means asserted.
+//!
+//! Lines named in the hardware description (`reset-gpios = <&gpio0 3
+//! GPIO_ACTIVE_LOW>`) are requested with [`request_named`] once their
+//! controller is registered with its node.
+//!
+//! Pins shared between GPIO and other functions go through the board's
+//! [`PinMux`], if one is set: requesting a line asks it to route the pin
+//! to the GPIO function.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use spin::{Mutex, RwLock};
+
+use crate::devtree::{DeviceTree, NodeId};
+use crate::{HalError, HalResult};
+
+/// Pull resistor setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pull {
+    /// No pull
+    None,
+    /// Pull up
+    Up,
+    /// Pull down
+    Down,
+}
+
+/// A GPIO controller
+///
+/// Values are physical levels (`true` is high); offsets run from 0 to
+/// [`ngpio`](GpioController::ngpio) - 1 and are checked by the caller.
+pub trait GpioController: Send + Sync {
+    /// Controller name
+    fn name(&self) -> &str;
+
+    /// Number of lines
+    fn ngpio(&self) -> u32;
+
+    /// Make line `offset` an input
+    fn direction_input(&self, offset: u32) -> HalResult<()>;
+
+    /// Make line `offset` an output driving `high`
+    fn direction_output(&self, offset: u32, high: bool) -> HalResult<()>;
+
+    /// Level of line `offset`
+    fn get(&self, offset: u32) -> HalResult<bool>;
+
+    /// Drive output line `offset`
+    fn set(&self, offset: u32, high: bool) -> HalResult<()>;
+
+    /// Set the pull resistor of line `offset`
+    fn set_pull(&self, _offset: u32, _pull: Pull) -> HalResult<()> {
+        Err(HalError::NotSupported)
+    }
+}
+
+/// Board pin controller hooks
+pub trait PinMux: Send + Sync {
+    /// Route line `offset` of `controller` to the GPIO function
+    fn request_gpio(&self, controller: &str, offset: u32) -> HalResult<()>;
+
+    /// Line `offset` of `controller` was released
+    fn free_gpio(&self, _controller: &str, _offset: u32) {}
+
+    /// Select `function` (`"i2c0"`, `"uart1"`) for pin group `group`
+    fn set_function(&self, group: &str, function: &str) -> HalResult<()>;
+}
+
+// =============================================================================
+// Registry
+// =============================================================================
+
+/// A registered controller
+struct Registered {
+    controller: &'static dyn GpioController,
+    node: Option<NodeId>,
+}
+
+/// Registered controllers, in registration order
+static CONTROLLERS: RwLock<Vec<Registered>> = RwLock::new(Vec::new());
+
+/// Requested lines, by controller name and offset
+static REQUESTED: Mutex<Vec<(String, u32)>> = Mutex::new(Vec::new());
+
+/// Board pin controller
+static PINMUX: RwLock<Option<&'static dyn PinMux>> = RwLock::new(None);
+
+/// Register a controller, described by `node` if it comes from the
+/// hardware description; names must be unique
+pub fn register(controller: &'static dyn GpioController, node: Option<NodeId>) -> HalResult<()> {
+    let mut controllers = CONTROLLERS.write();
+    if controllers.iter().any(|r| r.controller.name() == controller.name()) {
+        return Err(HalError::ResourceBusy);
+    }
+    controllers.push(Registered { controller, node });
+    Ok(())
+}
+
+/// All registered controllers
+pub fn controllers() -> Vec<&'static dyn GpioController> {
+    CONTROLLERS.read().iter().map(|r| r.controller).collect()
+}
+
+/// Controller named `name`
+pub fn find(name: &str) -> Option<&'static dyn GpioController> {
+    CONTROLLERS.read().iter().find(|r| r.controller.name() == name).map(|r| r.controller)
+}
+
+/// Controller registered for node `node`
+pub fn find_node(node: NodeId) -> Option<&'static dyn GpioController> {
+    CONTROLLERS.read().iter().find(|r| r.node == Some(node)).map(|r| r.controller)
+}
+
+/// Set the board pin controller
+pub fn set_pinmux(pinmux: &'static dyn PinMux) {
+    *PINMUX.write() = Some(pinmux);
+}
+
+/// The board pin controller, if any
+pub fn pinmux() -> Option<&'static dyn PinMux> {
+    *PINMUX.read()
+}
+
+// =============================================================================
+// Consumer API
+// =============================================================================
+
+/// A requested line; released when dropped
+pub struct GpioLine {
+    controller: &'static dyn GpioController,
+    offset: u32,
+    active_low: bool,
+}
+
+impl GpioLine {
+    /// Controller the line belongs to
+    pub fn controller(&self) -> &'static dyn GpioController {
+        self.controller
+    }
+
+    /// Line offset on its controller
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Is the line asserted when low?
+    pub fn is_active_low(&self) -> bool {
+        self.active_low
+    }
+
+    /// Make the line an input
+    pub fn set_input(&self) -> HalResult<()> {
+        self.controller.direction_input(self.offset)
+    }
+
+    /// Make the line an output, asserted or not
+    pub fn set_output(&self, asserted: bool) -> HalResult<()> {
+        self.controller.direction_output(self.offset, asserted != self.active_low)
+    }
+
+    /// Is the line asserted?
+    pub fn is_asserted(&self) -> HalResult<bool> {
+        Ok(self.controller.get(self.offset)? != self.active_low)
+    }
+
+    /// Assert or deassert the output
+    pub fn set(&self, asserted: bool) -> HalResult<()> {
+        self.controller.set(self.offset, asserted != self.active_low)
+    }
+
+    /// Set the pull resistor
+    pub fn set_pull(&self, pull: Pull) -> HalResult<()> {
+        self.controller.set_pull(self.offset, pull)
+    }
+}
+
+impl Drop for GpioLine {
+    fn drop(&mut self) {
+        let name = self.controller.name();
+        REQUESTED.lock().retain(|(c, o)| !(c == name && *o == self.offset));
+        if let Some(pinmux) = pinmux() {
+            pinmux.free_gpio(name, self.offset);
+        }
+    }
+}
+
+/// Request line `offset` of controller `controller`
+///
+/// Fails with `ResourceBusy` if the line is already requested.
+pub fn request(controller: &str, offset: u32, active_low: bool) -> HalResult<GpioLine> {
+    let controller = find(controller).ok_or(HalError::InvalidParameter)?;
+    request_line(controller, offset, active_low)
+}
+
+fn request_line(controller: &'static dyn GpioController, offset: u32, active_low: bool) -> HalResult<GpioLine> {
+    if offset >= controller.ngpio() {
+        return Err(HalError::InvalidParameter);
+    }
+    let mut requested = REQUESTED.lock();
+    if requested.iter().any(|(c, o)| c == controller.name() && *o == offset) {
+        return Err(HalError::ResourceBusy);
+    }
+    if let Some(pinmux) = pinmux() {
+        pinmux.request_gpio(controller.name(), offset)?;
+    }
+    requested.push((String::from(controller.name()), offset));
+    Ok(GpioLine { controller, offset, active_low })
+}
+
+/// `GPIO_ACTIVE_LOW` in a DT GPIO specifier
+const GPIO_ACTIVE_LOW: u32 = 1;
+
+/// Request line `index` of the `<function>-gpios` property of `node`
+///
+/// The property lists `<&controller offset flags>` specifiers; the
+/// controller must be registered with its node.
+pub fn request_named(tree: &DeviceTree, node: NodeId, function: &str, index: usize) -> HalResult<GpioLine> {
+    let desc = tree.node(node).ok_or(HalError::InvalidParameter)?;
+    let mut name = String::from(function);
+    name.push_str("-gpios");
+    let specifiers = desc.property(&name).ok_or(HalError::InvalidParameter)?;
+    let cells: Vec<u32> = specifiers.chunks_exact(4).map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]])).collect();
+
+    // Each controller says how long its specifiers are
+    let specifier_at = |pos: usize| -> HalResult<(NodeId, &[u32])> {
+        let phandle = *cells.get(pos).ok_or(HalError::InvalidParameter)?;
+        let controller = tree.find_phandle(phandle).ok_or(HalError::InvalidParameter)?;
+        let count = tree.node(controller).and_then(|n| n.property_u32("#gpio-cells")).unwrap_or(2) as usize;
+        Ok((controller, cells.get(pos + 1..pos + 1 + count).ok_or(HalError::InvalidParameter)?))
+    };
+    let mut pos = 0;
+    for _ in 0..index {
+        pos += 1 + specifier_at(pos)?.1.len();
+    }
+
+    let (controller_node, specifier) = specifier_at(pos)?;
+    let controller = find_node(controller_node).ok_or(HalError::NotInitialized)?;
+    let offset = *specifier.first().ok_or(HalError::InvalidParameter)?;
+    let flags = specifier.get(1).copied().unwrap_or(0);
+    request_line(controller, offset, flags & GPIO_ACTIVE_LOW != 0)
+}
+
+// =============================================================================
+// ARM PrimeCell PL061
+// =============================================================================
+
+/// PL061 data register window; address bits 9:2 mask the lines accessed
+const PL061_DATA: usize = 0x000;
+/// PL061 direction register (1 = output)
+const PL061_DIR: usize = 0x400;
+
+/// ARM PrimeCell PL061 GPIO controller (compatible `arm,pl061`), 8 lines
+pub struct Pl061 {
+    name: String,
+    base: *mut u32,
+    lock: Mutex<()>,
+}
+
+// SAFETY: the registers are only accessed with volatile 32-bit operations,
+// read-modify-write sequences under the lock
+unsafe impl Send for Pl061 {}
+// SAFETY: as above
+unsafe impl Sync for Pl061 {}
+
+impl Pl061 {
+    /// DT compatible string
+    pub const COMPATIBLE: &'static str = "arm,pl061";
+
+    /// Create over the controller's registers
+    ///
+    /// # Safety
+    ///
+    /// `base` must map the controller's 4 KiB register block for the life
+    /// of the controller.
+    pub unsafe fn new(name: &str, base: *mut u8) -> Self {
+        Self { name: String::from(name), base: base as *mut u32, lock: Mutex::new(()) }
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        // SAFETY: within the register block, per construction
+        unsafe { self.base.add(offset / 4).read_volatile() }
+    }
+
+    fn write(&self, offset: usize, value: u32) {
+        // SAFETY: within the register block, per construction
+        unsafe { self.base.add(offset / 4).write_volatile(value) }
+    }
+
+    /// Data register address selecting only line `offset`
+    fn data(offset: u32) -> usize {
+        PL061_DATA + (4 << offset)
+    }
+}
+
+impl GpioController for Pl061 {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn ngpio(&self) -> u32 {
+        8
+    }
+
+    fn direction_input(&self, offset: u32) -> HalResult<()> {
+        let _guard = self.lock.lock();
+        self.write(PL061_DIR, self.read(PL061_DIR) & !(1 << offset));
+        Ok(())
+    }
+
+    fn direction_output(&self, offset: u32, high: bool) -> HalResult<()> {
+        let _guard = self.lock.lock();
+        self.write(Self::data(offset), u32::from(high) << offset);
+        self.write(PL061_DIR, self.read(PL061_DIR) | (1 << offset));
+        // Some implementations latch the value only once the line drives
+        self.write(Self::data(offset), u32::from(high) << offset);
+        Ok(())
+    }
+
+    fn get(&self, offset: u32) -> HalResult<bool> {
+        Ok(self.read(Self::data(offset)) != 0)
+    }
+
+    fn set(&self, offset: u32, high: bool) -> HalResult<()> {
+        // The address mask makes this a single-line write; no lock needed
+        self.write(Self::data(offset), u32::from(high) << offset);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devtree::Source;
+    use alloc::boxed::Box;
+
+    /// PL061 over plain memory; the data window reads back what was
+    /// written at the same address
+    fn pl061(name: &str) -> (&'static Pl061, *const u32) {
+        let regs = Box::leak(Box::new([0u32; 0x400])).as_mut_ptr();
+        // SAFETY: the block outlives the controller (leaked)
+        let gpio = unsafe { Pl061::new(name, regs as *mut u8) };
+        (Box::leak(Box::new(gpio)), regs)
+    }
+
+    #[test]
+    fn test_pl061() {
+        let (gpio, regs) = pl061("gpio-test");
+        // SAFETY: within the leaked block
+        let reg = |offset: usize| unsafe { regs.add(offset / 4).read_volatile() };
+        gpio.direction_output(3, true).unwrap();
+        gpio.set(5, true).unwrap();
+        assert_eq!(reg(PL061_DIR), 1 << 3);
+        assert_eq!(reg(4 << 3), 1 << 3);
+        assert_eq!(reg(4 << 5), 1 << 5);
+        assert!(gpio.get(3).unwrap());
+        assert!(!gpio.get(4).unwrap());
+    }
+
+    #[test]
+    fn test_consumers() {
+        let (gpio, _) = pl061("gpio-consumer");
+        let mut tree = DeviceTree::new(Source::DeviceTree);
+        let controller = tree.add_node(tree.root(), "gpio@9030000", Source::DeviceTree);
+        tree.set_property(controller, "phandle", &7u32.to_be_bytes());
+        let phy = tree.add_node(tree.root(), "phy", Source::DeviceTree);
+        let cells: Vec<u8> = [7u32, 2, 0, 7, 6, GPIO_ACTIVE_LOW].iter().flat_map(|c| c.to_be_bytes()).collect();
+        tree.set_property(phy, "reset-gpios", &cells);
+
+        assert_eq!(request_named(&tree, phy, "reset", 0).err(), Some(HalError::NotInitialized));
+        register(gpio, Some(controller)).unwrap();
+        assert_eq!(register(gpio, None), Err(HalError::ResourceBusy));
+
+        let reset = request_named(&tree, phy, "reset", 1).unwrap();
+        assert_eq!((reset.offset(), reset.is_active_low()), (6, true));
+        reset.set_output(true).unwrap();
+        assert!(!gpio.get(6).unwrap());
+        assert!(reset.is_asserted().unwrap());
+
+        assert_eq!(request("gpio-consumer", 6, false).err(), Some(HalError::ResourceBusy));
+        drop(reset);
+        assert!(request("gpio-consumer", 6, false).is_ok());
+        assert_eq!(request("gpio-consumer", 8, false).err(), Some(HalError::InvalidParameter));
+        assert_eq!(request_named(&tree, phy, "reset", 2).err(), Some(HalError::InvalidParameter));
+    }
+}
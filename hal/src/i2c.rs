@@ -0,0 +1,479 @@
+//! # I2C
+//!
+//! I2C bus masters. Each controller is an [`I2cController`] running
+//! transfers of one or more messages to a 7- or 10-bit address; consumers
+//! talk to one device through an [`I2cClient`].
+//!
+//! Devices on a bus are not discoverable, so they come from the hardware
+//! description: children of the controller's DT node (`reg` is the
+//! address) or ACPI devices with an I2C connection in `_CRS`.
+//! [`board_devices`] lists those whose controller is registered.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use spin::{Mutex, RwLock};
+
+use crate::devtree::{DeviceTree, NodeId, Source};
+use crate::{HalError, HalResult};
+
+/// One message of a transfer
+#[derive(Debug)]
+pub enum I2cOp<'a> {
+    /// Write the bytes
+    Write(&'a [u8]),
+    /// Read into the buffer
+    Read(&'a mut [u8]),
+}
+
+/// An I2C bus master
+pub trait I2cController: Send + Sync {
+    /// Controller name
+    fn name(&self) -> &str;
+
+    /// Run `ops` against `address`, with a repeated start between
+    /// messages and a stop at the end
+    ///
+    /// A device that does not acknowledge fails the transfer with
+    /// `HardwareError`.
+    fn transfer(&self, address: u16, ten_bit: bool, ops: &mut [I2cOp<'_>]) -> HalResult<()>;
+}
+
+// =============================================================================
+// Registry
+// =============================================================================
+
+/// A registered controller
+struct Registered {
+    controller: &'static dyn I2cController,
+    node: Option<NodeId>,
+}
+
+/// Registered controllers, in registration order
+static CONTROLLERS: RwLock<Vec<Registered>> = RwLock::new(Vec::new());
+
+/// Register a controller, described by `node` if it comes from the
+/// hardware description; names must be unique
+pub fn register(controller: &'static dyn I2cController, node: Option<NodeId>) -> HalResult<()> {
+    let mut controllers = CONTROLLERS.write();
+    if controllers.iter().any(|r| r.controller.name() == controller.name()) {
+        return Err(HalError::ResourceBusy);
+    }
+    controllers.push(Registered { controller, node });
+    Ok(())
+}
+
+/// All registered controllers
+pub fn controllers() -> Vec<&'static dyn I2cController> {
+    CONTROLLERS.read().iter().map(|r| r.controller).collect()
+}
+
+/// Controller named `name`
+pub fn find(name: &str) -> Option<&'static dyn I2cController> {
+    CONTROLLERS.read().iter().find(|r| r.controller.name() == name).map(|r| r.controller)
+}
+
+/// Controller registered for node `node`
+pub fn find_node(node: NodeId) -> Option<&'static dyn I2cController> {
+    CONTROLLERS.read().iter().find(|r| r.node == Some(node)).map(|r| r.controller)
+}
+
+// =============================================================================
+// Consumer API
+// =============================================================================
+
+/// One device on a bus
+#[derive(Clone, Copy)]
+pub struct I2cClient {
+    /// Bus the device sits on
+    pub bus: &'static dyn I2cController,
+    /// Device address
+    pub address: u16,
+    /// 10-bit addressing
+    pub ten_bit: bool,
+}
+
+impl I2cClient {
+    /// Client for the 7-bit `address` on `bus`
+    pub fn new(bus: &'static dyn I2cController, address: u16) -> Self {
+        Self { bus, address, ten_bit: false }
+    }
+
+    /// Write `data`
+    pub fn write(&self, data: &[u8]) -> HalResult<()> {
+        self.bus.transfer(self.address, self.ten_bit, &mut [I2cOp::Write(data)])
+    }
+
+    /// Read into `buf`
+    pub fn read(&self, buf: &mut [u8]) -> HalResult<()> {
+        self.bus.transfer(self.address, self.ten_bit, &mut [I2cOp::Read(buf)])
+    }
+
+    /// Write `data`, then read into `buf` after a repeated start
+    pub fn write_read(&self, data: &[u8], buf: &mut [u8]) -> HalResult<()> {
+        self.bus.transfer(self.address, self.ten_bit, &mut [I2cOp::Write(data), I2cOp::Read(buf)])
+    }
+
+    /// Read the 8-bit register `reg`
+    pub fn read_reg(&self, reg: u8) -> HalResult<u8> {
+        let mut value = [0];
+        self.write_read(&[reg], &mut value)?;
+        Ok(value[0])
+    }
+
+    /// Write the 8-bit register `reg`
+    pub fn write_reg(&self, reg: u8, value: u8) -> HalResult<()> {
+        self.write(&[reg, value])
+    }
+}
+
+// =============================================================================
+// Board devices
+// =============================================================================
+
+/// A device the hardware description places on a registered bus
+#[derive(Clone, Copy)]
+pub struct I2cBoardDevice {
+    /// Device node
+    pub node: NodeId,
+    /// The device
+    pub client: I2cClient,
+}
+
+/// I2C address of node `node`, if the description places it on an I2C bus
+///
+/// ACPI devices carry an I2C connection; DT devices sit under an `i2c`
+/// bus node with their address in `reg`.
+pub fn client_address(tree: &DeviceTree, node: NodeId) -> Option<(u16, bool)> {
+    let desc = tree.node(node)?;
+    match desc.source {
+        Source::Acpi => desc.i2c.as_ref().map(|c| (c.address, c.ten_bit)),
+        Source::DeviceTree => {
+            let parent = tree.node(desc.parent?)?;
+            if !parent.name.starts_with("i2c") {
+                return None;
+            }
+            let reg = desc.property_u32("reg")?;
+            // I2C_TEN_BIT_ADDRESS in the DT binding
+            Some(((reg & 0x3ff) as u16, reg & 0x8000_0000 != 0))
+        }
+    }
+}
+
+/// Devices described on registered controllers, in tree order
+pub fn board_devices(tree: &DeviceTree) -> Vec<I2cBoardDevice> {
+    tree.nodes()
+        .filter(|(_, desc)| desc.is_enabled())
+        .filter_map(|(node, desc)| {
+            let (address, ten_bit) = client_address(tree, node)?;
+            let bus = match &desc.i2c {
+                Some(connection) => find_node(tree.find_acpi_path(&connection.controller)?)?,
+                None => find_node(desc.parent?)?,
+            };
+            Some(I2cBoardDevice { node, client: I2cClient { bus, address, ten_bit } })
+        })
+        .collect()
+}
+
+// =============================================================================
+// Synopsys DesignWare
+// =============================================================================
+
+/// Control: master mode, speed, restarts, slave disabled
+const DW_IC_CON: usize = 0x00;
+const DW_CON_MASTER: u32 = 1 << 0;
+const DW_CON_SPEED_STD: u32 = 1 << 1;
+const DW_CON_SPEED_FAST: u32 = 2 << 1;
+const DW_CON_RESTART_EN: u32 = 1 << 5;
+const DW_CON_SLAVE_DISABLE: u32 = 1 << 6;
+/// Target address
+const DW_IC_TAR: usize = 0x04;
+const DW_TAR_10BIT: u32 = 1 << 12;
+/// Data and command FIFO
+const DW_IC_DATA_CMD: usize = 0x10;
+const DW_CMD_READ: u32 = 1 << 8;
+const DW_CMD_STOP: u32 = 1 << 9;
+const DW_CMD_RESTART: u32 = 1 << 10;
+/// SCL counts, standard and fast mode
+const DW_IC_SS_SCL_HCNT: usize = 0x14;
+const DW_IC_SS_SCL_LCNT: usize = 0x18;
+const DW_IC_FS_SCL_HCNT: usize = 0x1c;
+const DW_IC_FS_SCL_LCNT: usize = 0x20;
+/// Interrupt mask
+const DW_IC_INTR_MASK: usize = 0x30;
+/// Raw interrupt status
+const DW_IC_RAW_INTR_STAT: usize = 0x34;
+const DW_INTR_TX_ABRT: u32 = 1 << 6;
+const DW_INTR_STOP_DET: u32 = 1 << 9;
+/// Reading clears the abort
+const DW_IC_CLR_TX_ABRT: usize = 0x54;
+/// Reading clears the stop detection
+const DW_IC_CLR_STOP_DET: usize = 0x60;
+/// Enable
+const DW_IC_ENABLE: usize = 0x6c;
+/// Status
+const DW_IC_STATUS: usize = 0x70;
+const DW_STATUS_TFNF: u32 = 1 << 1;
+const DW_STATUS_RFNE: u32 = 1 << 3;
+/// Why the last transfer aborted
+const DW_IC_TX_ABRT_SOURCE: usize = 0x80;
+/// Enable status
+const DW_IC_ENABLE_STATUS: usize = 0x9c;
+
+/// Register polls before a transfer times out
+const DW_SPIN_LIMIT: u32 = 100_000;
+
+/// SCL high and low counts for a `clock_khz` input clock
+///
+/// The controller adds 8 clocks to the high period (spike suppression
+/// included) and 1 to the low period; `fast` selects the 400 kHz minimums
+/// over the 100 kHz ones.
+pub fn dw_scl_counts(clock_khz: u32, fast: bool) -> (u32, u32) {
+    let (high_ns, low_ns) = if fast { (600, 1300) } else { (4000, 4700) };
+    let cycles = |ns: u64| (u64::from(clock_khz) * ns).div_ceil(1_000_000) as u32;
+    (cycles(high_ns).saturating_sub(8).max(6), cycles(low_ns).saturating_sub(1).max(8))
+}
+
+/// Synopsys DesignWare I2C controller, polled
+///
+/// Found as `snps,designware-i2c` on DT boards and as the LPSS and FCH
+/// controllers of x86 laptops (ACPI [`DesignWareI2c::ACPI_HIDS`]).
+pub struct DesignWareI2c {
+    name: String,
+    base: *mut u32,
+    clock_khz: u32,
+    bus_hz: u32,
+    lock: Mutex<()>,
+}
+
+// SAFETY: the registers are only accessed with volatile 32-bit operations,
+// and transfers are serialized by the lock
+unsafe impl Send for DesignWareI2c {}
+// SAFETY: as above
+unsafe impl Sync for DesignWareI2c {}
+
+impl DesignWareI2c {
+    /// DT compatible string
+    pub const COMPATIBLE: &'static str = "snps,designware-i2c";
+
+    /// ACPI hardware IDs
+    pub const ACPI_HIDS: &'static [&'static str] = &["INT33C2", "INT33C3", "INT3432", "INT3433", "AMDI0010", "80860F41", "808622C1"];
+
+    /// Create over the controller's registers
+    ///
+    /// `clock_khz` is the controller's input clock, `bus_hz` the bus
+    /// speed (fast mode above 100 kHz).
+    ///
+    /// # Safety
+    ///
+    /// `base` must map the controller's register block for the life of
+    /// the controller.
+    pub unsafe fn new(name: &str, base: *mut u8, clock_khz: u32, bus_hz: u32) -> Self {
+        Self { name: String::from(name), base: base as *mut u32, clock_khz, bus_hz, lock: Mutex::new(()) }
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        // SAFETY: within the register block, per construction
+        unsafe { self.base.add(offset / 4).read_volatile() }
+    }
+
+    fn write(&self, offset: usize, value: u32) {
+        // SAFETY: within the register block, per construction
+        unsafe { self.base.add(offset / 4).write_volatile(value) }
+    }
+
+    fn set_enabled(&self, enabled: bool) -> HalResult<()> {
+        self.write(DW_IC_ENABLE, u32::from(enabled));
+        for _ in 0..DW_SPIN_LIMIT {
+            if self.read(DW_IC_ENABLE_STATUS) & 1 == u32::from(enabled) {
+                return Ok(());
+            }
+            core::hint::spin_loop();
+        }
+        Err(HalError::Timeout)
+    }
+
+    /// Program master mode and bus timing; call once before transfers
+    pub fn init(&self) -> HalResult<()> {
+        let _guard = self.lock.lock();
+        self.set_enabled(false)?;
+        let fast = self.bus_hz > 100_000;
+        let (high, low) = dw_scl_counts(self.clock_khz, false);
+        self.write(DW_IC_SS_SCL_HCNT, high);
+        self.write(DW_IC_SS_SCL_LCNT, low);
+        let (high, low) = dw_scl_counts(self.clock_khz, true);
+        self.write(DW_IC_FS_SCL_HCNT, high);
+        self.write(DW_IC_FS_SCL_LCNT, low);
+        let speed = if fast { DW_CON_SPEED_FAST } else { DW_CON_SPEED_STD };
+        self.write(DW_IC_CON, DW_CON_MASTER | speed | DW_CON_RESTART_EN | DW_CON_SLAVE_DISABLE);
+        self.write(DW_IC_INTR_MASK, 0);
+        Ok(())
+    }
+
+    /// Wait for `status` bits, failing on an abort
+    fn wait_status(&self, bits: u32) -> HalResult<()> {
+        for _ in 0..DW_SPIN_LIMIT {
+            self.check_abort()?;
+            if self.read(DW_IC_STATUS) & bits != 0 {
+                return Ok(());
+            }
+            core::hint::spin_loop();
+        }
+        Err(HalError::Timeout)
+    }
+
+    fn check_abort(&self) -> HalResult<()> {
+        if self.read(DW_IC_RAW_INTR_STAT) & DW_INTR_TX_ABRT == 0 {
+            return Ok(());
+        }
+        let source = self.read(DW_IC_TX_ABRT_SOURCE);
+        self.read(DW_IC_CLR_TX_ABRT);
+        log::debug!("{}: transfer aborted ({:#x})", self.name, source);
+        Err(HalError::HardwareError)
+    }
+
+    fn run(&self, ops: &mut [I2cOp<'_>]) -> HalResult<()> {
+        let count = ops.len();
+        for (i, op) in ops.iter_mut().enumerate() {
+            let len = match op {
+                I2cOp::Write(data) => data.len(),
+                I2cOp::Read(buf) => buf.len(),
+            };
+            for byte in 0..len {
+                let mut cmd = match op {
+                    I2cOp::Write(data) => u32::from(data[byte]),
+                    I2cOp::Read(_) => DW_CMD_READ,
+                };
+                if byte == 0 && i > 0 {
+                    cmd |= DW_CMD_RESTART;
+                }
+                if byte == len - 1 && i == count - 1 {
+                    cmd |= DW_CMD_STOP;
+                }
+                self.wait_status(DW_STATUS_TFNF)?;
+                self.write(DW_IC_DATA_CMD, cmd);
+                if let I2cOp::Read(buf) = op {
+                    self.wait_status(DW_STATUS_RFNE)?;
+                    buf[byte] = self.read(DW_IC_DATA_CMD) as u8;
+                }
+            }
+        }
+
+        // Writes are done once the stop has gone out
+        for _ in 0..DW_SPIN_LIMIT {
+            self.check_abort()?;
+            if self.read(DW_IC_RAW_INTR_STAT) & DW_INTR_STOP_DET != 0 {
+                self.read(DW_IC_CLR_STOP_DET);
+                return Ok(());
+            }
+            core::hint::spin_loop();
+        }
+        Err(HalError::Timeout)
+    }
+}
+
+impl I2cController for DesignWareI2c {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn transfer(&self, address: u16, ten_bit: bool, ops: &mut [I2cOp<'_>]) -> HalResult<()> {
+        // The controller cannot send a message of no bytes
+        let empty = |op: &I2cOp<'_>| match op {
+            I2cOp::Write(data) => data.is_empty(),
+            I2cOp::Read(buf) => buf.is_empty(),
+        };
+        if ops.is_empty() || ops.iter().any(empty) {
+            return Err(HalError::InvalidParameter);
+        }
+
+        let _guard = self.lock.lock();
+        self.set_enabled(false)?;
+        self.write(DW_IC_TAR, u32::from(address) | if ten_bit { DW_TAR_10BIT } else { 0 });
+        self.read(DW_IC_CLR_STOP_DET);
+        self.set_enabled(true)?;
+        let result = self.run(ops);
+        self.set_enabled(false)?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::vec;
+
+    /// Bus that records the last address and answers reads with it
+    struct FakeBus(&'static str, Mutex<u16>);
+
+    impl I2cController for FakeBus {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn transfer(&self, address: u16, _ten_bit: bool, ops: &mut [I2cOp<'_>]) -> HalResult<()> {
+            *self.1.lock() = address;
+            for op in ops {
+                if let I2cOp::Read(buf) = op {
+                    buf.fill(address as u8);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_scl_counts() {
+        assert_eq!(dw_scl_counts(100_000, false), (392, 469));
+        assert_eq!(dw_scl_counts(100_000, true), (52, 129));
+        assert_eq!(dw_scl_counts(1_000, true), (6, 8));
+    }
+
+    #[test]
+    fn test_board_devices() {
+        let mut tree = DeviceTree::new(Source::DeviceTree);
+        let bus = tree.add_node(tree.root(), "i2c@9060000", Source::DeviceTree);
+        let eeprom = tree.add_node(bus, "eeprom@50", Source::DeviceTree);
+        tree.set_property(eeprom, "compatible", b"atmel,24c02\0");
+        tree.set_property(eeprom, "reg", &0x50u32.to_be_bytes());
+        let off = tree.add_node(bus, "sensor@48", Source::DeviceTree);
+        tree.set_property(off, "reg", &0x48u32.to_be_bytes());
+        tree.set_property(off, "status", b"disabled\0");
+        let gpio = tree.add_node(tree.root(), "gpio@9030000", Source::DeviceTree);
+        let led = tree.add_node(gpio, "led@1", Source::DeviceTree);
+        tree.set_property(led, "reg", &1u32.to_be_bytes());
+
+        assert_eq!(client_address(&tree, eeprom), Some((0x50, false)));
+        assert_eq!(client_address(&tree, led), None);
+        assert!(board_devices(&tree).is_empty());
+
+        let fake: &'static FakeBus = Box::leak(Box::new(FakeBus("i2c-test", Mutex::new(0))));
+        register(fake, Some(bus)).unwrap();
+        assert_eq!(register(fake, None), Err(HalError::ResourceBusy));
+        let devices = board_devices(&tree);
+        assert_eq!(devices.len(), 1);
+        assert_eq!((devices[0].node, devices[0].client.address), (eeprom, 0x50));
+        assert_eq!(devices[0].client.read_reg(0), Ok(0x50));
+    }
+
+    #[test]
+    fn test_acpi_board_devices() {
+        // I2cSerialBusV2 (0x2c, 400 kHz, "\_SB.I2C1")
+        let mut crs = vec![0x8e, 0x19, 0x00, 0x02, 0x00, 0x01, 0x02, 0x00, 0x00, 0x01, 0x06, 0x00];
+        crs.extend_from_slice(&[0x80, 0x1a, 0x06, 0x00, 0x2c, 0x00]);
+        crs.extend_from_slice(b"\\_SB.I2C1\0");
+        crs.extend_from_slice(&[0x79, 0x00]);
+        let mut tree = DeviceTree::new(Source::Acpi);
+        let sb = tree.add_node(tree.root(), "_SB_", Source::Acpi);
+        let bus = tree.add_acpi_device(sb, "I2C1", "AMDI0010", &[], &[0x79, 0x00]).unwrap();
+        let touchpad = tree.add_acpi_device(bus, "TPD0", "ELAN0001", &["PNP0C50"], &crs).unwrap();
+        assert_eq!(client_address(&tree, touchpad), Some((0x2c, false)));
+
+        let fake: &'static FakeBus = Box::leak(Box::new(FakeBus("i2c-acpi", Mutex::new(0))));
+        register(fake, Some(bus)).unwrap();
+        let devices = board_devices(&tree);
+        assert_eq!(devices.len(), 1);
+        devices[0].client.write_read(&[0x20], &mut [0; 2]).unwrap();
+        assert_eq!(*fake.1.lock(), 0x2c);
+    }
+}
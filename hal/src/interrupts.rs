@@ -4,6 +4,7 @@
 
 use crate::HalResult;
 use core::fmt::Debug;
+use core::sync::atomic::{AtomicPtr, Ordering};
 
 /// Interrupt vector number
 pub type InterruptVector = u8;
@@ -67,6 +68,18 @@ fn set_trigger_mode(
         vector: InterruptVector,
         mode: TriggerMode,
     ) -> HalResult<()>;
+    
+    /// Route a device interrupt to a CPU
+    fn set_affinity(&mut self, vector: InterruptVector, cpu: usize) -> HalResult<()> {
+        let _ = (vector, cpu);
+        Err(crate::HalError::NotSupported)
+    }
+    
+    /// Get the CPU a device interrupt is routed to
+    fn affinity(&self, vector: InterruptVector) -> Option<usize> {
+        let _ = vector;
+        None
+    }
 }
 
 /// Target for Inter-Processor Interrupts
@@ -185,3 +198,101 @@ pub struct PageFaultInfo {
     /// Was this a reserved bit violation?
     pub reserved_bit_violation: bool,
 }
+
+// =============================================================================
+// Hard-IRQ Context Hooks
+// =============================================================================
+
+/// Hard-IRQ bracket installed by the execution layer
+///
+/// The architecture IRQ paths call [`irq_enter`] before a device handler
+/// and [`irq_exit`] after it, so IRQ time is accounted and pending
+/// softirqs run when the outermost handler returns.
+#[derive(Clone, Copy)]
+pub struct IrqHooks {
+    /// Entering hard-IRQ context on a CPU
+    pub enter: fn(cpu: usize),
+    /// Leaving hard-IRQ context on a CPU
+    pub exit: fn(cpu: usize),
+}
+
+static IRQ_ENTER: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+static IRQ_EXIT: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Install the hard-IRQ bracket
+pub fn set_irq_hooks(hooks: IrqHooks) {
+    IRQ_EXIT.store(hooks.exit as *mut (), Ordering::Release);
+    IRQ_ENTER.store(hooks.enter as *mut (), Ordering::Release);
+}
+
+/// Call a hook stored in `slot`, if any
+fn call_hook(slot: &AtomicPtr<()>, cpu: usize) {
+    let hook = slot.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: Only `fn(usize)` pointers are stored, by `set_irq_hooks`
+        let hook: fn(usize) = unsafe { core::mem::transmute(hook) };
+        hook(cpu);
+    }
+}
+
+/// Enter hard-IRQ context on `cpu` (arch IRQ entry)
+pub fn irq_enter(cpu: usize) {
+    call_hook(&IRQ_ENTER, cpu);
+}
+
+/// Leave hard-IRQ context on `cpu` (arch IRQ exit)
+pub fn irq_exit(cpu: usize) {
+    call_hook(&IRQ_EXIT, cpu);
+}
+
+// =============================================================================
+// Local Interrupt State
+// =============================================================================
+
+/// Disable interrupts on the calling CPU
+///
+/// Returns whether they were enabled, for [`local_irq_restore`]. Hosted
+/// builds (unit tests) have no interrupts to mask and always return
+/// `false`.
+#[inline]
+pub fn local_irq_save() -> bool {
+    #[cfg(all(target_os = "none", target_arch = "x86_64"))]
+    {
+        crate::arch::x86_64::interrupts::disable()
+    }
+    #[cfg(all(target_os = "none", target_arch = "aarch64"))]
+    {
+        let daif: u64;
+        // SAFETY: Reading DAIF and masking IRQs has no other side effects
+        unsafe {
+            core::arch::asm!(
+                "mrs {}, daif",
+                "msr daifset, #2",
+                out(reg) daif,
+                options(nomem, nostack, preserves_flags),
+            );
+        }
+        daif & (1 << 7) == 0
+    }
+    #[cfg(not(all(target_os = "none", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+    {
+        false
+    }
+}
+
+/// Re-enable interrupts if [`local_irq_save`] found them enabled
+#[inline]
+pub fn local_irq_restore(enabled: bool) {
+    #[cfg(all(target_os = "none", target_arch = "x86_64"))]
+    if enabled {
+        // SAFETY: Interrupts were enabled when the matching save ran
+        unsafe { crate::arch::x86_64::interrupts::enable() };
+    }
+    #[cfg(all(target_os = "none", target_arch = "aarch64"))]
+    if enabled {
+        // SAFETY: Interrupts were enabled when the matching save ran
+        unsafe { core::arch::asm!("msr daifclr, #2", options(nomem, nostack, preserves_flags)) };
+    }
+    #[cfg(not(all(target_os = "none", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+    let _ = enabled;
+}
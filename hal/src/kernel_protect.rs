@@ -0,0 +1,479 @@
+//! # Kernel Image Protection
+//!
+//! W^X for the kernel image once early boot is over.
+//!
+//! The bootloader maps the whole image read-write-execute. Two steps
+//! tighten that:
+//!
+//! 1. [`protect_kernel`], after early boot: `.text` becomes read+execute,
+//!    `.rodata` read-only, and everything else (the relro region, `.data`,
+//!    `.bss`) non-executable.
+//! 2. [`lockdown`], once initialization is complete: the relro region
+//!    becomes read-only too. It holds the GOT, the static module registry
+//!    (`helix_modules`), the exception table (`helix_extable`) and every
+//!    static placed in [`RO_AFTER_INIT`], such as the IDT and the
+//!    system call table.
+//!
+//! Code that must patch protected memory afterwards (hot-patching, live
+//! module replacement) goes through [`with_writable`], which lifts the
+//! protection of the affected pages for the duration of a closure. It
+//! does not stop other CPUs; callers run it under `stop_machine`.
+//!
+//! ## Layout
+//!
+//! The regions come from the linker script symbols (`__text_start`,
+//! `__rodata_start`, `__relro_start`, `__data_start`, `__bss_end`, ...).
+//! Every region must start on a page boundary; ends are rounded up, so
+//! the padding after a region takes its protection.
+
+use core::fmt;
+
+use spin::Mutex;
+
+use crate::arch::current::kernel_protect as arch;
+
+/// Section for statics that are written during initialization only
+///
+/// ```ignore
+/// #[link_section = ".data..ro_after_init"]
+/// static mut TABLE: Table = Table::new();
+/// ```
+///
+/// The linker scripts place it in the relro region, so it becomes
+/// read-only at [`lockdown`].
+pub const RO_AFTER_INIT: &str = ".data..ro_after_init";
+
+/// Page granularity of the protection
+pub const PAGE_SIZE: u64 = 4096;
+
+/// Access allowed to a kernel region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protection {
+    /// Read and execute (`.text`)
+    ReadExecute,
+    /// Read only, never executable
+    ReadOnly,
+    /// Read and write, never executable
+    ReadWrite,
+    /// Read, write and execute: only while patching `.text`
+    ReadWriteExecute,
+    /// Not present: guard pages outside the image
+    NoAccess,
+}
+
+impl Protection {
+    /// Pages may be written
+    pub const fn writable(self) -> bool {
+        matches!(self, Self::ReadWrite | Self::ReadWriteExecute)
+    }
+
+    /// Pages may be executed
+    pub const fn executable(self) -> bool {
+        matches!(self, Self::ReadExecute | Self::ReadWriteExecute)
+    }
+
+    /// Pages may be accessed at all
+    pub const fn present(self) -> bool {
+        !matches!(self, Self::NoAccess)
+    }
+
+    /// The same protection with writes allowed
+    pub const fn with_write(self) -> Self {
+        match self {
+            Self::ReadExecute | Self::ReadWriteExecute => Self::ReadWriteExecute,
+            Self::ReadOnly | Self::ReadWrite | Self::NoAccess => Self::ReadWrite,
+        }
+    }
+}
+
+/// Protection failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtectError {
+    /// A region does not start on a page boundary
+    Misaligned(u64),
+    /// Regions are empty, out of order or overlap
+    BadLayout,
+    /// The range is not inside a protected region of the image
+    OutOfImage(u64),
+    /// The range overlaps the kernel image
+    InImage(u64),
+    /// An address in the range is not mapped
+    NotMapped(u64),
+    /// A large page must be split but no page table is left
+    NoTables,
+    /// A large page must be split but the architecture cannot do it live
+    Unsplittable(u64),
+    /// Step called out of order (e.g. [`lockdown`] before [`protect_kernel`])
+    WrongState,
+}
+
+impl fmt::Display for ProtectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Misaligned(addr) => write!(f, "region at {:#x} is not page aligned", addr),
+            Self::BadLayout => f.write_str("kernel regions are empty, out of order or overlap"),
+            Self::OutOfImage(addr) => write!(f, "{:#x} is not in a protected kernel region", addr),
+            Self::InImage(addr) => write!(f, "{:#x} is inside the kernel image", addr),
+            Self::NotMapped(addr) => write!(f, "{:#x} is not mapped", addr),
+            Self::NoTables => f.write_str("out of page tables for splitting large pages"),
+            Self::Unsplittable(addr) => write!(f, "large page at {:#x} cannot be split", addr),
+            Self::WrongState => f.write_str("kernel protection step called out of order"),
+        }
+    }
+}
+
+// =============================================================================
+// Layout
+// =============================================================================
+
+/// Half-open address range `[start, end)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    /// First address
+    pub start: u64,
+    /// One past the last address
+    pub end: u64,
+}
+
+impl Span {
+    /// Range `[start, end)`
+    pub const fn new(start: u64, end: u64) -> Self {
+        Self { start, end }
+    }
+
+    /// The range rounded out to whole pages
+    pub const fn pages(self) -> Self {
+        Self {
+            start: self.start & !(PAGE_SIZE - 1),
+            end: (self.end + PAGE_SIZE - 1) & !(PAGE_SIZE - 1),
+        }
+    }
+
+    /// Size in bytes
+    pub const fn len(self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Empty range
+    pub const fn is_empty(self) -> bool {
+        self.end <= self.start
+    }
+
+    /// `other` lies entirely inside this range
+    pub const fn covers(self, other: Span) -> bool {
+        other.start >= self.start && other.end <= self.end
+    }
+
+    /// The ranges share at least one address
+    pub const fn overlaps(self, other: Span) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+/// Regions of the kernel image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KernelLayout {
+    /// Code
+    pub text: Span,
+    /// Read-only data
+    pub rodata: Span,
+    /// Relro region: GOT, module registry, exception table, ro-after-init
+    pub ro_after_init: Span,
+    /// Initialized data and BSS
+    pub data: Span,
+}
+
+#[cfg(target_os = "none")]
+extern "C" {
+    static __text_start: u8;
+    static __text_end: u8;
+    static __rodata_start: u8;
+    static __rodata_end: u8;
+    static __relro_start: u8;
+    static __relro_end: u8;
+    static __data_start: u8;
+    static __bss_end: u8;
+}
+
+impl KernelLayout {
+    /// Layout of the running kernel, from the linker script symbols
+    #[cfg(target_os = "none")]
+    pub fn current() -> Self {
+        use core::ptr::addr_of;
+
+        let addr = |sym: *const u8| sym as u64;
+        Self {
+            text: Span::new(addr(addr_of!(__text_start)), addr(addr_of!(__text_end))),
+            rodata: Span::new(addr(addr_of!(__rodata_start)), addr(addr_of!(__rodata_end))),
+            ro_after_init: Span::new(addr(addr_of!(__relro_start)), addr(addr_of!(__relro_end))),
+            data: Span::new(addr(addr_of!(__data_start)), addr(addr_of!(__bss_end))),
+        }
+    }
+
+    /// Regions in address order
+    fn regions(&self) -> [Span; 4] {
+        [self.text, self.rodata, self.ro_after_init, self.data]
+    }
+
+    /// Check that the regions are page aligned, in order and disjoint
+    ///
+    /// The relro region may be empty; the others may not.
+    pub fn validate(&self) -> Result<(), ProtectError> {
+        let mut prev_end = 0;
+        for (i, region) in self.regions().into_iter().enumerate() {
+            if region.start % PAGE_SIZE != 0 {
+                return Err(ProtectError::Misaligned(region.start));
+            }
+            if region.end < region.start || (region.is_empty() && i != 2) {
+                return Err(ProtectError::BadLayout);
+            }
+            if region.start < prev_end {
+                return Err(ProtectError::BadLayout);
+            }
+            prev_end = region.pages().end;
+        }
+        Ok(())
+    }
+
+    /// Protection of each region after [`protect_kernel`]
+    pub fn boot_plan(&self) -> [(Span, Protection); 4] {
+        [
+            (self.text.pages(), Protection::ReadExecute),
+            (self.rodata.pages(), Protection::ReadOnly),
+            (self.ro_after_init.pages(), Protection::ReadWrite),
+            (self.data.pages(), Protection::ReadWrite),
+        ]
+    }
+
+    /// Protection of each region after [`lockdown`]
+    pub fn locked_plan(&self) -> [(Span, Protection); 4] {
+        let mut plan = self.boot_plan();
+        plan[2].1 = Protection::ReadOnly;
+        plan
+    }
+}
+
+/// Stage of the kernel protection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Image mapped as the bootloader left it
+    Boot,
+    /// W^X applied
+    Protected,
+    /// Relro region read-only too
+    LockedDown,
+}
+
+struct State {
+    stage: Stage,
+    layout: KernelLayout,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    stage: Stage::Boot,
+    layout: KernelLayout {
+        text: Span::new(0, 0),
+        rodata: Span::new(0, 0),
+        ro_after_init: Span::new(0, 0),
+        data: Span::new(0, 0),
+    },
+});
+
+/// Current protection stage
+pub fn stage() -> Stage {
+    STATE.lock().stage
+}
+
+/// The relro region is read-only
+pub fn is_locked_down() -> bool {
+    stage() == Stage::LockedDown
+}
+
+/// Apply `plan` through the architecture backend
+///
+/// # Safety
+///
+/// As for [`protect_kernel`].
+unsafe fn apply(plan: &[(Span, Protection)]) -> Result<(), ProtectError> {
+    for &(span, prot) in plan {
+        if !span.is_empty() {
+            unsafe { arch::set_protection(span, prot)? };
+        }
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Steps
+// =============================================================================
+
+/// Map the kernel image W^X
+///
+/// Call once on the boot CPU, after early boot and before other CPUs
+/// start: the remaining CPUs pick the new mappings up when they load the
+/// shared tables, but TLB entries cached elsewhere are not shot down.
+///
+/// # Safety
+///
+/// `layout` must describe the running kernel. Nothing may write to
+/// `.text` or `.rodata` afterwards except through [`with_writable`].
+pub unsafe fn protect_kernel(layout: KernelLayout) -> Result<(), ProtectError> {
+    layout.validate()?;
+    let mut state = STATE.lock();
+    if state.stage != Stage::Boot {
+        return Err(ProtectError::WrongState);
+    }
+    unsafe {
+        arch::enable();
+        apply(&layout.boot_plan())?;
+    }
+    state.layout = layout;
+    state.stage = Stage::Protected;
+    Ok(())
+}
+
+/// Make the relro region read-only
+///
+/// Call once initialization is complete: the IDT, the system call table
+/// and the module registry are frozen from here on.
+///
+/// # Safety
+///
+/// Nothing may write to the relro region afterwards except through
+/// [`with_writable`].
+pub unsafe fn lockdown() -> Result<(), ProtectError> {
+    let mut state = STATE.lock();
+    if state.stage != Stage::Protected {
+        return Err(ProtectError::WrongState);
+    }
+    let plan = state.layout.locked_plan();
+    unsafe { apply(&plan[2..3])? };
+    state.stage = Stage::LockedDown;
+    Ok(())
+}
+
+/// Protection of `span` at `stage`, if it lies inside a single region
+fn protection_of(layout: &KernelLayout, stage: Stage, span: Span) -> Option<Protection> {
+    let plan = match stage {
+        Stage::Boot => return Some(Protection::ReadWriteExecute),
+        Stage::Protected => layout.boot_plan(),
+        Stage::LockedDown => layout.locked_plan(),
+    };
+    plan.iter().find(|(region, _)| region.covers(span)).map(|&(_, prot)| prot)
+}
+
+/// Run `f` with the pages of `span` writable
+///
+/// Debug and hot-patching aid. The protection of the pages is restored
+/// before returning, even if `f` only needed part of the range. Other
+/// CPUs keep running; callers bring them to a halt first
+/// (`stop_machine`) so none executes or caches the pages being changed.
+///
+/// # Safety
+///
+/// The writes made by `f` must leave the kernel consistent. Code written
+/// to `.text` must be followed by whatever instruction cache maintenance
+/// the architecture needs before it runs.
+pub unsafe fn with_writable<R>(span: Span, f: impl FnOnce() -> R) -> Result<R, ProtectError> {
+    let state = STATE.lock();
+    let pages = span.pages();
+    let prot = protection_of(&state.layout, state.stage, pages)
+        .ok_or(ProtectError::OutOfImage(span.start))?;
+    if prot.writable() {
+        return Ok(f());
+    }
+    unsafe { arch::set_protection(pages, prot.with_write())? };
+    let result = f();
+    unsafe { arch::set_protection(pages, prot)? };
+    Ok(result)
+}
+
+/// Change the protection of kernel pages outside the image
+///
+/// Used for guard pages around heap and stack allocations:
+/// [`Protection::NoAccess`] unmaps the pages while keeping their
+/// translation, so any other protection maps them again.
+///
+/// # Safety
+///
+/// `span` must be page aligned, mapped by the kernel's tables and not
+/// overlap the kernel image. Nothing may touch the pages while they are
+/// not present.
+pub unsafe fn set_page_protection(span: Span, prot: Protection) -> Result<(), ProtectError> {
+    if span.start % PAGE_SIZE != 0 || span.end % PAGE_SIZE != 0 {
+        return Err(ProtectError::Misaligned(span.start));
+    }
+    let layout = STATE.lock().layout;
+    let image = [layout.text, layout.rodata, layout.ro_after_init, layout.data];
+    if image.iter().any(|region| region.overlaps(span)) {
+        return Err(ProtectError::InImage(span.start));
+    }
+    unsafe { arch::set_protection(span, prot) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> KernelLayout {
+        KernelLayout {
+            text: Span::new(0x20_0000, 0x23_4567),
+            rodata: Span::new(0x40_0000, 0x41_0000),
+            ro_after_init: Span::new(0x60_0000, 0x60_0800),
+            data: Span::new(0x80_0000, 0x90_0000),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(layout().validate(), Ok(()));
+
+        let mut bad = layout();
+        bad.rodata.start += 8;
+        assert_eq!(bad.validate(), Err(ProtectError::Misaligned(0x40_0008)));
+
+        let mut overlap = layout();
+        overlap.rodata.start = 0x23_4000;
+        assert_eq!(overlap.validate(), Err(ProtectError::BadLayout));
+
+        let mut no_relro = layout();
+        no_relro.ro_after_init = Span::new(0x60_0000, 0x60_0000);
+        assert_eq!(no_relro.validate(), Ok(()));
+
+        let mut no_text = layout();
+        no_text.text.end = no_text.text.start;
+        assert_eq!(no_text.validate(), Err(ProtectError::BadLayout));
+    }
+
+    #[test]
+    fn test_plans() {
+        let boot = layout().boot_plan();
+        assert_eq!(boot[0], (Span::new(0x20_0000, 0x23_5000), Protection::ReadExecute));
+        assert_eq!(boot[1].1, Protection::ReadOnly);
+        assert_eq!(boot[2], (Span::new(0x60_0000, 0x60_1000), Protection::ReadWrite));
+        assert!(!boot[3].1.executable());
+
+        let locked = layout().locked_plan();
+        assert_eq!(locked[2].1, Protection::ReadOnly);
+        assert_eq!(locked[0], boot[0]);
+        assert_eq!(locked[3], boot[3]);
+    }
+
+    #[test]
+    fn test_protection_of() {
+        let layout = layout();
+        let text = Span::new(0x20_1000, 0x20_2000);
+        assert_eq!(protection_of(&layout, Stage::Boot, text), Some(Protection::ReadWriteExecute));
+        assert_eq!(protection_of(&layout, Stage::Protected, text), Some(Protection::ReadExecute));
+
+        let table = Span::new(0x60_0000, 0x60_1000);
+        assert_eq!(protection_of(&layout, Stage::Protected, table), Some(Protection::ReadWrite));
+        assert_eq!(protection_of(&layout, Stage::LockedDown, table), Some(Protection::ReadOnly));
+
+        let straddle = Span::new(0x23_4000, 0x40_1000);
+        assert_eq!(protection_of(&layout, Stage::Protected, straddle), None);
+
+        assert_eq!(Protection::ReadExecute.with_write(), Protection::ReadWriteExecute);
+        assert_eq!(Protection::ReadOnly.with_write(), Protection::ReadWrite);
+    }
+}
@@ -35,6 +35,22 @@
 pub mod mmu;
 pub mod interrupts;
 pub mod firmware;
+pub mod dmi;
+pub mod quirks;
+pub mod devtree;
+pub mod gpio;
+pub mod i2c;
+pub mod uart;
+pub mod stack;
+pub mod topology;
+pub mod cache;
+pub mod cfi;
+pub mod speculation;
+pub mod uaccess;
+pub mod kernel_protect;
+pub mod lockdown;
+pub mod backlight;
+pub mod power_supply;
 
 // Kernel relocation support
 pub mod relocation;
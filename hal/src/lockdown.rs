@@ -0,0 +1,313 @@
+//! # Kernel Lockdown
+//!
+//! Restrictions that keep even a privileged user from changing the running
+//! kernel (`integrity`) or reading its memory (`confidentiality`), so a
+//! kernel that was verified at boot stays what was verified.
+//!
+//! The level comes from the `lockdown=` boot parameter and is at least
+//! `integrity` when the kernel was started with Secure Boot enforcing
+//! ([`LockdownLevel::for_boot`]). It can be raised at runtime by writing
+//! `/sys/kernel/security/lockdown`, never lowered:
+//!
+//! ```text
+//! $ cat /sys/kernel/security/lockdown
+//! none [integrity] confidentiality
+//! ```
+//!
+//! Interfaces that could bypass the lockdown ask [`check`] before acting
+//! and fail with `EPERM` when it refuses:
+//!
+//! | Level             | Refused                                                   |
+//! |-------------------|-----------------------------------------------------------|
+//! | `integrity`       | unsigned modules, hot patches, `/dev/mem`-style access,   |
+//! |                   | debugfs and tracing writes                                |
+//! | `confidentiality` | the above, and reading kernel memory (`/proc/kcore`,      |
+//! |                   | debugfs, perf events counting kernel mode)                |
+//!
+//! The bootloader measures the level it requested into PCR 9 next to the
+//! command line, so attestation sees which lockdown the kernel ran under.
+
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// sysfs file of the level, relative to `/sys`
+pub const SYSFS_PATH: &str = "kernel/security/lockdown";
+
+/// Lockdown level, ordered from least to most restrictive
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LockdownLevel {
+    /// No restrictions
+    #[default]
+    None = 0,
+    /// The running kernel cannot be modified
+    Integrity = 1,
+    /// The running kernel can be neither modified nor read
+    Confidentiality = 2,
+}
+
+impl LockdownLevel {
+    /// Every level, in order
+    pub const ALL: [Self; 3] = [Self::None, Self::Integrity, Self::Confidentiality];
+
+    /// Parse the value of `lockdown=`; absent means [`Self::None`]
+    pub fn parse(value: Option<&str>) -> Option<Self> {
+        match value {
+            None | Some("none") => Some(Self::None),
+            Some("integrity") => Some(Self::Integrity),
+            Some("confidentiality") => Some(Self::Confidentiality),
+            Some(_) => None,
+        }
+    }
+
+    /// Name as used by `lockdown=` and sysfs
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Integrity => "integrity",
+            Self::Confidentiality => "confidentiality",
+        }
+    }
+
+    /// Level to boot with
+    ///
+    /// Secure Boot implies at least [`Self::Integrity`]: a verified kernel
+    /// that loads unverified code is not verified any more.
+    pub fn for_boot(secure_boot: bool, requested: Self) -> Self {
+        if secure_boot {
+            requested.max(Self::Integrity)
+        } else {
+            requested
+        }
+    }
+
+    const fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::None,
+            1 => Self::Integrity,
+            _ => Self::Confidentiality,
+        }
+    }
+}
+
+impl fmt::Display for LockdownLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Operation restricted under lockdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockdownReason {
+    /// Loading a module without a verified signature
+    UnsignedModule,
+    /// Replacing running kernel code (module hot swap, live patch)
+    HotPatch,
+    /// Raw physical memory or I/O port access (`/dev/mem`, `/dev/port`)
+    RawMemory,
+    /// Writing debugfs
+    DebugfsWrite,
+    /// Changing tracing (tracefs writes)
+    TracingWrite,
+    /// Reading kernel memory (`/proc/kcore`)
+    KernelRead,
+    /// Reading debugfs
+    Debugfs,
+    /// Perf events counting kernel mode
+    PerfKernel,
+}
+
+impl LockdownReason {
+    /// Lowest level that refuses the operation
+    pub const fn level(&self) -> LockdownLevel {
+        match self {
+            Self::UnsignedModule
+            | Self::HotPatch
+            | Self::RawMemory
+            | Self::DebugfsWrite
+            | Self::TracingWrite => LockdownLevel::Integrity,
+            Self::KernelRead | Self::Debugfs | Self::PerfKernel => LockdownLevel::Confidentiality,
+        }
+    }
+
+    /// What is refused, for the denial message
+    pub const fn description(&self) -> &'static str {
+        match self {
+            Self::UnsignedModule => "unsigned module loading",
+            Self::HotPatch => "kernel hot patching",
+            Self::RawMemory => "raw memory access",
+            Self::DebugfsWrite => "debugfs writes",
+            Self::TracingWrite => "tracing changes",
+            Self::KernelRead => "kernel memory reads",
+            Self::Debugfs => "debugfs access",
+            Self::PerfKernel => "kernel perf events",
+        }
+    }
+}
+
+/// An operation refused by the lockdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockdownError {
+    /// Refused operation
+    pub reason: LockdownReason,
+    /// Level in force
+    pub level: LockdownLevel,
+}
+
+impl fmt::Display for LockdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "lockdown: {} is restricted ({})", self.reason.description(), self.level)
+    }
+}
+
+// =============================================================================
+// State
+// =============================================================================
+
+/// Lockdown state of the system
+pub struct Lockdown {
+    level: AtomicU8,
+}
+
+impl Lockdown {
+    /// Create unlocked
+    pub const fn new() -> Self {
+        Self { level: AtomicU8::new(LockdownLevel::None as u8) }
+    }
+
+    /// Level in force
+    pub fn level(&self) -> LockdownLevel {
+        LockdownLevel::from_u8(self.level.load(Ordering::Acquire))
+    }
+
+    /// Raise the level to at least `level`; returns the level in force
+    ///
+    /// A lower level is ignored: lockdown cannot be lifted without a
+    /// reboot.
+    pub fn raise(&self, level: LockdownLevel) -> LockdownLevel {
+        let previous = self.level.fetch_max(level as u8, Ordering::AcqRel);
+        LockdownLevel::from_u8(previous).max(level)
+    }
+
+    /// Is `reason` allowed at the current level?
+    pub fn check(&self, reason: LockdownReason) -> Result<(), LockdownError> {
+        let level = self.level();
+        if level >= reason.level() {
+            Err(LockdownError { reason, level })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for Lockdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global lockdown state
+static LOCKDOWN: Lockdown = Lockdown::new();
+
+/// Get the lockdown state
+pub fn lockdown() -> &'static Lockdown {
+    &LOCKDOWN
+}
+
+/// Level in force
+pub fn level() -> LockdownLevel {
+    LOCKDOWN.level()
+}
+
+/// Enter the boot level, see [`LockdownLevel::for_boot`]
+pub fn init(secure_boot: bool, requested: LockdownLevel) -> LockdownLevel {
+    let level = LOCKDOWN.raise(LockdownLevel::for_boot(secure_boot, requested));
+    if level != LockdownLevel::None {
+        log::info!("Lockdown: kernel is locked down ({})", level);
+    }
+    level
+}
+
+/// Ask whether `reason` is allowed, logging a refusal
+pub fn check(reason: LockdownReason) -> Result<(), LockdownError> {
+    LOCKDOWN.check(reason).map_err(|e| {
+        log::warn!("{}", e);
+        e
+    })
+}
+
+/// Render `/sys/kernel/security/lockdown`, the current level bracketed
+pub fn render_sysfs(out: &mut dyn Write) -> fmt::Result {
+    render_levels(LOCKDOWN.level(), out)
+}
+
+/// Write `/sys/kernel/security/lockdown`; `None` if `value` names no level
+pub fn write_sysfs(value: &str) -> Option<LockdownLevel> {
+    let level = LockdownLevel::parse(Some(value.trim()))?;
+    Some(LOCKDOWN.raise(level))
+}
+
+fn render_levels(current: LockdownLevel, out: &mut dyn Write) -> fmt::Result {
+    for (i, level) in LockdownLevel::ALL.into_iter().enumerate() {
+        if i > 0 {
+            out.write_char(' ')?;
+        }
+        if level == current {
+            write!(out, "[{}]", level)?;
+        } else {
+            out.write_str(level.name())?;
+        }
+    }
+    out.write_char('\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_parse() {
+        assert_eq!(LockdownLevel::parse(None), Some(LockdownLevel::None));
+        assert_eq!(LockdownLevel::parse(Some("integrity")), Some(LockdownLevel::Integrity));
+        assert_eq!(
+            LockdownLevel::parse(Some("confidentiality")),
+            Some(LockdownLevel::Confidentiality)
+        );
+        assert_eq!(LockdownLevel::parse(Some("on")), None);
+        assert_eq!(LockdownLevel::for_boot(true, LockdownLevel::None), LockdownLevel::Integrity);
+        assert_eq!(
+            LockdownLevel::for_boot(true, LockdownLevel::Confidentiality),
+            LockdownLevel::Confidentiality
+        );
+        assert_eq!(LockdownLevel::for_boot(false, LockdownLevel::None), LockdownLevel::None);
+    }
+
+    #[test]
+    fn test_raise_only() {
+        let lockdown = Lockdown::new();
+        assert!(lockdown.check(LockdownReason::UnsignedModule).is_ok());
+        assert_eq!(lockdown.raise(LockdownLevel::Integrity), LockdownLevel::Integrity);
+        assert_eq!(
+            lockdown.check(LockdownReason::HotPatch),
+            Err(LockdownError { reason: LockdownReason::HotPatch, level: LockdownLevel::Integrity })
+        );
+        assert!(lockdown.check(LockdownReason::PerfKernel).is_ok());
+
+        // Cannot be lowered
+        assert_eq!(lockdown.raise(LockdownLevel::None), LockdownLevel::Integrity);
+        assert_eq!(lockdown.raise(LockdownLevel::Confidentiality), LockdownLevel::Confidentiality);
+        assert!(lockdown.check(LockdownReason::KernelRead).is_err());
+    }
+
+    #[test]
+    fn test_sysfs_rendering() {
+        let mut out = alloc::string::String::new();
+        render_levels(LockdownLevel::Integrity, &mut out).unwrap();
+        assert_eq!(out, "none [integrity] confidentiality\n");
+
+        let error = LockdownError { reason: LockdownReason::RawMemory, level: LockdownLevel::Integrity };
+        out.clear();
+        write!(out, "{}", error).unwrap();
+        assert_eq!(out, "lockdown: raw memory access is restricted (integrity)");
+    }
+}
@@ -0,0 +1,493 @@
+//! # Power Supplies
+//!
+//! Batteries and AC adapters, as ACPI describes them: an AC adapter
+//! (`ACPI0003`) reports whether external power is connected through
+//! `_PSR`; a control method battery (`PNP0C0A`) reports its design figures
+//! through `_BIX` (or the older `_BIF`) and its live state through `_BST`.
+//! Each device is a [`PowerSupply`]; [`status`] folds the registered ones
+//! into what the rest of the kernel cares about: are we on AC, and how much
+//! charge is left.
+//!
+//! The methods are evaluated by the ACPI interpreter, which hands them in
+//! as closures; integer fields of the returned packages come in order, and
+//! string fields at their end are dropped.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use spin::{Mutex, RwLock};
+
+use crate::{HalError, HalResult};
+
+/// Value ACPI uses for an unknown battery field
+pub const ACPI_UNKNOWN: u32 = 0xFFFF_FFFF;
+
+/// Kind of power supply, as reported in sysfs `type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSupplyType {
+    /// External power (AC adapter)
+    Mains,
+    /// Battery
+    Battery,
+}
+
+impl PowerSupplyType {
+    /// sysfs name
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Mains => "Mains",
+            Self::Battery => "Battery",
+        }
+    }
+}
+
+/// Battery charging state, as reported in sysfs `status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryStatus {
+    /// State not reported
+    Unknown,
+    /// Charging
+    Charging,
+    /// Powering the system
+    Discharging,
+    /// On external power but not charging (charge threshold, hot battery)
+    NotCharging,
+    /// Fully charged
+    Full,
+}
+
+impl BatteryStatus {
+    /// sysfs name
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Unknown => "Unknown",
+            Self::Charging => "Charging",
+            Self::Discharging => "Discharging",
+            Self::NotCharging => "Not charging",
+            Self::Full => "Full",
+        }
+    }
+}
+
+/// Unit of battery capacities and rates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapacityUnit {
+    /// Energy: capacities in mWh, rates in mW
+    MilliWatt,
+    /// Charge: capacities in mAh, rates in mA
+    MilliAmp,
+}
+
+/// Static battery figures, from `_BIX` or `_BIF`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryInfo {
+    /// Unit of capacities and rates
+    pub unit: CapacityUnit,
+    /// Capacity when new
+    pub design_capacity: u32,
+    /// Capacity at the last full charge
+    pub full_capacity: u32,
+    /// Nominal voltage (mV)
+    pub design_voltage: u32,
+    /// Capacity at which firmware raises its low battery warning
+    pub warning_capacity: u32,
+    /// Capacity at which firmware considers the battery critical
+    pub low_capacity: u32,
+    /// Charge cycles, if counted (`_BIX` only)
+    pub cycle_count: Option<u32>,
+}
+
+impl BatteryInfo {
+    /// Parse a `_BIF` package: unit, design capacity, last full capacity,
+    /// technology, design voltage, warning and low capacities
+    pub fn from_bif(bif: &[u32]) -> Option<Self> {
+        let fields = bif.get(..7)?;
+        Some(Self {
+            unit: if fields[0] == 0 { CapacityUnit::MilliWatt } else { CapacityUnit::MilliAmp },
+            design_capacity: fields[1],
+            full_capacity: fields[2],
+            design_voltage: fields[4],
+            warning_capacity: fields[5],
+            low_capacity: fields[6],
+            cycle_count: None,
+        })
+    }
+
+    /// Parse a `_BIX` package: a revision, the `_BIF` fields, then the
+    /// cycle count
+    pub fn from_bix(bix: &[u32]) -> Option<Self> {
+        let cycle_count = *bix.get(8)?;
+        Some(Self {
+            cycle_count: (cycle_count != ACPI_UNKNOWN).then_some(cycle_count),
+            ..Self::from_bif(&bix[1..])?
+        })
+    }
+
+    /// Capacity percentages are relative to this: last full charge, or the
+    /// design capacity when the battery has not reported one
+    pub fn reference_capacity(&self) -> Option<u32> {
+        [self.full_capacity, self.design_capacity].into_iter().find(|c| *c != 0 && *c != ACPI_UNKNOWN)
+    }
+}
+
+/// `_BST` state bit: discharging
+const BST_DISCHARGING: u32 = 1 << 0;
+/// `_BST` state bit: charging
+const BST_CHARGING: u32 = 1 << 1;
+/// `_BST` state bit: critical energy state
+const BST_CRITICAL: u32 = 1 << 2;
+
+/// Live battery state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryReading {
+    /// Static figures
+    pub info: BatteryInfo,
+    /// Charging state
+    pub status: BatteryStatus,
+    /// Firmware flagged the charge as critical
+    pub critical: bool,
+    /// Remaining capacity, if known
+    pub remaining: Option<u32>,
+    /// Charge or discharge rate, if known
+    pub rate: Option<u32>,
+    /// Present voltage (mV), if known
+    pub voltage: Option<u32>,
+}
+
+impl BatteryReading {
+    /// Combine `info` with a `_BST` package: state, present rate, remaining
+    /// capacity, present voltage
+    pub fn from_bst(info: BatteryInfo, bst: &[u32]) -> Option<Self> {
+        let fields = bst.get(..4)?;
+        let known = |value: u32| (value != ACPI_UNKNOWN).then_some(value);
+        let mut reading = Self {
+            info,
+            status: BatteryStatus::Unknown,
+            critical: fields[0] & BST_CRITICAL != 0,
+            rate: known(fields[1]),
+            remaining: known(fields[2]),
+            voltage: known(fields[3]),
+        };
+        reading.status = match fields[0] & (BST_CHARGING | BST_DISCHARGING) {
+            BST_CHARGING => BatteryStatus::Charging,
+            BST_DISCHARGING => BatteryStatus::Discharging,
+            0 if reading.capacity() == Some(100) => BatteryStatus::Full,
+            0 => BatteryStatus::NotCharging,
+            _ => BatteryStatus::Unknown,
+        };
+        Some(reading)
+    }
+
+    /// Remaining charge in percent of the last full charge
+    pub fn capacity(&self) -> Option<u8> {
+        let full = self.info.reference_capacity()? as u64;
+        let remaining = self.remaining? as u64;
+        Some(((remaining * 100 + full / 2) / full).min(100) as u8)
+    }
+
+    /// Charge or discharge power (mW), converting charge rates with the
+    /// present voltage
+    pub fn power_mw(&self) -> Option<u32> {
+        let rate = self.rate?;
+        match self.info.unit {
+            CapacityUnit::MilliWatt => Some(rate),
+            CapacityUnit::MilliAmp => Some((rate as u64 * self.voltage? as u64 / 1000) as u32),
+        }
+    }
+}
+
+/// A power supply
+pub trait PowerSupply: Send + Sync {
+    /// Device name (`AC`, `BAT0`, ...)
+    fn name(&self) -> &str;
+
+    /// Supply kind
+    fn supply_type(&self) -> PowerSupplyType;
+
+    /// For mains, whether external power is connected; for batteries,
+    /// whether one is inserted
+    fn online(&self) -> HalResult<bool>;
+
+    /// Battery state; `None` for mains and empty battery bays
+    fn battery(&self) -> HalResult<Option<BatteryReading>> {
+        Ok(None)
+    }
+}
+
+// =============================================================================
+// ACPI
+// =============================================================================
+
+/// Evaluate a method returning an integer (`_PSR`, `_STA`)
+pub type AcpiInteger = Box<dyn Fn() -> HalResult<u32> + Send + Sync>;
+
+/// Evaluate a method returning a package (`_BIX`, `_BIF`, `_BST`)
+pub type AcpiPackage = Box<dyn Fn() -> HalResult<Vec<u32>> + Send + Sync>;
+
+/// ACPI AC adapter
+pub struct AcpiAcAdapter {
+    name: String,
+    psr: AcpiInteger,
+}
+
+impl AcpiAcAdapter {
+    /// Create over the adapter's `_PSR` method
+    pub fn new(name: &str, psr: AcpiInteger) -> Self {
+        Self {
+            name: String::from(name),
+            psr,
+        }
+    }
+}
+
+impl PowerSupply for AcpiAcAdapter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supply_type(&self) -> PowerSupplyType {
+        PowerSupplyType::Mains
+    }
+
+    fn online(&self) -> HalResult<bool> {
+        Ok((self.psr)()? != 0)
+    }
+}
+
+/// Method reporting a battery's static figures
+pub enum AcpiBatteryInfo {
+    /// `_BIX` (ACPI 4.0 and later)
+    Bix(AcpiPackage),
+    /// `_BIF`
+    Bif(AcpiPackage),
+}
+
+/// `_STA` bit: battery present
+const STA_BATTERY_PRESENT: u32 = 1 << 4;
+
+/// ACPI control method battery
+///
+/// Static figures are read once and kept until
+/// [`refresh_info`](AcpiBattery::refresh_info), which the battery's `0x81`
+/// notification (information changed) should trigger.
+pub struct AcpiBattery {
+    name: String,
+    sta: AcpiInteger,
+    info_method: AcpiBatteryInfo,
+    bst: AcpiPackage,
+    info: Mutex<Option<BatteryInfo>>,
+}
+
+impl AcpiBattery {
+    /// Create over the battery's `_STA`, `_BIX` or `_BIF`, and `_BST` methods
+    pub fn new(name: &str, sta: AcpiInteger, info_method: AcpiBatteryInfo, bst: AcpiPackage) -> Self {
+        Self {
+            name: String::from(name),
+            sta,
+            info_method,
+            bst,
+            info: Mutex::new(None),
+        }
+    }
+
+    /// Forget the static figures; they are read again on next use
+    pub fn refresh_info(&self) {
+        *self.info.lock() = None;
+    }
+
+    /// Static figures, reading them if needed
+    pub fn info(&self) -> HalResult<BatteryInfo> {
+        let mut cached = self.info.lock();
+        if let Some(info) = *cached {
+            return Ok(info);
+        }
+        let info = match &self.info_method {
+            AcpiBatteryInfo::Bix(bix) => BatteryInfo::from_bix(&bix()?),
+            AcpiBatteryInfo::Bif(bif) => BatteryInfo::from_bif(&bif()?),
+        };
+        let info = info.ok_or(HalError::InvalidParameter)?;
+        *cached = Some(info);
+        Ok(info)
+    }
+}
+
+impl PowerSupply for AcpiBattery {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supply_type(&self) -> PowerSupplyType {
+        PowerSupplyType::Battery
+    }
+
+    fn online(&self) -> HalResult<bool> {
+        Ok((self.sta)()? & STA_BATTERY_PRESENT != 0)
+    }
+
+    fn battery(&self) -> HalResult<Option<BatteryReading>> {
+        if !self.online()? {
+            self.refresh_info();
+            return Ok(None);
+        }
+        let reading = BatteryReading::from_bst(self.info()?, &(self.bst)()?);
+        reading.ok_or(HalError::InvalidParameter).map(Some)
+    }
+}
+
+// =============================================================================
+// Registry
+// =============================================================================
+
+/// Registered power supplies, in registration order
+static SUPPLIES: RwLock<Vec<&'static dyn PowerSupply>> = RwLock::new(Vec::new());
+
+/// Register a power supply; names must be unique
+pub fn register(supply: &'static dyn PowerSupply) -> HalResult<()> {
+    let mut supplies = SUPPLIES.write();
+    if supplies.iter().any(|s| s.name() == supply.name()) {
+        return Err(HalError::ResourceBusy);
+    }
+    supplies.push(supply);
+    Ok(())
+}
+
+/// All registered power supplies
+pub fn supplies() -> Vec<&'static dyn PowerSupply> {
+    SUPPLIES.read().clone()
+}
+
+/// Power supply named `name`
+pub fn find(name: &str) -> Option<&'static dyn PowerSupply> {
+    SUPPLIES.read().iter().find(|s| s.name() == name).copied()
+}
+
+/// System power state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerStatus {
+    /// Running on external power
+    pub on_ac: bool,
+    /// Charge left across all batteries (percent); `None` without battery
+    pub battery: Option<u8>,
+    /// Some battery is discharging
+    pub discharging: bool,
+    /// Firmware flagged some battery as critical
+    pub critical: bool,
+}
+
+/// Current system power state
+///
+/// Supplies that fail to answer are skipped. Without an AC adapter, the
+/// system counts as on AC unless a battery is discharging. The charge left
+/// is the total remaining capacity over the total full capacity, so a
+/// small empty battery weighs less than a large full one.
+pub fn status() -> PowerStatus {
+    let mut adapters = 0;
+    let mut online = false;
+    let (mut remaining, mut full, mut batteries) = (0u64, 0u64, 0);
+    let mut status = PowerStatus { on_ac: false, battery: None, discharging: false, critical: false };
+
+    for supply in supplies() {
+        match supply.supply_type() {
+            PowerSupplyType::Mains => {
+                adapters += 1;
+                online |= supply.online().unwrap_or(false);
+            }
+            PowerSupplyType::Battery => {
+                let Ok(Some(reading)) = supply.battery() else {
+                    continue;
+                };
+                status.discharging |= reading.status == BatteryStatus::Discharging;
+                status.critical |= reading.critical;
+                if let (Some(left), Some(total)) = (reading.remaining, reading.info.reference_capacity()) {
+                    remaining += left.min(total) as u64;
+                    full += total as u64;
+                    batteries += 1;
+                }
+            }
+        }
+    }
+
+    status.on_ac = if adapters > 0 { online } else { !status.discharging };
+    if batteries > 0 {
+        status.battery = Some(((remaining * 100 + full / 2) / full) as u8);
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_battery_packages() {
+        let bif = [1, 4400, 4000, 1, 11100, 400, 200, 10, 10];
+        let info = BatteryInfo::from_bif(&bif).unwrap();
+        assert_eq!(info.unit, CapacityUnit::MilliAmp);
+        assert_eq!(info.reference_capacity(), Some(4000));
+        assert_eq!(info.cycle_count, None);
+
+        let bix = [0, 0, 57000, 52000, 1, 11400, 5200, 2600, 312];
+        let info = BatteryInfo::from_bix(&bix).unwrap();
+        assert_eq!(info.unit, CapacityUnit::MilliWatt);
+        assert_eq!(info.cycle_count, Some(312));
+        assert!(BatteryInfo::from_bix(&bix[..8]).is_none());
+
+        let reading = BatteryReading::from_bst(info, &[BST_DISCHARGING, 7800, 13000, 11900]).unwrap();
+        assert_eq!(reading.status, BatteryStatus::Discharging);
+        assert_eq!(reading.capacity(), Some(25));
+        assert_eq!(reading.power_mw(), Some(7800));
+
+        let full = BatteryReading::from_bst(info, &[0, 0, 52000, 12600]).unwrap();
+        assert_eq!(full.status, BatteryStatus::Full);
+        let unknown = BatteryReading::from_bst(info, &[BST_CHARGING, ACPI_UNKNOWN, ACPI_UNKNOWN, 12000]).unwrap();
+        assert_eq!(unknown.status, BatteryStatus::Charging);
+        assert_eq!((unknown.capacity(), unknown.power_mw()), (None, None));
+
+        let charge = BatteryInfo::from_bif(&bif).unwrap();
+        let reading = BatteryReading::from_bst(charge, &[BST_CHARGING, 1500, 1000, 12000]).unwrap();
+        assert_eq!(reading.power_mw(), Some(18000));
+    }
+
+    #[test]
+    fn test_acpi_supplies() {
+        let psr = Arc::new(AtomicU32::new(0));
+        let sta = Arc::new(AtomicU32::new(0x1F));
+        let evaluations = Arc::new(AtomicU32::new(0));
+
+        let (psr_value, sta_value, count) = (psr.clone(), sta.clone(), evaluations.clone());
+        let ac = AcpiAcAdapter::new("AC", Box::new(move || Ok(psr_value.load(Ordering::Relaxed))));
+        let battery = AcpiBattery::new(
+            "BAT0",
+            Box::new(move || Ok(sta_value.load(Ordering::Relaxed))),
+            AcpiBatteryInfo::Bix(Box::new(move || {
+                count.fetch_add(1, Ordering::Relaxed);
+                Ok(vec![0, 0, 50000, 40000, 1, 11400, 4000, 2000, ACPI_UNKNOWN])
+            })),
+            Box::new(|| Ok(vec![BST_DISCHARGING, 9000, 10000, 11000])),
+        );
+        let battery: &'static AcpiBattery = Box::leak(Box::new(battery));
+        register(Box::leak(Box::new(ac))).unwrap();
+        register(battery).unwrap();
+        assert_eq!(register(battery), Err(HalError::ResourceBusy));
+
+        assert_eq!(find("AC").map(|s| s.supply_type()), Some(PowerSupplyType::Mains));
+        assert_eq!(battery.battery().unwrap().unwrap().capacity(), Some(25));
+        assert_eq!(
+            status(),
+            PowerStatus { on_ac: false, battery: Some(25), discharging: true, critical: false }
+        );
+        assert_eq!(evaluations.load(Ordering::Relaxed), 1);
+
+        battery.refresh_info();
+        psr.store(1, Ordering::Relaxed);
+        assert!(status().on_ac);
+        assert_eq!(evaluations.load(Ordering::Relaxed), 2);
+
+        sta.store(0x0F, Ordering::Relaxed);
+        assert_eq!(battery.battery(), Ok(None));
+        assert_eq!(status().battery, None);
+    }
+}
@@ -0,0 +1,162 @@
+//! # Machine Quirks
+//!
+//! Workarounds for specific machines, keyed by their DMI identification.
+//! Each [`QuirkEntry`] lists the fields to match and the [`Quirks`] it
+//! enables; [`dmi::init`](crate::dmi::init) matches the table once, and
+//! subsystems ask [`has`] before applying a workaround instead of comparing
+//! vendor strings themselves.
+//!
+//! Adding a machine means adding an entry to [`QUIRK_TABLE`], with the
+//! symptom in its comment.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::dmi::{Dmi, DmiField};
+
+bitflags::bitflags! {
+    /// Workarounds a machine needs
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Quirks: u32 {
+        /// NVMe autonomous power state transitions hang the controller
+        const NVME_NO_APST = 1 << 0;
+        /// The deepest NVMe power state does not wake up reliably
+        const NVME_NO_DEEPEST_PS = 1 << 1;
+        /// NVMe controllers must be shut down rather than suspended
+        const NVME_NO_SUSPEND = 1 << 2;
+        /// The HPET counter stalls or drifts; do not use it
+        const HPET_BROKEN = 1 << 3;
+    }
+}
+
+/// A DMI field condition
+#[derive(Debug, Clone, Copy)]
+pub enum DmiMatch {
+    /// Field contains the string
+    Contains(DmiField, &'static str),
+    /// Field equals the string
+    Exact(DmiField, &'static str),
+}
+
+impl DmiMatch {
+    /// Does `dmi` satisfy the condition? Missing fields never match.
+    pub fn matches(&self, dmi: &Dmi) -> bool {
+        match *self {
+            Self::Contains(field, value) => dmi.get(field).is_some_and(|v| v.contains(value)),
+            Self::Exact(field, value) => dmi.get(field) == Some(value),
+        }
+    }
+}
+
+/// Machines sharing a set of quirks
+#[derive(Debug, Clone, Copy)]
+pub struct QuirkEntry {
+    /// Machine description, for the log
+    pub ident: &'static str,
+    /// Conditions, all of which must hold
+    pub matches: &'static [DmiMatch],
+    /// Quirks the machine needs
+    pub quirks: Quirks,
+}
+
+impl QuirkEntry {
+    /// Does the entry describe the machine identified by `dmi`?
+    pub fn matches(&self, dmi: &Dmi) -> bool {
+        !self.matches.is_empty() && self.matches.iter().all(|m| m.matches(dmi))
+    }
+}
+
+/// Known machines
+pub static QUIRK_TABLE: &[QuirkEntry] = &[
+    // Samsung SM951/PM951 drives shipped in this model fail to leave the
+    // deepest power state
+    QuirkEntry {
+        ident: "Dell XPS 15 9550",
+        matches: &[
+            DmiMatch::Contains(DmiField::SysVendor, "Dell Inc."),
+            DmiMatch::Contains(DmiField::ProductName, "XPS 15 9550"),
+        ],
+        quirks: Quirks::NVME_NO_DEEPEST_PS,
+    },
+    // Controllers drop off the bus after an APST transition on these boards
+    QuirkEntry {
+        ident: "ASUS PRIME B350M-A",
+        matches: &[
+            DmiMatch::Exact(DmiField::BoardVendor, "ASUSTeK COMPUTER INC."),
+            DmiMatch::Exact(DmiField::BoardName, "PRIME B350M-A"),
+        ],
+        quirks: Quirks::NVME_NO_APST,
+    },
+    QuirkEntry {
+        ident: "ASUS PRIME Z370-A",
+        matches: &[
+            DmiMatch::Exact(DmiField::BoardVendor, "ASUSTeK COMPUTER INC."),
+            DmiMatch::Exact(DmiField::BoardName, "PRIME Z370-A"),
+        ],
+        quirks: Quirks::NVME_NO_APST,
+    },
+];
+
+/// Quirks of every entry in `table` matching `dmi`
+pub fn lookup(table: &[QuirkEntry], dmi: &Dmi) -> Quirks {
+    table
+        .iter()
+        .filter(|entry| entry.matches(dmi))
+        .fold(Quirks::empty(), |quirks, entry| quirks | entry.quirks)
+}
+
+/// Quirks of the running machine
+static ACTIVE: AtomicU32 = AtomicU32::new(0);
+
+/// Enable the quirks [`QUIRK_TABLE`] lists for `dmi`
+pub(crate) fn apply(dmi: &Dmi) -> Quirks {
+    for entry in QUIRK_TABLE.iter().filter(|entry| entry.matches(dmi)) {
+        log::info!("DMI: {} quirks: {:?}", entry.ident, entry.quirks);
+    }
+    let quirks = lookup(QUIRK_TABLE, dmi);
+    ACTIVE.fetch_or(quirks.bits(), Ordering::AcqRel);
+    quirks
+}
+
+/// Enable quirks by hand (command line, driver knowledge)
+pub fn force(quirks: Quirks) {
+    ACTIVE.fetch_or(quirks.bits(), Ordering::AcqRel);
+}
+
+/// Quirks of the running machine
+pub fn active() -> Quirks {
+    Quirks::from_bits_truncate(ACTIVE.load(Ordering::Acquire))
+}
+
+/// Does the running machine need all of `quirks`?
+pub fn has(quirks: Quirks) -> bool {
+    active().contains(quirks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmi::tests::{push_structure, xps_table};
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_quirk_table() {
+        let xps = Dmi::from_table((3, 0), &xps_table());
+        assert_eq!(lookup(QUIRK_TABLE, &xps), Quirks::NVME_NO_DEEPEST_PS);
+
+        let mut board = Vec::new();
+        push_structure(&mut board, 2, &[1, 2], &["ASUSTeK COMPUTER INC.", "PRIME Z370-A"]);
+        let asus = Dmi::from_table((3, 0), &board);
+        assert_eq!(lookup(QUIRK_TABLE, &asus), Quirks::NVME_NO_APST);
+
+        let mut board = Vec::new();
+        push_structure(&mut board, 2, &[1, 2], &["ASUSTeK COMPUTER INC.", "PRIME Z370-A II"]);
+        assert!(lookup(QUIRK_TABLE, &Dmi::from_table((3, 0), &board)).is_empty());
+        assert!(lookup(QUIRK_TABLE, &Dmi::default()).is_empty());
+
+        crate::dmi::init(xps);
+        assert!(has(Quirks::NVME_NO_DEEPEST_PS));
+        assert!(!has(Quirks::HPET_BROKEN));
+        force(Quirks::HPET_BROKEN);
+        assert!(has(Quirks::HPET_BROKEN | Quirks::NVME_NO_DEEPEST_PS));
+    }
+}
@@ -0,0 +1,368 @@
+//! # Speculative Execution Mitigations
+//!
+//! CPU vulnerabilities to speculative execution attacks and the
+//! mitigations applied against them.
+//!
+//! The architecture code detects which vulnerabilities the CPU has,
+//! chooses mitigations according to the [`MitigationPolicy`] given by
+//! the `mitigations=` boot parameter, and records the outcome here with
+//! [`Mitigations::set_state`]. The state is exposed read-only under
+//! `/sys`, one file per vulnerability, in the format Linux uses:
+//!
+//! ```text
+//! /sys/devices/system/cpu/vulnerabilities/meltdown     Mitigation: PTI
+//! /sys/devices/system/cpu/vulnerabilities/spectre_v2   Mitigation: Enhanced IBRS; IBPB: conditional
+//! /sys/devices/system/cpu/vulnerabilities/mds          Not affected
+//! ```
+//!
+//! `mitigations=off` leaves everything open, for benchmarking.
+
+use core::fmt::{self, Write};
+use spin::RwLock;
+
+/// Directory of the vulnerability files, relative to `/sys`
+pub const SYSFS_DIR: &str = "devices/system/cpu/vulnerabilities";
+
+/// Speculative execution vulnerability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vulnerability {
+    /// Rogue data cache load (kernel memory read from user mode)
+    Meltdown,
+    /// Bounds check bypass
+    SpectreV1,
+    /// Branch target injection
+    SpectreV2,
+    /// Speculative store bypass
+    SpecStoreBypass,
+    /// Microarchitectural data sampling
+    Mds,
+    /// TSX asynchronous abort
+    TsxAsyncAbort,
+}
+
+impl Vulnerability {
+    /// Number of tracked vulnerabilities
+    pub const COUNT: usize = 6;
+
+    /// Every tracked vulnerability
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::Meltdown,
+        Self::SpectreV1,
+        Self::SpectreV2,
+        Self::SpecStoreBypass,
+        Self::Mds,
+        Self::TsxAsyncAbort,
+    ];
+
+    /// sysfs file name
+    pub const fn sysfs_name(&self) -> &'static str {
+        match self {
+            Self::Meltdown => "meltdown",
+            Self::SpectreV1 => "spectre_v1",
+            Self::SpectreV2 => "spectre_v2",
+            Self::SpecStoreBypass => "spec_store_bypass",
+            Self::Mds => "mds",
+            Self::TsxAsyncAbort => "tsx_async_abort",
+        }
+    }
+
+    /// Look up by sysfs file name
+    pub fn from_sysfs_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.sysfs_name() == name)
+    }
+
+    const fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// How indirect branches are protected (Spectre v2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndirectBranchMode {
+    /// Kernel built with retpoline thunks
+    Retpoline,
+    /// Indirect branch restricted speculation, always on
+    Ibrs,
+    /// Enhanced IBRS, set once at boot
+    EnhancedIbrs,
+}
+
+impl IndirectBranchMode {
+    /// Name as reported in sysfs
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Retpoline => "Retpolines",
+            Self::Ibrs => "IBRS",
+            Self::EnhancedIbrs => "Enhanced IBRS",
+        }
+    }
+}
+
+/// An applied mitigation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mitigation {
+    /// Page table isolation: user mode runs on page tables without the kernel
+    Pti,
+    /// Index masking of user-controlled array accesses
+    PointerSanitization,
+    /// Indirect branch protection
+    IndirectBranch {
+        /// Protection of kernel indirect branches
+        mode: IndirectBranchMode,
+        /// Barrier when switching to another user task
+        ibpb: bool,
+        /// Single-thread indirect branch predictors
+        stibp: bool,
+    },
+    /// Store bypass disabled in hardware
+    StoreBypassDisabled,
+    /// CPU buffers cleared on return to user mode
+    ClearCpuBuffers,
+}
+
+impl fmt::Display for Mitigation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pti => write!(f, "PTI"),
+            Self::PointerSanitization => write!(f, "__user pointer sanitization"),
+            Self::IndirectBranch { mode, ibpb, stibp } => {
+                write!(f, "{}", mode.name())?;
+                if *ibpb {
+                    write!(f, "; IBPB: conditional")?;
+                }
+                if *stibp {
+                    write!(f, "; STIBP: forced")?;
+                }
+                Ok(())
+            }
+            Self::StoreBypassDisabled => write!(f, "Speculative Store Bypass disabled"),
+            Self::ClearCpuBuffers => write!(f, "Clear CPU buffers"),
+        }
+    }
+}
+
+/// Exposure of the system to a vulnerability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VulnerabilityState {
+    /// Not yet determined
+    Unknown,
+    /// The CPU is not affected
+    NotAffected,
+    /// Affected and unmitigated
+    Vulnerable,
+    /// Affected and mitigated
+    Mitigated(Mitigation),
+}
+
+impl fmt::Display for VulnerabilityState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "Unknown"),
+            Self::NotAffected => write!(f, "Not affected"),
+            Self::Vulnerable => write!(f, "Vulnerable"),
+            Self::Mitigated(mitigation) => write!(f, "Mitigation: {}", mitigation),
+        }
+    }
+}
+
+// =============================================================================
+// Policy
+// =============================================================================
+
+/// Boot-time mitigation policy (`mitigations=`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MitigationPolicy {
+    /// Mitigate everything the CPU is affected by
+    #[default]
+    Auto,
+    /// As `Auto`, and keep SMT siblings offline on affected CPUs
+    AutoNoSmt,
+    /// Mitigate nothing
+    Off,
+}
+
+impl MitigationPolicy {
+    /// Parse the value of `mitigations=`; absent means [`Self::Auto`]
+    pub fn parse(value: Option<&str>) -> Option<Self> {
+        match value {
+            None | Some("auto") => Some(Self::Auto),
+            Some("auto,nosmt") => Some(Self::AutoNoSmt),
+            Some("off") => Some(Self::Off),
+            Some(_) => None,
+        }
+    }
+
+    /// Mitigations are applied
+    pub const fn is_enabled(&self) -> bool {
+        !matches!(self, Self::Off)
+    }
+
+    /// SMT must stay off when the CPU is affected by cross-thread leaks
+    pub const fn disables_smt(&self) -> bool {
+        matches!(self, Self::AutoNoSmt)
+    }
+}
+
+// =============================================================================
+// State
+// =============================================================================
+
+/// Mitigation state of the system
+///
+/// Recorded by the boot CPU; all CPUs are assumed identical.
+pub struct Mitigations {
+    policy: RwLock<MitigationPolicy>,
+    states: RwLock<[VulnerabilityState; Vulnerability::COUNT]>,
+}
+
+impl Mitigations {
+    /// Create with nothing determined
+    pub const fn new() -> Self {
+        Self {
+            policy: RwLock::new(MitigationPolicy::Auto),
+            states: RwLock::new([VulnerabilityState::Unknown; Vulnerability::COUNT]),
+        }
+    }
+
+    /// Record the boot policy
+    pub fn set_policy(&self, policy: MitigationPolicy) {
+        *self.policy.write() = policy;
+    }
+
+    /// Boot policy
+    pub fn policy(&self) -> MitigationPolicy {
+        *self.policy.read()
+    }
+
+    /// Record the state of a vulnerability
+    pub fn set_state(&self, vulnerability: Vulnerability, state: VulnerabilityState) {
+        self.states.write()[vulnerability.index()] = state;
+    }
+
+    /// State of a vulnerability
+    pub fn state(&self, vulnerability: Vulnerability) -> VulnerabilityState {
+        self.states.read()[vulnerability.index()]
+    }
+
+    /// Mitigation applied against a vulnerability, if any
+    pub fn mitigation(&self, vulnerability: Vulnerability) -> Option<Mitigation> {
+        match self.state(vulnerability) {
+            VulnerabilityState::Mitigated(mitigation) => Some(mitigation),
+            _ => None,
+        }
+    }
+
+    /// Affected vulnerabilities left unmitigated
+    pub fn vulnerable(&self) -> impl Iterator<Item = Vulnerability> + '_ {
+        Vulnerability::ALL
+            .into_iter()
+            .filter(|v| self.state(*v) == VulnerabilityState::Vulnerable)
+    }
+}
+
+impl Default for Mitigations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global mitigation state
+static MITIGATIONS: Mitigations = Mitigations::new();
+
+/// Get the mitigation state
+pub fn mitigations() -> &'static Mitigations {
+    &MITIGATIONS
+}
+
+/// Render `/sys/devices/system/cpu/vulnerabilities/<name>`
+pub fn render_sysfs(vulnerability: Vulnerability, out: &mut dyn Write) -> fmt::Result {
+    writeln!(out, "{}", MITIGATIONS.state(vulnerability))
+}
+
+/// Log the state of every vulnerability
+pub fn log_status() {
+    for vulnerability in Vulnerability::ALL {
+        log::info!(
+            "Speculation: {}: {}",
+            vulnerability.sysfs_name(),
+            MITIGATIONS.state(vulnerability)
+        );
+    }
+}
+
+// =============================================================================
+// Bounds Check Bypass
+// =============================================================================
+
+/// Mask that is all ones when `index < size` and zero otherwise
+///
+/// Computed without a branch, so a mispredicted bounds check cannot
+/// speculatively use an out-of-range index. Both values must be below
+/// `isize::MAX`.
+#[inline(always)]
+pub fn array_index_mask_nospec(index: usize, size: usize) -> usize {
+    let spread = (index | size.wrapping_sub(1).wrapping_sub(index)) as isize;
+    (!spread >> (usize::BITS - 1)) as usize
+}
+
+/// Clamp a bounds-checked, user-controlled index under speculation
+///
+/// Use after the bounds check: `index` comes back unchanged when in
+/// range and as 0 otherwise, even on a mispredicted path.
+#[inline(always)]
+pub fn array_index_nospec(index: usize, size: usize) -> usize {
+    index & array_index_mask_nospec(core::hint::black_box(index), size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_parse() {
+        assert_eq!(MitigationPolicy::parse(None), Some(MitigationPolicy::Auto));
+        assert_eq!(MitigationPolicy::parse(Some("off")), Some(MitigationPolicy::Off));
+        assert_eq!(
+            MitigationPolicy::parse(Some("auto,nosmt")),
+            Some(MitigationPolicy::AutoNoSmt)
+        );
+        assert_eq!(MitigationPolicy::parse(Some("on")), None);
+        assert!(!MitigationPolicy::Off.is_enabled());
+        assert!(MitigationPolicy::AutoNoSmt.disables_smt());
+    }
+
+    #[test]
+    fn test_array_index_nospec() {
+        assert_eq!(array_index_nospec(3, 8), 3);
+        assert_eq!(array_index_nospec(0, 1), 0);
+        assert_eq!(array_index_nospec(8, 8), 0);
+        assert_eq!(array_index_nospec(usize::MAX / 4, 8), 0);
+        assert_eq!(array_index_mask_nospec(7, 8), usize::MAX);
+        assert_eq!(array_index_mask_nospec(9, 8), 0);
+    }
+
+    #[test]
+    fn test_state_rendering() {
+        let mitigations = Mitigations::new();
+        mitigations.set_state(Vulnerability::Meltdown, VulnerabilityState::Mitigated(Mitigation::Pti));
+        mitigations.set_state(
+            Vulnerability::SpectreV2,
+            VulnerabilityState::Mitigated(Mitigation::IndirectBranch {
+                mode: IndirectBranchMode::Ibrs,
+                ibpb: true,
+                stibp: true,
+            }),
+        );
+        mitigations.set_state(Vulnerability::Mds, VulnerabilityState::Vulnerable);
+
+        let mut out = alloc::string::String::new();
+        write!(out, "{}", mitigations.state(Vulnerability::SpectreV2)).unwrap();
+        assert_eq!(out, "Mitigation: IBRS; IBPB: conditional; STIBP: forced");
+        assert_eq!(mitigations.mitigation(Vulnerability::Meltdown), Some(Mitigation::Pti));
+        assert!(mitigations.vulnerable().eq([Vulnerability::Mds]));
+        assert_eq!(
+            Vulnerability::from_sysfs_name("spec_store_bypass"),
+            Some(Vulnerability::SpecStoreBypass)
+        );
+    }
+}
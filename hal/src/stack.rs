@@ -0,0 +1,276 @@
+//! # Guarded Kernel Stacks
+//!
+//! Architecture-independent layout of the per-CPU stack area: the kernel
+//! stack, a dedicated IRQ stack and the exception stacks, each with a guard
+//! page directly below it.
+//!
+//! ## Layout
+//!
+//! ```text
+//! base                                                             base + size
+//! ┌───────┬──────────────┬───────┬──────────────┬───────┬──────────────┐
+//! │ guard │   stack 0    │ guard │   stack 1    │ guard │   stack N    │
+//! │ 4 KB  │ ◄── grows    │ 4 KB  │ ◄── grows    │ 4 KB  │ ◄── grows    │
+//! └───────┴──────────────┴───────┴──────────────┴───────┴──────────────┘
+//! ```
+//!
+//! Stacks grow down, so an overflow runs into the guard page of the same
+//! stack. Guard pages are unmapped by the memory manager; the resulting
+//! fault is turned into a readable report by [`StackLayout::locate`] instead
+//! of silently corrupting the neighbouring stack.
+
+use core::fmt;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Guard page size
+pub const GUARD_PAGE_SIZE: usize = 4096;
+
+/// Default IRQ stack size (16 KB)
+pub const IRQ_STACK_SIZE: usize = 16 * 1024;
+
+// =============================================================================
+// STACK KIND
+// =============================================================================
+
+/// Purpose of a stack in the per-CPU area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackKind {
+    /// Kernel stack (syscalls and kernel threads)
+    Kernel,
+    /// Hardware interrupts
+    Irq,
+    /// Synchronous exceptions (AArch64 SP_EL1)
+    Exception,
+    /// Double fault (x86_64 IST1)
+    DoubleFault,
+    /// Non-maskable interrupt (x86_64 IST2)
+    Nmi,
+    /// Machine check (x86_64 IST3)
+    MachineCheck,
+    /// Debug exception (x86_64 IST4)
+    Debug,
+}
+
+impl StackKind {
+    /// Human-readable name
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Kernel => "kernel",
+            Self::Irq => "IRQ",
+            Self::Exception => "exception",
+            Self::DoubleFault => "double-fault",
+            Self::Nmi => "NMI",
+            Self::MachineCheck => "machine-check",
+            Self::Debug => "debug",
+        }
+    }
+}
+
+/// A stack slot in the per-CPU area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackSlot {
+    /// Stack purpose
+    pub kind: StackKind,
+    /// Usable size in bytes (multiple of [`GUARD_PAGE_SIZE`])
+    pub size: usize,
+}
+
+impl StackSlot {
+    /// Create a stack slot
+    pub const fn new(kind: StackKind, size: usize) -> Self {
+        assert!(size % GUARD_PAGE_SIZE == 0, "stack size must be page aligned");
+        Self { kind, size }
+    }
+}
+
+// =============================================================================
+// STACK LAYOUT
+// =============================================================================
+
+/// Where an address falls within the per-CPU stack area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackHit {
+    /// CPU owning the stack
+    pub cpu: usize,
+    /// Stack the address belongs to
+    pub kind: StackKind,
+    /// Address is in the guard page below the stack
+    pub in_guard: bool,
+    /// Bytes of the stack still free below the address (0 in the guard)
+    pub remaining: usize,
+}
+
+impl fmt::Display for StackHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.in_guard {
+            write!(f, "{} stack overflow on CPU {}", self.kind.name(), self.cpu)
+        } else {
+            write!(
+                f,
+                "{} stack of CPU {} ({} bytes left)",
+                self.kind.name(),
+                self.cpu,
+                self.remaining
+            )
+        }
+    }
+}
+
+/// Per-CPU guarded stack layout
+#[derive(Debug, Clone, Copy)]
+pub struct StackLayout<const N: usize> {
+    slots: [StackSlot; N],
+}
+
+impl<const N: usize> StackLayout<N> {
+    /// Create a layout from its stack slots, lowest address first
+    pub const fn new(slots: [StackSlot; N]) -> Self {
+        Self { slots }
+    }
+
+    /// Total size of one CPU's area, guard pages included
+    pub const fn size(&self) -> usize {
+        let mut total = 0;
+        let mut i = 0;
+        while i < N {
+            total += GUARD_PAGE_SIZE + self.slots[i].size;
+            i += 1;
+        }
+        total
+    }
+
+    /// Stack slots, lowest address first
+    pub const fn slots(&self) -> &[StackSlot; N] {
+        &self.slots
+    }
+
+    /// Offset of a slot's guard page from the area base
+    const fn guard_offset(&self, index: usize) -> usize {
+        let mut offset = 0;
+        let mut i = 0;
+        while i < index {
+            offset += GUARD_PAGE_SIZE + self.slots[i].size;
+            i += 1;
+        }
+        offset
+    }
+
+    fn index_of(&self, kind: StackKind) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.kind == kind)
+    }
+
+    /// Lowest usable address of a stack
+    pub fn bottom(&self, base: u64, kind: StackKind) -> Option<u64> {
+        let index = self.index_of(kind)?;
+        Some(base + (self.guard_offset(index) + GUARD_PAGE_SIZE) as u64)
+    }
+
+    /// Initial stack pointer of a stack (16-byte aligned)
+    pub fn top(&self, base: u64, kind: StackKind) -> Option<u64> {
+        let index = self.index_of(kind)?;
+        let bottom = self.bottom(base, kind)?;
+        Some((bottom + self.slots[index].size as u64) & !0xF)
+    }
+
+    /// Guard page addresses of one CPU's area
+    pub fn guard_pages(&self, base: u64) -> impl Iterator<Item = u64> {
+        let layout = *self;
+        (0..N).map(move |i| base + layout.guard_offset(i) as u64)
+    }
+
+    /// Locate an address in the stack areas of `cpu_count` CPUs
+    ///
+    /// `area_base` is the start of CPU 0's area; CPU areas are contiguous.
+    pub fn locate(&self, area_base: u64, cpu_count: usize, addr: u64) -> Option<StackHit> {
+        let size = self.size() as u64;
+        let offset = addr.checked_sub(area_base)?;
+        let cpu = (offset / size) as usize;
+        if cpu >= cpu_count {
+            return None;
+        }
+
+        let mut within = (offset % size) as usize;
+        for slot in &self.slots {
+            if within < GUARD_PAGE_SIZE {
+                return Some(StackHit {
+                    cpu,
+                    kind: slot.kind,
+                    in_guard: true,
+                    remaining: 0,
+                });
+            }
+            within -= GUARD_PAGE_SIZE;
+
+            if within < slot.size {
+                return Some(StackHit {
+                    cpu,
+                    kind: slot.kind,
+                    in_guard: false,
+                    remaining: within,
+                });
+            }
+            within -= slot.size;
+        }
+
+        None
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: StackLayout<2> = StackLayout::new([
+        StackSlot::new(StackKind::Kernel, 16 * 1024),
+        StackSlot::new(StackKind::Irq, 8 * 1024),
+    ]);
+
+    #[test]
+    fn test_layout_size() {
+        assert_eq!(LAYOUT.size(), 2 * GUARD_PAGE_SIZE + 24 * 1024);
+    }
+
+    #[test]
+    fn test_top_and_bottom() {
+        let base = 0x10_0000;
+        assert_eq!(LAYOUT.bottom(base, StackKind::Kernel), Some(base + 0x1000));
+        assert_eq!(LAYOUT.top(base, StackKind::Kernel), Some(base + 0x5000));
+        assert_eq!(LAYOUT.bottom(base, StackKind::Irq), Some(base + 0x6000));
+        assert_eq!(LAYOUT.top(base, StackKind::Irq), Some(base + 0x8000));
+        assert_eq!(LAYOUT.top(base, StackKind::Nmi), None);
+    }
+
+    #[test]
+    fn test_guard_pages() {
+        let mut guards = LAYOUT.guard_pages(0x10_0000);
+        assert_eq!(guards.next(), Some(0x10_0000));
+        assert_eq!(guards.next(), Some(0x10_5000));
+        assert_eq!(guards.next(), None);
+    }
+
+    #[test]
+    fn test_locate() {
+        let base = 0x10_0000;
+        let size = LAYOUT.size() as u64;
+
+        let hit = LAYOUT.locate(base, 2, base + size + 0x5008).unwrap();
+        assert_eq!(hit.cpu, 1);
+        assert_eq!(hit.kind, StackKind::Irq);
+        assert!(hit.in_guard);
+
+        let hit = LAYOUT.locate(base, 2, base + 0x1100).unwrap();
+        assert_eq!(hit.kind, StackKind::Kernel);
+        assert!(!hit.in_guard);
+        assert_eq!(hit.remaining, 0x100);
+
+        assert_eq!(LAYOUT.locate(base, 2, base - 1), None);
+        assert_eq!(LAYOUT.locate(base, 2, base + 2 * size), None);
+    }
+}
@@ -0,0 +1,205 @@
+//! # Hybrid CPU Topology
+//!
+//! Core types of hybrid CPUs (Intel P-cores/E-cores, ARM big.LITTLE),
+//! shared by the HAL and the scheduler.
+//!
+//! Each CPU records its own core type while it comes up, from CPUID leaf
+//! 0x1A on x86_64 or MIDR_EL1 on AArch64. Platforms that describe core
+//! performance instead (the device tree `capacity-dmips-mhz` property)
+//! record capacities and call [`HybridTopology::classify_by_capacity`].
+//!
+//! Capacities are normalized so the fastest core is 1024.
+
+use core::sync::atomic::{AtomicU16, AtomicU8, Ordering};
+
+/// CPUs tracked by the topology
+pub const MAX_TOPOLOGY_CPUS: usize = 64;
+
+/// Capacity of the fastest core
+pub const MAX_CAPACITY: u16 = 1024;
+
+/// Cores below this share of the fastest core are efficiency cores (percent)
+const EFFICIENCY_THRESHOLD_PERCENT: u32 = 80;
+
+/// No core type recorded
+const CORE_TYPE_UNKNOWN: u8 = 0xFF;
+
+/// Kind of core
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum CoreType {
+    /// Performance core (P-core, big)
+    Performance = 0,
+    /// Efficiency core (E-core, LITTLE)
+    Efficiency = 1,
+}
+
+/// Core type from CPUID leaf 0x1A EAX
+///
+/// Only meaningful when CPUID leaf 7 reports a hybrid part.
+pub fn core_type_from_cpuid(eax: u32) -> Option<CoreType> {
+    match eax >> 24 {
+        0x20 => Some(CoreType::Efficiency),
+        0x40 => Some(CoreType::Performance),
+        _ => None,
+    }
+}
+
+/// Core type from MIDR_EL1, for Arm Cortex cores
+///
+/// Only meaningful if the system also has cores of the other type.
+pub fn core_type_from_midr(midr: u64) -> Option<CoreType> {
+    const IMPLEMENTER_ARM: u64 = 0x41;
+    if (midr >> 24) & 0xFF != IMPLEMENTER_ARM {
+        return None;
+    }
+    match (midr >> 4) & 0xFFF {
+        // Cortex-A35, A53, A55, A510, A520
+        0xD04 | 0xD03 | 0xD05 | 0xD46 | 0xD80 => Some(CoreType::Efficiency),
+        // Cortex-A72 .. A78, A710 .. A720, X1 .. X4
+        0xD08 | 0xD09 | 0xD0A | 0xD0B | 0xD0D | 0xD41 | 0xD44 | 0xD47 | 0xD48 | 0xD4D
+        | 0xD4E | 0xD81 | 0xD82 => Some(CoreType::Performance),
+        _ => None,
+    }
+}
+
+/// Core types and capacities of all CPUs
+pub struct HybridTopology {
+    /// Core type per CPU
+    core_types: [AtomicU8; MAX_TOPOLOGY_CPUS],
+    /// Capacity per CPU (0 = unknown)
+    capacities: [AtomicU16; MAX_TOPOLOGY_CPUS],
+}
+
+impl HybridTopology {
+    /// Create an empty topology
+    pub const fn new() -> Self {
+        Self {
+            core_types: [const { AtomicU8::new(CORE_TYPE_UNKNOWN) }; MAX_TOPOLOGY_CPUS],
+            capacities: [const { AtomicU16::new(0) }; MAX_TOPOLOGY_CPUS],
+        }
+    }
+
+    /// Record the core type of a CPU
+    pub fn set_core_type(&self, cpu: usize, core_type: CoreType) {
+        if let Some(slot) = self.core_types.get(cpu) {
+            slot.store(core_type as u8, Ordering::Release);
+        }
+    }
+
+    /// Record the raw capacity of a CPU
+    ///
+    /// Any unit works (e.g. DMIPS/MHz times the maximum frequency) as long
+    /// as all CPUs use the same; [`Self::classify_by_capacity`] normalizes.
+    pub fn set_capacity(&self, cpu: usize, capacity: u16) {
+        if let Some(slot) = self.capacities.get(cpu) {
+            slot.store(capacity, Ordering::Release);
+        }
+    }
+
+    /// Normalize capacities and derive core types from them
+    ///
+    /// CPUs without a capacity keep their core type.
+    pub fn classify_by_capacity(&self) {
+        let max = self
+            .capacities
+            .iter()
+            .map(|c| c.load(Ordering::Acquire))
+            .max()
+            .unwrap_or(0) as u32;
+        if max == 0 {
+            return;
+        }
+        for cpu in 0..MAX_TOPOLOGY_CPUS {
+            let raw = self.capacities[cpu].load(Ordering::Acquire) as u32;
+            if raw == 0 {
+                continue;
+            }
+            let capacity = raw * MAX_CAPACITY as u32 / max;
+            self.capacities[cpu].store(capacity as u16, Ordering::Release);
+            let core_type = if capacity * 100 < MAX_CAPACITY as u32 * EFFICIENCY_THRESHOLD_PERCENT {
+                CoreType::Efficiency
+            } else {
+                CoreType::Performance
+            };
+            self.set_core_type(cpu, core_type);
+        }
+    }
+
+    /// Core type of a CPU, if known
+    pub fn core_type(&self, cpu: usize) -> Option<CoreType> {
+        match self.core_types.get(cpu)?.load(Ordering::Acquire) {
+            0 => Some(CoreType::Performance),
+            1 => Some(CoreType::Efficiency),
+            _ => None,
+        }
+    }
+
+    /// Capacity of a CPU (1024 if unknown)
+    pub fn capacity(&self, cpu: usize) -> u16 {
+        match self.capacities.get(cpu).map(|c| c.load(Ordering::Acquire)) {
+            Some(0) | None => MAX_CAPACITY,
+            Some(capacity) => capacity,
+        }
+    }
+
+    /// Mask of the CPUs of a core type
+    pub fn mask(&self, core_type: CoreType) -> u64 {
+        (0..MAX_TOPOLOGY_CPUS)
+            .filter(|&cpu| self.core_type(cpu) == Some(core_type))
+            .fold(0, |mask, cpu| mask | (1 << cpu))
+    }
+
+    /// Does the system have both core types?
+    pub fn is_hybrid(&self) -> bool {
+        self.mask(CoreType::Performance) != 0 && self.mask(CoreType::Efficiency) != 0
+    }
+}
+
+impl Default for HybridTopology {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global hybrid topology
+static TOPOLOGY: HybridTopology = HybridTopology::new();
+
+/// Get the hybrid topology
+pub fn topology() -> &'static HybridTopology {
+    &TOPOLOGY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(core_type_from_cpuid(0x2000_0001), Some(CoreType::Efficiency));
+        assert_eq!(core_type_from_cpuid(0x4000_0001), Some(CoreType::Performance));
+        assert_eq!(core_type_from_cpuid(0), None);
+
+        // Cortex-A55 r2p0, Cortex-A76 r4p1
+        assert_eq!(core_type_from_midr(0x412F_D050), Some(CoreType::Efficiency));
+        assert_eq!(core_type_from_midr(0x414F_D0B1), Some(CoreType::Performance));
+    }
+
+    #[test]
+    fn test_classify_by_capacity() {
+        let topology = HybridTopology::new();
+        assert!(!topology.is_hybrid());
+
+        // Two big cores at 1024 DMIPS/MHz, two LITTLE at 578
+        for (cpu, capacity) in [(0, 1024), (1, 1024), (2, 578), (3, 578)] {
+            topology.set_capacity(cpu, capacity);
+        }
+        topology.classify_by_capacity();
+
+        assert!(topology.is_hybrid());
+        assert_eq!(topology.mask(CoreType::Performance), 0b0011);
+        assert_eq!(topology.mask(CoreType::Efficiency), 0b1100);
+        assert_eq!(topology.capacity(2), 578);
+        assert_eq!(topology.capacity(10), MAX_CAPACITY);
+    }
+}
@@ -0,0 +1,276 @@
+//! # User Memory Access
+//!
+//! Safe access to user memory from system calls.
+//!
+//! The kernel never dereferences user pointers directly. Every access goes
+//! through [`copy_from_user`], [`copy_to_user`] and the helpers built on
+//! them, which:
+//!
+//! - reject ranges that reach outside the user address space
+//!   ([`access_ok`]), so a user pointer can never name kernel memory;
+//! - open user access only for the duration of the copy (`STAC`/`CLAC`
+//!   under SMAP on x86_64, unprivileged `LDTR`/`STTR` under PAN on
+//!   AArch64);
+//! - recover from faults: each instruction that may touch user memory has
+//!   an entry in the exception table, and the page fault handler resumes
+//!   at the entry's fixup ([`fixup_exception`]), which returns
+//!   [`UserFault`] (`EFAULT`) to the caller instead of panicking.
+//!
+//! ## Exception table
+//!
+//! Entries are emitted by the copy routines into the `helix_extable`
+//! section with `.pushsection`; the linker defines
+//! `__start_helix_extable` / `__stop_helix_extable` around it. Addresses
+//! are stored relative to the entry, so the table needs no relocation in
+//! PIE kernels.
+
+use core::fmt;
+use core::mem::size_of;
+
+use crate::arch::current::uaccess as arch;
+
+/// End of the user address space (exclusive)
+pub const USER_SPACE_END: u64 = arch::USER_SPACE_END;
+
+/// A user access faulted or named memory outside the user address space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserFault;
+
+impl fmt::Display for UserFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("bad user address")
+    }
+}
+
+// =============================================================================
+// Exception Table
+// =============================================================================
+
+/// Exception table entry: a faulting instruction and where to resume
+#[derive(Debug)]
+#[repr(C)]
+pub struct ExceptionTableEntry {
+    /// Instruction address, relative to this field
+    insn: i32,
+    /// Fixup address, relative to this field
+    fixup: i32,
+}
+
+impl ExceptionTableEntry {
+    /// Address of the instruction that may fault
+    pub fn insn(&self) -> u64 {
+        (&self.insn as *const i32 as u64).wrapping_add_signed(self.insn as i64)
+    }
+
+    /// Address to resume at when it does
+    pub fn fixup(&self) -> u64 {
+        (&self.fixup as *const i32 as u64).wrapping_add_signed(self.fixup as i64)
+    }
+}
+
+extern "C" {
+    static __start_helix_extable: u8;
+    static __stop_helix_extable: u8;
+}
+
+/// Keeps the section (and its bounds symbols) present even if no copy
+/// routine is linked in
+#[used]
+#[link_section = "helix_extable"]
+static SECTION_ANCHOR: [ExceptionTableEntry; 0] = [];
+
+/// The kernel's exception table
+pub fn exception_table() -> &'static [ExceptionTableEntry] {
+    // SAFETY: the linker places only `ExceptionTableEntry` records
+    // between the section bounds
+    unsafe {
+        let start = core::ptr::addr_of!(__start_helix_extable) as *const ExceptionTableEntry;
+        let stop = core::ptr::addr_of!(__stop_helix_extable) as *const ExceptionTableEntry;
+        let len = (stop as usize - start as usize) / size_of::<ExceptionTableEntry>();
+        core::slice::from_raw_parts(start, len)
+    }
+}
+
+/// Fixup for a fault at `ip` in `table`
+pub fn search_exception_table(table: &[ExceptionTableEntry], ip: u64) -> Option<u64> {
+    table.iter().find(|entry| entry.insn() == ip).map(ExceptionTableEntry::fixup)
+}
+
+/// Fixup for a kernel-mode fault at `ip`
+///
+/// Called by the page fault handlers; `Some` means the fault happened
+/// inside a user copy and execution should resume at the returned address.
+pub fn fixup_exception(ip: u64) -> Option<u64> {
+    search_exception_table(exception_table(), ip)
+}
+
+// =============================================================================
+// Access
+// =============================================================================
+
+/// `len` bytes at `addr` lie entirely in the user address space
+pub const fn access_ok(addr: u64, len: usize) -> bool {
+    match addr.checked_add(len as u64) {
+        Some(end) => end <= USER_SPACE_END,
+        None => false,
+    }
+}
+
+/// Copy `dst.len()` bytes from user address `src`
+pub fn copy_from_user(dst: &mut [u8], src: u64) -> Result<(), UserFault> {
+    if !access_ok(src, dst.len()) {
+        return Err(UserFault);
+    }
+    // SAFETY: `dst` is a kernel buffer; the user range passed `access_ok`
+    let left = unsafe { arch::raw_copy_from_user(dst.as_mut_ptr(), src as *const u8, dst.len()) };
+    if left == 0 { Ok(()) } else { Err(UserFault) }
+}
+
+/// Copy `src` to user address `dst`
+pub fn copy_to_user(dst: u64, src: &[u8]) -> Result<(), UserFault> {
+    if !access_ok(dst, src.len()) {
+        return Err(UserFault);
+    }
+    // SAFETY: `src` is a kernel buffer; the user range passed `access_ok`
+    let left = unsafe { arch::raw_copy_to_user(dst as *mut u8, src.as_ptr(), src.len()) };
+    if left == 0 { Ok(()) } else { Err(UserFault) }
+}
+
+/// Bytes per step of [`strncpy_from_user`]; steps never cross a page, so a
+/// string ending just before an unmapped page is still read
+const STRING_CHUNK: usize = 64;
+
+/// Copy a NUL-terminated string from user address `src` into `dst`
+///
+/// Returns the string length without the NUL, or `dst.len()` if `dst`
+/// filled up before a NUL was found.
+pub fn strncpy_from_user(dst: &mut [u8], src: u64) -> Result<usize, UserFault> {
+    let mut copied = 0;
+    while copied < dst.len() {
+        let addr = src.checked_add(copied as u64).ok_or(UserFault)?;
+        let page_left = 4096 - (addr as usize & 4095);
+        let chunk = STRING_CHUNK.min(page_left).min(dst.len() - copied);
+        let buf = &mut dst[copied..copied + chunk];
+        copy_from_user(buf, addr)?;
+        if let Some(nul) = buf.iter().position(|&b| b == 0) {
+            return Ok(copied + nul);
+        }
+        copied += chunk;
+    }
+    Ok(copied)
+}
+
+// =============================================================================
+// Typed Access
+// =============================================================================
+
+/// Plain data that can be copied to and from user memory as bytes
+///
+/// # Safety
+///
+/// Every bit pattern must be a valid value, and the type must have no
+/// padding (which would leak kernel memory to user space).
+pub unsafe trait UserCopy: Copy {}
+
+macro_rules! user_copy {
+    ($($ty:ty),*) => { $(unsafe impl UserCopy for $ty {})* };
+}
+
+user_copy!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+unsafe impl<T: UserCopy, const N: usize> UserCopy for [T; N] {}
+
+/// Bytes of `value`
+pub fn bytes_of<T: UserCopy>(value: &T) -> &[u8] {
+    // SAFETY: `UserCopy` types have no padding
+    unsafe { core::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+}
+
+/// Mutable bytes of `value`
+pub fn bytes_of_mut<T: UserCopy>(value: &mut T) -> &mut [u8] {
+    // SAFETY: any bytes written form a valid `UserCopy` value
+    unsafe { core::slice::from_raw_parts_mut(value as *mut T as *mut u8, size_of::<T>()) }
+}
+
+/// Read a `T` from user address `src`
+///
+/// # Safety
+///
+/// Every bit pattern of `size_of::<T>()` bytes must be a valid `T`.
+pub unsafe fn read_user<T: Copy>(src: u64) -> Result<T, UserFault> {
+    let mut value = core::mem::MaybeUninit::<T>::zeroed();
+    // SAFETY: `value` is `size_of::<T>()` initialized bytes
+    let bytes = unsafe { core::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>()) };
+    copy_from_user(bytes, src)?;
+    // SAFETY: fully written, and any bit pattern is valid per the caller
+    Ok(unsafe { value.assume_init() })
+}
+
+/// Write `value` to user address `dst`
+///
+/// # Safety
+///
+/// `T` must have no padding.
+pub unsafe fn write_user<T: Copy>(dst: u64, value: &T) -> Result<(), UserFault> {
+    // SAFETY: every byte of `value` is initialized per the caller
+    let bytes = unsafe { core::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) };
+    copy_to_user(dst, bytes)
+}
+
+/// Read a `T` from user address `src`
+pub fn get_user<T: UserCopy>(src: u64) -> Result<T, UserFault> {
+    // SAFETY: any bit pattern is a valid `UserCopy` value
+    unsafe { read_user(src) }
+}
+
+/// Write `value` to user address `dst`
+pub fn put_user<T: UserCopy>(dst: u64, value: &T) -> Result<(), UserFault> {
+    copy_to_user(dst, bytes_of(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_ok() {
+        assert!(access_ok(0x1000, 0x1000));
+        assert!(access_ok(USER_SPACE_END - 8, 8));
+        assert!(!access_ok(USER_SPACE_END - 8, 9));
+        assert!(!access_ok(USER_SPACE_END, 1));
+        assert!(!access_ok(u64::MAX, 2));
+        assert_eq!(copy_to_user(USER_SPACE_END, &[0]), Err(UserFault));
+    }
+
+    #[test]
+    fn test_search_exception_table() {
+        let mut table = [ExceptionTableEntry { insn: 0, fixup: 0 }, ExceptionTableEntry { insn: 0, fixup: 0 }];
+        for (i, entry) in table.iter_mut().enumerate() {
+            entry.insn = 0x100 * (i as i32 + 1);
+            // The fixup field sits 4 bytes after the instruction field
+            entry.fixup = entry.insn + 0x40 - 4;
+        }
+        let insn = table[1].insn();
+        assert_eq!(table[1].fixup(), insn + 0x40);
+        assert_eq!(search_exception_table(&table, insn), Some(insn + 0x40));
+        assert_eq!(search_exception_table(&table, insn + 1), None);
+    }
+
+    #[test]
+    fn test_copy_round_trip() {
+        let mut user = [0u8; 16];
+        let addr = user.as_mut_ptr() as u64;
+        copy_to_user(addr, b"hello\0world").unwrap();
+        let mut out = [0u8; 5];
+        copy_from_user(&mut out, addr).unwrap();
+        assert_eq!(&out, b"hello");
+
+        let mut name = [0u8; 32];
+        assert_eq!(strncpy_from_user(&mut name, addr), Ok(5));
+        assert_eq!(strncpy_from_user(&mut name[..3], addr), Ok(3));
+
+        put_user(addr, &0x1234_5678u32).unwrap();
+        assert_eq!(get_user::<u32>(addr), Ok(0x1234_5678));
+        assert_eq!(get_user::<u32>(USER_SPACE_END - 2), Err(UserFault));
+    }
+}
@@ -0,0 +1,1022 @@
+//! # Serial Ports
+//!
+//! UARTs behind one [`UartPort`] trait: the 8250/16550 family
+//! ([`Ns16550`], on I/O ports or MMIO), the ARM [`Pl011`] and, on RISC-V,
+//! the firmware console reached through SBI ([`SbiConsole`]).
+//!
+//! [`probe`] finds the ports listed in the hardware description and
+//! registers each one as a [`Tty`] named after its kind (`ttyS0`,
+//! `ttyAMA0`, `hvc0`). A tty with an interrupt takes received bytes into
+//! its buffer from [`handle_irq`], and drops RTS when the buffer is nearly
+//! full so that the sender pauses until the reader catches up. A tty
+//! without an interrupt is polled when read.
+//!
+//! Before drivers run, output goes to the *earlycon*, a port named by the
+//! bootloader handoff or the command line in Linux `earlycon=` syntax
+//! (`uart8250,io,0x3f8,115200`, `pl011,0x9000000`, `sbi`). [`Earlycon::parse`]
+//! decodes the name, [`init_earlycon`] brings up the port without
+//! allocating, and [`early_write`] prints to it until [`disable_earlycon`].
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use spin::{Mutex, RwLock};
+
+use crate::devtree::{DeviceTree, NodeId, Resource, Source};
+use crate::{HalError, HalResult};
+
+/// Kind of UART, which also names its ttys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UartKind {
+    /// 8250/16550 compatible
+    Ns16550,
+    /// ARM PrimeCell PL011
+    Pl011,
+    /// SBI firmware console
+    Sbi,
+}
+
+impl UartKind {
+    /// Name prefix of ttys on this kind of port
+    pub fn tty_prefix(self) -> &'static str {
+        match self {
+            Self::Ns16550 => "ttyS",
+            Self::Pl011 => "ttyAMA",
+            Self::Sbi => "hvc",
+        }
+    }
+}
+
+/// A serial port
+pub trait UartPort: Send + Sync {
+    /// Kind of port
+    fn kind(&self) -> UartKind;
+
+    /// Program `baud` with 8 data bits, no parity, one stop bit and FIFOs on
+    fn configure(&self, baud: u32) -> HalResult<()>;
+
+    /// Queue `byte` for transmission; `false` if the transmitter is full
+    fn try_put(&self, byte: u8) -> bool;
+
+    /// Next received byte
+    fn try_get(&self) -> Option<u8>;
+
+    /// Enable or disable the receive interrupt
+    fn set_rx_interrupt(&self, _enabled: bool) -> HalResult<()> {
+        Err(HalError::NotSupported)
+    }
+
+    /// Assert (`ready`) or deassert RTS
+    fn set_rts(&self, _ready: bool) {}
+
+    /// Transmit `byte`, waiting for room
+    fn put(&self, byte: u8) {
+        while !self.try_put(byte) {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+// =============================================================================
+// 16550
+// =============================================================================
+
+const UART_RBR: usize = 0;
+const UART_THR: usize = 0;
+const UART_DLL: usize = 0;
+const UART_IER: usize = 1;
+const UART_IER_RDI: u8 = 1 << 0;
+const UART_DLM: usize = 1;
+const UART_FCR: usize = 2;
+/// FIFOs on and cleared, receive interrupt at 8 bytes
+const UART_FCR_SETUP: u8 = 0x87;
+const UART_LCR: usize = 3;
+const UART_LCR_8N1: u8 = 0x03;
+const UART_LCR_DLAB: u8 = 0x80;
+const UART_MCR: usize = 4;
+const UART_MCR_DTR: u8 = 1 << 0;
+const UART_MCR_RTS: u8 = 1 << 1;
+/// Gates the interrupt line on PC-style ports
+const UART_MCR_OUT2: u8 = 1 << 3;
+const UART_LSR: usize = 5;
+const UART_LSR_DR: u8 = 1 << 0;
+const UART_LSR_THRE: u8 = 1 << 5;
+const UART_SCR: usize = 7;
+
+/// Input clock of PC serial ports
+pub const NS16550_CLOCK_HZ: u32 = 1_843_200;
+
+/// Where a 16550's registers are
+#[derive(Debug, Clone, Copy)]
+enum Ns16550Regs {
+    /// I/O ports
+    #[cfg(target_arch = "x86_64")]
+    Io(u16),
+    /// MMIO, registers `1 << shift` bytes apart, 32-bit accesses if `wide`
+    Mmio { base: *mut u8, shift: u8, wide: bool },
+}
+
+/// 8250/16550 compatible UART
+pub struct Ns16550 {
+    regs: Ns16550Regs,
+    clock_hz: u32,
+    lock: Mutex<()>,
+}
+
+// SAFETY: register accesses are single loads and stores; read-modify-write
+// sequences hold `lock`
+unsafe impl Send for Ns16550 {}
+// SAFETY: see above
+unsafe impl Sync for Ns16550 {}
+
+impl Ns16550 {
+    /// DT compatible strings and ACPI IDs
+    pub const COMPATIBLE: &'static [&'static str] =
+        &["ns16550a", "ns16550", "ns8250", "snps,dw-apb-uart", "PNP0501", "PNP0500"];
+
+    /// Create over the eight ports at `port`
+    ///
+    /// # Safety
+    ///
+    /// The ports must belong to a 16550 for the life of the driver.
+    #[cfg(target_arch = "x86_64")]
+    pub const unsafe fn new_io(port: u16, clock_hz: u32) -> Self {
+        Self { regs: Ns16550Regs::Io(port), clock_hz, lock: Mutex::new(()) }
+    }
+
+    /// Create over MMIO registers `1 << reg_shift` bytes apart, accessed
+    /// `io_width` (1 or 4) bytes at a time
+    ///
+    /// # Safety
+    ///
+    /// `base` must map the UART's registers for the life of the driver.
+    pub const unsafe fn new_mmio(base: *mut u8, reg_shift: u8, io_width: u8, clock_hz: u32) -> Self {
+        Self {
+            regs: Ns16550Regs::Mmio { base, shift: reg_shift, wide: io_width == 4 },
+            clock_hz,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self, reg: usize) -> u8 {
+        match self.regs {
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: the port block belongs to the UART, per construction
+            Ns16550Regs::Io(port) => unsafe { crate::arch::x86_64::cpu::inb(port + reg as u16) },
+            // SAFETY: within the register block, per construction
+            Ns16550Regs::Mmio { base, shift, wide } => unsafe {
+                let addr = base.add(reg << shift);
+                if wide {
+                    (addr as *mut u32).read_volatile() as u8
+                } else {
+                    addr.read_volatile()
+                }
+            },
+        }
+    }
+
+    fn write(&self, reg: usize, value: u8) {
+        match self.regs {
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: the port block belongs to the UART, per construction
+            Ns16550Regs::Io(port) => unsafe { crate::arch::x86_64::cpu::outb(port + reg as u16, value) },
+            // SAFETY: within the register block, per construction
+            Ns16550Regs::Mmio { base, shift, wide } => unsafe {
+                let addr = base.add(reg << shift);
+                if wide {
+                    (addr as *mut u32).write_volatile(value as u32)
+                } else {
+                    addr.write_volatile(value)
+                }
+            },
+        }
+    }
+
+    /// Does the scratch register hold what is written to it?
+    ///
+    /// Distinguishes a port that is present from an empty decode.
+    pub fn is_present(&self) -> bool {
+        [0x5a, 0xa5].into_iter().all(|value| {
+            self.write(UART_SCR, value);
+            self.read(UART_SCR) == value
+        })
+    }
+}
+
+impl UartPort for Ns16550 {
+    fn kind(&self) -> UartKind {
+        UartKind::Ns16550
+    }
+
+    fn configure(&self, baud: u32) -> HalResult<()> {
+        let divisor = baud.checked_mul(16).and_then(|d| self.clock_hz.checked_div(d)).unwrap_or(0);
+        if divisor == 0 || divisor > u16::MAX as u32 {
+            return Err(HalError::InvalidParameter);
+        }
+
+        let _guard = self.lock.lock();
+        self.write(UART_IER, 0);
+        self.write(UART_LCR, UART_LCR_DLAB);
+        self.write(UART_DLL, divisor as u8);
+        self.write(UART_DLM, (divisor >> 8) as u8);
+        self.write(UART_LCR, UART_LCR_8N1);
+        self.write(UART_FCR, UART_FCR_SETUP);
+        self.write(UART_MCR, UART_MCR_DTR | UART_MCR_RTS | UART_MCR_OUT2);
+        Ok(())
+    }
+
+    fn try_put(&self, byte: u8) -> bool {
+        if self.read(UART_LSR) & UART_LSR_THRE == 0 {
+            return false;
+        }
+        self.write(UART_THR, byte);
+        true
+    }
+
+    fn try_get(&self) -> Option<u8> {
+        (self.read(UART_LSR) & UART_LSR_DR != 0).then(|| self.read(UART_RBR))
+    }
+
+    fn set_rx_interrupt(&self, enabled: bool) -> HalResult<()> {
+        let _guard = self.lock.lock();
+        let ier = self.read(UART_IER);
+        self.write(UART_IER, if enabled { ier | UART_IER_RDI } else { ier & !UART_IER_RDI });
+        Ok(())
+    }
+
+    fn set_rts(&self, ready: bool) {
+        let _guard = self.lock.lock();
+        let mcr = self.read(UART_MCR);
+        self.write(UART_MCR, if ready { mcr | UART_MCR_RTS } else { mcr & !UART_MCR_RTS });
+    }
+}
+
+// =============================================================================
+// PL011
+// =============================================================================
+
+const PL011_DR: usize = 0x00;
+const PL011_FR: usize = 0x18;
+const PL011_FR_BUSY: u32 = 1 << 3;
+const PL011_FR_RXFE: u32 = 1 << 4;
+const PL011_FR_TXFF: u32 = 1 << 5;
+const PL011_IBRD: usize = 0x24;
+const PL011_FBRD: usize = 0x28;
+const PL011_LCR_H: usize = 0x2c;
+const PL011_LCR_H_FEN: u32 = 1 << 4;
+const PL011_LCR_H_WLEN8: u32 = 3 << 5;
+const PL011_CR: usize = 0x30;
+const PL011_CR_UARTEN: u32 = 1 << 0;
+const PL011_CR_TXE: u32 = 1 << 8;
+const PL011_CR_RXE: u32 = 1 << 9;
+const PL011_CR_DTR: u32 = 1 << 10;
+const PL011_CR_RTS: u32 = 1 << 11;
+const PL011_IMSC: usize = 0x38;
+const PL011_IMSC_RX: u32 = 1 << 4;
+const PL011_IMSC_RT: u32 = 1 << 6;
+const PL011_ICR: usize = 0x44;
+
+/// ARM PrimeCell PL011 UART
+pub struct Pl011 {
+    base: *mut u32,
+    clock_hz: u32,
+    lock: Mutex<()>,
+}
+
+// SAFETY: register accesses are single loads and stores; read-modify-write
+// sequences hold `lock`
+unsafe impl Send for Pl011 {}
+// SAFETY: see above
+unsafe impl Sync for Pl011 {}
+
+impl Pl011 {
+    /// DT compatible strings and ACPI IDs
+    pub const COMPATIBLE: &'static [&'static str] = &["arm,pl011", "ARMH0011"];
+
+    /// Create over the UART's registers, clocked at `clock_hz`
+    ///
+    /// # Safety
+    ///
+    /// `base` must map the UART's 4 KiB register block for the life of the
+    /// driver.
+    pub const unsafe fn new(base: *mut u8, clock_hz: u32) -> Self {
+        Self { base: base as *mut u32, clock_hz, lock: Mutex::new(()) }
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        // SAFETY: within the register block, per construction
+        unsafe { self.base.add(offset / 4).read_volatile() }
+    }
+
+    fn write(&self, offset: usize, value: u32) {
+        // SAFETY: within the register block, per construction
+        unsafe { self.base.add(offset / 4).write_volatile(value) }
+    }
+}
+
+impl UartPort for Pl011 {
+    fn kind(&self) -> UartKind {
+        UartKind::Pl011
+    }
+
+    fn configure(&self, baud: u32) -> HalResult<()> {
+        // Divisor in 64ths, rounded
+        let divisor = (self.clock_hz as u64 * 4 + baud as u64 / 2).checked_div(baud as u64).unwrap_or(0);
+        let (ibrd, fbrd) = (divisor >> 6, divisor & 0x3f);
+        if ibrd == 0 || ibrd > u16::MAX as u64 {
+            return Err(HalError::InvalidParameter);
+        }
+
+        let _guard = self.lock.lock();
+        // Line settings only take while disabled and idle
+        self.write(PL011_CR, 0);
+        while self.read(PL011_FR) & PL011_FR_BUSY != 0 {
+            core::hint::spin_loop();
+        }
+        self.write(PL011_IBRD, ibrd as u32);
+        self.write(PL011_FBRD, fbrd as u32);
+        self.write(PL011_LCR_H, PL011_LCR_H_WLEN8 | PL011_LCR_H_FEN);
+        self.write(PL011_ICR, 0x7ff);
+        self.write(PL011_CR, PL011_CR_UARTEN | PL011_CR_TXE | PL011_CR_RXE | PL011_CR_DTR | PL011_CR_RTS);
+        Ok(())
+    }
+
+    fn try_put(&self, byte: u8) -> bool {
+        if self.read(PL011_FR) & PL011_FR_TXFF != 0 {
+            return false;
+        }
+        self.write(PL011_DR, byte as u32);
+        true
+    }
+
+    fn try_get(&self) -> Option<u8> {
+        (self.read(PL011_FR) & PL011_FR_RXFE == 0).then(|| self.read(PL011_DR) as u8)
+    }
+
+    fn set_rx_interrupt(&self, enabled: bool) -> HalResult<()> {
+        // The receive timeout interrupt flushes bytes below the FIFO level
+        let mask = PL011_IMSC_RX | PL011_IMSC_RT;
+        let _guard = self.lock.lock();
+        let imsc = self.read(PL011_IMSC);
+        self.write(PL011_IMSC, if enabled { imsc | mask } else { imsc & !mask });
+        Ok(())
+    }
+
+    fn set_rts(&self, ready: bool) {
+        let _guard = self.lock.lock();
+        let cr = self.read(PL011_CR);
+        self.write(PL011_CR, if ready { cr | PL011_CR_RTS } else { cr & !PL011_CR_RTS });
+    }
+}
+
+// =============================================================================
+// SBI console
+// =============================================================================
+
+/// Console provided by the SBI firmware (legacy putchar/getchar calls)
+#[cfg(target_arch = "riscv64")]
+pub struct SbiConsole;
+
+#[cfg(target_arch = "riscv64")]
+impl SbiConsole {
+    const PUTCHAR: usize = 0x01;
+    const GETCHAR: usize = 0x02;
+
+    fn call(eid: usize, arg: usize) -> isize {
+        let ret: isize;
+        // SAFETY: the legacy console calls only touch a0
+        unsafe {
+            core::arch::asm!("ecall", in("a7") eid, inlateout("a0") arg => ret, options(nostack));
+        }
+        ret
+    }
+}
+
+#[cfg(target_arch = "riscv64")]
+impl UartPort for SbiConsole {
+    fn kind(&self) -> UartKind {
+        UartKind::Sbi
+    }
+
+    fn configure(&self, _baud: u32) -> HalResult<()> {
+        // Firmware owns the line settings
+        Ok(())
+    }
+
+    fn try_put(&self, byte: u8) -> bool {
+        Self::call(Self::PUTCHAR, byte as usize);
+        true
+    }
+
+    fn try_get(&self) -> Option<u8> {
+        let ret = Self::call(Self::GETCHAR, 0);
+        (ret >= 0).then_some(ret as u8)
+    }
+}
+
+// =============================================================================
+// TTYs
+// =============================================================================
+
+/// Size of a tty's receive buffer
+pub const RX_BUFFER_SIZE: usize = 4096;
+
+/// Buffer fill at which the sender is throttled
+const RX_THROTTLE: usize = RX_BUFFER_SIZE * 3 / 4;
+
+/// Buffer fill at which it may send again
+const RX_UNTHROTTLE: usize = RX_BUFFER_SIZE / 4;
+
+/// Receive side of a tty
+struct RxState {
+    buffer: VecDeque<u8>,
+    throttled: bool,
+    overruns: u64,
+}
+
+/// A registered serial port
+pub struct Tty {
+    name: String,
+    port: &'static dyn UartPort,
+    irq: Option<u32>,
+    node: Option<NodeId>,
+    rx: Mutex<RxState>,
+}
+
+impl Tty {
+    /// Device name (`ttyS0`)
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The port
+    pub fn port(&self) -> &'static dyn UartPort {
+        self.port
+    }
+
+    /// Receive interrupt; `None` if the tty is polled
+    pub fn irq(&self) -> Option<u32> {
+        self.irq
+    }
+
+    /// Node describing the port
+    pub fn node(&self) -> Option<NodeId> {
+        self.node
+    }
+
+    /// Move received bytes from the port into the buffer; returns how many
+    /// arrived
+    ///
+    /// Bytes arriving while the buffer is full are dropped and counted as
+    /// overruns.
+    pub fn receive(&self) -> usize {
+        let mut rx = self.rx.lock();
+        let mut count = 0;
+        while let Some(byte) = self.port.try_get() {
+            count += 1;
+            if rx.buffer.len() == RX_BUFFER_SIZE {
+                rx.overruns += 1;
+                continue;
+            }
+            rx.buffer.push_back(byte);
+        }
+        if !rx.throttled && rx.buffer.len() >= RX_THROTTLE {
+            self.port.set_rts(false);
+            rx.throttled = true;
+        }
+        count
+    }
+
+    /// Take buffered bytes into `buf`; returns how many were copied
+    pub fn read(&self, buf: &mut [u8]) -> usize {
+        if self.irq.is_none() {
+            self.receive();
+        }
+
+        let mut rx = self.rx.lock();
+        let count = buf.len().min(rx.buffer.len());
+        for (slot, byte) in buf.iter_mut().zip(rx.buffer.drain(..count)) {
+            *slot = byte;
+        }
+        if rx.throttled && rx.buffer.len() <= RX_UNTHROTTLE {
+            self.port.set_rts(true);
+            rx.throttled = false;
+        }
+        count
+    }
+
+    /// Transmit `data`
+    pub fn write(&self, data: &[u8]) {
+        for &byte in data {
+            self.port.put(byte);
+        }
+    }
+
+    /// Bytes waiting to be read
+    pub fn pending(&self) -> usize {
+        self.rx.lock().buffer.len()
+    }
+
+    /// Is the sender being held off?
+    pub fn is_throttled(&self) -> bool {
+        self.rx.lock().throttled
+    }
+
+    /// Bytes dropped on a full buffer
+    pub fn overruns(&self) -> u64 {
+        self.rx.lock().overruns
+    }
+}
+
+static TTYS: RwLock<Vec<&'static Tty>> = RwLock::new(Vec::new());
+
+/// Register `port` as the next tty of its kind, described by `node` if it
+/// comes from the hardware description
+///
+/// With `irq`, the receive interrupt is enabled and [`handle_irq`] fills
+/// the buffer; a port that cannot interrupt is polled instead.
+pub fn register(port: &'static dyn UartPort, irq: Option<u32>, node: Option<NodeId>) -> HalResult<&'static Tty> {
+    let mut ttys = TTYS.write();
+    if node.is_some() && ttys.iter().any(|t| t.node == node) {
+        return Err(HalError::ResourceBusy);
+    }
+
+    let irq = irq.filter(|_| port.set_rx_interrupt(true).is_ok());
+    port.set_rts(true);
+
+    let prefix = port.kind().tty_prefix();
+    let index = ttys.iter().filter(|t| t.port.kind().tty_prefix() == prefix).count();
+    let tty: &'static Tty = Box::leak(Box::new(Tty {
+        name: format!("{}{}", prefix, index),
+        port,
+        irq,
+        node,
+        rx: Mutex::new(RxState { buffer: VecDeque::with_capacity(RX_BUFFER_SIZE), throttled: false, overruns: 0 }),
+    }));
+    ttys.push(tty);
+    Ok(tty)
+}
+
+/// All registered ttys
+pub fn ttys() -> Vec<&'static Tty> {
+    TTYS.read().clone()
+}
+
+/// Tty named `name`
+pub fn find(name: &str) -> Option<&'static Tty> {
+    TTYS.read().iter().find(|t| t.name == name).copied()
+}
+
+/// Tty registered for node `node`
+pub fn find_node(node: NodeId) -> Option<&'static Tty> {
+    TTYS.read().iter().find(|t| t.node == Some(node)).copied()
+}
+
+/// Service interrupt `irq`; returns whether a tty claimed it
+///
+/// Called by the platform's interrupt dispatch for each device interrupt.
+pub fn handle_irq(irq: u32) -> bool {
+    let ttys = TTYS.read();
+    let mut claimed = false;
+    for tty in ttys.iter().filter(|t| t.irq == Some(irq)) {
+        tty.receive();
+        claimed = true;
+    }
+    claimed
+}
+
+// =============================================================================
+// Discovery
+// =============================================================================
+
+/// Default baud rate of ports the description gives no speed for
+pub const DEFAULT_BAUD: u32 = 115_200;
+
+/// Input clock of PL011s the description gives no frequency for
+pub const PL011_CLOCK_HZ: u32 = 24_000_000;
+
+/// Create the driver for `node`, if one of the kinds here drives it
+///
+/// # Safety
+///
+/// MMIO ranges of the node must be mapped at their physical address.
+unsafe fn port_for(tree: &DeviceTree, node: NodeId) -> Option<(&'static dyn UartPort, Option<u32>)> {
+    let desc = tree.node(node)?;
+    let is = |ids: &[&str]| ids.iter().any(|id| desc.is_compatible(id));
+    let clock = desc.property_u32("clock-frequency");
+
+    let port: &'static dyn UartPort = if is(Ns16550::COMPATIBLE) {
+        // PC serial ports run at the standard clock without saying so
+        let clock = clock.or((desc.source == Source::Acpi).then_some(NS16550_CLOCK_HZ));
+        let port = match desc.resources.first()? {
+            // SAFETY: mapped at its physical address, per the caller
+            &Resource::Memory { base, .. } => unsafe {
+                let shift = desc.property_u32("reg-shift").unwrap_or(0) as u8;
+                let width = desc.property_u32("reg-io-width").unwrap_or(1) as u8;
+                Ns16550::new_mmio(base as *mut u8, shift, width, clock.unwrap_or(NS16550_CLOCK_HZ))
+            },
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: the description assigns the ports to this UART
+            &Resource::Io { base, .. } => unsafe { Ns16550::new_io(base, clock.unwrap_or(NS16550_CLOCK_HZ)) },
+            #[cfg(not(target_arch = "x86_64"))]
+            Resource::Io { .. } => return None,
+        };
+        // Without a known clock, keep the firmware's line settings
+        if let Some(baud) = clock.map(|_| desc.property_u32("current-speed").unwrap_or(DEFAULT_BAUD)) {
+            port.configure(baud).ok()?;
+        }
+        Box::leak(Box::new(port))
+    } else if is(Pl011::COMPATIBLE) {
+        let &Resource::Memory { base, .. } = desc.resources.first()? else {
+            return None;
+        };
+        // SAFETY: mapped at its physical address, per the caller
+        let port = unsafe { Pl011::new(base as *mut u8, clock.unwrap_or(PL011_CLOCK_HZ)) };
+        if clock.is_some() {
+            port.configure(desc.property_u32("current-speed").unwrap_or(DEFAULT_BAUD)).ok()?;
+        }
+        Box::leak(Box::new(port))
+    } else {
+        return None;
+    };
+
+    Some((port, desc.interrupts.first().map(|i| i.number)))
+}
+
+/// Register a tty for each enabled port in `tree` not registered yet;
+/// returns the new ttys
+///
+/// # Safety
+///
+/// MMIO ranges in the tree must be mapped at their physical address.
+pub unsafe fn probe(tree: &DeviceTree) -> Vec<&'static Tty> {
+    let mut found = Vec::new();
+    for (node, desc) in tree.nodes() {
+        if !desc.is_enabled() || find_node(node).is_some() {
+            continue;
+        }
+        // SAFETY: forwarded from the caller
+        if let Some((port, irq)) = unsafe { port_for(tree, node) } {
+            if let Ok(tty) = register(port, irq, Some(node)) {
+                found.push(tty);
+            }
+        }
+    }
+    found
+}
+
+/// Register the PC serial ports that answer at their legacy addresses
+///
+/// For machines without a hardware description.
+#[cfg(target_arch = "x86_64")]
+pub fn probe_legacy() -> Vec<&'static Tty> {
+    const PORTS: [(u16, u32); 4] = [(0x3f8, 4), (0x2f8, 3), (0x3e8, 4), (0x2e8, 3)];
+
+    let mut found = Vec::new();
+    for (base, irq) in PORTS {
+        // SAFETY: the PC platform reserves these ports for serial ports
+        let port = unsafe { Ns16550::new_io(base, NS16550_CLOCK_HZ) };
+        if !port.is_present() || port.configure(DEFAULT_BAUD).is_err() {
+            continue;
+        }
+        if let Ok(tty) = register(Box::leak(Box::new(port)), Some(irq), None) {
+            found.push(tty);
+        }
+    }
+    found
+}
+
+// =============================================================================
+// Earlycon
+// =============================================================================
+
+/// Boot console port, in the terms of the `earlycon=` parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Earlycon {
+    /// 16550 on I/O ports (`uart8250,io,<port>`)
+    Ns16550Io {
+        /// First port
+        port: u16,
+        /// Baud rate to program; `None` keeps the firmware's
+        baud: Option<u32>,
+    },
+    /// 16550 on MMIO (`uart8250,mmio,<addr>` or `uart8250,mmio32,<addr>`)
+    Ns16550Mmio {
+        /// Physical address
+        base: u64,
+        /// 32-bit registers, 4 bytes apart
+        wide: bool,
+        /// Baud rate to program; `None` keeps the firmware's
+        baud: Option<u32>,
+    },
+    /// PL011 (`pl011,<addr>`)
+    Pl011 {
+        /// Physical address
+        base: u64,
+    },
+    /// SBI console (`sbi`)
+    Sbi,
+}
+
+impl Earlycon {
+    /// Parse an `earlycon=` value
+    ///
+    /// `<name>,<iotype>,<address>[,<options>]` for the 8250 family (`uart`
+    /// and `ns16550a` name it too; `iotype` is `io`, `mmio` or `mmio32`,
+    /// MMIO when left out), `pl011,[mmio32,]<address>` and `sbi`. Only the
+    /// baud rate at the start of the options is used (`115200n8`).
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut fields = spec.split(',');
+        let name = fields.next()?;
+        if name == "sbi" {
+            return Some(Self::Sbi);
+        }
+
+        let mut field = fields.next()?;
+        let iotype = match field {
+            "io" | "mmio" | "mmio32" => {
+                let iotype = field;
+                field = fields.next()?;
+                iotype
+            }
+            _ => "mmio",
+        };
+        let address = parse_number(field)?;
+        let baud = match fields.next() {
+            Some(options) => {
+                let digits = options.find(|c: char| !c.is_ascii_digit()).unwrap_or(options.len());
+                Some(options[..digits].parse().ok().filter(|&b| b != 0)?)
+            }
+            None => None,
+        };
+
+        match (name, iotype) {
+            ("uart8250" | "uart" | "ns16550" | "ns16550a", "io") => {
+                Some(Self::Ns16550Io { port: u16::try_from(address).ok()?, baud })
+            }
+            ("uart8250" | "uart" | "ns16550" | "ns16550a", _) => {
+                Some(Self::Ns16550Mmio { base: address, wide: iotype == "mmio32", baud })
+            }
+            ("pl011", "mmio" | "mmio32") => Some(Self::Pl011 { base: address }),
+            _ => None,
+        }
+    }
+}
+
+/// Hexadecimal with `0x`, decimal otherwise
+fn parse_number(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// Driver of the earlycon, held by value: the heap may not exist yet
+enum EarlyPort {
+    Ns16550(Ns16550),
+    Pl011(Pl011),
+    #[cfg(target_arch = "riscv64")]
+    Sbi(SbiConsole),
+}
+
+impl EarlyPort {
+    fn port(&self) -> &dyn UartPort {
+        match self {
+            Self::Ns16550(port) => port,
+            Self::Pl011(port) => port,
+            #[cfg(target_arch = "riscv64")]
+            Self::Sbi(port) => port,
+        }
+    }
+}
+
+static EARLYCON: Mutex<Option<EarlyPort>> = Mutex::new(None);
+
+/// Start the boot console on the port `spec` names
+///
+/// # Safety
+///
+/// The port's registers must be accessible at their physical address until
+/// [`disable_earlycon`].
+pub unsafe fn init_earlycon(spec: &Earlycon) -> HalResult<()> {
+    let (port, baud) = match *spec {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: per the caller
+        Earlycon::Ns16550Io { port, baud } => (EarlyPort::Ns16550(unsafe { Ns16550::new_io(port, NS16550_CLOCK_HZ) }), baud),
+        #[cfg(not(target_arch = "x86_64"))]
+        Earlycon::Ns16550Io { .. } => return Err(HalError::NotSupported),
+        Earlycon::Ns16550Mmio { base, wide, baud } => {
+            let (shift, width) = if wide { (2, 4) } else { (0, 1) };
+            // SAFETY: per the caller
+            let port = unsafe { Ns16550::new_mmio(base as *mut u8, shift, width, NS16550_CLOCK_HZ) };
+            (EarlyPort::Ns16550(port), baud)
+        }
+        // The clock is unknown this early: keep the firmware's settings
+        // SAFETY: per the caller
+        Earlycon::Pl011 { base } => (EarlyPort::Pl011(unsafe { Pl011::new(base as *mut u8, PL011_CLOCK_HZ) }), None),
+        #[cfg(target_arch = "riscv64")]
+        Earlycon::Sbi => (EarlyPort::Sbi(SbiConsole), None),
+        #[cfg(not(target_arch = "riscv64"))]
+        Earlycon::Sbi => return Err(HalError::NotSupported),
+    };
+
+    if let Some(baud) = baud {
+        port.port().configure(baud)?;
+    }
+    *EARLYCON.lock() = Some(port);
+    Ok(())
+}
+
+/// Is the boot console up?
+pub fn earlycon_active() -> bool {
+    EARLYCON.lock().is_some()
+}
+
+/// Print `s` on the boot console, if there is one
+///
+/// Line feeds go out as CR LF.
+pub fn early_write(s: &str) {
+    let earlycon = EARLYCON.lock();
+    let Some(port) = earlycon.as_ref().map(EarlyPort::port) else {
+        return;
+    };
+    for byte in s.bytes() {
+        if byte == b'\n' {
+            port.put(b'\r');
+        }
+        port.put(byte);
+    }
+}
+
+/// Stop using the boot console, once the real console has taken over
+pub fn disable_earlycon() {
+    *EARLYCON.lock() = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Port receiving from a queue and recording RTS
+    struct FakePort {
+        rx: Mutex<VecDeque<u8>>,
+        rts: Mutex<bool>,
+    }
+
+    impl FakePort {
+        fn leak() -> &'static Self {
+            Box::leak(Box::new(Self { rx: Mutex::new(VecDeque::new()), rts: Mutex::new(false) }))
+        }
+    }
+
+    impl UartPort for FakePort {
+        fn kind(&self) -> UartKind {
+            UartKind::Sbi
+        }
+
+        fn configure(&self, _baud: u32) -> HalResult<()> {
+            Ok(())
+        }
+
+        fn try_put(&self, _byte: u8) -> bool {
+            true
+        }
+
+        fn try_get(&self) -> Option<u8> {
+            self.rx.lock().pop_front()
+        }
+
+        fn set_rx_interrupt(&self, _enabled: bool) -> HalResult<()> {
+            Ok(())
+        }
+
+        fn set_rts(&self, ready: bool) {
+            *self.rts.lock() = ready;
+        }
+    }
+
+    #[test]
+    fn test_parse_earlycon() {
+        assert_eq!(
+            Earlycon::parse("uart8250,io,0x3f8,115200n8"),
+            Some(Earlycon::Ns16550Io { port: 0x3f8, baud: Some(115_200) })
+        );
+        assert_eq!(
+            Earlycon::parse("uart,mmio32,0xfe215040"),
+            Some(Earlycon::Ns16550Mmio { base: 0xfe21_5040, wide: true, baud: None })
+        );
+        assert_eq!(
+            Earlycon::parse("ns16550a,0x10000000,9600"),
+            Some(Earlycon::Ns16550Mmio { base: 0x1000_0000, wide: false, baud: Some(9600) })
+        );
+        assert_eq!(Earlycon::parse("pl011,0x9000000"), Some(Earlycon::Pl011 { base: 0x900_0000 }));
+        assert_eq!(Earlycon::parse("pl011,mmio32,0x9000000"), Some(Earlycon::Pl011 { base: 0x900_0000 }));
+        assert_eq!(Earlycon::parse("sbi"), Some(Earlycon::Sbi));
+
+        assert_eq!(Earlycon::parse("uart8250,io,0x10000"), None);
+        assert_eq!(Earlycon::parse("pl011,io,0x9000000"), None);
+        assert_eq!(Earlycon::parse("uart8250,io,0x3f8,fast"), None);
+        assert_eq!(Earlycon::parse("efifb"), None);
+    }
+
+    #[test]
+    fn test_16550_configure() {
+        // 32-bit registers 4 bytes apart
+        let regs: &'static mut [u32; 8] = Box::leak(Box::new([0; 8]));
+        let base = regs.as_mut_ptr();
+        let port = unsafe { Ns16550::new_mmio(base as *mut u8, 2, 4, NS16550_CLOCK_HZ) };
+        let reg = |i: usize| unsafe { base.add(i).read_volatile() };
+
+        port.configure(9600).unwrap();
+        assert_eq!((reg(UART_DLL), reg(UART_DLM)), (12, 0));
+        assert_eq!(reg(UART_LCR), UART_LCR_8N1 as u32);
+        assert_eq!(reg(UART_MCR), 0x0b);
+        assert_eq!(port.configure(1_000_000), Err(HalError::InvalidParameter));
+
+        port.set_rts(false);
+        assert_eq!(reg(UART_MCR), 0x09);
+        port.set_rx_interrupt(true).unwrap();
+        assert_eq!(reg(UART_IER), UART_IER_RDI as u32);
+    }
+
+    #[test]
+    fn test_pl011_configure() {
+        let regs: &'static mut [u32; 32] = Box::leak(Box::new([0; 32]));
+        let base = regs.as_mut_ptr();
+        let port = unsafe { Pl011::new(base as *mut u8, PL011_CLOCK_HZ) };
+        let reg = |offset: usize| unsafe { base.add(offset / 4).read_volatile() };
+
+        port.configure(115_200).unwrap();
+        assert_eq!((reg(PL011_IBRD), reg(PL011_FBRD)), (13, 1));
+        assert_eq!(reg(PL011_LCR_H), 0x70);
+        assert_ne!(reg(PL011_CR) & PL011_CR_RTS, 0);
+        port.set_rts(false);
+        assert_eq!(reg(PL011_CR) & PL011_CR_RTS, 0);
+
+        // Empty receive FIFO, room to transmit
+        unsafe { base.add(PL011_FR / 4).write_volatile(PL011_FR_RXFE) };
+        assert_eq!(port.try_get(), None);
+        assert!(port.try_put(b'A'));
+        assert_eq!(reg(PL011_DR), b'A' as u32);
+    }
+
+    #[test]
+    fn test_rx_flow_control() {
+        let port = FakePort::leak();
+        let tty = register(port, Some(1000), None).unwrap();
+        assert!(tty.name().starts_with("hvc"));
+        assert!(*port.rts.lock());
+
+        port.rx.lock().extend(core::iter::repeat(b'x').take(RX_THROTTLE));
+        assert!(!handle_irq(1001));
+        assert!(handle_irq(1000));
+        assert_eq!(tty.pending(), RX_THROTTLE);
+        assert!(tty.is_throttled() && !*port.rts.lock());
+
+        // Sender ignoring RTS overruns the buffer
+        port.rx.lock().extend(core::iter::repeat(b'y').take(RX_BUFFER_SIZE));
+        assert_eq!(tty.receive(), RX_BUFFER_SIZE);
+        assert_eq!(tty.overruns(), RX_THROTTLE as u64);
+
+        let mut buf = alloc::vec![0; RX_BUFFER_SIZE];
+        assert_eq!(tty.read(&mut buf[..RX_BUFFER_SIZE - RX_UNTHROTTLE - 1]), RX_BUFFER_SIZE - RX_UNTHROTTLE - 1);
+        assert!(tty.is_throttled());
+        assert_eq!(tty.read(&mut buf[..1]), 1);
+        assert!(!tty.is_throttled() && *port.rts.lock());
+        assert_eq!(tty.read(&mut buf), RX_UNTHROTTLE);
+        assert_eq!(&buf[RX_UNTHROTTLE - 1..RX_UNTHROTTLE], b"y");
+    }
+
+    #[test]
+    fn test_probe() {
+        let regs: &'static mut [u8; 8] = Box::leak(Box::new([0; 8]));
+        let base = regs.as_mut_ptr() as u64;
+
+        // QWordMemory over the registers, Interrupt 36
+        let mut crs = vec![0x8a, 43, 0, 0, 0, 0];
+        for value in [0, base, base + 7, 0, 8] {
+            crs.extend_from_slice(&value.to_le_bytes());
+        }
+        crs.extend_from_slice(&[0x89, 0x06, 0x00, 0x01, 0x01, 36, 0, 0, 0, 0x79, 0x00]);
+
+        let mut tree = DeviceTree::new(Source::Acpi);
+        let sb = tree.add_node(tree.root(), "_SB_", Source::Acpi);
+        let uart = tree.add_acpi_device(sb, "UAR1", "PNP0501", &[], &crs).unwrap();
+        let off = tree.add_acpi_device(sb, "UAR2", "PNP0501", &[], &crs).unwrap();
+        tree.set_property(off, "status", b"disabled\0");
+
+        let found = unsafe { probe(&tree) };
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].node(), found[0].irq()), (Some(uart), Some(36)));
+        assert_eq!(find_node(uart).map(Tty::name), Some(found[0].name()));
+        assert!(found[0].name().starts_with("ttyS") && find(found[0].name()).is_some());
+        assert!(unsafe { probe(&tree) }.is_empty());
+
+        // Programmed for 115200 off the PC clock, receive interrupt on
+        assert_eq!((regs[UART_DLL], regs[UART_LCR]), (1, UART_LCR_8N1));
+        assert_eq!(regs[UART_IER], UART_IER_RDI);
+    }
+}
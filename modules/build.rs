@@ -0,0 +1,12 @@
+//! Link flags for the host test binaries
+//!
+//! `linked` finds static modules through the `__start_helix_modules` /
+//! `__stop_helix_modules` symbols. Kernel linker scripts `KEEP` the
+//! section; host test binaries are linked with `--gc-sections`, and lld
+//! drops the section unless those symbols retain it.
+
+fn main() {
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("linux") {
+        println!("cargo:rustc-link-arg=-Wl,-z,nostart-stop-gc");
+    }
+}
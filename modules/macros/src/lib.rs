@@ -0,0 +1,470 @@
+//! # Helix Module Macros
+//!
+//! `#[helix_module]` registers a static module constructor at link time:
+//!
+//! ```rust,ignore
+//! use helix_modules::helix_module;
+//!
+//! #[helix_module]
+//! pub fn create_module() -> RoundRobinModule {
+//!     RoundRobinModule::new()
+//! }
+//! ```
+//!
+//! The function is kept as written. Next to it, the macro places a
+//! `helix_modules::linked::StaticModule` entry, named after the crate, in
+//! the `helix_modules` linker section; `helix_modules::linked` collects
+//! every entry of the final image.
+//!
+//! `#[derive(Message)]` implements the `helix_modules::schema` codec for
+//! IPC payload types; see that module for the wire format.
+//!
+//! Tokens are handled with `proc_macro` alone, so the macros build without
+//! external crates.
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// Register a module constructor in the link-time module registry
+///
+/// Applies to a function taking no arguments and returning a type that
+/// implements `helix_modules::v2::ModuleTrait`.
+#[proc_macro_attribute]
+pub fn helix_module(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return compile_error("#[helix_module] takes no arguments");
+    }
+    let name = match constructor_name(&item) {
+        Ok(name) => name,
+        Err(message) => return compile_error(message),
+    };
+
+    let registration = format!(
+        r#"
+        #[used]
+        #[doc(hidden)]
+        #[link_section = "helix_modules"]
+        static __HELIX_MODULE_{upper}: ::helix_modules::linked::StaticModule =
+            ::helix_modules::linked::StaticModule {{
+                crate_name: env!("CARGO_PKG_NAME"),
+                construct: || ::helix_modules::linked::boxed({name}()),
+            }};
+        "#,
+        upper = name.to_uppercase(),
+        name = name,
+    );
+
+    let mut out = item;
+    out.extend(registration.parse::<TokenStream>().expect("registration tokens"));
+    out
+}
+
+/// Name of the annotated function, which must take no arguments and have
+/// no generic parameters
+fn constructor_name(item: &TokenStream) -> Result<String, &'static str> {
+    let tokens: Vec<TokenTree> = item.clone().into_iter().collect();
+    let fn_pos = tokens
+        .iter()
+        .position(|t| matches!(t, TokenTree::Ident(ident) if ident.to_string() == "fn"))
+        .ok_or("#[helix_module] applies to functions")?;
+
+    let name = match tokens.get(fn_pos + 1) {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("#[helix_module] applies to functions"),
+    };
+    match tokens.get(fn_pos + 2) {
+        Some(TokenTree::Group(args)) if args.delimiter() == Delimiter::Parenthesis && args.stream().is_empty() => {
+            Ok(name)
+        }
+        _ => Err("#[helix_module] constructors take no arguments and no generic parameters"),
+    }
+}
+
+fn compile_error(message: &str) -> TokenStream {
+    format!("compile_error!({:?});", message).parse().expect("compile_error tokens")
+}
+
+// =============================================================================
+// #[derive(Message)]
+// =============================================================================
+
+/// Derive the `helix_modules::schema` codec for a struct or enum
+///
+/// Implements `Encode`, `Decode` and `ToJson`. With
+/// `#[message(name = "...", version = N, min_version = M)]` on the type
+/// it also implements `Message` (both versions default to 1). Fields added
+/// after the first version are marked `#[message(since = N)]`, come after
+/// older fields, and need a `Default`.
+#[proc_macro_derive(Message, attributes(message))]
+pub fn derive_message(item: TokenStream) -> TokenStream {
+    match message_impl(item) {
+        Ok(out) => out,
+        Err(message) => compile_error(&message),
+    }
+}
+
+/// Path of the schema module in expansions
+const SCHEMA: &str = "::helix_modules::schema";
+
+/// A field, by name or tuple index
+struct Field {
+    name: String,
+    since: u16,
+}
+
+/// Fields of a struct or variant
+enum Shape {
+    Named(Vec<Field>),
+    Tuple(Vec<Field>),
+    Unit,
+}
+
+/// An enum variant
+struct Variant {
+    name: String,
+    shape: Shape,
+}
+
+fn message_impl(item: TokenStream) -> Result<TokenStream, String> {
+    let tokens: Vec<TokenTree> = item.into_iter().collect();
+    let mut pos = 0;
+    let attrs = take_attributes(&tokens, &mut pos)?;
+    skip_visibility(&tokens, &mut pos);
+
+    let kind = ident_at(&tokens, pos).ok_or("#[derive(Message)] applies to structs and enums")?;
+    let name = ident_at(&tokens, pos + 1).ok_or("#[derive(Message)] applies to structs and enums")?;
+    pos += 2;
+    if matches!(tokens.get(pos), Some(TokenTree::Punct(p)) if p.as_char() == '<') {
+        return Err(String::from("#[derive(Message)] does not support generic types"));
+    }
+
+    let version = attr_u16(&attrs, "version")?.unwrap_or(1);
+    let min_version = attr_u16(&attrs, "min_version")?.unwrap_or(1);
+    if min_version == 0 || min_version > version {
+        return Err(String::from("#[message] needs 1 <= min_version <= version"));
+    }
+
+    let (encode, decode, json) = match kind.as_str() {
+        "struct" => {
+            let shape = match tokens.get(pos) {
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                    Shape::Named(parse_fields(g.stream(), true)?)
+                }
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                    Shape::Tuple(parse_fields(g.stream(), false)?)
+                }
+                _ => Shape::Unit,
+            };
+            check_since(&shape, version)?;
+            struct_impls(&shape)
+        }
+        "enum" => {
+            let variants = match tokens.get(pos) {
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => parse_variants(g.stream())?,
+                _ => return Err(String::from("malformed enum")),
+            };
+            if variants.is_empty() {
+                return Err(String::from("#[derive(Message)] needs at least one variant"));
+            }
+            for variant in &variants {
+                check_since(&variant.shape, version)?;
+            }
+            enum_impls(&variants)
+        }
+        _ => return Err(String::from("#[derive(Message)] applies to structs and enums")),
+    };
+
+    let mut out = format!(
+        r#"
+        impl {schema}::Encode for {name} {{
+            fn encode(&self, w: &mut {schema}::Writer) {{ {encode} }}
+        }}
+        impl {schema}::Decode for {name} {{
+            fn decode(r: &mut {schema}::Reader<'_>) -> ::core::result::Result<Self, {schema}::DecodeError> {{ {decode} }}
+        }}
+        impl {schema}::ToJson for {name} {{
+            fn write_json(&self, out: &mut ::alloc::string::String) {{ {json} }}
+        }}
+        "#,
+        schema = SCHEMA,
+    );
+    if let Some((_, schema_name)) = attrs.iter().find(|(key, _)| key == "name") {
+        out += &format!(
+            r#"
+            impl {schema}::Message for {name} {{
+                const NAME: &'static str = {schema_name};
+                const VERSION: {schema}::SchemaVersion = {version};
+                const MIN_VERSION: {schema}::SchemaVersion = {min_version};
+            }}
+            "#,
+            schema = SCHEMA,
+        );
+    }
+
+    out.parse().map_err(|_| String::from("#[derive(Message)] expansion failed"))
+}
+
+/// Encode, decode and JSON bodies for a struct
+fn struct_impls(shape: &Shape) -> (String, String, String) {
+    let access = |f: &Field| format!("&self.{}", f.name);
+    let encode = format!("w.nested(|w| {{ let _ = &w; {} }});", encode_fields(shape, access));
+    let decode = format!("r.nested(|r| {{ let _ = &r; Ok({}) }})", construct(shape, "Self"));
+    let json = match shape {
+        Shape::Unit => String::from("out.push_str(\"null\");"),
+        _ => json_fields(shape, access),
+    };
+    (encode, decode, json)
+}
+
+/// Encode, decode and JSON bodies for an enum
+fn enum_impls(variants: &[Variant]) -> (String, String, String) {
+    let mut encode = String::from("match self {");
+    let mut decode = String::from("match r.read_varint()? {");
+    let mut json = String::from("match self {");
+
+    for (index, variant) in variants.iter().enumerate() {
+        let pattern = match &variant.shape {
+            Shape::Named(fields) => format!(
+                "Self::{} {{ {} }}",
+                variant.name,
+                fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>().join(", "),
+            ),
+            Shape::Tuple(fields) => format!(
+                "Self::{}({})",
+                variant.name,
+                fields.iter().map(|f| format!("__f{}", f.name)).collect::<Vec<_>>().join(", "),
+            ),
+            Shape::Unit => format!("Self::{}", variant.name),
+        };
+        let access = |f: &Field| match &variant.shape {
+            Shape::Tuple(_) => format!("__f{}", f.name),
+            _ => f.name.clone(),
+        };
+
+        encode += &format!(
+            "{pattern} => {{ w.write_varint({index}); w.nested(|w| {{ let _ = &w; {} }}); }}",
+            encode_fields(&variant.shape, access),
+        );
+        decode += &format!(
+            "{index} => r.nested(|r| {{ let _ = &r; Ok({}) }}),",
+            construct(&variant.shape, &format!("Self::{}", variant.name)),
+        );
+        json += &match &variant.shape {
+            Shape::Unit => format!("{pattern} => {SCHEMA}::json::string(out, {:?}),", variant.name),
+            shape => format!(
+                "{pattern} => {{ out.push('{{'); {SCHEMA}::json::key(out, {:?}, true); {} out.push('}}'); }}",
+                variant.name,
+                json_fields(shape, access),
+            ),
+        };
+    }
+
+    encode.push('}');
+    decode += &format!("tag => Err({SCHEMA}::DecodeError::InvalidTag(tag)), }}");
+    json.push('}');
+    (encode, decode, json)
+}
+
+/// Statements writing each field, skipping those newer than the writer
+fn encode_fields(shape: &Shape, access: impl Fn(&Field) -> String) -> String {
+    fields(shape).iter()
+        .map(|f| {
+            let write = format!("{SCHEMA}::Encode::encode({}, w);", access(f));
+            if f.since > 1 {
+                format!("if w.version() >= {} {{ {} }}", f.since, write)
+            } else {
+                write
+            }
+        })
+        .collect()
+}
+
+/// Expression building `path` from fields read off `r`
+fn construct(shape: &Shape, path: &str) -> String {
+    let read = |f: &Field| if f.since > 1 { "r.field_or_default()?" } else { "r.field()?" };
+    match shape {
+        Shape::Named(fields) => format!(
+            "{path} {{ {} }}",
+            fields.iter().map(|f| format!("{}: {}", f.name, read(f))).collect::<Vec<_>>().join(", "),
+        ),
+        Shape::Tuple(fields) => format!(
+            "{path}({})",
+            fields.iter().map(read).collect::<Vec<_>>().join(", "),
+        ),
+        Shape::Unit => String::from(path),
+    }
+}
+
+/// Statements rendering fields as a JSON object (named) or array (tuple)
+fn json_fields(shape: &Shape, access: impl Fn(&Field) -> String) -> String {
+    let mut out = String::new();
+    match shape {
+        Shape::Named(fields) => {
+            out += "out.push('{');";
+            for (i, f) in fields.iter().enumerate() {
+                out += &format!(
+                    "{SCHEMA}::json::key(out, {:?}, {}); {SCHEMA}::ToJson::write_json({}, out);",
+                    f.name.trim_start_matches("r#"),
+                    i == 0,
+                    access(f),
+                );
+            }
+            out += "out.push('}');";
+        }
+        Shape::Tuple(fields) => {
+            out += "out.push('[');";
+            for (i, f) in fields.iter().enumerate() {
+                if i > 0 {
+                    out += "out.push(',');";
+                }
+                out += &format!("{SCHEMA}::ToJson::write_json({}, out);", access(f));
+            }
+            out += "out.push(']');";
+        }
+        Shape::Unit => out += "out.push_str(\"null\");",
+    }
+    out
+}
+
+fn fields(shape: &Shape) -> &[Field] {
+    match shape {
+        Shape::Named(fields) | Shape::Tuple(fields) => fields,
+        Shape::Unit => &[],
+    }
+}
+
+/// Check that `since` fields are trailing, in order, and not newer than
+/// the schema
+fn check_since(shape: &Shape, version: u16) -> Result<(), String> {
+    let mut last = 1;
+    for field in fields(shape) {
+        if field.since < last {
+            return Err(format!("field `{}` is older than the fields before it", field.name));
+        }
+        if field.since > version {
+            return Err(format!("field `{}` is newer than the schema version", field.name));
+        }
+        last = field.since;
+    }
+    Ok(())
+}
+
+/// Parse the fields of a brace (named) or parenthesis (tuple) group
+fn parse_fields(stream: TokenStream, named: bool) -> Result<Vec<Field>, String> {
+    split_commas(stream)
+        .into_iter()
+        .enumerate()
+        .map(|(index, tokens)| {
+            let mut pos = 0;
+            let attrs = take_attributes(&tokens, &mut pos)?;
+            skip_visibility(&tokens, &mut pos);
+            let name = if named {
+                ident_at(&tokens, pos).ok_or("malformed field")?
+            } else {
+                index.to_string()
+            };
+            let since = attr_u16(&attrs, "since")?.unwrap_or(1);
+            Ok(Field { name, since })
+        })
+        .collect()
+}
+
+/// Parse the variants of an enum body
+fn parse_variants(stream: TokenStream) -> Result<Vec<Variant>, String> {
+    split_commas(stream)
+        .into_iter()
+        .map(|tokens| {
+            let mut pos = 0;
+            take_attributes(&tokens, &mut pos)?;
+            let name = ident_at(&tokens, pos).ok_or("malformed variant")?;
+            let shape = match tokens.get(pos + 1) {
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                    Shape::Named(parse_fields(g.stream(), true)?)
+                }
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                    Shape::Tuple(parse_fields(g.stream(), false)?)
+                }
+                _ => Shape::Unit,
+            };
+            Ok(Variant { name, shape })
+        })
+        .collect()
+}
+
+/// Split a stream at commas outside of `<...>`, dropping empty pieces
+fn split_commas(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut pieces = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut arrow = false;
+    for token in stream {
+        if let TokenTree::Punct(p) = &token {
+            match p.as_char() {
+                ',' if depth == 0 => {
+                    pieces.push(Vec::new());
+                    continue;
+                }
+                '<' => depth += 1,
+                // `->` in function pointer types is not a closing bracket
+                '>' if !arrow => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            arrow = p.as_char() == '-' && p.spacing() == Spacing::Joint;
+        } else {
+            arrow = false;
+        }
+        pieces.last_mut().expect("pieces").push(token);
+    }
+    pieces.retain(|piece| !piece.is_empty());
+    pieces
+}
+
+/// Consume outer attributes, returning the `key = value` pairs of
+/// `#[message(...)]`
+fn take_attributes(tokens: &[TokenTree], pos: &mut usize) -> Result<Vec<(String, String)>, String> {
+    let mut pairs = Vec::new();
+    while let (Some(TokenTree::Punct(hash)), Some(TokenTree::Group(group))) = (tokens.get(*pos), tokens.get(*pos + 1)) {
+        if hash.as_char() != '#' || group.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        *pos += 2;
+
+        let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+        let args = match inner.as_slice() {
+            [TokenTree::Ident(ident), TokenTree::Group(args)] if ident.to_string() == "message" => args.stream(),
+            _ => continue,
+        };
+        for arg in split_commas(args) {
+            match arg.as_slice() {
+                [TokenTree::Ident(key), TokenTree::Punct(eq), value] if eq.as_char() == '=' => {
+                    pairs.push((key.to_string(), value.to_string()));
+                }
+                _ => return Err(String::from("expected #[message(key = value, ...)]")),
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Integer value of a `#[message]` key
+fn attr_u16(attrs: &[(String, String)], key: &str) -> Result<Option<u16>, String> {
+    attrs.iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.parse().map_err(|_| format!("#[message({} = ...)] expects an integer", key)))
+        .transpose()
+}
+
+/// Skip `pub`, `pub(crate)` and the like
+fn skip_visibility(tokens: &[TokenTree], pos: &mut usize) {
+    if matches!(tokens.get(*pos), Some(TokenTree::Ident(ident)) if ident.to_string() == "pub") {
+        *pos += 1;
+        if matches!(tokens.get(*pos), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis) {
+            *pos += 1;
+        }
+    }
+}
+
+fn ident_at(tokens: &[TokenTree], pos: usize) -> Option<String> {
+    match tokens.get(pos) {
+        Some(TokenTree::Ident(ident)) => Some(ident.to_string()),
+        _ => None,
+    }
+}
@@ -48,6 +48,44 @@ fn cmp(&self, other: &Self) -> Ordering {
     }
 }
 
+/// ABI fingerprint: the ABI version and a hash of the exported symbols
+///
+/// Like a `vermagic` string, it ties a module build to the kernel it was
+/// built against. Written as `MAJOR.MINOR-HASH` (16 hex digits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbiFingerprint {
+    /// ABI version
+    pub version: AbiVersion,
+    /// FNV-1a hash of the exported symbol names and kinds, in name order
+    pub symbols: u64,
+}
+
+impl AbiFingerprint {
+    /// Can a module built for `self` be loaded by a kernel with `kernel`?
+    pub fn is_compatible_with(&self, kernel: &Self) -> bool {
+        self.symbols == kernel.symbols && kernel.version.is_compatible_with(&self.version)
+    }
+
+    /// Parse `MAJOR.MINOR-HASH`
+    pub fn parse(text: &str) -> Option<Self> {
+        let (version, hash) = text.split_once('-')?;
+        let (major, minor) = version.split_once('.')?;
+        if hash.len() != 16 {
+            return None;
+        }
+        Some(Self {
+            version: AbiVersion::new(major.parse().ok()?, minor.parse().ok()?),
+            symbols: u64::from_str_radix(hash, 16).ok()?,
+        })
+    }
+}
+
+impl core::fmt::Display for AbiFingerprint {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}-{:016x}", self.version.major, self.version.minor, self.symbols)
+    }
+}
+
 /// ABI compatibility checker
 pub struct AbiChecker {
     /// Minimum supported ABI version
@@ -183,6 +221,20 @@ pub fn exported_symbols(&self) -> alloc::vec::Vec<Symbol> {
             .cloned()
             .collect()
     }
+
+    /// Fingerprint of the exported symbols at ABI `version`
+    pub fn fingerprint(&self, version: AbiVersion) -> AbiFingerprint {
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = FNV_OFFSET;
+        for symbol in self.symbols.read().values().filter(|s| s.exported) {
+            for &byte in symbol.name.as_bytes().iter().chain(&[0, symbol.kind as u8]) {
+                hash = (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+            }
+        }
+        AbiFingerprint { version, symbols: hash }
+    }
 }
 
 /// Global symbol table
@@ -192,3 +244,8 @@ pub fn exported_symbols(&self) -> alloc::vec::Vec<Symbol> {
 pub fn global_symbols() -> &'static SymbolTable {
     &GLOBAL_SYMBOLS
 }
+
+/// Fingerprint of the running kernel, which packages must match
+pub fn kernel_fingerprint() -> AbiFingerprint {
+    GLOBAL_SYMBOLS.fingerprint(AbiVersion::CURRENT)
+}
@@ -0,0 +1,633 @@
+//! # Module Memory Accounting
+//!
+//! Attributes kernel heap allocations to the module that made them, so a
+//! module leaking memory shows up instead of disappearing into the
+//! global heap numbers.
+//!
+//! - Module entry points run inside a per-CPU module context
+//!   ([`MemoryAccounting::enter`]). Allocations made while it is set are
+//!   charged to that module, wherever they are freed.
+//! - The heap's allocation hooks feed [`MemoryAccounting::on_alloc`] and
+//!   [`MemoryAccounting::on_dealloc`]. Each charged allocation is recorded
+//!   with its callsite (from the backtrace hook).
+//! - A module that grows past its high-water mark raises one alarm. The
+//!   mark re-arms once usage drops below it again.
+//! - When a module is unregistered with a non-zero balance, its
+//!   outstanding allocations are logged with their callsites.
+//!
+//! Everything runs inside the allocator, so the tables are fixed-size and
+//! never allocate. Allocations made while this CPU is already inside the
+//! accounting code (the alarm hook, a report) are not charged. Once the
+//! allocation table or the module slots are full, new allocations are
+//! counted as untracked.
+//!
+//! Usage is rendered at `/proc/module_memory`:
+//!
+//! ```text
+//! module               bytes     peak      allocs    frees     high_water  alarms
+//! net.e1000            184320    262144    1204      1160      1048576     0
+//! fs.helixfs           72704     72704     93        12        -           0
+//! ```
+
+use crate::registry::registry;
+use crate::ModuleId;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use spin::{Mutex, RwLock};
+
+/// Modules that can be accounted at once
+pub const MAX_MODULES: usize = 64;
+
+/// Allocations that can be recorded at once
+pub const MAX_ALLOCATIONS: usize = 4096;
+
+/// Return addresses recorded per allocation
+pub const CALLSITE_FRAMES: usize = 4;
+
+/// Maximum CPUs with a module context
+pub const MAX_CPUS: usize = 64;
+
+/// Outstanding allocations listed in a leak report
+pub const LEAK_REPORT_MAX: usize = 16;
+
+/// File name under `/proc`
+pub const PROC_PATH: &str = "module_memory";
+
+/// No module
+const NONE: u64 = 0;
+
+/// Per-module memory usage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModuleMemory {
+    /// Bytes currently allocated
+    pub bytes: usize,
+    /// Highest `bytes` seen
+    pub peak: usize,
+    /// Allocations charged
+    pub allocations: u64,
+    /// Charged allocations freed
+    pub frees: u64,
+    /// Alarm threshold (0: none)
+    pub high_water: usize,
+    /// Alarms raised
+    pub alarms: u64,
+}
+
+/// An outstanding allocation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Allocation {
+    /// Block address
+    pub ptr: usize,
+    /// Requested size
+    pub size: usize,
+    /// Return addresses, innermost first (0: unused)
+    pub callsite: [u64; CALLSITE_FRAMES],
+}
+
+/// Allocations outstanding when a module was unregistered
+#[derive(Debug, Clone, Copy)]
+pub struct LeakReport {
+    /// Module
+    pub module: ModuleId,
+    /// Bytes outstanding
+    pub bytes: usize,
+    /// Allocations outstanding
+    pub count: usize,
+    /// The first outstanding allocations
+    pub allocations: [Allocation; LEAK_REPORT_MAX],
+}
+
+impl LeakReport {
+    /// Listed allocations
+    pub fn listed(&self) -> &[Allocation] {
+        &self.allocations[..self.count.min(LEAK_REPORT_MAX)]
+    }
+}
+
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "module {} unloaded with {} bytes in {} allocations outstanding",
+            self.module.as_u64(),
+            self.bytes,
+            self.count
+        )?;
+        for alloc in self.listed() {
+            write!(f, "    {:#x} ({} bytes) from", alloc.ptr, alloc.size)?;
+            for &addr in alloc.callsite.iter().take_while(|&&a| a != 0) {
+                write!(f, " {:#x}", addr)?;
+            }
+            writeln!(f)?;
+        }
+        if self.count > LEAK_REPORT_MAX {
+            writeln!(f, "    ... {} more", self.count - LEAK_REPORT_MAX)?;
+        }
+        Ok(())
+    }
+}
+
+/// Callbacks into the kernel
+#[derive(Clone, Copy)]
+pub struct AccountingHooks {
+    /// Current CPU
+    pub cpu_id: fn() -> usize,
+    /// Fill the slice with the caller's return addresses; returns the
+    /// number written
+    pub backtrace: fn(&mut [u64]) -> usize,
+    /// A module crossed its high-water mark (bytes, mark)
+    pub alarm: fn(ModuleId, usize, usize),
+}
+
+// =============================================================================
+// Tables
+// =============================================================================
+
+#[derive(Clone, Copy)]
+struct Slot {
+    module: u64,
+    usage: ModuleMemory,
+    /// Above the high-water mark since the last alarm
+    alarmed: bool,
+}
+
+const EMPTY_SLOT: Slot = Slot {
+    module: NONE,
+    usage: ModuleMemory {
+        bytes: 0,
+        peak: 0,
+        allocations: 0,
+        frees: 0,
+        high_water: 0,
+        alarms: 0,
+    },
+    alarmed: false,
+};
+
+#[derive(Clone, Copy)]
+struct Record {
+    module: u64,
+    alloc: Allocation,
+}
+
+const EMPTY_RECORD: Record = Record {
+    module: NONE,
+    alloc: Allocation {
+        ptr: 0,
+        size: 0,
+        callsite: [0; CALLSITE_FRAMES],
+    },
+};
+
+/// Module slots and an open-addressed allocation table keyed by address
+struct Tables {
+    slots: [Slot; MAX_MODULES],
+    records: [Record; MAX_ALLOCATIONS],
+    recorded: usize,
+}
+
+impl Tables {
+    fn slot(&mut self, module: u64) -> Option<&mut Slot> {
+        self.slots.iter_mut().find(|s| s.module == module)
+    }
+
+    fn slot_or_insert(&mut self, module: u64) -> Option<&mut Slot> {
+        let index = self
+            .slots
+            .iter()
+            .position(|s| s.module == module)
+            .or_else(|| self.slots.iter().position(|s| s.module == NONE))?;
+        let slot = &mut self.slots[index];
+        slot.module = module;
+        Some(slot)
+    }
+
+    fn home(ptr: usize) -> usize {
+        (ptr >> 4).wrapping_mul(0x9E37_79B9_7F4A_7C15) % MAX_ALLOCATIONS
+    }
+
+    fn find(&self, ptr: usize) -> Option<usize> {
+        let mut i = Self::home(ptr);
+        loop {
+            match self.records[i].alloc.ptr {
+                0 => return None,
+                p if p == ptr => return Some(i),
+                _ => i = (i + 1) % MAX_ALLOCATIONS,
+            }
+        }
+    }
+
+    /// Insert, keeping the table at most 7/8 full so probes terminate
+    fn insert(&mut self, record: Record) -> bool {
+        if self.recorded >= MAX_ALLOCATIONS / 8 * 7 {
+            return false;
+        }
+        let mut i = Self::home(record.alloc.ptr);
+        while self.records[i].alloc.ptr != 0 {
+            i = (i + 1) % MAX_ALLOCATIONS;
+        }
+        self.records[i] = record;
+        self.recorded += 1;
+        true
+    }
+
+    /// Remove entry `i`, shifting later entries of its probe run back
+    fn remove(&mut self, mut i: usize) -> Record {
+        let removed = self.records[i];
+        let mut j = i;
+        loop {
+            j = (j + 1) % MAX_ALLOCATIONS;
+            if self.records[j].alloc.ptr == 0 {
+                break;
+            }
+            let home = Self::home(self.records[j].alloc.ptr);
+            // Move `j` into the hole unless its home lies in (i, j]
+            let stays = if i <= j { i < home && home <= j } else { i < home || home <= j };
+            if !stays {
+                self.records[i] = self.records[j];
+                i = j;
+            }
+        }
+        self.records[i] = EMPTY_RECORD;
+        self.recorded -= 1;
+        removed
+    }
+
+    /// Un-charge a removed record
+    fn release(&mut self, record: &Record) {
+        if let Some(slot) = self.slot(record.module) {
+            slot.usage.bytes = slot.usage.bytes.saturating_sub(record.alloc.size);
+            slot.usage.frees += 1;
+            if slot.usage.bytes < slot.usage.high_water {
+                slot.alarmed = false;
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Accounting
+// =============================================================================
+
+/// Per-module memory accounting state
+pub struct MemoryAccounting {
+    tables: Mutex<Tables>,
+    /// Module context per CPU
+    current: [AtomicU64; MAX_CPUS],
+    /// CPU is inside the accounting code
+    busy: [AtomicBool; MAX_CPUS],
+    /// Allocations recorded (lets frees skip the table while 0)
+    tracked: AtomicUsize,
+    /// Allocations not recorded because the table was full
+    untracked: AtomicU64,
+    hooks: RwLock<Option<AccountingHooks>>,
+}
+
+impl MemoryAccounting {
+    /// Create empty accounting state
+    pub const fn new() -> Self {
+        Self {
+            tables: Mutex::new(Tables {
+                slots: [EMPTY_SLOT; MAX_MODULES],
+                records: [EMPTY_RECORD; MAX_ALLOCATIONS],
+                recorded: 0,
+            }),
+            current: [const { AtomicU64::new(NONE) }; MAX_CPUS],
+            busy: [const { AtomicBool::new(false) }; MAX_CPUS],
+            tracked: AtomicUsize::new(0),
+            untracked: AtomicU64::new(0),
+            hooks: RwLock::new(None),
+        }
+    }
+
+    /// Install the kernel callbacks
+    pub fn set_hooks(&self, hooks: AccountingHooks) {
+        *self.hooks.write() = Some(hooks);
+    }
+
+    fn cpu(&self) -> usize {
+        self.hooks
+            .read()
+            .map(|h| (h.cpu_id)())
+            .unwrap_or(0)
+            .min(MAX_CPUS - 1)
+    }
+
+    /// Run `f` on the tables with this CPU marked busy, so allocations it
+    /// causes skip accounting instead of deadlocking on the tables
+    ///
+    /// Returns `None` if this CPU is already inside the accounting code.
+    fn locked<R>(&self, cpu: usize, f: impl FnOnce(&mut Tables) -> R) -> Option<R> {
+        if self.busy[cpu].swap(true, Ordering::Acquire) {
+            return None;
+        }
+        let ret = f(&mut self.tables.lock());
+        self.busy[cpu].store(false, Ordering::Release);
+        Some(ret)
+    }
+
+    // =========================================================================
+    // Module Context
+    // =========================================================================
+
+    /// Charge allocations on this CPU to `module` until the guard drops
+    ///
+    /// Contexts nest. The caller must not migrate to another CPU while the
+    /// guard is alive.
+    pub fn enter(&self, module: ModuleId) -> ModuleContextGuard<'_> {
+        let cpu = self.cpu();
+        let previous = self.current[cpu].swap(module.as_u64(), Ordering::Relaxed);
+        ModuleContextGuard { accounting: self, cpu, previous }
+    }
+
+    /// Module charged for allocations on this CPU
+    pub fn current(&self) -> Option<ModuleId> {
+        match self.current[self.cpu()].load(Ordering::Relaxed) {
+            NONE => None,
+            id => Some(ModuleId::from_raw(id)),
+        }
+    }
+
+    // =========================================================================
+    // Allocator Hooks
+    // =========================================================================
+
+    /// Allocator hook: `size` bytes were allocated at `ptr`
+    pub fn on_alloc(&self, ptr: *mut u8, size: usize) {
+        let cpu = self.cpu();
+        let module = self.current[cpu].load(Ordering::Relaxed);
+        if module == NONE || self.busy[cpu].load(Ordering::Relaxed) {
+            return;
+        }
+        let hooks = *self.hooks.read();
+
+        let mut block = Allocation { ptr: ptr as usize, size, callsite: [0; CALLSITE_FRAMES] };
+        if let Some(hooks) = hooks {
+            (hooks.backtrace)(&mut block.callsite);
+        }
+
+        let alarm = self.locked(cpu, |t| {
+            // A stale record for a reused address: its free went unseen
+            if let Some(i) = t.find(block.ptr) {
+                let stale = t.remove(i);
+                t.release(&stale);
+                self.tracked.fetch_sub(1, Ordering::Relaxed);
+            }
+            let Some(slot) = t.slot_or_insert(module) else {
+                self.untracked.fetch_add(1, Ordering::Relaxed);
+                return None;
+            };
+            slot.usage.bytes += size;
+            slot.usage.peak = slot.usage.peak.max(slot.usage.bytes);
+            slot.usage.allocations += 1;
+            let usage = slot.usage;
+            let alarm = usage.high_water != 0 && usage.bytes > usage.high_water && !slot.alarmed;
+            if alarm {
+                slot.alarmed = true;
+                slot.usage.alarms += 1;
+            }
+
+            if t.insert(Record { module, alloc: block }) {
+                self.tracked.fetch_add(1, Ordering::Relaxed);
+            } else if let Some(slot) = t.slot(module) {
+                // Unrecorded blocks cannot be un-charged when freed
+                slot.usage.bytes -= size;
+                slot.usage.allocations -= 1;
+                self.untracked.fetch_add(1, Ordering::Relaxed);
+            }
+            alarm.then_some((usage.bytes, usage.high_water))
+        });
+
+        if let Some(Some((bytes, mark))) = alarm {
+            // Still busy: allocations made while reporting are not charged
+            self.busy[cpu].store(true, Ordering::Relaxed);
+            match hooks {
+                Some(hooks) => (hooks.alarm)(ModuleId::from_raw(module), bytes, mark),
+                None => log::warn!("module {}: {} bytes allocated, high-water mark {}", module, bytes, mark),
+            }
+            self.busy[cpu].store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Allocator hook: the block at `ptr` is about to be freed
+    pub fn on_dealloc(&self, ptr: *mut u8, _size: usize) {
+        if self.tracked.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        let cpu = self.cpu();
+        self.locked(cpu, |t| {
+            if let Some(i) = t.find(ptr as usize) {
+                let record = t.remove(i);
+                t.release(&record);
+                self.tracked.fetch_sub(1, Ordering::Relaxed);
+            }
+        });
+    }
+
+    // =========================================================================
+    // Queries
+    // =========================================================================
+
+    /// Usage of `module`, if it has been charged anything
+    pub fn usage(&self, module: ModuleId) -> Option<ModuleMemory> {
+        self.locked(self.cpu(), |t| t.slot(module.as_u64()).map(|s| s.usage))
+            .flatten()
+    }
+
+    /// Allocations not recorded because the table was full
+    pub fn untracked(&self) -> u64 {
+        self.untracked.load(Ordering::Relaxed)
+    }
+
+    /// Raise an alarm when `module` grows past `bytes` (0: never)
+    pub fn set_high_water(&self, module: ModuleId, bytes: usize) -> bool {
+        self.locked(self.cpu(), |t| match t.slot_or_insert(module.as_u64()) {
+            Some(slot) => {
+                slot.usage.high_water = bytes;
+                slot.alarmed = bytes != 0 && slot.usage.bytes > bytes;
+                true
+            }
+            None => false,
+        })
+        .unwrap_or(false)
+    }
+
+    /// Forget `module`, returning its outstanding allocations if any
+    ///
+    /// The records are dropped: blocks freed later are no longer charged.
+    pub fn release(&self, module: ModuleId) -> Option<LeakReport> {
+        let id = module.as_u64();
+        self.locked(self.cpu(), |t| {
+            let slot = t.slot(id)?;
+            let bytes = slot.usage.bytes;
+            *slot = EMPTY_SLOT;
+
+            let mut report = LeakReport {
+                module,
+                bytes,
+                count: 0,
+                allocations: [Allocation::default(); LEAK_REPORT_MAX],
+            };
+            let mut i = 0;
+            while i < MAX_ALLOCATIONS {
+                if t.records[i].alloc.ptr == 0 || t.records[i].module != id {
+                    i += 1;
+                    continue;
+                }
+                // Removal may shift another record into `i`: look again
+                let record = t.remove(i);
+                self.tracked.fetch_sub(1, Ordering::Relaxed);
+                if report.count < LEAK_REPORT_MAX {
+                    report.allocations[report.count] = record.alloc;
+                }
+                report.count += 1;
+            }
+            (bytes != 0 || report.count != 0).then_some(report)
+        })
+        .flatten()
+    }
+
+    /// Render `/proc/module_memory`
+    pub fn render_proc(&self, out: &mut dyn Write) -> fmt::Result {
+        // Snapshot first: names come from the registry, which must not be
+        // locked inside the tables
+        let slots = self
+            .locked(self.cpu(), |t| t.slots)
+            .unwrap_or([EMPTY_SLOT; MAX_MODULES]);
+
+        writeln!(
+            out,
+            "module               bytes     peak      allocs    frees     high_water  alarms"
+        )?;
+        for slot in slots.iter().filter(|s| s.module != NONE) {
+            let usage = &slot.usage;
+            let id = ModuleId::from_raw(slot.module);
+            match registry().get(id) {
+                Some(meta) => write!(out, "{:<20} ", meta.name)?,
+                None => write!(out, "{:<20} ", slot.module)?,
+            }
+            write!(
+                out,
+                "{:<9} {:<9} {:<9} {:<9} ",
+                usage.bytes, usage.peak, usage.allocations, usage.frees
+            )?;
+            match usage.high_water {
+                0 => write!(out, "{:<11} ", "-")?,
+                mark => write!(out, "{:<11} ", mark)?,
+            }
+            writeln!(out, "{}", usage.alarms)?;
+        }
+        let untracked = self.untracked();
+        if untracked != 0 {
+            writeln!(out, "\nuntracked allocations: {}", untracked)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for MemoryAccounting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Restores the previous module context when dropped
+pub struct ModuleContextGuard<'a> {
+    accounting: &'a MemoryAccounting,
+    cpu: usize,
+    previous: u64,
+}
+
+impl !Send for ModuleContextGuard<'_> {}
+
+impl Drop for ModuleContextGuard<'_> {
+    fn drop(&mut self) {
+        self.accounting.current[self.cpu].store(self.previous, Ordering::Relaxed);
+    }
+}
+
+/// Global module memory accounting
+static ACCOUNTING: MemoryAccounting = MemoryAccounting::new();
+
+/// Get the global module memory accounting
+pub fn accounting() -> &'static MemoryAccounting {
+    &ACCOUNTING
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(addr: usize) -> *mut u8 {
+        addr as *mut u8
+    }
+
+    #[test]
+    fn test_charges_current_module() {
+        let acct = MemoryAccounting::new();
+        let module = ModuleId::from_raw(7);
+
+        acct.on_alloc(block(0x1000), 64);
+        assert_eq!(acct.usage(module), None);
+
+        {
+            let _ctx = acct.enter(module);
+            acct.on_alloc(block(0x2000), 128);
+            acct.on_alloc(block(0x3000), 32);
+        }
+        assert_eq!(acct.current(), None);
+
+        // Freed outside the module context: still un-charged
+        acct.on_dealloc(block(0x2000), 128);
+        acct.on_dealloc(block(0x1000), 64);
+
+        let usage = acct.usage(module).unwrap();
+        assert_eq!(usage.bytes, 32);
+        assert_eq!(usage.peak, 160);
+        assert_eq!((usage.allocations, usage.frees), (2, 1));
+    }
+
+    #[test]
+    fn test_high_water_alarm() {
+        let acct = MemoryAccounting::new();
+        let module = ModuleId::from_raw(3);
+        assert!(acct.set_high_water(module, 100));
+
+        let _ctx = acct.enter(module);
+        acct.on_alloc(block(0x1000), 80);
+        acct.on_alloc(block(0x2000), 40);
+        acct.on_alloc(block(0x3000), 40);
+        assert_eq!(acct.usage(module).unwrap().alarms, 1);
+
+        // Re-armed after dropping below the mark
+        acct.on_dealloc(block(0x2000), 40);
+        acct.on_dealloc(block(0x3000), 40);
+        acct.on_alloc(block(0x4000), 50);
+        assert_eq!(acct.usage(module).unwrap().alarms, 2);
+    }
+
+    #[test]
+    fn test_leak_report() {
+        let acct = MemoryAccounting::new();
+        let module = ModuleId::from_raw(9);
+        {
+            let _ctx = acct.enter(module);
+            for i in 0..20 {
+                acct.on_alloc(block(0x10_0000 + i * 0x40), 16);
+            }
+        }
+        for i in 0..10 {
+            acct.on_dealloc(block(0x10_0000 + i * 0x40), 16);
+        }
+
+        let report = acct.release(module).unwrap();
+        assert_eq!(report.bytes, 160);
+        assert_eq!(report.count, 10);
+        assert_eq!(report.listed().len(), 10);
+        assert!(report.listed().iter().all(|a| a.ptr >= 0x10_0000 + 10 * 0x40));
+
+        assert_eq!(acct.usage(module), None);
+        assert!(acct.release(module).is_none());
+        assert_eq!(acct.tracked.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,189 @@
+//! # Module Aliases
+//!
+//! On-demand loading, modprobe style. When no registered driver matches a
+//! device, the driver subsystem asks for the device's modaliases; the
+//! modules listed for them in [`ALIAS_FILE`] are loaded from
+//! [`MODULE_DIR`] as `NAME.hxm`.
+//!
+//! ```text
+//! # alias PATTERN MODULE
+//! alias pci:v00001AF4d00001041* virtio-net
+//! alias virtio:d00000001 virtio-net
+//! alias acpi:PNP0501 serial-8250
+//! alias of:ns16550a serial-8250
+//! ```
+//!
+//! A pattern matches the whole modalias; `*` matches any run of
+//! characters and `?` any one. Modaliases are built by the driver
+//! subsystem: `pci:v<vendor>d<device>sv<subvendor>sd<subdevice>bc<class>sc<subclass>i<interface>`
+//! (hex, 8 digits for IDs and 2 for class bytes), `virtio:d<type>`,
+//! `acpi:<HID or CID>` and `of:<compatible>`.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use spin::RwLock;
+
+use crate::loader::{self, LoadedModule};
+use crate::package::MODULE_DIR;
+use crate::registry;
+use crate::ModuleResult;
+
+/// Alias table, installed alongside the packages
+pub const ALIAS_FILE: &str = "/lib/modules/modules.alias";
+
+/// Does `pattern` match all of `text`?
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it resumes from
+    let mut star = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more character
+                Some((after, from)) => {
+                    p = after;
+                    t = from + 1;
+                    star = Some((after, from + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// One `alias` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alias {
+    /// Modalias pattern
+    pub pattern: String,
+    /// Module name
+    pub module: String,
+}
+
+/// Parsed alias file
+#[derive(Debug, Clone, Default)]
+pub struct AliasTable {
+    aliases: Vec<Alias>,
+}
+
+impl AliasTable {
+    /// Parse an alias file, skipping comments and malformed lines
+    pub fn parse(text: &str) -> Self {
+        let aliases = text
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                match (words.next(), words.next(), words.next(), words.next()) {
+                    (Some("alias"), Some(pattern), Some(module), None) => Some(Alias {
+                        pattern: pattern.to_string(),
+                        module: module.to_string(),
+                    }),
+                    _ => None,
+                }
+            })
+            .collect();
+        Self { aliases }
+    }
+
+    /// Read [`ALIAS_FILE`] through the registered file source
+    pub fn load() -> ModuleResult<Self> {
+        let text = loader::file_source()?.read(ALIAS_FILE)?;
+        Ok(Self::parse(&String::from_utf8_lossy(&text)))
+    }
+
+    /// Aliases in file order
+    pub fn aliases(&self) -> &[Alias] {
+        &self.aliases
+    }
+
+    /// Modules for `modalias`, in file order without duplicates
+    pub fn modules_for(&self, modalias: &str) -> Vec<&str> {
+        let mut modules: Vec<&str> = Vec::new();
+        for alias in &self.aliases {
+            if glob_match(&alias.pattern, modalias) && !modules.contains(&alias.module.as_str()) {
+                modules.push(&alias.module);
+            }
+        }
+        modules
+    }
+}
+
+/// Alias table, read on first use
+static ALIASES: RwLock<Option<AliasTable>> = RwLock::new(None);
+
+/// Forget the cached alias table (after modules were installed)
+pub fn reload_aliases() {
+    *ALIASES.write() = None;
+}
+
+/// Load the modules providing drivers for `modalias`
+///
+/// Modules already registered are skipped. Loaded modules are registered
+/// and returned for the caller to initialize; none is not an error.
+pub fn request_module(modalias: &str) -> ModuleResult<Vec<LoadedModule>> {
+    if ALIASES.read().is_none() {
+        let table = AliasTable::load()?;
+        *ALIASES.write() = Some(table);
+    }
+    let modules: Vec<String> = match &*ALIASES.read() {
+        Some(table) => table.modules_for(modalias).into_iter().map(String::from).collect(),
+        None => Vec::new(),
+    };
+
+    let mut loaded = Vec::new();
+    for name in modules {
+        if registry::registry().id_by_name(&name).is_some() {
+            continue;
+        }
+        log::info!("Loading module {} for {}", name, modalias);
+        let module = loader::load_from_path(&format!("{}/{}.hxm", MODULE_DIR, name))?;
+        let metadata = module.module.read().metadata().clone();
+        registry::registry().register(metadata)?;
+        loaded.push(module);
+    }
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("pci:v00001AF4d00001041*", "pci:v00001AF4d00001041sv00001AF4sd00001100bc02sc00i00"));
+        assert!(!glob_match("pci:v00001AF4d00001041*", "pci:v00001AF4d00001042sv00001AF4"));
+        assert!(glob_match("pci:v*d*bc02sc00i*", "pci:v00008086d0000100Esv00008086sd00000000bc02sc00i00"));
+        assert!(glob_match("acpi:PNP050?", "acpi:PNP0501"));
+        assert!(!glob_match("acpi:PNP050?", "acpi:PNP05011"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("virtio:d00000001", "virtio:d00000002"));
+    }
+
+    #[test]
+    fn test_alias_table() {
+        let table = AliasTable::parse(
+            "# generated by helix-mod\n\
+             alias pci:v00001AF4d00001041* virtio-net\n\
+             alias virtio:d00000001 virtio-net\n\
+             alias acpi:PNP0501 serial-8250\n\
+             alias acpi:PNP05* serial-8250\n\
+             alias broken\n",
+        );
+        assert_eq!(table.aliases().len(), 4);
+        assert_eq!(table.modules_for("virtio:d00000001"), ["virtio-net"]);
+        assert_eq!(table.modules_for("acpi:PNP0501"), ["serial-8250"]);
+        assert!(table.modules_for("of:arm,pl011").is_empty());
+    }
+}
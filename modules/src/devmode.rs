@@ -0,0 +1,509 @@
+//! # Developer Mode
+//!
+//! Watch-and-reload for iterating on a module without rebooting. The
+//! kernel either polls module binaries in a build directory shared with
+//! the host (a 9p or virtio-fs mount, read through the registered
+//! [`ModuleFileSource`](crate::loader::ModuleFileSource)) or receives them
+//! over a serial channel, and hot-swaps the running module whenever its
+//! binary changes.
+//!
+//! Swaps go through [`HotReloadEngine::swap`](crate::hot_reload::HotReloadEngine::swap),
+//! so only modules flagged `HOT_RELOADABLE` are replaced and a failed
+//! swap leaves the old instance running. Every attempt is logged and kept
+//! as a [`SwapReport`].
+//!
+//! Serial uploads are framed as below (integers little-endian, the
+//! checksum is FNV-1a over the image); bytes outside a frame are skipped.
+//!
+//! ```text
+//! "HXUP" | name length: u16 | name | image length: u32 | image | checksum: u32
+//! ```
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::hot_reload::{self, ModuleHost};
+use crate::loader::{self, LoadedModule, ModuleLoader};
+use crate::registry;
+use crate::{ModuleError, ModuleResult, ModuleVersion};
+
+/// Start of a serial upload frame
+pub const UPLOAD_MAGIC: [u8; 4] = *b"HXUP";
+
+/// Largest image accepted over serial
+pub const MAX_UPLOAD: usize = 16 * 1024 * 1024;
+
+/// Longest module name accepted over serial
+const MAX_NAME: usize = 64;
+
+/// Swap reports kept for [`DevMode::reports`]
+pub const MAX_REPORTS: usize = 32;
+
+/// Frame bytes around the name and image
+const FRAME_OVERHEAD: usize = 4 + 2 + 4 + 4;
+
+/// FNV-1a, the upload checksum and the content stamp of watched files
+pub fn fnv1a(data: &[u8]) -> u32 {
+    data.iter().fold(0x811C_9DC5, |hash, &byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+/// Build a serial upload frame (for tests and host tools)
+pub fn encode_upload(module: &str, image: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(FRAME_OVERHEAD + module.len() + image.len());
+    frame.extend_from_slice(&UPLOAD_MAGIC);
+    frame.extend_from_slice(&(module.len() as u16).to_le_bytes());
+    frame.extend_from_slice(module.as_bytes());
+    frame.extend_from_slice(&(image.len() as u32).to_le_bytes());
+    frame.extend_from_slice(image);
+    frame.extend_from_slice(&fnv1a(image).to_le_bytes());
+    frame
+}
+
+/// A module image received over serial
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upload {
+    /// Module to replace
+    pub module: String,
+    /// Module binary or signed package
+    pub image: Vec<u8>,
+}
+
+/// Reassembles upload frames from serial bytes
+#[derive(Debug, Default)]
+pub struct UploadReceiver {
+    buffer: Vec<u8>,
+}
+
+impl UploadReceiver {
+    /// Create an empty receiver
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed received bytes, returning the frames they complete
+    ///
+    /// Malformed frames (bad length, name or checksum) come back as
+    /// errors and are skipped.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<ModuleResult<Upload>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut frames = Vec::new();
+
+        loop {
+            // Resynchronize on the magic, keeping a partial one
+            match self.buffer.windows(4).position(|w| w == UPLOAD_MAGIC) {
+                Some(start) => {
+                    self.buffer.drain(..start);
+                }
+                None => {
+                    let keep = self.buffer.len().min(3);
+                    self.buffer.drain(..self.buffer.len() - keep);
+                    return frames;
+                }
+            }
+
+            match self.parse() {
+                Some(Ok((frame, used))) => {
+                    self.buffer.drain(..used);
+                    frames.push(Ok(frame));
+                }
+                Some(Err(e)) => {
+                    // Skip this magic and look for the next frame
+                    self.buffer.drain(..UPLOAD_MAGIC.len());
+                    frames.push(Err(e));
+                }
+                None => return frames,
+            }
+        }
+    }
+
+    /// Parse the frame at the start of the buffer (`None`: incomplete)
+    fn parse(&self) -> Option<ModuleResult<(Upload, usize)>> {
+        let bad = |what: &str| Some(Err(ModuleError::LoadError(format!("serial upload: {}", what))));
+        let buf = &self.buffer;
+
+        let name_len = u16::from_le_bytes(buf.get(4..6)?.try_into().ok()?) as usize;
+        if name_len == 0 || name_len > MAX_NAME {
+            return bad("bad module name length");
+        }
+        let image_at = 6 + name_len + 4;
+        let image_len = u32::from_le_bytes(buf.get(image_at - 4..image_at)?.try_into().ok()?) as usize;
+        if image_len > MAX_UPLOAD {
+            return bad("image too large");
+        }
+        let end = image_at + image_len;
+        let checksum = u32::from_le_bytes(buf.get(end..end + 4)?.try_into().ok()?);
+
+        let image = &buf[image_at..end];
+        if fnv1a(image) != checksum {
+            return bad("checksum mismatch");
+        }
+        let Ok(module) = core::str::from_utf8(&buf[6..6 + name_len]) else {
+            return bad("module name is not UTF-8");
+        };
+        Some(Ok((Upload { module: module.to_string(), image: image.to_vec() }, end + 4)))
+    }
+}
+
+/// Outcome of one swap attempt
+#[derive(Debug, Clone)]
+pub struct SwapReport {
+    /// Module name
+    pub module: String,
+    /// Where the image came from: a path, or `serial`
+    pub origin: String,
+    /// Version now running, or why the old one still is
+    pub result: ModuleResult<ModuleVersion>,
+}
+
+/// A watched module binary
+struct Watch {
+    module: String,
+    path: String,
+    /// Last modification or content stamp seen
+    stamp: Option<u64>,
+}
+
+/// Watch-and-reload state
+pub struct DevMode {
+    host: &'static dyn ModuleHost,
+    watches: Vec<Watch>,
+    receiver: UploadReceiver,
+    /// Images swapped in here, unloaded when replaced again
+    loaded: BTreeMap<String, (Arc<dyn ModuleLoader>, LoadedModule)>,
+    reports: VecDeque<SwapReport>,
+}
+
+impl DevMode {
+    /// Developer mode swapping instances held by `host`
+    pub fn new(host: &'static dyn ModuleHost) -> Self {
+        Self {
+            host,
+            watches: Vec::new(),
+            receiver: UploadReceiver::new(),
+            loaded: BTreeMap::new(),
+            reports: VecDeque::new(),
+        }
+    }
+
+    /// Swap `module` whenever the binary at absolute `path` changes
+    ///
+    /// The binary as it is now is taken as already running.
+    pub fn watch(&mut self, module: &str, path: &str) -> ModuleResult<()> {
+        if !path.starts_with('/') {
+            return Err(ModuleError::LoadError(format!("{}: watched path must be absolute", path)));
+        }
+        let stamp = Self::stamp(path)?.map(|(stamp, _)| stamp);
+        self.watches.retain(|w| w.module != module);
+        self.watches.push(Watch { module: module.to_string(), path: path.to_string(), stamp });
+        log::info!("devmode: watching {} for {}", path, module);
+        Ok(())
+    }
+
+    /// Stop watching `module`; was it watched?
+    pub fn unwatch(&mut self, module: &str) -> bool {
+        let before = self.watches.len();
+        self.watches.retain(|w| w.module != module);
+        self.watches.len() != before
+    }
+
+    /// Watched modules and their paths
+    pub fn watches(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.watches.iter().map(|w| (w.module.as_str(), w.path.as_str()))
+    }
+
+    /// Check the watched binaries, swapping modules whose binary changed
+    ///
+    /// Call periodically, e.g. from a timer. Returns this poll's reports.
+    pub fn poll(&mut self) -> Vec<SwapReport> {
+        let mut changed = Vec::new();
+        for watch in &mut self.watches {
+            match Self::stamp(&watch.path) {
+                Ok(Some((stamp, image))) if Some(stamp) != watch.stamp => {
+                    watch.stamp = Some(stamp);
+                    changed.push((watch.module.clone(), watch.path.clone(), image));
+                }
+                Ok(_) => {}
+                // Mid-rebuild files come and go; try again next poll
+                Err(e) => log::debug!("devmode: {}: {:?}", watch.path, e),
+            }
+        }
+
+        changed
+            .into_iter()
+            .map(|(module, path, image)| {
+                let image = match image {
+                    Some(image) => Ok(image),
+                    None => loader::file_source().and_then(|source| source.read(&path)),
+                };
+                match image {
+                    Ok(image) => self.swap(&module, &path, &image),
+                    Err(e) => self.report(module, path, Err(e)),
+                }
+            })
+            .collect()
+    }
+
+    /// Feed bytes from the serial channel, swapping uploaded modules
+    pub fn receive(&mut self, bytes: &[u8]) -> Vec<SwapReport> {
+        self.receiver
+            .feed(bytes)
+            .into_iter()
+            .map(|frame| match frame {
+                Ok(upload) => self.swap(&upload.module, "serial", &upload.image),
+                Err(e) => self.report("?".to_string(), "serial".to_string(), Err(e)),
+            })
+            .collect()
+    }
+
+    /// Recent swap reports, oldest first
+    pub fn reports(&self) -> impl Iterator<Item = &SwapReport> {
+        self.reports.iter()
+    }
+
+    /// Stamp of `path`, with the contents if they had to be read for it
+    ///
+    /// `None` if the file does not exist.
+    fn stamp(path: &str) -> ModuleResult<Option<(u64, Option<Vec<u8>>)>> {
+        let source = loader::file_source()?;
+        if let Some(modified) = source.modified(path) {
+            return Ok(Some((modified, None)));
+        }
+        match source.read(path) {
+            Ok(image) => Ok(Some((fnv1a(&image) as u64, Some(image)))),
+            Err(ModuleError::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Load `image` and swap it in for the running `module`
+    fn swap(&mut self, module: &str, origin: &str, image: &[u8]) -> SwapReport {
+        let result = (|| {
+            let id = registry::registry().id_by_name(module).ok_or(ModuleError::NotFound)?;
+            let (image_loader, loaded) = loader::load_image_with(image, origin)?;
+            let version = loaded.module.read().metadata().version;
+
+            let swapped = hot_reload::engine().swap(registry::registry(), id, loaded.module.clone(), self.host);
+            if !swapped.success {
+                let _ = image_loader.unload(&loaded);
+                return Err(swapped.error.unwrap_or(ModuleError::Internal("swap failed".into())));
+            }
+            if let Some((previous_loader, previous)) = self.loaded.insert(module.to_string(), (image_loader, loaded)) {
+                let _ = previous_loader.unload(&previous);
+            }
+            Ok(version)
+        })();
+        self.report(module.to_string(), origin.to_string(), result)
+    }
+
+    /// Log and keep a report
+    fn report(&mut self, module: String, origin: String, result: ModuleResult<ModuleVersion>) -> SwapReport {
+        match &result {
+            Ok(version) => log::info!("devmode: swapped {} {} from {}", module, version, origin),
+            Err(e) => log::warn!("devmode: {} from {} not swapped: {:?}", module, origin, e),
+        }
+        let report = SwapReport { module, origin, result };
+        if self.reports.len() == MAX_REPORTS {
+            self.reports.pop_front();
+        }
+        self.reports.push_back(report.clone());
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::ModuleFormat;
+    use crate::{
+        Module, ModuleContext, ModuleFlags, ModuleId, ModuleMetadata, ModuleState,
+    };
+    use alloc::boxed::Box;
+    use core::any::Any;
+    use spin::{Mutex, RwLock};
+
+    /// Counts calls; the count is its hot-reload state
+    struct Counter {
+        metadata: ModuleMetadata,
+        count: u32,
+        running: bool,
+        fail_start: bool,
+    }
+
+    impl Module for Counter {
+        fn metadata(&self) -> &ModuleMetadata {
+            &self.metadata
+        }
+
+        fn init(&mut self, _context: &ModuleContext) -> ModuleResult<()> {
+            Ok(())
+        }
+
+        fn start(&mut self) -> ModuleResult<()> {
+            if self.fail_start {
+                return Err(ModuleError::InitError("start".into()));
+            }
+            self.running = true;
+            Ok(())
+        }
+
+        fn stop(&mut self) -> ModuleResult<()> {
+            self.running = false;
+            Ok(())
+        }
+
+        fn get_state(&self) -> Option<Box<dyn Any + Send + Sync>> {
+            Some(Box::new(self.count))
+        }
+
+        fn restore_state(&mut self, state: Box<dyn Any + Send + Sync>) -> ModuleResult<()> {
+            self.count = *state.downcast::<u32>().map_err(|_| ModuleError::Internal("state".into()))?;
+            Ok(())
+        }
+    }
+
+    fn counter(id: ModuleId, minor: u16, fail_start: bool) -> Counter {
+        Counter {
+            metadata: ModuleMetadata {
+                id,
+                name: "devmode-counter".into(),
+                version: ModuleVersion::new(1, minor, 0),
+                description: String::new(),
+                authors: Vec::new(),
+                license: String::new(),
+                flags: ModuleFlags::HOT_RELOADABLE,
+                dependencies: Vec::new(),
+                provides: Vec::new(),
+                abi_version: crate::abi::AbiVersion::CURRENT,
+            },
+            count: 0,
+            running: false,
+            fail_start,
+        }
+    }
+
+    /// Images are `CTR` + minor version + fail-start flag
+    struct CounterLoader;
+
+    impl ModuleLoader for CounterLoader {
+        fn format(&self) -> ModuleFormat {
+            ModuleFormat::HelixNative
+        }
+
+        fn validate(&self, binary: &[u8]) -> ModuleResult<()> {
+            match binary {
+                [b'C', b'T', b'R', _, _] => Ok(()),
+                _ => Err(ModuleError::LoadError("not a counter".into())),
+            }
+        }
+
+        fn extract_metadata(&self, _binary: &[u8]) -> ModuleResult<ModuleMetadata> {
+            Err(ModuleError::NotFound)
+        }
+
+        fn load(&self, binary: &[u8]) -> ModuleResult<LoadedModule> {
+            self.validate(binary)?;
+            let module = counter(ModuleId::new(), binary[3] as u16, binary[4] != 0);
+            Ok(LoadedModule {
+                module: Arc::new(RwLock::new(module)),
+                load_address: None,
+                size: binary.len(),
+                state: ModuleState::Loaded,
+            })
+        }
+
+        fn unload(&self, _module: &LoadedModule) -> ModuleResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Holds one instance
+    struct Host(Mutex<Option<(ModuleId, Instance)>>);
+
+    type Instance = Arc<RwLock<dyn Module>>;
+
+    impl ModuleHost for Host {
+        fn instance(&self, id: ModuleId) -> Option<Arc<RwLock<dyn Module>>> {
+            self.0.lock().as_ref().filter(|(held, _)| *held == id).map(|(_, m)| m.clone())
+        }
+
+        fn context(&self, id: ModuleId) -> ModuleContext {
+            ModuleContext::new(id, |_| None, |_, _| Ok(()))
+        }
+
+        fn replace(&self, id: ModuleId, module: &Arc<RwLock<dyn Module>>) {
+            *self.0.lock() = Some((id, module.clone()));
+        }
+    }
+
+    static HOST: Host = Host(Mutex::new(None));
+
+    #[test]
+    fn test_serial_swap() {
+        loader::registry().register(Arc::new(CounterLoader));
+        let id = ModuleId::new();
+        registry::registry().register(counter(id, 0, false).metadata).unwrap();
+        registry::registry().set_state(id, ModuleState::Running).unwrap();
+
+        let mut running = counter(id, 0, false);
+        running.count = 7;
+        running.running = true;
+        let old: Arc<RwLock<dyn Module>> = Arc::new(RwLock::new(running));
+        HOST.replace(id, &old);
+
+        // The new build takes over the old one's count
+        let mut devmode = DevMode::new(&HOST);
+        let reports = devmode.receive(&encode_upload("devmode-counter", b"CTR\x01\x00"));
+        assert_eq!(reports[0].result, Ok(ModuleVersion::new(1, 1, 0)));
+        let current = HOST.instance(id).unwrap();
+        assert_eq!(current.read().metadata().version, ModuleVersion::new(1, 1, 0));
+        assert!(current.read().get_state().unwrap().downcast::<u32>().is_ok_and(|count| *count == 7));
+
+        // A build that fails to start leaves the running one in place
+        let reports = devmode.receive(&encode_upload("devmode-counter", b"CTR\x02\x01"));
+        assert!(reports[0].result.is_err());
+        assert!(Arc::ptr_eq(&HOST.instance(id).unwrap(), &current));
+        assert_eq!(devmode.reports().count(), 2);
+    }
+
+    #[test]
+    fn test_upload_frames() {
+        let mut receiver = UploadReceiver::new();
+        let frame = encode_upload("round-robin", b"\x7fELF\x02module");
+
+        // Noise, then a frame split across reads
+        assert!(receiver.feed(b"boot log\r\nHX").is_empty());
+        assert!(receiver.feed(&frame[..9]).is_empty());
+        let frames = receiver.feed(&frame[9..]);
+        assert_eq!(frames.len(), 1);
+        let upload = frames[0].as_ref().unwrap();
+        assert_eq!(upload.module, "round-robin");
+        assert_eq!(upload.image, b"\x7fELF\x02module");
+
+        // A corrupted frame is reported and the next one still arrives
+        let mut corrupt = encode_upload("net", b"image");
+        let last = corrupt.len() - 5;
+        corrupt[last] ^= 0xFF;
+        corrupt.extend_from_slice(&encode_upload("net", b"image2"));
+        let frames = receiver.feed(&corrupt);
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].is_err());
+        assert_eq!(frames[1].as_ref().unwrap().image, b"image2");
+    }
+
+    #[test]
+    fn test_upload_limits() {
+        let mut receiver = UploadReceiver::new();
+        let mut frame = Vec::from(UPLOAD_MAGIC);
+        frame.extend_from_slice(&0u16.to_le_bytes());
+        assert!(matches!(receiver.feed(&frame)[..], [Err(_)]));
+
+        let mut frame = Vec::from(UPLOAD_MAGIC);
+        frame.extend_from_slice(&1u16.to_le_bytes());
+        frame.push(b'x');
+        frame.extend_from_slice(&(MAX_UPLOAD as u32 + 1).to_le_bytes());
+        assert!(matches!(receiver.feed(&frame)[..], [Err(_)]));
+    }
+}
@@ -1,14 +1,28 @@
 //! # Hot Reload Engine
 //!
 //! Enables replacing modules at runtime without system restart.
+//!
+//! [`HotReloadEngine::swap`] replaces a running instance with one already
+//! loaded, through the [`ModuleHost`] that holds the kernel's instances.
+//! The old instance is locked while its state is migrated, and the switch
+//! to the replacement runs under
+//! [`stop_machine`](helix_execution::stop_machine::stop_machine), so no
+//! CPU executes the old text or observes half-migrated state. If the
+//! replacement fails to come up, the old instance is restarted.
+//!
+//! Swapping replaces running kernel code, so it is refused once the
+//! kernel is locked down.
 
 use crate::{
-    Module, ModuleId, ModuleResult, ModuleError, ModuleState, ModuleFlags,
+    Module, ModuleContext, ModuleId, ModuleResult, ModuleError, ModuleState, ModuleFlags,
     registry::{ModuleRegistry},
 };
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::sync::Arc;
 use core::any::Any;
+use helix_execution::stop_machine::stop_machine;
+use helix_hal::lockdown::{self, LockdownReason};
 use spin::RwLock;
 
 /// Hot reload state
@@ -42,6 +56,21 @@ pub struct ReloadResult {
     pub error: Option<ModuleError>,
 }
 
+/// Where the kernel keeps running module instances
+pub trait ModuleHost: Send + Sync {
+    /// Running instance of `id`
+    fn instance(&self, id: ModuleId) -> Option<Arc<RwLock<dyn Module>>>;
+
+    /// Context to initialize a replacement for `id` with
+    fn context(&self, id: ModuleId) -> ModuleContext;
+
+    /// Dispatch calls for `id` to `module` from now on
+    ///
+    /// Runs with the other CPUs stopped: it must not allocate or free, nor
+    /// take locks a stopped CPU may hold.
+    fn replace(&self, id: ModuleId, module: &Arc<RwLock<dyn Module>>);
+}
+
 /// Hot reload engine
 pub struct HotReloadEngine {
     /// Current reload state
@@ -69,6 +98,8 @@ pub fn state(&self) -> ReloadState {
 
     /// Check if a module can be hot-reloaded
     pub fn can_reload(&self, registry: &ModuleRegistry, id: ModuleId) -> ModuleResult<()> {
+        lockdown::check(LockdownReason::HotPatch).map_err(ModuleError::Lockdown)?;
+
         // Check state
         if self.state() != ReloadState::Idle {
             return Err(ModuleError::WrongState {
@@ -241,6 +272,81 @@ pub fn reload<F>(
             error: None,
         }
     }
+
+    /// Replace the running instance of `id` with `replacement`
+    ///
+    /// The replacement is initialized, the old instance stopped and its
+    /// state handed over, then the replacement started and switched in
+    /// under `stop_machine`. On success the old instance is returned in
+    /// `old_module` (as an `Arc<RwLock<dyn Module>>`) for the caller to
+    /// unload; on failure the old instance keeps running.
+    pub fn swap(
+        &self,
+        registry: &ModuleRegistry,
+        id: ModuleId,
+        replacement: Arc<RwLock<dyn Module>>,
+        host: &dyn ModuleHost,
+    ) -> ReloadResult {
+        let failed = |error| ReloadResult { success: false, old_module: None, error: Some(error) };
+
+        if let Err(e) = self.can_reload(registry, id).and_then(|()| self.begin_reload(id)) {
+            return failed(e);
+        }
+        let Some(old) = host.instance(id) else {
+            return failed(self.fail_reload(ModuleError::NotFound));
+        };
+
+        // The replacement must be the same module
+        let name = old.read().metadata().name.clone();
+        let mut new = replacement.write();
+        if new.metadata().name != name {
+            let error = ModuleError::LoadError(format!("replacement for {} is {}", name, new.metadata().name));
+            return failed(self.fail_reload(error));
+        }
+
+        *self.state.write() = ReloadState::Loading;
+        if let Err(e) = new.init(&host.context(id)) {
+            return failed(self.fail_reload(e));
+        }
+
+        // Callers block on the old instance's lock until the switch
+        let mut guard = old.write();
+        if let Err(e) = self.save_state(&*guard).and_then(|()| guard.stop()) {
+            let _ = new.cleanup();
+            return failed(self.fail_reload(e));
+        }
+
+        // Undo for the steps below: bring the old instance back
+        let rollback = |guard: &mut dyn Module, new: &mut dyn Module, error| {
+            let _ = new.stop();
+            let _ = new.cleanup();
+            if let Err(e) = guard.start() {
+                log::error!("Module {} did not restart after failed reload: {:?}", name, e);
+            }
+            failed(self.fail_reload(error))
+        };
+
+        *self.state.write() = ReloadState::RestoringState;
+        let restored = match self.take_saved_state() {
+            Some(state) => new.restore_state(state),
+            None => Ok(()),
+        };
+        if let Err(e) = restored.and_then(|()| new.start()) {
+            return rollback(&mut *guard, &mut *new, e);
+        }
+        drop(new);
+
+        if let Err(e) = stop_machine(|| host.replace(id, &replacement)) {
+            let error = ModuleError::Internal(format!("cannot stop CPUs for swap: {:?}", e));
+            return rollback(&mut *guard, &mut *replacement.write(), error);
+        }
+        drop(guard);
+
+        match self.complete_reload() {
+            Ok(()) => ReloadResult { success: true, old_module: Some(Box::new(old)), error: None },
+            Err(e) => failed(e),
+        }
+    }
 }
 
 /// Global hot reload engine
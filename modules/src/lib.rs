@@ -2,15 +2,21 @@
 //!
 //! The module system is the heart of Helix's flexibility. It provides:
 //!
-//! - Dynamic module loading and unloading
+//! - Dynamic module loading and unloading, from the VFS and on demand
+//!   when hardware without a driver appears (see [`alias`])
 //! - Static module linking
-//! - Hot-reload capabilities
+//! - Hot-reload capabilities, with a watch-and-reload developer mode
+//!   (see `devmode`, behind the `devmode` feature)
 //! - Dependency resolution
 //! - ABI versioning and compatibility
+//! - Per-module memory accounting and leak detection
+//! - Versioned message schemas for module IPC
+//! - Signed module packages for distribution
 //!
 //! ## Module Types
 //!
-//! - **Static Modules**: Linked into the kernel at compile time
+//! - **Static Modules**: Linked into the kernel at compile time, registered
+//!   with `#[helix_module]` (see [`linked`])
 //! - **Dynamic Modules**: Loaded at runtime
 //! - **Kernel-space Modules**: Run in ring 0
 //! - **User-space Modules**: Run in ring 3 with IPC
@@ -32,13 +38,26 @@
 
 extern crate alloc;
 
+// Lets `#[helix_module]` expansions name this crate in its own tests
+#[cfg(test)]
+extern crate self as helix_modules;
+
 pub mod loader;
+pub mod alias;
 pub mod registry;
 pub mod dependencies;
 pub mod abi;
 pub mod hot_reload;
+#[cfg(feature = "devmode")]
+pub mod devmode;
 pub mod interface;
 pub mod v2;
+pub mod schema;
+pub mod package;
+pub mod accounting;
+pub mod linked;
+
+pub use helix_modules_macros::helix_module;
 
 use alloc::boxed::Box;
 use alloc::string::String;
@@ -47,6 +66,7 @@
 use core::any::Any;
 use core::sync::atomic::{AtomicU64, Ordering};
 use bitflags::bitflags;
+use helix_hal::lockdown::LockdownError;
 
 /// Module identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -104,6 +124,13 @@ pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
     pub fn is_compatible_with(&self, required: &Self) -> bool {
         self.major == required.major && self.minor >= required.minor
     }
+
+    /// Parse `MAJOR.MINOR.PATCH`
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.splitn(3, '.');
+        let mut next = || parts.next()?.parse().ok();
+        Some(Self::new(next()?, next()?, next()?))
+    }
 }
 
 impl core::fmt::Display for ModuleVersion {
@@ -230,6 +257,8 @@ pub enum ModuleError {
     WrongState { current: ModuleState, required: ModuleState },
     /// Module is essential and cannot be unloaded
     Essential,
+    /// Refused by the kernel lockdown
+    Lockdown(LockdownError),
     /// Internal error
     Internal(String),
 }
@@ -0,0 +1,122 @@
+//! # Link-Time Module Registry
+//!
+//! Static modules register their constructors with
+//! [`#[helix_module]`](crate::helix_module), which places a
+//! [`StaticModule`] entry in the `helix_modules` linker section. The
+//! linker concatenates the entries of every crate in the image, and the
+//! `__start_helix_modules` / `__stop_helix_modules` symbols it defines for
+//! the section bound the list.
+//!
+//! A profile pulls a module in by depending on its crate and linking it
+//! (`extern crate helix_scheduler_round_robin as _;`): nothing else lists
+//! static modules. Kernel linker scripts `KEEP` the section so entries
+//! survive section garbage collection.
+//!
+//! Entries are laid out back to back: [`StaticModule`] is `repr(C)` and
+//! its size is a multiple of its alignment.
+
+use crate::v2::ModuleTrait;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// Linker section holding [`StaticModule`] entries
+pub const SECTION: &str = "helix_modules";
+
+/// A statically linked module
+#[repr(C)]
+pub struct StaticModule {
+    /// Crate that registered the module
+    pub crate_name: &'static str,
+    /// Create an instance of the module
+    pub construct: fn() -> Box<dyn ModuleTrait>,
+}
+
+/// Box a module for [`StaticModule::construct`]
+pub fn boxed<M: ModuleTrait + 'static>(module: M) -> Box<dyn ModuleTrait> {
+    Box::new(module)
+}
+
+extern "C" {
+    static __start_helix_modules: u8;
+    static __stop_helix_modules: u8;
+}
+
+/// Makes the section exist even when no module is linked
+#[used]
+#[link_section = "helix_modules"]
+static SECTION_ANCHOR: [StaticModule; 0] = [];
+
+/// All statically linked modules, in link order
+pub fn static_modules() -> &'static [StaticModule] {
+    // SAFETY: the linker defines both symbols around the section, which
+    // only holds `StaticModule` entries placed by `#[helix_module]`
+    unsafe {
+        let start = core::ptr::addr_of!(__start_helix_modules) as *const StaticModule;
+        let stop = core::ptr::addr_of!(__stop_helix_modules) as *const StaticModule;
+        let len = (stop as usize - start as usize) / core::mem::size_of::<StaticModule>();
+        core::slice::from_raw_parts(start, len)
+    }
+}
+
+/// Is a module from `crate_name` linked in?
+pub fn is_linked(crate_name: &str) -> bool {
+    static_modules().iter().any(|m| m.crate_name == crate_name)
+}
+
+/// Instantiate every statically linked module
+///
+/// Modules of crates listed in `order` come first, in that order; the
+/// others follow sorted by crate name, so the result does not depend on
+/// link order.
+pub fn instantiate(order: &[&str]) -> Vec<Box<dyn ModuleTrait>> {
+    let mut modules: Vec<&StaticModule> = static_modules().iter().collect();
+    modules.sort_by_key(|m| {
+        let rank = order.iter().position(|name| *name == m.crate_name).unwrap_or(order.len());
+        (rank, m.crate_name)
+    });
+    modules.iter().map(|m| (m.construct)()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helix_module;
+    use crate::v2::{Context, ModuleInfo};
+    use crate::ModuleError;
+
+    struct LinkedModule;
+
+    impl ModuleTrait for LinkedModule {
+        fn info(&self) -> ModuleInfo {
+            ModuleInfo::new("linked-module")
+        }
+
+        fn init(&mut self, _ctx: &Context) -> Result<(), ModuleError> {
+            Ok(())
+        }
+
+        fn start(&mut self) -> Result<(), ModuleError> {
+            Ok(())
+        }
+
+        fn stop(&mut self) -> Result<(), ModuleError> {
+            Ok(())
+        }
+    }
+
+    #[helix_module]
+    fn create_module() -> LinkedModule {
+        LinkedModule
+    }
+
+    #[test]
+    fn test_link_time_registry() {
+        assert_eq!(static_modules().len(), 1);
+        assert!(is_linked("helix-modules"));
+        assert!(!is_linked("helix-scheduler-round-robin"));
+
+        let modules = instantiate(&["helix-scheduler-round-robin"]);
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].info().name, "linked-module");
+    }
+}
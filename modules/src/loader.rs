@@ -1,11 +1,23 @@
 //! # Module Loader
 //!
 //! Handles loading module binaries from various sources.
+//!
+//! Binaries already in memory go straight to a [`ModuleLoader`].
+//! [`load_from_path`] reads them through the VFS instead, once the kernel
+//! has registered a [`ModuleFileSource`]; signed packages (`.hxm`, see
+//! [`package`](crate::package)) are verified and unwrapped on the way.
+//! Once a key is enrolled in the [platform keyring](platform_keyring),
+//! only signed packages load.
 
+use crate::abi::{kernel_fingerprint, AbiFingerprint};
+use crate::package::{platform_keyring, ModuleKeyring, Package, PACKAGE_MAGIC};
 use crate::{Module, ModuleMetadata, ModuleResult, ModuleError, ModuleState};
 use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use helix_hal::lockdown::{self, LockdownReason};
 use spin::RwLock;
 
 /// Module binary format
@@ -163,3 +175,212 @@ pub fn detect_and_get(&self, binary: &[u8]) -> Option<Arc<dyn ModuleLoader>> {
 pub fn registry() -> &'static LoaderRegistry {
     &REGISTRY
 }
+
+// =============================================================================
+// Loading from the filesystem
+// =============================================================================
+
+/// Filesystem access for [`load_from_path`]
+///
+/// The module system does not depend on the VFS; the kernel registers a
+/// source backed by it with [`set_file_source`].
+pub trait ModuleFileSource: Send + Sync {
+    /// Read the whole file at absolute `path`
+    fn read(&self, path: &str) -> ModuleResult<Vec<u8>>;
+
+    /// Modification stamp of `path` (e.g. mtime), if the filesystem has one
+    ///
+    /// Without one, watchers compare file contents.
+    fn modified(&self, _path: &str) -> Option<u64> {
+        None
+    }
+
+    /// Keyring and kernel ABI to check packages against
+    ///
+    /// Defaults to the platform keyring and the running kernel's
+    /// fingerprint once a key is enrolled. Without a keyring, packages are
+    /// refused; with one, so are bare binaries.
+    fn keyring(&self) -> Option<(&dyn ModuleKeyring, AbiFingerprint)> {
+        platform_package_keyring()
+    }
+}
+
+/// The platform keyring and kernel fingerprint, if a key is enrolled
+fn platform_package_keyring() -> Option<(&'static dyn ModuleKeyring, AbiFingerprint)> {
+    let keyring = platform_keyring();
+    if keyring.is_empty() {
+        return None;
+    }
+    Some((keyring, kernel_fingerprint()))
+}
+
+/// Files the bootloader placed in memory, by path
+///
+/// A file source for kernels that load modules before a filesystem is
+/// mounted, e.g. from Multiboot2 modules named by their command line.
+pub struct BootFiles {
+    files: RwLock<Vec<(String, &'static [u8])>>,
+}
+
+impl BootFiles {
+    /// Create an empty set
+    pub const fn new() -> Self {
+        Self { files: RwLock::new(Vec::new()) }
+    }
+
+    /// Make `data` readable as `path`
+    pub fn add(&self, path: &str, data: &'static [u8]) {
+        self.files.write().push((path.into(), data));
+    }
+}
+
+impl Default for BootFiles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModuleFileSource for BootFiles {
+    fn read(&self, path: &str) -> ModuleResult<Vec<u8>> {
+        self.files.read()
+            .iter()
+            .find(|(name, _)| name == path)
+            .map(|(_, data)| data.to_vec())
+            .ok_or(ModuleError::NotFound)
+    }
+}
+
+/// Registered file source
+static FILE_SOURCE: RwLock<Option<&'static dyn ModuleFileSource>> = RwLock::new(None);
+
+/// Register the filesystem modules are read from
+pub fn set_file_source(source: &'static dyn ModuleFileSource) {
+    *FILE_SOURCE.write() = Some(source);
+}
+
+/// Registered file source
+pub(crate) fn file_source() -> ModuleResult<&'static dyn ModuleFileSource> {
+    FILE_SOURCE.read()
+        .ok_or_else(|| ModuleError::Internal("no module file source registered".into()))
+}
+
+/// Load a module object from the filesystem, e.g. `/lib/modules/virtio-net.hxm`
+///
+/// The file is either a bare module binary or a signed package, which
+/// must verify against the file source's keyring and the running kernel
+/// ABI. Bare binaries are refused when a keyring is configured or the
+/// kernel is locked down. The binary goes to the first registered loader
+/// that accepts it.
+pub fn load_from_path(path: &str) -> ModuleResult<LoadedModule> {
+    if !path.starts_with('/') {
+        return Err(ModuleError::LoadError(format!("{}: module path must be absolute", path)));
+    }
+    let file = file_source()?.read(path)?;
+    load_image(&file, path)
+}
+
+/// Load a module binary or signed package already in memory
+///
+/// Packages are checked as in [`load_from_path`]; `origin` names the
+/// image in errors.
+pub fn load_image(file: &[u8], origin: &str) -> ModuleResult<LoadedModule> {
+    load_image_with(file, origin).map(|(_, module)| module)
+}
+
+/// [`load_image`], also returning the loader to unload the module with
+pub(crate) fn load_image_with(
+    file: &[u8],
+    origin: &str,
+) -> ModuleResult<(Arc<dyn ModuleLoader>, LoadedModule)> {
+    let binary = unwrap_image(file, origin, package_keyring())?;
+
+    let loader = registry().detect_and_get(binary)
+        .ok_or_else(|| ModuleError::LoadError(format!("{}: unknown module format", origin)))?;
+    let module = loader.load(binary)?;
+    Ok((loader, module))
+}
+
+/// Keyring packages are checked against: the file source's, or the
+/// platform keyring before a file source is registered
+fn package_keyring() -> Option<(&'static dyn ModuleKeyring, AbiFingerprint)> {
+    match *FILE_SOURCE.read() {
+        Some(source) => source.keyring(),
+        None => platform_package_keyring(),
+    }
+}
+
+/// The module binary in `file`: a verified package's image, or `file`
+/// itself if it is a bare binary and may be loaded unsigned
+fn unwrap_image<'a>(
+    file: &'a [u8],
+    origin: &str,
+    keyring: Option<(&dyn ModuleKeyring, AbiFingerprint)>,
+) -> ModuleResult<&'a [u8]> {
+    if file.starts_with(&PACKAGE_MAGIC) {
+        let package = Package::parse(file)?;
+        let (keyring, abi) = keyring
+            .ok_or_else(|| ModuleError::LoadError(format!("{}: no keyring to verify package", origin)))?;
+        package.verify(keyring, &abi)?;
+        return Ok(package.image);
+    }
+
+    if keyring.is_some() {
+        return Err(ModuleError::LoadError(format!("{}: unsigned module, a signed package is required", origin)));
+    }
+    lockdown::check(LockdownReason::UnsignedModule).map_err(ModuleError::Lockdown)?;
+    Ok(file)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::AbiVersion;
+    use crate::package::{KeyId, PackageManifest, PackageWriter};
+
+    const KEY: KeyId = [1; 32];
+    const ABI: AbiFingerprint = AbiFingerprint { version: AbiVersion::new(1, 0), symbols: 0 };
+
+    /// Trusts signature `ok` from `KEY`
+    struct TestKeyring;
+
+    impl ModuleKeyring for TestKeyring {
+        fn verify(&self, key_id: &KeyId, _message: &[u8], signature: &[u8]) -> bool {
+            *key_id == KEY && signature == b"ok"
+        }
+    }
+
+    fn package() -> Vec<u8> {
+        let manifest =
+            PackageManifest::parse("name = net\nversion = 1.0.0\nabi = 1.0-0000000000000000\n").unwrap();
+        PackageWriter::new(&manifest, b"\x7fELF net").unwrap().finish(&KEY, b"ok")
+    }
+
+    #[test]
+    fn test_unwrap_image() {
+        let package = package();
+
+        // No keyring: packages cannot be checked, bare binaries load
+        assert!(matches!(unwrap_image(&package, "net.hxm", None), Err(ModuleError::LoadError(_))));
+        assert_eq!(unwrap_image(b"\x7fELF net", "net.ko", None).unwrap(), b"\x7fELF net");
+
+        // With a keyring only signed packages load
+        let keyring = Some((&TestKeyring as &dyn ModuleKeyring, ABI));
+        assert_eq!(unwrap_image(&package, "net.hxm", keyring).unwrap(), b"\x7fELF net");
+        assert!(matches!(unwrap_image(b"\x7fELF net", "net.ko", keyring), Err(ModuleError::LoadError(_))));
+    }
+
+    #[test]
+    fn test_boot_files() {
+        static IMAGE: [u8; 4] = *b"\x7fELF";
+
+        let files = BootFiles::new();
+        files.add("/lib/modules/net.ko", &IMAGE);
+        assert_eq!(files.read("/lib/modules/net.ko").unwrap(), IMAGE);
+        assert_eq!(files.read("/lib/modules/blk.ko"), Err(ModuleError::NotFound));
+        assert!(files.keyring().is_none());
+    }
+}
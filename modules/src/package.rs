@@ -0,0 +1,531 @@
+//! # Module Packages
+//!
+//! Distribution format of dynamic modules (`.hxm`): a manifest, the module
+//! image and a signature over both. `helix-mod` fetches packages and their
+//! dependencies from a repository and stages them in [`MODULE_DIR`], where
+//! the kernel loads them from.
+//!
+//! ```text
+//! ┌──────────────┬──────────────────────────────────────────────────┐
+//! │ Header       │ magic "HXM\0", format u16, manifest length u16,  │
+//! │              │ image length u32                                 │
+//! │ Manifest     │ `key = value` lines (UTF-8)                      │
+//! │ Image        │ module binary                                    │
+//! │ Signature    │ key id [u8; 32], length u16, signature bytes     │
+//! └──────────────┴──────────────────────────────────────────────────┘
+//! ```
+//!
+//! Integers are little endian. The signature covers everything before it
+//! and is checked against the kernel's module keyring.
+//!
+//! ## Manifest
+//!
+//! ```text
+//! name = virtio-net
+//! version = 1.2.0
+//! abi = 1.0-3fa2c4e19b0d7a55
+//! depends = virtio-core>=1.0.0 ?trace>=0.2.0<=0.9.9
+//! description = VirtIO network driver
+//! ```
+//!
+//! `name`, `version` and `abi` (see [`AbiFingerprint`]) are required.
+//! Dependencies are `NAME[>=MIN][<=MAX]`, optional ones prefixed with `?`.
+//! Unknown keys are ignored.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use spin::RwLock;
+
+use crate::abi::AbiFingerprint;
+use crate::{ModuleDependency, ModuleError, ModuleFlags, ModuleId, ModuleMetadata, ModuleVersion};
+
+/// Package magic
+pub const PACKAGE_MAGIC: [u8; 4] = *b"HXM\0";
+
+/// Package format version
+pub const PACKAGE_FORMAT: u16 = 1;
+
+/// Where installed packages are staged, as `NAME.hxm`
+pub const MODULE_DIR: &str = "/lib/modules";
+
+/// Header size
+const HEADER_SIZE: usize = 12;
+
+/// Signing key identifier
+pub type KeyId = [u8; 32];
+
+/// Keys trusted to sign modules
+pub trait ModuleKeyring {
+    /// Check `signature` over `message` with key `key_id`
+    ///
+    /// Unknown keys fail.
+    fn verify(&self, key_id: &KeyId, message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Keys the platform trusts to sign modules
+///
+/// The kernel's built-in key and keys taken from the firmware at boot are
+/// enrolled as keyrings of their own; a signature is trusted if any of
+/// them verifies it.
+pub struct PlatformKeyring {
+    keyrings: RwLock<Vec<&'static (dyn ModuleKeyring + Sync)>>,
+}
+
+impl PlatformKeyring {
+    /// Create an empty keyring, which trusts nothing
+    pub const fn new() -> Self {
+        Self { keyrings: RwLock::new(Vec::new()) }
+    }
+
+    /// Trust the keys of `keyring`
+    pub fn enroll(&self, keyring: &'static (dyn ModuleKeyring + Sync)) {
+        self.keyrings.write().push(keyring);
+    }
+
+    /// No keys enrolled?
+    pub fn is_empty(&self) -> bool {
+        self.keyrings.read().is_empty()
+    }
+}
+
+impl Default for PlatformKeyring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModuleKeyring for PlatformKeyring {
+    fn verify(&self, key_id: &KeyId, message: &[u8], signature: &[u8]) -> bool {
+        self.keyrings.read().iter().any(|keyring| keyring.verify(key_id, message, signature))
+    }
+}
+
+/// Platform keyring
+static PLATFORM_KEYRING: PlatformKeyring = PlatformKeyring::new();
+
+/// Get the platform keyring
+pub fn platform_keyring() -> &'static PlatformKeyring {
+    &PLATFORM_KEYRING
+}
+
+// =============================================================================
+// Errors
+// =============================================================================
+
+/// Package error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageError {
+    /// Data ends early
+    Truncated,
+    /// Not a module package
+    BadMagic,
+    /// Unsupported format version
+    UnsupportedFormat(u16),
+    /// Manifest is malformed (reason)
+    BadManifest(String),
+    /// Signature invalid, or key not trusted
+    Untrusted,
+    /// Built for another kernel ABI
+    AbiMismatch {
+        /// Package ABI
+        package: AbiFingerprint,
+        /// Running kernel ABI
+        kernel: AbiFingerprint,
+    },
+}
+
+impl fmt::Display for PackageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "package truncated"),
+            Self::BadMagic => write!(f, "not a module package"),
+            Self::UnsupportedFormat(format) => write!(f, "unsupported package format {}", format),
+            Self::BadManifest(reason) => write!(f, "bad manifest: {}", reason),
+            Self::Untrusted => write!(f, "package signature not trusted"),
+            Self::AbiMismatch { package, kernel } => {
+                write!(f, "package built for ABI {}, kernel has {}", package, kernel)
+            }
+        }
+    }
+}
+
+impl From<PackageError> for ModuleError {
+    fn from(err: PackageError) -> Self {
+        match err {
+            PackageError::AbiMismatch { .. } => ModuleError::AbiIncompatible,
+            err => ModuleError::LoadError(err.to_string()),
+        }
+    }
+}
+
+// =============================================================================
+// Manifest
+// =============================================================================
+
+/// Parse a dependency: `NAME[>=MIN][<=MAX]`, `?` prefix for optional
+pub fn parse_dependency(spec: &str) -> Option<ModuleDependency> {
+    let (optional, spec) = match spec.strip_prefix('?') {
+        Some(rest) => (true, rest),
+        None => (false, spec),
+    };
+    let (rest, max_version) = match spec.split_once("<=") {
+        Some((rest, max)) => (rest, Some(ModuleVersion::parse(max)?)),
+        None => (spec, None),
+    };
+    let (name, min_version) = match rest.split_once(">=") {
+        Some((name, min)) => (name, ModuleVersion::parse(min)?),
+        None => (rest, ModuleVersion::new(0, 0, 0)),
+    };
+    if name.is_empty() || name.contains(['<', '>', '=', '?']) {
+        return None;
+    }
+    Some(ModuleDependency {
+        name: name.to_string(),
+        min_version,
+        max_version,
+        optional,
+    })
+}
+
+/// Dependency in manifest syntax
+pub struct DependencySpec<'a>(pub &'a ModuleDependency);
+
+impl fmt::Display for DependencySpec<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dep = self.0;
+        if dep.optional {
+            f.write_char('?')?;
+        }
+        f.write_str(&dep.name)?;
+        if dep.min_version != ModuleVersion::new(0, 0, 0) {
+            write!(f, ">={}", dep.min_version)?;
+        }
+        if let Some(max) = dep.max_version {
+            write!(f, "<={}", max)?;
+        }
+        Ok(())
+    }
+}
+
+/// Package manifest
+#[derive(Debug, Clone)]
+pub struct PackageManifest {
+    /// Module name
+    pub name: String,
+    /// Module version
+    pub version: ModuleVersion,
+    /// Kernel ABI the module was built against
+    pub abi: AbiFingerprint,
+    /// Modules that must be loaded first
+    pub dependencies: Vec<ModuleDependency>,
+    /// Description
+    pub description: String,
+    /// License
+    pub license: String,
+}
+
+impl PackageManifest {
+    /// Parse manifest text
+    pub fn parse(text: &str) -> Result<Self, PackageError> {
+        let bad = |reason: &str| PackageError::BadManifest(reason.to_string());
+
+        let (mut name, mut version, mut abi) = (None, None, None);
+        let mut dependencies = Vec::new();
+        let (mut description, mut license) = (String::new(), String::new());
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| bad(line))?;
+            let value = value.trim();
+            match key.trim() {
+                "name" => {
+                    if value.is_empty() || value.contains(char::is_whitespace) {
+                        return Err(bad("invalid name"));
+                    }
+                    name = Some(value.to_string());
+                }
+                "version" => version = Some(ModuleVersion::parse(value).ok_or_else(|| bad("invalid version"))?),
+                "abi" => abi = Some(AbiFingerprint::parse(value).ok_or_else(|| bad("invalid abi"))?),
+                "depends" => {
+                    for spec in value.split_whitespace() {
+                        dependencies.push(parse_dependency(spec).ok_or_else(|| bad(spec))?);
+                    }
+                }
+                "description" => description = value.to_string(),
+                "license" => license = value.to_string(),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            name: name.ok_or_else(|| bad("missing name"))?,
+            version: version.ok_or_else(|| bad("missing version"))?,
+            abi: abi.ok_or_else(|| bad("missing abi"))?,
+            dependencies,
+            description,
+            license,
+        })
+    }
+
+    /// Manifest text
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "name = {}", self.name);
+        let _ = writeln!(text, "version = {}", self.version);
+        let _ = writeln!(text, "abi = {}", self.abi);
+        if !self.dependencies.is_empty() {
+            text.push_str("depends =");
+            for dep in &self.dependencies {
+                let _ = write!(text, " {}", DependencySpec(dep));
+            }
+            text.push('\n');
+        }
+        if !self.description.is_empty() {
+            let _ = writeln!(text, "description = {}", self.description);
+        }
+        if !self.license.is_empty() {
+            let _ = writeln!(text, "license = {}", self.license);
+        }
+        text
+    }
+
+    /// Metadata for registering the module
+    pub fn metadata(&self) -> ModuleMetadata {
+        ModuleMetadata {
+            id: ModuleId::new(),
+            name: self.name.clone(),
+            version: self.version,
+            description: self.description.clone(),
+            authors: Vec::new(),
+            license: self.license.clone(),
+            flags: ModuleFlags::empty(),
+            dependencies: self.dependencies.clone(),
+            provides: Vec::new(),
+            abi_version: self.abi.version,
+        }
+    }
+}
+
+// =============================================================================
+// Package
+// =============================================================================
+
+/// A parsed module package
+///
+/// Parsing does not check the signature; only [`Package::verify`] does.
+#[derive(Debug, Clone)]
+pub struct Package<'a> {
+    /// Manifest
+    pub manifest: PackageManifest,
+    /// Module image
+    pub image: &'a [u8],
+    /// Signing key
+    pub key_id: KeyId,
+    /// Signature over `signed`
+    pub signature: &'a [u8],
+    /// Signed bytes: header, manifest and image
+    signed: &'a [u8],
+}
+
+impl<'a> Package<'a> {
+    /// Parse a package
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, PackageError> {
+        let header = bytes.get(..HEADER_SIZE).ok_or(PackageError::Truncated)?;
+        if header[0..4] != PACKAGE_MAGIC {
+            return Err(PackageError::BadMagic);
+        }
+        let format = u16::from_le_bytes([header[4], header[5]]);
+        if format != PACKAGE_FORMAT {
+            return Err(PackageError::UnsupportedFormat(format));
+        }
+        let manifest_len = u16::from_le_bytes([header[6], header[7]]) as usize;
+        let image_len = u32::from_le_bytes([header[8], header[9], header[10], header[11]]) as usize;
+
+        let signed_len = HEADER_SIZE + manifest_len + image_len;
+        let signed = bytes.get(..signed_len).ok_or(PackageError::Truncated)?;
+        let manifest = core::str::from_utf8(&signed[HEADER_SIZE..HEADER_SIZE + manifest_len])
+            .map_err(|_| PackageError::BadManifest("not UTF-8".to_string()))?;
+        let manifest = PackageManifest::parse(manifest)?;
+
+        let trailer = &bytes[signed_len..];
+        let key_id: KeyId = trailer.get(..32).ok_or(PackageError::Truncated)?.try_into().unwrap();
+        let sig_len = trailer.get(32..34).ok_or(PackageError::Truncated)?;
+        let sig_len = u16::from_le_bytes([sig_len[0], sig_len[1]]) as usize;
+        let signature = trailer.get(34..34 + sig_len).ok_or(PackageError::Truncated)?;
+
+        Ok(Self {
+            manifest,
+            image: &signed[HEADER_SIZE + manifest_len..],
+            key_id,
+            signature,
+            signed,
+        })
+    }
+
+    /// Check the signature and that the package fits the running kernel
+    pub fn verify(&self, keyring: &dyn ModuleKeyring, kernel: &AbiFingerprint) -> Result<(), PackageError> {
+        if !keyring.verify(&self.key_id, self.signed, self.signature) {
+            return Err(PackageError::Untrusted);
+        }
+        if !self.manifest.abi.is_compatible_with(kernel) {
+            return Err(PackageError::AbiMismatch { package: self.manifest.abi, kernel: *kernel });
+        }
+        Ok(())
+    }
+
+    /// File name the package is staged under
+    pub fn file_name(&self) -> String {
+        let mut name = self.manifest.name.clone();
+        name.push_str(".hxm");
+        name
+    }
+}
+
+/// Builds a package (for packaging tools and tests)
+///
+/// Sign [`PackageWriter::message`], then [`PackageWriter::finish`] with
+/// the signature.
+pub struct PackageWriter {
+    signed: Vec<u8>,
+}
+
+impl PackageWriter {
+    /// Package `image` described by `manifest`
+    pub fn new(manifest: &PackageManifest, image: &[u8]) -> Result<Self, PackageError> {
+        let text = manifest.to_text();
+        let manifest_len = u16::try_from(text.len())
+            .map_err(|_| PackageError::BadManifest("too long".to_string()))?;
+        let image_len = u32::try_from(image.len()).map_err(|_| PackageError::Truncated)?;
+
+        let mut signed = Vec::with_capacity(HEADER_SIZE + text.len() + image.len());
+        signed.extend_from_slice(&PACKAGE_MAGIC);
+        signed.extend_from_slice(&PACKAGE_FORMAT.to_le_bytes());
+        signed.extend_from_slice(&manifest_len.to_le_bytes());
+        signed.extend_from_slice(&image_len.to_le_bytes());
+        signed.extend_from_slice(text.as_bytes());
+        signed.extend_from_slice(image);
+        Ok(Self { signed })
+    }
+
+    /// Bytes to sign
+    pub fn message(&self) -> &[u8] {
+        &self.signed
+    }
+
+    /// Serialize with `signature` over [`PackageWriter::message`]
+    pub fn finish(self, key_id: &KeyId, signature: &[u8]) -> Vec<u8> {
+        let mut out = self.signed;
+        out.extend_from_slice(key_id);
+        out.extend_from_slice(&(signature.len() as u16).to_le_bytes());
+        out.extend_from_slice(signature);
+        out
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::AbiVersion;
+
+    const KEY: KeyId = [7; 32];
+    const ABI: AbiFingerprint = AbiFingerprint { version: AbiVersion::new(1, 2), symbols: 0x3fa2_c4e1_9b0d_7a55 };
+
+    /// Test "signature": a byte sum
+    fn sign(message: &[u8]) -> Vec<u8> {
+        message.iter().fold(0u32, |sum, &b| sum.wrapping_mul(31).wrapping_add(b as u32)).to_le_bytes().to_vec()
+    }
+
+    struct TestKeyring;
+
+    impl ModuleKeyring for TestKeyring {
+        fn verify(&self, key_id: &KeyId, message: &[u8], signature: &[u8]) -> bool {
+            *key_id == KEY && signature == sign(message).as_slice()
+        }
+    }
+
+    fn manifest() -> PackageManifest {
+        PackageManifest::parse(
+            "# VirtIO NIC\n\
+             name = virtio-net\n\
+             version = 1.2.0\n\
+             abi = 1.0-3fa2c4e19b0d7a55\n\
+             depends = virtio-core>=1.0.0 ?trace>=0.2.0<=0.9.9\n\
+             description = VirtIO network driver\n\
+             homepage = ignored\n",
+        )
+        .unwrap()
+    }
+
+    fn package(manifest: &PackageManifest) -> Vec<u8> {
+        let writer = PackageWriter::new(manifest, b"\x7fELF image").unwrap();
+        let signature = sign(writer.message());
+        writer.finish(&KEY, &signature)
+    }
+
+    #[test]
+    fn test_manifest() {
+        let manifest = manifest();
+        assert_eq!(manifest.name, "virtio-net");
+        assert_eq!(manifest.version, ModuleVersion::new(1, 2, 0));
+        assert_eq!(manifest.abi.to_string(), "1.0-3fa2c4e19b0d7a55");
+        assert_eq!(manifest.dependencies.len(), 2);
+        assert!(!manifest.dependencies[0].optional);
+        assert_eq!(manifest.dependencies[1].max_version, Some(ModuleVersion::new(0, 9, 9)));
+        assert_eq!(manifest.metadata().abi_version, AbiVersion::new(1, 0));
+
+        // Round trip
+        let text = manifest.to_text();
+        assert!(text.contains("depends = virtio-core>=1.0.0 ?trace>=0.2.0<=0.9.9\n"));
+        assert_eq!(PackageManifest::parse(&text).unwrap().to_text(), text);
+
+        assert!(matches!(PackageManifest::parse("name = x\nversion = 1.0\n"), Err(PackageError::BadManifest(_))));
+        assert!(matches!(PackageManifest::parse("version = 1.0.0\nabi = 1.0-0000000000000000\n"), Err(PackageError::BadManifest(_))));
+        assert!(parse_dependency("core").is_some_and(|d| d.min_version == ModuleVersion::new(0, 0, 0)));
+        assert!(parse_dependency(">=1.0.0").is_none());
+    }
+
+    #[test]
+    fn test_package_verify() {
+        let bytes = package(&manifest());
+        let package = Package::parse(&bytes).unwrap();
+        assert_eq!(package.image, b"\x7fELF image");
+        assert_eq!(package.file_name(), "virtio-net.hxm");
+        assert_eq!(package.verify(&TestKeyring, &ABI), Ok(()));
+
+        // Same symbols, older kernel ABI
+        let old = AbiFingerprint { version: AbiVersion::new(0, 9), ..ABI };
+        assert!(matches!(package.verify(&TestKeyring, &old), Err(PackageError::AbiMismatch { .. })));
+
+        // Tampered image
+        let mut tampered = bytes.clone();
+        let image_at = bytes.len() - 32 - 2 - 4 - 4;
+        tampered[image_at] ^= 1;
+        assert_eq!(Package::parse(&tampered).unwrap().verify(&TestKeyring, &ABI), Err(PackageError::Untrusted));
+
+        assert_eq!(Package::parse(&bytes[..bytes.len() - 1]).unwrap_err(), PackageError::Truncated);
+        assert_eq!(Package::parse(&[0; 64]).unwrap_err(), PackageError::BadMagic);
+        assert_eq!(ModuleError::from(PackageError::AbiMismatch { package: ABI, kernel: old }), ModuleError::AbiIncompatible);
+    }
+
+    #[test]
+    fn test_platform_keyring() {
+        static TEST_KEYRING: TestKeyring = TestKeyring;
+
+        let bytes = package(&manifest());
+        let package = Package::parse(&bytes).unwrap();
+        let keyring = PlatformKeyring::new();
+        assert!(keyring.is_empty());
+        assert_eq!(package.verify(&keyring, &ABI), Err(PackageError::Untrusted));
+
+        keyring.enroll(&TEST_KEYRING);
+        assert!(!keyring.is_empty());
+        assert_eq!(package.verify(&keyring, &ABI), Ok(()));
+    }
+}
@@ -5,6 +5,7 @@
 use crate::{
     Module, ModuleId, ModuleMetadata, ModuleResult, ModuleError, 
     ModuleState, ModuleFlags,
+    accounting::{accounting, ModuleMemory},
     loader::LoadedModule,
 };
 use alloc::collections::BTreeMap;
@@ -107,6 +108,10 @@ pub fn unregister(&self, id: ModuleId) -> ModuleResult<()> {
         modules.remove(&id);
         self.name_to_id.write().remove(&name);
 
+        if let Some(leak) = accounting().release(id) {
+            log::warn!("{}: {}", name, leak);
+        }
+
         // Unregister provides
         for cap in provides {
             if let Some(v) = self.provides.write().get_mut(&cap) {
@@ -155,6 +160,11 @@ pub fn set_state(&self, id: ModuleId, state: ModuleState) -> ModuleResult<()> {
         Ok(())
     }
 
+    /// Get the heap usage charged to a module
+    pub fn memory_usage(&self, id: ModuleId) -> Option<ModuleMemory> {
+        accounting().usage(id)
+    }
+
     /// Get all registered modules
     pub fn list_all(&self) -> Vec<ModuleMetadata> {
         self.modules.read()
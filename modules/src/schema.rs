@@ -0,0 +1,1104 @@
+//! # Message Schemas
+//!
+//! Typed payloads for module IPC, in place of hand-formatted strings.
+//!
+//! Payload types derive [`Message`](macro@Message), which implements a
+//! compact binary encoding ([`Encode`], [`Decode`]) and a JSON rendering
+//! for logs and debugging ([`ToJson`]):
+//!
+//! ```rust,ignore
+//! use helix_modules::schema::Message;
+//!
+//! #[derive(Message)]
+//! #[message(name = "helix.scheduler.stats", version = 2)]
+//! pub struct SchedulerStatsReply {
+//!     pub context_switches: u64,
+//!     pub runnable_threads: u64,
+//!     #[message(since = 2)]
+//!     pub blocked_threads: u64,
+//! }
+//! ```
+//!
+//! ## Wire format
+//!
+//! - Integers are LEB128 varints, signed ones zigzag encoded; `bool` is
+//!   one byte.
+//! - Strings, byte buffers and sequences are a varint length followed by
+//!   their contents.
+//! - `Option` is a tag byte followed by the value when present.
+//! - A struct is a length-prefixed frame holding its fields in
+//!   declaration order. An enum is its variant index followed by such a
+//!   frame.
+//! - A framed payload ([`encode`]) starts with the schema version it was
+//!   written at.
+//!
+//! ## Evolution
+//!
+//! Fields are only ever appended, marked `#[message(since = N)]`, and need
+//! a `Default`. Writing at version `N - 1` leaves them out; a reader gets
+//! the default for fields missing from a frame and skips fields it does
+//! not know, so old and new peers read each other's payloads.
+//!
+//! Peers agree on a version per schema with a [`NEGOTIATE`] request
+//! carrying a [`SchemaOffer`]; a [`SchemaTable`] remembers the outcome.
+//! Without negotiation, payloads are written at the schema's
+//! `MIN_VERSION`, which every peer supporting the schema can read.
+
+use crate::interface::{MessagePayload, MessageType, ModuleMessage};
+use crate::v2::{Request, Response};
+use crate::ModuleId;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+pub use helix_modules_macros::Message;
+
+/// Schema version
+pub type SchemaVersion = u16;
+
+/// Request type of a schema negotiation
+pub const NEGOTIATE: &str = "schema.negotiate";
+
+// =============================================================================
+// Errors
+// =============================================================================
+
+/// Payload decoding error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Payload ended inside a value
+    Truncated,
+    /// Varint too long, or value out of range for its type
+    Overflow,
+    /// String is not UTF-8
+    InvalidUtf8,
+    /// Unknown enum variant, `bool` or `Option` tag
+    InvalidTag(u64),
+    /// Payload written at a version older than the reader supports
+    IncompatibleVersion {
+        /// Version of the payload
+        found: SchemaVersion,
+        /// Oldest version the reader supports
+        min: SchemaVersion,
+    },
+    /// Bytes left after the payload
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "payload truncated"),
+            Self::Overflow => write!(f, "integer out of range"),
+            Self::InvalidUtf8 => write!(f, "string is not UTF-8"),
+            Self::InvalidTag(tag) => write!(f, "invalid tag {}", tag),
+            Self::IncompatibleVersion { found, min } => {
+                write!(f, "schema version {} older than supported {}", found, min)
+            }
+            Self::TrailingBytes => write!(f, "trailing bytes after payload"),
+        }
+    }
+}
+
+// =============================================================================
+// Writer and Reader
+// =============================================================================
+
+/// Binary payload writer
+pub struct Writer {
+    buf: Vec<u8>,
+    version: SchemaVersion,
+}
+
+impl Writer {
+    /// Create a writer for a payload at `version`
+    pub fn new(version: SchemaVersion) -> Self {
+        Self { buf: Vec::new(), version }
+    }
+
+    /// Schema version being written
+    pub fn version(&self) -> SchemaVersion {
+        self.version
+    }
+
+    /// Write a byte
+    pub fn write_u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    /// Write a LEB128 varint
+    pub fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    /// Write a length-prefixed byte string
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_varint(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Write a length-prefixed frame
+    pub fn nested(&mut self, f: impl FnOnce(&mut Writer)) {
+        let mut inner = Writer::new(self.version);
+        f(&mut inner);
+        self.write_bytes(&inner.buf);
+    }
+
+    /// Written bytes
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Binary payload reader
+pub struct Reader<'a> {
+    data: &'a [u8],
+    version: SchemaVersion,
+}
+
+impl<'a> Reader<'a> {
+    /// Create a reader for a payload written at `version`
+    pub fn new(data: &'a [u8], version: SchemaVersion) -> Self {
+        Self { data, version }
+    }
+
+    /// Schema version the payload was written at
+    pub fn version(&self) -> SchemaVersion {
+        self.version
+    }
+
+    /// Check if everything has been read
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Read a byte
+    pub fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let (&byte, rest) = self.data.split_first().ok_or(DecodeError::Truncated)?;
+        self.data = rest;
+        Ok(byte)
+    }
+
+    /// Read a LEB128 varint
+    pub fn read_varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            let bits = u64::from(byte & 0x7F);
+            if shift == 63 && bits > 1 {
+                return Err(DecodeError::Overflow);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::Overflow)
+    }
+
+    /// Read a length-prefixed byte string
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = usize::try_from(self.read_varint()?).map_err(|_| DecodeError::Overflow)?;
+        if len > self.data.len() {
+            return Err(DecodeError::Truncated);
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    /// Read a length-prefixed frame
+    ///
+    /// Bytes `f` leaves unread are fields from a newer schema, and are
+    /// skipped.
+    pub fn nested<T>(&mut self, f: impl FnOnce(&mut Reader<'a>) -> Result<T, DecodeError>) -> Result<T, DecodeError> {
+        let mut inner = Reader::new(self.read_bytes()?, self.version);
+        f(&mut inner)
+    }
+
+    /// Read a field
+    pub fn field<T: Decode>(&mut self) -> Result<T, DecodeError> {
+        T::decode(self)
+    }
+
+    /// Read a field added in a later version, defaulting when absent
+    pub fn field_or_default<T: Decode + Default>(&mut self) -> Result<T, DecodeError> {
+        if self.is_empty() {
+            Ok(T::default())
+        } else {
+            T::decode(self)
+        }
+    }
+}
+
+// =============================================================================
+// Traits
+// =============================================================================
+
+/// Binary encoding
+pub trait Encode {
+    /// Write the value
+    fn encode(&self, w: &mut Writer);
+}
+
+/// Binary decoding
+pub trait Decode: Sized {
+    /// Read a value
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError>;
+}
+
+/// JSON rendering, for logs and debugging
+pub trait ToJson {
+    /// Append the value as JSON
+    fn write_json(&self, out: &mut String);
+}
+
+/// A versioned IPC payload
+pub trait Message: Encode + Decode + ToJson {
+    /// Schema name
+    const NAME: &'static str;
+    /// Current schema version
+    const VERSION: SchemaVersion;
+    /// Oldest version this side can read
+    const MIN_VERSION: SchemaVersion;
+
+    /// Versions supported by this side
+    fn schema() -> SchemaRange {
+        SchemaRange {
+            name: String::from(Self::NAME),
+            min: Self::MIN_VERSION,
+            max: Self::VERSION,
+        }
+    }
+}
+
+/// Encode a payload at `version` (clamped to the supported range)
+pub fn encode<T: Message>(message: &T, version: SchemaVersion) -> Vec<u8> {
+    let version = version.clamp(T::MIN_VERSION, T::VERSION);
+    let mut w = Writer::new(version);
+    w.write_varint(u64::from(version));
+    message.encode(&mut w);
+    w.into_bytes()
+}
+
+/// Decode a payload written by [`encode`]
+pub fn decode<T: Message>(bytes: &[u8]) -> Result<T, DecodeError> {
+    let mut r = Reader::new(bytes, 0);
+    let version = SchemaVersion::try_from(r.read_varint()?).map_err(|_| DecodeError::Overflow)?;
+    if version < T::MIN_VERSION {
+        return Err(DecodeError::IncompatibleVersion { found: version, min: T::MIN_VERSION });
+    }
+    r.version = version;
+
+    let message = T::decode(&mut r)?;
+    if !r.is_empty() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    Ok(message)
+}
+
+/// Render a value as JSON
+pub fn to_json<T: ToJson + ?Sized>(value: &T) -> String {
+    let mut out = String::new();
+    value.write_json(&mut out);
+    out
+}
+
+/// JSON building blocks for [`ToJson`] implementations
+pub mod json {
+    use alloc::string::String;
+    use core::fmt::Write;
+
+    /// Append a quoted, escaped string
+    pub fn string(out: &mut String, s: &str) {
+        out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => {
+                    let _ = write!(out, "\\u{:04x}", c as u32);
+                }
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+
+    /// Append an object key, preceded by a comma unless it is the first
+    pub fn key(out: &mut String, name: &str, first: bool) {
+        if !first {
+            out.push(',');
+        }
+        string(out, name);
+        out.push(':');
+    }
+}
+
+// =============================================================================
+// Primitive Implementations
+// =============================================================================
+
+macro_rules! impl_unsigned {
+    ($($ty:ty),*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, w: &mut Writer) {
+                w.write_varint(*self as u64);
+            }
+        }
+
+        impl Decode for $ty {
+            fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+                <$ty>::try_from(r.read_varint()?).map_err(|_| DecodeError::Overflow)
+            }
+        }
+
+        impl ToJson for $ty {
+            fn write_json(&self, out: &mut String) {
+                let _ = write!(out, "{}", self);
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_signed {
+    ($($ty:ty),*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, w: &mut Writer) {
+                let value = *self as i64;
+                w.write_varint(((value << 1) ^ (value >> 63)) as u64);
+            }
+        }
+
+        impl Decode for $ty {
+            fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+                let raw = r.read_varint()?;
+                let value = (raw >> 1) as i64 ^ -((raw & 1) as i64);
+                <$ty>::try_from(value).map_err(|_| DecodeError::Overflow)
+            }
+        }
+
+        impl ToJson for $ty {
+            fn write_json(&self, out: &mut String) {
+                let _ = write!(out, "{}", self);
+            }
+        }
+    )*};
+}
+
+impl_unsigned!(u8, u16, u32, u64, usize);
+impl_signed!(i8, i16, i32, i64, isize);
+
+impl Encode for bool {
+    fn encode(&self, w: &mut Writer) {
+        w.write_u8(u8::from(*self));
+    }
+}
+
+impl Decode for bool {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        match r.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(DecodeError::InvalidTag(u64::from(tag))),
+        }
+    }
+}
+
+impl ToJson for bool {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, w: &mut Writer) {
+        w.write_bytes(self.as_bytes());
+    }
+}
+
+impl ToJson for str {
+    fn write_json(&self, out: &mut String) {
+        json::string(out, self);
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, w: &mut Writer) {
+        self.as_str().encode(w);
+    }
+}
+
+impl Decode for String {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let bytes = r.read_bytes()?;
+        core::str::from_utf8(bytes)
+            .map(String::from)
+            .map_err(|_| DecodeError::InvalidUtf8)
+    }
+}
+
+impl ToJson for String {
+    fn write_json(&self, out: &mut String) {
+        json::string(out, self);
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, w: &mut Writer) {
+        (**self).encode(w);
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn write_json(&self, out: &mut String) {
+        (**self).write_json(out);
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            None => w.write_u8(0),
+            Some(value) => {
+                w.write_u8(1);
+                value.encode(w);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        match r.read_u8()? {
+            0 => Ok(None),
+            1 => T::decode(r).map(Some),
+            tag => Err(DecodeError::InvalidTag(u64::from(tag))),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            None => out.push_str("null"),
+            Some(value) => value.write_json(out),
+        }
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, w: &mut Writer) {
+        w.write_varint(self.len() as u64);
+        for item in self {
+            item.encode(w);
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, w: &mut Writer) {
+        self.as_slice().encode(w);
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let len = usize::try_from(r.read_varint()?).map_err(|_| DecodeError::Overflow)?;
+        // Every element takes at least one byte
+        if len > r.data.len() {
+            return Err(DecodeError::Truncated);
+        }
+        (0..len).map(|_| T::decode(r)).collect()
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+        for (i, item) in self.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            item.write_json(out);
+        }
+        out.push(']');
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn write_json(&self, out: &mut String) {
+        self.as_slice().write_json(out);
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, w: &mut Writer) {
+        self.0.encode(w);
+        self.1.encode(w);
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok((A::decode(r)?, B::decode(r)?))
+    }
+}
+
+impl<A: ToJson, B: ToJson> ToJson for (A, B) {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+        self.0.write_json(out);
+        out.push(',');
+        self.1.write_json(out);
+        out.push(']');
+    }
+}
+
+impl Encode for ModuleId {
+    fn encode(&self, w: &mut Writer) {
+        w.write_varint(self.as_u64());
+    }
+}
+
+impl Decode for ModuleId {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        r.read_varint().map(ModuleId::from_raw)
+    }
+}
+
+impl ToJson for ModuleId {
+    fn write_json(&self, out: &mut String) {
+        self.as_u64().write_json(out);
+    }
+}
+
+// =============================================================================
+// Version Negotiation
+// =============================================================================
+
+/// Versions of a schema one side supports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaRange {
+    /// Schema name
+    pub name: String,
+    /// Oldest readable version
+    pub min: SchemaVersion,
+    /// Newest version
+    pub max: SchemaVersion,
+}
+
+impl SchemaRange {
+    /// Newest version both sides support
+    pub fn negotiate(&self, peer: &SchemaRange) -> Option<SchemaVersion> {
+        if self.name != peer.name {
+            return None;
+        }
+        let version = self.max.min(peer.max);
+        (version >= self.min.max(peer.min)).then_some(version)
+    }
+}
+
+impl Encode for SchemaRange {
+    fn encode(&self, w: &mut Writer) {
+        w.nested(|w| {
+            self.name.encode(w);
+            self.min.encode(w);
+            self.max.encode(w);
+        });
+    }
+}
+
+impl Decode for SchemaRange {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        r.nested(|r| Ok(Self { name: r.field()?, min: r.field()?, max: r.field()? }))
+    }
+}
+
+impl ToJson for SchemaRange {
+    fn write_json(&self, out: &mut String) {
+        out.push('{');
+        json::key(out, "name", true);
+        self.name.write_json(out);
+        json::key(out, "min", false);
+        self.min.write_json(out);
+        json::key(out, "max", false);
+        self.max.write_json(out);
+        out.push('}');
+    }
+}
+
+/// Schemas offered in a [`NEGOTIATE`] request, or agreed in its response
+///
+/// An agreed schema has `min == max`, the version to use.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaOffer {
+    /// Schemas
+    pub schemas: Vec<SchemaRange>,
+}
+
+impl SchemaOffer {
+    /// Agree on a version for every offered schema `local` also supports
+    pub fn answer(&self, local: &[SchemaRange]) -> SchemaOffer {
+        let schemas = self.schemas.iter()
+            .filter_map(|offered| {
+                let ours = local.iter().find(|l| l.name == offered.name)?;
+                let version = ours.negotiate(offered)?;
+                Some(SchemaRange { name: offered.name.clone(), min: version, max: version })
+            })
+            .collect();
+        SchemaOffer { schemas }
+    }
+}
+
+impl Encode for SchemaOffer {
+    fn encode(&self, w: &mut Writer) {
+        w.nested(|w| self.schemas.encode(w));
+    }
+}
+
+impl Decode for SchemaOffer {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        r.nested(|r| Ok(Self { schemas: r.field()? }))
+    }
+}
+
+impl ToJson for SchemaOffer {
+    fn write_json(&self, out: &mut String) {
+        out.push('{');
+        json::key(out, "schemas", true);
+        self.schemas.write_json(out);
+        out.push('}');
+    }
+}
+
+impl Message for SchemaOffer {
+    const NAME: &'static str = "helix.schema.offer";
+    const VERSION: SchemaVersion = 1;
+    const MIN_VERSION: SchemaVersion = 1;
+}
+
+/// Versions agreed with one peer
+#[derive(Debug, Clone, Default)]
+pub struct SchemaTable {
+    agreed: BTreeMap<String, SchemaVersion>,
+}
+
+impl SchemaTable {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the versions of a [`NEGOTIATE`] response
+    pub fn accept(&mut self, agreed: &SchemaOffer) {
+        for schema in &agreed.schemas {
+            self.agreed.insert(schema.name.clone(), schema.max);
+        }
+    }
+
+    /// Version to write `T` at for this peer
+    ///
+    /// Falls back to `T::MIN_VERSION` for schemas not negotiated.
+    pub fn version_of<T: Message>(&self) -> SchemaVersion {
+        self.agreed.get(T::NAME)
+            .copied()
+            .map_or(T::MIN_VERSION, |v| v.clamp(T::MIN_VERSION, T::VERSION))
+    }
+
+    /// Encode `T` at the version agreed with this peer
+    pub fn encode<T: Message>(&self, message: &T) -> Vec<u8> {
+        encode(message, self.version_of::<T>())
+    }
+}
+
+// =============================================================================
+// Envelopes
+// =============================================================================
+
+impl Request {
+    /// Create a request carrying a typed payload
+    pub fn typed<T: Message>(source: &'static str, request_type: &str, payload: &T, version: SchemaVersion) -> Self {
+        Self {
+            source,
+            request_type: String::from(request_type),
+            payload: encode(payload, version),
+        }
+    }
+
+    /// Create a [`NEGOTIATE`] request offering `local`
+    pub fn negotiate(source: &'static str, local: &[SchemaRange]) -> Self {
+        let offer = SchemaOffer { schemas: local.to_vec() };
+        Self::typed(source, NEGOTIATE, &offer, SchemaOffer::VERSION)
+    }
+
+    /// Decode the payload as `T`
+    pub fn decode<T: Message>(&self) -> Result<T, DecodeError> {
+        decode(&self.payload)
+    }
+}
+
+impl Response {
+    /// Create a success response carrying a typed payload
+    pub fn ok_typed<T: Message>(payload: &T, version: SchemaVersion) -> Self {
+        Self::ok(encode(payload, version))
+    }
+
+    /// Answer a [`NEGOTIATE`] request with the versions agreed for `local`
+    pub fn negotiated(request: &Request, local: &[SchemaRange]) -> Self {
+        match request.decode::<SchemaOffer>() {
+            Ok(offer) => Self::ok_typed(&offer.answer(local), SchemaOffer::VERSION),
+            Err(e) => Self::err(alloc::format!("bad schema offer: {}", e)),
+        }
+    }
+
+    /// Decode the payload as `T`
+    pub fn decode<T: Message>(&self) -> Result<T, DecodeError> {
+        decode(&self.payload)
+    }
+}
+
+impl Encode for Request {
+    fn encode(&self, w: &mut Writer) {
+        w.nested(|w| {
+            self.source.encode(w);
+            self.request_type.encode(w);
+            self.payload.encode(w);
+        });
+    }
+}
+
+impl ToJson for Request {
+    fn write_json(&self, out: &mut String) {
+        out.push('{');
+        json::key(out, "source", true);
+        self.source.write_json(out);
+        json::key(out, "request_type", false);
+        self.request_type.write_json(out);
+        json::key(out, "payload", false);
+        self.payload.write_json(out);
+        out.push('}');
+    }
+}
+
+impl Encode for Response {
+    fn encode(&self, w: &mut Writer) {
+        w.nested(|w| {
+            self.success.encode(w);
+            self.payload.encode(w);
+            self.error.encode(w);
+        });
+    }
+}
+
+impl Decode for Response {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        r.nested(|r| Ok(Self { success: r.field()?, payload: r.field()?, error: r.field()? }))
+    }
+}
+
+impl ToJson for Response {
+    fn write_json(&self, out: &mut String) {
+        out.push('{');
+        json::key(out, "success", true);
+        self.success.write_json(out);
+        json::key(out, "payload", false);
+        self.payload.write_json(out);
+        json::key(out, "error", false);
+        self.error.write_json(out);
+        out.push('}');
+    }
+}
+
+impl Message for Response {
+    const NAME: &'static str = "helix.response";
+    const VERSION: SchemaVersion = 1;
+    const MIN_VERSION: SchemaVersion = 1;
+}
+
+impl Encode for MessageType {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Self::Request => w.write_varint(0),
+            Self::Notify => w.write_varint(1),
+            Self::Query => w.write_varint(2),
+            Self::Control => w.write_varint(3),
+            Self::Error => w.write_varint(4),
+            Self::Custom(code) => {
+                w.write_varint(5);
+                code.encode(w);
+            }
+        }
+    }
+}
+
+impl Decode for MessageType {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok(match r.read_varint()? {
+            0 => Self::Request,
+            1 => Self::Notify,
+            2 => Self::Query,
+            3 => Self::Control,
+            4 => Self::Error,
+            5 => Self::Custom(r.field()?),
+            tag => return Err(DecodeError::InvalidTag(tag)),
+        })
+    }
+}
+
+impl ToJson for MessageType {
+    fn write_json(&self, out: &mut String) {
+        let name = match self {
+            Self::Request => "Request",
+            Self::Notify => "Notify",
+            Self::Query => "Query",
+            Self::Control => "Control",
+            Self::Error => "Error",
+            Self::Custom(code) => {
+                out.push('{');
+                json::key(out, "Custom", true);
+                code.write_json(out);
+                out.push('}');
+                return;
+            }
+        };
+        json::string(out, name);
+    }
+}
+
+impl Encode for MessagePayload {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Self::Empty => w.write_varint(0),
+            Self::Bytes(bytes) => {
+                w.write_varint(1);
+                w.write_bytes(bytes);
+            }
+            Self::Text(text) => {
+                w.write_varint(2);
+                text.encode(w);
+            }
+            Self::Integer(value) => {
+                w.write_varint(3);
+                value.encode(w);
+            }
+            Self::Boolean(value) => {
+                w.write_varint(4);
+                value.encode(w);
+            }
+            Self::Map(entries) => {
+                w.write_varint(5);
+                entries.encode(w);
+            }
+            Self::List(items) => {
+                w.write_varint(6);
+                items.encode(w);
+            }
+        }
+    }
+}
+
+impl Decode for MessagePayload {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok(match r.read_varint()? {
+            0 => Self::Empty,
+            1 => Self::Bytes(r.read_bytes()?.to_vec()),
+            2 => Self::Text(r.field()?),
+            3 => Self::Integer(r.field()?),
+            4 => Self::Boolean(r.field()?),
+            5 => Self::Map(r.field()?),
+            6 => Self::List(r.field()?),
+            tag => return Err(DecodeError::InvalidTag(tag)),
+        })
+    }
+}
+
+impl ToJson for MessagePayload {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Self::Empty => out.push_str("null"),
+            Self::Bytes(bytes) => bytes.write_json(out),
+            Self::Text(text) => text.write_json(out),
+            Self::Integer(value) => value.write_json(out),
+            Self::Boolean(value) => value.write_json(out),
+            Self::Map(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    json::key(out, key, i == 0);
+                    value.write_json(out);
+                }
+                out.push('}');
+            }
+            Self::List(items) => items.write_json(out),
+        }
+    }
+}
+
+impl Encode for ModuleMessage {
+    fn encode(&self, w: &mut Writer) {
+        w.nested(|w| {
+            self.source.encode(w);
+            self.target.encode(w);
+            self.msg_type.encode(w);
+            self.payload.encode(w);
+            self.id.encode(w);
+            self.is_response.encode(w);
+        });
+    }
+}
+
+impl Decode for ModuleMessage {
+    fn decode(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        r.nested(|r| {
+            Ok(Self {
+                source: r.field()?,
+                target: r.field()?,
+                msg_type: r.field()?,
+                payload: r.field()?,
+                id: r.field()?,
+                is_response: r.field()?,
+            })
+        })
+    }
+}
+
+impl ToJson for ModuleMessage {
+    fn write_json(&self, out: &mut String) {
+        out.push('{');
+        json::key(out, "source", true);
+        self.source.write_json(out);
+        json::key(out, "target", false);
+        self.target.write_json(out);
+        json::key(out, "msg_type", false);
+        self.msg_type.write_json(out);
+        json::key(out, "payload", false);
+        self.payload.write_json(out);
+        json::key(out, "id", false);
+        self.id.write_json(out);
+        json::key(out, "is_response", false);
+        self.is_response.write_json(out);
+        out.push('}');
+    }
+}
+
+impl Message for ModuleMessage {
+    const NAME: &'static str = "helix.module_message";
+    const VERSION: SchemaVersion = 1;
+    const MIN_VERSION: SchemaVersion = 1;
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[derive(Debug, PartialEq, Message)]
+    #[message(name = "test.stats", version = 1)]
+    struct StatsV1 {
+        switches: u64,
+        delta: i32,
+    }
+
+    #[derive(Debug, PartialEq, Message)]
+    #[message(name = "test.stats", version = 2)]
+    struct StatsV2 {
+        switches: u64,
+        delta: i32,
+        #[message(since = 2)]
+        label: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Message)]
+    enum Command {
+        Stop,
+        Slice(u64),
+        Move { cpu: u32, pinned: bool },
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let stats = StatsV2 { switches: 300, delta: -5, label: Some(String::from("cpu\"0")) };
+        let bytes = encode(&stats, 2);
+        assert_eq!(decode::<StatsV2>(&bytes), Ok(stats));
+
+        for command in [Command::Stop, Command::Slice(1 << 40), Command::Move { cpu: 3, pinned: true }] {
+            let mut w = Writer::new(1);
+            command.encode(&mut w);
+            let bytes = w.into_bytes();
+            assert_eq!(Command::decode(&mut Reader::new(&bytes, 1)), Ok(command));
+        }
+    }
+
+    #[test]
+    fn test_version_evolution() {
+        // New writer, old reader: the unknown field is skipped
+        let new = StatsV2 { switches: 7, delta: 1, label: Some(String::from("x")) };
+        assert_eq!(decode::<StatsV1>(&encode(&new, 2)), Ok(StatsV1 { switches: 7, delta: 1 }));
+
+        // Old writer, new reader: the missing field defaults
+        let old = StatsV1 { switches: 7, delta: 1 };
+        assert_eq!(decode::<StatsV2>(&encode(&old, 1)).unwrap().label, None);
+
+        // Writing at version 1 leaves the field out
+        assert_eq!(encode(&new, 1), encode(&old, 1));
+    }
+
+    #[test]
+    fn test_negotiation() {
+        let ours = [StatsV2::schema(), SchemaOffer::schema()];
+        let theirs = [StatsV1::schema(), SchemaRange { name: String::from("other"), min: 1, max: 1 }];
+
+        let request = Request::negotiate("peer", &theirs);
+        let response = Response::negotiated(&request, &ours);
+        let agreed = response.decode::<SchemaOffer>().unwrap();
+        assert_eq!(agreed.schemas, vec![SchemaRange { name: String::from("test.stats"), min: 1, max: 1 }]);
+
+        let mut table = SchemaTable::new();
+        assert_eq!(table.version_of::<StatsV2>(), 1);
+        table.accept(&SchemaOffer { schemas: vec![SchemaRange { name: String::from("test.stats"), min: 2, max: 2 }] });
+        assert_eq!(table.version_of::<StatsV2>(), 2);
+
+        let newer = SchemaRange { name: String::from("test.stats"), min: 3, max: 4 };
+        assert_eq!(StatsV2::schema().negotiate(&newer), None);
+    }
+
+    #[test]
+    fn test_json() {
+        let stats = StatsV2 { switches: 1, delta: -2, label: Some(String::from("a\"b")) };
+        assert_eq!(to_json(&stats), r#"{"switches":1,"delta":-2,"label":"a\"b"}"#);
+        assert_eq!(to_json(&Command::Stop), r#""Stop""#);
+        assert_eq!(to_json(&Command::Move { cpu: 1, pinned: false }), r#"{"Move":{"cpu":1,"pinned":false}}"#);
+        assert_eq!(to_json(&Command::Slice(4)), r#"{"Slice":[4]}"#);
+    }
+
+    #[test]
+    fn test_envelopes() {
+        let message = ModuleMessage {
+            source: ModuleId::from_raw(1),
+            target: ModuleId::from_raw(2),
+            msg_type: MessageType::Custom(9),
+            payload: MessagePayload::Map(vec![(String::from("n"), MessagePayload::Integer(-3))]),
+            id: 42,
+            is_response: false,
+        };
+        let decoded = decode::<ModuleMessage>(&encode(&message, 1)).unwrap();
+        assert_eq!(decoded.id, 42);
+        assert_eq!(decoded.msg_type, MessageType::Custom(9));
+        assert_eq!(to_json(&decoded.payload), r#"{"n":-3}"#);
+
+        assert_eq!(decode::<Response>(&[0]).unwrap_err(), DecodeError::IncompatibleVersion { found: 0, min: 1 });
+        assert_eq!(decode::<Response>(&[1, 5, 1]).unwrap_err(), DecodeError::Truncated);
+    }
+}
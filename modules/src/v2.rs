@@ -124,6 +124,30 @@ pub enum MemoryPressureLevel {
     Critical,
 }
 
+impl Event {
+    /// The event as published on the kernel event bus
+    ///
+    /// Ticks are not published: they are per-module scheduling, not news.
+    pub fn to_bus_event(&self) -> Option<helix_events::EventKind> {
+        use helix_events::{EventKind, PressureLevel};
+
+        Some(match self {
+            Event::Tick { .. } => return None,
+            Event::Shutdown => EventKind::Shutdown,
+            Event::MemoryPressure { level } => EventKind::MemoryPressure {
+                level: match level {
+                    MemoryPressureLevel::Normal => PressureLevel::Normal,
+                    MemoryPressureLevel::Low => PressureLevel::Low,
+                    MemoryPressureLevel::Critical => PressureLevel::Critical,
+                },
+                free_bytes: 0,
+            },
+            Event::CpuHotplug { cpu_id, online } => EventKind::CpuHotplug { cpu: *cpu_id, online: *online },
+            Event::Custom { name, data } => EventKind::Custom { name: name.clone(), data: data.clone() },
+        })
+    }
+}
+
 /// Response to an event
 #[derive(Debug, Clone)]
 pub enum EventResponse {
@@ -331,11 +355,14 @@ fn restore_state(&mut self, _state: &[u8]) -> Result<(), ModuleError> {
 // =============================================================================
 
 use crate::{Module, ModuleContext, ModuleMetadata, ModuleDependency};
+use crate::accounting::accounting;
 
 /// Adapter to use ModuleTrait with the old Module interface
 pub struct ModuleAdapter<T: ModuleTrait> {
     inner: T,
     metadata_cache: Option<ModuleMetadata>,
+    /// ID from the init context, for memory accounting
+    id: Option<ModuleId>,
 }
 
 impl<T: ModuleTrait> ModuleAdapter<T> {
@@ -344,6 +371,7 @@ pub fn new(module: T) -> Self {
         Self {
             inner: module,
             metadata_cache: None,
+            id: None,
         }
     }
 
@@ -412,15 +440,19 @@ fn init(&mut self, ctx: &ModuleContext) -> crate::ModuleResult<()> {
             Err(ModuleError::NotFound)
         };
         
+        self.id = Some(ctx.id);
+        let _charge = accounting().enter(ctx.id);
         let v2_ctx = Context::new(ctx.id, &config_fn, &request_fn);
         self.inner.init(&v2_ctx)
     }
 
     fn start(&mut self) -> crate::ModuleResult<()> {
+        let _charge = self.id.map(|id| accounting().enter(id));
         self.inner.start()
     }
 
     fn stop(&mut self) -> crate::ModuleResult<()> {
+        let _charge = self.id.map(|id| accounting().enter(id));
         self.inner.stop()
     }
 
@@ -25,9 +25,36 @@
 pub use config::RoundRobinConfig;
 
 use helix_modules::v2::{ModuleTrait, ModuleInfo, Context, Event, EventResponse, Request, Response};
-use helix_modules::{ModuleError, ModuleFlags};
+use helix_modules::schema::{self, Message, SchemaOffer, SchemaRange, SchemaTable};
+use helix_modules::{helix_module, ModuleError, ModuleFlags};
 use helix_execution::scheduler::Scheduler;  // Import the Scheduler trait
+use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+// =============================================================================
+// Request Payloads
+// =============================================================================
+
+/// Reply to `get_stats`
+#[derive(Debug, Clone, PartialEq, Eq, Message)]
+#[message(name = "helix.scheduler.stats", version = 1)]
+pub struct StatsReply {
+    /// Total number of context switches
+    pub context_switches: u64,
+    /// Threads currently runnable
+    pub runnable_threads: u64,
+    /// Threads currently blocked
+    pub blocked_threads: u64,
+}
+
+/// Payload of `set_time_slice`
+#[derive(Debug, Clone, PartialEq, Eq, Message)]
+#[message(name = "helix.scheduler.time_slice", version = 1)]
+pub struct SetTimeSlice {
+    /// Default time slice, in nanoseconds
+    pub time_slice_ns: u64,
+}
 
 // =============================================================================
 // Module Definition using v2 API
@@ -41,6 +68,8 @@ pub struct RoundRobinModule {
     config: RoundRobinConfig,
     /// CPU count
     cpu_count: usize,
+    /// Schema versions agreed with each requesting module
+    peers: BTreeMap<&'static str, SchemaTable>,
 }
 
 impl RoundRobinModule {
@@ -50,6 +79,7 @@ pub fn new() -> Self {
             scheduler: None,
             config: RoundRobinConfig::default(),
             cpu_count: 1,
+            peers: BTreeMap::new(),
         }
     }
 
@@ -59,10 +89,18 @@ pub fn with_config(config: RoundRobinConfig) -> Self {
             scheduler: None,
             config,
             cpu_count: 1,
+            peers: BTreeMap::new(),
         }
     }
 }
 
+impl RoundRobinModule {
+    /// Payload schemas this module speaks
+    pub fn schemas() -> Vec<SchemaRange> {
+        alloc::vec![StatsReply::schema(), SetTimeSlice::schema(), SchemaOffer::schema()]
+    }
+}
+
 impl Default for RoundRobinModule {
     fn default() -> Self {
         Self::new()
@@ -164,24 +202,42 @@ fn handle_event(&mut self, event: &Event) -> EventResponse {
 
     fn handle_request(&mut self, request: &Request) -> Result<Response, ModuleError> {
         match request.request_type.as_str() {
+            schema::NEGOTIATE => {
+                let response = Response::negotiated(request, &Self::schemas());
+                if let Ok(agreed) = response.decode::<SchemaOffer>() {
+                    self.peers.entry(request.source).or_default().accept(&agreed);
+                }
+                Ok(response)
+            }
             "get_stats" => {
                 if let Some(ref scheduler) = self.scheduler {
                     let stats = scheduler.stats();
-                    // Serialize stats (simplified - would use proper serialization)
-                    let payload = alloc::format!(
-                        "{{\"context_switches\":{},\"runnable_threads\":{},\"blocked_threads\":{}}}",
-                        stats.context_switches,
-                        stats.runnable_threads,
-                        stats.blocked_threads
+                    let reply = StatsReply {
+                        context_switches: stats.context_switches,
+                        runnable_threads: stats.runnable_threads as u64,
+                        blocked_threads: stats.blocked_threads as u64,
+                    };
+                    // At the version agreed with the requester, if any
+                    let payload = self.peers.get(request.source).map_or_else(
+                        || schema::encode(&reply, StatsReply::MIN_VERSION),
+                        |peer| peer.encode(&reply),
                     );
-                    Ok(Response::ok(payload.into_bytes()))
+                    Ok(Response::ok(payload))
                 } else {
                     Ok(Response::err("Scheduler not initialized"))
                 }
             }
             "set_time_slice" => {
-                // Parse time slice from payload
-                // For now, just acknowledge
+                let request = match request.decode::<SetTimeSlice>() {
+                    Ok(request) => request,
+                    Err(e) => return Ok(Response::err(alloc::format!("Bad set_time_slice payload: {}", e))),
+                };
+                let range = self.config.min_time_slice_ns..=self.config.max_time_slice_ns;
+                if !range.contains(&request.time_slice_ns) {
+                    return Ok(Response::err("Time slice out of range"));
+                }
+                // Applies from the next scheduler initialization
+                self.config.default_time_slice_ns = request.time_slice_ns;
                 Ok(Response::ok_empty())
             }
             _ => Ok(Response::err("Unknown request type")),
@@ -208,6 +264,7 @@ fn restore_state(&mut self, _state: &[u8]) -> Result<(), ModuleError> {
 // =============================================================================
 
 /// Create a new instance of this module
+#[helix_module]
 pub fn create_module() -> RoundRobinModule {
     RoundRobinModule::new()
 }
@@ -90,8 +90,8 @@ fn time_slice_for(&self, thread: &SchedulableThread) -> u64 {
         self.config.time_slice_for_priority(thread.priority.static_priority())
     }
 
-    /// Find the least loaded CPU
-    fn find_least_loaded_cpu(&self) -> usize {
+    /// Find the least loaded CPU allowed by `affinity`
+    fn find_least_loaded_cpu(&self, affinity: u64) -> usize {
         let threads = self.threads.read();
         let cpu_count = *self.cpu_count.read();
         
@@ -114,6 +114,7 @@ fn find_least_loaded_cpu(&self) -> usize {
 
         load.iter()
             .enumerate()
+            .filter(|(cpu, _)| affinity & (1 << cpu) != 0)
             .min_by_key(|(_, &l)| l)
             .map(|(cpu, _)| cpu)
             .unwrap_or(0)
@@ -221,7 +222,7 @@ fn thread_ready(&self, id: ThreadId) -> ExecResult<()> {
         drop(threads);
         
         // Find a suitable CPU
-        let cpu = self.find_least_loaded_cpu();
+        let cpu = self.find_least_loaded_cpu(affinity);
         
         // Add to that CPU's queue
         let cpu_queues = self.cpu_queues.read();
@@ -413,4 +414,10 @@ fn migrate_thread(&self, id: ThreadId, target_cpu: usize) -> ExecResult<()> {
         log::debug!("Migrated thread {:?} to CPU {}", id, target_cpu);
         Ok(())
     }
+
+    fn runqueue_len(&self, cpu: usize) -> Option<usize> {
+        self.cpu_queues.read()
+            .get(cpu)
+            .map(|q| q.queue.lock().len())
+    }
 }
@@ -0,0 +1,118 @@
+//! Target Disk Detection
+//!
+//! Enumerates block devices through the firmware block layer and filters
+//! them down to whole, writable disks large enough to hold Helix. The disk
+//! the installer itself was booted from is never offered as a target.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use helix_uefi::error::{Error, Result};
+use helix_uefi::protocols::{BlockDevice, DevicePath, ProtocolLocator};
+use helix_uefi::raw::protocols::loaded_image::{EfiDevicePathProtocol, EfiLoadedImageProtocol};
+use helix_uefi::raw::types::guids;
+use helix_uefi::services;
+use helix_uefi::Handle;
+
+use crate::gpt::MIN_DISK_SIZE;
+
+// =============================================================================
+// CANDIDATE DISK
+// =============================================================================
+
+/// A disk the installer can target
+pub struct Disk {
+    /// Block device
+    pub device: BlockDevice,
+    /// Device path (raw bytes, including end node)
+    pub device_path: Vec<u8>,
+}
+
+impl Disk {
+    /// Human-readable description for the selection menu
+    pub fn describe(&self) -> String {
+        let media = self.device.media();
+        format!(
+            "{} disk, {} ({} x {} byte blocks)",
+            if media.removable { "Removable" } else { "Fixed" },
+            media.size_string(),
+            self.device.block_count(),
+            self.device.block_size(),
+        )
+    }
+
+    /// Whether the disk already carries a partition table
+    pub fn has_partitions(&self) -> bool {
+        self.device.partitions().map(|p| !p.is_empty()).unwrap_or(false)
+    }
+}
+
+// =============================================================================
+// DETECTION
+// =============================================================================
+
+/// Device handle the installer image was loaded from
+pub fn boot_device_handle() -> Result<Handle> {
+    let image = services::image_handle().ok_or(Error::NotReady)?;
+    let bs = unsafe { services::boot_services() };
+
+    let loaded: *mut EfiLoadedImageProtocol =
+        unsafe { bs.handle_protocol(image, &guids::LOADED_IMAGE_PROTOCOL) }
+            .map_err(Error::from_status)?;
+
+    Ok(unsafe { (*loaded).device_handle })
+}
+
+/// Raw device path bytes for a handle
+fn device_path_bytes(handle: Handle) -> Option<Vec<u8>> {
+    let bs = unsafe { services::boot_services() };
+    let ptr: *mut EfiDevicePathProtocol =
+        unsafe { bs.handle_protocol(handle, &guids::DEVICE_PATH_PROTOCOL) }.ok()?;
+
+    let path = unsafe { DevicePath::from_raw(ptr) };
+    if path.is_empty() {
+        return None;
+    }
+
+    let bytes = unsafe { core::slice::from_raw_parts(path.as_ptr() as *const u8, path.len()) };
+    Some(bytes.to_vec())
+}
+
+/// Whether `child` is a path below `parent` (ignoring the end node)
+fn is_below(parent: &[u8], child: &[u8]) -> bool {
+    let prefix = &parent[..parent.len().saturating_sub(4)];
+    !prefix.is_empty() && child.starts_with(prefix)
+}
+
+/// Find every disk suitable as an install target
+pub fn detect() -> Result<Vec<Disk>> {
+    let live_path = boot_device_handle().ok().and_then(device_path_bytes);
+    let mut disks = Vec::new();
+
+    for located in ProtocolLocator::locate_all::<BlockDevice>()? {
+        let handle = located.handle();
+        let device = located.leak();
+        let media = device.media();
+
+        if media.logical || !media.present || media.readonly {
+            continue;
+        }
+        if device.size() < MIN_DISK_SIZE {
+            continue;
+        }
+
+        let Some(device_path) = device_path_bytes(handle) else {
+            continue;
+        };
+
+        // Skip the medium we booted from
+        if live_path.as_deref().is_some_and(|live| is_below(&device_path, live)) {
+            continue;
+        }
+
+        disks.push(Disk { device, device_path });
+    }
+
+    Ok(disks)
+}
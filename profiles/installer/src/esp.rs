@@ -0,0 +1,561 @@
+//! ESP Formatter
+//!
+//! Formats the new EFI System Partition as FAT32 and populates it in the
+//! same pass. Files are laid out in contiguous cluster runs, so the whole
+//! volume is written sequentially without needing a FAT driver on top of a
+//! partition the firmware has not yet connected.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use helix_uefi::error::{Error, Result};
+use helix_uefi::protocols::BlockDevice;
+
+use crate::gpt::PlannedPartition;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Reserved sectors before the first FAT
+const RESERVED_SECTORS: u32 = 32;
+
+/// Number of FAT copies
+const NUM_FATS: u32 = 2;
+
+/// FSInfo sector number
+const FSINFO_SECTOR: u32 = 1;
+
+/// Backup boot sector number
+const BACKUP_BOOT_SECTOR: u32 = 6;
+
+/// Minimum cluster count for a valid FAT32 volume
+const FAT32_MIN_CLUSTERS: u32 = 65525;
+
+/// End-of-chain marker
+const FAT_EOC: u32 = 0x0FFF_FFFF;
+
+/// Directory entry size
+const DIR_ENTRY_SIZE: usize = 32;
+
+/// Directory attribute
+const ATTR_DIRECTORY: u8 = 0x10;
+
+/// Volume label attribute
+const ATTR_VOLUME_ID: u8 = 0x08;
+
+/// Archive attribute
+const ATTR_ARCHIVE: u8 = 0x20;
+
+// =============================================================================
+// INPUT
+// =============================================================================
+
+/// A file to place on the new ESP
+#[derive(Debug, Clone)]
+pub struct EspFile {
+    /// Path using backslashes, e.g. `\EFI\BOOT\BOOTX64.EFI`
+    pub path: String,
+    /// File contents
+    pub data: Vec<u8>,
+}
+
+// =============================================================================
+// GEOMETRY
+// =============================================================================
+
+/// FAT32 volume geometry
+#[derive(Debug, Clone, Copy)]
+pub struct Fat32Geometry {
+    /// Bytes per sector (= device block size)
+    pub bytes_per_sector: u32,
+    /// Sectors per cluster
+    pub sectors_per_cluster: u32,
+    /// Total sectors in the volume
+    pub total_sectors: u32,
+    /// Sectors per FAT
+    pub fat_sectors: u32,
+    /// Number of data clusters
+    pub cluster_count: u32,
+}
+
+impl Fat32Geometry {
+    /// Compute geometry for a partition
+    pub fn compute(bytes_per_sector: u32, total_sectors: u64) -> Result<Self> {
+        let total_sectors = u32::try_from(total_sectors).map_err(|_| Error::Unsupported)?;
+
+        for spc in [8u32, 4, 2, 1] {
+            // Iterate to a fixed point: FAT size depends on cluster count
+            let mut fat_sectors = 1;
+            loop {
+                let data = total_sectors
+                    .saturating_sub(RESERVED_SECTORS + NUM_FATS * fat_sectors);
+                let clusters = data / spc;
+                let needed = ((clusters + 2) * 4).div_ceil(bytes_per_sector);
+                if needed <= fat_sectors {
+                    if clusters >= FAT32_MIN_CLUSTERS {
+                        return Ok(Self {
+                            bytes_per_sector,
+                            sectors_per_cluster: spc,
+                            total_sectors,
+                            fat_sectors,
+                            cluster_count: clusters,
+                        });
+                    }
+                    break;
+                }
+                fat_sectors = needed;
+            }
+        }
+
+        Err(Error::VolumeFull)
+    }
+
+    /// Bytes per cluster
+    pub const fn cluster_bytes(&self) -> usize {
+        (self.bytes_per_sector * self.sectors_per_cluster) as usize
+    }
+
+    /// First sector of the data region
+    pub const fn data_start(&self) -> u32 {
+        RESERVED_SECTORS + NUM_FATS * self.fat_sectors
+    }
+
+    /// First sector of a cluster
+    pub const fn cluster_sector(&self, cluster: u32) -> u32 {
+        self.data_start() + (cluster - 2) * self.sectors_per_cluster
+    }
+}
+
+// =============================================================================
+// DIRECTORY TREE
+// =============================================================================
+
+/// Node in the in-memory directory tree
+enum Node {
+    Dir {
+        name: [u8; 11],
+        children: Vec<Node>,
+        cluster: u32,
+    },
+    File {
+        name: [u8; 11],
+        data: Vec<u8>,
+        cluster: u32,
+    },
+}
+
+impl Node {
+    fn name(&self) -> &[u8; 11] {
+        match self {
+            Node::Dir { name, .. } | Node::File { name, .. } => name,
+        }
+    }
+}
+
+/// Characters allowed in a short name besides letters and digits
+const SHORT_NAME_SPECIAL: &[u8] = b"!#$%&'()-@^_`{}~";
+
+/// Convert a path component into an 8.3 short name
+fn short_name(component: &str) -> Result<[u8; 11]> {
+    let mut name = [b' '; 11];
+    let (base, ext) = match component.rfind('.') {
+        Some(pos) => (&component[..pos], &component[pos + 1..]),
+        None => (component, ""),
+    };
+
+    if base.is_empty() || base.len() > 8 || ext.len() > 3 {
+        return Err(Error::InvalidPath);
+    }
+    let valid = |b: &u8| b.is_ascii_alphanumeric() || SHORT_NAME_SPECIAL.contains(b);
+    if !base.bytes().chain(ext.bytes()).all(|b| valid(&b)) {
+        return Err(Error::InvalidPath);
+    }
+
+    for (i, b) in base.bytes().enumerate() {
+        name[i] = b.to_ascii_uppercase();
+    }
+    for (i, b) in ext.bytes().enumerate() {
+        name[8 + i] = b.to_ascii_uppercase();
+    }
+
+    Ok(name)
+}
+
+/// Insert a file into the tree, creating intermediate directories
+fn insert(children: &mut Vec<Node>, components: &[&str], data: Vec<u8>) -> Result<()> {
+    let name = short_name(components[0])?;
+
+    if components.len() == 1 {
+        if children.iter().any(|c| c.name() == &name) {
+            return Err(Error::InvalidPath);
+        }
+        children.push(Node::File { name, data, cluster: 0 });
+        return Ok(());
+    }
+
+    let index = match children.iter().position(|c| c.name() == &name) {
+        Some(i) => i,
+        None => {
+            children.push(Node::Dir { name, children: Vec::new(), cluster: 0 });
+            children.len() - 1
+        }
+    };
+
+    match &mut children[index] {
+        Node::Dir { children, .. } => insert(children, &components[1..], data),
+        Node::File { .. } => Err(Error::InvalidPath),
+    }
+}
+
+/// Number of clusters a directory needs (with `.`/`..` for non-root)
+fn dir_clusters(entry_count: usize, cluster_bytes: usize) -> u32 {
+    ((entry_count * DIR_ENTRY_SIZE).div_ceil(cluster_bytes)).max(1) as u32
+}
+
+// =============================================================================
+// BUILDER
+// =============================================================================
+
+/// Builds a populated FAT32 volume
+pub struct EspBuilder {
+    geometry: Fat32Geometry,
+    label: [u8; 11],
+    volume_id: u32,
+    root: Vec<Node>,
+    /// FAT entries for allocated clusters (index = cluster number)
+    fat: Vec<u32>,
+}
+
+impl EspBuilder {
+    /// Create a builder for a partition
+    pub fn new(partition: &PlannedPartition, block_size: u32, volume_id: u32) -> Result<Self> {
+        let geometry = Fat32Geometry::compute(block_size, partition.blocks())?;
+        let mut label = [b' '; 11];
+        label[..9].copy_from_slice(b"HELIX-ESP");
+
+        Ok(Self {
+            geometry,
+            label,
+            volume_id,
+            root: Vec::new(),
+            fat: vec![0x0FFF_FFF8, FAT_EOC],
+        })
+    }
+
+    /// Add a file
+    pub fn add_file(&mut self, file: EspFile) -> Result<()> {
+        let components: Vec<&str> = file.path.split('\\').filter(|c| !c.is_empty()).collect();
+        if components.is_empty() {
+            return Err(Error::InvalidPath);
+        }
+        insert(&mut self.root, &components, file.data)
+    }
+
+    /// Allocate a contiguous run of clusters, returning the first
+    fn allocate(&mut self, count: u32) -> Result<u32> {
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let first = self.fat.len() as u32;
+        if first - 2 + count > self.geometry.cluster_count {
+            return Err(Error::VolumeFull);
+        }
+
+        for i in 0..count {
+            let next = if i + 1 == count { FAT_EOC } else { first + i + 1 };
+            self.fat.push(next);
+        }
+
+        Ok(first)
+    }
+
+    /// Assign clusters to every node (root directory first)
+    fn assign(&mut self, nodes: &mut [Node]) -> Result<()> {
+        let cluster_bytes = self.geometry.cluster_bytes();
+
+        for node in nodes.iter_mut() {
+            match node {
+                Node::File { data, cluster, .. } => {
+                    *cluster = self.allocate(data.len().div_ceil(cluster_bytes) as u32)?;
+                }
+                Node::Dir { children, cluster, .. } => {
+                    *cluster = self.allocate(dir_clusters(children.len() + 2, cluster_bytes))?;
+                    self.assign(children)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serialize a directory's entries
+    fn dir_entries(&self, nodes: &[Node], self_cluster: u32, parent_cluster: Option<u32>) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        match parent_cluster {
+            None => out.extend_from_slice(&dir_entry(&self.label, ATTR_VOLUME_ID, 0, 0)),
+            Some(parent) => {
+                out.extend_from_slice(&dir_entry(b".          ", ATTR_DIRECTORY, self_cluster, 0));
+                out.extend_from_slice(&dir_entry(b"..         ", ATTR_DIRECTORY, parent, 0));
+            }
+        }
+
+        for node in nodes {
+            let entry = match node {
+                Node::Dir { name, cluster, .. } => dir_entry(name, ATTR_DIRECTORY, *cluster, 0),
+                Node::File { name, data, cluster } => {
+                    dir_entry(name, ATTR_ARCHIVE, *cluster, data.len() as u32)
+                }
+            };
+            out.extend_from_slice(&entry);
+        }
+
+        out
+    }
+
+    /// Write a directory and everything below it
+    fn write_dir(
+        &self,
+        device: &BlockDevice,
+        base_lba: u64,
+        nodes: &[Node],
+        self_cluster: u32,
+        parent_cluster: Option<u32>,
+    ) -> Result<()> {
+        let cluster_bytes = self.geometry.cluster_bytes();
+        let mut data = self.dir_entries(nodes, self_cluster, parent_cluster);
+        let len = data.len().div_ceil(cluster_bytes).max(1) * cluster_bytes;
+        data.resize(len, 0);
+        self.write_run(device, base_lba, self_cluster, &data)?;
+
+        for node in nodes {
+            match node {
+                Node::Dir { children, cluster, .. } => {
+                    // `..` of a first-level directory points at cluster 0 by convention
+                    let parent = if parent_cluster.is_none() { 0 } else { self_cluster };
+                    self.write_dir(device, base_lba, children, *cluster, Some(parent))?;
+                }
+                Node::File { data, cluster, .. } if !data.is_empty() => {
+                    let mut padded = data.clone();
+                    padded.resize(data.len().div_ceil(cluster_bytes) * cluster_bytes, 0);
+                    self.write_run(device, base_lba, *cluster, &padded)?;
+                }
+                Node::File { .. } => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write a contiguous cluster run
+    fn write_run(&self, device: &BlockDevice, base_lba: u64, cluster: u32, data: &[u8]) -> Result<()> {
+        let lba = base_lba + self.geometry.cluster_sector(cluster) as u64;
+        device.write_blocks(lba, data)
+    }
+
+    /// Build the boot sector
+    fn boot_sector(&self, hidden_sectors: u32) -> Vec<u8> {
+        let g = &self.geometry;
+        let mut bs = vec![0u8; g.bytes_per_sector as usize];
+
+        bs[0..3].copy_from_slice(&[0xEB, 0x58, 0x90]);
+        bs[3..11].copy_from_slice(b"HELIXOS ");
+        bs[11..13].copy_from_slice(&(g.bytes_per_sector as u16).to_le_bytes());
+        bs[13] = g.sectors_per_cluster as u8;
+        bs[14..16].copy_from_slice(&(RESERVED_SECTORS as u16).to_le_bytes());
+        bs[16] = NUM_FATS as u8;
+        bs[21] = 0xF8; // Fixed disk
+        bs[24..26].copy_from_slice(&63u16.to_le_bytes());
+        bs[26..28].copy_from_slice(&255u16.to_le_bytes());
+        bs[28..32].copy_from_slice(&hidden_sectors.to_le_bytes());
+        bs[32..36].copy_from_slice(&g.total_sectors.to_le_bytes());
+        bs[36..40].copy_from_slice(&g.fat_sectors.to_le_bytes());
+        bs[44..48].copy_from_slice(&2u32.to_le_bytes()); // Root cluster
+        bs[48..50].copy_from_slice(&(FSINFO_SECTOR as u16).to_le_bytes());
+        bs[50..52].copy_from_slice(&(BACKUP_BOOT_SECTOR as u16).to_le_bytes());
+        bs[64] = 0x80; // Drive number
+        bs[66] = 0x29; // Extended boot signature
+        bs[67..71].copy_from_slice(&self.volume_id.to_le_bytes());
+        bs[71..82].copy_from_slice(&self.label);
+        bs[82..90].copy_from_slice(b"FAT32   ");
+        bs[510] = 0x55;
+        bs[511] = 0xAA;
+
+        bs
+    }
+
+    /// Build the FSInfo sector
+    fn fsinfo_sector(&self) -> Vec<u8> {
+        let mut fs = vec![0u8; self.geometry.bytes_per_sector as usize];
+        let used = self.fat.len() as u32 - 2;
+
+        fs[0..4].copy_from_slice(&0x4161_5252u32.to_le_bytes());
+        fs[484..488].copy_from_slice(&0x6141_7272u32.to_le_bytes());
+        fs[488..492].copy_from_slice(&(self.geometry.cluster_count - used).to_le_bytes());
+        fs[492..496].copy_from_slice(&(self.fat.len() as u32).to_le_bytes());
+        fs[508..512].copy_from_slice(&0xAA55_0000u32.to_le_bytes());
+
+        fs
+    }
+
+    /// Format and populate the partition
+    pub fn write(mut self, device: &BlockDevice, partition: &PlannedPartition) -> Result<()> {
+        let mut root = core::mem::take(&mut self.root);
+        // Root directory always starts at cluster 2
+        let root_cluster = self.allocate(dir_clusters(root.len() + 1, self.geometry.cluster_bytes()))?;
+        self.assign(&mut root)?;
+
+        let base = partition.first_lba;
+        let g = self.geometry;
+        let sector = g.bytes_per_sector as usize;
+
+        // Reserved region
+        let boot = self.boot_sector(base.min(u32::MAX as u64) as u32);
+        let fsinfo = self.fsinfo_sector();
+        let mut reserved = vec![0u8; RESERVED_SECTORS as usize * sector];
+        for copy in [0, BACKUP_BOOT_SECTOR as usize] {
+            reserved[copy * sector..(copy + 1) * sector].copy_from_slice(&boot);
+            let fsi = (copy + FSINFO_SECTOR as usize) * sector;
+            reserved[fsi..fsi + sector].copy_from_slice(&fsinfo);
+        }
+        device.write_blocks(base, &reserved)?;
+
+        // FATs: allocated entries first, zeroes for the remainder
+        let mut head: Vec<u8> = self.fat.iter().flat_map(|e| e.to_le_bytes()).collect();
+        head.resize(head.len().div_ceil(sector) * sector, 0);
+        let head_sectors = (head.len() / sector) as u32;
+        let zeros = vec![0u8; 64 * sector];
+
+        for fat in 0..NUM_FATS {
+            let fat_lba = base + (RESERVED_SECTORS + fat * g.fat_sectors) as u64;
+            device.write_blocks(fat_lba, &head)?;
+
+            let mut s = head_sectors;
+            while s < g.fat_sectors {
+                let n = (g.fat_sectors - s).min(64) as usize;
+                device.write_blocks(fat_lba + s as u64, &zeros[..n * sector])?;
+                s += n as u32;
+            }
+        }
+
+        // Directory tree and file data
+        self.write_dir(device, base, &root, root_cluster, None)?;
+        device.flush()
+    }
+}
+
+/// Build a raw 32-byte directory entry
+fn dir_entry(name: &[u8; 11], attr: u8, cluster: u32, size: u32) -> [u8; DIR_ENTRY_SIZE] {
+    let mut e = [0u8; DIR_ENTRY_SIZE];
+    e[0..11].copy_from_slice(name);
+    e[11] = attr;
+    e[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+    e[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+    e[28..32].copy_from_slice(&size.to_le_bytes());
+    e
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Smallest volume with enough clusters for FAT32 at 512-byte sectors
+    const MIN_SECTORS: u64 = 66_599;
+
+    fn check(geometry: &Fat32Geometry) {
+        let fat_entries = geometry.fat_sectors * geometry.bytes_per_sector / 4;
+        assert!(fat_entries >= geometry.cluster_count + 2);
+        assert!(geometry.cluster_count >= FAT32_MIN_CLUSTERS);
+        let data_end =
+            geometry.data_start() + geometry.cluster_count * geometry.sectors_per_cluster;
+        assert!(data_end <= geometry.total_sectors);
+    }
+
+    #[test]
+    fn test_geometry() {
+        // Default ESP
+        let geometry = Fat32Geometry::compute(512, 1024 * 1024).unwrap();
+        assert_eq!(geometry.sectors_per_cluster, 8);
+        assert_eq!(geometry.cluster_bytes(), 4096);
+        check(&geometry);
+
+        let geometry = Fat32Geometry::compute(4096, 128 * 1024).unwrap();
+        assert_eq!(geometry.cluster_bytes(), 4096);
+        check(&geometry);
+    }
+
+    #[test]
+    fn test_geometry_boundaries() {
+        let geometry = Fat32Geometry::compute(512, MIN_SECTORS).unwrap();
+        assert_eq!(geometry.sectors_per_cluster, 1);
+        assert_eq!(geometry.cluster_count, FAT32_MIN_CLUSTERS);
+        check(&geometry);
+
+        assert_eq!(
+            Fat32Geometry::compute(512, MIN_SECTORS - 1).unwrap_err(),
+            Error::VolumeFull
+        );
+        assert_eq!(
+            Fat32Geometry::compute(512, 0).unwrap_err(),
+            Error::VolumeFull
+        );
+        assert_eq!(
+            Fat32Geometry::compute(512, 1 << 32).unwrap_err(),
+            Error::Unsupported
+        );
+        check(&Fat32Geometry::compute(512, u32::MAX as u64).unwrap());
+    }
+
+    #[test]
+    fn test_short_name() {
+        assert_eq!(&short_name("BOOTX64.EFI").unwrap(), b"BOOTX64 EFI");
+        assert_eq!(&short_name("boot.cfg").unwrap(), b"BOOT    CFG");
+        assert_eq!(&short_name("helix").unwrap(), b"HELIX      ");
+        assert_eq!(&short_name("12345678.abc").unwrap(), b"12345678ABC");
+
+        for name in [
+            "123456789",
+            "kernel.abcd",
+            ".cfg",
+            "",
+            "a.b.c",
+            "two words",
+            "a+b",
+            "caf\u{e9}",
+        ] {
+            assert_eq!(short_name(name), Err(Error::InvalidPath), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_short_name_collisions() {
+        let file = |data: u8| vec![data];
+        let mut root = Vec::new();
+
+        insert(&mut root, &["EFI", "helix", "boot.cfg"], file(1)).unwrap();
+        insert(&mut root, &["efi", "HELIX", "kernel"], file(2)).unwrap();
+        assert_eq!(root.len(), 1);
+
+        // Same 8.3 name once upper-cased
+        assert_eq!(
+            insert(&mut root, &["EFI", "helix", "BOOT.CFG"], file(3)),
+            Err(Error::InvalidPath)
+        );
+        // A file where a directory is needed, and the reverse
+        assert_eq!(
+            insert(&mut root, &["efi", "helix", "kernel", "x"], file(4)),
+            Err(Error::InvalidPath)
+        );
+        assert_eq!(
+            insert(&mut root, &["EFI", "Helix"], file(5)),
+            Err(Error::InvalidPath)
+        );
+    }
+}
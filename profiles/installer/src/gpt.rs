@@ -0,0 +1,313 @@
+//! GPT Layout Planning and Writing
+//!
+//! Computes the target disk layout (ESP + HelixFS root) and writes a
+//! protective MBR plus primary and backup GPT structures to a block device.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use helix_uefi::diag::crc32;
+use helix_uefi::error::{Error, Result};
+use helix_uefi::partition::{partition_types, Guid, GPT_ENTRY_SIZE, MAX_GPT_PARTITIONS};
+use helix_uefi::protocols::BlockDevice;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Partition alignment in bytes (1 MiB, matches every modern partitioner)
+pub const PARTITION_ALIGN: u64 = 1024 * 1024;
+
+/// Default ESP size
+pub const DEFAULT_ESP_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Smallest disk the installer accepts
+pub const MIN_DISK_SIZE: u64 = 2048 * 1024 * 1024;
+
+/// GPT header revision 1.0
+const GPT_REVISION: u32 = 0x0001_0000;
+
+/// GPT header size
+const GPT_HEADER_SIZE: u32 = 92;
+
+/// Size of the partition entry array in bytes
+const ENTRY_ARRAY_SIZE: u64 = (MAX_GPT_PARTITIONS * GPT_ENTRY_SIZE) as u64;
+
+// =============================================================================
+// LAYOUT
+// =============================================================================
+
+/// A single planned partition
+#[derive(Debug, Clone, Copy)]
+pub struct PlannedPartition {
+    /// Partition type GUID
+    pub type_guid: Guid,
+    /// Unique partition GUID
+    pub unique_guid: Guid,
+    /// First LBA (inclusive)
+    pub first_lba: u64,
+    /// Last LBA (inclusive)
+    pub last_lba: u64,
+    /// Partition name
+    pub name: &'static str,
+}
+
+impl PlannedPartition {
+    /// Size in blocks
+    pub const fn blocks(&self) -> u64 {
+        self.last_lba - self.first_lba + 1
+    }
+
+    /// Serialize to a 128-byte GPT entry
+    fn to_entry(self) -> [u8; GPT_ENTRY_SIZE] {
+        let mut entry = [0u8; GPT_ENTRY_SIZE];
+        entry[0..16].copy_from_slice(&self.type_guid.to_bytes());
+        entry[16..32].copy_from_slice(&self.unique_guid.to_bytes());
+        entry[32..40].copy_from_slice(&self.first_lba.to_le_bytes());
+        entry[40..48].copy_from_slice(&self.last_lba.to_le_bytes());
+        // Attributes (48..56) left zero
+
+        for (i, ch) in self.name.encode_utf16().take(36).enumerate() {
+            let off = 56 + i * 2;
+            entry[off..off + 2].copy_from_slice(&ch.to_le_bytes());
+        }
+
+        entry
+    }
+}
+
+/// Complete target disk layout
+#[derive(Debug, Clone, Copy)]
+pub struct DiskLayout {
+    /// Logical block size
+    pub block_size: u32,
+    /// Total blocks on the device
+    pub total_blocks: u64,
+    /// Disk GUID
+    pub disk_guid: Guid,
+    /// EFI System Partition
+    pub esp: PlannedPartition,
+    /// HelixFS root partition
+    pub root: PlannedPartition,
+}
+
+impl DiskLayout {
+    /// Plan the layout for a disk
+    ///
+    /// `guids` supplies the disk, ESP and root GUIDs, in that order.
+    pub fn plan(block_size: u32, total_blocks: u64, esp_size: u64, guids: [Guid; 3]) -> Result<Self> {
+        if block_size < 512 || !block_size.is_power_of_two() {
+            return Err(Error::Unsupported);
+        }
+
+        let bs = block_size as u64;
+        if total_blocks.saturating_mul(bs) < MIN_DISK_SIZE {
+            return Err(Error::VolumeFull);
+        }
+
+        let entry_blocks = ENTRY_ARRAY_SIZE.div_ceil(bs);
+        let align = (PARTITION_ALIGN / bs).max(1);
+
+        // LBA 0 protective MBR, LBA 1 header, then the entry array
+        let first_usable = 2 + entry_blocks;
+        // Backup entries + backup header at the end
+        let last_usable = total_blocks - 1 - entry_blocks - 1;
+
+        let esp_first = first_usable.next_multiple_of(align);
+        let esp_last = esp_first + esp_size.div_ceil(bs) - 1;
+
+        let root_first = (esp_last + 1).next_multiple_of(align);
+        // Keep the root partition a whole number of aligned units
+        let root_last = ((last_usable + 1) / align) * align - 1;
+
+        if root_first >= root_last {
+            return Err(Error::VolumeFull);
+        }
+
+        Ok(Self {
+            block_size,
+            total_blocks,
+            disk_guid: guids[0],
+            esp: PlannedPartition {
+                type_guid: partition_types::EFI_SYSTEM,
+                unique_guid: guids[1],
+                first_lba: esp_first,
+                last_lba: esp_last,
+                name: "EFI System Partition",
+            },
+            root: PlannedPartition {
+                type_guid: partition_types::HELIX_SYSTEM,
+                unique_guid: guids[2],
+                first_lba: root_first,
+                last_lba: root_last,
+                name: "Helix Root",
+            },
+        })
+    }
+
+    /// Number of blocks occupied by the partition entry array
+    pub fn entry_blocks(&self) -> u64 {
+        ENTRY_ARRAY_SIZE.div_ceil(self.block_size as u64)
+    }
+
+    /// First usable LBA for partitions
+    pub fn first_usable_lba(&self) -> u64 {
+        2 + self.entry_blocks()
+    }
+
+    /// Last usable LBA for partitions
+    pub fn last_usable_lba(&self) -> u64 {
+        self.total_blocks - 1 - self.entry_blocks() - 1
+    }
+
+    /// Build the serialized partition entry array
+    fn entry_array(&self) -> Vec<u8> {
+        let mut array = vec![0u8; self.entry_blocks() as usize * self.block_size as usize];
+        array[..GPT_ENTRY_SIZE].copy_from_slice(&self.esp.to_entry());
+        array[GPT_ENTRY_SIZE..2 * GPT_ENTRY_SIZE].copy_from_slice(&self.root.to_entry());
+        array
+    }
+
+    /// Build a GPT header block
+    fn header(&self, current: u64, backup: u64, entries_lba: u64, entries_crc: u32) -> Vec<u8> {
+        let mut block = vec![0u8; self.block_size as usize];
+
+        block[0..8].copy_from_slice(b"EFI PART");
+        block[8..12].copy_from_slice(&GPT_REVISION.to_le_bytes());
+        block[12..16].copy_from_slice(&GPT_HEADER_SIZE.to_le_bytes());
+        // Header CRC (16..20) computed last
+        block[24..32].copy_from_slice(&current.to_le_bytes());
+        block[32..40].copy_from_slice(&backup.to_le_bytes());
+        block[40..48].copy_from_slice(&self.first_usable_lba().to_le_bytes());
+        block[48..56].copy_from_slice(&self.last_usable_lba().to_le_bytes());
+        block[56..72].copy_from_slice(&self.disk_guid.to_bytes());
+        block[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+        block[80..84].copy_from_slice(&(MAX_GPT_PARTITIONS as u32).to_le_bytes());
+        block[84..88].copy_from_slice(&(GPT_ENTRY_SIZE as u32).to_le_bytes());
+        block[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+
+        let header_crc = crc32(&block[..GPT_HEADER_SIZE as usize]);
+        block[16..20].copy_from_slice(&header_crc.to_le_bytes());
+
+        block
+    }
+
+    /// Build the protective MBR block
+    fn protective_mbr(&self) -> Vec<u8> {
+        let mut block = vec![0u8; self.block_size as usize];
+        let entry = &mut block[446..462];
+
+        entry[1..4].copy_from_slice(&[0x00, 0x02, 0x00]); // CHS start
+        entry[4] = 0xEE; // GPT protective
+        entry[5..8].copy_from_slice(&[0xFF, 0xFF, 0xFF]); // CHS end
+        entry[8..12].copy_from_slice(&1u32.to_le_bytes());
+        let size = (self.total_blocks - 1).min(u32::MAX as u64) as u32;
+        entry[12..16].copy_from_slice(&size.to_le_bytes());
+
+        block[510] = 0x55;
+        block[511] = 0xAA;
+        block
+    }
+
+    /// Write the complete partition table to a device
+    pub fn write(&self, device: &BlockDevice) -> Result<()> {
+        if device.is_readonly() {
+            return Err(Error::WriteProtected);
+        }
+        if device.block_size() != self.block_size || device.block_count() != self.total_blocks {
+            return Err(Error::MediaChanged);
+        }
+
+        let last = self.total_blocks - 1;
+        let entries = self.entry_array();
+        let entries_crc = crc32(&entries[..ENTRY_ARRAY_SIZE as usize]);
+        let backup_entries_lba = last - self.entry_blocks();
+
+        device.write_blocks(0, &self.protective_mbr())?;
+        device.write_blocks(2, &entries)?;
+        device.write_blocks(1, &self.header(1, last, 2, entries_crc))?;
+        device.write_blocks(backup_entries_lba, &entries)?;
+        device.write_blocks(last, &self.header(last, 1, backup_entries_lba, entries_crc))?;
+
+        device.flush()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUIDS: [Guid; 3] = [
+        Guid::new(1, 0, 0, [0; 8]),
+        Guid::new(2, 0, 0, [0; 8]),
+        Guid::new(3, 0, 0, [0; 8]),
+    ];
+
+    fn check(layout: &DiskLayout) {
+        let align = (PARTITION_ALIGN / layout.block_size as u64).max(1);
+        for part in [&layout.esp, &layout.root] {
+            assert_eq!(part.first_lba % align, 0);
+            assert!(part.first_lba >= layout.first_usable_lba());
+            assert!(part.last_lba <= layout.last_usable_lba());
+        }
+        assert!(layout.esp.last_lba < layout.root.first_lba);
+        assert_eq!((layout.root.last_lba + 1) % align, 0);
+    }
+
+    #[test]
+    fn test_plan() {
+        let blocks = MIN_DISK_SIZE / 512;
+        let layout = DiskLayout::plan(512, blocks, DEFAULT_ESP_SIZE, GUIDS).unwrap();
+        check(&layout);
+
+        assert_eq!(layout.entry_blocks(), 32);
+        assert_eq!(layout.first_usable_lba(), 34);
+        assert_eq!(layout.last_usable_lba(), blocks - 34);
+        assert_eq!(layout.esp.first_lba, 2048);
+        assert_eq!(layout.esp.blocks() * 512, DEFAULT_ESP_SIZE);
+        assert_eq!(layout.root.first_lba, layout.esp.last_lba + 1);
+        // The last, partial alignment unit is left unused
+        assert_eq!(layout.root.last_lba, blocks - 2048 - 1);
+        assert_eq!(layout.disk_guid, GUIDS[0]);
+        assert_eq!(layout.root.unique_guid, GUIDS[2]);
+    }
+
+    #[test]
+    fn test_plan_block_sizes() {
+        let layout = DiskLayout::plan(4096, MIN_DISK_SIZE / 4096, DEFAULT_ESP_SIZE, GUIDS).unwrap();
+        check(&layout);
+        assert_eq!(layout.entry_blocks(), 4);
+        assert_eq!(layout.esp.first_lba, 256);
+
+        // Odd-sized ESP rounded up to whole blocks
+        let layout =
+            DiskLayout::plan(512, MIN_DISK_SIZE / 512, DEFAULT_ESP_SIZE + 1, GUIDS).unwrap();
+        check(&layout);
+        assert_eq!(layout.esp.blocks(), DEFAULT_ESP_SIZE / 512 + 1);
+
+        for block_size in [0, 256, 520, 3000] {
+            let err = DiskLayout::plan(block_size, 1 << 24, DEFAULT_ESP_SIZE, GUIDS).unwrap_err();
+            assert_eq!(err, Error::Unsupported);
+        }
+    }
+
+    #[test]
+    fn test_plan_too_small() {
+        let blocks = MIN_DISK_SIZE / 512;
+        let plan = |blocks, esp_size| DiskLayout::plan(512, blocks, esp_size, GUIDS).map(|_| ());
+
+        assert_eq!(plan(blocks - 1, DEFAULT_ESP_SIZE), Err(Error::VolumeFull));
+        assert_eq!(plan(0, DEFAULT_ESP_SIZE), Err(Error::VolumeFull));
+        // No room left for the root partition
+        assert_eq!(
+            plan(blocks, MIN_DISK_SIZE - 2 * PARTITION_ALIGN),
+            Err(Error::VolumeFull)
+        );
+        assert_eq!(plan(blocks, MIN_DISK_SIZE), Err(Error::VolumeFull));
+        assert!(plan(blocks, MIN_DISK_SIZE - 3 * PARTITION_ALIGN).is_ok());
+    }
+}
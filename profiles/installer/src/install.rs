@@ -0,0 +1,273 @@
+//! Installation Steps
+//!
+//! Runs the individual install phases against a selected disk:
+//! partitioning, ESP population, HelixFS root formatting, and firmware
+//! boot entry registration.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use helix_uefi::device_path::DevicePathBuilder;
+use helix_uefi::error::{Error, Result};
+use helix_uefi::partition::Guid;
+use helix_uefi::protocols::{FileSystem, Protocol};
+use helix_uefi::services::variables::{self, BootOption};
+use helix_uefi::settings::Settings;
+use helixfs::disk::superblock::SuperblockLocations;
+use helixfs::Superblock;
+
+use crate::disk::{self, Disk};
+use crate::esp::{EspBuilder, EspFile};
+use crate::gpt::{DiskLayout, PlannedPartition, DEFAULT_ESP_SIZE};
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Bootloader path, on both the live medium and the target ESP
+pub const BOOTLOADER_PATH: &str = "\\EFI\\BOOT\\BOOTX64.EFI";
+
+/// Kernel path
+pub const KERNEL_PATH: &str = "\\EFI\\helix\\kernel";
+
+/// Initrd path (optional on the live medium)
+pub const INITRD_PATH: &str = "\\EFI\\helix\\initrd";
+
+/// Boot configuration path on the target ESP
+pub const BOOT_CONFIG_PATH: &str = "\\EFI\\helix\\boot.cfg";
+
+/// Boot entry description
+pub const BOOT_DESCRIPTION: &str = "Helix OS";
+
+/// Default kernel command line
+pub const DEFAULT_CMDLINE: &str = "quiet loglevel=3";
+
+/// HelixFS block size
+pub const ROOT_BLOCK_SIZE: u32 = 4096;
+
+/// HelixFS volume label
+pub const ROOT_LABEL: &str = "helix-root";
+
+// =============================================================================
+// PAYLOAD
+// =============================================================================
+
+/// Files read from the live medium
+pub struct Payload {
+    /// Bootloader image
+    pub bootloader: Vec<u8>,
+    /// Kernel image
+    pub kernel: Vec<u8>,
+    /// Initial ramdisk, if the live medium has one
+    pub initrd: Option<Vec<u8>>,
+}
+
+impl Payload {
+    /// Read the payload from the medium the installer was booted from
+    pub fn load() -> Result<Self> {
+        let mut fs = <FileSystem as Protocol>::open(disk::boot_device_handle()?)?;
+
+        Ok(Self {
+            bootloader: fs.read(BOOTLOADER_PATH)?,
+            kernel: fs.read(KERNEL_PATH)?,
+            initrd: fs.read(INITRD_PATH).ok(),
+        })
+    }
+
+    /// Total size in bytes
+    pub fn size(&self) -> usize {
+        self.bootloader.len() + self.kernel.len() + self.initrd.as_ref().map_or(0, Vec::len)
+    }
+}
+
+// =============================================================================
+// STEPS
+// =============================================================================
+
+/// Install phases, in execution order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Write GPT
+    Partition,
+    /// Format and populate the ESP
+    FormatEsp,
+    /// Format the HelixFS root
+    FormatRoot,
+    /// Register the firmware boot entry
+    RegisterBoot,
+}
+
+impl Step {
+    /// All steps in order
+    pub const ALL: [Step; 4] = [Step::Partition, Step::FormatEsp, Step::FormatRoot, Step::RegisterBoot];
+
+    /// Step description
+    pub fn name(&self) -> &'static str {
+        match self {
+            Step::Partition => "Writing partition table",
+            Step::FormatEsp => "Formatting EFI System Partition",
+            Step::FormatRoot => "Formatting HelixFS root",
+            Step::RegisterBoot => "Registering boot entry",
+        }
+    }
+}
+
+// =============================================================================
+// INSTALLER
+// =============================================================================
+
+/// A planned installation onto one disk
+pub struct Installation<'a> {
+    disk: &'a Disk,
+    layout: DiskLayout,
+    root_uuid: [u8; 16],
+    volume_id: u32,
+}
+
+impl<'a> Installation<'a> {
+    /// Plan an installation
+    ///
+    /// `random` must hold at least 68 bytes of entropy for GUIDs and
+    /// volume identifiers.
+    pub fn plan(disk: &'a Disk, random: &[u8; 68]) -> Result<Self> {
+        let guid = |i: usize| Guid::from_bytes(&random[i * 16..(i + 1) * 16]).ok_or(Error::InvalidData);
+        let layout = DiskLayout::plan(
+            disk.device.block_size(),
+            disk.device.block_count(),
+            DEFAULT_ESP_SIZE,
+            [guid(0)?, guid(1)?, guid(2)?],
+        )?;
+
+        let mut root_uuid = [0u8; 16];
+        root_uuid.copy_from_slice(&random[48..64]);
+
+        Ok(Self {
+            disk,
+            layout,
+            root_uuid,
+            volume_id: u32::from_le_bytes([random[64], random[65], random[66], random[67]]),
+        })
+    }
+
+    /// Planned disk layout
+    pub fn layout(&self) -> &DiskLayout {
+        &self.layout
+    }
+
+    /// Run a single step
+    pub fn run(&self, step: Step, payload: &Payload) -> Result<()> {
+        match step {
+            Step::Partition => self.layout.write(&self.disk.device),
+            Step::FormatEsp => self.format_esp(payload),
+            Step::FormatRoot => self.format_root(),
+            Step::RegisterBoot => self.register_boot(),
+        }
+    }
+
+    /// Format the ESP and copy the payload onto it
+    fn format_esp(&self, payload: &Payload) -> Result<()> {
+        let esp = &self.layout.esp;
+        let mut builder = EspBuilder::new(esp, self.layout.block_size, self.volume_id)?;
+
+        let file = |path: &str, data: Vec<u8>| EspFile { path: String::from(path), data };
+
+        builder.add_file(file(BOOTLOADER_PATH, payload.bootloader.clone()))?;
+        builder.add_file(file(KERNEL_PATH, payload.kernel.clone()))?;
+        if let Some(initrd) = &payload.initrd {
+            builder.add_file(file(INITRD_PATH, initrd.clone()))?;
+        }
+        builder.add_file(file(BOOT_CONFIG_PATH, boot_config(payload.initrd.is_some()).into_bytes()))?;
+
+        builder.write(&self.disk.device, esp)
+    }
+
+    /// Write HelixFS superblocks to the root partition
+    fn format_root(&self) -> Result<()> {
+        let root = &self.layout.root;
+        let bs = self.layout.block_size as u64;
+        let fs_blocks = root.blocks() * bs / ROOT_BLOCK_SIZE as u64;
+        let per_fs_block = ROOT_BLOCK_SIZE as u64 / bs;
+
+        let mut sb = Superblock::create(fs_blocks, ROOT_BLOCK_SIZE, self.root_uuid, ROOT_LABEL);
+        sb.prepare_sync();
+
+        let mut block = vec![0u8; ROOT_BLOCK_SIZE as usize];
+        let bytes = sb.to_bytes();
+        block[..bytes.len()].copy_from_slice(&bytes);
+
+        for location in SuperblockLocations::calculate(fs_blocks).all_locations() {
+            let lba = root.first_lba + location.get() * per_fs_block;
+            self.disk.device.write_blocks(lba, &block)?;
+        }
+
+        self.disk.device.flush()
+    }
+
+    /// Add a Boot#### entry for the new ESP and put it first in BootOrder
+    fn register_boot(&self) -> Result<()> {
+        let option = BootOption {
+            attributes: BootOption::ACTIVE,
+            description: String::from(BOOT_DESCRIPTION),
+            device_path: self.boot_device_path(),
+            optional_data: Vec::new(),
+        };
+
+        let mut order = variables::read_boot_order().unwrap_or_default();
+        let num = (0..=u16::MAX)
+            .find(|n| !order.contains(n) && variables::read_boot_option(*n).is_err())
+            .ok_or(Error::OutOfResources)?;
+
+        variables::write_boot_option(num, &option).map_err(Error::from_status)?;
+        order.insert(0, num);
+        variables::write_boot_order(&order).map_err(Error::from_status)
+    }
+
+    /// Full device path: disk, then ESP partition, then bootloader file
+    fn boot_device_path(&self) -> Vec<u8> {
+        let esp: &PlannedPartition = &self.layout.esp;
+        let tail = DevicePathBuilder::new()
+            .gpt_partition(1, esp.first_lba, esp.blocks(), esp.unique_guid.to_bytes())
+            .file_path(BOOTLOADER_PATH)
+            .build();
+
+        let mut buf = vec![0u8; tail.length()];
+        let len = tail.to_bytes(&mut buf);
+        buf.truncate(len);
+
+        // Drop the disk path's end node before appending
+        let disk_path = &self.disk.device_path;
+        let mut path = disk_path[..disk_path.len().saturating_sub(4)].to_vec();
+        path.extend_from_slice(&buf);
+        path
+    }
+}
+
+// =============================================================================
+// GENERATED FILES
+// =============================================================================
+
+/// Initial `boot.cfg` in the format read by `helix_uefi::config`
+pub fn boot_config(has_initrd: bool) -> String {
+    let mut cfg = format!(
+        "# Generated by the Helix installer\n\
+         timeout = {}\n\
+         default = 0\n\
+         \n\
+         [entry.helix]\n\
+         title = {}\n\
+         kernel = {}\n\
+         cmdline = {}\n",
+        Settings::default().boot.timeout_secs,
+        BOOT_DESCRIPTION,
+        KERNEL_PATH,
+        DEFAULT_CMDLINE,
+    );
+
+    if has_initrd {
+        cfg.push_str(&format!("initrd = {}\n", INITRD_PATH));
+    }
+
+    cfg
+}
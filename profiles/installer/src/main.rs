@@ -0,0 +1,121 @@
+//! # Helix Installer
+//!
+//! UEFI application that installs Helix from the live medium onto a local
+//! disk:
+//!
+//! 1. Detect candidate disks through the firmware block layer
+//! 2. Let the user pick a target and confirm
+//! 3. Write a GPT with an EFI System Partition and a HelixFS root
+//! 4. Copy the bootloader, kernel and `boot.cfg` onto the new ESP
+//! 5. Register a `Boot####` entry
+
+#![no_std]
+#![cfg_attr(not(test), no_main)]
+
+extern crate alloc;
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use helix_uefi::error::{Error, Result};
+use helix_uefi::protocols::console::Console;
+use helix_uefi::protocols::rng::SimplePrng;
+use helix_uefi::{services, UefiEnv};
+
+mod disk;
+mod esp;
+mod gpt;
+mod install;
+
+use install::{Installation, Payload, Step};
+
+helix_uefi::entry!(installer_main);
+
+/// Installer entry point
+fn installer_main(env: UefiEnv) -> Result<()> {
+    unsafe {
+        services::initialize(env.image_handle(), env.system_table() as *const _ as *mut _)
+            .map_err(Error::from_status)?;
+    }
+
+    let console = Console::get()?;
+    console.clear()?;
+    console.writeln("Helix OS Installer")?;
+    console.writeln("==================")?;
+    console.writeln("")?;
+
+    let result = run(&console);
+    if let Err(e) = &result {
+        console.writeln("")?;
+        console.writeln(&format!("Installation failed: {:?}", e))?;
+    }
+
+    console.writeln("Press any key to exit.")?;
+    console.wait_for_key()?;
+    result
+}
+
+/// Interactive installation flow
+fn run(console: &Console) -> Result<()> {
+    console.writeln("Reading installation payload...")?;
+    let payload = Payload::load()?;
+    console.writeln(&format!("  {} KiB to install", payload.size() / 1024))?;
+    console.writeln("")?;
+
+    let disks = disk::detect()?;
+    if disks.is_empty() {
+        console.writeln("No suitable target disk found.")?;
+        return Err(Error::NotFound);
+    }
+
+    let labels: Vec<_> = disks.iter().map(disk::Disk::describe).collect();
+    let options: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+    let target = &disks[console.menu("Select target disk:", &options)?];
+
+    let installation = Installation::plan(target, &entropy())?;
+    let layout = installation.layout();
+    let mib = |blocks: u64| blocks * layout.block_size as u64 / (1024 * 1024);
+
+    console.writeln("")?;
+    console.writeln("Planned layout:")?;
+    console.writeln(&format!("  1. EFI System Partition  {} MiB", mib(layout.esp.blocks())))?;
+    console.writeln(&format!("  2. Helix Root (HelixFS)  {} MiB", mib(layout.root.blocks())))?;
+    console.writeln("")?;
+
+    if target.has_partitions() {
+        console.writeln("WARNING: the selected disk already contains partitions.")?;
+    }
+
+    if !cfg!(feature = "unattended")
+        && !console.confirm("All data on the selected disk will be erased. Continue?")?
+    {
+        return Err(Error::Aborted);
+    }
+
+    console.writeln("")?;
+    for (i, step) in Step::ALL.iter().enumerate() {
+        console.writeln(step.name())?;
+        installation.run(*step, &payload)?;
+        console.progress_bar(i + 1, Step::ALL.len(), 40)?;
+        console.writeln("")?;
+    }
+
+    console.writeln("")?;
+    console.writeln("Installation complete. Remove the installation medium and reboot.")?;
+    Ok(())
+}
+
+/// Entropy for partition GUIDs and volume identifiers
+fn entropy() -> [u8; 68] {
+    let mut prng = SimplePrng::new(helix_uefi::time::read_tsc());
+    let mut bytes = [0u8; 68];
+    prng.fill(&mut bytes);
+
+    // Mark the three partition GUIDs and the root UUID as version 4
+    for guid in bytes[..64].chunks_exact_mut(16) {
+        guid[7] = (guid[7] & 0x0F) | 0x40;
+        guid[8] = (guid[8] & 0x3F) | 0x80;
+    }
+
+    bytes
+}
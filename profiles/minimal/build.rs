@@ -0,0 +1,7 @@
+//! Generates the profile configuration from `helix.toml`
+
+fn main() {
+    if let Err(err) = helix_config::build_profile() {
+        panic!("helix.toml: {}", err);
+    }
+}
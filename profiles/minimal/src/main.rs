@@ -133,7 +133,12 @@ fn init_heap() {
 }
 
 // Framework components
-// These would be conditionally compiled based on helix.toml
+// Generated from helix.toml by build.rs; features and subsystems are also
+// available as cfg(helix_feature = "...") and cfg(helix_subsystem = "...")
+#[allow(dead_code)]
+mod config {
+    include!(concat!(env!("OUT_DIR"), "/helix_config.rs"));
+}
 
 /// Kernel entry point
 ///
@@ -153,6 +158,14 @@ pub extern "C" fn kernel_main(multiboot2_info: *const u8) -> ! {
     serial_write_str("========================================\n");
     serial_write_str("\n");
 
+    serial_write_str("[BOOT] Profile: ");
+    serial_write_str(config::PROFILE_NAME);
+    serial_write_str(" ");
+    serial_write_str(config::PROFILE_VERSION);
+    serial_write_str(" (");
+    serial_write_str(config::TARGET);
+    serial_write_str(")\n");
+
     // Phase 0: Parse Multiboot2 info and initialize framebuffer
     serial_write_str("[BOOT] Parsing Multiboot2 boot information...\n");
     unsafe {
@@ -178,6 +191,9 @@ pub extern "C" fn kernel_main(multiboot2_info: *const u8) -> ! {
     serial_write_str("[BOOT] Initializing interrupts...\n");
     init_interrupts();
 
+    serial_write_str("[BOOT] Protecting kernel image...\n");
+    protect_kernel_image();
+
     // Phase 3: Scheduler
     serial_write_str("[BOOT] Initializing scheduler...\n");
     init_scheduler();
@@ -186,7 +202,20 @@ pub extern "C" fn kernel_main(multiboot2_info: *const u8) -> ! {
     serial_write_str("[BOOT] Initializing HelixFS...\n");
     init_filesystem();
 
-    // Phase 5: Start the kernel with graphical output
+    // Phase 4.5: Fill in the syscall table while it is still writable
+    serial_write_str("[BOOT] Initializing userspace...\n");
+    init_userspace();
+
+    // Phase 4.6: Module files handed over by the bootloader
+    serial_write_str("[BOOT] Registering boot modules...\n");
+    init_modules(multiboot2_info);
+
+    // Phase 5: Freeze the tables written during init (IDT, module registry,
+    // syscall table) and enter the lockdown level asked for on the command line
+    serial_write_str("[BOOT] Locking down kernel...\n");
+    lockdown_kernel(unsafe { multiboot2_cmdline(multiboot2_info) });
+
+    // Phase 6: Start the kernel with graphical output
     serial_write_str("[BOOT] Starting kernel...\n");
 
     // Display boot message on graphical console
@@ -304,6 +333,76 @@ unsafe fn parse_multiboot2_framebuffer(mb2_info: *const u8) {
     serial_write_str("  [FB] No framebuffer tag found in Multiboot2 info\n");
 }
 
+/// Kernel command line from the Multiboot2 info, empty if absent
+///
+/// # Safety
+///
+/// `mb2_info` must be null or point to the Multiboot2 info structure,
+/// which must stay mapped.
+unsafe fn multiboot2_cmdline(mb2_info: *const u8) -> &'static str {
+    if mb2_info.is_null() {
+        return "";
+    }
+    let total_size = *(mb2_info as *const u32);
+    let mut tag_ptr = mb2_info.add(8);
+    let end_ptr = mb2_info.add(total_size as usize);
+
+    while (tag_ptr as usize) < (end_ptr as usize) {
+        let tag_type = *(tag_ptr as *const u32);
+        let tag_size = *(tag_ptr.add(4) as *const u32);
+        if tag_type == 0 {
+            break;
+        }
+
+        // Tag type 1 = command line, a NUL-terminated string after type/size
+        if tag_type == 1 {
+            let bytes = core::slice::from_raw_parts(tag_ptr.add(8), (tag_size as usize).saturating_sub(8));
+            let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            return core::str::from_utf8(&bytes[..len]).unwrap_or("");
+        }
+
+        tag_ptr = tag_ptr.add(((tag_size as usize + 7) & !7).max(8));
+    }
+    ""
+}
+
+/// Multiboot2 modules as (command line, contents)
+///
+/// # Safety
+///
+/// As for [`multiboot2_cmdline`]; the modules must stay mapped too.
+unsafe fn multiboot2_modules(mb2_info: *const u8) -> alloc::vec::Vec<(&'static str, &'static [u8])> {
+    let mut modules = alloc::vec::Vec::new();
+    if mb2_info.is_null() {
+        return modules;
+    }
+    let total_size = *(mb2_info as *const u32);
+    let mut tag_ptr = mb2_info.add(8);
+    let end_ptr = mb2_info.add(total_size as usize);
+
+    while (tag_ptr as usize) < (end_ptr as usize) {
+        let tag_type = *(tag_ptr as *const u32);
+        let tag_size = *(tag_ptr.add(4) as *const u32);
+        if tag_type == 0 {
+            break;
+        }
+
+        // Tag type 3 = module: start and end address, then its command line
+        if tag_type == 3 && tag_size >= 16 {
+            let start = *(tag_ptr.add(8) as *const u32) as usize;
+            let end = *(tag_ptr.add(12) as *const u32) as usize;
+            let bytes = core::slice::from_raw_parts(tag_ptr.add(16), tag_size as usize - 16);
+            let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            if let (Ok(cmdline), Some(size)) = (core::str::from_utf8(&bytes[..len]), end.checked_sub(start)) {
+                modules.push((cmdline, core::slice::from_raw_parts(start as *const u8, size)));
+            }
+        }
+
+        tag_ptr = tag_ptr.add(((tag_size as usize + 7) & !7).max(8));
+    }
+    modules
+}
+
 /// Test heap allocation
 fn test_allocation() {
     use alloc::vec::Vec;
@@ -411,6 +510,9 @@ fn init_memory() {
     // - Virtual memory (if enabled)
     // - Heap allocator
 
+    // Seed heap canaries and freelist links before any hardened heap exists
+    helix_memory::allocator::hardening::init_from_entropy();
+
     kernel_log!("Memory initialized");
 }
 
@@ -424,9 +526,60 @@ fn init_interrupts() {
         helix_hal::arch::x86_64::init();
     }
 
+    // Account IRQ time and run softirqs on IRQ exit
+    helix_execution::irq::init();
+
     kernel_log!("Interrupts initialized");
 }
 
+/// Map the kernel image W^X (text read+execute, rodata read-only, data NX)
+/// and enable heap guard pages
+fn protect_kernel_image() {
+    use helix_hal::kernel_protect::{protect_kernel, KernelLayout};
+    use helix_memory::allocator::hardening::{self, GuardPages};
+
+    // SAFETY: the layout comes from this kernel's linker script
+    match unsafe { protect_kernel(KernelLayout::current()) } {
+        Ok(()) => {
+            kernel_log!("Kernel image protected (W^X)");
+
+            // Paging is under our control now: large heap blocks get guard pages
+            hardening::set_guard_pages(GuardPages::KERNEL);
+        }
+        Err(e) => {
+            kernel_log!(&alloc::format!("WARNING: kernel image left unprotected: {}", e));
+        }
+    }
+}
+
+/// Make the tables written during init read-only, then enter the
+/// `lockdown=` level
+///
+/// Multiboot2 does not pass the Secure Boot state; a loader that
+/// verified the kernel adds `lockdown=integrity` itself.
+fn lockdown_kernel(cmdline: &str) {
+    use helix_hal::lockdown::{self, LockdownLevel};
+
+    // SAFETY: initialization is complete; later updates go through
+    // helix_execution::stop_machine::patch_kernel
+    match unsafe { helix_hal::kernel_protect::lockdown() } {
+        Ok(()) => {
+            kernel_log!("Kernel locked down");
+        }
+        Err(e) => {
+            kernel_log!(&alloc::format!("WARNING: kernel lockdown failed: {}", e));
+        }
+    }
+
+    let value = cmdline.split_whitespace().filter_map(|arg| arg.strip_prefix("lockdown=")).next_back();
+    let requested = LockdownLevel::parse(value).unwrap_or_else(|| {
+        kernel_log!(&alloc::format!("WARNING: unknown lockdown level {:?}", value));
+        LockdownLevel::None
+    });
+    let level = lockdown::init(false, requested);
+    kernel_log!(&alloc::format!("Lockdown: {}", level));
+}
+
 /// Initialize scheduler
 fn init_scheduler() {
     kernel_log!("Initializing scheduler...");
@@ -462,6 +615,42 @@ fn init_filesystem() {
     kernel_log!("HelixFS initialized");
 }
 
+/// Module files loaded by the bootloader
+static BOOT_MODULES: helix_modules::loader::BootFiles = helix_modules::loader::BootFiles::new();
+
+/// Register the module file source
+///
+/// Multiboot2 modules whose command line is an absolute path (e.g.
+/// `/lib/modules/virtio-net.hxm`) load by that path. Packages are checked
+/// against the platform keyring and the kernel ABI fingerprint; once a key
+/// is enrolled, bare module binaries are refused.
+fn init_modules(multiboot2_info: *const u8) {
+    kernel_log!("Registering boot modules...");
+
+    for (path, data) in unsafe { multiboot2_modules(multiboot2_info) } {
+        if path.starts_with('/') {
+            BOOT_MODULES.add(path, data);
+        }
+    }
+    helix_modules::loader::set_file_source(&BOOT_MODULES);
+
+    kernel_log!("Boot modules registered");
+}
+
+/// Initialize the userspace subsystem
+///
+/// Must run before [`lockdown_kernel`]: the syscall table lives in
+/// `.data..ro_after_init`.
+fn init_userspace() {
+    kernel_log!("Initializing userspace...");
+
+    if helix_userspace::init().is_err() {
+        serial_write_str("  [Userspace] ❌ Failed to initialize userspace\n");
+    }
+
+    kernel_log!("Userspace initialized");
+}
+
 // =============================================================================
 // KERNEL RELOCATION DEMO
 // =============================================================================
@@ -1234,7 +1423,8 @@ macro_rules! kernel_log {
 /// Demonstrate the AI-powered system intelligence
 fn run_ai_demo() {
     // Note: Full Cortex initialization requires more memory and logging infrastructure.
-    // This demo simulates the AI behavior to demonstrate the concepts.
+    // This demo reports the live cortex if one is running and simulates the AI
+    // behavior to demonstrate the concepts.
 
     serial_write_str("\n");
     serial_write_str("╔══════════════════════════════════════════════════════════════════════╗\n");
@@ -1243,6 +1433,21 @@ fn run_ai_demo() {
     serial_write_str("╚══════════════════════════════════════════════════════════════════════╝\n");
     serial_write_str("\n");
 
+    match helix_ai::try_cortex() {
+        Ok(cortex) => {
+            let stats = cortex.statistics();
+            serial_write_str("[AI] Cortex active: ");
+            print_num(stats.events_processed);
+            serial_write_str(" events, ");
+            print_num(stats.decisions_made);
+            serial_write_str(" decisions\n\n");
+        },
+        Err(_) if helix_ai::status() == helix_ai::AiStatus::Degraded => {
+            serial_write_str("[AI] Cortex degraded, simulating\n\n");
+        },
+        Err(_) => serial_write_str("[AI] Cortex not running, simulating\n\n"),
+    }
+
     // =========================================================================
     // STEP 1: Show AI Architecture
     // =========================================================================
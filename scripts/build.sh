@@ -448,9 +448,50 @@ step_9_build_userland_framework() {
         log_info "No profiles found, skipping..."
     fi
 
+    build_initrd
+
     return 0
 }
 
+# Build the core utilities and pack them into a cpio (newc) initrd
+build_initrd() {
+    local coreutils_dir="${HELIX_ROOT}/userland/coreutils"
+    local staging_dir="${HELIX_BUILD_DIR}/initrd"
+    local log_file="${HELIX_LOGS_DIR}/coreutils.log"
+
+    if [[ ! -d "${coreutils_dir}" ]]; then
+        log_info "No userland utilities found, skipping initrd..."
+        return 0
+    fi
+
+    start_spinner "Building core utilities..."
+    if (cd "${coreutils_dir}" && cargo build --release) > "${log_file}" 2>&1; then
+        stop_spinner "success" "Core utilities built"
+    else
+        stop_spinner "warn" "Core utilities skipped"
+        log_warn "See ${log_file} for details"
+        return 0
+    fi
+
+    if ! cmd_exists cpio; then
+        log_warn "cpio not found, initrd not packed"
+        return 0
+    fi
+
+    print_action "Packing" "Initrd"
+    rm -rf "${staging_dir}"
+    mkdir -p "${staging_dir}/bin" "${staging_dir}/proc" "${staging_dir}/mnt" "${HELIX_OUTPUT_DIR}"
+
+    local release_dir="${coreutils_dir}/target/x86_64-unknown-none/release"
+    local util
+    for util in cat ls cp mv rm ps top mount umount vi less helixctl helix-mod; do
+        cp "${release_dir}/${util}" "${staging_dir}/bin/${util}"
+    done
+
+    (cd "${staging_dir}" && find . | cpio -o -H newc --quiet) > "${HELIX_OUTPUT_DIR}/initrd"
+    log_success "Initrd: ${HELIX_OUTPUT_DIR}/initrd"
+}
+
 step_10_test_all() {
     print_subheader "Running Tests"
 
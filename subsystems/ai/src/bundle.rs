@@ -0,0 +1,689 @@
+//! # Signed Model Bundles
+//!
+//! Models loaded from disk or from modules are only activated by the
+//! [`NeuralEngine`](crate::neural::NeuralEngine) if they come as a bundle
+//! signed by a platform key.
+//!
+//! ## Format
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//!   ┌──────────────┬───────────────────────────────────────────────────┐
+//!   │ Header       │ magic "HXMB", format u16, reserved u16            │
+//!   ├──────────────┼───────────────────────────────────────────────────┤
+//!   │ Metadata     │ model id u64, version u32, name (u16 len + UTF-8) │
+//!   │              │ input shape, output shape (u8 ndim + u32 dims)    │
+//!   │              │ layer count u16                                   │
+//!   ├──────────────┼───────────────────────────────────────────────────┤
+//!   │ Hash tree    │ chunk size u32, chunk count u32                   │
+//!   │              │ SHA-256 of each payload chunk                     │
+//!   ├──────────────┼───────────────────────────────────────────────────┤
+//!   │ Payload      │ length u32, then per layer: activation u8,        │
+//!   │              │ inputs u32, outputs u32, weights, bias (f32)      │
+//!   ├──────────────┼───────────────────────────────────────────────────┤
+//!   │ Signature    │ key id [u8; 32], length u16, signature bytes      │
+//!   └──────────────┴───────────────────────────────────────────────────┘
+//! ```
+//!
+//! Leaves are `SHA-256(0x00 || chunk)` and the root is
+//! `SHA-256(0x01 || leaves)`. The bundle digest, which is signed and
+//! measured, is `SHA-256(header || metadata || root)`, so it covers every
+//! byte of the model.
+//!
+//! ## Activation
+//!
+//! 1. The bundle is parsed and every chunk checked against the hash tree.
+//! 2. The signature over the digest is checked against the platform keys
+//!    ([`PlatformTrust::verify`]).
+//! 3. The version must not be older than the newest one activated for the
+//!    model id, unless downgrades were explicitly allowed.
+//! 4. The digest is measured into the TPM event log
+//!    ([`PlatformTrust::measure`]) before the model is used.
+
+use crate::neural::{Activation, DenseLayer, NeuralModel, Tensor, TensorShape};
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Bundle magic
+pub const BUNDLE_MAGIC: [u8; 4] = *b"HXMB";
+
+/// Bundle format version
+pub const BUNDLE_FORMAT: u16 = 1;
+
+/// Default payload chunk size for the hash tree
+pub const DEFAULT_CHUNK_SIZE: u32 = 4096;
+
+/// Maximum tensor rank in a bundle
+const MAX_NDIM: usize = 8;
+
+/// Platform key identifier (hash of the public key)
+pub type KeyId = [u8; 32];
+
+/// SHA-256 digest
+pub type Digest = [u8; 32];
+
+// =============================================================================
+// Platform Trust
+// =============================================================================
+
+/// Platform services for bundle verification
+///
+/// Implemented by the kernel on top of its key store and TPM driver.
+pub trait PlatformTrust {
+    /// Check `signature` over `digest` with platform key `key_id`
+    ///
+    /// Must return false for keys that are unknown or revoked.
+    fn verify(&self, key_id: &KeyId, digest: &Digest, signature: &[u8]) -> bool;
+
+    /// Extend `digest` into the TPM and append it to the event log
+    fn measure(&self, digest: &Digest, description: &str) -> bool;
+}
+
+// =============================================================================
+// Errors
+// =============================================================================
+
+/// Bundle rejection reasons
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+    /// Structurally invalid bundle
+    Malformed(&'static str),
+    /// Unknown bundle format
+    UnsupportedFormat(u16),
+    /// A payload chunk does not match its hash
+    HashMismatch {
+        /// Chunk index
+        chunk: u32,
+    },
+    /// No platform trust provider installed
+    NoTrustAnchor,
+    /// Signature invalid, or key not trusted
+    BadSignature,
+    /// Version older than the active one
+    Downgrade {
+        /// Newest version activated
+        current: u32,
+        /// Version offered
+        offered: u32,
+    },
+    /// The TPM measurement failed
+    MeasurementFailed,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(what) => write!(f, "malformed model bundle: {}", what),
+            Self::UnsupportedFormat(v) => write!(f, "unsupported bundle format {}", v),
+            Self::HashMismatch { chunk } => write!(f, "payload chunk {} fails its hash", chunk),
+            Self::NoTrustAnchor => write!(f, "no platform trust provider"),
+            Self::BadSignature => write!(f, "bundle signature not trusted"),
+            Self::Downgrade { current, offered } => {
+                write!(f, "refusing downgrade from version {} to {}", current, offered)
+            }
+            Self::MeasurementFailed => write!(f, "model measurement failed"),
+        }
+    }
+}
+
+// =============================================================================
+// Layers
+// =============================================================================
+
+/// A dense layer as stored in a bundle
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleLayer {
+    /// Activation function
+    pub activation: Activation,
+    /// Input width
+    pub inputs: u32,
+    /// Output width
+    pub outputs: u32,
+    /// Weights, `inputs × outputs`, row-major
+    pub weights: Vec<f32>,
+    /// Bias, `outputs`
+    pub bias: Vec<f32>,
+}
+
+fn activation_code(activation: Activation) -> u8 {
+    match activation {
+        Activation::None => 0,
+        Activation::ReLU => 1,
+        Activation::Sigmoid => 2,
+        Activation::Softmax => 3,
+    }
+}
+
+fn activation_from(code: u8) -> Option<Activation> {
+    Some(match code {
+        0 => Activation::None,
+        1 => Activation::ReLU,
+        2 => Activation::Sigmoid,
+        3 => Activation::Softmax,
+        _ => return None,
+    })
+}
+
+// =============================================================================
+// Parsed Bundle
+// =============================================================================
+
+/// A bundle whose structure and hash tree have been checked
+///
+/// Parsing does not check the signature; only
+/// [`NeuralEngine::activate_bundle`](crate::neural::NeuralEngine::activate_bundle)
+/// makes a model from a bundle usable.
+#[derive(Debug, Clone)]
+pub struct ModelBundle {
+    /// Model identifier
+    pub model_id: u64,
+    /// Model version (monotonic per model id)
+    pub version: u32,
+    /// Model name
+    pub name: String,
+    /// Input shape
+    pub input_shape: TensorShape,
+    /// Output shape
+    pub output_shape: TensorShape,
+    /// Layers in order
+    pub layers: Vec<BundleLayer>,
+    /// Signed digest
+    pub digest: Digest,
+    /// Signing key
+    pub key_id: KeyId,
+    /// Signature over `digest`
+    pub signature: Vec<u8>,
+}
+
+impl ModelBundle {
+    /// Parse a bundle, checking the payload against its hash tree
+    pub fn parse(bytes: &[u8]) -> Result<Self, BundleError> {
+        let mut r = Reader { bytes, pos: 0 };
+
+        if r.take(4)? != BUNDLE_MAGIC {
+            return Err(BundleError::Malformed("bad magic"));
+        }
+        let format = r.u16()?;
+        if format != BUNDLE_FORMAT {
+            return Err(BundleError::UnsupportedFormat(format));
+        }
+        r.u16()?;
+
+        let model_id = r.u64()?;
+        let version = r.u32()?;
+        let name_len = r.u16()? as usize;
+        let name = core::str::from_utf8(r.take(name_len)?)
+            .map_err(|_| BundleError::Malformed("name is not UTF-8"))?
+            .into();
+        let input_shape = r.shape()?;
+        let output_shape = r.shape()?;
+        let layer_count = r.u16()?;
+        let signed_len = r.pos;
+
+        let chunk_size = r.u32()?;
+        let chunk_count = r.u32()?;
+        if chunk_size == 0 {
+            return Err(BundleError::Malformed("zero chunk size"));
+        }
+        let leaves = r.take(
+            (chunk_count as usize)
+                .checked_mul(32)
+                .ok_or(BundleError::Malformed("chunk count"))?,
+        )?;
+        let payload_len = r.u32()? as usize;
+        let payload = r.take(payload_len)?;
+        if payload_len.div_ceil(chunk_size as usize) != chunk_count as usize {
+            return Err(BundleError::Malformed("chunk count does not cover payload"));
+        }
+        for (index, (chunk, leaf)) in
+            payload.chunks(chunk_size as usize).zip(leaves.chunks(32)).enumerate()
+        {
+            if sha256(&[&[0x00], chunk]) != leaf {
+                return Err(BundleError::HashMismatch { chunk: index as u32 });
+            }
+        }
+        let root = sha256(&[&[0x01], leaves]);
+        let digest = sha256(&[&bytes[..signed_len], &root]);
+
+        let key_id = r.array()?;
+        let sig_len = r.u16()? as usize;
+        let signature = r.take(sig_len)?.to_vec();
+        if r.pos != bytes.len() {
+            return Err(BundleError::Malformed("trailing bytes"));
+        }
+
+        let mut p = Reader { bytes: payload, pos: 0 };
+        let mut layers = Vec::with_capacity(layer_count as usize);
+        for _ in 0..layer_count {
+            let activation =
+                activation_from(p.u8()?).ok_or(BundleError::Malformed("unknown activation"))?;
+            let inputs = p.u32()?;
+            let outputs = p.u32()?;
+            let weights = (inputs as usize)
+                .checked_mul(outputs as usize)
+                .ok_or(BundleError::Malformed("layer size"))?;
+            layers.push(BundleLayer {
+                activation,
+                inputs,
+                outputs,
+                weights: p.f32s(weights)?,
+                bias: p.f32s(outputs as usize)?,
+            });
+        }
+        if p.pos != payload.len() {
+            return Err(BundleError::Malformed("trailing payload"));
+        }
+
+        let bundle = Self {
+            model_id,
+            version,
+            name,
+            input_shape,
+            output_shape,
+            layers,
+            digest,
+            key_id,
+            signature,
+        };
+        bundle.check_shapes()?;
+        Ok(bundle)
+    }
+
+    /// Check that consecutive layer widths chain from input to output
+    fn check_shapes(&self) -> Result<(), BundleError> {
+        let mut width = self.input_shape.size();
+        if self.layers.is_empty() {
+            return Err(BundleError::Malformed("no layers"));
+        }
+        for layer in &self.layers {
+            if layer.inputs as usize != width {
+                return Err(BundleError::Malformed("layer widths do not chain"));
+            }
+            width = layer.outputs as usize;
+        }
+        if width != self.output_shape.size() {
+            return Err(BundleError::Malformed("last layer does not match output shape"));
+        }
+        Ok(())
+    }
+
+    /// TPM event log description
+    pub fn measurement_description(&self) -> String {
+        alloc::format!("helix-model:{}:{}:v{}", self.model_id, self.name, self.version)
+    }
+
+    /// Build the model
+    pub fn into_model(self) -> NeuralModel {
+        let mut model =
+            NeuralModel::new(self.model_id, self.name, self.input_shape, self.output_shape);
+        for layer in self.layers {
+            let weights = Tensor::from_vec(
+                layer.weights,
+                TensorShape::matrix(layer.inputs as usize, layer.outputs as usize),
+            );
+            let bias = Tensor::from_vec(layer.bias, TensorShape::vector(layer.outputs as usize));
+            model.add_layer(Box::new(DenseLayer::new(weights, bias, layer.activation)));
+        }
+        model
+    }
+}
+
+/// Bounds-checked little-endian reader
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BundleError> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or(BundleError::Malformed("truncated"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], BundleError> {
+        let mut out = [0; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, BundleError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, BundleError> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, BundleError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, BundleError> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn f32s(&mut self, count: usize) -> Result<Vec<f32>, BundleError> {
+        let len = count.checked_mul(4).ok_or(BundleError::Malformed("layer size"))?;
+        let raw = self.take(len)?;
+        Ok(raw
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect())
+    }
+
+    fn shape(&mut self) -> Result<TensorShape, BundleError> {
+        let ndim = self.u8()? as usize;
+        if ndim > MAX_NDIM {
+            return Err(BundleError::Malformed("tensor rank"));
+        }
+        let dims = (0..ndim)
+            .map(|_| self.u32().map(|d| d as usize))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TensorShape::new(dims))
+    }
+}
+
+// =============================================================================
+// Writer
+// =============================================================================
+
+/// Builds a bundle (for signing tools and tests)
+///
+/// Call [`BundleWriter::digest`], sign it with a platform key, then
+/// [`BundleWriter::finish`] with the signature.
+pub struct BundleWriter {
+    signed: Vec<u8>,
+    payload: Vec<u8>,
+    layer_count: u16,
+    chunk_size: u32,
+}
+
+impl BundleWriter {
+    /// Start a bundle
+    pub fn new(
+        model_id: u64,
+        name: &str,
+        version: u32,
+        input_shape: &TensorShape,
+        output_shape: &TensorShape,
+    ) -> Self {
+        let mut signed = Vec::new();
+        signed.extend_from_slice(&BUNDLE_MAGIC);
+        signed.extend_from_slice(&BUNDLE_FORMAT.to_le_bytes());
+        signed.extend_from_slice(&0u16.to_le_bytes());
+        signed.extend_from_slice(&model_id.to_le_bytes());
+        signed.extend_from_slice(&version.to_le_bytes());
+        signed.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        signed.extend_from_slice(name.as_bytes());
+        for shape in [input_shape, output_shape] {
+            signed.push(shape.ndim() as u8);
+            for dim in &shape.dims {
+                signed.extend_from_slice(&(*dim as u32).to_le_bytes());
+            }
+        }
+        Self {
+            signed,
+            payload: Vec::new(),
+            layer_count: 0,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Use a different hash tree chunk size
+    pub fn chunk_size(mut self, chunk_size: u32) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Append a layer
+    pub fn layer(mut self, layer: &BundleLayer) -> Self {
+        self.payload.push(activation_code(layer.activation));
+        self.payload.extend_from_slice(&layer.inputs.to_le_bytes());
+        self.payload.extend_from_slice(&layer.outputs.to_le_bytes());
+        for value in layer.weights.iter().chain(&layer.bias) {
+            self.payload.extend_from_slice(&value.to_le_bytes());
+        }
+        self.layer_count += 1;
+        self
+    }
+
+    fn leaves(&self) -> Vec<u8> {
+        self.payload
+            .chunks(self.chunk_size as usize)
+            .flat_map(|chunk| sha256(&[&[0x00], chunk]))
+            .collect()
+    }
+
+    /// Digest to sign
+    pub fn digest(&self) -> Digest {
+        let root = sha256(&[&[0x01], &self.leaves()]);
+        sha256(&[&self.signed, &self.layer_count.to_le_bytes(), &root])
+    }
+
+    /// Serialize with the signature over [`BundleWriter::digest`]
+    pub fn finish(self, key_id: &KeyId, signature: &[u8]) -> Vec<u8> {
+        let leaves = self.leaves();
+        let mut out = self.signed;
+        out.extend_from_slice(&self.layer_count.to_le_bytes());
+        out.extend_from_slice(&self.chunk_size.to_le_bytes());
+        out.extend_from_slice(&((leaves.len() / 32) as u32).to_le_bytes());
+        out.extend_from_slice(&leaves);
+        out.extend_from_slice(&(self.payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.payload);
+        out.extend_from_slice(key_id);
+        out.extend_from_slice(&(signature.len() as u16).to_le_bytes());
+        out.extend_from_slice(signature);
+        out
+    }
+}
+
+// =============================================================================
+// SHA-256
+// =============================================================================
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+fn compress(state: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for (i, word) in block.chunks_exact(4).enumerate() {
+        w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+/// SHA-256 of the concatenation of `parts`
+pub fn sha256(parts: &[&[u8]]) -> Digest {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    let mut block = [0u8; 64];
+    let mut fill = 0;
+    let mut total = 0u64;
+
+    for part in parts {
+        total += part.len() as u64;
+        for &byte in *part {
+            block[fill] = byte;
+            fill += 1;
+            if fill == 64 {
+                compress(&mut state, &block);
+                fill = 0;
+            }
+        }
+    }
+
+    block[fill] = 0x80;
+    fill += 1;
+    if fill > 56 {
+        block[fill..].fill(0);
+        compress(&mut state, &block);
+        fill = 0;
+    }
+    block[fill..56].fill(0);
+    block[56..].copy_from_slice(&(total * 8).to_be_bytes());
+    compress(&mut state, &block);
+
+    let mut out = [0u8; 32];
+    for (chunk, word) in out.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neural::NeuralEngine;
+    use alloc::vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    const KEY: KeyId = [7; 32];
+
+    /// Test "signature": the digest XOR the key
+    fn sign(digest: &Digest) -> Vec<u8> {
+        digest.iter().zip(KEY).map(|(d, k)| d ^ k).collect()
+    }
+
+    static MEASURED: AtomicUsize = AtomicUsize::new(0);
+
+    struct TestTrust;
+
+    impl PlatformTrust for TestTrust {
+        fn verify(&self, key_id: &KeyId, digest: &Digest, signature: &[u8]) -> bool {
+            *key_id == KEY && signature == sign(digest).as_slice()
+        }
+
+        fn measure(&self, _digest: &Digest, description: &str) -> bool {
+            assert!(description.starts_with("helix-model:9:tiny:"));
+            MEASURED.fetch_add(1, Ordering::Relaxed);
+            true
+        }
+    }
+
+    fn bundle(version: u32) -> Vec<u8> {
+        let writer = BundleWriter::new(
+            9,
+            "tiny",
+            version,
+            &TensorShape::vector(2),
+            &TensorShape::vector(1),
+        )
+        .chunk_size(8)
+        .layer(&BundleLayer {
+            activation: Activation::None,
+            inputs: 2,
+            outputs: 1,
+            weights: vec![1.0, 2.0],
+            bias: vec![0.5],
+        });
+        let signature = sign(&writer.digest());
+        writer.finish(&KEY, &signature)
+    }
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            sha256(&[b"ab", b"c"]),
+            [
+                0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d,
+                0xae, 0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10,
+                0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let parsed = ModelBundle::parse(&bundle(1)).unwrap();
+        assert_eq!(parsed.model_id, 9);
+        assert_eq!(parsed.layers[0].weights, vec![1.0, 2.0]);
+
+        let model = parsed.into_model();
+        let out = model.forward(&Tensor::from_vec(vec![1.0, 1.0], TensorShape::vector(2)));
+        assert_eq!(out.data(), &[3.5]);
+    }
+
+    #[test]
+    fn test_bundle_tamper() {
+        let mut bytes = bundle(1);
+        let end = bytes.len() - 32 - 2 - 32 - 4;
+        bytes[end] ^= 1;
+        assert_eq!(ModelBundle::parse(&bytes).unwrap_err(), BundleError::HashMismatch { chunk: 2 });
+
+        // Rewriting the metadata changes the digest, so the signature fails
+        let mut bytes = bundle(1);
+        bytes[12] ^= 1;
+        let engine = NeuralEngine::new(false, false);
+        engine.set_platform_trust(Box::new(TestTrust));
+        assert_eq!(engine.activate_bundle(&bytes).unwrap_err(), BundleError::BadSignature);
+    }
+
+    #[test]
+    fn test_activate_bundle() {
+        let engine = NeuralEngine::new(false, false);
+        assert_eq!(engine.activate_bundle(&bundle(1)).unwrap_err(), BundleError::NoTrustAnchor);
+
+        engine.set_platform_trust(Box::new(TestTrust));
+        let before = MEASURED.load(Ordering::Relaxed);
+        assert_eq!(engine.activate_bundle(&bundle(2)), Ok(9));
+        assert!(MEASURED.load(Ordering::Relaxed) > before);
+        assert_eq!(engine.model_version(9), Some(2));
+        assert!(engine.infer(9, &Tensor::from_vec(vec![1.0, 1.0], TensorShape::vector(2))).is_some());
+
+        assert_eq!(
+            engine.activate_bundle(&bundle(1)).unwrap_err(),
+            BundleError::Downgrade { current: 2, offered: 1 }
+        );
+        engine.set_allow_downgrade(true);
+        assert_eq!(engine.activate_bundle(&bundle(1)), Ok(9));
+        assert_eq!(engine.model_version(9), Some(2));
+    }
+}
@@ -52,6 +52,7 @@
     resources::ResourceOracle,
     safety::SafetyChecker,
     security::SecurityOracle,
+    tenant::{TenantId, TenantManager},
 };
 
 use alloc::{
@@ -91,6 +92,12 @@ pub struct Cortex {
     /// AI subsystem components
     components: RwLock<Option<CortexComponents>>,
 
+    /// Tenant scoping, budgets and learning
+    tenants: TenantManager,
+
+    /// Why initialization failed, for a degraded stub
+    degraded: Option<String>,
+
     /// Statistics
     stats: CortexStats,
 }
@@ -140,6 +147,7 @@ struct QueuedEvent {
 #[derive(Debug, Clone)]
 pub struct DecisionRecord {
     pub decision: AiDecision,
+    pub scope: TenantId,
     pub executed: bool,
     pub outcome: Option<DecisionOutcome>,
     pub execution_time_us: u64,
@@ -202,6 +210,7 @@ impl Cortex {
 
     /// Create a new Cortex with the given configuration
     pub fn new(config: AiConfig) -> Self {
+        let tenants = TenantManager::new(config.continuous_learning_enabled);
         Self {
             config: RwLock::new(config),
             state: RwLock::new(AiState::Initializing),
@@ -210,10 +219,35 @@ pub fn new(config: AiConfig) -> Self {
             decision_history: Mutex::new(VecDeque::with_capacity(1000)),
             active_rollbacks: Mutex::new(Vec::new()),
             components: RwLock::new(None),
+            tenants,
+            degraded: None,
             stats: CortexStats::default(),
         }
     }
 
+    /// Create a no-op stub standing in for a Cortex that failed to initialize
+    ///
+    /// The stub stays in safe mode with no components: events are dropped,
+    /// processing yields no decisions and every execution is denied.
+    pub fn degraded(config: AiConfig, reason: String) -> Self {
+        let cortex = Self::new(config);
+        *cortex.state.write() = AiState::SafeMode;
+        Self {
+            degraded: Some(reason),
+            ..cortex
+        }
+    }
+
+    /// Is this a degraded stub?
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.is_some()
+    }
+
+    /// Why initialization failed, for a degraded stub
+    pub fn degraded_reason(&self) -> Option<&str> {
+        self.degraded.as_deref()
+    }
+
     /// Initialize the Cortex and all its components
     pub fn initialize(&self) -> AiResult<()> {
         let config = self.config.read();
@@ -257,8 +291,18 @@ pub fn state(&self) -> AiState {
         *self.state.read()
     }
 
+    /// Tenant scoping, budgets and learning
+    pub fn tenants(&self) -> &TenantManager {
+        &self.tenants
+    }
+
     /// Submit an event for processing
     pub fn submit_event(&self, event: AiEvent, priority: AiPriority) -> AiResult<()> {
+        // Nothing would ever consume it
+        if self.is_degraded() {
+            return Ok(());
+        }
+
         let state = *self.state.read();
         if state == AiState::Suspended {
             return Err(AiError::ActionDenied {
@@ -294,6 +338,10 @@ pub fn submit_event(&self, event: AiEvent, priority: AiPriority) -> AiResult<()>
 
     /// Process pending events and make decisions
     pub fn process(&self) -> AiResult<Vec<AiDecision>> {
+        if self.is_degraded() {
+            return Ok(Vec::new());
+        }
+
         let mut state = self.state.write();
         if *state == AiState::Suspended {
             return Ok(Vec::new());
@@ -326,14 +374,29 @@ pub fn process(&self) -> AiResult<Vec<AiDecision>> {
         // Filter by confidence threshold
         let config = self.config.read();
         let threshold = config.min_confidence_threshold;
-        decisions.retain(|d| d.confidence.meets_threshold(threshold));
+        decisions.retain(|(d, _)| d.confidence.meets_threshold(threshold));
 
         // Safety check all decisions
         decisions = self.safety_filter(decisions);
 
+        // Charge tenant budgets
+        let now = self.get_timestamp();
+        decisions.retain(|(decision, scope)| {
+            if self.tenants.charge(*scope, now) {
+                true
+            } else {
+                log::warn!(
+                    "Decision {:?} dropped: budget of tenant {} spent",
+                    decision.id,
+                    scope.0
+                );
+                false
+            }
+        });
+
         // Record decisions
-        for decision in &decisions {
-            self.record_decision(decision.clone());
+        for (decision, scope) in &decisions {
+            self.record_decision(decision.clone(), *scope);
         }
 
         let elapsed = self.get_timestamp() - start_time;
@@ -346,17 +409,20 @@ pub fn process(&self) -> AiResult<Vec<AiDecision>> {
 
         *self.state.write() = AiState::Idle;
 
-        Ok(decisions)
+        Ok(decisions.into_iter().map(|(decision, _)| decision).collect())
     }
 
     /// Process a single event
-    fn process_event(&self, queued: QueuedEvent) -> AiResult<Option<AiDecision>> {
+    ///
+    /// The decision is scoped to the tenant whose processes raised the event.
+    fn process_event(&self, queued: QueuedEvent) -> AiResult<Option<(AiDecision, TenantId)>> {
         let components = self.components.read();
         let components = components
             .as_ref()
             .ok_or(AiError::NotInitialized)?;
 
         let context = self.build_context(&queued.event);
+        let scope = self.tenants.scope_of(&queued.event);
 
         // Collect recommendations from all engines
         let mut recommendations: Vec<(AiAction, Confidence, String)> = Vec::new();
@@ -408,14 +474,42 @@ fn process_event(&self, queued: QueuedEvent) -> AiResult<Option<AiDecision>> {
         }
 
         // Fuse recommendations into final decision
+        let support = Self::agreement(&recommendations);
         let decision = self.fuse_recommendations(recommendations, queued.priority, context)?;
 
+        // Actions reaching beyond the tenant need elevated consensus
+        if let Err(reason) = self.tenants.check_scope(scope, &decision.action, support) {
+            return Err(AiError::ActionDenied {
+                action: format!("{:?}", decision.action),
+                reason,
+            });
+        }
+
         // Record event for learning (using current timestamp approximation)
-        components
-            .learning_engine
-            .record_event(&queued.event, 0); // TODO: Get actual timestamp
+        // TODO: Get actual timestamp
+        if scope.is_host() {
+            components.learning_engine.record_event(&queued.event, 0);
+        } else {
+            self.tenants
+                .with_learning(scope, |engine| engine.record_event(&queued.event, 0));
+        }
+
+        Ok(Some((decision, scope)))
+    }
 
-        Ok(Some(decision))
+    /// Number of recommendations agreeing with the most confident one
+    fn agreement(recommendations: &[(AiAction, Confidence, String)]) -> usize {
+        let leading = recommendations
+            .iter()
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(core::cmp::Ordering::Equal));
+        let Some((leading, _, _)) = leading else {
+            return 0;
+        };
+        let kind = core::mem::discriminant(leading);
+        recommendations
+            .iter()
+            .filter(|(action, _, _)| core::mem::discriminant(action) == kind)
+            .count()
     }
 
     /// Fuse multiple recommendations into a single decision
@@ -572,7 +666,9 @@ fn generate_rollback(&self, action: &AiAction) -> Option<RollbackStrategy> {
     }
 
     /// Generate proactive decisions (not triggered by events)
-    fn generate_proactive_decisions(&self) -> AiResult<Vec<AiDecision>> {
+    ///
+    /// Predictions from a tenant's learned patterns are scoped to it.
+    fn generate_proactive_decisions(&self) -> AiResult<Vec<(AiDecision, TenantId)>> {
         let components = self.components.read();
         let components = components.as_ref().ok_or(AiError::NotInitialized)?;
 
@@ -581,35 +677,53 @@ fn generate_proactive_decisions(&self) -> AiResult<Vec<AiDecision>> {
 
         // Optimizer proactive suggestions
         if let Ok(Some(decision)) = components.optimizer.proactive_check(&context) {
-            decisions.push(decision);
+            decisions.push((decision, TenantId::HOST));
         }
 
         // Security Oracle proactive scan
         if let Ok(Some(decision)) = components.security_oracle.proactive_check(&context) {
-            decisions.push(decision);
+            decisions.push((decision, TenantId::HOST));
         }
 
         // Resource Oracle proactive allocation
         if let Ok(Some(decision)) = components.resource_oracle.proactive_check(&context) {
-            decisions.push(decision);
+            decisions.push((decision, TenantId::HOST));
         }
 
-        // Learning Engine pattern predictions
+        // Learning Engine pattern predictions, host then each tenant
         let state_vector = crate::learning::StateVector::from_context(&context);
-        let predicted = components.learning_engine.predict_upcoming(&state_vector);
+        let mut predicted: Vec<(AiAction, Confidence, TenantId)> = components
+            .learning_engine
+            .predict_upcoming(&state_vector)
+            .into_iter()
+            .map(|(action, confidence)| (action, confidence, TenantId::HOST))
+            .collect();
+        for tenant in self.tenants.learning_tenants() {
+            let tenant_predicted =
+                self.tenants.with_learning(tenant, |engine| engine.predict_upcoming(&state_vector));
+            predicted.extend(
+                tenant_predicted
+                    .into_iter()
+                    .filter(|(action, _)| self.tenants.check_scope(tenant, action, 1).is_ok())
+                    .map(|(action, confidence)| (action, confidence, tenant)),
+            );
+        }
         // Convert predictions to decisions
-        for (action, confidence) in predicted {
-            decisions.push(AiDecision {
-                id: DecisionId::new(),
-                timestamp: 0,
-                action,
-                confidence,
-                priority: AiPriority::Low,
-                reasoning: vec!["Predicted from learned patterns".to_string()],
-                expected_outcome: "Pattern-based optimization".to_string(),
-                rollback: None,
-                context: context.clone(),
-            });
+        for (action, confidence, scope) in predicted {
+            decisions.push((
+                AiDecision {
+                    id: DecisionId::new(),
+                    timestamp: 0,
+                    action,
+                    confidence,
+                    priority: AiPriority::Low,
+                    reasoning: vec!["Predicted from learned patterns".to_string()],
+                    expected_outcome: "Pattern-based optimization".to_string(),
+                    rollback: None,
+                    context: context.clone(),
+                },
+                scope,
+            ));
         }
 
         Ok(decisions)
@@ -678,7 +792,7 @@ fn get_active_constraints(&self) -> Vec<String> {
     }
 
     /// Filter decisions through safety checker
-    fn safety_filter(&self, decisions: Vec<AiDecision>) -> Vec<AiDecision> {
+    fn safety_filter(&self, decisions: Vec<(AiDecision, TenantId)>) -> Vec<(AiDecision, TenantId)> {
         let components = self.components.read();
         if components.is_none() {
             return Vec::new();
@@ -687,7 +801,7 @@ fn safety_filter(&self, decisions: Vec<AiDecision>) -> Vec<AiDecision> {
 
         decisions
             .into_iter()
-            .filter(|decision| {
+            .filter(|(decision, _)| {
                 let check_result = components.safety_checker.check(decision);
                 if check_result.allowed {
                     true
@@ -714,7 +828,7 @@ fn drain_events(&self) -> Vec<QueuedEvent> {
     }
 
     /// Record a decision in history
-    fn record_decision(&self, decision: AiDecision) {
+    fn record_decision(&self, decision: AiDecision, scope: TenantId) {
         let mut history = self.decision_history.lock();
 
         // Trim if needed
@@ -724,6 +838,7 @@ fn record_decision(&self, decision: AiDecision) {
 
         history.push_back(DecisionRecord {
             decision,
+            scope,
             executed: false,
             outcome: None,
             execution_time_us: 0,
@@ -732,6 +847,13 @@ fn record_decision(&self, decision: AiDecision) {
 
     /// Execute a decision
     pub fn execute(&self, decision: &AiDecision) -> AiResult<DecisionOutcome> {
+        if self.is_degraded() {
+            return Err(AiError::ActionDenied {
+                action: "execute".to_string(),
+                reason: "AI is degraded".to_string(),
+            });
+        }
+
         *self.state.write() = AiState::Acting;
 
         let start_time = self.get_timestamp();
@@ -766,7 +888,14 @@ pub fn execute(&self, decision: &AiDecision) -> AiResult<DecisionOutcome> {
         if let Some(ref components) = *self.components.read() {
             let success = matches!(outcome, DecisionOutcome::Success | DecisionOutcome::PartialSuccess { .. });
             let impact = crate::learning::ImpactMetrics::default();
-            components.learning_engine.record_outcome(decision, success, impact, None);
+            let scope = self.decision_scope(decision.id);
+            if scope.is_host() {
+                components.learning_engine.record_outcome(decision, success, impact, None);
+            } else {
+                self.tenants.with_learning(scope, |engine| {
+                    engine.record_outcome(decision, success, impact, None)
+                });
+            }
         }
 
         *self.state.write() = AiState::Idle;
@@ -1000,6 +1129,16 @@ fn update_decision_record(
         }
     }
 
+    /// Tenant a recorded decision is scoped to
+    fn decision_scope(&self, decision_id: DecisionId) -> TenantId {
+        let history = self.decision_history.lock();
+        history
+            .iter()
+            .rev()
+            .find(|record| record.decision.id == decision_id)
+            .map_or(TenantId::HOST, |record| record.scope)
+    }
+
     /// Get current timestamp (microseconds)
     fn get_timestamp(&self) -> u64 {
         // In real implementation: read hardware timer
@@ -1008,6 +1147,9 @@ fn get_timestamp(&self) -> u64 {
 
     /// Suspend AI operations
     pub fn suspend(&self) {
+        if self.is_degraded() {
+            return;
+        }
         *self.state.write() = AiState::Suspended;
         log::info!("Cortex suspended");
     }
@@ -11,6 +11,15 @@
 //! - **Transfer Learning**: Apply knowledge across domains
 //! - **Active Learning**: Identify informative samples
 //!
+//! ## Checkpointing
+//!
+//! Online learning can drift. A share of recorded experiences is held out
+//! of training; at every checkpoint interval the policy is scored on them
+//! and compared with the last checkpoint (or a pinned known-good one). If
+//! decision quality dropped by more than the configured margin, the
+//! policy, patterns and reward statistics are rolled back to that
+//! checkpoint; otherwise a new checkpoint is taken.
+//!
 //! ## Architecture
 //!
 //! ```text
@@ -395,6 +404,39 @@ pub fn best_action(&self, state: &StateVector, available_actions: &[u32]) -> u32
             .unwrap_or(0)
     }
 
+    /// Score the policy on recorded experiences
+    ///
+    /// A decision agrees when the policy would repeat a rewarded action
+    /// or avoid a penalized one.
+    pub fn evaluate<'a>(
+        &self,
+        experiences: impl Iterator<Item = &'a Experience>,
+        available_actions: &[u32],
+    ) -> DecisionQuality {
+        let mut samples = 0;
+        let mut agreed = 0;
+        let mut signed = 0;
+        for exp in experiences {
+            let action = exp.action.action_type;
+            let best = self.best_action(&exp.state, available_actions);
+            let rewarded = exp.reward > 0.0;
+            if (best == action) == rewarded {
+                agreed += 1;
+            }
+            if (self.get_q(&exp.state, action) > 0.0) == rewarded {
+                signed += 1;
+            }
+            samples += 1;
+        }
+
+        let ratio = |n: usize| if samples > 0 { n as f32 / samples as f32 } else { 0.0 };
+        DecisionQuality {
+            agreement: ratio(agreed),
+            value_sign_accuracy: ratio(signed),
+            samples,
+        }
+    }
+
     /// Set learning parameters
     pub fn set_params(&mut self, alpha: f32, gamma: f32, epsilon: f32) {
         self.alpha = alpha.clamp(0.0, 1.0);
@@ -471,6 +513,109 @@ pub fn clear(&mut self) {
     }
 }
 
+// =============================================================================
+// Checkpointing
+// =============================================================================
+
+/// Checkpoint identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CheckpointId(pub u64);
+
+/// Decision quality measured on held-out experiences
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DecisionQuality {
+    /// Fraction of decisions the policy agrees with (0.0-1.0)
+    pub agreement: f32,
+    /// Fraction of Q-values with the sign of the observed reward (0.0-1.0)
+    pub value_sign_accuracy: f32,
+    /// Held-out experiences scored
+    pub samples: usize,
+}
+
+impl DecisionQuality {
+    /// Check if quality dropped by more than `margin` from `baseline`
+    pub fn degraded_from(&self, baseline: &DecisionQuality, margin: f32) -> bool {
+        self.agreement < baseline.agreement - margin ||
+            self.value_sign_accuracy < baseline.value_sign_accuracy - margin
+    }
+}
+
+/// Summary of a stored checkpoint
+#[derive(Debug, Clone)]
+pub struct CheckpointInfo {
+    /// Checkpoint ID
+    pub id: CheckpointId,
+    /// Training iteration it was taken at
+    pub iteration: u64,
+    /// Quality when taken
+    pub quality: DecisionQuality,
+    /// Patterns saved
+    pub patterns: usize,
+    /// Pinned as known-good
+    pub pinned: bool,
+}
+
+/// Result of a validation pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointOutcome {
+    /// Too few held-out experiences to judge quality
+    Skipped,
+    /// Quality held; a new checkpoint was taken
+    Checkpointed(CheckpointId),
+    /// Quality degraded; rolled back to this checkpoint
+    RolledBack(CheckpointId),
+}
+
+/// Saved learning state
+#[derive(Debug, Clone)]
+struct Checkpoint {
+    id: CheckpointId,
+    iteration: u64,
+    quality: DecisionQuality,
+    policy: QPolicy,
+    patterns: Vec<Pattern>,
+    action_rewards: BTreeMap<u32, RewardStats>,
+}
+
+/// Stored checkpoints, oldest first
+#[derive(Debug, Default)]
+struct CheckpointStore {
+    checkpoints: VecDeque<Checkpoint>,
+    pinned: Option<CheckpointId>,
+    next_id: u64,
+}
+
+impl CheckpointStore {
+    /// Checkpoint automatic rollbacks return to: the pinned one, else the
+    /// latest
+    fn baseline(&self) -> Option<&Checkpoint> {
+        match self.pinned {
+            Some(id) => self.get(id),
+            None => self.checkpoints.back(),
+        }
+    }
+
+    fn get(&self, id: CheckpointId) -> Option<&Checkpoint> {
+        self.checkpoints.iter().find(|c| c.id == id)
+    }
+
+    /// Store a checkpoint, evicting the oldest unpinned beyond `max`
+    fn push(&mut self, mut checkpoint: Checkpoint, max: usize) -> CheckpointId {
+        self.next_id += 1;
+        checkpoint.id = CheckpointId(self.next_id);
+        self.checkpoints.push_back(checkpoint);
+
+        while self.checkpoints.len() > max.max(1) {
+            let pinned = self.pinned;
+            match self.checkpoints.iter().position(|c| Some(c.id) != pinned) {
+                Some(oldest) => self.checkpoints.remove(oldest),
+                None => break,
+            };
+        }
+        CheckpointId(self.next_id)
+    }
+}
+
 // =============================================================================
 // Learning Engine
 // =============================================================================
@@ -483,6 +628,12 @@ pub struct LearningEngine {
     /// Experience buffer
     experience_buffer: Mutex<ExperienceBuffer>,
 
+    /// Experiences held out of training, for validation
+    holdout: Mutex<ExperienceBuffer>,
+
+    /// Checkpoints of the learned state
+    checkpoints: Mutex<CheckpointStore>,
+
     /// Q-Learning policy
     q_policy: RwLock<QPolicy>,
 
@@ -537,6 +688,16 @@ pub struct LearningConfig {
     pub batch_size: usize,
     /// Pattern mining threshold
     pub pattern_threshold: u64,
+    /// Training iterations between validation checkpoints (0 = manual only)
+    pub checkpoint_interval: u64,
+    /// Checkpoints kept (the pinned one is never evicted)
+    pub max_checkpoints: usize,
+    /// Hold out one in this many experiences for validation (0 = none)
+    pub holdout_every: u64,
+    /// Minimum held-out experiences before quality is judged
+    pub min_holdout: usize,
+    /// Quality drop (absolute, 0.0-1.0) that triggers a rollback
+    pub max_quality_drop: f32,
 }
 
 impl Default for LearningConfig {
@@ -548,6 +709,11 @@ fn default() -> Self {
             min_experiences: 100,
             batch_size: 32,
             pattern_threshold: 5,
+            checkpoint_interval: 10,
+            max_checkpoints: 8,
+            holdout_every: 10,
+            min_holdout: 20,
+            max_quality_drop: 0.15,
         }
     }
 }
@@ -560,6 +726,8 @@ struct LearningStats {
     successful_predictions: AtomicU64,
     failed_predictions: AtomicU64,
     policy_updates: AtomicU64,
+    checkpoints_taken: AtomicU64,
+    rollbacks: AtomicU64,
 }
 
 impl Default for LearningStats {
@@ -571,6 +739,8 @@ fn default() -> Self {
             successful_predictions: AtomicU64::new(0),
             failed_predictions: AtomicU64::new(0),
             policy_updates: AtomicU64::new(0),
+            checkpoints_taken: AtomicU64::new(0),
+            rollbacks: AtomicU64::new(0),
         }
     }
 }
@@ -582,11 +752,16 @@ impl LearningEngine {
     /// Maximum event sequence length
     const MAX_EVENTS: usize = 1000;
 
+    /// Maximum held-out experiences
+    const MAX_HOLDOUT: usize = 1000;
+
     /// Create a new Learning Engine
     pub fn new(enabled: bool) -> Self {
         Self {
             enabled,
             experience_buffer: Mutex::new(ExperienceBuffer::new(Self::MAX_EXPERIENCES)),
+            holdout: Mutex::new(ExperienceBuffer::new(Self::MAX_HOLDOUT)),
+            checkpoints: Mutex::new(CheckpointStore::default()),
             q_policy: RwLock::new(QPolicy::new()),
             patterns: RwLock::new(Vec::new()),
             pattern_counter: AtomicU64::new(1),
@@ -616,9 +791,14 @@ pub fn record_experience(&self, experience: Experience) {
             }
         }
 
-        // Add to buffer
-        self.experience_buffer.lock().add(experience);
-        self.stats.experiences_recorded.fetch_add(1, Ordering::Relaxed);
+        // Add to buffer, holding out a share for validation
+        let recorded = self.stats.experiences_recorded.fetch_add(1, Ordering::Relaxed) + 1;
+        let holdout_every = self.config.read().holdout_every;
+        if holdout_every != 0 && recorded % holdout_every == 0 {
+            self.holdout.lock().add(experience);
+        } else {
+            self.experience_buffer.lock().add(experience);
+        }
     }
 
     /// Record a decision outcome
@@ -773,39 +953,159 @@ pub fn train(&self) -> Result<(), ()> {
             return Ok(());
         }
 
-        let config = self.config.read();
-        let buffer = self.experience_buffer.lock();
+        let config = self.config.read().clone();
+        {
+            let buffer = self.experience_buffer.lock();
 
-        if buffer.len() < config.min_experiences {
-            return Ok(());
-        }
+            if buffer.len() < config.min_experiences {
+                return Ok(());
+            }
 
-        // Sample batch
-        let batch = buffer.sample(config.batch_size);
+            // Sample batch
+            let batch = buffer.sample(config.batch_size);
 
-        // Update policy
-        let mut policy = self.q_policy.write();
-        let available_actions: Vec<u32> = (0..10).collect();
+            // Update policy
+            let mut policy = self.q_policy.write();
+            let available_actions: Vec<u32> = (0..10).collect();
 
-        for exp in batch {
-            // Create a "next state" - in reality would come from the next experience
-            let next_state = exp.state.clone();
-
-            policy.update(
-                &exp.state,
-                exp.action.action_type,
-                exp.reward,
-                &next_state,
-                &available_actions,
-            );
+            for exp in batch {
+                // Create a "next state" - in reality would come from the next experience
+                let next_state = exp.state.clone();
+
+                policy.update(
+                    &exp.state,
+                    exp.action.action_type,
+                    exp.reward,
+                    &next_state,
+                    &available_actions,
+                );
+            }
         }
 
-        self.stats.training_iterations.fetch_add(1, Ordering::Relaxed);
+        let iteration = self.stats.training_iterations.fetch_add(1, Ordering::Relaxed) + 1;
         self.stats.policy_updates.fetch_add(1, Ordering::Relaxed);
 
+        if config.checkpoint_interval != 0 && iteration % config.checkpoint_interval == 0 {
+            self.validate();
+        }
+
         Ok(())
     }
 
+    /// Score the current policy on held-out experiences, then checkpoint
+    /// it or roll back
+    ///
+    /// Quality is compared with the baseline checkpoint (the pinned one,
+    /// else the latest), both scored on the same held-out set. Called by
+    /// [`LearningEngine::train`] every `checkpoint_interval` iterations.
+    pub fn validate(&self) -> CheckpointOutcome {
+        let config = self.config.read().clone();
+        let available_actions: Vec<u32> = (0..10).collect();
+        let holdout = self.holdout.lock();
+        if holdout.len() < config.min_holdout.max(1) {
+            return CheckpointOutcome::Skipped;
+        }
+
+        let current = self.q_policy.read().evaluate(holdout.all(), &available_actions);
+        let mut store = self.checkpoints.lock();
+        if let Some(baseline) = store.baseline() {
+            let reference = baseline.policy.evaluate(holdout.all(), &available_actions);
+            if current.degraded_from(&reference, config.max_quality_drop) {
+                log::warn!(
+                    "[HELIX-AI] Learning quality degraded (agreement {:.2} -> {:.2}), \
+                     rolling back to checkpoint {}",
+                    reference.agreement,
+                    current.agreement,
+                    baseline.id.0
+                );
+                self.restore(baseline);
+                self.stats.rollbacks.fetch_add(1, Ordering::Relaxed);
+                return CheckpointOutcome::RolledBack(baseline.id);
+            }
+        }
+        drop(holdout);
+
+        let id = store.push(self.snapshot(current), config.max_checkpoints);
+        self.stats.checkpoints_taken.fetch_add(1, Ordering::Relaxed);
+        CheckpointOutcome::Checkpointed(id)
+    }
+
+    /// Copy the learned state
+    fn snapshot(&self, quality: DecisionQuality) -> Checkpoint {
+        Checkpoint {
+            id: CheckpointId(0),
+            iteration: self.stats.training_iterations.load(Ordering::Relaxed),
+            quality,
+            policy: self.q_policy.read().clone(),
+            patterns: self.patterns.read().clone(),
+            action_rewards: self.action_rewards.read().clone(),
+        }
+    }
+
+    /// Replace the learned state with a checkpoint
+    fn restore(&self, checkpoint: &Checkpoint) {
+        *self.q_policy.write() = checkpoint.policy.clone();
+        *self.patterns.write() = checkpoint.patterns.clone();
+        *self.action_rewards.write() = checkpoint.action_rewards.clone();
+    }
+
+    /// Take a checkpoint now, without validation
+    pub fn checkpoint(&self) -> CheckpointId {
+        let config = self.config.read().clone();
+        let available_actions: Vec<u32> = (0..10).collect();
+        let holdout = self.holdout.lock();
+        let quality = self.q_policy.read().evaluate(holdout.all(), &available_actions);
+        drop(holdout);
+        let snapshot = self.snapshot(quality);
+        self.stats.checkpoints_taken.fetch_add(1, Ordering::Relaxed);
+        self.checkpoints.lock().push(snapshot, config.max_checkpoints)
+    }
+
+    /// List stored checkpoints, oldest first
+    pub fn checkpoints(&self) -> Vec<CheckpointInfo> {
+        let store = self.checkpoints.lock();
+        store
+            .checkpoints
+            .iter()
+            .map(|c| CheckpointInfo {
+                id: c.id,
+                iteration: c.iteration,
+                quality: c.quality,
+                patterns: c.patterns.len(),
+                pinned: store.pinned == Some(c.id),
+            })
+            .collect()
+    }
+
+    /// Pin a known-good checkpoint
+    ///
+    /// Automatic rollbacks return to it, and it is never evicted, until
+    /// unpinned. Returns false if no such checkpoint is stored.
+    pub fn pin_checkpoint(&self, id: CheckpointId) -> bool {
+        let mut store = self.checkpoints.lock();
+        if store.get(id).is_none() {
+            return false;
+        }
+        store.pinned = Some(id);
+        true
+    }
+
+    /// Unpin the pinned checkpoint
+    pub fn unpin_checkpoint(&self) {
+        self.checkpoints.lock().pinned = None;
+    }
+
+    /// Roll back to a stored checkpoint
+    pub fn rollback_to(&self, id: CheckpointId) -> bool {
+        let store = self.checkpoints.lock();
+        let Some(checkpoint) = store.get(id) else {
+            return false;
+        };
+        self.restore(checkpoint);
+        self.stats.rollbacks.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
     /// Mine patterns from event sequences
     pub fn mine_patterns(&self) -> Vec<Pattern> {
         if !self.enabled {
@@ -1021,8 +1321,11 @@ pub fn set_enabled(&self, enabled: bool) {
     }
 
     /// Clear all learned data
+    ///
+    /// Checkpoints are kept, so the cleared state can be rolled back.
     pub fn clear(&self) {
         self.experience_buffer.lock().clear();
+        self.holdout.lock().clear();
         self.patterns.write().clear();
         self.event_sequences.lock().clear();
         self.action_rewards.write().clear();
@@ -1049,6 +1352,9 @@ pub fn statistics(&self) -> LearningStatistics {
             policy_updates: self.stats.policy_updates.load(Ordering::Relaxed),
             experience_buffer_size: self.experience_buffer.lock().len(),
             known_patterns: self.patterns.read().len(),
+            holdout_size: self.holdout.lock().len(),
+            checkpoints_taken: self.stats.checkpoints_taken.load(Ordering::Relaxed),
+            rollbacks: self.stats.rollbacks.load(Ordering::Relaxed),
         }
     }
 }
@@ -1066,6 +1372,9 @@ pub struct LearningStatistics {
     pub policy_updates: u64,
     pub experience_buffer_size: usize,
     pub known_patterns: usize,
+    pub holdout_size: usize,
+    pub checkpoints_taken: u64,
+    pub rollbacks: u64,
 }
 
 // =============================================================================
@@ -1159,6 +1468,84 @@ fn test_state_vector_distance() {
         assert!((dist - 1.0).abs() < 0.001);
     }
 
+    fn rewarded(action_type: u32, reward: f32) -> Experience {
+        Experience {
+            id: ExperienceId::new(),
+            state: StateVector::new(vec![0.5], vec!["x".to_string()]),
+            action: ActionVector {
+                action_type,
+                parameters: Vec::new(),
+            },
+            outcome: Outcome {
+                success: reward > 0.0,
+                impact: ImpactMetrics::default(),
+                user_feedback: None,
+                time_to_effect_us: 0,
+            },
+            reward,
+            timestamp: 0,
+            decision_id: None,
+        }
+    }
+
+    /// Engine with a held-out set preferring action 1, trained to match
+    fn checkpointed_engine() -> LearningEngine {
+        let engine = LearningEngine::new(true);
+        engine.configure(LearningConfig {
+            holdout_every: 2,
+            min_holdout: 4,
+            ..LearningConfig::default()
+        });
+        for _ in 0..8 {
+            engine.record_experience(rewarded(1, 1.0));
+        }
+        assert_eq!(engine.statistics().holdout_size, 4);
+
+        let state = rewarded(1, 1.0).state;
+        engine.q_policy.write().update(&state, 1, 1.0, &state, &[0, 1, 2]);
+        engine
+    }
+
+    /// Make the policy penalize action 1
+    fn drift(engine: &LearningEngine) {
+        let state = rewarded(1, 1.0).state;
+        for _ in 0..5 {
+            engine.q_policy.write().update(&state, 1, -10.0, &state, &[0, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_rollback() {
+        let engine = checkpointed_engine();
+        let CheckpointOutcome::Checkpointed(good) = engine.validate() else {
+            panic!("expected a checkpoint");
+        };
+
+        drift(&engine);
+        assert_eq!(engine.validate(), CheckpointOutcome::RolledBack(good));
+
+        let state = rewarded(1, 1.0).state;
+        assert!(engine.q_policy.read().get_q(&state, 1) > 0.0);
+        assert_eq!(engine.statistics().rollbacks, 1);
+    }
+
+    #[test]
+    fn test_pinned_checkpoint() {
+        let engine = checkpointed_engine();
+        let pinned = engine.checkpoint();
+        assert!(engine.pin_checkpoint(pinned));
+
+        // Later checkpoints do not evict or replace the pinned baseline
+        for _ in 0..10 {
+            engine.checkpoint();
+        }
+        assert!(engine.checkpoints().iter().any(|c| c.id == pinned && c.pinned));
+
+        drift(&engine);
+        assert_eq!(engine.validate(), CheckpointOutcome::RolledBack(pinned));
+        assert!(!engine.pin_checkpoint(CheckpointId(999)));
+    }
+
     #[test]
     fn test_reward_computation() {
         let engine = LearningEngine::new(true);
@@ -197,6 +197,9 @@ pub fn ceil_f32(x: f32) -> f32 {
 /// Neural network inference engine
 pub mod neural;
 
+/// Signed model bundles
+pub mod bundle;
+
 /// Self-optimization subsystem
 pub mod optimizer;
 
@@ -221,6 +224,12 @@ pub fn ceil_f32(x: f32) -> f32 {
 /// Safety constraints and invariants
 pub mod safety;
 
+/// Per-tenant scoping of AI decisions
+pub mod tenant;
+
+/// Availability, degradation reporting and `/proc` flag
+pub mod status;
+
 /// Test suite
 #[cfg(test)]
 mod tests;
@@ -240,17 +249,27 @@ pub fn ceil_f32(x: f32) -> f32 {
 
 pub use neural::{NeuralEngine, NeuralModel, Tensor, TensorShape};
 
-pub use optimizer::{OptimizationHint, Optimizer, PerformanceProfile, WorkloadAnalysis};
+pub use bundle::{BundleError, ModelBundle, PlatformTrust};
+
+pub use optimizer::{
+    OptimizationHint, Optimizer, PerformanceProfile, ProcessUsageSample, SchedLatencyStats,
+    WorkloadAnalysis,
+};
 
 pub use healer::{BugSignature, Healer, HealingAction, HotPatch};
 
-pub use security::{SecurityOracle, Threat, ThreatLevel, ThreatPrediction, ThreatType};
+pub use security::{
+    CfiViolationSample, SecurityOracle, Threat, ThreatLevel, ThreatPrediction, ThreatType,
+};
 
 pub use resources::{
-    ComputeDevice, DeviceType, ResourceAllocation, ResourceOracle, WorkloadProfile,
+    ComputeDevice, CoreKind, CpuEnergyModel, DeviceType, IrqLoadSample, IrqPlacement,
+    LlcPartition, LlcSample, ResourceAllocation, ResourceOracle, WorkloadProfile,
 };
 
-pub use learning::{Experience, LearningEngine, Pattern, PatternType};
+pub use learning::{
+    CheckpointId, CheckpointOutcome, Experience, LearningEngine, Pattern, PatternType,
+};
 
 pub use memory::{AiMemory, MemoryEntry, MemoryId, MemoryUsage};
 
@@ -258,6 +277,10 @@ pub fn ceil_f32(x: f32) -> f32 {
 
 pub use safety::{Invariant, RiskAssessment, SafetyChecker, SafetyCheckResult, SafetyConstraint};
 
+pub use tenant::{TenantBudget, TenantId, TenantManager, TenantResolver};
+
+pub use status::{AiHealthEvent, AiStatus, HealthSink};
+
 // =============================================================================
 // Global AI Instance
 // =============================================================================
@@ -275,22 +298,39 @@ pub fn ceil_f32(x: f32) -> f32 {
 /// This must be called early in kernel initialization, after memory
 /// management is available but before user-space processes start.
 ///
+/// If the cortex fails to initialize, a degraded stub is installed in its
+/// place, a health event is raised and `Ok` is still returned: the AI is
+/// optional and must not fail the boot. Check [`status`] for the outcome.
+///
 /// # Panics
 ///
 /// Panics if called more than once.
 pub fn init(config: AiConfig) -> AiResult<()> {
     log::info!("[HELIX-AI] Initializing kernel AI subsystem...");
 
-    let cortex = Cortex::new(config);
-
-    HELIX_AI.call_once(|| cortex);
-
-    log::info!("[HELIX-AI] AI subsystem initialized successfully");
+    let cortex = Cortex::new(config.clone());
+    match cortex.initialize() {
+        Ok(()) => {
+            HELIX_AI.call_once(|| cortex);
+            log::info!("[HELIX-AI] AI subsystem initialized successfully");
+        }
+        Err(e) => {
+            let reason = alloc::format!("initialization failed: {:?}", e);
+            HELIX_AI.call_once(|| Cortex::degraded(config, reason.clone()));
+            status::report(AiHealthEvent {
+                status: AiStatus::Degraded,
+                reason,
+            });
+        }
+    }
     Ok(())
 }
 
 /// Get a reference to the global AI cortex
 ///
+/// In degraded mode this is the no-op stub. Prefer [`try_cortex`] where
+/// the caller can do without AI decisions.
+///
 /// # Panics
 ///
 /// Panics if the AI subsystem has not been initialized.
@@ -300,9 +340,29 @@ pub fn cortex() -> &'static Cortex {
         .expect("Helix AI not initialized. Call helix_ai::init() first.")
 }
 
-/// Check if the AI subsystem is initialized
+/// Get the global Cortex if it is fully initialized
+///
+/// Fails with [`AiError::NotInitialized`] before [`init`] and when the
+/// cortex runs as the degraded stub; check [`status`] to tell them apart.
+pub fn try_cortex() -> AiResult<&'static Cortex> {
+    HELIX_AI
+        .get()
+        .filter(|cortex| !cortex.is_degraded())
+        .ok_or(AiError::NotInitialized)
+}
+
+/// Availability of the AI subsystem
+pub fn status() -> AiStatus {
+    match HELIX_AI.get() {
+        None => AiStatus::Uninitialized,
+        Some(cortex) if cortex.is_degraded() => AiStatus::Degraded,
+        Some(_) => AiStatus::Active,
+    }
+}
+
+/// Check if the AI subsystem is initialized and not degraded
 pub fn is_initialized() -> bool {
-    HELIX_AI.get().is_some()
+    status().is_available()
 }
 
 // =============================================================================
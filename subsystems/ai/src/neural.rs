@@ -40,16 +40,18 @@
 //!                     └─────────────────────────────────────┘
 //! ```
 
+use crate::bundle::{BundleError, ModelBundle, PlatformTrust};
 use crate::core::{AiAction, AiEvent, Confidence, DecisionContext};
 
 use alloc::{
     boxed::Box,
+    collections::BTreeMap,
     format,
     string::{String, ToString},
     vec,
     vec::Vec,
 };
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use spin::RwLock;
 
 // =============================================================================
@@ -672,6 +674,15 @@ pub struct NeuralEngine {
     /// Pattern matchers
     pattern_matchers: RwLock<Vec<PatternMatcher>>,
 
+    /// Bundle signature and measurement provider
+    trust: RwLock<Option<Box<dyn PlatformTrust + Send + Sync>>>,
+
+    /// Newest bundle version activated per model
+    model_versions: RwLock<BTreeMap<u64, u32>>,
+
+    /// Accept bundles older than the active version
+    allow_downgrade: AtomicBool,
+
     /// Statistics
     stats: NeuralStats,
 }
@@ -682,6 +693,8 @@ struct NeuralStats {
     total_inference_time_us: AtomicU64,
     gpu_inferences: AtomicU64,
     npu_inferences: AtomicU64,
+    bundles_activated: AtomicU64,
+    bundles_rejected: AtomicU64,
 }
 
 impl Default for NeuralStats {
@@ -692,6 +705,8 @@ fn default() -> Self {
             total_inference_time_us: AtomicU64::new(0),
             gpu_inferences: AtomicU64::new(0),
             npu_inferences: AtomicU64::new(0),
+            bundles_activated: AtomicU64::new(0),
+            bundles_rejected: AtomicU64::new(0),
         }
     }
 }
@@ -705,15 +720,99 @@ pub fn new(gpu_enabled: bool, npu_enabled: bool) -> Self {
             models: RwLock::new(Vec::new()),
             decision_trees: RwLock::new(Vec::new()),
             pattern_matchers: RwLock::new(Vec::new()),
+            trust: RwLock::new(None),
+            model_versions: RwLock::new(BTreeMap::new()),
+            allow_downgrade: AtomicBool::new(false),
             stats: NeuralStats::default(),
         }
     }
 
     /// Register a neural model
+    ///
+    /// For built-in models only; models from disk or modules go through
+    /// [`NeuralEngine::activate_bundle`].
     pub fn register_model(&self, model: NeuralModel) {
         self.models.write().push(model);
     }
 
+    /// Install the platform key and TPM provider for bundle activation
+    pub fn set_platform_trust(&self, trust: Box<dyn PlatformTrust + Send + Sync>) {
+        *self.trust.write() = Some(trust);
+    }
+
+    /// Allow activating bundles older than the active version
+    pub fn set_allow_downgrade(&self, allow: bool) {
+        self.allow_downgrade.store(allow, Ordering::Relaxed);
+    }
+
+    /// Raise the minimum version accepted for a model
+    ///
+    /// Used to carry rollback protection across boots from persistent
+    /// storage.
+    pub fn set_version_floor(&self, model_id: u64, version: u32) {
+        let mut versions = self.model_versions.write();
+        let floor = versions.entry(model_id).or_insert(0);
+        *floor = (*floor).max(version);
+    }
+
+    /// Newest bundle version activated for a model
+    pub fn model_version(&self, model_id: u64) -> Option<u32> {
+        self.model_versions.read().get(&model_id).copied()
+    }
+
+    /// Verify a signed model bundle and activate its model
+    ///
+    /// Replaces any model with the same id. Returns the model id.
+    pub fn activate_bundle(&self, bytes: &[u8]) -> Result<u64, BundleError> {
+        let result = self.verify_bundle(bytes).map(|bundle| {
+            let id = bundle.model_id;
+            let model = bundle.into_model();
+            let mut models = self.models.write();
+            models.retain(|m| m.id != id);
+            models.push(model);
+            id
+        });
+
+        match &result {
+            Ok(_) => self.stats.bundles_activated.fetch_add(1, Ordering::Relaxed),
+            Err(e) => {
+                log::warn!("[HELIX-AI] Rejected model bundle: {}", e);
+                self.stats.bundles_rejected.fetch_add(1, Ordering::Relaxed)
+            }
+        };
+        result
+    }
+
+    /// Check integrity, signature and version, then measure the bundle
+    fn verify_bundle(&self, bytes: &[u8]) -> Result<ModelBundle, BundleError> {
+        let bundle = ModelBundle::parse(bytes)?;
+
+        let trust = self.trust.read();
+        let trust = trust.as_ref().ok_or(BundleError::NoTrustAnchor)?;
+        if !trust.verify(&bundle.key_id, &bundle.digest, &bundle.signature) {
+            return Err(BundleError::BadSignature);
+        }
+
+        let mut versions = self.model_versions.write();
+        let current = versions.get(&bundle.model_id).copied().unwrap_or(0);
+        if bundle.version < current && !self.allow_downgrade.load(Ordering::Relaxed) {
+            return Err(BundleError::Downgrade { current, offered: bundle.version });
+        }
+
+        if !trust.measure(&bundle.digest, &bundle.measurement_description()) {
+            return Err(BundleError::MeasurementFailed);
+        }
+        versions.insert(bundle.model_id, current.max(bundle.version));
+
+        log::info!(
+            "[HELIX-AI] Activated model {} '{}' v{}",
+            bundle.model_id,
+            bundle.name,
+            bundle.version
+        );
+        Ok(bundle)
+    }
+
     /// Register a decision tree
     pub fn register_tree(&self, tree: DecisionTree) {
         self.decision_trees.write().push(tree);
@@ -881,6 +980,8 @@ pub fn statistics(&self) -> NeuralEngineStatistics {
             total_inference_time_us: self.stats.total_inference_time_us.load(Ordering::Relaxed),
             gpu_inferences: self.stats.gpu_inferences.load(Ordering::Relaxed),
             npu_inferences: self.stats.npu_inferences.load(Ordering::Relaxed),
+            bundles_activated: self.stats.bundles_activated.load(Ordering::Relaxed),
+            bundles_rejected: self.stats.bundles_rejected.load(Ordering::Relaxed),
         }
     }
 }
@@ -898,6 +999,8 @@ pub struct NeuralEngineStatistics {
     pub total_inference_time_us: u64,
     pub gpu_inferences: u64,
     pub npu_inferences: u64,
+    pub bundles_activated: u64,
+    pub bundles_rejected: u64,
 }
 
 // =============================================================================
@@ -54,7 +54,7 @@
 };
 
 use alloc::{
-    collections::VecDeque,
+    collections::{BTreeMap, VecDeque},
     format,
     string::{String, ToString},
     vec,
@@ -309,6 +309,50 @@ pub enum IoPattern {
     Mixed,
 }
 
+// =============================================================================
+// Scheduler Latency
+// =============================================================================
+
+/// Scheduler tracing summary (from `/proc/sched_stats`)
+#[derive(Debug, Clone, Default)]
+pub struct SchedLatencyStats {
+    /// Wakeups measured
+    pub wakeups: u64,
+    /// 99th percentile wakeup-to-run latency (ns)
+    pub wakeup_p99_ns: u64,
+    /// Worst wakeup-to-run latency (ns)
+    pub wakeup_max_ns: u64,
+    /// Mean runqueue depth, in hundredths
+    pub runqueue_avg_centi: u64,
+    /// Migrations
+    pub migrations: u64,
+}
+
+// =============================================================================
+// Process Resource Usage
+// =============================================================================
+
+/// Cumulative resource usage of one process (from `getrusage` / `wait4`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessUsageSample {
+    /// Process ID
+    pub pid: u64,
+    /// When the sample was taken (µs)
+    pub timestamp_us: u64,
+    /// User plus system time (ns)
+    pub cpu_ns: u64,
+    /// Peak resident set size (KiB)
+    pub maxrss_kb: u64,
+    /// Page faults that required I/O
+    pub major_faults: u64,
+    /// Blocks read and written
+    pub block_io: u64,
+    /// Voluntary context switches
+    pub voluntary_switches: u64,
+    /// Involuntary context switches
+    pub involuntary_switches: u64,
+}
+
 // =============================================================================
 // Optimization Hints
 // =============================================================================
@@ -351,6 +395,9 @@ pub struct Optimizer {
     /// Applied optimizations history
     optimization_history: Mutex<VecDeque<AppliedOptimization>>,
 
+    /// Resource usage samples per process, oldest first
+    process_history: Mutex<BTreeMap<u64, VecDeque<ProcessUsageSample>>>,
+
     /// Statistics
     stats: OptimizerStats,
 }
@@ -416,6 +463,7 @@ pub fn new(enabled: bool) -> Self {
             profiles: RwLock::new(Self::builtin_profiles()),
             metrics_history: Mutex::new(VecDeque::with_capacity(Self::MAX_HISTORY)),
             optimization_history: Mutex::new(VecDeque::with_capacity(Self::MAX_HISTORY)),
+            process_history: Mutex::new(BTreeMap::new()),
             stats: OptimizerStats::default(),
         }
     }
@@ -753,6 +801,59 @@ fn handle_resource_spike(
         )))
     }
 
+    /// Minimum wakeups before latency statistics are trusted
+    const MIN_LATENCY_SAMPLES: u64 = 100;
+
+    /// Analyze scheduler tracing against the current profile
+    ///
+    /// The profile's wakeup granularity is the p99 wakeup latency target.
+    /// When it is missed with threads queueing, shorter slices are
+    /// recommended.
+    pub fn analyze_sched_latency(
+        &self,
+        stats: &SchedLatencyStats,
+    ) -> Option<(AiAction, Confidence, String)> {
+        if !self.enabled || stats.wakeups < Self::MIN_LATENCY_SAMPLES {
+            return None;
+        }
+
+        let params = self.current_profile.read().scheduler.clone();
+        if stats.wakeup_p99_ns <= params.wakeup_granularity_ns {
+            return None;
+        }
+        if stats.runqueue_avg_centi < 100 {
+            // Runqueues are short: the latency is not caused by time slices
+            return None;
+        }
+
+        let granularity_ns = (params.sched_granularity_ns / 2).max(params.min_granularity_ns);
+        if granularity_ns >= params.sched_granularity_ns {
+            return None;
+        }
+
+        // More confident the further the target is missed
+        let confidence = if stats.wakeup_p99_ns > params.wakeup_granularity_ns * 2 {
+            0.8
+        } else {
+            0.6
+        };
+
+        Some((
+            AiAction::TuneScheduler {
+                granularity_ns,
+                preemption: true,
+            },
+            Confidence::new(confidence),
+            format!(
+                "p99 wakeup latency {}ns exceeds {}ns target (runqueue depth {}.{:02})",
+                stats.wakeup_p99_ns,
+                params.wakeup_granularity_ns,
+                stats.runqueue_avg_centi / 100,
+                stats.runqueue_avg_centi % 100
+            ),
+        ))
+    }
+
     /// Proactive optimization check
     pub fn proactive_check(&self, context: &DecisionContext) -> AiResult<Option<AiDecision>> {
         if !self.enabled {
@@ -823,10 +924,103 @@ fn classify_workload(&self, context: &DecisionContext) -> WorkloadCategory {
         } else if metrics.context_switch_rate > 10000 {
             WorkloadCategory::Interactive
         } else {
-            WorkloadCategory::Interactive // Default
+            // Ambiguous system-wide: go by the busiest process
+            self.dominant_process_workload()
+                .unwrap_or(WorkloadCategory::Interactive)
+        }
+    }
+
+    /// Samples kept per process
+    const MAX_PROCESS_SAMPLES: usize = 64;
+
+    /// Processes tracked at once
+    const MAX_TRACKED_PROCESSES: usize = 256;
+
+    /// Record a process's cumulative resource usage
+    ///
+    /// When too many processes are tracked, the one sampled least recently
+    /// is dropped.
+    pub fn record_process_usage(&self, sample: ProcessUsageSample) {
+        let mut history = self.process_history.lock();
+        if !history.contains_key(&sample.pid) && history.len() >= Self::MAX_TRACKED_PROCESSES {
+            let stalest = history
+                .iter()
+                .min_by_key(|(_, samples)| samples.back().map_or(0, |s| s.timestamp_us))
+                .map(|(pid, _)| *pid);
+            if let Some(pid) = stalest {
+                history.remove(&pid);
+            }
+        }
+
+        let samples = history.entry(sample.pid).or_default();
+        if samples.len() >= Self::MAX_PROCESS_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// Stop tracking a reaped process
+    pub fn forget_process(&self, pid: u64) {
+        self.process_history.lock().remove(&pid);
+    }
+
+    /// Usage of a process over its recorded history: (elapsed µs, change)
+    fn process_delta(samples: &VecDeque<ProcessUsageSample>) -> Option<(u64, ProcessUsageSample)> {
+        let (first, last) = (samples.front()?, samples.back()?);
+        let elapsed_us = last.timestamp_us.checked_sub(first.timestamp_us)?;
+        if elapsed_us == 0 {
+            return None;
+        }
+        Some((elapsed_us, ProcessUsageSample {
+            pid: last.pid,
+            timestamp_us: last.timestamp_us,
+            cpu_ns: last.cpu_ns.saturating_sub(first.cpu_ns),
+            maxrss_kb: last.maxrss_kb,
+            major_faults: last.major_faults.saturating_sub(first.major_faults),
+            block_io: last.block_io.saturating_sub(first.block_io),
+            voluntary_switches: last.voluntary_switches.saturating_sub(first.voluntary_switches),
+            involuntary_switches: last
+                .involuntary_switches
+                .saturating_sub(first.involuntary_switches),
+        }))
+    }
+
+    /// Classify a process from its resource history
+    ///
+    /// Needs two samples. CPU share, block I/O and major faults per second,
+    /// and voluntary switches per second (waits on input or locks) decide
+    /// between idle, I/O-bound, CPU-bound and interactive.
+    pub fn classify_process(&self, pid: u64) -> Option<WorkloadCategory> {
+        let history = self.process_history.lock();
+        let (elapsed_us, delta) = Self::process_delta(history.get(&pid)?)?;
+        Some(Self::classify_delta(elapsed_us, &delta))
+    }
+
+    fn classify_delta(elapsed_us: u64, delta: &ProcessUsageSample) -> WorkloadCategory {
+        let per_sec = |count: u64| count.saturating_mul(1_000_000) / elapsed_us;
+        let cpu_percent = delta.cpu_ns / 10 / elapsed_us;
+
+        if per_sec(delta.block_io) > 100 || per_sec(delta.major_faults) > 10 {
+            WorkloadCategory::IoIntensive
+        } else if cpu_percent > 80 && per_sec(delta.voluntary_switches) < 100 {
+            WorkloadCategory::Computation
+        } else if cpu_percent < 5 && per_sec(delta.voluntary_switches) < 10 {
+            WorkloadCategory::Idle
+        } else {
+            WorkloadCategory::Interactive
         }
     }
 
+    /// Workload of the process that used the most CPU over its history
+    fn dominant_process_workload(&self) -> Option<WorkloadCategory> {
+        let history = self.process_history.lock();
+        history
+            .values()
+            .filter_map(Self::process_delta)
+            .max_by_key(|(_, delta)| delta.cpu_ns)
+            .map(|(elapsed_us, delta)| Self::classify_delta(elapsed_us, &delta))
+    }
+
     /// Find a profile for the given workload
     fn find_profile_for_workload(&self, workload: WorkloadCategory) -> Option<PerformanceProfile> {
         let profiles = self.profiles.read();
@@ -1069,6 +1263,49 @@ fn test_workload_classification() {
         assert_eq!(optimizer.classify_workload(&context), WorkloadCategory::Computation);
     }
 
+    #[test]
+    fn test_process_classification() {
+        let optimizer = Optimizer::new(true);
+        let sample = |pid, secs: u64, cpu_ms: u64, block_io, voluntary| ProcessUsageSample {
+            pid,
+            timestamp_us: secs * 1_000_000,
+            cpu_ns: cpu_ms * 1_000_000,
+            block_io,
+            voluntary_switches: voluntary,
+            ..Default::default()
+        };
+
+        // Compiler: 1.8s of CPU in 2s
+        optimizer.record_process_usage(sample(10, 0, 0, 0, 0));
+        assert_eq!(optimizer.classify_process(10), None);
+        optimizer.record_process_usage(sample(10, 2, 1800, 20, 50));
+        assert_eq!(optimizer.classify_process(10), Some(WorkloadCategory::Computation));
+
+        // Backup: 1000 blocks/s
+        optimizer.record_process_usage(sample(11, 0, 0, 0, 0));
+        optimizer.record_process_usage(sample(11, 2, 300, 2000, 900));
+        assert_eq!(optimizer.classify_process(11), Some(WorkloadCategory::IoIntensive));
+
+        // Editor waiting on keystrokes
+        optimizer.record_process_usage(sample(12, 0, 0, 0, 0));
+        optimizer.record_process_usage(sample(12, 2, 200, 0, 400));
+        assert_eq!(optimizer.classify_process(12), Some(WorkloadCategory::Interactive));
+
+        // Ambiguous system metrics defer to the busiest process
+        let context = DecisionContext {
+            system_metrics: SystemMetrics {
+                cpu_usage_percent: 50,
+                io_wait_percent: 10,
+                context_switch_rate: 2000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(optimizer.classify_workload(&context), WorkloadCategory::Computation);
+        optimizer.forget_process(10);
+        assert_eq!(optimizer.classify_workload(&context), WorkloadCategory::IoIntensive);
+    }
+
     #[test]
     fn test_custom_profile() {
         let optimizer = Optimizer::new(true);
@@ -11,6 +11,10 @@
 //! - **Memory Management**: Intelligent memory allocation across devices
 //! - **Predictive Allocation**: Pre-allocate resources for anticipated needs
 //! - **Thermal Management**: Prevent overheating through load balancing
+//! - **Energy-Aware Placement**: Steer threads between efficient and
+//!   performance cores on hybrid CPUs
+//! - **LLC Partitioning**: Split last-level cache ways between
+//!   latency-critical and batch work (Intel CAT)
 //!
 //! ## Architecture
 //!
@@ -317,6 +321,177 @@ pub struct AllocatedDevice {
     pub priority: u8,
 }
 
+// =============================================================================
+// IRQ Placement
+// =============================================================================
+
+/// Measured load of a device IRQ (from the kernel IRQ balancer)
+#[derive(Debug, Clone, Copy)]
+pub struct IrqLoadSample {
+    /// IRQ number
+    pub irq: u32,
+    /// CPU it is routed to
+    pub cpu: usize,
+    /// CPUs it may be routed to
+    pub allowed: u64,
+    /// Interrupts per second
+    pub rate: u64,
+    /// Placement currently fixed by the oracle
+    pub overridden: bool,
+}
+
+/// Placement override for an IRQ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IrqPlacement {
+    /// IRQ number
+    pub irq: u32,
+    /// CPU to fix the IRQ on (`None` = hand back to the balancer)
+    pub cpu: Option<usize>,
+}
+
+// =============================================================================
+// CPU Energy Model
+// =============================================================================
+
+/// Kind of core on a hybrid CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreKind {
+    /// Performance core
+    Performance,
+    /// Efficiency core
+    Efficiency,
+}
+
+/// Where a CPU energy model came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnergyModelSource {
+    /// ACPI CPPC performance levels
+    Cppc,
+    /// CPUID hybrid core type
+    Cpuid,
+    /// Static platform table
+    Static,
+}
+
+/// Operating point of a CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerfState {
+    /// Capacity (1024 = fastest core at its highest state)
+    pub capacity: u32,
+    /// Power at this state (mW)
+    pub power_mw: u32,
+}
+
+/// Energy model of one CPU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuEnergyModel {
+    /// CPU number
+    pub cpu: usize,
+    /// Core kind
+    pub kind: CoreKind,
+    /// Operating points, slowest first
+    pub states: Vec<PerfState>,
+    /// Source of the model
+    pub source: EnergyModelSource,
+}
+
+impl CpuEnergyModel {
+    /// Cores below this fraction of the fastest core's capacity are
+    /// efficiency cores (percent)
+    const EFFICIENCY_CAPACITY_PERCENT: u64 = 80;
+
+    /// Capacity of an efficiency core when only its type is known
+    const EFFICIENCY_CORE_CAPACITY: u32 = 600;
+
+    /// CPUID leaf 0x1A core types
+    const CPUID_CORE_TYPE_ATOM: u8 = 0x20;
+
+    /// Build from ACPI CPPC performance levels
+    ///
+    /// `system_highest` is the highest performance of the fastest CPU.
+    /// CPPC reports no power, so it is estimated from `max_power_mw`
+    /// assuming power grows with the cube of performance.
+    pub fn from_cppc(
+        cpu: usize,
+        lowest_perf: u32,
+        highest_perf: u32,
+        system_highest: u32,
+        max_power_mw: u32,
+    ) -> Self {
+        let capacity = |perf: u32| (perf as u64 * 1024 / system_highest.max(1) as u64) as u32;
+        let power = |perf: u32| {
+            let ratio = perf as u64 * 1000 / highest_perf.max(1) as u64;
+            (max_power_mw as u64 * ratio * ratio * ratio / 1_000_000_000) as u32
+        };
+        let efficient = (highest_perf as u64) * 100
+            < (system_highest as u64) * Self::EFFICIENCY_CAPACITY_PERCENT;
+        Self {
+            cpu,
+            kind: if efficient { CoreKind::Efficiency } else { CoreKind::Performance },
+            states: vec![
+                PerfState { capacity: capacity(lowest_perf), power_mw: power(lowest_perf) },
+                PerfState { capacity: capacity(highest_perf), power_mw: max_power_mw },
+            ],
+            source: EnergyModelSource::Cppc,
+        }
+    }
+
+    /// Build from the CPUID leaf 0x1A core type (EAX[31:24])
+    ///
+    /// Only the core kind is known; capacity is estimated.
+    pub fn from_cpuid(cpu: usize, core_type: u8, max_power_mw: u32) -> Self {
+        let (kind, capacity) = if core_type == Self::CPUID_CORE_TYPE_ATOM {
+            (CoreKind::Efficiency, Self::EFFICIENCY_CORE_CAPACITY)
+        } else {
+            (CoreKind::Performance, 1024)
+        };
+        Self {
+            cpu,
+            kind,
+            states: vec![PerfState { capacity, power_mw: max_power_mw }],
+            source: EnergyModelSource::Cpuid,
+        }
+    }
+
+    /// Highest capacity
+    pub fn capacity(&self) -> u32 {
+        self.states.last().map_or(0, |s| s.capacity)
+    }
+
+    /// Power per unit of capacity at the highest state (mW per 1024)
+    pub fn cost(&self) -> u32 {
+        let top = self.states.last().copied().unwrap_or(PerfState { capacity: 0, power_mw: 0 });
+        (top.power_mw as u64 * 1024 / top.capacity.max(1) as u64) as u32
+    }
+}
+
+// =============================================================================
+// LLC Partitioning
+// =============================================================================
+
+/// Last-level cache behaviour over one sampling interval
+#[derive(Debug, Clone, Copy)]
+pub struct LlcSample {
+    /// Allocatable LLC ways (CAT capacity bitmask length)
+    pub ways: u8,
+    /// LLC misses per thousand instructions of latency-critical tasks
+    pub latency_mpki: u32,
+    /// Batch tasks ran during the interval
+    pub batch_active: bool,
+}
+
+/// Capacity bitmasks for the two workload classes
+///
+/// The masks are contiguous and do not overlap, so batch fills never
+/// evict latency-critical lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LlcPartition {
+    /// Ways of latency-critical tasks
+    pub latency_mask: u32,
+    /// Ways of batch tasks
+    pub batch_mask: u32,
+}
+
 // =============================================================================
 // Resource Oracle Engine
 // =============================================================================
@@ -347,6 +522,12 @@ pub struct ResourceOracle {
     /// Current power profile
     power_profile: RwLock<PowerProfile>,
 
+    /// Per-CPU energy models
+    energy_model: RwLock<Vec<CpuEnergyModel>>,
+
+    /// LLC ways reserved for latency-critical tasks (0 = not partitioned)
+    llc_latency_ways: RwLock<u8>,
+
     /// Statistics
     stats: ResourceStats,
 }
@@ -409,6 +590,8 @@ pub fn new(gpu_enabled: bool, npu_enabled: bool) -> Self {
             allocation_history: Mutex::new(VecDeque::with_capacity(Self::MAX_HISTORY)),
             power_budget_mw: RwLock::new(u32::MAX), // Unlimited by default
             power_profile: RwLock::new(PowerProfile::Balanced),
+            energy_model: RwLock::new(Vec::new()),
+            llc_latency_ways: RwLock::new(0),
             stats: ResourceStats::default(),
         }
     }
@@ -895,6 +1078,149 @@ pub fn set_power_profile(&self, profile: PowerProfile) {
         *self.power_profile.write() = profile;
     }
 
+    /// Interrupt rate one CPU absorbs when IRQs are consolidated
+    const IRQ_CPU_CAPACITY: u64 = 50_000;
+
+    /// Recommend IRQ placements overriding the kernel balancer
+    ///
+    /// The balancer spreads interrupts for throughput. Under the power
+    /// saver profile, the oracle instead packs them onto as few CPUs as
+    /// capacity allows so the others can stay in deep idle states. In
+    /// other profiles, previous overrides are released.
+    pub fn recommend_irq_placement(&self, loads: &[IrqLoadSample]) -> Vec<IrqPlacement> {
+        if !matches!(*self.power_profile.read(), PowerProfile::PowerSaver) {
+            return loads
+                .iter()
+                .filter(|l| l.overridden)
+                .map(|l| IrqPlacement { irq: l.irq, cpu: None })
+                .collect();
+        }
+
+        let mut sorted: Vec<&IrqLoadSample> = loads.iter().filter(|l| l.allowed != 0).collect();
+        sorted.sort_by(|a, b| b.rate.cmp(&a.rate));
+
+        // First-fit decreasing onto the lowest-numbered CPUs
+        let mut used = [0u64; 64];
+        let mut placements = Vec::new();
+        for load in sorted {
+            let allowed = |cpu: &usize| load.allowed & (1 << *cpu) != 0;
+            let cpu = (0..64)
+                .filter(allowed)
+                .find(|&cpu| used[cpu] + load.rate <= Self::IRQ_CPU_CAPACITY)
+                .or_else(|| (0..64).filter(allowed).min_by_key(|&cpu| used[cpu]));
+            let Some(cpu) = cpu else {
+                continue;
+            };
+            used[cpu] += load.rate;
+            if cpu != load.cpu || !load.overridden {
+                placements.push(IrqPlacement { irq: load.irq, cpu: Some(cpu) });
+            }
+        }
+
+        let moved = placements
+            .iter()
+            .filter(|p| loads.iter().any(|l| l.irq == p.irq && Some(l.cpu) != p.cpu))
+            .count();
+        self.stats.migrations_performed.fetch_add(moved as u64, Ordering::Relaxed);
+        placements
+    }
+
+    /// Install the per-CPU energy models
+    pub fn set_energy_model(&self, models: Vec<CpuEnergyModel>) {
+        let efficient = models.iter().filter(|m| m.kind == CoreKind::Efficiency).count();
+        log::info!(
+            "[HELIX-AI] Energy model: {} CPUs, {} efficiency cores",
+            models.len(),
+            efficient
+        );
+        *self.energy_model.write() = models;
+    }
+
+    /// Get the per-CPU energy models
+    pub fn energy_model(&self) -> Vec<CpuEnergyModel> {
+        self.energy_model.read().clone()
+    }
+
+    /// CPUs to place a thread on, out of the `allowed` mask
+    ///
+    /// On hybrid CPUs, `MinPower` work goes to the efficiency cores and
+    /// `MaxPerformance` work to the performance cores. `Balanced` and
+    /// `Deadline` work follows the power profile: efficiency cores under
+    /// power saver, performance cores under performance, anywhere
+    /// otherwise. Returns `allowed` when there is no preference.
+    pub fn placement_hint(&self, preference: EnergyPreference, allowed: u64) -> u64 {
+        let kind = match preference {
+            EnergyPreference::MinPower => CoreKind::Efficiency,
+            EnergyPreference::MaxPerformance => CoreKind::Performance,
+            EnergyPreference::Balanced | EnergyPreference::Deadline => {
+                match *self.power_profile.read() {
+                    PowerProfile::PowerSaver => CoreKind::Efficiency,
+                    PowerProfile::Performance => CoreKind::Performance,
+                    _ => return allowed,
+                }
+            }
+        };
+
+        let models = self.energy_model.read();
+        let mask_of = |kind: CoreKind| {
+            models
+                .iter()
+                .filter(|m| m.kind == kind && m.cpu < 64)
+                .fold(0u64, |mask, m| mask | (1 << m.cpu))
+                & allowed
+        };
+        let performance = mask_of(CoreKind::Performance);
+        let efficiency = mask_of(CoreKind::Efficiency);
+        if performance == 0 || efficiency == 0 {
+            // Not hybrid within the allowed CPUs
+            return allowed;
+        }
+
+        match kind {
+            CoreKind::Efficiency => efficiency,
+            CoreKind::Performance => performance,
+        }
+    }
+
+    /// Latency-critical miss rate above which the partition grows (MPKI)
+    const LLC_GROW_MPKI: u32 = 5;
+
+    /// Latency-critical miss rate below which the partition shrinks (MPKI)
+    const LLC_SHRINK_MPKI: u32 = 1;
+
+    /// Recommend an LLC partition between latency-critical and batch tasks
+    ///
+    /// Without batch tasks the cache stays shared (`None`). Otherwise
+    /// latency-critical tasks start with half the ways, gain a way while
+    /// their miss rate stays high and give one back while it stays low;
+    /// batch tasks always keep at least one way. The kernel programs the
+    /// masks into two classes of service.
+    pub fn recommend_llc_partition(&self, sample: &LlcSample) -> Option<LlcPartition> {
+        let mut latency_ways = self.llc_latency_ways.write();
+        if !sample.batch_active || sample.ways < 2 {
+            *latency_ways = 0;
+            return None;
+        }
+
+        let ways = sample.ways.min(32);
+        *latency_ways = if *latency_ways == 0 {
+            ways / 2
+        } else if sample.latency_mpki > Self::LLC_GROW_MPKI {
+            (*latency_ways + 1).min(ways - 1)
+        } else if sample.latency_mpki < Self::LLC_SHRINK_MPKI {
+            (*latency_ways - 1).max(1)
+        } else {
+            (*latency_ways).min(ways - 1)
+        };
+
+        let mask = |n: u8| if n >= 32 { u32::MAX } else { (1u32 << n) - 1 };
+        let batch_ways = ways - *latency_ways;
+        Some(LlcPartition {
+            latency_mask: mask(*latency_ways) << batch_ways,
+            batch_mask: mask(batch_ways),
+        })
+    }
+
     /// Get all devices
     pub fn devices(&self) -> Vec<ComputeDevice> {
         self.devices.read().clone()
@@ -954,6 +1280,81 @@ pub struct ResourceOracleStatistics {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_irq_placement_power_saver() {
+        let oracle = ResourceOracle::new(false, false);
+        let load = |irq, cpu, rate| IrqLoadSample {
+            irq,
+            cpu,
+            allowed: 0b1111,
+            rate,
+            overridden: false,
+        };
+        let loads = [load(10, 1, 20_000), load(11, 2, 20_000), load(12, 3, 20_000)];
+
+        // Balanced: nothing to override
+        assert!(oracle.recommend_irq_placement(&loads).is_empty());
+
+        oracle.set_power_profile(PowerProfile::PowerSaver);
+        let placements = oracle.recommend_irq_placement(&loads);
+        assert_eq!(placements.len(), 3);
+        assert_eq!(placements.iter().filter(|p| p.cpu == Some(0)).count(), 2);
+        assert_eq!(placements.iter().filter(|p| p.cpu == Some(1)).count(), 1);
+    }
+
+    #[test]
+    fn test_energy_placement_hint() {
+        let oracle = ResourceOracle::new(false, false);
+
+        // Not hybrid yet: no preference
+        assert_eq!(oracle.placement_hint(EnergyPreference::MinPower, 0xFF), 0xFF);
+
+        // CPUs 0-3 performance, 4-7 efficiency
+        let models = (0..8)
+            .map(|cpu| {
+                let (highest, power) = if cpu < 4 { (100, 6000) } else { (60, 2000) };
+                CpuEnergyModel::from_cppc(cpu, 20, highest, 100, power)
+            })
+            .collect();
+        oracle.set_energy_model(models);
+        let e_core = &oracle.energy_model()[4];
+        assert_eq!(e_core.kind, CoreKind::Efficiency);
+        assert!(e_core.cost() < oracle.energy_model()[0].cost());
+
+        assert_eq!(oracle.placement_hint(EnergyPreference::MinPower, 0xFF), 0xF0);
+        assert_eq!(oracle.placement_hint(EnergyPreference::MaxPerformance, 0xFF), 0x0F);
+        assert_eq!(oracle.placement_hint(EnergyPreference::Balanced, 0xFF), 0xFF);
+
+        oracle.set_power_profile(PowerProfile::PowerSaver);
+        assert_eq!(oracle.placement_hint(EnergyPreference::Balanced, 0xFF), 0xF0);
+
+        // Affinity without a choice of core kind
+        assert_eq!(oracle.placement_hint(EnergyPreference::MinPower, 0x03), 0x03);
+    }
+
+    #[test]
+    fn test_llc_partition() {
+        let oracle = ResourceOracle::new(false, false);
+        let sample = |latency_mpki, batch_active| LlcSample { ways: 11, latency_mpki, batch_active };
+
+        assert_eq!(oracle.recommend_llc_partition(&sample(10, false)), None);
+
+        let first = oracle.recommend_llc_partition(&sample(3, true)).unwrap();
+        assert_eq!(first, LlcPartition { latency_mask: 0x7C0, batch_mask: 0x03F });
+        assert_eq!(first.latency_mask & first.batch_mask, 0);
+
+        // High miss rate grows the latency-critical share, never past ways - 1
+        for _ in 0..10 {
+            oracle.recommend_llc_partition(&sample(20, true));
+        }
+        let grown = oracle.recommend_llc_partition(&sample(20, true)).unwrap();
+        assert_eq!(grown, LlcPartition { latency_mask: 0x7FE, batch_mask: 0x001 });
+
+        let shrunk = oracle.recommend_llc_partition(&sample(0, true)).unwrap();
+        assert_eq!(shrunk.latency_mask.count_ones(), 9);
+        assert_eq!(shrunk.latency_mask | shrunk.batch_mask, 0x7FF);
+    }
+
     #[test]
     fn test_resource_oracle_creation() {
         let oracle = ResourceOracle::new(true, true);
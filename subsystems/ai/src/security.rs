@@ -105,6 +105,8 @@ pub enum ThreatType {
     BufferOverflow,
     /// Code injection attempt
     CodeInjection,
+    /// Hijacked return or indirect branch (ROP/JOP)
+    ControlFlowHijack,
     /// Rootkit behavior
     Rootkit,
     /// Cryptominer activity
@@ -493,6 +495,26 @@ pub enum AttackStage {
     Actions = 6,
 }
 
+// =============================================================================
+// Control-Flow Integrity
+// =============================================================================
+
+/// A control-flow violation trapped by the hardware (shadow stack, IBT,
+/// BTI or pointer authentication)
+#[derive(Debug, Clone)]
+pub struct CfiViolationSample {
+    /// Offending process, `None` for the kernel
+    pub pid: Option<u64>,
+    /// Faulting instruction
+    pub ip: u64,
+    /// A return was hijacked (as opposed to an indirect branch)
+    pub backward_edge: bool,
+    /// Violation description from the HAL
+    pub description: String,
+    /// Timestamp
+    pub timestamp: u64,
+}
+
 // =============================================================================
 // Security Oracle Engine
 // =============================================================================
@@ -548,6 +570,7 @@ enum SecurityEventType {
     AuthFailure,
     ConfigChange,
     ModuleLoad,
+    ControlFlowViolation,
 }
 
 /// Blocklist for known bad entities
@@ -1066,6 +1089,83 @@ fn security_to_ai_action(&self, action: &SecurityAction) -> AiAction {
         }
     }
 
+    /// Record a control-flow violation
+    ///
+    /// The hardware only traps on an actual hijack, so this is never a
+    /// false positive: the offending process is killed. A kernel violation
+    /// is fatal to the kernel, so it only raises the threat level and asks
+    /// for a scan.
+    pub fn report_cfi_violation(
+        &self,
+        sample: &CfiViolationSample,
+    ) -> (AiAction, Confidence, String) {
+        self.stats.events_analyzed.fetch_add(1, Ordering::Relaxed);
+        let id = self.stats.threats_detected.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let level = if sample.pid.is_some() {
+            ThreatLevel::High
+        } else {
+            ThreatLevel::Critical
+        };
+        {
+            let mut current = self.current_threat_level.write();
+            *current = (*current).max(level);
+        }
+
+        self.buffer_security_event(SecurityEvent {
+            timestamp: sample.timestamp,
+            event_type: SecurityEventType::ControlFlowViolation,
+            source_pid: sample.pid,
+            details: sample.description.clone(),
+            severity: level,
+        });
+
+        let edge = if sample.backward_edge {
+            "return"
+        } else {
+            "indirect branch"
+        };
+        let description = format!("{} at {:#x}: {}", edge, sample.ip, sample.description);
+        let recommendation = match sample.pid {
+            Some(pid) => SecurityAction::BlockProcess { pid, kill: true },
+            None => SecurityAction::TriggerScan {
+                scope: SecurityScanScope::QuickScan,
+            },
+        };
+        let action = self.security_to_ai_action(&recommendation);
+
+        self.active_threats.lock().push(Threat {
+            id,
+            threat_type: ThreatType::ControlFlowHijack,
+            level,
+            confidence: Confidence::new(1.0),
+            source_pid: sample.pid,
+            source_user: None,
+            target: None,
+            detected_at: sample.timestamp,
+            description: description.clone(),
+            iocs: Vec::new(),
+            recommendations: vec![recommendation],
+            status: ThreatStatus::Blocked,
+        });
+
+        match sample.pid {
+            Some(pid) => {
+                self.stats.threats_blocked.fetch_add(1, Ordering::Relaxed);
+                self.block_process(pid);
+            },
+            None => {
+                self.stats.scans_triggered.fetch_add(1, Ordering::Relaxed);
+            },
+        }
+
+        (
+            action,
+            Confidence::new(1.0),
+            format!("Control-flow hijack: {}", description),
+        )
+    }
+
     /// Buffer a security event
     fn buffer_security_event(&self, event: SecurityEvent) {
         let mut buffer = self.event_buffer.lock();
@@ -1223,4 +1323,35 @@ fn test_threat_resolution() {
         oracle.resolve_threat(1);
         assert_eq!(oracle.active_threats().len(), 0);
     }
+
+    #[test]
+    fn test_cfi_violation() {
+        let oracle = SecurityOracle::new(true);
+
+        let (action, confidence, _) = oracle.report_cfi_violation(&CfiViolationSample {
+            pid: Some(42),
+            ip: 0x40_1000,
+            backward_edge: true,
+            description: "shadow stack return mismatch".to_string(),
+            timestamp: 0,
+        });
+        assert!(matches!(action, AiAction::BlockProcess { pid: 42, .. }));
+        assert_eq!(confidence.value(), 1.0);
+        assert_eq!(oracle.current_threat_level(), ThreatLevel::High);
+        assert!(oracle.blocklist.read().processes.contains(&42));
+
+        let (action, _, _) = oracle.report_cfi_violation(&CfiViolationSample {
+            pid: None,
+            ip: 0xffff_8000_0000_1000,
+            backward_edge: false,
+            description: "missing ENDBR64 at branch target".to_string(),
+            timestamp: 0,
+        });
+        assert!(matches!(action, AiAction::TriggerSecurityScan { .. }));
+        assert_eq!(oracle.current_threat_level(), ThreatLevel::Critical);
+
+        let stats = oracle.statistics();
+        assert_eq!(stats.threats_detected, 2);
+        assert_eq!(stats.active_threats, 2);
+    }
 }
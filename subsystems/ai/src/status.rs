@@ -0,0 +1,76 @@
+//! # AI Subsystem Status
+//!
+//! A failed [`crate::init`] does not take the boot down with it: the
+//! global cortex is replaced by a degraded stub (see [`Cortex::degraded`])
+//! and the failure is reported once through the registered
+//! [`HealthSink`]. Subsystems ask [`crate::status`] or
+//! [`crate::try_cortex`] instead of relying on [`crate::cortex`].
+//!
+//! The state is exposed read-only under `/proc`:
+//!
+//! ```text
+//! /proc/sys/kernel/ai_degraded    1 if the AI runs as a degraded stub
+//! ```
+//!
+//! [`Cortex::degraded`]: crate::cortex::Cortex::degraded
+
+use alloc::string::String;
+use core::fmt::{self, Write};
+use spin::Once;
+
+/// File path relative to `/proc`
+pub const PROC_PATH: &str = "sys/kernel/ai_degraded";
+
+/// Availability of the AI subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiStatus {
+    /// `init` was never called
+    Uninitialized,
+    /// Fully initialized cortex
+    Active,
+    /// Initialization failed; the cortex is a no-op stub
+    Degraded,
+}
+
+impl AiStatus {
+    /// Can AI decisions be relied upon?
+    pub fn is_available(&self) -> bool {
+        *self == Self::Active
+    }
+}
+
+/// Health event raised when the AI subsystem changes availability
+#[derive(Debug, Clone)]
+pub struct AiHealthEvent {
+    /// New status
+    pub status: AiStatus,
+    /// What went wrong
+    pub reason: String,
+}
+
+/// Receiver for AI health events (the kernel health monitor)
+pub trait HealthSink: Send + Sync {
+    /// Handle an event
+    fn report(&self, event: &AiHealthEvent);
+}
+
+/// Registered health sink
+static HEALTH_SINK: Once<&'static dyn HealthSink> = Once::new();
+
+/// Register the health sink; only the first registration takes effect
+pub fn set_health_sink(sink: &'static dyn HealthSink) {
+    HEALTH_SINK.call_once(|| sink);
+}
+
+/// Deliver `event` to the health sink, if any
+pub(crate) fn report(event: AiHealthEvent) {
+    log::error!("[HELIX-AI] {:?}: {}", event.status, event.reason);
+    if let Some(sink) = HEALTH_SINK.get() {
+        sink.report(&event);
+    }
+}
+
+/// Render `/proc/sys/kernel/ai_degraded`
+pub fn render_proc(status: AiStatus, out: &mut dyn Write) -> fmt::Result {
+    writeln!(out, "{}", (status == AiStatus::Degraded) as u8)
+}
@@ -0,0 +1,425 @@
+//! # Tenant Scoping
+//!
+//! Keeps AI actions inside the namespace (tenant) whose signals triggered
+//! them.
+//!
+//! - **Scope**: every decision is scoped to the tenant of the processes
+//!   in its triggering event; events without a process, and proactive
+//!   decisions, are scoped to the host.
+//! - **Reach**: an action reaches the processes it targets, or the whole
+//!   system for global tuning (scheduler, allocator, modules, ...).
+//! - **Consensus**: a tenant-scoped decision whose action reaches other
+//!   tenants or the whole system is only accepted when enough AI
+//!   components independently recommended it.
+//! - **Budgets**: each tenant may only have a bounded number of decisions
+//!   accepted per time window.
+//! - **Learning isolation**: each tenant learns patterns in its own
+//!   [`LearningEngine`], so one tenant's behavior never drives decisions
+//!   for another.
+//!
+//! The host scope (the root namespace) administers every tenant and is
+//! not restricted.
+
+use crate::core::{AiAction, AiEvent};
+use crate::learning::LearningEngine;
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::String,
+    vec::Vec,
+};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use spin::{Mutex, RwLock};
+
+// =============================================================================
+// Tenant Types
+// =============================================================================
+
+/// Tenant (namespace) identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct TenantId(pub u64);
+
+impl TenantId {
+    /// The host (root namespace)
+    pub const HOST: TenantId = TenantId(0);
+
+    /// Is this the host?
+    pub fn is_host(&self) -> bool {
+        *self == Self::HOST
+    }
+}
+
+/// Maps processes to their tenant
+///
+/// Implemented by the kernel on top of its namespaces.
+pub trait TenantResolver {
+    /// Tenant owning process `pid`
+    fn tenant_of(&self, pid: u64) -> TenantId;
+}
+
+/// Decision budget of a tenant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TenantBudget {
+    /// Decisions accepted per window
+    pub max_decisions: u32,
+    /// Window length (microseconds)
+    pub window_us: u64,
+}
+
+impl Default for TenantBudget {
+    fn default() -> Self {
+        Self {
+            max_decisions: 16,
+            window_us: 1_000_000,
+        }
+    }
+}
+
+/// What an action affects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionReach {
+    /// Only AI-internal state
+    Local,
+    /// The listed processes
+    Processes(Vec<u64>),
+    /// The whole system
+    Global,
+}
+
+impl ActionReach {
+    /// Reach of an action
+    pub fn of(action: &AiAction) -> Self {
+        let mut reach = ActionReach::Local;
+        reach.add(action);
+        reach
+    }
+
+    fn add(&mut self, action: &AiAction) {
+        use AiAction::*;
+
+        let pid = match action {
+            NoOp | UpdateModel { .. } | RecordPattern { .. } | InvalidatePattern { .. } => {
+                return;
+            }
+            MigrateProcess { pid, .. } |
+            AdjustProcessPriority { pid, .. } |
+            IsolateProcess { pid, .. } |
+            BlockProcess { pid, .. } |
+            TerminateProcess { pid } => *pid,
+            Sequence(actions) | Parallel(actions) => {
+                for action in actions {
+                    self.add(action);
+                }
+                return;
+            }
+            Conditional { if_true, if_false, .. } => {
+                self.add(if_true);
+                self.add(if_false);
+                return;
+            }
+            _ => {
+                *self = ActionReach::Global;
+                return;
+            }
+        };
+
+        match self {
+            ActionReach::Global => {}
+            ActionReach::Processes(pids) => pids.push(pid),
+            ActionReach::Local => *self = ActionReach::Processes(alloc::vec![pid]),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BudgetWindow {
+    start_us: u64,
+    used: u32,
+}
+
+// =============================================================================
+// Tenant Manager
+// =============================================================================
+
+/// Per-tenant scoping, budgets and learning
+pub struct TenantManager {
+    /// Process to tenant mapping
+    resolver: RwLock<Option<Box<dyn TenantResolver + Send + Sync>>>,
+
+    /// Budgets set per tenant
+    budgets: RwLock<BTreeMap<TenantId, TenantBudget>>,
+
+    /// Budget for tenants without their own
+    default_budget: RwLock<TenantBudget>,
+
+    /// Current budget windows
+    windows: Mutex<BTreeMap<TenantId, BudgetWindow>>,
+
+    /// Per-tenant learning engines
+    learning: RwLock<BTreeMap<TenantId, LearningEngine>>,
+
+    /// Whether tenant learning engines learn
+    learning_enabled: bool,
+
+    /// Recommendations needed for a cross-tenant action
+    elevated_consensus: AtomicUsize,
+
+    /// Decisions refused for lack of consensus
+    scope_denials: AtomicU64,
+
+    /// Decisions refused for exhausted budgets
+    budget_denials: AtomicU64,
+}
+
+impl TenantManager {
+    /// Default recommendations needed for a cross-tenant action
+    pub const DEFAULT_ELEVATED_CONSENSUS: usize = 2;
+
+    /// Create a manager
+    pub fn new(learning_enabled: bool) -> Self {
+        Self {
+            resolver: RwLock::new(None),
+            budgets: RwLock::new(BTreeMap::new()),
+            default_budget: RwLock::new(TenantBudget::default()),
+            windows: Mutex::new(BTreeMap::new()),
+            learning: RwLock::new(BTreeMap::new()),
+            learning_enabled,
+            elevated_consensus: AtomicUsize::new(Self::DEFAULT_ELEVATED_CONSENSUS),
+            scope_denials: AtomicU64::new(0),
+            budget_denials: AtomicU64::new(0),
+        }
+    }
+
+    /// Install the process to tenant mapping
+    ///
+    /// Without one, everything is scoped to the host.
+    pub fn set_resolver(&self, resolver: Box<dyn TenantResolver + Send + Sync>) {
+        *self.resolver.write() = Some(resolver);
+    }
+
+    /// Set the decision budget of a tenant
+    pub fn set_budget(&self, tenant: TenantId, budget: TenantBudget) {
+        self.budgets.write().insert(tenant, budget);
+    }
+
+    /// Set the budget of tenants without their own
+    pub fn set_default_budget(&self, budget: TenantBudget) {
+        *self.default_budget.write() = budget;
+    }
+
+    /// Set the recommendations needed for a cross-tenant action
+    pub fn set_elevated_consensus(&self, recommendations: usize) {
+        self.elevated_consensus.store(recommendations.max(1), Ordering::Relaxed);
+    }
+
+    /// Forget a tenant (its namespace was destroyed)
+    pub fn remove_tenant(&self, tenant: TenantId) {
+        self.budgets.write().remove(&tenant);
+        self.windows.lock().remove(&tenant);
+        self.learning.write().remove(&tenant);
+    }
+
+    /// Tenant owning a process
+    pub fn tenant_of(&self, pid: u64) -> TenantId {
+        match self.resolver.read().as_ref() {
+            Some(resolver) => resolver.tenant_of(pid),
+            None => TenantId::HOST,
+        }
+    }
+
+    /// Scope of the decisions an event triggers
+    pub fn scope_of(&self, event: &AiEvent) -> TenantId {
+        match event {
+            AiEvent::ProcessSpawn { pid, .. } |
+            AiEvent::ProcessResourceSpike { pid, .. } |
+            AiEvent::PermissionViolation { pid, .. } => self.tenant_of(*pid),
+            _ => TenantId::HOST,
+        }
+    }
+
+    /// Check that `action` stays within `scope`
+    ///
+    /// `support` is the number of AI components that recommended the
+    /// action. An action reaching beyond a tenant needs elevated
+    /// consensus; the error says why it was refused.
+    pub fn check_scope(&self, scope: TenantId, action: &AiAction, support: usize) -> Result<(), String> {
+        if scope.is_host() {
+            return Ok(());
+        }
+
+        let outside = match ActionReach::of(action) {
+            ActionReach::Local => return Ok(()),
+            ActionReach::Global => String::from("the whole system"),
+            ActionReach::Processes(pids) => {
+                match pids.iter().find(|&&pid| self.tenant_of(pid) != scope) {
+                    Some(pid) => format!("process {} outside tenant {}", pid, scope.0),
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        let required = self.elevated_consensus.load(Ordering::Relaxed);
+        if support >= required {
+            return Ok(());
+        }
+        self.scope_denials.fetch_add(1, Ordering::Relaxed);
+        Err(format!(
+            "reaches {} with {} of {} required recommendations",
+            outside, support, required
+        ))
+    }
+
+    /// Charge one decision to the budget of `scope` at `now_us`
+    ///
+    /// Returns false if the budget for the current window is spent. The
+    /// host is not charged.
+    pub fn charge(&self, scope: TenantId, now_us: u64) -> bool {
+        if scope.is_host() {
+            return true;
+        }
+
+        let budget = self
+            .budgets
+            .read()
+            .get(&scope)
+            .copied()
+            .unwrap_or(*self.default_budget.read());
+        let mut windows = self.windows.lock();
+        let window = windows.entry(scope).or_default();
+        if now_us.saturating_sub(window.start_us) >= budget.window_us {
+            window.start_us = now_us;
+            window.used = 0;
+        }
+        if window.used >= budget.max_decisions {
+            self.budget_denials.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        window.used += 1;
+        true
+    }
+
+    /// Run `f` on the learning engine of a tenant, creating it if needed
+    pub fn with_learning<R>(&self, tenant: TenantId, f: impl FnOnce(&LearningEngine) -> R) -> R {
+        if let Some(engine) = self.learning.read().get(&tenant) {
+            return f(engine);
+        }
+        let mut learning = self.learning.write();
+        let engine = learning
+            .entry(tenant)
+            .or_insert_with(|| LearningEngine::new(self.learning_enabled));
+        f(engine)
+    }
+
+    /// Tenants with a learning engine
+    pub fn learning_tenants(&self) -> Vec<TenantId> {
+        self.learning.read().keys().copied().collect()
+    }
+
+    /// Get statistics
+    pub fn statistics(&self) -> TenantStatistics {
+        TenantStatistics {
+            tenants_learning: self.learning.read().len(),
+            tenants_budgeted: self.budgets.read().len(),
+            scope_denials: self.scope_denials.load(Ordering::Relaxed),
+            budget_denials: self.budget_denials.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Public statistics
+#[derive(Debug, Clone)]
+pub struct TenantStatistics {
+    pub tenants_learning: usize,
+    pub tenants_budgeted: usize,
+    pub scope_denials: u64,
+    pub budget_denials: u64,
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    /// PIDs 1000-1999 belong to tenant 1, the rest to the host
+    struct Ranges;
+
+    impl TenantResolver for Ranges {
+        fn tenant_of(&self, pid: u64) -> TenantId {
+            if (1000..2000).contains(&pid) {
+                TenantId(1)
+            } else {
+                TenantId::HOST
+            }
+        }
+    }
+
+    fn manager() -> TenantManager {
+        let manager = TenantManager::new(true);
+        manager.set_resolver(Box::new(Ranges));
+        manager
+    }
+
+    #[test]
+    fn test_scope_of_event() {
+        let manager = manager();
+        let spawn = |pid| AiEvent::ProcessSpawn { pid, name: "x".to_string() };
+        assert_eq!(manager.scope_of(&spawn(1500)), TenantId(1));
+        assert_eq!(manager.scope_of(&spawn(10)), TenantId::HOST);
+        assert_eq!(manager.scope_of(&AiEvent::SystemBoot), TenantId::HOST);
+    }
+
+    #[test]
+    fn test_cross_tenant_consensus() {
+        let manager = manager();
+        let tenant = TenantId(1);
+        let terminate = |pid| AiAction::TerminateProcess { pid };
+
+        // Inside the tenant
+        assert!(manager.check_scope(tenant, &terminate(1500), 1).is_ok());
+
+        // Another tenant's process, or the whole system
+        assert!(manager.check_scope(tenant, &terminate(10), 1).is_err());
+        let gc = AiAction::Sequence(vec![terminate(1500), AiAction::ForceGarbageCollection]);
+        assert!(manager.check_scope(tenant, &gc, 1).is_err());
+        assert!(manager.check_scope(tenant, &gc, 2).is_ok());
+
+        // The host is unrestricted
+        assert!(manager.check_scope(TenantId::HOST, &terminate(1500), 1).is_ok());
+        assert_eq!(manager.statistics().scope_denials, 2);
+    }
+
+    #[test]
+    fn test_budget() {
+        let manager = manager();
+        let tenant = TenantId(1);
+        manager.set_budget(tenant, TenantBudget { max_decisions: 2, window_us: 100 });
+
+        assert!(manager.charge(tenant, 0));
+        assert!(manager.charge(tenant, 10));
+        assert!(!manager.charge(tenant, 20));
+        assert!(manager.charge(TenantId::HOST, 20));
+
+        // Next window
+        assert!(manager.charge(tenant, 150));
+    }
+
+    #[test]
+    fn test_learning_isolation() {
+        let manager = manager();
+        manager.with_learning(TenantId(1), |engine| {
+            engine.record_event(&AiEvent::SystemBoot, 0)
+        });
+        manager.with_learning(TenantId(2), |_| {});
+        assert_eq!(manager.learning_tenants(), vec![TenantId(1), TenantId(2)]);
+
+        manager.remove_tenant(TenantId(1));
+        assert_eq!(manager.learning_tenants(), vec![TenantId(2)]);
+    }
+}
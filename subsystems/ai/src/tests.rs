@@ -16,6 +16,7 @@ mod tests {
     use crate::memory::AiMemory;
     use crate::metrics::MetricsCollector;
     use crate::safety::SafetyChecker;
+    use crate::status::{render_proc, AiStatus};
     use alloc::string::String;
     use alloc::vec;
 
@@ -495,6 +496,35 @@ fn test_cortex_statistics() {
         assert!(stats.events_processed >= 0);
     }
 
+    #[test]
+    fn test_cortex_degraded() {
+        let cortex = Cortex::degraded(AiConfig::default(), String::from("no memory"));
+        assert!(cortex.is_degraded());
+        assert_eq!(cortex.degraded_reason(), Some("no memory"));
+        assert_eq!(cortex.state(), AiState::SafeMode);
+
+        // Events are dropped and no decisions come out
+        cortex.submit_event(AiEvent::SystemBoot, AiPriority::High).unwrap();
+        assert_eq!(cortex.statistics().event_queue_size, 0);
+        assert!(cortex.process().unwrap().is_empty());
+
+        cortex.suspend();
+        assert_eq!(cortex.state(), AiState::SafeMode);
+    }
+
+    #[test]
+    fn test_status_proc() {
+        let mut out = String::new();
+        render_proc(AiStatus::Degraded, &mut out).unwrap();
+        assert_eq!(out, "1\n");
+
+        out.clear();
+        render_proc(AiStatus::Active, &mut out).unwrap();
+        assert_eq!(out, "0\n");
+        assert!(AiStatus::Active.is_available());
+        assert!(!AiStatus::Uninitialized.is_available());
+    }
+
     // =========================================================================
     // Math Utility Tests
     // =========================================================================
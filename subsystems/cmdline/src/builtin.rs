@@ -0,0 +1,121 @@
+//! Standard kernel parameters
+//!
+//! Parameters understood by the core kernel. Bootloaders validate edits
+//! against [`boot_registry`] so that a typo in the boot menu is reported
+//! before handoff rather than after the kernel has started.
+
+use crate::param::{ParamFlags, ParamSpec};
+use crate::registry::Registry;
+
+/// Suppress most boot messages
+pub static QUIET: ParamSpec = ParamSpec::flag("quiet")
+    .boot()
+    .description("Suppress most boot messages");
+
+/// Enable kernel debugging output
+pub static DEBUG: ParamSpec = ParamSpec::flag("debug")
+    .boot()
+    .description("Enable kernel debugging output");
+
+/// Console log level
+pub static LOGLEVEL: ParamSpec = ParamSpec::int("loglevel", 0, 7)
+    .default_value("4")
+    .boot()
+    .description("Console log level (0 = emergency .. 7 = debug)");
+
+/// Root device
+pub static ROOT: ParamSpec = ParamSpec::string("root", 255)
+    .boot()
+    .description("Root filesystem device");
+
+/// Init program
+pub static INIT: ParamSpec = ParamSpec::string("init", 255)
+    .default_value("/sbin/init")
+    .boot()
+    .description("Path of the first userspace program");
+
+/// Console device
+pub static CONSOLE: ParamSpec = ParamSpec::string("console", 64)
+    .boot()
+    .description("Console device and options");
+
+/// Boot console
+pub static EARLYCON: ParamSpec = ParamSpec::string("earlycon", 64)
+    .boot()
+    .description("Early serial console (uart8250,io,0x3f8 / pl011,0x9000000 / sbi)");
+
+/// Disable KASLR
+pub static NOKASLR: ParamSpec = ParamSpec::flag("nokaslr")
+    .boot()
+    .description("Disable kernel address space layout randomization");
+
+/// Fixed KASLR slide
+pub static KASLR_SLIDE: ParamSpec = ParamSpec::size("kaslr_slide", 0, 1 << 40)
+    .boot()
+    .description("Fixed KASLR slide (debugging only)");
+
+/// Memory limit
+pub static MEM: ParamSpec = ParamSpec::size("mem", 16 << 20, u64::MAX)
+    .boot()
+    .description("Limit usable physical memory");
+
+/// CPU limit
+pub static MAXCPUS: ParamSpec = ParamSpec::int("maxcpus", 1, 4096)
+    .boot()
+    .description("Maximum number of CPUs to bring up");
+
+/// Disable SMP
+pub static NOSMP: ParamSpec = ParamSpec::flag("nosmp")
+    .boot()
+    .description("Run on the boot CPU only");
+
+/// Reboot delay after panic
+pub static PANIC: ParamSpec = ParamSpec::int("panic", -1, 3600)
+    .default_value("0")
+    .boot()
+    .description("Seconds before rebooting after a panic (0 = halt, -1 = immediate)");
+
+/// CPUs removed from scheduling balance and unbound work
+pub static ISOLCPUS: ParamSpec = ParamSpec::cpulist("isolcpus")
+    .boot()
+    .description("CPUs reserved for explicitly pinned tasks");
+
+/// CPUs that stop the scheduler tick when running a single task
+pub static NOHZ_FULL: ParamSpec = ParamSpec::cpulist("nohz_full")
+    .boot()
+    .description("CPUs running tickless with a single runnable task");
+
+/// Default device IRQ affinity
+pub static IRQAFFINITY: ParamSpec = ParamSpec::cpulist("irqaffinity")
+    .boot()
+    .description("CPUs that handle device interrupts");
+
+/// Speculative execution mitigations
+pub static MITIGATIONS: ParamSpec = ParamSpec::choice("mitigations", &["auto", "auto,nosmt", "off"])
+    .default_value("auto")
+    .boot()
+    .description("CPU vulnerability mitigations (off = none, for benchmarking)");
+
+/// Kernel lockdown
+pub static LOCKDOWN: ParamSpec = ParamSpec::choice("lockdown", &["none", "integrity", "confidentiality"])
+    .default_value("none")
+    .boot()
+    .description("Kernel lockdown level (at least integrity under Secure Boot)");
+
+/// All standard kernel parameters
+pub static BOOT_PARAMS: [&ParamSpec; 18] = [
+    &QUIET, &DEBUG, &LOGLEVEL, &ROOT, &INIT, &CONSOLE, &EARLYCON,
+    &NOKASLR, &KASLR_SLIDE, &MEM, &MAXCPUS, &NOSMP, &PANIC,
+    &ISOLCPUS, &NOHZ_FULL, &IRQAFFINITY, &MITIGATIONS, &LOCKDOWN,
+];
+
+/// Registry holding the standard kernel parameters
+pub fn boot_registry() -> Registry<24> {
+    let mut registry = Registry::new();
+    for spec in BOOT_PARAMS.iter() {
+        debug_assert!(spec.flags.contains(ParamFlags::BOOT));
+        // Capacity exceeds BOOT_PARAMS and names are unique
+        let _ = registry.register(spec);
+    }
+    registry
+}
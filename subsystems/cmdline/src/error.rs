@@ -0,0 +1,54 @@
+//! Command-line error types
+
+use core::fmt;
+
+/// Command-line error
+///
+/// Parameter names always refer to the registered specification, so errors
+/// stay `'static` and can outlive the command line that produced them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmdlineError {
+    /// Command line exceeds `MAX_CMDLINE_LEN`
+    TooLong,
+    /// A quote was opened but never closed (byte offset)
+    UnterminatedQuote(usize),
+    /// Parameter registered twice
+    Duplicate(&'static str),
+    /// Registry capacity exhausted
+    RegistryFull,
+    /// Parameter not registered
+    Unknown,
+    /// Parameter requires a value but none was given
+    MissingValue(&'static str),
+    /// Flag parameter was given a value
+    UnexpectedValue(&'static str),
+    /// Value does not parse as the parameter's type
+    InvalidValue(&'static str),
+    /// Numeric value outside the permitted range
+    OutOfRange(&'static str),
+    /// String value longer than permitted
+    ValueTooLong(&'static str),
+    /// Value is not one of the permitted choices
+    InvalidChoice(&'static str),
+}
+
+impl fmt::Display for CmdlineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong => write!(f, "command line too long"),
+            Self::UnterminatedQuote(pos) => write!(f, "unterminated quote at offset {}", pos),
+            Self::Duplicate(name) => write!(f, "parameter '{}' registered twice", name),
+            Self::RegistryFull => write!(f, "parameter registry full"),
+            Self::Unknown => write!(f, "unknown parameter"),
+            Self::MissingValue(name) => write!(f, "'{}' requires a value", name),
+            Self::UnexpectedValue(name) => write!(f, "'{}' does not take a value", name),
+            Self::InvalidValue(name) => write!(f, "invalid value for '{}'", name),
+            Self::OutOfRange(name) => write!(f, "value for '{}' out of range", name),
+            Self::ValueTooLong(name) => write!(f, "value for '{}' too long", name),
+            Self::InvalidChoice(name) => write!(f, "value for '{}' is not a valid choice", name),
+        }
+    }
+}
+
+/// Command-line result type
+pub type CmdlineResult<T> = Result<T, CmdlineError>;
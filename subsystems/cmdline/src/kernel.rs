@@ -0,0 +1,101 @@
+//! Global kernel parameter registry
+//!
+//! The kernel records the command line once during early boot; subsystems
+//! then register their own parameters and read typed values back. Every
+//! registered parameter is reachable through [`proc_entries`] without
+//! further wiring.
+//!
+//! ```rust,no_run
+//! use helix_cmdline::{kernel, ParamSpec};
+//!
+//! static SCHED_QUANTUM: ParamSpec = ParamSpec::int("sched.quantum_ms", 1, 100)
+//!     .default_value("10")
+//!     .description("Scheduler time slice");
+//!
+//! kernel::register(&SCHED_QUANTUM).unwrap();
+//! let quantum = kernel::value("sched.quantum_ms").unwrap().as_int();
+//! ```
+
+use spin::{Mutex, Once};
+
+use crate::builtin::BOOT_PARAMS;
+use crate::error::{CmdlineError, CmdlineResult};
+use crate::param::{ParamSpec, Value};
+use crate::parse::Cmdline;
+use crate::proc::{self, ProcEntry};
+use crate::registry::Registry;
+
+/// Maximum number of kernel parameters
+pub const MAX_KERNEL_PARAMS: usize = 128;
+
+/// Command line passed by the bootloader
+static CMDLINE: Once<&'static str> = Once::new();
+
+/// Registered kernel parameters
+static REGISTRY: Mutex<Registry<MAX_KERNEL_PARAMS>> = Mutex::new(Registry::new());
+
+/// Record the boot command line and register the standard parameters
+///
+/// Only the first call has any effect.
+pub fn init(cmdline: &'static str) -> CmdlineResult<()> {
+    let mut first = false;
+    CMDLINE.call_once(|| {
+        first = true;
+        cmdline
+    });
+
+    if first {
+        REGISTRY.lock().register_all(&BOOT_PARAMS)?;
+    }
+
+    Ok(())
+}
+
+/// The boot command line (empty before [`init`])
+pub fn cmdline() -> Cmdline<'static> {
+    Cmdline::new(CMDLINE.get().copied().unwrap_or(""))
+}
+
+/// Register a subsystem parameter and validate its current value
+pub fn register(spec: &'static ParamSpec) -> CmdlineResult<()> {
+    REGISTRY.lock().register(spec)?;
+
+    match cmdline().get(spec.name) {
+        Some(value) => spec.parse(value).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// Typed value of a registered parameter
+pub fn value(name: &str) -> CmdlineResult<Value<'static>> {
+    REGISTRY.lock().value(&cmdline(), name)
+}
+
+/// Look up a registered parameter
+pub fn find(name: &str) -> Option<&'static ParamSpec> {
+    REGISTRY.lock().find(name)
+}
+
+/// Render a `/proc` file by path relative to `/proc`
+pub fn proc_read(path: &str, out: &mut dyn core::fmt::Write) -> CmdlineResult<()> {
+    let registry = REGISTRY.lock();
+    let entry = proc::entries(&registry)
+        .find(|e| match e.dir() {
+            "" => path == e.name(),
+            dir => path
+                .strip_prefix(dir)
+                .and_then(|rest| rest.strip_prefix('/'))
+                == Some(e.name()),
+        })
+        .ok_or(CmdlineError::Unknown)?;
+
+    entry
+        .render(&registry, &cmdline(), out)
+        .map_err(|_| CmdlineError::TooLong)
+}
+
+/// Snapshot of all `/proc` entries
+pub fn proc_entries(out: &mut dyn FnMut(ProcEntry)) {
+    let registry = REGISTRY.lock();
+    proc::entries(&registry).for_each(out);
+}
@@ -0,0 +1,91 @@
+//! # Helix Command Line
+//!
+//! Unified kernel command-line handling shared by every boot path and the
+//! kernel itself.
+//!
+//! ## Overview
+//!
+//! - **Parsing**: tokenizer with quoting and `--` init-argument separator
+//! - **Parameters**: declarative, typed parameter specifications with
+//!   defaults and validation
+//! - **Registry**: fixed-capacity parameter registry usable before any
+//!   allocator exists
+//! - **Built-ins**: the standard kernel parameters, so bootloaders can
+//!   validate user edits before handing off
+//! - **Proc**: rendering of registered parameters for `/proc`
+//!
+//! ## Architecture
+//!
+//! ```text
+//! ┌─────────────────────────────────────────────────────────────┐
+//! │                      CMDLINE SUBSYSTEM                       │
+//! ├─────────────────────────────────────────────────────────────┤
+//! │  ┌─────────────┐  ┌─────────────┐  ┌─────────────────────┐  │
+//! │  │   Parser    │  │  Registry   │  │      Consumers      │  │
+//! │  │  (tokens)   │──│ (validate)  │──│ (boot menu, kernel) │  │
+//! │  └─────────────┘  └─────────────┘  └─────────────────────┘  │
+//! └─────────────────────────────────────────────────────────────┘
+//! ```
+//!
+//! ## Usage
+//!
+//! ```rust,no_run
+//! use helix_cmdline::{Cmdline, ParamSpec, Registry, Value};
+//!
+//! static LOGLEVEL: ParamSpec = ParamSpec::int("loglevel", 0, 7)
+//!     .default_value("4")
+//!     .description("Console log level");
+//!
+//! let mut registry: Registry<8> = Registry::new();
+//! registry.register(&LOGLEVEL).unwrap();
+//!
+//! let cmdline = Cmdline::new("quiet loglevel=3 -- single");
+//! registry.validate(&cmdline).unwrap();
+//! assert_eq!(registry.value(&cmdline, "loglevel"), Ok(Value::Int(3)));
+//! ```
+//!
+//! ## Features
+//!
+//! - `kernel`: Global kernel registry that subsystems register into
+
+#![no_std]
+#![deny(unsafe_op_in_unsafe_fn)]
+
+// ============================================================================
+// MODULES
+// ============================================================================
+
+/// Error types
+pub mod error;
+
+/// Command-line tokenizer
+pub mod parse;
+
+/// Parameter specifications and typed values
+pub mod param;
+
+/// Parameter registry and validation
+pub mod registry;
+
+/// Standard kernel parameters
+pub mod builtin;
+
+/// `/proc` exposure of registered parameters
+pub mod proc;
+
+/// Global kernel registry
+#[cfg(feature = "kernel")]
+pub mod kernel;
+
+// ============================================================================
+// RE-EXPORTS
+// ============================================================================
+
+pub use error::{CmdlineError, CmdlineResult};
+pub use parse::{Arg, Args, Cmdline};
+pub use param::{ParamFlags, ParamKind, ParamSpec, Value};
+pub use registry::Registry;
+pub use builtin::{boot_registry, BOOT_PARAMS};
+
+/// Maximum command-line length accepted by any boot path
+pub const MAX_CMDLINE_LEN: usize = 4096;
@@ -0,0 +1,443 @@
+//! Parameter specifications and typed values
+//!
+//! Parameters are declared as `static` [`ParamSpec`]s built with `const fn`
+//! constructors, so a declaration reads as a single expression and needs no
+//! allocation:
+//!
+//! ```rust
+//! use helix_cmdline::ParamSpec;
+//!
+//! static MAXCPUS: ParamSpec = ParamSpec::int("maxcpus", 1, 4096)
+//!     .description("Maximum number of CPUs to bring up");
+//! ```
+
+use crate::error::{CmdlineError, CmdlineResult};
+
+// ============================================================================
+// KIND
+// ============================================================================
+
+/// Parameter type and its constraints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// Presence-only switch (`quiet`)
+    Flag,
+    /// Boolean (`y/n`, `1/0`, `on/off`, `true/false`; bare = true)
+    Bool,
+    /// Signed integer within an inclusive range
+    Int { min: i64, max: i64 },
+    /// Byte size with optional `K`/`M`/`G` suffix, inclusive range
+    Size { min: u64, max: u64 },
+    /// Free-form string up to a maximum length
+    Str { max_len: usize },
+    /// One of a fixed set of strings
+    Choice(&'static [&'static str]),
+    /// CPU list (`0-3,6`) over the first 64 CPUs
+    CpuList,
+}
+
+impl ParamKind {
+    /// Short type name for display
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Flag => "flag",
+            Self::Bool => "bool",
+            Self::Int { .. } => "int",
+            Self::Size { .. } => "size",
+            Self::Str { .. } => "string",
+            Self::Choice(_) => "choice",
+            Self::CpuList => "cpulist",
+        }
+    }
+}
+
+// ============================================================================
+// FLAGS
+// ============================================================================
+
+/// Parameter flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamFlags(u32);
+
+impl ParamFlags {
+    /// No flags
+    pub const NONE: Self = Self(0);
+    /// Validated by bootloaders before handoff
+    pub const BOOT: Self = Self(1 << 0);
+    /// Exposed under `/proc`
+    pub const PROC: Self = Self(1 << 1);
+    /// Accepted but ignored
+    pub const DEPRECATED: Self = Self(1 << 2);
+    /// Default for newly declared parameters
+    pub const DEFAULT: Self = Self::PROC;
+
+    /// Union of two flag sets
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Whether all bits of `other` are set
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Raw bits
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+}
+
+// ============================================================================
+// VALUE
+// ============================================================================
+
+/// A parsed parameter value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value<'a> {
+    /// Flag present
+    Flag(bool),
+    /// Boolean
+    Bool(bool),
+    /// Integer
+    Int(i64),
+    /// Size in bytes
+    Size(u64),
+    /// String or choice
+    Str(&'a str),
+    /// CPU list as a bit mask (bit N = CPU N)
+    CpuMask(u64),
+}
+
+impl<'a> Value<'a> {
+    /// As boolean (flags and bools)
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Flag(b) | Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// As integer
+    pub const fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// As size in bytes
+    pub const fn as_size(&self) -> Option<u64> {
+        match self {
+            Self::Size(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// As CPU mask
+    pub const fn as_cpu_mask(&self) -> Option<u64> {
+        match self {
+            Self::CpuMask(m) => Some(*m),
+            _ => None,
+        }
+    }
+
+    /// As string
+    pub const fn as_str(&self) -> Option<&'a str> {
+        match self {
+            Self::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// SPECIFICATION
+// ============================================================================
+
+/// Declarative parameter specification
+#[derive(Debug, Clone, Copy)]
+pub struct ParamSpec {
+    /// Parameter name as written on the command line
+    pub name: &'static str,
+    /// Type and constraints
+    pub kind: ParamKind,
+    /// Default value (in command-line syntax)
+    pub default: Option<&'static str>,
+    /// One-line description
+    pub description: &'static str,
+    /// Flags
+    pub flags: ParamFlags,
+}
+
+impl ParamSpec {
+    /// Create a parameter of any kind
+    pub const fn new(name: &'static str, kind: ParamKind) -> Self {
+        Self {
+            name,
+            kind,
+            default: None,
+            description: "",
+            flags: ParamFlags::DEFAULT,
+        }
+    }
+
+    /// Presence-only switch
+    pub const fn flag(name: &'static str) -> Self {
+        Self::new(name, ParamKind::Flag)
+    }
+
+    /// Boolean
+    pub const fn bool(name: &'static str) -> Self {
+        Self::new(name, ParamKind::Bool)
+    }
+
+    /// Integer in `min..=max`
+    pub const fn int(name: &'static str, min: i64, max: i64) -> Self {
+        Self::new(name, ParamKind::Int { min, max })
+    }
+
+    /// Byte size in `min..=max`
+    pub const fn size(name: &'static str, min: u64, max: u64) -> Self {
+        Self::new(name, ParamKind::Size { min, max })
+    }
+
+    /// String of at most `max_len` bytes
+    pub const fn string(name: &'static str, max_len: usize) -> Self {
+        Self::new(name, ParamKind::Str { max_len })
+    }
+
+    /// One of `choices`
+    pub const fn choice(name: &'static str, choices: &'static [&'static str]) -> Self {
+        Self::new(name, ParamKind::Choice(choices))
+    }
+
+    /// CPU list
+    pub const fn cpulist(name: &'static str) -> Self {
+        Self::new(name, ParamKind::CpuList)
+    }
+
+    /// Set the default value
+    pub const fn default_value(mut self, value: &'static str) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    /// Set the description
+    pub const fn description(mut self, text: &'static str) -> Self {
+        self.description = text;
+        self
+    }
+
+    /// Add flags
+    pub const fn flags(mut self, flags: ParamFlags) -> Self {
+        self.flags = self.flags.union(flags);
+        self
+    }
+
+    /// Mark as validated by bootloaders
+    pub const fn boot(self) -> Self {
+        self.flags(ParamFlags::BOOT)
+    }
+
+    /// Parse and validate a value for this parameter
+    ///
+    /// `value` is `None` when the parameter appeared without `=`.
+    pub fn parse<'a>(&self, value: Option<&'a str>) -> CmdlineResult<Value<'a>> {
+        let name = self.name;
+
+        match (self.kind, value) {
+            (ParamKind::Flag, None) => Ok(Value::Flag(true)),
+            (ParamKind::Flag, Some(_)) => Err(CmdlineError::UnexpectedValue(name)),
+            (ParamKind::Bool, None) => Ok(Value::Bool(true)),
+            (ParamKind::Bool, Some(v)) => parse_bool(v)
+                .map(Value::Bool)
+                .ok_or(CmdlineError::InvalidValue(name)),
+            (_, None) => Err(CmdlineError::MissingValue(name)),
+            (ParamKind::Int { min, max }, Some(v)) => {
+                let n = parse_int(v).ok_or(CmdlineError::InvalidValue(name))?;
+                if n < min || n > max {
+                    return Err(CmdlineError::OutOfRange(name));
+                }
+                Ok(Value::Int(n))
+            }
+            (ParamKind::Size { min, max }, Some(v)) => {
+                let n = parse_size(v).ok_or(CmdlineError::InvalidValue(name))?;
+                if n < min || n > max {
+                    return Err(CmdlineError::OutOfRange(name));
+                }
+                Ok(Value::Size(n))
+            }
+            (ParamKind::Str { max_len }, Some(v)) => {
+                if v.len() > max_len {
+                    return Err(CmdlineError::ValueTooLong(name));
+                }
+                Ok(Value::Str(v))
+            }
+            (ParamKind::Choice(choices), Some(v)) => {
+                if choices.contains(&v) {
+                    Ok(Value::Str(v))
+                } else {
+                    Err(CmdlineError::InvalidChoice(name))
+                }
+            }
+            (ParamKind::CpuList, Some(v)) => parse_cpulist(v)
+                .map(Value::CpuMask)
+                .ok_or(CmdlineError::InvalidValue(name)),
+        }
+    }
+
+    /// Value used when the parameter is absent
+    pub fn default_parsed(&self) -> CmdlineResult<Value<'static>> {
+        match (self.kind, self.default) {
+            (ParamKind::Flag, _) => Ok(Value::Flag(false)),
+            (ParamKind::Bool, None) => Ok(Value::Bool(false)),
+            (_, Some(d)) => self.parse(Some(d)),
+            (_, None) => Err(CmdlineError::MissingValue(self.name)),
+        }
+    }
+}
+
+// ============================================================================
+// VALUE PARSERS
+// ============================================================================
+
+/// Parse a boolean
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s {
+        "1" | "y" | "Y" | "yes" | "on" | "true" => Some(true),
+        "0" | "n" | "N" | "no" | "off" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a decimal or `0x` hexadecimal integer
+pub fn parse_int(s: &str) -> Option<i64> {
+    let (neg, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+
+    let magnitude = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i64>().ok()?,
+    };
+
+    Some(if neg { -magnitude } else { magnitude })
+}
+
+/// Parse a size with optional `K`, `M`, `G` or `T` suffix (binary units)
+pub fn parse_size(s: &str) -> Option<u64> {
+    let (digits, shift) = match s.as_bytes().last()? {
+        b'K' | b'k' => (&s[..s.len() - 1], 10),
+        b'M' | b'm' => (&s[..s.len() - 1], 20),
+        b'G' | b'g' => (&s[..s.len() - 1], 30),
+        b'T' | b't' => (&s[..s.len() - 1], 40),
+        _ => (s, 0),
+    };
+
+    let base = match digits.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<u64>().ok()?,
+    };
+
+    base.checked_mul(1u64 << shift)
+}
+
+/// Parse a CPU list (`0-3,6,8-9`) into a mask of the first 64 CPUs
+///
+/// An empty list is an empty mask.
+pub fn parse_cpulist(s: &str) -> Option<u64> {
+    let mut mask = 0u64;
+    if s.is_empty() {
+        return Some(mask);
+    }
+
+    for part in s.split(',') {
+        let (first, last) = match part.split_once('-') {
+            Some((a, b)) => (a.parse::<u32>().ok()?, b.parse::<u32>().ok()?),
+            None => {
+                let cpu = part.parse::<u32>().ok()?;
+                (cpu, cpu)
+            }
+        };
+        if first > last || last >= u64::BITS {
+            return None;
+        }
+        for cpu in first..=last {
+            mask |= 1 << cpu;
+        }
+    }
+
+    Some(mask)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag() {
+        let spec = ParamSpec::flag("quiet");
+        assert_eq!(spec.parse(None), Ok(Value::Flag(true)));
+        assert_eq!(spec.parse(Some("1")), Err(CmdlineError::UnexpectedValue("quiet")));
+        assert_eq!(spec.default_parsed(), Ok(Value::Flag(false)));
+    }
+
+    #[test]
+    fn test_int_range() {
+        let spec = ParamSpec::int("loglevel", 0, 7).default_value("4");
+        assert_eq!(spec.parse(Some("3")), Ok(Value::Int(3)));
+        assert_eq!(spec.parse(Some("0x7")), Ok(Value::Int(7)));
+        assert_eq!(spec.parse(Some("8")), Err(CmdlineError::OutOfRange("loglevel")));
+        assert_eq!(spec.parse(Some("x")), Err(CmdlineError::InvalidValue("loglevel")));
+        assert_eq!(spec.parse(None), Err(CmdlineError::MissingValue("loglevel")));
+        assert_eq!(spec.default_parsed(), Ok(Value::Int(4)));
+    }
+
+    #[test]
+    fn test_size() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("4K"), Some(4096));
+        assert_eq!(parse_size("2G"), Some(2 << 30));
+        assert_eq!(parse_size("0x1000"), Some(0x1000));
+        assert_eq!(parse_size("M"), None);
+    }
+
+    #[test]
+    fn test_choice_and_string() {
+        let spec = ParamSpec::choice("mode", &["fast", "safe"]);
+        assert_eq!(spec.parse(Some("safe")), Ok(Value::Str("safe")));
+        assert_eq!(spec.parse(Some("other")), Err(CmdlineError::InvalidChoice("mode")));
+
+        let spec = ParamSpec::string("root", 4);
+        assert_eq!(spec.parse(Some("sda1")), Ok(Value::Str("sda1")));
+        assert_eq!(spec.parse(Some("sda10")), Err(CmdlineError::ValueTooLong("root")));
+    }
+
+    #[test]
+    fn test_cpulist() {
+        assert_eq!(parse_cpulist("0"), Some(0b1));
+        assert_eq!(parse_cpulist("1-3,6"), Some(0b100_1110));
+        assert_eq!(parse_cpulist("63"), Some(1 << 63));
+        assert_eq!(parse_cpulist(""), Some(0));
+        assert_eq!(parse_cpulist("64"), None);
+        assert_eq!(parse_cpulist("3-1"), None);
+        assert_eq!(parse_cpulist("1,,2"), None);
+
+        let spec = ParamSpec::cpulist("isolcpus");
+        assert_eq!(spec.parse(Some("2-3")), Ok(Value::CpuMask(0b1100)));
+        assert_eq!(spec.parse(Some("x")), Err(CmdlineError::InvalidValue("isolcpus")));
+    }
+
+    #[test]
+    fn test_flags() {
+        let spec = ParamSpec::flag("nokaslr").boot();
+        assert!(spec.flags.contains(ParamFlags::BOOT));
+        assert!(spec.flags.contains(ParamFlags::PROC));
+        assert!(!spec.flags.contains(ParamFlags::DEPRECATED));
+    }
+}
@@ -0,0 +1,203 @@
+//! Command-line tokenizer
+//!
+//! Splits a command line into `key` / `key=value` arguments. Double quotes
+//! group whitespace either around the value (`key="a b"`) or around the
+//! whole argument (`"key=a b"`). Everything after a bare `--` belongs to
+//! init and is not interpreted as kernel parameters.
+
+use crate::error::{CmdlineError, CmdlineResult};
+use crate::MAX_CMDLINE_LEN;
+
+// ============================================================================
+// ARGUMENT
+// ============================================================================
+
+/// A single command-line argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arg<'a> {
+    /// Parameter name
+    pub key: &'a str,
+    /// Value, if `=` was present
+    pub value: Option<&'a str>,
+}
+
+// ============================================================================
+// COMMAND LINE
+// ============================================================================
+
+/// Borrowed view of a kernel command line
+#[derive(Debug, Clone, Copy)]
+pub struct Cmdline<'a> {
+    raw: &'a str,
+}
+
+impl<'a> Cmdline<'a> {
+    /// Wrap a raw command line
+    pub const fn new(raw: &'a str) -> Self {
+        Self { raw }
+    }
+
+    /// Raw command line
+    pub const fn as_str(&self) -> &'a str {
+        self.raw
+    }
+
+    /// Check the command line is well formed
+    pub fn check(&self) -> CmdlineResult<()> {
+        if self.raw.len() > MAX_CMDLINE_LEN {
+            return Err(CmdlineError::TooLong);
+        }
+
+        let mut open = None;
+        for (i, b) in self.raw.bytes().enumerate() {
+            if b == b'"' {
+                open = match open {
+                    Some(_) => None,
+                    None => Some(i),
+                };
+            }
+        }
+
+        match open {
+            Some(pos) => Err(CmdlineError::UnterminatedQuote(pos)),
+            None => Ok(()),
+        }
+    }
+
+    /// Iterate over kernel arguments (stops at `--`)
+    pub fn args(&self) -> Args<'a> {
+        Args { rest: self.raw }
+    }
+
+    /// Arguments passed through to init (after `--`)
+    pub fn init_args(&self) -> &'a str {
+        let mut args = self.args();
+        while args.next().is_some() {}
+        args.rest.trim_start().strip_prefix("--").unwrap_or("").trim()
+    }
+
+    /// Value of the last occurrence of `key`
+    ///
+    /// Returns `Some(None)` for a bare key and `None` if absent.
+    pub fn get(&self, key: &str) -> Option<Option<&'a str>> {
+        self.args().filter(|a| a.key == key).last().map(|a| a.value)
+    }
+
+    /// Whether `key` appears at all
+    pub fn contains(&self, key: &str) -> bool {
+        self.args().any(|a| a.key == key)
+    }
+}
+
+// ============================================================================
+// ITERATOR
+// ============================================================================
+
+/// Iterator over kernel arguments
+#[derive(Debug, Clone)]
+pub struct Args<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = Arg<'a>;
+
+    fn next(&mut self) -> Option<Arg<'a>> {
+        let s = self.rest.trim_start();
+        self.rest = s;
+
+        if s.is_empty() || s == "--" || s.starts_with("-- ") {
+            return None;
+        }
+
+        // Find the end of the token, honouring quotes
+        let mut in_quote = false;
+        let mut end = s.len();
+        for (i, c) in s.char_indices() {
+            match c {
+                '"' => in_quote = !in_quote,
+                c if c.is_whitespace() && !in_quote => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let token = strip_quotes(&s[..end]);
+        self.rest = &s[end..];
+
+        let arg = match token.split_once('=') {
+            Some((key, value)) => Arg { key, value: Some(strip_quotes(value)) },
+            None => Arg { key: token, value: None },
+        };
+
+        Some(arg)
+    }
+}
+
+/// Remove one pair of surrounding double quotes
+fn strip_quotes(s: &str) -> &str {
+    s.strip_prefix('"')
+        .map(|inner| inner.strip_suffix('"').unwrap_or(inner))
+        .unwrap_or(s)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(s: &str) -> [Option<Arg<'_>>; 4] {
+        let mut out = [None; 4];
+        for (slot, arg) in out.iter_mut().zip(Cmdline::new(s).args()) {
+            *slot = Some(arg);
+        }
+        out
+    }
+
+    #[test]
+    fn test_simple_args() {
+        let args = collect("quiet root=/dev/sda1  loglevel=3");
+        assert_eq!(args[0], Some(Arg { key: "quiet", value: None }));
+        assert_eq!(args[1], Some(Arg { key: "root", value: Some("/dev/sda1") }));
+        assert_eq!(args[2], Some(Arg { key: "loglevel", value: Some("3") }));
+        assert_eq!(args[3], None);
+    }
+
+    #[test]
+    fn test_quoted_values() {
+        let args = collect("console=\"ttyS0 115200\" \"init=/bin/sh -x\"");
+        assert_eq!(args[0], Some(Arg { key: "console", value: Some("ttyS0 115200") }));
+        assert_eq!(args[1], Some(Arg { key: "init", value: Some("/bin/sh -x") }));
+    }
+
+    #[test]
+    fn test_init_args() {
+        let cmdline = Cmdline::new("quiet -- single --verbose");
+        assert_eq!(cmdline.args().count(), 1);
+        assert_eq!(cmdline.init_args(), "single --verbose");
+        assert_eq!(Cmdline::new("quiet").init_args(), "");
+    }
+
+    #[test]
+    fn test_last_occurrence_wins() {
+        let cmdline = Cmdline::new("loglevel=3 loglevel=7 debug");
+        assert_eq!(cmdline.get("loglevel"), Some(Some("7")));
+        assert_eq!(cmdline.get("debug"), Some(None));
+        assert_eq!(cmdline.get("missing"), None);
+        assert!(cmdline.contains("debug"));
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(Cmdline::new("a=\"b c\"").check().is_ok());
+        assert_eq!(
+            Cmdline::new("a=\"b c").check(),
+            Err(CmdlineError::UnterminatedQuote(2))
+        );
+    }
+}
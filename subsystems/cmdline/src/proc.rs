@@ -0,0 +1,192 @@
+//! `/proc` exposure
+//!
+//! Renders the command line and every registered parameter carrying
+//! [`ParamFlags::PROC`] as read-only pseudo files. The procfs driver lists
+//! [`entries`] and calls [`ProcEntry::render`] on read:
+//!
+//! ```text
+//! /proc/cmdline                          raw command line
+//! /proc/sys/kernel/cmdline/<name>        effective value of <name>
+//! ```
+
+use core::fmt::{self, Write};
+
+use crate::param::{ParamFlags, ParamSpec, Value};
+use crate::parse::Cmdline;
+use crate::registry::Registry;
+
+/// Directory holding one file per parameter
+pub const PARAM_DIR: &str = "sys/kernel/cmdline";
+
+/// A pseudo file under `/proc`
+#[derive(Debug, Clone, Copy)]
+pub enum ProcEntry {
+    /// `/proc/cmdline`
+    Cmdline,
+    /// `/proc/sys/kernel/cmdline/<name>`
+    Param(&'static ParamSpec),
+}
+
+impl ProcEntry {
+    /// File name relative to its directory
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Cmdline => "cmdline",
+            Self::Param(spec) => spec.name,
+        }
+    }
+
+    /// Directory relative to `/proc`
+    pub const fn dir(&self) -> &'static str {
+        match self {
+            Self::Cmdline => "",
+            Self::Param(_) => PARAM_DIR,
+        }
+    }
+
+    /// Render the file contents
+    pub fn render<const N: usize>(
+        &self,
+        registry: &Registry<N>,
+        cmdline: &Cmdline<'_>,
+        out: &mut dyn Write,
+    ) -> fmt::Result {
+        match self {
+            Self::Cmdline => writeln!(out, "{}", cmdline.as_str()),
+            Self::Param(spec) => match registry.value(cmdline, spec.name) {
+                Ok(value) => {
+                    write_value(out, &value)?;
+                    writeln!(out)
+                }
+                // Required parameter that was never given
+                Err(_) => writeln!(out),
+            },
+        }
+    }
+}
+
+/// All entries for a registry
+pub fn entries<const N: usize>(registry: &Registry<N>) -> impl Iterator<Item = ProcEntry> + '_ {
+    core::iter::once(ProcEntry::Cmdline).chain(
+        registry
+            .iter()
+            .filter(|spec| spec.flags.contains(ParamFlags::PROC))
+            .map(ProcEntry::Param),
+    )
+}
+
+/// Write a value in command-line syntax
+pub fn write_value(out: &mut dyn Write, value: &Value<'_>) -> fmt::Result {
+    match value {
+        Value::Flag(b) | Value::Bool(b) => write!(out, "{}", if *b { 1 } else { 0 }),
+        Value::Int(n) => write!(out, "{}", n),
+        Value::Size(n) => write!(out, "{}", n),
+        Value::Str(s) => write!(out, "{}", s),
+        Value::CpuMask(mask) => write_cpulist(out, *mask),
+    }
+}
+
+/// Write a CPU mask as a CPU list (`0-3,6`)
+pub fn write_cpulist(out: &mut dyn Write, mask: u64) -> fmt::Result {
+    let mut first = true;
+    let mut cpu = 0;
+    while cpu < u64::BITS {
+        if mask & (1 << cpu) == 0 {
+            cpu += 1;
+            continue;
+        }
+        let start = cpu;
+        while cpu + 1 < u64::BITS && mask & (1 << (cpu + 1)) != 0 {
+            cpu += 1;
+        }
+
+        if !first {
+            write!(out, ",")?;
+        }
+        first = false;
+        if start == cpu {
+            write!(out, "{}", start)?;
+        } else {
+            write!(out, "{}-{}", start, cpu)?;
+        }
+        cpu += 1;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fixed-size writer for tests
+    struct Buf {
+        data: [u8; 128],
+        len: usize,
+    }
+
+    impl Buf {
+        fn new() -> Self {
+            Self { data: [0; 128], len: 0 }
+        }
+
+        fn as_str(&self) -> &str {
+            core::str::from_utf8(&self.data[..self.len]).unwrap()
+        }
+    }
+
+    impl Write for Buf {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.data[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    static LEVEL: ParamSpec = ParamSpec::int("level", 0, 9).default_value("2");
+    static HIDDEN: ParamSpec = ParamSpec {
+        flags: ParamFlags::NONE,
+        ..ParamSpec::flag("hidden")
+    };
+
+    #[test]
+    fn test_entries() {
+        let mut registry: Registry<4> = Registry::new();
+        registry.register_all(&[&LEVEL, &HIDDEN]).unwrap();
+
+        let mut names = entries(&registry).map(|e| e.name());
+        assert_eq!(names.next(), Some("cmdline"));
+        assert_eq!(names.next(), Some("level"));
+        assert_eq!(names.next(), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut registry: Registry<4> = Registry::new();
+        registry.register(&LEVEL).unwrap();
+        let cmdline = Cmdline::new("quiet level=5");
+
+        let mut buf = Buf::new();
+        ProcEntry::Cmdline.render(&registry, &cmdline, &mut buf).unwrap();
+        assert_eq!(buf.as_str(), "quiet level=5\n");
+
+        let mut buf = Buf::new();
+        ProcEntry::Param(&LEVEL).render(&registry, &Cmdline::new(""), &mut buf).unwrap();
+        assert_eq!(buf.as_str(), "2\n");
+    }
+
+    #[test]
+    fn test_write_cpulist() {
+        let mut buf = Buf::new();
+        write_cpulist(&mut buf, 0b100_1111 | (1 << 63)).unwrap();
+        assert_eq!(buf.as_str(), "0-3,6,63");
+
+        let mut buf = Buf::new();
+        write_cpulist(&mut buf, 0).unwrap();
+        assert_eq!(buf.as_str(), "");
+    }
+}
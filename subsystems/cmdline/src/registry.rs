@@ -0,0 +1,163 @@
+//! Parameter registry
+//!
+//! A fixed-capacity table of `&'static ParamSpec` usable before the heap is
+//! up. Unregistered arguments are left alone: they belong to init or to
+//! subsystems that have not registered yet.
+
+use crate::error::{CmdlineError, CmdlineResult};
+use crate::param::{ParamFlags, ParamSpec, Value};
+use crate::parse::Cmdline;
+
+/// Fixed-capacity parameter registry
+pub struct Registry<const N: usize> {
+    params: [Option<&'static ParamSpec>; N],
+    count: usize,
+}
+
+impl<const N: usize> Registry<N> {
+    /// Create an empty registry
+    pub const fn new() -> Self {
+        Self {
+            params: [None; N],
+            count: 0,
+        }
+    }
+
+    /// Register a parameter
+    pub fn register(&mut self, spec: &'static ParamSpec) -> CmdlineResult<()> {
+        if self.find(spec.name).is_some() {
+            return Err(CmdlineError::Duplicate(spec.name));
+        }
+        if self.count == N {
+            return Err(CmdlineError::RegistryFull);
+        }
+
+        self.params[self.count] = Some(spec);
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Register several parameters
+    pub fn register_all(&mut self, specs: &[&'static ParamSpec]) -> CmdlineResult<()> {
+        specs.iter().try_for_each(|spec| self.register(spec))
+    }
+
+    /// Look up a parameter by name
+    pub fn find(&self, name: &str) -> Option<&'static ParamSpec> {
+        self.iter().find(|spec| spec.name == name)
+    }
+
+    /// Iterate over registered parameters
+    pub fn iter(&self) -> impl Iterator<Item = &'static ParamSpec> + '_ {
+        self.params[..self.count].iter().flatten().copied()
+    }
+
+    /// Number of registered parameters
+    pub const fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether the registry is empty
+    pub const fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Validate every registered parameter present on the command line
+    pub fn validate(&self, cmdline: &Cmdline<'_>) -> CmdlineResult<()> {
+        self.validate_with(cmdline, ParamFlags::NONE)
+    }
+
+    /// Validate only parameters carrying `flags`
+    pub fn validate_with(&self, cmdline: &Cmdline<'_>, flags: ParamFlags) -> CmdlineResult<()> {
+        cmdline.check()?;
+
+        for arg in cmdline.args() {
+            if let Some(spec) = self.find(arg.key) {
+                if spec.flags.contains(flags) {
+                    spec.parse(arg.value)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Typed value of a parameter, falling back to its default
+    pub fn value<'a>(&self, cmdline: &Cmdline<'a>, name: &str) -> CmdlineResult<Value<'a>> {
+        let spec = self.find(name).ok_or(CmdlineError::Unknown)?;
+
+        match cmdline.get(name) {
+            Some(value) => spec.parse(value),
+            None => spec.default_parsed(),
+        }
+    }
+}
+
+impl<const N: usize> Default for Registry<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static LOGLEVEL: ParamSpec = ParamSpec::int("loglevel", 0, 7).default_value("4");
+    static QUIET: ParamSpec = ParamSpec::flag("quiet").boot();
+    static ROOT: ParamSpec = ParamSpec::string("root", 64);
+
+    fn registry() -> Registry<4> {
+        let mut r = Registry::new();
+        r.register_all(&[&LOGLEVEL, &QUIET, &ROOT]).unwrap();
+        r
+    }
+
+    #[test]
+    fn test_register() {
+        let mut r = registry();
+        assert_eq!(r.len(), 3);
+        assert_eq!(r.register(&QUIET), Err(CmdlineError::Duplicate("quiet")));
+
+        static EXTRA: ParamSpec = ParamSpec::flag("extra");
+        static FULL: ParamSpec = ParamSpec::flag("full");
+        r.register(&EXTRA).unwrap();
+        assert_eq!(r.register(&FULL), Err(CmdlineError::RegistryFull));
+    }
+
+    #[test]
+    fn test_validate() {
+        let r = registry();
+        assert!(r.validate(&Cmdline::new("quiet loglevel=3 unknown=1")).is_ok());
+        assert_eq!(
+            r.validate(&Cmdline::new("loglevel=9")),
+            Err(CmdlineError::OutOfRange("loglevel"))
+        );
+        assert_eq!(
+            r.validate(&Cmdline::new("quiet=1")),
+            Err(CmdlineError::UnexpectedValue("quiet"))
+        );
+    }
+
+    #[test]
+    fn test_validate_boot_only() {
+        let r = registry();
+        let cmdline = Cmdline::new("loglevel=9 quiet");
+        assert!(r.validate_with(&cmdline, ParamFlags::BOOT).is_ok());
+        assert!(r.validate(&cmdline).is_err());
+    }
+
+    #[test]
+    fn test_value_and_default() {
+        let r = registry();
+        let cmdline = Cmdline::new("root=/dev/nvme0n1p2");
+        assert_eq!(r.value(&cmdline, "root"), Ok(Value::Str("/dev/nvme0n1p2")));
+        assert_eq!(r.value(&cmdline, "loglevel"), Ok(Value::Int(4)));
+        assert_eq!(r.value(&cmdline, "quiet"), Ok(Value::Flag(false)));
+        assert_eq!(r.value(&cmdline, "nope"), Err(CmdlineError::Unknown));
+    }
+}
@@ -98,6 +98,9 @@
 
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
 // =============================================================================
 // CORE MODULES
 // =============================================================================
@@ -110,6 +113,7 @@
 pub mod meta;
 pub mod neural;
 pub mod policy;
+pub mod replay;
 pub mod survivability;
 pub mod telemetry;
 pub mod temporal;
@@ -155,6 +159,7 @@
     Comparison, ComparisonOp, Condition, Policy, PolicyContext, PolicyEngine, PolicyEngineConfig,
     PolicyId, PolicyResult, PolicyRule, PolicyStatus, PolicyVersion, Priority, Value,
 };
+pub use replay::{DecisionLog, DecisionRecorder, DecisionRng, ReplayEntry, ReplayError};
 pub use survivability::{
     Anomaly, AnomalyDetector, Recovery, RecoveryStrategy, SurvivabilityCore, Threat, ThreatLevel,
     ThreatResponse,
@@ -415,6 +420,7 @@ pub fn global_mut() -> Option<&'static mut Cortex> {
     pub fn process_event(&mut self, event: CortexEvent) -> CortexResult {
         let start = Self::get_timestamp();
         EVENT_COUNTER.fetch_add(1, Ordering::Relaxed);
+        self.neural.record_event(&event);
 
         // Route through core bus
         self.bus.route_event(&event);
@@ -51,6 +51,7 @@
 use core::sync::atomic::{AtomicU64, Ordering};
 
 use crate::consciousness::{InvariantViolation, ViolationPrediction};
+use crate::replay::{DecisionLog, DecisionRecorder, DecisionRng};
 use crate::{CortexConfig, CortexEvent, CortexResult, DecisionAction, PatternId, SubsystemId};
 
 // =============================================================================
@@ -653,7 +654,7 @@ fn get_cycles() -> u64 {
 }
 
 /// Context for decision making
-#[derive(Default)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct DecisionContext {
     pub memory_usage: f64,
     pub cpu_load: f64,
@@ -842,6 +843,12 @@ pub struct NeuralEngine {
 
     /// Average decision time
     avg_decision_time: f64,
+
+    /// Random source for probabilistic decisions
+    rng: DecisionRng,
+
+    /// Decision log, while recording
+    recorder: Option<DecisionRecorder>,
 }
 
 impl NeuralEngine {
@@ -856,6 +863,8 @@ pub fn new(config: NeuralConfig) -> Self {
             prediction_accuracy: PredictionAccuracy::new(),
             decisions_made: 0,
             avg_decision_time: 0.0,
+            rng: DecisionRng::new(0),
+            recorder: None,
         };
 
         // Register built-in trees
@@ -1206,36 +1215,26 @@ pub fn decide(&mut self, event: &CortexEvent) -> Option<Decision> {
 
         // Select appropriate tree based on event
         let tree_id = self.select_tree(event);
+        let decision = self.run_tree(tree_id);
 
-        if let Some(tree) = self.trees.get_mut(&tree_id) {
-            let decision = tree.decide(&self.context);
-
-            if let Some(ref d) = decision {
-                self.decisions_made += 1;
-
-                // Update rolling average
-                let alpha = 0.1;
-                self.avg_decision_time =
-                    self.avg_decision_time * (1.0 - alpha) + d.decision_time as f64 * alpha;
-            }
+        if let Some(ref d) = decision {
+            self.decisions_made += 1;
 
-            decision
-        } else {
-            None
+            // Update rolling average
+            let alpha = 0.1;
+            self.avg_decision_time =
+                self.avg_decision_time * (1.0 - alpha) + d.decision_time as f64 * alpha;
         }
+
+        decision
     }
 
     /// Decide action for invariant violation
     pub fn decide_violation(&mut self, violation: &InvariantViolation) -> Option<Decision> {
         self.context.violation_severity = violation.severity as u32;
 
-        let tree_id = DecisionTreeId(3); // Security tree
-
-        if let Some(tree) = self.trees.get_mut(&tree_id) {
-            tree.decide(&self.context)
-        } else {
-            None
-        }
+        // Security tree
+        self.run_tree(DecisionTreeId(3))
     }
 
     /// Decide action for prediction
@@ -1243,18 +1242,80 @@ pub fn decide_prediction(&mut self, prediction: &Prediction) -> Option<Decision>
         match prediction.target {
             PredictionTarget::MemoryUsage => {
                 self.context.memory_usage = prediction.value;
-                let tree_id = DecisionTreeId(1);
-
-                if let Some(tree) = self.trees.get_mut(&tree_id) {
-                    tree.decide(&self.context)
-                } else {
-                    None
-                }
+                self.run_tree(DecisionTreeId(1))
             },
             _ => None,
         }
     }
 
+    /// Evaluate a tree against a given context
+    ///
+    /// Used by the replay harness; the engine's own context is replaced.
+    pub fn decide_with(
+        &mut self,
+        tree_id: DecisionTreeId,
+        context: &DecisionContext,
+    ) -> Option<Decision> {
+        self.context = context.clone();
+        self.run_tree(tree_id)
+    }
+
+    /// Evaluate a tree against the current context, recording it
+    fn run_tree(&mut self, tree_id: DecisionTreeId) -> Option<Decision> {
+        let decision = self.trees.get_mut(&tree_id)?.decide(&self.context);
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_decision(tree_id, &self.context, decision.as_ref());
+        }
+        decision
+    }
+
+    // =========================================================================
+    // Record / Replay
+    // =========================================================================
+
+    /// Start recording decision inputs, in a log of at most `budget` bytes
+    ///
+    /// The current RNG seed is the first entry. Restarts any recording in
+    /// progress.
+    pub fn start_recording(&mut self, budget: usize) {
+        let mut recorder = DecisionRecorder::new(budget);
+        recorder.record_seed(self.rng.seed());
+        self.recorder = Some(recorder);
+    }
+
+    /// Stop recording and take the log
+    pub fn stop_recording(&mut self) -> Option<DecisionLog> {
+        self.recorder.take().map(DecisionRecorder::finish)
+    }
+
+    /// Check if recording
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Record an event entering CORTEX (no-op unless recording)
+    pub fn record_event(&mut self, event: &CortexEvent) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_event(event);
+        }
+    }
+
+    /// Reseed the decision RNG
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = DecisionRng::new(seed);
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_seed(seed);
+        }
+    }
+
+    /// Random source for probabilistic decisions
+    ///
+    /// Draw from this rather than any other entropy source, so recorded
+    /// decisions replay identically.
+    pub fn rng(&mut self) -> &mut DecisionRng {
+        &mut self.rng
+    }
+
     /// Select appropriate tree for event
     fn select_tree(&self, event: &CortexEvent) -> DecisionTreeId {
         match event {
@@ -0,0 +1,695 @@
+//! # Decision Record / Replay
+//!
+//! Makes the neural decision pipeline reproducible offline. While
+//! recording, the engine logs every input a decision depends on, and the
+//! outcome it produced:
+//!
+//! - **Seeds** of the decision RNG
+//! - **Events** entering CORTEX (category and priority, in order)
+//! - **Metrics** snapshots: the decision context a tree was evaluated
+//!   against (written only when it changed since the last snapshot)
+//! - **Decisions**: the tree evaluated and a digest of its result
+//!
+//! The replay harness (`std` feature) feeds a log through a fresh
+//! [`NeuralEngine`](crate::neural::NeuralEngine), re-evaluating each
+//! decision against its recorded context, and reports every decision whose
+//! result differs. Replaying one log against two builds bisects a change
+//! in AI behavior to the first diverging decision.
+//!
+//! ## Log Format
+//!
+//! ```text
+//! header    "HXRP" version:u8
+//! SEED      0x01 seed:varint
+//! EVENT     0x02 category:u8 priority:u8
+//! METRICS   0x03 memory cpu latency ctxsw pgfault:f64 procs:varint
+//!                severity:varint health:f64
+//! DECISION  0x04 tree:varint 0x00                                  (none)
+//!                tree:varint 0x01 action:u8 path_len:varint
+//!                path_hash:u64 confidence:f64                      (made)
+//! ```
+//!
+//! Integers are LEB128 varints unless sized; sized fields and `f64` bit
+//! patterns are little-endian.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::neural::{Decision, DecisionContext, DecisionTreeId};
+#[cfg(feature = "std")]
+use crate::neural::{NeuralConfig, NeuralEngine};
+use crate::{CortexEvent, DecisionAction};
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Log magic
+pub const LOG_MAGIC: [u8; 4] = *b"HXRP";
+
+/// Log format version
+pub const LOG_VERSION: u8 = 1;
+
+/// Default recording budget (bytes)
+pub const DEFAULT_LOG_BUDGET: usize = 1024 * 1024;
+
+const TAG_SEED: u8 = 0x01;
+const TAG_EVENT: u8 = 0x02;
+const TAG_METRICS: u8 = 0x03;
+const TAG_DECISION: u8 = 0x04;
+
+// =============================================================================
+// DETERMINISTIC RNG
+// =============================================================================
+
+/// Seedable random source for probabilistic decisions
+///
+/// xorshift64*: every draw is a pure function of the seed, so a recorded
+/// seed reproduces the whole sequence.
+#[derive(Debug, Clone)]
+pub struct DecisionRng {
+    seed: u64,
+    state: u64,
+}
+
+impl DecisionRng {
+    /// Create from a seed (0 is remapped, xorshift cannot leave it)
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+        }
+    }
+
+    /// Seed this source was created from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform value in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// =============================================================================
+// ENTRIES
+// =============================================================================
+
+/// Compact fingerprint of a decision's result
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecisionDigest {
+    /// Action kind (see [`action_kind`])
+    pub action: u8,
+    /// Nodes visited
+    pub path_len: u32,
+    /// FNV-1a hash of the visited node IDs
+    pub path_hash: u64,
+    /// Leaf confidence
+    pub confidence: f64,
+}
+
+impl DecisionDigest {
+    /// Digest a decision
+    pub fn of(decision: &Decision) -> Self {
+        let mut hash = 0xCBF2_9CE4_8422_2325u64;
+        for node in &decision.node_path {
+            for byte in node.0.to_le_bytes() {
+                hash = (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3);
+            }
+        }
+        Self {
+            action: action_kind(&decision.action),
+            path_len: decision.node_path.len() as u32,
+            path_hash: hash,
+            confidence: decision.confidence.0,
+        }
+    }
+}
+
+/// Stable numeric kind of an action
+pub fn action_kind(action: &DecisionAction) -> u8 {
+    match action {
+        DecisionAction::NoOp => 0,
+        DecisionAction::AdjustScheduler(_) => 1,
+        DecisionAction::AdjustMemory(_) => 2,
+        DecisionAction::IsolateSubsystem(_) => 3,
+        DecisionAction::HotSwap(_) => 4,
+        DecisionAction::Rollback(_) => 5,
+        DecisionAction::ReconfigureMMU(_) => 6,
+        DecisionAction::DisablePath(_) => 7,
+        DecisionAction::Custom(_) => 8,
+    }
+}
+
+/// One log entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayEntry {
+    /// Decision RNG reseeded
+    Seed(u64),
+
+    /// Event entered CORTEX
+    Event {
+        /// [`EventCategory`](crate::EventCategory) as `u8`
+        category: u8,
+        /// [`EventPriority`](crate::EventPriority) as `u8`
+        priority: u8,
+    },
+
+    /// Decision context for the following decisions
+    Metrics(DecisionContext),
+
+    /// Tree evaluated, with its result
+    Decision {
+        /// Tree evaluated
+        tree: DecisionTreeId,
+        /// Result (`None`: the tree made no decision)
+        outcome: Option<DecisionDigest>,
+    },
+}
+
+/// Log decoding errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Missing or wrong magic
+    BadHeader,
+    /// Log written by a newer format
+    UnsupportedVersion(u8),
+    /// Log ends inside an entry
+    Truncated {
+        /// Offset of the entry
+        offset: usize,
+    },
+    /// Unknown entry tag
+    UnknownTag {
+        /// Offset of the entry
+        offset: usize,
+        /// Tag byte
+        tag: u8,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadHeader => write!(f, "not a decision log"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported log version {}", v),
+            Self::Truncated { offset } => write!(f, "log truncated in entry at {:#x}", offset),
+            Self::UnknownTag { offset, tag } => {
+                write!(f, "unknown entry tag {:#04x} at {:#x}", tag, offset)
+            },
+        }
+    }
+}
+
+// =============================================================================
+// RECORDER
+// =============================================================================
+
+/// Builds a decision log
+///
+/// Recording stops (and [`DecisionRecorder::truncated`] is set) once the
+/// log reaches its byte budget, so a long-running system cannot grow it
+/// without bound.
+pub struct DecisionRecorder {
+    log: Vec<u8>,
+    budget: usize,
+    last_metrics: Option<DecisionContext>,
+    decisions: u64,
+    truncated: bool,
+}
+
+impl DecisionRecorder {
+    /// Create a recorder limited to `budget` bytes
+    pub fn new(budget: usize) -> Self {
+        let mut log = Vec::with_capacity(budget.min(4096));
+        log.extend_from_slice(&LOG_MAGIC);
+        log.push(LOG_VERSION);
+        Self {
+            log,
+            budget,
+            last_metrics: None,
+            decisions: 0,
+            truncated: false,
+        }
+    }
+
+    /// Log size so far (bytes)
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Check if nothing but the header was written
+    pub fn is_empty(&self) -> bool {
+        self.log.len() == LOG_MAGIC.len() + 1
+    }
+
+    /// Decisions recorded
+    pub fn decisions(&self) -> u64 {
+        self.decisions
+    }
+
+    /// Check if entries were dropped because the budget was reached
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Record a reseed of the decision RNG
+    pub fn record_seed(&mut self, seed: u64) {
+        let mut entry = Vec::with_capacity(11);
+        entry.push(TAG_SEED);
+        put_varint(&mut entry, seed);
+        self.commit(&entry);
+    }
+
+    /// Record an event entering CORTEX
+    pub fn record_event(&mut self, event: &CortexEvent) {
+        self.commit(&[
+            TAG_EVENT,
+            event.category() as u8,
+            event.default_priority() as u8,
+        ]);
+    }
+
+    /// Record that `tree` was evaluated against `context`
+    pub fn record_decision(
+        &mut self,
+        tree: DecisionTreeId,
+        context: &DecisionContext,
+        decision: Option<&Decision>,
+    ) {
+        let mut entry = Vec::with_capacity(96);
+        let metrics_changed = self.last_metrics.as_ref() != Some(context);
+        if metrics_changed {
+            put_metrics(&mut entry, context);
+        }
+
+        entry.push(TAG_DECISION);
+        put_varint(&mut entry, tree.0);
+        match decision.map(DecisionDigest::of) {
+            None => entry.push(0),
+            Some(digest) => {
+                entry.push(1);
+                entry.push(digest.action);
+                put_varint(&mut entry, digest.path_len as u64);
+                entry.extend_from_slice(&digest.path_hash.to_le_bytes());
+                entry.extend_from_slice(&digest.confidence.to_bits().to_le_bytes());
+            },
+        }
+
+        if self.commit(&entry) {
+            self.decisions += 1;
+            if metrics_changed {
+                self.last_metrics = Some(context.clone());
+            }
+        }
+    }
+
+    /// Append an entry unless it would exceed the budget
+    fn commit(&mut self, entry: &[u8]) -> bool {
+        if self.truncated || self.log.len() + entry.len() > self.budget {
+            self.truncated = true;
+            return false;
+        }
+        self.log.extend_from_slice(entry);
+        true
+    }
+
+    /// Finish recording
+    pub fn finish(self) -> DecisionLog {
+        DecisionLog { bytes: self.log }
+    }
+}
+
+fn put_metrics(out: &mut Vec<u8>, context: &DecisionContext) {
+    out.push(TAG_METRICS);
+    for value in [
+        context.memory_usage,
+        context.cpu_load,
+        context.interrupt_latency,
+        context.context_switch_rate,
+        context.page_fault_rate,
+    ] {
+        out.extend_from_slice(&value.to_bits().to_le_bytes());
+    }
+    put_varint(out, context.process_count);
+    put_varint(out, context.violation_severity as u64);
+    out.extend_from_slice(&context.subsystem_health.to_bits().to_le_bytes());
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+// =============================================================================
+// LOG
+// =============================================================================
+
+/// A recorded decision log
+#[derive(Debug, Clone)]
+pub struct DecisionLog {
+    bytes: Vec<u8>,
+}
+
+impl DecisionLog {
+    /// Wrap raw log bytes, checking the header
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, ReplayError> {
+        if bytes.len() < LOG_MAGIC.len() + 1 || bytes[..LOG_MAGIC.len()] != LOG_MAGIC {
+            return Err(ReplayError::BadHeader);
+        }
+        match bytes[LOG_MAGIC.len()] {
+            LOG_VERSION => Ok(Self { bytes }),
+            version => Err(ReplayError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Raw log bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Decode the entries in order
+    pub fn entries(&self) -> LogReader<'_> {
+        LogReader {
+            bytes: &self.bytes,
+            pos: LOG_MAGIC.len() + 1,
+            failed: false,
+        }
+    }
+}
+
+/// Iterator over the entries of a [`DecisionLog`]
+///
+/// Yields one error and stops at the first malformed entry.
+pub struct LogReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    failed: bool,
+}
+
+impl LogReader<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let raw = self.bytes.get(self.pos..self.pos + 8)?;
+        self.pos += 8;
+        Some(u64::from_le_bytes(raw.try_into().ok()?))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        self.u64().map(f64::from_bits)
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn entry(&mut self, tag: u8) -> Option<ReplayEntry> {
+        Some(match tag {
+            TAG_SEED => ReplayEntry::Seed(self.varint()?),
+            TAG_EVENT => ReplayEntry::Event {
+                category: self.byte()?,
+                priority: self.byte()?,
+            },
+            TAG_METRICS => ReplayEntry::Metrics(DecisionContext {
+                memory_usage: self.f64()?,
+                cpu_load: self.f64()?,
+                interrupt_latency: self.f64()?,
+                context_switch_rate: self.f64()?,
+                page_fault_rate: self.f64()?,
+                process_count: self.varint()?,
+                violation_severity: self.varint()? as u32,
+                subsystem_health: self.f64()?,
+            }),
+            TAG_DECISION => {
+                let tree = DecisionTreeId(self.varint()?);
+                let outcome = match self.byte()? {
+                    0 => None,
+                    _ => Some(DecisionDigest {
+                        action: self.byte()?,
+                        path_len: self.varint()? as u32,
+                        path_hash: self.u64()?,
+                        confidence: self.f64()?,
+                    }),
+                };
+                ReplayEntry::Decision { tree, outcome }
+            },
+            _ => return None,
+        })
+    }
+}
+
+impl Iterator for LogReader<'_> {
+    type Item = Result<ReplayEntry, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos >= self.bytes.len() {
+            return None;
+        }
+        let offset = self.pos;
+        let tag = self.byte()?;
+        let entry = self.entry(tag);
+        if entry.is_none() {
+            self.failed = true;
+            let known = matches!(tag, TAG_SEED | TAG_EVENT | TAG_METRICS | TAG_DECISION);
+            return Some(Err(if known {
+                ReplayError::Truncated { offset }
+            } else {
+                ReplayError::UnknownTag { offset, tag }
+            }));
+        }
+        entry.map(Ok)
+    }
+}
+
+// =============================================================================
+// REPLAY HARNESS
+// =============================================================================
+
+/// A decision whose replayed result differs from the recorded one
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Decision index in the log
+    pub index: u64,
+    /// Tree evaluated
+    pub tree: DecisionTreeId,
+    /// Recorded result
+    pub recorded: Option<DecisionDigest>,
+    /// Replayed result
+    pub replayed: Option<DecisionDigest>,
+    /// Events logged before the decision
+    pub events_before: u64,
+}
+
+/// Result of replaying a log
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    /// Decisions replayed
+    pub decisions: u64,
+    /// Events in the log
+    pub events: u64,
+    /// Decisions that replayed differently, in log order
+    pub divergences: Vec<Divergence>,
+}
+
+#[cfg(feature = "std")]
+impl ReplayReport {
+    /// First decision that replayed differently
+    pub fn first_divergence(&self) -> Option<&Divergence> {
+        self.divergences.first()
+    }
+
+    /// Check if every decision replayed identically
+    pub fn is_faithful(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Offline re-execution of a decision log
+///
+/// Each run uses a fresh engine, so tree state (visit counts) matches the
+/// recording engine's at the start of the log.
+#[cfg(feature = "std")]
+pub struct Replayer {
+    config: NeuralConfig,
+}
+
+#[cfg(feature = "std")]
+impl Replayer {
+    /// Create a replayer for engines built from `config`
+    pub fn new(config: NeuralConfig) -> Self {
+        Self { config }
+    }
+
+    /// Read a log from a file
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<DecisionLog> {
+        let bytes = std::fs::read(path)?;
+        DecisionLog::from_bytes(bytes).map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, std::format!("{}", e))
+        })
+    }
+
+    /// Write a log to a file
+    pub fn save(log: &DecisionLog, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, log.as_bytes())
+    }
+
+    /// Replay a log, comparing every decision with its recording
+    pub fn run(&self, log: &DecisionLog) -> Result<ReplayReport, ReplayError> {
+        let mut engine = NeuralEngine::new(self.config.clone());
+        let mut context = DecisionContext::default();
+        let mut report = ReplayReport::default();
+
+        for entry in log.entries() {
+            match entry? {
+                ReplayEntry::Seed(seed) => engine.reseed(seed),
+                ReplayEntry::Event { .. } => report.events += 1,
+                ReplayEntry::Metrics(metrics) => context = metrics,
+                ReplayEntry::Decision { tree, outcome } => {
+                    let replayed = engine.decide_with(tree, &context);
+                    let replayed = replayed.as_ref().map(DecisionDigest::of);
+                    if replayed != outcome {
+                        report.divergences.push(Divergence {
+                            index: report.decisions,
+                            tree,
+                            recorded: outcome,
+                            replayed,
+                            events_before: report.events,
+                        });
+                    }
+                    report.decisions += 1;
+                },
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(memory: f64) -> DecisionContext {
+        DecisionContext {
+            memory_usage: memory,
+            process_count: 150,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rng_reproducible() {
+        let mut a = DecisionRng::new(42);
+        let mut b = DecisionRng::new(42);
+        for _ in 0..16 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        let x = DecisionRng::new(0).next_f64();
+        assert!((0.0..1.0).contains(&x));
+    }
+
+    #[test]
+    fn test_log_roundtrip() {
+        let mut recorder = DecisionRecorder::new(DEFAULT_LOG_BUDGET);
+        recorder.record_seed(0xDEAD_BEEF);
+        recorder.record_decision(DecisionTreeId(1), &context(95.0), None);
+        // Same context: no second metrics entry
+        recorder.record_decision(DecisionTreeId(2), &context(95.0), None);
+        recorder.record_decision(DecisionTreeId(1), &context(40.0), None);
+        assert_eq!(recorder.decisions(), 3);
+
+        let log = DecisionLog::from_bytes(recorder.finish().as_bytes().to_vec()).unwrap();
+        let entries: Vec<_> = log.entries().collect::<Result<_, _>>().unwrap();
+        assert_eq!(entries, [
+            ReplayEntry::Seed(0xDEAD_BEEF),
+            ReplayEntry::Metrics(context(95.0)),
+            ReplayEntry::Decision {
+                tree: DecisionTreeId(1),
+                outcome: None
+            },
+            ReplayEntry::Decision {
+                tree: DecisionTreeId(2),
+                outcome: None
+            },
+            ReplayEntry::Metrics(context(40.0)),
+            ReplayEntry::Decision {
+                tree: DecisionTreeId(1),
+                outcome: None
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_log_errors() {
+        assert_eq!(
+            DecisionLog::from_bytes(b"HXR".to_vec()).unwrap_err(),
+            ReplayError::BadHeader
+        );
+        assert_eq!(
+            DecisionLog::from_bytes(b"HXRP\x09".to_vec()).unwrap_err(),
+            ReplayError::UnsupportedVersion(9)
+        );
+
+        let log = DecisionLog::from_bytes(b"HXRP\x01\x01\x80".to_vec()).unwrap();
+        let entries: Vec<_> = log.entries().collect();
+        assert_eq!(entries, [Err(ReplayError::Truncated { offset: 5 })]);
+
+        let log = DecisionLog::from_bytes(b"HXRP\x01\x7F".to_vec()).unwrap();
+        let entries: Vec<_> = log.entries().collect();
+        assert_eq!(entries, [Err(ReplayError::UnknownTag {
+            offset: 5,
+            tag: 0x7F
+        })]);
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut recorder = DecisionRecorder::new(LOG_MAGIC.len() + 1 + 4);
+        recorder.record_seed(1);
+        assert!(!recorder.truncated());
+        recorder.record_decision(DecisionTreeId(1), &context(10.0), None);
+        assert!(recorder.truncated());
+        assert_eq!(recorder.decisions(), 0);
+    }
+}
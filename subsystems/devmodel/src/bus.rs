@@ -0,0 +1,326 @@
+//! Emulated PCI bus
+//!
+//! Holds device models by bus/device/function, serves configuration
+//! accesses through [`ConfigAccess`] and routes MMIO and port I/O to the
+//! device whose BAR decodes the address. Devices only see guest memory
+//! while bus mastering is enabled in their command register.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use core::any::Any;
+use core::fmt;
+
+use crate::config::{command, BarKind, ConfigSpace};
+use crate::error::{DevModelError, DevModelResult};
+use crate::memory::GuestMemory;
+
+/// Bus/device/function address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bdf {
+    /// Bus number
+    pub bus: u8,
+    /// Device number (0-31)
+    pub device: u8,
+    /// Function number (0-7)
+    pub function: u8,
+}
+
+impl Bdf {
+    /// Create an address
+    pub const fn new(bus: u8, device: u8, function: u8) -> Self {
+        Self { bus, device, function }
+    }
+}
+
+impl fmt::Display for Bdf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}:{:02x}.{}", self.bus, self.device, self.function)
+    }
+}
+
+/// Configuration space access, as used by enumeration code
+///
+/// Absent functions read as all ones; writes to them are dropped.
+pub trait ConfigAccess {
+    /// Read a dword (`offset` is dword aligned)
+    fn read32(&self, bdf: Bdf, offset: u16) -> u32;
+
+    /// Write a dword (`offset` is dword aligned)
+    fn write32(&mut self, bdf: Bdf, offset: u16, value: u32);
+
+    /// Read a word
+    fn read16(&self, bdf: Bdf, offset: u16) -> u16 {
+        (self.read32(bdf, offset & !3) >> ((offset & 2) * 8)) as u16
+    }
+
+    /// Read a byte
+    fn read8(&self, bdf: Bdf, offset: u16) -> u8 {
+        (self.read32(bdf, offset & !3) >> ((offset & 3) * 8)) as u8
+    }
+}
+
+/// An emulated PCI function
+pub trait DeviceModel: Any + Send {
+    /// Configuration space
+    fn config(&self) -> &ConfigSpace;
+
+    /// Configuration space (mutable)
+    fn config_mut(&mut self) -> &mut ConfigSpace;
+
+    /// Read `width` bytes at `offset` within BAR `bar`
+    fn bar_read(&mut self, bar: usize, offset: u64, width: usize) -> u64;
+
+    /// Write `width` bytes at `offset` within BAR `bar`
+    ///
+    /// `dma` is `None` while bus mastering is disabled.
+    fn bar_write(
+        &mut self,
+        bar: usize,
+        offset: u64,
+        width: usize,
+        value: u64,
+        dma: Option<&mut GuestMemory>,
+    );
+
+    /// Downcast support for tests inspecting device state
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// An emulated PCI segment with guest memory
+pub struct PciBus {
+    devices: BTreeMap<Bdf, Box<dyn DeviceModel>>,
+    memory: GuestMemory,
+}
+
+impl PciBus {
+    /// Create an empty bus
+    pub fn new(memory: GuestMemory) -> Self {
+        Self {
+            devices: BTreeMap::new(),
+            memory,
+        }
+    }
+
+    /// Attach a device model
+    pub fn attach(&mut self, bdf: Bdf, device: impl DeviceModel) -> DevModelResult<()> {
+        if self.devices.contains_key(&bdf) {
+            return Err(DevModelError::SlotInUse);
+        }
+        self.devices.insert(bdf, Box::new(device));
+        Ok(())
+    }
+
+    /// Detach a device model (hot-unplug)
+    pub fn detach(&mut self, bdf: Bdf) -> DevModelResult<()> {
+        self.devices.remove(&bdf).map(|_| ()).ok_or(DevModelError::NoDevice)
+    }
+
+    /// Attached addresses, in bus order
+    pub fn addresses(&self) -> impl Iterator<Item = Bdf> + '_ {
+        self.devices.keys().copied()
+    }
+
+    /// Concrete device model at an address
+    pub fn device_mut<T: DeviceModel>(&mut self, bdf: Bdf) -> Option<&mut T> {
+        self.devices.get_mut(&bdf)?.as_any_mut().downcast_mut()
+    }
+
+    /// Guest memory
+    pub fn memory(&self) -> &GuestMemory {
+        &self.memory
+    }
+
+    /// Guest memory (mutable)
+    pub fn memory_mut(&mut self) -> &mut GuestMemory {
+        &mut self.memory
+    }
+
+    /// Find the device and BAR decoding an address
+    fn decode(&self, io: bool, addr: u64, width: usize) -> DevModelResult<(Bdf, usize, u64)> {
+        if !matches!(width, 1 | 2 | 4 | 8) {
+            return Err(DevModelError::InvalidWidth(width));
+        }
+        if addr % width as u64 != 0 {
+            return Err(DevModelError::Unaligned(addr));
+        }
+
+        for (&bdf, device) in &self.devices {
+            for index in 0..6 {
+                let Some((kind, base, size)) = device.config().bar(index) else {
+                    continue;
+                };
+                if (kind == BarKind::Io) == io && addr >= base && addr + width as u64 <= base + size {
+                    return Ok((bdf, index, addr - base));
+                }
+            }
+        }
+        Err(DevModelError::Unmapped(addr))
+    }
+
+    fn access_read(&mut self, io: bool, addr: u64, width: usize) -> DevModelResult<u64> {
+        let (bdf, index, offset) = self.decode(io, addr, width)?;
+        let device = self.devices.get_mut(&bdf).ok_or(DevModelError::NoDevice)?;
+        Ok(device.bar_read(index, offset, width))
+    }
+
+    fn access_write(&mut self, io: bool, addr: u64, width: usize, value: u64) -> DevModelResult<()> {
+        let (bdf, index, offset) = self.decode(io, addr, width)?;
+        let device = self.devices.get_mut(&bdf).ok_or(DevModelError::NoDevice)?;
+        let dma = (device.config().command() & command::BUS_MASTER != 0).then_some(&mut self.memory);
+        device.bar_write(index, offset, width, value, dma);
+        Ok(())
+    }
+
+    /// MMIO read
+    pub fn mmio_read(&mut self, addr: u64, width: usize) -> DevModelResult<u64> {
+        self.access_read(false, addr, width)
+    }
+
+    /// MMIO write
+    pub fn mmio_write(&mut self, addr: u64, width: usize, value: u64) -> DevModelResult<()> {
+        self.access_write(false, addr, width, value)
+    }
+
+    /// Port I/O read
+    pub fn io_read(&mut self, port: u64, width: usize) -> DevModelResult<u64> {
+        self.access_read(true, port, width)
+    }
+
+    /// Port I/O write
+    pub fn io_write(&mut self, port: u64, width: usize, value: u64) -> DevModelResult<()> {
+        self.access_write(true, port, width, value)
+    }
+}
+
+impl ConfigAccess for PciBus {
+    fn read32(&self, bdf: Bdf, offset: u16) -> u32 {
+        self.devices
+            .get(&bdf)
+            .and_then(|d| d.config().read(offset as usize & !3, 4).ok())
+            .unwrap_or(0xFFFF_FFFF)
+    }
+
+    fn write32(&mut self, bdf: Bdf, offset: u16, value: u32) {
+        if let Some(device) = self.devices.get_mut(&bdf) {
+            let _ = device.config_mut().write(offset as usize & !3, 4, value);
+        }
+    }
+}
+
+// ============================================================================
+// SHARED BUS
+// ============================================================================
+
+/// Thread-safe handle to a [`PciBus`]
+///
+/// Clones share the same bus, so a driver under test and the test body can
+/// both hold one.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct SharedBus(std::sync::Arc<std::sync::Mutex<PciBus>>);
+
+#[cfg(feature = "std")]
+impl SharedBus {
+    /// Share a bus
+    pub fn new(bus: PciBus) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(bus)))
+    }
+
+    /// Lock the bus
+    pub fn lock(&self) -> std::sync::MutexGuard<'_, PciBus> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(feature = "std")]
+impl ConfigAccess for SharedBus {
+    fn read32(&self, bdf: Bdf, offset: u16) -> u32 {
+        self.lock().read32(bdf, offset)
+    }
+
+    fn write32(&mut self, bdf: Bdf, offset: u16, value: u32) {
+        self.lock().write32(bdf, offset, value)
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::offset;
+
+    /// Device with one 4 KB register page that echoes writes
+    struct Scratch {
+        config: ConfigSpace,
+        regs: [u64; 512],
+    }
+
+    impl DeviceModel for Scratch {
+        fn config(&self) -> &ConfigSpace {
+            &self.config
+        }
+
+        fn config_mut(&mut self) -> &mut ConfigSpace {
+            &mut self.config
+        }
+
+        fn bar_read(&mut self, _bar: usize, offset: u64, _width: usize) -> u64 {
+            self.regs[offset as usize / 8]
+        }
+
+        fn bar_write(&mut self, _bar: usize, offset: u64, _width: usize, value: u64, dma: Option<&mut GuestMemory>) {
+            self.regs[offset as usize / 8] = value;
+            if let Some(mem) = dma {
+                let base = mem.base();
+                mem.write_u64(base, value).unwrap();
+            }
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn bus() -> PciBus {
+        let mut bus = PciBus::new(GuestMemory::new(0x1_0000, 0x1000));
+        let config = ConfigSpace::new(0xABCD, 0x0001, 0xFF, 0, 0).with_bar(0, BarKind::Memory32, 0x1000);
+        bus.attach(Bdf::new(0, 2, 0), Scratch { config, regs: [0; 512] }).unwrap();
+        bus
+    }
+
+    #[test]
+    fn test_config_access() {
+        let mut bus = bus();
+        let bdf = Bdf::new(0, 2, 0);
+        assert_eq!(bus.read16(bdf, 0x02), 0x0001);
+        assert_eq!(bus.read8(bdf, 0x0B), 0xFF);
+        assert_eq!(bus.read32(Bdf::new(0, 3, 0), 0), 0xFFFF_FFFF);
+        assert_eq!(bus.addresses().count(), 1);
+        assert_eq!(bus.attach(bdf, Scratch { config: ConfigSpace::new(1, 1, 0, 0, 0), regs: [0; 512] }), Err(DevModelError::SlotInUse));
+
+        bus.write32(bdf, offset::BAR0 as u16, 0xFFFF_FFFF);
+        assert_eq!(bus.read32(bdf, offset::BAR0 as u16), 0xFFFF_F000);
+    }
+
+    #[test]
+    fn test_mmio_routing() {
+        let mut bus = bus();
+        let bdf = Bdf::new(0, 2, 0);
+        bus.write32(bdf, offset::BAR0 as u16, 0xFE00_0000);
+        assert_eq!(bus.mmio_read(0xFE00_0008, 8), Err(DevModelError::Unmapped(0xFE00_0008)));
+
+        bus.write32(bdf, offset::COMMAND as u16, command::MEMORY_SPACE as u32);
+        bus.mmio_write(0xFE00_0008, 8, 42).unwrap();
+        assert_eq!(bus.mmio_read(0xFE00_0008, 8), Ok(42));
+        assert_eq!(bus.memory().read_u64(0x1_0000), Ok(0));
+
+        bus.write32(bdf, offset::COMMAND as u16, (command::MEMORY_SPACE | command::BUS_MASTER) as u32);
+        bus.mmio_write(0xFE00_0010, 8, 7).unwrap();
+        assert_eq!(bus.memory().read_u64(0x1_0000), Ok(7));
+        assert_eq!(bus.device_mut::<Scratch>(bdf).unwrap().regs[2], 7);
+        assert_eq!(bus.mmio_read(0xFE00_0003, 4), Err(DevModelError::Unaligned(0xFE00_0003)));
+    }
+}
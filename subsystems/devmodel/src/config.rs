@@ -0,0 +1,325 @@
+//! Emulated PCI configuration space
+//!
+//! Writes go through a per-byte write mask, so read-only fields stay
+//! intact and BAR sizing (write all ones, read back the size mask) works
+//! the way enumeration code expects. Status error bits are write-1-to-clear.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::{DevModelError, DevModelResult};
+
+/// PCIe configuration space size
+pub const CONFIG_SPACE_SIZE: usize = 4096;
+
+/// First byte available for capabilities
+const CAP_START: usize = 0x40;
+
+/// End of the legacy configuration space (capabilities live below it)
+const LEGACY_END: usize = 0x100;
+
+/// Standard header offsets
+pub mod offset {
+    /// Vendor ID
+    pub const VENDOR_ID: usize = 0x00;
+    /// Device ID
+    pub const DEVICE_ID: usize = 0x02;
+    /// Command register
+    pub const COMMAND: usize = 0x04;
+    /// Status register
+    pub const STATUS: usize = 0x06;
+    /// Revision ID
+    pub const REVISION: usize = 0x08;
+    /// Programming interface
+    pub const PROG_IF: usize = 0x09;
+    /// Subclass
+    pub const SUBCLASS: usize = 0x0A;
+    /// Class code
+    pub const CLASS: usize = 0x0B;
+    /// Header type
+    pub const HEADER_TYPE: usize = 0x0E;
+    /// First BAR
+    pub const BAR0: usize = 0x10;
+    /// Subsystem vendor ID
+    pub const SUBSYSTEM_VENDOR_ID: usize = 0x2C;
+    /// Subsystem ID
+    pub const SUBSYSTEM_ID: usize = 0x2E;
+    /// Capabilities pointer
+    pub const CAP_PTR: usize = 0x34;
+    /// Interrupt line
+    pub const INTERRUPT_LINE: usize = 0x3C;
+    /// Interrupt pin
+    pub const INTERRUPT_PIN: usize = 0x3D;
+}
+
+/// Command register bits
+pub mod command {
+    /// I/O space decoding
+    pub const IO_SPACE: u16 = 1 << 0;
+    /// Memory space decoding
+    pub const MEMORY_SPACE: u16 = 1 << 1;
+    /// Bus mastering (DMA)
+    pub const BUS_MASTER: u16 = 1 << 2;
+    /// INTx disable
+    pub const INTX_DISABLE: u16 = 1 << 10;
+}
+
+/// Status register: capability list present
+const STATUS_CAP_LIST: u16 = 1 << 4;
+
+/// Status register: write-1-to-clear error bits
+const STATUS_W1C: u16 = 0xF900;
+
+/// BAR decoding type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarKind {
+    /// 32-bit memory BAR
+    Memory32,
+    /// 64-bit memory BAR (uses the next BAR slot for the high half)
+    Memory64,
+    /// I/O port BAR
+    Io,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BarDef {
+    kind: BarKind,
+    size: u64,
+}
+
+/// A function's configuration space
+#[derive(Debug, Clone)]
+pub struct ConfigSpace {
+    data: Vec<u8>,
+    wmask: Vec<u8>,
+    w1c: Vec<u8>,
+    bars: [Option<BarDef>; 6],
+    /// Offset of the last capability's next pointer
+    cap_link: usize,
+    /// Next free capability offset
+    cap_next: usize,
+}
+
+impl ConfigSpace {
+    /// Create a type 0 header
+    pub fn new(vendor_id: u16, device_id: u16, class: u8, subclass: u8, prog_if: u8) -> Self {
+        let mut space = Self {
+            data: vec![0; CONFIG_SPACE_SIZE],
+            wmask: vec![0; CONFIG_SPACE_SIZE],
+            w1c: vec![0; CONFIG_SPACE_SIZE],
+            bars: [None; 6],
+            cap_link: offset::CAP_PTR,
+            cap_next: CAP_START,
+        };
+
+        space.set_u16(offset::VENDOR_ID, vendor_id);
+        space.set_u16(offset::DEVICE_ID, device_id);
+        space.data[offset::PROG_IF] = prog_if;
+        space.data[offset::SUBCLASS] = subclass;
+        space.data[offset::CLASS] = class;
+        space.data[offset::INTERRUPT_PIN] = 1;
+
+        let cmd = command::IO_SPACE | command::MEMORY_SPACE | command::BUS_MASTER | command::INTX_DISABLE;
+        space.wmask[offset::COMMAND..offset::COMMAND + 2].copy_from_slice(&cmd.to_le_bytes());
+        space.w1c[offset::STATUS..offset::STATUS + 2].copy_from_slice(&STATUS_W1C.to_le_bytes());
+        space.wmask[offset::INTERRUPT_LINE] = 0xFF;
+        space
+    }
+
+    /// Set the revision ID
+    pub fn with_revision(mut self, revision: u8) -> Self {
+        self.data[offset::REVISION] = revision;
+        self
+    }
+
+    /// Set the subsystem IDs
+    pub fn with_subsystem(mut self, vendor_id: u16, id: u16) -> Self {
+        self.set_u16(offset::SUBSYSTEM_VENDOR_ID, vendor_id);
+        self.set_u16(offset::SUBSYSTEM_ID, id);
+        self
+    }
+
+    /// Declare a BAR; `size` must be a power of two
+    pub fn with_bar(mut self, index: usize, kind: BarKind, size: u64) -> Self {
+        assert!(size.is_power_of_two(), "BAR size must be a power of two");
+        assert!(index < 6 && (kind != BarKind::Memory64 || index < 5));
+
+        let reg = offset::BAR0 + index * 4;
+        let (flags, mask) = match kind {
+            BarKind::Memory32 => (0x0, !(size as u32 - 1) & !0xF),
+            BarKind::Memory64 => (0x4, !(size - 1) as u32 & !0xF),
+            BarKind::Io => (0x1, !(size as u32 - 1) & !0x3),
+        };
+        self.data[reg..reg + 4].copy_from_slice(&(flags as u32).to_le_bytes());
+        self.wmask[reg..reg + 4].copy_from_slice(&mask.to_le_bytes());
+
+        if kind == BarKind::Memory64 {
+            let high = ((!(size - 1)) >> 32) as u32;
+            self.wmask[reg + 4..reg + 8].copy_from_slice(&high.to_le_bytes());
+        }
+
+        self.bars[index] = Some(BarDef { kind, size });
+        self
+    }
+
+    /// Append a capability; `body` follows the ID and next-pointer bytes
+    ///
+    /// Returns the capability's offset.
+    pub fn add_capability(&mut self, id: u8, body: &[u8]) -> DevModelResult<usize> {
+        let cap = self.cap_next;
+        let end = cap + 2 + body.len();
+        if end > LEGACY_END {
+            return Err(DevModelError::ConfigFull);
+        }
+
+        self.data[cap] = id;
+        self.data[cap + 1] = 0;
+        self.data[cap + 2..end].copy_from_slice(body);
+        self.data[self.cap_link] = cap as u8;
+
+        let status = self.get_u16(offset::STATUS) | STATUS_CAP_LIST;
+        self.set_u16(offset::STATUS, status);
+
+        self.cap_link = cap + 1;
+        self.cap_next = end.next_multiple_of(4);
+        Ok(cap)
+    }
+
+    /// Make bytes writable by software (device-specific registers)
+    pub fn set_writable(&mut self, offset: usize, mask: &[u8]) {
+        self.wmask[offset..offset + mask.len()].copy_from_slice(mask);
+    }
+
+    fn check(offset: usize, width: usize) -> DevModelResult<()> {
+        if !matches!(width, 1 | 2 | 4) {
+            return Err(DevModelError::InvalidWidth(width));
+        }
+        if offset % width != 0 || offset + width > CONFIG_SPACE_SIZE {
+            return Err(DevModelError::Unaligned(offset as u64));
+        }
+        Ok(())
+    }
+
+    /// Software read
+    pub fn read(&self, offset: usize, width: usize) -> DevModelResult<u32> {
+        Self::check(offset, width)?;
+        let mut buf = [0; 4];
+        buf[..width].copy_from_slice(&self.data[offset..offset + width]);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    /// Software write (masked; status error bits are write-1-to-clear)
+    pub fn write(&mut self, offset: usize, width: usize, value: u32) -> DevModelResult<()> {
+        Self::check(offset, width)?;
+        for (i, byte) in value.to_le_bytes()[..width].iter().enumerate() {
+            let at = offset + i;
+            let wmask = self.wmask[at];
+            self.data[at] = (self.data[at] & !wmask) | (byte & wmask);
+            self.data[at] &= !(byte & self.w1c[at]);
+        }
+        Ok(())
+    }
+
+    /// Read a 16-bit field directly (device side)
+    pub fn get_u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.data[offset], self.data[offset + 1]])
+    }
+
+    /// Set a 16-bit field directly, bypassing the write mask (device side)
+    pub fn set_u16(&mut self, offset: usize, value: u16) {
+        self.data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Command register
+    pub fn command(&self) -> u16 {
+        self.get_u16(offset::COMMAND)
+    }
+
+    /// Raise status error bits (device side)
+    pub fn raise_status(&mut self, bits: u16) {
+        let status = self.get_u16(offset::STATUS) | (bits & STATUS_W1C);
+        self.set_u16(offset::STATUS, status);
+    }
+
+    /// Decoded BAR: kind, address and size
+    ///
+    /// `None` if the BAR is not implemented, not yet assigned, or its
+    /// space is disabled in the command register.
+    pub fn bar(&self, index: usize) -> Option<(BarKind, u64, u64)> {
+        let def = (*self.bars.get(index)?)?;
+        let reg = offset::BAR0 + index * 4;
+        let low = self.read(reg, 4).ok()?;
+
+        let (addr, enable) = match def.kind {
+            BarKind::Memory32 => ((low & !0xF) as u64, command::MEMORY_SPACE),
+            BarKind::Memory64 => {
+                let high = self.read(reg + 4, 4).ok()?;
+                (((high as u64) << 32) | (low & !0xF) as u64, command::MEMORY_SPACE)
+            }
+            BarKind::Io => ((low & !0x3) as u64, command::IO_SPACE),
+        };
+
+        (addr != 0 && self.command() & enable != 0).then_some((def.kind, addr, def.size))
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn space() -> ConfigSpace {
+        ConfigSpace::new(0x1234, 0x5678, 0x01, 0x08, 0x02)
+            .with_bar(0, BarKind::Memory64, 0x4000)
+            .with_bar(2, BarKind::Io, 0x20)
+    }
+
+    #[test]
+    fn test_read_only_fields() {
+        let mut cfg = space();
+        cfg.write(offset::VENDOR_ID, 2, 0xFFFF).unwrap();
+        assert_eq!(cfg.read(offset::VENDOR_ID, 4).unwrap(), 0x5678_1234);
+        assert_eq!(cfg.read(offset::CLASS, 1).unwrap(), 0x01);
+        assert_eq!(cfg.read(offset::INTERRUPT_PIN, 1).unwrap(), 1);
+        assert_eq!(cfg.read(3, 2), Err(DevModelError::Unaligned(3)));
+    }
+
+    #[test]
+    fn test_bar_sizing() {
+        let mut cfg = space();
+        cfg.write(0x10, 4, 0xFFFF_FFFF).unwrap();
+        cfg.write(0x14, 4, 0xFFFF_FFFF).unwrap();
+        assert_eq!(cfg.read(0x10, 4).unwrap(), 0xFFFF_C004);
+        assert_eq!(cfg.read(0x14, 4).unwrap(), 0xFFFF_FFFF);
+
+        cfg.write(0x18, 4, 0xFFFF_FFFF).unwrap();
+        assert_eq!(cfg.read(0x18, 4).unwrap(), 0xFFFF_FFE1);
+
+        cfg.write(0x10, 4, 0xFEB0_0000).unwrap();
+        cfg.write(0x14, 4, 0x1).unwrap();
+        assert_eq!(cfg.bar(0), None);
+        cfg.write(offset::COMMAND, 2, command::MEMORY_SPACE as u32).unwrap();
+        assert_eq!(cfg.bar(0), Some((BarKind::Memory64, 0x1_FEB0_0000, 0x4000)));
+        assert_eq!(cfg.bar(1), None);
+    }
+
+    #[test]
+    fn test_status_w1c_and_capabilities() {
+        let mut cfg = space();
+        let msi = cfg.add_capability(0x05, &[0; 10]).unwrap();
+        let pcie = cfg.add_capability(0x10, &[0; 2]).unwrap();
+        assert_eq!(msi, 0x40);
+        assert_eq!(pcie, 0x4C);
+        assert_eq!(cfg.read(offset::CAP_PTR, 1).unwrap(), 0x40);
+        assert_eq!(cfg.read(msi + 1, 1).unwrap(), 0x4C);
+        assert_eq!(cfg.read(pcie + 1, 1).unwrap(), 0);
+
+        cfg.raise_status(1 << 13);
+        assert_eq!(cfg.read(offset::STATUS, 2).unwrap(), 0x2010);
+        cfg.write(offset::STATUS, 2, 1 << 13).unwrap();
+        assert_eq!(cfg.read(offset::STATUS, 2).unwrap(), 0x0010);
+    }
+}
@@ -0,0 +1,45 @@
+//! Device model error types
+
+use core::fmt;
+
+/// Device model error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DevModelError {
+    /// No function at the address
+    NoDevice,
+    /// A function is already attached at the address
+    SlotInUse,
+    /// Access width is not 1, 2, 4 or 8 bytes
+    InvalidWidth(usize),
+    /// Access is not naturally aligned
+    Unaligned(u64),
+    /// No enabled BAR decodes the address
+    Unmapped(u64),
+    /// DMA outside guest memory
+    BadDma(u64),
+    /// Guest memory exhausted
+    OutOfMemory,
+    /// Capability list does not fit in config space
+    ConfigFull,
+}
+
+impl fmt::Display for DevModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoDevice => write!(f, "no device at address"),
+            Self::SlotInUse => write!(f, "slot already in use"),
+            Self::InvalidWidth(width) => write!(f, "invalid access width {}", width),
+            Self::Unaligned(addr) => write!(f, "unaligned access at {:#x}", addr),
+            Self::Unmapped(addr) => write!(f, "no BAR decodes {:#x}", addr),
+            Self::BadDma(addr) => write!(f, "DMA outside guest memory at {:#x}", addr),
+            Self::OutOfMemory => write!(f, "guest memory exhausted"),
+            Self::ConfigFull => write!(f, "configuration space full"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DevModelError {}
+
+/// Result type for device model operations
+pub type DevModelResult<T> = Result<T, DevModelError>;
@@ -0,0 +1,91 @@
+//! # Helix Device Model
+//!
+//! Emulated PCI devices for running driver logic under `cargo test`,
+//! without QEMU or real hardware.
+//!
+//! ## Overview
+//!
+//! - **Config space**: 4 KB PCIe configuration space with read-only fields,
+//!   write-1-to-clear status bits, BAR sizing and capability lists
+//! - **Bus**: routes configuration, MMIO and port I/O accesses to devices
+//!   by bus/device/function and BAR decoding
+//! - **Guest memory**: DMA-visible memory devices read and write
+//! - **Devices**: an NVMe controller and a legacy virtio-blk device with
+//!   enough behavior for enumeration, queue setup and block I/O
+//!
+//! Devices act synchronously on register writes (e.g. a doorbell), so tests
+//! are fully deterministic.
+//!
+//! ## Architecture
+//!
+//! ```text
+//! ┌─────────────────────────────────────────────────────────────┐
+//! │                      DRIVER UNDER TEST                       │
+//! ├─────────────────────────────────────────────────────────────┤
+//! │        ConfigAccess           │     mmio/io read/write       │
+//! ├───────────────────────────────┴─────────────────────────────┤
+//! │                          PciBus                              │
+//! │  ┌─────────────┐  ┌─────────────┐  ┌─────────────────────┐  │
+//! │  │ ConfigSpace │  │ DeviceModel │  │     GuestMemory     │  │
+//! │  │ (per func)  │──│ (NVMe, blk) │──│  (DMA, queues)      │  │
+//! │  └─────────────┘  └─────────────┘  └─────────────────────┘  │
+//! └─────────────────────────────────────────────────────────────┘
+//! ```
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use helix_devmodel::{Bdf, ConfigAccess, GuestMemory, NvmeModel, PciBus};
+//!
+//! let mut bus = PciBus::new(GuestMemory::new(0x10_0000, 1 << 20));
+//! bus.attach(Bdf::new(0, 3, 0), NvmeModel::new(64)).unwrap();
+//!
+//! assert_eq!(bus.read16(Bdf::new(0, 3, 0), 0x00), 0x1B36);
+//! assert_eq!(bus.read16(Bdf::new(0, 4, 0), 0x00), 0xFFFF);
+//! ```
+//!
+//! ## Features
+//!
+//! - `std` (default): [`SharedBus`] for drivers that hold the bus across
+//!   threads, and `std::error::Error` for [`DevModelError`]
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(unsafe_op_in_unsafe_fn)]
+
+extern crate alloc;
+
+// ============================================================================
+// MODULES
+// ============================================================================
+
+/// Error types
+pub mod error;
+
+/// Emulated configuration space
+pub mod config;
+
+/// DMA-visible guest memory
+pub mod memory;
+
+/// Device trait and bus
+pub mod bus;
+
+/// NVMe controller model
+pub mod nvme;
+
+/// Legacy virtio-blk model
+pub mod virtio;
+
+// ============================================================================
+// RE-EXPORTS
+// ============================================================================
+
+pub use error::{DevModelError, DevModelResult};
+pub use config::{BarKind, ConfigSpace};
+pub use memory::GuestMemory;
+pub use bus::{Bdf, ConfigAccess, DeviceModel, PciBus};
+pub use nvme::NvmeModel;
+pub use virtio::VirtioBlkModel;
+
+#[cfg(feature = "std")]
+pub use bus::SharedBus;
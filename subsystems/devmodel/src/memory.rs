@@ -0,0 +1,145 @@
+//! Guest memory
+//!
+//! A contiguous, zero-initialized block of memory at a fixed bus address.
+//! Drivers place queues and buffers in it; devices access it as DMA.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::{DevModelError, DevModelResult};
+
+/// DMA-visible memory
+#[derive(Debug, Clone)]
+pub struct GuestMemory {
+    base: u64,
+    data: Vec<u8>,
+    /// Next free byte for [`GuestMemory::alloc`]
+    next: u64,
+}
+
+impl GuestMemory {
+    /// Create `size` bytes of memory at bus address `base`
+    pub fn new(base: u64, size: usize) -> Self {
+        Self {
+            base,
+            data: vec![0; size],
+            next: base,
+        }
+    }
+
+    /// Bus address of the first byte
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Size in bytes
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Allocate zeroed memory (never freed)
+    pub fn alloc(&mut self, size: usize, align: u64) -> DevModelResult<u64> {
+        let addr = self.next.next_multiple_of(align.max(1));
+        let end = addr + size as u64;
+        if end > self.base + self.data.len() as u64 {
+            return Err(DevModelError::OutOfMemory);
+        }
+        self.next = end;
+        Ok(addr)
+    }
+
+    fn range(&self, addr: u64, len: usize) -> DevModelResult<core::ops::Range<usize>> {
+        let start = addr
+            .checked_sub(self.base)
+            .ok_or(DevModelError::BadDma(addr))? as usize;
+        let end = start.checked_add(len).ok_or(DevModelError::BadDma(addr))?;
+        if end > self.data.len() {
+            return Err(DevModelError::BadDma(addr));
+        }
+        Ok(start..end)
+    }
+
+    /// Borrow bytes
+    pub fn slice(&self, addr: u64, len: usize) -> DevModelResult<&[u8]> {
+        let range = self.range(addr, len)?;
+        Ok(&self.data[range])
+    }
+
+    /// Borrow bytes mutably
+    pub fn slice_mut(&mut self, addr: u64, len: usize) -> DevModelResult<&mut [u8]> {
+        let range = self.range(addr, len)?;
+        Ok(&mut self.data[range])
+    }
+
+    /// Copy bytes out
+    pub fn read(&self, addr: u64, buf: &mut [u8]) -> DevModelResult<()> {
+        buf.copy_from_slice(self.slice(addr, buf.len())?);
+        Ok(())
+    }
+
+    /// Copy bytes in
+    pub fn write(&mut self, addr: u64, buf: &[u8]) -> DevModelResult<()> {
+        self.slice_mut(addr, buf.len())?.copy_from_slice(buf);
+        Ok(())
+    }
+
+    /// Read a little-endian `u16`
+    pub fn read_u16(&self, addr: u64) -> DevModelResult<u16> {
+        let mut buf = [0; 2];
+        self.read(addr, &mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    /// Read a little-endian `u32`
+    pub fn read_u32(&self, addr: u64) -> DevModelResult<u32> {
+        let mut buf = [0; 4];
+        self.read(addr, &mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    /// Read a little-endian `u64`
+    pub fn read_u64(&self, addr: u64) -> DevModelResult<u64> {
+        let mut buf = [0; 8];
+        self.read(addr, &mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Write a little-endian `u16`
+    pub fn write_u16(&mut self, addr: u64, value: u16) -> DevModelResult<()> {
+        self.write(addr, &value.to_le_bytes())
+    }
+
+    /// Write a little-endian `u32`
+    pub fn write_u32(&mut self, addr: u64, value: u32) -> DevModelResult<()> {
+        self.write(addr, &value.to_le_bytes())
+    }
+
+    /// Write a little-endian `u64`
+    pub fn write_u64(&mut self, addr: u64, value: u64) -> DevModelResult<()> {
+        self.write(addr, &value.to_le_bytes())
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alloc_and_access() {
+        let mut mem = GuestMemory::new(0x1000, 0x2000);
+        let a = mem.alloc(10, 1).unwrap();
+        let b = mem.alloc(16, 0x1000).unwrap();
+        assert_eq!(a, 0x1000);
+        assert_eq!(b, 0x2000);
+        assert_eq!(mem.alloc(0x1000, 1), Err(DevModelError::OutOfMemory));
+
+        mem.write_u32(b, 0xDEAD_BEEF).unwrap();
+        assert_eq!(mem.read_u16(b + 2).unwrap(), 0xDEAD);
+        assert_eq!(mem.read_u64(0xFFF), Err(DevModelError::BadDma(0xFFF)));
+        assert_eq!(mem.read_u32(0x2FFE), Err(DevModelError::BadDma(0x2FFE)));
+    }
+}
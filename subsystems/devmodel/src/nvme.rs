@@ -0,0 +1,795 @@
+//! NVMe controller model
+//!
+//! A single-namespace NVMe 1.4 controller with a RAM-backed disk:
+//!
+//! - Controller enable/disable and shutdown handshake (`CC`/`CSTS`)
+//! - Admin queue from `AQA`/`ASQ`/`ACQ`
+//! - Admin commands: Identify (controller, namespace, active list),
+//!   Set Features (number of queues), Create/Delete I/O queues
+//! - I/O commands: Read, Write, Flush, with PRP1/PRP2 and PRP lists
+//! - Dataset Management deallocate; deallocated blocks read back as zeroes
+//!
+//! Commands run when their submission queue tail doorbell is written;
+//! completions are posted with the phase tag before the write returns.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::bus::DeviceModel;
+use crate::config::{BarKind, ConfigSpace};
+use crate::memory::GuestMemory;
+
+/// Emulated vendor ID (Red Hat, as used by QEMU)
+pub const VENDOR_ID: u16 = 0x1B36;
+
+/// Emulated device ID (QEMU NVMe)
+pub const DEVICE_ID: u16 = 0x0010;
+
+/// Logical block size
+pub const BLOCK_SIZE: usize = 512;
+
+/// Memory page size (CC.MPS = 0)
+const PAGE_SIZE: u64 = 4096;
+
+/// BAR0 size
+const BAR_SIZE: u64 = 0x4000;
+
+/// Queue pairs supported (admin included)
+const MAX_QUEUES: usize = 16;
+
+/// Maximum queue entries (CAP.MQES + 1)
+const MAX_QUEUE_ENTRIES: u32 = 64;
+
+/// Register offsets
+mod reg {
+    pub const CAP: u64 = 0x00;
+    pub const VS: u64 = 0x08;
+    pub const INTMS: u64 = 0x0C;
+    pub const INTMC: u64 = 0x10;
+    pub const CC: u64 = 0x14;
+    pub const CSTS: u64 = 0x1C;
+    pub const AQA: u64 = 0x24;
+    pub const ASQ: u64 = 0x28;
+    pub const ACQ: u64 = 0x30;
+    pub const DOORBELL: u64 = 0x1000;
+}
+
+/// Completion status (SCT << 8 | SC)
+mod status {
+    pub const SUCCESS: u16 = 0x000;
+    pub const INVALID_OPCODE: u16 = 0x001;
+    pub const INVALID_FIELD: u16 = 0x002;
+    pub const DATA_TRANSFER_ERROR: u16 = 0x004;
+    pub const INVALID_NAMESPACE: u16 = 0x00B;
+    pub const LBA_OUT_OF_RANGE: u16 = 0x080;
+    pub const INVALID_QUEUE_ID: u16 = 0x101;
+    pub const INVALID_QUEUE_SIZE: u16 = 0x102;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SubmissionQueue {
+    base: u64,
+    size: u32,
+    head: u32,
+    cqid: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CompletionQueue {
+    base: u64,
+    size: u32,
+    tail: u32,
+    phase: bool,
+}
+
+/// Decoded submission queue entry
+#[derive(Debug, Clone, Copy)]
+struct Command {
+    opcode: u8,
+    cid: u16,
+    nsid: u32,
+    prp1: u64,
+    prp2: u64,
+    cdw10: u32,
+    cdw11: u32,
+    cdw12: u32,
+}
+
+impl Command {
+    fn parse(raw: &[u8]) -> Self {
+        let dw = |i: usize| u32::from_le_bytes([raw[i * 4], raw[i * 4 + 1], raw[i * 4 + 2], raw[i * 4 + 3]]);
+        let qw = |i: usize| dw(i) as u64 | ((dw(i + 1) as u64) << 32);
+        Self {
+            opcode: raw[0],
+            cid: (dw(0) >> 16) as u16,
+            nsid: dw(1),
+            prp1: qw(6),
+            prp2: qw(8),
+            cdw10: dw(10),
+            cdw11: dw(11),
+            cdw12: dw(12),
+        }
+    }
+}
+
+/// Emulated NVMe controller
+pub struct NvmeModel {
+    config: ConfigSpace,
+    disk: Vec<u8>,
+    serial: &'static str,
+    cc: u32,
+    csts: u32,
+    intmask: u32,
+    aqa: u32,
+    asq: u64,
+    acq: u64,
+    sq: [Option<SubmissionQueue>; MAX_QUEUES],
+    cq: [Option<CompletionQueue>; MAX_QUEUES],
+    commands: u64,
+}
+
+impl NvmeModel {
+    /// Create a controller with a zeroed namespace of `blocks` blocks
+    pub fn new(blocks: u64) -> Self {
+        let mut config = ConfigSpace::new(VENDOR_ID, DEVICE_ID, 0x01, 0x08, 0x02)
+            .with_subsystem(VENDOR_ID, 0x1100)
+            .with_bar(0, BarKind::Memory64, BAR_SIZE);
+        // PCI Express capability: version 2, endpoint
+        let _ = config.add_capability(0x10, &[0x02, 0x00]);
+
+        Self {
+            config,
+            disk: vec![0; blocks as usize * BLOCK_SIZE],
+            serial: "HELIX-NVME-0001",
+            cc: 0,
+            csts: 0,
+            intmask: 0,
+            aqa: 0,
+            asq: 0,
+            acq: 0,
+            sq: [None; MAX_QUEUES],
+            cq: [None; MAX_QUEUES],
+            commands: 0,
+        }
+    }
+
+    /// Namespace contents
+    pub fn disk(&self) -> &[u8] {
+        &self.disk
+    }
+
+    /// Namespace contents (mutable, e.g. to preload a disk image)
+    pub fn disk_mut(&mut self) -> &mut [u8] {
+        &mut self.disk
+    }
+
+    /// Commands processed so far
+    pub fn commands(&self) -> u64 {
+        self.commands
+    }
+
+    fn blocks(&self) -> u64 {
+        (self.disk.len() / BLOCK_SIZE) as u64
+    }
+
+    fn cap(&self) -> u64 {
+        (MAX_QUEUE_ENTRIES as u64 - 1)  // MQES
+            | (1 << 16)                 // CQR: contiguous queues required
+            | (20 << 24)                // TO: 10 s
+            | (1 << 37)                 // CSS: NVM command set
+    }
+
+    fn enable(&mut self) {
+        let sq_size = (self.aqa & 0xFFF) + 1;
+        let cq_size = ((self.aqa >> 16) & 0xFFF) + 1;
+        if self.asq == 0 || self.acq == 0 || sq_size < 2 || cq_size < 2 {
+            self.csts |= 1 << 1; // CFS
+            return;
+        }
+
+        self.sq[0] = Some(SubmissionQueue {
+            base: self.asq,
+            size: sq_size,
+            head: 0,
+            cqid: 0,
+        });
+        self.cq[0] = Some(CompletionQueue {
+            base: self.acq,
+            size: cq_size,
+            tail: 0,
+            phase: true,
+        });
+        self.csts |= 1;
+    }
+
+    fn reset(&mut self) {
+        self.sq = [None; MAX_QUEUES];
+        self.cq = [None; MAX_QUEUES];
+        self.csts = 0;
+    }
+
+    fn write_cc(&mut self, value: u32) {
+        let was_enabled = self.cc & 1 != 0;
+        self.cc = value;
+
+        match (was_enabled, value & 1 != 0) {
+            (false, true) => self.enable(),
+            (true, false) => self.reset(),
+            _ => {}
+        }
+
+        // Shutdown notification completes immediately
+        if (value >> 14) & 0x3 != 0 {
+            self.csts = (self.csts & !(0x3 << 2)) | (0x2 << 2);
+        }
+    }
+
+    fn doorbell(&mut self, offset: u64, value: u32, dma: Option<&mut GuestMemory>) {
+        let index = ((offset - reg::DOORBELL) / 4) as usize;
+        let qid = index / 2;
+        if qid >= MAX_QUEUES || index % 2 == 1 || self.csts & 1 == 0 {
+            // Completion head doorbells only free slots; nothing to do
+            return;
+        }
+        let Some(mem) = dma else {
+            return;
+        };
+
+        while let Some(sq) = self.sq[qid] {
+            if sq.head == value % sq.size {
+                break;
+            }
+
+            let mut raw = [0u8; 64];
+            if mem.read(sq.base + sq.head as u64 * 64, &mut raw).is_err() {
+                self.csts |= 1 << 1;
+                return;
+            }
+            let head = (sq.head + 1) % sq.size;
+            self.sq[qid] = Some(SubmissionQueue { head, ..sq });
+
+            let cmd = Command::parse(&raw);
+            let (status, result) = if qid == 0 {
+                self.admin(&cmd, mem)
+            } else {
+                self.io(&cmd, mem)
+            };
+            self.commands += 1;
+            self.complete(sq.cqid, qid as u16, head as u16, cmd.cid, status, result, mem);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn complete(
+        &mut self,
+        cqid: usize,
+        sqid: u16,
+        sq_head: u16,
+        cid: u16,
+        status: u16,
+        result: u32,
+        mem: &mut GuestMemory,
+    ) {
+        let Some(cq) = self.cq[cqid].as_mut() else {
+            return;
+        };
+
+        let mut entry = [0u8; 16];
+        entry[0..4].copy_from_slice(&result.to_le_bytes());
+        entry[8..10].copy_from_slice(&sq_head.to_le_bytes());
+        entry[10..12].copy_from_slice(&sqid.to_le_bytes());
+        entry[12..14].copy_from_slice(&cid.to_le_bytes());
+        entry[14..16].copy_from_slice(&((status << 1) | cq.phase as u16).to_le_bytes());
+
+        let _ = mem.write(cq.base + cq.tail as u64 * 16, &entry);
+        cq.tail = (cq.tail + 1) % cq.size;
+        if cq.tail == 0 {
+            cq.phase = !cq.phase;
+        }
+    }
+
+    fn admin(&mut self, cmd: &Command, mem: &mut GuestMemory) -> (u16, u32) {
+        match cmd.opcode {
+            // Delete I/O SQ
+            0x00 => self.delete_queue(cmd.cdw10 as usize & 0xFFFF, true),
+            // Create I/O SQ
+            0x01 => {
+                let qid = cmd.cdw10 as usize & 0xFFFF;
+                let size = (cmd.cdw10 >> 16) + 1;
+                let cqid = (cmd.cdw11 >> 16) as usize;
+                if qid == 0 || qid >= MAX_QUEUES || self.sq[qid].is_some() {
+                    return (status::INVALID_QUEUE_ID, 0);
+                }
+                if cqid >= MAX_QUEUES || self.cq[cqid].is_none() {
+                    return (status::INVALID_QUEUE_ID, 0);
+                }
+                if !(2..=MAX_QUEUE_ENTRIES).contains(&size) {
+                    return (status::INVALID_QUEUE_SIZE, 0);
+                }
+                self.sq[qid] = Some(SubmissionQueue {
+                    base: cmd.prp1,
+                    size,
+                    head: 0,
+                    cqid,
+                });
+                (status::SUCCESS, 0)
+            }
+            // Delete I/O CQ
+            0x04 => self.delete_queue(cmd.cdw10 as usize & 0xFFFF, false),
+            // Create I/O CQ
+            0x05 => {
+                let qid = cmd.cdw10 as usize & 0xFFFF;
+                let size = (cmd.cdw10 >> 16) + 1;
+                if qid == 0 || qid >= MAX_QUEUES || self.cq[qid].is_some() {
+                    return (status::INVALID_QUEUE_ID, 0);
+                }
+                if !(2..=MAX_QUEUE_ENTRIES).contains(&size) {
+                    return (status::INVALID_QUEUE_SIZE, 0);
+                }
+                self.cq[qid] = Some(CompletionQueue {
+                    base: cmd.prp1,
+                    size,
+                    tail: 0,
+                    phase: true,
+                });
+                (status::SUCCESS, 0)
+            }
+            // Identify
+            0x06 => {
+                let Some(data) = self.identify(cmd) else {
+                    return (status::INVALID_FIELD, 0);
+                };
+                match write_prp(mem, cmd.prp1, cmd.prp2, &data) {
+                    Some(()) => (status::SUCCESS, 0),
+                    None => (status::DATA_TRANSFER_ERROR, 0),
+                }
+            }
+            // Set Features: Number of Queues
+            0x09 if cmd.cdw10 & 0xFF == 0x07 => {
+                let n = MAX_QUEUES as u32 - 2;
+                (status::SUCCESS, (n << 16) | n)
+            }
+            0x09 => (status::INVALID_FIELD, 0),
+            _ => (status::INVALID_OPCODE, 0),
+        }
+    }
+
+    fn delete_queue(&mut self, qid: usize, submission: bool) -> (u16, u32) {
+        if qid == 0 || qid >= MAX_QUEUES {
+            return (status::INVALID_QUEUE_ID, 0);
+        }
+        let removed = if submission {
+            self.sq[qid].take().is_some()
+        } else {
+            self.cq[qid].take().is_some()
+        };
+        if removed {
+            (status::SUCCESS, 0)
+        } else {
+            (status::INVALID_QUEUE_ID, 0)
+        }
+    }
+
+    fn identify(&self, cmd: &Command) -> Option<Vec<u8>> {
+        let mut data = vec![0u8; PAGE_SIZE as usize];
+        match cmd.cdw10 & 0xFF {
+            // Namespace
+            0x00 if cmd.nsid == 1 => {
+                let blocks = self.blocks().to_le_bytes();
+                data[0..8].copy_from_slice(&blocks); // NSZE
+                data[8..16].copy_from_slice(&blocks); // NCAP
+                data[16..24].copy_from_slice(&blocks); // NUSE
+                data[33] = 1; // DLFEAT: deallocated blocks read as zeroes
+                data[130] = 9; // LBAF0.LBADS: 512-byte blocks
+            }
+            // Controller
+            0x01 => {
+                data[0..2].copy_from_slice(&VENDOR_ID.to_le_bytes());
+                data[2..4].copy_from_slice(&VENDOR_ID.to_le_bytes());
+                pad_ascii(&mut data[4..24], self.serial);
+                pad_ascii(&mut data[24..64], "Helix Emulated NVMe");
+                pad_ascii(&mut data[64..72], "1.0");
+                data[80..84].copy_from_slice(&0x0001_0400u32.to_le_bytes()); // VER
+                data[512] = 0x66; // SQES
+                data[513] = 0x44; // CQES
+                data[516..520].copy_from_slice(&1u32.to_le_bytes()); // NN
+                data[520..522].copy_from_slice(&(1u16 << 2).to_le_bytes()); // ONCS: DSM
+            }
+            // Active namespace list
+            0x02 => data[0..4].copy_from_slice(&1u32.to_le_bytes()),
+            _ => return None,
+        }
+        Some(data)
+    }
+
+    fn io(&mut self, cmd: &Command, mem: &mut GuestMemory) -> (u16, u32) {
+        if cmd.nsid != 1 {
+            return (status::INVALID_NAMESPACE, 0);
+        }
+
+        match cmd.opcode {
+            // Flush
+            0x00 => (status::SUCCESS, 0),
+            // Write / Read
+            0x01 | 0x02 => {
+                let slba = cmd.cdw10 as u64 | ((cmd.cdw11 as u64) << 32);
+                let nlb = (cmd.cdw12 & 0xFFFF) as u64 + 1;
+                if !matches!(slba.checked_add(nlb), Some(end) if end <= self.blocks()) {
+                    return (status::LBA_OUT_OF_RANGE, 0);
+                }
+
+                let start = slba as usize * BLOCK_SIZE;
+                let range = start..start + nlb as usize * BLOCK_SIZE;
+                let ok = if cmd.opcode == 0x01 {
+                    read_prp(mem, cmd.prp1, cmd.prp2, &mut self.disk[range])
+                } else {
+                    write_prp(mem, cmd.prp1, cmd.prp2, &self.disk[range])
+                };
+                match ok {
+                    Some(()) => (status::SUCCESS, 0),
+                    None => (status::DATA_TRANSFER_ERROR, 0),
+                }
+            }
+            // Dataset Management
+            0x09 => {
+                let nr = (cmd.cdw10 & 0xFF) as usize + 1;
+                let mut ranges = vec![0u8; nr * 16];
+                if read_prp(mem, cmd.prp1, cmd.prp2, &mut ranges).is_none() {
+                    return (status::DATA_TRANSFER_ERROR, 0);
+                }
+                // Only AD has an effect; integral read/write hints are ignored
+                if cmd.cdw11 & (1 << 2) == 0 {
+                    return (status::SUCCESS, 0);
+                }
+
+                for range in ranges.chunks_exact(16) {
+                    let nlb = u32::from_le_bytes(range[4..8].try_into().unwrap()) as u64;
+                    let slba = u64::from_le_bytes(range[8..16].try_into().unwrap());
+                    if !matches!(slba.checked_add(nlb), Some(end) if end <= self.blocks()) {
+                        return (status::LBA_OUT_OF_RANGE, 0);
+                    }
+                }
+                for range in ranges.chunks_exact(16) {
+                    let nlb = u32::from_le_bytes(range[4..8].try_into().unwrap()) as usize;
+                    let slba = u64::from_le_bytes(range[8..16].try_into().unwrap()) as usize;
+                    self.disk[slba * BLOCK_SIZE..(slba + nlb) * BLOCK_SIZE].fill(0);
+                }
+                (status::SUCCESS, 0)
+            }
+            _ => (status::INVALID_OPCODE, 0),
+        }
+    }
+}
+
+impl DeviceModel for NvmeModel {
+    fn config(&self) -> &ConfigSpace {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConfigSpace {
+        &mut self.config
+    }
+
+    fn bar_read(&mut self, _bar: usize, offset: u64, width: usize) -> u64 {
+        let qword = |value: u64, base: u64| value >> ((offset - base) * 8);
+        let value = match offset {
+            reg::CAP..=0x07 => qword(self.cap(), reg::CAP),
+            reg::VS => 0x0001_0400,
+            reg::INTMS | reg::INTMC => self.intmask as u64,
+            reg::CC => self.cc as u64,
+            reg::CSTS => self.csts as u64,
+            reg::AQA => self.aqa as u64,
+            reg::ASQ..=0x2F => qword(self.asq, reg::ASQ),
+            reg::ACQ..=0x37 => qword(self.acq, reg::ACQ),
+            _ => 0,
+        };
+        match width {
+            8 => value,
+            _ => value & ((1u64 << (width * 8)) - 1),
+        }
+    }
+
+    fn bar_write(
+        &mut self,
+        _bar: usize,
+        offset: u64,
+        width: usize,
+        value: u64,
+        dma: Option<&mut GuestMemory>,
+    ) {
+        let set_half = |target: &mut u64, base: u64| {
+            if width == 8 {
+                *target = value;
+            } else if offset == base {
+                *target = (*target & !0xFFFF_FFFF) | (value & 0xFFFF_FFFF);
+            } else {
+                *target = (*target & 0xFFFF_FFFF) | (value << 32);
+            }
+        };
+
+        match offset {
+            reg::INTMS => self.intmask |= value as u32,
+            reg::INTMC => self.intmask &= !(value as u32),
+            reg::CC => self.write_cc(value as u32),
+            reg::AQA => self.aqa = value as u32,
+            reg::ASQ | 0x2C => set_half(&mut self.asq, reg::ASQ),
+            reg::ACQ | 0x34 => set_half(&mut self.acq, reg::ACQ),
+            reg::DOORBELL.. if width == 4 => self.doorbell(offset, value as u32, dma),
+            _ => {}
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Fill an identify string field, space padded
+fn pad_ascii(field: &mut [u8], text: &str) {
+    field.fill(b' ');
+    let len = text.len().min(field.len());
+    field[..len].copy_from_slice(&text.as_bytes()[..len]);
+}
+
+/// Guest pages described by PRP1/PRP2 for a transfer of `len` bytes
+fn prp_segments(mem: &GuestMemory, prp1: u64, prp2: u64, len: usize) -> Option<Vec<(u64, usize)>> {
+    let first = ((PAGE_SIZE - prp1 % PAGE_SIZE) as usize).min(len);
+    let mut segments = vec![(prp1, first)];
+    let mut remaining = len - first;
+    if remaining == 0 {
+        return Some(segments);
+    }
+
+    if remaining <= PAGE_SIZE as usize {
+        segments.push((prp2, remaining));
+        return Some(segments);
+    }
+
+    // PRP2 points to a list of page addresses (no chaining)
+    let mut entry = prp2;
+    while remaining > 0 {
+        let page = mem.read_u64(entry).ok()?;
+        let chunk = remaining.min(PAGE_SIZE as usize);
+        segments.push((page, chunk));
+        remaining -= chunk;
+        entry += 8;
+    }
+    Some(segments)
+}
+
+/// Copy device data to guest memory
+fn write_prp(mem: &mut GuestMemory, prp1: u64, prp2: u64, data: &[u8]) -> Option<()> {
+    let mut done = 0;
+    for (addr, len) in prp_segments(mem, prp1, prp2, data.len())? {
+        mem.write(addr, &data[done..done + len]).ok()?;
+        done += len;
+    }
+    Some(())
+}
+
+/// Copy guest memory to the device
+fn read_prp(mem: &GuestMemory, prp1: u64, prp2: u64, data: &mut [u8]) -> Option<()> {
+    let mut done = 0;
+    for (addr, len) in prp_segments(mem, prp1, prp2, data.len())? {
+        mem.read(addr, &mut data[done..done + len]).ok()?;
+        done += len;
+    }
+    Some(())
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{Bdf, ConfigAccess, PciBus};
+    use crate::config::{command, offset};
+
+    const BAR: u64 = 0xFEB0_0000;
+    const NVME: Bdf = Bdf::new(0, 4, 0);
+
+    /// Minimal driver state for the tests
+    struct Driver {
+        bus: PciBus,
+        asq: u64,
+        acq: u64,
+        sq_tail: u32,
+        cq_head: u32,
+        phase: bool,
+        cid: u16,
+    }
+
+    impl Driver {
+        fn new() -> Self {
+            let mut bus = PciBus::new(GuestMemory::new(0x10_0000, 1 << 20));
+            bus.attach(NVME, NvmeModel::new(128)).unwrap();
+
+            bus.write32(NVME, offset::BAR0 as u16, BAR as u32);
+            bus.write32(NVME, offset::BAR0 as u16 + 4, 0);
+            let cmd = command::MEMORY_SPACE | command::BUS_MASTER;
+            bus.write32(NVME, offset::COMMAND as u16, cmd as u32);
+
+            let asq = bus.memory_mut().alloc(64 * 8, 4096).unwrap();
+            let acq = bus.memory_mut().alloc(16 * 8, 4096).unwrap();
+            Self {
+                bus,
+                asq,
+                acq,
+                sq_tail: 0,
+                cq_head: 0,
+                phase: true,
+                cid: 0,
+            }
+        }
+
+        fn reg32(&mut self, offset: u64) -> u32 {
+            self.bus.mmio_read(BAR + offset, 4).unwrap() as u32
+        }
+
+        fn enable(&mut self) {
+            self.bus.mmio_write(BAR + reg::AQA, 4, (7 << 16) | 7).unwrap();
+            self.bus.mmio_write(BAR + reg::ASQ, 8, self.asq).unwrap();
+            self.bus.mmio_write(BAR + reg::ACQ, 8, self.acq).unwrap();
+            self.bus.mmio_write(BAR + reg::CC, 4, 1).unwrap();
+            assert_eq!(self.reg32(reg::CSTS) & 1, 1);
+        }
+
+        /// Submit an admin command and return (status, dw0)
+        fn admin(&mut self, opcode: u8, nsid: u32, prp1: u64, cdw10: u32, cdw11: u32) -> (u16, u32) {
+            self.cid += 1;
+            let mut sqe = [0u8; 64];
+            sqe[0] = opcode;
+            sqe[2..4].copy_from_slice(&self.cid.to_le_bytes());
+            sqe[4..8].copy_from_slice(&nsid.to_le_bytes());
+            sqe[24..32].copy_from_slice(&prp1.to_le_bytes());
+            sqe[40..44].copy_from_slice(&cdw10.to_le_bytes());
+            sqe[44..48].copy_from_slice(&cdw11.to_le_bytes());
+
+            let slot = self.asq + self.sq_tail as u64 * 64;
+            self.bus.memory_mut().write(slot, &sqe).unwrap();
+            self.sq_tail = (self.sq_tail + 1) % 8;
+            self.bus.mmio_write(BAR + reg::DOORBELL, 4, self.sq_tail as u64).unwrap();
+
+            let cqe = self.acq + self.cq_head as u64 * 16;
+            let dw0 = self.bus.memory().read_u32(cqe).unwrap();
+            let cid = self.bus.memory().read_u16(cqe + 12).unwrap();
+            let tag = self.bus.memory().read_u16(cqe + 14).unwrap();
+            assert_eq!(cid, self.cid);
+            assert_eq!(tag & 1 == 1, self.phase);
+
+            self.cq_head = (self.cq_head + 1) % 8;
+            if self.cq_head == 0 {
+                self.phase = !self.phase;
+            }
+            self.bus.mmio_write(BAR + reg::DOORBELL + 4, 4, self.cq_head as u64).unwrap();
+            (tag >> 1, dw0)
+        }
+    }
+
+    #[test]
+    fn test_enable_and_identify() {
+        let mut drv = Driver::new();
+        assert_eq!(drv.bus.read16(NVME, 0), VENDOR_ID);
+        assert_eq!(drv.reg32(reg::VS), 0x0001_0400);
+        assert_eq!(drv.bus.mmio_read(BAR + reg::CAP, 8).unwrap() & 0xFFFF, 63);
+        drv.enable();
+
+        let page = drv.bus.memory_mut().alloc(4096, 4096).unwrap();
+        assert_eq!(drv.admin(0x06, 0, page, 1, 0).0, status::SUCCESS);
+        assert_eq!(drv.bus.memory().slice(page + 4, 15).unwrap(), b"HELIX-NVME-0001");
+
+        assert_eq!(drv.admin(0x06, 1, page, 0, 0).0, status::SUCCESS);
+        assert_eq!(drv.bus.memory().read_u64(page).unwrap(), 128);
+        assert_eq!(drv.admin(0x06, 0, page, 0x10, 0).0, status::INVALID_FIELD);
+        assert_eq!(drv.admin(0x09, 0, 0, 0x07, 0), (status::SUCCESS, (14 << 16) | 14));
+        assert_eq!(drv.admin(0x7F, 0, 0, 0, 0).0, status::INVALID_OPCODE);
+    }
+
+    #[test]
+    fn test_io_queue_read_write() {
+        let mut drv = Driver::new();
+        drv.enable();
+
+        let iocq = drv.bus.memory_mut().alloc(16 * 4, 4096).unwrap();
+        let iosq = drv.bus.memory_mut().alloc(64 * 4, 4096).unwrap();
+        assert_eq!(drv.admin(0x05, 0, iocq, (3 << 16) | 1, 1).0, status::SUCCESS);
+        assert_eq!(drv.admin(0x01, 0, iosq, (3 << 16) | 1, (1 << 16) | 1).0, status::SUCCESS);
+        assert_eq!(drv.admin(0x01, 0, iosq, (3 << 16) | 1, (1 << 16) | 1).0, status::INVALID_QUEUE_ID);
+
+        // Write 3 blocks spanning two pages from an unaligned buffer
+        let buf = drv.bus.memory_mut().alloc(3 * 4096, 4096).unwrap() + 0xE00;
+        let data: Vec<u8> = (0..3 * BLOCK_SIZE).map(|i| i as u8).collect();
+        drv.bus.memory_mut().write(buf, &data).unwrap();
+
+        let mut sqe = [0u8; 64];
+        sqe[0] = 0x01;
+        sqe[2] = 0x42;
+        sqe[4] = 1;
+        sqe[24..32].copy_from_slice(&buf.to_le_bytes());
+        sqe[32..40].copy_from_slice(&(buf + 0x200).to_le_bytes());
+        sqe[40] = 5; // SLBA
+        sqe[48] = 2; // NLB - 1
+        drv.bus.memory_mut().write(iosq, &sqe).unwrap();
+        drv.bus.mmio_write(BAR + reg::DOORBELL + 8, 4, 1).unwrap();
+
+        let tag = drv.bus.memory().read_u16(iocq + 14).unwrap();
+        assert_eq!(tag, 1);
+        assert_eq!(drv.bus.memory().read_u16(iocq + 12).unwrap(), 0x42);
+
+        let nvme = drv.bus.device_mut::<NvmeModel>(NVME).unwrap();
+        assert_eq!(&nvme.disk()[5 * BLOCK_SIZE..8 * BLOCK_SIZE], &data[..]);
+        assert_eq!(nvme.commands(), 3 + 1);
+
+        // Out of range read
+        sqe[0] = 0x02;
+        sqe[40] = 127;
+        drv.bus.memory_mut().write(iosq + 64, &sqe).unwrap();
+        drv.bus.mmio_write(BAR + reg::DOORBELL + 8, 4, 2).unwrap();
+        let tag = drv.bus.memory().read_u16(iocq + 16 + 14).unwrap();
+        assert_eq!(tag >> 1, status::LBA_OUT_OF_RANGE);
+    }
+
+    #[test]
+    fn test_dataset_management_deallocate() {
+        let mut drv = Driver::new();
+        drv.enable();
+
+        let page = drv.bus.memory_mut().alloc(4096, 4096).unwrap();
+        assert_eq!(drv.admin(0x06, 0, page, 1, 0).0, status::SUCCESS);
+        assert_eq!(drv.bus.memory().read_u16(page + 520).unwrap() & (1 << 2), 1 << 2);
+
+        let iocq = drv.bus.memory_mut().alloc(16 * 4, 4096).unwrap();
+        let iosq = drv.bus.memory_mut().alloc(64 * 4, 4096).unwrap();
+        assert_eq!(drv.admin(0x05, 0, iocq, (3 << 16) | 1, 1).0, status::SUCCESS);
+        assert_eq!(drv.admin(0x01, 0, iosq, (3 << 16) | 1, (1 << 16) | 1).0, status::SUCCESS);
+
+        drv.bus.device_mut::<NvmeModel>(NVME).unwrap().disk_mut().fill(0xAA);
+
+        // Two ranges: LBA 2..4 and LBA 10..11
+        let mut ranges = [0u8; 32];
+        ranges[4] = 2;
+        ranges[8] = 2;
+        ranges[16 + 4] = 1;
+        ranges[16 + 8] = 10;
+        drv.bus.memory_mut().write(page, &ranges).unwrap();
+
+        let mut sqe = [0u8; 64];
+        sqe[0] = 0x09;
+        sqe[4] = 1;
+        sqe[24..32].copy_from_slice(&page.to_le_bytes());
+        sqe[40] = 1; // NR - 1
+        sqe[44] = 1 << 2; // AD
+        drv.bus.memory_mut().write(iosq, &sqe).unwrap();
+        drv.bus.mmio_write(BAR + reg::DOORBELL + 8, 4, 1).unwrap();
+        assert_eq!(drv.bus.memory().read_u16(iocq + 14).unwrap(), 1);
+
+        let nvme = drv.bus.device_mut::<NvmeModel>(NVME).unwrap();
+        let disk = nvme.disk();
+        assert!(disk[..2 * BLOCK_SIZE].iter().all(|&b| b == 0xAA));
+        assert!(disk[2 * BLOCK_SIZE..4 * BLOCK_SIZE].iter().all(|&b| b == 0));
+        assert!(disk[4 * BLOCK_SIZE..10 * BLOCK_SIZE].iter().all(|&b| b == 0xAA));
+        assert!(disk[10 * BLOCK_SIZE..11 * BLOCK_SIZE].iter().all(|&b| b == 0));
+        assert_eq!(disk[11 * BLOCK_SIZE], 0xAA);
+
+        // A range past the end fails without touching the disk
+        ranges[8] = 127;
+        drv.bus.memory_mut().write(page, &ranges).unwrap();
+        drv.bus.memory_mut().write(iosq + 64, &sqe).unwrap();
+        drv.bus.mmio_write(BAR + reg::DOORBELL + 8, 4, 2).unwrap();
+        let tag = drv.bus.memory().read_u16(iocq + 16 + 14).unwrap();
+        assert_eq!(tag >> 1, status::LBA_OUT_OF_RANGE);
+    }
+
+    #[test]
+    fn test_shutdown_and_disable() {
+        let mut drv = Driver::new();
+        drv.enable();
+        drv.bus.mmio_write(BAR + reg::CC, 4, (1 << 14) | 1).unwrap();
+        assert_eq!((drv.reg32(reg::CSTS) >> 2) & 3, 2);
+        drv.bus.mmio_write(BAR + reg::CC, 4, 0).unwrap();
+        assert_eq!(drv.reg32(reg::CSTS) & 1, 0);
+    }
+}
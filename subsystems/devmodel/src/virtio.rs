@@ -0,0 +1,519 @@
+//! Legacy virtio-blk model
+//!
+//! A virtio 0.9.5 ("legacy") block device behind an I/O BAR, as QEMU
+//! exposes with `disable-modern=on`:
+//!
+//! - Feature negotiation and device status handshake
+//! - One request queue (queue 0, 128 entries, 4 KB aligned layout)
+//! - Requests: IN, OUT, FLUSH, GET_ID and DISCARD, with descriptor
+//!   chains of any shape; discarded sectors read back as zeroes
+//!
+//! Requests run when the queue is notified; the used ring and ISR are
+//! updated before the notify write returns.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::bus::DeviceModel;
+use crate::config::{BarKind, ConfigSpace};
+use crate::memory::GuestMemory;
+
+/// Virtio vendor ID
+pub const VENDOR_ID: u16 = 0x1AF4;
+
+/// Transitional virtio-blk device ID
+pub const DEVICE_ID: u16 = 0x1001;
+
+/// Sector size
+pub const SECTOR_SIZE: usize = 512;
+
+/// Queue size
+pub const QUEUE_SIZE: u16 = 128;
+
+/// Legacy ring alignment
+const QUEUE_ALIGN: u64 = 4096;
+
+/// I/O BAR size
+const BAR_SIZE: u64 = 0x80;
+
+/// Device ID string returned by GET_ID
+const DEVICE_SERIAL: &[u8] = b"helix-virtio-blk";
+
+/// Legacy register offsets
+pub mod reg {
+    /// Device features (RO)
+    pub const HOST_FEATURES: u64 = 0x00;
+    /// Driver features
+    pub const GUEST_FEATURES: u64 = 0x04;
+    /// Queue page frame number
+    pub const QUEUE_PFN: u64 = 0x08;
+    /// Queue size (RO)
+    pub const QUEUE_NUM: u64 = 0x0C;
+    /// Queue select
+    pub const QUEUE_SEL: u64 = 0x0E;
+    /// Queue notify
+    pub const QUEUE_NOTIFY: u64 = 0x10;
+    /// Device status
+    pub const STATUS: u64 = 0x12;
+    /// ISR status (read clears)
+    pub const ISR: u64 = 0x13;
+    /// Device configuration: capacity in sectors (u64)
+    pub const CAPACITY: u64 = 0x14;
+    /// Maximum sectors per discard segment (u32)
+    pub const MAX_DISCARD_SECTORS: u64 = CAPACITY + 36;
+    /// Maximum discard segments per request (u32)
+    pub const MAX_DISCARD_SEG: u64 = CAPACITY + 40;
+    /// Discard sector alignment (u32)
+    pub const DISCARD_SECTOR_ALIGNMENT: u64 = CAPACITY + 44;
+}
+
+/// Block request types
+mod req {
+    pub const IN: u32 = 0;
+    pub const OUT: u32 = 1;
+    pub const FLUSH: u32 = 4;
+    pub const GET_ID: u32 = 8;
+    pub const DISCARD: u32 = 11;
+}
+
+/// Request status bytes
+mod blk_status {
+    pub const OK: u8 = 0;
+    pub const IOERR: u8 = 1;
+    pub const UNSUPP: u8 = 2;
+}
+
+/// VIRTIO_BLK_F_FLUSH
+const FEATURE_FLUSH: u32 = 1 << 9;
+
+/// VIRTIO_BLK_F_DISCARD
+const FEATURE_DISCARD: u32 = 1 << 13;
+
+/// Features offered to the driver
+const HOST_FEATURES: u32 = FEATURE_FLUSH | FEATURE_DISCARD;
+
+/// Discard segments accepted per request
+const MAX_DISCARD_SEG: u32 = 16;
+
+/// Descriptor flags
+const DESC_NEXT: u16 = 1;
+const DESC_WRITE: u16 = 2;
+
+/// Emulated legacy virtio-blk device
+pub struct VirtioBlkModel {
+    config: ConfigSpace,
+    disk: Vec<u8>,
+    guest_features: u32,
+    queue_pfn: u32,
+    queue_sel: u16,
+    status: u8,
+    isr: u8,
+    last_avail: u16,
+    requests: u64,
+}
+
+impl VirtioBlkModel {
+    /// Create a device with a zeroed disk of `sectors` sectors
+    pub fn new(sectors: u64) -> Self {
+        let config = ConfigSpace::new(VENDOR_ID, DEVICE_ID, 0x01, 0x00, 0x00)
+            .with_subsystem(VENDOR_ID, 2)
+            .with_bar(0, BarKind::Io, BAR_SIZE);
+
+        Self {
+            config,
+            disk: vec![0; sectors as usize * SECTOR_SIZE],
+            guest_features: 0,
+            queue_pfn: 0,
+            queue_sel: 0,
+            status: 0,
+            isr: 0,
+            last_avail: 0,
+            requests: 0,
+        }
+    }
+
+    /// Disk contents
+    pub fn disk(&self) -> &[u8] {
+        &self.disk
+    }
+
+    /// Disk contents (mutable, e.g. to preload a disk image)
+    pub fn disk_mut(&mut self) -> &mut [u8] {
+        &mut self.disk
+    }
+
+    /// Requests completed so far
+    pub fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    /// Negotiated features
+    pub fn guest_features(&self) -> u32 {
+        self.guest_features
+    }
+
+    fn reset(&mut self) {
+        self.guest_features = 0;
+        self.queue_pfn = 0;
+        self.queue_sel = 0;
+        self.status = 0;
+        self.isr = 0;
+        self.last_avail = 0;
+    }
+
+    fn device_config(&self, offset: u64) -> u64 {
+        let capacity = (self.disk.len() / SECTOR_SIZE) as u64;
+        let (base, value) = match offset {
+            reg::CAPACITY..=0x1B => (reg::CAPACITY, capacity),
+            reg::MAX_DISCARD_SECTORS..=0x3B => {
+                (reg::MAX_DISCARD_SECTORS, capacity.min(u32::MAX as u64))
+            }
+            reg::MAX_DISCARD_SEG..=0x3F => (reg::MAX_DISCARD_SEG, MAX_DISCARD_SEG as u64),
+            reg::DISCARD_SECTOR_ALIGNMENT..=0x43 => (reg::DISCARD_SECTOR_ALIGNMENT, 1),
+            _ => return 0,
+        };
+        value >> ((offset - base) * 8)
+    }
+
+    /// Ring addresses: (descriptors, available, used)
+    fn rings(&self) -> (u64, u64, u64) {
+        let desc = self.queue_pfn as u64 * QUEUE_ALIGN;
+        let avail = desc + 16 * QUEUE_SIZE as u64;
+        let used = (avail + 6 + 2 * QUEUE_SIZE as u64).next_multiple_of(QUEUE_ALIGN);
+        (desc, avail, used)
+    }
+
+    fn notify(&mut self, mem: &mut GuestMemory) {
+        if self.queue_pfn == 0 {
+            return;
+        }
+        let (desc, avail, used) = self.rings();
+
+        while let Ok(avail_idx) = mem.read_u16(avail + 2) {
+            if self.last_avail == avail_idx {
+                break;
+            }
+
+            let slot = (self.last_avail % QUEUE_SIZE) as u64;
+            let Ok(head) = mem.read_u16(avail + 4 + 2 * slot) else {
+                break;
+            };
+            self.last_avail = self.last_avail.wrapping_add(1);
+
+            let written = self.process(mem, desc, head).unwrap_or(0);
+
+            let Ok(used_idx) = mem.read_u16(used + 2) else {
+                break;
+            };
+            let elem = used + 4 + 8 * (used_idx % QUEUE_SIZE) as u64;
+            let _ = mem.write_u32(elem, head as u32);
+            let _ = mem.write_u32(elem + 4, written);
+            let _ = mem.write_u16(used + 2, used_idx.wrapping_add(1));
+            self.requests += 1;
+        }
+
+        self.isr |= 1;
+    }
+
+    /// Run one request; returns bytes written to the guest
+    fn process(&mut self, mem: &mut GuestMemory, desc: u64, head: u16) -> Option<u32> {
+        // Gather the chain: device-readable bytes and device-writable buffers
+        let mut readable = Vec::new();
+        let mut writable: Vec<(u64, usize)> = Vec::new();
+        let mut index = head;
+        for _ in 0..QUEUE_SIZE {
+            let entry = desc + 16 * index as u64;
+            let addr = mem.read_u64(entry).ok()?;
+            let len = mem.read_u32(entry + 8).ok()? as usize;
+            let flags = mem.read_u16(entry + 12).ok()?;
+            if flags & DESC_WRITE != 0 {
+                writable.push((addr, len));
+            } else {
+                readable.extend_from_slice(mem.slice(addr, len).ok()?);
+            }
+            if flags & DESC_NEXT == 0 {
+                break;
+            }
+            index = mem.read_u16(entry + 14).ok()?;
+        }
+
+        let capacity: usize = writable.iter().map(|&(_, len)| len).sum();
+        if readable.len() < 16 || capacity == 0 {
+            return None;
+        }
+        let kind = u32::from_le_bytes(readable[0..4].try_into().ok()?);
+        let sector = u64::from_le_bytes(readable[8..16].try_into().ok()?);
+
+        // Data into the guest, excluding the trailing status byte
+        let mut reply = Vec::new();
+        let status = match kind {
+            req::IN => match self.sectors(sector, capacity - 1) {
+                Some(range) => {
+                    reply.extend_from_slice(&self.disk[range]);
+                    blk_status::OK
+                }
+                None => blk_status::IOERR,
+            },
+            req::OUT => match self.sectors(sector, readable.len() - 16) {
+                Some(range) => {
+                    self.disk[range].copy_from_slice(&readable[16..]);
+                    blk_status::OK
+                }
+                None => blk_status::IOERR,
+            },
+            req::FLUSH => blk_status::OK,
+            req::DISCARD => self.discard(&readable[16..]),
+            req::GET_ID => {
+                reply.extend_from_slice(DEVICE_SERIAL);
+                reply.resize(20.min(capacity - 1), 0);
+                blk_status::OK
+            }
+            _ => blk_status::UNSUPP,
+        };
+        reply.push(status);
+
+        // The status byte always lands in the last writable byte
+        let mut done = 0;
+        let data_len = reply.len() - 1;
+        for &(addr, len) in &writable {
+            let chunk = len.min(data_len.saturating_sub(done));
+            mem.write(addr, &reply[done..done + chunk]).ok()?;
+            done += chunk;
+        }
+        let &(last_addr, last_len) = writable.last()?;
+        mem.write(last_addr + last_len as u64 - 1, &[status]).ok()?;
+
+        Some(done as u32 + 1)
+    }
+
+    /// Zero every segment of a discard request, or none if any is invalid
+    fn discard(&mut self, segments: &[u8]) -> u8 {
+        let count = segments.len() / 16;
+        if segments.len() % 16 != 0 || count == 0 || count > MAX_DISCARD_SEG as usize {
+            return blk_status::IOERR;
+        }
+
+        let mut ranges = Vec::with_capacity(count);
+        for segment in segments.chunks_exact(16) {
+            let sector = u64::from_le_bytes(segment[0..8].try_into().unwrap());
+            let num = u32::from_le_bytes(segment[8..12].try_into().unwrap()) as usize;
+            let flags = u32::from_le_bytes(segment[12..16].try_into().unwrap());
+            let range = num.checked_mul(SECTOR_SIZE).and_then(|len| self.sectors(sector, len));
+            match range {
+                Some(range) if flags == 0 => ranges.push(range),
+                _ => return blk_status::IOERR,
+            }
+        }
+        for range in ranges {
+            self.disk[range].fill(0);
+        }
+        blk_status::OK
+    }
+
+    /// Disk byte range of a sector-aligned transfer
+    fn sectors(&self, sector: u64, len: usize) -> Option<core::ops::Range<usize>> {
+        if len % SECTOR_SIZE != 0 {
+            return None;
+        }
+        let start = (sector as usize).checked_mul(SECTOR_SIZE)?;
+        let end = start.checked_add(len)?;
+        (end <= self.disk.len()).then_some(start..end)
+    }
+}
+
+impl DeviceModel for VirtioBlkModel {
+    fn config(&self) -> &ConfigSpace {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConfigSpace {
+        &mut self.config
+    }
+
+    fn bar_read(&mut self, _bar: usize, offset: u64, width: usize) -> u64 {
+        let value = match offset {
+            reg::HOST_FEATURES => HOST_FEATURES as u64,
+            reg::GUEST_FEATURES => self.guest_features as u64,
+            reg::QUEUE_PFN if self.queue_sel == 0 => self.queue_pfn as u64,
+            reg::QUEUE_NUM if self.queue_sel == 0 => QUEUE_SIZE as u64,
+            reg::QUEUE_SEL => self.queue_sel as u64,
+            reg::STATUS => self.status as u64,
+            reg::ISR => core::mem::take(&mut self.isr) as u64,
+            reg::CAPACITY.. => self.device_config(offset),
+            _ => 0,
+        };
+        match width {
+            8 => value,
+            _ => value & ((1u64 << (width * 8)) - 1),
+        }
+    }
+
+    fn bar_write(
+        &mut self,
+        _bar: usize,
+        offset: u64,
+        _width: usize,
+        value: u64,
+        dma: Option<&mut GuestMemory>,
+    ) {
+        match offset {
+            reg::GUEST_FEATURES => self.guest_features = value as u32 & HOST_FEATURES,
+            reg::QUEUE_PFN if self.queue_sel == 0 => self.queue_pfn = value as u32,
+            reg::QUEUE_SEL => self.queue_sel = value as u16,
+            reg::QUEUE_NOTIFY if value == 0 => {
+                if let Some(mem) = dma {
+                    self.notify(mem);
+                }
+            }
+            reg::STATUS if value == 0 => self.reset(),
+            reg::STATUS => self.status = value as u8,
+            _ => {}
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{Bdf, ConfigAccess, PciBus};
+    use crate::config::{command, offset};
+
+    const PORT: u64 = 0xC000;
+    const BLK: Bdf = Bdf::new(0, 5, 0);
+
+    /// Bring the device to DRIVER_OK and return (bus, ring base)
+    fn setup() -> (PciBus, u64) {
+        let mut bus = PciBus::new(GuestMemory::new(0x10_0000, 1 << 20));
+        bus.attach(BLK, VirtioBlkModel::new(32)).unwrap();
+
+        assert_eq!(bus.read16(BLK, 0x2E), 2);
+        bus.write32(BLK, offset::BAR0 as u16, 0xFFFF_FFFF);
+        assert_eq!(bus.read32(BLK, offset::BAR0 as u16), 0xFFFF_FF81);
+        bus.write32(BLK, offset::BAR0 as u16, PORT as u32);
+        bus.write32(BLK, offset::COMMAND as u16, (command::IO_SPACE | command::BUS_MASTER) as u32);
+
+        bus.io_write(PORT + reg::STATUS, 1, 0).unwrap();
+        bus.io_write(PORT + reg::STATUS, 1, 1 | 2).unwrap();
+        let features = bus.io_read(PORT + reg::HOST_FEATURES, 4).unwrap();
+        bus.io_write(PORT + reg::GUEST_FEATURES, 4, features).unwrap();
+
+        bus.io_write(PORT + reg::QUEUE_SEL, 2, 0).unwrap();
+        assert_eq!(bus.io_read(PORT + reg::QUEUE_NUM, 2).unwrap(), QUEUE_SIZE as u64);
+        let ring = bus.memory_mut().alloc(0x2000, QUEUE_ALIGN).unwrap();
+        bus.io_write(PORT + reg::QUEUE_PFN, 4, ring / QUEUE_ALIGN).unwrap();
+        bus.io_write(PORT + reg::STATUS, 1, 1 | 2 | 4).unwrap();
+        (bus, ring)
+    }
+
+    /// Submit a header/data/status chain and notify
+    fn submit(bus: &mut PciBus, ring: u64, n: u16, kind: u32, sector: u64, data: u64, len: u32) {
+        let mem = bus.memory_mut();
+        let hdr = mem.alloc(16, 16).unwrap();
+        let status = mem.alloc(1, 1).unwrap();
+        mem.write_u32(hdr, kind).unwrap();
+        mem.write_u64(hdr + 8, sector).unwrap();
+
+        let base = n * 3;
+        let descs = [
+            (hdr, 16, DESC_NEXT),
+            (data, len, DESC_NEXT | if matches!(kind, req::OUT | req::DISCARD) { 0 } else { DESC_WRITE }),
+            (status, 1, DESC_WRITE),
+        ];
+        for (i, &(addr, len, flags)) in descs.iter().enumerate() {
+            let entry = ring + 16 * (base + i as u16) as u64;
+            mem.write_u64(entry, addr).unwrap();
+            mem.write_u32(entry + 8, len).unwrap();
+            mem.write_u16(entry + 12, flags).unwrap();
+            mem.write_u16(entry + 14, base + i as u16 + 1).unwrap();
+        }
+
+        let avail = ring + 16 * QUEUE_SIZE as u64;
+        mem.write_u16(avail + 4 + 2 * n as u64, base).unwrap();
+        mem.write_u16(avail + 2, n + 1).unwrap();
+        bus.io_write(PORT + reg::QUEUE_NOTIFY, 2, 0).unwrap();
+        assert_eq!(bus.io_read(PORT + reg::ISR, 1).unwrap(), 1);
+        assert_eq!(bus.io_read(PORT + reg::ISR, 1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_negotiation() {
+        let (mut bus, _) = setup();
+        assert_eq!(bus.io_read(PORT + reg::CAPACITY, 4).unwrap(), 32);
+        let blk = bus.device_mut::<VirtioBlkModel>(BLK).unwrap();
+        assert_eq!(blk.guest_features(), FEATURE_FLUSH | FEATURE_DISCARD);
+        assert_eq!(bus.io_read(PORT + reg::MAX_DISCARD_SECTORS, 4).unwrap(), 32);
+        assert_eq!(bus.io_read(PORT + reg::MAX_DISCARD_SEG, 4).unwrap(), MAX_DISCARD_SEG as u64);
+        assert_eq!(bus.io_read(PORT + reg::DISCARD_SECTOR_ALIGNMENT, 4).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_block_io() {
+        let (mut bus, ring) = setup();
+        let used = ring + 0x1000;
+
+        let buf = bus.memory_mut().alloc(1024, 512).unwrap();
+        let data: Vec<u8> = (0..1024).map(|i| (i * 7) as u8).collect();
+        bus.memory_mut().write(buf, &data).unwrap();
+        submit(&mut bus, ring, 0, req::OUT, 4, buf, 1024);
+        assert_eq!(bus.memory().read_u16(used + 2).unwrap(), 1);
+
+        let back = bus.memory_mut().alloc(1024, 512).unwrap();
+        submit(&mut bus, ring, 1, req::IN, 4, back, 1024);
+        assert_eq!(bus.memory().slice(back, 1024).unwrap(), &data[..]);
+        assert_eq!(bus.memory().read_u32(used + 4 + 8 + 4).unwrap(), 1025);
+
+        let id = bus.memory_mut().alloc(20, 1).unwrap();
+        submit(&mut bus, ring, 2, req::GET_ID, 0, id, 20);
+        assert_eq!(bus.memory().slice(id, 16).unwrap(), DEVICE_SERIAL);
+
+        // Past the end of the disk
+        submit(&mut bus, ring, 3, req::IN, 31, back, 1024);
+        let status_addr = bus.memory().read_u64(ring + 16 * (3 * 3 + 2)).unwrap();
+        assert_eq!(bus.memory().slice(status_addr, 1).unwrap(), &[blk_status::IOERR]);
+
+        let blk = bus.device_mut::<VirtioBlkModel>(BLK).unwrap();
+        assert_eq!(&blk.disk()[4 * SECTOR_SIZE..6 * SECTOR_SIZE], &data[..]);
+        assert_eq!(blk.requests(), 4);
+    }
+
+    #[test]
+    fn test_discard() {
+        let (mut bus, ring) = setup();
+        bus.device_mut::<VirtioBlkModel>(BLK).unwrap().disk_mut().fill(0xAA);
+
+        // Sectors 2..4 and 8..9
+        let segs = bus.memory_mut().alloc(32, 16).unwrap();
+        bus.memory_mut().write_u64(segs, 2).unwrap();
+        bus.memory_mut().write_u32(segs + 8, 2).unwrap();
+        bus.memory_mut().write_u64(segs + 16, 8).unwrap();
+        bus.memory_mut().write_u32(segs + 24, 1).unwrap();
+        submit(&mut bus, ring, 0, req::DISCARD, 0, segs, 32);
+        let status_addr = bus.memory().read_u64(ring + 16 * 2).unwrap();
+        assert_eq!(bus.memory().slice(status_addr, 1).unwrap(), &[blk_status::OK]);
+
+        let disk = bus.device_mut::<VirtioBlkModel>(BLK).unwrap().disk();
+        assert!(disk[..2 * SECTOR_SIZE].iter().all(|&b| b == 0xAA));
+        assert!(disk[2 * SECTOR_SIZE..4 * SECTOR_SIZE].iter().all(|&b| b == 0));
+        assert!(disk[4 * SECTOR_SIZE..8 * SECTOR_SIZE].iter().all(|&b| b == 0xAA));
+        assert!(disk[8 * SECTOR_SIZE..9 * SECTOR_SIZE].iter().all(|&b| b == 0));
+
+        // A segment past the end rejects the whole request
+        bus.memory_mut().write_u64(segs, 0).unwrap();
+        bus.memory_mut().write_u64(segs + 16, 31).unwrap();
+        bus.memory_mut().write_u32(segs + 24, 2).unwrap();
+        submit(&mut bus, ring, 1, req::DISCARD, 0, segs, 32);
+        let status_addr = bus.memory().read_u64(ring + 16 * (3 + 2)).unwrap();
+        assert_eq!(bus.memory().slice(status_addr, 1).unwrap(), &[blk_status::IOERR]);
+        let disk = bus.device_mut::<VirtioBlkModel>(BLK).unwrap().disk();
+        assert_eq!(disk[0], 0xAA);
+    }
+}
@@ -505,6 +505,342 @@ pub fn is_closed(&self) -> bool {
     }
 }
 
+// =============================================================================
+// Real-Time Channel
+// =============================================================================
+
+/// Wakeup latency histogram buckets (bucket `i` holds values below `2^i` ns)
+pub const RT_LATENCY_BUCKETS: usize = 32;
+
+/// Priority lent by a blocked receiver to the sender it waits on
+///
+/// The scheduler applies it with `QueueManager::inherit_priority` and
+/// undoes it with `QueueManager::reset_priority`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityDonation {
+    /// Blocked receiver
+    pub donor: TaskId,
+    /// Sender running on the donated priority
+    pub recipient: TaskId,
+    /// Donated priority (higher is more urgent)
+    pub priority: i16,
+}
+
+/// Outcome of a real-time send
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtSend {
+    /// Queued; the receiver was not waiting
+    Queued,
+    /// Completed a rendezvous with the blocked receiver
+    Rendezvous {
+        /// Receiver to wake
+        wake: TaskId,
+        /// Donation to revoke from the sender
+        revoke: Option<PriorityDonation>,
+    },
+}
+
+/// Outcome of a real-time receive
+#[derive(Debug)]
+pub enum RtRecv {
+    /// Message dequeued
+    Message {
+        /// The message
+        msg: Message,
+        /// Sender blocked on a full queue, to wake now that there is room
+        wake_sender: Option<TaskId>,
+    },
+    /// Queue empty; the receiver blocks until the sender's next message
+    Block {
+        /// Donation to apply to the sender while the receiver waits
+        donation: Option<PriorityDonation>,
+    },
+}
+
+/// Latency histogram with power-of-two buckets
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyHistogram {
+    /// Per-bucket counts
+    pub buckets: [u64; RT_LATENCY_BUCKETS],
+    /// Values recorded
+    pub count: u64,
+    /// Sum of recorded values
+    pub sum: u64,
+    /// Largest recorded value
+    pub max: u64,
+}
+
+impl LatencyHistogram {
+    /// Create an empty histogram
+    pub const fn new() -> Self {
+        Self {
+            buckets: [0; RT_LATENCY_BUCKETS],
+            count: 0,
+            sum: 0,
+            max: 0,
+        }
+    }
+    
+    /// Record one latency
+    pub fn record(&mut self, latency: Nanoseconds) {
+        let ns = latency.0;
+        let bucket = ((u64::BITS - ns.leading_zeros()) as usize).min(RT_LATENCY_BUCKETS - 1);
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum = self.sum.saturating_add(ns);
+        self.max = self.max.max(ns);
+    }
+    
+    /// Upper bound of the bucket containing the `percent`th percentile
+    ///
+    /// Never exceeds the recorded maximum. Returns 0 when empty.
+    pub fn percentile(&self, percent: u8) -> Nanoseconds {
+        if self.count == 0 {
+            return Nanoseconds::zero();
+        }
+        let rank = (self.count * percent.min(100) as u64).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                let upper = if i == 0 { 0 } else { (1u64 << i) - 1 };
+                return Nanoseconds(upper.min(self.max));
+            }
+        }
+        Nanoseconds(self.max)
+    }
+    
+    /// Mean latency
+    pub fn mean(&self) -> Nanoseconds {
+        Nanoseconds(self.sum.checked_div(self.count).unwrap_or(0))
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Mutable state of a real-time channel
+struct RtState {
+    /// Queued messages
+    queue: VecDeque<Message>,
+    /// Priority of the receiver while it is blocked
+    waiting: Option<i16>,
+    /// Donation in force
+    donation: Option<PriorityDonation>,
+    /// Sender blocked on a full queue
+    sender_blocked: bool,
+    /// Rendezvous time of a woken receiver that has not yet run
+    woken_at: Option<Nanoseconds>,
+    /// Rendezvous-to-receive latency
+    latency: LatencyHistogram,
+}
+
+/// Real-time channel statistics
+#[derive(Debug, Default)]
+struct RtChannelStats {
+    sent: AtomicU64,
+    received: AtomicU64,
+    rendezvous: AtomicU64,
+    donations: AtomicU64,
+    full: AtomicU64,
+}
+
+/// Bounded one-way channel for real-time tasks
+///
+/// At most `depth` messages queue; a send to a full queue fails with
+/// [`DISError::QueueFull`] and the sender blocks until a receive reports
+/// it in `wake_sender`. A receiver that finds the queue empty blocks and
+/// donates its priority to the sender, so a low-priority producer cannot
+/// hold up a real-time consumer. The sender's next message completes the
+/// rendezvous, revokes the donation and wakes the receiver; the time from
+/// there to the receiver collecting the message is its wakeup latency.
+pub struct RtChannel {
+    /// Channel ID
+    pub id: ChannelId,
+    /// Sending task
+    pub sender: TaskId,
+    /// Receiving task
+    pub receiver: TaskId,
+    /// Queue depth
+    depth: usize,
+    /// Queue and rendezvous state
+    state: Mutex<RtState>,
+    /// Channel state
+    channel_state: AtomicU32,
+    /// Statistics
+    stats: RtChannelStats,
+}
+
+impl RtChannel {
+    /// Create a channel holding at most `depth` messages (at least one)
+    pub const fn new(id: ChannelId, sender: TaskId, receiver: TaskId, depth: usize) -> Self {
+        Self {
+            id,
+            sender,
+            receiver,
+            depth: if depth == 0 { 1 } else { depth },
+            state: Mutex::new(RtState {
+                queue: VecDeque::new(),
+                waiting: None,
+                donation: None,
+                sender_blocked: false,
+                woken_at: None,
+                latency: LatencyHistogram::new(),
+            }),
+            channel_state: AtomicU32::new(ChannelState::Open as u32),
+            stats: RtChannelStats {
+                sent: AtomicU64::new(0),
+                received: AtomicU64::new(0),
+                rendezvous: AtomicU64::new(0),
+                donations: AtomicU64::new(0),
+                full: AtomicU64::new(0),
+            },
+        }
+    }
+    
+    /// Send a message from `from` at time `now`
+    pub fn send(&self, from: TaskId, msg: Message, now: Nanoseconds) -> DISResult<RtSend> {
+        if self.is_closed() {
+            return Err(DISError::ChannelClosed);
+        }
+        if from != self.sender {
+            return Err(DISError::NotChannelEndpoint);
+        }
+        
+        let mut state = self.state.lock();
+        if state.queue.len() >= self.depth {
+            state.sender_blocked = true;
+            self.stats.full.fetch_add(1, Ordering::Relaxed);
+            return Err(DISError::QueueFull);
+        }
+        
+        state.queue.push_back(msg);
+        self.stats.sent.fetch_add(1, Ordering::Relaxed);
+        
+        if state.waiting.take().is_none() {
+            return Ok(RtSend::Queued);
+        }
+        
+        state.woken_at = Some(now);
+        self.stats.rendezvous.fetch_add(1, Ordering::Relaxed);
+        Ok(RtSend::Rendezvous {
+            wake: self.receiver,
+            revoke: state.donation.take(),
+        })
+    }
+    
+    /// Receive on behalf of `to`, running at `priority`, at time `now`
+    pub fn receive(&self, to: TaskId, priority: i16, now: Nanoseconds) -> DISResult<RtRecv> {
+        if to != self.receiver {
+            return Err(DISError::NotChannelEndpoint);
+        }
+        
+        let mut state = self.state.lock();
+        if let Some(msg) = state.queue.pop_front() {
+            if let Some(woken_at) = state.woken_at.take() {
+                state.latency.record(Nanoseconds(now.0.saturating_sub(woken_at.0)));
+            }
+            let wake_sender = core::mem::take(&mut state.sender_blocked).then_some(self.sender);
+            self.stats.received.fetch_add(1, Ordering::Relaxed);
+            return Ok(RtRecv::Message { msg, wake_sender });
+        }
+        
+        if self.is_closed() {
+            return Err(DISError::ChannelClosed);
+        }
+        
+        // Already blocked: the donation stands
+        if state.waiting.is_some() {
+            return Ok(RtRecv::Block { donation: None });
+        }
+        
+        state.waiting = Some(priority);
+        let donation = PriorityDonation {
+            donor: self.receiver,
+            recipient: self.sender,
+            priority,
+        };
+        state.donation = Some(donation);
+        self.stats.donations.fetch_add(1, Ordering::Relaxed);
+        Ok(RtRecv::Block { donation: Some(donation) })
+    }
+    
+    /// Queue depth
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+    
+    /// Messages currently queued
+    pub fn queued(&self) -> usize {
+        self.state.lock().queue.len()
+    }
+    
+    /// Donation currently in force
+    pub fn donation(&self) -> Option<PriorityDonation> {
+        self.state.lock().donation
+    }
+    
+    /// Copy of the wakeup latency histogram
+    pub fn latency(&self) -> LatencyHistogram {
+        self.state.lock().latency
+    }
+    
+    /// Clear the wakeup latency histogram
+    pub fn reset_latency(&self) {
+        self.state.lock().latency = LatencyHistogram::new();
+    }
+    
+    /// Close the channel, returning the donation to revoke (if any)
+    pub fn close(&self) -> Option<PriorityDonation> {
+        self.channel_state.store(ChannelState::Closed as u32, Ordering::SeqCst);
+        let mut state = self.state.lock();
+        state.waiting = None;
+        state.donation.take()
+    }
+    
+    /// Check if closed
+    pub fn is_closed(&self) -> bool {
+        self.channel_state.load(Ordering::Relaxed) == ChannelState::Closed as u32
+    }
+    
+    /// Get statistics
+    pub fn statistics(&self) -> RtChannelStatistics {
+        let latency = self.latency();
+        RtChannelStatistics {
+            depth: self.depth as u64,
+            queued: self.queued() as u64,
+            sent: self.stats.sent.load(Ordering::Relaxed),
+            received: self.stats.received.load(Ordering::Relaxed),
+            rendezvous: self.stats.rendezvous.load(Ordering::Relaxed),
+            donations: self.stats.donations.load(Ordering::Relaxed),
+            full: self.stats.full.load(Ordering::Relaxed),
+            wakeups: latency.count,
+            wakeup_mean: latency.mean(),
+            wakeup_p99: latency.percentile(99),
+            wakeup_max: Nanoseconds(latency.max),
+        }
+    }
+}
+
+/// Real-time channel statistics
+#[derive(Debug, Clone)]
+pub struct RtChannelStatistics {
+    pub depth: u64,
+    pub queued: u64,
+    pub sent: u64,
+    pub received: u64,
+    pub rendezvous: u64,
+    pub donations: u64,
+    pub full: u64,
+    pub wakeups: u64,
+    pub wakeup_mean: Nanoseconds,
+    pub wakeup_p99: Nanoseconds,
+    pub wakeup_max: Nanoseconds,
+}
+
 // =============================================================================
 // Notification
 // =============================================================================
@@ -638,6 +974,8 @@ pub struct IPCManager {
     queues: RwLock<BTreeMap<TaskId, Mutex<MessageQueue>>>,
     /// Channels
     channels: RwLock<BTreeMap<ChannelId, Channel>>,
+    /// Real-time channels
+    rt_channels: RwLock<BTreeMap<ChannelId, RtChannel>>,
     /// Subscribers
     subscribers: RwLock<BTreeMap<TaskId, Subscriber>>,
     /// Next channel ID
@@ -665,6 +1003,7 @@ pub fn new() -> Self {
         Self {
             queues: RwLock::new(BTreeMap::new()),
             channels: RwLock::new(BTreeMap::new()),
+            rt_channels: RwLock::new(BTreeMap::new()),
             subscribers: RwLock::new(BTreeMap::new()),
             next_channel_id: AtomicU64::new(1),
             stats: IPCStats::default(),
@@ -693,6 +1032,21 @@ pub fn unregister_task(&self, task_id: TaskId) {
         for id in channels_to_close {
             self.close_channel(id);
         }
+        
+        let rt_channels_to_close: Vec<_> = self.rt_channels.read()
+            .iter()
+            .filter(|(_, ch)| ch.sender == task_id || ch.receiver == task_id)
+            .map(|(id, _)| *id)
+            .collect();
+        
+        for id in rt_channels_to_close {
+            self.close_rt_channel(id);
+        }
+    }
+    
+    /// Advance the IPC clock (timestamps real-time channel latency)
+    pub fn tick(&self, now: Nanoseconds) {
+        self.current_time.store(now.0, Ordering::Relaxed);
     }
     
     // =========================================================================
@@ -792,6 +1146,59 @@ pub fn channel_receive(&self, channel_id: ChannelId, to: TaskId) -> Option<Messa
             .and_then(|ch| ch.receive(to))
     }
     
+    // =========================================================================
+    // Real-Time Channel Operations
+    // =========================================================================
+    
+    /// Create real-time channel from `sender` to `receiver`
+    pub fn create_rt_channel(&self, sender: TaskId, receiver: TaskId, depth: usize) -> ChannelId {
+        let id = ChannelId::new(self.next_channel_id.fetch_add(1, Ordering::Relaxed));
+        let channel = RtChannel::new(id, sender, receiver, depth);
+        
+        self.rt_channels.write().insert(id, channel);
+        self.stats.channels_created.fetch_add(1, Ordering::Relaxed);
+        
+        id
+    }
+    
+    /// Close real-time channel, returning the donation to revoke (if any)
+    pub fn close_rt_channel(&self, id: ChannelId) -> Option<PriorityDonation> {
+        let channel = self.rt_channels.write().remove(&id)?;
+        self.stats.channels_closed.fetch_add(1, Ordering::Relaxed);
+        channel.close()
+    }
+    
+    /// Send on real-time channel
+    pub fn rt_send(&self, channel_id: ChannelId, from: TaskId, msg: Message) -> DISResult<RtSend> {
+        let now = Nanoseconds(self.current_time.load(Ordering::Relaxed));
+        let channels = self.rt_channels.read();
+        let channel = channels.get(&channel_id).ok_or(DISError::ChannelNotFound)?;
+        let result = channel.send(from, msg, now);
+        if result.is_ok() {
+            self.stats.messages_sent.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+    
+    /// Receive from real-time channel
+    pub fn rt_receive(&self, channel_id: ChannelId, to: TaskId, priority: i16) -> DISResult<RtRecv> {
+        let now = Nanoseconds(self.current_time.load(Ordering::Relaxed));
+        let channels = self.rt_channels.read();
+        let channel = channels.get(&channel_id).ok_or(DISError::ChannelNotFound)?;
+        let result = channel.receive(to, priority, now);
+        if let Ok(RtRecv::Message { .. }) = result {
+            self.stats.messages_received.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+    
+    /// Get real-time channel statistics
+    pub fn rt_channel_statistics(&self, channel_id: ChannelId) -> Option<RtChannelStatistics> {
+        self.rt_channels.read()
+            .get(&channel_id)
+            .map(|ch| ch.statistics())
+    }
+    
     // =========================================================================
     // Notification Operations
     // =========================================================================
@@ -843,6 +1250,7 @@ pub fn statistics(&self) -> IPCStatistics {
             messages_received: self.stats.messages_received.load(Ordering::Relaxed),
             messages_dropped: self.stats.messages_dropped.load(Ordering::Relaxed),
             channels_open: self.channels.read().len() as u64,
+            rt_channels_open: self.rt_channels.read().len() as u64,
             channels_created: self.stats.channels_created.load(Ordering::Relaxed),
             channels_closed: self.stats.channels_closed.load(Ordering::Relaxed),
             notifications_sent: self.stats.notifications_sent.load(Ordering::Relaxed),
@@ -864,6 +1272,7 @@ pub struct IPCStatistics {
     pub messages_received: u64,
     pub messages_dropped: u64,
     pub channels_open: u64,
+    pub rt_channels_open: u64,
     pub channels_created: u64,
     pub channels_closed: u64,
     pub notifications_sent: u64,
@@ -1001,6 +1410,71 @@ fn test_ipc_manager() {
         assert_eq!(received.sender, TaskId::new(1));
     }
     
+    #[test]
+    fn test_rt_channel_bounded() {
+        let (a, b) = (TaskId::new(1), TaskId::new(2));
+        let channel = RtChannel::new(ChannelId::new(1), a, b, 2);
+        
+        assert_eq!(channel.send(a, Message::signal(a, b, 1), Nanoseconds(0)), Ok(RtSend::Queued));
+        assert_eq!(channel.send(a, Message::signal(a, b, 2), Nanoseconds(0)), Ok(RtSend::Queued));
+        assert_eq!(channel.send(a, Message::signal(a, b, 3), Nanoseconds(0)), Err(DISError::QueueFull));
+        assert_eq!(channel.send(b, Message::signal(b, a, 4), Nanoseconds(0)), Err(DISError::NotChannelEndpoint));
+        
+        match channel.receive(b, 0, Nanoseconds(0)).unwrap() {
+            RtRecv::Message { wake_sender, .. } => assert_eq!(wake_sender, Some(a)),
+            RtRecv::Block { .. } => panic!("expected a message"),
+        }
+        assert_eq!(channel.queued(), 1);
+        assert_eq!(channel.statistics().full, 1);
+    }
+    
+    #[test]
+    fn test_rt_channel_donation() {
+        let (a, b) = (TaskId::new(1), TaskId::new(2));
+        let channel = RtChannel::new(ChannelId::new(1), a, b, 4);
+        
+        let donation = match channel.receive(b, 90, Nanoseconds(1_000)).unwrap() {
+            RtRecv::Block { donation } => donation.unwrap(),
+            RtRecv::Message { .. } => panic!("expected to block"),
+        };
+        assert_eq!(donation, PriorityDonation { donor: b, recipient: a, priority: 90 });
+        assert_eq!(channel.donation(), Some(donation));
+        
+        // Spurious wakeup does not donate twice
+        assert!(matches!(channel.receive(b, 90, Nanoseconds(1_500)), Ok(RtRecv::Block { donation: None })));
+        
+        let sent = channel.send(a, Message::signal(a, b, 7), Nanoseconds(2_000)).unwrap();
+        assert_eq!(sent, RtSend::Rendezvous { wake: b, revoke: Some(donation) });
+        assert_eq!(channel.donation(), None);
+        
+        assert!(matches!(channel.receive(b, 90, Nanoseconds(2_300)), Ok(RtRecv::Message { .. })));
+        let latency = channel.latency();
+        assert_eq!(latency.count, 1);
+        assert_eq!(latency.max, 300);
+        assert_eq!(latency.percentile(99), Nanoseconds(300));
+    }
+    
+    #[test]
+    fn test_ipc_manager_rt_channel() {
+        let manager = IPCManager::new();
+        let (a, b) = (TaskId::new(1), TaskId::new(2));
+        let id = manager.create_rt_channel(a, b, 8);
+        
+        manager.tick(Nanoseconds(100));
+        assert!(matches!(manager.rt_receive(id, b, 50), Ok(RtRecv::Block { donation: Some(_) })));
+        assert!(matches!(manager.rt_send(id, a, Message::signal(a, b, 1)), Ok(RtSend::Rendezvous { .. })));
+        manager.tick(Nanoseconds(140));
+        assert!(matches!(manager.rt_receive(id, b, 50), Ok(RtRecv::Message { .. })));
+        
+        let stats = manager.rt_channel_statistics(id).unwrap();
+        assert_eq!(stats.rendezvous, 1);
+        assert_eq!(stats.wakeup_max, Nanoseconds(40));
+        
+        manager.unregister_task(a);
+        assert_eq!(manager.statistics().rt_channels_open, 0);
+        assert_eq!(manager.rt_send(id, a, Message::signal(a, b, 2)), Err(DISError::ChannelNotFound));
+    }
+    
     #[test]
     fn test_notifications() {
         let manager = IPCManager::new();
@@ -111,7 +111,7 @@
 pub use queues::{MultiLevelQueue, QueueManager};
 pub use executor::{Executor, ExecutionContext};
 pub use api::{DIS, DISEvent, TaskHandle};
-pub use ipc::{Message, Request, Response, MessagePayload, IPCManager};
+pub use ipc::{Message, Request, Response, MessagePayload, IPCManager, RtChannel, PriorityDonation};
 
 // =============================================================================
 // Core Types
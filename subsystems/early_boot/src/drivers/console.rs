@@ -246,7 +246,14 @@ pub fn init(boot_info: &BootInfo) -> BootResult<Self> {
     }
 
     /// Initialize serial console
-    fn init_serial(&mut self, _boot_info: &BootInfo) {
+    fn init_serial(&mut self, boot_info: &BootInfo) {
+        // Keep the line the bootloader configured; reprogramming it garbles
+        // whatever is still in flight
+        if let Some(serial) = boot_info.console.and_then(|c| c.serial) {
+            self.serial = Some(SerialConsole::configured(serial.base));
+            return;
+        }
+
         // Platform-specific serial initialization
         #[cfg(target_arch = "x86_64")]
         {
@@ -482,6 +489,14 @@ pub fn new(base: u64) -> Self {
         console
     }
 
+    /// Use a serial port someone else already configured
+    pub fn configured(base: u64) -> Self {
+        Self {
+            base,
+            initialized: true,
+        }
+    }
+
     /// Initialize serial port
     fn init(&mut self) {
         #[cfg(target_arch = "x86_64")]
@@ -11,6 +11,18 @@
 //! - Text rendering with built-in bitmap font
 //! - Boot splash and progress bar support
 //! - Double buffering support (when memory permits)
+//! - Rotation (90/180/270) for panels mounted sideways, and integer
+//!   scaling for HiDPI screens
+//!
+//! ## Rotation and Scaling
+//!
+//! Drawing uses logical coordinates: with a 90 or 270 degree rotation
+//! `width()` and `height()` are the physical height and width, and every
+//! pixel, rectangle and scroll is mapped onto the physical framebuffer.
+//! Both are taken from the kernel command line (`fbcon=rotate:N` with N
+//! in quarter turns clockwise, `fbcon=scale:N`), else the rotation from
+//! the panel orientation the firmware reported (ACPI BGRT) and a scale
+//! from the display's pixel density.
 //!
 //! ## Usage
 //!
@@ -24,7 +36,7 @@
 #![allow(dead_code)]
 
 use crate::error::{BootError, BootResult};
-use crate::info::BootInfo;
+use crate::info::{BootInfo, ConsoleHandoverInfo, DisplayInfo, SplashInfo};
 
 // =============================================================================
 // COLOR TYPES
@@ -204,6 +216,87 @@ pub const fn is_bgr(self) -> bool {
     }
 }
 
+// =============================================================================
+// ROTATION
+// =============================================================================
+
+/// Largest console scale
+pub const MAX_SCALE: u32 = 4;
+
+/// Console rotation, clockwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    /// Upright
+    #[default]
+    None,
+    /// 90 degrees
+    Cw90,
+    /// 180 degrees (upside down)
+    Cw180,
+    /// 270 degrees
+    Cw270,
+}
+
+impl Rotation {
+    /// From quarter turns clockwise (`fbcon=rotate:N`)
+    pub const fn from_quarter_turns(turns: u32) -> Self {
+        match turns % 4 {
+            0 => Rotation::None,
+            1 => Rotation::Cw90,
+            2 => Rotation::Cw180,
+            _ => Rotation::Cw270,
+        }
+    }
+
+    /// From degrees clockwise; only right angles are valid
+    pub const fn from_degrees(degrees: u32) -> Option<Self> {
+        if degrees % 90 != 0 {
+            return None;
+        }
+        Some(Self::from_quarter_turns(degrees / 90))
+    }
+
+    /// Width and height trade places
+    pub const fn swaps_axes(self) -> bool {
+        matches!(self, Rotation::Cw90 | Rotation::Cw270)
+    }
+}
+
+/// Rotation and scale requested with `fbcon=` on the command line
+///
+/// Accepts `fbcon=rotate:N` and `fbcon=scale:N`, separately or comma
+/// separated; later values win.
+pub fn console_settings(cmdline: &str) -> (Option<Rotation>, Option<u32>) {
+    let mut rotation = None;
+    let mut scale = None;
+    for option in cmdline
+        .split_whitespace()
+        .filter_map(|param| param.strip_prefix("fbcon="))
+        .flat_map(|options| options.split(','))
+    {
+        match option.split_once(':') {
+            Some(("rotate", turns)) => {
+                rotation = turns.parse().ok().map(Rotation::from_quarter_turns).or(rotation);
+            }
+            Some(("scale", n)) => {
+                scale = n.parse().ok().filter(|n| (1..=MAX_SCALE).contains(n)).or(scale);
+            }
+            _ => {}
+        }
+    }
+    (rotation, scale)
+}
+
+/// Console scale for a screen: one step per 96 dpi when the display size
+/// is known, else 2x from 4K up
+pub fn auto_scale(width: u32, height: u32, display: Option<&DisplayInfo>) -> u32 {
+    match display.and_then(DisplayInfo::dpi) {
+        Some(dpi) => (dpi / 96).clamp(1, MAX_SCALE),
+        None if width.min(height) >= 2160 => 2,
+        None => 1,
+    }
+}
+
 // =============================================================================
 // FRAMEBUFFER INFO
 // =============================================================================
@@ -304,6 +397,21 @@ pub struct Framebuffer {
 
     /// Font height
     font_height: u32,
+
+    /// Graphics mode the bootloader left the display in
+    mode: u32,
+
+    /// Bootloader splash kept on screen
+    splash: Option<SplashInfo>,
+
+    /// Attached display, if the bootloader read its EDID
+    display: Option<DisplayInfo>,
+
+    /// Console rotation
+    rotation: Rotation,
+
+    /// Glyph and drawing scale
+    scale: u32,
 }
 
 impl Framebuffer {
@@ -317,7 +425,7 @@ pub fn init(boot_info: &BootInfo) -> BootResult<Self> {
             return Err(BootError::HardwareNotFound);
         }
 
-        Ok(Self {
+        let mut fb = Self {
             info,
             cursor_x: 0,
             cursor_y: 0,
@@ -325,7 +433,109 @@ pub fn init(boot_info: &BootInfo) -> BootResult<Self> {
             bg_color: Color::BLACK,
             font_width: FONT_WIDTH as u32,
             font_height: FONT_HEIGHT as u32,
-        })
+            mode: 0,
+            splash: None,
+            display: boot_info.display,
+            rotation: Rotation::None,
+            scale: 1,
+        };
+
+        let (rotation, scale) = console_settings(boot_info.cmdline.as_str());
+        let rotation = rotation.or_else(|| {
+            boot_info.display.and_then(|d| Rotation::from_degrees(d.orientation as u32))
+        });
+        fb.set_rotation(rotation.unwrap_or_default());
+        fb.set_scale(scale.unwrap_or_else(|| {
+            auto_scale(fb.info.width, fb.info.height, boot_info.display.as_ref())
+        }));
+
+        if let Some(ref console) = boot_info.console {
+            fb.continue_from(console);
+        }
+
+        Ok(fb)
+    }
+
+    /// Rotate the console
+    ///
+    /// Nothing is redrawn; `clear` to start over in the new orientation.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
+        self.clamp_cursor();
+    }
+
+    /// Console rotation
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Scale glyphs by `scale` (1 to [`MAX_SCALE`])
+    pub fn set_scale(&mut self, scale: u32) {
+        self.scale = scale.clamp(1, MAX_SCALE);
+        self.font_width = FONT_WIDTH as u32 * self.scale;
+        self.font_height = FONT_HEIGHT as u32 * self.scale;
+        self.clamp_cursor();
+    }
+
+    /// Glyph and drawing scale
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Keep the cursor on screen after the text grid changed
+    fn clamp_cursor(&mut self) {
+        let max_cols = (self.width() / self.font_width).max(1);
+        let max_rows = (self.height() / self.font_height).max(1);
+        if self.cursor_x >= max_cols {
+            self.cursor_x = 0;
+            self.cursor_y += 1;
+        }
+        self.cursor_y = self.cursor_y.min(max_rows - 1);
+    }
+
+    /// Carry on from the bootloader's screen rather than starting afresh
+    fn continue_from(&mut self, console: &ConsoleHandoverInfo) {
+        // The bootloader's cells may differ from ours; start on the first
+        // of our rows below its last line of text. Its text runs the other
+        // way on a rotated console, so that starts at the top.
+        if self.rotation == Rotation::None {
+            let (cell_width, cell_height) = (console.cell.0 as u32, console.cell.1 as u32);
+            self.cursor_x = (console.cursor_column * cell_width).div_ceil(self.font_width);
+            self.cursor_y = (console.cursor_row * cell_height).div_ceil(self.font_height);
+            self.clamp_cursor();
+        }
+        self.mode = console.mode;
+
+        if console.splash.visible {
+            self.bg_color = Color::from_rgb32(console.splash.background);
+            self.splash = Some(console.splash);
+        }
+    }
+
+    /// Check if the bootloader splash is still held on screen
+    pub fn splash_held(&self) -> bool {
+        self.splash.is_some()
+    }
+
+    /// Stop holding the bootloader splash
+    ///
+    /// Call when the display server takes the screen over. The splash stays
+    /// in the framebuffer until something draws over it; `clear` first if the
+    /// text console should take the screen instead.
+    pub fn release_splash(&mut self) {
+        self.splash = None;
+    }
+
+    /// Console state for the next stage to continue from
+    pub fn handover(&self) -> ConsoleHandoverInfo {
+        ConsoleHandoverInfo {
+            mode: self.mode,
+            cursor_column: self.cursor_x,
+            cursor_row: self.cursor_y,
+            cell: (self.font_width as u8, self.font_height as u8),
+            splash: self.splash.unwrap_or(SplashInfo::none()),
+            serial: None,
+        }
     }
 
     /// Detect framebuffer from boot information
@@ -385,19 +595,63 @@ pub fn info(&self) -> &FramebufferInfo {
         &self.info
     }
 
-    /// Get width
+    /// Display behind the framebuffer (size, native mode, HDR)
+    pub fn display(&self) -> Option<&DisplayInfo> {
+        self.display.as_ref()
+    }
+
+    /// Get width (as drawn, after rotation)
     pub fn width(&self) -> u32 {
-        self.info.width
+        if self.rotation.swaps_axes() {
+            self.info.height
+        } else {
+            self.info.width
+        }
     }
 
-    /// Get height
+    /// Get height (as drawn, after rotation)
     pub fn height(&self) -> u32 {
-        self.info.height
+        if self.rotation.swaps_axes() {
+            self.info.width
+        } else {
+            self.info.height
+        }
+    }
+
+    /// Map a logical pixel onto the framebuffer
+    fn to_physical(&self, x: u32, y: u32) -> (u32, u32) {
+        let (width, height) = (self.info.width, self.info.height);
+        match self.rotation {
+            Rotation::None => (x, y),
+            Rotation::Cw90 => (width - 1 - y, x),
+            Rotation::Cw180 => (width - 1 - x, height - 1 - y),
+            Rotation::Cw270 => (y, height - 1 - x),
+        }
+    }
+
+    /// Map a logical rectangle (inside the screen) onto the framebuffer
+    fn to_physical_rect(&self, x: u32, y: u32, w: u32, h: u32) -> (u32, u32, u32, u32) {
+        let (width, height) = (self.info.width, self.info.height);
+        match self.rotation {
+            Rotation::None => (x, y, w, h),
+            Rotation::Cw90 => (width - y - h, x, h, w),
+            Rotation::Cw180 => (width - x - w, height - y - h, w, h),
+            Rotation::Cw270 => (y, height - x - w, h, w),
+        }
     }
 
     /// Set a pixel
     #[inline]
     pub fn set_pixel(&self, x: u32, y: u32, color: Color) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        let (x, y) = self.to_physical(x, y);
+        self.write_pixel(x, y, color);
+    }
+
+    /// Set a pixel of the framebuffer, ignoring rotation
+    fn write_pixel(&self, x: u32, y: u32, color: Color) {
         if x >= self.info.width || y >= self.info.height {
             return;
         }
@@ -440,10 +694,11 @@ pub fn set_pixel(&self, x: u32, y: u32, color: Color) {
 
     /// Get a pixel
     pub fn get_pixel(&self, x: u32, y: u32) -> Color {
-        if x >= self.info.width || y >= self.info.height {
+        if x >= self.width() || y >= self.height() {
             return Color::BLACK;
         }
 
+        let (x, y) = self.to_physical(x, y);
         let offset = self.info.pixel_offset(x, y);
         let ptr = self.info.virt_base as *const u8;
 
@@ -472,7 +727,7 @@ pub fn get_pixel(&self, x: u32, y: u32) -> Color {
 
     /// Clear the screen with a color
     pub fn clear(&self, color: Color) {
-        self.fill_rect(0, 0, self.info.width, self.info.height, color);
+        self.fill_rect(0, 0, self.width(), self.height(), color);
     }
 
     /// Draw a horizontal line
@@ -531,6 +786,21 @@ pub fn draw_rect(&self, x: u32, y: u32, width: u32, height: u32, color: Color) {
 
     /// Fill a rectangle
     pub fn fill_rect(&self, x: u32, y: u32, width: u32, height: u32, color: Color) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        let width = width.min(self.width() - x);
+        let height = height.min(self.height() - y);
+        let (x, y, width, height) = self.to_physical_rect(x, y, width, height);
+        self.fill_physical(x, y, width, height, color);
+    }
+
+    /// Fill a rectangle of the framebuffer, ignoring rotation
+    fn fill_physical(&self, x: u32, y: u32, width: u32, height: u32, color: Color) {
+        if x >= self.info.width || y >= self.info.height {
+            return;
+        }
+
         // Optimize for 32-bit formats
         if matches!(self.info.format, PixelFormat::Rgb32 | PixelFormat::Bgr32) {
             let pixel_value = if self.info.format == PixelFormat::Rgb32 {
@@ -586,7 +856,7 @@ pub fn draw_circle(&self, cx: i32, cy: i32, radius: i32, color: Color) {
     /// Fill a circle
     pub fn fill_circle(&self, cx: i32, cy: i32, radius: i32, color: Color) {
         for y in (cy - radius)..=(cy + radius) {
-            if y < 0 || y >= self.info.height as i32 {
+            if y < 0 || y >= self.height() as i32 {
                 continue;
             }
 
@@ -594,7 +864,7 @@ pub fn fill_circle(&self, cx: i32, cy: i32, radius: i32, color: Color) {
             let dx = ((radius * radius - dy * dy) as f64).sqrt() as i32;
 
             let x0 = (cx - dx).max(0) as u32;
-            let x1 = (cx + dx).min(self.info.width as i32 - 1) as u32;
+            let x1 = (cx + dx).min(self.width() as i32 - 1) as u32;
 
             for x in x0..=x1 {
                 self.set_pixel(x, y as u32, color);
@@ -614,7 +884,7 @@ pub fn draw_char(&self, x: u32, y: u32, ch: char, fg: Color, bg: Color) {
         for (row, &bits) in glyph.iter().enumerate() {
             for col in 0..FONT_WIDTH {
                 let color = if bits & (0x80 >> col) != 0 { fg } else { bg };
-                self.set_pixel(x + col as u32, y + row as u32, color);
+                self.glyph_pixel(x, y, col as u32, row as u32, color);
             }
         }
     }
@@ -631,12 +901,22 @@ pub fn draw_char_transparent(&self, x: u32, y: u32, ch: char, color: Color) {
         for (row, &bits) in glyph.iter().enumerate() {
             for col in 0..FONT_WIDTH {
                 if bits & (0x80 >> col) != 0 {
-                    self.set_pixel(x + col as u32, y + row as u32, color);
+                    self.glyph_pixel(x, y, col as u32, row as u32, color);
                 }
             }
         }
     }
 
+    /// Draw font pixel (`col`, `row`) of a glyph at (`x`, `y`), scaled
+    #[inline]
+    fn glyph_pixel(&self, x: u32, y: u32, col: u32, row: u32, color: Color) {
+        if self.scale == 1 {
+            self.set_pixel(x + col, y + row, color);
+        } else {
+            self.fill_rect(x + col * self.scale, y + row * self.scale, self.scale, self.scale, color);
+        }
+    }
+
     /// Draw text at position
     pub fn draw_text(&mut self, x: u32, y: u32, text: &str, color: Color) {
         let mut cx = x;
@@ -659,12 +939,12 @@ pub fn draw_text(&mut self, x: u32, y: u32, text: &str, color: Color) {
                 continue;
             }
 
-            if cx + self.font_width > self.info.width {
+            if cx + self.font_width > self.width() {
                 cx = x;
                 cy += self.font_height;
             }
 
-            if cy + self.font_height > self.info.height {
+            if cy + self.font_height > self.height() {
                 break;
             }
 
@@ -685,12 +965,12 @@ pub fn draw_text_bg(&mut self, x: u32, y: u32, text: &str, fg: Color, bg: Color)
                 continue;
             }
 
-            if cx + self.font_width > self.info.width {
+            if cx + self.font_width > self.width() {
                 cx = x;
                 cy += self.font_height;
             }
 
-            if cy + self.font_height > self.info.height {
+            if cy + self.font_height > self.height() {
                 break;
             }
 
@@ -700,7 +980,12 @@ pub fn draw_text_bg(&mut self, x: u32, y: u32, text: &str, fg: Color, bg: Color)
     }
 
     /// Print to console area (bottom of screen)
+    ///
+    /// Nothing is drawn while the splash is held.
     pub fn print(&mut self, text: &str) {
+        if self.splash.is_some() {
+            return;
+        }
         for ch in text.chars() {
             self.print_char(ch);
         }
@@ -708,8 +993,8 @@ pub fn print(&mut self, text: &str) {
 
     /// Print a character to console area
     fn print_char(&mut self, ch: char) {
-        let max_cols = self.info.width / self.font_width;
-        let max_rows = self.info.height / self.font_height;
+        let max_cols = self.width() / self.font_width;
+        let max_rows = self.height() / self.font_height;
 
         match ch {
             '\n' => {
@@ -745,33 +1030,48 @@ fn print_char(&mut self, ch: char) {
     }
 
     /// Scroll the screen up
+    ///
+    /// On a rotated console "up" is a move along whichever framebuffer
+    /// axis the text runs down.
     fn scroll_up(&mut self, lines: u32) {
-        let scroll_height = lines * self.font_height;
-        let copy_height = self.info.height - scroll_height;
+        let scroll_height = (lines * self.font_height).min(self.height());
+        let copy_height = self.height() - scroll_height;
 
         // Copy pixels up
-        for y in 0..copy_height {
-            let src_offset = self.info.pixel_offset(0, y + scroll_height);
-            let dst_offset = self.info.pixel_offset(0, y);
-
-            unsafe {
-                let src = (self.info.virt_base + src_offset as u64) as *const u8;
-                let dst = (self.info.virt_base + dst_offset as u64) as *mut u8;
-
-                core::ptr::copy_nonoverlapping(src, dst, self.info.pitch as usize);
-            }
-        }
+        let src = self.to_physical_rect(0, scroll_height, self.width(), copy_height);
+        let (dst_x, dst_y, _, _) = self.to_physical_rect(0, 0, self.width(), copy_height);
+        self.copy_physical(src, dst_x, dst_y);
 
         // Clear the bottom
         self.fill_rect(
             0,
             copy_height,
-            self.info.width,
+            self.width(),
             scroll_height,
             self.bg_color,
         );
     }
 
+    /// Move a rectangle of the framebuffer to (`dst_x`, `dst_y`)
+    ///
+    /// Rows are copied in the order that keeps overlapping source rows
+    /// intact; a row moved onto itself is copied with `memmove` semantics.
+    fn copy_physical(&self, (x, y, width, height): (u32, u32, u32, u32), dst_x: u32, dst_y: u32) {
+        let bytes = width as usize * self.info.bytes_per_pixel();
+        let base = self.info.virt_base as *mut u8;
+        let copy_row = |row: u32| unsafe {
+            let src = base.add(self.info.pixel_offset(x, y + row));
+            let dst = base.add(self.info.pixel_offset(dst_x, dst_y + row));
+            core::ptr::copy(src, dst, bytes);
+        };
+
+        if dst_y <= y {
+            (0..height).for_each(copy_row);
+        } else {
+            (0..height).rev().for_each(copy_row);
+        }
+    }
+
     /// Set console colors
     pub fn set_colors(&mut self, fg: Color, bg: Color) {
         self.fg_color = fg;
@@ -804,12 +1104,18 @@ pub fn draw_progress_bar(
     }
 
     /// Draw boot splash screen
+    ///
+    /// Leaves a splash handed over by the bootloader as it is.
     pub fn draw_boot_splash(&mut self) {
+        if self.splash.is_some() {
+            return;
+        }
+
         // Clear screen with Helix background
         self.clear(Color::HELIX_BG);
 
-        let center_x = self.info.width / 2;
-        let center_y = self.info.height / 2;
+        let center_x = self.width() / 2;
+        let center_y = self.height() / 2;
 
         // Draw Helix logo placeholder (simple helix shape)
         self.draw_helix_logo(center_x - 50, center_y - 80, Color::HELIX_PRIMARY);
@@ -857,11 +1163,23 @@ fn draw_helix_logo(&self, x: u32, y: u32, color: Color) {
     }
 
     /// Draw boot progress
+    ///
+    /// With the bootloader splash held, fills its progress bar instead.
     pub fn draw_boot_progress(&self, progress: f32, status: &str) {
+        if let Some(ref splash) = self.splash {
+            // The bootloader drew the splash unrotated
+            let (x, y, width, height) = splash.progress_bar;
+            let fill_width = (width as f32 * progress.clamp(0.0, 1.0)) as u32;
+            if fill_width > 0 {
+                self.fill_physical(x, y, fill_width, height, Color::HELIX_PRIMARY);
+            }
+            return;
+        }
+
         let bar_width = 300;
         let bar_height = 20;
-        let bar_x = (self.info.width - bar_width) / 2;
-        let bar_y = (self.info.height / 2) + 80;
+        let bar_x = (self.width() - bar_width) / 2;
+        let bar_y = (self.height() / 2) + 80;
 
         // Draw progress bar
         self.draw_progress_bar(
@@ -876,20 +1194,20 @@ pub fn draw_boot_progress(&self, progress: f32, status: &str) {
         );
 
         // Draw status text
-        let status_x = (self.info.width - (status.len() as u32 * FONT_WIDTH as u32)) / 2;
+        let status_x = (self.width() - (status.len() as u32 * self.font_width)) / 2;
 
         // Clear old status area
         self.fill_rect(
             0,
             bar_y + 30,
-            self.info.width,
-            FONT_HEIGHT as u32,
+            self.width(),
+            self.font_height,
             Color::HELIX_BG,
         );
 
         // Draw new status - using immutable self requires a workaround
         for (i, ch) in status.chars().enumerate() {
-            let cx = status_x + (i as u32 * FONT_WIDTH as u32);
+            let cx = status_x + (i as u32 * self.font_width);
             self.draw_char_transparent(cx, bar_y + 30, ch, Color::HELIX_FG);
         }
     }
@@ -1096,4 +1414,164 @@ fn test_color_darken() {
         assert_eq!(darkened.g, 127);
         assert_eq!(darkened.b, 127);
     }
+
+    #[test]
+    fn test_console_handover() {
+        let mut info = FramebufferInfo::new();
+        info.width = 800;
+        info.height = 600;
+        let mut fb = Framebuffer {
+            info,
+            cursor_x: 0,
+            cursor_y: 0,
+            fg_color: Color::WHITE,
+            bg_color: Color::BLACK,
+            font_width: FONT_WIDTH as u32,
+            font_height: FONT_HEIGHT as u32,
+            mode: 0,
+            splash: None,
+            display: None,
+            rotation: Rotation::None,
+            scale: 1,
+        };
+
+        let mut console = ConsoleHandoverInfo::empty();
+        console.mode = 3;
+        console.cursor_row = 5;
+        console.cell = (8, 19);
+        console.splash = SplashInfo { visible: true, background: 0x102030, ..SplashInfo::none() };
+        fb.continue_from(&console);
+
+        // 5 rows of 19 pixels end at 95; our first whole row below is 6
+        assert_eq!((fb.cursor_x, fb.cursor_y), (0, 6));
+        assert_eq!(fb.bg_color, Color::rgb(0x10, 0x20, 0x30));
+        assert!(fb.splash_held());
+
+        // Text stays off the splash
+        fb.print("hidden");
+        assert_eq!(fb.cursor_x, 0);
+
+        let handover = fb.handover();
+        assert_eq!(handover.mode, 3);
+        assert_eq!(handover.cursor_row, 6);
+        assert!(handover.splash.visible);
+
+        fb.release_splash();
+        assert!(!fb.splash_held());
+        assert!(!fb.handover().splash.visible);
+    }
+
+    /// 32-bit framebuffer over `pixels`
+    fn memory_fb(width: u32, height: u32, pixels: &mut [u32]) -> Framebuffer {
+        let mut info = FramebufferInfo::new();
+        info.virt_base = pixels.as_mut_ptr() as u64;
+        info.width = width;
+        info.height = height;
+        info.pitch = width * 4;
+        info.bpp = 32;
+        info.format = PixelFormat::Rgb32;
+        Framebuffer {
+            info,
+            cursor_x: 0,
+            cursor_y: 0,
+            fg_color: Color::WHITE,
+            bg_color: Color::BLACK,
+            font_width: FONT_WIDTH as u32,
+            font_height: FONT_HEIGHT as u32,
+            mode: 0,
+            splash: None,
+            display: None,
+            rotation: Rotation::None,
+            scale: 1,
+        }
+    }
+
+    #[test]
+    fn test_rotated_pixels() {
+        let mut pixels = [0u32; 4 * 2];
+        let mut fb = memory_fb(4, 2, &mut pixels);
+        let red = Color::rgb(0xFF, 0, 0);
+
+        fb.set_rotation(Rotation::Cw90);
+        assert_eq!((fb.width(), fb.height()), (2, 4));
+        // Logical top left is the physical top right
+        fb.set_pixel(0, 0, red);
+        assert_eq!(fb.get_pixel(0, 0), red);
+        fb.set_rotation(Rotation::None);
+        assert_eq!(fb.get_pixel(3, 0), red);
+
+        fb.set_rotation(Rotation::Cw270);
+        fb.set_pixel(0, 0, Color::WHITE);
+        fb.set_rotation(Rotation::Cw180);
+        assert_eq!(fb.get_pixel(3, 0), Color::WHITE);
+        assert_eq!(fb.get_pixel(0, 1), red);
+    }
+
+    #[test]
+    fn test_rotated_fill_and_scroll() {
+        let mut pixels = [0u32; 6 * 4];
+        let mut fb = memory_fb(6, 4, &mut pixels);
+        let red = Color::rgb(0xFF, 0, 0);
+
+        fb.set_rotation(Rotation::Cw270);
+        fb.fill_rect(0, 0, 2, 10, red);
+        fb.set_rotation(Rotation::None);
+        // Two logical columns are the bottom two physical rows
+        assert_eq!(fb.get_pixel(0, 2), red);
+        assert_eq!(fb.get_pixel(5, 3), red);
+        assert_eq!(fb.get_pixel(0, 1), Color::BLACK);
+
+        // Scrolling a 4x6 logical screen by two rows moves physical columns left
+        fb.set_rotation(Rotation::Cw90);
+        fb.fill_rect(0, 0, 4, 6, Color::BLACK);
+        fb.set_pixel(1, 3, red);
+        fb.font_height = 2;
+        fb.scroll_up(1);
+        assert_eq!(fb.get_pixel(1, 1), red);
+        assert_eq!(fb.get_pixel(1, 3), Color::BLACK);
+    }
+
+    #[test]
+    fn test_scaled_glyphs() {
+        let mut pixels = [0u32; 64 * 64];
+        let mut fb = memory_fb(64, 64, &mut pixels);
+        fb.set_scale(9);
+        assert_eq!(fb.scale(), MAX_SCALE);
+        fb.set_scale(2);
+        assert_eq!((fb.font_width, fb.font_height), (16, 32));
+
+        // The glyph cell covers 16x32 pixels
+        let red = Color::rgb(0xFF, 0, 0);
+        fb.draw_char(0, 0, ' ', Color::WHITE, red);
+        assert_eq!(fb.get_pixel(15, 31), red);
+        assert_eq!(fb.get_pixel(16, 0), Color::BLACK);
+        assert_eq!(fb.get_pixel(0, 32), Color::BLACK);
+    }
+
+    #[test]
+    fn test_console_settings() {
+        assert_eq!(console_settings("quiet"), (None, None));
+        assert_eq!(
+            console_settings("fbcon=rotate:1 fbcon=scale:2"),
+            (Some(Rotation::Cw90), Some(2)),
+        );
+        assert_eq!(
+            console_settings("fbcon=scale:9,rotate:6"),
+            (Some(Rotation::Cw180), None),
+        );
+        assert_eq!(Rotation::from_degrees(270), Some(Rotation::Cw270));
+        assert_eq!(Rotation::from_degrees(45), None);
+    }
+
+    #[test]
+    fn test_auto_scale() {
+        let laptop = DisplayInfo {
+            native: (3840, 2160),
+            size_mm: (344, 194),
+            ..DisplayInfo::default()
+        };
+        assert_eq!(auto_scale(3840, 2160, Some(&laptop)), 2);
+        assert_eq!(auto_scale(3840, 2160, None), 2);
+        assert_eq!(auto_scale(1920, 1080, None), 1);
+    }
 }
@@ -112,6 +112,9 @@ pub enum BootError {
     /// Out of memory
     OutOfMemory,
 
+    /// Reservation overlaps an existing one
+    ReservationConflict { addr: u64, owner: &'static str },
+
     // =========================================================================
     // Interrupt Errors
     // =========================================================================
@@ -280,6 +283,9 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             },
             Self::EarlyHeapFailed => write!(f, "Early heap setup failed"),
             Self::OutOfMemory => write!(f, "Out of memory"),
+            Self::ReservationConflict { addr, owner } => {
+                write!(f, "Reservation at {:#x} overlaps {}", addr, owner)
+            },
 
             Self::IdtSetupFailed(reason) => write!(f, "IDT setup failed: {}", reason),
             Self::GdtSetupFailed(reason) => write!(f, "GDT setup failed: {}", reason),
@@ -596,6 +602,7 @@ pub fn error_code(&self) -> u32 {
             Self::AddressNotAligned { .. } => 0x0308,
             Self::EarlyHeapFailed => 0x0309,
             Self::OutOfMemory => 0x030A,
+            Self::ReservationConflict { .. } => 0x030B,
 
             Self::IdtSetupFailed(_) => 0x0400,
             Self::GdtSetupFailed(_) => 0x0401,
@@ -45,6 +45,9 @@
 
 use crate::core::{BootContext, BootState};
 use crate::error::{BootError, BootResult};
+use crate::info::ConsoleHandoverInfo;
+use crate::memblock::{MemBlock, ReservationRecord, MAX_RESERVATIONS};
+use crate::placement::{LayoutRecord, PlacementLayout};
 
 // =============================================================================
 // CONSTANTS
@@ -126,7 +129,7 @@ fn default() -> Self {
 // =============================================================================
 
 /// Simple PRNG for KASLR (xorshift64)
-struct Xorshift64 {
+pub(crate) struct Xorshift64 {
     state: u64,
 }
 
@@ -221,7 +224,7 @@ fn get_hardware_random() -> Option<u64> {
 }
 
 /// Get entropy from various sources for seeding
-fn gather_entropy() -> u64 {
+pub(crate) fn gather_entropy() -> u64 {
     let mut entropy: u64 = 0;
 
     // Try hardware RNG first
@@ -524,6 +527,9 @@ pub struct HandoffState {
     /// Framebuffer pitch
     pub framebuffer_pitch: u32,
 
+    /// Console state for the kernel console to continue from
+    pub console: ConsoleHandoverInfo,
+
     /// Initial page table root
     pub page_table_root: u64,
 
@@ -532,6 +538,15 @@ pub struct HandoffState {
 
     /// Reserved for architecture-specific data
     pub arch_data: [u64; 16],
+
+    /// Physical placement of early regions (for crash dumps)
+    pub layout: LayoutRecord,
+
+    /// Early memory reservations the page allocator must not hand out
+    pub reservations: [ReservationRecord; MAX_RESERVATIONS],
+
+    /// Number of valid entries in `reservations`
+    pub reservation_count: u32,
 }
 
 /// Handoff state magic: "HLXHAND\0"
@@ -555,9 +570,13 @@ pub const fn new() -> Self {
             framebuffer_width: 0,
             framebuffer_height: 0,
             framebuffer_pitch: 0,
+            console: ConsoleHandoverInfo::empty(),
             page_table_root: 0,
             kernel_stack_top: 0,
             arch_data: [0; 16],
+            layout: LayoutRecord::empty(),
+            reservations: [ReservationRecord::empty(); MAX_RESERVATIONS],
+            reservation_count: 0,
         }
     }
 
@@ -581,6 +600,12 @@ pub fn get_arch_data(&self, index: usize) -> u64 {
             0
         }
     }
+
+    /// Early memory reservations
+    pub fn reservations(&self) -> &[ReservationRecord] {
+        let count = (self.reservation_count as usize).min(MAX_RESERVATIONS);
+        &self.reservations[..count]
+    }
 }
 
 impl Default for HandoffState {
@@ -645,6 +670,14 @@ pub fn prepare_state(&mut self, ctx: &BootContext) {
         self.state.cpu_count = ctx.smp_state.cpu_count as u32;
         self.state.bsp_id = ctx.smp_state.bsp_id;
 
+        // Record randomized placement
+        if let Some(layout) = crate::placement::layout() {
+            self.set_layout(&layout);
+        }
+
+        // Record early reservations
+        self.set_reservations(&crate::memblock::memblock());
+
         // Copy architecture-specific data
         #[cfg(target_arch = "x86_64")]
         {
@@ -675,6 +708,25 @@ pub fn prepare_state(&mut self, ctx: &BootContext) {
         }
     }
 
+    /// Record physical placement of early regions
+    pub fn set_layout(&mut self, layout: &PlacementLayout) {
+        self.state.layout = layout.record(self.state.kaslr_offset);
+    }
+
+    /// Record early memory reservations
+    pub fn set_reservations(&mut self, memblock: &MemBlock) {
+        let reservations = memblock.reservations();
+        for (record, reservation) in self.state.reservations.iter_mut().zip(reservations) {
+            *record = ReservationRecord::new(reservation);
+        }
+        self.state.reservation_count = reservations.len().min(MAX_RESERVATIONS) as u32;
+    }
+
+    /// Record the console state, so the kernel keeps the screen as it is
+    pub fn set_console(&mut self, console: ConsoleHandoverInfo) {
+        self.state.console = console;
+    }
+
     /// Set kernel stack
     pub fn set_kernel_stack(&mut self, stack_top: u64) {
         self.state.kernel_stack_top = stack_top;
@@ -25,6 +25,9 @@
 //! │  ├── Address, Width, Height, Pitch, BPP                                 │
 //! │  └── Pixel Format                                                       │
 //! ├─────────────────────────────────────────────────────────────────────────┤
+//! │  Console Handover (Optional)                                             │
+//! │  └── Cursor, Splash State, Serial Settings                              │
+//! ├─────────────────────────────────────────────────────────────────────────┤
 //! │  Firmware Tables                                                         │
 //! │  ├── ACPI RSDP                                                          │
 //! │  ├── SMBIOS Entry                                                       │
@@ -41,6 +44,7 @@
 //! ```
 
 use crate::error::{BootError, BootResult};
+use crate::SerialConfig;
 
 // =============================================================================
 // CONSTANTS
@@ -157,6 +161,10 @@ pub struct BootInfoFlags: u32 {
         const KERNEL_MAPPED = 1 << 11;
         /// Page tables provided
         const PAGE_TABLES = 1 << 12;
+        /// Console handed over by the bootloader
+        const CONSOLE_HANDOVER = 1 << 13;
+        /// Display identification (EDID) present
+        const DISPLAY = 1 << 14;
     }
 }
 
@@ -180,6 +188,12 @@ pub struct BootInfo {
     /// Framebuffer information (optional)
     pub framebuffer: Option<FramebufferInfo>,
 
+    /// Display behind the framebuffer (optional)
+    pub display: Option<DisplayInfo>,
+
+    /// Console state left by the bootloader (optional)
+    pub console: Option<ConsoleHandoverInfo>,
+
     /// ACPI information (optional)
     pub acpi: Option<AcpiInfo>,
 
@@ -524,6 +538,106 @@ pub enum PixelFormat {
     Unknown = 255,
 }
 
+/// Display attached to the framebuffer, from its EDID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(C)]
+pub struct DisplayInfo {
+    /// PNP manufacturer ID (e.g. `DEL`)
+    pub manufacturer: [u8; 3],
+    /// Manufacturer product code
+    pub product: u16,
+    /// Native resolution (width, height), zero if unknown
+    pub native: (u32, u32),
+    /// Refresh rate of the native mode in Hz, zero if unknown
+    pub refresh_hz: u32,
+    /// Physical size (width, height) in millimetres, zero if unknown
+    pub size_mm: (u16, u16),
+    /// Display accepts HDR (PQ or HLG) signals
+    pub hdr: bool,
+    /// Desired content max luminance in cd/m², zero if not reported
+    pub max_luminance: u16,
+    /// Panel mounting, degrees clockwise (from the ACPI BGRT), zero if upright
+    pub orientation: u16,
+}
+
+impl DisplayInfo {
+    /// Horizontal pixel density in dots per inch, if the size is known
+    pub fn dpi(&self) -> Option<u32> {
+        if self.size_mm.0 == 0 || self.native.0 == 0 {
+            return None;
+        }
+        Some(self.native.0 * 254 / (self.size_mm.0 as u32 * 10))
+    }
+}
+
+// =============================================================================
+// CONSOLE HANDOVER
+// =============================================================================
+
+/// Splash screen the bootloader left on the display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct SplashInfo {
+    /// Splash is on screen
+    pub visible: bool,
+    /// Progress shown in the bar (0-100)
+    pub progress: u8,
+    /// Screen background (0x00RRGGBB)
+    pub background: u32,
+    /// Logo rectangle (x, y, width, height)
+    pub logo: (u32, u32, u32, u32),
+    /// Progress bar rectangle (x, y, width, height), zero if there is none
+    pub progress_bar: (u32, u32, u32, u32),
+}
+
+impl SplashInfo {
+    /// No splash on screen
+    pub const fn none() -> Self {
+        Self {
+            visible: false,
+            progress: 0,
+            background: 0,
+            logo: (0, 0, 0, 0),
+            progress_bar: (0, 0, 0, 0),
+        }
+    }
+}
+
+/// Console state at kernel entry
+///
+/// The framebuffer is still in the mode the bootloader set and holds what
+/// it drew; continuing from here avoids a mode set and a clear.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ConsoleHandoverInfo {
+    /// Graphics mode the framebuffer was left in
+    pub mode: u32,
+    /// Next text column
+    pub cursor_column: u32,
+    /// Next text row
+    pub cursor_row: u32,
+    /// Glyph cell (width, height) in pixels
+    pub cell: (u8, u8),
+    /// Splash state
+    pub splash: SplashInfo,
+    /// Serial console settings, if one is in use
+    pub serial: Option<SerialConfig>,
+}
+
+impl ConsoleHandoverInfo {
+    /// Nothing to continue from
+    pub const fn empty() -> Self {
+        Self {
+            mode: 0,
+            cursor_column: 0,
+            cursor_row: 0,
+            cell: (0, 0),
+            splash: SplashInfo::none(),
+            serial: None,
+        }
+    }
+}
+
 // =============================================================================
 // ACPI INFORMATION
 // =============================================================================
@@ -910,6 +1024,8 @@ pub fn new(protocol: BootProtocol) -> Self {
                     stack_size: 0,
                 },
                 framebuffer: None,
+                display: None,
+                console: None,
                 acpi: None,
                 smbios: None,
                 device_tree: None,
@@ -973,6 +1089,20 @@ pub fn set_framebuffer(&mut self, fb: FramebufferInfo) -> &mut Self {
         self
     }
 
+    /// Set display information
+    pub fn set_display(&mut self, display: DisplayInfo) -> &mut Self {
+        self.info.display = Some(display);
+        self.info.header.flags.insert(BootInfoFlags::DISPLAY);
+        self
+    }
+
+    /// Set console handover
+    pub fn set_console_handover(&mut self, console: ConsoleHandoverInfo) -> &mut Self {
+        self.info.console = Some(console);
+        self.info.header.flags.insert(BootInfoFlags::CONSOLE_HANDOVER);
+        self
+    }
+
     /// Set ACPI info
     pub fn set_acpi(&mut self, acpi: AcpiInfo) -> &mut Self {
         self.info.acpi = Some(acpi);
@@ -123,6 +123,12 @@
 /// Boot handoff and KASLR
 pub mod handoff;
 
+/// Physical placement randomization
+pub mod placement;
+
+/// Early memory reservations
+pub mod memblock;
+
 /// Debug and diagnostic facilities
 pub mod debug;
 
@@ -146,8 +152,10 @@
 pub use crate::info::{
     AcpiInfo, BootInfo, BootInfoBuilder, FramebufferInfo, MemoryMapEntry, MemoryType, SmbiosInfo,
 };
-pub use crate::memory::{
-    BootAllocator, EarlyAllocator, PageTableSetup, PhysicalMemoryMap, VirtualMapping,
+pub use crate::memory::{EarlyAllocator, EARLY_HEAP};
+pub use crate::memblock::{MemBlock, Reservation, ReservationKind, ReservationRecord};
+pub use crate::placement::{
+    LayoutRecord, PhysRange, PlacementConfig, PlacementLayout, PlacementRegion,
 };
 pub use crate::stages::{
     BootSequence, CpuInitStage, DriverInitStage, HandoffStage, InterruptInitStage, MemoryInitStage,
@@ -180,6 +188,9 @@ pub struct BootConfig {
     /// KASLR entropy bits (typically 8-16)
     pub kaslr_entropy_bits: u8,
 
+    /// Randomize physical placement of early heap, per-CPU and module regions
+    pub placement_randomized: bool,
+
     /// Enable SMP (Symmetric Multi-Processing)
     pub smp_enabled: bool,
 
@@ -223,6 +234,7 @@ pub const fn new() -> Self {
         Self {
             kaslr_enabled: true,
             kaslr_entropy_bits: 12,
+            placement_randomized: true,
             smp_enabled: true,
             max_cpus: 256,
             serial_enabled: true,
@@ -243,6 +255,7 @@ pub const fn qemu() -> Self {
         Self {
             kaslr_enabled: false,
             kaslr_entropy_bits: 0,
+            placement_randomized: false,
             smp_enabled: true,
             max_cpus: 16,
             serial_enabled: true,
@@ -263,6 +276,7 @@ pub const fn minimal() -> Self {
         Self {
             kaslr_enabled: false,
             kaslr_entropy_bits: 0,
+            placement_randomized: false,
             smp_enabled: false,
             max_cpus: 1,
             serial_enabled: true,
@@ -0,0 +1,666 @@
+//! # Helix OS Early Boot - Early Memory Reservations
+//!
+//! Subsystems need physical ranges (crash dump area, AP trampoline, DMA
+//! pools) long before the buddy allocator exists. [`MemBlock`] tracks the
+//! usable memory reported by the firmware and a list of named reservations
+//! carved out of it, in the spirit of Linux memblock.
+//!
+//! ## Lifecycle
+//!
+//! ```text
+//! memory map ──► sanitize ──► MemBlock ◄── reserve / alloc (stages, drivers)
+//!                  │              │
+//!                  │              ▼
+//!     page-align, sort,     handoff: reservations + free ranges
+//!     merge, drop holes           │
+//!                                 ▼
+//!                     buddy allocator seeded from free ranges only
+//! ```
+//!
+//! ## Sanitizing
+//!
+//! Usable ranges are shrunk to page boundaries, sorted and merged; ranges
+//! the firmware reports as anything else win over overlapping usable
+//! entries. Reclaimable memory (ACPI tables, bootloader data, EFI boot
+//! services) is kept as memory but reserved under a firmware name, so it
+//! reaches the page allocator only once [`MemBlock::release`] is called.
+
+use spin::{Mutex, MutexGuard};
+
+use crate::error::{BootError, BootResult};
+use crate::info::{MemoryMapEntry, MemoryType};
+use crate::placement::PhysRange;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Granularity of memory and reservations
+pub const MEMBLOCK_PAGE_SIZE: u64 = 0x1000;
+
+/// Maximum number of usable memory ranges
+pub const MAX_MEMBLOCK_REGIONS: usize = 128;
+
+/// Maximum number of reservations
+pub const MAX_RESERVATIONS: usize = 64;
+
+/// Reservation name length in handoff records
+pub const RESERVATION_NAME_LEN: usize = 16;
+
+/// Round down to alignment (power of two)
+const fn align_down(value: u64, align: u64) -> u64 {
+    value & !(align - 1)
+}
+
+/// Round up to alignment (power of two)
+const fn align_up(value: u64, align: u64) -> u64 {
+    (value.saturating_add(align - 1)) & !(align - 1)
+}
+
+// =============================================================================
+// RESERVATIONS
+// =============================================================================
+
+/// Owner class of a reservation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ReservationKind {
+    /// Firmware data that may be released later
+    Firmware  = 0,
+    /// Kernel image and kernel-critical ranges
+    Kernel    = 1,
+    /// Allocated with [`MemBlock::alloc`]
+    Allocated = 2,
+}
+
+/// A named reserved range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reservation {
+    /// Reserved range (page granular)
+    pub range: PhysRange,
+
+    /// Owner name
+    pub name: &'static str,
+
+    /// Owner class
+    pub kind: ReservationKind,
+}
+
+impl Reservation {
+    const EMPTY: Self = Self {
+        range: PhysRange { start: 0, end: 0 },
+        name: "",
+        kind: ReservationKind::Kernel,
+    };
+}
+
+/// Reservation as recorded in the handoff state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct ReservationRecord {
+    /// Physical base
+    pub base: u64,
+
+    /// Size in bytes
+    pub size: u64,
+
+    /// [`ReservationKind`] value
+    pub kind: u32,
+
+    /// Owner name (NUL padded)
+    pub name: [u8; RESERVATION_NAME_LEN],
+}
+
+impl ReservationRecord {
+    /// Empty record
+    pub const fn empty() -> Self {
+        Self {
+            base: 0,
+            size: 0,
+            kind: 0,
+            name: [0; RESERVATION_NAME_LEN],
+        }
+    }
+
+    /// Record a reservation, truncating its name
+    pub fn new(reservation: &Reservation) -> Self {
+        let mut name = [0; RESERVATION_NAME_LEN];
+        let len = reservation.name.len().min(RESERVATION_NAME_LEN);
+        name[..len].copy_from_slice(&reservation.name.as_bytes()[..len]);
+
+        Self {
+            base: reservation.range.start,
+            size: reservation.range.size(),
+            kind: reservation.kind as u32,
+            name,
+        }
+    }
+
+    /// Owner name
+    pub fn name(&self) -> &str {
+        let len = self.name.iter().position(|&b| b == 0).unwrap_or(RESERVATION_NAME_LEN);
+        core::str::from_utf8(&self.name[..len]).unwrap_or("")
+    }
+
+    /// Reserved range
+    pub const fn range(&self) -> PhysRange {
+        PhysRange::new(self.base, self.size)
+    }
+}
+
+// =============================================================================
+// MEMBLOCK
+// =============================================================================
+
+/// Early physical memory and reservation tracker
+pub struct MemBlock {
+    /// Usable memory, sorted and non-adjacent
+    memory: [PhysRange; MAX_MEMBLOCK_REGIONS],
+    memory_count: usize,
+
+    /// Reservations, sorted and non-overlapping
+    reserved: [Reservation; MAX_RESERVATIONS],
+    reserved_count: usize,
+}
+
+impl MemBlock {
+    /// Create an empty tracker
+    pub const fn new() -> Self {
+        Self {
+            memory: [PhysRange { start: 0, end: 0 }; MAX_MEMBLOCK_REGIONS],
+            memory_count: 0,
+            reserved: [Reservation::EMPTY; MAX_RESERVATIONS],
+            reserved_count: 0,
+        }
+    }
+
+    /// Build from a firmware memory map
+    pub fn from_memory_map(map: &[MemoryMapEntry]) -> BootResult<Self> {
+        let mut memblock = Self::new();
+
+        for entry in map {
+            if Self::firmware_name(entry.memory_type).is_some() || entry.is_usable() {
+                memblock.add_memory(entry.base, entry.end())?;
+            }
+        }
+
+        // Anything else the firmware owns wins over overlapping usable entries
+        for entry in map {
+            if Self::firmware_name(entry.memory_type).is_none() && !entry.is_usable() {
+                memblock.remove_memory(PhysRange::new(entry.base, entry.length));
+            }
+        }
+
+        for entry in map {
+            let Some(name) = Self::firmware_name(entry.memory_type) else {
+                continue;
+            };
+            let kind = if entry.memory_type == MemoryType::KernelAndModules {
+                ReservationKind::Kernel
+            } else {
+                ReservationKind::Firmware
+            };
+
+            // Overlapping firmware entries: the first one keeps the range
+            match memblock.reserve(name, entry.base, entry.length, kind) {
+                Ok(()) | Err(BootError::ReservationConflict { .. }) => {},
+                Err(e) => return Err(e),
+            }
+        }
+
+        if memblock.memory_count == 0 {
+            return Err(BootError::InsufficientMemory);
+        }
+
+        Ok(memblock)
+    }
+
+    /// Reservation name for memory the firmware hands over in use
+    fn firmware_name(memory_type: MemoryType) -> Option<&'static str> {
+        match memory_type {
+            MemoryType::AcpiReclaimable => Some("acpi"),
+            MemoryType::BootloaderReclaimable => Some("bootloader"),
+            MemoryType::EfiBoot => Some("efi-boot"),
+            MemoryType::KernelAndModules => Some("kernel"),
+            _ => None,
+        }
+    }
+
+    /// Add usable memory, merging with neighbours
+    fn add_memory(&mut self, start: u64, end: u64) -> BootResult<()> {
+        let mut range = PhysRange {
+            start: align_up(start, MEMBLOCK_PAGE_SIZE),
+            end: align_down(end, MEMBLOCK_PAGE_SIZE),
+        };
+        if range.is_empty() {
+            return Ok(());
+        }
+
+        // Absorb every range that overlaps or touches the new one
+        let mut i = 0;
+        while i < self.memory_count {
+            let other = self.memory[i];
+            if other.start <= range.end && range.start <= other.end {
+                range.start = range.start.min(other.start);
+                range.end = range.end.max(other.end);
+                self.memory.copy_within(i + 1..self.memory_count, i);
+                self.memory_count -= 1;
+            } else {
+                i += 1;
+            }
+        }
+
+        if self.memory_count == MAX_MEMBLOCK_REGIONS {
+            return Err(BootError::ResourceUnavailable("memblock regions"));
+        }
+
+        let index = self.memory[..self.memory_count].partition_point(|r| r.start < range.start);
+        self.memory.copy_within(index..self.memory_count, index + 1);
+        self.memory[index] = range;
+        self.memory_count += 1;
+
+        Ok(())
+    }
+
+    /// Remove a hole from usable memory
+    fn remove_memory(&mut self, hole: PhysRange) {
+        let hole = PhysRange {
+            start: align_down(hole.start, MEMBLOCK_PAGE_SIZE),
+            end: align_up(hole.end, MEMBLOCK_PAGE_SIZE),
+        };
+
+        let mut i = 0;
+        while i < self.memory_count {
+            let range = self.memory[i];
+            if !range.overlaps(&hole) {
+                i += 1;
+                continue;
+            }
+
+            let below = PhysRange { start: range.start, end: hole.start };
+            let above = PhysRange { start: hole.end, end: range.end };
+
+            match (below.is_empty(), above.is_empty()) {
+                (false, false) if self.memory_count < MAX_MEMBLOCK_REGIONS => {
+                    self.memory[i] = below;
+                    self.memory.copy_within(i + 1..self.memory_count, i + 2);
+                    self.memory[i + 1] = above;
+                    self.memory_count += 1;
+                    i += 2;
+                },
+                // Out of slots: losing the upper part only wastes memory
+                (false, _) => {
+                    self.memory[i] = below;
+                    i += 1;
+                },
+                (true, false) => {
+                    self.memory[i] = above;
+                    i += 1;
+                },
+                (true, true) => {
+                    self.memory.copy_within(i + 1..self.memory_count, i);
+                    self.memory_count -= 1;
+                },
+            }
+        }
+    }
+
+    /// Usable memory ranges, ascending
+    pub fn memory(&self) -> &[PhysRange] {
+        &self.memory[..self.memory_count]
+    }
+
+    /// Reservations, ascending
+    pub fn reservations(&self) -> &[Reservation] {
+        &self.reserved[..self.reserved_count]
+    }
+
+    /// Total usable memory in bytes
+    pub fn total_memory(&self) -> u64 {
+        self.memory().iter().map(PhysRange::size).sum()
+    }
+
+    /// Total reserved bytes
+    pub fn reserved_bytes(&self) -> u64 {
+        self.reservations().iter().map(|r| r.range.size()).sum()
+    }
+
+    /// Reserve a named range
+    ///
+    /// The range is widened to page boundaries. Reserving a range that
+    /// overlaps a reservation with another name fails; the same name
+    /// extends its existing reservation.
+    pub fn reserve(
+        &mut self,
+        name: &'static str,
+        base: u64,
+        size: u64,
+        kind: ReservationKind,
+    ) -> BootResult<()> {
+        if size == 0 {
+            return Err(BootError::InvalidParameter("reservation size"));
+        }
+
+        let mut range = PhysRange {
+            start: align_down(base, MEMBLOCK_PAGE_SIZE),
+            end: align_up(base.saturating_add(size), MEMBLOCK_PAGE_SIZE),
+        };
+
+        if let Some(owner) = self
+            .reservations()
+            .iter()
+            .find(|r| r.range.overlaps(&range) && (r.name != name || r.kind != kind))
+        {
+            return Err(BootError::ReservationConflict {
+                addr: range.start.max(owner.range.start),
+                owner: owner.name,
+            });
+        }
+
+        // Absorb overlapping or adjacent reservations of the same owner
+        let mut i = 0;
+        while i < self.reserved_count {
+            let other = self.reserved[i];
+            let touches = other.range.start <= range.end && range.start <= other.range.end;
+            if touches && other.name == name && other.kind == kind {
+                range.start = range.start.min(other.range.start);
+                range.end = range.end.max(other.range.end);
+                self.reserved.copy_within(i + 1..self.reserved_count, i);
+                self.reserved_count -= 1;
+            } else {
+                i += 1;
+            }
+        }
+
+        if self.reserved_count == MAX_RESERVATIONS {
+            return Err(BootError::ResourceUnavailable("memblock reservations"));
+        }
+
+        let index = self.reservations().partition_point(|r| r.range.start < range.start);
+        self.reserved.copy_within(index..self.reserved_count, index + 1);
+        self.reserved[index] = Reservation { range, name, kind };
+        self.reserved_count += 1;
+
+        Ok(())
+    }
+
+    /// Allocate and reserve `size` bytes within `[min, max)`
+    ///
+    /// Searches top-down, keeping low memory free for devices and the AP
+    /// trampoline.
+    pub fn alloc(
+        &mut self,
+        name: &'static str,
+        size: u64,
+        align: u64,
+        min: u64,
+        max: u64,
+    ) -> BootResult<u64> {
+        if size == 0 {
+            return Err(BootError::InvalidParameter("allocation size"));
+        }
+        if !align.is_power_of_two() {
+            return Err(BootError::InvalidParameter("allocation alignment"));
+        }
+
+        let align = align.max(MEMBLOCK_PAGE_SIZE);
+        let size = align_up(size, MEMBLOCK_PAGE_SIZE);
+
+        let mut found = None;
+        self.for_each_free(|range| {
+            let start = range.start.max(min);
+            let end = range.end.min(max);
+            if start >= end || end - start < size {
+                return;
+            }
+            let base = align_down(end - size, align);
+            if base >= start {
+                found = found.max(Some(base));
+            }
+        });
+
+        let base = found.ok_or(BootError::OutOfMemory)?;
+        self.reserve(name, base, size, ReservationKind::Allocated)?;
+
+        Ok(base)
+    }
+
+    /// Drop every reservation held by `name`
+    ///
+    /// Returns the number of bytes returned to free memory.
+    pub fn release(&mut self, name: &str) -> u64 {
+        let mut released = 0;
+        let mut i = 0;
+        while i < self.reserved_count {
+            if self.reserved[i].name == name {
+                released += self.reserved[i].range.size();
+                self.reserved.copy_within(i + 1..self.reserved_count, i);
+                self.reserved_count -= 1;
+            } else {
+                i += 1;
+            }
+        }
+        released
+    }
+
+    /// Find the first reservation held by `name`
+    pub fn find(&self, name: &str) -> Option<&Reservation> {
+        self.reservations().iter().find(|r| r.name == name)
+    }
+
+    /// Reservation covering an address
+    pub fn reserved_at(&self, addr: u64) -> Option<&Reservation> {
+        self.reservations().iter().find(|r| r.range.contains(addr))
+    }
+
+    /// Visit usable memory not covered by any reservation, ascending
+    pub fn for_each_free(&self, mut f: impl FnMut(PhysRange)) {
+        let reserved = self.reservations();
+        let mut next = 0;
+
+        for memory in self.memory() {
+            let mut cursor = memory.start;
+
+            // Reservations are sorted, so skip those wholly below this range
+            while next < reserved.len() && reserved[next].range.end <= memory.start {
+                next += 1;
+            }
+
+            let mut i = next;
+            while i < reserved.len() && reserved[i].range.start < memory.end {
+                let hole = reserved[i].range;
+                if hole.start > cursor {
+                    f(PhysRange { start: cursor, end: hole.start });
+                }
+                cursor = cursor.max(hole.end);
+                i += 1;
+            }
+
+            if cursor < memory.end {
+                f(PhysRange { start: cursor, end: memory.end });
+            }
+        }
+    }
+
+    /// Copy free ranges into `out`, returning how many were written
+    pub fn free_ranges(&self, out: &mut [PhysRange]) -> usize {
+        let mut count = 0;
+        self.for_each_free(|range| {
+            if count < out.len() {
+                out[count] = range;
+                count += 1;
+            }
+        });
+        count
+    }
+
+    /// Copy reserved ranges into `out`, returning how many were written
+    pub fn reserved_ranges(&self, out: &mut [PhysRange]) -> usize {
+        let count = self.reserved_count.min(out.len());
+        for (slot, reservation) in out.iter_mut().zip(self.reservations()) {
+            *slot = reservation.range;
+        }
+        count
+    }
+}
+
+impl Default for MemBlock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// GLOBAL STATE
+// =============================================================================
+
+/// Reservations made during early boot
+static MEMBLOCK: Mutex<MemBlock> = Mutex::new(MemBlock::new());
+
+/// Install the boot memblock
+pub fn set_memblock(memblock: MemBlock) {
+    *MEMBLOCK.lock() = memblock;
+}
+
+/// Access the boot memblock
+pub fn memblock() -> MutexGuard<'static, MemBlock> {
+    MEMBLOCK.lock()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::MemoryAttributes;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn entry(base: u64, length: u64, memory_type: MemoryType) -> MemoryMapEntry {
+        MemoryMapEntry {
+            base,
+            length,
+            memory_type,
+            attributes: MemoryAttributes::empty(),
+        }
+    }
+
+    fn free(memblock: &MemBlock) -> ([PhysRange; 16], usize) {
+        let mut out = [PhysRange::default(); 16];
+        let count = memblock.free_ranges(&mut out);
+        (out, count)
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let map = [
+            entry(2 * MB, 2 * MB, MemoryType::Usable),
+            entry(0x800, MB, MemoryType::Usable),
+            entry(MB + 0x800, MB - 0x800, MemoryType::Usable),
+            entry(3 * MB, 0x1000, MemoryType::Reserved),
+            entry(8 * MB, MB, MemoryType::AcpiReclaimable),
+        ];
+        let memblock = MemBlock::from_memory_map(&map).unwrap();
+
+        assert_eq!(
+            memblock.memory(),
+            &[
+                PhysRange { start: 0x1000, end: MB },
+                PhysRange { start: MB + 0x1000, end: 3 * MB },
+                PhysRange { start: 3 * MB + 0x1000, end: 4 * MB },
+                PhysRange::new(8 * MB, MB),
+            ]
+        );
+
+        let acpi = memblock.find("acpi").unwrap();
+        assert_eq!(acpi.range, PhysRange::new(8 * MB, MB));
+        assert_eq!(acpi.kind, ReservationKind::Firmware);
+
+        let (ranges, count) = free(&memblock);
+        assert_eq!(count, 3);
+        assert_eq!(ranges[2], PhysRange { start: 3 * MB + 0x1000, end: 4 * MB });
+    }
+
+    #[test]
+    fn test_reserve_overlap() {
+        let map = [entry(0, 64 * MB, MemoryType::Usable)];
+        let mut memblock = MemBlock::from_memory_map(&map).unwrap();
+
+        memblock.reserve("crashkernel", 16 * MB, 8 * MB, ReservationKind::Kernel).unwrap();
+        assert!(matches!(
+            memblock.reserve("dma-pool", 20 * MB, 8 * MB, ReservationKind::Kernel),
+            Err(BootError::ReservationConflict { addr, owner: "crashkernel" }) if addr == 20 * MB
+        ));
+
+        // Same owner extends its reservation
+        memblock.reserve("crashkernel", 24 * MB, MB, ReservationKind::Kernel).unwrap();
+        assert_eq!(memblock.reservations().len(), 1);
+        assert_eq!(memblock.find("crashkernel").unwrap().range, PhysRange::new(16 * MB, 9 * MB));
+
+        // Unaligned reservations cover whole pages
+        memblock.reserve("trampoline", 0x8010, 0x10, ReservationKind::Kernel).unwrap();
+        assert_eq!(memblock.reserved_at(0x8000).unwrap().name, "trampoline");
+        assert_eq!(memblock.reserved_bytes(), 9 * MB + 0x1000);
+    }
+
+    #[test]
+    fn test_alloc() {
+        let map = [entry(0, 64 * MB, MemoryType::Usable)];
+        let mut memblock = MemBlock::from_memory_map(&map).unwrap();
+        memblock.reserve("kernel", 60 * MB, 4 * MB, ReservationKind::Kernel).unwrap();
+
+        let top = memblock.alloc("dma-pool", 2 * MB, 2 * MB, 0, u64::MAX).unwrap();
+        assert_eq!(top, 58 * MB);
+
+        let low = memblock.alloc("trampoline", 0x1000, 0x1000, 0, MB).unwrap();
+        assert_eq!(low, MB - 0x1000);
+        assert_eq!(memblock.reserved_at(low).unwrap().kind, ReservationKind::Allocated);
+
+        assert!(matches!(
+            memblock.alloc("huge", 128 * MB, 0x1000, 0, u64::MAX),
+            Err(BootError::OutOfMemory)
+        ));
+    }
+
+    #[test]
+    fn test_free_ranges_and_release() {
+        let map = [
+            entry(0, 16 * MB, MemoryType::Usable),
+            entry(16 * MB, 4 * MB, MemoryType::BootloaderReclaimable),
+            entry(32 * MB, 16 * MB, MemoryType::Usable),
+        ];
+        let mut memblock = MemBlock::from_memory_map(&map).unwrap();
+        memblock.reserve("kernel", 2 * MB, 4 * MB, ReservationKind::Kernel).unwrap();
+
+        let (ranges, count) = free(&memblock);
+        assert_eq!(
+            &ranges[..count],
+            &[
+                PhysRange { start: 0, end: 2 * MB },
+                PhysRange { start: 6 * MB, end: 16 * MB },
+                PhysRange { start: 32 * MB, end: 48 * MB },
+            ]
+        );
+
+        assert_eq!(memblock.release("bootloader"), 4 * MB);
+        let (ranges, count) = free(&memblock);
+        assert_eq!(count, 3);
+        assert_eq!(ranges[1], PhysRange { start: 6 * MB, end: 20 * MB });
+    }
+
+    #[test]
+    fn test_reservation_record() {
+        let reservation = Reservation {
+            range: PhysRange::new(0x10_0000, 0x2000),
+            name: "a-very-long-reservation-name",
+            kind: ReservationKind::Firmware,
+        };
+        let record = ReservationRecord::new(&reservation);
+
+        assert_eq!(record.name(), "a-very-long-rese");
+        assert_eq!(record.range(), reservation.range);
+        assert_eq!(record.kind, ReservationKind::Firmware as u32);
+    }
+}
@@ -0,0 +1,194 @@
+//! # Helix OS Early Boot - Early Heap
+//!
+//! Boot-time allocations (boot log formatting, driver tables) are served
+//! from the early heap until the kernel heap takes over. The heap lives in
+//! the physical region [`crate::placement`] chose for it and is reached
+//! through the HHDM.
+//!
+//! [`EarlyAllocator`] is a bump allocator: allocation is a pointer
+//! increment and nothing is ever freed. Early boot allocates little and
+//! hands the whole region back at once, so reuse is not worth the code.
+
+use core::alloc::{GlobalAlloc, Layout};
+
+use spin::Mutex;
+
+use crate::core::BootContext;
+use crate::error::{BootError, BootResult};
+use crate::placement::PhysRange;
+
+// =============================================================================
+// EARLY ALLOCATOR
+// =============================================================================
+
+/// Bump allocator over a fixed region
+pub struct EarlyAllocator {
+    heap: Mutex<EarlyHeap>,
+}
+
+/// Region and allocation cursor of an [`EarlyAllocator`]
+struct EarlyHeap {
+    /// First byte of the region (0: not initialized)
+    start: usize,
+    /// Next free byte
+    next: usize,
+    /// One past the last byte
+    end: usize,
+}
+
+impl EarlyAllocator {
+    /// Create an allocator with no region; every allocation fails
+    pub const fn new() -> Self {
+        Self {
+            heap: Mutex::new(EarlyHeap {
+                start: 0,
+                next: 0,
+                end: 0,
+            }),
+        }
+    }
+
+    /// Hand the allocator its region
+    ///
+    /// # Safety
+    ///
+    /// `[start, start + size)` must be mapped, writable and used by
+    /// nothing else for as long as allocations from it are live.
+    pub unsafe fn init(&self, start: usize, size: usize) -> BootResult<()> {
+        let mut heap = self.heap.lock();
+        if heap.start != 0 || start == 0 || size == 0 {
+            return Err(BootError::EarlyHeapFailed);
+        }
+        let end = start.checked_add(size).ok_or(BootError::EarlyHeapFailed)?;
+
+        *heap = EarlyHeap {
+            start,
+            next: start,
+            end,
+        };
+        Ok(())
+    }
+
+    /// Size of the region in bytes
+    pub fn size(&self) -> usize {
+        let heap = self.heap.lock();
+        heap.end - heap.start
+    }
+
+    /// Bytes handed out so far, alignment padding included
+    pub fn used(&self) -> usize {
+        let heap = self.heap.lock();
+        heap.next - heap.start
+    }
+}
+
+impl Default for EarlyAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for EarlyAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut heap = self.heap.lock();
+        let Some(start) = heap.next.checked_add(layout.align() - 1) else {
+            return core::ptr::null_mut();
+        };
+        let start = start & !(layout.align() - 1);
+        match start.checked_add(layout.size()) {
+            Some(end) if end <= heap.end => {
+                heap.next = end;
+                start as *mut u8
+            },
+            _ => core::ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
+        // Freed all at once when the kernel heap takes over
+    }
+}
+
+/// The early heap
+pub static EARLY_HEAP: EarlyAllocator = EarlyAllocator::new();
+
+// =============================================================================
+// INITIALIZATION
+// =============================================================================
+
+/// Set up the early heap in the physical range `range`
+///
+/// # Safety
+///
+/// The boot page tables must be live with `range` covered by the HHDM, and
+/// `range` must be reserved so nothing else is placed in it.
+pub unsafe fn init_early_heap(ctx: &mut BootContext, range: PhysRange) -> BootResult<()> {
+    if range.is_empty() {
+        return Err(BootError::EarlyHeapFailed);
+    }
+    let start = ctx
+        .memory_state
+        .hhdm_base
+        .checked_add(range.start)
+        .ok_or(BootError::InvalidAddress(range.start))?;
+
+    EARLY_HEAP.init(start as usize, range.size() as usize)?;
+    ctx.memory_state.early_heap_size = range.size();
+
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C, align(4096))]
+    struct Region([u8; 4096]);
+
+    fn heap_over(region: &mut Region) -> EarlyAllocator {
+        let allocator = EarlyAllocator::new();
+        unsafe { allocator.init(region.0.as_mut_ptr() as usize, region.0.len()) }.unwrap();
+        allocator
+    }
+
+    #[test]
+    fn test_alloc_aligned_and_bounded() {
+        let mut region = Region([0; 4096]);
+        let base = region.0.as_ptr() as usize;
+        let allocator = heap_over(&mut region);
+
+        let a = unsafe { allocator.alloc(Layout::from_size_align(3, 1).unwrap()) };
+        let b = unsafe { allocator.alloc(Layout::from_size_align(64, 64).unwrap()) };
+        assert_eq!(a as usize, base);
+        assert_eq!(b as usize, base + 64);
+        assert_eq!(allocator.used(), 128);
+
+        let rest = Layout::from_size_align(4096 - 128, 1).unwrap();
+        assert!(!unsafe { allocator.alloc(rest) }.is_null());
+        let full = unsafe { allocator.alloc(Layout::from_size_align(1, 1).unwrap()) };
+        assert!(full.is_null());
+        assert_eq!(allocator.used(), allocator.size());
+    }
+
+    #[test]
+    fn test_init_rejected() {
+        let layout = Layout::from_size_align(8, 8).unwrap();
+        assert!(unsafe { EarlyAllocator::new().alloc(layout) }.is_null());
+
+        let mut region = Region([0; 4096]);
+        let start = region.0.as_ptr() as usize;
+        let allocator = heap_over(&mut region);
+        assert!(matches!(
+            unsafe { allocator.init(start, 4096) },
+            Err(BootError::EarlyHeapFailed)
+        ));
+        assert!(matches!(
+            unsafe { EarlyAllocator::new().init(start, 0) },
+            Err(BootError::EarlyHeapFailed)
+        ));
+    }
+}
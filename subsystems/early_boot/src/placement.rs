@@ -0,0 +1,864 @@
+//! # Helix OS Early Boot - Physical Placement Randomization
+//!
+//! Image KASLR only moves the kernel text. This module extends randomization
+//! to the physical placement of the early kernel heap, the per-CPU areas and
+//! the module loading region, using the same boot entropy as KASLR.
+//!
+//! ## Algorithm
+//!
+//! ```text
+//! memory map ──► usable ranges ──► minus reserved (kernel image, …)
+//!                                        │
+//!                                        ▼
+//!                         free list of aligned candidate slots
+//!                                        │
+//!          ┌─────────────────────────────┼─────────────────────────────┐
+//!          ▼                             ▼                             ▼
+//!   module region                   early heap                   per-CPU areas
+//!   (largest first)            (slot n of remaining)        (slot n of remaining)
+//! ```
+//!
+//! Each region picks a uniformly random aligned slot among every position
+//! where it fits, then the chosen range is carved out of the free list so
+//! later regions never overlap it.
+//!
+//! ## Crash Dumps
+//!
+//! The chosen layout is summarized in a [`LayoutRecord`], a fixed-size,
+//! checksummed structure embedded in the handoff state. Post-mortem tools
+//! read it back from the dump with [`LayoutRecord::from_bytes`] and use
+//! [`LayoutRecord::resolve`] to map physical addresses to regions.
+
+use spin::Mutex;
+
+use crate::error::{BootError, BootResult};
+use crate::handoff::{gather_entropy, Xorshift64};
+use crate::info::MemoryMapEntry;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Placement alignment (2MB, large-page friendly)
+pub const PLACEMENT_ALIGNMENT: u64 = 2 * 1024 * 1024;
+
+/// Lowest physical address considered (keeps legacy low memory free)
+pub const PLACEMENT_MIN_PHYS: u64 = 16 * 1024 * 1024;
+
+/// Default early heap size (16MB)
+pub const DEFAULT_HEAP_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Default per-CPU area size (64KB per CPU)
+pub const DEFAULT_PERCPU_SIZE: u64 = 64 * 1024;
+
+/// Default module region size (64MB)
+pub const DEFAULT_MODULE_REGION_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Maximum number of free ranges tracked during placement
+pub const MAX_FREE_RANGES: usize = 64;
+
+/// Layout record magic: "HLXLAYT\0"
+pub const LAYOUT_MAGIC: u64 = 0x0054_5941_4C58_4C48;
+
+/// Layout record format version
+pub const LAYOUT_VERSION: u32 = 1;
+
+// =============================================================================
+// PLACEMENT CONFIGURATION
+// =============================================================================
+
+/// Placement randomization configuration
+#[derive(Debug, Clone, Copy)]
+pub struct PlacementConfig {
+    /// Randomize placement (lowest fit otherwise)
+    pub enabled: bool,
+
+    /// Early heap size (bytes)
+    pub heap_size: u64,
+
+    /// Per-CPU area size (bytes per CPU)
+    pub percpu_size: u64,
+
+    /// Number of per-CPU areas
+    pub cpu_count: u32,
+
+    /// Module loading region size (bytes)
+    pub module_region_size: u64,
+
+    /// Alignment requirement (bytes)
+    pub alignment: u64,
+
+    /// Lowest acceptable physical address
+    pub min_phys: u64,
+
+    /// Highest acceptable physical address (exclusive)
+    pub max_phys: u64,
+
+    /// Use hardware RNG if available
+    pub use_hardware_rng: bool,
+
+    /// Fallback seed (if no RNG available)
+    pub fallback_seed: u64,
+}
+
+impl PlacementConfig {
+    /// Create default placement configuration
+    pub const fn new() -> Self {
+        Self {
+            enabled: true,
+            heap_size: DEFAULT_HEAP_SIZE,
+            percpu_size: DEFAULT_PERCPU_SIZE,
+            cpu_count: 1,
+            module_region_size: DEFAULT_MODULE_REGION_SIZE,
+            alignment: PLACEMENT_ALIGNMENT,
+            min_phys: PLACEMENT_MIN_PHYS,
+            max_phys: u64::MAX,
+            use_hardware_rng: true,
+            fallback_seed: 0xC0FF_EE15_DEAD_F00D,
+        }
+    }
+
+    /// Fixed lowest-fit placement
+    pub const fn disabled() -> Self {
+        Self {
+            enabled: false,
+            use_hardware_rng: false,
+            fallback_seed: 0,
+            ..Self::new()
+        }
+    }
+
+    /// Set number of per-CPU areas
+    pub const fn with_cpus(mut self, cpu_count: u32) -> Self {
+        self.cpu_count = cpu_count;
+        self
+    }
+
+    /// Per-CPU stride (area size rounded up to a page)
+    pub const fn percpu_stride(&self) -> u64 {
+        (self.percpu_size + 0xFFF) & !0xFFF
+    }
+
+    /// Total size of all per-CPU areas
+    pub const fn percpu_total(&self) -> u64 {
+        self.percpu_stride() * self.cpu_count as u64
+    }
+}
+
+impl Default for PlacementConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// PHYSICAL RANGES
+// =============================================================================
+
+/// Half-open physical address range `[start, end)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PhysRange {
+    /// First byte
+    pub start: u64,
+    /// One past the last byte
+    pub end: u64,
+}
+
+impl PhysRange {
+    /// Create range from base and size
+    pub const fn new(start: u64, size: u64) -> Self {
+        Self {
+            start,
+            end: start.saturating_add(size),
+        }
+    }
+
+    /// Size in bytes
+    pub const fn size(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Check if empty
+    pub const fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    /// Check if address is in range
+    pub const fn contains(&self, addr: u64) -> bool {
+        addr >= self.start && addr < self.end
+    }
+
+    /// Check if two ranges overlap
+    pub const fn overlaps(&self, other: &PhysRange) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+/// Round up to alignment (power of two)
+const fn align_up(value: u64, align: u64) -> u64 {
+    (value.saturating_add(align - 1)) & !(align - 1)
+}
+
+/// Fixed-capacity list of free physical ranges
+struct FreeList {
+    ranges: [PhysRange; MAX_FREE_RANGES],
+    count: usize,
+}
+
+impl FreeList {
+    /// Build from usable memory map entries clipped to `[min, max)`
+    fn from_memory_map(map: &[MemoryMapEntry], min: u64, max: u64) -> Self {
+        let mut list = Self {
+            ranges: [PhysRange::default(); MAX_FREE_RANGES],
+            count: 0,
+        };
+
+        for entry in map.iter().filter(|e| e.is_usable()) {
+            let start = entry.base.max(min);
+            let end = entry.end().min(max);
+            if start < end {
+                list.push(PhysRange { start, end });
+            }
+        }
+
+        list
+    }
+
+    fn push(&mut self, range: PhysRange) {
+        // Excess fragments are dropped: placement only gets less random
+        if self.count < MAX_FREE_RANGES {
+            self.ranges[self.count] = range;
+            self.count += 1;
+        }
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.ranges.copy_within(index + 1..self.count, index);
+        self.count -= 1;
+    }
+
+    /// Remove `hole` from every free range
+    fn subtract(&mut self, hole: PhysRange) {
+        let mut i = 0;
+        while i < self.count {
+            let range = self.ranges[i];
+            if !range.overlaps(&hole) {
+                i += 1;
+                continue;
+            }
+
+            let below = PhysRange { start: range.start, end: hole.start };
+            let above = PhysRange { start: hole.end, end: range.end };
+
+            self.remove(i);
+            if !below.is_empty() {
+                self.push(below);
+            }
+            if !above.is_empty() {
+                self.push(above);
+            }
+        }
+    }
+
+    /// Number of aligned slots of `size` in a range
+    fn slots_in(range: &PhysRange, size: u64, align: u64) -> u64 {
+        let first = align_up(range.start, align);
+        if first >= range.end || range.end - first < size {
+            return 0;
+        }
+        (range.end - first - size) / align + 1
+    }
+
+    /// Total number of candidate slots
+    fn count_slots(&self, size: u64, align: u64) -> u64 {
+        self.ranges[..self.count]
+            .iter()
+            .map(|r| Self::slots_in(r, size, align))
+            .fold(0u64, |acc, n| acc.saturating_add(n))
+    }
+
+    /// The `n`-th candidate slot, counting in ascending address order
+    fn nth_slot(&self, mut n: u64, size: u64, align: u64) -> Option<PhysRange> {
+        let mut order = [0usize; MAX_FREE_RANGES];
+        for (i, slot) in order[..self.count].iter_mut().enumerate() {
+            *slot = i;
+        }
+        order[..self.count].sort_unstable_by_key(|&i| self.ranges[i].start);
+
+        for &i in &order[..self.count] {
+            let range = &self.ranges[i];
+            let slots = Self::slots_in(range, size, align);
+            if n < slots {
+                let start = align_up(range.start, align) + n * align;
+                return Some(PhysRange::new(start, size));
+            }
+            n -= slots;
+        }
+
+        None
+    }
+}
+
+// =============================================================================
+// LAYOUT
+// =============================================================================
+
+/// Randomized region kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum PlacementRegion {
+    /// Early kernel heap
+    Heap    = 0,
+    /// Per-CPU areas
+    PerCpu  = 1,
+    /// Module loading region
+    Modules = 2,
+}
+
+impl PlacementRegion {
+    /// Region name
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Heap => "heap",
+            Self::PerCpu => "percpu",
+            Self::Modules => "modules",
+        }
+    }
+}
+
+/// A placed region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    /// Chosen physical range
+    pub range: PhysRange,
+
+    /// Number of slots the region could have landed in
+    pub slots: u64,
+}
+
+impl Placement {
+    /// Entropy of this placement in bits
+    pub const fn entropy_bits(&self) -> u32 {
+        if self.slots <= 1 {
+            0
+        } else {
+            64 - (self.slots - 1).leading_zeros()
+        }
+    }
+}
+
+/// Randomized physical layout of early kernel regions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlacementLayout {
+    /// Early kernel heap
+    pub heap: Placement,
+
+    /// Per-CPU areas (contiguous, `percpu_stride` apart)
+    pub percpu: Placement,
+
+    /// Distance between consecutive per-CPU areas
+    pub percpu_stride: u64,
+
+    /// Number of per-CPU areas
+    pub cpu_count: u32,
+
+    /// Module loading region
+    pub modules: Placement,
+
+    /// Whether placement was randomized
+    pub randomized: bool,
+}
+
+impl PlacementLayout {
+    /// Get placement of a region
+    pub const fn region(&self, region: PlacementRegion) -> &Placement {
+        match region {
+            PlacementRegion::Heap => &self.heap,
+            PlacementRegion::PerCpu => &self.percpu,
+            PlacementRegion::Modules => &self.modules,
+        }
+    }
+
+    /// Physical base of a CPU's per-CPU area
+    pub fn percpu_base(&self, cpu: u32) -> Option<u64> {
+        if cpu < self.cpu_count {
+            Some(self.percpu.range.start + cpu as u64 * self.percpu_stride)
+        } else {
+            None
+        }
+    }
+
+    /// Build crash-dump record
+    pub fn record(&self, kaslr_offset: u64) -> LayoutRecord {
+        LayoutRecord::new(self, kaslr_offset)
+    }
+}
+
+/// Compute a randomized placement for the early regions
+///
+/// `reserved` lists ranges that must not be used even if the memory map
+/// reports them usable (typically the kernel image and boot modules).
+pub fn randomize(
+    map: &[MemoryMapEntry],
+    reserved: &[PhysRange],
+    config: &PlacementConfig,
+) -> BootResult<PlacementLayout> {
+    let seed = if config.enabled {
+        let entropy = if config.use_hardware_rng { gather_entropy() } else { 0 };
+        entropy ^ config.fallback_seed
+    } else {
+        0
+    };
+
+    randomize_with_seed(map, reserved, config, seed)
+}
+
+/// Compute a placement from an explicit seed
+pub fn randomize_with_seed(
+    map: &[MemoryMapEntry],
+    reserved: &[PhysRange],
+    config: &PlacementConfig,
+    seed: u64,
+) -> BootResult<PlacementLayout> {
+    if !config.alignment.is_power_of_two() {
+        return Err(BootError::InvalidParameter("placement alignment"));
+    }
+    if config.cpu_count == 0 {
+        return Err(BootError::InvalidParameter("placement cpu count"));
+    }
+
+    let mut free = FreeList::from_memory_map(map, config.min_phys, config.max_phys);
+    if free.count == 0 {
+        return Err(BootError::InsufficientMemory);
+    }
+    for hole in reserved {
+        free.subtract(*hole);
+    }
+
+    let mut rng = Xorshift64::new(seed);
+    let mut place = |size: u64, error: BootError| -> BootResult<Placement> {
+        let size = align_up(size, 0x1000);
+        let slots = free.count_slots(size, config.alignment);
+        if slots == 0 {
+            return Err(error);
+        }
+
+        let n = if config.enabled { rng.next_range(slots) } else { 0 };
+        let range = free.nth_slot(n, size, config.alignment).ok_or(error)?;
+        free.subtract(range);
+
+        Ok(Placement { range, slots })
+    };
+
+    // Largest region first so it is least likely to be starved
+    let modules = place(config.module_region_size, BootError::InsufficientMemory)?;
+    let heap = place(config.heap_size, BootError::EarlyHeapFailed)?;
+    let percpu = place(config.percpu_total(), BootError::PerCpuAllocationFailed)?;
+
+    Ok(PlacementLayout {
+        heap,
+        percpu,
+        percpu_stride: config.percpu_stride(),
+        cpu_count: config.cpu_count,
+        modules,
+        randomized: config.enabled,
+    })
+}
+
+// =============================================================================
+// CRASH-DUMP RECORD
+// =============================================================================
+
+/// Physical layout summary embedded in crash dumps
+///
+/// Fixed size, little-endian, and self-validating so it can be located and
+/// trusted in a raw memory image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct LayoutRecord {
+    /// Magic value ([`LAYOUT_MAGIC`])
+    pub magic: u64,
+
+    /// Format version
+    pub version: u32,
+
+    /// Number of per-CPU areas
+    pub cpu_count: u32,
+
+    /// Image KASLR offset
+    pub kaslr_offset: u64,
+
+    /// Early heap base
+    pub heap_base: u64,
+
+    /// Early heap size
+    pub heap_size: u64,
+
+    /// First per-CPU area base
+    pub percpu_base: u64,
+
+    /// Distance between per-CPU areas
+    pub percpu_stride: u64,
+
+    /// Module region base
+    pub module_base: u64,
+
+    /// Module region size
+    pub module_size: u64,
+
+    /// Non-zero if placement was randomized
+    pub randomized: u64,
+
+    /// Checksum over all preceding fields
+    pub checksum: u64,
+}
+
+impl LayoutRecord {
+    /// Number of serialized words
+    const WORDS: usize = 11;
+
+    /// Serialized size
+    pub const SIZE: usize = Self::WORDS * 8;
+
+    /// Empty (invalid) record
+    pub const fn empty() -> Self {
+        Self {
+            magic: 0,
+            version: 0,
+            cpu_count: 0,
+            kaslr_offset: 0,
+            heap_base: 0,
+            heap_size: 0,
+            percpu_base: 0,
+            percpu_stride: 0,
+            module_base: 0,
+            module_size: 0,
+            randomized: 0,
+            checksum: 0,
+        }
+    }
+
+    /// Build record from a layout
+    pub fn new(layout: &PlacementLayout, kaslr_offset: u64) -> Self {
+        let mut record = Self {
+            magic: LAYOUT_MAGIC,
+            version: LAYOUT_VERSION,
+            cpu_count: layout.cpu_count,
+            kaslr_offset,
+            heap_base: layout.heap.range.start,
+            heap_size: layout.heap.range.size(),
+            percpu_base: layout.percpu.range.start,
+            percpu_stride: layout.percpu_stride,
+            module_base: layout.modules.range.start,
+            module_size: layout.modules.range.size(),
+            randomized: layout.randomized as u64,
+            checksum: 0,
+        };
+        record.checksum = record.compute_checksum();
+        record
+    }
+
+    /// Fields in serialization order, checksum last
+    fn words(&self) -> [u64; Self::WORDS] {
+        [
+            self.magic,
+            self.version as u64 | ((self.cpu_count as u64) << 32),
+            self.kaslr_offset,
+            self.heap_base,
+            self.heap_size,
+            self.percpu_base,
+            self.percpu_stride,
+            self.module_base,
+            self.module_size,
+            self.randomized,
+            self.checksum,
+        ]
+    }
+
+    /// FNV-1a over the serialized fields
+    fn compute_checksum(&self) -> u64 {
+        let words = self.words();
+        let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+        for word in &words[..words.len() - 1] {
+            for byte in word.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+            }
+        }
+        hash
+    }
+
+    /// Validate magic, version and checksum
+    pub fn validate(&self) -> bool {
+        self.magic == LAYOUT_MAGIC
+            && self.version == LAYOUT_VERSION
+            && self.checksum == self.compute_checksum()
+    }
+
+    /// Serialize to bytes
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(self.words()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Parse and validate from bytes (e.g. read from a crash dump)
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < Self::SIZE {
+            return None;
+        }
+
+        let mut words = [0u64; Self::WORDS];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+            *word = u64::from_le_bytes(chunk.try_into().ok()?);
+        }
+
+        let record = Self {
+            magic: words[0],
+            version: words[1] as u32,
+            cpu_count: (words[1] >> 32) as u32,
+            kaslr_offset: words[2],
+            heap_base: words[3],
+            heap_size: words[4],
+            percpu_base: words[5],
+            percpu_stride: words[6],
+            module_base: words[7],
+            module_size: words[8],
+            randomized: words[9],
+            checksum: words[10],
+        };
+
+        record.validate().then_some(record)
+    }
+
+    /// Resolve a physical address to a region and offset
+    ///
+    /// For per-CPU addresses the offset is relative to the owning CPU's
+    /// area; use [`LayoutRecord::percpu_owner`] to find the CPU.
+    pub fn resolve(&self, addr: u64) -> Option<(PlacementRegion, u64)> {
+        let percpu_size = self.percpu_stride * self.cpu_count as u64;
+
+        if PhysRange::new(self.heap_base, self.heap_size).contains(addr) {
+            Some((PlacementRegion::Heap, addr - self.heap_base))
+        } else if PhysRange::new(self.percpu_base, percpu_size).contains(addr) {
+            Some((PlacementRegion::PerCpu, (addr - self.percpu_base) % self.percpu_stride))
+        } else if PhysRange::new(self.module_base, self.module_size).contains(addr) {
+            Some((PlacementRegion::Modules, addr - self.module_base))
+        } else {
+            None
+        }
+    }
+
+    /// CPU owning a per-CPU address
+    pub fn percpu_owner(&self, addr: u64) -> Option<u32> {
+        match self.resolve(addr) {
+            Some((PlacementRegion::PerCpu, _)) => {
+                Some(((addr - self.percpu_base) / self.percpu_stride) as u32)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for LayoutRecord {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+// =============================================================================
+// GLOBAL LAYOUT
+// =============================================================================
+
+/// Layout chosen during memory initialization
+static LAYOUT: Mutex<Option<PlacementLayout>> = Mutex::new(None);
+
+/// Record the boot layout
+pub fn set_layout(layout: PlacementLayout) {
+    *LAYOUT.lock() = Some(layout);
+}
+
+/// Get the boot layout, if placement has run
+pub fn layout() -> Option<PlacementLayout> {
+    *LAYOUT.lock()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::{MemoryAttributes, MemoryType};
+
+    const MB: u64 = 1024 * 1024;
+
+    fn entry(base: u64, length: u64, memory_type: MemoryType) -> MemoryMapEntry {
+        MemoryMapEntry {
+            base,
+            length,
+            memory_type,
+            attributes: MemoryAttributes::empty(),
+        }
+    }
+
+    fn test_map() -> [MemoryMapEntry; 4] {
+        [
+            entry(0, MB, MemoryType::Reserved),
+            entry(MB, 1023 * MB, MemoryType::Usable),
+            entry(1024 * MB, 64 * MB, MemoryType::Reserved),
+            entry(1088 * MB, 960 * MB, MemoryType::Usable),
+        ]
+    }
+
+    fn assert_in_usable(range: &PhysRange, map: &[MemoryMapEntry]) {
+        assert!(map
+            .iter()
+            .any(|e| e.is_usable() && range.start >= e.base && range.end <= e.end()));
+    }
+
+    #[test]
+    fn test_free_list_subtract() {
+        let map = [entry(0, 100 * MB, MemoryType::Usable)];
+        let mut free = FreeList::from_memory_map(&map, 16 * MB, u64::MAX);
+        free.subtract(PhysRange::new(32 * MB, 8 * MB));
+
+        assert_eq!(free.count, 2);
+        assert_eq!(free.count_slots(2 * MB, 2 * MB), 8 + 30);
+        assert_eq!(
+            free.nth_slot(8, 2 * MB, 2 * MB),
+            Some(PhysRange::new(40 * MB, 2 * MB))
+        );
+    }
+
+    #[test]
+    fn test_randomize_no_overlap() {
+        let map = test_map();
+        let kernel = PhysRange::new(16 * MB, 32 * MB);
+        let config = PlacementConfig::new().with_cpus(8);
+
+        for seed in 1..64 {
+            let layout = randomize_with_seed(&map, &[kernel], &config, seed).unwrap();
+            let regions = [layout.heap.range, layout.percpu.range, layout.modules.range];
+
+            for (i, a) in regions.iter().enumerate() {
+                assert_eq!(a.start % PLACEMENT_ALIGNMENT, 0);
+                assert!(a.start >= PLACEMENT_MIN_PHYS);
+                assert!(!a.overlaps(&kernel));
+                assert_in_usable(a, &map);
+                for b in &regions[i + 1..] {
+                    assert!(!a.overlaps(b));
+                }
+            }
+
+            assert_eq!(layout.percpu.range.size(), 8 * DEFAULT_PERCPU_SIZE);
+            assert_eq!(
+                layout.percpu_base(7),
+                Some(layout.percpu.range.start + 7 * DEFAULT_PERCPU_SIZE)
+            );
+            assert_eq!(layout.percpu_base(8), None);
+        }
+    }
+
+    #[test]
+    fn test_randomize_varies() {
+        let map = test_map();
+        let config = PlacementConfig::new();
+
+        let a = randomize_with_seed(&map, &[], &config, 1).unwrap();
+        let b = randomize_with_seed(&map, &[], &config, 2).unwrap();
+
+        assert_ne!(a.heap.range, b.heap.range);
+        assert!(a.heap.entropy_bits() >= 8);
+    }
+
+    #[test]
+    fn test_disabled_is_lowest_fit() {
+        let map = test_map();
+        let config = PlacementConfig::disabled();
+
+        let layout = randomize_with_seed(&map, &[], &config, 12345).unwrap();
+
+        assert!(!layout.randomized);
+        assert_eq!(layout.modules.range.start, PLACEMENT_MIN_PHYS);
+        assert_eq!(layout.heap.range.start, PLACEMENT_MIN_PHYS + DEFAULT_MODULE_REGION_SIZE);
+    }
+
+    #[test]
+    fn test_insufficient_memory() {
+        let map = [entry(16 * MB, 8 * MB, MemoryType::Usable)];
+        let config = PlacementConfig::new();
+
+        assert!(randomize_with_seed(&map, &[], &config, 1).is_err());
+    }
+
+    #[test]
+    fn test_layout_record_roundtrip() {
+        let map = test_map();
+        let config = PlacementConfig::new().with_cpus(4);
+        let layout = randomize_with_seed(&map, &[], &config, 99).unwrap();
+
+        let record = layout.record(0x20_0000);
+        assert!(record.validate());
+
+        let bytes = record.to_bytes();
+        assert_eq!(LayoutRecord::from_bytes(&bytes), Some(record));
+
+        let mut corrupt = bytes;
+        corrupt[24] ^= 1;
+        assert_eq!(LayoutRecord::from_bytes(&corrupt), None);
+        assert_eq!(LayoutRecord::from_bytes(&bytes[..16]), None);
+        assert!(!LayoutRecord::empty().validate());
+    }
+
+    #[test]
+    fn test_layout_record_size() {
+        let mut record = LayoutRecord {
+            magic: LAYOUT_MAGIC,
+            version: LAYOUT_VERSION,
+            cpu_count: u32::MAX,
+            kaslr_offset: 0x1111_1111_1111_1111,
+            heap_base: 0x2222_2222_2222_2222,
+            heap_size: 0x3333_3333_3333_3333,
+            percpu_base: 0x4444_4444_4444_4444,
+            percpu_stride: 0x5555_5555_5555_5555,
+            module_base: 0x6666_6666_6666_6666,
+            module_size: 0x7777_7777_7777_7777,
+            randomized: 1,
+            checksum: 0,
+        };
+        record.checksum = record.compute_checksum();
+
+        let bytes = record.to_bytes();
+        assert_eq!(bytes.len(), LayoutRecord::SIZE);
+        assert_eq!(&bytes[LayoutRecord::SIZE - 8..], &record.checksum.to_le_bytes());
+        assert_eq!(LayoutRecord::from_bytes(&bytes), Some(record));
+        assert_eq!(LayoutRecord::from_bytes(&bytes[..LayoutRecord::SIZE - 1]), None);
+    }
+
+    #[test]
+    fn test_layout_record_resolve() {
+        let map = test_map();
+        let config = PlacementConfig::new().with_cpus(4);
+        let layout = randomize_with_seed(&map, &[], &config, 7).unwrap();
+        let record = layout.record(0);
+
+        let heap = layout.heap.range.start + 0x1234;
+        assert_eq!(record.resolve(heap), Some((PlacementRegion::Heap, 0x1234)));
+
+        let cpu2 = layout.percpu_base(2).unwrap() + 0x10;
+        assert_eq!(record.resolve(cpu2), Some((PlacementRegion::PerCpu, 0x10)));
+        assert_eq!(record.percpu_owner(cpu2), Some(2));
+
+        let module = layout.modules.range.end - 1;
+        assert_eq!(
+            record.resolve(module),
+            Some((PlacementRegion::Modules, layout.modules.range.size() - 1))
+        );
+
+        assert_eq!(record.resolve(0), None);
+    }
+}
@@ -38,6 +38,7 @@
     PagingMode, SmpState, StageExecutor, TimerState,
 };
 use crate::error::{BootError, BootResult};
+use crate::memblock::{MemBlock, ReservationKind, MAX_RESERVATIONS};
 use crate::info::BootInfo;
 use crate::{BootConfig, BootStatus, BOOT_STATE};
 
@@ -463,9 +464,57 @@ fn execute(&mut self, ctx: &mut BootContext) -> BootResult<()> {
             ctx.memory_state.paging_mode
         ));
 
-        // Initialize early heap
+        // Choose physical placement of early regions
+        // Per-CPU areas for the CPUs the firmware reported, as SmpInitStage
+        // brings up, not for every CPU the kernel could support
+        let cpu_count = if ctx.config.smp_enabled {
+            (boot_info.smp.cpu_count as usize).clamp(1, ctx.config.max_cpus.max(1)) as u32
+        } else {
+            1
+        };
+        let config = if ctx.config.placement_randomized {
+            crate::placement::PlacementConfig::new()
+        } else {
+            crate::placement::PlacementConfig::disabled()
+        }
+        .with_cpus(cpu_count);
+        let kernel = crate::placement::PhysRange {
+            start: boot_info.memory.kernel_phys_start,
+            end: boot_info.memory.kernel_phys_end,
+        };
+
+        // Early reservations, kept until the page allocator takes over
+        let mut memblock = MemBlock::from_memory_map(memory_map)?;
+        if !kernel.is_empty() {
+            memblock.reserve("kernel", kernel.start, kernel.size(), ReservationKind::Kernel)?;
+        }
+
+        let mut reserved = [crate::placement::PhysRange::default(); MAX_RESERVATIONS];
+        let count = memblock.reserved_ranges(&mut reserved);
+        let layout = crate::placement::randomize(memory_map, &reserved[..count], &config)?;
+        crate::placement::set_layout(layout);
+
+        for (name, placement) in [
+            ("modules", layout.modules),
+            ("early-heap", layout.heap),
+            ("percpu", layout.percpu),
+        ] {
+            let range = placement.range;
+            memblock.reserve(name, range.start, range.size(), ReservationKind::Kernel)?;
+        }
+        crate::memblock::set_memblock(memblock);
+
+        crate::boot_log(&alloc::format!(
+            "Placement: heap {:#x}, percpu {:#x}, modules {:#x} ({} bits)",
+            layout.heap.range.start,
+            layout.percpu.range.start,
+            layout.modules.range.start,
+            layout.heap.entropy_bits()
+        ));
+
+        // Initialize early heap in the region chosen above
         unsafe {
-            crate::memory::init_early_heap(ctx)?;
+            crate::memory::init_early_heap(ctx, layout.heap.range)?;
         }
 
         crate::boot_log(&alloc::format!(
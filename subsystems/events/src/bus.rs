@@ -0,0 +1,304 @@
+//! Publish/subscribe bus
+//!
+//! Publishing never blocks on a subscriber: each subscription owns a
+//! bounded queue, and when it is full the oldest event is dropped and
+//! counted. Sequence numbers are bus-wide, so a subscriber sees exactly
+//! where it lost events.
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::{Mutex, RwLock};
+
+use crate::error::{EventError, EventResult};
+use crate::event::{Event, EventKind};
+use crate::filter::Filter;
+use crate::topic::Severity;
+use crate::MAX_SUBSCRIBERS;
+
+/// Subscriber state shared between the bus and the [`Subscription`]
+struct Subscriber {
+    id: u64,
+    name: &'static str,
+    filter: RwLock<Filter>,
+    queue: Mutex<VecDeque<Event>>,
+    capacity: usize,
+    dropped: AtomicU64,
+}
+
+impl Subscriber {
+    /// Still referenced by its [`Subscription`]?
+    fn is_live(self: &Arc<Self>) -> bool {
+        Arc::strong_count(self) > 1
+    }
+
+    /// Queue `event`; returns `false` if an older event was dropped for it
+    fn push(&self, event: Event) -> bool {
+        let mut queue = self.queue.lock();
+        let overflow = queue.len() == self.capacity;
+        if overflow {
+            queue.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        queue.push_back(event);
+        !overflow
+    }
+}
+
+/// A subscriber's handle: receives the events matching its filter
+///
+/// Dropping the handle unsubscribes.
+pub struct Subscription {
+    inner: Arc<Subscriber>,
+}
+
+impl Subscription {
+    /// Subscriber ID
+    pub fn id(&self) -> u64 {
+        self.inner.id
+    }
+
+    /// Subscriber name
+    pub fn name(&self) -> &'static str {
+        self.inner.name
+    }
+
+    /// Next queued event
+    pub fn try_recv(&self) -> Option<Event> {
+        self.inner.queue.lock().pop_front()
+    }
+
+    /// Up to `max` queued events, oldest first
+    pub fn drain(&self, max: usize) -> Vec<Event> {
+        let mut queue = self.inner.queue.lock();
+        let count = max.min(queue.len());
+        queue.drain(..count).collect()
+    }
+
+    /// Put `event` back at the head of the queue (it was not consumed)
+    ///
+    /// Dropped instead if the queue has filled up in the meantime.
+    pub fn unread(&self, event: Event) {
+        let mut queue = self.inner.queue.lock();
+        if queue.len() < self.inner.capacity {
+            queue.push_front(event);
+        } else {
+            self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Number of queued events
+    pub fn pending(&self) -> usize {
+        self.inner.queue.lock().len()
+    }
+
+    /// Events lost to queue overflow
+    pub fn dropped(&self) -> u64 {
+        self.inner.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Current filter
+    pub fn filter(&self) -> Filter {
+        self.inner.filter.read().clone()
+    }
+
+    /// Replace the filter; already queued events are kept
+    pub fn set_filter(&self, filter: Filter) {
+        *self.inner.filter.write() = filter;
+    }
+}
+
+/// Bus statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BusStats {
+    /// Events published
+    pub published: u64,
+    /// Events queued to subscribers
+    pub delivered: u64,
+    /// Events dropped from full queues
+    pub dropped: u64,
+    /// Live subscriptions
+    pub subscribers: usize,
+}
+
+/// The event bus
+pub struct EventBus {
+    subscribers: RwLock<Vec<Arc<Subscriber>>>,
+    clock: RwLock<Option<fn() -> u64>>,
+    next_seq: AtomicU64,
+    next_id: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl EventBus {
+    /// Create an empty bus
+    pub const fn new() -> Self {
+        Self {
+            subscribers: RwLock::new(Vec::new()),
+            clock: RwLock::new(None),
+            next_seq: AtomicU64::new(1),
+            next_id: AtomicU64::new(1),
+            delivered: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Timestamp source for published events (ns since boot)
+    pub fn set_clock(&self, clock: fn() -> u64) {
+        *self.clock.write() = Some(clock);
+    }
+
+    /// Subscribe to the events passing `filter`, queueing up to `capacity`
+    pub fn subscribe(&self, name: &'static str, filter: Filter, capacity: usize) -> EventResult<Subscription> {
+        if capacity == 0 {
+            return Err(EventError::InvalidCapacity);
+        }
+
+        let mut subscribers = self.subscribers.write();
+        subscribers.retain(|s| s.is_live());
+        if subscribers.len() >= MAX_SUBSCRIBERS {
+            return Err(EventError::TooManySubscribers);
+        }
+
+        let inner = Arc::new(Subscriber {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            name,
+            filter: RwLock::new(filter),
+            queue: Mutex::new(VecDeque::with_capacity(capacity.min(64))),
+            capacity,
+            dropped: AtomicU64::new(0),
+        });
+        subscribers.push(inner.clone());
+        Ok(Subscription { inner })
+    }
+
+    /// Publish `kind` from `source` at its default severity
+    pub fn publish(&self, source: &'static str, kind: EventKind) -> u64 {
+        let severity = kind.default_severity();
+        self.publish_with(source, severity, kind)
+    }
+
+    /// Publish `kind` from `source` at `severity`; returns the sequence number
+    pub fn publish_with(&self, source: &'static str, severity: Severity, kind: EventKind) -> u64 {
+        let event = Event {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            timestamp_ns: self.clock.read().map_or(0, |clock| clock()),
+            source,
+            severity,
+            kind,
+        };
+
+        for subscriber in self.subscribers.read().iter() {
+            if !subscriber.is_live() || !subscriber.filter.read().matches(&event) {
+                continue;
+            }
+            if !subscriber.push(event.clone()) {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            self.delivered.fetch_add(1, Ordering::Relaxed);
+        }
+        event.seq
+    }
+
+    /// Names and IDs of the live subscriptions
+    pub fn subscribers(&self) -> Vec<(u64, &'static str)> {
+        self.subscribers
+            .read()
+            .iter()
+            .filter(|s| s.is_live())
+            .map(|s| (s.id, s.name))
+            .collect()
+    }
+
+    /// Bus statistics
+    pub fn stats(&self) -> BusStats {
+        BusStats {
+            published: self.next_seq.load(Ordering::Relaxed) - 1,
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            subscribers: self.subscribers.read().iter().filter(|s| s.is_live()).count(),
+        }
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topic::Topic;
+    use alloc::string::ToString;
+
+    fn hotplug(cpu: u32) -> EventKind {
+        EventKind::CpuHotplug { cpu, online: true }
+    }
+
+    #[test]
+    fn test_publish_filtered() {
+        let bus = EventBus::new();
+        let all = bus.subscribe("all", Filter::all(), 8).unwrap();
+        let devices = bus.subscribe("dev", Filter::all().topics(Topic::Device.into()), 8).unwrap();
+
+        bus.publish("smp", hotplug(1));
+        bus.publish("nvme", EventKind::DeviceAdded { device_id: 1, name: "nvme0".to_string() });
+
+        assert_eq!(all.pending(), 2);
+        assert_eq!(all.try_recv().map(|e| e.seq), Some(1));
+        let event = devices.try_recv().unwrap();
+        assert_eq!((event.seq, event.source), (2, "nvme"));
+        assert_eq!(devices.try_recv(), None);
+        assert_eq!(bus.stats().delivered, 3);
+    }
+
+    #[test]
+    fn test_bounded_queue() {
+        let bus = EventBus::new();
+        let sub = bus.subscribe("slow", Filter::all(), 2).unwrap();
+        for cpu in 0..5 {
+            bus.publish("smp", hotplug(cpu));
+        }
+
+        // Oldest events are dropped; the sequence gap shows the loss
+        assert_eq!(sub.dropped(), 3);
+        let seqs: Vec<u64> = sub.drain(10).iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [4, 5]);
+        assert_eq!(bus.stats().dropped, 3);
+        assert_eq!(bus.subscribe("bad", Filter::all(), 0).err(), Some(EventError::InvalidCapacity));
+    }
+
+    #[test]
+    fn test_unsubscribe_on_drop() {
+        let bus = EventBus::new();
+        let sub = bus.subscribe("tmp", Filter::all(), 4).unwrap();
+        let id = sub.id();
+        assert_eq!(bus.subscribers(), [(id, "tmp")]);
+
+        drop(sub);
+        assert_eq!(bus.stats().subscribers, 0);
+        bus.publish("smp", hotplug(0));
+        assert_eq!(bus.stats().delivered, 0);
+    }
+
+    #[test]
+    fn test_set_filter_and_clock() {
+        let bus = EventBus::new();
+        bus.set_clock(|| 42);
+        let sub = bus.subscribe("mon", Filter::all(), 4).unwrap();
+        sub.set_filter(Filter::all().min_severity(Severity::Error));
+
+        bus.publish("smp", hotplug(0));
+        bus.publish("nvme", EventKind::DeviceError { device_id: 1, code: 5 });
+        let event = sub.try_recv().unwrap();
+        assert_eq!((event.severity, event.timestamp_ns), (Severity::Error, 42));
+        assert_eq!(sub.pending(), 0);
+
+        sub.unread(event.clone());
+        assert_eq!(sub.try_recv(), Some(event));
+    }
+}
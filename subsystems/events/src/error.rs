@@ -0,0 +1,33 @@
+//! Event bus error types
+
+use core::fmt;
+
+/// Event bus error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventError {
+    /// Queue capacity must be at least one event
+    InvalidCapacity,
+    /// `MAX_SUBSCRIBERS` live subscriptions already exist
+    TooManySubscribers,
+    /// Unknown filter key
+    UnknownFilterKey,
+    /// Unknown topic name in a filter
+    UnknownTopic,
+    /// Unknown severity name in a filter
+    UnknownSeverity,
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCapacity => write!(f, "queue capacity must be non-zero"),
+            Self::TooManySubscribers => write!(f, "too many subscribers"),
+            Self::UnknownFilterKey => write!(f, "unknown filter key"),
+            Self::UnknownTopic => write!(f, "unknown topic"),
+            Self::UnknownSeverity => write!(f, "unknown severity"),
+        }
+    }
+}
+
+/// Result type for event bus operations
+pub type EventResult<T> = Result<T, EventError>;
@@ -0,0 +1,419 @@
+//! Event records
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::topic::{Severity, Topic};
+
+/// Memory pressure level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressureLevel {
+    /// Memory is fine
+    Normal,
+    /// Memory is getting low
+    Low,
+    /// Memory is critically low
+    Critical,
+}
+
+impl PressureLevel {
+    /// Level name
+    pub const fn name(self) -> &'static str {
+        match self {
+            PressureLevel::Normal => "normal",
+            PressureLevel::Low => "low",
+            PressureLevel::Critical => "critical",
+        }
+    }
+}
+
+/// What happened, with its typed payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    /// Module loaded and started
+    ModuleLoaded {
+        /// Module ID
+        id: u64,
+        /// Module name
+        name: String,
+    },
+    /// Module stopped and unloaded
+    ModuleUnloaded {
+        /// Module ID
+        id: u64,
+        /// Module name
+        name: String,
+    },
+    /// Module replaced by a new version
+    ModuleReloaded {
+        /// Module ID
+        id: u64,
+        /// Module name
+        name: String,
+        /// New version
+        version: String,
+    },
+    /// Module crashed or failed to start
+    ModuleFailed {
+        /// Module ID
+        id: u64,
+        /// Module name
+        name: String,
+        /// Failure description
+        error: String,
+    },
+    /// The AI made a decision
+    AiDecision {
+        /// Decision ID
+        decision_id: u64,
+        /// Chosen action
+        action: String,
+        /// Confidence (percent)
+        confidence: u8,
+        /// Whether the action was applied
+        applied: bool,
+    },
+    /// The AI mode changed
+    AiModeChanged {
+        /// New mode
+        mode: String,
+    },
+    /// The AI detected an anomaly
+    AiAnomaly {
+        /// What looks wrong
+        description: String,
+        /// Anomaly score (percent)
+        score: u8,
+    },
+    /// Device discovered
+    DeviceAdded {
+        /// Device ID
+        device_id: u64,
+        /// Device name
+        name: String,
+    },
+    /// Device removed
+    DeviceRemoved {
+        /// Device ID
+        device_id: u64,
+        /// Device name
+        name: String,
+    },
+    /// Device reported an error
+    DeviceError {
+        /// Device ID
+        device_id: u64,
+        /// Driver-specific error code
+        code: u32,
+    },
+    /// Boot phase finished
+    BootPhase {
+        /// Phase name
+        phase: String,
+        /// Time since boot (ns)
+        elapsed_ns: u64,
+    },
+    /// Boot finished; userspace is starting
+    BootComplete {
+        /// Time since boot (ns)
+        elapsed_ns: u64,
+    },
+    /// Memory pressure changed
+    MemoryPressure {
+        /// New level
+        level: PressureLevel,
+        /// Free memory (bytes)
+        free_bytes: u64,
+    },
+    /// CPU went online or offline
+    CpuHotplug {
+        /// CPU ID
+        cpu: u32,
+        /// `true` if the CPU came online
+        online: bool,
+    },
+    /// External power connected or disconnected
+    AcPower {
+        /// `true` if the system now runs on external power
+        online: bool,
+    },
+    /// Battery charge or charging state changed
+    Battery {
+        /// Battery name
+        name: String,
+        /// Charge left (percent)
+        capacity: u8,
+        /// Charging state, as in sysfs `status`
+        status: String,
+        /// Charge or discharge power (mW)
+        power_mw: u32,
+    },
+    /// Battery charge crossed a low threshold while discharging
+    BatteryLow {
+        /// Battery name
+        name: String,
+        /// Charge left (percent)
+        capacity: u8,
+        /// `true` past the critical threshold
+        critical: bool,
+    },
+    /// System is shutting down
+    Shutdown,
+    /// Subsystem-defined event
+    Custom {
+        /// Event name
+        name: String,
+        /// Opaque payload
+        data: Vec<u8>,
+    },
+}
+
+impl EventKind {
+    /// Topic the event is published on
+    pub fn topic(&self) -> Topic {
+        match self {
+            Self::ModuleLoaded { .. }
+            | Self::ModuleUnloaded { .. }
+            | Self::ModuleReloaded { .. }
+            | Self::ModuleFailed { .. } => Topic::Module,
+            Self::AiDecision { .. } | Self::AiModeChanged { .. } | Self::AiAnomaly { .. } => Topic::Ai,
+            Self::DeviceAdded { .. } | Self::DeviceRemoved { .. } | Self::DeviceError { .. } => Topic::Device,
+            Self::BootPhase { .. } | Self::BootComplete { .. } => Topic::Boot,
+            Self::MemoryPressure { .. } => Topic::Memory,
+            Self::CpuHotplug { .. }
+            | Self::AcPower { .. }
+            | Self::Battery { .. }
+            | Self::BatteryLow { .. }
+            | Self::Shutdown => Topic::System,
+            Self::Custom { .. } => Topic::Custom,
+        }
+    }
+
+    /// Short name, unique within the topic
+    pub fn name(&self) -> &str {
+        match self {
+            Self::ModuleLoaded { .. } => "loaded",
+            Self::ModuleUnloaded { .. } => "unloaded",
+            Self::ModuleReloaded { .. } => "reloaded",
+            Self::ModuleFailed { .. } => "failed",
+            Self::AiDecision { .. } => "decision",
+            Self::AiModeChanged { .. } => "mode",
+            Self::AiAnomaly { .. } => "anomaly",
+            Self::DeviceAdded { .. } => "added",
+            Self::DeviceRemoved { .. } => "removed",
+            Self::DeviceError { .. } => "error",
+            Self::BootPhase { .. } => "phase",
+            Self::BootComplete { .. } => "complete",
+            Self::MemoryPressure { .. } => "pressure",
+            Self::CpuHotplug { .. } => "hotplug",
+            Self::AcPower { .. } => "ac",
+            Self::Battery { .. } => "battery",
+            Self::BatteryLow { .. } => "battery_low",
+            Self::Shutdown => "shutdown",
+            Self::Custom { name, .. } => name,
+        }
+    }
+
+    /// Severity used when the publisher does not pick one
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Self::ModuleFailed { .. } | Self::DeviceError { .. } => Severity::Error,
+            Self::AiAnomaly { .. } => Severity::Warning,
+            Self::MemoryPressure { level: PressureLevel::Low, .. } => Severity::Warning,
+            Self::MemoryPressure { level: PressureLevel::Critical, .. } => Severity::Critical,
+            Self::BatteryLow { critical: false, .. } => Severity::Warning,
+            Self::BatteryLow { critical: true, .. } => Severity::Critical,
+            _ => Severity::Info,
+        }
+    }
+
+    fn fmt_fields(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ModuleLoaded { id, name } | Self::ModuleUnloaded { id, name } => {
+                write!(f, " id={} name={}", id, Quoted(name))
+            }
+            Self::ModuleReloaded { id, name, version } => {
+                write!(f, " id={} name={} version={}", id, Quoted(name), Quoted(version))
+            }
+            Self::ModuleFailed { id, name, error } => {
+                write!(f, " id={} name={} error={}", id, Quoted(name), Quoted(error))
+            }
+            Self::AiDecision { decision_id, action, confidence, applied } => write!(
+                f,
+                " id={} action={} confidence={} applied={}",
+                decision_id,
+                Quoted(action),
+                confidence,
+                applied
+            ),
+            Self::AiModeChanged { mode } => write!(f, " mode={}", Quoted(mode)),
+            Self::AiAnomaly { description, score } => {
+                write!(f, " score={} description={}", score, Quoted(description))
+            }
+            Self::DeviceAdded { device_id, name } | Self::DeviceRemoved { device_id, name } => {
+                write!(f, " device={} name={}", device_id, Quoted(name))
+            }
+            Self::DeviceError { device_id, code } => write!(f, " device={} code={:#x}", device_id, code),
+            Self::BootPhase { phase, elapsed_ns } => {
+                write!(f, " phase={} elapsed_ns={}", Quoted(phase), elapsed_ns)
+            }
+            Self::BootComplete { elapsed_ns } => write!(f, " elapsed_ns={}", elapsed_ns),
+            Self::MemoryPressure { level, free_bytes } => {
+                write!(f, " level={} free={}", level.name(), free_bytes)
+            }
+            Self::CpuHotplug { cpu, online } => write!(f, " cpu={} online={}", cpu, online),
+            Self::AcPower { online } => write!(f, " online={}", online),
+            Self::Battery { name, capacity, status, power_mw } => write!(
+                f,
+                " name={} capacity={} status={} power_mw={}",
+                Quoted(name),
+                capacity,
+                Quoted(status),
+                power_mw
+            ),
+            Self::BatteryLow { name, capacity, critical } => {
+                write!(f, " name={} capacity={} critical={}", Quoted(name), capacity, critical)
+            }
+            Self::Shutdown => Ok(()),
+            Self::Custom { data, .. } => {
+                write!(f, " data=")?;
+                data.iter().try_for_each(|b| write!(f, "{:02x}", b))
+            }
+        }
+    }
+}
+
+/// A published event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// Bus-wide sequence number (gaps mean a subscriber lost events)
+    pub seq: u64,
+    /// Publication time (ns since boot)
+    pub timestamp_ns: u64,
+    /// Publishing subsystem
+    pub source: &'static str,
+    /// Severity
+    pub severity: Severity,
+    /// What happened
+    pub kind: EventKind,
+}
+
+impl Event {
+    /// Topic of the event
+    pub fn topic(&self) -> Topic {
+        self.kind.topic()
+    }
+}
+
+/// One line of `/dev/events`:
+///
+/// ```text
+/// <seq> <timestamp_ns> <topic>.<name> <severity> <source> key=value...
+/// ```
+///
+/// String values are double-quoted with `\"`, `\\` and `\n` escaped.
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}.{} {} {}",
+            self.seq,
+            self.timestamp_ns,
+            self.topic(),
+            self.kind.name(),
+            self.severity,
+            self.source
+        )?;
+        self.kind.fmt_fields(f)
+    }
+}
+
+/// Double-quoted, escaped string
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                c => fmt::Write::write_char(f, c)?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::format;
+
+    #[test]
+    fn test_kind_topics() {
+        let failed = EventKind::ModuleFailed { id: 3, name: "net".to_string(), error: "panic".to_string() };
+        assert_eq!(failed.topic(), Topic::Module);
+        assert_eq!(failed.default_severity(), Severity::Error);
+
+        let pressure = EventKind::MemoryPressure { level: PressureLevel::Critical, free_bytes: 0 };
+        assert_eq!(pressure.topic(), Topic::Memory);
+        assert_eq!(pressure.default_severity(), Severity::Critical);
+
+        assert_eq!(EventKind::Shutdown.topic(), Topic::System);
+        assert_eq!(EventKind::Shutdown.default_severity(), Severity::Info);
+
+        let low = EventKind::BatteryLow { name: "BAT0".to_string(), capacity: 4, critical: true };
+        assert_eq!(low.topic(), Topic::System);
+        assert_eq!(low.default_severity(), Severity::Critical);
+    }
+
+    #[test]
+    fn test_event_line() {
+        let event = Event {
+            seq: 7,
+            timestamp_ns: 1500,
+            source: "modules",
+            severity: Severity::Error,
+            kind: EventKind::ModuleFailed {
+                id: 3,
+                name: "net".to_string(),
+                error: "bad \"magic\"".to_string(),
+            },
+        };
+        assert_eq!(
+            format!("{}", event),
+            "7 1500 module.failed error modules id=3 name=\"net\" error=\"bad \\\"magic\\\"\""
+        );
+
+        let custom = Event {
+            seq: 8,
+            timestamp_ns: 0,
+            source: "test",
+            severity: Severity::Debug,
+            kind: EventKind::Custom { name: "blob".to_string(), data: vec![0xde, 0xad] },
+        };
+        assert_eq!(format!("{}", custom), "8 0 custom.blob debug test data=dead");
+
+        let battery = Event {
+            seq: 9,
+            timestamp_ns: 0,
+            source: "power",
+            severity: Severity::Info,
+            kind: EventKind::Battery {
+                name: "BAT0".to_string(),
+                capacity: 80,
+                status: "Not charging".to_string(),
+                power_mw: 0,
+            },
+        };
+        assert_eq!(
+            format!("{}", battery),
+            "9 0 system.battery info power name=\"BAT0\" capacity=80 status=\"Not charging\" power_mw=0"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Subscriber filters
+
+use alloc::string::{String, ToString};
+
+use crate::error::{EventError, EventResult};
+use crate::event::Event;
+use crate::topic::{Severity, Topic, TopicMask};
+
+/// Which events a subscriber receives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    /// Accepted topics
+    pub topics: TopicMask,
+    /// Least severe event accepted
+    pub min_severity: Severity,
+    /// Only events from this source, if set
+    pub source: Option<String>,
+}
+
+impl Filter {
+    /// Accept every event
+    pub const fn all() -> Self {
+        Self {
+            topics: TopicMask::ALL,
+            min_severity: Severity::Debug,
+            source: None,
+        }
+    }
+
+    /// Accept only `topics`
+    pub fn topics(mut self, topics: TopicMask) -> Self {
+        self.topics = topics;
+        self
+    }
+
+    /// Accept only events at least as severe as `severity`
+    pub fn min_severity(mut self, severity: Severity) -> Self {
+        self.min_severity = severity;
+        self
+    }
+
+    /// Accept only events published by `source`
+    pub fn source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Does `event` pass the filter?
+    pub fn matches(&self, event: &Event) -> bool {
+        self.topics.contains(event.topic())
+            && event.severity >= self.min_severity
+            && !matches!(self.source.as_deref(), Some(source) if source != event.source)
+    }
+
+    /// Parse a filter specification, as written to `/dev/events`
+    ///
+    /// Space-separated `key=value` pairs; omitted keys accept everything:
+    ///
+    /// ```text
+    /// topics=module,device severity=warning source=devmgr
+    /// ```
+    pub fn parse(spec: &str) -> EventResult<Self> {
+        let mut filter = Self::all();
+        for pair in spec.split_whitespace() {
+            let (key, value) = pair.split_once('=').ok_or(EventError::UnknownFilterKey)?;
+            match key {
+                "topics" => {
+                    filter.topics = value.split(',').try_fold(TopicMask::NONE, |mask, name| {
+                        Topic::from_name(name).map(|topic| mask.with(topic)).ok_or(EventError::UnknownTopic)
+                    })?;
+                }
+                "severity" => {
+                    filter.min_severity = (0..=4)
+                        .filter_map(Severity::from_level)
+                        .find(|severity| severity.name() == value)
+                        .ok_or(EventError::UnknownSeverity)?;
+                }
+                "source" => filter.source = Some(value.to_string()),
+                _ => return Err(EventError::UnknownFilterKey),
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventKind;
+
+    fn event(source: &'static str, severity: Severity, kind: EventKind) -> Event {
+        Event { seq: 0, timestamp_ns: 0, source, severity, kind }
+    }
+
+    #[test]
+    fn test_matches() {
+        let hotplug = event("smp", Severity::Info, EventKind::CpuHotplug { cpu: 1, online: false });
+        let error = event("nvme", Severity::Error, EventKind::DeviceError { device_id: 4, code: 2 });
+
+        assert!(Filter::all().matches(&hotplug));
+        let devices = Filter::all().topics(Topic::Device.into());
+        assert!(!devices.matches(&hotplug));
+        assert!(devices.matches(&error));
+        assert!(!Filter::all().min_severity(Severity::Warning).matches(&hotplug));
+        assert!(!Filter::all().source("virtio").matches(&error));
+        assert!(Filter::all().source("nvme").matches(&error));
+    }
+
+    #[test]
+    fn test_parse() {
+        let filter = Filter::parse("topics=module,device severity=warning source=devmgr").unwrap();
+        assert_eq!(filter.topics, TopicMask::from(Topic::Module).with(Topic::Device));
+        assert_eq!(filter.min_severity, Severity::Warning);
+        assert_eq!(filter.source.as_deref(), Some("devmgr"));
+
+        assert_eq!(Filter::parse(""), Ok(Filter::all()));
+        assert_eq!(Filter::parse("topics=bogus"), Err(EventError::UnknownTopic));
+        assert_eq!(Filter::parse("severity=loud"), Err(EventError::UnknownSeverity));
+        assert_eq!(Filter::parse("colour=red"), Err(EventError::UnknownFilterKey));
+    }
+}
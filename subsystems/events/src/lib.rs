@@ -0,0 +1,101 @@
+//! # Helix Events
+//!
+//! Kernel-wide publish/subscribe event bus. Module lifecycle, AI decisions,
+//! device changes, boot phases and system events all travel as one
+//! sequenced stream instead of per-subsystem callbacks.
+//!
+//! ## Overview
+//!
+//! - **Topics**: every [`EventKind`] belongs to one [`Topic`]; subscribers
+//!   select topics with a [`TopicMask`]
+//! - **Filters**: topic, minimum [`Severity`] and source, also parsable from
+//!   text for `/dev/events`
+//! - **Queues**: bounded per subscriber; a slow subscriber loses its oldest
+//!   events and never blocks a publisher
+//! - **Wire format**: one text line per event (`Display` for [`Event`]),
+//!   served to userspace by the `/dev/events` device
+//!
+//! ## Architecture
+//!
+//! ```text
+//! ┌─────────────────────────────────────────────────────────────┐
+//! │                        EVENT BUS                             │
+//! ├─────────────────────────────────────────────────────────────┤
+//! │  publishers            bus                  subscribers     │
+//! │  ┌──────────┐    ┌──────────────┐    ┌──────────────────┐   │
+//! │  │ modules  │──┐ │ seq + stamp  │ ┌─▶│ AI cortex queue  │   │
+//! │  │ devices  │──┼▶│   filter     │─┼─▶│ /dev/events fd   │   │
+//! │  │ boot, AI │──┘ │  per queue   │ └─▶│ monitors         │   │
+//! │  └──────────┘    └──────────────┘    └──────────────────┘   │
+//! └─────────────────────────────────────────────────────────────┘
+//! ```
+//!
+//! ## Usage
+//!
+//! ```rust,no_run
+//! use helix_events::{EventBus, EventKind, Filter, Severity};
+//!
+//! static BUS: EventBus = EventBus::new();
+//!
+//! let errors = BUS.subscribe("monitor", Filter::all().min_severity(Severity::Error), 64).unwrap();
+//! BUS.publish("nvme", EventKind::DeviceError { device_id: 1, code: 5 });
+//! assert!(errors.try_recv().is_some());
+//! ```
+
+#![no_std]
+#![deny(unsafe_op_in_unsafe_fn)]
+#![warn(missing_docs)]
+
+extern crate alloc;
+
+// ============================================================================
+// MODULES
+// ============================================================================
+
+/// Error types
+pub mod error;
+
+/// Topics and severities
+pub mod topic;
+
+/// Event records and wire format
+pub mod event;
+
+/// Subscriber filters
+pub mod filter;
+
+/// Publish/subscribe bus
+pub mod bus;
+
+// ============================================================================
+// RE-EXPORTS
+// ============================================================================
+
+pub use bus::{BusStats, EventBus, Subscription};
+pub use error::{EventError, EventResult};
+pub use event::{Event, EventKind, PressureLevel};
+pub use filter::Filter;
+pub use topic::{Severity, Topic, TopicMask};
+
+/// Maximum number of live subscriptions per bus
+pub const MAX_SUBSCRIBERS: usize = 64;
+
+/// Default queue capacity for a subscription
+pub const DEFAULT_QUEUE_CAPACITY: usize = 256;
+
+// ============================================================================
+// KERNEL BUS
+// ============================================================================
+
+/// The kernel event bus
+static KERNEL_BUS: EventBus = EventBus::new();
+
+/// The kernel event bus
+pub fn bus() -> &'static EventBus {
+    &KERNEL_BUS
+}
+
+/// Publish `kind` from `source` on the kernel bus
+pub fn publish(source: &'static str, kind: EventKind) -> u64 {
+    KERNEL_BUS.publish(source, kind)
+}
@@ -0,0 +1,174 @@
+//! Topics and severities
+
+use core::fmt;
+
+/// Event topic
+///
+/// Every event kind belongs to exactly one topic; subscribers select the
+/// topics they want with a [`TopicMask`].
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topic {
+    /// Module lifecycle (load, unload, hot-reload, failure)
+    Module = 0,
+    /// AI cortex decisions and mode changes
+    Ai = 1,
+    /// Device discovery, removal and errors
+    Device = 2,
+    /// Boot phases
+    Boot = 3,
+    /// Memory pressure
+    Memory = 4,
+    /// CPU hotplug, power supplies, shutdown and other system-wide events
+    System = 5,
+    /// Subsystem-defined events
+    Custom = 6,
+}
+
+impl Topic {
+    /// All topics
+    pub const ALL: [Topic; 7] = [
+        Topic::Module,
+        Topic::Ai,
+        Topic::Device,
+        Topic::Boot,
+        Topic::Memory,
+        Topic::System,
+        Topic::Custom,
+    ];
+
+    /// Topic name, as used on `/dev/events`
+    pub const fn name(self) -> &'static str {
+        match self {
+            Topic::Module => "module",
+            Topic::Ai => "ai",
+            Topic::Device => "device",
+            Topic::Boot => "boot",
+            Topic::Memory => "memory",
+            Topic::System => "system",
+            Topic::Custom => "custom",
+        }
+    }
+
+    /// Look up a topic by name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|topic| topic.name() == name)
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Set of topics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TopicMask(u32);
+
+impl TopicMask {
+    /// No topics
+    pub const NONE: Self = Self(0);
+    /// Every topic
+    pub const ALL: Self = Self((1 << Topic::ALL.len()) - 1);
+
+    /// Mask from raw bits (bit `n` = topic `n`); unknown bits are dropped
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    /// Raw bits
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Mask with `topic` added
+    pub const fn with(self, topic: Topic) -> Self {
+        Self(self.0 | (1 << topic as u32))
+    }
+
+    /// Does the mask include `topic`?
+    pub const fn contains(self, topic: Topic) -> bool {
+        self.0 & (1 << topic as u32) != 0
+    }
+}
+
+impl From<Topic> for TopicMask {
+    fn from(topic: Topic) -> Self {
+        Self::NONE.with(topic)
+    }
+}
+
+/// Event severity, ordered from least to most severe
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Severity {
+    /// Diagnostic detail
+    Debug = 0,
+    /// Normal operation
+    #[default]
+    Info = 1,
+    /// Unexpected but handled
+    Warning = 2,
+    /// Operation failed
+    Error = 3,
+    /// System stability at risk
+    Critical = 4,
+}
+
+impl Severity {
+    /// Severity name, as used on `/dev/events`
+    pub const fn name(self) -> &'static str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// Severity from its numeric level
+    pub const fn from_level(level: u8) -> Option<Self> {
+        match level {
+            0 => Some(Severity::Debug),
+            1 => Some(Severity::Info),
+            2 => Some(Severity::Warning),
+            3 => Some(Severity::Error),
+            4 => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_mask() {
+        let mask = TopicMask::from(Topic::Module).with(Topic::Device);
+        assert!(mask.contains(Topic::Module));
+        assert!(mask.contains(Topic::Device));
+        assert!(!mask.contains(Topic::Ai));
+        assert_eq!(TopicMask::from_bits(u32::MAX), TopicMask::ALL);
+        assert!(Topic::ALL.iter().all(|&t| TopicMask::ALL.contains(t)));
+    }
+
+    #[test]
+    fn test_names() {
+        for topic in Topic::ALL {
+            assert_eq!(Topic::from_name(topic.name()), Some(topic));
+        }
+        assert_eq!(Topic::from_name("bogus"), None);
+        assert!(Severity::Warning > Severity::Info);
+        assert_eq!(Severity::from_level(4), Some(Severity::Critical));
+        assert_eq!(Severity::from_level(5), None);
+    }
+}
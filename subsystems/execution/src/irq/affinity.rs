@@ -0,0 +1,374 @@
+//! # IRQ Affinity and Balancing
+//!
+//! Tracks where each device IRQ is routed and how often it fires, and
+//! spreads the high-rate ones across CPUs:
+//!
+//! - **Affinity** ([`IrqAffinity::set_affinity`]): the CPUs an IRQ may be
+//!   routed to. Isolated CPUs are excluded unless the mask names nothing
+//!   else.
+//! - **Balancing** ([`IrqAffinity::balance`]): rates are sampled every
+//!   interval and IRQs above [`MIN_BALANCE_RATE`] are placed, busiest
+//!   first, on the least-loaded CPU they allow. An IRQ stays put unless
+//!   moving it clearly reduces the imbalance.
+//! - **Overrides** ([`IrqAffinity::set_override`]): a fixed placement, as
+//!   recommended by the resource oracle, which the balancer leaves alone.
+//!
+//! The interrupt controller driver performs the actual routing through a
+//! hook installed with [`IrqAffinity::set_route`]. The balancing service
+//! runs on the system workqueue.
+
+use super::MAX_CPUS;
+use crate::isolation::isolation;
+use crate::kworker::system_wq;
+use crate::{ExecError, ExecResult};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use spin::RwLock;
+
+/// IRQs firing less often than this (per second) are not balanced
+pub const MIN_BALANCE_RATE: u64 = 1_000;
+
+/// Default balancing interval, in workqueue ticks
+pub const DEFAULT_BALANCE_INTERVAL: u64 = 1_000;
+
+/// Route `irq` to `cpu`; returns `false` if the controller refused
+pub type RouteFn = fn(irq: u32, cpu: usize) -> bool;
+
+/// Per-IRQ routing state
+struct IrqEntry {
+    /// Allowed CPUs
+    affinity: AtomicU64,
+    /// CPU the IRQ is routed to
+    cpu: AtomicUsize,
+    /// Fixed placement (`usize::MAX` = none)
+    pinned: AtomicUsize,
+    /// Interrupts since registration
+    count: AtomicU64,
+    /// Count at the last sample
+    last_count: AtomicU64,
+    /// Smoothed rate, interrupts per second
+    rate: AtomicU64,
+}
+
+/// Rate and placement of one IRQ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IrqLoad {
+    /// IRQ number
+    pub irq: u32,
+    /// CPU it is routed to
+    pub cpu: usize,
+    /// CPUs it may be routed to
+    pub allowed: u64,
+    /// Interrupts per second
+    pub rate: u64,
+    /// Placement fixed by an override
+    pub overridden: bool,
+}
+
+/// IRQ affinity registry and balancer
+pub struct IrqAffinity {
+    irqs: RwLock<BTreeMap<u32, IrqEntry>>,
+    route: RwLock<Option<RouteFn>>,
+    /// Time of the last rate sample (ns)
+    last_sample: AtomicU64,
+    /// Balancing service running
+    running: AtomicBool,
+    /// IRQs moved by the balancer
+    migrations: AtomicU64,
+}
+
+impl IrqAffinity {
+    /// Create an empty registry
+    pub const fn new() -> Self {
+        Self {
+            irqs: RwLock::new(BTreeMap::new()),
+            route: RwLock::new(None),
+            last_sample: AtomicU64::new(0),
+            running: AtomicBool::new(false),
+            migrations: AtomicU64::new(0),
+        }
+    }
+
+    /// Install the interrupt controller's routing hook
+    pub fn set_route(&self, route: RouteFn) {
+        *self.route.write() = Some(route);
+    }
+
+    /// Track `irq`, currently routed to `cpu`
+    pub fn register(&self, irq: u32, cpu: usize) -> ExecResult<()> {
+        let mut irqs = self.irqs.write();
+        if irqs.contains_key(&irq) {
+            return Err(ExecError::AlreadyExists);
+        }
+        irqs.insert(
+            irq,
+            IrqEntry {
+                affinity: AtomicU64::new(u64::MAX),
+                cpu: AtomicUsize::new(cpu),
+                pinned: AtomicUsize::new(usize::MAX),
+                count: AtomicU64::new(0),
+                last_count: AtomicU64::new(0),
+                rate: AtomicU64::new(0),
+            },
+        );
+        Ok(())
+    }
+
+    /// Stop tracking `irq`
+    pub fn unregister(&self, irq: u32) -> ExecResult<()> {
+        self.irqs.write().remove(&irq).map(|_| ()).ok_or(ExecError::InvalidArgument)
+    }
+
+    /// Count one interrupt (hard-IRQ path)
+    pub fn record(&self, irq: u32) {
+        if let Some(entry) = self.irqs.read().get(&irq) {
+            entry.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // =========================================================================
+    // Affinity
+    // =========================================================================
+
+    /// Restrict `irq` to the CPUs in `mask`
+    ///
+    /// Reroutes immediately if the current CPU is no longer allowed. Drops
+    /// an override outside the mask.
+    pub fn set_affinity(&self, irq: u32, mask: u64) -> ExecResult<()> {
+        if mask & isolation().online() == 0 {
+            return Err(ExecError::InvalidArgument);
+        }
+
+        let irqs = self.irqs.read();
+        let entry = irqs.get(&irq).ok_or(ExecError::InvalidArgument)?;
+        entry.affinity.store(mask, Ordering::Release);
+
+        let pinned = entry.pinned.load(Ordering::Relaxed);
+        if pinned != usize::MAX && mask & (1 << pinned) == 0 {
+            entry.pinned.store(usize::MAX, Ordering::Relaxed);
+        }
+
+        let allowed = Self::allowed(mask);
+        let cpu = entry.cpu.load(Ordering::Relaxed);
+        if cpu >= MAX_CPUS || allowed & (1 << cpu) == 0 {
+            let loads = self.cpu_loads(&irqs);
+            let target = Self::least_loaded(&loads, allowed);
+            self.route_to(irq, entry, target)?;
+        }
+        Ok(())
+    }
+
+    /// CPUs `irq` may be routed to
+    pub fn affinity(&self, irq: u32) -> Option<u64> {
+        self.irqs.read().get(&irq).map(|e| e.affinity.load(Ordering::Acquire))
+    }
+
+    /// CPU `irq` is routed to
+    pub fn effective_cpu(&self, irq: u32) -> Option<usize> {
+        self.irqs.read().get(&irq).map(|e| e.cpu.load(Ordering::Acquire))
+    }
+
+    /// Fix `irq` on `cpu`, or return it to the balancer (`None`)
+    ///
+    /// The CPU must be allowed by the IRQ's affinity.
+    pub fn set_override(&self, irq: u32, cpu: Option<usize>) -> ExecResult<()> {
+        let irqs = self.irqs.read();
+        let entry = irqs.get(&irq).ok_or(ExecError::InvalidArgument)?;
+        let Some(cpu) = cpu else {
+            entry.pinned.store(usize::MAX, Ordering::Relaxed);
+            return Ok(());
+        };
+
+        let allowed = Self::allowed(entry.affinity.load(Ordering::Acquire));
+        if cpu >= MAX_CPUS || allowed & (1 << cpu) == 0 {
+            return Err(ExecError::InvalidArgument);
+        }
+        entry.pinned.store(cpu, Ordering::Relaxed);
+        if entry.cpu.load(Ordering::Relaxed) != cpu {
+            self.route_to(irq, entry, cpu)?;
+        }
+        Ok(())
+    }
+
+    /// Rates and placement of every tracked IRQ
+    pub fn loads(&self) -> Vec<IrqLoad> {
+        self.irqs
+            .read()
+            .iter()
+            .map(|(&irq, e)| IrqLoad {
+                irq,
+                cpu: e.cpu.load(Ordering::Relaxed),
+                allowed: Self::allowed(e.affinity.load(Ordering::Relaxed)),
+                rate: e.rate.load(Ordering::Relaxed),
+                overridden: e.pinned.load(Ordering::Relaxed) != usize::MAX,
+            })
+            .collect()
+    }
+
+    /// IRQs moved by the balancer
+    pub fn migrations(&self) -> u64 {
+        self.migrations.load(Ordering::Relaxed)
+    }
+
+    /// Online CPUs in `mask`, without isolated ones unless nothing else is left
+    fn allowed(mask: u64) -> u64 {
+        let iso = isolation();
+        let online = mask & iso.online();
+        match online & iso.irq_mask() {
+            0 => online,
+            allowed => allowed,
+        }
+    }
+
+    fn route_to(&self, irq: u32, entry: &IrqEntry, cpu: usize) -> ExecResult<()> {
+        if let Some(route) = *self.route.read() {
+            if !route(irq, cpu) {
+                return Err(ExecError::InvalidState);
+            }
+        }
+        entry.cpu.store(cpu, Ordering::Release);
+        Ok(())
+    }
+
+    // =========================================================================
+    // Balancing
+    // =========================================================================
+
+    /// Sample rates and rebalance; returns the number of IRQs moved
+    pub fn balance(&self, now_ns: u64) -> usize {
+        let irqs = self.irqs.read();
+        self.sample(&irqs, now_ns);
+
+        let mut loads = self.cpu_loads(&irqs);
+        let mut movable: Vec<(u32, &IrqEntry, u64)> = Vec::new();
+        for (&irq, entry) in irqs.iter() {
+            let rate = entry.rate.load(Ordering::Relaxed);
+            let cpu = entry.cpu.load(Ordering::Relaxed);
+            let allowed = Self::allowed(entry.affinity.load(Ordering::Relaxed));
+            let misplaced = cpu >= MAX_CPUS || allowed & (1 << cpu) == 0;
+            let pinned = entry.pinned.load(Ordering::Relaxed) != usize::MAX;
+
+            if misplaced || (!pinned && rate >= MIN_BALANCE_RATE) {
+                if cpu < MAX_CPUS {
+                    loads[cpu] = loads[cpu].saturating_sub(rate);
+                }
+                movable.push((irq, entry, rate));
+            }
+        }
+        movable.sort_by(|a, b| b.2.cmp(&a.2));
+
+        let mut moved = 0;
+        for (irq, entry, rate) in movable {
+            let cpu = entry.cpu.load(Ordering::Relaxed);
+            let allowed = Self::allowed(entry.affinity.load(Ordering::Relaxed));
+            let best = Self::least_loaded(&loads, allowed);
+
+            // Stay unless the move is clearly better
+            let stay = cpu < MAX_CPUS
+                && allowed & (1 << cpu) != 0
+                && loads[cpu] <= loads[best] + rate / 2;
+            let target = if stay { cpu } else { best };
+
+            if target != cpu && self.route_to(irq, entry, target).is_ok() {
+                moved += 1;
+            }
+            let placed = entry.cpu.load(Ordering::Relaxed);
+            if placed < MAX_CPUS {
+                loads[placed] += rate;
+            }
+        }
+
+        self.migrations.fetch_add(moved as u64, Ordering::Relaxed);
+        moved
+    }
+
+    /// Update smoothed rates from the counters
+    fn sample(&self, irqs: &BTreeMap<u32, IrqEntry>, now_ns: u64) {
+        let last = self.last_sample.swap(now_ns, Ordering::Relaxed);
+        let elapsed = now_ns.saturating_sub(last);
+        for entry in irqs.values() {
+            let count = entry.count.load(Ordering::Relaxed);
+            let delta = count - entry.last_count.swap(count, Ordering::Relaxed);
+            if last == 0 || elapsed == 0 {
+                continue;
+            }
+            let rate = (delta as u128 * 1_000_000_000 / elapsed as u128) as u64;
+            let old = entry.rate.load(Ordering::Relaxed);
+            let smoothed = if old == 0 { rate } else { (old * 3 + rate) / 4 };
+            entry.rate.store(smoothed, Ordering::Relaxed);
+        }
+    }
+
+    /// Summed IRQ rate per CPU
+    fn cpu_loads(&self, irqs: &BTreeMap<u32, IrqEntry>) -> [u64; MAX_CPUS] {
+        let mut loads = [0u64; MAX_CPUS];
+        for entry in irqs.values() {
+            let cpu = entry.cpu.load(Ordering::Relaxed);
+            if cpu < MAX_CPUS {
+                loads[cpu] += entry.rate.load(Ordering::Relaxed);
+            }
+        }
+        loads
+    }
+
+    /// Allowed CPU with the lowest load (lowest number on ties)
+    fn least_loaded(loads: &[u64; MAX_CPUS], allowed: u64) -> usize {
+        (0..MAX_CPUS)
+            .filter(|&cpu| allowed & (1 << cpu) != 0)
+            .min_by_key(|&cpu| loads[cpu])
+            .unwrap_or(0)
+    }
+
+    // =========================================================================
+    // Service
+    // =========================================================================
+
+    /// Run [`IrqAffinity::balance`] every `interval` ticks on the system
+    /// workqueue, reading time from `clock` (ns)
+    pub fn start_balancer(&'static self, interval: u64, clock: fn() -> u64) -> ExecResult<()> {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return Err(ExecError::AlreadyExists);
+        }
+        self.last_sample.store(clock(), Ordering::Relaxed);
+        self.schedule(interval, clock).inspect_err(|_| self.stop_balancer())
+    }
+
+    /// Stop the balancing service after its current run
+    pub fn stop_balancer(&self) {
+        self.running.store(false, Ordering::Release);
+    }
+
+    /// Is the balancing service running?
+    pub fn balancer_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    fn schedule(&'static self, interval: u64, clock: fn() -> u64) -> ExecResult<()> {
+        system_wq().queue_delayed(interval, move || {
+            if !self.balancer_running() {
+                return;
+            }
+            self.balance(clock());
+            if let Err(e) = self.schedule(interval, clock) {
+                log::warn!("irq balancer stopped: {:?}", e);
+                self.stop_balancer();
+            }
+        })?;
+        Ok(())
+    }
+}
+
+impl Default for IrqAffinity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global IRQ affinity registry
+static IRQ_AFFINITY: IrqAffinity = IrqAffinity::new();
+
+/// Get the IRQ affinity registry
+pub fn irq_affinity() -> &'static IrqAffinity {
+    &IRQ_AFFINITY
+}
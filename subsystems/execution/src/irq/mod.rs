@@ -0,0 +1,79 @@
+//! # Interrupt Bottom Halves
+//!
+//! Deferral mechanisms that move work out of hard-IRQ context:
+//!
+//! - [`softirq`]: per-CPU softirq vectors and tasklets, run on IRQ exit
+//!   or by the per-CPU `ksoftirqd` thread under load
+//! - [`threaded`]: threaded IRQ handlers, each backed by a kernel thread
+//!   whose priority is managed by the scheduler framework
+//! - work that must run in process context goes to the shared worker
+//!   pools in [`crate::kworker`], e.g. [`system_wq`](crate::kworker::system_wq)
+//! - [`affinity`]: per-IRQ CPU affinity and a balancer spreading
+//!   high-rate interrupts across CPUs
+//!
+//! The architecture IRQ path brackets hard handlers with [`irq_enter`] and
+//! [`irq_exit`], once [`init`] has installed them as the HAL's IRQ hooks;
+//! pending softirqs run when the outermost handler exits.
+
+pub mod affinity;
+pub mod softirq;
+pub mod threaded;
+
+pub use affinity::{irq_affinity, IrqLoad};
+pub use softirq::{softirqs, SoftirqVector, Tasklet};
+pub use threaded::{threaded_irqs, IrqReturn};
+
+use crate::scheduler::cputime::CpuMode;
+use crate::scheduler::framework;
+use core::sync::atomic::{AtomicU32, Ordering};
+use helix_hal::interrupts::IrqHooks;
+
+/// Maximum number of CPUs (matches the width of affinity masks)
+pub const MAX_CPUS: usize = 64;
+
+/// Per-CPU hard-IRQ nesting depth
+static HARDIRQ_DEPTH: [AtomicU32; MAX_CPUS] = [const { AtomicU32::new(0) }; MAX_CPUS];
+
+/// Hook [`irq_enter`] and [`irq_exit`] into the architecture IRQ paths
+pub fn init() {
+    helix_hal::interrupts::set_irq_hooks(IrqHooks {
+        enter: irq_enter,
+        exit: irq_exit,
+    });
+}
+
+/// Enter hard-IRQ context on `cpu`
+pub fn irq_enter(cpu: usize) {
+    if cpu >= MAX_CPUS {
+        return;
+    }
+    if HARDIRQ_DEPTH[cpu].fetch_add(1, Ordering::Relaxed) == 0 {
+        framework().cputime().enter_interrupt(cpu, CpuMode::Irq);
+    }
+}
+
+/// Leave hard-IRQ context on `cpu`
+///
+/// When the outermost handler exits, pending softirqs run before this
+/// returns. Their time is accounted as softirq time.
+pub fn irq_exit(cpu: usize) {
+    if cpu >= MAX_CPUS {
+        return;
+    }
+    if HARDIRQ_DEPTH[cpu].fetch_sub(1, Ordering::Relaxed) == 1 {
+        let cputime = framework().cputime();
+        cputime.enter_interrupt(cpu, CpuMode::Softirq);
+        softirqs().run_on_exit(cpu);
+        cputime.exit_interrupt(cpu);
+    }
+}
+
+/// Is `cpu` running a hard-IRQ handler?
+pub fn in_hardirq(cpu: usize) -> bool {
+    HARDIRQ_DEPTH[cpu].load(Ordering::Relaxed) > 0
+}
+
+/// Is `cpu` in any interrupt context (hard IRQ or softirq)?
+pub fn in_interrupt(cpu: usize) -> bool {
+    in_hardirq(cpu) || softirqs().in_softirq(cpu)
+}
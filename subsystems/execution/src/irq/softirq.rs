@@ -0,0 +1,584 @@
+//! # Softirqs and Tasklets
+//!
+//! Softirqs are a fixed set of vectors raised per CPU and run on IRQ exit,
+//! in vector order. When a CPU keeps raising softirqs, processing is handed
+//! to its `ksoftirqd/N` thread after a restart budget so that threads are
+//! not starved.
+//!
+//! Tasklets are dynamically created deferred functions run from the
+//! `HiTasklet` and `Tasklet` vectors. A tasklet never runs concurrently
+//! with itself, and scheduling an already scheduled tasklet is a no-op.
+//! A disabled tasklet that comes up is parked on its CPU, without keeping
+//! the vector raised, and queued again by [`Tasklet::enable`].
+//!
+//! Scheduling may happen in hard-IRQ context, so the per-CPU tasklet
+//! queues are lock-free; the parked list is only locked with interrupts
+//! disabled.
+
+use super::MAX_CPUS;
+use crate::scheduler::{framework, Priority, SchedulableThread};
+use crate::sync::IrqMutex;
+use crate::{ExecError, ExecResult, ProcessId, ThreadId};
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use spin::RwLock;
+
+/// Softirq vectors, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SoftirqVector {
+    /// High-priority tasklets
+    HiTasklet = 0,
+    /// Timer expiry
+    Timer = 1,
+    /// Network transmit completion
+    NetTx = 2,
+    /// Network receive
+    NetRx = 3,
+    /// Block I/O completion
+    Block = 4,
+    /// Normal tasklets
+    Tasklet = 5,
+    /// Scheduler load balancing
+    Sched = 6,
+    /// RCU callbacks
+    Rcu = 7,
+}
+
+impl SoftirqVector {
+    /// Number of vectors
+    pub const COUNT: usize = 8;
+
+    /// All vectors, in run order
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::HiTasklet,
+        Self::Timer,
+        Self::NetTx,
+        Self::NetRx,
+        Self::Block,
+        Self::Tasklet,
+        Self::Sched,
+        Self::Rcu,
+    ];
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Softirq handler, called with the CPU number
+pub type SoftirqHandler = fn(cpu: usize);
+
+/// Rounds run on IRQ exit before deferring to ksoftirqd
+const MAX_RESTART: u32 = 10;
+
+/// Tasklet is queued
+const TASKLET_SCHEDULED: u8 = 1 << 0;
+
+/// Tasklet is running on some CPU
+const TASKLET_RUNNING: u8 = 1 << 1;
+
+/// `Tasklet::parked_on` of a tasklet that is not parked
+const NOT_PARKED: u32 = u32::MAX;
+
+// ============================================================================
+// TASKLETS
+// ============================================================================
+
+/// A deferred function run in softirq context
+pub struct Tasklet {
+    /// Function to run
+    func: fn(usize),
+    /// Argument passed to `func`
+    data: usize,
+    /// SCHEDULED / RUNNING bits
+    state: AtomicU8,
+    /// Disable count; a disabled tasklet stays scheduled but is parked
+    disabled: AtomicU32,
+    /// CPU and vector the tasklet is parked on, or `NOT_PARKED`
+    parked_on: AtomicU32,
+    /// Next tasklet in the `TaskletList` holding this one
+    next: AtomicPtr<Tasklet>,
+}
+
+impl Tasklet {
+    /// Create a tasklet
+    pub const fn new(func: fn(usize), data: usize) -> Self {
+        Self {
+            func,
+            data,
+            state: AtomicU8::new(0),
+            disabled: AtomicU32::new(0),
+            parked_on: AtomicU32::new(NOT_PARKED),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Is the tasklet queued?
+    pub fn is_scheduled(&self) -> bool {
+        self.state.load(Ordering::Acquire) & TASKLET_SCHEDULED != 0
+    }
+
+    /// Prevent the tasklet from running, waiting for a running instance
+    pub fn disable(&self) {
+        self.disabled.fetch_add(1, Ordering::AcqRel);
+        while self.state.load(Ordering::Acquire) & TASKLET_RUNNING != 0 {
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Undo one [`Tasklet::disable`]
+    ///
+    /// The last enable queues the tasklet again if it was parked.
+    pub fn enable(&self) {
+        if self.disabled.fetch_sub(1, Ordering::SeqCst) == 1 {
+            softirqs().unpark(self);
+        }
+    }
+
+    /// Is the tasklet disabled?
+    pub fn is_disabled(&self) -> bool {
+        self.disabled.load(Ordering::SeqCst) != 0
+    }
+
+    /// Run the tasklet if it is enabled and not running elsewhere
+    ///
+    /// Returns `false` if it must stay queued.
+    fn try_run(&self) -> bool {
+        if self.disabled.load(Ordering::Acquire) != 0 {
+            return false;
+        }
+        if self.state.fetch_or(TASKLET_RUNNING, Ordering::AcqRel) & TASKLET_RUNNING != 0 {
+            return false;
+        }
+
+        // Clear SCHEDULED first so the function may reschedule itself
+        self.state.fetch_and(!TASKLET_SCHEDULED, Ordering::AcqRel);
+        (self.func)(self.data);
+        self.state.fetch_and(!TASKLET_RUNNING, Ordering::Release);
+        true
+    }
+}
+
+fn tasklet_hi_action(cpu: usize) {
+    softirqs().run_tasklets(cpu, SoftirqVector::HiTasklet);
+}
+
+fn tasklet_action(cpu: usize) {
+    softirqs().run_tasklets(cpu, SoftirqVector::Tasklet);
+}
+
+/// Lock-free queue of scheduled tasklets
+///
+/// Pushed from any context and drained whole by the tasklet softirq. A
+/// tasklet is on at most one list at a time: it is only pushed while it
+/// holds `TASKLET_SCHEDULED` and is not already queued or parked.
+struct TaskletList {
+    /// Most recently pushed tasklet, owning one `Arc` reference per entry
+    head: AtomicPtr<Tasklet>,
+}
+
+impl TaskletList {
+    const fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    fn push(&self, tasklet: Arc<Tasklet>) {
+        let node = Arc::into_raw(tasklet).cast_mut();
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: `node` is kept alive by the reference the list now owns
+            unsafe { (*node).next.store(head, Ordering::Relaxed) };
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Remove every queued tasklet, oldest first
+    fn take(&self) -> Vec<Arc<Tasklet>> {
+        let mut node = self.head.swap(ptr::null_mut(), Ordering::Acquire);
+        let mut batch = Vec::new();
+        while !node.is_null() {
+            // SAFETY: Every node was leaked from an `Arc` by `push`, and the
+            // swap above made this the only owner of the chain
+            let tasklet = unsafe { Arc::from_raw(node) };
+            node = tasklet.next.swap(ptr::null_mut(), Ordering::Relaxed);
+            batch.push(tasklet);
+        }
+        batch.reverse();
+        batch
+    }
+}
+
+impl Drop for TaskletList {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+// ============================================================================
+// SOFTIRQ TABLE
+// ============================================================================
+
+/// Per-CPU softirq state
+struct CpuSoftirq {
+    /// Pending vector bits
+    pending: AtomicU32,
+    /// Softirqs are running on this CPU
+    active: AtomicBool,
+    /// ksoftirqd thread, once spawned
+    ksoftirqd: RwLock<Option<ThreadId>>,
+    /// Queued high-priority tasklets
+    hi_tasklets: TaskletList,
+    /// Queued normal tasklets
+    tasklets: TaskletList,
+    /// Scheduled tasklets waiting to be enabled
+    parked: IrqMutex<Vec<Arc<Tasklet>>>,
+}
+
+impl CpuSoftirq {
+    const fn new() -> Self {
+        Self {
+            pending: AtomicU32::new(0),
+            active: AtomicBool::new(false),
+            ksoftirqd: RwLock::new(None),
+            hi_tasklets: TaskletList::new(),
+            tasklets: TaskletList::new(),
+            parked: IrqMutex::new(Vec::new()),
+        }
+    }
+
+    fn tasklet_list(&self, vector: SoftirqVector) -> &TaskletList {
+        match vector {
+            SoftirqVector::HiTasklet => &self.hi_tasklets,
+            _ => &self.tasklets,
+        }
+    }
+}
+
+/// Softirq vectors and per-CPU pending state
+pub struct SoftirqTable {
+    /// Handler per vector
+    handlers: [RwLock<Option<SoftirqHandler>>; SoftirqVector::COUNT],
+    /// Handler invocations per vector
+    counts: [AtomicU64; SoftirqVector::COUNT],
+    /// Per-CPU state
+    cpus: [CpuSoftirq; MAX_CPUS],
+}
+
+impl SoftirqTable {
+    /// Create a table with only the tasklet vectors open
+    pub const fn new() -> Self {
+        let mut handlers = [const { RwLock::new(None) }; SoftirqVector::COUNT];
+        handlers[SoftirqVector::HiTasklet as usize] =
+            RwLock::new(Some(tasklet_hi_action as SoftirqHandler));
+        handlers[SoftirqVector::Tasklet as usize] =
+            RwLock::new(Some(tasklet_action as SoftirqHandler));
+
+        Self {
+            handlers,
+            counts: [const { AtomicU64::new(0) }; SoftirqVector::COUNT],
+            cpus: [const { CpuSoftirq::new() }; MAX_CPUS],
+        }
+    }
+
+    /// Install the handler for a vector
+    pub fn open(&self, vector: SoftirqVector, handler: SoftirqHandler) {
+        *self.handlers[vector as usize].write() = Some(handler);
+    }
+
+    /// Mark a vector pending on `cpu`
+    ///
+    /// Outside interrupt context the softirq would otherwise wait for the
+    /// next IRQ exit, so ksoftirqd is woken instead.
+    pub fn raise(&self, cpu: usize, vector: SoftirqVector) {
+        self.cpus[cpu].pending.fetch_or(vector.bit(), Ordering::AcqRel);
+        if !super::in_interrupt(cpu) {
+            self.wake_ksoftirqd(cpu);
+        }
+    }
+
+    /// Pending vector bits on `cpu`
+    pub fn pending(&self, cpu: usize) -> u32 {
+        self.cpus[cpu].pending.load(Ordering::Acquire)
+    }
+
+    /// Is `cpu` running softirqs?
+    pub fn in_softirq(&self, cpu: usize) -> bool {
+        self.cpus[cpu].active.load(Ordering::Acquire)
+    }
+
+    /// Handler invocations for a vector, across CPUs
+    pub fn count(&self, vector: SoftirqVector) -> u64 {
+        self.counts[vector as usize].load(Ordering::Relaxed)
+    }
+
+    /// Run pending softirqs on `cpu`
+    ///
+    /// Vectors raised while running are picked up for up to
+    /// `MAX_RESTART` rounds. Returns `true` if softirqs are still pending.
+    pub fn run(&self, cpu: usize) -> bool {
+        let state = &self.cpus[cpu];
+        if state.active.swap(true, Ordering::Acquire) {
+            // Already running further up this CPU's stack
+            return false;
+        }
+
+        for _ in 0..MAX_RESTART {
+            let pending = state.pending.swap(0, Ordering::AcqRel);
+            if pending == 0 {
+                break;
+            }
+
+            for vector in SoftirqVector::ALL {
+                if pending & vector.bit() == 0 {
+                    continue;
+                }
+                let handler = *self.handlers[vector as usize].read();
+                if let Some(handler) = handler {
+                    handler(cpu);
+                    self.counts[vector as usize].fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        state.active.store(false, Ordering::Release);
+        self.pending(cpu) != 0
+    }
+
+    /// Softirq processing on IRQ exit
+    pub(super) fn run_on_exit(&self, cpu: usize) {
+        if self.pending(cpu) != 0 && self.run(cpu) {
+            self.wake_ksoftirqd(cpu);
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    // ksoftirqd
+    // -------------------------------------------------------------------------
+
+    /// Register the `ksoftirqd/N` thread for `cpu` with the scheduler
+    pub fn spawn_ksoftirqd(&self, cpu: usize) -> ExecResult<ThreadId> {
+        if cpu >= MAX_CPUS {
+            return Err(ExecError::InvalidArgument);
+        }
+        let mut slot = self.cpus[cpu].ksoftirqd.write();
+        if slot.is_some() {
+            return Err(ExecError::AlreadyExists);
+        }
+
+        let id = ThreadId::new();
+        let thread = SchedulableThread::new(id, ProcessId::kernel(), Priority::normal(0))
+            .with_name(format!("ksoftirqd/{}", cpu))
+            .with_affinity(1 << cpu)
+            .kernel();
+        framework().add_thread(thread)?;
+        *slot = Some(id);
+        Ok(id)
+    }
+
+    /// Body of `ksoftirqd/N`: drain pending softirqs, then block
+    pub fn ksoftirqd_run(&self, cpu: usize) {
+        while self.pending(cpu) != 0 {
+            self.run(cpu);
+            framework().yield_current(cpu);
+        }
+        if let Some(id) = *self.cpus[cpu].ksoftirqd.read() {
+            let _ = framework().thread_block(id);
+        }
+    }
+
+    fn wake_ksoftirqd(&self, cpu: usize) {
+        if let Some(id) = *self.cpus[cpu].ksoftirqd.read() {
+            let _ = framework().thread_ready(id);
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    // Tasklets
+    // -------------------------------------------------------------------------
+
+    /// Queue a tasklet on `cpu`
+    pub fn tasklet_schedule(&self, cpu: usize, tasklet: &Arc<Tasklet>) {
+        self.queue_tasklet(cpu, tasklet, SoftirqVector::Tasklet);
+    }
+
+    /// Queue a tasklet on `cpu` at high priority
+    pub fn tasklet_hi_schedule(&self, cpu: usize, tasklet: &Arc<Tasklet>) {
+        self.queue_tasklet(cpu, tasklet, SoftirqVector::HiTasklet);
+    }
+
+    fn queue_tasklet(&self, cpu: usize, tasklet: &Arc<Tasklet>, vector: SoftirqVector) {
+        if tasklet.state.fetch_or(TASKLET_SCHEDULED, Ordering::AcqRel) & TASKLET_SCHEDULED != 0 {
+            return;
+        }
+        self.cpus[cpu].tasklet_list(vector).push(tasklet.clone());
+        self.raise(cpu, vector);
+    }
+
+    fn run_tasklets(&self, cpu: usize, vector: SoftirqVector) {
+        let list = self.cpus[cpu].tasklet_list(vector);
+        let batch = list.take();
+
+        for tasklet in batch {
+            if tasklet.is_disabled() {
+                self.park(cpu, tasklet, vector);
+            } else if !tasklet.try_run() {
+                list.push(tasklet);
+                self.cpus[cpu].pending.fetch_or(vector.bit(), Ordering::AcqRel);
+            }
+        }
+    }
+
+    /// Set a disabled tasklet aside until it is enabled
+    fn park(&self, cpu: usize, tasklet: Arc<Tasklet>, vector: SoftirqVector) {
+        {
+            let mut parked = self.cpus[cpu].parked.lock();
+            parked.push(tasklet.clone());
+            tasklet.parked_on.store(((cpu as u32) << 8) | vector as u32, Ordering::SeqCst);
+        }
+
+        // An enable that ran before `parked_on` was set found nothing to
+        // unpark
+        if !tasklet.is_disabled() {
+            self.unpark(&tasklet);
+        }
+    }
+
+    /// Queue a parked tasklet again
+    fn unpark(&self, tasklet: &Tasklet) {
+        let slot = tasklet.parked_on.swap(NOT_PARKED, Ordering::SeqCst);
+        if slot == NOT_PARKED {
+            return;
+        }
+        let cpu = (slot >> 8) as usize;
+        let vector = if slot & 0xFF == SoftirqVector::HiTasklet as u32 {
+            SoftirqVector::HiTasklet
+        } else {
+            SoftirqVector::Tasklet
+        };
+
+        let mut parked = self.cpus[cpu].parked.lock();
+        let Some(index) = parked.iter().position(|t| core::ptr::eq(&**t, tasklet)) else {
+            return;
+        };
+        let tasklet = parked.swap_remove(index);
+        drop(parked);
+
+        self.cpus[cpu].tasklet_list(vector).push(tasklet);
+        self.raise(cpu, vector);
+    }
+
+    /// Number of tasklets parked on `cpu`
+    pub fn parked_tasklets(&self, cpu: usize) -> usize {
+        self.cpus[cpu].parked.lock().len()
+    }
+}
+
+impl Default for SoftirqTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global softirq table
+static SOFTIRQS: SoftirqTable = SoftirqTable::new();
+
+/// Get the softirq table
+pub fn softirqs() -> &'static SoftirqTable {
+    &SOFTIRQS
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    static RUNS: [AtomicUsize; 4] = [const { AtomicUsize::new(0) }; 4];
+
+    fn count(slot: usize) {
+        RUNS[slot].fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn runs(slot: usize) -> usize {
+        RUNS[slot].load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_schedule_with_lock_held() {
+        // Scheduling from an IRQ that interrupted the parked-list owner
+        let cpu = 10;
+        let tasklet = Arc::new(Tasklet::new(count, 0));
+        let parked = softirqs().cpus[cpu].parked.lock();
+        softirqs().tasklet_schedule(cpu, &tasklet);
+        softirqs().tasklet_hi_schedule(cpu, &Arc::new(Tasklet::new(count, 0)));
+        drop(parked);
+
+        assert!(tasklet.is_scheduled());
+        softirqs().run(cpu);
+        assert_eq!(runs(0), 2);
+        assert!(!tasklet.is_scheduled());
+    }
+
+    #[test]
+    fn test_tasklet_list_order() {
+        let list = TaskletList::new();
+        let tasklets: Vec<_> = (0..3).map(|i| Arc::new(Tasklet::new(count, i))).collect();
+        for tasklet in &tasklets {
+            list.push(tasklet.clone());
+        }
+
+        let batch = list.take();
+        let data: Vec<_> = batch.iter().map(|t| t.data).collect();
+        assert_eq!(data, [0, 1, 2]);
+        assert!(list.take().is_empty());
+        drop(batch);
+        assert!(tasklets.iter().all(|t| Arc::strong_count(t) == 1));
+    }
+
+    static RESCHEDULED: spin::Once<Arc<Tasklet>> = spin::Once::new();
+
+    /// Tasklet body that queues itself again on its first run
+    fn reschedule(cpu: usize) {
+        count(1);
+        if runs(1) == 1 {
+            softirqs().tasklet_schedule(cpu, RESCHEDULED.get().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_reschedule_from_tasklet() {
+        let tasklet = RESCHEDULED.call_once(|| Arc::new(Tasklet::new(reschedule, 11)));
+        softirqs().tasklet_schedule(11, tasklet);
+        softirqs().run(11);
+        assert_eq!(runs(1), 2);
+        assert!(!tasklet.is_scheduled());
+    }
+
+    #[test]
+    fn test_disabled_tasklet_parks() {
+        let cpu = 12;
+        let tasklet = Arc::new(Tasklet::new(count, 2));
+        tasklet.disable();
+        softirqs().tasklet_schedule(cpu, &tasklet);
+        softirqs().run(cpu);
+        assert_eq!(runs(2), 0);
+        assert_eq!(softirqs().parked_tasklets(cpu), 1);
+
+        tasklet.enable();
+        assert_eq!(softirqs().parked_tasklets(cpu), 0);
+        softirqs().run(cpu);
+        assert_eq!(runs(2), 1);
+    }
+}
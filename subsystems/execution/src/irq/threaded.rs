@@ -0,0 +1,259 @@
+//! # Threaded IRQ Handlers
+//!
+//! A threaded handler splits interrupt handling in two: an optional
+//! primary handler runs in hard-IRQ context and decides whether the
+//! thread is needed, and the thread handler runs in a dedicated kernel
+//! thread (`irq/N-name`) scheduled like any other thread. Its priority
+//! defaults to the middle of the real-time range and can be changed
+//! through the scheduler framework.
+//!
+//! One-shot lines stay masked from the hard IRQ until the thread handler
+//! has run, for level-triggered devices that can only be quiesced from
+//! the thread.
+
+use crate::scheduler::{framework, Priority, SchedulableThread};
+use crate::{ExecError, ExecResult, ProcessId, ThreadId};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use spin::{Mutex, RwLock};
+
+/// Result of a primary (hard-IRQ) handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrqReturn {
+    /// Interrupt was not from this device
+    None,
+    /// Interrupt fully handled
+    Handled,
+    /// Wake the handler thread
+    WakeThread,
+}
+
+/// Primary handler, run in hard-IRQ context
+pub type PrimaryHandler = fn(irq: u32) -> IrqReturn;
+
+/// Thread handler, run in the IRQ thread
+pub type ThreadHandler = Box<dyn FnMut(u32) + Send>;
+
+/// Default IRQ thread priority
+pub const DEFAULT_IRQ_PRIORITY: Priority = Priority::realtime(50);
+
+/// Threaded IRQ request
+pub struct ThreadedIrq {
+    name: &'static str,
+    primary: Option<PrimaryHandler>,
+    handler: ThreadHandler,
+    priority: Priority,
+    oneshot: bool,
+    affinity: u64,
+}
+
+impl ThreadedIrq {
+    /// Create a request whose hard IRQ always wakes the thread
+    pub fn new(name: &'static str, handler: impl FnMut(u32) + Send + 'static) -> Self {
+        Self {
+            name,
+            primary: None,
+            handler: Box::new(handler),
+            priority: DEFAULT_IRQ_PRIORITY,
+            oneshot: false,
+            affinity: u64::MAX,
+        }
+    }
+
+    /// Set the primary handler
+    pub fn with_primary(mut self, primary: PrimaryHandler) -> Self {
+        self.primary = Some(primary);
+        self
+    }
+
+    /// Set the thread priority
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set the thread CPU affinity
+    pub fn with_affinity(mut self, affinity: u64) -> Self {
+        self.affinity = affinity;
+        self
+    }
+
+    /// Keep the line masked until the thread handler completes
+    pub fn oneshot(mut self) -> Self {
+        self.oneshot = true;
+        self
+    }
+}
+
+/// An installed threaded handler
+struct IrqAction {
+    primary: Option<PrimaryHandler>,
+    handler: Mutex<ThreadHandler>,
+    thread: ThreadId,
+    oneshot: bool,
+    /// The thread has work to do
+    pending: AtomicBool,
+    /// Thread handler runs
+    runs: AtomicU64,
+    /// Interrupts the primary handler did not claim
+    unhandled: AtomicU64,
+}
+
+/// Line masking hooks provided by the interrupt controller driver
+#[derive(Clone, Copy)]
+struct LineControl {
+    mask: fn(u32),
+    unmask: fn(u32),
+}
+
+/// Threaded IRQ handler registry
+pub struct ThreadedIrqs {
+    actions: RwLock<BTreeMap<u32, Arc<IrqAction>>>,
+    line_control: RwLock<Option<LineControl>>,
+}
+
+impl ThreadedIrqs {
+    /// Create an empty registry
+    pub const fn new() -> Self {
+        Self {
+            actions: RwLock::new(BTreeMap::new()),
+            line_control: RwLock::new(None),
+        }
+    }
+
+    /// Install the interrupt controller's mask/unmask hooks
+    pub fn set_line_control(&self, mask: fn(u32), unmask: fn(u32)) {
+        *self.line_control.write() = Some(LineControl { mask, unmask });
+    }
+
+    /// Install a threaded handler and register its thread
+    pub fn request(&self, irq: u32, request: ThreadedIrq) -> ExecResult<ThreadId> {
+        let mut actions = self.actions.write();
+        if actions.contains_key(&irq) {
+            return Err(ExecError::AlreadyExists);
+        }
+
+        let thread = ThreadId::new();
+        let mut schedulable = SchedulableThread::new(thread, ProcessId::kernel(), request.priority)
+            .with_name(format!("irq/{}-{}", irq, request.name))
+            .with_affinity(request.affinity)
+            .kernel();
+        if request.priority.is_realtime() {
+            schedulable = schedulable.realtime();
+        }
+        framework().add_thread(schedulable)?;
+        let _ = framework().thread_block(thread);
+
+        actions.insert(
+            irq,
+            Arc::new(IrqAction {
+                primary: request.primary,
+                handler: Mutex::new(request.handler),
+                thread,
+                oneshot: request.oneshot,
+                pending: AtomicBool::new(false),
+                runs: AtomicU64::new(0),
+                unhandled: AtomicU64::new(0),
+            }),
+        );
+        Ok(thread)
+    }
+
+    /// Remove a threaded handler and its thread
+    pub fn free(&self, irq: u32) -> ExecResult<()> {
+        let action = self.actions.write().remove(&irq).ok_or(ExecError::InvalidArgument)?;
+        framework().remove_thread(action.thread)
+    }
+
+    /// Hard-IRQ entry for `irq`
+    pub fn handle(&self, irq: u32) -> IrqReturn {
+        let Some(action) = self.actions.read().get(&irq).cloned() else {
+            return IrqReturn::None;
+        };
+
+        let ret = match action.primary {
+            Some(primary) => primary(irq),
+            None => IrqReturn::WakeThread,
+        };
+
+        match ret {
+            IrqReturn::None => {
+                action.unhandled.fetch_add(1, Ordering::Relaxed);
+            },
+            IrqReturn::Handled => {},
+            IrqReturn::WakeThread => {
+                if action.oneshot {
+                    if let Some(control) = *self.line_control.read() {
+                        (control.mask)(irq);
+                    }
+                }
+                action.pending.store(true, Ordering::Release);
+                let _ = framework().thread_ready(action.thread);
+            },
+        }
+        ret
+    }
+
+    /// Body of the `irq/N` thread: run the handler if woken, then block
+    ///
+    /// Returns `true` if the handler ran.
+    pub fn thread_run(&self, irq: u32) -> bool {
+        let Some(action) = self.actions.read().get(&irq).cloned() else {
+            return false;
+        };
+
+        let ran = action.pending.swap(false, Ordering::AcqRel);
+        if ran {
+            (action.handler.lock())(irq);
+            action.runs.fetch_add(1, Ordering::Relaxed);
+            if action.oneshot {
+                if let Some(control) = *self.line_control.read() {
+                    (control.unmask)(irq);
+                }
+            }
+        }
+
+        if !action.pending.load(Ordering::Acquire) {
+            let _ = framework().thread_block(action.thread);
+        }
+        ran
+    }
+
+    /// Change the priority of an IRQ thread
+    pub fn set_priority(&self, irq: u32, priority: Priority) -> ExecResult<()> {
+        let thread = self.thread_of(irq).ok_or(ExecError::InvalidArgument)?;
+        framework().set_priority(thread, priority)
+    }
+
+    /// Thread serving `irq`
+    pub fn thread_of(&self, irq: u32) -> Option<ThreadId> {
+        self.actions.read().get(&irq).map(|a| a.thread)
+    }
+
+    /// (thread handler runs, unclaimed interrupts) for `irq`
+    pub fn stats(&self, irq: u32) -> Option<(u64, u64)> {
+        self.actions.read().get(&irq).map(|a| {
+            (
+                a.runs.load(Ordering::Relaxed),
+                a.unhandled.load(Ordering::Relaxed),
+            )
+        })
+    }
+}
+
+impl Default for ThreadedIrqs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global threaded IRQ registry
+static THREADED_IRQS: ThreadedIrqs = ThreadedIrqs::new();
+
+/// Get the threaded IRQ registry
+pub fn threaded_irqs() -> &'static ThreadedIrqs {
+    &THREADED_IRQS
+}
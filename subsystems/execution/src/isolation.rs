@@ -0,0 +1,282 @@
+//! # CPU Isolation
+//!
+//! Carves CPUs out of general kernel activity so latency-critical tasks
+//! (audio, control loops) can own them:
+//!
+//! - **Isolated** CPUs (`isolcpus=`) run only threads explicitly pinned
+//!   to them. Unbound threads, IRQ threads and workqueue workers are
+//!   confined to the housekeeping CPUs, and the load balancer does not
+//!   migrate onto them.
+//! - **nohz_full** CPUs (`nohz_full=`) stop the periodic scheduler tick
+//!   while running a single task. They are also excluded from
+//!   housekeeping.
+//! - **Device IRQs** are routed to the housekeeping CPUs, or to
+//!   `irqaffinity=` if given. Per-CPU interrupts (local timer, IPIs) are
+//!   unaffected.
+//!
+//! At least one online CPU always remains for housekeeping. The timer and
+//! interrupt-controller layers apply changes through [`IsolationHooks`].
+
+use crate::{ExecError, ExecResult};
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::RwLock;
+
+/// Callbacks into the timer and interrupt-controller layers
+#[derive(Clone, Copy)]
+pub struct IsolationHooks {
+    /// Restart (`true`) or stop (`false`) the periodic tick on a CPU
+    pub set_tick: fn(cpu: usize, enabled: bool),
+    /// Route device IRQs to the CPUs in the mask
+    pub set_irq_affinity: fn(mask: u64),
+}
+
+/// CPU isolation state
+pub struct CpuIsolation {
+    /// Online CPUs
+    online: AtomicU64,
+    /// Isolated CPUs
+    isolated: AtomicU64,
+    /// Tickless CPUs
+    nohz_full: AtomicU64,
+    /// Requested device IRQ affinity (0 = housekeeping)
+    irq_affinity: AtomicU64,
+    /// nohz_full CPUs whose tick is currently stopped
+    tick_stopped: AtomicU64,
+    /// Timer / IRQ layer callbacks
+    hooks: RwLock<Option<IsolationHooks>>,
+}
+
+impl CpuIsolation {
+    /// No isolation, CPU 0 online
+    pub const fn new() -> Self {
+        Self {
+            online: AtomicU64::new(1),
+            isolated: AtomicU64::new(0),
+            nohz_full: AtomicU64::new(0),
+            irq_affinity: AtomicU64::new(0),
+            tick_stopped: AtomicU64::new(0),
+            hooks: RwLock::new(None),
+        }
+    }
+
+    /// Install the timer / IRQ layer callbacks and apply the current state
+    pub fn set_hooks(&self, hooks: IsolationHooks) {
+        *self.hooks.write() = Some(hooks);
+        self.apply_irq_affinity();
+    }
+
+    /// Configure from `isolcpus=`, `nohz_full=` and `irqaffinity=`
+    ///
+    /// An invalid combination (no housekeeping CPU left) is logged and
+    /// ignored.
+    pub fn init_from_cmdline(&self, online: u64) {
+        let mask = |name| {
+            helix_cmdline::kernel::value(name)
+                .ok()
+                .and_then(|v| v.as_cpu_mask())
+                .unwrap_or(0)
+        };
+
+        let (isolated, nohz_full, irq) = (mask("isolcpus"), mask("nohz_full"), mask("irqaffinity"));
+        if let Err(e) = self.configure(online, isolated, nohz_full, irq) {
+            log::warn!("CPU isolation: ignoring cmdline configuration ({:?})", e);
+            let _ = self.configure(online, 0, 0, irq);
+        } else if isolated | nohz_full != 0 {
+            log::info!(
+                "CPU isolation: isolated {:#x}, nohz_full {:#x}, housekeeping {:#x}",
+                isolated,
+                nohz_full,
+                self.housekeeping()
+            );
+        }
+    }
+
+    /// Replace the whole configuration
+    pub fn configure(&self, online: u64, isolated: u64, nohz_full: u64, irq_affinity: u64) -> ExecResult<()> {
+        if online & !(isolated | nohz_full) == 0 {
+            return Err(ExecError::InvalidArgument);
+        }
+
+        self.online.store(online, Ordering::Release);
+        self.isolated.store(isolated & online, Ordering::Release);
+        self.irq_affinity.store(irq_affinity, Ordering::Release);
+        self.set_nohz_mask(nohz_full & online);
+        self.apply_irq_affinity();
+        Ok(())
+    }
+
+    /// A CPU came online
+    pub fn cpu_online(&self, cpu: usize) {
+        if cpu < 64 {
+            self.online.fetch_or(1 << cpu, Ordering::AcqRel);
+            self.apply_irq_affinity();
+        }
+    }
+
+    // =========================================================================
+    // Runtime API
+    // =========================================================================
+
+    /// Isolate more CPUs
+    ///
+    /// Already-running unbound threads keep their affinity until the
+    /// scheduler re-applies [`CpuIsolation::unbound_affinity`].
+    pub fn isolate(&self, cpus: u64) -> ExecResult<()> {
+        self.update(|isolated, nohz| (isolated | cpus, nohz))
+    }
+
+    /// Return CPUs to housekeeping
+    pub fn unisolate(&self, cpus: u64) -> ExecResult<()> {
+        self.update(|isolated, nohz| (isolated & !cpus, nohz & !cpus))
+    }
+
+    /// Replace the set of tickless CPUs
+    pub fn set_nohz_full(&self, cpus: u64) -> ExecResult<()> {
+        self.update(|isolated, _| (isolated, cpus))
+    }
+
+    /// Route device IRQs to `cpus` (0 = housekeeping CPUs)
+    pub fn set_irq_affinity(&self, cpus: u64) -> ExecResult<()> {
+        if cpus != 0 && cpus & self.online() == 0 {
+            return Err(ExecError::InvalidArgument);
+        }
+        self.irq_affinity.store(cpus, Ordering::Release);
+        self.apply_irq_affinity();
+        Ok(())
+    }
+
+    fn update(&self, f: impl FnOnce(u64, u64) -> (u64, u64)) -> ExecResult<()> {
+        let online = self.online();
+        let (isolated, nohz) = f(self.isolated(), self.nohz_full());
+        let (isolated, nohz) = (isolated & online, nohz & online);
+        if online & !(isolated | nohz) == 0 {
+            return Err(ExecError::InvalidArgument);
+        }
+
+        self.isolated.store(isolated, Ordering::Release);
+        self.set_nohz_mask(nohz);
+        self.apply_irq_affinity();
+        Ok(())
+    }
+
+    fn set_nohz_mask(&self, nohz: u64) {
+        self.nohz_full.store(nohz, Ordering::Release);
+
+        // CPUs leaving nohz_full get their tick back
+        let restart = self.tick_stopped.fetch_and(nohz, Ordering::AcqRel) & !nohz;
+        if let Some(hooks) = *self.hooks.read() {
+            for cpu in (0..64).filter(|cpu| restart & (1 << cpu) != 0) {
+                (hooks.set_tick)(cpu, true);
+            }
+        }
+    }
+
+    fn apply_irq_affinity(&self) {
+        if let Some(hooks) = *self.hooks.read() {
+            (hooks.set_irq_affinity)(self.irq_mask());
+        }
+    }
+
+    // =========================================================================
+    // Queries
+    // =========================================================================
+
+    /// Online CPUs
+    pub fn online(&self) -> u64 {
+        self.online.load(Ordering::Acquire)
+    }
+
+    /// Isolated CPUs
+    pub fn isolated(&self) -> u64 {
+        self.isolated.load(Ordering::Acquire)
+    }
+
+    /// Tickless CPUs
+    pub fn nohz_full(&self) -> u64 {
+        self.nohz_full.load(Ordering::Acquire)
+    }
+
+    /// CPUs that run unbound kernel work
+    pub fn housekeeping(&self) -> u64 {
+        self.online() & !(self.isolated() | self.nohz_full())
+    }
+
+    /// Is `cpu` isolated?
+    pub fn is_isolated(&self, cpu: usize) -> bool {
+        cpu < 64 && self.isolated() & (1 << cpu) != 0
+    }
+
+    /// Is `cpu` a housekeeping CPU?
+    pub fn is_housekeeping(&self, cpu: usize) -> bool {
+        cpu < 64 && self.housekeeping() & (1 << cpu) != 0
+    }
+
+    /// Affinity for a thread that asked for `requested`
+    ///
+    /// Threads allowed on any housekeeping CPU are confined to them;
+    /// threads pinned only to non-housekeeping CPUs keep their pinning.
+    pub fn unbound_affinity(&self, requested: u64) -> u64 {
+        match requested & self.housekeeping() {
+            0 => requested,
+            confined => confined,
+        }
+    }
+
+    /// CPUs device IRQs are routed to
+    pub fn irq_mask(&self) -> u64 {
+        let online = self.online();
+        let requested = self.irq_affinity.load(Ordering::Acquire) & online & !self.isolated();
+        if requested != 0 {
+            requested
+        } else {
+            self.housekeeping()
+        }
+    }
+
+    // =========================================================================
+    // Tick Control
+    // =========================================================================
+
+    /// Re-evaluate the tick of `cpu` with `runnable` threads (current included)
+    ///
+    /// A nohz_full CPU with at most one runnable thread stops its tick;
+    /// it restarts as soon as a second thread becomes runnable.
+    pub fn update_tick(&self, cpu: usize, runnable: usize) {
+        if cpu >= 64 {
+            return;
+        }
+        let bit = 1u64 << cpu;
+        let stop = self.nohz_full() & bit != 0 && runnable <= 1;
+
+        let was_stopped = if stop {
+            self.tick_stopped.fetch_or(bit, Ordering::AcqRel)
+        } else {
+            self.tick_stopped.fetch_and(!bit, Ordering::AcqRel)
+        } & bit != 0;
+
+        if was_stopped != stop {
+            if let Some(hooks) = *self.hooks.read() {
+                (hooks.set_tick)(cpu, !stop);
+            }
+        }
+    }
+
+    /// Is the tick of `cpu` stopped?
+    pub fn tick_stopped(&self, cpu: usize) -> bool {
+        cpu < 64 && self.tick_stopped.load(Ordering::Acquire) & (1 << cpu) != 0
+    }
+}
+
+impl Default for CpuIsolation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global CPU isolation state
+static ISOLATION: CpuIsolation = CpuIsolation::new();
+
+/// Get the CPU isolation state
+pub fn isolation() -> &'static CpuIsolation {
+    &ISOLATION
+}
@@ -0,0 +1,778 @@
+//! # Kernel Worker Pools
+//!
+//! Shared worker threads for background work, so subsystems queue work
+//! items instead of each running its own kernel thread loop:
+//!
+//! - **Pools**: every CPU has a bound pool per [`WorkClass`], whose worker
+//!   (`kworker/N:class`) is pinned to that CPU. Each class also has an
+//!   unbound pool (`kworker/u:class`) that grows up to
+//!   [`UNBOUND_MAX_WORKERS`] workers on the housekeeping CPUs.
+//! - **Workqueues** ([`Workqueue`]) choose a class and bound or unbound
+//!   pools, and account for the work they queue. Work with no queue of its
+//!   own goes to [`system_wq`].
+//! - **Work items** run once, once after a delay, or periodically. Each has
+//!   a [`WorkId`] for [`Workqueue::cancel`] and [`Workqueue::flush_work`].
+//!
+//! Delays count ticks of the clock advanced by [`KworkerPools::tick`] from
+//! the timer softirq, so the queues it shares with thread context are
+//! [`IrqMutex`]es; busy time is measured with the clock installed by
+//! [`KworkerPools::set_clock`]. Accounting is rendered at
+//! `/proc/workqueues`:
+//!
+//! ```text
+//! pool                 workers  pending  delayed  running  executed  busy_ns
+//! kworker/0:high       1        0        0        0        12        48211
+//! kworker/u:normal     2        3        1        2        907       1822040
+//!
+//! workqueue            pool     class       queued  executed  cancelled
+//! writeback            unbound  background  310     309       1
+//! ```
+
+use crate::isolation::isolation;
+use crate::scheduler::{framework, Priority, PriorityClass, SchedulableThread};
+use crate::sync::IrqMutex;
+use crate::{ExecError, ExecResult, ProcessId, ThreadId};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use spin::RwLock;
+
+/// Maximum number of CPUs with bound pools
+pub const MAX_CPUS: usize = crate::irq::MAX_CPUS;
+
+/// Workers an unbound pool may grow to
+pub const UNBOUND_MAX_WORKERS: usize = 4;
+
+/// File name under `/proc`
+pub const PROC_PATH: &str = "workqueues";
+
+// =============================================================================
+// Work Items
+// =============================================================================
+
+/// Priority class of a workqueue and the pools serving it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WorkClass {
+    /// Latency-sensitive work (`kworker/N:high`)
+    High,
+    /// Default
+    Normal,
+    /// Bulk work that yields to everything else
+    Background,
+}
+
+impl WorkClass {
+    /// Number of classes
+    pub const COUNT: usize = 3;
+
+    /// All classes
+    pub const ALL: [Self; Self::COUNT] = [Self::High, Self::Normal, Self::Background];
+
+    /// Name used in worker names and `/proc`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::High => "high",
+            Self::Normal => "normal",
+            Self::Background => "background",
+        }
+    }
+
+    /// Priority of the class's workers
+    pub fn priority(self) -> Priority {
+        match self {
+            Self::High => PriorityClass::High.to_priority(),
+            Self::Normal => PriorityClass::Normal.to_priority(),
+            Self::Background => PriorityClass::BelowNormal.to_priority(),
+        }
+    }
+}
+
+/// Identifies a queued work item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WorkId(u64);
+
+/// What a work item runs
+enum Job {
+    /// Run once
+    Once(Box<dyn FnOnce() + Send>),
+    /// Run every `period` ticks until cancelled
+    Periodic {
+        period: u64,
+        func: Box<dyn FnMut() + Send>,
+    },
+}
+
+/// A queued work item
+struct Item {
+    id: WorkId,
+    wq: &'static Workqueue,
+    job: Job,
+}
+
+/// Work waiting for its due tick
+struct Delayed {
+    due: u64,
+    item: Item,
+}
+
+/// Where a live work item is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemState {
+    /// In a pool's ready queue
+    Pending,
+    /// Waiting for its due tick
+    Delayed,
+    /// Being run by a worker
+    Running,
+    /// Cancelled while running; a periodic item is not re-armed
+    Cancelled,
+}
+
+/// Bookkeeping for a live work item
+#[derive(Clone, Copy)]
+struct Entry {
+    /// Index of the pool holding it
+    pool: usize,
+    /// Owning workqueue
+    wq: &'static Workqueue,
+    /// Re-armed after each run
+    periodic: bool,
+    state: ItemState,
+}
+
+// =============================================================================
+// Pools
+// =============================================================================
+
+/// A set of workers sharing one queue
+struct Pool {
+    /// Pool name; workers are named after it
+    name: String,
+    /// Class served
+    class: WorkClass,
+    /// CPU of a bound pool
+    cpu: Option<usize>,
+    /// Worker limit
+    max_workers: usize,
+    /// Worker threads
+    workers: IrqMutex<Vec<ThreadId>>,
+    /// Work ready to run, in queue order
+    ready: IrqMutex<VecDeque<Item>>,
+    /// Work waiting for its due tick
+    delayed: IrqMutex<Vec<Delayed>>,
+    /// Items being run
+    running: AtomicU32,
+    /// Items completed
+    executed: AtomicU64,
+    /// Time spent running items (ns)
+    busy_ns: AtomicU64,
+}
+
+impl Pool {
+    fn new(class: WorkClass, cpu: Option<usize>) -> Self {
+        let name = match cpu {
+            Some(cpu) => format!("kworker/{}:{}", cpu, class.as_str()),
+            None => format!("kworker/u:{}", class.as_str()),
+        };
+        Self {
+            name,
+            class,
+            cpu,
+            max_workers: if cpu.is_some() {
+                1
+            } else {
+                UNBOUND_MAX_WORKERS
+            },
+            workers: IrqMutex::new(Vec::new()),
+            ready: IrqMutex::new(VecDeque::new()),
+            delayed: IrqMutex::new(Vec::new()),
+            running: AtomicU32::new(0),
+            executed: AtomicU64::new(0),
+            busy_ns: AtomicU64::new(0),
+        }
+    }
+
+    /// Register one more worker thread with the scheduler
+    fn spawn_worker(&self) -> ExecResult<ThreadId> {
+        let affinity = match self.cpu {
+            Some(cpu) => 1 << cpu,
+            None => isolation().unbound_affinity(u64::MAX),
+        };
+        let id = ThreadId::new();
+        let thread = SchedulableThread::new(id, ProcessId::kernel(), self.class.priority())
+            .with_name(self.name.clone())
+            .with_affinity(affinity)
+            .kernel();
+        framework().add_thread(thread)?;
+        self.workers.lock().push(id);
+        Ok(id)
+    }
+
+    /// Wake a worker, adding one to an unbound pool that is falling behind
+    fn wake(&self) {
+        let backlog = self.ready.lock().len();
+        let workers = self.workers.lock().len();
+        if workers < self.max_workers && (workers == 0 || backlog > workers) {
+            if let Err(e) = self.spawn_worker() {
+                log::warn!("{}: cannot add worker: {:?}", self.name, e);
+            }
+        }
+        let workers = self.workers.lock().clone();
+        for id in workers {
+            let _ = framework().thread_ready(id);
+        }
+    }
+}
+
+// =============================================================================
+// Registry
+// =============================================================================
+
+/// All worker pools and the work they hold
+pub struct KworkerPools {
+    /// Bound pools (per CPU and class), then one unbound pool per class
+    pools: RwLock<Vec<Arc<Pool>>>,
+    /// Every live work item
+    items: IrqMutex<BTreeMap<WorkId, Entry>>,
+    /// Workqueues, for `/proc`
+    workqueues: RwLock<Vec<&'static Workqueue>>,
+    /// Last tick passed to `tick`
+    now: AtomicU64,
+    /// Next work ID
+    next_id: AtomicU64,
+    /// Timestamp source for busy time (ns)
+    clock: RwLock<Option<fn() -> u64>>,
+    /// Current CPU, for work queued without a CPU on bound queues
+    cpu_id: RwLock<Option<fn() -> usize>>,
+}
+
+impl KworkerPools {
+    /// Create an empty registry (no pools until [`KworkerPools::init`])
+    pub const fn new() -> Self {
+        Self {
+            pools: RwLock::new(Vec::new()),
+            items: IrqMutex::new(BTreeMap::new()),
+            workqueues: RwLock::new(Vec::new()),
+            now: AtomicU64::new(0),
+            next_id: AtomicU64::new(1),
+            clock: RwLock::new(None),
+            cpu_id: RwLock::new(None),
+        }
+    }
+
+    /// Create the unbound pools and bound pools for the CPUs in `online`
+    ///
+    /// Workers are registered lazily, when a pool first gets work.
+    pub fn init(&self, online: u64) {
+        {
+            let mut pools = self.pools.write();
+            if pools.is_empty() {
+                pools.extend(
+                    WorkClass::ALL
+                        .iter()
+                        .map(|&class| Arc::new(Pool::new(class, None))),
+                );
+            }
+        }
+        for cpu in (0..MAX_CPUS).filter(|cpu| online & (1 << cpu) != 0) {
+            self.cpu_online(cpu);
+        }
+    }
+
+    /// Create the bound pools of a CPU that came online
+    pub fn cpu_online(&self, cpu: usize) {
+        let mut pools = self.pools.write();
+        if cpu >= MAX_CPUS || pools.iter().any(|p| p.cpu == Some(cpu)) {
+            return;
+        }
+        pools.extend(
+            WorkClass::ALL
+                .iter()
+                .map(|&class| Arc::new(Pool::new(class, Some(cpu)))),
+        );
+    }
+
+    /// Measure busy time with `clock` (ns)
+    pub fn set_clock(&self, clock: fn() -> u64) {
+        *self.clock.write() = Some(clock);
+    }
+
+    /// Report the current CPU with `cpu_id`; without it, bound work queued
+    /// without a CPU goes to CPU 0
+    pub fn set_cpu_id(&self, cpu_id: fn() -> usize) {
+        *self.cpu_id.write() = Some(cpu_id);
+    }
+
+    fn now_ns(&self) -> u64 {
+        self.clock.read().map(|clock| clock()).unwrap_or(0)
+    }
+
+    fn current_cpu(&self) -> usize {
+        self.cpu_id.read().map(|cpu_id| cpu_id()).unwrap_or(0)
+    }
+
+    /// Index of the pool serving `class` on `cpu` (`None`: unbound)
+    fn pool_index(&self, class: WorkClass, cpu: Option<usize>) -> ExecResult<usize> {
+        self.pools
+            .read()
+            .iter()
+            .position(|p| p.class == class && p.cpu == cpu)
+            .ok_or(ExecError::InvalidArgument)
+    }
+
+    fn pool(&self, index: usize) -> Arc<Pool> {
+        self.pools.read()[index].clone()
+    }
+
+    fn register(&self, wq: &'static Workqueue) {
+        if !wq.registered.swap(true, Ordering::AcqRel) {
+            self.workqueues.write().push(wq);
+        }
+    }
+
+    // =========================================================================
+    // Queueing
+    // =========================================================================
+
+    fn enqueue(&self, pool: usize, delay: u64, item: Item) -> WorkId {
+        let id = item.id;
+        let target = self.pool(pool);
+        let periodic = matches!(item.job, Job::Periodic { .. });
+        let mut entry = Entry {
+            pool,
+            wq: item.wq,
+            periodic,
+            state: ItemState::Pending,
+        };
+        let mut items = self.items.lock();
+        if delay == 0 {
+            items.insert(id, entry);
+            target.ready.lock().push_back(item);
+            drop(items);
+            target.wake();
+        } else {
+            let due = self.now.load(Ordering::Acquire).saturating_add(delay);
+            entry.state = ItemState::Delayed;
+            items.insert(id, entry);
+            target.delayed.lock().push(Delayed { due, item });
+        }
+        id
+    }
+
+    fn alloc_id(&self) -> WorkId {
+        WorkId(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Advance the clock, releasing delayed work that is due
+    pub fn tick(&self, now: u64) {
+        self.now.store(now, Ordering::Release);
+
+        let pools = self.pools.read().clone();
+        for pool in pools {
+            let mut items = self.items.lock();
+            let mut due = Vec::new();
+            {
+                let mut delayed = pool.delayed.lock();
+                let mut i = 0;
+                while i < delayed.len() {
+                    if delayed[i].due <= now {
+                        due.push(delayed.swap_remove(i));
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            if due.is_empty() {
+                continue;
+            }
+
+            due.sort_by_key(|d| (d.due, d.item.id));
+            for d in &due {
+                if let Some(entry) = items.get_mut(&d.item.id) {
+                    entry.state = ItemState::Pending;
+                }
+            }
+            pool.ready.lock().extend(due.into_iter().map(|d| d.item));
+            drop(items);
+            pool.wake();
+        }
+    }
+
+    // =========================================================================
+    // Running
+    // =========================================================================
+
+    /// Take the next ready item of `pool`, marking it running
+    fn take(&self, pool: &Pool, filter: impl Fn(&Item) -> bool) -> Option<Item> {
+        let mut items = self.items.lock();
+        let mut ready = pool.ready.lock();
+        let pos = ready.iter().position(filter)?;
+        let item = ready.remove(pos)?;
+        if let Some(entry) = items.get_mut(&item.id) {
+            entry.state = ItemState::Running;
+        }
+        pool.running.fetch_add(1, Ordering::Relaxed);
+        Some(item)
+    }
+
+    /// Run `item`, then retire it or re-arm it if periodic
+    fn run(&self, pool: &Pool, item: Item) {
+        let Item { id, wq, job } = item;
+        let start = self.now_ns();
+        let job = match job {
+            Job::Once(func) => {
+                func();
+                None
+            },
+            Job::Periodic { period, mut func } => {
+                func();
+                Some((period, func))
+            },
+        };
+        pool.busy_ns
+            .fetch_add(self.now_ns().saturating_sub(start), Ordering::Relaxed);
+        pool.executed.fetch_add(1, Ordering::Relaxed);
+        pool.running.fetch_sub(1, Ordering::Relaxed);
+        wq.executed.fetch_add(1, Ordering::Relaxed);
+
+        let mut items = self.items.lock();
+        let entry = items.get_mut(&id);
+        match (job, entry) {
+            (Some((period, func)), Some(entry)) if entry.state == ItemState::Running => {
+                let due = self.now.load(Ordering::Acquire).saturating_add(period);
+                entry.state = ItemState::Delayed;
+                let item = Item {
+                    id,
+                    wq,
+                    job: Job::Periodic { period, func },
+                };
+                pool.delayed.lock().push(Delayed { due, item });
+            },
+            _ => {
+                items.remove(&id);
+            },
+        }
+    }
+
+    /// Body of a worker thread: run ready work of its pool, then block
+    ///
+    /// Returns the items run.
+    pub fn worker_run(&self, worker: ThreadId) -> usize {
+        let found = self
+            .pools
+            .read()
+            .iter()
+            .find(|p| p.workers.lock().contains(&worker))
+            .cloned();
+        let Some(pool) = found else {
+            return 0;
+        };
+
+        let mut ran = 0;
+        while let Some(item) = self.take(&pool, |_| true) {
+            self.run(&pool, item);
+            ran += 1;
+        }
+        if pool.ready.lock().is_empty() {
+            let _ = framework().thread_block(worker);
+        }
+        ran
+    }
+
+    // =========================================================================
+    // Cancel / Flush
+    // =========================================================================
+
+    /// Cancel `id` if it has not started; a running periodic item is not
+    /// re-armed. Returns `true` if a future run was prevented.
+    fn cancel(&self, id: WorkId) -> bool {
+        let mut items = self.items.lock();
+        let Some(entry) = items.get_mut(&id) else {
+            return false;
+        };
+        let pool = self.pool(entry.pool);
+        let removed = match entry.state {
+            ItemState::Pending => {
+                let mut ready = pool.ready.lock();
+                ready
+                    .iter()
+                    .position(|i| i.id == id)
+                    .and_then(|pos| ready.remove(pos))
+            },
+            ItemState::Delayed => {
+                let mut delayed = pool.delayed.lock();
+                delayed
+                    .iter()
+                    .position(|d| d.item.id == id)
+                    .map(|pos| delayed.swap_remove(pos).item)
+            },
+            ItemState::Running => {
+                entry.state = ItemState::Cancelled;
+                if entry.periodic {
+                    entry.wq.cancelled.fetch_add(1, Ordering::Relaxed);
+                }
+                return entry.periodic;
+            },
+            ItemState::Cancelled => return false,
+        };
+        match removed {
+            Some(item) => {
+                items.remove(&id);
+                item.wq.cancelled.fetch_add(1, Ordering::Relaxed);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn state(&self, id: WorkId) -> Option<ItemState> {
+        self.items.lock().get(&id).map(|e| e.state)
+    }
+
+    /// Wait until `id` is neither pending nor running
+    ///
+    /// Pending work is run on the calling thread rather than waiting for
+    /// a worker. A periodic item counts as flushed once it is re-armed.
+    fn flush_work(&self, id: WorkId) {
+        loop {
+            match self.state(id) {
+                Some(ItemState::Pending) => {
+                    let Some(index) = self.items.lock().get(&id).map(|e| e.pool) else {
+                        continue;
+                    };
+                    let pool = self.pool(index);
+                    if let Some(item) = self.take(&pool, |i| i.id == id) {
+                        self.run(&pool, item);
+                    }
+                },
+                Some(ItemState::Running | ItemState::Cancelled) => core::hint::spin_loop(),
+                Some(ItemState::Delayed) | None => return,
+            }
+        }
+    }
+
+    /// Items of `wq` that are pending or running
+    fn active(&self, wq: &Workqueue) -> Vec<WorkId> {
+        self.items
+            .lock()
+            .iter()
+            .filter(|(_, e)| core::ptr::eq(e.wq, wq) && e.state != ItemState::Delayed)
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    // =========================================================================
+    // Accounting
+    // =========================================================================
+
+    /// Render `/proc/workqueues`
+    ///
+    /// Pools that never had a worker are omitted.
+    pub fn render_proc(&self, out: &mut dyn Write) -> fmt::Result {
+        writeln!(
+            out,
+            "pool                 workers  pending  delayed  running  executed  busy_ns"
+        )?;
+        for pool in self.pools.read().iter() {
+            let workers = pool.workers.lock().len();
+            if workers == 0 {
+                continue;
+            }
+            writeln!(
+                out,
+                "{:<20} {:<8} {:<8} {:<8} {:<8} {:<9} {}",
+                pool.name,
+                workers,
+                pool.ready.lock().len(),
+                pool.delayed.lock().len(),
+                pool.running.load(Ordering::Relaxed),
+                pool.executed.load(Ordering::Relaxed),
+                pool.busy_ns.load(Ordering::Relaxed)
+            )?;
+        }
+
+        writeln!(
+            out,
+            "\nworkqueue            pool     class       queued  executed  cancelled"
+        )?;
+        for wq in self.workqueues.read().iter() {
+            writeln!(
+                out,
+                "{:<20} {:<8} {:<11} {:<7} {:<9} {}",
+                wq.name,
+                if wq.unbound { "unbound" } else { "bound" },
+                wq.class.as_str(),
+                wq.queued.load(Ordering::Relaxed),
+                wq.executed.load(Ordering::Relaxed),
+                wq.cancelled.load(Ordering::Relaxed)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for KworkerPools {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global worker pools
+static KWORKER_POOLS: KworkerPools = KworkerPools::new();
+
+/// Get the worker pools
+pub fn kworkers() -> &'static KworkerPools {
+    &KWORKER_POOLS
+}
+
+// =============================================================================
+// Workqueues
+// =============================================================================
+
+/// A named stream of work for one class of pools
+///
+/// Declare one as a `static` (or leak it) and queue work on it; it shows
+/// up in `/proc/workqueues` once it has queued something.
+pub struct Workqueue {
+    name: &'static str,
+    class: WorkClass,
+    unbound: bool,
+    registered: AtomicBool,
+    queued: AtomicU64,
+    executed: AtomicU64,
+    cancelled: AtomicU64,
+}
+
+impl Workqueue {
+    /// Workqueue whose work runs on the CPU it is queued for
+    pub const fn bound(name: &'static str, class: WorkClass) -> Self {
+        Self::new(name, class, false)
+    }
+
+    /// Workqueue whose work runs on any housekeeping CPU
+    pub const fn unbound(name: &'static str, class: WorkClass) -> Self {
+        Self::new(name, class, true)
+    }
+
+    const fn new(name: &'static str, class: WorkClass, unbound: bool) -> Self {
+        Self {
+            name,
+            class,
+            unbound,
+            registered: AtomicBool::new(false),
+            queued: AtomicU64::new(0),
+            executed: AtomicU64::new(0),
+            cancelled: AtomicU64::new(0),
+        }
+    }
+
+    /// Workqueue name
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Priority class
+    pub fn class(&self) -> WorkClass {
+        self.class
+    }
+
+    fn submit(&'static self, cpu: Option<usize>, delay: u64, job: Job) -> ExecResult<WorkId> {
+        let pools = kworkers();
+        let cpu = match (self.unbound, cpu) {
+            (true, _) => None,
+            (false, Some(cpu)) => Some(cpu),
+            (false, None) => Some(pools.current_cpu()),
+        };
+        let pool = pools.pool_index(self.class, cpu)?;
+        pools.register(self);
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        let item = Item {
+            id: pools.alloc_id(),
+            wq: self,
+            job,
+        };
+        Ok(pools.enqueue(pool, delay, item))
+    }
+
+    /// Queue work to run as soon as possible (on the current CPU if bound)
+    pub fn queue(&'static self, work: impl FnOnce() + Send + 'static) -> ExecResult<WorkId> {
+        self.submit(None, 0, Job::Once(Box::new(work)))
+    }
+
+    /// Queue work on `cpu` (unbound queues ignore the CPU)
+    pub fn queue_on(
+        &'static self,
+        cpu: usize,
+        work: impl FnOnce() + Send + 'static,
+    ) -> ExecResult<WorkId> {
+        self.submit(Some(cpu), 0, Job::Once(Box::new(work)))
+    }
+
+    /// Queue work to run once `delay` ticks have passed
+    pub fn queue_delayed(
+        &'static self,
+        delay: u64,
+        work: impl FnOnce() + Send + 'static,
+    ) -> ExecResult<WorkId> {
+        self.submit(None, delay, Job::Once(Box::new(work)))
+    }
+
+    /// Run `work` every `period` ticks, first after one period, until
+    /// cancelled
+    pub fn queue_periodic(
+        &'static self,
+        period: u64,
+        work: impl FnMut() + Send + 'static,
+    ) -> ExecResult<WorkId> {
+        if period == 0 {
+            return Err(ExecError::InvalidArgument);
+        }
+        self.submit(None, period, Job::Periodic {
+            period,
+            func: Box::new(work),
+        })
+    }
+
+    /// Cancel work that has not started, or stop a periodic item
+    ///
+    /// Returns `true` if a future run was prevented. A run in progress
+    /// continues; use [`Workqueue::cancel_sync`] to wait for it.
+    pub fn cancel(&self, id: WorkId) -> bool {
+        kworkers().cancel(id)
+    }
+
+    /// Cancel `id` and wait for a run in progress to finish
+    pub fn cancel_sync(&self, id: WorkId) -> bool {
+        let cancelled = kworkers().cancel(id);
+        while kworkers().state(id).is_some() {
+            core::hint::spin_loop();
+        }
+        cancelled
+    }
+
+    /// Wait until `id` has run (a periodic item: its current run)
+    pub fn flush_work(&self, id: WorkId) {
+        kworkers().flush_work(id);
+    }
+
+    /// Wait until all work queued so far has run; delayed work is not
+    /// waited for
+    pub fn flush(&self) {
+        for id in kworkers().active(self) {
+            kworkers().flush_work(id);
+        }
+    }
+}
+
+/// System-wide workqueue, for work with no queue of its own
+static SYSTEM_WQ: Workqueue = Workqueue::bound("events", WorkClass::Normal);
+
+/// Get the system workqueue
+pub fn system_wq() -> &'static Workqueue {
+    &SYSTEM_WQ
+}
@@ -6,6 +6,11 @@
 //! - Scheduler framework
 //! - Context switching
 //! - Execution domains
+//! - Interrupt bottom halves (softirqs, threaded IRQs, workqueues)
+//! - Priority-inheritance locks
+//! - CPU isolation and tickless (nohz_full) CPUs
+//! - Shared kernel worker pools (kworkers)
+//! - Stop-machine for cross-CPU critical updates
 //!
 //! ## Key Principle
 //!
@@ -24,6 +29,11 @@
 pub mod context;
 pub mod thread;
 pub mod process;
+pub mod irq;
+pub mod sync;
+pub mod isolation;
+pub mod kworker;
+pub mod stop_machine;
 
 use core::sync::atomic::{AtomicU64, Ordering};
 
@@ -43,6 +53,11 @@ pub const fn idle() -> Self {
         Self(0)
     }
 
+    /// Thread ID from its raw value (e.g. passed in by userspace)
+    pub const fn from_raw(id: u64) -> Self {
+        Self(id)
+    }
+
     /// Get the raw ID value
     pub fn as_u64(self) -> u64 {
         self.0
@@ -71,6 +86,11 @@ pub const fn kernel() -> Self {
         Self(0)
     }
 
+    /// Process ID from its raw value (e.g. passed in by userspace)
+    pub const fn from_raw(id: u64) -> Self {
+        Self(id)
+    }
+
     /// Get the raw ID value
     pub fn as_u64(self) -> u64 {
         self.0
@@ -103,6 +123,10 @@ pub enum ExecError {
     PermissionDenied,
     /// Invalid argument
     InvalidArgument,
+    /// Operation would deadlock
+    Deadlock,
+    /// Operation timed out
+    Timeout,
     /// Internal error
     Internal,
 }
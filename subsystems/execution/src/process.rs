@@ -1,6 +1,13 @@
 //! # Process Management
 //!
 //! Process abstraction and management.
+//!
+//! Each process accumulates its resource usage ([`Rusage`]): CPU time and
+//! context switches from the scheduler's accounting, plus peak resident
+//! set size, page faults and block I/O recorded by the memory and block
+//! layers. When a parent reaps a zombie child, the child's usage and that
+//! of the children it reaped are added to the parent's children usage,
+//! which is what `getrusage(RUSAGE_CHILDREN)` and `wait4` report.
 
 use crate::{ThreadId, ProcessId, ExecResult, ExecError};
 use alloc::collections::BTreeMap;
@@ -8,7 +15,7 @@
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use spin::RwLock;
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 /// Process state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,6 +32,68 @@ pub enum ProcessState {
     Dead,
 }
 
+/// Resource usage of a process, its reaped children or a thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rusage {
+    /// User time (ns)
+    pub utime: u64,
+    /// System time (ns)
+    pub stime: u64,
+    /// Peak resident set size (KiB)
+    pub maxrss_kb: u64,
+    /// Page faults served without I/O
+    pub minflt: u64,
+    /// Page faults that required I/O
+    pub majflt: u64,
+    /// Blocks read
+    pub inblock: u64,
+    /// Blocks written
+    pub oublock: u64,
+    /// Voluntary context switches
+    pub nvcsw: u64,
+    /// Involuntary context switches
+    pub nivcsw: u64,
+}
+
+impl Rusage {
+    /// Add another sample into this one; the peak RSS is the larger one
+    pub fn merge(&mut self, other: &Rusage) {
+        self.utime += other.utime;
+        self.stime += other.stime;
+        self.maxrss_kb = self.maxrss_kb.max(other.maxrss_kb);
+        self.minflt += other.minflt;
+        self.majflt += other.majflt;
+        self.inblock += other.inblock;
+        self.oublock += other.oublock;
+        self.nvcsw += other.nvcsw;
+        self.nivcsw += other.nivcsw;
+    }
+
+    /// Usage of a single thread (CPU time and context switches only)
+    pub fn of_thread(id: ThreadId) -> Option<Self> {
+        let cputime = crate::scheduler::framework().cputime();
+        let times = cputime.thread_times(id)?;
+        let switches = cputime.thread_switches(id).unwrap_or_default();
+        Some(Self {
+            utime: times.user,
+            stime: times.system,
+            nvcsw: switches.voluntary,
+            nivcsw: switches.involuntary,
+            ..Self::default()
+        })
+    }
+}
+
+/// Resource counters recorded by the memory and block layers
+#[derive(Default)]
+struct ResourceCounters {
+    maxrss_kb: AtomicU64,
+    minflt: AtomicU64,
+    majflt: AtomicU64,
+    inblock: AtomicU64,
+    oublock: AtomicU64,
+}
+
 /// Process structure
 pub struct Process {
     /// Process ID
@@ -47,6 +116,10 @@ pub struct Process {
     uid: u32,
     /// Group ID
     gid: u32,
+    /// Resource counters of this process
+    counters: ResourceCounters,
+    /// Usage of reaped children
+    children_usage: RwLock<Rusage>,
 }
 
 impl Process {
@@ -63,6 +136,8 @@ pub fn new(id: ProcessId, parent: Option<ProcessId>, name: impl Into<String>) ->
             exit_code: RwLock::new(None),
             uid: 0,
             gid: 0,
+            counters: ResourceCounters::default(),
+            children_usage: RwLock::new(Rusage::default()),
         }
     }
 
@@ -140,11 +215,84 @@ pub fn remove_child(&self, child: ProcessId) {
         self.children.write().retain(|&c| c != child);
     }
 
+    /// Get child processes
+    pub fn children(&self) -> Vec<ProcessId> {
+        self.children.read().clone()
+    }
+
     /// Exit the process
     pub fn exit(&self, code: i32) {
         *self.exit_code.write() = Some(code);
         self.set_state(ProcessState::Zombie);
     }
+
+    /// Exit code, once the process has exited
+    pub fn exit_code(&self) -> Option<i32> {
+        *self.exit_code.read()
+    }
+
+    /// Record a page fault
+    pub fn record_page_fault(&self, major: bool) {
+        let counter = if major { &self.counters.majflt } else { &self.counters.minflt };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the current resident set size, keeping the peak
+    pub fn record_rss(&self, kb: u64) {
+        self.counters.maxrss_kb.fetch_max(kb, Ordering::Relaxed);
+    }
+
+    /// Record blocks read from and written to storage
+    pub fn record_block_io(&self, reads: u64, writes: u64) {
+        self.counters.inblock.fetch_add(reads, Ordering::Relaxed);
+        self.counters.oublock.fetch_add(writes, Ordering::Relaxed);
+    }
+
+    /// Resource usage of this process (live and exited threads)
+    pub fn usage(&self) -> Rusage {
+        let cputime = crate::scheduler::framework().cputime();
+        let times = cputime.process_times(self.id);
+        let switches = cputime.process_switches(self.id);
+        let counter = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        Rusage {
+            utime: times.user,
+            stime: times.system,
+            maxrss_kb: counter(&self.counters.maxrss_kb),
+            minflt: counter(&self.counters.minflt),
+            majflt: counter(&self.counters.majflt),
+            inblock: counter(&self.counters.inblock),
+            oublock: counter(&self.counters.oublock),
+            nvcsw: switches.voluntary,
+            nivcsw: switches.involuntary,
+        }
+    }
+
+    /// Resource usage of the children this process has reaped
+    pub fn children_usage(&self) -> Rusage {
+        *self.children_usage.read()
+    }
+
+    /// Reap a zombie child: returns its exit code and resource usage
+    ///
+    /// The child's usage, including its own reaped children, is added to
+    /// this process's children usage, and the child becomes dead.
+    pub fn reap_child(&self, child: &Process) -> ExecResult<(i32, Rusage)> {
+        if child.parent != Some(self.id) {
+            return Err(ExecError::ProcessNotFound);
+        }
+        let code = match (child.state(), child.exit_code()) {
+            (ProcessState::Zombie, Some(code)) => code,
+            _ => return Err(ExecError::InvalidState),
+        };
+
+        let mut usage = child.usage();
+        usage.merge(&child.children_usage());
+        self.children_usage.write().merge(&usage);
+        self.remove_child(child.id);
+        crate::scheduler::framework().cputime().reap_process(child.id);
+        child.set_state(ProcessState::Dead);
+        Ok((code, usage))
+    }
 }
 
 /// Process registry
@@ -0,0 +1,498 @@
+//! # CPU Time Accounting
+//!
+//! Where each CPU spends its time, and how much CPU time each thread and
+//! process has consumed:
+//!
+//! - **Per CPU**: user, system, hard IRQ, softirq and idle time
+//! - **Per thread**: user and system time, kept with the thread rather
+//!   than the CPU so it survives migrations
+//! - **Per process**: its live threads plus those that have exited
+//!
+//! Context switches are counted per thread alongside its time: a switch
+//! away from a thread that blocked (see [`CpuTimeAccounting::mark_blocked`])
+//! is voluntary, any other switch away from it is a preemption.
+//!
+//! Every CPU is in one [`CpuMode`] at a time. Mode changes (syscall entry
+//! and exit, IRQ entry and exit) and context switches charge the time
+//! since the previous transition to the old mode and, for user and system
+//! time, to the thread that was running. Reads add the in-flight time of
+//! running threads, so `clock_gettime(CLOCK_THREAD_CPUTIME_ID)` is exact.
+//!
+//! Timestamps come from a nanosecond clock installed with
+//! [`CpuTimeAccounting::set_clock`]; until one is installed, nothing is
+//! charged.
+//!
+//! The per-CPU times are rendered at `/proc/stat` in Linux's format, in
+//! [`USER_HZ`] ticks.
+
+use crate::{ProcessId, ThreadId};
+use alloc::collections::BTreeMap;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use spin::RwLock;
+
+/// Maximum number of CPUs accounted
+pub const MAX_CPUS: usize = crate::irq::MAX_CPUS;
+
+/// Tick rate of `/proc/stat` values
+pub const USER_HZ: u64 = 100;
+
+/// File name under `/proc`
+pub const PROC_PATH: &str = "stat";
+
+/// What a CPU is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CpuMode {
+    /// Running user code
+    User = 0,
+    /// Running kernel code on behalf of a thread
+    System = 1,
+    /// Running a hard IRQ handler
+    Irq = 2,
+    /// Running softirqs
+    Softirq = 3,
+    /// Idle
+    Idle = 4,
+}
+
+impl CpuMode {
+    /// Number of modes
+    const COUNT: usize = 5;
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::User,
+            1 => Self::System,
+            2 => Self::Irq,
+            3 => Self::Softirq,
+            _ => Self::Idle,
+        }
+    }
+}
+
+/// CPU time split by mode (nanoseconds)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    /// User time
+    pub user: u64,
+    /// System time
+    pub system: u64,
+    /// Hard IRQ time
+    pub irq: u64,
+    /// Softirq time
+    pub softirq: u64,
+    /// Idle time
+    pub idle: u64,
+}
+
+impl CpuTimes {
+    /// Time charged to threads (user plus system)
+    pub fn total(&self) -> u64 {
+        self.user + self.system
+    }
+
+    /// Add another sample into this one
+    pub fn merge(&mut self, other: &CpuTimes) {
+        self.user += other.user;
+        self.system += other.system;
+        self.irq += other.irq;
+        self.softirq += other.softirq;
+        self.idle += other.idle;
+    }
+}
+
+/// Context switches away from a thread or process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextSwitches {
+    /// Switches because the thread blocked
+    pub voluntary: u64,
+    /// Switches because the thread was preempted
+    pub involuntary: u64,
+}
+
+impl ContextSwitches {
+    /// Add another sample into this one
+    pub fn merge(&mut self, other: &ContextSwitches) {
+        self.voluntary += other.voluntary;
+        self.involuntary += other.involuntary;
+    }
+}
+
+/// Accounting state of one CPU
+struct CpuAccount {
+    /// Current [`CpuMode`]
+    mode: AtomicU8,
+    /// Clock value at the last transition
+    since: AtomicU64,
+    /// Running thread ID plus one (0 = none)
+    thread: AtomicU64,
+    /// Nanoseconds per mode
+    times: [AtomicU64; CpuMode::COUNT],
+    /// Mode an interrupt arrived in
+    interrupted: AtomicU8,
+    /// Has the CPU reported a transition yet?
+    online: AtomicBool,
+}
+
+impl CpuAccount {
+    const fn new() -> Self {
+        Self {
+            mode: AtomicU8::new(CpuMode::Idle as u8),
+            since: AtomicU64::new(0),
+            thread: AtomicU64::new(0),
+            times: [const { AtomicU64::new(0) }; CpuMode::COUNT],
+            interrupted: AtomicU8::new(CpuMode::Idle as u8),
+            online: AtomicBool::new(false),
+        }
+    }
+
+    fn snapshot(&self) -> CpuTimes {
+        let time = |mode: CpuMode| self.times[mode as usize].load(Ordering::Relaxed);
+        CpuTimes {
+            user: time(CpuMode::User),
+            system: time(CpuMode::System),
+            irq: time(CpuMode::Irq),
+            softirq: time(CpuMode::Softirq),
+            idle: time(CpuMode::Idle),
+        }
+    }
+}
+
+/// CPU time of one thread
+struct ThreadAccount {
+    process: ProcessId,
+    user: AtomicU64,
+    system: AtomicU64,
+    /// Blocked since it last ran; the next switch away is voluntary
+    blocked: AtomicBool,
+    voluntary: AtomicU64,
+    involuntary: AtomicU64,
+}
+
+impl ThreadAccount {
+    fn switches(&self) -> ContextSwitches {
+        ContextSwitches {
+            voluntary: self.voluntary.load(Ordering::Relaxed),
+            involuntary: self.involuntary.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Time and switches of a process's exited threads
+type Exited = (CpuTimes, ContextSwitches);
+
+/// CPU time accounting for all CPUs, threads and processes
+pub struct CpuTimeAccounting {
+    clock: spin::Once<fn() -> u64>,
+    cpus: [CpuAccount; MAX_CPUS],
+    threads: RwLock<BTreeMap<ThreadId, ThreadAccount>>,
+    /// Time of exited threads, per process
+    exited: RwLock<BTreeMap<ProcessId, Exited>>,
+    /// Threads registered since boot
+    created: AtomicU64,
+}
+
+impl CpuTimeAccounting {
+    /// Create accounting without a clock
+    pub const fn new() -> Self {
+        Self {
+            clock: spin::Once::new(),
+            cpus: [const { CpuAccount::new() }; MAX_CPUS],
+            threads: RwLock::new(BTreeMap::new()),
+            exited: RwLock::new(BTreeMap::new()),
+            created: AtomicU64::new(0),
+        }
+    }
+
+    /// Install the nanosecond clock (first call wins)
+    pub fn set_clock(&self, clock: fn() -> u64) {
+        self.clock.call_once(|| clock);
+    }
+
+    /// Current clock value, if a clock is installed
+    pub fn now(&self) -> Option<u64> {
+        self.clock.get().map(|clock| clock())
+    }
+
+    /// Start accounting a thread
+    pub fn register_thread(&self, id: ThreadId, process: ProcessId) {
+        self.threads.write().insert(id, ThreadAccount {
+            process,
+            user: AtomicU64::new(0),
+            system: AtomicU64::new(0),
+            blocked: AtomicBool::new(false),
+            voluntary: AtomicU64::new(0),
+            involuntary: AtomicU64::new(0),
+        });
+        self.created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Stop accounting a thread, keeping its time in its process
+    pub fn exit_thread(&self, id: ThreadId) {
+        let Some(times) = self.thread_times(id) else {
+            return;
+        };
+        if let Some(account) = self.threads.write().remove(&id) {
+            let mut exited = self.exited.write();
+            let (exited_times, exited_switches) = exited.entry(account.process).or_default();
+            exited_times.merge(&times);
+            exited_switches.merge(&account.switches());
+        }
+    }
+
+    /// `id` is blocking: the next switch away from it is voluntary
+    pub fn mark_blocked(&self, id: ThreadId) {
+        if let Some(account) = self.threads.read().get(&id) {
+            account.blocked.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Count a switch away from the thread `raw` (ID plus one)
+    ///
+    /// A thread picked again (`switched` is false) woke before it was
+    /// switched out, so it is no longer blocked.
+    fn count_switch(&self, raw: u64, switched: bool) {
+        if raw == 0 {
+            return;
+        }
+        if let Some(account) = self.threads.read().get(&ThreadId::from_raw(raw - 1)) {
+            let blocked = account.blocked.swap(false, Ordering::Relaxed);
+            if !switched {
+                return;
+            }
+            if blocked {
+                account.voluntary.fetch_add(1, Ordering::Relaxed);
+            } else {
+                account.involuntary.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Forget the time of a reaped process's exited threads
+    pub fn reap_process(&self, process: ProcessId) {
+        self.exited.write().remove(&process);
+    }
+
+    /// Charge the time since the last transition on `cpu`
+    ///
+    /// A CPU's first transition brings it online; time before that is not
+    /// accounted.
+    fn charge(&self, cpu: &CpuAccount, now: u64) {
+        if !cpu.online.load(Ordering::Relaxed) {
+            return;
+        }
+        let delta = now.saturating_sub(cpu.since.swap(now, Ordering::Relaxed));
+        let mode = CpuMode::from_u8(cpu.mode.load(Ordering::Relaxed));
+        cpu.times[mode as usize].fetch_add(delta, Ordering::Relaxed);
+
+        let thread = cpu.thread.load(Ordering::Relaxed);
+        if thread == 0 {
+            return;
+        }
+        if let Some(account) = self.threads.read().get(&ThreadId::from_raw(thread - 1)) {
+            match mode {
+                CpuMode::User => account.user.fetch_add(delta, Ordering::Relaxed),
+                CpuMode::System => account.system.fetch_add(delta, Ordering::Relaxed),
+                _ => 0,
+            };
+        }
+    }
+
+    /// Charge the time of the mode being left, bringing the CPU online
+    fn transition(&self, cpu: &CpuAccount, now: u64) {
+        if cpu.online.swap(true, Ordering::Relaxed) {
+            self.charge(cpu, now);
+        } else {
+            cpu.since.store(now, Ordering::Relaxed);
+        }
+    }
+
+    /// `cpu` switched to `thread` (`None` = idle)
+    ///
+    /// The incoming thread starts in system mode; the return to user
+    /// space is a [`Self::set_mode`] to [`CpuMode::User`].
+    pub fn switch_to(&self, cpu: usize, thread: Option<ThreadId>) {
+        let (Some(account), Some(now)) = (self.cpus.get(cpu), self.now()) else {
+            return;
+        };
+        self.transition(account, now);
+        let (raw, mode) = match thread {
+            Some(id) => (id.as_u64() + 1, CpuMode::System),
+            None => (0, CpuMode::Idle),
+        };
+        let previous = account.thread.swap(raw, Ordering::Relaxed);
+        self.count_switch(previous, previous != raw);
+        account.mode.store(mode as u8, Ordering::Relaxed);
+    }
+
+    /// Switch `cpu` to `mode`; returns the previous mode
+    ///
+    /// Syscall entry and exit switch between user and system mode; IRQ
+    /// entry switches to [`CpuMode::Irq`] and exit restores the returned
+    /// mode.
+    pub fn set_mode(&self, cpu: usize, mode: CpuMode) -> CpuMode {
+        let Some(account) = self.cpus.get(cpu) else {
+            return mode;
+        };
+        if let Some(now) = self.now() {
+            self.transition(account, now);
+        }
+        CpuMode::from_u8(account.mode.swap(mode as u8, Ordering::Relaxed))
+    }
+
+    /// Enter interrupt context (`Irq` or `Softirq`) on `cpu`
+    ///
+    /// The mode the interrupt arrived in is restored by
+    /// [`Self::exit_interrupt`]; nested entries keep the outermost one.
+    pub fn enter_interrupt(&self, cpu: usize, mode: CpuMode) {
+        let previous = self.set_mode(cpu, mode);
+        if let Some(account) = self.cpus.get(cpu) {
+            if !matches!(previous, CpuMode::Irq | CpuMode::Softirq) {
+                account.interrupted.store(previous as u8, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Leave interrupt context on `cpu`
+    pub fn exit_interrupt(&self, cpu: usize) {
+        if let Some(account) = self.cpus.get(cpu) {
+            let mode = CpuMode::from_u8(account.interrupted.load(Ordering::Relaxed));
+            self.set_mode(cpu, mode);
+        }
+    }
+
+    /// Current mode of `cpu`
+    pub fn mode(&self, cpu: usize) -> Option<CpuMode> {
+        self.cpus.get(cpu).map(|c| CpuMode::from_u8(c.mode.load(Ordering::Relaxed)))
+    }
+
+    /// User and system time of a thread, including the running slice
+    pub fn thread_times(&self, id: ThreadId) -> Option<CpuTimes> {
+        let threads = self.threads.read();
+        let account = threads.get(&id)?;
+        let mut times = CpuTimes {
+            user: account.user.load(Ordering::Relaxed),
+            system: account.system.load(Ordering::Relaxed),
+            ..CpuTimes::default()
+        };
+
+        let now = self.now().unwrap_or(0);
+        for cpu in &self.cpus {
+            if cpu.thread.load(Ordering::Relaxed) != id.as_u64() + 1 {
+                continue;
+            }
+            let running = now.saturating_sub(cpu.since.load(Ordering::Relaxed));
+            match CpuMode::from_u8(cpu.mode.load(Ordering::Relaxed)) {
+                CpuMode::User => times.user += running,
+                CpuMode::System => times.system += running,
+                _ => {}
+            }
+        }
+        Some(times)
+    }
+
+    /// Context switches away from a thread
+    pub fn thread_switches(&self, id: ThreadId) -> Option<ContextSwitches> {
+        self.threads.read().get(&id).map(ThreadAccount::switches)
+    }
+
+    /// Context switches of a process: live threads plus exited ones
+    pub fn process_switches(&self, process: ProcessId) -> ContextSwitches {
+        let mut switches = self.exited.read().get(&process).map(|e| e.1).unwrap_or_default();
+        for account in self.threads.read().values().filter(|a| a.process == process) {
+            switches.merge(&account.switches());
+        }
+        switches
+    }
+
+    /// Process a thread belongs to
+    pub fn process_of(&self, id: ThreadId) -> Option<ProcessId> {
+        self.threads.read().get(&id).map(|a| a.process)
+    }
+
+    /// User and system time of a process: live threads plus exited ones
+    pub fn process_times(&self, process: ProcessId) -> CpuTimes {
+        let mut times = self.exited.read().get(&process).map(|e| e.0).unwrap_or_default();
+        let threads: alloc::vec::Vec<ThreadId> = self
+            .threads
+            .read()
+            .iter()
+            .filter(|(_, a)| a.process == process)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in threads {
+            if let Some(thread) = self.thread_times(id) {
+                times.merge(&thread);
+            }
+        }
+        times
+    }
+
+    /// Times of one CPU, including the current slice
+    pub fn cpu_times(&self, cpu: usize) -> Option<CpuTimes> {
+        let account = self.cpus.get(cpu)?;
+        if let Some(now) = self.now() {
+            self.charge(account, now);
+        }
+        Some(account.snapshot())
+    }
+
+    /// Render `/proc/stat`
+    ///
+    /// CPUs that are not online are omitted. `context_switches` comes from the
+    /// scheduler metrics and `boot_time` is the boot time in seconds since
+    /// the epoch.
+    pub fn render_proc(&self, out: &mut dyn Write, context_switches: u64, boot_time: u64) -> fmt::Result {
+        let mut rows = alloc::vec::Vec::new();
+        let mut all = CpuTimes::default();
+        for (cpu, account) in self.cpus.iter().enumerate() {
+            if !account.online.load(Ordering::Relaxed) {
+                continue;
+            }
+            let times = self.cpu_times(cpu).unwrap_or_default();
+            all.merge(&times);
+            rows.push((cpu, times));
+        }
+
+        render_row(out, "cpu ", &all)?;
+        for (cpu, times) in rows {
+            let mut name = alloc::string::String::new();
+            write!(name, "cpu{}", cpu)?;
+            render_row(out, &name, &times)?;
+        }
+
+        let running = self.cpus.iter().filter(|c| c.thread.load(Ordering::Relaxed) != 0).count();
+        writeln!(out, "ctxt {}", context_switches)?;
+        writeln!(out, "btime {}", boot_time)?;
+        writeln!(out, "processes {}", self.created.load(Ordering::Relaxed))?;
+        writeln!(out, "procs_running {}", running)?;
+        writeln!(out, "procs_blocked 0")
+    }
+}
+
+impl Default for CpuTimeAccounting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Nanoseconds to [`USER_HZ`] ticks
+fn ticks(ns: u64) -> u64 {
+    ns / (1_000_000_000 / USER_HZ)
+}
+
+/// One `/proc/stat` CPU line: user nice system idle iowait irq softirq
+/// steal guest guest_nice
+fn render_row(out: &mut dyn Write, name: &str, times: &CpuTimes) -> fmt::Result {
+    writeln!(
+        out,
+        "{} {} 0 {} {} 0 {} {} 0 0 0",
+        name,
+        ticks(times.user),
+        ticks(times.system),
+        ticks(times.idle),
+        ticks(times.irq),
+        ticks(times.softirq)
+    )
+}
@@ -7,8 +7,13 @@
 pub mod queue;
 pub mod priority;
 pub mod metrics;
+pub mod trace;
+pub mod cputime;
 
 use crate::{ThreadId, ExecResult, ExecError};
+use crate::isolation::isolation;
+use helix_hal::topology::{topology, CoreType};
+use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use spin::RwLock;
 
@@ -24,8 +29,18 @@ pub struct SchedulerFramework {
     scheduler: RwLock<Option<Arc<dyn Scheduler>>>,
     /// Scheduler metrics
     metrics: metrics::SchedulerMetrics,
+    /// Latency and runqueue tracing
+    trace: trace::SchedTrace,
+    /// CPU time accounting
+    cputime: cputime::CpuTimeAccounting,
     /// Load balancer (for SMP)
     load_balancer: RwLock<Option<Arc<dyn LoadBalancer>>>,
+    /// Energy-aware placement advisor (for hybrid CPUs)
+    placement_advisor: RwLock<Option<Arc<dyn PlacementAdvisor>>>,
+    /// Priority-inheritance boosts: thread -> (base priority, boost)
+    boosts: RwLock<BTreeMap<ThreadId, (Priority, PiBoost)>>,
+    /// Placement classes other than `Normal`
+    placements: RwLock<BTreeMap<ThreadId, PlacementClass>>,
 }
 
 impl SchedulerFramework {
@@ -34,7 +49,12 @@ pub const fn new() -> Self {
         Self {
             scheduler: RwLock::new(None),
             metrics: metrics::SchedulerMetrics::new(),
+            trace: trace::SchedTrace::new(),
+            cputime: cputime::CpuTimeAccounting::new(),
             load_balancer: RwLock::new(None),
+            placement_advisor: RwLock::new(None),
+            boosts: RwLock::new(BTreeMap::new()),
+            placements: RwLock::new(BTreeMap::new()),
         }
     }
 
@@ -54,22 +74,113 @@ pub fn set_load_balancer(&self, balancer: Arc<dyn LoadBalancer>) {
         *self.load_balancer.write() = Some(balancer);
     }
 
+    /// Set the energy-aware placement advisor
+    pub fn set_placement_advisor(&self, advisor: Arc<dyn PlacementAdvisor>) {
+        *self.placement_advisor.write() = Some(advisor);
+    }
+
+    /// CPUs a thread should preferably run on, within its affinity
+    ///
+    /// Real-time threads are placed as latency-sensitive.
+    pub fn preferred_cpus(&self, thread: &SchedulableThread) -> u64 {
+        let class = if thread.is_realtime {
+            PlacementClass::LatencySensitive
+        } else {
+            thread.placement
+        };
+        self.placement_mask(class, thread.affinity)
+    }
+
+    /// CPUs preferred for `class` within `affinity`
+    ///
+    /// Asks the advisor, or else sends background work to efficiency cores
+    /// and latency-sensitive work to performance cores of a hybrid
+    /// topology. Falls back to the affinity when there is no preference.
+    pub fn placement_mask(&self, class: PlacementClass, affinity: u64) -> u64 {
+        let preferred = match self.placement_advisor.read().as_ref() {
+            Some(advisor) => advisor.preferred_cpus(class, affinity),
+            None if topology().is_hybrid() => match class {
+                PlacementClass::Background => topology().mask(CoreType::Efficiency),
+                PlacementClass::LatencySensitive => topology().mask(CoreType::Performance),
+                PlacementClass::Normal => affinity,
+            },
+            None => affinity,
+        } & affinity;
+        if preferred == 0 { affinity } else { preferred }
+    }
+
+    /// Set the scheduling attributes of a thread (`sched_setattr`)
+    pub fn set_attr(&self, id: ThreadId, attr: SchedAttr) -> ExecResult<()> {
+        if let Some(priority) = attr.priority {
+            self.set_priority(id, priority)?;
+        }
+
+        let scheduler = self.scheduler.read();
+        let scheduler = scheduler.as_ref().ok_or(ExecError::Internal)?;
+        if let Some(policy) = attr.policy {
+            scheduler.set_policy(id, policy)?;
+        }
+
+        let affinity = scheduler.affinity(id).unwrap_or(u64::MAX);
+        let preferred = self.placement_mask(attr.placement, affinity);
+        scheduler.set_placement(id, attr.placement, preferred)?;
+        let mut placements = self.placements.write();
+        match attr.placement {
+            PlacementClass::Normal => placements.remove(&id),
+            placement => placements.insert(id, placement),
+        };
+        Ok(())
+    }
+
+    /// Scheduling attributes of a thread (`sched_getattr`)
+    ///
+    /// The policy is not reported back by schedulers and is left `None`.
+    pub fn get_attr(&self, id: ThreadId) -> Option<SchedAttr> {
+        let priority = self.base_priority(id)?;
+        Some(SchedAttr {
+            policy: None,
+            priority: Some(priority),
+            placement: self.placements.read().get(&id).copied().unwrap_or_default(),
+        })
+    }
+
     /// Pick the next thread to run
     pub fn pick_next(&self, cpu: usize) -> Option<ThreadId> {
         let scheduler = self.scheduler.read();
-        scheduler.as_ref()?.pick_next(cpu)
+        let scheduler = scheduler.as_ref()?;
+        let next = scheduler.pick_next(cpu);
+        self.cputime.switch_to(cpu, next);
+        let next = next?;
+        let depth = scheduler.runqueue_len(cpu);
+        self.trace.record_pick(cpu, next, depth);
+        isolation().update_tick(cpu, depth.map_or(usize::MAX, |d| d + 1));
+        Some(next)
     }
 
     /// Add a thread to the scheduler
-    pub fn add_thread(&self, thread: SchedulableThread) -> ExecResult<()> {
+    ///
+    /// Unbound threads are confined to the housekeeping CPUs, and their
+    /// preferred CPUs are filled in by the placement advisor.
+    pub fn add_thread(&self, mut thread: SchedulableThread) -> ExecResult<()> {
+        thread.affinity = isolation().unbound_affinity(thread.affinity);
+        thread.preferred = self.preferred_cpus(&thread);
+        let (id, process, placement) = (thread.id, thread.process, thread.placement);
         let scheduler = self.scheduler.read();
         scheduler.as_ref()
             .ok_or(ExecError::Internal)?
-            .add_thread(thread)
+            .add_thread(thread)?;
+        self.cputime.register_thread(id, process);
+        if placement != PlacementClass::Normal {
+            self.placements.write().insert(id, placement);
+        }
+        Ok(())
     }
 
     /// Remove a thread from the scheduler
     pub fn remove_thread(&self, id: ThreadId) -> ExecResult<()> {
+        self.boosts.write().remove(&id);
+        self.placements.write().remove(&id);
+        self.cputime.exit_thread(id);
         let scheduler = self.scheduler.read();
         scheduler.as_ref()
             .ok_or(ExecError::Internal)?
@@ -81,11 +192,14 @@ pub fn thread_ready(&self, id: ThreadId) -> ExecResult<()> {
         let scheduler = self.scheduler.read();
         scheduler.as_ref()
             .ok_or(ExecError::Internal)?
-            .thread_ready(id)
+            .thread_ready(id)?;
+        self.trace.record_wakeup(id);
+        Ok(())
     }
 
     /// Notify that a thread is blocking
     pub fn thread_block(&self, id: ThreadId) -> ExecResult<()> {
+        self.cputime.mark_blocked(id);
         let scheduler = self.scheduler.read();
         scheduler.as_ref()
             .ok_or(ExecError::Internal)?
@@ -96,6 +210,8 @@ pub fn thread_block(&self, id: ThreadId) -> ExecResult<()> {
     pub fn tick(&self, cpu: usize) {
         if let Some(scheduler) = self.scheduler.read().as_ref() {
             scheduler.tick(cpu);
+            let runnable = scheduler.runqueue_len(cpu).map_or(usize::MAX, |d| d + 1);
+            isolation().update_tick(cpu, runnable);
         }
         self.metrics.record_tick();
     }
@@ -108,11 +224,95 @@ pub fn yield_current(&self, cpu: usize) {
     }
 
     /// Update thread priority
+    ///
+    /// While the thread is boosted this changes its base priority; the
+    /// boost still applies if it is higher.
     pub fn set_priority(&self, id: ThreadId, priority: Priority) -> ExecResult<()> {
+        let scheduler = self.scheduler.read();
+        let scheduler = scheduler.as_ref().ok_or(ExecError::Internal)?;
+
+        let mut boosts = self.boosts.write();
+        match boosts.get_mut(&id) {
+            Some((base, boost)) => {
+                *base = priority;
+                scheduler.set_priority(id, priority.max(boost.priority))
+            },
+            None => scheduler.set_priority(id, priority),
+        }
+    }
+
+    /// Apply (`Some`) or clear (`None`) a priority-inheritance boost
+    ///
+    /// Called by PI-aware locks when the highest-priority waiter of a lock
+    /// holder changes. The thread runs at the higher of its base priority
+    /// and the boost, and with the boost's deadline if any.
+    pub fn pi_boost(&self, id: ThreadId, boost: Option<PiBoost>) -> ExecResult<()> {
+        let scheduler = self.scheduler.read();
+        let scheduler = scheduler.as_ref().ok_or(ExecError::Internal)?;
+
+        let mut boosts = self.boosts.write();
+        match boost {
+            Some(boost) => {
+                let base = match boosts.get(&id) {
+                    Some((base, _)) => *base,
+                    None => scheduler.get_priority(id).ok_or(ExecError::ThreadNotFound)?,
+                };
+                scheduler.set_priority(id, base.max(boost.priority))?;
+                scheduler.inherit_deadline(id, boost.deadline)?;
+                boosts.insert(id, (base, boost));
+            },
+            None => {
+                if let Some((base, _)) = boosts.remove(&id) {
+                    scheduler.set_priority(id, base)?;
+                    scheduler.inherit_deadline(id, None)?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Current boost of a thread
+    pub fn pi_boost_of(&self, id: ThreadId) -> Option<PiBoost> {
+        self.boosts.read().get(&id).map(|(_, boost)| *boost)
+    }
+
+    /// Priority the thread would run at without boosts
+    pub fn base_priority(&self, id: ThreadId) -> Option<Priority> {
+        if let Some((base, _)) = self.boosts.read().get(&id) {
+            return Some(*base);
+        }
+        self.scheduler.read().as_ref()?.get_priority(id)
+    }
+
+    /// Priority the thread currently runs at, boosts included
+    pub fn effective_priority(&self, id: ThreadId) -> Option<Priority> {
+        self.scheduler.read().as_ref()?.get_priority(id)
+    }
+
+    /// Deadline the thread currently runs with, inherited ones included
+    pub fn effective_deadline(&self, id: ThreadId) -> Option<u64> {
+        let own = self.scheduler.read().as_ref()?.deadline(id);
+        let inherited = self.pi_boost_of(id).and_then(|b| b.deadline);
+        match (own, inherited) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Move a thread from `from` to `to`
+    ///
+    /// Balancing onto an isolated CPU is refused.
+    pub fn migrate_thread(&self, id: ThreadId, from: usize, to: usize) -> ExecResult<()> {
+        if isolation().is_isolated(to) && !isolation().is_isolated(from) {
+            return Err(ExecError::PermissionDenied);
+        }
         let scheduler = self.scheduler.read();
         scheduler.as_ref()
             .ok_or(ExecError::Internal)?
-            .set_priority(id, priority)
+            .migrate_thread(id, to)?;
+        self.metrics.record_migration();
+        self.trace.record_migration(from, to);
+        Ok(())
     }
 
     /// Get scheduler metrics
@@ -120,6 +320,16 @@ pub fn metrics(&self) -> &metrics::SchedulerMetrics {
         &self.metrics
     }
 
+    /// Get scheduler tracing (`/proc/sched_stats`)
+    pub fn trace(&self) -> &trace::SchedTrace {
+        &self.trace
+    }
+
+    /// Get CPU time accounting (`/proc/stat`, CPU-time clocks)
+    pub fn cputime(&self) -> &cputime::CpuTimeAccounting {
+        &self.cputime
+    }
+
     /// Trigger load balancing
     pub fn balance_load(&self) {
         if let Some(balancer) = self.load_balancer.read().as_ref() {
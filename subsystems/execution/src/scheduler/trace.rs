@@ -0,0 +1,439 @@
+//! # Scheduler Tracing
+//!
+//! Per-CPU instrumentation collected by the scheduler framework:
+//!
+//! - **Wakeup latency**: time from `thread_ready` until the thread is
+//!   picked to run
+//! - **Runqueue depth**: queue length sampled at every pick
+//! - **Migrations**: threads moved onto / off each CPU
+//!
+//! Every record is a handful of relaxed atomic updates on the recording
+//! CPU's own histograms, so tracing stays on in production. Timestamps
+//! come from a clock installed with [`SchedTrace::set_clock`]; until one
+//! is installed, latency is not recorded.
+//!
+//! The statistics are rendered at `/proc/sched_stats`:
+//!
+//! ```text
+//! cpu  wakeups  lat_p50_ns  lat_p99_ns  lat_max_ns  rq_avg  rq_max  migr_in  migr_out
+//! 0    1520     1023        16383       20114         1.42  6       12       3
+//! all  1520     1023        16383       20114         1.42  6       12       3
+//! ```
+
+use crate::ThreadId;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Maximum number of CPUs traced
+pub const MAX_CPUS: usize = crate::irq::MAX_CPUS;
+
+/// Histogram buckets (bucket `i` holds values below `2^i`)
+pub const HISTOGRAM_BUCKETS: usize = 32;
+
+/// Pending wakeup slots (indexed by thread ID)
+const WAKE_SLOTS: usize = 256;
+
+/// File name under `/proc`
+pub const PROC_PATH: &str = "sched_stats";
+
+// =============================================================================
+// Histogram
+// =============================================================================
+
+/// Lock-free histogram with power-of-two buckets
+pub struct Histogram {
+    buckets: [AtomicU64; HISTOGRAM_BUCKETS],
+    count: AtomicU64,
+    sum: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Histogram {
+    /// Create an empty histogram
+    pub const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; HISTOGRAM_BUCKETS],
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    /// Bucket holding `value`
+    fn bucket(value: u64) -> usize {
+        ((u64::BITS - value.leading_zeros()) as usize).min(HISTOGRAM_BUCKETS - 1)
+    }
+
+    /// Record one value
+    pub fn record(&self, value: u64) {
+        self.buckets[Self::bucket(value)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value, Ordering::Relaxed);
+        self.max.fetch_max(value, Ordering::Relaxed);
+    }
+
+    /// Copy the current contents
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let mut snapshot = HistogramSnapshot::default();
+        for (dst, src) in snapshot.buckets.iter_mut().zip(&self.buckets) {
+            *dst = src.load(Ordering::Relaxed);
+        }
+        snapshot.count = self.count.load(Ordering::Relaxed);
+        snapshot.sum = self.sum.load(Ordering::Relaxed);
+        snapshot.max = self.max.load(Ordering::Relaxed);
+        snapshot
+    }
+
+    /// Clear all buckets
+    pub fn reset(&self) {
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+        self.count.store(0, Ordering::Relaxed);
+        self.sum.store(0, Ordering::Relaxed);
+        self.max.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Point-in-time copy of a [`Histogram`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HistogramSnapshot {
+    /// Per-bucket counts
+    pub buckets: [u64; HISTOGRAM_BUCKETS],
+    /// Values recorded
+    pub count: u64,
+    /// Sum of recorded values
+    pub sum: u64,
+    /// Largest recorded value
+    pub max: u64,
+}
+
+impl HistogramSnapshot {
+    /// Add another snapshot into this one
+    pub fn merge(&mut self, other: &HistogramSnapshot) {
+        for (dst, src) in self.buckets.iter_mut().zip(&other.buckets) {
+            *dst += src;
+        }
+        self.count += other.count;
+        self.sum = self.sum.saturating_add(other.sum);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Upper bound of the bucket containing the `percent`th percentile
+    ///
+    /// The result never exceeds the recorded maximum. Returns 0 when empty.
+    pub fn percentile(&self, percent: u8) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = (self.count * percent.min(100) as u64).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                let upper = if i == 0 { 0 } else { (1u64 << i) - 1 };
+                return upper.min(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Mean of recorded values, in hundredths
+    pub fn mean_centi(&self) -> u64 {
+        if self.count == 0 {
+            0
+        } else {
+            self.sum.saturating_mul(100) / self.count
+        }
+    }
+}
+
+// =============================================================================
+// Per-CPU Statistics
+// =============================================================================
+
+/// Statistics collected on one CPU
+pub struct CpuSchedStats {
+    /// Wakeup-to-run latency (ns)
+    pub wakeup_latency: Histogram,
+    /// Runqueue length at each pick
+    pub runqueue_depth: Histogram,
+    /// Threads migrated onto this CPU
+    pub migrations_in: AtomicU64,
+    /// Threads migrated off this CPU
+    pub migrations_out: AtomicU64,
+}
+
+impl CpuSchedStats {
+    const fn new() -> Self {
+        Self {
+            wakeup_latency: Histogram::new(),
+            runqueue_depth: Histogram::new(),
+            migrations_in: AtomicU64::new(0),
+            migrations_out: AtomicU64::new(0),
+        }
+    }
+
+    fn reset(&self) {
+        self.wakeup_latency.reset();
+        self.runqueue_depth.reset();
+        self.migrations_in.store(0, Ordering::Relaxed);
+        self.migrations_out.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Aggregated statistics, as consumed by the optimizer and benchmarks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SchedStatsSummary {
+    /// Wakeups measured
+    pub wakeups: u64,
+    /// Median wakeup latency (ns)
+    pub wakeup_p50_ns: u64,
+    /// 99th percentile wakeup latency (ns)
+    pub wakeup_p99_ns: u64,
+    /// Worst wakeup latency (ns)
+    pub wakeup_max_ns: u64,
+    /// Mean runqueue depth, in hundredths
+    pub runqueue_avg_centi: u64,
+    /// Deepest runqueue seen
+    pub runqueue_max: u64,
+    /// Migrations
+    pub migrations: u64,
+}
+
+/// Wakeup awaiting its first run
+struct WakeSlot {
+    /// Thread ID plus one (0 = free)
+    thread: AtomicU64,
+    /// Clock value at wakeup
+    stamp: AtomicU64,
+}
+
+// =============================================================================
+// Trace
+// =============================================================================
+
+/// Scheduler trace collector
+pub struct SchedTrace {
+    enabled: AtomicBool,
+    clock: spin::Once<fn() -> u64>,
+    cpus: [CpuSchedStats; MAX_CPUS],
+    pending: [WakeSlot; WAKE_SLOTS],
+}
+
+impl SchedTrace {
+    /// Create an enabled collector without a clock
+    pub const fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            clock: spin::Once::new(),
+            cpus: [const { CpuSchedStats::new() }; MAX_CPUS],
+            pending: [const {
+                WakeSlot {
+                    thread: AtomicU64::new(0),
+                    stamp: AtomicU64::new(0),
+                }
+            }; WAKE_SLOTS],
+        }
+    }
+
+    /// Install the nanosecond clock used for latency (first call wins)
+    pub fn set_clock(&self, clock: fn() -> u64) {
+        self.clock.call_once(|| clock);
+    }
+
+    /// Enable or disable collection
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Is collection enabled?
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn now(&self) -> Option<u64> {
+        if !self.is_enabled() {
+            return None;
+        }
+        self.clock.get().map(|clock| clock())
+    }
+
+    fn slot(&self, id: ThreadId) -> &WakeSlot {
+        &self.pending[id.as_u64() as usize % WAKE_SLOTS]
+    }
+
+    /// `id` became runnable
+    ///
+    /// A later wakeup of another thread hashing to the same slot replaces
+    /// this one, dropping its sample rather than mis-attributing it.
+    pub fn record_wakeup(&self, id: ThreadId) {
+        let Some(now) = self.now() else {
+            return;
+        };
+        let slot = self.slot(id);
+        slot.stamp.store(now, Ordering::Relaxed);
+        slot.thread.store(id.as_u64() + 1, Ordering::Release);
+    }
+
+    /// `id` was picked to run on `cpu` with `depth` threads still queued
+    pub fn record_pick(&self, cpu: usize, id: ThreadId, depth: Option<usize>) {
+        let Some(stats) = self.cpus.get(cpu) else {
+            return;
+        };
+        if !self.is_enabled() {
+            return;
+        }
+        if let Some(depth) = depth {
+            stats.runqueue_depth.record(depth as u64);
+        }
+
+        let slot = self.slot(id);
+        if slot
+            .thread
+            .compare_exchange(id.as_u64() + 1, 0, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+        {
+            let stamp = slot.stamp.load(Ordering::Relaxed);
+            if let Some(now) = self.now() {
+                stats.wakeup_latency.record(now.saturating_sub(stamp));
+            }
+        }
+    }
+
+    /// A thread moved from `from` to `to`
+    pub fn record_migration(&self, from: usize, to: usize) {
+        if !self.is_enabled() {
+            return;
+        }
+        if let Some(stats) = self.cpus.get(from) {
+            stats.migrations_out.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(stats) = self.cpus.get(to) {
+            stats.migrations_in.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Statistics for one CPU
+    pub fn cpu(&self, cpu: usize) -> Option<&CpuSchedStats> {
+        self.cpus.get(cpu)
+    }
+
+    /// Wakeup latency across all CPUs
+    pub fn wakeup_latency(&self) -> HistogramSnapshot {
+        let mut all = HistogramSnapshot::default();
+        for stats in &self.cpus {
+            all.merge(&stats.wakeup_latency.snapshot());
+        }
+        all
+    }
+
+    /// Summary across all CPUs
+    pub fn summary(&self) -> SchedStatsSummary {
+        let mut latency = HistogramSnapshot::default();
+        let mut depth = HistogramSnapshot::default();
+        let mut migrations = 0;
+        for stats in &self.cpus {
+            latency.merge(&stats.wakeup_latency.snapshot());
+            depth.merge(&stats.runqueue_depth.snapshot());
+            migrations += stats.migrations_in.load(Ordering::Relaxed);
+        }
+        summarize(&latency, &depth, migrations)
+    }
+
+    /// Clear all statistics
+    pub fn reset(&self) {
+        for stats in &self.cpus {
+            stats.reset();
+        }
+        for slot in &self.pending {
+            slot.thread.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Render `/proc/sched_stats`
+    ///
+    /// CPUs with no activity are omitted.
+    pub fn render_proc(&self, out: &mut dyn Write) -> fmt::Result {
+        writeln!(
+            out,
+            "cpu  wakeups  lat_p50_ns  lat_p99_ns  lat_max_ns  rq_avg  rq_max  migr_in  migr_out"
+        )?;
+
+        let mut latency = HistogramSnapshot::default();
+        let mut depth = HistogramSnapshot::default();
+        let (mut total_in, mut total_out) = (0, 0);
+        for (cpu, stats) in self.cpus.iter().enumerate() {
+            let cpu_latency = stats.wakeup_latency.snapshot();
+            let cpu_depth = stats.runqueue_depth.snapshot();
+            let migr_in = stats.migrations_in.load(Ordering::Relaxed);
+            let migr_out = stats.migrations_out.load(Ordering::Relaxed);
+            if cpu_latency.count == 0 && cpu_depth.count == 0 && migr_in == 0 && migr_out == 0 {
+                continue;
+            }
+
+            write!(out, "{:<4} ", cpu)?;
+            render_row(out, &summarize(&cpu_latency, &cpu_depth, migr_in), migr_out)?;
+            latency.merge(&cpu_latency);
+            depth.merge(&cpu_depth);
+            total_in += migr_in;
+            total_out += migr_out;
+        }
+
+        write!(out, "all  ")?;
+        render_row(out, &summarize(&latency, &depth, total_in), total_out)?;
+
+        writeln!(out, "\nwakeup_latency_ns:")?;
+        for (i, &n) in latency.buckets.iter().enumerate() {
+            if n != 0 {
+                let upper = if i == 0 { 0 } else { (1u64 << i) - 1 };
+                writeln!(out, "  <= {:<12} {}", upper, n)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for SchedTrace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn summarize(
+    latency: &HistogramSnapshot,
+    depth: &HistogramSnapshot,
+    migrations: u64,
+) -> SchedStatsSummary {
+    SchedStatsSummary {
+        wakeups: latency.count,
+        wakeup_p50_ns: latency.percentile(50),
+        wakeup_p99_ns: latency.percentile(99),
+        wakeup_max_ns: latency.max,
+        runqueue_avg_centi: depth.mean_centi(),
+        runqueue_max: depth.max,
+        migrations,
+    }
+}
+
+fn render_row(out: &mut dyn Write, s: &SchedStatsSummary, migr_out: u64) -> fmt::Result {
+    writeln!(
+        out,
+        "{:<8} {:<11} {:<11} {:<11} {:>3}.{:02}  {:<7} {:<8} {}",
+        s.wakeups,
+        s.wakeup_p50_ns,
+        s.wakeup_p99_ns,
+        s.wakeup_max_ns,
+        s.runqueue_avg_centi / 100,
+        s.runqueue_avg_centi % 100,
+        s.runqueue_max,
+        s.migrations,
+        migr_out
+    )
+}
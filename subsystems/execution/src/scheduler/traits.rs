@@ -24,6 +24,10 @@ pub struct SchedulableThread {
     pub is_kernel: bool,
     /// Is this a real-time thread?
     pub is_realtime: bool,
+    /// Energy placement class
+    pub placement: PlacementClass,
+    /// CPUs placement prefers, within `affinity` (set by the framework)
+    pub preferred: u64,
 }
 
 impl SchedulableThread {
@@ -37,6 +41,8 @@ pub fn new(id: ThreadId, process: ProcessId, priority: Priority) -> Self {
             name: String::new(),
             is_kernel: false,
             is_realtime: false,
+            placement: PlacementClass::Normal,
+            preferred: u64::MAX,
         }
     }
 
@@ -63,6 +69,24 @@ pub fn with_affinity(mut self, affinity: u64) -> Self {
         self.affinity = affinity;
         self
     }
+
+    /// Set the energy placement class
+    pub fn with_placement(mut self, placement: PlacementClass) -> Self {
+        self.placement = placement;
+        self
+    }
+}
+
+/// How a thread trades energy for performance on hybrid CPUs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlacementClass {
+    /// Throughput work that may be packed onto efficient cores
+    Background,
+    /// No preference
+    #[default]
+    Normal,
+    /// Latency-sensitive work that should get performance cores
+    LatencySensitive,
 }
 
 /// The core scheduler trait
@@ -120,6 +144,57 @@ fn set_policy(&self, _id: ThreadId, _policy: SchedulingPolicy) -> ExecResult<()>
     fn migrate_thread(&self, _id: ThreadId, _target_cpu: usize) -> ExecResult<()> {
         Err(crate::ExecError::InvalidArgument)
     }
+
+    /// Threads queued on a CPU (`None` if not tracked)
+    fn runqueue_len(&self, _cpu: usize) -> Option<usize> {
+        None
+    }
+
+    /// Absolute deadline of a deadline-scheduled thread
+    fn deadline(&self, _id: ThreadId) -> Option<u64> {
+        None
+    }
+
+    /// Run a thread with an inherited deadline (`None` restores its own)
+    ///
+    /// Deadline schedulers override this to honour priority inheritance
+    /// from deadline waiters; others can ignore it.
+    fn inherit_deadline(&self, _id: ThreadId, _deadline: Option<u64>) -> ExecResult<()> {
+        Ok(())
+    }
+
+    /// CPU affinity of a thread (`None` if not tracked)
+    fn affinity(&self, _id: ThreadId) -> Option<u64> {
+        None
+    }
+
+    /// Change the placement class of a thread and its preferred CPUs
+    ///
+    /// Schedulers that place threads by core type override this; others
+    /// can ignore it.
+    fn set_placement(&self, _id: ThreadId, _placement: PlacementClass, _preferred: u64) -> ExecResult<()> {
+        Ok(())
+    }
+}
+
+/// Scheduling attributes of a thread (`sched_setattr`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchedAttr {
+    /// Scheduling policy (`None` keeps the current one)
+    pub policy: Option<SchedulingPolicy>,
+    /// Priority (`None` keeps the current one)
+    pub priority: Option<Priority>,
+    /// Energy placement class
+    pub placement: PlacementClass,
+}
+
+/// Priority-inheritance boost applied to a lock holder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PiBoost {
+    /// Highest priority among blocked waiters
+    pub priority: Priority,
+    /// Earliest deadline among blocked deadline waiters
+    pub deadline: Option<u64>,
 }
 
 /// Scheduling policies
@@ -175,6 +250,18 @@ pub trait LoadBalancer: Send + Sync {
     fn suggest_migration(&self, thread: ThreadId, current_cpu: usize) -> Option<usize>;
 }
 
+/// Energy-aware placement advisor for hybrid CPUs
+///
+/// Consulted when threads are added and when a scheduler picks a CPU to
+/// wake or balance a thread on. Without one, the core types of the HAL
+/// topology are used.
+pub trait PlacementAdvisor: Send + Sync {
+    /// CPUs preferred for a thread of `class`, out of `allowed`
+    ///
+    /// Returning 0 or `allowed` expresses no preference.
+    fn preferred_cpus(&self, class: PlacementClass, allowed: u64) -> u64;
+}
+
 /// Per-CPU scheduler data
 pub trait PerCpuScheduler: Send + Sync {
     /// Get the current thread on this CPU
@@ -0,0 +1,331 @@
+//! # Stop Machine
+//!
+//! Runs a function while every other online CPU is parked, for updates
+//! no CPU may observe half-done (kernel text patching, page-table
+//! splitting, module hot-reload):
+//!
+//! 1. The caller disables local interrupts and sends the stop IPI to all
+//!    other online CPUs.
+//! 2. Each CPU's IPI handler ([`StopMachine::handle_ipi`]) checks in and
+//!    spins with interrupts disabled.
+//! 3. Once every CPU has checked in, the function runs on the calling
+//!    CPU.
+//! 4. All CPUs serialize their instruction stream (so patched text is
+//!    fetched afresh) and resume.
+//!
+//! Latency is bounded: if a CPU does not check in within the rendezvous
+//! timeout (it is spinning with interrupts disabled, or wedged), the stop
+//! is aborted and [`ExecError::Timeout`] returned without running the
+//! function. Parked CPUs keep touching the soft-lockup watchdog, and a
+//! stop that holds the machine longer than the hold budget is logged.
+//!
+//! The architecture layer provides the IPI, interrupt masking, clock and
+//! watchdog through [`StopMachineHooks`].
+//!
+//! [`patch_kernel`] writes to kernel memory that is write-protected
+//! (`.text`, or tables frozen at lockdown) from inside a stop.
+
+use crate::isolation::isolation;
+use crate::irq::{in_interrupt, MAX_CPUS};
+use crate::{ExecError, ExecResult};
+use helix_hal::kernel_protect::{self, ProtectError, Span};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use spin::{Mutex, RwLock};
+
+/// Default time allowed for all CPUs to check in (ns)
+pub const DEFAULT_RENDEZVOUS_TIMEOUT_NS: u64 = 10_000_000;
+
+/// Default time the machine may stay stopped before it is logged (ns)
+pub const DEFAULT_HOLD_BUDGET_NS: u64 = 1_000_000;
+
+/// Spins between watchdog touches
+const WATCHDOG_TOUCH_SPINS: u32 = 1024;
+
+/// No CPU is running a stop
+const NO_CPU: usize = usize::MAX;
+
+/// Control word stages (low byte; the generation is above it)
+const STAGE_IDLE: u64 = 0;
+const STAGE_PREPARE: u64 = 1;
+const STAGE_RUN: u64 = 2;
+
+/// Callbacks into the architecture layer
+#[derive(Clone, Copy)]
+pub struct StopMachineHooks {
+    /// Current CPU
+    pub cpu_id: fn() -> usize,
+    /// Send the stop IPI to the CPUs in the mask; its handler calls
+    /// [`StopMachine::handle_ipi`]
+    pub send_ipi: fn(mask: u64),
+    /// Disable local interrupts, returning whether they were enabled
+    pub irq_save: fn() -> bool,
+    /// Restore local interrupts saved by `irq_save`
+    pub irq_restore: fn(enabled: bool),
+    /// Serialize the local instruction stream
+    pub sync_core: fn(),
+    /// Monotonic time (ns)
+    pub clock: fn() -> u64,
+    /// Reset the soft-lockup watchdog of a CPU
+    pub touch_watchdog: fn(cpu: usize),
+}
+
+/// Stop-machine statistics
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StopMachineStats {
+    /// Completed stops
+    pub completed: u64,
+    /// Stops aborted because a CPU did not check in
+    pub timeouts: u64,
+    /// Time for all CPUs to check in, last stop (ns)
+    pub last_rendezvous_ns: u64,
+    /// Longest rendezvous (ns)
+    pub max_rendezvous_ns: u64,
+    /// Time the machine was stopped, last stop (ns)
+    pub last_hold_ns: u64,
+    /// Longest hold (ns)
+    pub max_hold_ns: u64,
+}
+
+/// Stop-machine state
+pub struct StopMachine {
+    /// Architecture callbacks
+    hooks: RwLock<Option<StopMachineHooks>>,
+    /// Serializes stops
+    lock: Mutex<()>,
+    /// Generation << 8 | stage
+    control: AtomicU64,
+    /// CPUs that checked in for the current generation
+    arrived: AtomicU64,
+    /// CPU running the current stop
+    owner: AtomicUsize,
+    /// Rendezvous timeout (ns)
+    rendezvous_timeout: AtomicU64,
+    /// Hold budget (ns)
+    hold_budget: AtomicU64,
+    /// Statistics
+    stats: Mutex<StopMachineStats>,
+}
+
+impl StopMachine {
+    /// Create an idle stop-machine with default limits
+    pub const fn new() -> Self {
+        Self {
+            hooks: RwLock::new(None),
+            lock: Mutex::new(()),
+            control: AtomicU64::new(STAGE_IDLE),
+            arrived: AtomicU64::new(0),
+            owner: AtomicUsize::new(NO_CPU),
+            rendezvous_timeout: AtomicU64::new(DEFAULT_RENDEZVOUS_TIMEOUT_NS),
+            hold_budget: AtomicU64::new(DEFAULT_HOLD_BUDGET_NS),
+            stats: Mutex::new(StopMachineStats {
+                completed: 0,
+                timeouts: 0,
+                last_rendezvous_ns: 0,
+                max_rendezvous_ns: 0,
+                last_hold_ns: 0,
+                max_hold_ns: 0,
+            }),
+        }
+    }
+
+    /// Install the architecture callbacks
+    pub fn set_hooks(&self, hooks: StopMachineHooks) {
+        *self.hooks.write() = Some(hooks);
+    }
+
+    /// Set the rendezvous timeout and the hold budget (ns)
+    pub fn set_limits(&self, rendezvous_timeout: u64, hold_budget: u64) -> ExecResult<()> {
+        if rendezvous_timeout == 0 {
+            return Err(ExecError::InvalidArgument);
+        }
+        self.rendezvous_timeout.store(rendezvous_timeout, Ordering::Relaxed);
+        self.hold_budget.store(hold_budget, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Statistics
+    pub fn stats(&self) -> StopMachineStats {
+        *self.stats.lock()
+    }
+
+    /// Is the machine being stopped or stopped?
+    ///
+    /// A soft-lockup detector may skip its check while this is set.
+    pub fn active(&self) -> bool {
+        self.control.load(Ordering::Acquire) & 0xFF != STAGE_IDLE
+    }
+
+    /// Run `f` on the calling CPU with all other online CPUs parked
+    ///
+    /// Fails with [`ExecError::Timeout`] if a CPU does not check in within
+    /// the rendezvous timeout, [`ExecError::Deadlock`] if called from
+    /// inside a stop, and [`ExecError::InvalidState`] from interrupt
+    /// context or on SMP without hooks. `f` runs with interrupts disabled
+    /// and must not sleep or take locks other CPUs may hold.
+    pub fn run<R>(&self, f: impl FnOnce() -> R) -> ExecResult<R> {
+        let hooks = *self.hooks.read();
+        let cpu = hooks.map(|h| (h.cpu_id)()).unwrap_or(0);
+        if cpu >= MAX_CPUS {
+            return Err(ExecError::InvalidArgument);
+        }
+        if self.owner.load(Ordering::Acquire) == cpu {
+            return Err(ExecError::Deadlock);
+        }
+        if in_interrupt(cpu) {
+            return Err(ExecError::InvalidState);
+        }
+
+        let others = isolation().online() & !(1 << cpu);
+        let Some(hooks) = hooks else {
+            // Nothing else can run without a second CPU
+            return if others == 0 { Ok(f()) } else { Err(ExecError::InvalidState) };
+        };
+
+        // Interrupts stay enabled while waiting, so a stop started by
+        // another CPU can park this one
+        let _serial = self.lock.lock();
+
+        let irq = (hooks.irq_save)();
+        let generation = (self.control.load(Ordering::Relaxed) >> 8).wrapping_add(1);
+        self.arrived.store(0, Ordering::Relaxed);
+        self.owner.store(cpu, Ordering::Release);
+        self.control.store((generation << 8) | STAGE_PREPARE, Ordering::Release);
+
+        let start = (hooks.clock)();
+        if others != 0 {
+            (hooks.send_ipi)(others);
+        }
+
+        let timeout = self.rendezvous_timeout.load(Ordering::Relaxed);
+        let mut spins = 0u32;
+        while self.arrived.load(Ordering::Acquire) & others != others {
+            if (hooks.clock)().saturating_sub(start) > timeout {
+                let missing = others & !self.arrived.load(Ordering::Acquire);
+                self.finish(generation, cpu, &hooks, irq);
+                self.stats.lock().timeouts += 1;
+                log::warn!("stop_machine: CPUs {:#x} did not respond, aborting", missing);
+                return Err(ExecError::Timeout);
+            }
+            spins = spins.wrapping_add(1);
+            if spins % WATCHDOG_TOUCH_SPINS == 0 {
+                (hooks.touch_watchdog)(cpu);
+            }
+            core::hint::spin_loop();
+        }
+
+        let parked = (hooks.clock)();
+        self.control.store((generation << 8) | STAGE_RUN, Ordering::Release);
+        let ret = f();
+        let held = (hooks.clock)().saturating_sub(parked);
+        self.finish(generation, cpu, &hooks, irq);
+
+        let rendezvous = parked.saturating_sub(start);
+        let mut stats = self.stats.lock();
+        stats.completed += 1;
+        stats.last_rendezvous_ns = rendezvous;
+        stats.max_rendezvous_ns = stats.max_rendezvous_ns.max(rendezvous);
+        stats.last_hold_ns = held;
+        stats.max_hold_ns = stats.max_hold_ns.max(held);
+        drop(stats);
+
+        let budget = self.hold_budget.load(Ordering::Relaxed);
+        if budget != 0 && held > budget {
+            log::warn!("stop_machine: CPUs held for {} ns (budget {} ns)", held, budget);
+        }
+        Ok(ret)
+    }
+
+    /// Release the parked CPUs and restore the caller
+    fn finish(&self, generation: u64, cpu: usize, hooks: &StopMachineHooks, irq: bool) {
+        (hooks.sync_core)();
+        self.control.store((generation << 8) | STAGE_IDLE, Ordering::Release);
+        self.owner.store(NO_CPU, Ordering::Release);
+        (hooks.touch_watchdog)(cpu);
+        (hooks.irq_restore)(irq);
+    }
+
+    /// Stop IPI handler: park `cpu` until the current stop completes
+    ///
+    /// Returns at once for a stale IPI (no stop in its check-in stage).
+    pub fn handle_ipi(&self, cpu: usize) {
+        let Some(hooks) = *self.hooks.read() else {
+            return;
+        };
+        let control = self.control.load(Ordering::Acquire);
+        if control & 0xFF != STAGE_PREPARE || cpu >= MAX_CPUS {
+            return;
+        }
+        let generation = control >> 8;
+
+        let irq = (hooks.irq_save)();
+        self.arrived.fetch_or(1 << cpu, Ordering::AcqRel);
+
+        let mut spins = 0u32;
+        loop {
+            let now = self.control.load(Ordering::Acquire);
+            if now >> 8 != generation || now & 0xFF == STAGE_IDLE {
+                break;
+            }
+            spins = spins.wrapping_add(1);
+            if spins % WATCHDOG_TOUCH_SPINS == 0 {
+                (hooks.touch_watchdog)(cpu);
+            }
+            core::hint::spin_loop();
+        }
+
+        (hooks.sync_core)();
+        (hooks.touch_watchdog)(cpu);
+        (hooks.irq_restore)(irq);
+    }
+}
+
+impl Default for StopMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global stop-machine state
+static STOP_MACHINE: StopMachine = StopMachine::new();
+
+/// Get the stop-machine state
+pub fn stopper() -> &'static StopMachine {
+    &STOP_MACHINE
+}
+
+/// Run `f` with all other online CPUs parked
+///
+/// See [`StopMachine::run`].
+pub fn stop_machine<R>(f: impl FnOnce() -> R) -> ExecResult<R> {
+    STOP_MACHINE.run(f)
+}
+
+/// Write `bytes` at `addr` in protected kernel memory with all other
+/// online CPUs parked
+///
+/// For text patching and for updating tables frozen at kernel lockdown:
+/// the pages are made writable for the copy only
+/// ([`with_writable`](kernel_protect::with_writable)), and every CPU
+/// serializes its instruction stream before resuming.
+///
+/// # Safety
+///
+/// The new bytes must leave the kernel consistent, including for code
+/// the stopped CPUs will resume in.
+pub unsafe fn patch_kernel(addr: u64, bytes: &[u8]) -> ExecResult<()> {
+    let end = addr
+        .checked_add(bytes.len() as u64)
+        .ok_or(ExecError::InvalidArgument)?;
+    let patched = stop_machine(|| {
+        // SAFETY: the caller vouches for the bytes; the other CPUs are parked
+        unsafe {
+            kernel_protect::with_writable(Span::new(addr, end), || {
+                core::ptr::copy_nonoverlapping(bytes.as_ptr(), addr as *mut u8, bytes.len())
+            })
+        }
+    })?;
+    patched.map_err(|e| match e {
+        ProtectError::OutOfImage(_) => ExecError::InvalidArgument,
+        _ => ExecError::Internal,
+    })
+}
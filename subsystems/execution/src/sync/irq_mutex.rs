@@ -0,0 +1,75 @@
+//! # Interrupt-Safe Spinlock
+//!
+//! A spinlock that keeps interrupts disabled on the local CPU while it is
+//! held, for state shared with hard-IRQ or softirq context. With a plain
+//! spinlock, an interrupt arriving on a CPU that holds the lock spins on
+//! it forever; here the interrupt (and the softirqs run on its exit) waits
+//! until the lock is released.
+
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use helix_hal::interrupts::{local_irq_restore, local_irq_save};
+use spin::{Mutex, MutexGuard};
+
+/// Spinlock taken with local interrupts disabled
+pub struct IrqMutex<T> {
+    inner: Mutex<T>,
+}
+
+impl<T> IrqMutex<T> {
+    /// Create an unlocked mutex
+    pub const fn new(value: T) -> Self {
+        Self {
+            inner: Mutex::new(value),
+        }
+    }
+
+    /// Disable local interrupts and take the lock
+    pub fn lock(&self) -> IrqMutexGuard<'_, T> {
+        let irq_enabled = local_irq_save();
+        IrqMutexGuard {
+            guard: ManuallyDrop::new(self.inner.lock()),
+            irq_enabled,
+        }
+    }
+
+    /// Is the lock held?
+    pub fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+}
+
+impl<T: Default> Default for IrqMutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// Guard of an [`IrqMutex`]; restores interrupts after unlocking
+pub struct IrqMutexGuard<'a, T> {
+    guard: ManuallyDrop<MutexGuard<'a, T>>,
+    /// Interrupt state to restore
+    irq_enabled: bool,
+}
+
+impl<T> Deref for IrqMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for IrqMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for IrqMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: The guard is dropped exactly once, here
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        local_irq_restore(self.irq_enabled);
+    }
+}
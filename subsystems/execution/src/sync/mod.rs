@@ -0,0 +1,17 @@
+//! # Kernel Synchronization
+//!
+//! Sleeping locks that cooperate with the scheduler framework:
+//!
+//! - [`pi_mutex`]: mutexes with priority inheritance, so a high-priority
+//!   or deadline thread blocked on a lock is never delayed by
+//!   medium-priority threads preempting the low-priority holder
+//!
+//! and spinlocks for state shared with interrupt context:
+//!
+//! - [`irq_mutex`]: spinlocks held with local interrupts disabled
+
+pub mod irq_mutex;
+pub mod pi_mutex;
+
+pub use irq_mutex::{IrqMutex, IrqMutexGuard};
+pub use pi_mutex::{PiMutex, PiMutexGuard};
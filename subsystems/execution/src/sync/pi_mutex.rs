@@ -0,0 +1,371 @@
+//! # Priority-Inheritance Mutex
+//!
+//! A sleeping mutex whose holder inherits the priority (and, under a
+//! deadline scheduler, the deadline) of its most urgent waiter through
+//! [`SchedulerFramework::pi_boost`](crate::scheduler::SchedulerFramework::pi_boost).
+//! Boosts propagate along blocking chains: if the holder is itself
+//! blocked on another PI mutex, that mutex's holder is boosted too.
+//!
+//! The uncontended paths are a single compare-exchange on the owner word.
+//! Contended state (waiters, blocking chains) lives in one global graph
+//! under a spinlock, which also makes chain walks and deadlock detection
+//! consistent.
+//!
+//! Unlock hands the mutex directly to the most urgent waiter, so a
+//! boosted holder cannot have the lock stolen back by a lower-priority
+//! thread.
+
+use crate::scheduler::{framework, PiBoost, Priority};
+use crate::{ExecError, ExecResult, ThreadId};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
+
+/// Owner word flag: the mutex has blocked waiters
+const WAITERS: u64 = 1 << 63;
+
+/// Longest blocking chain followed when propagating boosts
+const MAX_CHAIN_DEPTH: usize = 16;
+
+// =============================================================================
+// Waiter Graph
+// =============================================================================
+
+/// A thread blocked on a PI mutex
+#[derive(Debug, Clone, Copy)]
+struct Waiter {
+    thread: ThreadId,
+    priority: Priority,
+    deadline: Option<u64>,
+}
+
+impl Waiter {
+    /// Snapshot the current (possibly boosted) urgency of `thread`
+    fn of(thread: ThreadId) -> Self {
+        Self {
+            thread,
+            priority: framework().effective_priority(thread).unwrap_or(Priority::MIN),
+            deadline: framework().effective_deadline(thread),
+        }
+    }
+
+    /// Should `self` get the lock before `other`?
+    ///
+    /// Deadline threads come first (earliest deadline wins), then
+    /// priority. Ties keep arrival order.
+    fn outranks(&self, other: &Waiter) -> bool {
+        match (self.deadline, other.deadline) {
+            (Some(a), Some(b)) => a < b,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => self.priority > other.priority,
+        }
+    }
+}
+
+/// Who holds and who waits for contended PI mutexes
+struct PiGraph {
+    /// Lock -> holder (locks with waiters only)
+    owners: BTreeMap<usize, ThreadId>,
+    /// Lock -> blocked waiters, in arrival order
+    waiters: BTreeMap<usize, Vec<Waiter>>,
+    /// Thread -> lock it is blocked on
+    blocked_on: BTreeMap<ThreadId, usize>,
+}
+
+impl PiGraph {
+    const fn new() -> Self {
+        Self {
+            owners: BTreeMap::new(),
+            waiters: BTreeMap::new(),
+            blocked_on: BTreeMap::new(),
+        }
+    }
+
+    /// Would `me` blocking on a lock held by `holder` close a cycle?
+    fn would_deadlock(&self, me: ThreadId, mut holder: ThreadId) -> bool {
+        for _ in 0..MAX_CHAIN_DEPTH {
+            if holder == me {
+                return true;
+            }
+            let Some(lock) = self.blocked_on.get(&holder) else {
+                return false;
+            };
+            let Some(&next) = self.owners.get(lock) else {
+                return false;
+            };
+            holder = next;
+        }
+        false
+    }
+
+    /// Boost owed to `thread` by the waiters of every lock it holds
+    fn boost_for(&self, thread: ThreadId) -> Option<PiBoost> {
+        let mut boost: Option<PiBoost> = None;
+        let held = self.owners.iter().filter(|(_, owner)| **owner == thread);
+        for (lock, _) in held {
+            for waiter in self.waiters.get(lock).into_iter().flatten() {
+                let b = boost.get_or_insert(PiBoost {
+                    priority: waiter.priority,
+                    deadline: waiter.deadline,
+                });
+                b.priority = b.priority.max(waiter.priority);
+                b.deadline = match (b.deadline, waiter.deadline) {
+                    (Some(a), Some(d)) => Some(a.min(d)),
+                    (a, d) => a.or(d),
+                };
+            }
+        }
+        boost
+    }
+
+    /// Recompute the boost of `thread` and every holder down its chain
+    fn propagate(&mut self, mut thread: ThreadId) {
+        for _ in 0..MAX_CHAIN_DEPTH {
+            let _ = framework().pi_boost(thread, self.boost_for(thread));
+
+            let Some(&lock) = self.blocked_on.get(&thread) else {
+                return;
+            };
+            // The thread's urgency changed: refresh its waiter record
+            if let Some(waiter) = self
+                .waiters
+                .get_mut(&lock)
+                .and_then(|w| w.iter_mut().find(|w| w.thread == thread))
+            {
+                *waiter = Waiter::of(thread);
+            }
+            let Some(&holder) = self.owners.get(&lock) else {
+                return;
+            };
+            thread = holder;
+        }
+    }
+
+    /// Remove and return the most urgent waiter of `lock`
+    fn take_top_waiter(&mut self, lock: usize) -> Option<Waiter> {
+        let waiters = self.waiters.get_mut(&lock).filter(|w| !w.is_empty())?;
+        let mut best = 0;
+        for (i, waiter) in waiters.iter().enumerate().skip(1) {
+            if waiter.outranks(&waiters[best]) {
+                best = i;
+            }
+        }
+        let waiter = waiters.remove(best);
+        if waiters.is_empty() {
+            self.waiters.remove(&lock);
+        }
+        self.blocked_on.remove(&waiter.thread);
+        Some(waiter)
+    }
+}
+
+/// Global PI waiter graph
+static GRAPH: Mutex<PiGraph> = Mutex::new(PiGraph::new());
+
+// =============================================================================
+// PI Mutex
+// =============================================================================
+
+/// Mutex with priority inheritance
+///
+/// Callers identify themselves by [`ThreadId`]; a blocked caller is
+/// reported to the scheduler with `thread_block` and woken with
+/// `thread_ready` when the lock is handed to it.
+pub struct PiMutex<T> {
+    /// Holder ID plus one (0 = unlocked), with [`WAITERS`] flag
+    owner: AtomicU64,
+    data: UnsafeCell<T>,
+}
+
+// SAFETY: the owner word serializes all access to `data`
+unsafe impl<T: Send> Send for PiMutex<T> {}
+unsafe impl<T: Send> Sync for PiMutex<T> {}
+
+impl<T> PiMutex<T> {
+    /// Create an unlocked mutex
+    pub const fn new(data: T) -> Self {
+        Self {
+            owner: AtomicU64::new(0),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Graph key (stable while the mutex is borrowed)
+    fn key(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    fn word(thread: ThreadId) -> u64 {
+        thread.as_u64() + 1
+    }
+
+    /// Acquire the mutex as `me`, blocking while another thread holds it
+    ///
+    /// While blocked, the holder (and any holder it is blocked behind)
+    /// runs with at least `me`'s priority and deadline. Fails with
+    /// [`ExecError::Deadlock`] if `me` already holds it or waiting would
+    /// close a cycle.
+    pub fn lock(&self, me: ThreadId) -> ExecResult<PiMutexGuard<'_, T>> {
+        let word = Self::word(me);
+        if self
+            .owner
+            .compare_exchange(0, word, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            return Ok(PiMutexGuard { lock: self, owner: me });
+        }
+
+        {
+            let mut graph = GRAPH.lock();
+            let key = self.key();
+            let holder = loop {
+                let current = self.owner.load(Ordering::Relaxed);
+                if current == 0 {
+                    if self
+                        .owner
+                        .compare_exchange(0, word, Ordering::Acquire, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        return Ok(PiMutexGuard { lock: self, owner: me });
+                    }
+                    continue;
+                }
+
+                let holder = ThreadId((current & !WAITERS) - 1);
+                if graph.would_deadlock(me, holder) {
+                    return Err(ExecError::Deadlock);
+                }
+                // Force the holder's unlock onto the slow path
+                if self
+                    .owner
+                    .compare_exchange(current, current | WAITERS, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    break holder;
+                }
+            };
+
+            graph.owners.insert(key, holder);
+            graph.waiters.entry(key).or_default().push(Waiter::of(me));
+            graph.blocked_on.insert(me, key);
+            graph.propagate(holder);
+        }
+
+        let _ = framework().thread_block(me);
+        while self.owner.load(Ordering::Acquire) & !WAITERS != word {
+            core::hint::spin_loop();
+        }
+        Ok(PiMutexGuard { lock: self, owner: me })
+    }
+
+    /// Acquire the mutex as `me` if it is free
+    pub fn try_lock(&self, me: ThreadId) -> Option<PiMutexGuard<'_, T>> {
+        self.owner
+            .compare_exchange(0, Self::word(me), Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| PiMutexGuard { lock: self, owner: me })
+    }
+
+    /// Release the mutex held by `me`
+    fn unlock(&self, me: ThreadId) {
+        if self
+            .owner
+            .compare_exchange(Self::word(me), 0, Ordering::Release, Ordering::Relaxed)
+            .is_ok()
+        {
+            return;
+        }
+
+        let mut graph = GRAPH.lock();
+        let key = self.key();
+        let Some(next) = graph.take_top_waiter(key) else {
+            graph.owners.remove(&key);
+            self.owner.store(0, Ordering::Release);
+            graph.propagate(me);
+            return;
+        };
+
+        let more = graph.waiters.contains_key(&key);
+        if more {
+            graph.owners.insert(key, next.thread);
+        } else {
+            graph.owners.remove(&key);
+        }
+        let flag = if more { WAITERS } else { 0 };
+        self.owner.store(Self::word(next.thread) | flag, Ordering::Release);
+
+        // Drop the boost this lock gave us; the new holder inherits it
+        graph.propagate(me);
+        graph.propagate(next.thread);
+        drop(graph);
+
+        let _ = framework().thread_ready(next.thread);
+    }
+
+    /// Current holder
+    pub fn owner(&self) -> Option<ThreadId> {
+        match self.owner.load(Ordering::Relaxed) & !WAITERS {
+            0 => None,
+            word => Some(ThreadId(word - 1)),
+        }
+    }
+
+    /// Is the mutex held?
+    pub fn is_locked(&self) -> bool {
+        self.owner().is_some()
+    }
+
+    /// Are threads blocked on the mutex?
+    pub fn has_waiters(&self) -> bool {
+        self.owner.load(Ordering::Relaxed) & WAITERS != 0
+    }
+
+    /// Access the data through a unique reference (no locking needed)
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// Consume the mutex, returning the data
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+/// Held [`PiMutex`]; released on drop
+pub struct PiMutexGuard<'a, T> {
+    lock: &'a PiMutex<T>,
+    owner: ThreadId,
+}
+
+impl<T> PiMutexGuard<'_, T> {
+    /// Thread holding the lock
+    pub fn owner(&self) -> ThreadId {
+        self.owner
+    }
+}
+
+impl<T> Deref for PiMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard proves exclusive ownership
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for PiMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard proves exclusive ownership
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for PiMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.unlock(self.owner);
+    }
+}
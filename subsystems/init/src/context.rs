@@ -506,6 +506,9 @@ pub struct BootInfo {
     /// EFI system table
     pub efi_system_table: Option<u64>,
 
+    /// Boot console chosen by the bootloader, in `earlycon=` syntax
+    pub earlycon: Option<String>,
+
     /// Custom data
     pub custom: BTreeMap<String, Vec<u8>>,
 }
@@ -519,6 +522,7 @@ fn default() -> Self {
             rsdp_addr: None,
             dtb_addr: None,
             efi_system_table: None,
+            earlycon: None,
             custom: BTreeMap::new(),
         }
     }
@@ -256,6 +256,7 @@ pub mod subsystems {
     pub mod debug;
     pub mod drivers;
     pub mod filesystem;
+    pub mod initramfs;
     pub mod interrupts;
     pub mod ipc;
     pub mod memory;
@@ -11,6 +11,7 @@
 extern crate alloc;
 use alloc::string::String;
 use alloc::vec::Vec;
+use helix_hal::uart::{self, Earlycon};
 
 // =============================================================================
 // FIRMWARE SUBSYSTEM
@@ -97,6 +98,20 @@ fn init(&mut self, ctx: &mut InitContext) -> InitResult<()> {
             self.firmware_type
         ));
 
+        // Hardware description from the device tree blob; ACPI machines
+        // build theirs during namespace enumeration
+        if let Some(dtb) = ctx.boot_info().and_then(|info| info.dtb_addr) {
+            // SAFETY: the bootloader hands over a valid blob, identity
+            // mapped during the boot phase
+            match unsafe { helix_hal::devtree::init_from_fdt(dtb as *const u8) } {
+                Ok(tree) => ctx.debug(alloc::format!(
+                    "Device tree: {} nodes",
+                    tree.nodes().count()
+                )),
+                Err(e) => ctx.warn(alloc::format!("Invalid device tree blob: {:?}", e)),
+            }
+        }
+
         // Architecture-specific initialization
         #[cfg(target_arch = "x86_64")]
         {
@@ -256,7 +271,8 @@ fn init(&mut self, ctx: &mut InitContext) -> InitResult<()> {
 /// Early console subsystem
 ///
 /// Provides basic output capability during early boot before the
-/// full console driver is available.
+/// full console driver is available. Serial output goes to the HAL
+/// earlycon, picked by [`earlycon_spec`].
 pub struct EarlyConsoleSubsystem {
     info: SubsystemInfo,
     console_type: ConsoleType,
@@ -272,6 +288,27 @@ pub enum ConsoleType {
     Both,
 }
 
+/// Boot console when neither the command line nor the bootloader names one
+#[cfg(target_arch = "x86_64")]
+pub const DEFAULT_EARLYCON: Option<&str> = Some("uart8250,io,0x3f8,115200");
+
+/// Boot console when neither the command line nor the bootloader names one
+#[cfg(not(target_arch = "x86_64"))]
+pub const DEFAULT_EARLYCON: Option<&str> = None;
+
+/// Boot console port: `earlycon=` from the kernel command line, else the
+/// one the bootloader handed over, else [`DEFAULT_EARLYCON`]
+pub fn earlycon_spec(cmdline: Option<&str>, handoff: Option<&str>) -> Option<Earlycon> {
+    cmdline
+        .and_then(|c| {
+            c.split_whitespace()
+                .find_map(|arg| arg.strip_prefix("earlycon="))
+        })
+        .or(handoff)
+        .or(DEFAULT_EARLYCON)
+        .and_then(Earlycon::parse)
+}
+
 impl EarlyConsoleSubsystem {
     /// Create new early console subsystem
     pub fn new() -> Self {
@@ -308,21 +345,7 @@ pub fn write(&self, s: &str) {
     }
 
     fn write_serial(&self, s: &str) {
-        // In real code: write to serial port
-        #[cfg(target_arch = "x86_64")]
-        {
-            for byte in s.bytes() {
-                // Serial port write (0x3F8 = COM1)
-                unsafe {
-                    core::arch::asm!(
-                        "out dx, al",
-                        in("dx") 0x3F8u16,
-                        in("al") byte,
-                        options(nostack, preserves_flags)
-                    );
-                }
-            }
-        }
+        uart::early_write(s);
     }
 
     fn write_framebuffer(&self, _s: &str) {
@@ -330,16 +353,34 @@ fn write_framebuffer(&self, _s: &str) {
     }
 
     fn detect_console(&mut self, ctx: &InitContext) {
-        // Check for framebuffer
-        if let Some(boot_info) = ctx.boot_info() {
-            if boot_info.framebuffer.is_some() {
-                self.console_type = ConsoleType::Both;
-                return;
-            }
-        }
+        let framebuffer = ctx
+            .boot_info()
+            .is_some_and(|boot_info| boot_info.framebuffer.is_some());
+
+        self.console_type = match (uart::earlycon_active(), framebuffer) {
+            (true, true) => ConsoleType::Both,
+            (true, false) => ConsoleType::Serial,
+            (false, true) => ConsoleType::Framebuffer,
+            (false, false) => ConsoleType::None,
+        };
+    }
+
+    /// Bring up the serial boot console
+    fn start_earlycon(&self, ctx: &mut InitContext) {
+        let spec = match ctx.boot_info() {
+            Some(boot_info) => earlycon_spec(boot_info.cmdline.as_deref(), boot_info.earlycon.as_deref()),
+            None => earlycon_spec(None, None),
+        };
+        let Some(spec) = spec else {
+            ctx.debug("No serial boot console");
+            return;
+        };
 
-        // Default to serial
-        self.console_type = ConsoleType::Serial;
+        // SAFETY: early boot runs with physical memory identity mapped
+        match unsafe { uart::init_earlycon(&spec) } {
+            Ok(()) => ctx.debug(alloc::format!("Boot console: {:?}", spec)),
+            Err(e) => ctx.warn(alloc::format!("Boot console {:?} unavailable: {:?}", spec, e)),
+        }
     }
 }
 
@@ -357,30 +398,9 @@ fn info(&self) -> &SubsystemInfo {
     fn init(&mut self, ctx: &mut InitContext) -> InitResult<()> {
         ctx.info("Initializing early console");
 
-        // Detect available console
+        self.start_earlycon(ctx);
         self.detect_console(ctx);
 
-        // Initialize serial port
-        #[cfg(target_arch = "x86_64")]
-        {
-            // Initialize COM1
-            unsafe {
-                // Disable interrupts
-                core::arch::asm!("out dx, al", in("dx") 0x3F9u16, in("al") 0u8);
-                // Enable DLAB
-                core::arch::asm!("out dx, al", in("dx") 0x3FBu16, in("al") 0x80u8);
-                // Set baud rate divisor (115200)
-                core::arch::asm!("out dx, al", in("dx") 0x3F8u16, in("al") 1u8);
-                core::arch::asm!("out dx, al", in("dx") 0x3F9u16, in("al") 0u8);
-                // 8 bits, no parity, one stop bit
-                core::arch::asm!("out dx, al", in("dx") 0x3FBu16, in("al") 0x03u8);
-                // Enable FIFO
-                core::arch::asm!("out dx, al", in("dx") 0x3FAu16, in("al") 0xC7u8);
-                // IRQs enabled, RTS/DSR set
-                core::arch::asm!("out dx, al", in("dx") 0x3FCu16, in("al") 0x0Bu8);
-            }
-        }
-
         self.initialized = true;
 
         ctx.info(alloc::format!("Console type: {:?}", self.console_type));
@@ -423,4 +443,13 @@ fn test_early_console_subsystem() {
         assert_eq!(sub.info().phase, InitPhase::Boot);
         assert!(sub.info().provides.contains(PhaseCapabilities::CONSOLE));
     }
+
+    #[test]
+    fn test_earlycon_spec() {
+        let pl011 = Some(Earlycon::Pl011 { base: 0x900_0000 });
+        assert_eq!(earlycon_spec(None, Some("pl011,0x9000000")), pl011);
+        assert_eq!(earlycon_spec(Some("quiet earlycon=pl011,0x9000000"), Some("sbi")), pl011);
+        assert_eq!(earlycon_spec(Some("quiet"), None), DEFAULT_EARLYCON.and_then(Earlycon::parse));
+        assert_eq!(earlycon_spec(Some("earlycon=bogus"), Some("sbi")), None);
+    }
 }
@@ -472,50 +472,14 @@ pub fn log(&mut self, entry: LogEntry) {
         self.log_buffer.push(entry);
     }
 
-    /// Output log entry
+    /// Output log entry to the boot console
     fn output_log(&self, entry: &LogEntry) {
-        #[cfg(target_arch = "x86_64")]
-        {
-            // Output to serial port (COM1)
-            let msg = alloc::format!(
-                "[{:>5}] {}: {}\n",
-                entry.level.name(),
-                entry.module,
-                entry.message
-            );
-
-            for byte in msg.bytes() {
-                Self::serial_write(byte);
-            }
-        }
-    }
-
-    #[cfg(target_arch = "x86_64")]
-    fn serial_write(byte: u8) {
-        const COM1: u16 = 0x3F8;
-        unsafe {
-            // Wait for transmit buffer empty
-            loop {
-                let status: u8;
-                core::arch::asm!(
-                    "in al, dx",
-                    out("al") status,
-                    in("dx") COM1 + 5,
-                    options(nostack)
-                );
-                if (status & 0x20) != 0 {
-                    break;
-                }
-            }
-
-            // Send byte
-            core::arch::asm!(
-                "out dx, al",
-                in("al") byte,
-                in("dx") COM1,
-                options(nostack)
-            );
-        }
+        helix_hal::uart::early_write(&alloc::format!(
+            "[{:>5}] {}: {}\n",
+            entry.level.name(),
+            entry.module,
+            entry.message
+        ));
     }
 
     /// Set log level
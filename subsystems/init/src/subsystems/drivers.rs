@@ -2,6 +2,28 @@
 //!
 //! Device driver management, discovery, and initialization.
 //! Late phase subsystem for hardware driver loading.
+//!
+//! ## Binding
+//!
+//! Drivers declare match tables (PCI IDs, ACPI HIDs, compatible strings,
+//! virtio device types). Devices described by firmware come from the HAL's
+//! [`devtree`](helix_hal::devtree), so a `compatible` entry matches a DT
+//! compatible string and an ACPI `_HID`/`_CID` alike.
+//! The subsystem pairs unbound devices with matching drivers and probes
+//! them. A probe can be deferred, either because the driver declares
+//! device classes it requires or because `probe` returns
+//! [`ErrorKind::DependencyNotSatisfied`]; deferred devices are retried
+//! whenever another device binds.
+//!
+//! Drivers loaded from modules record their owning module. Unloading the
+//! module unbinds its devices, and reloading it rebinds them.
+//!
+//! ## On-demand loading
+//!
+//! With a [`DriverModuleLoader`] set, devices no registered driver matches
+//! have their [`modaliases`](Device::modaliases) requested from it, like
+//! modprobe; drivers of the modules it loads are registered and binding
+//! runs again. Each modalias is requested once.
 
 use crate::context::InitContext;
 use crate::error::{ErrorKind, InitError, InitResult};
@@ -13,6 +35,8 @@
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use helix_hal::devtree::{self, DeviceTree, NodeId, Resource, Source};
+use helix_hal::uart;
 
 // =============================================================================
 // DEVICE TYPES
@@ -95,6 +119,8 @@ pub enum DeviceState {
     Probing,
     Active,
     Suspended,
+    /// Probe deferred until a dependency binds
+    Deferred,
     Error,
     Removed,
 }
@@ -125,6 +151,12 @@ pub struct Device {
     pub subsystem_device: u32,
     pub revision: u8,
     pub class_code: u32,
+    /// ACPI hardware ID (e.g. `PNP0501`)
+    pub acpi_hid: Option<String>,
+    /// DT compatible strings or ACPI `_HID` then `_CID`s, most specific first
+    pub compatible: Vec<String>,
+    /// Virtio device type (e.g. 2 for block)
+    pub virtio_type: Option<u32>,
 
     // Resources
     pub mmio_regions: Vec<MmioRegion>,
@@ -153,6 +185,9 @@ pub fn new(id: DeviceId, name: String, class: DeviceClass, bus: BusType) -> Self
             subsystem_device: 0,
             revision: 0,
             class_code: 0,
+            acpi_hid: None,
+            compatible: Vec::new(),
+            virtio_type: None,
             mmio_regions: Vec::new(),
             io_ports: Vec::new(),
             irqs: Vec::new(),
@@ -162,6 +197,58 @@ pub fn new(id: DeviceId, name: String, class: DeviceClass, bus: BusType) -> Self
         }
     }
 
+    /// Create a device from a firmware-described node
+    pub fn from_node(tree: &DeviceTree, node: NodeId) -> Option<Self> {
+        let desc = tree.node(node)?;
+        let bus = match desc.source {
+            _ if helix_hal::i2c::client_address(tree, node).is_some() => BusType::I2c,
+            Source::DeviceTree => BusType::DeviceTree,
+            Source::Acpi => BusType::Acpi,
+        };
+        let mut dev = Self::new(0, tree.path(node), DeviceClass::Unknown, bus);
+        if desc.source == Source::Acpi {
+            dev.acpi_hid = desc.compatible.first().cloned();
+        }
+        dev.compatible = desc.compatible.clone();
+        for resource in &desc.resources {
+            match *resource {
+                Resource::Memory { base, size } => dev.mmio_regions.push(MmioRegion { base, size, flags: 0 }),
+                Resource::Io { base, len } => dev.io_ports.push(IoPortRange { start: base, count: len }),
+            }
+        }
+        dev.irqs = desc.interrupts.iter().map(|irq| irq.number).collect();
+        Some(dev)
+    }
+
+    /// Modaliases for on-demand driver loading, most specific first
+    ///
+    /// `virtio:d<type>`, `pci:v<vendor>d<device>sv<subvendor>sd<subdevice>bc<class>sc<subclass>i<interface>`
+    /// (upper-case hex), then `acpi:<id>` or `of:<compatible>` per
+    /// compatible identifier.
+    pub fn modaliases(&self) -> Vec<String> {
+        let mut aliases = Vec::new();
+        if let Some(virtio_type) = self.virtio_type {
+            aliases.push(alloc::format!("virtio:d{:08X}", virtio_type));
+        }
+        if matches!(self.bus, BusType::Pci | BusType::PciExpress) && self.vendor_id != 0 {
+            aliases.push(alloc::format!(
+                "pci:v{:08X}d{:08X}sv{:08X}sd{:08X}bc{:02X}sc{:02X}i{:02X}",
+                self.vendor_id,
+                self.device_id,
+                self.subsystem_vendor,
+                self.subsystem_device,
+                (self.class_code >> 16) & 0xFF,
+                (self.class_code >> 8) & 0xFF,
+                self.class_code & 0xFF
+            ));
+        }
+        let prefix = if self.acpi_hid.is_some() { "acpi" } else { "of" };
+        for compatible in &self.compatible {
+            aliases.push(alloc::format!("{}:{}", prefix, compatible));
+        }
+        aliases
+    }
+
     /// Is device active?
     pub fn is_active(&self) -> bool {
         self.state == DeviceState::Active
@@ -198,6 +285,10 @@ pub trait Driver: Send + Sync {
     fn info(&self) -> &DriverInfo;
 
     /// Probe device
+    ///
+    /// Returns `Ok(false)` to decline the device. Returning an error of
+    /// kind [`ErrorKind::DependencyNotSatisfied`] defers the probe until
+    /// another device binds.
     fn probe(&mut self, device: &mut Device) -> InitResult<bool>;
 
     /// Remove device
@@ -231,6 +322,52 @@ pub struct DriverInfo {
     pub class: DeviceClass,
     pub bus: BusType,
     pub match_table: Vec<DeviceMatch>,
+    /// Device classes that must have an active device before probing
+    pub requires: Vec<DeviceClass>,
+    /// Owning module (raw module ID), if loaded from a module
+    pub module: Option<u64>,
+}
+
+impl DriverInfo {
+    /// Create driver info with an empty match table
+    pub fn new(name: &'static str, class: DeviceClass, bus: BusType) -> Self {
+        Self {
+            id: 0,
+            name,
+            version: "0.1.0",
+            author: "",
+            description: "",
+            license: "MIT OR Apache-2.0",
+            class,
+            bus,
+            match_table: Vec::new(),
+            requires: Vec::new(),
+            module: None,
+        }
+    }
+
+    /// Add a match table entry
+    pub fn with_match(mut self, entry: DeviceMatch) -> Self {
+        self.match_table.push(entry);
+        self
+    }
+
+    /// Require an active device of `class` before probing
+    pub fn with_requirement(mut self, class: DeviceClass) -> Self {
+        self.requires.push(class);
+        self
+    }
+
+    /// Record the owning module
+    pub fn with_module(mut self, module: u64) -> Self {
+        self.module = Some(module);
+        self
+    }
+
+    /// Does any match table entry match the device?
+    pub fn matches(&self, device: &Device) -> bool {
+        self.match_table.iter().any(|m| m.matches(device))
+    }
 }
 
 /// Device matching criteria
@@ -240,6 +377,9 @@ pub struct DeviceMatch {
     pub device_id: Option<u32>,
     pub class_code: Option<u32>,
     pub class_mask: u32,
+    pub acpi_hid: Option<&'static str>,
+    pub compatible: Option<&'static str>,
+    pub virtio_type: Option<u32>,
 }
 
 impl DeviceMatch {
@@ -250,6 +390,9 @@ pub fn vendor_device(vendor: u32, device: u32) -> Self {
             device_id: Some(device),
             class_code: None,
             class_mask: 0,
+            acpi_hid: None,
+            compatible: None,
+            virtio_type: None,
         }
     }
 
@@ -260,6 +403,48 @@ pub fn class(class_code: u32, mask: u32) -> Self {
             device_id: None,
             class_code: Some(class_code),
             class_mask: mask,
+            acpi_hid: None,
+            compatible: None,
+            virtio_type: None,
+        }
+    }
+
+    /// Create match for an ACPI hardware ID
+    pub fn acpi(hid: &'static str) -> Self {
+        Self {
+            vendor_id: None,
+            device_id: None,
+            class_code: None,
+            class_mask: 0,
+            acpi_hid: Some(hid),
+            compatible: None,
+            virtio_type: None,
+        }
+    }
+
+    /// Create match for a compatible string (DT compatible, ACPI HID or CID)
+    pub fn compatible(compatible: &'static str) -> Self {
+        Self {
+            vendor_id: None,
+            device_id: None,
+            class_code: None,
+            class_mask: 0,
+            acpi_hid: None,
+            compatible: Some(compatible),
+            virtio_type: None,
+        }
+    }
+
+    /// Create match for a virtio device type
+    pub fn virtio(device_type: u32) -> Self {
+        Self {
+            vendor_id: None,
+            device_id: None,
+            class_code: None,
+            class_mask: 0,
+            acpi_hid: None,
+            compatible: None,
+            virtio_type: Some(device_type),
         }
     }
 
@@ -283,16 +468,253 @@ pub fn matches(&self, device: &Device) -> bool {
             }
         }
 
+        if let Some(hid) = self.acpi_hid {
+            if device.acpi_hid.as_deref() != Some(hid) {
+                return false;
+            }
+        }
+
+        if let Some(compatible) = self.compatible {
+            if !device.compatible.iter().any(|c| c == compatible) {
+                return false;
+            }
+        }
+
+        if let Some(virtio_type) = self.virtio_type {
+            if device.virtio_type != Some(virtio_type) {
+                return false;
+            }
+        }
+
         true
     }
 }
 
+/// Loads driver modules on demand
+///
+/// The kernel implements this over the module system's alias table.
+pub trait DriverModuleLoader: Send + Sync {
+    /// Load the modules for `modalias` and return their drivers, with
+    /// their owning module recorded in [`DriverInfo::module`]
+    fn request(&self, modalias: &str) -> Vec<(DriverInfo, Box<dyn Driver>)>;
+}
+
 /// Registered driver
 struct RegisteredDriver {
     info: DriverInfo,
     driver: Box<dyn Driver>,
 }
 
+// =============================================================================
+// PCI ENUMERATION
+// =============================================================================
+
+/// PCI configuration space access
+///
+/// Absent functions read as all ones.
+pub trait PciConfigAccess {
+    /// Read a dword (`offset` is dword aligned)
+    fn read32(&mut self, bus: u8, device: u8, function: u8, offset: u8) -> u32;
+
+    /// Write a dword (`offset` is dword aligned)
+    fn write32(&mut self, bus: u8, device: u8, function: u8, offset: u8, value: u32);
+}
+
+/// Configuration mechanism #1 (ports 0xCF8/0xCFC)
+#[cfg(target_arch = "x86_64")]
+pub struct PortConfigAccess;
+
+#[cfg(target_arch = "x86_64")]
+impl PortConfigAccess {
+    fn select(bus: u8, device: u8, function: u8, offset: u8) {
+        let config_addr = 0x8000_0000u32
+            | ((bus as u32) << 16)
+            | ((device as u32) << 11)
+            | ((function as u32) << 8)
+            | (offset as u32 & 0xFC);
+
+        unsafe {
+            core::arch::asm!(
+                "out dx, eax",
+                in("dx") 0xCF8u16,
+                in("eax") config_addr,
+                options(nostack)
+            );
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl PciConfigAccess for PortConfigAccess {
+    fn read32(&mut self, bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+        Self::select(bus, device, function, offset);
+
+        let value: u32;
+        unsafe {
+            core::arch::asm!(
+                "in eax, dx",
+                out("eax") value,
+                in("dx") 0xCFCu16,
+                options(nostack)
+            );
+        }
+        value
+    }
+
+    fn write32(&mut self, bus: u8, device: u8, function: u8, offset: u8, value: u32) {
+        Self::select(bus, device, function, offset);
+
+        unsafe {
+            core::arch::asm!(
+                "out dx, eax",
+                in("dx") 0xCFCu16,
+                in("eax") value,
+                options(nostack)
+            );
+        }
+    }
+}
+
+/// Enumerate every function on every bus
+pub fn scan_pci(config: &mut impl PciConfigAccess) -> Vec<Device> {
+    let mut devices = Vec::new();
+
+    for bus in 0..=255u8 {
+        for device in 0..32u8 {
+            for function in 0..8u8 {
+                if let Some(dev) = probe_pci_function(config, bus, device, function) {
+                    devices.push(dev);
+                }
+            }
+        }
+    }
+
+    devices
+}
+
+/// Read a function's IDs, class, BARs and legacy IRQ
+///
+/// BARs are sized with decoding disabled; unassigned BARs are skipped.
+pub fn probe_pci_function(
+    config: &mut impl PciConfigAccess,
+    bus: u8,
+    device: u8,
+    function: u8,
+) -> Option<Device> {
+    // Read vendor/device ID
+    let id = config.read32(bus, device, function, 0x00);
+    let vendor_id = id & 0xFFFF;
+    let device_id = id >> 16;
+
+    if vendor_id == 0xFFFF || vendor_id == 0 {
+        return None;
+    }
+
+    // Read class code
+    let class_config = config.read32(bus, device, function, 0x08);
+    let class_code = class_config >> 8;
+    let revision = (class_config & 0xFF) as u8;
+
+    let mut dev = Device::new(
+        0,
+        alloc::format!("pci-{:04x}:{:04x}", vendor_id, device_id),
+        pci_class_to_device_class(class_code),
+        BusType::Pci,
+    );
+    dev.vendor_id = vendor_id;
+    dev.device_id = device_id;
+    dev.class_code = class_code;
+    dev.revision = revision;
+
+    // Size BARs with memory and I/O decoding off
+    let command = config.read32(bus, device, function, 0x04) & 0xFFFF;
+    config.write32(bus, device, function, 0x04, command & !0x3);
+
+    let mut index = 0;
+    while index < 6 {
+        let offset = 0x10 + index * 4;
+        let value = config.read32(bus, device, function, offset);
+        let mask = size_bar(config, bus, device, function, offset);
+        index += 1;
+
+        if (value & 1) == 0 {
+            // MMIO, with the upper half in the next BAR if 64-bit
+            let is_64bit = (value >> 1) & 0x3 == 0x2 && index < 6;
+            let (base, mask) = if is_64bit {
+                let offset = 0x10 + index * 4;
+                let high = config.read32(bus, device, function, offset);
+                let high_mask = size_bar(config, bus, device, function, offset);
+                index += 1;
+                (
+                    ((high as u64) << 32) | (value & !0xF) as u64,
+                    ((high_mask as u64) << 32) | (mask & !0xF) as u64,
+                )
+            } else {
+                (
+                    (value & !0xF) as u64,
+                    (mask & !0xF) as u64 | !0xFFFF_FFFFu64,
+                )
+            };
+
+            if base != 0 && mask != 0 {
+                dev.mmio_regions.push(MmioRegion {
+                    base,
+                    size: !mask + 1,
+                    flags: value & 0xF,
+                });
+            }
+        } else {
+            // I/O port
+            let mask = mask & !0x3;
+            if value & !0x3 != 0 && mask != 0 {
+                dev.io_ports.push(IoPortRange {
+                    start: (value & !0x3) as u16,
+                    count: (!mask + 1) as u16,
+                });
+            }
+        }
+    }
+
+    config.write32(bus, device, function, 0x04, command);
+
+    // Read IRQ
+    let irq_config = config.read32(bus, device, function, 0x3C);
+    let irq = irq_config & 0xFF;
+    if irq != 0 && irq != 255 {
+        dev.irqs.push(irq);
+    }
+
+    Some(dev)
+}
+
+/// Write all ones to a BAR and read back its size mask, restoring it after
+fn size_bar(
+    config: &mut impl PciConfigAccess,
+    bus: u8,
+    device: u8,
+    function: u8,
+    offset: u8,
+) -> u32 {
+    let original = config.read32(bus, device, function, offset);
+    config.write32(bus, device, function, offset, 0xFFFF_FFFF);
+    let mask = config.read32(bus, device, function, offset);
+    config.write32(bus, device, function, offset, original);
+    mask
+}
+
+/// Device class for a PCI class code
+fn pci_class_to_device_class(class_code: u32) -> DeviceClass {
+    match (class_code >> 16) & 0xFF {
+        0x01 => DeviceClass::BlockDevice,      // Mass storage
+        0x02 => DeviceClass::NetworkInterface, // Network
+        0x03 => DeviceClass::Display,          // Display
+        0x04 => DeviceClass::AudioOutput,      // Multimedia
+        0x07 => DeviceClass::SerialPort,       // Simple comm
+        0x0C => DeviceClass::UsbBus,           // Serial bus
+        _ => DeviceClass::Unknown,
+    }
+}
+
 // =============================================================================
 // DRIVER SUBSYSTEM
 // =============================================================================
@@ -311,6 +733,10 @@ pub struct DriverSubsystem {
     drivers: Vec<RegisteredDriver>,
     next_driver_id: AtomicU64,
 
+    // On-demand loading
+    module_loader: Option<&'static dyn DriverModuleLoader>,
+    requested: Vec<String>,
+
     // Statistics
     devices_discovered: u32,
     devices_active: u32,
@@ -336,6 +762,8 @@ pub fn new() -> Self {
             next_device_id: AtomicU64::new(1),
             drivers: Vec::new(),
             next_driver_id: AtomicU64::new(1),
+            module_loader: None,
+            requested: Vec::new(),
             devices_discovered: 0,
             devices_active: 0,
             drivers_loaded: 0,
@@ -392,79 +820,378 @@ pub fn register_driver(&mut self, mut info: DriverInfo, driver: Box<dyn Driver>)
         id
     }
 
+    /// Load driver modules on demand for unmatched devices
+    pub fn set_module_loader(&mut self, loader: &'static dyn DriverModuleLoader) {
+        self.module_loader = Some(loader);
+    }
+
     /// Probe all devices with registered drivers
     pub fn probe_all(&mut self, ctx: &mut InitContext) -> InitResult<()> {
         ctx.info("Probing devices...");
 
-        for device_idx in 0..self.devices.len() {
-            let device = &self.devices[device_idx];
-            if device.driver_id.is_some() {
-                continue; // Already has driver
+        for id in self.bind_pending() {
+            if let Some(device) = self.get_device(id) {
+                let driver = device
+                    .driver_id
+                    .and_then(|d| self.drivers.iter().find(|r| r.info.id == d))
+                    .map_or("?", |r| r.info.name);
+                ctx.debug(alloc::format!(
+                    "Device '{}' bound to driver '{}'",
+                    device.name,
+                    driver
+                ));
             }
+        }
 
-            // Find matching driver
-            let mut matching_driver = None;
-            for driver_idx in 0..self.drivers.len() {
-                let driver_info = &self.drivers[driver_idx].info;
+        for device in &self.devices {
+            match device.state {
+                DeviceState::Deferred => ctx.warn(alloc::format!(
+                    "Device '{}' probe deferred: dependencies not ready",
+                    device.name
+                )),
+                DeviceState::Error => {
+                    ctx.warn(alloc::format!("Failed to probe device '{}'", device.name))
+                },
+                _ => {},
+            }
+        }
 
-                for match_entry in &driver_info.match_table {
-                    if match_entry.matches(device) {
-                        matching_driver = Some(driver_idx);
-                        break;
-                    }
-                }
+        ctx.info(alloc::format!(
+            "Probed {} devices, {} active",
+            self.devices_discovered,
+            self.devices_active
+        ));
 
-                if matching_driver.is_some() {
-                    break;
+        Ok(())
+    }
+
+    // -------------------------------------------------------------------------
+    // Binding
+    // -------------------------------------------------------------------------
+
+    /// Bind every unbound or deferred device
+    ///
+    /// Deferred devices are retried until a pass binds nothing new, then
+    /// driver modules are requested for unmatched devices and binding
+    /// continues if any were loaded. Returns the devices bound by this call.
+    pub fn bind_pending(&mut self) -> Vec<DeviceId> {
+        let mut bound = Vec::new();
+
+        loop {
+            let mut progress = false;
+            for idx in 0..self.devices.len() {
+                if matches!(
+                    self.devices[idx].state,
+                    DeviceState::Discovered | DeviceState::Deferred
+                ) && self.try_bind(idx)
+                {
+                    bound.push(self.devices[idx].id);
+                    progress = true;
                 }
             }
+            if !progress && !self.load_missing_drivers() {
+                break;
+            }
+        }
 
-            if let Some(driver_idx) = matching_driver {
-                let device = &mut self.devices[device_idx];
-                let registered = &mut self.drivers[driver_idx];
-
-                device.state = DeviceState::Probing;
-
-                match registered.driver.probe(device) {
-                    Ok(true) => {
-                        device.driver_id = Some(registered.info.id);
-                        device.state = DeviceState::Active;
-                        self.devices_active += 1;
-
-                        ctx.debug(alloc::format!(
-                            "Device '{}' bound to driver '{}'",
-                            device.name,
-                            registered.info.name
-                        ));
-                    },
-                    Ok(false) => {
-                        device.state = DeviceState::Discovered;
-                    },
-                    Err(e) => {
-                        device.state = DeviceState::Error;
-                        ctx.warn(alloc::format!(
-                            "Failed to probe device '{}': {:?}",
-                            device.name,
-                            e
-                        ));
-                    },
+        bound
+    }
+
+    /// Request driver modules for devices no driver matches
+    ///
+    /// Returns whether any driver was registered.
+    fn load_missing_drivers(&mut self) -> bool {
+        let Some(loader) = self.module_loader else {
+            return false;
+        };
+
+        let mut wanted: Vec<String> = Vec::new();
+        for device in &self.devices {
+            if device.state != DeviceState::Discovered
+                || self.drivers.iter().any(|r| r.info.matches(device))
+            {
+                continue;
+            }
+            for alias in device.modaliases() {
+                if !self.requested.contains(&alias) && !wanted.contains(&alias) {
+                    wanted.push(alias);
                 }
             }
         }
 
-        ctx.info(alloc::format!(
-            "Probed {} devices, {} active",
-            self.devices_discovered,
-            self.devices_active
-        ));
+        let mut registered = false;
+        for alias in wanted {
+            for (info, driver) in loader.request(&alias) {
+                self.register_driver(info, driver);
+                registered = true;
+            }
+            self.requested.push(alias);
+        }
+        registered
+    }
+
+    /// Probe one device against each matching driver in registration order
+    fn try_bind(&mut self, device_idx: usize) -> bool {
+        let mut deferred = false;
+        let mut failed = false;
+
+        for driver_idx in 0..self.drivers.len() {
+            let info = &self.drivers[driver_idx].info;
+            if !info.matches(&self.devices[device_idx]) {
+                continue;
+            }
+            if !self.requirements_met(&info.requires) {
+                deferred = true;
+                continue;
+            }
+
+            let device = &mut self.devices[device_idx];
+            let registered = &mut self.drivers[driver_idx];
+            device.state = DeviceState::Probing;
+
+            match registered.driver.probe(device) {
+                Ok(true) => {
+                    device.driver_id = Some(registered.info.id);
+                    device.state = DeviceState::Active;
+                    self.devices_active += 1;
+                    return true;
+                },
+                Ok(false) => {},
+                Err(e) if e.kind() == ErrorKind::DependencyNotSatisfied => deferred = true,
+                Err(_) => failed = true,
+            }
+        }
+
+        self.devices[device_idx].state = if deferred {
+            DeviceState::Deferred
+        } else if failed {
+            DeviceState::Error
+        } else {
+            DeviceState::Discovered
+        };
+        false
+    }
+
+    /// Is there an active device of every required class?
+    fn requirements_met(&self, requires: &[DeviceClass]) -> bool {
+        requires
+            .iter()
+            .all(|class| self.devices.iter().any(|d| d.class == *class && d.is_active()))
+    }
+
+    /// Detach a device from its driver, children first
+    ///
+    /// With `force`, a failing `remove` still detaches the device.
+    fn detach(&mut self, id: DeviceId, force: bool) -> InitResult<()> {
+        let device_idx = self
+            .devices
+            .iter()
+            .position(|d| d.id == id)
+            .ok_or_else(|| InitError::new(ErrorKind::NotFound, "Device not found"))?;
+
+        for child in self.devices[device_idx].children.clone() {
+            self.detach(child, force)?;
+        }
 
+        let device = &mut self.devices[device_idx];
+        let Some(driver_id) = device.driver_id else {
+            return Ok(());
+        };
+        if let Some(registered) = self.drivers.iter_mut().find(|r| r.info.id == driver_id) {
+            let result = registered.driver.remove(device);
+            if !force {
+                result?;
+            }
+        }
+
+        device.driver_id = None;
+        device.state = DeviceState::Discovered;
+        self.devices_active -= 1;
+        Ok(())
+    }
+
+    /// Detach devices whose driver's requirements are no longer met
+    fn detach_unsatisfied(&mut self) {
+        loop {
+            let stale = self.devices.iter().find(|d| {
+                d.driver_id
+                    .and_then(|id| self.drivers.iter().find(|r| r.info.id == id))
+                    .is_some_and(|r| !self.requirements_met(&r.info.requires))
+            });
+            let Some(id) = stale.map(|d| d.id) else {
+                break;
+            };
+            let _ = self.detach(id, true);
+            if let Some(device) = self.get_device_mut(id) {
+                device.state = DeviceState::Deferred;
+            }
+        }
+    }
+
+    /// Unbind a device from its driver
+    ///
+    /// Children are unbound first, and devices depending on it through
+    /// driver requirements are deferred.
+    pub fn unbind_device(&mut self, id: DeviceId) -> InitResult<()> {
+        self.detach(id, false)?;
+        self.detach_unsatisfied();
         Ok(())
     }
 
+    /// Devices bound to a driver
+    pub fn devices_of(&self, driver: DriverId) -> Vec<DeviceId> {
+        self.devices
+            .iter()
+            .filter(|d| d.driver_id == Some(driver))
+            .map(|d| d.id)
+            .collect()
+    }
+
+    /// Unregister a driver, unbinding its devices
+    ///
+    /// Returns the devices that were bound to it.
+    pub fn unregister_driver(&mut self, id: DriverId) -> InitResult<Vec<DeviceId>> {
+        let driver_idx = self
+            .drivers
+            .iter()
+            .position(|r| r.info.id == id)
+            .ok_or_else(|| InitError::new(ErrorKind::NotFound, "Driver not found"))?;
+
+        let unbound = self.devices_of(id);
+        for &device in &unbound {
+            let _ = self.detach(device, true);
+        }
+
+        self.drivers.remove(driver_idx);
+        self.drivers_loaded -= 1;
+        self.detach_unsatisfied();
+
+        Ok(unbound)
+    }
+
+    /// Unregister every driver owned by a module
+    ///
+    /// Called by the module loader before unloading `module`. Devices the
+    /// module's drivers were bound to are offered to the remaining drivers;
+    /// returns the devices left without a driver.
+    pub fn unload_module(&mut self, module: u64) -> Vec<DeviceId> {
+        let owned: Vec<DriverId> = self
+            .drivers
+            .iter()
+            .filter(|r| r.info.module == Some(module))
+            .map(|r| r.info.id)
+            .collect();
+
+        let mut unbound = Vec::new();
+        for id in owned {
+            unbound.extend(self.unregister_driver(id).unwrap_or_default());
+        }
+
+        self.bind_pending();
+        unbound.retain(|&id| self.get_device(id).is_some_and(|d| !d.has_driver()));
+        unbound
+    }
+
+    /// Replace a driver in place (hot reload)
+    ///
+    /// The old instance's devices are unbound, the new instance takes over
+    /// the driver ID, and pending devices are probed again. Returns the
+    /// devices bound to the new instance.
+    pub fn reload_driver(
+        &mut self,
+        id: DriverId,
+        mut info: DriverInfo,
+        driver: Box<dyn Driver>,
+    ) -> InitResult<Vec<DeviceId>> {
+        if !self.drivers.iter().any(|r| r.info.id == id) {
+            return Err(InitError::new(ErrorKind::NotFound, "Driver not found"));
+        }
+
+        for device in self.devices_of(id) {
+            let _ = self.detach(device, true);
+        }
+        self.detach_unsatisfied();
+
+        info.id = id;
+        if let Some(registered) = self.drivers.iter_mut().find(|r| r.info.id == id) {
+            *registered = RegisteredDriver { info, driver };
+        }
+
+        self.bind_pending();
+        Ok(self.devices_of(id))
+    }
+
+    /// Register the enabled devices of a hardware description
+    ///
+    /// Nodes without compatible identifiers (buses, containers) are left
+    /// out; a device's parent is its nearest registered ancestor. Returns
+    /// the devices registered.
+    pub fn register_described_devices(&mut self, tree: &DeviceTree) -> Vec<DeviceId> {
+        let mut ids: Vec<(NodeId, DeviceId)> = Vec::new();
+
+        for (node, desc) in tree.nodes() {
+            if desc.compatible.is_empty() || !desc.is_enabled() {
+                continue;
+            }
+            let Some(mut dev) = Device::from_node(tree, node) else {
+                continue;
+            };
+
+            let mut ancestor = desc.parent;
+            while let Some(a) = ancestor {
+                if let Some(&(_, parent)) = ids.iter().find(|(n, _)| *n == a) {
+                    dev.parent = Some(parent);
+                    break;
+                }
+                ancestor = tree.node(a).and_then(|n| n.parent);
+            }
+
+            let parent = dev.parent;
+            let id = self.register_device(dev);
+            if let Some(parent) = parent.and_then(|p| self.get_device_mut(p)) {
+                parent.children.push(id);
+            }
+            ids.push((node, id));
+        }
+
+        ids.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Register the serial ports as ttys
+    fn register_ttys(ctx: &mut InitContext) {
+        let ttys = match devtree::get() {
+            // SAFETY: device registers are used at their physical address,
+            // like the interrupt controllers'
+            Some(tree) => unsafe { uart::probe(tree) },
+            #[cfg(target_arch = "x86_64")]
+            None => uart::probe_legacy(),
+            #[cfg(not(target_arch = "x86_64"))]
+            None => Vec::new(),
+        };
+
+        for tty in ttys {
+            ctx.debug(alloc::format!(
+                "{}: {:?} serial port, {}",
+                tty.name(),
+                tty.port().kind(),
+                tty.irq().map_or(String::from("polled"), |irq| alloc::format!("IRQ {}", irq))
+            ));
+        }
+    }
+
     /// Discover platform devices
+    ///
+    /// Devices come from the firmware's hardware description; legacy x86
+    /// devices at their fixed ports stand in when there is none.
     fn discover_platform_devices(&mut self, ctx: &mut InitContext) {
         ctx.debug("Discovering platform devices...");
 
+        if let Some(tree) = devtree::get() {
+            let found = self.register_described_devices(tree);
+            ctx.debug(alloc::format!("{} devices described by firmware", found.len()));
+            return;
+        }
+
         // Serial ports (x86_64)
         #[cfg(target_arch = "x86_64")]
         {
@@ -532,127 +1259,14 @@ fn discover_platform_devices(&mut self, ctx: &mut InitContext) {
     fn discover_pci_devices(&mut self, ctx: &mut InitContext) {
         ctx.debug("Scanning PCI bus...");
 
-        for bus in 0..=255u8 {
-            for device in 0..32u8 {
-                for function in 0..8u8 {
-                    if let Some(dev) = self.probe_pci_device(bus, device, function) {
-                        ctx.debug(alloc::format!(
-                            "PCI {:02x}:{:02x}.{}: {:04x}:{:04x}",
-                            bus,
-                            device,
-                            function,
-                            dev.vendor_id,
-                            dev.device_id
-                        ));
-                        self.register_device(dev);
-                    }
-                }
-            }
-        }
-    }
-
-    #[cfg(target_arch = "x86_64")]
-    fn probe_pci_device(&self, bus: u8, device: u8, function: u8) -> Option<Device> {
-        let addr = 0x8000_0000u32
-            | ((bus as u32) << 16)
-            | ((device as u32) << 11)
-            | ((function as u32) << 8);
-
-        // Read vendor/device ID
-        let config = self.pci_read_config(addr, 0);
-        let vendor_id = (config & 0xFFFF) as u32;
-        let device_id = ((config >> 16) & 0xFFFF) as u32;
-
-        if vendor_id == 0xFFFF || vendor_id == 0 {
-            return None;
-        }
-
-        // Read class code
-        let class_config = self.pci_read_config(addr, 0x08);
-        let class_code = (class_config >> 8) & 0xFFFFFF;
-        let revision = (class_config & 0xFF) as u8;
-
-        let device_class = Self::pci_class_to_device_class(class_code);
-
-        let mut dev = Device::new(
-            0,
-            alloc::format!("pci-{:04x}:{:04x}", vendor_id, device_id),
-            device_class,
-            BusType::Pci,
-        );
-        dev.vendor_id = vendor_id;
-        dev.device_id = device_id;
-        dev.class_code = class_code;
-        dev.revision = revision;
-
-        // Read BARs
-        for bar_idx in 0..6 {
-            let bar_offset = 0x10 + (bar_idx * 4);
-            let bar = self.pci_read_config(addr, bar_offset);
-
-            if bar != 0 {
-                if (bar & 1) == 0 {
-                    // MMIO
-                    dev.mmio_regions.push(MmioRegion {
-                        base: (bar & !0xF) as u64,
-                        size: 0, // Would need to probe size
-                        flags: bar & 0xF,
-                    });
-                } else {
-                    // I/O port
-                    dev.io_ports.push(IoPortRange {
-                        start: (bar & !0x3) as u16,
-                        count: 0, // Would need to probe size
-                    });
-                }
-            }
-        }
-
-        // Read IRQ
-        let irq_config = self.pci_read_config(addr, 0x3C);
-        let irq = (irq_config & 0xFF) as u32;
-        if irq != 0 && irq != 255 {
-            dev.irqs.push(irq);
-        }
-
-        Some(dev)
-    }
-
-    #[cfg(target_arch = "x86_64")]
-    fn pci_read_config(&self, addr: u32, offset: u8) -> u32 {
-        let config_addr = addr | (offset as u32);
-
-        unsafe {
-            // Write config address
-            core::arch::asm!(
-                "out dx, eax",
-                in("dx") 0xCF8u16,
-                in("eax") config_addr,
-                options(nostack)
-            );
-
-            // Read config data
-            let value: u32;
-            core::arch::asm!(
-                "in eax, dx",
-                out("eax") value,
-                in("dx") 0xCFCu16,
-                options(nostack)
-            );
-            value
-        }
-    }
-
-    #[cfg(target_arch = "x86_64")]
-    fn pci_class_to_device_class(class_code: u32) -> DeviceClass {
-        match (class_code >> 16) & 0xFF {
-            0x01 => DeviceClass::BlockDevice,      // Mass storage
-            0x02 => DeviceClass::NetworkInterface, // Network
-            0x03 => DeviceClass::Display,          // Display
-            0x04 => DeviceClass::AudioOutput,      // Multimedia
-            0x07 => DeviceClass::SerialPort,       // Simple comm
-            0x0C => DeviceClass::UsbBus,           // Serial bus
-            _ => DeviceClass::Unknown,
+        for dev in scan_pci(&mut PortConfigAccess) {
+            ctx.debug(alloc::format!(
+                "PCI {:04x}:{:04x} ({:?})",
+                dev.vendor_id,
+                dev.device_id,
+                dev.class
+            ));
+            self.register_device(dev);
         }
     }
 
@@ -690,6 +1304,7 @@ fn init(&mut self, ctx: &mut InitContext) -> InitResult<()> {
 
         // Discover platform devices
         self.discover_platform_devices(ctx);
+        Self::register_ttys(ctx);
 
         // Discover PCI devices
         #[cfg(target_arch = "x86_64")]
@@ -733,6 +1348,8 @@ fn shutdown(&mut self, ctx: &mut InitContext) -> InitResult<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
+    use helix_devmodel::{Bdf, ConfigAccess, GuestMemory, NvmeModel, PciBus, VirtioBlkModel};
 
     #[test]
     fn test_driver_subsystem() {
@@ -768,5 +1385,257 @@ fn test_device_match() {
 
         let match2 = DeviceMatch::vendor_device(0x8086, 0x5678);
         assert!(!match2.matches(&dev));
+
+        dev.acpi_hid = Some(String::from("PNP0501"));
+        assert!(DeviceMatch::acpi("PNP0501").matches(&dev));
+        assert!(!DeviceMatch::acpi("PNP0303").matches(&dev));
+        assert!(!DeviceMatch::virtio(2).matches(&dev));
+    }
+
+    #[test]
+    fn test_described_devices() {
+        // IO 0x3f8-0x3ff, IRQ 4, end tag
+        let crs = [0x47, 0x01, 0xf8, 0x03, 0xf8, 0x03, 0x01, 0x08, 0x22, 0x10, 0x00, 0x79, 0x00];
+        let mut tree = DeviceTree::new(Source::Acpi);
+        let sb = tree.add_node(tree.root(), "_SB_", Source::Acpi);
+        let pci = tree.add_acpi_device(sb, "PCI0", "PNP0A08", &["PNP0A03"], &[0x79, 0x00]).unwrap();
+        tree.add_acpi_device(pci, "UAR1", "PNP0501", &["PNP0500"], &crs).unwrap();
+
+        let mut sub = DriverSubsystem::new();
+        let ids = sub.register_described_devices(&tree);
+        assert_eq!(ids.len(), 2);
+
+        let uart = sub.get_device(ids[1]).unwrap();
+        assert_eq!(uart.name, "/_SB_/PCI0/UAR1");
+        assert_eq!(uart.bus, BusType::Acpi);
+        assert_eq!(uart.parent, Some(ids[0]));
+        assert_eq!(uart.io_ports[0].start, 0x3f8);
+        assert_eq!(uart.irqs, vec![4]);
+        assert!(DeviceMatch::acpi("PNP0501").matches(uart));
+        assert!(DeviceMatch::compatible("PNP0500").matches(uart));
+        assert!(!DeviceMatch::compatible("arm,pl011").matches(uart));
+        assert_eq!(sub.get_device(ids[0]).unwrap().children, vec![ids[1]]);
+
+        // Devices on an I2C bus sit on the I2C bus type
+        let mut tree = DeviceTree::new(Source::DeviceTree);
+        let i2c = tree.add_node(tree.root(), "i2c@9060000", Source::DeviceTree);
+        tree.set_property(i2c, "compatible", b"snps,designware-i2c\0");
+        let eeprom = tree.add_node(i2c, "eeprom@50", Source::DeviceTree);
+        tree.set_property(eeprom, "compatible", b"atmel,24c02\0");
+        tree.set_property(eeprom, "reg", &0x50u32.to_be_bytes());
+        let ids = sub.register_described_devices(&tree);
+        assert_eq!(sub.get_device(ids[0]).unwrap().bus, BusType::DeviceTree);
+        assert_eq!(sub.get_device(ids[1]).unwrap().bus, BusType::I2c);
+    }
+
+    /// Driver that defers its first `defer` probes
+    struct TestDriver {
+        info: DriverInfo,
+        defer: u32,
+    }
+
+    impl Driver for TestDriver {
+        fn info(&self) -> &DriverInfo {
+            &self.info
+        }
+
+        fn probe(&mut self, _device: &mut Device) -> InitResult<bool> {
+            if self.defer > 0 {
+                self.defer -= 1;
+                return Err(InitError::from_kind(ErrorKind::DependencyNotSatisfied));
+            }
+            Ok(true)
+        }
+
+        fn remove(&mut self, _device: &mut Device) -> InitResult<()> {
+            Ok(())
+        }
+    }
+
+    fn register(sub: &mut DriverSubsystem, info: DriverInfo, defer: u32) -> DriverId {
+        let driver = TestDriver {
+            info: info.clone(),
+            defer,
+        };
+        sub.register_driver(info, Box::new(driver))
+    }
+
+    fn virtio_device(sub: &mut DriverSubsystem, virtio_type: u32) -> DeviceId {
+        let mut dev = Device::new(0, String::from("virtio"), DeviceClass::Unknown, BusType::Pci);
+        dev.virtio_type = Some(virtio_type);
+        sub.register_device(dev)
+    }
+
+    #[test]
+    fn test_deferred_probe() {
+        let mut sub = DriverSubsystem::new();
+        let blk = virtio_device(&mut sub, 2);
+        let net = virtio_device(&mut sub, 1);
+        sub.get_device_mut(net).unwrap().class = DeviceClass::NetworkInterface;
+
+        // Block driver needs a network interface; the net driver defers once
+        let blk_info = DriverInfo::new("vblk", DeviceClass::BlockDevice, BusType::Pci)
+            .with_match(DeviceMatch::virtio(2))
+            .with_requirement(DeviceClass::NetworkInterface);
+        let net_info = DriverInfo::new("vnet", DeviceClass::NetworkInterface, BusType::Pci)
+            .with_match(DeviceMatch::virtio(1));
+        register(&mut sub, blk_info, 0);
+        let vnet = register(&mut sub, net_info, 1);
+
+        assert_eq!(sub.bind_pending(), Vec::<DeviceId>::new());
+        assert_eq!(sub.get_device(blk).unwrap().state, DeviceState::Deferred);
+        assert_eq!(sub.get_device(net).unwrap().state, DeviceState::Deferred);
+
+        assert_eq!(sub.bind_pending(), vec![net, blk]);
+        assert_eq!(sub.stats().devices_active, 2);
+
+        // Losing the dependency defers the dependent again
+        sub.unregister_driver(vnet).unwrap();
+        assert_eq!(sub.get_device(blk).unwrap().state, DeviceState::Deferred);
+        assert_eq!(sub.stats().devices_active, 0);
+    }
+
+    #[test]
+    fn test_module_unload_and_reload() {
+        let mut sub = DriverSubsystem::new();
+        let dev = virtio_device(&mut sub, 2);
+
+        let generic = DriverInfo::new("generic", DeviceClass::BlockDevice, BusType::Pci)
+            .with_match(DeviceMatch::virtio(2));
+        let module = DriverInfo::new("vblk", DeviceClass::BlockDevice, BusType::Pci)
+            .with_match(DeviceMatch::virtio(2))
+            .with_module(7);
+        let vblk = register(&mut sub, module.clone(), 0);
+        let generic = register(&mut sub, generic, 0);
+        sub.bind_pending();
+        assert_eq!(sub.get_device(dev).unwrap().driver_id, Some(vblk));
+
+        // Hot reload keeps the driver ID and rebinds
+        let reloaded = TestDriver {
+            info: module.clone(),
+            defer: 0,
+        };
+        assert_eq!(sub.reload_driver(vblk, module, Box::new(reloaded)).unwrap(), vec![dev]);
+
+        // Unloading hands the device to the remaining driver
+        assert!(sub.unload_module(7).is_empty());
+        assert_eq!(sub.get_device(dev).unwrap().driver_id, Some(generic));
+        assert_eq!(sub.stats().drivers_loaded, 1);
+    }
+
+    /// Loads a virtio-blk driver module for `virtio:d00000002`
+    struct TestModules {
+        requests: core::sync::atomic::AtomicU32,
+    }
+
+    impl DriverModuleLoader for TestModules {
+        fn request(&self, modalias: &str) -> Vec<(DriverInfo, Box<dyn Driver>)> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            if modalias != "virtio:d00000002" {
+                return Vec::new();
+            }
+            let info = DriverInfo::new("vblk", DeviceClass::BlockDevice, BusType::Pci)
+                .with_match(DeviceMatch::virtio(2))
+                .with_module(9);
+            let driver = TestDriver {
+                info: info.clone(),
+                defer: 0,
+            };
+            vec![(info, Box::new(driver) as Box<dyn Driver>)]
+        }
+    }
+
+    #[test]
+    fn test_on_demand_loading() {
+        static MODULES: TestModules = TestModules {
+            requests: core::sync::atomic::AtomicU32::new(0),
+        };
+
+        let mut dev = Device::new(0, String::from("nic"), DeviceClass::Unknown, BusType::Pci);
+        (dev.vendor_id, dev.device_id, dev.class_code) = (0x1AF4, 0x1041, 0x020000);
+        assert_eq!(
+            dev.modaliases(),
+            vec!["pci:v00001AF4d00001041sv00000000sd00000000bc02sc00i00"]
+        );
+        dev.acpi_hid = Some(String::from("PNP0501"));
+        dev.compatible = vec![String::from("PNP0501"), String::from("PNP0500")];
+        assert_eq!(dev.modaliases()[1..], ["acpi:PNP0501", "acpi:PNP0500"]);
+
+        let mut sub = DriverSubsystem::new();
+        sub.set_module_loader(&MODULES);
+        let blk = virtio_device(&mut sub, 2);
+        let rng = virtio_device(&mut sub, 4);
+
+        assert_eq!(sub.bind_pending(), vec![blk]);
+        assert_eq!(sub.get_device(rng).unwrap().state, DeviceState::Discovered);
+        assert_eq!(MODULES.requests.load(Ordering::SeqCst), 2);
+
+        // Unloading does not load the module again, nor retry the miss
+        assert_eq!(sub.unload_module(9), vec![blk]);
+        assert_eq!(MODULES.requests.load(Ordering::SeqCst), 2);
+    }
+
+    /// Configuration access through the emulated bus
+    impl PciConfigAccess for PciBus {
+        fn read32(&mut self, bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+            ConfigAccess::read32(self, Bdf::new(bus, device, function), offset as u16)
+        }
+
+        fn write32(&mut self, bus: u8, device: u8, function: u8, offset: u8, value: u32) {
+            ConfigAccess::write32(self, Bdf::new(bus, device, function), offset as u16, value);
+        }
+    }
+
+    #[test]
+    fn test_pci_scan() {
+        let nvme = Bdf::new(0, 3, 0);
+        let blk = Bdf::new(1, 0, 0);
+        let mut bus = PciBus::new(GuestMemory::new(0x10_0000, 1 << 20));
+        bus.attach(nvme, NvmeModel::new(64)).unwrap();
+        bus.attach(blk, VirtioBlkModel::new(64)).unwrap();
+
+        // As assigned by firmware
+        ConfigAccess::write32(&mut bus, nvme, 0x10, 0xFEB0_0000);
+        ConfigAccess::write32(&mut bus, nvme, 0x14, 0x1);
+        ConfigAccess::write32(&mut bus, nvme, 0x04, 0x6);
+        ConfigAccess::write32(&mut bus, blk, 0x10, 0xC000);
+        ConfigAccess::write32(&mut bus, blk, 0x3C, 11);
+
+        let devices = scan_pci(&mut bus);
+        assert_eq!(devices.len(), 2);
+
+        let dev = &devices[0];
+        assert_eq!((dev.vendor_id, dev.device_id), (0x1B36, 0x0010));
+        assert_eq!(dev.class, DeviceClass::BlockDevice);
+        assert_eq!(dev.mmio_regions.len(), 1);
+        assert_eq!(dev.mmio_regions[0].base, 0x1_FEB0_0000);
+        assert_eq!(dev.mmio_regions[0].size, 0x4000);
+        assert!(dev.io_ports.is_empty());
+
+        let dev = &devices[1];
+        assert_eq!((dev.vendor_id, dev.device_id), (0x1AF4, 0x1001));
+        assert_eq!(dev.io_ports.len(), 1);
+        assert_eq!(
+            (dev.io_ports[0].start, dev.io_ports[0].count),
+            (0xC000, 0x80)
+        );
+        assert_eq!(dev.irqs, vec![11]);
+
+        // Sizing leaves the BARs and decoding as firmware set them
+        assert_eq!(ConfigAccess::read32(&bus, nvme, 0x10), 0xFEB0_0004);
+        assert_eq!(ConfigAccess::read32(&bus, nvme, 0x14), 0x1);
+        assert_eq!(ConfigAccess::read16(&bus, nvme, 0x04), 0x6);
+    }
+
+    #[test]
+    fn test_pci_unassigned_bar() {
+        let bdf = Bdf::new(0, 2, 0);
+        let mut bus = PciBus::new(GuestMemory::new(0x10_0000, 1 << 20));
+        bus.attach(bdf, VirtioBlkModel::new(8)).unwrap();
+
+        let dev = probe_pci_function(&mut bus, 0, 2, 0).unwrap();
+        assert!(dev.io_ports.is_empty());
+        assert!(dev.irqs.is_empty());
+        assert!(probe_pci_function(&mut bus, 0, 2, 1).is_none());
     }
 }
@@ -3,6 +3,7 @@
 //! Virtual Filesystem (VFS) and filesystem driver initialization.
 //! Late phase subsystem for filesystem support.
 
+use super::initramfs::{rdinit, unpack, EarlyRoot, TmpFs};
 use crate::context::InitContext;
 use crate::error::{ErrorKind, InitError, InitResult};
 use crate::phase::{InitPhase, PhaseCapabilities};
@@ -12,6 +13,7 @@
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU64, Ordering};
 
@@ -505,7 +507,7 @@ pub fn unmount(&mut self, path: &str) -> InitResult<()> {
     }
 
     /// Find mount point for path
-    fn find_mount(&self, path: &str) -> Option<(usize, &str)> {
+    fn find_mount<'a>(&self, path: &'a str) -> Option<(usize, &'a str)> {
         let mut best_match = None;
         let mut best_len = 0;
 
@@ -662,6 +664,152 @@ pub fn mkdir(&mut self, path: &str, mode: FileMode) -> InitResult<()> {
         Ok(())
     }
 
+    /// Remove the mount at `idx`, keeping open file mount indices valid
+    fn remove_mount(&mut self, idx: usize) -> MountPoint {
+        for file in self.open_files.values_mut() {
+            if file.mount_idx > idx {
+                file.mount_idx -= 1;
+            }
+        }
+        self.mounts.remove(idx)
+    }
+
+    /// Root mount and the mount at `new_root`, checked for a root switch
+    fn root_switch(&self, new_root: &str) -> InitResult<(usize, usize)> {
+        let old_idx = self
+            .mounts
+            .iter()
+            .position(|m| m.path == "/")
+            .ok_or_else(|| InitError::new(ErrorKind::NotFound, "No root mounted"))?;
+        let new_idx = self
+            .mounts
+            .iter()
+            .position(|m| m.path == new_root)
+            .ok_or_else(|| InitError::new(ErrorKind::NotFound, "New root is not a mount point"))?;
+        if new_idx == old_idx {
+            return Err(InitError::new(
+                ErrorKind::InvalidArgument,
+                "Already the root",
+            ));
+        }
+        Ok((old_idx, new_idx))
+    }
+
+    /// Path of `path` once `new_root` is the root, if it is beneath it
+    fn under_new_root(path: &str, new_root: &str) -> Option<String> {
+        if path == new_root {
+            return Some(String::from("/"));
+        }
+        path.strip_prefix(new_root)
+            .filter(|rest| rest.starts_with('/'))
+            .map(String::from)
+    }
+
+    /// Make the filesystem mounted at `new_root` the root, dropping the old one
+    ///
+    /// As `switch_root(8)` does when leaving an initramfs: the old root is
+    /// unmounted, which frees its memory, mounts beneath `new_root` move up,
+    /// and the other mounts (`/dev`, `/proc`, ...) keep their paths on the
+    /// new root. Fails while a file of the old root is open.
+    pub fn switch_root(&mut self, new_root: &str) -> InitResult<()> {
+        let new_root = new_root.trim_end_matches('/');
+        let (old_idx, _) = self.root_switch(new_root)?;
+        if self.open_files.values().any(|f| f.mount_idx == old_idx) {
+            return Err(InitError::new(ErrorKind::ResourceBusy, "Old root busy"));
+        }
+
+        let paths: Vec<String> = self
+            .mounts
+            .iter()
+            .map(|m| Self::under_new_root(&m.path, new_root).unwrap_or_else(|| m.path.clone()))
+            .collect();
+        for (idx, path) in paths.iter().enumerate() {
+            if idx != old_idx
+                && paths
+                    .iter()
+                    .enumerate()
+                    .any(|(other, p)| other != old_idx && other != idx && p == path)
+            {
+                return Err(InitError::new(
+                    ErrorKind::AlreadyExists,
+                    "Mount point taken on the new root",
+                )
+                .with_details(path.clone()));
+            }
+        }
+        for (mount, path) in self.mounts.iter_mut().zip(paths) {
+            mount.path = path;
+        }
+
+        let mut old = self.remove_mount(old_idx);
+        old.filesystem.unmount()
+    }
+
+    /// Make the filesystem mounted at `new_root` the root, keeping the old
+    /// one at `put_old`
+    ///
+    /// As `pivot_root(2)`: `put_old` must be a directory beneath `new_root`;
+    /// the old root and the mounts outside `new_root` move under it.
+    pub fn pivot_root(&mut self, new_root: &str, put_old: &str) -> InitResult<()> {
+        let new_root = new_root.trim_end_matches('/');
+        self.root_switch(new_root)?;
+        let old_path = Self::under_new_root(put_old.trim_end_matches('/'), new_root)
+            .filter(|p| p != "/")
+            .ok_or_else(|| {
+                InitError::new(
+                    ErrorKind::InvalidArgument,
+                    "put_old is not beneath the new root",
+                )
+            })?;
+        if self.stat(put_old)?.file_type != FileType::Directory {
+            return Err(InitError::new(
+                ErrorKind::InvalidArgument,
+                "put_old is not a directory",
+            ));
+        }
+
+        for mount in &mut self.mounts {
+            mount.path = match Self::under_new_root(&mount.path, new_root) {
+                Some(path) => path,
+                None if mount.path == "/" => old_path.clone(),
+                None => alloc::format!("{}{}", old_path, mount.path),
+            };
+        }
+        Ok(())
+    }
+
+    /// Unpack an initramfs and mount it as the root
+    ///
+    /// Registers an [`EarlyRoot`] service naming the program userland
+    /// should run first (`rdinit=`, `/init` by default), if the archive
+    /// holds it as an executable file.
+    pub fn mount_initramfs(&mut self, ctx: &mut InitContext, archive: &[u8]) -> InitResult<()> {
+        let mut fs = TmpFs::new();
+        let stats = unpack(&mut fs, archive)?;
+
+        let cmdline = ctx.boot_info().and_then(|info| info.cmdline.clone());
+        let path = rdinit(cmdline.as_deref());
+        let init = fs
+            .lookup_path(path)
+            .and_then(|ino| fs.stat(ino))
+            .map(|inode| inode.file_type == FileType::Regular && inode.mode & 0o111 != 0)
+            .unwrap_or(false)
+            .then(|| String::from(path));
+
+        self.mount("/", None, "rootfs", Box::new(fs), "")?;
+        ctx.info(alloc::format!(
+            "initramfs: {} files, {} directories, {} links, {} bytes",
+            stats.files,
+            stats.dirs,
+            stats.symlinks,
+            stats.bytes
+        ));
+        if init.is_none() {
+            ctx.warn(alloc::format!("initramfs has no executable {}", path));
+        }
+        ctx.register_service(Arc::new(EarlyRoot { init, stats }))
+    }
+
     /// Sync all filesystems
     pub fn sync_all(&mut self) -> InitResult<()> {
         for mount in &mut self.mounts {
@@ -719,6 +867,15 @@ fn init(&mut self, ctx: &mut InitContext) -> InitResult<()> {
             self.fs_types
         ));
 
+        // The initrd loaded by the bootloader becomes the early root; /init
+        // switches to the real root once it is reachable
+        let initrd = ctx
+            .boot_info()
+            .and_then(|info| info.custom.get("initrd").cloned());
+        if let Some(archive) = initrd {
+            self.mount_initramfs(ctx, &archive)?;
+        }
+
         // In real kernel: mount root filesystem
         // self.mount("/", Some(root_dev), "ext4", Box::new(Ext4Fs::new()), "")?;
 
@@ -797,4 +954,57 @@ fn test_open_file_seek() {
         assert_eq!(file.seek(SeekFrom::Current(50), 1000).unwrap(), 150);
         assert_eq!(file.seek(SeekFrom::End(-100), 1000).unwrap(), 900);
     }
+
+    /// Early root with `/init` and a mounted `/newroot` holding `/sbin`
+    /// and `/oldroot`, plus a `/dev` mount
+    fn early_vfs() -> FilesystemSubsystem {
+        let mut early = TmpFs::new();
+        let root = early.root().unwrap();
+        early.create(root, "init", 0o755).unwrap();
+        let mut real = TmpFs::new();
+        let root = real.root().unwrap();
+        real.mkdir(root, "sbin", 0o755).unwrap();
+        real.mkdir(root, "oldroot", 0o755).unwrap();
+
+        let mut vfs = FilesystemSubsystem::new();
+        vfs.mount("/", None, "rootfs", Box::new(early), "").unwrap();
+        vfs.mount("/newroot", None, "tmpfs", Box::new(real), "")
+            .unwrap();
+        vfs.mount("/dev", None, "tmpfs", Box::new(TmpFs::new()), "")
+            .unwrap();
+        vfs
+    }
+
+    fn mount_paths(vfs: &FilesystemSubsystem) -> Vec<String> {
+        vfs.mounts().into_iter().map(|m| m.path).collect()
+    }
+
+    #[test]
+    fn test_switch_root() {
+        let mut vfs = early_vfs();
+        let fd = vfs.open("/init", OpenFlags::READ).unwrap();
+        assert!(vfs.switch_root("/newroot").is_err());
+        vfs.close(fd).unwrap();
+
+        vfs.switch_root("/newroot/").unwrap();
+        assert_eq!(mount_paths(&vfs), ["/", "/dev"]);
+        assert_eq!(vfs.stat("/sbin").unwrap().file_type, FileType::Directory);
+        assert!(vfs.stat("/init").is_err());
+        assert!(vfs.switch_root("/").is_err());
+    }
+
+    #[test]
+    fn test_pivot_root() {
+        let mut vfs = early_vfs();
+        assert!(vfs.pivot_root("/newroot", "/elsewhere").is_err());
+        assert!(vfs.pivot_root("/newroot", "/newroot/missing").is_err());
+
+        vfs.pivot_root("/newroot", "/newroot/oldroot").unwrap();
+        assert_eq!(mount_paths(&vfs), ["/oldroot", "/", "/oldroot/dev"]);
+        assert_eq!(
+            vfs.stat("/oldroot/init").unwrap().file_type,
+            FileType::Regular
+        );
+        assert_eq!(vfs.stat("/sbin").unwrap().file_type, FileType::Directory);
+    }
 }
@@ -0,0 +1,870 @@
+//! # Initramfs
+//!
+//! Early root filesystem unpacked from the initrd loaded by the bootloader.
+//!
+//! - [`CpioReader`]: `newc` cpio archive parser (concatenated archives
+//!   included, as produced by `cat early.cpio main.cpio`)
+//! - [`TmpFs`]: memory-backed filesystem the archive is extracted into
+//! - [`unpack`]: extraction, with parent directories created on demand and
+//!   hard links preserved
+//! - [`EarlyRoot`]: service telling userland which `/init` to run from it
+//!
+//! The filesystem subsystem mounts the tmpfs as `/`; once drivers are up,
+//! `/init` mounts the real root and switches to it with
+//! [`FilesystemSubsystem::switch_root`](super::filesystem::FilesystemSubsystem::switch_root).
+
+use super::filesystem::{
+    DeviceNum, DirEntry, FileMode, FileSystemOps, FileType, FsStats, Inode, InodeNum,
+};
+use crate::context::Service;
+use crate::error::{ErrorKind, InitError, InitResult};
+
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+
+// =============================================================================
+// CPIO (NEWC)
+// =============================================================================
+
+/// `newc` magic
+const MAGIC_NEWC: &[u8; 6] = b"070701";
+
+/// `newc` magic with checksums
+const MAGIC_CRC: &[u8; 6] = b"070702";
+
+/// Header size: magic and 13 eight-digit hex fields
+const HEADER_LEN: usize = 110;
+
+/// Name of the entry ending an archive
+const TRAILER: &str = "TRAILER!!!";
+
+/// File type mask of a mode
+const S_IFMT: u32 = 0o170000;
+
+/// Regular file type bits
+const S_IFREG: u32 = 0o100000;
+
+/// Directory type bits
+const S_IFDIR: u32 = 0o040000;
+
+/// Symbolic link type bits
+const S_IFLNK: u32 = 0o120000;
+
+/// Archive entry
+#[derive(Debug, Clone, Copy)]
+pub struct CpioEntry<'a> {
+    /// Inode number in the archive (identifies hard links)
+    pub ino: u32,
+    /// Mode, file type included
+    pub mode: u32,
+    /// Owner
+    pub uid: u32,
+    /// Group
+    pub gid: u32,
+    /// Link count
+    pub nlink: u32,
+    /// Modification time (seconds)
+    pub mtime: u32,
+    /// Device of the archived file (identifies hard links)
+    pub dev: DeviceNum,
+    /// Device number, for device nodes
+    pub rdev: DeviceNum,
+    /// Path, as stored
+    pub name: &'a str,
+    /// File contents, or the target of a symbolic link
+    pub data: &'a [u8],
+}
+
+impl CpioEntry<'_> {
+    /// File type
+    pub fn file_type(&self) -> FileType {
+        FileType::from(self.mode)
+    }
+}
+
+/// Device number from its major and minor parts
+pub fn makedev(major: u32, minor: u32) -> DeviceNum {
+    ((major as u64) << 32) | minor as u64
+}
+
+/// Iterator over the entries of a `newc` archive
+pub struct CpioReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    failed: bool,
+}
+
+impl<'a> CpioReader<'a> {
+    /// Read entries from `data`
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            failed: false,
+        }
+    }
+
+    fn corrupt(&mut self, details: &str) -> InitError {
+        self.failed = true;
+        InitError::new(ErrorKind::DataCorruption, "Malformed cpio archive")
+            .with_details(alloc::format!("{} at offset {}", details, self.pos))
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    /// Skip padding to the next 4-byte boundary
+    fn align(&mut self) {
+        self.pos = (self.pos + 3) & !3;
+    }
+
+    fn field(header: &[u8], index: usize) -> Option<u32> {
+        let digits = core::str::from_utf8(&header[6 + index * 8..14 + index * 8]).ok()?;
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    fn entry(&mut self) -> InitResult<Option<CpioEntry<'a>>> {
+        // Archives may be concatenated, with zero padding in between
+        while self.data.get(self.pos) == Some(&0) {
+            self.pos += 1;
+        }
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+
+        let header = match self.take(HEADER_LEN) {
+            Some(header) => header,
+            None => return Err(self.corrupt("truncated header")),
+        };
+        if &header[..6] != MAGIC_NEWC && &header[..6] != MAGIC_CRC {
+            return Err(self.corrupt("bad magic"));
+        }
+        let mut fields = [0u32; 13];
+        for (index, field) in fields.iter_mut().enumerate() {
+            *field = match Self::field(header, index) {
+                Some(value) => value,
+                None => return Err(self.corrupt("bad header field")),
+            };
+        }
+        let [ino, mode, uid, gid, nlink, mtime, size, dev_major, dev_minor, rdev_major, rdev_minor, name_size, _check] =
+            fields;
+
+        // The name is NUL-terminated and padded together with the header
+        let name = match self.take(name_size as usize) {
+            Some(name) if name.last() == Some(&0) => &name[..name.len() - 1],
+            _ => return Err(self.corrupt("bad name")),
+        };
+        self.align();
+        let name = match core::str::from_utf8(name) {
+            Ok(name) => name,
+            Err(_) => return Err(self.corrupt("name is not UTF-8")),
+        };
+        let data = match self.take(size as usize) {
+            Some(data) => data,
+            None => return Err(self.corrupt("truncated data")),
+        };
+        self.align();
+
+        Ok(Some(CpioEntry {
+            ino,
+            mode,
+            uid,
+            gid,
+            nlink,
+            mtime,
+            dev: makedev(dev_major, dev_minor),
+            rdev: makedev(rdev_major, rdev_minor),
+            name,
+            data,
+        }))
+    }
+}
+
+impl<'a> Iterator for CpioReader<'a> {
+    type Item = InitResult<CpioEntry<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.failed {
+                return None;
+            }
+            match self.entry() {
+                Ok(Some(entry)) if entry.name == TRAILER => continue,
+                Ok(Some(entry)) => return Some(Ok(entry)),
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+// =============================================================================
+// TMPFS
+// =============================================================================
+
+/// Root inode of a [`TmpFs`]
+pub const TMPFS_ROOT: InodeNum = 1;
+
+/// A tmpfs inode
+struct TmpNode {
+    inode: Inode,
+    /// File contents or symbolic link target
+    data: Vec<u8>,
+    /// Directory entries
+    entries: BTreeMap<String, InodeNum>,
+}
+
+/// Memory-backed filesystem
+pub struct TmpFs {
+    nodes: BTreeMap<InodeNum, TmpNode>,
+    next_ino: InodeNum,
+}
+
+impl TmpFs {
+    /// Create a tmpfs holding an empty root directory
+    pub fn new() -> Self {
+        let mut fs = Self {
+            nodes: BTreeMap::new(),
+            next_ino: TMPFS_ROOT,
+        };
+        let root = fs.alloc(S_IFDIR | 0o755, 0);
+        fs.node_mut(root).inode.nlink = 2;
+        fs
+    }
+
+    fn alloc(&mut self, mode: FileMode, rdev: DeviceNum) -> InodeNum {
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        let inode = Inode {
+            ino,
+            file_type: FileType::from(mode),
+            mode,
+            nlink: 1,
+            rdev,
+            ..Inode::default()
+        };
+        self.nodes.insert(ino, TmpNode {
+            inode,
+            data: Vec::new(),
+            entries: BTreeMap::new(),
+        });
+        ino
+    }
+
+    fn node(&self, ino: InodeNum) -> InitResult<&TmpNode> {
+        self.nodes
+            .get(&ino)
+            .ok_or(InitError::new(ErrorKind::NotFound, "No such inode"))
+    }
+
+    fn node_mut(&mut self, ino: InodeNum) -> &mut TmpNode {
+        self.nodes.get_mut(&ino).expect("tmpfs inode")
+    }
+
+    fn dir(&self, ino: InodeNum) -> InitResult<&TmpNode> {
+        let node = self.node(ino)?;
+        if node.inode.file_type != FileType::Directory {
+            return Err(InitError::new(
+                ErrorKind::InvalidArgument,
+                "Not a directory",
+            ));
+        }
+        Ok(node)
+    }
+
+    /// Add `name` to directory `parent`, pointing at a new inode
+    fn add(
+        &mut self,
+        parent: InodeNum,
+        name: &str,
+        mode: FileMode,
+        rdev: DeviceNum,
+    ) -> InitResult<InodeNum> {
+        if self.dir(parent)?.entries.contains_key(name) {
+            return Err(InitError::new(ErrorKind::AlreadyExists, "File exists"));
+        }
+        if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            return Err(InitError::new(
+                ErrorKind::InvalidArgument,
+                "Invalid file name",
+            ));
+        }
+        let ino = self.alloc(mode, rdev);
+        self.node_mut(parent)
+            .entries
+            .insert(String::from(name), ino);
+        Ok(ino)
+    }
+
+    /// Drop one link to `ino`, freeing it with the last one
+    fn release(&mut self, ino: InodeNum) {
+        let node = self.node_mut(ino);
+        node.inode.nlink = node.inode.nlink.saturating_sub(1);
+        if node.inode.nlink == 0 {
+            self.nodes.remove(&ino);
+        }
+    }
+
+    /// Create a symbolic link to `target`
+    pub fn symlink(&mut self, parent: InodeNum, name: &str, target: &str) -> InitResult<InodeNum> {
+        let ino = self.add(parent, name, S_IFLNK | 0o777, 0)?;
+        let node = self.node_mut(ino);
+        node.data.extend_from_slice(target.as_bytes());
+        node.inode.size = target.len() as u64;
+        Ok(ino)
+    }
+
+    /// Target of a symbolic link
+    pub fn readlink(&self, ino: InodeNum) -> InitResult<String> {
+        let node = self.node(ino)?;
+        if node.inode.file_type != FileType::Symlink {
+            return Err(InitError::new(
+                ErrorKind::InvalidArgument,
+                "Not a symbolic link",
+            ));
+        }
+        Ok(String::from_utf8_lossy(&node.data).into_owned())
+    }
+
+    /// Create a device node, FIFO or socket; `mode` includes the file type
+    pub fn mknod(
+        &mut self,
+        parent: InodeNum,
+        name: &str,
+        mode: FileMode,
+        rdev: DeviceNum,
+    ) -> InitResult<InodeNum> {
+        match FileType::from(mode) {
+            FileType::CharDevice | FileType::BlockDevice | FileType::Fifo | FileType::Socket => {
+                self.add(parent, name, mode, rdev)
+            },
+            _ => Err(InitError::new(
+                ErrorKind::InvalidArgument,
+                "Not a special file type",
+            )),
+        }
+    }
+
+    /// Add a hard link to the non-directory `ino`
+    pub fn link(&mut self, parent: InodeNum, name: &str, ino: InodeNum) -> InitResult<()> {
+        if self.node(ino)?.inode.file_type == FileType::Directory {
+            return Err(InitError::new(
+                ErrorKind::PermissionDenied,
+                "Hard link to directory",
+            ));
+        }
+        if self.dir(parent)?.entries.contains_key(name) {
+            return Err(InitError::new(ErrorKind::AlreadyExists, "File exists"));
+        }
+        self.node_mut(parent)
+            .entries
+            .insert(String::from(name), ino);
+        self.node_mut(ino).inode.nlink += 1;
+        Ok(())
+    }
+
+    /// Set permission bits, owner and modification time
+    pub fn set_attr(
+        &mut self,
+        ino: InodeNum,
+        mode: FileMode,
+        uid: u32,
+        gid: u32,
+        mtime: u64,
+    ) -> InitResult<()> {
+        self.node(ino)?;
+        let inode = &mut self.node_mut(ino).inode;
+        inode.mode = (inode.mode & S_IFMT) | (mode & !S_IFMT);
+        inode.uid = uid;
+        inode.gid = gid;
+        inode.mtime = mtime;
+        inode.ctime = mtime;
+        Ok(())
+    }
+
+    /// Resolve an absolute path without following symbolic links
+    pub fn lookup_path(&self, path: &str) -> InitResult<InodeNum> {
+        path.split('/')
+            .filter(|c| !c.is_empty() && *c != ".")
+            .try_fold(TMPFS_ROOT, |ino, name| self.lookup(ino, name))
+    }
+
+    /// Bytes held by file contents
+    pub fn used_bytes(&self) -> u64 {
+        self.nodes.values().map(|n| n.data.len() as u64).sum()
+    }
+}
+
+impl Default for TmpFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystemOps for TmpFs {
+    fn name(&self) -> &str {
+        "tmpfs"
+    }
+
+    fn mount(&mut self, _device: Option<DeviceNum>, _options: &str) -> InitResult<()> {
+        Ok(())
+    }
+
+    fn unmount(&mut self) -> InitResult<()> {
+        // Contents live only as long as the mount
+        self.nodes.retain(|&ino, _| ino == TMPFS_ROOT);
+        self.node_mut(TMPFS_ROOT).entries.clear();
+        Ok(())
+    }
+
+    fn root(&self) -> InitResult<InodeNum> {
+        Ok(TMPFS_ROOT)
+    }
+
+    fn lookup(&self, parent: InodeNum, name: &str) -> InitResult<InodeNum> {
+        self.dir(parent)?
+            .entries
+            .get(name)
+            .copied()
+            .ok_or(InitError::new(
+                ErrorKind::NotFound,
+                "No such file or directory",
+            ))
+    }
+
+    fn stat(&self, ino: InodeNum) -> InitResult<Inode> {
+        Ok(self.node(ino)?.inode.clone())
+    }
+
+    fn readdir(&self, ino: InodeNum, offset: u64) -> InitResult<Vec<DirEntry>> {
+        Ok(self
+            .dir(ino)?
+            .entries
+            .iter()
+            .skip(offset as usize)
+            .map(|(name, &ino)| DirEntry {
+                ino,
+                name: name.clone(),
+                file_type: self.nodes[&ino].inode.file_type,
+            })
+            .collect())
+    }
+
+    fn read(&self, ino: InodeNum, offset: u64, size: usize) -> InitResult<Vec<u8>> {
+        let node = self.node(ino)?;
+        if node.inode.file_type != FileType::Regular {
+            return Err(InitError::new(
+                ErrorKind::InvalidArgument,
+                "Not a regular file",
+            ));
+        }
+        let start = (offset as usize).min(node.data.len());
+        let end = start.saturating_add(size).min(node.data.len());
+        Ok(node.data[start..end].to_vec())
+    }
+
+    fn write(&mut self, ino: InodeNum, offset: u64, data: &[u8]) -> InitResult<usize> {
+        if self.node(ino)?.inode.file_type != FileType::Regular {
+            return Err(InitError::new(
+                ErrorKind::InvalidArgument,
+                "Not a regular file",
+            ));
+        }
+        let node = self.node_mut(ino);
+        let end = offset as usize + data.len();
+        if node.data.len() < end {
+            node.data.resize(end, 0);
+        }
+        node.data[offset as usize..end].copy_from_slice(data);
+        node.inode.size = node.data.len() as u64;
+        node.inode.blocks = node.inode.size.div_ceil(512);
+        Ok(data.len())
+    }
+
+    fn create(&mut self, parent: InodeNum, name: &str, mode: FileMode) -> InitResult<InodeNum> {
+        self.add(parent, name, S_IFREG | (mode & !S_IFMT), 0)
+    }
+
+    fn mkdir(&mut self, parent: InodeNum, name: &str, mode: FileMode) -> InitResult<InodeNum> {
+        let ino = self.add(parent, name, S_IFDIR | (mode & !S_IFMT), 0)?;
+        self.node_mut(ino).inode.nlink = 2;
+        self.node_mut(parent).inode.nlink += 1;
+        Ok(ino)
+    }
+
+    fn unlink(&mut self, parent: InodeNum, name: &str) -> InitResult<()> {
+        let ino = self.lookup(parent, name)?;
+        if self.nodes[&ino].inode.file_type == FileType::Directory {
+            return Err(InitError::new(ErrorKind::InvalidArgument, "Is a directory"));
+        }
+        self.node_mut(parent).entries.remove(name);
+        self.release(ino);
+        Ok(())
+    }
+
+    fn rmdir(&mut self, parent: InodeNum, name: &str) -> InitResult<()> {
+        let ino = self.lookup(parent, name)?;
+        if !self.dir(ino)?.entries.is_empty() {
+            return Err(InitError::new(
+                ErrorKind::ResourceBusy,
+                "Directory not empty",
+            ));
+        }
+        self.node_mut(parent).entries.remove(name);
+        self.node_mut(parent).inode.nlink -= 1;
+        self.nodes.remove(&ino);
+        Ok(())
+    }
+
+    fn rename(
+        &mut self,
+        old_parent: InodeNum,
+        old_name: &str,
+        new_parent: InodeNum,
+        new_name: &str,
+    ) -> InitResult<()> {
+        let ino = self.lookup(old_parent, old_name)?;
+        self.dir(new_parent)?;
+        if let Ok(existing) = self.lookup(new_parent, new_name) {
+            if existing == ino {
+                return Ok(());
+            }
+            if self.nodes[&existing].inode.file_type == FileType::Directory {
+                self.rmdir(new_parent, new_name)?;
+            } else {
+                self.unlink(new_parent, new_name)?;
+            }
+        }
+        self.node_mut(old_parent).entries.remove(old_name);
+        self.node_mut(new_parent)
+            .entries
+            .insert(String::from(new_name), ino);
+        if self.nodes[&ino].inode.file_type == FileType::Directory {
+            self.node_mut(old_parent).inode.nlink -= 1;
+            self.node_mut(new_parent).inode.nlink += 1;
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> InitResult<()> {
+        Ok(())
+    }
+
+    fn statfs(&self) -> InitResult<FsStats> {
+        Ok(FsStats {
+            block_size: 4096,
+            total_blocks: self.used_bytes().div_ceil(4096),
+            total_inodes: self.nodes.len() as u64,
+            fs_type: 0x0102_1994, // TMPFS_MAGIC
+            max_name_len: 255,
+            ..FsStats::default()
+        })
+    }
+}
+
+// =============================================================================
+// EXTRACTION
+// =============================================================================
+
+/// What [`unpack`] created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnpackStats {
+    /// Regular files, hard links included
+    pub files: usize,
+    /// Directories
+    pub dirs: usize,
+    /// Symbolic links
+    pub symlinks: usize,
+    /// Device nodes, FIFOs and sockets
+    pub nodes: usize,
+    /// Bytes of file contents
+    pub bytes: u64,
+}
+
+/// Directory `path` of `fs`, created (mode 0755) along with its parents
+fn make_dirs(fs: &mut TmpFs, path: &str) -> InitResult<InodeNum> {
+    let mut ino = TMPFS_ROOT;
+    for name in path.split('/').filter(|c| !c.is_empty()) {
+        ino = match fs.lookup(ino, name) {
+            Ok(child) => child,
+            Err(_) => fs.mkdir(ino, name, 0o755)?,
+        };
+    }
+    fs.dir(ino)?;
+    Ok(ino)
+}
+
+/// Extract a `newc` archive into `fs`
+///
+/// Later entries replace earlier ones of the same path, so an archive
+/// appended to another overrides its files. Directories that already
+/// exist only have their attributes updated.
+pub fn unpack(fs: &mut TmpFs, archive: &[u8]) -> InitResult<UnpackStats> {
+    let mut stats = UnpackStats::default();
+    // Archive (dev, ino) to tmpfs inode, for hard links
+    let mut links: BTreeMap<(DeviceNum, u32), InodeNum> = BTreeMap::new();
+
+    for entry in CpioReader::new(archive) {
+        let entry = entry?;
+        let path = entry.name.trim_start_matches("./").trim_matches('/');
+        if path.is_empty() || path == "." {
+            continue;
+        }
+        if path.split('/').any(|c| c == "..") {
+            return Err(InitError::new(
+                ErrorKind::PermissionDenied,
+                "Path escapes the archive root",
+            )
+            .with_details(String::from(path)));
+        }
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let parent = make_dirs(fs, dir)?;
+        let file_type = entry.file_type();
+
+        // Replace what is there, unless both are directories
+        if let Ok(existing) = fs.lookup(parent, name) {
+            let existing_dir = fs.nodes[&existing].inode.file_type == FileType::Directory;
+            match (existing_dir, file_type == FileType::Directory) {
+                (true, true) => {},
+                (true, false) => fs.rmdir(parent, name)?,
+                (false, _) => fs.unlink(parent, name)?,
+            }
+        }
+
+        let ino = match file_type {
+            FileType::Directory => {
+                stats.dirs += 1;
+                match fs.lookup(parent, name) {
+                    Ok(ino) => ino,
+                    Err(_) => fs.mkdir(parent, name, entry.mode)?,
+                }
+            },
+            FileType::Regular => {
+                stats.files += 1;
+                let key = (entry.dev, entry.ino);
+                let ino = match links.get(&key) {
+                    Some(&target) if entry.nlink > 1 => {
+                        fs.link(parent, name, target)?;
+                        target
+                    },
+                    _ => fs.create(parent, name, entry.mode)?,
+                };
+                if entry.nlink > 1 {
+                    links.insert(key, ino);
+                }
+                // newc stores the contents of a hard-linked file once, with its last link
+                if !entry.data.is_empty() {
+                    fs.write(ino, 0, entry.data)?;
+                    stats.bytes += entry.data.len() as u64;
+                }
+                ino
+            },
+            FileType::Symlink => {
+                stats.symlinks += 1;
+                let target = core::str::from_utf8(entry.data).map_err(|_| {
+                    InitError::new(
+                        ErrorKind::DataCorruption,
+                        "Symbolic link target is not UTF-8",
+                    )
+                })?;
+                fs.symlink(parent, name, target)?
+            },
+            FileType::CharDevice | FileType::BlockDevice | FileType::Fifo | FileType::Socket => {
+                stats.nodes += 1;
+                fs.mknod(parent, name, entry.mode, entry.rdev)?
+            },
+            FileType::Unknown => {
+                return Err(InitError::new(
+                    ErrorKind::NotSupported,
+                    "Unknown file type in archive",
+                )
+                .with_details(String::from(path)));
+            },
+        };
+        fs.set_attr(ino, entry.mode, entry.uid, entry.gid, entry.mtime as u64)?;
+    }
+
+    Ok(stats)
+}
+
+// =============================================================================
+// EARLY ROOT SERVICE
+// =============================================================================
+
+/// Program run from the initramfs when the command line names none
+pub const DEFAULT_RDINIT: &str = "/init";
+
+/// `rdinit=` from the kernel command line, or [`DEFAULT_RDINIT`]
+pub fn rdinit(cmdline: Option<&str>) -> &str {
+    cmdline
+        .and_then(|c| {
+            c.split_whitespace()
+                .find_map(|arg| arg.strip_prefix("rdinit="))
+        })
+        .unwrap_or(DEFAULT_RDINIT)
+}
+
+/// Initramfs mounted as the early root
+///
+/// Registered by the filesystem subsystem as `"early_root"` when it
+/// mounts an initramfs.
+#[derive(Debug, Clone)]
+pub struct EarlyRoot {
+    /// Program to run as PID 1, if the archive holds it
+    pub init: Option<String>,
+    /// What was extracted
+    pub stats: UnpackStats,
+}
+
+impl Service for EarlyRoot {
+    fn name(&self) -> &'static str {
+        "early_root"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Append a `newc` entry
+    fn push(archive: &mut Vec<u8>, name: &str, ino: u32, mode: u32, nlink: u32, data: &[u8]) {
+        let fields = [
+            ino,
+            mode,
+            0,
+            0,
+            nlink,
+            1_700_000_000,
+            data.len() as u32,
+            0,
+            1,
+            0,
+            0,
+            name.len() as u32 + 1,
+            0,
+        ];
+        archive.extend_from_slice(MAGIC_NEWC);
+        for field in fields {
+            archive.extend_from_slice(alloc::format!("{:08X}", field).as_bytes());
+        }
+        archive.extend_from_slice(name.as_bytes());
+        archive.push(0);
+        archive.resize((archive.len() + 3) & !3, 0);
+        archive.extend_from_slice(data);
+        archive.resize((archive.len() + 3) & !3, 0);
+    }
+
+    fn sample() -> Vec<u8> {
+        let mut archive = Vec::new();
+        push(&mut archive, ".", 1, S_IFDIR | 0o755, 2, b"");
+        push(&mut archive, "bin", 2, S_IFDIR | 0o755, 2, b"");
+        push(
+            &mut archive,
+            "init",
+            3,
+            S_IFREG | 0o755,
+            1,
+            b"#!/bin/sh\nexec switch_root /newroot /sbin/init\n",
+        );
+        push(&mut archive, "bin/sh", 4, S_IFLNK | 0o777, 1, b"busybox");
+        push(&mut archive, "bin/busybox", 5, S_IFREG | 0o755, 2, b"");
+        push(
+            &mut archive,
+            "bin/switch_root",
+            5,
+            S_IFREG | 0o755,
+            2,
+            b"\x7fELF",
+        );
+        push(&mut archive, "dev/console", 6, 0o020600, 1, b"");
+        push(&mut archive, TRAILER, 0, 0, 1, b"");
+        archive.resize(archive.len() + 512, 0);
+        // Second archive overriding /init
+        push(&mut archive, "./init", 7, S_IFREG | 0o700, 1, b"override");
+        push(&mut archive, TRAILER, 0, 0, 1, b"");
+        archive
+    }
+
+    #[test]
+    fn test_cpio_reader() {
+        let archive = sample();
+        let entries: Vec<_> = CpioReader::new(&archive)
+            .collect::<InitResult<_>>()
+            .unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name).collect();
+        assert_eq!(names, [
+            ".",
+            "bin",
+            "init",
+            "bin/sh",
+            "bin/busybox",
+            "bin/switch_root",
+            "dev/console",
+            "./init"
+        ]);
+        assert_eq!(entries[3].file_type(), FileType::Symlink);
+        assert_eq!(entries[6].dev, makedev(0, 1));
+
+        // Bad magic on the second entry, which follows the 112-byte "." entry
+        let mut corrupt = archive.clone();
+        corrupt[112..118].copy_from_slice(b"07070X");
+        let results: Vec<_> = CpioReader::new(&corrupt).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok() && results[1].is_err());
+        assert!(CpioReader::new(&archive[..200]).any(|e| e.is_err()));
+    }
+
+    #[test]
+    fn test_unpack() {
+        let mut fs = TmpFs::new();
+        let stats = unpack(&mut fs, &sample()).unwrap();
+        assert_eq!(stats.files, 4);
+        assert_eq!(stats.symlinks, 1);
+        assert_eq!(stats.nodes, 1);
+
+        let init = fs.lookup_path("/init").unwrap();
+        assert_eq!(fs.read(init, 0, 64).unwrap(), b"override");
+        assert_eq!(fs.stat(init).unwrap().mode, S_IFREG | 0o700);
+
+        let busybox = fs.lookup_path("/bin/busybox").unwrap();
+        assert_eq!(fs.lookup_path("/bin/switch_root").unwrap(), busybox);
+        assert_eq!(fs.stat(busybox).unwrap().nlink, 2);
+        assert_eq!(fs.read(busybox, 0, 16).unwrap(), b"\x7fELF");
+
+        let sh = fs.lookup_path("bin/sh").unwrap();
+        assert_eq!(fs.readlink(sh).unwrap(), "busybox");
+        let console = fs.lookup_path("/dev/console").unwrap();
+        assert_eq!(fs.stat(console).unwrap().file_type, FileType::CharDevice);
+        assert_eq!(
+            fs.stat(fs.lookup_path("/dev").unwrap()).unwrap().mode,
+            S_IFDIR | 0o755
+        );
+
+        let mut escape = Vec::new();
+        push(&mut escape, "../etc/passwd", 1, S_IFREG | 0o644, 1, b"x");
+        assert!(unpack(&mut TmpFs::new(), &escape).is_err());
+    }
+
+    #[test]
+    fn test_rdinit() {
+        assert_eq!(rdinit(None), "/init");
+        assert_eq!(
+            rdinit(Some("quiet rdinit=/bin/sh root=/dev/sda1")),
+            "/bin/sh"
+        );
+    }
+}
@@ -13,6 +13,16 @@
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+use helix_hal::devtree::Resource;
+
+/// GICv2 compatible strings
+#[cfg(target_arch = "aarch64")]
+const GIC_V2_COMPATIBLE: &[&str] = &["arm,gic-400", "arm,cortex-a15-gic", "arm,cortex-a9-gic"];
+
+/// PLIC compatible strings
+#[cfg(target_arch = "riscv64")]
+const PLIC_COMPATIBLE: &[&str] = &["riscv,plic0", "sifive,plic-1.0.0"];
 
 // =============================================================================
 // INTERRUPT HANDLER TYPES
@@ -687,13 +697,19 @@ fn init_arm(&mut self, ctx: &mut InitContext) -> InitResult<()> {
             self.vectors.push(VectorEntry::default());
         }
 
-        // Detect GIC version from DTB or ACPI
-        // For now, assume GICv2
+        // Only GICv2 is driven for now
         self.controller = InterruptController::Gic2;
 
-        // TODO: Get GIC addresses from device tree
-        self.gic_dist_base = 0x0800_0000; // Placeholder
-        self.gic_cpu_base = 0x0801_0000;
+        // Distributor and CPU interface from the hardware description,
+        // QEMU virt addresses otherwise
+        let described = helix_hal::devtree::get().and_then(|tree| {
+            let node = GIC_V2_COMPATIBLE.iter().find_map(|c| tree.find_compatible(c).next())?;
+            match tree.node(node)?.resources.as_slice() {
+                [Resource::Memory { base: dist, .. }, Resource::Memory { base: cpu, .. }, ..] => Some((*dist, *cpu)),
+                _ => None,
+            }
+        });
+        (self.gic_dist_base, self.gic_cpu_base) = described.unwrap_or((0x0800_0000, 0x0801_0000));
 
         ctx.debug(alloc::format!(
             "GIC distributor: 0x{:x}, CPU interface: 0x{:x}",
@@ -744,9 +760,17 @@ fn init_riscv(&mut self, ctx: &mut InitContext) -> InitResult<()> {
             self.vectors.push(VectorEntry::default());
         }
 
-        // Detect PLIC from DTB
+        // PLIC from the hardware description, common base otherwise
         self.controller = InterruptController::Plic;
-        self.plic_base = 0x0C00_0000; // Common PLIC base
+        self.plic_base = helix_hal::devtree::get()
+            .and_then(|tree| {
+                let node = PLIC_COMPATIBLE.iter().find_map(|c| tree.find_compatible(c).next())?;
+                tree.node(node)?.resources.iter().find_map(|r| match *r {
+                    Resource::Memory { base, .. } => Some(base),
+                    Resource::Io { .. } => None,
+                })
+            })
+            .unwrap_or(0x0C00_0000);
 
         ctx.debug(alloc::format!("PLIC base: 0x{:x}", self.plic_base));
 
@@ -17,6 +17,7 @@
 //! ├── ipc/         - Inter-process communication
 //! ├── drivers/     - Driver framework
 //! ├── filesystem/  - VFS and filesystems
+//! ├── initramfs/   - Initrd unpacking into the early root
 //! ├── network/     - Network stack
 //! ├── security/    - Security subsystem
 //! ├── debug/       - Debugging and tracing
@@ -28,6 +29,7 @@
 pub mod debug;
 pub mod drivers;
 pub mod filesystem;
+pub mod initramfs;
 pub mod interrupts;
 pub mod ipc;
 pub mod memory;
@@ -3,6 +3,7 @@
 //! Userspace transition and initial process management.
 //! Runtime phase subsystem for user-mode initialization.
 
+use super::initramfs::EarlyRoot;
 use crate::context::InitContext;
 use crate::error::{ErrorKind, InitError, InitResult};
 use crate::phase::{InitPhase, PhaseCapabilities};
@@ -813,6 +814,14 @@ fn init(&mut self, ctx: &mut InitContext) -> InitResult<()> {
             self.init_path = path;
         }
 
+        // An initramfs supplies the first program, which later switches
+        // to the real root and executes its init
+        if let Some(early) = ctx.services().get_as::<EarlyRoot>("early_root") {
+            if let Some(init) = &early.init {
+                self.init_path = init.clone();
+            }
+        }
+
         // Initialize userspace
         self.init_userspace(ctx)?;
 
@@ -0,0 +1,477 @@
+//! # Heap Hardening
+//!
+//! Exploit mitigations for the kernel heap, enabled with the `hardening`
+//! feature.
+//!
+//! - [`HardenedHeap`] surrounds every allocation with canaries that are
+//!   checked on free, and detects double and invalid frees.
+//! - Large allocations get guard pages on both sides once a page
+//!   protection backend is installed with [`set_guard_pages`].
+//! - Slab freelists store obfuscated links, are validated when walked,
+//!   and hand out objects in a randomized order.
+//!
+//! Violations are logged and panic by default: a corrupted heap is not
+//! something to keep running on.
+//!
+//! ## Setup
+//!
+//! ```text
+//! 1. hardening::init_from_entropy()                // RDSEED/RDRAND mixed with the TSC
+//! 2. hardening::set_guard_pages(GuardPages::KERNEL) // optional, once paging is up
+//! 3. GLOBAL_HEAP.set_allocator(slab)               // wrapped in HardenedHeap
+//! ```
+//!
+//! The secret is seeded from boot entropy on first use if step 1 is
+//! skipped, so it is never a value known ahead of time.
+
+use core::alloc::Layout;
+use core::mem::{align_of, size_of};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use alloc::sync::Arc;
+use helix_hal::kernel_protect::{self, Protection, Span};
+use spin::RwLock;
+
+use super::{HeapAllocator, HeapStats};
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Page size used for guard pages
+pub const PAGE_SIZE: usize = 4096;
+
+/// Allocations of at least this size get guard pages
+pub const GUARD_THRESHOLD: usize = 4 * PAGE_SIZE;
+
+/// Size of the canary following each allocation
+pub const CANARY_SIZE: usize = size_of::<u64>();
+
+/// Header state of a live allocation
+const STATE_ALIVE: u64 = 0x4845_4150_4C49_5645;
+
+/// Header state of a freed allocation
+const STATE_FREED: u64 = 0x4845_4150_4652_4545;
+
+/// Golden ratio increment of the random generator
+const GOLDEN: u64 = 0x9E37_79B9_7F4A_7C15;
+
+// =============================================================================
+// Global State
+// =============================================================================
+
+/// Secret mixed into canaries and freelist links (0 until seeded)
+static SECRET: AtomicU64 = AtomicU64::new(0);
+
+/// Random generator state
+static RANDOM: AtomicU64 = AtomicU64::new(0);
+
+/// Number of violations detected
+static VIOLATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Panic on the first violation
+static PANIC_ON_VIOLATION: AtomicBool = AtomicBool::new(true);
+
+/// Page protection backend for guard pages
+static GUARD_PAGES: RwLock<Option<GuardPages>> = RwLock::new(None);
+
+/// Seed the canary secret and the freelist randomization
+///
+/// Call before the heap is set up: canaries of blocks allocated under the
+/// old secret no longer verify.
+pub fn init(seed: u64) {
+    let secret = mix(seed ^ GOLDEN) | 1;
+    RANDOM.store(mix(secret), Ordering::Relaxed);
+    SECRET.store(secret, Ordering::Relaxed);
+}
+
+/// Seed from the boot entropy sources (RDSEED, RDRAND, TSC)
+pub fn init_from_entropy() {
+    let (seed, quality) = helix_hal::kaslr::collect_entropy();
+    init(seed);
+    log::debug!("heap hardening: seeded ({:?} entropy)", quality);
+}
+
+/// Current secret, seeded from boot entropy on first use
+fn secret() -> u64 {
+    let secret = SECRET.load(Ordering::Relaxed);
+    if secret != 0 {
+        return secret;
+    }
+
+    let seeded = mix(helix_hal::kaslr::collect_entropy().0 ^ GOLDEN) | 1;
+    match SECRET.compare_exchange(0, seeded, Ordering::Relaxed, Ordering::Relaxed) {
+        Ok(_) => {
+            RANDOM.store(mix(seeded), Ordering::Relaxed);
+            seeded
+        }
+        Err(current) => current,
+    }
+}
+
+/// Panic on the first violation instead of continuing
+pub fn set_panic_on_violation(enabled: bool) {
+    PANIC_ON_VIOLATION.store(enabled, Ordering::Relaxed);
+}
+
+/// Number of violations detected so far
+pub fn violation_count() -> u64 {
+    VIOLATIONS.load(Ordering::Relaxed)
+}
+
+/// SplitMix64 finalizer
+const fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Next pseudo-random number
+pub fn random() -> u64 {
+    secret();
+    mix(RANDOM.fetch_add(GOLDEN, Ordering::Relaxed).wrapping_add(GOLDEN))
+}
+
+/// Shuffle `items` in place (Fisher-Yates)
+pub fn shuffle<T>(items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let j = (random() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+// =============================================================================
+// Violations
+// =============================================================================
+
+/// Kind of heap corruption detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// A canary around an allocation was overwritten
+    CanaryCorrupted,
+    /// A block was freed twice
+    DoubleFree,
+    /// A pointer not returned by the allocator was freed
+    InvalidFree,
+    /// A slab freelist link does not decode to a free object
+    FreelistCorrupted,
+}
+
+impl Violation {
+    /// Short name for reports
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::CanaryCorrupted => "heap canary corrupted",
+            Self::DoubleFree => "double free",
+            Self::InvalidFree => "invalid free",
+            Self::FreelistCorrupted => "freelist corrupted",
+        }
+    }
+}
+
+/// Log a violation and apply the panic policy
+pub(crate) fn report(violation: Violation, addr: usize) {
+    VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+    log::error!("heap hardening: {} at {:#x}", violation.name(), addr);
+
+    if PANIC_ON_VIOLATION.load(Ordering::Relaxed) {
+        panic!("heap hardening: {} at {:#x}", violation.name(), addr);
+    }
+}
+
+// =============================================================================
+// Freelist Obfuscation
+// =============================================================================
+
+/// Key for the freelist of the slab at `base`
+pub(crate) fn freelist_key(base: usize) -> usize {
+    (secret() ^ mix(base as u64)) as usize
+}
+
+/// Encode or decode the freelist link stored in the object at `slot`
+///
+/// The link is mixed with the slab key and the byte-swapped slot address,
+/// so a leaked link reveals neither the secret nor the heap layout, and a
+/// link copied to another slot no longer decodes.
+#[inline]
+pub(crate) fn freelist_link(link: usize, slot: usize, key: usize) -> usize {
+    link ^ key ^ slot.swap_bytes()
+}
+
+// =============================================================================
+// Guard Pages
+// =============================================================================
+
+/// Page protection backend for guard pages
+#[derive(Clone, Copy)]
+pub struct GuardPages {
+    /// Make pages inaccessible
+    pub protect: fn(addr: usize, size: usize),
+    /// Make pages accessible again
+    pub unprotect: fn(addr: usize, size: usize),
+}
+
+impl GuardPages {
+    /// Backend on the kernel page tables: guard pages are made not present
+    pub const KERNEL: Self = Self {
+        protect: |addr, size| kernel_protect(addr, size, Protection::NoAccess),
+        unprotect: |addr, size| kernel_protect(addr, size, Protection::ReadWrite),
+    };
+}
+
+/// Apply `prot` to heap pages through the kernel page tables
+fn kernel_protect(addr: usize, size: usize, prot: Protection) {
+    let span = Span::new(addr as u64, (addr + size) as u64);
+    // SAFETY: Guard pages are page aligned heap memory nothing else uses
+    if let Err(e) = unsafe { kernel_protect::set_page_protection(span, prot) } {
+        log::warn!("heap hardening: guard page at {:#x}: {}", addr, e);
+    }
+}
+
+/// Install the guard page backend
+///
+/// Only allocations made afterwards get guard pages.
+pub fn set_guard_pages(guards: GuardPages) {
+    *GUARD_PAGES.write() = Some(guards);
+}
+
+// =============================================================================
+// Allocation Header
+// =============================================================================
+
+/// Metadata stored right before each allocation
+///
+/// The underlying allocator may reuse the first word of a freed block,
+/// so fields needed after free do not come first.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Header {
+    /// Layout of the underlying block
+    block_size: usize,
+    block_align: usize,
+    /// Offset of the user pointer from the underlying block
+    offset: usize,
+    /// Requested size
+    size: usize,
+    /// Block is surrounded by guard pages
+    guarded: bool,
+    /// `STATE_ALIVE` or `STATE_FREED`
+    state: u64,
+    /// Must equal `canary(user)`
+    canary: u64,
+}
+
+const HEADER_SIZE: usize = size_of::<Header>();
+
+/// Canary value for the allocation at `user`
+fn canary(user: usize) -> u64 {
+    secret() ^ mix(user as u64)
+}
+
+// =============================================================================
+// Hardened Allocator
+// =============================================================================
+
+/// Heap allocator wrapper adding canaries, free checks and guard pages
+pub struct HardenedHeap {
+    inner: Arc<dyn HeapAllocator>,
+}
+
+impl HardenedHeap {
+    /// Wrap an allocator
+    pub fn new(inner: Arc<dyn HeapAllocator>) -> Self {
+        Self { inner }
+    }
+
+    /// Underlying block and user offset for `layout`
+    fn block_layout(layout: Layout, guarded: bool) -> Option<(Layout, usize)> {
+        let align = layout.align().max(align_of::<Header>());
+        let left = HEADER_SIZE.next_multiple_of(align);
+        let body = left.checked_add(layout.size())?.checked_add(CANARY_SIZE)?;
+
+        if guarded {
+            // [guard page][header][user][canary][slack][guard page]
+            let front = PAGE_SIZE.max(align);
+            let size = front + body.next_multiple_of(PAGE_SIZE) + PAGE_SIZE;
+            let block = Layout::from_size_align(size, front).ok()?;
+            Some((block, front + left))
+        } else {
+            Some((Layout::from_size_align(body, align).ok()?, left))
+        }
+    }
+}
+
+impl HeapAllocator for HardenedHeap {
+    fn allocate(&self, layout: Layout) -> *mut u8 {
+        let guards = if layout.size() >= GUARD_THRESHOLD {
+            *GUARD_PAGES.read()
+        } else {
+            None
+        };
+        let Some((block, offset)) = Self::block_layout(layout, guards.is_some()) else {
+            return core::ptr::null_mut();
+        };
+
+        let raw = self.inner.allocate(block);
+        if raw.is_null() {
+            return raw;
+        }
+
+        let base = raw as usize;
+        let user = base + offset;
+        let header = Header {
+            block_size: block.size(),
+            block_align: block.align(),
+            offset,
+            size: layout.size(),
+            guarded: guards.is_some(),
+            state: STATE_ALIVE,
+            canary: canary(user),
+        };
+        // SAFETY: The header and the tail canary lie within our block
+        unsafe {
+            ((user - HEADER_SIZE) as *mut Header).write(header);
+            ((user + layout.size()) as *mut u64).write_unaligned(canary(user).rotate_left(32));
+        }
+
+        if let Some(guards) = guards {
+            (guards.protect)(base, PAGE_SIZE);
+            (guards.protect)(base + block.size() - PAGE_SIZE, PAGE_SIZE);
+        }
+
+        user as *mut u8
+    }
+
+    fn deallocate(&self, ptr: *mut u8, layout: Layout) {
+        let user = ptr as usize;
+        if user % align_of::<Header>() != 0 || user < HEADER_SIZE {
+            return report(Violation::InvalidFree, user);
+        }
+
+        // SAFETY: Every pointer we hand out is preceded by its header
+        let header = unsafe { &mut *((user - HEADER_SIZE) as *mut Header) };
+        match header.state {
+            STATE_ALIVE => {}
+            STATE_FREED => return report(Violation::DoubleFree, user),
+            _ => return report(Violation::InvalidFree, user),
+        }
+        if header.size != layout.size() {
+            return report(Violation::InvalidFree, user);
+        }
+
+        // SAFETY: The tail canary was written in `allocate`
+        let tail = unsafe { ((user + header.size) as *const u64).read_unaligned() };
+        if header.canary != canary(user) || tail != canary(user).rotate_left(32) {
+            // The block is leaked: its neighbours may be corrupt too
+            return report(Violation::CanaryCorrupted, user);
+        }
+
+        header.state = STATE_FREED;
+        let base = user - header.offset;
+        if header.guarded {
+            if let Some(guards) = *GUARD_PAGES.read() {
+                (guards.unprotect)(base, PAGE_SIZE);
+                (guards.unprotect)(base + header.block_size - PAGE_SIZE, PAGE_SIZE);
+            }
+        }
+
+        // SAFETY: Recorded from a valid layout in `allocate`
+        let block = unsafe { Layout::from_size_align_unchecked(header.block_size, header.block_align) };
+        self.inner.deallocate(base as *mut u8, block);
+    }
+
+    fn name(&self) -> &'static str {
+        "Hardened"
+    }
+
+    fn stats(&self) -> HeapStats {
+        self.inner.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocator::slab::SlabAllocator;
+
+    /// Backing allocator that never reuses memory, so freed headers stay
+    /// readable
+    struct LeakingHeap;
+
+    impl HeapAllocator for LeakingHeap {
+        fn allocate(&self, layout: Layout) -> *mut u8 {
+            // SAFETY: Layouts built by `HardenedHeap` are never zero sized
+            unsafe { alloc::alloc::alloc(layout) }
+        }
+
+        fn deallocate(&self, _ptr: *mut u8, _layout: Layout) {}
+
+        fn name(&self) -> &'static str {
+            "Leaking"
+        }
+
+        fn stats(&self) -> HeapStats {
+            HeapStats::default()
+        }
+    }
+
+    fn hardened() -> HardenedHeap {
+        HardenedHeap::new(Arc::new(LeakingHeap))
+    }
+
+    #[test]
+    fn test_clean_alloc_free() {
+        let heap = hardened();
+        let layout = Layout::from_size_align(48, 16).unwrap();
+        let ptr = heap.allocate(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % 16, 0);
+        // SAFETY: The allocation is 48 bytes long
+        unsafe { ptr.write_bytes(0xAA, 48) };
+        heap.deallocate(ptr, layout);
+    }
+
+    #[test]
+    #[should_panic(expected = "heap canary corrupted")]
+    fn test_canary_overwrite() {
+        let heap = hardened();
+        let layout = Layout::from_size_align(40, 8).unwrap();
+        let ptr = heap.allocate(layout);
+        // SAFETY: One byte past the allocation lands in the tail canary
+        unsafe { ptr.add(40).write(0) };
+        heap.deallocate(ptr, layout);
+    }
+
+    #[test]
+    #[should_panic(expected = "double free")]
+    fn test_double_free() {
+        let heap = hardened();
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let ptr = heap.allocate(layout);
+        heap.deallocate(ptr, layout);
+        heap.deallocate(ptr, layout);
+    }
+
+    #[test]
+    #[should_panic(expected = "freelist corrupted")]
+    fn test_corrupted_freelist_link() {
+        let layout = Layout::from_size_align(64 * 1024, PAGE_SIZE).unwrap();
+        // SAFETY: Non-zero size; the memory is leaked to the slab
+        let memory = unsafe { alloc::alloc::alloc_zeroed(layout) };
+        let slab = SlabAllocator::new();
+        slab.init(memory, layout.size());
+
+        let object = Layout::from_size_align(64, 8).unwrap();
+        let ptr = slab.allocate(object);
+        slab.deallocate(ptr, object);
+        // SAFETY: The freed object now holds the obfuscated freelist link
+        unsafe { *(ptr as *mut usize) ^= 0x40 };
+        slab.allocate(object);
+    }
+
+    #[test]
+    fn test_secret_seeded() {
+        assert_ne!(secret(), 0);
+        assert_ne!(freelist_key(0x1000), freelist_key(0x2000));
+    }
+}
@@ -3,6 +3,8 @@
 //! Framework for kernel heap allocators.
 
 pub mod slab;
+#[cfg(feature = "hardening")]
+pub mod hardening;
 
 use core::alloc::{GlobalAlloc, Layout};
 use alloc::sync::Arc;
@@ -58,10 +60,24 @@ pub struct HeapStats {
     pub deallocations: u64,
 }
 
+/// Allocation observers (per-owner accounting, leak tracking)
+///
+/// Hooks run inside the allocator: they must not block on locks an
+/// allocating caller may hold, and allocations they make re-enter them.
+#[derive(Clone, Copy)]
+pub struct AllocHooks {
+    /// Called after a successful allocation
+    pub alloc: fn(ptr: *mut u8, size: usize),
+    /// Called before a block is freed
+    pub dealloc: fn(ptr: *mut u8, size: usize),
+}
+
 /// Global heap allocator wrapper
 pub struct GlobalHeap {
     /// Current allocator
     allocator: RwLock<Option<Arc<dyn HeapAllocator>>>,
+    /// Allocation observers
+    hooks: RwLock<Option<AllocHooks>>,
 }
 
 impl GlobalHeap {
@@ -69,34 +85,75 @@ impl GlobalHeap {
     pub const fn new() -> Self {
         Self {
             allocator: RwLock::new(None),
+            hooks: RwLock::new(None),
         }
     }
 
+    /// Install allocation observers
+    pub fn set_hooks(&self, hooks: AllocHooks) {
+        *self.hooks.write() = Some(hooks);
+    }
+
     /// Set the allocator
+    ///
+    /// With the `hardening` feature the allocator is wrapped in a
+    /// [`HardenedHeap`](hardening::HardenedHeap), and with the `kasan`
+    /// feature in a [`KasanHeap`](crate::sanitizer::KasanHeap).
     pub fn set_allocator(&self, allocator: Arc<dyn HeapAllocator>) {
+        #[cfg(feature = "hardening")]
+        let allocator: Arc<dyn HeapAllocator> =
+            Arc::new(hardening::HardenedHeap::new(allocator));
+
+        #[cfg(feature = "kasan")]
+        let allocator: Arc<dyn HeapAllocator> =
+            Arc::new(crate::sanitizer::KasanHeap::new(allocator));
+
         *self.allocator.write() = Some(allocator);
     }
 }
 
 unsafe impl GlobalAlloc for GlobalHeap {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        self.allocator.read()
+        let ptr = self.allocator.read()
             .as_ref()
             .map(|a| a.allocate(layout))
-            .unwrap_or(core::ptr::null_mut())
+            .unwrap_or(core::ptr::null_mut());
+        if !ptr.is_null() {
+            if let Some(hooks) = *self.hooks.read() {
+                (hooks.alloc)(ptr, layout.size());
+            }
+        }
+        ptr
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if let Some(hooks) = *self.hooks.read() {
+            (hooks.dealloc)(ptr, layout.size());
+        }
         if let Some(ref allocator) = *self.allocator.read() {
             allocator.deallocate(ptr, layout);
         }
     }
 
     unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
-        self.allocator.read()
+        // The old block is reported freed before it can be reused, and
+        // reported again if it survives a failed reallocation
+        let hooks = *self.hooks.read();
+        if let Some(hooks) = hooks {
+            (hooks.dealloc)(ptr, layout.size());
+        }
+        let new = self.allocator.read()
             .as_ref()
             .map(|a| a.reallocate(ptr, layout, new_size))
-            .unwrap_or(core::ptr::null_mut())
+            .unwrap_or(core::ptr::null_mut());
+        if let Some(hooks) = hooks {
+            if new.is_null() {
+                (hooks.alloc)(ptr, layout.size());
+            } else {
+                (hooks.alloc)(new, new_size);
+            }
+        }
+        new
     }
 }
 
@@ -7,10 +7,18 @@
 use alloc::vec::Vec;
 use spin::Mutex;
 use core::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "kasan")]
+use crate::sanitizer::{self, ShadowCode};
+#[cfg(feature = "hardening")]
+use super::hardening::{self, Violation};
 
 /// Slab size classes
 const SIZE_CLASSES: [usize; 8] = [16, 32, 64, 128, 256, 512, 1024, 2048];
 
+/// Maximum objects per slab (one page of the smallest class)
+#[cfg(feature = "hardening")]
+const MAX_OBJECTS: usize = 4096 / SIZE_CLASSES[0];
+
 /// A single slab
 struct Slab {
     /// Memory block
@@ -23,6 +31,12 @@ struct Slab {
     free_head: Option<usize>,
     /// Number of allocated objects
     allocated: usize,
+    /// Freelist obfuscation key
+    #[cfg(feature = "hardening")]
+    key: usize,
+    /// Bitmap of free objects, for double-free and freelist checks
+    #[cfg(feature = "hardening")]
+    free_map: [u64; MAX_OBJECTS / 64],
 }
 
 // SAFETY: Slab memory is exclusively owned and managed through the Mutex in SlabAllocator.
@@ -31,6 +45,7 @@ unsafe impl Send for Slab {}
 
 impl Slab {
     /// Create a new slab
+    #[cfg(not(feature = "hardening"))]
     unsafe fn new(memory: *mut u8, size: usize, obj_size: usize) -> Self {
         let capacity = size / obj_size;
         
@@ -45,6 +60,9 @@ unsafe fn new(memory: *mut u8, size: usize, obj_size: usize) -> Self {
             let last_ptr = memory.add((capacity - 1) * obj_size) as *mut usize;
             *last_ptr = usize::MAX;
         }
+
+        #[cfg(feature = "kasan")]
+        sanitizer::poison(memory as usize, size, ShadowCode::SlabFree);
         
         Self {
             memory,
@@ -55,7 +73,62 @@ unsafe fn new(memory: *mut u8, size: usize, obj_size: usize) -> Self {
         }
     }
 
+    /// Create a new slab with an obfuscated freelist in random order
+    #[cfg(feature = "hardening")]
+    unsafe fn new(memory: *mut u8, size: usize, obj_size: usize) -> Self {
+        let capacity = (size / obj_size).min(MAX_OBJECTS);
+
+        let mut order = [0u16; MAX_OBJECTS];
+        for (i, slot) in order[..capacity].iter_mut().enumerate() {
+            *slot = i as u16;
+        }
+        hardening::shuffle(&mut order[..capacity]);
+
+        let mut slab = Self {
+            memory,
+            obj_size,
+            capacity,
+            free_head: Some(order[0] as usize),
+            allocated: 0,
+            key: hardening::freelist_key(memory as usize),
+            free_map: [0; MAX_OBJECTS / 64],
+        };
+
+        for (i, &index) in order[..capacity].iter().enumerate() {
+            let next = if i + 1 < capacity { order[i + 1] as usize } else { usize::MAX };
+            // SAFETY: Caller guarantees memory is valid for size bytes
+            unsafe { slab.set_link(index as usize, next) };
+            slab.free_map[index as usize / 64] |= 1 << (index % 64);
+        }
+
+        #[cfg(feature = "kasan")]
+        sanitizer::poison(memory as usize, size, ShadowCode::SlabFree);
+
+        slab
+    }
+
+    /// Store the obfuscated freelist link of a free object
+    ///
+    /// # Safety
+    ///
+    /// `index` must be below `capacity`.
+    #[cfg(feature = "hardening")]
+    unsafe fn set_link(&mut self, index: usize, next: usize) {
+        // SAFETY: Forwarded from the caller
+        let slot = unsafe { self.memory.add(index * self.obj_size) };
+        let link = hardening::freelist_link(next, slot as usize, self.key);
+        // SAFETY: The object is free and at least a word long
+        unsafe { *(slot as *mut usize) = link };
+    }
+
+    /// Check if object `index` is on the freelist
+    #[cfg(feature = "hardening")]
+    fn is_free(&self, index: usize) -> bool {
+        self.free_map[index / 64] & (1 << (index % 64)) != 0
+    }
+
     /// Allocate an object
+    #[cfg(not(feature = "hardening"))]
     fn allocate(&mut self) -> Option<*mut u8> {
         let index = self.free_head?;
         
@@ -69,7 +142,34 @@ fn allocate(&mut self) -> Option<*mut u8> {
         Some(ptr)
     }
 
+    /// Allocate an object, validating the freelist link
+    ///
+    /// A link that does not decode to a free object abandons the rest of
+    /// the freelist rather than hand out memory an attacker chose.
+    #[cfg(feature = "hardening")]
+    fn allocate(&mut self) -> Option<*mut u8> {
+        let index = self.free_head?;
+
+        let ptr = unsafe { self.memory.add(index * self.obj_size) };
+
+        let link = unsafe { *(ptr as *const usize) };
+        let next = hardening::freelist_link(link, ptr as usize, self.key);
+        self.free_head = match next {
+            usize::MAX => None,
+            next if next < self.capacity && next != index && self.is_free(next) => Some(next),
+            _ => {
+                hardening::report(Violation::FreelistCorrupted, ptr as usize);
+                None
+            }
+        };
+        self.free_map[index / 64] &= !(1 << (index % 64));
+        self.allocated += 1;
+
+        Some(ptr)
+    }
+
     /// Deallocate an object
+    #[cfg(not(feature = "hardening"))]
     fn deallocate(&mut self, ptr: *mut u8) {
         let offset = (ptr as usize) - (self.memory as usize);
         let index = offset / self.obj_size;
@@ -80,6 +180,32 @@ fn deallocate(&mut self, ptr: *mut u8) {
         }
         self.free_head = Some(index);
         self.allocated -= 1;
+
+        #[cfg(feature = "kasan")]
+        sanitizer::poison(ptr as usize, self.obj_size, ShadowCode::SlabFree);
+    }
+
+    /// Deallocate an object, rejecting double and misaligned frees
+    #[cfg(feature = "hardening")]
+    fn deallocate(&mut self, ptr: *mut u8) {
+        let offset = (ptr as usize) - (self.memory as usize);
+        let index = offset / self.obj_size;
+
+        if offset % self.obj_size != 0 {
+            return hardening::report(Violation::InvalidFree, ptr as usize);
+        }
+        if self.is_free(index) {
+            return hardening::report(Violation::DoubleFree, ptr as usize);
+        }
+
+        // SAFETY: `index` is below capacity since the slab contains `ptr`
+        unsafe { self.set_link(index, self.free_head.unwrap_or(usize::MAX)) };
+        self.free_map[index / 64] |= 1 << (index % 64);
+        self.free_head = Some(index);
+        self.allocated -= 1;
+
+        #[cfg(feature = "kasan")]
+        sanitizer::poison(ptr as usize, self.obj_size, ShadowCode::SlabFree);
     }
 
     /// Check if this slab contains the pointer
@@ -214,6 +340,9 @@ fn allocate(&self, layout: Layout) -> *mut u8 {
             if let Some(ref mut cache) = caches[class] {
                 if let Some(ptr) = cache.allocate() {
                     self.allocations.fetch_add(1, Ordering::Relaxed);
+                    // Only the requested bytes are accessible, not the whole size class
+                    #[cfg(feature = "kasan")]
+                    sanitizer::unpoison(ptr as usize, layout.size());
                     return ptr;
                 }
             }
@@ -6,6 +6,8 @@
 //! - Allocator framework
 //! - Memory region tracking
 //! - Memory protection
+//! - Kernel address sanitizer (`kasan` feature)
+//! - Heap hardening (`hardening` feature)
 //!
 //! ## Key Principle
 //!
@@ -24,6 +26,8 @@
 pub mod allocator;
 pub mod region;
 pub mod protection;
+#[cfg(feature = "kasan")]
+pub mod sanitizer;
 
 use helix_hal::{PhysAddr, VirtAddr, PageSize};
 
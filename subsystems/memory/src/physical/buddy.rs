@@ -95,20 +95,19 @@ fn buddy_addr(&self, addr: u64, order: usize) -> u64 {
     }
 }
 
-impl Default for BuddyAllocator {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl PhysicalAllocator for BuddyAllocator {
-    fn name(&self) -> &'static str {
-        "Buddy Allocator"
-    }
-
-    fn init(&mut self, regions: &[PhysicalRegion]) -> MemResult<()> {
+impl BuddyAllocator {
+    /// Initialize from usable regions, skipping reserved ranges
+    ///
+    /// `reserved` carries the early boot reservations (kernel image, crash
+    /// dump area, AP trampoline, ...) so they never reach the free lists.
+    pub fn init_reserved(
+        &mut self,
+        regions: &[PhysicalRegion],
+        reserved: &[PhysicalRegion],
+    ) -> MemResult<()> {
+        let page_size = self.page_size as u64;
         let mut lists = self.free_lists.lock();
-        
+
         for region in regions {
             if !region.is_usable() {
                 continue;
@@ -118,26 +117,9 @@ fn init(&mut self, regions: &[PhysicalRegion]) -> MemResult<()> {
                 self.base = region.start;
             }
 
-            // Add region to free lists
-            let mut addr = region.start.as_u64();
-            let end = region.end().as_u64();
-
-            while addr < end {
-                // Find the largest order that fits
-                let mut order = MAX_ORDER - 1;
-                while order > 0 {
-                    let block_size = (1 << order) * self.page_size as u64;
-                    if addr + block_size <= end && addr % block_size == 0 {
-                        break;
-                    }
-                    order -= 1;
-                }
-
-                let block_size = (1 << order) * self.page_size as u64;
-                lists[order].insert(addr);
-                addr += block_size;
-                self.total_size += block_size;
-            }
+            let start = (region.start.as_u64() + page_size - 1) & !(page_size - 1);
+            let end = region.end().as_u64() & !(page_size - 1);
+            self.total_size += Self::seed(&mut lists, page_size, start, end, reserved);
         }
 
         log::info!(
@@ -148,6 +130,66 @@ fn init(&mut self, regions: &[PhysicalRegion]) -> MemResult<()> {
         Ok(())
     }
 
+    /// Add `[start, end)` minus `reserved` to the free lists
+    fn seed(
+        lists: &mut [BTreeSet<u64>; MAX_ORDER],
+        page_size: u64,
+        start: u64,
+        end: u64,
+        reserved: &[PhysicalRegion],
+    ) -> u64 {
+        if start >= end {
+            return 0;
+        }
+
+        // Split around the first reservation that overlaps, if any
+        if let Some(index) = reserved.iter().position(|r| {
+            r.start.as_u64() < end && start < r.end().as_u64()
+        }) {
+            let hole = &reserved[index];
+            let hole_start = hole.start.as_u64() & !(page_size - 1);
+            let hole_end = (hole.end().as_u64() + page_size - 1) & !(page_size - 1);
+            let rest = &reserved[index + 1..];
+
+            return Self::seed(lists, page_size, start, hole_start.max(start), rest)
+                + Self::seed(lists, page_size, hole_end.min(end), end, rest);
+        }
+
+        let mut addr = start;
+        while addr < end {
+            // Find the largest order that fits
+            let mut order = MAX_ORDER - 1;
+            while order > 0 {
+                let block_size = (1 << order) * page_size;
+                if addr + block_size <= end && addr % block_size == 0 {
+                    break;
+                }
+                order -= 1;
+            }
+
+            lists[order].insert(addr);
+            addr += (1 << order) * page_size;
+        }
+
+        end - start
+    }
+}
+
+impl Default for BuddyAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhysicalAllocator for BuddyAllocator {
+    fn name(&self) -> &'static str {
+        "Buddy Allocator"
+    }
+
+    fn init(&mut self, regions: &[PhysicalRegion]) -> MemResult<()> {
+        self.init_reserved(regions, &[])
+    }
+
     fn allocate(&self, size: PageSize) -> MemResult<Frame> {
         let order = match size {
             PageSize::Size4KiB => 0,
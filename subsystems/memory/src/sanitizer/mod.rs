@@ -0,0 +1,497 @@
+//! # Kernel Address Sanitizer (KASAN-lite)
+//!
+//! Shadow-memory based detection of heap memory errors, enabled with the
+//! `kasan` feature.
+//!
+//! ## What is checked
+//!
+//! - Every allocation made through [`KasanHeap`] gets a left redzone (which
+//!   also holds the allocation header and traces) and a right redzone.
+//! - Freed allocations are poisoned and held in a [`Quarantine`] before
+//!   their memory is reused.
+//! - Slab objects are poisoned while free and unpoisoned to the requested
+//!   size, so overflows into the unused tail of a size class are caught.
+//! - Double and invalid frees are detected when they happen.
+//!
+//! Accesses are not instrumented by the compiler: code handling untrusted
+//! pointers (drivers, DMA buffers, copy routines) calls [`check_read`] /
+//! [`check_write`] before touching memory.
+//!
+//! ## Setup
+//!
+//! ```text
+//! 1. sanitizer::init(heap_base, heap_size, shadow)   // shadow = heap_size / 8
+//! 2. GLOBAL_HEAP.set_allocator(slab)                  // wrapped in KasanHeap
+//! 3. sanitizer::set_symbolizer(ksyms::lookup)         // optional
+//! ```
+
+pub mod quarantine;
+pub mod report;
+pub mod shadow;
+pub mod trace;
+
+use core::alloc::Layout;
+use core::mem::size_of;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use alloc::sync::Arc;
+use spin::{Mutex, Once};
+
+use super::allocator::{HeapAllocator, HeapStats};
+
+pub use quarantine::{Quarantine, QuarantineEntry, DEFAULT_QUARANTINE_BYTES};
+pub use report::{AllocationInfo, BugKind, Report};
+pub use shadow::{Shadow, ShadowCode, GRANULE};
+pub use trace::{set_symbolizer, StackTrace, Symbol, Symbolizer};
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Right redzone size in bytes
+pub const REDZONE_SIZE: usize = 32;
+
+/// Header magic of a live allocation
+const MAGIC_ALIVE: u64 = 0x4B41_5341_4E41_4C56;
+
+/// Header magic of a freed allocation
+const MAGIC_FREED: u64 = 0x4B41_5341_4E46_5245;
+
+/// Maximum distance scanned to find the allocation owning an address
+const MAX_SCAN: usize = 64 * 1024;
+
+// =============================================================================
+// Global State
+// =============================================================================
+
+/// Shadow map of the kernel heap
+static SHADOW: Once<Shadow> = Once::new();
+
+/// Freed allocations awaiting reuse
+static QUARANTINE: Mutex<Quarantine> = Mutex::new(Quarantine::new(DEFAULT_QUARANTINE_BYTES));
+
+/// Number of reports issued
+static REPORTS: AtomicU64 = AtomicU64::new(0);
+
+/// Panic after the first report
+static PANIC_ON_REPORT: AtomicBool = AtomicBool::new(false);
+
+/// A report is being printed (suppresses nested reports)
+static IN_REPORT: AtomicBool = AtomicBool::new(false);
+
+/// Enable shadow tracking for the kernel heap
+///
+/// Only the first call has any effect.
+///
+/// # Safety
+///
+/// `shadow` must point to [`Shadow::shadow_size`]`(heap_size)` bytes
+/// reserved for the sanitizer for the rest of the kernel's lifetime.
+pub unsafe fn init(heap_base: usize, heap_size: usize, shadow: *mut u8) {
+    SHADOW.call_once(|| {
+        // SAFETY: Forwarded from the caller
+        unsafe { Shadow::new(heap_base, heap_size, shadow) }
+    });
+    log::info!(
+        "KASAN: tracking heap {:#x}-{:#x}",
+        heap_base,
+        heap_base + heap_size
+    );
+}
+
+/// Check if shadow tracking is active
+pub fn is_enabled() -> bool {
+    SHADOW.get().is_some()
+}
+
+/// Panic on the first report instead of continuing
+pub fn set_panic_on_report(enabled: bool) {
+    PANIC_ON_REPORT.store(enabled, Ordering::Relaxed);
+}
+
+/// Number of reports issued so far
+pub fn report_count() -> u64 {
+    REPORTS.load(Ordering::Relaxed)
+}
+
+/// Poison a range (no-op before [`init`])
+pub fn poison(addr: usize, size: usize, code: ShadowCode) {
+    if let Some(shadow) = SHADOW.get() {
+        shadow.poison(addr, size, code);
+    }
+}
+
+/// Make a range accessible (no-op before [`init`])
+pub fn unpoison(addr: usize, size: usize) {
+    if let Some(shadow) = SHADOW.get() {
+        shadow.unpoison(addr, size);
+    }
+}
+
+// =============================================================================
+// Access Checks
+// =============================================================================
+
+/// Check that `size` bytes at `addr` may be read
+///
+/// Returns `false` (after reporting) if the range touches poisoned memory.
+#[inline(never)]
+pub fn check_read(addr: usize, size: usize) -> bool {
+    check_access(addr, size, false)
+}
+
+/// Check that `size` bytes at `addr` may be written
+///
+/// Returns `false` (after reporting) if the range touches poisoned memory.
+#[inline(never)]
+pub fn check_write(addr: usize, size: usize) -> bool {
+    check_access(addr, size, true)
+}
+
+fn check_access(addr: usize, size: usize, is_write: bool) -> bool {
+    let Some(shadow) = SHADOW.get() else {
+        return true;
+    };
+    let Some((bad, value)) = shadow.check(addr, size) else {
+        return true;
+    };
+
+    emit(Report {
+        kind: BugKind::from_shadow(value),
+        addr: bad,
+        size,
+        is_write,
+        shadow: value,
+        trace: StackTrace::capture(2),
+        allocation: find_allocation(shadow, bad),
+    });
+    false
+}
+
+/// Log a report and apply the panic policy
+fn emit(report: Report) {
+    REPORTS.fetch_add(1, Ordering::Relaxed);
+
+    if IN_REPORT.swap(true, Ordering::Acquire) {
+        return;
+    }
+    log::error!("{}", report);
+    IN_REPORT.store(false, Ordering::Release);
+
+    if PANIC_ON_REPORT.load(Ordering::Relaxed) {
+        panic!("KASAN: {} at {:#x}", report.kind.name(), report.addr);
+    }
+}
+
+// =============================================================================
+// Allocation Header
+// =============================================================================
+
+/// Metadata stored at the end of the left redzone
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Header {
+    magic: u64,
+    /// Requested size
+    size: usize,
+    /// Offset of the user pointer from the underlying block
+    offset: usize,
+    /// Layout of the underlying block
+    block_size: usize,
+    block_align: usize,
+    alloc_trace: StackTrace,
+    free_trace: StackTrace,
+}
+
+const HEADER_SIZE: usize = size_of::<Header>();
+
+/// Header of a user pointer, if it looks like one of ours
+fn header_of(shadow: Option<&Shadow>, user: usize) -> Option<*mut Header> {
+    if user % GRANULE != 0 || user < HEADER_SIZE {
+        return None;
+    }
+    let header = user - HEADER_SIZE;
+
+    // Only dereference headers inside a left redzone we poisoned
+    if let Some(shadow) = shadow {
+        if shadow.shadow_of(header) != Some(ShadowCode::LeftRedzone as u8) {
+            return None;
+        }
+    }
+    Some(header as *mut Header)
+}
+
+/// Find the allocation an address belongs to or is adjacent to
+fn find_allocation(shadow: &Shadow, addr: usize) -> Option<AllocationInfo> {
+    let granule = addr & !(GRANULE - 1);
+    let left = ShadowCode::LeftRedzone as u8;
+
+    let user = if shadow.shadow_of(granule) == Some(left) {
+        // Underflow: the allocation starts after this redzone
+        (0..MAX_SCAN / GRANULE)
+            .map(|i| granule + i * GRANULE)
+            .find(|&a| shadow.shadow_of(a) != Some(left))?
+    } else {
+        // Overflow or use-after-free: walk back to the left redzone
+        (0..MAX_SCAN / GRANULE)
+            .map(|i| granule.wrapping_sub(i * GRANULE))
+            .find(|&a| shadow.shadow_of(a) == Some(left))?
+            + GRANULE
+    };
+
+    // SAFETY: `header_of` only returns headers inside our redzones
+    let header = unsafe { *header_of(Some(shadow), user)? };
+    if header.magic != MAGIC_ALIVE && header.magic != MAGIC_FREED {
+        return None;
+    }
+
+    Some(AllocationInfo {
+        ptr: user,
+        size: header.size,
+        alloc_trace: header.alloc_trace,
+        free_trace: header.free_trace,
+    })
+}
+
+// =============================================================================
+// Instrumented Allocator
+// =============================================================================
+
+/// Heap allocator wrapper adding redzones, poisoning and quarantine
+pub struct KasanHeap {
+    inner: Arc<dyn HeapAllocator>,
+}
+
+impl KasanHeap {
+    /// Wrap an allocator
+    pub fn new(inner: Arc<dyn HeapAllocator>) -> Self {
+        Self { inner }
+    }
+
+    /// Return every quarantined block to the underlying allocator
+    pub fn flush_quarantine(&self) {
+        QUARANTINE.lock().drain(|entry| self.release(entry));
+    }
+
+    /// Hand a block back to the underlying allocator
+    fn release(&self, entry: QuarantineEntry) {
+        poison(entry.ptr as usize, entry.layout.size(), ShadowCode::HeapFree);
+        self.inner.deallocate(entry.ptr, entry.layout);
+    }
+
+    /// Report a bad free
+    fn report_free(&self, kind: BugKind, ptr: usize, header: Option<Header>) {
+        emit(Report {
+            kind,
+            addr: ptr,
+            size: 0,
+            is_write: false,
+            shadow: SHADOW.get().and_then(|s| s.shadow_of(ptr)).unwrap_or(0),
+            trace: StackTrace::capture(2),
+            allocation: header.map(|h| AllocationInfo {
+                ptr,
+                size: h.size,
+                alloc_trace: h.alloc_trace,
+                free_trace: h.free_trace,
+            }),
+        });
+    }
+}
+
+impl HeapAllocator for KasanHeap {
+    fn allocate(&self, layout: Layout) -> *mut u8 {
+        let align = layout.align().max(GRANULE);
+        let left = HEADER_SIZE.next_multiple_of(align);
+        let body = layout.size().next_multiple_of(GRANULE);
+        let Ok(block) = Layout::from_size_align(left + body + REDZONE_SIZE, align) else {
+            return core::ptr::null_mut();
+        };
+
+        let raw = self.inner.allocate(block);
+        if raw.is_null() {
+            return raw;
+        }
+
+        let base = raw as usize;
+        let user = base + left;
+        let header = Header {
+            magic: MAGIC_ALIVE,
+            size: layout.size(),
+            offset: left,
+            block_size: block.size(),
+            block_align: block.align(),
+            alloc_trace: StackTrace::capture(1),
+            free_trace: StackTrace::empty(),
+        };
+        // SAFETY: The header lies within the left redzone of our block
+        unsafe {
+            ((user - HEADER_SIZE) as *mut Header).write(header);
+        }
+
+        poison(base, left, ShadowCode::LeftRedzone);
+        poison(user + body, REDZONE_SIZE, ShadowCode::RightRedzone);
+        unpoison(user, layout.size());
+
+        user as *mut u8
+    }
+
+    fn deallocate(&self, ptr: *mut u8, _layout: Layout) {
+        let user = ptr as usize;
+        let Some(header_ptr) = header_of(SHADOW.get(), user) else {
+            return self.report_free(BugKind::InvalidFree, user, None);
+        };
+
+        // SAFETY: `header_of` validated the header location
+        let header = unsafe { &mut *header_ptr };
+        match header.magic {
+            MAGIC_ALIVE => {}
+            MAGIC_FREED => return self.report_free(BugKind::DoubleFree, user, Some(*header)),
+            _ => return self.report_free(BugKind::InvalidFree, user, None),
+        }
+
+        header.magic = MAGIC_FREED;
+        header.free_trace = StackTrace::capture(1);
+        poison(user, header.size.next_multiple_of(GRANULE), ShadowCode::Freed);
+
+        let entry = QuarantineEntry {
+            ptr: (user - header.offset) as *mut u8,
+            // SAFETY: Recorded from a valid layout in `allocate`
+            layout: unsafe { Layout::from_size_align_unchecked(header.block_size, header.block_align) },
+        };
+        QUARANTINE.lock().push(entry, |old| self.release(old));
+    }
+
+    fn reallocate(&self, ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
+        if !check_read(ptr as usize, old_layout.size().min(new_size)) {
+            return core::ptr::null_mut();
+        }
+
+        let Ok(new_layout) = Layout::from_size_align(new_size, old_layout.align()) else {
+            return core::ptr::null_mut();
+        };
+        let new_ptr = self.allocate(new_layout);
+        if !new_ptr.is_null() {
+            // SAFETY: Both ranges were checked / freshly allocated
+            unsafe {
+                core::ptr::copy_nonoverlapping(ptr, new_ptr, old_layout.size().min(new_size));
+            }
+            self.deallocate(ptr, old_layout);
+        }
+        new_ptr
+    }
+
+    fn name(&self) -> &'static str {
+        "KASAN"
+    }
+
+    fn stats(&self) -> HeapStats {
+        self.inner.stats()
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    /// Size of the tracked test heap
+    const HEAP_SIZE: usize = 1024 * 1024;
+
+    /// Bump allocator over the tracked heap that never reuses memory
+    struct BumpHeap {
+        next: AtomicUsize,
+        end: usize,
+    }
+
+    impl HeapAllocator for BumpHeap {
+        fn allocate(&self, layout: Layout) -> *mut u8 {
+            let mut start = 0;
+            let claimed = self.next.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| {
+                start = next.next_multiple_of(layout.align());
+                Some(start + layout.size()).filter(|&end| end <= self.end)
+            });
+            match claimed {
+                Ok(_) => start as *mut u8,
+                Err(_) => core::ptr::null_mut(),
+            }
+        }
+
+        fn deallocate(&self, _ptr: *mut u8, _layout: Layout) {}
+
+        fn name(&self) -> &'static str {
+            "Bump"
+        }
+
+        fn stats(&self) -> HeapStats {
+            HeapStats::default()
+        }
+    }
+
+    /// Sanitized heap shared by every test, since the shadow map is global
+    fn kasan() -> &'static KasanHeap {
+        static HEAP: Once<KasanHeap> = Once::new();
+        HEAP.call_once(|| {
+            let heap = Layout::from_size_align(HEAP_SIZE, 4096).unwrap();
+            let shadow = Layout::array::<u8>(Shadow::shadow_size(HEAP_SIZE)).unwrap();
+            // SAFETY: Non-zero sizes; both buffers are leaked to the sanitizer
+            let (base, shadow) = unsafe { (alloc::alloc::alloc(heap), alloc::alloc::alloc(shadow)) };
+            // SAFETY: The shadow buffer is sized for the heap and never freed
+            unsafe { init(base as usize, HEAP_SIZE, shadow) };
+            set_panic_on_report(true);
+
+            let bump = BumpHeap {
+                next: AtomicUsize::new(base as usize),
+                end: base as usize + HEAP_SIZE,
+            };
+            KasanHeap::new(Arc::new(bump))
+        })
+    }
+
+    #[test]
+    fn test_clean_alloc_free() {
+        let heap = kasan();
+        let layout = Layout::from_size_align(40, 16).unwrap();
+        let ptr = heap.allocate(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % 16, 0);
+        assert!(check_write(ptr as usize, 40));
+        heap.deallocate(ptr, layout);
+    }
+
+    #[test]
+    #[should_panic(expected = "slab-out-of-bounds")]
+    fn test_redzone_overwrite() {
+        let heap = kasan();
+        let layout = Layout::from_size_align(40, 8).unwrap();
+        let ptr = heap.allocate(layout);
+        // Straddles the end of the allocation into the right redzone
+        check_write(ptr as usize + 36, 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "use-after-free")]
+    fn test_use_after_free() {
+        let heap = kasan();
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let ptr = heap.allocate(layout);
+        heap.deallocate(ptr, layout);
+        // Still quarantined, so the memory has not been handed out again
+        check_read(ptr as usize + 8, 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid-free")]
+    fn test_corrupted_header_magic() {
+        let heap = kasan();
+        let layout = Layout::from_size_align(32, 8).unwrap();
+        let ptr = heap.allocate(layout);
+        // SAFETY: The header sits just below the user pointer
+        unsafe { (*((ptr as usize - HEADER_SIZE) as *mut Header)).magic ^= 1 };
+        heap.deallocate(ptr, layout);
+    }
+}
@@ -0,0 +1,98 @@
+//! # Quarantine
+//!
+//! Freed allocations are held back for a while before their memory is
+//! returned to the underlying allocator, so a use-after-free hits poisoned
+//! shadow instead of a recycled object.
+
+use core::alloc::Layout;
+
+/// Maximum number of quarantined allocations
+pub const QUARANTINE_SLOTS: usize = 256;
+
+/// Default quarantine budget in bytes (1 MB)
+pub const DEFAULT_QUARANTINE_BYTES: usize = 1024 * 1024;
+
+/// A quarantined allocation (as seen by the underlying allocator)
+#[derive(Debug, Clone, Copy)]
+pub struct QuarantineEntry {
+    /// Block pointer
+    pub ptr: *mut u8,
+    /// Block layout
+    pub layout: Layout,
+}
+
+/// FIFO of freed allocations bounded by count and bytes
+pub struct Quarantine {
+    entries: [Option<QuarantineEntry>; QUARANTINE_SLOTS],
+    head: usize,
+    len: usize,
+    bytes: usize,
+    max_bytes: usize,
+}
+
+// SAFETY: Entries are owned blocks only touched under the sanitizer lock.
+unsafe impl Send for Quarantine {}
+
+impl Quarantine {
+    /// Create an empty quarantine
+    pub const fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: [None; QUARANTINE_SLOTS],
+            head: 0,
+            len: 0,
+            bytes: 0,
+            max_bytes,
+        }
+    }
+
+    /// Quarantined bytes
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Quarantined allocations
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the quarantine is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove the oldest entry
+    pub fn pop(&mut self) -> Option<QuarantineEntry> {
+        if self.len == 0 {
+            return None;
+        }
+        let entry = self.entries[self.head].take()?;
+        self.head = (self.head + 1) % QUARANTINE_SLOTS;
+        self.len -= 1;
+        self.bytes -= entry.layout.size();
+        Some(entry)
+    }
+
+    /// Add a freed block, handing evicted blocks to `release`
+    pub fn push(&mut self, entry: QuarantineEntry, mut release: impl FnMut(QuarantineEntry)) {
+        while self.len == QUARANTINE_SLOTS
+            || (self.len > 0 && self.bytes + entry.layout.size() > self.max_bytes)
+        {
+            match self.pop() {
+                Some(old) => release(old),
+                None => break,
+            }
+        }
+
+        let tail = (self.head + self.len) % QUARANTINE_SLOTS;
+        self.entries[tail] = Some(entry);
+        self.len += 1;
+        self.bytes += entry.layout.size();
+    }
+
+    /// Release every quarantined block
+    pub fn drain(&mut self, mut release: impl FnMut(QuarantineEntry)) {
+        while let Some(entry) = self.pop() {
+            release(entry);
+        }
+    }
+}
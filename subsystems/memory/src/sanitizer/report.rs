@@ -0,0 +1,117 @@
+//! # Bug Reports
+//!
+//! Classification and formatting of sanitizer findings.
+
+use core::fmt;
+
+use super::shadow::ShadowCode;
+use super::trace::StackTrace;
+
+/// Kind of memory error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BugKind {
+    /// Access past the end or before the start of an allocation
+    OutOfBounds,
+    /// Access to freed memory
+    UseAfterFree,
+    /// Access to heap memory that was never allocated
+    WildAccess,
+    /// Second free of the same allocation
+    DoubleFree,
+    /// Free of a pointer not returned by the allocator
+    InvalidFree,
+}
+
+impl BugKind {
+    /// Classify a poisoned shadow byte
+    pub fn from_shadow(value: u8) -> Self {
+        match ShadowCode::from_u8(value) {
+            Some(ShadowCode::Freed) => Self::UseAfterFree,
+            Some(ShadowCode::HeapFree) | Some(ShadowCode::SlabFree) => Self::WildAccess,
+            // Redzones and partial granules
+            _ => Self::OutOfBounds,
+        }
+    }
+
+    /// Report title
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::OutOfBounds => "slab-out-of-bounds",
+            Self::UseAfterFree => "use-after-free",
+            Self::WildAccess => "wild-memory-access",
+            Self::DoubleFree => "double-free",
+            Self::InvalidFree => "invalid-free",
+        }
+    }
+}
+
+/// Allocation the faulting address belongs to
+#[derive(Debug, Clone, Copy)]
+pub struct AllocationInfo {
+    /// User pointer
+    pub ptr: usize,
+    /// Requested size
+    pub size: usize,
+    /// Where it was allocated
+    pub alloc_trace: StackTrace,
+    /// Where it was freed (empty if live)
+    pub free_trace: StackTrace,
+}
+
+/// A sanitizer finding
+#[derive(Debug, Clone, Copy)]
+pub struct Report {
+    /// Error kind
+    pub kind: BugKind,
+    /// Faulting address
+    pub addr: usize,
+    /// Access size (0 for free errors)
+    pub size: usize,
+    /// Access was a write
+    pub is_write: bool,
+    /// Shadow byte at the faulting address
+    pub shadow: u8,
+    /// Where the bad access happened
+    pub trace: StackTrace,
+    /// Owning allocation, if it could be found
+    pub allocation: Option<AllocationInfo>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "BUG: KASAN: {} at addr {:#x}", self.kind.name(), self.addr)?;
+        match self.kind {
+            BugKind::DoubleFree | BugKind::InvalidFree => writeln!(f, "Free of {:#x}", self.addr)?,
+            _ => writeln!(
+                f,
+                "{} of size {} at addr {:#x} (shadow {:#04x})",
+                if self.is_write { "Write" } else { "Read" },
+                self.size,
+                self.addr,
+                self.shadow
+            )?,
+        }
+        write!(f, "{}", self.trace)?;
+
+        if let Some(alloc) = &self.allocation {
+            let offset = self.addr as isize - alloc.ptr as isize;
+            writeln!(
+                f,
+                "Address is {} bytes {} {}-byte region [{:#x}, {:#x})",
+                offset.unsigned_abs(),
+                if offset < 0 { "before" } else if (offset as usize) < alloc.size { "inside" } else { "after" },
+                alloc.size,
+                alloc.ptr,
+                alloc.ptr + alloc.size
+            )?;
+            writeln!(f, "Allocated by:")?;
+            write!(f, "{}", alloc.alloc_trace)?;
+            if !alloc.free_trace.is_empty() {
+                writeln!(f, "Freed by:")?;
+                write!(f, "{}", alloc.free_trace)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+//! # Shadow Memory
+//!
+//! One shadow byte describes an 8-byte granule of the covered heap:
+//!
+//! | Shadow value | Meaning                                   |
+//! |--------------|-------------------------------------------|
+//! | `0x00`       | All 8 bytes accessible                    |
+//! | `0x01..0x07` | Only the first N bytes accessible         |
+//! | `0xF0..`     | Poisoned, see [`ShadowCode`]               |
+//!
+//! Addresses outside the covered region are never reported.
+
+use core::ptr;
+
+/// Bytes covered by one shadow byte
+pub const GRANULE: usize = 8;
+
+/// Granule shift (log2 of [`GRANULE`])
+pub const GRANULE_SHIFT: usize = 3;
+
+/// Poison codes stored in shadow memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ShadowCode {
+    /// Heap memory not handed out by the allocator
+    HeapFree = 0xF0,
+    /// Redzone before an allocation
+    LeftRedzone = 0xF1,
+    /// Redzone after an allocation
+    RightRedzone = 0xF2,
+    /// Freed allocation held in quarantine
+    Freed = 0xF3,
+    /// Free slab object
+    SlabFree = 0xF4,
+}
+
+impl ShadowCode {
+    /// Decode a poisoned shadow byte
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0xF0 => Some(Self::HeapFree),
+            0xF1 => Some(Self::LeftRedzone),
+            0xF2 => Some(Self::RightRedzone),
+            0xF3 => Some(Self::Freed),
+            0xF4 => Some(Self::SlabFree),
+            _ => None,
+        }
+    }
+}
+
+/// Shadow map for a contiguous heap region
+pub struct Shadow {
+    /// Start of the covered region (granule aligned)
+    base: usize,
+    /// Size of the covered region in bytes
+    size: usize,
+    /// Shadow bytes (`size / GRANULE` of them)
+    shadow: *mut u8,
+}
+
+// SAFETY: The shadow buffer is owned by the sanitizer for the kernel's
+// lifetime. Concurrent byte stores race only on granules that are being
+// allocated or freed by the caller, exactly like the underlying memory.
+unsafe impl Send for Shadow {}
+unsafe impl Sync for Shadow {}
+
+impl Shadow {
+    /// Shadow size needed to cover `size` bytes
+    pub const fn shadow_size(size: usize) -> usize {
+        size.div_ceil(GRANULE)
+    }
+
+    /// Create a shadow map, marking the whole region as free heap
+    ///
+    /// # Safety
+    ///
+    /// `shadow` must be valid for [`Shadow::shadow_size`]`(size)` bytes and
+    /// must not be used for anything else while the map exists.
+    pub unsafe fn new(base: usize, size: usize, shadow: *mut u8) -> Self {
+        debug_assert!(base % GRANULE == 0);
+        let map = Self { base, size, shadow };
+        // SAFETY: Caller guarantees the shadow buffer size
+        unsafe {
+            ptr::write_bytes(shadow, ShadowCode::HeapFree as u8, Self::shadow_size(size));
+        }
+        map
+    }
+
+    /// Check if the map covers an address
+    pub fn covers(&self, addr: usize) -> bool {
+        addr >= self.base && addr - self.base < self.size
+    }
+
+    /// Shadow byte of an address
+    fn load(&self, addr: usize) -> u8 {
+        let index = (addr - self.base) >> GRANULE_SHIFT;
+        // SAFETY: Callers check `covers(addr)`
+        unsafe { ptr::read_volatile(self.shadow.add(index)) }
+    }
+
+    /// Set the shadow of a granule-aligned range
+    fn fill(&self, addr: usize, size: usize, value: u8) {
+        let start = addr.max(self.base);
+        let end = (addr + size).min(self.base + self.size);
+        if start >= end {
+            return;
+        }
+
+        let first = (start - self.base) >> GRANULE_SHIFT;
+        let last = (end - self.base).div_ceil(GRANULE);
+        // SAFETY: Indices are clamped to the covered region
+        unsafe {
+            ptr::write_bytes(self.shadow.add(first), value, last - first);
+        }
+    }
+
+    /// Poison a granule-aligned range
+    pub fn poison(&self, addr: usize, size: usize, code: ShadowCode) {
+        self.fill(addr, size, code as u8);
+    }
+
+    /// Make `size` bytes at a granule-aligned address accessible
+    ///
+    /// A trailing partial granule gets a partial shadow value, so accesses
+    /// past `addr + size` are caught even inside the granule.
+    pub fn unpoison(&self, addr: usize, size: usize) {
+        let full = size & !(GRANULE - 1);
+        self.fill(addr, full, 0);
+
+        let tail = size - full;
+        if tail != 0 && self.covers(addr + full) {
+            let index = (addr + full - self.base) >> GRANULE_SHIFT;
+            // SAFETY: `covers` checked above
+            unsafe {
+                ptr::write_volatile(self.shadow.add(index), tail as u8);
+            }
+        }
+    }
+
+    /// Find the first poisoned byte in an access
+    ///
+    /// Returns the faulting address and its shadow byte.
+    pub fn check(&self, addr: usize, size: usize) -> Option<(usize, u8)> {
+        let end = addr.checked_add(size)?;
+        let mut cur = addr;
+
+        while cur < end {
+            if !self.covers(cur) {
+                cur = (cur | (GRANULE - 1)) + 1;
+                continue;
+            }
+
+            let value = self.load(cur);
+            let offset = (cur & (GRANULE - 1)) as u8;
+            if value != 0 && (value >= GRANULE as u8 || offset >= value) {
+                return Some((cur, value));
+            }
+
+            cur = if value == 0 { (cur | (GRANULE - 1)) + 1 } else { cur + 1 };
+        }
+
+        None
+    }
+
+    /// Shadow byte of an address, if covered
+    pub fn shadow_of(&self, addr: usize) -> Option<u8> {
+        self.covers(addr).then(|| self.load(addr))
+    }
+}
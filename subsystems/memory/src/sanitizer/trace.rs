@@ -0,0 +1,145 @@
+//! # Stack Traces
+//!
+//! Frame-pointer based stack capture and symbolization for sanitizer
+//! reports. The kernel registers a symbolizer once its symbol table is
+//! available; until then traces print raw return addresses.
+
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Maximum frames recorded per trace
+pub const MAX_FRAMES: usize = 8;
+
+/// A resolved symbol
+#[derive(Debug, Clone, Copy)]
+pub struct Symbol {
+    /// Symbol name
+    pub name: &'static str,
+    /// Offset of the address from the symbol start
+    pub offset: u64,
+}
+
+/// Symbol lookup hook
+pub type Symbolizer = fn(u64) -> Option<Symbol>;
+
+/// Registered symbolizer (0 if none)
+static SYMBOLIZER: AtomicUsize = AtomicUsize::new(0);
+
+/// Register the symbol lookup used when printing traces
+pub fn set_symbolizer(symbolizer: Symbolizer) {
+    SYMBOLIZER.store(symbolizer as usize, Ordering::Release);
+}
+
+/// Resolve an address with the registered symbolizer
+pub fn symbolize(addr: u64) -> Option<Symbol> {
+    let raw = SYMBOLIZER.load(Ordering::Acquire);
+    if raw == 0 {
+        return None;
+    }
+    // SAFETY: Only ever stored from a `Symbolizer` in `set_symbolizer`
+    let symbolizer: Symbolizer = unsafe { core::mem::transmute(raw) };
+    symbolizer(addr)
+}
+
+/// A captured stack trace
+#[derive(Clone, Copy)]
+pub struct StackTrace {
+    frames: [u64; MAX_FRAMES],
+    len: usize,
+}
+
+impl StackTrace {
+    /// Empty trace
+    pub const fn empty() -> Self {
+        Self {
+            frames: [0; MAX_FRAMES],
+            len: 0,
+        }
+    }
+
+    /// Capture the caller's stack, skipping `skip` frames
+    #[inline(never)]
+    pub fn capture(skip: usize) -> Self {
+        let mut trace = Self::empty();
+        let mut fp = frame_pointer();
+        let mut skip = skip;
+
+        while fp != 0 && fp % 8 == 0 && trace.len < MAX_FRAMES {
+            // SAFETY: Kernel code is built with frame pointers; each frame
+            // holds the previous frame pointer followed by the return address.
+            let (next, ret) = unsafe {
+                let frame = fp as *const u64;
+                (frame.read(), frame.add(1).read())
+            };
+            if ret == 0 {
+                break;
+            }
+
+            if skip == 0 {
+                trace.frames[trace.len] = ret;
+                trace.len += 1;
+            } else {
+                skip -= 1;
+            }
+
+            // Stacks grow down: a sane caller frame is above this one
+            if next <= fp {
+                break;
+            }
+            fp = next;
+        }
+
+        trace
+    }
+
+    /// Recorded return addresses, innermost first
+    pub fn frames(&self) -> &[u64] {
+        &self.frames[..self.len]
+    }
+
+    /// Check if nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl fmt::Display for StackTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "    <no frames>");
+        }
+        for (i, &addr) in self.frames().iter().enumerate() {
+            match symbolize(addr) {
+                Some(sym) => writeln!(f, "    #{} {:#018x} {}+{:#x}", i, addr, sym.name, sym.offset)?,
+                None => writeln!(f, "    #{} {:#018x}", i, addr)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for StackTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.frames()).finish()
+    }
+}
+
+/// Current frame pointer (0 where the frame layout is not supported)
+#[inline(always)]
+fn frame_pointer() -> u64 {
+    let fp: u64;
+    // SAFETY: Reading the frame pointer register has no side effects
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        core::arch::asm!("mov {}, rbp", out(reg) fp, options(nomem, nostack, preserves_flags));
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        core::arch::asm!("mov {}, x29", out(reg) fp, options(nomem, nostack, preserves_flags));
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        fp = 0;
+    }
+    fp
+}
@@ -6,6 +6,11 @@
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU64, Ordering};
 
+/// Lowest battery charge (percent) at which an update may start off AC
+///
+/// A flash interrupted by a dead battery can leave the machine unbootable.
+pub const MIN_UPDATE_BATTERY_PERCENT: u8 = 25;
+
 /// Firmware update state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UpdateState {
@@ -81,6 +86,10 @@ pub struct FirmwareUpdateManager {
     next_id: AtomicU64,
     /// Updates allowed
     updates_allowed: bool,
+    /// Running on external power
+    on_ac: bool,
+    /// Battery charge (percent), if the system has a battery
+    battery_level: Option<u8>,
 }
 
 impl FirmwareUpdateManager {
@@ -93,6 +102,8 @@ pub fn new() -> Self {
             failed_updates: Vec::new(),
             next_id: AtomicU64::new(1),
             updates_allowed: true,
+            on_ac: true,
+            battery_level: None,
         }
     }
 
@@ -117,8 +128,11 @@ pub fn pending_updates(&self) -> &[FirmwareUpdate] {
     }
 
     /// Start update
+    ///
+    /// Refused while another update runs, when updates are disabled, or on
+    /// a battery below [`MIN_UPDATE_BATTERY_PERCENT`].
     pub fn start_update(&mut self, id: u64) -> bool {
-        if self.active_update.is_some() || !self.updates_allowed {
+        if self.active_update.is_some() || !self.updates_allowed || !self.power_sufficient() {
             return false;
         }
 
@@ -175,6 +189,17 @@ pub fn updates_allowed(&self) -> bool {
         self.updates_allowed
     }
 
+    /// Record the power state reported by the power supply drivers
+    pub fn set_power_state(&mut self, on_ac: bool, battery_level: Option<u8>) {
+        self.on_ac = on_ac;
+        self.battery_level = battery_level;
+    }
+
+    /// Is there enough power to start an update?
+    pub fn power_sufficient(&self) -> bool {
+        self.on_ac || self.battery_level.is_none_or(|level| level >= MIN_UPDATE_BATTERY_PERCENT)
+    }
+
     /// Get active update ID
     pub fn active_update(&self) -> Option<u64> {
         self.active_update
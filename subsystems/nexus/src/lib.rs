@@ -479,8 +479,8 @@
     SpinlockAnalyzer, SyncIntelligence, ThreadId, WaitTimeModel, WaitTimePredictor,
 };
 pub use crate::telemetry::{
-    AlertRule, Counter, DataPoint, Gauge, TelemetryHistogram, TelemetryRegistry,
-    TimeSeries as TelemetryTimeSeries,
+    AlertRule, AtomicHistogram, Counter, DataPoint, Gauge, MetricsEndpoint, MetricsRegistry,
+    TelemetryHistogram, TelemetryRegistry, TimeSeries as TelemetryTimeSeries,
 };
 // Q1 Re-exports
 pub use crate::testing::{TestCase, TestResult, TestRunner, TestSuite};
@@ -179,7 +179,7 @@ pub struct CacheEvent {
 // ============================================================================
 
 /// Event type
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EventType {
     /// Hardware event
     Hardware(HardwareEvent),
@@ -0,0 +1,567 @@
+//! Perf Event File Descriptors
+//!
+//! Backs the `perf_event_open` syscall: userspace describes an event with
+//! a [`PerfEventAttr`], attaches it to a process, a CPU or both, and gets
+//! a descriptor to read the count from, toggle with ioctls, and `mmap`
+//! for samples.
+//!
+//! Counts are pushed by their sources through [`PerfFdTable::account`]:
+//! PMU overflow interrupts for hardware events, the scheduler and fault
+//! handlers for software events, tracepoint probes for tracepoints.
+//! Process-attached events only run while the process is scheduled in
+//! ([`PerfFdTable::sched_in`] / [`PerfFdTable::sched_out`]). Hardware
+//! events take a counter from the matching [`Pmu`](super::Pmu); without a
+//! free counter they stay enabled but not running, and readers scale the
+//! count by `time_enabled / time_running`.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use super::ring::PerfRingBuffer;
+use super::{
+    CacheEvent, CacheLevel, CacheOp, CacheResult, CpuId, EventConfig, EventId, EventType,
+    HardwareEvent, PerfManager, PmuType, Sample, SoftwareEvent,
+};
+
+// ============================================================================
+// ABI
+// ============================================================================
+
+/// `PerfEventAttr::event_type` values
+pub mod perf_type {
+    /// Generic hardware event (`config` = hardware event index)
+    pub const HARDWARE: u32 = 0;
+    /// Software event (`config` = software event index)
+    pub const SOFTWARE: u32 = 1;
+    /// Tracepoint (`config` = tracepoint ID)
+    pub const TRACEPOINT: u32 = 2;
+    /// Cache event (`config` = level | op << 8 | result << 16)
+    pub const HW_CACHE: u32 = 3;
+    /// Raw PMU event code
+    pub const RAW: u32 = 4;
+}
+
+/// `PerfEventAttr::flags` bits
+pub mod attr_flags {
+    /// Start disabled
+    pub const DISABLED: u64 = 1 << 0;
+    /// Count in children
+    pub const INHERIT: u64 = 1 << 1;
+    /// Always on the PMU
+    pub const PINNED: u64 = 1 << 2;
+    /// Do not count user mode
+    pub const EXCLUDE_USER: u64 = 1 << 4;
+    /// Do not count kernel mode
+    pub const EXCLUDE_KERNEL: u64 = 1 << 5;
+    /// Do not count hypervisor mode
+    pub const EXCLUDE_HV: u64 = 1 << 6;
+    /// Do not count when idle
+    pub const EXCLUDE_IDLE: u64 = 1 << 7;
+}
+
+/// `PerfEventAttr::read_format` bits
+pub mod read_format {
+    /// Include time enabled
+    pub const TOTAL_TIME_ENABLED: u64 = 1 << 0;
+    /// Include time running
+    pub const TOTAL_TIME_RUNNING: u64 = 1 << 1;
+    /// Include the event ID
+    pub const ID: u64 = 1 << 2;
+}
+
+/// ioctl requests on a perf descriptor
+pub mod ioctl {
+    /// Start counting
+    pub const ENABLE: u64 = 0x2400;
+    /// Stop counting
+    pub const DISABLE: u64 = 0x2401;
+    /// Zero the count
+    pub const RESET: u64 = 0x2403;
+}
+
+/// Event description passed to `perf_event_open`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PerfEventAttr {
+    /// [`perf_type`] value
+    pub event_type: u32,
+    /// Size of this structure
+    pub size: u32,
+    /// Event selector, meaning depends on `event_type`
+    pub config: u64,
+    /// Sample every N events (0 = counting only)
+    pub sample_period: u64,
+    /// [`SampleType`](super::SampleType) bits
+    pub sample_type: u64,
+    /// [`read_format`] bits
+    pub read_format: u64,
+    /// [`attr_flags`] bits
+    pub flags: u64,
+}
+
+impl PerfEventAttr {
+    /// Size of the structure userspace must pass
+    pub const SIZE: u32 = core::mem::size_of::<Self>() as u32;
+}
+
+/// What an event counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerfTarget {
+    /// One process, on any CPU
+    Process(i32),
+    /// Everything on one CPU
+    Cpu(CpuId),
+    /// One process, only while on one CPU
+    ProcessOnCpu(i32, CpuId),
+}
+
+impl PerfTarget {
+    /// Build from `perf_event_open` `pid` / `cpu` arguments (-1 = any)
+    pub fn from_args(pid: i32, cpu: i32) -> Result<Self, PerfError> {
+        match (pid, cpu) {
+            (-1, -1) => Err(PerfError::InvalidArgument),
+            (-1, cpu) if cpu >= 0 => Ok(Self::Cpu(CpuId(cpu as u32))),
+            (pid, -1) if pid >= 0 => Ok(Self::Process(pid)),
+            (pid, cpu) if pid >= 0 && cpu >= 0 => Ok(Self::ProcessOnCpu(pid, CpuId(cpu as u32))),
+            _ => Err(PerfError::InvalidArgument),
+        }
+    }
+
+    fn pid(&self) -> Option<i32> {
+        match *self {
+            Self::Process(pid) | Self::ProcessOnCpu(pid, _) => Some(pid),
+            Self::Cpu(_) => None,
+        }
+    }
+
+    fn cpu(&self) -> Option<CpuId> {
+        match *self {
+            Self::Cpu(cpu) | Self::ProcessOnCpu(_, cpu) => Some(cpu),
+            Self::Process(_) => None,
+        }
+    }
+
+    /// Does an occurrence by `pid` on `cpu` count?
+    fn matches(&self, cpu: CpuId, pid: i32) -> bool {
+        self.pid().is_none_or(|p| p == pid) && self.cpu().is_none_or(|c| c == cpu)
+    }
+}
+
+/// Perf syscall errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerfError {
+    /// Malformed attribute or arguments
+    InvalidArgument,
+    /// Unknown event
+    NoSuchEvent,
+    /// No PMU provides the event
+    NotSupported,
+    /// Unknown descriptor
+    BadDescriptor,
+    /// Ring buffer already mapped
+    Busy,
+    /// Ring buffer allocation failed
+    OutOfMemory,
+}
+
+/// Value returned by `read` on a perf descriptor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PerfReadValue {
+    /// Count
+    pub value: u64,
+    /// Time enabled (ns), if requested
+    pub time_enabled: Option<u64>,
+    /// Time running (ns), if requested
+    pub time_running: Option<u64>,
+    /// Event ID, if requested
+    pub id: Option<u64>,
+}
+
+impl PerfReadValue {
+    /// Serialize in `read_format` order; returns the bytes written
+    pub fn write_to(&self, buf: &mut [u8]) -> Option<usize> {
+        let words = [Some(self.value), self.time_enabled, self.time_running, self.id];
+        let mut len = 0;
+        for word in words.into_iter().flatten() {
+            buf.get_mut(len..len + 8)?.copy_from_slice(&word.to_ne_bytes());
+            len += 8;
+        }
+        Some(len)
+    }
+}
+
+// ============================================================================
+// DESCRIPTOR TABLE
+// ============================================================================
+
+/// An open perf descriptor
+struct PerfFile {
+    event: EventId,
+    target: PerfTarget,
+    attr: PerfEventAttr,
+    /// Holds a PMU counter
+    has_counter: bool,
+    /// Enabled by the user
+    enabled: bool,
+    /// Target process currently scheduled in (always true for CPU events)
+    scheduled: bool,
+    /// Time of the last time accounting
+    last_update: u64,
+    /// Events left until the next sample
+    period_left: u64,
+    ring: Option<PerfRingBuffer>,
+}
+
+impl PerfFile {
+    fn running(&self) -> bool {
+        self.enabled && self.scheduled && self.has_counter
+    }
+}
+
+/// Open perf events, by descriptor
+pub struct PerfFdTable {
+    manager: PerfManager,
+    files: BTreeMap<i32, PerfFile>,
+    /// Tracepoint ID -> (system, name)
+    tracepoints: BTreeMap<u64, (String, String)>,
+    next_fd: i32,
+}
+
+impl PerfFdTable {
+    /// Create a table over the PMUs registered with `manager`
+    pub fn new(manager: PerfManager) -> Self {
+        Self {
+            manager,
+            files: BTreeMap::new(),
+            tracepoints: BTreeMap::new(),
+            next_fd: 0,
+        }
+    }
+
+    /// PMU and event bookkeeping
+    pub fn manager(&self) -> &PerfManager {
+        &self.manager
+    }
+
+    /// Make a tracepoint openable by ID
+    pub fn register_tracepoint(&mut self, id: u64, system: &str, name: &str) {
+        self.tracepoints.insert(id, (String::from(system), String::from(name)));
+    }
+
+    /// Translate a userspace attribute into an event type
+    fn event_type(&self, attr: &PerfEventAttr) -> Result<EventType, PerfError> {
+        use HardwareEvent as Hw;
+        use SoftwareEvent as Sw;
+
+        let config = attr.config;
+        match attr.event_type {
+            perf_type::HARDWARE => {
+                let events = [
+                    Hw::CpuCycles,
+                    Hw::Instructions,
+                    Hw::CacheReferences,
+                    Hw::CacheMisses,
+                    Hw::BranchInstructions,
+                    Hw::BranchMisses,
+                    Hw::BusCycles,
+                    Hw::StalledCyclesFrontend,
+                    Hw::StalledCyclesBackend,
+                    Hw::RefCpuCycles,
+                ];
+                events.get(config as usize).map(|&e| EventType::Hardware(e))
+            },
+            perf_type::SOFTWARE => {
+                let event = match config {
+                    2 => Some(Sw::PageFaults),
+                    3 => Some(Sw::ContextSwitches),
+                    4 => Some(Sw::CpuMigrations),
+                    5 => Some(Sw::MinorFaults),
+                    6 => Some(Sw::MajorFaults),
+                    7 => Some(Sw::AlignmentFaults),
+                    8 => Some(Sw::EmulationFaults),
+                    9 => Some(Sw::Dummy),
+                    _ => None,
+                };
+                event.map(EventType::Software)
+            },
+            perf_type::TRACEPOINT => self.tracepoints.get(&config).map(|(system, name)| {
+                EventType::Tracepoint {
+                    system: system.clone(),
+                    name: name.clone(),
+                }
+            }),
+            perf_type::HW_CACHE => {
+                let levels = [
+                    CacheLevel::L1D,
+                    CacheLevel::L1I,
+                    CacheLevel::LL,
+                    CacheLevel::DTLB,
+                    CacheLevel::ITLB,
+                    CacheLevel::BPU,
+                    CacheLevel::Node,
+                ];
+                let ops = [CacheOp::Read, CacheOp::Write, CacheOp::Prefetch];
+                let results = [CacheResult::Access, CacheResult::Miss];
+                let level = levels.get((config & 0xff) as usize);
+                let op = ops.get(((config >> 8) & 0xff) as usize);
+                let result = results.get(((config >> 16) & 0xff) as usize);
+                match (level, op, result) {
+                    (Some(&level), Some(&op), Some(&result)) => {
+                        Some(EventType::Cache(CacheEvent { level, op, result }))
+                    },
+                    _ => None,
+                }
+            },
+            perf_type::RAW => Some(EventType::Raw(config)),
+            _ => return Err(PerfError::InvalidArgument),
+        }
+        .ok_or(PerfError::NoSuchEvent)
+    }
+
+    fn pmu_type(event: &EventType) -> PmuType {
+        match event {
+            EventType::Hardware(_) | EventType::Cache(_) => PmuType::Core,
+            EventType::Raw(_) => PmuType::RawHardware,
+            EventType::Software(_) => PmuType::Software,
+            EventType::Tracepoint { .. } => PmuType::Tracepoint,
+        }
+    }
+
+    /// `perf_event_open`: returns the new descriptor
+    pub fn open(&mut self, attr: &PerfEventAttr, target: PerfTarget, now: u64) -> Result<i32, PerfError> {
+        if attr.size != PerfEventAttr::SIZE {
+            return Err(PerfError::InvalidArgument);
+        }
+        let event_type = self.event_type(attr)?;
+
+        // Raw codes may also be served by the core PMU
+        let pmu_type = Self::pmu_type(&event_type);
+        let pmu = self
+            .manager
+            .pmus
+            .values()
+            .find(|p| p.pmu_type == pmu_type)
+            .or_else(|| {
+                (pmu_type == PmuType::RawHardware).then(|| self.manager.core_pmu()).flatten()
+            })
+            .ok_or(PerfError::NotSupported)?;
+        let pmu_id = pmu.id;
+        if attr.sample_period != 0 && pmu.pmu_type.is_hardware() && !pmu.capabilities.supports_sampling {
+            return Err(PerfError::NotSupported);
+        }
+
+        let flags = attr.flags;
+        let mut config = EventConfig::new(event_type).with_period(attr.sample_period);
+        config.exclude_user = flags & attr_flags::EXCLUDE_USER != 0;
+        config.exclude_kernel = flags & attr_flags::EXCLUDE_KERNEL != 0;
+        config.exclude_hv = flags & attr_flags::EXCLUDE_HV != 0;
+        config.exclude_idle = flags & attr_flags::EXCLUDE_IDLE != 0;
+        config.inherit = flags & attr_flags::INHERIT != 0;
+        config.pinned = flags & attr_flags::PINNED != 0;
+
+        let event = self.manager.create_event(config, pmu_id);
+        if let Some(e) = self.manager.get_event_mut(event) {
+            e.cpu = target.cpu();
+            e.pid = target.pid();
+        }
+
+        let fd = self.next_fd;
+        self.next_fd += 1;
+        self.files.insert(fd, PerfFile {
+            event,
+            target,
+            attr: *attr,
+            has_counter: false,
+            enabled: false,
+            scheduled: target.pid().is_none(),
+            last_update: now,
+            period_left: attr.sample_period,
+            ring: None,
+        });
+
+        if flags & attr_flags::DISABLED == 0 {
+            self.enable(fd, now)?;
+        }
+        Ok(fd)
+    }
+
+    /// Close a descriptor, releasing its counter and ring
+    pub fn close(&mut self, fd: i32, now: u64) -> Result<(), PerfError> {
+        self.disable(fd, now)?;
+        let file = self.files.remove(&fd).ok_or(PerfError::BadDescriptor)?;
+        self.manager.events.remove(&file.event);
+        Ok(())
+    }
+
+    /// Is `fd` an open perf descriptor?
+    pub fn contains(&self, fd: i32) -> bool {
+        self.files.contains_key(&fd)
+    }
+
+    /// Handle an ioctl on `fd`
+    pub fn ioctl(&mut self, fd: i32, request: u64, now: u64) -> Result<(), PerfError> {
+        match request {
+            ioctl::ENABLE => self.enable(fd, now),
+            ioctl::DISABLE => self.disable(fd, now),
+            ioctl::RESET => {
+                let file = self.files.get_mut(&fd).ok_or(PerfError::BadDescriptor)?;
+                file.period_left = file.attr.sample_period;
+                if let Some(event) = self.manager.get_event(file.event) {
+                    event.update_count(0);
+                }
+                Ok(())
+            },
+            _ => Err(PerfError::InvalidArgument),
+        }
+    }
+
+    /// Start counting
+    pub fn enable(&mut self, fd: i32, now: u64) -> Result<(), PerfError> {
+        self.update_times(fd, now)?;
+        let file = self.files.get_mut(&fd).ok_or(PerfError::BadDescriptor)?;
+        if file.enabled {
+            return Ok(());
+        }
+        file.enabled = true;
+
+        let event = self.manager.get_event(file.event).ok_or(PerfError::BadDescriptor)?;
+        let has_free_counter = self
+            .manager
+            .get_pmu(event.pmu)
+            .is_some_and(|pmu| !pmu.pmu_type.is_hardware() || pmu.available_counters() > 0);
+        if has_free_counter {
+            file.has_counter = self.manager.start_event(file.event);
+        }
+        Ok(())
+    }
+
+    /// Stop counting
+    pub fn disable(&mut self, fd: i32, now: u64) -> Result<(), PerfError> {
+        self.update_times(fd, now)?;
+        let file = self.files.get_mut(&fd).ok_or(PerfError::BadDescriptor)?;
+        file.enabled = false;
+        if core::mem::take(&mut file.has_counter) {
+            self.manager.stop_event(file.event);
+        }
+        Ok(())
+    }
+
+    /// Read the count of `fd` in its `read_format`
+    pub fn read(&mut self, fd: i32, now: u64) -> Result<PerfReadValue, PerfError> {
+        self.update_times(fd, now)?;
+        let file = self.files.get(&fd).ok_or(PerfError::BadDescriptor)?;
+        let event = self.manager.get_event(file.event).ok_or(PerfError::BadDescriptor)?;
+        let format = file.attr.read_format;
+        Ok(PerfReadValue {
+            value: event.count(),
+            time_enabled: (format & read_format::TOTAL_TIME_ENABLED != 0).then(|| event.time_enabled()),
+            time_running: (format & read_format::TOTAL_TIME_RUNNING != 0).then(|| event.time_running()),
+            id: (format & read_format::ID != 0).then_some(file.event.0),
+        })
+    }
+
+    /// Create the sample ring of `fd` with `data_pages` data pages
+    ///
+    /// Returns the ring for the caller to map into the process.
+    pub fn mmap(&mut self, fd: i32, data_pages: usize) -> Result<&PerfRingBuffer, PerfError> {
+        let file = self.files.get_mut(&fd).ok_or(PerfError::BadDescriptor)?;
+        if file.ring.is_some() {
+            return Err(PerfError::Busy);
+        }
+        let ring = PerfRingBuffer::new(file.event.0, data_pages).ok_or(PerfError::OutOfMemory)?;
+        Ok(file.ring.insert(ring))
+    }
+
+    /// Sample ring of `fd`, if mapped
+    pub fn mmap_ring(&self, fd: i32) -> Option<&PerfRingBuffer> {
+        self.files.get(&fd)?.ring.as_ref()
+    }
+
+    /// Charge elapsed time to the event of `fd`
+    fn update_times(&mut self, fd: i32, now: u64) -> Result<(), PerfError> {
+        let file = self.files.get_mut(&fd).ok_or(PerfError::BadDescriptor)?;
+        let elapsed = now.saturating_sub(file.last_update);
+        file.last_update = now;
+
+        let event = self.manager.get_event(file.event).ok_or(PerfError::BadDescriptor)?;
+        if file.enabled && elapsed > 0 {
+            event.add_time(elapsed, if file.running() { elapsed } else { 0 });
+        }
+        if let Some(ring) = file.ring.as_mut() {
+            ring.update_counts(event.count(), event.time_enabled(), event.time_running());
+        }
+        Ok(())
+    }
+
+    // ========================================================================
+    // SOURCES
+    // ========================================================================
+
+    /// Count `delta` occurrences of `event` by `sample.pid` on `sample.cpu`
+    ///
+    /// Sampling events write a sample to their ring every `sample_period`
+    /// occurrences. Returns the number of samples written.
+    pub fn account(&mut self, event: &EventType, delta: u64, sample: &Sample) -> usize {
+        let cpu = sample.cpu.unwrap_or(CpuId(0));
+        let pid = sample.pid.unwrap_or(-1);
+        let mut written = 0;
+
+        for file in self.files.values_mut() {
+            if !file.running() || !file.target.matches(cpu, pid) {
+                continue;
+            }
+            let Some(perf_event) = self.manager.events.get(&file.event) else {
+                continue;
+            };
+            if perf_event.config.event_type != *event {
+                continue;
+            }
+            perf_event.add_count(delta);
+
+            let period = file.attr.sample_period;
+            if period == 0 {
+                continue;
+            }
+            let mut remaining = delta;
+            while remaining >= file.period_left {
+                remaining -= file.period_left;
+                file.period_left = period;
+                if let Some(ring) = file.ring.as_mut() {
+                    let mut sample = sample.clone();
+                    sample.period = Some(period);
+                    if ring.write_sample(file.attr.sample_type, file.event.0, &sample) {
+                        perf_event.record_sample();
+                        written += 1;
+                    }
+                }
+            }
+            file.period_left -= remaining;
+        }
+        written
+    }
+
+    /// A process is about to run on `cpu`
+    pub fn sched_in(&mut self, cpu: CpuId, pid: i32, now: u64) {
+        self.set_scheduled(cpu, pid, true, now);
+    }
+
+    /// A process stopped running on `cpu`
+    pub fn sched_out(&mut self, cpu: CpuId, pid: i32, now: u64) {
+        self.set_scheduled(cpu, pid, false, now);
+    }
+
+    fn set_scheduled(&mut self, cpu: CpuId, pid: i32, scheduled: bool, now: u64) {
+        let fds: alloc::vec::Vec<i32> = self
+            .files
+            .iter()
+            .filter(|(_, f)| f.target.pid() == Some(pid) && f.target.cpu().is_none_or(|c| c == cpu))
+            .map(|(&fd, _)| fd)
+            .collect();
+        for fd in fds {
+            let _ = self.update_times(fd, now);
+            if let Some(file) = self.files.get_mut(&fd) {
+                file.scheduled = scheduled;
+            }
+        }
+    }
+}
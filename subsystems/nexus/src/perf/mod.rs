@@ -0,0 +1,202 @@
+//! Performance Monitoring Intelligence Module
+//!
+//! This module provides AI-powered performance monitoring analysis including
+//! PMU hardware counters, perf events, sampling, and workload characterization,
+//! and the `perf_event_open` descriptors and mmap ring buffers that export
+//! counters and samples to userspace.
+
+// Submodules
+mod events;
+mod fd;
+mod intelligence;
+mod manager;
+mod metrics;
+mod perf_event;
+mod pmu;
+mod ring;
+mod types;
+mod workload;
+
+// Re-export core types
+// Re-export event types
+pub use events::{
+    CacheEvent, CacheLevel, CacheOp, CacheResult, EventType, HardwareEvent, SoftwareEvent,
+};
+// Re-export perf_event_open interface
+pub use fd::{
+    attr_flags, ioctl, perf_type, read_format, PerfError, PerfEventAttr, PerfFdTable,
+    PerfReadValue, PerfTarget,
+};
+// Re-export intelligence
+pub use intelligence::{
+    PerfAction, PerfAnalysis, PerfIntelligence, PerfIssue, PerfIssueType, PerfRecommendation,
+};
+// Re-export manager
+pub use manager::PerfManager;
+// Re-export metrics
+pub use metrics::{BranchMissRate, CacheMissRate, Ipc, PerfMetrics};
+// Re-export perf event types
+pub use perf_event::{EventConfig, EventState, PerfEvent, Sample, SampleType};
+// Re-export PMU types
+pub use pmu::{Pmu, PmuCapabilities, PmuType};
+// Re-export ring buffer
+pub use ring::{PerfMmapPage, PerfRingBuffer, RecordHeader, RecordType, PAGE_SIZE};
+pub use types::{CpuId, EventId, PmuId};
+// Re-export workload
+pub use workload::{WorkloadAnalysis, WorkloadCharacter};
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipc() {
+        let ipc = Ipc::calculate(2_000_000_000, 1_000_000_000);
+        assert!((ipc.0 - 2.0).abs() < 0.01);
+        assert!(ipc.is_good());
+    }
+
+    #[test]
+    fn test_cache_miss_rate() {
+        let rate = CacheMissRate::calculate(1000, 50);
+        assert!((rate.0 - 5.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_perf_metrics() {
+        let mut metrics = PerfMetrics::new();
+        metrics.cycles = 1_000_000_000;
+        metrics.instructions = 1_500_000_000;
+        metrics.cache_refs = 10000;
+        metrics.cache_misses = 500;
+        metrics.calculate_derived();
+
+        assert!(metrics.ipc.unwrap() > 1.0);
+        assert!((metrics.cache_miss_rate.unwrap() - 5.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_workload_analysis() {
+        let mut metrics = PerfMetrics::new();
+        metrics.cycles = 1_000_000_000;
+        metrics.instructions = 2_000_000_000;
+        metrics.ipc = Some(2.0);
+
+        let analysis = WorkloadAnalysis::from_metrics(&metrics);
+        assert!(matches!(analysis.character, WorkloadCharacter::CpuBound));
+    }
+
+    fn perf_table() -> PerfFdTable {
+        let mut manager = PerfManager::new();
+        let mut core = Pmu::new(PmuId(0), alloc::string::String::from("cpu"), PmuType::Core);
+        core.capabilities.num_counters = 1;
+        core.capabilities.supports_sampling = true;
+        manager.register_pmu(core);
+        manager.register_pmu(Pmu::new(
+            PmuId(1),
+            alloc::string::String::from("software"),
+            PmuType::Software,
+        ));
+        PerfFdTable::new(manager)
+    }
+
+    fn attr(event_type: u32, config: u64) -> PerfEventAttr {
+        PerfEventAttr {
+            event_type,
+            size: PerfEventAttr::SIZE,
+            config,
+            read_format: read_format::TOTAL_TIME_ENABLED | read_format::TOTAL_TIME_RUNNING,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_perf_fd_counting() {
+        let mut table = perf_table();
+        let cycles = attr(perf_type::HARDWARE, 0);
+        let fd = table.open(&cycles, PerfTarget::Process(7), 0).unwrap();
+
+        // Process not scheduled: no counting
+        let mut sample = Sample::new();
+        sample.pid = Some(7);
+        sample.cpu = Some(CpuId(0));
+        table.account(&EventType::Hardware(HardwareEvent::CpuCycles), 100, &sample);
+        assert_eq!(table.read(fd, 10).unwrap().value, 0);
+
+        table.sched_in(CpuId(0), 7, 10);
+        table.account(&EventType::Hardware(HardwareEvent::CpuCycles), 100, &sample);
+        table.sched_out(CpuId(0), 7, 30);
+        let value = table.read(fd, 40).unwrap();
+        assert_eq!(value.value, 100);
+        assert_eq!(value.time_enabled, Some(40));
+        assert_eq!(value.time_running, Some(20));
+
+        // The only counter is taken: a second event is enabled but not running
+        let fd2 = table.open(&cycles, PerfTarget::Cpu(CpuId(0)), 40).unwrap();
+        assert_eq!(table.read(fd2, 50).unwrap().time_running, Some(0));
+
+        table.ioctl(fd, ioctl::RESET, 50).unwrap();
+        assert_eq!(table.read(fd, 50).unwrap().value, 0);
+        table.close(fd, 60).unwrap();
+        assert_eq!(table.read(fd, 60), Err(PerfError::BadDescriptor));
+
+        assert_eq!(
+            table.open(&attr(perf_type::TRACEPOINT, 1), PerfTarget::Process(7), 0),
+            Err(PerfError::NoSuchEvent)
+        );
+    }
+
+    #[test]
+    fn test_perf_ring_sampling() {
+        let mut table = perf_table();
+        let mut faults = attr(perf_type::SOFTWARE, 2);
+        faults.sample_period = 10;
+        faults.sample_type = SampleType::IP | SampleType::TID | SampleType::PERIOD;
+        let fd = table.open(&faults, PerfTarget::Cpu(CpuId(1)), 0).unwrap();
+        let ring = table.mmap(fd, 1).unwrap();
+        assert_eq!(ring.mapping_size(), 2 * PAGE_SIZE);
+        assert_eq!(table.mmap(fd, 1).err(), Some(PerfError::Busy));
+
+        let mut sample = Sample::new();
+        sample.cpu = Some(CpuId(1));
+        sample.pid = Some(3);
+        sample.tid = Some(4);
+        sample.ip = Some(0x1000);
+        let event = EventType::Software(SoftwareEvent::PageFaults);
+        assert_eq!(table.account(&event, 25, &sample), 2);
+        assert_eq!(table.account(&event, 5, &sample), 1);
+
+        let ring = table.mmap_ring(fd).unwrap();
+        let (header, words) = ring.read_record().unwrap();
+        assert_eq!(header.record_type, RecordType::Sample as u32);
+        assert_eq!(words, [0x1000, 3 | (4 << 32), 10]);
+        assert!(ring.read_record().is_some());
+        assert!(ring.read_record().is_some());
+        assert!(ring.read_record().is_none());
+    }
+
+    #[test]
+    fn test_perf_intelligence() {
+        let mut intel = PerfIntelligence::new();
+
+        let mut metrics = PerfMetrics::new();
+        metrics.cycles = 1_000_000_000;
+        metrics.instructions = 400_000_000; // Low IPC
+        metrics.ipc = Some(0.4);
+
+        intel.update_metrics(metrics);
+
+        let analysis = intel.analyze();
+        // Should detect low IPC
+        assert!(
+            analysis
+                .issues
+                .iter()
+                .any(|i| matches!(i.issue_type, PerfIssueType::LowIpc))
+        );
+    }
+}
@@ -163,6 +163,22 @@ pub fn time_running(&self) -> u64 {
         self.time_running.load(Ordering::Relaxed)
     }
 
+    /// Charge elapsed time (ns) enabled and actually counting
+    pub fn add_time(&self, enabled: u64, running: u64) {
+        self.time_enabled.fetch_add(enabled, Ordering::Relaxed);
+        self.time_running.fetch_add(running, Ordering::Relaxed);
+    }
+
+    /// Samples taken
+    pub fn sample_count(&self) -> u64 {
+        self.sample_count.load(Ordering::Relaxed)
+    }
+
+    /// Count a sample
+    pub fn record_sample(&self) {
+        self.sample_count.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Multiplexing ratio
     pub fn mux_ratio(&self) -> f64 {
         let enabled = self.time_enabled();
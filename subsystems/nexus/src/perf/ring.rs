@@ -0,0 +1,289 @@
+//! Perf Ring Buffer
+//!
+//! Sample ring shared with userspace through `mmap`: one control page
+//! ([`PerfMmapPage`]) followed by a power-of-two number of data pages.
+//! The kernel appends records and advances `data_head`; the reader
+//! consumes them and advances `data_tail`. Records that do not fit are
+//! dropped and reported by a [`RecordType::Lost`] record once space frees
+//! up, like the tracing ring buffers.
+
+use alloc::alloc::{alloc_zeroed, dealloc, Layout};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::{Sample, SampleType};
+
+/// Page size of the mapping
+pub const PAGE_SIZE: usize = 4096;
+
+/// Largest data area, in pages
+pub const MAX_DATA_PAGES: usize = 512;
+
+// ============================================================================
+// CONTROL PAGE
+// ============================================================================
+
+/// First page of the mapping
+#[repr(C)]
+#[derive(Debug)]
+pub struct PerfMmapPage {
+    /// Layout version
+    pub version: u32,
+    /// Oldest compatible version
+    pub compat_version: u32,
+    /// Event ID
+    pub id: u64,
+    /// Counter value at the last update
+    pub count: u64,
+    /// Time the event was enabled (ns)
+    pub time_enabled: u64,
+    /// Time the event was counting (ns)
+    pub time_running: u64,
+    /// Records lost so far
+    pub lost: u64,
+    /// Offset of the data area from the start of the mapping
+    pub data_offset: u64,
+    /// Size of the data area in bytes
+    pub data_size: u64,
+    /// Write position (bytes, monotonic), written by the kernel
+    pub data_head: AtomicU64,
+    /// Read position (bytes, monotonic), written by the reader
+    pub data_tail: AtomicU64,
+}
+
+impl PerfMmapPage {
+    /// Current layout version
+    pub const VERSION: u32 = 1;
+}
+
+// ============================================================================
+// RECORDS
+// ============================================================================
+
+/// Record type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum RecordType {
+    /// Records were dropped
+    Lost = 2,
+    /// Sample
+    Sample = 9,
+}
+
+/// Header preceding every record
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordHeader {
+    /// [`RecordType`]
+    pub record_type: u32,
+    /// Flags (unused)
+    pub misc: u16,
+    /// Total size including the header, multiple of 8
+    pub size: u16,
+}
+
+impl RecordHeader {
+    fn to_word(self) -> u64 {
+        self.record_type as u64 | ((self.misc as u64) << 32) | ((self.size as u64) << 48)
+    }
+
+    fn from_word(word: u64) -> Self {
+        Self {
+            record_type: word as u32,
+            misc: (word >> 32) as u16,
+            size: (word >> 48) as u16,
+        }
+    }
+}
+
+// ============================================================================
+// RING BUFFER
+// ============================================================================
+
+/// Mappable perf ring buffer
+pub struct PerfRingBuffer {
+    /// Control page followed by the data pages
+    mem: NonNull<u8>,
+    /// Number of data pages
+    data_pages: usize,
+    /// Records dropped since the last lost record
+    pending_lost: u64,
+}
+
+// SAFETY: the buffer is exclusively owned; the reader only touches
+// `data_tail` and the data it has been handed
+unsafe impl Send for PerfRingBuffer {}
+
+impl PerfRingBuffer {
+    /// Allocate a ring with `data_pages` data pages (power of two)
+    pub fn new(id: u64, data_pages: usize) -> Option<Self> {
+        if !data_pages.is_power_of_two() || data_pages > MAX_DATA_PAGES {
+            return None;
+        }
+        // SAFETY: non-zero size, power-of-two alignment
+        let mem = NonNull::new(unsafe { alloc_zeroed(Self::layout(data_pages)) })?;
+
+        let mut ring = Self {
+            mem,
+            data_pages,
+            pending_lost: 0,
+        };
+        let page = ring.page_mut();
+        page.version = PerfMmapPage::VERSION;
+        page.compat_version = PerfMmapPage::VERSION;
+        page.id = id;
+        page.data_offset = PAGE_SIZE as u64;
+        page.data_size = (data_pages * PAGE_SIZE) as u64;
+        Some(ring)
+    }
+
+    fn layout(data_pages: usize) -> Layout {
+        Layout::from_size_align((1 + data_pages) * PAGE_SIZE, PAGE_SIZE).unwrap()
+    }
+
+    /// Start of the mapping (page aligned)
+    pub fn as_ptr(&self) -> *const u8 {
+        self.mem.as_ptr()
+    }
+
+    /// Size of the mapping in bytes
+    pub fn mapping_size(&self) -> usize {
+        (1 + self.data_pages) * PAGE_SIZE
+    }
+
+    /// Control page
+    pub fn page(&self) -> &PerfMmapPage {
+        // SAFETY: the first page holds an initialized, aligned PerfMmapPage
+        unsafe { &*(self.mem.as_ptr() as *const PerfMmapPage) }
+    }
+
+    fn page_mut(&mut self) -> &mut PerfMmapPage {
+        // SAFETY: as in `page`, and `&mut self` gives exclusive access
+        unsafe { &mut *(self.mem.as_ptr() as *mut PerfMmapPage) }
+    }
+
+    fn data_size(&self) -> u64 {
+        (self.data_pages * PAGE_SIZE) as u64
+    }
+
+    /// Bytes available to the writer
+    pub fn free_space(&self) -> u64 {
+        let page = self.page();
+        let head = page.data_head.load(Ordering::Relaxed);
+        let tail = page.data_tail.load(Ordering::Acquire);
+        self.data_size() - head.wrapping_sub(tail)
+    }
+
+    /// Publish counter state to the control page
+    pub fn update_counts(&mut self, count: u64, time_enabled: u64, time_running: u64) {
+        let page = self.page_mut();
+        page.count = count;
+        page.time_enabled = time_enabled;
+        page.time_running = time_running;
+    }
+
+    /// Append a record of `words` 8-byte words after the header
+    ///
+    /// Returns `false` (and counts a lost record) if it does not fit.
+    fn write_record(&mut self, record_type: RecordType, words: &[u64]) -> bool {
+        let size = 8 * (1 + words.len()) as u64;
+        if size > u16::MAX as u64 || size > self.free_space() {
+            self.pending_lost += 1;
+            self.page_mut().lost += 1;
+            return false;
+        }
+
+        let header = RecordHeader {
+            record_type: record_type as u32,
+            misc: 0,
+            size: size as u16,
+        };
+        let mut head = self.page().data_head.load(Ordering::Relaxed);
+        self.write_word(head, header.to_word());
+        for &word in words {
+            head += 8;
+            self.write_word(head, word);
+        }
+        self.page().data_head.store(head + 8, Ordering::Release);
+        true
+    }
+
+    fn write_word(&mut self, pos: u64, word: u64) {
+        let offset = PAGE_SIZE + (pos % self.data_size()) as usize;
+        // SAFETY: offset is 8-byte aligned and inside the data area
+        unsafe { (self.mem.as_ptr().add(offset) as *mut u64).write_volatile(word) }
+    }
+
+    fn read_word(&self, pos: u64) -> u64 {
+        let offset = PAGE_SIZE + (pos % self.data_size()) as usize;
+        // SAFETY: as in `write_word`
+        unsafe { (self.mem.as_ptr().add(offset) as *const u64).read_volatile() }
+    }
+
+    /// Append a sample, laid out in [`SampleType`] bit order
+    pub fn write_sample(&mut self, sample_type: u64, id: u64, sample: &Sample) -> bool {
+        if self.pending_lost > 0 {
+            if !self.write_record(RecordType::Lost, &[id, self.pending_lost]) {
+                return false;
+            }
+            self.pending_lost = 0;
+        }
+
+        let mut words = alloc::vec::Vec::with_capacity(8 + sample.callchain.len());
+        if sample_type & SampleType::IP != 0 {
+            words.push(sample.ip.unwrap_or(0));
+        }
+        if sample_type & SampleType::TID != 0 {
+            let pid = sample.pid.unwrap_or(-1) as u32 as u64;
+            let tid = sample.tid.unwrap_or(-1) as u32 as u64;
+            words.push(pid | (tid << 32));
+        }
+        if sample_type & SampleType::TIME != 0 {
+            words.push(sample.time.unwrap_or(0));
+        }
+        if sample_type & SampleType::ADDR != 0 {
+            words.push(sample.addr.unwrap_or(0));
+        }
+        if sample_type & SampleType::ID != 0 {
+            words.push(id);
+        }
+        if sample_type & SampleType::CPU != 0 {
+            words.push(sample.cpu.map(|c| c.0 as u64).unwrap_or(0));
+        }
+        if sample_type & SampleType::PERIOD != 0 {
+            words.push(sample.period.unwrap_or(0));
+        }
+        if sample_type & SampleType::CALLCHAIN != 0 {
+            words.push(sample.callchain.len() as u64);
+            words.extend_from_slice(&sample.callchain);
+        }
+        if sample_type & SampleType::WEIGHT != 0 {
+            words.push(sample.weight.unwrap_or(0));
+        }
+        self.write_record(RecordType::Sample, &words)
+    }
+
+    /// Consume the next record as a reader would: header and payload words
+    pub fn read_record(&self) -> Option<(RecordHeader, alloc::vec::Vec<u64>)> {
+        let page = self.page();
+        let tail = page.data_tail.load(Ordering::Relaxed);
+        if tail == page.data_head.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let header = RecordHeader::from_word(self.read_word(tail));
+        let words = (1..header.size as u64 / 8)
+            .map(|i| self.read_word(tail + 8 * i))
+            .collect();
+        page.data_tail
+            .store(tail + header.size as u64, Ordering::Release);
+        Some((header, words))
+    }
+}
+
+impl Drop for PerfRingBuffer {
+    fn drop(&mut self) {
+        // SAFETY: allocated in `new` with the same layout
+        unsafe { dealloc(self.mem.as_ptr(), Self::layout(self.data_pages)) }
+    }
+}
@@ -104,6 +104,13 @@ pub fn set_battery(&mut self, level: u8, on_ac: bool) {
         self.on_ac = on_ac;
     }
 
+    /// Set the power state reported by the power supply drivers; `None`
+    /// when the system has no battery
+    pub fn set_power_status(&mut self, on_ac: bool, battery_level: Option<u8>) {
+        self.battery_level = battery_level;
+        self.on_ac = on_ac;
+    }
+
     /// Set power mode
     pub fn set_mode(&mut self, mode: PowerMode) {
         self.mode = mode;
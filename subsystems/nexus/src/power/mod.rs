@@ -108,4 +108,18 @@ fn test_energy_profiler() {
         let energy = profiler.get_task_energy(1).unwrap();
         assert!(energy.total() > 0.0);
     }
+
+    #[test]
+    fn test_power_status() {
+        let mut power = PowerIntelligence::new(vec![PState::new(2000, 900)], vec![CState::C0, CState::C1]);
+
+        power.set_power_status(false, Some(8));
+        assert_eq!(power.update(0.5, 0).power_mode, PowerMode::BatterySaver);
+
+        power.set_power_status(true, Some(8));
+        assert_eq!(power.update(0.5, 0).power_mode, PowerMode::Balanced);
+
+        power.set_power_status(false, None);
+        assert_eq!(power.update(0.5, 0).power_mode, PowerMode::Balanced);
+    }
 }
@@ -0,0 +1,487 @@
+//! Prometheus exposition.
+//!
+//! Subsystems register counters, gauges and histograms with a
+//! [`MetricsRegistry`] and keep the returned handle; updates go straight to
+//! the handle's atomics, so the hot path never touches the registry. The
+//! registry renders every metric in the Prometheus text format (0.0.4),
+//! served by the `/proc/metrics` file and by [`MetricsEndpoint`], a tiny
+//! HTTP responder the network stack feeds with connection bytes.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::metrics::{Counter, Gauge};
+use super::types::MetricType;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// `Content-Type` of the text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Conventional port of the metrics endpoint
+pub const DEFAULT_PORT: u16 = 9100;
+
+/// Path served by the metrics endpoint
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Largest request head the endpoint accepts
+const MAX_REQUEST: usize = 4096;
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Metric registration error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricError {
+    /// Name or label name is not `[a-zA-Z_:][a-zA-Z0-9_:]*`
+    InvalidName,
+    /// Name already registered as another metric type
+    TypeConflict,
+    /// Name and labels already registered
+    Duplicate,
+    /// Histogram boundaries empty or not increasing
+    InvalidBuckets,
+}
+
+impl fmt::Display for MetricError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName => write!(f, "invalid metric or label name"),
+            Self::TypeConflict => write!(f, "metric registered with another type"),
+            Self::Duplicate => write!(f, "metric already registered"),
+            Self::InvalidBuckets => write!(f, "invalid histogram buckets"),
+        }
+    }
+}
+
+// ============================================================================
+// HISTOGRAM
+// ============================================================================
+
+/// Atomic histogram metric
+///
+/// Unlike [`TelemetryHistogram`](super::TelemetryHistogram) it is updated
+/// through a shared reference, so any subsystem can observe into it.
+pub struct AtomicHistogram {
+    /// Upper bounds (inclusive), increasing
+    boundaries: Vec<f64>,
+    /// Counts per bucket, plus the overflow bucket
+    counts: Vec<AtomicU64>,
+    /// Sum of all values (stored as bits)
+    sum: AtomicU64,
+}
+
+impl AtomicHistogram {
+    /// Create with bucket upper bounds
+    pub fn new(boundaries: Vec<f64>) -> Self {
+        let counts = (0..=boundaries.len()).map(|_| AtomicU64::new(0)).collect();
+        Self {
+            boundaries,
+            counts,
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    /// Observe a value
+    pub fn observe(&self, value: f64) {
+        let bucket = self
+            .boundaries
+            .iter()
+            .position(|&b| value <= b)
+            .unwrap_or(self.boundaries.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+
+        let mut old = self.sum.load(Ordering::Relaxed);
+        loop {
+            let new = (f64::from_bits(old) + value).to_bits();
+            match self
+                .sum
+                .compare_exchange_weak(old, new, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => old = current,
+            }
+        }
+    }
+
+    /// Number of observations
+    pub fn count(&self) -> u64 {
+        self.counts.iter().map(|c| c.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Sum of observations
+    pub fn sum(&self) -> f64 {
+        f64::from_bits(self.sum.load(Ordering::Relaxed))
+    }
+
+    /// Cumulative counts per upper bound, ending with `+Inf`
+    pub fn cumulative(&self) -> Vec<(f64, u64)> {
+        let mut total = 0;
+        self.boundaries
+            .iter()
+            .copied()
+            .chain(core::iter::once(f64::INFINITY))
+            .zip(&self.counts)
+            .map(|(bound, count)| {
+                total += count.load(Ordering::Relaxed);
+                (bound, total)
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// REGISTRY
+// ============================================================================
+
+/// Label pairs of one series
+type Labels = Vec<(String, String)>;
+
+/// Handle of a registered series
+enum Series {
+    Counter(Arc<Counter>),
+    Gauge(Arc<Gauge>),
+    Histogram(Arc<AtomicHistogram>),
+}
+
+/// Metrics sharing a name, one series per label set
+struct Family {
+    help: String,
+    metric_type: MetricType,
+    series: Vec<(Labels, Series)>,
+}
+
+/// Registry of exported metrics
+#[derive(Default)]
+pub struct MetricsRegistry {
+    families: BTreeMap<String, Family>,
+}
+
+impl MetricsRegistry {
+    /// Create empty registry
+    pub const fn new() -> Self {
+        Self {
+            families: BTreeMap::new(),
+        }
+    }
+
+    /// Register a counter
+    pub fn register_counter(
+        &mut self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+    ) -> Result<Arc<Counter>, MetricError> {
+        let counter = Arc::new(Counter::new(name));
+        self.insert(name, help, labels, MetricType::Counter, Series::Counter(counter.clone()))?;
+        Ok(counter)
+    }
+
+    /// Register a gauge
+    pub fn register_gauge(
+        &mut self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+    ) -> Result<Arc<Gauge>, MetricError> {
+        let gauge = Arc::new(Gauge::new(name));
+        self.insert(name, help, labels, MetricType::Gauge, Series::Gauge(gauge.clone()))?;
+        Ok(gauge)
+    }
+
+    /// Register a histogram with bucket upper bounds `boundaries`
+    pub fn register_histogram(
+        &mut self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        boundaries: &[f64],
+    ) -> Result<Arc<AtomicHistogram>, MetricError> {
+        let increasing = boundaries.windows(2).all(|w| w[0] < w[1]);
+        if boundaries.is_empty() || !increasing || boundaries.iter().any(|b| !b.is_finite()) {
+            return Err(MetricError::InvalidBuckets);
+        }
+        if labels.iter().any(|&(label, _)| label == "le") {
+            return Err(MetricError::InvalidName);
+        }
+
+        let histogram = Arc::new(AtomicHistogram::new(boundaries.to_vec()));
+        self.insert(
+            name,
+            help,
+            labels,
+            MetricType::Histogram,
+            Series::Histogram(histogram.clone()),
+        )?;
+        Ok(histogram)
+    }
+
+    /// Remove a series; returns whether it was registered
+    pub fn unregister(&mut self, name: &str, labels: &[(&str, &str)]) -> bool {
+        let Some(family) = self.families.get_mut(name) else {
+            return false;
+        };
+        let before = family.series.len();
+        family.series.retain(|(l, _)| !same_labels(l, labels));
+        let removed = family.series.len() != before;
+        if family.series.is_empty() {
+            self.families.remove(name);
+        }
+        removed
+    }
+
+    /// Number of registered series
+    pub fn len(&self) -> usize {
+        self.families.values().map(|f| f.series.len()).sum()
+    }
+
+    /// Is the registry empty?
+    pub fn is_empty(&self) -> bool {
+        self.families.is_empty()
+    }
+
+    fn insert(
+        &mut self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        metric_type: MetricType,
+        series: Series,
+    ) -> Result<(), MetricError> {
+        if !valid_name(name) || !labels.iter().all(|&(label, _)| valid_name(label)) {
+            return Err(MetricError::InvalidName);
+        }
+
+        let family = self.families.entry(name.to_string()).or_insert_with(|| Family {
+            help: help.to_string(),
+            metric_type,
+            series: Vec::new(),
+        });
+        if family.metric_type != metric_type {
+            return Err(MetricError::TypeConflict);
+        }
+        if family.series.iter().any(|(l, _)| same_labels(l, labels)) {
+            return Err(MetricError::Duplicate);
+        }
+
+        let labels = labels
+            .iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        family.series.push((labels, series));
+        Ok(())
+    }
+
+    /// Render every metric in the Prometheus text format
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = self.write_to(&mut out);
+        out
+    }
+
+    fn write_to(&self, out: &mut String) -> fmt::Result {
+        for (name, family) in &self.families {
+            let type_name = match family.metric_type {
+                MetricType::Counter => "counter",
+                MetricType::Gauge => "gauge",
+                MetricType::Histogram => "histogram",
+                _ => "untyped",
+            };
+            if !family.help.is_empty() {
+                writeln!(out, "# HELP {} {}", name, Escaped(&family.help, false))?;
+            }
+            writeln!(out, "# TYPE {} {}", name, type_name)?;
+
+            for (labels, series) in &family.series {
+                match series {
+                    Series::Counter(counter) => {
+                        writeln!(out, "{}{} {}", name, LabelSet(labels, None), counter.get())?
+                    }
+                    Series::Gauge(gauge) => writeln!(
+                        out,
+                        "{}{} {}",
+                        name,
+                        LabelSet(labels, None),
+                        Value(gauge.get())
+                    )?,
+                    Series::Histogram(histogram) => {
+                        let buckets = histogram.cumulative();
+                        for &(bound, count) in &buckets {
+                            let le = Value(bound).to_string();
+                            let set = LabelSet(labels, Some(&le));
+                            writeln!(out, "{}_bucket{} {}", name, set, count)?;
+                        }
+                        let count = buckets.last().map_or(0, |&(_, count)| count);
+                        let set = LabelSet(labels, None);
+                        writeln!(out, "{}_sum{} {}", name, set, Value(histogram.sum()))?;
+                        writeln!(out, "{}_count{} {}", name, set, count)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn same_labels(labels: &Labels, other: &[(&str, &str)]) -> bool {
+    labels.len() == other.len()
+        && labels
+            .iter()
+            .zip(other)
+            .all(|((k, v), &(ok, ov))| k == ok && v == ov)
+}
+
+/// `[a-zA-Z_:][a-zA-Z0-9_:]*`
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+/// Sample value: `+Inf`, `-Inf` and `NaN` spelled as Prometheus expects
+struct Value(f64);
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            v if v.is_nan() => f.write_str("NaN"),
+            f64::INFINITY => f.write_str("+Inf"),
+            f64::NEG_INFINITY => f.write_str("-Inf"),
+            v => write!(f, "{}", v),
+        }
+    }
+}
+
+/// HELP text (`\\`, `\n`) or label value (also `"`) escaping
+struct Escaped<'a>(&'a str, bool);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '"' if self.1 => f.write_str("\\\"")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `{k="v",...}`, with an optional trailing `le` label; empty if no labels
+struct LabelSet<'a>(&'a Labels, Option<&'a str>);
+
+impl fmt::Display for LabelSet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() && self.1.is_none() {
+            return Ok(());
+        }
+        f.write_char('{')?;
+        let le = self.1.map(|le| ("le", le));
+        let pairs = self.0.iter().map(|(k, v)| (k.as_str(), v.as_str())).chain(le);
+        for (i, (key, value)) in pairs.enumerate() {
+            if i > 0 {
+                f.write_char(',')?;
+            }
+            write!(f, "{}=\"{}\"", key, Escaped(value, true))?;
+        }
+        f.write_char('}')
+    }
+}
+
+// ============================================================================
+// HTTP ENDPOINT
+// ============================================================================
+
+/// Minimal HTTP/1.x responder for `GET /metrics`
+///
+/// The network stack accumulates a connection's bytes with
+/// [`MetricsEndpoint::feed`] until it returns a response, sends it and
+/// closes the connection. Only the request line matters; headers and any
+/// body are ignored.
+#[derive(Debug, Default)]
+pub struct MetricsEndpoint {
+    buffer: Vec<u8>,
+}
+
+impl MetricsEndpoint {
+    /// Create for a new connection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add received bytes; returns the response once the request head is
+    /// complete
+    pub fn feed(&mut self, data: &[u8], registry: &MetricsRegistry) -> Option<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+        if self.buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+            Some(respond(&self.buffer, registry))
+        } else if self.buffer.len() > MAX_REQUEST {
+            Some(response(431, "Request Header Fields Too Large", ""))
+        } else {
+            None
+        }
+    }
+}
+
+/// Response to a complete HTTP request head
+pub fn respond(request: &[u8], registry: &MetricsRegistry) -> Vec<u8> {
+    let line = request
+        .split(|&b| b == b'\n')
+        .next()
+        .and_then(|line| core::str::from_utf8(line).ok())
+        .map(str::trim_end);
+    let mut parts = line.unwrap_or("").split(' ');
+
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version), None) if version.starts_with("HTTP/1.") => {
+            let path = target.split('?').next().unwrap_or(target);
+            match (method, path) {
+                ("GET" | "HEAD", METRICS_PATH) => {
+                    let mut reply = response(200, "OK", &registry.encode());
+                    if method == "HEAD" {
+                        let head = reply.windows(4).position(|w| w == b"\r\n\r\n");
+                        reply.truncate(head.map_or(reply.len(), |end| end + 4));
+                    }
+                    reply
+                }
+                ("GET" | "HEAD", _) => response(404, "Not Found", "not found\n"),
+                _ => response(405, "Method Not Allowed", ""),
+            }
+        }
+        _ => response(400, "Bad Request", ""),
+    }
+}
+
+fn response(status: u16, reason: &str, body: &str) -> Vec<u8> {
+    let mut head = String::new();
+    let _ = write!(
+        head,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        reason,
+        CONTENT_TYPE,
+        body.len()
+    );
+    if status == 405 {
+        head.push_str("Allow: GET, HEAD\r\n");
+    }
+    head.push_str("\r\n");
+
+    let mut reply = head.into_bytes();
+    reply.extend_from_slice(body.as_bytes());
+    reply
+}
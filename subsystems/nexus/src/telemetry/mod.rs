@@ -8,7 +8,7 @@
 //! - **Time Series Storage**: Efficient time series database
 //! - **Aggregation**: Statistical aggregation at multiple granularities
 //! - **Alerting**: Threshold-based alerting
-//! - **Export**: Export to external monitoring systems
+//! - **Export**: Prometheus text format for `/proc/metrics` and HTTP scrapes
 
 #![allow(dead_code)]
 
@@ -16,6 +16,7 @@
 
 // Submodules
 mod alert;
+mod exposition;
 mod histogram;
 mod metrics;
 mod registry;
@@ -24,6 +25,10 @@
 
 // Re-exports
 pub use alert::{Alert, AlertCondition, AlertRule, AlertSeverity, AlertState};
+pub use exposition::{
+    respond as respond_http, AtomicHistogram, MetricError, MetricsEndpoint, MetricsRegistry,
+    CONTENT_TYPE, DEFAULT_PORT, METRICS_PATH,
+};
 pub use histogram::TelemetryHistogram;
 pub use metrics::{Counter, Gauge};
 pub use registry::{TelemetryRegistry, TelemetryStats};
@@ -111,4 +116,83 @@ fn test_downsample() {
         let downsampled = ts.downsample(100);
         assert!(downsampled.len() < ts.len());
     }
+
+    #[test]
+    fn test_prometheus_encode() {
+        let mut registry = MetricsRegistry::new();
+        let reads = registry
+            .register_counter("helix_disk_reads_total", "Disk reads", &[("dev", "nvme0")])
+            .unwrap();
+        let load = registry.register_gauge("helix_load", "Load\naverage", &[]).unwrap();
+        let latency = registry
+            .register_histogram("helix_irq_seconds", "", &[("cpu", "0")], &[0.1, 1.0])
+            .unwrap();
+
+        reads.add(3);
+        load.set(0.5);
+        latency.observe(0.05);
+        latency.observe(0.5);
+        latency.observe(7.0);
+
+        assert_eq!(
+            registry.encode(),
+            "# HELP helix_disk_reads_total Disk reads\n\
+             # TYPE helix_disk_reads_total counter\n\
+             helix_disk_reads_total{dev=\"nvme0\"} 3\n\
+             # TYPE helix_irq_seconds histogram\n\
+             helix_irq_seconds_bucket{cpu=\"0\",le=\"0.1\"} 1\n\
+             helix_irq_seconds_bucket{cpu=\"0\",le=\"1\"} 2\n\
+             helix_irq_seconds_bucket{cpu=\"0\",le=\"+Inf\"} 3\n\
+             helix_irq_seconds_sum{cpu=\"0\"} 7.55\n\
+             helix_irq_seconds_count{cpu=\"0\"} 3\n\
+             # HELP helix_load Load\\naverage\n\
+             # TYPE helix_load gauge\n\
+             helix_load 0.5\n"
+        );
+    }
+
+    #[test]
+    fn test_metrics_registration_errors() {
+        let mut registry = MetricsRegistry::new();
+        registry.register_counter("requests", "", &[("code", "200")]).unwrap();
+        registry.register_counter("requests", "", &[("code", "500")]).unwrap();
+
+        let dup = registry.register_counter("requests", "", &[("code", "200")]);
+        assert_eq!(dup.err(), Some(MetricError::Duplicate));
+        let conflict = registry.register_gauge("requests", "", &[]);
+        assert_eq!(conflict.err(), Some(MetricError::TypeConflict));
+        let bad = registry.register_gauge("9lives", "", &[]);
+        assert_eq!(bad.err(), Some(MetricError::InvalidName));
+        let buckets = registry.register_histogram("h", "", &[], &[1.0, 1.0]);
+        assert_eq!(buckets.err(), Some(MetricError::InvalidBuckets));
+
+        assert_eq!(registry.len(), 2);
+        assert!(registry.unregister("requests", &[("code", "200")]));
+        assert!(!registry.unregister("requests", &[("code", "200")]));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_metrics_endpoint() {
+        let mut registry = MetricsRegistry::new();
+        registry.register_counter("up", "", &[]).unwrap().inc();
+
+        let mut endpoint = MetricsEndpoint::new();
+        assert_eq!(endpoint.feed(b"GET /metrics HTTP/1.1\r\nHost: x", &registry), None);
+        let reply = endpoint.feed(b"\r\n\r\n", &registry).unwrap();
+        let reply = core::str::from_utf8(&reply).unwrap();
+        assert!(reply.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(reply.contains(CONTENT_TYPE));
+        assert!(reply.ends_with("\r\n\r\n# TYPE up counter\nup 1\n"));
+
+        let status = |request: &[u8]| {
+            let reply = respond_http(request, &registry);
+            core::str::from_utf8(&reply[9..12]).unwrap().parse::<u16>().unwrap()
+        };
+        assert_eq!(status(b"GET / HTTP/1.0\r\n\r\n"), 404);
+        assert_eq!(status(b"POST /metrics HTTP/1.1\r\n\r\n"), 405);
+        assert_eq!(status(b"garbage\r\n\r\n"), 400);
+        let head = respond_http(b"HEAD /metrics HTTP/1.1\r\n\r\n", &registry);
+        assert!(head.ends_with(b"\r\n\r\n"));
+    }
 }
@@ -0,0 +1,366 @@
+//! # Archive Encoding
+//!
+//! Just enough of the standard formats to hand files to a host:
+//!
+//! - [`Tar`]: ustar archive of regular files
+//! - [`lz4_frame`]: LZ4 frame (independent 64 KiB blocks, no checksums),
+//!   readable by `lz4 -d`
+//! - [`base64`]: for binary data sent over a text-only console
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// =============================================================================
+// Tar
+// =============================================================================
+
+/// Tar block size
+const BLOCK: usize = 512;
+
+/// ustar archive writer
+#[derive(Debug, Default)]
+pub struct Tar {
+    data: Vec<u8>,
+}
+
+impl Tar {
+    /// Create an empty archive
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a regular file (mode 0644); `path` is truncated to 99 bytes
+    pub fn add_file(&mut self, path: &str, contents: &[u8], mtime: u64) {
+        let mut header = [0u8; BLOCK];
+        let name = &path.as_bytes()[..path.len().min(99)];
+        header[..name.len()].copy_from_slice(name);
+        octal(&mut header[100..108], 0o644);
+        octal(&mut header[108..116], 0);
+        octal(&mut header[116..124], 0);
+        octal(&mut header[124..136], contents.len() as u64);
+        octal(&mut header[136..148], mtime);
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        // Checksum is computed with its own field as spaces
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| b as u32).sum();
+        octal(&mut header[148..155], sum as u64);
+
+        self.data.extend_from_slice(&header);
+        self.data.extend_from_slice(contents);
+        let padding = (BLOCK - contents.len() % BLOCK) % BLOCK;
+        self.data.resize(self.data.len() + padding, 0);
+    }
+
+    /// Terminate the archive
+    pub fn finish(mut self) -> Vec<u8> {
+        self.data.resize(self.data.len() + 2 * BLOCK, 0);
+        self.data
+    }
+}
+
+/// Zero-padded octal, NUL-terminated, filling `field`
+fn octal(field: &mut [u8], mut value: u64) {
+    let digits = field.len() - 1;
+    for byte in field[..digits].iter_mut().rev() {
+        *byte = b'0' + (value & 7) as u8;
+        value >>= 3;
+    }
+    field[digits] = 0;
+}
+
+// =============================================================================
+// LZ4
+// =============================================================================
+
+/// LZ4 frame magic
+const LZ4_MAGIC: u32 = 0x184D_2204;
+
+/// Uncompressed size of a frame block
+const LZ4_BLOCK: usize = 64 * 1024;
+
+/// Shortest match
+const MIN_MATCH: usize = 4;
+
+/// No match may start in the last 12 bytes of a block
+const MF_LIMIT: usize = 12;
+
+/// The last 5 bytes of a block are always literals
+const LAST_LITERALS: usize = 5;
+
+/// Match finder hash table size (log2)
+const HASH_LOG: u32 = 12;
+
+/// Compress `data` into an LZ4 frame
+pub fn lz4_frame(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 2 + 16);
+    out.extend_from_slice(&LZ4_MAGIC.to_le_bytes());
+
+    // Version 01, independent blocks; 64 KiB maximum block size
+    let descriptor = [0x60, 0x40];
+    out.extend_from_slice(&descriptor);
+    out.push((xxh32(&descriptor, 0) >> 8) as u8);
+
+    let mut block = Vec::new();
+    for chunk in data.chunks(LZ4_BLOCK) {
+        block.clear();
+        lz4_block(chunk, &mut block);
+        if block.len() < chunk.len() {
+            out.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            out.extend_from_slice(&block);
+        } else {
+            // High bit marks a stored block
+            out.extend_from_slice(&(chunk.len() as u32 | 1 << 31).to_le_bytes());
+            out.extend_from_slice(chunk);
+        }
+    }
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out
+}
+
+/// Compress one block (greedy, single-entry hash chain)
+fn lz4_block(src: &[u8], out: &mut Vec<u8>) {
+    let mut table = [0u32; 1 << HASH_LOG];
+    let hash = |pos: usize| {
+        let word = u32::from_le_bytes([src[pos], src[pos + 1], src[pos + 2], src[pos + 3]]);
+        (word.wrapping_mul(2_654_435_761) >> (32 - HASH_LOG)) as usize
+    };
+
+    let mut anchor = 0;
+    let mut pos = 0;
+    let match_limit = src.len().saturating_sub(MF_LIMIT);
+    while pos < match_limit {
+        let h = hash(pos);
+        let candidate = table[h] as usize;
+        table[h] = pos as u32;
+
+        let offset = pos.wrapping_sub(candidate);
+        if candidate >= pos || offset > 0xFFFF || src[candidate..candidate + 4] != src[pos..pos + 4] {
+            pos += 1;
+            continue;
+        }
+
+        let end = src.len() - LAST_LITERALS;
+        let mut len = MIN_MATCH;
+        while pos + len < end && src[candidate + len] == src[pos + len] {
+            len += 1;
+        }
+        lz4_sequence(out, &src[anchor..pos], Some((offset as u16, len)));
+        pos += len;
+        anchor = pos;
+    }
+    lz4_sequence(out, &src[anchor..], None);
+}
+
+/// Emit literals followed by an optional match
+fn lz4_sequence(out: &mut Vec<u8>, literals: &[u8], matched: Option<(u16, usize)>) {
+    let match_len = matched.map_or(0, |(_, len)| len - MIN_MATCH);
+    let token = (literals.len().min(15) << 4 | match_len.min(15)) as u8;
+    out.push(token);
+    lz4_length(out, literals.len());
+    out.extend_from_slice(literals);
+
+    if let Some((offset, _)) = matched {
+        out.extend_from_slice(&offset.to_le_bytes());
+        lz4_length(out, match_len);
+    }
+}
+
+/// Extra length bytes for a token nibble that saturated at 15
+fn lz4_length(out: &mut Vec<u8>, len: usize) {
+    if len < 15 {
+        return;
+    }
+    let mut rest = len - 15;
+    while rest >= 255 {
+        out.push(255);
+        rest -= 255;
+    }
+    out.push(rest as u8);
+}
+
+/// xxHash32
+fn xxh32(data: &[u8], seed: u32) -> u32 {
+    const P1: u32 = 2_654_435_761;
+    const P2: u32 = 2_246_822_519;
+    const P3: u32 = 3_266_489_917;
+    const P4: u32 = 668_265_263;
+    const P5: u32 = 374_761_393;
+
+    let word = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    let round = |acc: u32, lane: u32| acc.wrapping_add(lane.wrapping_mul(P2)).rotate_left(13).wrapping_mul(P1);
+
+    let mut stripes = data.chunks_exact(16);
+    let mut h = if data.len() >= 16 {
+        let mut v = [
+            seed.wrapping_add(P1).wrapping_add(P2),
+            seed.wrapping_add(P2),
+            seed,
+            seed.wrapping_sub(P1),
+        ];
+        for stripe in &mut stripes {
+            for (lane, acc) in v.iter_mut().enumerate() {
+                *acc = round(*acc, word(&stripe[lane * 4..]));
+            }
+        }
+        v[0].rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18))
+    } else {
+        seed.wrapping_add(P5)
+    };
+    h = h.wrapping_add(data.len() as u32);
+
+    let mut tail = stripes.remainder().chunks_exact(4);
+    for w in &mut tail {
+        h = h.wrapping_add(word(w).wrapping_mul(P3)).rotate_left(17).wrapping_mul(P4);
+    }
+    for &b in tail.remainder() {
+        h = h.wrapping_add((b as u32).wrapping_mul(P5)).rotate_left(11).wrapping_mul(P1);
+    }
+
+    h ^= h >> 15;
+    h = h.wrapping_mul(P2);
+    h ^= h >> 13;
+    h = h.wrapping_mul(P3);
+    h ^ (h >> 16)
+}
+
+// =============================================================================
+// Base64
+// =============================================================================
+
+/// Standard base64 with padding, wrapped at 76 columns
+pub fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len() * 4 / 3 + data.len() / 57 + 4);
+    for (i, chunk) in data.chunks(3).enumerate() {
+        if i > 0 && i % 19 == 0 {
+            out.push('\n');
+        }
+        let bits = (chunk[0] as u32) << 16
+            | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+            | chunk.get(2).copied().unwrap_or(0) as u32;
+        for n in 0..4 {
+            if n <= chunk.len() {
+                out.push(ALPHABET[(bits >> (18 - 6 * n) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tar_header() {
+        let mut tar = Tar::new();
+        tar.add_file("report/version.txt", b"0.1.0\n", 0);
+        let data = tar.finish();
+
+        assert_eq!(data.len(), 4 * BLOCK);
+        assert_eq!(&data[..18], b"report/version.txt");
+        assert_eq!(&data[124..136], b"00000000006\0");
+        assert_eq!(&data[257..263], b"ustar\0");
+        let stored = core::str::from_utf8(&data[148..154]).unwrap();
+        let sum: u32 = data[..BLOCK]
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| if (148..156).contains(&i) { b' ' as u32 } else { b as u32 })
+            .sum();
+        assert_eq!(u32::from_str_radix(stored, 8), Ok(sum));
+        assert_eq!(&data[BLOCK..BLOCK + 6], b"0.1.0\n");
+    }
+
+    #[test]
+    fn test_xxh32() {
+        assert_eq!(xxh32(b"", 0), 0x02CC_5D05);
+        assert_eq!(xxh32(b"a", 0), 0x550D_7456);
+        assert_eq!(xxh32(b"Nobody inspects the spammish repetition", 0), 0xE229_3B2F);
+    }
+
+    /// Reference LZ4 block decoder
+    fn unlz4(mut src: &[u8], out: &mut Vec<u8>) {
+        fn length(src: &mut &[u8], nibble: usize) -> usize {
+            let mut len = nibble;
+            if nibble == 15 {
+                loop {
+                    let b = src[0];
+                    *src = &src[1..];
+                    len += b as usize;
+                    if b != 255 {
+                        break;
+                    }
+                }
+            }
+            len
+        }
+        while !src.is_empty() {
+            let token = src[0] as usize;
+            src = &src[1..];
+            let literals = length(&mut src, token >> 4);
+            out.extend_from_slice(&src[..literals]);
+            src = &src[literals..];
+            if src.is_empty() {
+                break;
+            }
+            let offset = u16::from_le_bytes([src[0], src[1]]) as usize;
+            src = &src[2..];
+            let len = length(&mut src, token & 15) + MIN_MATCH;
+            for _ in 0..len {
+                out.push(out[out.len() - offset]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lz4_roundtrip() {
+        let mut data = Vec::new();
+        for i in 0..20_000u32 {
+            data.extend_from_slice(b"module=net state=running ");
+            data.extend_from_slice(&(i % 97).to_le_bytes());
+        }
+
+        let frame = lz4_frame(&data);
+        assert!(frame.len() < data.len() / 4);
+        assert_eq!(&frame[..4], &LZ4_MAGIC.to_le_bytes());
+        assert_eq!(frame[6], (xxh32(&frame[4..6], 0) >> 8) as u8);
+
+        let mut decoded = Vec::new();
+        let mut rest = &frame[7..];
+        loop {
+            let size = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            rest = &rest[4..];
+            if size == 0 {
+                break;
+            }
+            let len = (size & !(1 << 31)) as usize;
+            if size & 1 << 31 != 0 {
+                decoded.extend_from_slice(&rest[..len]);
+            } else {
+                unlz4(&rest[..len], &mut decoded);
+            }
+            rest = &rest[len..];
+        }
+        assert!(rest.is_empty());
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        let wrapped = base64(&[0u8; 60]);
+        assert_eq!(wrapped.lines().next().map(str::len), Some(76));
+    }
+}
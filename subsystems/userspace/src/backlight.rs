@@ -0,0 +1,223 @@
+//! # Backlight Control
+//!
+//! `/sys/class/backlight/<device>/`: one directory per panel backlight
+//! registered with the HAL, laid out as on Linux so existing tools work:
+//!
+//! ```text
+//! brightness          requested level (read-write)
+//! actual_brightness   level applied by the hardware
+//! max_brightness      highest level
+//! type                firmware, platform or raw
+//! ```
+//!
+//! The attributes are snapshots taken at `open`, served through
+//! [`procfs`](super::procfs); a write to `brightness` applies a new level.
+//! [`adjust`] backs the shell's `brightness` command.
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+use helix_hal::backlight::{self, level_to_percent, percent_to_level, Backlight};
+use helix_hal::HalError;
+
+use super::syscalls::SyscallError;
+
+/// Directory holding one directory per backlight
+pub const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// A backlight attribute file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    /// `brightness`
+    Brightness,
+    /// `actual_brightness`
+    ActualBrightness,
+    /// `max_brightness`
+    MaxBrightness,
+    /// `type`
+    Type,
+}
+
+impl Attribute {
+    /// Attribute named `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "brightness" => Some(Self::Brightness),
+            "actual_brightness" => Some(Self::ActualBrightness),
+            "max_brightness" => Some(Self::MaxBrightness),
+            "type" => Some(Self::Type),
+            _ => None,
+        }
+    }
+}
+
+/// Device name and attribute of a path under [`BACKLIGHT_DIR`]
+pub fn parse_path(path: &str) -> Option<(&str, Attribute)> {
+    let rest = path.strip_prefix(BACKLIGHT_DIR)?.strip_prefix('/')?;
+    let (device, attribute) = rest.split_once('/')?;
+    Some((device, Attribute::from_name(attribute)?))
+}
+
+fn hal_error(err: HalError) -> SyscallError {
+    match err {
+        HalError::InvalidParameter => SyscallError::EINVAL,
+        HalError::ResourceBusy => SyscallError::EBUSY,
+        _ => SyscallError::EIO,
+    }
+}
+
+/// Contents of an attribute file
+pub fn render(device: &dyn Backlight, attribute: Attribute) -> Result<String, SyscallError> {
+    Ok(match attribute {
+        // Levels set by other means show up here too, so both read the hardware
+        Attribute::Brightness | Attribute::ActualBrightness => {
+            format!("{}\n", device.brightness().map_err(hal_error)?)
+        }
+        Attribute::MaxBrightness => format!("{}\n", device.max_brightness()),
+        Attribute::Type => format!("{}\n", device.backlight_type().as_str()),
+    })
+}
+
+/// Apply a brightness request to `device`; returns the new level
+///
+/// `spec` is a level (`7`), a percentage (`40%`) or a relative percentage
+/// step (`+10%`, `-10%`), clamped to the device range.
+pub fn adjust(device: &dyn Backlight, spec: &str) -> Result<u32, SyscallError> {
+    let max = device.max_brightness();
+    let level = match spec.strip_suffix('%') {
+        Some(percent) => {
+            let (sign, digits) = match percent.as_bytes().first() {
+                Some(b'+') => (1, &percent[1..]),
+                Some(b'-') => (-1, &percent[1..]),
+                _ => (0, percent),
+            };
+            let value: u32 = digits.parse().map_err(|_| SyscallError::EINVAL)?;
+            let current = level_to_percent(device.brightness().map_err(hal_error)?, max);
+            let target = match sign {
+                1 => current.saturating_add(value),
+                -1 => current.saturating_sub(value),
+                _ => value,
+            };
+            percent_to_level(target, max)
+        }
+        None => spec.parse::<u32>().map_err(|_| SyscallError::EINVAL)?.min(max),
+    };
+    device.set_brightness(level).map_err(hal_error)?;
+    Ok(level)
+}
+
+/// One line per backlight, `*` marking the one the shell adjusts
+pub fn render_list() -> String {
+    let preferred = backlight::preferred().map(|b| b.name());
+    let mut out = String::new();
+    for device in backlight::backlights() {
+        let max = device.max_brightness();
+        let marker = if Some(device.name()) == preferred { '*' } else { ' ' };
+        let _ = match device.brightness() {
+            Ok(level) => writeln!(
+                out,
+                "{} {:<16} {:<8} {}/{} ({}%)",
+                marker,
+                device.name(),
+                device.backlight_type().as_str(),
+                level,
+                max,
+                level_to_percent(level, max)
+            ),
+            Err(_) => writeln!(
+                out,
+                "{} {:<16} {:<8} ?/{}",
+                marker,
+                device.name(),
+                device.backlight_type().as_str(),
+                max
+            ),
+        };
+    }
+    out
+}
+
+/// Contents of the attribute file at `path`
+pub fn render_file(path: &str) -> Result<String, SyscallError> {
+    let (device, attribute) = parse_path(path).ok_or(SyscallError::ENOENT)?;
+    render(backlight::find(device).ok_or(SyscallError::ENOENT)?, attribute)
+}
+
+/// Set the level written to the `brightness` file at `path`
+pub fn write_file(path: &str, data: &[u8]) -> Result<usize, SyscallError> {
+    let (device, attribute) = parse_path(path).ok_or(SyscallError::ENOENT)?;
+    if attribute != Attribute::Brightness {
+        return Err(SyscallError::EACCES);
+    }
+    let device = backlight::find(device).ok_or(SyscallError::ENODEV)?;
+    let text = core::str::from_utf8(data).map_err(|_| SyscallError::EINVAL)?;
+    let level: u32 = text.trim().parse().map_err(|_| SyscallError::EINVAL)?;
+    if level > device.max_brightness() {
+        return Err(SyscallError::EINVAL);
+    }
+    device.set_brightness(level).map_err(hal_error)?;
+    Ok(data.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs;
+    use alloc::boxed::Box;
+    use core::sync::atomic::{AtomicU8, Ordering};
+    use helix_hal::backlight::{EcBacklight, EmbeddedController};
+    use helix_hal::HalResult;
+
+    struct TestEc(AtomicU8);
+
+    impl EmbeddedController for TestEc {
+        fn read(&self, _register: u8) -> HalResult<u8> {
+            Ok(self.0.load(Ordering::Relaxed))
+        }
+
+        fn write(&self, _register: u8, value: u8) -> HalResult<()> {
+            self.0.store(value, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    static EC: TestEc = TestEc(AtomicU8::new(10));
+
+    #[test]
+    fn test_sysfs_backlight() {
+        assert_eq!(
+            parse_path("/sys/class/backlight/vendor_ec/max_brightness"),
+            Some(("vendor_ec", Attribute::MaxBrightness))
+        );
+        assert_eq!(parse_path("/sys/class/backlight/vendor_ec"), None);
+        assert_eq!(parse_path("/sys/class/backlight/vendor_ec/power"), None);
+
+        let device = Box::leak(Box::new(EcBacklight::new("vendor_ec", &EC, 0x44, 20)));
+        backlight::register(device).unwrap();
+        assert!(render_list().contains("* vendor_ec        platform 10/20 (50%)\n"));
+        assert_eq!(render_file("/sys/class/backlight/missing/type"), Err(SyscallError::ENOENT));
+
+        let path = "/sys/class/backlight/vendor_ec/brightness";
+        let slot = procfs::proc_fd(procfs::open(procfs::find(path).unwrap(), path).unwrap()).unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(procfs::read(slot, &mut buf), Ok(3));
+        assert_eq!(&buf[..3], b"10\n");
+        assert_eq!(procfs::write(slot, b"15\n"), Ok(3));
+        assert_eq!(EC.0.load(Ordering::Relaxed), 15);
+        assert_eq!(procfs::write(slot, b"21"), Err(SyscallError::EINVAL));
+        assert_eq!(procfs::close(slot), Ok(()));
+
+        let path = "/sys/class/backlight/vendor_ec/type";
+        let slot = procfs::proc_fd(procfs::open(procfs::find(path).unwrap(), path).unwrap()).unwrap();
+        assert_eq!(procfs::write(slot, b"1"), Err(SyscallError::EACCES));
+        assert_eq!(procfs::read(slot, &mut buf), Ok(9));
+        assert_eq!(&buf[..9], b"platform\n");
+        assert_eq!(procfs::close(slot), Ok(()));
+
+        assert_eq!(adjust(device, "-25%"), Ok(10));
+        assert_eq!(adjust(device, "100%"), Ok(20));
+        assert_eq!(adjust(device, "7"), Ok(7));
+        assert_eq!(adjust(device, "99"), Ok(20));
+        assert_eq!(adjust(device, "bright"), Err(SyscallError::EINVAL));
+    }
+}
@@ -0,0 +1,62 @@
+//! # Boot History
+//!
+//! `/proc/boot_history`: per-stage boot times of recent boots with
+//! regressed stages flagged, as recorded by the bootloader.
+//!
+//! The bootloader hands the rendered report over as a `BootHistory` boot
+//! module; the kernel passes its contents to [`set_boot_history`]. Served
+//! through [`procfs`](super::procfs).
+
+use alloc::string::String;
+use spin::Mutex;
+
+/// Path of the boot history file
+pub const BOOT_HISTORY_PATH: &str = "/proc/boot_history";
+
+/// Report from the bootloader
+static HISTORY: Mutex<String> = Mutex::new(String::new());
+
+/// Install the report from the bootloader's boot history module
+pub fn set_boot_history(report: &str) {
+    let mut history = HISTORY.lock();
+    history.clear();
+    history.push_str(report);
+}
+
+/// The boot history report
+pub fn render() -> String {
+    let history = HISTORY.lock();
+    if history.is_empty() {
+        return String::from("no boot history recorded\n");
+    }
+    history.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_proc_boot_history() {
+        let file = procfs::find(BOOT_HISTORY_PATH).unwrap();
+        set_boot_history("boot     total_ms  regressed\n#2            900  total\n");
+        let slot = procfs::proc_fd(procfs::open(file, BOOT_HISTORY_PATH).unwrap()).unwrap();
+        set_boot_history(""); // After the snapshot
+
+        let mut text = Vec::new();
+        let mut buf = [0u8; 16];
+        loop {
+            let len = procfs::read(slot, &mut buf).unwrap();
+            if len == 0 {
+                break;
+            }
+            text.extend_from_slice(&buf[..len]);
+        }
+        assert!(text.ends_with(b"#2            900  total\n"));
+        assert_eq!(render(), "no boot history recorded\n");
+
+        assert_eq!(procfs::close(slot), Ok(()));
+    }
+}
@@ -0,0 +1,99 @@
+//! # Cache Topology
+//!
+//! `/proc/caches`: the cache hierarchy enumerated by the HAL, one line per
+//! cache, followed by the last-level cache allocation capabilities when
+//! the CPU supports partitioning.
+//!
+//! Served through [`procfs`](super::procfs).
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+use helix_hal::cache::{caches, CacheTopology, CacheType};
+
+/// Path of the cache topology file
+pub const CACHES_PATH: &str = "/proc/caches";
+
+/// Human-readable size
+fn format_size(bytes: u32) -> String {
+    if bytes >= 1 << 20 && bytes % (1 << 20) == 0 {
+        format!("{}M", bytes >> 20)
+    } else {
+        format!("{}K", bytes >> 10)
+    }
+}
+
+/// Render a cache topology
+fn render_topology(topology: &CacheTopology) -> String {
+    let mut out = format!(
+        "{:<5} {:<11} {:>5} {:>5} {:>5} {:>6} {:>7}\n",
+        "level", "type", "size", "line", "ways", "sets", "shared"
+    );
+    topology.for_each(|cache| {
+        let kind = match cache.cache_type {
+            CacheType::Data => "data",
+            CacheType::Instruction => "instruction",
+            CacheType::Unified => "unified",
+        };
+        let _ = writeln!(
+            out,
+            "L{:<4} {:<11} {:>5} {:>5} {:>5} {:>6} {:>7}",
+            cache.level,
+            kind,
+            format_size(cache.size),
+            cache.line_size,
+            cache.ways,
+            cache.sets,
+            cache.shared_by
+        );
+    });
+    match topology.cat() {
+        Some(cat) => {
+            let _ = writeln!(
+                out,
+                "llc_allocation: ways={} classes={} shareable={:#x}",
+                cat.cbm_len,
+                cat.max_cos as u32 + 1,
+                cat.shareable
+            );
+        }
+        None => out.push_str("llc_allocation: unsupported\n"),
+    }
+    out
+}
+
+/// The cache topology report
+pub fn render() -> String {
+    render_topology(caches())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs;
+    use helix_hal::cache::{decode_cpuid_cache, CatCapability};
+
+    #[test]
+    fn test_render_topology() {
+        let topology = CacheTopology::new();
+        topology.add(decode_cpuid_cache(0x0000_4121, 0x02C0_003F, 0x3F).unwrap());
+        topology.add(decode_cpuid_cache(0x0003_C163, 0x03C0_003F, 0x7FFF).unwrap());
+        let text = render_topology(&topology);
+        assert!(text.contains("L1    data          48K    64    12     64       2\n"));
+        assert!(text.contains("L3    unified       32M    64    16  32768      16\n"));
+        assert!(text.ends_with("llc_allocation: unsupported\n"));
+
+        topology.set_cat(CatCapability::from_cpuid(10, 0x600, 15));
+        assert!(render_topology(&topology).ends_with("llc_allocation: ways=11 classes=16 shareable=0x600\n"));
+    }
+
+    #[test]
+    fn test_proc_caches() {
+        let file = procfs::find(CACHES_PATH).unwrap();
+        let slot = procfs::proc_fd(procfs::open(file, CACHES_PATH).unwrap()).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(procfs::read(slot, &mut buf), Ok(8));
+        assert_eq!(&buf, b"level ty");
+        assert_eq!(procfs::close(slot), Ok(()));
+    }
+}
@@ -0,0 +1,204 @@
+//! # Clocks
+//!
+//! `clock_gettime` / `clock_getres` over the scheduler's nanosecond clock
+//! and CPU time accounting:
+//!
+//! ```text
+//! clock_gettime(clock_id, tp)
+//! clock_getres(clock_id, res)
+//! ```
+//!
+//! [`clock_id::PROCESS_CPUTIME_ID`] and [`clock_id::THREAD_CPUTIME_ID`]
+//! report the user plus system time of the calling process or thread,
+//! including the slice currently running. The realtime clock is the
+//! monotonic clock plus the wall time at boot, set with
+//! [`set_boot_time`].
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use helix_execution::scheduler::framework;
+use helix_execution::ThreadId;
+use helix_hal::uaccess::{self, UserCopy};
+
+use super::syscalls::{SyscallArgs, SyscallError, SyscallResult};
+
+/// Clock IDs (Linux numbering)
+pub mod clock_id {
+    /// Wall clock
+    pub const REALTIME: u32 = 0;
+    /// Time since boot, not adjusted
+    pub const MONOTONIC: u32 = 1;
+    /// CPU time of the calling process
+    pub const PROCESS_CPUTIME_ID: u32 = 2;
+    /// CPU time of the calling thread
+    pub const THREAD_CPUTIME_ID: u32 = 3;
+    /// Monotonic clock, never slewed
+    pub const MONOTONIC_RAW: u32 = 4;
+    /// Monotonic clock including suspend
+    pub const BOOTTIME: u32 = 7;
+}
+
+/// Nanoseconds per second
+const NSEC_PER_SEC: u64 = 1_000_000_000;
+
+/// Wall time at clock zero (ns since the epoch)
+static BOOT_TIME_NS: AtomicU64 = AtomicU64::new(0);
+
+/// `struct timespec`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timespec {
+    /// Seconds
+    pub tv_sec: i64,
+    /// Nanoseconds
+    pub tv_nsec: i64,
+}
+
+// SAFETY: `repr(C)` integers without padding
+unsafe impl UserCopy for Timespec {}
+
+impl Timespec {
+    /// Split nanoseconds into seconds and nanoseconds
+    pub fn from_nanos(ns: u64) -> Self {
+        Self {
+            tv_sec: (ns / NSEC_PER_SEC) as i64,
+            tv_nsec: (ns % NSEC_PER_SEC) as i64,
+        }
+    }
+}
+
+/// Set the wall time at clock zero, from the RTC or the network
+pub fn set_boot_time(epoch_ns: u64) {
+    BOOT_TIME_NS.store(epoch_ns, Ordering::Relaxed);
+}
+
+/// Wall time at clock zero, in seconds since the epoch
+pub fn boot_time_secs() -> u64 {
+    BOOT_TIME_NS.load(Ordering::Relaxed) / NSEC_PER_SEC
+}
+
+/// Current value of a clock for the calling thread `tid`
+pub fn clock_ns(id: u32, tid: ThreadId) -> Result<u64, SyscallError> {
+    let cputime = framework().cputime();
+    match id {
+        clock_id::REALTIME | clock_id::MONOTONIC | clock_id::MONOTONIC_RAW | clock_id::BOOTTIME => {
+            let now = cputime.now().ok_or(SyscallError::ENODEV)?;
+            Ok(if id == clock_id::REALTIME {
+                now + BOOT_TIME_NS.load(Ordering::Relaxed)
+            } else {
+                now
+            })
+        }
+        clock_id::THREAD_CPUTIME_ID => {
+            cputime.thread_times(tid).map(|t| t.total()).ok_or(SyscallError::EINVAL)
+        }
+        clock_id::PROCESS_CPUTIME_ID => {
+            let process = cputime.process_of(tid).ok_or(SyscallError::EINVAL)?;
+            Ok(cputime.process_times(process).total())
+        }
+        _ => Err(SyscallError::EINVAL),
+    }
+}
+
+/// Resolution of a clock (all clocks count nanoseconds)
+pub fn clock_res(id: u32) -> Result<Timespec, SyscallError> {
+    match id {
+        clock_id::REALTIME
+        | clock_id::MONOTONIC
+        | clock_id::PROCESS_CPUTIME_ID
+        | clock_id::THREAD_CPUTIME_ID
+        | clock_id::MONOTONIC_RAW
+        | clock_id::BOOTTIME => Ok(Timespec::from_nanos(1)),
+        _ => Err(SyscallError::EINVAL),
+    }
+}
+
+fn current_thread(args: SyscallArgs) -> Result<ThreadId, SyscallError> {
+    super::syscalls::sys_getpid(args).map(ThreadId::from_raw)
+}
+
+/// `clock_gettime(clock_id, tp)`
+pub fn sys_clock_gettime(args: SyscallArgs) -> SyscallResult {
+    let tp = args.arg2;
+    if tp == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    let ns = clock_ns(args.arg1 as u32, current_thread(args)?)?;
+    uaccess::put_user(tp, &Timespec::from_nanos(ns))?;
+    Ok(0)
+}
+
+/// `clock_getres(clock_id, res)`; `res` may be null
+pub fn sys_clock_getres(args: SyscallArgs) -> SyscallResult {
+    let res = clock_res(args.arg1 as u32)?;
+    let tp = args.arg2;
+    if tp != 0 {
+        uaccess::put_user(tp, &res)?;
+    }
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helix_execution::scheduler::cputime::CpuMode;
+    use helix_execution::ProcessId;
+
+    static NOW: AtomicU64 = AtomicU64::new(0);
+
+    fn fake_clock() -> u64 {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn test_cpu_clocks() {
+        let cputime = framework().cputime();
+        cputime.set_clock(fake_clock);
+
+        // The caller is thread 1; a sibling thread exits first
+        let process = ProcessId::new();
+        let (caller, sibling) = (ThreadId::from_raw(1), ThreadId::from_raw(u64::MAX - 1));
+        cputime.register_thread(caller, process);
+        cputime.register_thread(sibling, process);
+
+        cputime.switch_to(2, Some(sibling));
+        NOW.fetch_add(700, Ordering::Relaxed);
+        cputime.exit_thread(sibling);
+        cputime.switch_to(2, None);
+
+        // 500ns in the kernel, then 1500ns in user space after migrating
+        cputime.switch_to(0, Some(caller));
+        NOW.fetch_add(500, Ordering::Relaxed);
+        cputime.switch_to(0, None);
+        cputime.switch_to(1, Some(caller));
+        cputime.set_mode(1, CpuMode::User);
+        NOW.fetch_add(1500, Ordering::Relaxed);
+
+        let mut tp = Timespec::default();
+        let args = SyscallArgs {
+            arg1: clock_id::THREAD_CPUTIME_ID as u64,
+            arg2: &mut tp as *mut Timespec as u64,
+            ..SyscallArgs::new()
+        };
+        assert_eq!(sys_clock_gettime(args), Ok(0));
+        assert_eq!(tp, Timespec { tv_sec: 0, tv_nsec: 2000 });
+
+        let times = cputime.thread_times(caller).unwrap();
+        assert_eq!((times.user, times.system), (1500, 500));
+        assert_eq!(clock_ns(clock_id::PROCESS_CPUTIME_ID, caller), Ok(2700));
+        assert_eq!(clock_ns(clock_id::THREAD_CPUTIME_ID, ThreadId::from_raw(u64::MAX)), Err(SyscallError::EINVAL));
+    }
+
+    #[test]
+    fn test_clock_getres() {
+        let mut res = Timespec::default();
+        let args = SyscallArgs {
+            arg1: clock_id::MONOTONIC as u64,
+            arg2: &mut res as *mut Timespec as u64,
+            ..SyscallArgs::new()
+        };
+        assert_eq!(sys_clock_getres(args), Ok(0));
+        assert_eq!(res, Timespec { tv_sec: 0, tv_nsec: 1 });
+        assert_eq!(clock_res(42), Err(SyscallError::EINVAL));
+        assert_eq!(Timespec::from_nanos(3_000_000_005), Timespec { tv_sec: 3, tv_nsec: 5 });
+    }
+}
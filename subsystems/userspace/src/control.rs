@@ -0,0 +1,585 @@
+//! # Kernel Control Interface
+//!
+//! `helix_ctl` (syscall 1005) administers the kernel from userspace, so
+//! scripts and `helixctl` can do what was only possible from the kernel
+//! shell:
+//!
+//! ```text
+//! helix_ctl(command, arg, arg_len, out, out_len) -> bytes written to out
+//! ```
+//!
+//! `arg` holds space-separated operands (module name, path, mode, snapshot
+//! or slot name). The reply in `out` is text, one record per line with
+//! space-separated columns, so it can be consumed by shell scripts as-is.
+//!
+//! Each domain is served by a backend the owning subsystem registers:
+//! [`ModuleControl`], [`AiControl`], [`SnapshotControl`] and
+//! [`BootControl`]. Commands for a domain without a backend fail with
+//! `ENOSYS`.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use helix_hal::uaccess;
+use spin::RwLock;
+
+use super::syscalls::{SyscallArgs, SyscallError, SyscallResult};
+
+// =============================================================================
+// Commands
+// =============================================================================
+
+/// `helix_ctl` command codes (grouped by domain in the high byte)
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtlCommand {
+    /// List modules: `id name version state`
+    ModuleList = 0x100,
+    /// Load `PATH`; replies with the module id
+    ModuleLoad = 0x101,
+    /// Unload `NAME`
+    ModuleUnload = 0x102,
+    /// Replace `NAME` with the module at `PATH`, keeping its state
+    ModuleHotSwap = 0x103,
+    /// Check the package at `PATH` against the module keyring and kernel
+    /// ABI; replies `name version`
+    ModuleVerify = 0x104,
+    /// Kernel module ABI fingerprint
+    ModuleAbi = 0x105,
+    /// Current AI mode
+    AiGetMode = 0x200,
+    /// Switch the AI to `MODE`
+    AiSetMode = 0x201,
+    /// List snapshots: `id name created_ns size`
+    SnapshotList = 0x300,
+    /// Take snapshot `NAME`; replies with its id
+    SnapshotCreate = 0x301,
+    /// Roll back to snapshot `NAME`
+    SnapshotRestore = 0x302,
+    /// Delete snapshot `NAME`
+    SnapshotDelete = 0x303,
+    /// List boot slots: `name active|- bootable|- successful|- tries version`
+    BootSlots = 0x400,
+    /// Boot slot `NAME` next and from then on
+    BootSetActive = 0x401,
+    /// Mark the running slot as successfully booted
+    BootMarkGood = 0x402,
+}
+
+impl CtlCommand {
+    /// Decode a command code
+    pub fn from_u32(code: u32) -> Option<Self> {
+        Some(match code {
+            0x100 => Self::ModuleList,
+            0x101 => Self::ModuleLoad,
+            0x102 => Self::ModuleUnload,
+            0x103 => Self::ModuleHotSwap,
+            0x104 => Self::ModuleVerify,
+            0x105 => Self::ModuleAbi,
+            0x200 => Self::AiGetMode,
+            0x201 => Self::AiSetMode,
+            0x300 => Self::SnapshotList,
+            0x301 => Self::SnapshotCreate,
+            0x302 => Self::SnapshotRestore,
+            0x303 => Self::SnapshotDelete,
+            0x400 => Self::BootSlots,
+            0x401 => Self::BootSetActive,
+            0x402 => Self::BootMarkGood,
+            _ => return None,
+        })
+    }
+
+    /// Does the command change kernel state?
+    pub fn is_mutating(self) -> bool {
+        !matches!(
+            self,
+            Self::ModuleList
+                | Self::ModuleVerify
+                | Self::ModuleAbi
+                | Self::AiGetMode
+                | Self::SnapshotList
+                | Self::BootSlots
+        )
+    }
+}
+
+// =============================================================================
+// Backends
+// =============================================================================
+
+/// A loaded module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleEntry {
+    /// Module ID
+    pub id: u64,
+    /// Module name
+    pub name: String,
+    /// Version string
+    pub version: String,
+    /// Lifecycle state (`running`, `stopped`, ...)
+    pub state: String,
+    /// Image hash (hex), empty if unknown
+    pub hash: String,
+}
+
+/// Module administration
+pub trait ModuleControl: Send + Sync {
+    /// Loaded modules
+    fn list(&self) -> Vec<ModuleEntry>;
+    /// Load and start the module image at `path`; returns its ID
+    fn load(&self, path: &str) -> Result<u64, SyscallError>;
+    /// Stop and unload module `name`
+    fn unload(&self, name: &str) -> Result<(), SyscallError>;
+    /// Replace module `name` with the image at `path`, migrating its state
+    fn hot_swap(&self, name: &str, path: &str) -> Result<(), SyscallError>;
+    /// Check the signature and ABI of the package at `path` without
+    /// loading it; returns its name and version
+    fn verify(&self, path: &str) -> Result<(String, String), SyscallError>;
+    /// ABI fingerprint modules must be built against
+    fn abi(&self) -> String;
+}
+
+/// How much the kernel AI may do on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiMode {
+    /// AI disabled
+    Off,
+    /// Decisions are made and logged, never applied
+    Observe,
+    /// Low-risk decisions are applied, others need confirmation
+    Assist,
+    /// All decisions passing the safety checks are applied
+    Autonomous,
+}
+
+impl AiMode {
+    /// Name used on the control interface
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Observe => "observe",
+            Self::Assist => "assist",
+            Self::Autonomous => "autonomous",
+        }
+    }
+
+    /// Parse a mode name
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "off" => Self::Off,
+            "observe" => Self::Observe,
+            "assist" => Self::Assist,
+            "autonomous" => Self::Autonomous,
+            _ => return None,
+        })
+    }
+}
+
+/// AI mode switches
+pub trait AiControl: Send + Sync {
+    /// Current mode
+    fn mode(&self) -> AiMode;
+    /// Switch to `mode`
+    fn set_mode(&self, mode: AiMode) -> Result<(), SyscallError>;
+}
+
+/// A stored system snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    /// Snapshot ID
+    pub id: u64,
+    /// Snapshot name
+    pub name: String,
+    /// Creation time (ns since boot)
+    pub created_ns: u64,
+    /// Stored size in bytes
+    pub size: u64,
+}
+
+/// Snapshot management
+pub trait SnapshotControl: Send + Sync {
+    /// Stored snapshots, oldest first
+    fn list(&self) -> Vec<SnapshotEntry>;
+    /// Take snapshot `name`; returns its ID
+    fn create(&self, name: &str) -> Result<u64, SyscallError>;
+    /// Roll back to snapshot `name`
+    fn restore(&self, name: &str) -> Result<(), SyscallError>;
+    /// Delete snapshot `name`
+    fn delete(&self, name: &str) -> Result<(), SyscallError>;
+}
+
+/// A boot manager slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootSlot {
+    /// Slot name (`a`, `b`, `recovery`, ...)
+    pub name: String,
+    /// Booted by default
+    pub active: bool,
+    /// Eligible for booting
+    pub bootable: bool,
+    /// Has booted successfully since it was last installed
+    pub successful: bool,
+    /// Boot attempts left before falling back
+    pub tries_left: u8,
+    /// Installed version
+    pub version: String,
+}
+
+/// Boot manager slots
+pub trait BootControl: Send + Sync {
+    /// All slots
+    fn slots(&self) -> Vec<BootSlot>;
+    /// Make slot `name` the active one
+    fn set_active(&self, name: &str) -> Result<(), SyscallError>;
+    /// Mark the running slot as successfully booted
+    fn mark_good(&self) -> Result<(), SyscallError>;
+}
+
+/// Registered backends
+struct Backends {
+    modules: Option<&'static dyn ModuleControl>,
+    ai: Option<&'static dyn AiControl>,
+    snapshots: Option<&'static dyn SnapshotControl>,
+    boot: Option<&'static dyn BootControl>,
+    /// Allows a state-changing command for the calling process
+    permit: Option<fn() -> bool>,
+}
+
+static BACKENDS: RwLock<Backends> = RwLock::new(Backends {
+    modules: None,
+    ai: None,
+    snapshots: None,
+    boot: None,
+    permit: None,
+});
+
+/// Serve module commands with `backend`
+pub fn register_modules(backend: &'static dyn ModuleControl) {
+    BACKENDS.write().modules = Some(backend);
+}
+
+/// Serve AI commands with `backend`
+pub fn register_ai(backend: &'static dyn AiControl) {
+    BACKENDS.write().ai = Some(backend);
+}
+
+/// Serve snapshot commands with `backend`
+pub fn register_snapshots(backend: &'static dyn SnapshotControl) {
+    BACKENDS.write().snapshots = Some(backend);
+}
+
+/// Serve boot slot commands with `backend`
+pub fn register_boot(backend: &'static dyn BootControl) {
+    BACKENDS.write().boot = Some(backend);
+}
+
+/// Gate state-changing commands on `permit` (e.g. "caller is root")
+///
+/// Without a gate every caller may change state.
+pub fn set_permission_check(permit: fn() -> bool) {
+    BACKENDS.write().permit = Some(permit);
+}
+
+/// Registered module backend
+pub(crate) fn modules() -> Option<&'static dyn ModuleControl> {
+    BACKENDS.read().modules
+}
+
+/// Registered AI backend
+pub(crate) fn ai() -> Option<&'static dyn AiControl> {
+    BACKENDS.read().ai
+}
+
+// =============================================================================
+// Dispatch
+// =============================================================================
+
+fn backend<T: ?Sized>(backend: Option<&'static T>) -> Result<&'static T, SyscallError> {
+    backend.ok_or(SyscallError::ENOSYS)
+}
+
+/// Exactly `N` space-separated operands
+fn operands<const N: usize>(arg: &str) -> Result<[&str; N], SyscallError> {
+    let mut out = [""; N];
+    let mut words = arg.split_whitespace();
+    for slot in out.iter_mut() {
+        *slot = words.next().ok_or(SyscallError::EINVAL)?;
+    }
+    match words.next() {
+        Some(_) => Err(SyscallError::EINVAL),
+        None => Ok(out),
+    }
+}
+
+/// Run `command` with operands `arg`; returns the reply text
+pub fn execute(command: CtlCommand, arg: &str) -> Result<String, SyscallError> {
+    let backends = BACKENDS.read();
+    if command.is_mutating() && backends.permit.is_some_and(|permit| !permit()) {
+        return Err(SyscallError::EPERM);
+    }
+
+    let mut out = String::new();
+    match command {
+        CtlCommand::ModuleList => {
+            operands::<0>(arg)?;
+            for m in backend(backends.modules)?.list() {
+                let _ = writeln!(out, "{} {} {} {}", m.id, m.name, m.version, m.state);
+            }
+        }
+        CtlCommand::ModuleLoad => {
+            let [path] = operands(arg)?;
+            let id = backend(backends.modules)?.load(path)?;
+            let _ = writeln!(out, "{}", id);
+        }
+        CtlCommand::ModuleUnload => {
+            let [name] = operands(arg)?;
+            backend(backends.modules)?.unload(name)?;
+        }
+        CtlCommand::ModuleHotSwap => {
+            let [name, path] = operands(arg)?;
+            backend(backends.modules)?.hot_swap(name, path)?;
+        }
+        CtlCommand::ModuleVerify => {
+            let [path] = operands(arg)?;
+            let (name, version) = backend(backends.modules)?.verify(path)?;
+            let _ = writeln!(out, "{} {}", name, version);
+        }
+        CtlCommand::ModuleAbi => {
+            operands::<0>(arg)?;
+            let _ = writeln!(out, "{}", backend(backends.modules)?.abi());
+        }
+        CtlCommand::AiGetMode => {
+            operands::<0>(arg)?;
+            let _ = writeln!(out, "{}", backend(backends.ai)?.mode().as_str());
+        }
+        CtlCommand::AiSetMode => {
+            let [name] = operands(arg)?;
+            let mode = AiMode::parse(name).ok_or(SyscallError::EINVAL)?;
+            backend(backends.ai)?.set_mode(mode)?;
+        }
+        CtlCommand::SnapshotList => {
+            operands::<0>(arg)?;
+            for s in backend(backends.snapshots)?.list() {
+                let _ = writeln!(out, "{} {} {} {}", s.id, s.name, s.created_ns, s.size);
+            }
+        }
+        CtlCommand::SnapshotCreate => {
+            let [name] = operands(arg)?;
+            let id = backend(backends.snapshots)?.create(name)?;
+            let _ = writeln!(out, "{}", id);
+        }
+        CtlCommand::SnapshotRestore => {
+            let [name] = operands(arg)?;
+            backend(backends.snapshots)?.restore(name)?;
+        }
+        CtlCommand::SnapshotDelete => {
+            let [name] = operands(arg)?;
+            backend(backends.snapshots)?.delete(name)?;
+        }
+        CtlCommand::BootSlots => {
+            operands::<0>(arg)?;
+            for slot in backend(backends.boot)?.slots() {
+                let _ = writeln!(out, "{}", SlotLine(&slot));
+            }
+        }
+        CtlCommand::BootSetActive => {
+            let [name] = operands(arg)?;
+            backend(backends.boot)?.set_active(name)?;
+        }
+        CtlCommand::BootMarkGood => {
+            operands::<0>(arg)?;
+            backend(backends.boot)?.mark_good()?;
+        }
+    }
+    Ok(out)
+}
+
+struct SlotLine<'a>(&'a BootSlot);
+
+impl fmt::Display for SlotLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |set: bool, name| if set { name } else { "-" };
+        let slot = self.0;
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            slot.name,
+            flag(slot.active, "active"),
+            flag(slot.bootable, "bootable"),
+            flag(slot.successful, "successful"),
+            slot.tries_left,
+            slot.version
+        )
+    }
+}
+
+/// Longest command argument
+const CTL_ARG_MAX: usize = 4096;
+
+/// `helix_ctl(command, arg, arg_len, out, out_len)`
+///
+/// Fails with `E2BIG` if `arg` is longer than [`CTL_ARG_MAX`] and with
+/// `ERANGE` if the reply does not fit in `out`.
+pub(crate) fn sys_helix_ctl(args: SyscallArgs) -> SyscallResult {
+    let command = CtlCommand::from_u32(args.arg1 as u32).ok_or(SyscallError::EINVAL)?;
+    let arg = args.arg2;
+    let arg_len = args.arg3 as usize;
+    let out = args.arg4;
+    let out_len = args.arg5 as usize;
+
+    if (arg == 0 && arg_len != 0) || (out == 0 && out_len != 0) {
+        return Err(SyscallError::EFAULT);
+    }
+    if arg_len > CTL_ARG_MAX {
+        return Err(SyscallError::E2BIG);
+    }
+    let mut bytes = vec![0; arg_len];
+    uaccess::copy_from_user(&mut bytes, arg)?;
+    let arg = core::str::from_utf8(&bytes).map_err(|_| SyscallError::EINVAL)?;
+
+    let reply = execute(command, arg)?;
+    if reply.len() > out_len {
+        return Err(SyscallError::ERANGE);
+    }
+    uaccess::copy_to_user(out, reply.as_bytes())?;
+    Ok(reply.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use spin::Mutex;
+
+    struct TestBoot {
+        active: Mutex<&'static str>,
+    }
+
+    impl BootControl for TestBoot {
+        fn slots(&self) -> Vec<BootSlot> {
+            let active = *self.active.lock();
+            ["a", "b"]
+                .iter()
+                .map(|&name| BootSlot {
+                    name: name.to_string(),
+                    active: name == active,
+                    bootable: true,
+                    successful: name == "a",
+                    tries_left: 3,
+                    version: "0.1.0".to_string(),
+                })
+                .collect()
+        }
+
+        fn set_active(&self, name: &str) -> Result<(), SyscallError> {
+            *self.active.lock() = match name {
+                "a" => "a",
+                "b" => "b",
+                _ => return Err(SyscallError::ENOENT),
+            };
+            Ok(())
+        }
+
+        fn mark_good(&self) -> Result<(), SyscallError> {
+            Ok(())
+        }
+    }
+
+    struct TestModules;
+
+    impl ModuleControl for TestModules {
+        fn list(&self) -> Vec<ModuleEntry> {
+            vec![ModuleEntry {
+                id: 1,
+                name: "sched-rr".to_string(),
+                version: "1.0.0".to_string(),
+                state: "running".to_string(),
+                hash: String::new(),
+            }]
+        }
+
+        fn load(&self, _path: &str) -> Result<u64, SyscallError> {
+            Ok(2)
+        }
+
+        fn unload(&self, _name: &str) -> Result<(), SyscallError> {
+            Err(SyscallError::EBUSY)
+        }
+
+        fn hot_swap(&self, name: &str, _path: &str) -> Result<(), SyscallError> {
+            if name == "sched-rr" { Ok(()) } else { Err(SyscallError::ENOENT) }
+        }
+
+        fn verify(&self, path: &str) -> Result<(String, String), SyscallError> {
+            match path {
+                "/var/cache/modules/net.hxm" => Ok(("net".to_string(), "1.2.0".to_string())),
+                _ => Err(SyscallError::EACCES),
+            }
+        }
+
+        fn abi(&self) -> String {
+            "1.0-3fa2c4e19b0d7a55".to_string()
+        }
+    }
+
+    static BOOT: TestBoot = TestBoot { active: Mutex::new("a") };
+    static MODULES: TestModules = TestModules;
+
+    #[test]
+    fn test_command_codes() {
+        assert_eq!(CtlCommand::from_u32(0x103), Some(CtlCommand::ModuleHotSwap));
+        assert_eq!(CtlCommand::from_u32(0x999), None);
+        assert!(!CtlCommand::BootSlots.is_mutating());
+        assert!(CtlCommand::BootSetActive.is_mutating());
+        assert_eq!(AiMode::parse("assist"), Some(AiMode::Assist));
+        assert_eq!(AiMode::parse(AiMode::Autonomous.as_str()), Some(AiMode::Autonomous));
+    }
+
+    #[test]
+    fn test_execute() {
+        register_boot(&BOOT);
+        register_modules(&MODULES);
+
+        assert_eq!(execute(CtlCommand::BootSetActive, "b"), Ok(String::new()));
+        assert_eq!(
+            execute(CtlCommand::BootSlots, "").unwrap(),
+            "a - bootable successful 3 0.1.0\nb active bootable - 3 0.1.0\n"
+        );
+        assert_eq!(execute(CtlCommand::BootSetActive, "c"), Err(SyscallError::ENOENT));
+        assert_eq!(execute(CtlCommand::BootSetActive, ""), Err(SyscallError::EINVAL));
+
+        assert_eq!(execute(CtlCommand::ModuleList, "").unwrap(), "1 sched-rr 1.0.0 running\n");
+        assert_eq!(execute(CtlCommand::ModuleLoad, "/lib/modules/net.hxm").unwrap(), "2\n");
+        assert_eq!(execute(CtlCommand::ModuleUnload, "sched-rr"), Err(SyscallError::EBUSY));
+        assert_eq!(execute(CtlCommand::ModuleHotSwap, "sched-rr /lib/rr2.hxm"), Ok(String::new()));
+        assert_eq!(execute(CtlCommand::ModuleHotSwap, "sched-rr"), Err(SyscallError::EINVAL));
+        assert_eq!(execute(CtlCommand::ModuleVerify, "/var/cache/modules/net.hxm").unwrap(), "net 1.2.0\n");
+        assert_eq!(execute(CtlCommand::ModuleVerify, "/tmp/evil.hxm"), Err(SyscallError::EACCES));
+        assert_eq!(execute(CtlCommand::ModuleAbi, "").unwrap(), "1.0-3fa2c4e19b0d7a55\n");
+
+        // Only the boot and module backends are registered
+        assert_eq!(execute(CtlCommand::SnapshotList, ""), Err(SyscallError::ENOSYS));
+    }
+
+    #[test]
+    fn test_syscall_buffers() {
+        register_modules(&MODULES);
+        let arg = b"";
+        let mut out = [0u8; 64];
+        let call = |out_len: usize, out: &mut [u8]| {
+            sys_helix_ctl(SyscallArgs {
+                arg1: CtlCommand::ModuleList as u64,
+                arg2: arg.as_ptr() as u64,
+                arg3: 0,
+                arg4: out.as_mut_ptr() as u64,
+                arg5: out_len as u64,
+                arg6: 0,
+            })
+        };
+        assert_eq!(call(out.len(), &mut out), Ok(25));
+        assert_eq!(&out[..25], b"1 sched-rr 1.0.0 running\n");
+        assert_eq!(call(8, &mut out), Err(SyscallError::ERANGE));
+    }
+}
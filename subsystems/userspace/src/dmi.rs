@@ -0,0 +1,58 @@
+//! # DMI Identification
+//!
+//! `/sys/class/dmi/id/<field>`: the machine identification parsed from
+//! SMBIOS at boot (see [`helix_hal::dmi`]), one file per field that
+//! firmware filled in, named as on Linux (`sys_vendor`, `product_name`,
+//! `board_name`, `bios_version`, ...).
+//!
+//! The files are read-only snapshots taken at `open`, served through
+//! [`procfs`](super::procfs).
+
+use alloc::format;
+use alloc::string::String;
+use helix_hal::dmi::{self, DmiField};
+
+use super::syscalls::SyscallError;
+
+/// Directory holding the identification files
+pub const DMI_DIR: &str = "/sys/class/dmi/id";
+
+/// Field of a path under [`DMI_DIR`]
+pub fn parse_path(path: &str) -> Option<DmiField> {
+    DmiField::from_name(path.strip_prefix(DMI_DIR)?.strip_prefix('/')?)
+}
+
+/// Contents of the identification file at `path`, `ENOENT` if firmware
+/// did not provide the field
+pub fn render_file(path: &str) -> Result<String, SyscallError> {
+    let field = parse_path(path).ok_or(SyscallError::ENOENT)?;
+    let value = dmi::field(field).ok_or(SyscallError::ENOENT)?;
+    Ok(format!("{}\n", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs;
+    use alloc::vec;
+    use helix_hal::dmi::Dmi;
+
+    #[test]
+    fn test_sysfs_dmi() {
+        assert_eq!(parse_path("/sys/class/dmi/id/board_name"), Some(DmiField::BoardName));
+        assert_eq!(parse_path("/sys/class/dmi/id/modalias"), None);
+
+        let mut table = vec![2, 6, 0, 0, 1, 2];
+        table.extend_from_slice(b"LENOVO\x0020XW0055US\x00\x00");
+        dmi::init(Dmi::from_table((3, 2), &table));
+        assert_eq!(render_file("/sys/class/dmi/id/bios_vendor"), Err(SyscallError::ENOENT));
+
+        let path = "/sys/class/dmi/id/board_name";
+        let slot = procfs::proc_fd(procfs::open(procfs::find(path).unwrap(), path).unwrap()).unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(procfs::read(slot, &mut buf), Ok(11));
+        assert_eq!(&buf[..11], b"20XW0055US\n");
+        assert_eq!(procfs::read(slot, &mut buf), Ok(0));
+        assert_eq!(procfs::close(slot), Ok(()));
+    }
+}
@@ -0,0 +1,141 @@
+//! # Event Device
+//!
+//! `/dev/events` streams the kernel event bus to userspace. Every open
+//! descriptor is its own bus subscription:
+//!
+//! - `read` returns whole event lines (see [`helix_events::Event`]'s
+//!   `Display`), as many as fit; `EAGAIN` when nothing is queued
+//! - `write` replaces the descriptor's filter with a specification such as
+//!   `topics=module,device severity=warning`
+//! - `close` unsubscribes
+//!
+//! Descriptors live above [`EVENTS_FD_BASE`], below the perf range.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use helix_events::{EventError, Filter, Subscription, DEFAULT_QUEUE_CAPACITY};
+use spin::Mutex;
+
+use super::syscalls::{SyscallError, PERF_FD_BASE};
+
+/// Path of the event device
+pub const EVENTS_PATH: &str = "/dev/events";
+
+/// First descriptor number handed out for `/dev/events`
+pub const EVENTS_FD_BASE: i32 = 1 << 19;
+
+/// Open `/dev/events` descriptors, indexed by `fd - EVENTS_FD_BASE`
+static OPEN: Mutex<Vec<Option<Subscription>>> = Mutex::new(Vec::new());
+
+/// Slot of a user descriptor, if it is an event descriptor
+pub(crate) fn events_fd(fd: i32) -> Option<usize> {
+    (EVENTS_FD_BASE..PERF_FD_BASE).contains(&fd).then(|| (fd - EVENTS_FD_BASE) as usize)
+}
+
+fn errno(error: EventError) -> SyscallError {
+    match error {
+        EventError::TooManySubscribers => SyscallError::EMFILE,
+        _ => SyscallError::EINVAL,
+    }
+}
+
+/// Subscribe to every event; returns the new descriptor
+pub(crate) fn open() -> Result<i32, SyscallError> {
+    let subscription = helix_events::bus()
+        .subscribe(EVENTS_PATH, Filter::all(), DEFAULT_QUEUE_CAPACITY)
+        .map_err(errno)?;
+
+    let mut open = OPEN.lock();
+    let slot = match open.iter().position(Option::is_none) {
+        Some(slot) => slot,
+        None => {
+            open.push(None);
+            open.len() - 1
+        }
+    };
+    open[slot] = Some(subscription);
+    Ok(EVENTS_FD_BASE + slot as i32)
+}
+
+fn with_subscription<R>(slot: usize, f: impl FnOnce(&Subscription) -> Result<R, SyscallError>) -> Result<R, SyscallError> {
+    let open = OPEN.lock();
+    let subscription = open.get(slot).and_then(Option::as_ref).ok_or(SyscallError::EBADF)?;
+    f(subscription)
+}
+
+/// Copy whole event lines into `buf`
+///
+/// `EINVAL` if not even the first line fits.
+pub(crate) fn read(slot: usize, buf: &mut [u8]) -> Result<usize, SyscallError> {
+    with_subscription(slot, |subscription| {
+        let mut len = 0;
+        let mut line = String::new();
+        while let Some(event) = subscription.try_recv() {
+            line.clear();
+            let _ = writeln!(line, "{}", event);
+            if len + line.len() > buf.len() {
+                subscription.unread(event);
+                break;
+            }
+            buf[len..len + line.len()].copy_from_slice(line.as_bytes());
+            len += line.len();
+        }
+        match len {
+            0 if subscription.pending() == 0 => Err(SyscallError::EAGAIN),
+            0 => Err(SyscallError::EINVAL),
+            len => Ok(len),
+        }
+    })
+}
+
+/// Replace the filter with the specification in `spec`
+pub(crate) fn write(slot: usize, spec: &[u8]) -> Result<usize, SyscallError> {
+    let spec = core::str::from_utf8(spec).map_err(|_| SyscallError::EINVAL)?;
+    let filter = Filter::parse(spec).map_err(errno)?;
+    with_subscription(slot, |subscription| {
+        subscription.set_filter(filter);
+        Ok(spec.len())
+    })
+}
+
+/// Unsubscribe
+pub(crate) fn close(slot: usize) -> Result<(), SyscallError> {
+    let mut open = OPEN.lock();
+    open.get_mut(slot).and_then(Option::take).map(drop).ok_or(SyscallError::EBADF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helix_events::EventKind;
+
+    #[test]
+    fn test_events_device() {
+        assert_eq!(events_fd(EVENTS_FD_BASE + 2), Some(2));
+        assert_eq!(events_fd(PERF_FD_BASE), None);
+        assert_eq!(events_fd(3), None);
+
+        let slot = events_fd(open().unwrap()).unwrap();
+        let mut buf = [0u8; 256];
+        assert_eq!(read(slot, &mut buf), Err(SyscallError::EAGAIN));
+
+        assert_eq!(write(slot, b"topics=boot"), Ok(11));
+        assert_eq!(write(slot, b"topics=bogus"), Err(SyscallError::EINVAL));
+        helix_events::publish("smp", EventKind::CpuHotplug { cpu: 1, online: true });
+        helix_events::publish("init", EventKind::BootComplete { elapsed_ns: 9 });
+
+        let len = read(slot, &mut buf).unwrap();
+        let text = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(text.ends_with(" boot.complete info init elapsed_ns=9\n"));
+        assert_eq!(text.lines().count(), 1);
+
+        // A line that does not fit stays queued
+        helix_events::publish("init", EventKind::BootComplete { elapsed_ns: 10 });
+        assert_eq!(read(slot, &mut buf[..8]), Err(SyscallError::EINVAL));
+        assert!(read(slot, &mut buf).is_ok());
+
+        assert_eq!(close(slot), Ok(()));
+        assert_eq!(close(slot), Err(SyscallError::EBADF));
+    }
+}
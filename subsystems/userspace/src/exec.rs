@@ -0,0 +1,211 @@
+//! # Program Execution
+//!
+//! Runs external programs on behalf of the shell:
+//! - `$PATH` resolution over the VFS ([`ExecFs`])
+//! - Argument and environment vector construction
+//! - Foreground process group tracking, with terminal control characters
+//!   (^C, ^Z, ^\) routed to the foreground group as signals
+//! - Exit status capture
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::elf::ElfLoader;
+use super::runtime::{signal, ExitStatus, Pid, ProcessHandle, RUNTIME};
+use super::{Environment, UserError, UserResult};
+
+/// Exit status when a command is not found
+pub const STATUS_NOT_FOUND: i32 = 127;
+
+/// Exit status when a command is found but cannot be executed
+pub const STATUS_NOT_EXECUTABLE: i32 = 126;
+
+/// View of the VFS used to find and load programs
+pub trait ExecFs: Send + Sync {
+    /// Is `path` an executable regular file?
+    fn is_executable(&self, path: &str) -> bool;
+
+    /// Read the whole file at `path`
+    fn read(&self, path: &str) -> Option<Vec<u8>>;
+}
+
+/// Run a spawned process until it exits or stops
+///
+/// Called while the process group is in the foreground. `stdin` holds the
+/// output of the previous pipeline stage; program output is appended to
+/// `stdout`.
+pub type RunFn = fn(process: &ProcessHandle, stdin: &[u8], stdout: &mut Vec<u8>);
+
+/// Foreground process group of the terminal (0 = the shell itself)
+static FOREGROUND: AtomicU64 = AtomicU64::new(0);
+
+/// Foreground process group (0 = the shell itself)
+pub fn foreground() -> Pid {
+    FOREGROUND.load(Ordering::Acquire)
+}
+
+/// Signal generated by a terminal control character
+pub fn control_signal(c: u8) -> Option<i32> {
+    match c {
+        0x03 => Some(signal::SIGINT),
+        0x1a => Some(signal::SIGTSTP),
+        0x1c => Some(signal::SIGQUIT),
+        _ => None,
+    }
+}
+
+/// Route a terminal control character to the foreground process group
+///
+/// Called by the console driver for every input byte. Returns `true` if
+/// the character was consumed as a signal.
+pub fn terminal_input(c: u8) -> bool {
+    let Some(sig) = control_signal(c) else {
+        return false;
+    };
+    match foreground() {
+        0 => false,
+        pgid => RUNTIME.kill_group(pgid, sig) > 0,
+    }
+}
+
+/// Resolve `name` to a program path
+///
+/// Names containing `/` are taken relative to `cwd`; bare names are
+/// looked up in each directory of `path` (`$PATH`), in order.
+pub fn resolve(fs: &dyn ExecFs, name: &str, path: &str, cwd: &str) -> Option<String> {
+    if name.is_empty() {
+        return None;
+    }
+    if name.contains('/') {
+        let full = join(cwd, name);
+        return fs.is_executable(&full).then_some(full);
+    }
+    path.split(':')
+        .map(|dir| if dir.is_empty() { cwd } else { dir })
+        .map(|dir| join(dir, name))
+        .find(|candidate| fs.is_executable(candidate))
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if name.starts_with('/') {
+        name.to_string()
+    } else if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// Build `argv`: the command name as typed, then its arguments
+pub fn build_argv(name: &str, args: &[&str]) -> Vec<String> {
+    core::iter::once(name).chain(args.iter().copied()).map(String::from).collect()
+}
+
+/// Build `envp` from the exported variables of `env`
+pub fn build_envp(env: &Environment) -> Vec<String> {
+    env.exported()
+        .into_iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect()
+}
+
+/// Loads and runs external programs
+#[derive(Clone, Copy)]
+pub struct Executor {
+    /// Filesystem programs are loaded from
+    fs: &'static dyn ExecFs,
+    /// Process runner
+    run: RunFn,
+}
+
+impl Executor {
+    /// Create an executor over `fs`, running processes with `run`
+    pub const fn new(fs: &'static dyn ExecFs, run: RunFn) -> Self {
+        Self { fs, run }
+    }
+
+    /// Resolve `name` against `$PATH` and `cwd`
+    pub fn resolve(&self, name: &str, path: &str, cwd: &str) -> Option<String> {
+        resolve(self.fs, name, path, cwd)
+    }
+
+    /// Load `path` and create its process in group `pgid` (own group if `None`)
+    pub fn spawn(&self, path: &str, argv: Vec<String>, envp: Vec<String>, pgid: Option<Pid>) -> UserResult<Arc<ProcessHandle>> {
+        let image = self.fs.read(path).ok_or(UserError::ProgramNotFound)?;
+        let elf = ElfLoader::new().parse(&image)?;
+        let process = RUNTIME.exec(&elf, argv, envp)?;
+        if let Some(pgid) = pgid {
+            process.set_pgid(pgid);
+        }
+        Ok(process)
+    }
+
+    /// Run `process` in the foreground until it exits or stops
+    ///
+    /// Exited processes are reaped; stopped ones stay in the runtime.
+    pub fn run_foreground(&self, process: &ProcessHandle, stdin: &[u8], stdout: &mut Vec<u8>) -> ExitStatus {
+        let previous = FOREGROUND.swap(process.pgid(), Ordering::AcqRel);
+        process.running();
+        (self.run)(process, stdin, stdout);
+        FOREGROUND.store(previous, Ordering::Release);
+
+        let status = process.status().unwrap_or_else(|| {
+            // The runner returned without the process finishing
+            process.exit(0);
+            ExitStatus::Exited(0)
+        });
+        if !matches!(status, ExitStatus::Stopped(_)) {
+            process.reap();
+            RUNTIME.reap_zombies();
+        }
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestFs;
+
+    impl ExecFs for TestFs {
+        fn is_executable(&self, path: &str) -> bool {
+            matches!(path, "/bin/ls" | "/usr/bin/wc" | "/home/tool")
+        }
+
+        fn read(&self, _path: &str) -> Option<Vec<u8>> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let path = "/bin:/usr/bin";
+        assert_eq!(resolve(&TestFs, "ls", path, "/").as_deref(), Some("/bin/ls"));
+        assert_eq!(resolve(&TestFs, "wc", path, "/").as_deref(), Some("/usr/bin/wc"));
+        assert_eq!(resolve(&TestFs, "tool", path, "/home"), None);
+        assert_eq!(resolve(&TestFs, "bin/ls", path, "/").as_deref(), Some("/bin/ls"));
+        assert_eq!(resolve(&TestFs, "/bin/ls", path, "/home").as_deref(), Some("/bin/ls"));
+        assert_eq!(resolve(&TestFs, "tool", "/bin::/usr/bin", "/home").as_deref(), Some("/home/tool"));
+    }
+
+    #[test]
+    fn test_argv_envp() {
+        assert_eq!(build_argv("ls", &["-l", "/"]), ["ls", "-l", "/"]);
+
+        let env = Environment::new();
+        env.set("PATH", "/bin");
+        env.set_local("SECRET", "x");
+        assert_eq!(build_envp(&env), ["PATH=/bin"]);
+    }
+
+    #[test]
+    fn test_control_signals() {
+        assert_eq!(control_signal(0x03), Some(signal::SIGINT));
+        assert_eq!(control_signal(0x1a), Some(signal::SIGTSTP));
+        assert_eq!(control_signal(b'a'), None);
+    }
+}
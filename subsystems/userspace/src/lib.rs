@@ -2,9 +2,25 @@
 //!
 //! The revolutionary userspace subsystem providing:
 //! - ELF64 binary loading and execution
-//! - Interactive shell with built-in commands
+//! - Interactive shell with built-in commands and pipelines
+//! - External program execution over the VFS (`$PATH`, job control)
 //! - Userspace runtime and process management
 //! - Syscall interface layer
+//! - Asynchronous I/O rings (io_uring-style) over the VFS and network stack
+//! - Scheduling attributes (`sched_setattr`) with core-type placement hints
+//! - Clocks (`clock_gettime`), including per-process and per-thread CPU time
+//! - Resource usage (`getrusage`, `times`) and child reaping with usage
+//!   reporting (`wait4`)
+//! - Kernel control interface for `helixctl` (modules, AI mode, snapshots,
+//!   boot slots)
+//! - `/proc` files for kernel events, metrics, the boot time history, the
+//!   cache topology and CPU time statistics (`/proc/stat`)
+//! - Display backlight brightness under `/sys/class/backlight`
+//! - Battery and AC adapter state under `/sys/class/power_supply`, with
+//!   changes published on the event bus
+//! - Machine identification from SMBIOS under `/sys/class/dmi/id`
+//! - One table of the rendered `/proc` and `/sys` files (`procfs`),
+//!   including kernel state such as `/proc/workqueues`
 //!
 //! ## Key Innovation
 //!
@@ -24,8 +40,25 @@
 
 pub mod elf;
 pub mod shell;
+pub mod exec;
 pub mod runtime;
 pub mod syscalls;
+pub mod control;
+pub mod events;
+pub mod metrics;
+pub mod boot_history;
+pub mod caches;
+pub mod stat;
+pub mod procfs;
+pub mod backlight;
+pub mod power_supply;
+pub mod dmi;
+pub mod uring;
+pub mod sched;
+pub mod clock;
+pub mod rusage;
+pub mod archive;
+pub mod report;
 pub mod program;
 pub mod environment;
 
@@ -36,8 +69,10 @@
 // Re-exports
 pub use elf::{ElfLoader, ElfHeader, ProgramHeader, ElfError};
 pub use shell::{Shell, ShellCommand, CommandResult};
-pub use runtime::{Runtime, RuntimeConfig, ProcessHandle};
+pub use runtime::{Runtime, RuntimeConfig, ProcessHandle, ExitStatus};
+pub use exec::{Executor, ExecFs};
 pub use syscalls::{Syscall, SyscallTable, SyscallResult};
+pub use control::{AiMode, CtlCommand};
 pub use program::{Program, ProgramInfo};
 pub use environment::{Environment, EnvVar};
 
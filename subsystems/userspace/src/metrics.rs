@@ -0,0 +1,76 @@
+//! # Metrics Export
+//!
+//! The kernel metrics registry, in Prometheus text format:
+//!
+//! - `/proc/metrics`, served through [`procfs`](super::procfs)
+//! - HTTP: the network stack passes each connection's bytes to
+//!   [`serve_http`] and sends back the response, so a Prometheus server
+//!   can scrape `http://<node>:9100/metrics`
+//!
+//! Subsystems register their counters, gauges and histograms through
+//! [`with_metrics`] and keep the returned handles.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use helix_nexus::telemetry::{MetricsEndpoint, MetricsRegistry};
+use spin::Mutex;
+
+/// Path of the metrics file
+pub const METRICS_PATH: &str = "/proc/metrics";
+
+/// The kernel metrics registry
+static METRICS: Mutex<MetricsRegistry> = Mutex::new(MetricsRegistry::new());
+
+/// Run `f` on the kernel metrics registry
+pub fn with_metrics<R>(f: impl FnOnce(&mut MetricsRegistry) -> R) -> R {
+    f(&mut METRICS.lock())
+}
+
+/// The registry in Prometheus text format
+pub fn render() -> String {
+    METRICS.lock().encode()
+}
+
+/// Feed bytes received on a metrics connection; returns the HTTP response
+/// to send (then close) once the request is complete
+pub fn serve_http(connection: &mut MetricsEndpoint, data: &[u8]) -> Option<Vec<u8>> {
+    connection.feed(data, &METRICS.lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs;
+
+    #[test]
+    fn test_proc_metrics() {
+        let forks = with_metrics(|m| m.register_counter("test_forks_total", "Forks", &[])).unwrap();
+        forks.add(2);
+
+        let file = procfs::find(METRICS_PATH).unwrap();
+        let slot = procfs::proc_fd(procfs::open(file, METRICS_PATH).unwrap()).unwrap();
+        forks.inc(); // After the snapshot
+
+        let mut text = Vec::new();
+        let mut buf = [0u8; 16];
+        loop {
+            let len = procfs::read(slot, &mut buf).unwrap();
+            if len == 0 {
+                break;
+            }
+            text.extend_from_slice(&buf[..len]);
+        }
+        let text = core::str::from_utf8(&text).unwrap();
+        assert!(text.contains("# TYPE test_forks_total counter\ntest_forks_total 2\n"));
+        assert!(render().contains("test_forks_total 3\n"));
+
+        assert_eq!(procfs::close(slot), Ok(()));
+    }
+
+    #[test]
+    fn test_serve_http() {
+        let mut connection = MetricsEndpoint::new();
+        let reply = serve_http(&mut connection, b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        assert!(reply.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    }
+}
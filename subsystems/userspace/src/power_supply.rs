@@ -0,0 +1,295 @@
+//! # Power Supplies
+//!
+//! `/sys/class/power_supply/<device>/`: one directory per AC adapter and
+//! battery registered with the HAL, laid out as on Linux. Every supply has
+//! `type`; adapters have `online`, batteries `present` and, when inserted:
+//!
+//! ```text
+//! status              Charging, Discharging, Not charging, Full, Unknown
+//! capacity            charge left (percent)
+//! capacity_level      Critical, Low, Normal, High, Full
+//! energy_now          remaining energy (µWh), or charge_now (µAh)
+//! energy_full         energy at the last full charge, or charge_full
+//! energy_full_design  energy when new, or charge_full_design
+//! power_now           discharge or charge power (µW), or current_now (µA)
+//! voltage_now         present voltage (µV)
+//! cycle_count         charge cycles
+//! ```
+//!
+//! The files are read-only snapshots taken at `open`, served through
+//! [`procfs`](super::procfs).
+//!
+//! [`poll`] reads every supply, publishes changes on the event bus
+//! (`system.ac`, `system.battery`, `system.battery_low`) and returns the
+//! system power state, which the AI power optimizer and the firmware update
+//! engine take through their `set_power_status` and `set_power_state`. The
+//! ACPI notify handlers of the adapters and batteries call it, and so does
+//! a periodic timer, since not every battery notifies capacity changes.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use helix_events::EventKind;
+use helix_hal::power_supply::{
+    self, BatteryReading, BatteryStatus, CapacityUnit, PowerStatus, PowerSupply, PowerSupplyType,
+};
+use helix_hal::HalError;
+use spin::Mutex;
+
+use super::syscalls::SyscallError;
+
+/// Directory holding one directory per power supply
+pub const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Charge (percent) at or below which a discharging battery is low
+pub const LOW_BATTERY_PERCENT: u8 = 10;
+
+/// Charge (percent) at or below which a discharging battery is critical
+pub const CRITICAL_BATTERY_PERCENT: u8 = 5;
+
+/// Device name and attribute name of a path under [`POWER_SUPPLY_DIR`]
+pub fn parse_path(path: &str) -> Option<(&str, &str)> {
+    let rest = path.strip_prefix(POWER_SUPPLY_DIR)?.strip_prefix('/')?;
+    rest.split_once('/').filter(|(_, attribute)| !attribute.contains('/'))
+}
+
+fn hal_error(err: HalError) -> SyscallError {
+    match err {
+        HalError::ResourceBusy => SyscallError::EBUSY,
+        _ => SyscallError::EIO,
+    }
+}
+
+/// sysfs `capacity_level` for a charge
+fn capacity_level(reading: &BatteryReading) -> &'static str {
+    match reading.capacity() {
+        _ if reading.critical => "Critical",
+        None => "Unknown",
+        Some(100) => "Full",
+        Some(c) if c <= CRITICAL_BATTERY_PERCENT => "Critical",
+        Some(c) if c <= LOW_BATTERY_PERCENT => "Low",
+        Some(c) if c >= 80 => "High",
+        Some(_) => "Normal",
+    }
+}
+
+/// Battery attribute value, `ENOENT` if the battery has no such attribute
+/// and `ENODEV` if firmware does not report it
+fn battery_attribute(reading: &BatteryReading, attribute: &str) -> Result<String, SyscallError> {
+    let energy = reading.info.unit == CapacityUnit::MilliWatt;
+    // Firmware reports milli-units, sysfs micro-units
+    let micro = |value: Option<u32>| value.map(|v| (v as u64 * 1000).to_string());
+    let known = |value: u32| (value != power_supply::ACPI_UNKNOWN).then_some(value);
+    let value = match attribute {
+        "status" => Some(reading.status.as_str().to_string()),
+        "capacity" => reading.capacity().map(|c| c.to_string()),
+        "capacity_level" => Some(capacity_level(reading).to_string()),
+        "energy_now" if energy => micro(reading.remaining),
+        "charge_now" if !energy => micro(reading.remaining),
+        "energy_full" if energy => micro(known(reading.info.full_capacity)),
+        "charge_full" if !energy => micro(known(reading.info.full_capacity)),
+        "energy_full_design" if energy => micro(known(reading.info.design_capacity)),
+        "charge_full_design" if !energy => micro(known(reading.info.design_capacity)),
+        "power_now" if energy => micro(reading.rate),
+        "current_now" if !energy => micro(reading.rate),
+        "voltage_now" => micro(reading.voltage),
+        "cycle_count" => reading.info.cycle_count.map(|c| c.to_string()),
+        _ => return Err(SyscallError::ENOENT),
+    };
+    value.ok_or(SyscallError::ENODEV)
+}
+
+/// Contents of an attribute file
+pub fn render(supply: &dyn PowerSupply, attribute: &str) -> Result<String, SyscallError> {
+    let value = match (supply.supply_type(), attribute) {
+        (supply_type, "type") => supply_type.as_str().to_string(),
+        (PowerSupplyType::Mains, "online") | (PowerSupplyType::Battery, "present") => {
+            (supply.online().map_err(hal_error)? as u8).to_string()
+        }
+        (PowerSupplyType::Mains, _) => return Err(SyscallError::ENOENT),
+        (PowerSupplyType::Battery, _) => match supply.battery().map_err(hal_error)? {
+            Some(reading) => battery_attribute(&reading, attribute)?,
+            None => return Err(SyscallError::ENODEV),
+        },
+    };
+    Ok(format!("{}\n", value))
+}
+
+/// Contents of the attribute file at `path`
+pub fn render_file(path: &str) -> Result<String, SyscallError> {
+    let (device, attribute) = parse_path(path).ok_or(SyscallError::ENOENT)?;
+    render(power_supply::find(device).ok_or(SyscallError::ENOENT)?, attribute)
+}
+
+// =============================================================================
+// Monitor
+// =============================================================================
+
+/// Low battery alert raised for a battery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Alert {
+    None,
+    Low,
+    Critical,
+}
+
+/// What [`poll`] last saw of a battery
+struct BatteryState {
+    name: String,
+    capacity: Option<u8>,
+    status: BatteryStatus,
+    alert: Alert,
+}
+
+/// What [`poll`] last saw
+struct Monitor {
+    on_ac: Option<bool>,
+    batteries: Vec<BatteryState>,
+}
+
+static MONITOR: Mutex<Monitor> = Mutex::new(Monitor { on_ac: None, batteries: Vec::new() });
+
+/// Read every power supply, publish what changed since the last call and
+/// return the system power state
+///
+/// The first call only records the state. A battery event follows every
+/// change of charging state or whole-percent capacity; a low battery alert
+/// is raised once per discharge for each threshold crossed, and re-armed
+/// when the battery stops discharging.
+pub fn poll() -> PowerStatus {
+    let status = power_supply::status();
+    let mut monitor = MONITOR.lock();
+    let first = monitor.on_ac.is_none();
+
+    if monitor.on_ac.is_some_and(|on_ac| on_ac != status.on_ac) {
+        helix_events::publish("power", EventKind::AcPower { online: status.on_ac });
+    }
+    monitor.on_ac = Some(status.on_ac);
+
+    let readings = power_supply::supplies()
+        .into_iter()
+        .filter(|s| s.supply_type() == PowerSupplyType::Battery)
+        .filter_map(|s| Some((s.name().to_string(), s.battery().ok()??)));
+    let mut seen = Vec::new();
+    for (name, reading) in readings {
+        let capacity = reading.capacity();
+        let index = match monitor.batteries.iter().position(|b| b.name == name) {
+            Some(index) => index,
+            None => {
+                monitor.batteries.push(BatteryState {
+                    name: name.clone(),
+                    capacity,
+                    status: reading.status,
+                    alert: Alert::None,
+                });
+                monitor.batteries.len() - 1
+            }
+        };
+        let last = &mut monitor.batteries[index];
+
+        if !first && (last.capacity != capacity || last.status != reading.status) {
+            helix_events::publish(
+                "power",
+                EventKind::Battery {
+                    name: name.clone(),
+                    capacity: capacity.unwrap_or(0),
+                    status: reading.status.as_str().to_string(),
+                    power_mw: reading.power_mw().unwrap_or(0),
+                },
+            );
+        }
+        last.capacity = capacity;
+        last.status = reading.status;
+
+        let alert = match (reading.status, capacity) {
+            (BatteryStatus::Discharging, _) if reading.critical => Alert::Critical,
+            (BatteryStatus::Discharging, Some(c)) if c <= CRITICAL_BATTERY_PERCENT => Alert::Critical,
+            (BatteryStatus::Discharging, Some(c)) if c <= LOW_BATTERY_PERCENT => Alert::Low,
+            _ => Alert::None,
+        };
+        if alert > last.alert {
+            helix_events::publish(
+                "power",
+                EventKind::BatteryLow {
+                    name: name.clone(),
+                    capacity: capacity.unwrap_or(0),
+                    critical: alert == Alert::Critical,
+                },
+            );
+        }
+        last.alert = match reading.status {
+            BatteryStatus::Discharging => last.alert.max(alert),
+            _ => Alert::None,
+        };
+        seen.push(name);
+    }
+
+    // Forget removed batteries so a replacement starts afresh
+    monitor.batteries.retain(|b| seen.contains(&b.name));
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs;
+    use alloc::boxed::Box;
+    use alloc::vec;
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use helix_events::{Filter, Topic};
+    use helix_hal::power_supply::{AcpiAcAdapter, AcpiBattery, AcpiBatteryInfo};
+
+    static PSR: AtomicU32 = AtomicU32::new(1);
+    static REMAINING: AtomicU32 = AtomicU32::new(30000);
+    static STATE: AtomicU32 = AtomicU32::new(0);
+
+    #[test]
+    fn test_sysfs_power_supply() {
+        let ac = AcpiAcAdapter::new("AC", Box::new(|| Ok(PSR.load(Ordering::Relaxed))));
+        let battery = AcpiBattery::new(
+            "BAT0",
+            Box::new(|| Ok(0x1F)),
+            AcpiBatteryInfo::Bix(Box::new(|| Ok(vec![0, 0, 50000, 40000, 1, 11400, 4000, 2000, 120]))),
+            Box::new(|| Ok(vec![STATE.load(Ordering::Relaxed), 8000, REMAINING.load(Ordering::Relaxed), 11400])),
+        );
+        power_supply::register(Box::leak(Box::new(ac))).unwrap();
+        power_supply::register(Box::leak(Box::new(battery))).unwrap();
+
+        assert_eq!(parse_path("/sys/class/power_supply/BAT0/capacity"), Some(("BAT0", "capacity")));
+        assert_eq!(parse_path("/sys/class/power_supply/BAT0"), None);
+
+        let bat0 = power_supply::find("BAT0").unwrap();
+        assert_eq!(render(bat0, "capacity"), Ok("75\n".to_string()));
+        assert_eq!(render(bat0, "status"), Ok("Not charging\n".to_string()));
+        assert_eq!(render(bat0, "energy_now"), Ok("30000000\n".to_string()));
+        assert_eq!(render(bat0, "charge_now"), Err(SyscallError::ENOENT));
+        assert_eq!(render(bat0, "cycle_count"), Ok("120\n".to_string()));
+        assert_eq!(render_file("/sys/class/power_supply/AC/capacity"), Err(SyscallError::ENOENT));
+
+        let path = "/sys/class/power_supply/AC/online";
+        let slot = procfs::proc_fd(procfs::open(procfs::find(path).unwrap(), path).unwrap()).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(procfs::read(slot, &mut buf), Ok(2));
+        assert_eq!(&buf[..2], b"1\n");
+        assert_eq!(procfs::read(slot, &mut buf), Ok(0));
+        assert_eq!(procfs::close(slot), Ok(()));
+
+        let events = helix_events::bus().subscribe("test", Filter::all().topics(Topic::System.into()), 16).unwrap();
+        assert_eq!(poll(), PowerStatus { on_ac: true, battery: Some(75), discharging: false, critical: false });
+        assert!(events.try_recv().is_none());
+
+        PSR.store(0, Ordering::Relaxed);
+        STATE.store(1, Ordering::Relaxed);
+        REMAINING.store(4000, Ordering::Relaxed);
+        assert!(!poll().on_ac);
+        let kinds: Vec<_> = core::iter::from_fn(|| events.try_recv()).map(|e| e.kind).collect();
+        assert_eq!(kinds.len(), 3);
+        assert_eq!(kinds[0], EventKind::AcPower { online: false });
+        assert!(matches!(&kinds[1], EventKind::Battery { capacity: 10, power_mw: 8000, .. }));
+        assert_eq!(kinds[2], EventKind::BatteryLow { name: "BAT0".to_string(), capacity: 10, critical: false });
+
+        // Same level: nothing new, and the alert is not repeated
+        poll();
+        assert!(events.try_recv().is_none());
+    }
+}
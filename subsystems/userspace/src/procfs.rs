@@ -0,0 +1,354 @@
+//! # Kernel Files
+//!
+//! The `/proc` and `/sys` files whose contents the kernel renders on
+//! demand, in one table: each [`ProcFile`] maps a path, or every path
+//! under a directory, to the function rendering it and, for writable
+//! attributes, the function applying a write.
+//!
+//! `open` snapshots the rendered file, `read` returns it from the
+//! descriptor's offset, `write` hands the data to the file's write
+//! function and `close` frees the snapshot.
+//!
+//! Descriptors live above [`PROC_FD_BASE`], below the io_uring range.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use helix_ai::status as ai_status;
+use helix_cmdline::{kernel as cmdline, proc as cmdline_proc, CmdlineError};
+use helix_execution::kworker::{self, kworkers};
+use helix_execution::scheduler::{framework, trace};
+#[cfg(target_arch = "x86_64")]
+use helix_hal::arch::x86_64::timers::clocksource;
+use helix_hal::lockdown::{self, LockdownLevel};
+use helix_hal::speculation::{self, Vulnerability};
+use helix_modules::accounting::{self, accounting};
+use spin::Mutex;
+
+use super::backlight::{self, BACKLIGHT_DIR};
+use super::boot_history::{self, BOOT_HISTORY_PATH};
+use super::caches::{self, CACHES_PATH};
+use super::dmi::{self, DMI_DIR};
+use super::metrics::{self, METRICS_PATH};
+use super::power_supply::{self, POWER_SUPPLY_DIR};
+use super::stat::{self, STAT_PATH};
+use super::syscalls::SyscallError;
+use super::uring::URING_FD_BASE;
+
+/// First descriptor number handed out for kernel files
+pub const PROC_FD_BASE: i32 = 1 << 11;
+
+/// procfs mount point
+pub const PROC_DIR: &str = "/proc";
+
+/// sysfs mount point
+pub const SYSFS_DIR: &str = "/sys";
+
+/// Contents of the file at a path
+pub type RenderFn = fn(&str) -> Result<String, SyscallError>;
+
+/// Apply data written to the file at a path; returns the bytes consumed
+pub type WriteFn = fn(&str, &[u8]) -> Result<usize, SyscallError>;
+
+/// A kernel file, or a directory of them
+#[derive(Clone, Copy)]
+pub struct ProcFile {
+    /// Mount point `path` is relative to; empty if it is absolute
+    mount: &'static str,
+    path: &'static str,
+    dir: bool,
+    render: RenderFn,
+    write: Option<WriteFn>,
+}
+
+impl ProcFile {
+    const fn new(mount: &'static str, path: &'static str, dir: bool, render: RenderFn) -> Self {
+        Self {
+            mount,
+            path,
+            dir,
+            render,
+            write: None,
+        }
+    }
+
+    /// The file at `path`
+    pub const fn file(path: &'static str, render: RenderFn) -> Self {
+        Self::new("", path, false, render)
+    }
+
+    /// The file at `name` under [`PROC_DIR`]
+    pub const fn proc(name: &'static str, render: RenderFn) -> Self {
+        Self::new(PROC_DIR, name, false, render)
+    }
+
+    /// The file at `name` under [`SYSFS_DIR`]
+    pub const fn sysfs(name: &'static str, render: RenderFn) -> Self {
+        Self::new(SYSFS_DIR, name, false, render)
+    }
+
+    /// Every file under `dir`; `render` gets the full path and fails with
+    /// `ENOENT` for names it does not serve
+    pub const fn dir(dir: &'static str, render: RenderFn) -> Self {
+        Self::new("", dir, true, render)
+    }
+
+    /// Every file under `dir` under [`PROC_DIR`], as for [`ProcFile::dir`]
+    pub const fn proc_dir(dir: &'static str, render: RenderFn) -> Self {
+        Self::new(PROC_DIR, dir, true, render)
+    }
+
+    /// Every file under `dir` under [`SYSFS_DIR`], as for [`ProcFile::dir`]
+    pub const fn sysfs_dir(dir: &'static str, render: RenderFn) -> Self {
+        Self::new(SYSFS_DIR, dir, true, render)
+    }
+
+    /// Accept writes through `write`
+    pub const fn writable(mut self, write: WriteFn) -> Self {
+        self.write = Some(write);
+        self
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let path = match self.mount {
+            "" => Some(path),
+            mount => path.strip_prefix(mount).and_then(|rest| rest.strip_prefix('/')),
+        };
+        let Some(path) = path else {
+            return false;
+        };
+        if self.dir {
+            path.strip_prefix(self.path)
+                .is_some_and(|rest| rest.len() > 1 && rest.starts_with('/'))
+        } else {
+            path == self.path
+        }
+    }
+}
+
+/// A report rendered by `render`
+fn rendered(render: impl FnOnce(&mut String) -> fmt::Result) -> Result<String, SyscallError> {
+    let mut out = String::new();
+    render(&mut out).map_err(|_| SyscallError::EIO)?;
+    Ok(out)
+}
+
+/// `/proc/cmdline` and `/proc/sys/kernel/cmdline/<name>`
+fn cmdline_file(path: &str) -> Result<String, SyscallError> {
+    let name = path
+        .strip_prefix(PROC_DIR)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .ok_or(SyscallError::ENOENT)?;
+    let mut out = String::new();
+    cmdline::proc_read(name, &mut out).map_err(|e| match e {
+        CmdlineError::Unknown => SyscallError::ENOENT,
+        _ => SyscallError::EIO,
+    })?;
+    Ok(out)
+}
+
+/// `/sys/devices/system/cpu/vulnerabilities/<name>`
+fn vulnerability(path: &str) -> Result<String, SyscallError> {
+    let vulnerability = path
+        .rsplit_once('/')
+        .filter(|(dir, _)| dir.ends_with(speculation::SYSFS_DIR))
+        .and_then(|(_, name)| Vulnerability::from_sysfs_name(name))
+        .ok_or(SyscallError::ENOENT)?;
+    rendered(|out| speculation::render_sysfs(vulnerability, out))
+}
+
+/// Raise the lockdown level; `EPERM` for a level below the current one
+fn write_lockdown(_path: &str, data: &[u8]) -> Result<usize, SyscallError> {
+    let value = core::str::from_utf8(data).map_err(|_| SyscallError::EINVAL)?;
+    let requested = LockdownLevel::parse(Some(value.trim())).ok_or(SyscallError::EINVAL)?;
+    if lockdown::write_sysfs(value) != Some(requested) {
+        return Err(SyscallError::EPERM);
+    }
+    Ok(data.len())
+}
+
+/// Every kernel file
+static FILES: &[ProcFile] = &[
+    ProcFile::file(METRICS_PATH, |_| Ok(metrics::render())),
+    ProcFile::file(BOOT_HISTORY_PATH, |_| Ok(boot_history::render())),
+    ProcFile::file(CACHES_PATH, |_| Ok(caches::render())),
+    ProcFile::file(STAT_PATH, |_| Ok(stat::render())),
+    ProcFile::proc(kworker::PROC_PATH, |_| rendered(|out| kworkers().render_proc(out))),
+    ProcFile::proc(trace::PROC_PATH, |_| rendered(|out| framework().trace().render_proc(out))),
+    ProcFile::proc(accounting::PROC_PATH, |_| rendered(|out| accounting().render_proc(out))),
+    ProcFile::proc("cmdline", cmdline_file),
+    ProcFile::proc_dir(cmdline_proc::PARAM_DIR, cmdline_file),
+    ProcFile::proc(ai_status::PROC_PATH, |_| {
+        rendered(|out| ai_status::render_proc(helix_ai::status(), out))
+    }),
+    #[cfg(target_arch = "x86_64")]
+    ProcFile::proc(clocksource::PROC_PATH, |_| rendered(|out| clocksource::render_proc(out))),
+    ProcFile::dir(BACKLIGHT_DIR, backlight::render_file).writable(backlight::write_file),
+    ProcFile::dir(POWER_SUPPLY_DIR, power_supply::render_file),
+    ProcFile::dir(DMI_DIR, dmi::render_file),
+    ProcFile::sysfs_dir(speculation::SYSFS_DIR, vulnerability),
+    ProcFile::sysfs(lockdown::SYSFS_PATH, |_| rendered(|out| lockdown::render_sysfs(out)))
+        .writable(write_lockdown),
+];
+
+/// The table entry serving `path`
+pub fn find(path: &str) -> Option<&'static ProcFile> {
+    FILES.iter().find(|file| file.matches(path))
+}
+
+/// An open kernel file
+struct OpenFile {
+    file: &'static ProcFile,
+    path: String,
+    snapshot: Vec<u8>,
+    offset: usize,
+}
+
+/// Open kernel files, indexed by `fd - PROC_FD_BASE`
+static OPEN: Mutex<Vec<Option<OpenFile>>> = Mutex::new(Vec::new());
+
+/// Slot of a user descriptor, if it is a kernel file descriptor
+pub(crate) fn proc_fd(fd: i32) -> Option<usize> {
+    (PROC_FD_BASE..URING_FD_BASE)
+        .contains(&fd)
+        .then(|| (fd - PROC_FD_BASE) as usize)
+}
+
+/// Snapshot `file` at `path`; returns the new descriptor
+pub(crate) fn open(file: &'static ProcFile, path: &str) -> Result<i32, SyscallError> {
+    let snapshot = (file.render)(path)?.into_bytes();
+
+    let mut open = OPEN.lock();
+    let slot = match open.iter().position(Option::is_none) {
+        Some(slot) => slot,
+        None => {
+            open.push(None);
+            open.len() - 1
+        },
+    };
+    if slot as i32 >= URING_FD_BASE - PROC_FD_BASE {
+        return Err(SyscallError::EMFILE);
+    }
+    open[slot] = Some(OpenFile {
+        file,
+        path: String::from(path),
+        snapshot,
+        offset: 0,
+    });
+    Ok(PROC_FD_BASE + slot as i32)
+}
+
+/// Copy the snapshot from the descriptor's offset; 0 at end of file
+pub(crate) fn read(slot: usize, buf: &mut [u8]) -> Result<usize, SyscallError> {
+    let mut open = OPEN.lock();
+    let file = open
+        .get_mut(slot)
+        .and_then(Option::as_mut)
+        .ok_or(SyscallError::EBADF)?;
+    let len = buf.len().min(file.snapshot.len() - file.offset);
+    buf[..len].copy_from_slice(&file.snapshot[file.offset..file.offset + len]);
+    file.offset += len;
+    Ok(len)
+}
+
+/// Pass `data` to the file's write function; `EBADF` if it is read-only
+pub(crate) fn write(slot: usize, data: &[u8]) -> Result<usize, SyscallError> {
+    let open = OPEN.lock();
+    let file = open
+        .get(slot)
+        .and_then(Option::as_ref)
+        .ok_or(SyscallError::EBADF)?;
+    let write = file.file.write.ok_or(SyscallError::EBADF)?;
+    write(&file.path, data)
+}
+
+/// Free the snapshot
+pub(crate) fn close(slot: usize) -> Result<(), SyscallError> {
+    let mut open = OPEN.lock();
+    open.get_mut(slot)
+        .and_then(Option::take)
+        .map(drop)
+        .ok_or(SyscallError::EBADF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let path = |path| find(path).map(|file| file.path);
+        assert_eq!(path(STAT_PATH), Some(STAT_PATH));
+        assert_eq!(path("/proc/stat/cpu"), None);
+        assert_eq!(path("/proc/workqueues"), Some(kworker::PROC_PATH));
+        assert_eq!(path("/procworkqueues"), None);
+        assert_eq!(path("workqueues"), None);
+        assert_eq!(path("/proc/sched_stats"), Some(trace::PROC_PATH));
+        assert_eq!(path("/proc/module_memory"), Some(accounting::PROC_PATH));
+        assert_eq!(path("/proc/sys/kernel/ai_degraded"), Some(ai_status::PROC_PATH));
+        assert_eq!(path("/proc/cmdline"), Some("cmdline"));
+        assert_eq!(path("/proc/sys/kernel/cmdline/quiet"), Some(cmdline_proc::PARAM_DIR));
+        assert_eq!(path("/proc/sys/kernel/cmdline"), None);
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(path("/proc/clocksource"), Some(clocksource::PROC_PATH));
+        assert_eq!(path("/sys/class/dmi/id/board_name"), Some(DMI_DIR));
+        assert_eq!(path("/sys/class/dmi/id"), None);
+        assert_eq!(path("/sys/class/dmi/id/"), None);
+        assert_eq!(path("/sys/class/dmi/identity"), None);
+    }
+
+    #[test]
+    fn test_cmdline() {
+        cmdline::init("quiet root=/dev/vda").unwrap();
+        let render = |path: &str| (find(path).unwrap().render)(path);
+
+        assert_eq!(render("/proc/cmdline"), Ok(String::from("quiet root=/dev/vda\n")));
+        assert_eq!(render("/proc/sys/kernel/cmdline/quiet"), Ok(String::from("1\n")));
+        assert_eq!(render("/proc/sys/kernel/cmdline/loglevel"), Ok(String::from("4\n")));
+        assert_eq!(render("/proc/sys/kernel/cmdline/nosuch"), Err(SyscallError::ENOENT));
+    }
+
+    #[test]
+    fn test_lockdown() {
+        let path = "/sys/kernel/security/lockdown";
+        let file = find(path).unwrap();
+        assert!((file.render)(path).unwrap().contains('['));
+
+        let write = file.write.unwrap();
+        assert_eq!(write(path, b"on"), Err(SyscallError::EINVAL));
+        assert_eq!(write(path, &[0xff]), Err(SyscallError::EINVAL));
+        assert_eq!(write(path, b"none\n"), Ok(5));
+    }
+
+    #[test]
+    fn test_vulnerabilities() {
+        let dir = "/sys/devices/system/cpu/vulnerabilities";
+        let render = |name: &str| {
+            let path = alloc::format!("{}/{}", dir, name);
+            (find(&path).unwrap().render)(&path)
+        };
+        assert_eq!(
+            render("meltdown"),
+            Ok(alloc::format!("{}\n", speculation::mitigations().state(Vulnerability::Meltdown)))
+        );
+        assert_eq!(render("rowhammer"), Err(SyscallError::ENOENT));
+        assert_eq!(render("x/meltdown"), Err(SyscallError::ENOENT));
+        assert!(find(dir).is_none());
+    }
+
+    #[test]
+    fn test_descriptors() {
+        assert_eq!(proc_fd(PROC_FD_BASE + 2), Some(2));
+        assert_eq!(proc_fd(URING_FD_BASE), None);
+        assert_eq!(proc_fd(3), None);
+
+        let file = find(BOOT_HISTORY_PATH).unwrap();
+        let slot = proc_fd(open(file, BOOT_HISTORY_PATH).unwrap()).unwrap();
+        let mut buf = [0u8; 4];
+        assert_eq!(read(slot, &mut buf), Ok(4));
+        assert_eq!(write(slot, b"1"), Err(SyscallError::EBADF));
+        assert_eq!(close(slot), Ok(()));
+        assert_eq!(read(slot, &mut buf), Err(SyscallError::EBADF));
+        assert_eq!(close(slot), Err(SyscallError::EBADF));
+    }
+}
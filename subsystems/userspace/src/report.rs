@@ -0,0 +1,370 @@
+//! # Bug Reports
+//!
+//! `helix report` gathers everything needed to reproduce an issue into one
+//! archive: kernel version, loaded modules with versions and hashes, recent
+//! events and AI decisions, metrics, and whatever the kernel registered
+//! with [`register_section`] (logs, memory and scheduler statistics, boot
+//! measurements).
+//!
+//! The archive is a tar of `helix-report/<section>.txt` files compressed as
+//! an LZ4 frame. It is written to the VFS through the registered file
+//! writer, or sent over serial as base64 between marker lines:
+//!
+//! ```text
+//! sed -n '/BEGIN HELIX REPORT/,/END HELIX REPORT/p' console.log \
+//!     | sed '1d;$d' | base64 -d | lz4 -d | tar x
+//! ```
+//!
+//! With redaction, IPv4 and MAC addresses and the values of sensitive
+//! `key=value` pairs (user, host, password, token, ...) are masked.
+
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::sync::atomic::Ordering;
+use helix_events::{Event, EventResult, Filter, Subscription, Topic};
+use spin::{Mutex, RwLock};
+
+use super::archive::{base64, lz4_frame, Tar};
+use super::syscalls::SyscallError;
+use super::{control, metrics, BUILD_INFO, STATS, VERSION};
+
+/// Where `helix report` writes by default
+pub const DEFAULT_PATH: &str = "/var/log/helix-report.tar.lz4";
+
+/// Events kept for the report
+pub const HISTORY_CAPACITY: usize = 512;
+
+/// Architecture the kernel was built for
+const ARCH: &str = if cfg!(target_arch = "x86_64") {
+    "x86_64"
+} else if cfg!(target_arch = "aarch64") {
+    "aarch64"
+} else if cfg!(target_arch = "riscv64") {
+    "riscv64"
+} else {
+    "unknown"
+};
+
+/// Directory of the sections inside the archive
+const ARCHIVE_DIR: &str = "helix-report";
+
+/// Marker lines around a report sent over serial
+const SERIAL_BEGIN: &str = "-----BEGIN HELIX REPORT-----";
+const SERIAL_END: &str = "-----END HELIX REPORT-----";
+
+/// Keys whose values are masked when redacting
+const SENSITIVE_KEYS: &[&str] = &[
+    "user", "username", "host", "hostname", "password", "passwd", "token", "secret", "key",
+    "serial", "uuid", "ip", "mac", "home",
+];
+
+/// Writes a report section as text
+pub type SectionFn = fn(&mut String);
+
+/// Writes `data` to the file at `path`
+pub type FileWriter = fn(path: &str, data: &[u8]) -> Result<(), SyscallError>;
+
+/// Writes `data` to the serial console
+pub type SerialWriter = fn(data: &[u8]);
+
+/// Report options
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReportOptions {
+    /// Mask addresses and sensitive values
+    pub redact: bool,
+    /// Archive timestamp (seconds since the epoch), 0 if unknown
+    pub mtime: u64,
+}
+
+/// Sections registered by the kernel, in archive order
+static SECTIONS: RwLock<Vec<(&'static str, SectionFn)>> = RwLock::new(Vec::new());
+
+/// Output channels
+static FILE_WRITER: RwLock<Option<FileWriter>> = RwLock::new(None);
+static SERIAL_WRITER: RwLock<Option<SerialWriter>> = RwLock::new(None);
+
+/// Recent events, fed by a bus subscription
+struct History {
+    subscription: Option<Subscription>,
+    events: VecDeque<Event>,
+}
+
+static HISTORY: Mutex<History> = Mutex::new(History {
+    subscription: None,
+    events: VecDeque::new(),
+});
+
+// =============================================================================
+// Registration
+// =============================================================================
+
+/// Add section `name`, or replace the section of that name
+///
+/// The kernel registers `log`, `memory`, `scheduler` and `boot`.
+pub fn register_section(name: &'static str, collect: SectionFn) {
+    let mut sections = SECTIONS.write();
+    match sections.iter_mut().find(|(n, _)| *n == name) {
+        Some(section) => section.1 = collect,
+        None => sections.push((name, collect)),
+    }
+}
+
+/// Write reports to the VFS with `writer`
+pub fn set_file_writer(writer: FileWriter) {
+    *FILE_WRITER.write() = Some(writer);
+}
+
+/// Send reports over serial with `writer`
+pub fn set_serial_writer(writer: SerialWriter) {
+    *SERIAL_WRITER.write() = Some(writer);
+}
+
+/// Start recording the events included in reports
+pub fn start_history() -> EventResult<()> {
+    let subscription = helix_events::bus().subscribe("report", Filter::all(), HISTORY_CAPACITY)?;
+    HISTORY.lock().subscription = Some(subscription);
+    Ok(())
+}
+
+/// Recorded events, oldest first
+fn history() -> Vec<Event> {
+    let mut history = HISTORY.lock();
+    let History { subscription, events } = &mut *history;
+    if let Some(subscription) = subscription {
+        while let Some(event) = subscription.try_recv() {
+            if events.len() == HISTORY_CAPACITY {
+                events.pop_front();
+            }
+            events.push_back(event);
+        }
+    }
+    events.iter().cloned().collect()
+}
+
+// =============================================================================
+// Collection
+// =============================================================================
+
+fn section_version(out: &mut String) {
+    let _ = writeln!(out, "helix {}", VERSION);
+    let _ = writeln!(out, "build {}", BUILD_INFO);
+    let _ = writeln!(out, "arch {}", ARCH);
+}
+
+fn section_modules(out: &mut String) {
+    match control::modules() {
+        Some(modules) => {
+            for m in modules.list() {
+                let hash = if m.hash.is_empty() { "-" } else { &m.hash };
+                let _ = writeln!(out, "{} {} {} {} {}", m.id, m.name, m.version, m.state, hash);
+            }
+        }
+        None => out.push_str("unavailable\n"),
+    }
+}
+
+fn section_ai(out: &mut String, history: &[Event]) {
+    match control::ai() {
+        Some(ai) => {
+            let _ = writeln!(out, "mode {}", ai.mode().as_str());
+        }
+        None => out.push_str("mode unavailable\n"),
+    }
+    for event in history.iter().filter(|e| e.topic() == Topic::Ai) {
+        let _ = writeln!(out, "{}", event);
+    }
+}
+
+fn section_events(out: &mut String, history: &[Event]) {
+    for event in history {
+        let _ = writeln!(out, "{}", event);
+    }
+}
+
+fn section_userspace(out: &mut String) {
+    let _ = writeln!(out, "programs_loaded {}", STATS.programs_loaded.load(Ordering::Relaxed));
+    let _ = writeln!(out, "processes_spawned {}", STATS.processes_spawned.load(Ordering::Relaxed));
+    let _ = writeln!(out, "syscalls_made {}", STATS.syscalls_made.load(Ordering::Relaxed));
+    let _ = writeln!(out, "commands_executed {}", STATS.commands_executed.load(Ordering::Relaxed));
+}
+
+/// Collect every section: `(name, text)`
+pub fn collect(options: &ReportOptions) -> Vec<(&'static str, String)> {
+    let history = history();
+    let mut sections = Vec::new();
+    let mut add = |name, text: String| {
+        let text = if options.redact { redact(&text) } else { text };
+        sections.push((name, text));
+    };
+
+    let mut text = String::new();
+    section_version(&mut text);
+    add("version", text);
+
+    let mut text = String::new();
+    section_modules(&mut text);
+    add("modules", text);
+
+    let mut text = String::new();
+    section_ai(&mut text, &history);
+    add("ai", text);
+
+    let mut text = String::new();
+    section_events(&mut text, &history);
+    add("events", text);
+
+    add("metrics", metrics::render());
+
+    let mut text = String::new();
+    section_userspace(&mut text);
+    add("userspace", text);
+
+    for &(name, collect) in SECTIONS.read().iter() {
+        let mut text = String::new();
+        collect(&mut text);
+        add(name, text);
+    }
+    sections
+}
+
+/// Build the compressed report archive
+pub fn archive(options: &ReportOptions) -> Vec<u8> {
+    let sections = collect(options);
+
+    let mut manifest = String::new();
+    let _ = writeln!(manifest, "helix {}", VERSION);
+    let _ = writeln!(manifest, "redacted {}", options.redact);
+    for (name, text) in &sections {
+        let _ = writeln!(manifest, "{}.txt {}", name, text.len());
+    }
+
+    let mut tar = Tar::new();
+    let mut path = String::new();
+    let _ = write!(path, "{}/MANIFEST", ARCHIVE_DIR);
+    tar.add_file(&path, manifest.as_bytes(), options.mtime);
+    for (name, text) in &sections {
+        path.clear();
+        let _ = write!(path, "{}/{}.txt", ARCHIVE_DIR, name);
+        tar.add_file(&path, text.as_bytes(), options.mtime);
+    }
+    lz4_frame(&tar.finish())
+}
+
+/// Write the report to `path`; returns its size
+pub fn write_file(path: &str, options: &ReportOptions) -> Result<usize, SyscallError> {
+    let writer = FILE_WRITER.read().ok_or(SyscallError::ENOSYS)?;
+    let data = archive(options);
+    writer(path, &data)?;
+    Ok(data.len())
+}
+
+/// Send the report over serial; returns the archive size
+pub fn send_serial(options: &ReportOptions) -> Result<usize, SyscallError> {
+    let writer = SERIAL_WRITER.read().ok_or(SyscallError::ENOSYS)?;
+    let data = archive(options);
+    writer(SERIAL_BEGIN.as_bytes());
+    writer(b"\n");
+    writer(base64(&data).as_bytes());
+    writer(b"\n");
+    writer(SERIAL_END.as_bytes());
+    writer(b"\n");
+    Ok(data.len())
+}
+
+// =============================================================================
+// Redaction
+// =============================================================================
+
+/// Mask addresses and sensitive values in `text`
+pub fn redact(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        for (j, word) in line.split(' ').enumerate() {
+            if j > 0 {
+                out.push(' ');
+            }
+            redact_word(word, &mut out);
+        }
+    }
+    out
+}
+
+fn redact_word(word: &str, out: &mut String) {
+    if let Some((key, _)) = word.split_once('=') {
+        if SENSITIVE_KEYS.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+            out.push_str(key);
+            out.push_str("=[redacted]");
+            return;
+        }
+    }
+
+    let core = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    let host = core.split(':').next().unwrap_or(core);
+    let (target, replacement) = if is_mac(core) {
+        (core, "[mac]")
+    } else if is_ipv4(host) {
+        (host, "[ip]")
+    } else {
+        out.push_str(word);
+        return;
+    };
+    let start = word.find(target).unwrap_or(0);
+    out.push_str(&word[..start]);
+    out.push_str(replacement);
+    out.push_str(&word[start + target.len()..]);
+}
+
+fn is_ipv4(s: &str) -> bool {
+    let mut parts = 0;
+    s.split('.').all(|part| {
+        parts += 1;
+        !part.is_empty() && part.len() <= 3 && part.parse::<u8>().is_ok()
+    }) && parts == 4
+}
+
+fn is_mac(s: &str) -> bool {
+    let mut parts = 0;
+    s.split(':').all(|part| {
+        parts += 1;
+        part.len() == 2 && part.bytes().all(|b| b.is_ascii_hexdigit())
+    }) && parts == 6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("user=alice dhcp 10.0.2.15:68 via 52:54:00:12:34:56,\nok token=\"x y\""),
+            "user=[redacted] dhcp [ip]:68 via [mac],\nok token=[redacted] y\""
+        );
+        assert_eq!(redact("version 1.2.3.4.5 at 0.1.0"), "version 1.2.3.4.5 at 0.1.0");
+        assert_eq!(redact("peer (192.168.1.1)"), "peer ([ip])");
+    }
+
+    #[test]
+    fn test_report_sections() {
+        fn scheduler(out: &mut String) {
+            out.push_str("runqueue 3 host=build01\n");
+        }
+        register_section("scheduler", scheduler);
+
+        let options = ReportOptions { redact: true, mtime: 0 };
+        let sections = collect(&options);
+        let names: Vec<&str> = sections.iter().map(|(name, _)| *name).collect();
+        assert_eq!(&names[..6], ["version", "modules", "ai", "events", "metrics", "userspace"]);
+        let (_, text) = sections.iter().find(|(name, _)| *name == "scheduler").unwrap();
+        assert_eq!(text, "runqueue 3 host=[redacted]\n");
+
+        let data = archive(&options);
+        assert_eq!(&data[..4], &0x184D_2204u32.to_le_bytes());
+        assert_eq!(write_file(DEFAULT_PATH, &options), Err(SyscallError::ENOSYS));
+    }
+}
@@ -12,7 +12,7 @@
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::sync::atomic::{AtomicU64, AtomicBool, Ordering};
+use core::sync::atomic::{AtomicI32, AtomicU64, AtomicBool, Ordering};
 use spin::{Mutex, RwLock};
 
 use super::{UserResult, UserError, STATS};
@@ -35,6 +35,24 @@
 /// Maximum processes
 pub const MAX_PROCESSES: usize = 1024;
 
+/// Signal numbers
+pub mod signal {
+    /// Interrupt from keyboard (^C)
+    pub const SIGINT: i32 = 2;
+    /// Quit from keyboard (^\)
+    pub const SIGQUIT: i32 = 3;
+    /// Kill
+    pub const SIGKILL: i32 = 9;
+    /// Termination
+    pub const SIGTERM: i32 = 15;
+    /// Continue if stopped
+    pub const SIGCONT: i32 = 18;
+    /// Stop
+    pub const SIGSTOP: i32 = 19;
+    /// Stop from keyboard (^Z)
+    pub const SIGTSTP: i32 = 20;
+}
+
 /// Process state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcessState {
@@ -54,6 +72,32 @@ pub enum ProcessState {
     Dead,
 }
 
+/// How a process finished (or paused)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Exited with a code
+    Exited(i32),
+    /// Terminated by a signal
+    Signaled(i32),
+    /// Stopped by a signal
+    Stopped(i32),
+}
+
+impl ExitStatus {
+    /// Shell `$?` value (128 + signal for signals)
+    pub fn code(self) -> i32 {
+        match self {
+            ExitStatus::Exited(code) => code,
+            ExitStatus::Signaled(sig) | ExitStatus::Stopped(sig) => 128 + sig,
+        }
+    }
+
+    /// Exited with code 0?
+    pub fn success(self) -> bool {
+        self == ExitStatus::Exited(0)
+    }
+}
+
 /// Process priority
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Priority {
@@ -204,6 +248,12 @@ pub struct ProcessHandle {
     pub ppid: Pid,
     /// Process name
     pub name: String,
+    /// Process group ID
+    pgid: AtomicU64,
+    /// Argument vector
+    pub argv: Vec<String>,
+    /// Environment (`NAME=VALUE`)
+    pub envp: Vec<String>,
     /// State
     state: Mutex<ProcessState>,
     /// Priority
@@ -212,6 +262,8 @@ pub struct ProcessHandle {
     fd_table: Mutex<FdTable>,
     /// Exit code (if terminated)
     exit_code: Mutex<Option<i32>>,
+    /// Signal that terminated or stopped the process (0 = none)
+    last_signal: AtomicI32,
     /// Entry point
     pub entry_point: u64,
     /// Stack pointer
@@ -231,10 +283,14 @@ pub fn new(pid: Pid, ppid: Pid, name: impl Into<String>) -> Self {
             pid,
             ppid,
             name: name.into(),
+            pgid: AtomicU64::new(pid),
+            argv: Vec::new(),
+            envp: Vec::new(),
             state: Mutex::new(ProcessState::Creating),
             priority: Priority::Normal,
             fd_table: Mutex::new(FdTable::new()),
             exit_code: Mutex::new(None),
+            last_signal: AtomicI32::new(0),
             entry_point: 0,
             stack_ptr: 0,
             heap_base: 0,
@@ -268,6 +324,51 @@ pub fn exit(&self, code: i32) {
         self.set_state(ProcessState::Zombie);
     }
     
+    /// Process group ID
+    pub fn pgid(&self) -> Pid {
+        self.pgid.load(Ordering::Acquire)
+    }
+    
+    /// Move to process group `pgid`
+    pub fn set_pgid(&self, pgid: Pid) {
+        self.pgid.store(pgid, Ordering::Release);
+    }
+    
+    /// Deliver a signal with its default action
+    ///
+    /// No user handlers yet: stop signals stop, `SIGCONT` resumes,
+    /// `SIGINT`/`SIGQUIT`/`SIGKILL`/`SIGTERM` terminate, others are ignored.
+    pub fn signal(&self, sig: i32) {
+        let state = self.state();
+        if matches!(state, ProcessState::Zombie | ProcessState::Dead) {
+            return;
+        }
+        
+        match sig {
+            signal::SIGSTOP | signal::SIGTSTP => {
+                self.last_signal.store(sig, Ordering::Release);
+                self.set_state(ProcessState::Stopped);
+            }
+            signal::SIGCONT if state == ProcessState::Stopped => self.ready(),
+            signal::SIGINT | signal::SIGQUIT | signal::SIGKILL | signal::SIGTERM => {
+                self.last_signal.store(sig, Ordering::Release);
+                self.exit(128 + sig);
+            }
+            _ => {}
+        }
+    }
+    
+    /// Exit or stop status, if the process is no longer runnable
+    pub fn status(&self) -> Option<ExitStatus> {
+        let sig = self.last_signal.load(Ordering::Acquire);
+        match self.state() {
+            ProcessState::Stopped => Some(ExitStatus::Stopped(sig)),
+            ProcessState::Zombie | ProcessState::Dead if sig != 0 => Some(ExitStatus::Signaled(sig)),
+            ProcessState::Zombie | ProcessState::Dead => self.exit_code.lock().map(ExitStatus::Exited),
+            _ => None,
+        }
+    }
+    
     /// Reap the process
     pub fn reap(&self) -> Option<i32> {
         let code = *self.exit_code.lock();
@@ -373,6 +474,26 @@ pub fn spawn(&self, elf: &ParsedElf, name: &str) -> UserResult<Arc<ProcessHandle
         Ok(handle)
     }
     
+    /// Create a new process from ELF with its argument and environment vectors
+    ///
+    /// The process is named after `argv[0]`.
+    pub fn exec(&self, elf: &ParsedElf, argv: Vec<String>, envp: Vec<String>) -> UserResult<Arc<ProcessHandle>> {
+        let name = argv.first().ok_or(UserError::InvalidArgument)?;
+        let pid = self.next_pid.fetch_add(1, Ordering::SeqCst);
+        
+        let mut process = ProcessHandle::new(pid, 0, name.rsplit('/').next().unwrap_or(name));
+        process.entry_point = elf.entry_point;
+        process.argv = argv;
+        process.envp = envp;
+        
+        let handle = Arc::new(process);
+        self.processes.write().insert(pid, handle.clone());
+        
+        handle.ready();
+        
+        Ok(handle)
+    }
+    
     /// Spawn a simple process (without ELF)
     pub fn spawn_simple(&self, name: &str, entry: u64) -> UserResult<Arc<ProcessHandle>> {
         let pid = self.next_pid.fetch_add(1, Ordering::SeqCst);
@@ -401,15 +522,26 @@ pub fn list_processes(&self) -> Vec<Arc<ProcessHandle>> {
     /// Kill a process
     pub fn kill(&self, pid: Pid, signal: i32) -> UserResult<()> {
         if let Some(process) = self.get_process(pid) {
-            if signal == 9 || signal == 15 {
-                process.exit(128 + signal);
-            }
+            process.signal(signal);
             Ok(())
         } else {
             Err(UserError::InvalidArgument)
         }
     }
     
+    /// Signal every process in group `pgid`; returns how many were signalled
+    pub fn kill_group(&self, pgid: Pid, signal: i32) -> usize {
+        let members: Vec<_> = self.processes.read()
+            .values()
+            .filter(|p| p.pgid() == pgid)
+            .cloned()
+            .collect();
+        for process in &members {
+            process.signal(signal);
+        }
+        members.len()
+    }
+    
     /// Reap zombie processes
     pub fn reap_zombies(&self) {
         let mut processes = self.processes.write();
@@ -470,4 +602,21 @@ fn test_process_handle() {
         process.exit(0);
         assert_eq!(process.state(), ProcessState::Zombie);
     }
+
+    #[test]
+    fn test_process_signals() {
+        let process = ProcessHandle::new(7, 0, "test");
+        process.ready();
+        assert_eq!(process.pgid(), 7);
+        assert_eq!(process.status(), None);
+        
+        process.signal(signal::SIGTSTP);
+        assert_eq!(process.status(), Some(ExitStatus::Stopped(signal::SIGTSTP)));
+        process.signal(signal::SIGCONT);
+        assert_eq!(process.state(), ProcessState::Ready);
+        
+        process.signal(signal::SIGINT);
+        assert_eq!(process.status(), Some(ExitStatus::Signaled(signal::SIGINT)));
+        assert_eq!(process.status().unwrap().code(), 130);
+    }
 }
@@ -0,0 +1,305 @@
+//! # Resource Usage
+//!
+//! `getrusage`, `times` and `wait4` over the execution layer's per-process
+//! resource accounting:
+//!
+//! ```text
+//! getrusage(who, usage)
+//! times(buf)
+//! wait4(pid, wstatus, options, rusage)
+//! ```
+//!
+//! Usage is reported in Linux's `struct rusage` and `struct tms` layouts.
+//! `RUSAGE_THREAD` covers CPU time and context switches only; faults,
+//! peak RSS and block I/O are accounted per process.
+//!
+//! `wait4` reaps a zombie child, adding its usage to the caller's children
+//! usage. Blocking waits are not supported yet: without `WNOHANG`, a wait
+//! with no zombie child fails with `EAGAIN`.
+
+use helix_execution::process::{registry, Process, Rusage};
+use helix_execution::scheduler::cputime::USER_HZ;
+use helix_execution::scheduler::framework;
+use helix_execution::{ProcessId, ThreadId};
+use helix_hal::uaccess::{self, UserCopy};
+
+use super::syscalls::{SyscallArgs, SyscallError, SyscallResult};
+
+/// `getrusage` targets
+pub mod who {
+    /// The calling process
+    pub const SELF: i32 = 0;
+    /// Reaped children of the calling process
+    pub const CHILDREN: i32 = -1;
+    /// The calling thread
+    pub const THREAD: i32 = 1;
+}
+
+/// `wait4` option: return 0 instead of waiting
+pub const WNOHANG: u32 = 1;
+
+/// `struct timeval`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timeval {
+    /// Seconds
+    pub tv_sec: i64,
+    /// Microseconds
+    pub tv_usec: i64,
+}
+
+impl Timeval {
+    /// Split nanoseconds into seconds and microseconds
+    pub fn from_nanos(ns: u64) -> Self {
+        Self {
+            tv_sec: (ns / 1_000_000_000) as i64,
+            tv_usec: (ns % 1_000_000_000 / 1_000) as i64,
+        }
+    }
+}
+
+/// `struct rusage`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UserRusage {
+    /// User time
+    pub ru_utime: Timeval,
+    /// System time
+    pub ru_stime: Timeval,
+    /// Peak resident set size (KiB)
+    pub ru_maxrss: i64,
+    /// Unused
+    pub ru_ixrss: i64,
+    /// Unused
+    pub ru_idrss: i64,
+    /// Unused
+    pub ru_isrss: i64,
+    /// Page faults without I/O
+    pub ru_minflt: i64,
+    /// Page faults with I/O
+    pub ru_majflt: i64,
+    /// Unused
+    pub ru_nswap: i64,
+    /// Blocks read
+    pub ru_inblock: i64,
+    /// Blocks written
+    pub ru_oublock: i64,
+    /// Unused
+    pub ru_msgsnd: i64,
+    /// Unused
+    pub ru_msgrcv: i64,
+    /// Unused
+    pub ru_nsignals: i64,
+    /// Voluntary context switches
+    pub ru_nvcsw: i64,
+    /// Involuntary context switches
+    pub ru_nivcsw: i64,
+}
+
+// SAFETY: `repr(C)` integers without padding
+unsafe impl UserCopy for UserRusage {}
+
+impl From<&Rusage> for UserRusage {
+    fn from(usage: &Rusage) -> Self {
+        Self {
+            ru_utime: Timeval::from_nanos(usage.utime),
+            ru_stime: Timeval::from_nanos(usage.stime),
+            ru_maxrss: usage.maxrss_kb as i64,
+            ru_minflt: usage.minflt as i64,
+            ru_majflt: usage.majflt as i64,
+            ru_inblock: usage.inblock as i64,
+            ru_oublock: usage.oublock as i64,
+            ru_nvcsw: usage.nvcsw as i64,
+            ru_nivcsw: usage.nivcsw as i64,
+            ..Self::default()
+        }
+    }
+}
+
+/// `struct tms`, in [`USER_HZ`] ticks
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tms {
+    /// User time
+    pub tms_utime: i64,
+    /// System time
+    pub tms_stime: i64,
+    /// User time of reaped children
+    pub tms_cutime: i64,
+    /// System time of reaped children
+    pub tms_cstime: i64,
+}
+
+// SAFETY: `repr(C)` integers without padding
+unsafe impl UserCopy for Tms {}
+
+/// Nanoseconds to [`USER_HZ`] ticks
+fn ticks(ns: u64) -> i64 {
+    (ns / (1_000_000_000 / USER_HZ)) as i64
+}
+
+/// Process of the calling thread `tid`
+fn process_of(tid: ThreadId) -> Result<alloc::sync::Arc<Process>, SyscallError> {
+    let process = framework().cputime().process_of(tid).ok_or(SyscallError::ESRCH)?;
+    registry().get(process).ok_or(SyscallError::ESRCH)
+}
+
+/// Resource usage of `who` for the calling thread `tid`
+pub fn usage_of(who: i32, tid: ThreadId) -> Result<Rusage, SyscallError> {
+    match who {
+        who::SELF => Ok(process_of(tid)?.usage()),
+        who::CHILDREN => Ok(process_of(tid)?.children_usage()),
+        who::THREAD => Rusage::of_thread(tid).ok_or(SyscallError::ESRCH),
+        _ => Err(SyscallError::EINVAL),
+    }
+}
+
+/// Reap a zombie child of `parent`: `pid` > 0 waits for that child, -1 for
+/// any child
+///
+/// Returns `None` when no child has exited yet.
+pub fn reap(parent: &Process, pid: i64) -> Result<Option<(ProcessId, i32, Rusage)>, SyscallError> {
+    let children = parent.children();
+    let candidates: alloc::vec::Vec<ProcessId> = match pid {
+        -1 => children,
+        pid if pid > 0 => children.into_iter().filter(|c| c.as_u64() == pid as u64).collect(),
+        // Process groups are not supported
+        _ => return Err(SyscallError::EINVAL),
+    };
+    if candidates.is_empty() {
+        return Err(SyscallError::ECHILD);
+    }
+
+    for id in candidates {
+        let Some(child) = registry().get(id) else {
+            continue;
+        };
+        if let Ok((code, usage)) = parent.reap_child(&child) {
+            let _ = registry().unregister(id);
+            return Ok(Some((id, code, usage)));
+        }
+    }
+    Ok(None)
+}
+
+fn current_thread(args: SyscallArgs) -> Result<ThreadId, SyscallError> {
+    super::syscalls::sys_getpid(args).map(ThreadId::from_raw)
+}
+
+/// `getrusage(who, usage)`
+pub fn sys_getrusage(args: SyscallArgs) -> SyscallResult {
+    let out = args.arg2;
+    if out == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    let usage = usage_of(args.arg1 as i32, current_thread(args)?)?;
+    uaccess::put_user(out, &UserRusage::from(&usage))?;
+    Ok(0)
+}
+
+/// `times(buf)`; returns the ticks since boot, `buf` may be null
+pub fn sys_times(args: SyscallArgs) -> SyscallResult {
+    let process = process_of(current_thread(args)?)?;
+    let (usage, children) = (process.usage(), process.children_usage());
+    let buf = args.arg1;
+    if buf != 0 {
+        let tms = Tms {
+            tms_utime: ticks(usage.utime),
+            tms_stime: ticks(usage.stime),
+            tms_cutime: ticks(children.utime),
+            tms_cstime: ticks(children.stime),
+        };
+        uaccess::put_user(buf, &tms)?;
+    }
+    Ok(ticks(framework().cputime().now().unwrap_or(0)) as u64)
+}
+
+/// `wait4(pid, wstatus, options, rusage)`; `wstatus` and `rusage` may be
+/// null
+pub fn sys_wait4(args: SyscallArgs) -> SyscallResult {
+    let parent = process_of(current_thread(args)?)?;
+    let Some((pid, code, usage)) = reap(&parent, args.arg1 as i64)? else {
+        return if args.arg3 as u32 & WNOHANG != 0 { Ok(0) } else { Err(SyscallError::EAGAIN) };
+    };
+
+    let status = args.arg2;
+    if status != 0 {
+        uaccess::put_user(status, &((code & 0xFF) << 8))?;
+    }
+    let out = args.arg4;
+    if out != 0 {
+        uaccess::put_user(out, &UserRusage::from(&usage))?;
+    }
+    Ok(pid.as_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn test_wait4_reports_child_usage() {
+        let parent = Arc::new(Process::new(ProcessId::new(), None, "sh"));
+        let child = Arc::new(Process::new(ProcessId::new(), Some(parent.id()), "make"));
+        parent.add_child(child.id());
+        registry().register(child.clone()).unwrap();
+
+        child.record_rss(2048);
+        child.record_rss(1024);
+        child.record_page_fault(false);
+        child.record_page_fault(true);
+        child.record_block_io(8, 3);
+        assert_eq!(reap(&parent, -1), Ok(None));
+        assert_eq!(reap(&parent, 999_999), Err(SyscallError::ECHILD));
+
+        child.exit(3);
+        let (pid, code, usage) = reap(&parent, child.id().as_u64() as i64).unwrap().unwrap();
+        assert_eq!((pid, code), (child.id(), 3));
+        assert_eq!((usage.maxrss_kb, usage.minflt, usage.majflt), (2048, 1, 1));
+        assert_eq!((usage.inblock, usage.oublock), (8, 3));
+        assert_eq!(parent.children_usage(), usage);
+        assert!(registry().get(child.id()).is_none());
+        assert_eq!(reap(&parent, -1), Err(SyscallError::ECHILD));
+    }
+
+    #[test]
+    fn test_thread_context_switches() {
+        let cputime = framework().cputime();
+        cputime.set_clock(|| 0);
+        let thread = ThreadId::from_raw(u64::MAX - 2);
+        cputime.register_thread(thread, ProcessId::new());
+
+        // Preempted once, then blocks; re-picking a woken thread is no switch
+        cputime.switch_to(5, Some(thread));
+        cputime.switch_to(5, None);
+        cputime.switch_to(5, Some(thread));
+        cputime.mark_blocked(thread);
+        cputime.switch_to(5, None);
+        cputime.switch_to(5, Some(thread));
+        cputime.mark_blocked(thread);
+        cputime.switch_to(5, Some(thread));
+        cputime.switch_to(5, None);
+
+        let usage = usage_of(who::THREAD, thread).unwrap();
+        assert_eq!((usage.nvcsw, usage.nivcsw), (1, 2));
+        cputime.exit_thread(thread);
+    }
+
+    #[test]
+    fn test_user_rusage_layout() {
+        assert_eq!(core::mem::size_of::<UserRusage>(), 144);
+        let usage = Rusage {
+            utime: 1_500_000_000,
+            stime: 2_000_500,
+            nvcsw: 4,
+            ..Rusage::default()
+        };
+        let user = UserRusage::from(&usage);
+        assert_eq!(user.ru_utime, Timeval { tv_sec: 1, tv_usec: 500_000 });
+        assert_eq!(user.ru_stime, Timeval { tv_sec: 0, tv_usec: 2_000 });
+        assert_eq!(user.ru_nvcsw, 4);
+        assert_eq!(ticks(usage.utime), 150);
+        assert_eq!(usage_of(7, ThreadId::from_raw(1)), Err(SyscallError::EINVAL));
+    }
+}
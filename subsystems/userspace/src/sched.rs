@@ -0,0 +1,333 @@
+//! # Scheduling Attributes
+//!
+//! `sched_setattr` / `sched_getattr` with Linux's `struct sched_attr`,
+//! extended with a Helix placement class so workloads can ask for the
+//! right core type on hybrid CPUs:
+//!
+//! ```text
+//! sched_setattr(tid, attr, flags)
+//! sched_getattr(tid, attr, size, flags)
+//! ```
+//!
+//! The placement class ([`placement`]) sits after Linux's utilization
+//! clamps. Callers built against Linux pass a smaller `size` and get the
+//! default class; `sched_getattr` fills in as much as `size` allows.
+//!
+//! A tid of 0 means the calling thread.
+
+use helix_execution::scheduler::{
+    framework, PlacementClass, Priority, SchedAttr, SchedulingPolicy,
+};
+use helix_execution::{ExecError, ThreadId};
+use helix_hal::uaccess::{self, UserCopy};
+
+use super::syscalls::{SyscallArgs, SyscallError, SyscallResult};
+
+/// Scheduling policies (Linux numbering)
+pub mod policy {
+    /// Time-sharing
+    pub const NORMAL: u32 = 0;
+    /// Real-time first-in-first-out
+    pub const FIFO: u32 = 1;
+    /// Real-time round-robin
+    pub const RR: u32 = 2;
+    /// Batch
+    pub const BATCH: u32 = 3;
+    /// Idle
+    pub const IDLE: u32 = 5;
+    /// Deadline
+    pub const DEADLINE: u32 = 6;
+}
+
+/// `sched_flags` bits (Linux numbering)
+pub mod flags {
+    /// Keep the current policy
+    pub const KEEP_POLICY: u64 = 0x08;
+    /// Keep the current priority
+    pub const KEEP_PARAMS: u64 = 0x10;
+}
+
+/// Placement classes (`sched_placement`)
+pub mod placement {
+    /// No preference
+    pub const NORMAL: u32 = 0;
+    /// Pack onto efficiency cores
+    pub const BACKGROUND: u32 = 1;
+    /// Run on performance cores
+    pub const LATENCY_SENSITIVE: u32 = 2;
+}
+
+/// Smallest `sched_attr` accepted (Linux `SCHED_ATTR_SIZE_VER0`)
+pub const SCHED_ATTR_SIZE_VER0: u32 = 48;
+
+/// `struct sched_attr`, as laid out in user memory
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchedAttrAbi {
+    /// Size of the structure as known to the caller
+    pub size: u32,
+    /// Policy ([`policy`])
+    pub sched_policy: u32,
+    /// Flags ([`flags`])
+    pub sched_flags: u64,
+    /// Nice value (time-sharing policies)
+    pub sched_nice: i32,
+    /// Real-time priority, 1 (lowest) to 99
+    pub sched_priority: u32,
+    /// Deadline runtime (ns)
+    pub sched_runtime: u64,
+    /// Relative deadline (ns)
+    pub sched_deadline: u64,
+    /// Deadline period (ns)
+    pub sched_period: u64,
+    /// Utilization clamp minimum (ignored)
+    pub sched_util_min: u32,
+    /// Utilization clamp maximum (ignored)
+    pub sched_util_max: u32,
+    /// Helix placement class ([`placement`])
+    pub sched_placement: u32,
+    /// Reserved, must be zero
+    pub reserved: u32,
+}
+
+// SAFETY: `repr(C)` integers without padding
+unsafe impl UserCopy for SchedAttrAbi {}
+
+/// Size of [`SchedAttrAbi`]
+pub const SCHED_ATTR_SIZE: u32 = core::mem::size_of::<SchedAttrAbi>() as u32;
+
+impl SchedAttrAbi {
+    /// Convert to scheduler attributes
+    pub fn to_attr(&self) -> Result<SchedAttr, SyscallError> {
+        let supported = flags::KEEP_POLICY | flags::KEEP_PARAMS;
+        if self.sched_flags & !supported != 0 || self.reserved != 0 {
+            return Err(SyscallError::EINVAL);
+        }
+
+        let (policy, priority) = match self.sched_policy {
+            policy::NORMAL | policy::BATCH | policy::IDLE => {
+                if !(-20..=19).contains(&self.sched_nice) || self.sched_priority != 0 {
+                    return Err(SyscallError::EINVAL);
+                }
+                let policy = match self.sched_policy {
+                    policy::NORMAL => SchedulingPolicy::Normal,
+                    policy::BATCH => SchedulingPolicy::Batch,
+                    _ => SchedulingPolicy::Idle,
+                };
+                let priority = if policy == SchedulingPolicy::Idle {
+                    Priority::IDLE
+                } else {
+                    Priority::normal(self.sched_nice as i8)
+                };
+                (policy, Some(priority))
+            }
+            policy::FIFO | policy::RR => {
+                if !(1..=99).contains(&self.sched_priority) {
+                    return Err(SyscallError::EINVAL);
+                }
+                let policy = if self.sched_policy == policy::FIFO {
+                    SchedulingPolicy::Fifo
+                } else {
+                    SchedulingPolicy::RoundRobin
+                };
+                (policy, Some(Priority::realtime(99 - self.sched_priority as u8)))
+            }
+            policy::DEADLINE => {
+                let period = if self.sched_period == 0 { self.sched_deadline } else { self.sched_period };
+                if self.sched_runtime == 0
+                    || self.sched_runtime > self.sched_deadline
+                    || self.sched_deadline > period
+                {
+                    return Err(SyscallError::EINVAL);
+                }
+                let policy = SchedulingPolicy::Deadline {
+                    runtime: self.sched_runtime,
+                    period,
+                    deadline: self.sched_deadline,
+                };
+                (policy, None)
+            }
+            _ => return Err(SyscallError::EINVAL),
+        };
+
+        let placement = match self.sched_placement {
+            placement::NORMAL => PlacementClass::Normal,
+            placement::BACKGROUND => PlacementClass::Background,
+            placement::LATENCY_SENSITIVE => PlacementClass::LatencySensitive,
+            _ => return Err(SyscallError::EINVAL),
+        };
+
+        Ok(SchedAttr {
+            policy: (self.sched_flags & flags::KEEP_POLICY == 0).then_some(policy),
+            priority: priority.filter(|_| self.sched_flags & flags::KEEP_PARAMS == 0),
+            placement,
+        })
+    }
+
+    /// Convert from scheduler attributes
+    ///
+    /// Without a policy, real-time priorities report `FIFO` and others
+    /// `NORMAL`.
+    pub fn from_attr(attr: &SchedAttr) -> Self {
+        let priority = attr.priority.unwrap_or_default();
+        let mut abi = Self {
+            size: SCHED_ATTR_SIZE,
+            sched_placement: match attr.placement {
+                PlacementClass::Normal => placement::NORMAL,
+                PlacementClass::Background => placement::BACKGROUND,
+                PlacementClass::LatencySensitive => placement::LATENCY_SENSITIVE,
+            },
+            ..Self::default()
+        };
+
+        match attr.policy {
+            Some(SchedulingPolicy::Deadline { runtime, period, deadline }) => {
+                abi.sched_policy = policy::DEADLINE;
+                abi.sched_runtime = runtime;
+                abi.sched_deadline = deadline;
+                abi.sched_period = period;
+                return abi;
+            }
+            Some(SchedulingPolicy::Fifo) => abi.sched_policy = policy::FIFO,
+            Some(SchedulingPolicy::RoundRobin) => abi.sched_policy = policy::RR,
+            Some(SchedulingPolicy::Batch) => abi.sched_policy = policy::BATCH,
+            Some(SchedulingPolicy::Idle) => abi.sched_policy = policy::IDLE,
+            Some(SchedulingPolicy::Normal) => abi.sched_policy = policy::NORMAL,
+            None if priority.is_realtime() => abi.sched_policy = policy::FIFO,
+            None => abi.sched_policy = policy::NORMAL,
+        }
+        if priority.is_realtime() {
+            abi.sched_priority = 99 - priority.static_priority() as u32;
+        } else {
+            abi.sched_nice = priority.nice() as i32;
+        }
+        abi
+    }
+}
+
+fn exec_errno(error: ExecError) -> SyscallError {
+    match error {
+        ExecError::ThreadNotFound => SyscallError::ESRCH,
+        ExecError::PermissionDenied => SyscallError::EPERM,
+        ExecError::InvalidArgument => SyscallError::EINVAL,
+        _ => SyscallError::EIO,
+    }
+}
+
+fn thread_of(tid: u64, args: SyscallArgs) -> Result<ThreadId, SyscallError> {
+    let tid = if tid == 0 { super::syscalls::sys_getpid(args)? } else { tid };
+    Ok(ThreadId::from_raw(tid))
+}
+
+/// `sched_setattr(tid, attr, flags)`
+pub fn sys_sched_setattr(args: SyscallArgs) -> SyscallResult {
+    let attr = args.arg2;
+    if attr == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    if args.arg3 != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+
+    let size = uaccess::get_user::<u32>(attr)?;
+    let size = if size == 0 { SCHED_ATTR_SIZE_VER0 } else { size };
+    if size < SCHED_ATTR_SIZE_VER0 {
+        return Err(SyscallError::EINVAL);
+    }
+    if size > SCHED_ATTR_SIZE {
+        return Err(SyscallError::E2BIG);
+    }
+
+    let mut abi = SchedAttrAbi::default();
+    uaccess::copy_from_user(&mut uaccess::bytes_of_mut(&mut abi)[..size as usize], attr)?;
+
+    let id = thread_of(args.arg1, args)?;
+    framework().set_attr(id, abi.to_attr()?).map_err(exec_errno)?;
+    Ok(0)
+}
+
+/// `sched_getattr(tid, attr, size, flags)`
+pub fn sys_sched_getattr(args: SyscallArgs) -> SyscallResult {
+    let attr = args.arg2;
+    let size = args.arg3 as u32;
+    if attr == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    if size < SCHED_ATTR_SIZE_VER0 || args.arg4 != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+
+    let id = thread_of(args.arg1, args)?;
+    let current = framework().get_attr(id).ok_or(SyscallError::ESRCH)?;
+    let mut abi = SchedAttrAbi::from_attr(&current);
+    abi.size = size.min(SCHED_ATTR_SIZE);
+
+    uaccess::copy_to_user(attr, &uaccess::bytes_of(&abi)[..abi.size as usize])?;
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normal(nice: i32) -> SchedAttrAbi {
+        SchedAttrAbi {
+            size: SCHED_ATTR_SIZE,
+            sched_nice: nice,
+            ..SchedAttrAbi::default()
+        }
+    }
+
+    #[test]
+    fn test_abi_layout() {
+        assert_eq!(SCHED_ATTR_SIZE, 64);
+        assert_eq!(core::mem::offset_of!(SchedAttrAbi, sched_util_max), 52);
+    }
+
+    #[test]
+    fn test_to_attr() {
+        let attr = SchedAttrAbi {
+            sched_placement: placement::BACKGROUND,
+            ..normal(5)
+        }
+        .to_attr()
+        .unwrap();
+        assert_eq!(attr.policy, Some(SchedulingPolicy::Normal));
+        assert_eq!(attr.priority, Some(Priority::normal(5)));
+        assert_eq!(attr.placement, PlacementClass::Background);
+
+        let fifo = SchedAttrAbi {
+            sched_policy: policy::FIFO,
+            sched_priority: 90,
+            ..SchedAttrAbi::default()
+        };
+        assert_eq!(fifo.to_attr().unwrap().priority, Some(Priority::realtime(9)));
+
+        // Keep everything but the placement
+        let keep = SchedAttrAbi {
+            sched_flags: flags::KEEP_POLICY | flags::KEEP_PARAMS,
+            sched_placement: placement::LATENCY_SENSITIVE,
+            ..normal(0)
+        };
+        let attr = keep.to_attr().unwrap();
+        assert_eq!((attr.policy, attr.priority), (None, None));
+
+        assert_eq!(normal(20).to_attr(), Err(SyscallError::EINVAL));
+        let bad = SchedAttrAbi { sched_placement: 7, ..normal(0) };
+        assert_eq!(bad.to_attr(), Err(SyscallError::EINVAL));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let abi = SchedAttrAbi {
+            sched_policy: policy::RR,
+            sched_priority: 50,
+            sched_placement: placement::LATENCY_SENSITIVE,
+            ..SchedAttrAbi::default()
+        };
+        let back = SchedAttrAbi::from_attr(&abi.to_attr().unwrap());
+        assert_eq!(back.sched_policy, policy::RR);
+        assert_eq!(back.sched_priority, 50);
+        assert_eq!(back.sched_placement, placement::LATENCY_SENSITIVE);
+    }
+}
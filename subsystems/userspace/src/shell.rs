@@ -20,9 +20,13 @@
 use alloc::format;
 use alloc::collections::BTreeMap;
 use core::fmt::Write;
+use core::sync::atomic::AtomicI32;
 use spin::Mutex;
 
 use super::{UserResult, UserError, STATS, Environment};
+use super::exec::{build_argv, build_envp, Executor, STATUS_NOT_EXECUTABLE, STATUS_NOT_FOUND};
+use super::runtime::{ExitStatus, Pid};
+use super::syscalls::SyscallError;
 
 /// Maximum command history size
 const MAX_HISTORY: usize = 100;
@@ -91,6 +95,13 @@ fn help(&self) -> &str {
     
     /// Execute the command
     fn execute(&self, args: &[&str], shell: &Shell) -> CommandResult;
+    
+    /// Execute as a later pipeline stage, with the previous stage's output
+    ///
+    /// Commands that do not read input ignore it.
+    fn execute_piped(&self, args: &[&str], _input: &str, shell: &Shell) -> CommandResult {
+        self.execute(args, shell)
+    }
 }
 
 /// Built-in help command
@@ -440,9 +451,20 @@ fn execute(&self, args: &[&str], _shell: &Shell) -> CommandResult {
             "/proc/version" | "version" => {
                 CommandResult::output("Helix version 0.1.0-dev (x86_64)")
             }
+            "/proc/metrics" => {
+                CommandResult::output(crate::metrics::render())
+            }
             _ => CommandResult::error(format!("cat: {}: No such file (filesystem not yet implemented)", filename))
         }
     }
+    
+    fn execute_piped(&self, args: &[&str], input: &str, shell: &Shell) -> CommandResult {
+        if args.is_empty() {
+            CommandResult::output(input)
+        } else {
+            self.execute(args, shell)
+        }
+    }
 }
 
 /// Run ELF command
@@ -456,14 +478,17 @@ fn help(&self) -> &str {
          Load and execute an ELF binary."
     }
     
-    fn execute(&self, args: &[&str], _shell: &Shell) -> CommandResult {
+    fn execute(&self, args: &[&str], shell: &Shell) -> CommandResult {
         if args.is_empty() {
             return CommandResult::error("Usage: run <program> [args...]");
         }
         
         let program = args[0];
         
-        // In real OS, would use ElfLoader to load and execute
+        if shell.executor.lock().is_some() {
+            return shell.run_external(program, &args[1..], None, &mut None).0;
+        }
+        
         CommandResult::output(format!(
             "{}Note:{} Filesystem not yet implemented. Cannot load: {}\n\
              The ELF loader is ready - just needs VFS!",
@@ -500,6 +525,118 @@ fn execute(&self, _args: &[&str], _shell: &Shell) -> CommandResult {
     }
 }
 
+/// Helix administration command
+struct HelixCommand;
+
+impl ShellCommand for HelixCommand {
+    fn name(&self) -> &str { "helix" }
+    fn description(&self) -> &str { "Helix administration" }
+    fn help(&self) -> &str {
+        "Usage: helix report [--redact] [--serial | --print] [PATH]\n\n\
+         Gather version, modules, events, AI decisions, metrics, logs and\n\
+         boot measurements into a compressed archive (tar + lz4).\n\n\
+         Options:\n\
+           --redact    Mask addresses and sensitive values\n\
+           --serial    Send the archive over serial as base64\n\
+           --print     Print the sections instead of archiving them\n\
+           PATH        Output file (default /var/log/helix-report.tar.lz4)"
+    }
+    
+    fn execute(&self, args: &[&str], _shell: &Shell) -> CommandResult {
+        use super::report::{self, ReportOptions, DEFAULT_PATH};
+        
+        if args.first() != Some(&"report") {
+            return CommandResult::error(self.help());
+        }
+        
+        let mut options = ReportOptions::default();
+        let (mut serial, mut print, mut path) = (false, false, DEFAULT_PATH);
+        for &arg in &args[1..] {
+            match arg {
+                "--redact" => options.redact = true,
+                "--serial" => serial = true,
+                "--print" => print = true,
+                _ if arg.starts_with('-') => {
+                    return CommandResult::error(format!("helix report: unknown option {}", arg));
+                }
+                _ => path = arg,
+            }
+        }
+        
+        if print {
+            let mut output = String::new();
+            for (name, text) in report::collect(&options) {
+                writeln!(output, "{}== {} =={}", colors::CYAN, name, colors::RESET).ok();
+                output.push_str(&text);
+            }
+            return CommandResult::output(output);
+        }
+        
+        let (result, target) = if serial {
+            (report::send_serial(&options), "serial")
+        } else {
+            (report::write_file(path, &options), path)
+        };
+        match result {
+            Ok(size) => CommandResult::output(format!("helix report: {} bytes written to {}", size, target)),
+            Err(SyscallError::ENOSYS) => {
+                CommandResult::error(format!("helix report: no {} output available", target))
+            }
+            Err(e) => CommandResult::error(format!("helix report: {}: error {}", target, e.to_errno())),
+        }
+    }
+}
+
+/// Brightness command
+struct BrightnessCommand;
+
+impl ShellCommand for BrightnessCommand {
+    fn name(&self) -> &str { "brightness" }
+    fn description(&self) -> &str { "Show or set display brightness" }
+    fn help(&self) -> &str {
+        "Usage: brightness [-d NAME] [LEVEL | N% | +N% | -N%]\n\n\
+         Without a value, list backlights; * marks the one adjusted by\n\
+         default (firmware, then platform, then raw).\n\n\
+         Options:\n\
+           -d NAME     Adjust the backlight NAME\n\
+           LEVEL       Absolute level, up to max_brightness\n\
+           N%          Percentage of the maximum\n\
+           +N%, -N%    Step up or down by N percent"
+    }
+    
+    fn execute(&self, args: &[&str], _shell: &Shell) -> CommandResult {
+        use super::backlight;
+        use helix_hal::backlight as hal_backlight;
+        
+        let (name, spec) = match args {
+            [] => return CommandResult::output(backlight::render_list()),
+            ["-d", name] => (Some(*name), None),
+            ["-d", name, spec] => (Some(*name), Some(*spec)),
+            [spec] => (None, Some(*spec)),
+            _ => return CommandResult::error(self.help()),
+        };
+        
+        let device = match name {
+            Some(name) => hal_backlight::find(name),
+            None => hal_backlight::preferred(),
+        };
+        let Some(device) = device else {
+            return CommandResult::error(format!("brightness: {}: no such backlight", name.unwrap_or("default")));
+        };
+        let Some(spec) = spec else {
+            return match device.brightness() {
+                Ok(level) => CommandResult::output(format!("{}: {}/{}", device.name(), level, device.max_brightness())),
+                Err(_) => CommandResult::error(format!("brightness: {}: read failed", device.name())),
+            };
+        };
+        match backlight::adjust(device, spec) {
+            Ok(level) => CommandResult::output(format!("{}: {}/{}", device.name(), level, device.max_brightness())),
+            Err(SyscallError::EINVAL) => CommandResult::error(format!("brightness: invalid value {}", spec)),
+            Err(e) => CommandResult::error(format!("brightness: {}: error {}", device.name(), e.to_errno())),
+        }
+    }
+}
+
 /// Demo command - demonstrates OS features
 struct DemoCommand;
 
@@ -577,6 +714,10 @@ pub struct Shell {
     pub env: Environment,
     /// Current working directory
     pub cwd: Mutex<String>,
+    /// External program executor (built-ins only if unset)
+    executor: Mutex<Option<Executor>>,
+    /// Exit status of the last command (`$?`)
+    last_status: AtomicI32,
     /// Running flag
     running: core::sync::atomic::AtomicBool,
 }
@@ -589,6 +730,8 @@ pub fn new() -> Self {
             history: Mutex::new(Vec::new()),
             env: Environment::new(),
             cwd: Mutex::new(String::from("/")),
+            executor: Mutex::new(None),
+            last_status: AtomicI32::new(0),
             running: core::sync::atomic::AtomicBool::new(false),
         };
         
@@ -601,10 +744,26 @@ pub fn new() -> Self {
         shell.env.set("SHELL", "/bin/hsh");
         shell.env.set("USER", "root");
         shell.env.set("PS1", PROMPT);
+        shell.env.set_local("?", "0");
         
         shell
     }
     
+    /// Enable external programs, loaded and run through `executor`
+    pub fn set_executor(&self, executor: Executor) {
+        *self.executor.lock() = Some(executor);
+    }
+    
+    /// Exit status of the last command
+    pub fn last_status(&self) -> i32 {
+        self.last_status.load(core::sync::atomic::Ordering::Relaxed)
+    }
+    
+    fn set_status(&self, status: i32) {
+        self.last_status.store(status, core::sync::atomic::Ordering::Relaxed);
+        self.env.set_local("?", status.to_string());
+    }
+    
     /// Register built-in commands
     fn register_builtins(&self) {
         let mut commands = self.commands.lock();
@@ -624,6 +783,8 @@ fn register_builtins(&self) {
         commands.push(Box::new(CatCommand));
         commands.push(Box::new(RunCommand));
         commands.push(Box::new(VersionCommand));
+        commands.push(Box::new(HelixCommand));
+        commands.push(Box::new(BrightnessCommand));
         commands.push(Box::new(DemoCommand));
     }
     
@@ -640,6 +801,9 @@ pub fn find_command(&self, name: &str) -> Option<Box<dyn ShellCommand>> {
     }
     
     /// Execute a command line
+    ///
+    /// Stages separated by `|` run in order, each reading the output of
+    /// the previous one. The exit status of the last stage is kept in `$?`.
     pub fn execute_line(&self, line: &str) -> CommandResult {
         let line = line.trim();
         
@@ -656,25 +820,107 @@ pub fn execute_line(&self, line: &str) -> CommandResult {
             history.push(line.to_string());
         }
         
-        // Parse command
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.is_empty() {
-            return CommandResult::Continue;
+        // Parse pipeline
+        let stages: Vec<&str> = line.split('|').map(str::trim).collect();
+        if stages.iter().any(|stage| stage.is_empty()) {
+            self.set_status(2);
+            return CommandResult::error("Syntax error near '|'");
+        }
+        
+        let (last, first) = stages.split_last().unwrap();
+        let mut input: Option<String> = None;
+        let mut pgid = None;
+        
+        for stage in first {
+            let (result, status) = self.execute_stage(stage, input.as_deref(), &mut pgid);
+            self.set_status(status);
+            input = match result {
+                CommandResult::Success(output) => Some(output.unwrap_or_default()),
+                CommandResult::Error(msg) => return CommandResult::Error(msg),
+                CommandResult::Exit(_) | CommandResult::Continue => Some(String::new()),
+            };
         }
         
+        let (result, status) = self.execute_stage(last, input.as_deref(), &mut pgid);
+        self.set_status(status);
+        result
+    }
+    
+    /// Execute one pipeline stage; returns its result and exit status
+    fn execute_stage(&self, stage: &str, input: Option<&str>, pgid: &mut Option<Pid>) -> (CommandResult, i32) {
+        let parts: Vec<&str> = stage.split_whitespace().collect();
         let cmd_name = parts[0];
         let args = &parts[1..];
         
-        // Find and execute command
-        let commands = self.commands.lock();
-        for cmd in commands.iter() {
-            if cmd.name() == cmd_name {
+        // Built-ins first
+        {
+            let commands = self.commands.lock();
+            if let Some(cmd) = commands.iter().find(|cmd| cmd.name() == cmd_name) {
                 STATS.command_executed();
-                return cmd.execute(args, self);
+                let result = match input {
+                    Some(input) => cmd.execute_piped(args, input, self),
+                    None => cmd.execute(args, self),
+                };
+                let status = match result {
+                    CommandResult::Error(_) => 1,
+                    CommandResult::Exit(code) => code,
+                    _ => 0,
+                };
+                return (result, status);
             }
         }
         
-        CommandResult::error(format!("Unknown command: {}. Type 'help' for available commands.", cmd_name))
+        self.run_external(cmd_name, args, input, pgid)
+    }
+    
+    /// Run an external program in the foreground
+    ///
+    /// The program joins process group `pgid`, or starts one recorded there.
+    fn run_external(&self, name: &str, args: &[&str], input: Option<&str>, pgid: &mut Option<Pid>) -> (CommandResult, i32) {
+        let Some(executor) = *self.executor.lock() else {
+            return (
+                CommandResult::error(format!("Unknown command: {}. Type 'help' for available commands.", name)),
+                STATUS_NOT_FOUND,
+            );
+        };
+        
+        let path_var = self.env.get("PATH").unwrap_or_default();
+        let cwd = self.cwd.lock().clone();
+        let Some(path) = executor.resolve(name, &path_var, &cwd) else {
+            return (CommandResult::error(format!("{}: command not found", name)), STATUS_NOT_FOUND);
+        };
+        
+        let args: Vec<String> = args.iter().map(|arg| self.env.expand(arg)).collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let process = match executor.spawn(&path, build_argv(name, &args), build_envp(&self.env), *pgid) {
+            Ok(process) => process,
+            Err(e) => {
+                return (
+                    CommandResult::error(format!("{}: cannot execute: {:?}", path, e)),
+                    STATUS_NOT_EXECUTABLE,
+                );
+            }
+        };
+        pgid.get_or_insert(process.pid);
+        STATS.command_executed();
+        
+        let mut output = Vec::new();
+        let status = executor.run_foreground(&process, input.unwrap_or("").as_bytes(), &mut output);
+        let output = String::from_utf8_lossy(&output).into_owned();
+        
+        let result = match status {
+            ExitStatus::Exited(0) => CommandResult::output(output),
+            ExitStatus::Stopped(_) => {
+                CommandResult::output(format!("{}[{}] Stopped    {}", output, process.pgid(), name))
+            }
+            ExitStatus::Exited(code) => {
+                CommandResult::error(format!("{}{}: exited with status {}", output, name, code))
+            }
+            ExitStatus::Signaled(sig) => {
+                CommandResult::error(format!("{}{}: terminated by signal {}", output, name, sig))
+            }
+        };
+        (result, status.code())
     }
     
     /// Print the prompt
@@ -785,6 +1031,8 @@ fn default() -> Self {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::exec::ExecFs;
+    use crate::runtime::ProcessHandle;
 
     #[test]
     fn test_shell_creation() {
@@ -811,4 +1059,79 @@ fn test_unknown_command() {
             _ => panic!("Expected error"),
         }
     }
+
+    #[test]
+    fn test_builtin_pipeline() {
+        let shell = Shell::new();
+        match shell.execute_line("echo hello | cat") {
+            CommandResult::Success(Some(output)) => assert_eq!(output, "hello"),
+            _ => panic!("Expected success"),
+        }
+        assert_eq!(shell.last_status(), 0);
+        
+        assert!(matches!(shell.execute_line("echo hello |"), CommandResult::Error(_)));
+        assert_eq!(shell.last_status(), 2);
+        
+        shell.execute_line("nonexistent");
+        assert_eq!(shell.env.get("?").as_deref(), Some("127"));
+    }
+
+    struct TestFs;
+
+    impl ExecFs for TestFs {
+        fn is_executable(&self, path: &str) -> bool {
+            matches!(path, "/bin/upper" | "/bin/fail" | "/bin/hang")
+        }
+
+        fn read(&self, _path: &str) -> Option<Vec<u8>> {
+            // Minimal x86_64 executable: ELF header plus one PT_LOAD segment
+            let mut image = vec![0u8; 64 + 56];
+            image[..7].copy_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1]);
+            image[16..20].copy_from_slice(&[2, 0, 0x3e, 0]);
+            image[32] = 64;
+            image[54] = 56;
+            image[56] = 1;
+            image[64] = 1;
+            image[64 + 40] = 1;
+            Some(image)
+        }
+    }
+
+    static TEST_FS: TestFs = TestFs;
+
+    /// Stand-in for entering user mode: behaves according to argv[0]
+    fn run_test_program(process: &ProcessHandle, stdin: &[u8], stdout: &mut Vec<u8>) {
+        match process.argv[0].as_str() {
+            "upper" => {
+                stdout.extend(stdin.to_ascii_uppercase());
+                stdout.extend(process.argv[1..].concat().bytes());
+                process.exit(0);
+            }
+            "fail" => process.exit(3),
+            // Interrupted from the terminal while running
+            _ => assert!(crate::exec::terminal_input(0x03)),
+        }
+    }
+
+    #[test]
+    fn test_external_pipeline() {
+        let shell = Shell::new();
+        shell.set_executor(Executor::new(&TEST_FS, run_test_program));
+        shell.env.set("NAME", "x");
+        
+        match shell.execute_line("echo hello | upper $NAME") {
+            CommandResult::Success(Some(output)) => assert_eq!(output, "HELLOx"),
+            _ => panic!("Expected success"),
+        }
+        
+        assert!(matches!(shell.execute_line("fail"), CommandResult::Error(_)));
+        assert_eq!(shell.last_status(), 3);
+        
+        assert!(matches!(shell.execute_line("hang | cat"), CommandResult::Error(_)));
+        assert_eq!(shell.last_status(), 130);
+        assert_eq!(crate::exec::foreground(), 0);
+        
+        shell.execute_line("missing");
+        assert_eq!(shell.last_status(), STATUS_NOT_FOUND);
+    }
 }
@@ -0,0 +1,72 @@
+//! # System Statistics
+//!
+//! `/proc/stat`: per-CPU user, system, IRQ, softirq and idle time from the
+//! scheduler's CPU time accounting, in Linux's format so `top`, `mpstat`
+//! and `vmstat` work unchanged.
+//!
+//! Served through [`procfs`](super::procfs).
+
+use alloc::string::String;
+use helix_execution::scheduler::framework;
+
+use super::clock::boot_time_secs;
+
+/// Path of the statistics file
+pub const STAT_PATH: &str = "/proc/stat";
+
+/// The `/proc/stat` report
+pub fn render() -> String {
+    let framework = framework();
+    let mut out = String::new();
+    let _ = framework.cputime().render_proc(
+        &mut out,
+        framework.metrics().context_switches(),
+        boot_time_secs(),
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs;
+    use helix_execution::scheduler::cputime::{CpuMode, CpuTimeAccounting};
+    use helix_execution::{ProcessId, ThreadId};
+
+    #[test]
+    fn test_render_proc_stat() {
+        static NOW: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(0);
+        let cputime = CpuTimeAccounting::new();
+        cputime.set_clock(|| NOW.load(core::sync::atomic::Ordering::Relaxed));
+        let advance = |ms: u64| NOW.fetch_add(ms * 1_000_000, core::sync::atomic::Ordering::Relaxed);
+
+        // CPU 1: 30ms user, 20ms system, 10ms IRQ, then 40ms idle
+        let thread = ThreadId::new();
+        cputime.register_thread(thread, ProcessId::new());
+        cputime.switch_to(1, Some(thread));
+        advance(20);
+        cputime.set_mode(1, CpuMode::User);
+        advance(30);
+        cputime.enter_interrupt(1, CpuMode::Irq);
+        advance(10);
+        cputime.exit_interrupt(1);
+        assert_eq!(cputime.mode(1), Some(CpuMode::User));
+        cputime.switch_to(1, None);
+        advance(40);
+
+        let mut text = String::new();
+        cputime.render_proc(&mut text, 7, 1_700_000_000).unwrap();
+        assert!(text.starts_with("cpu  3 0 2 4 0 1 0 0 0 0\ncpu1 3 0 2 4 0 1 0 0 0 0\nctxt 7\n"));
+        assert!(text.contains("btime 1700000000\nprocesses 1\nprocs_running 0\n"));
+    }
+
+    #[test]
+    fn test_proc_stat() {
+        let file = procfs::find(STAT_PATH).unwrap();
+        let slot = procfs::proc_fd(procfs::open(file, STAT_PATH).unwrap()).unwrap();
+        let mut buf = [0u8; 4];
+        assert_eq!(procfs::read(slot, &mut buf), Ok(4));
+        assert_eq!(&buf, b"cpu ");
+        assert_eq!(procfs::close(slot), Ok(()));
+    }
+}
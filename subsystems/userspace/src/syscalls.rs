@@ -14,10 +14,24 @@
 //! - IPC (pipe, socket, etc.)
 
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
-use core::sync::atomic::{AtomicU64, Ordering};
-use spin::RwLock;
-
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use helix_hal::lockdown::{self, LockdownReason};
+use helix_hal::uaccess::{self, UserFault};
+use helix_nexus::perf::{
+    attr_flags, PerfError, PerfEventAttr, PerfFdTable, PerfManager, PerfTarget, PAGE_SIZE,
+};
+use spin::{Mutex, RwLock};
+
+use super::control::sys_helix_ctl;
+use super::clock::{sys_clock_getres, sys_clock_gettime};
+use super::events::{self, events_fd, EVENTS_PATH};
+use super::procfs::{self, proc_fd};
+use super::rusage::{sys_getrusage, sys_times, sys_wait4};
+use super::sched::{sys_sched_getattr, sys_sched_setattr};
+use super::uring::{self, sys_io_uring_enter, sys_io_uring_setup, uring_fd};
 use super::{UserResult, UserError, STATS};
 
 /// Syscall numbers (Linux-compatible subset)
@@ -36,6 +50,8 @@ pub enum Syscall {
     Stat = 4,
     /// Get file status (fd)
     Fstat = 5,
+    /// Get file status (no symlink follow)
+    Lstat = 6,
     /// Seek
     Lseek = 8,
     /// Memory map
@@ -76,6 +92,8 @@ pub enum Syscall {
     Getcwd = 79,
     /// Change directory
     Chdir = 80,
+    /// Rename file
+    Rename = 82,
     /// Create directory
     Mkdir = 83,
     /// Remove directory
@@ -86,6 +104,8 @@ pub enum Syscall {
     Gettimeofday = 96,
     /// Get resource usage
     Getrusage = 98,
+    /// Get process times
+    Times = 100,
     /// Get UID
     Getuid = 102,
     /// Get GID
@@ -102,8 +122,28 @@ pub enum Syscall {
     RtSigreturn = 15,
     /// Architecture-specific
     ArchPrctl = 158,
+    /// Mount filesystem
+    Mount = 165,
+    /// Unmount filesystem
+    Umount2 = 166,
+    /// Read directory entries
+    Getdents64 = 217,
+    /// Read a clock
+    ClockGettime = 228,
+    /// Get the resolution of a clock
+    ClockGetres = 229,
     /// Exit process group
     ExitGroup = 231,
+    /// Open a performance counter
+    PerfEventOpen = 298,
+    /// Set scheduling attributes
+    SchedSetattr = 314,
+    /// Get scheduling attributes
+    SchedGetattr = 315,
+    /// Create an async I/O ring
+    IoUringSetup = 425,
+    /// Submit to / reap from an async I/O ring
+    IoUringEnter = 426,
     
     // Helix-specific syscalls (start at 1000)
     /// Get DIS statistics
@@ -116,6 +156,8 @@ pub enum Syscall {
     HelixBenchmark = 1003,
     /// Get kernel info
     HelixKernelInfo = 1004,
+    /// Kernel control interface (see [`crate::control`])
+    HelixCtl = 1005,
 }
 
 impl Syscall {
@@ -128,6 +170,7 @@ pub fn from_num(num: u64) -> Option<Self> {
             3 => Some(Syscall::Close),
             4 => Some(Syscall::Stat),
             5 => Some(Syscall::Fstat),
+            6 => Some(Syscall::Lstat),
             8 => Some(Syscall::Lseek),
             9 => Some(Syscall::Mmap),
             10 => Some(Syscall::Mprotect),
@@ -148,23 +191,36 @@ pub fn from_num(num: u64) -> Option<Self> {
             62 => Some(Syscall::Kill),
             79 => Some(Syscall::Getcwd),
             80 => Some(Syscall::Chdir),
+            82 => Some(Syscall::Rename),
             83 => Some(Syscall::Mkdir),
             84 => Some(Syscall::Rmdir),
             87 => Some(Syscall::Unlink),
             96 => Some(Syscall::Gettimeofday),
             98 => Some(Syscall::Getrusage),
+            100 => Some(Syscall::Times),
             102 => Some(Syscall::Getuid),
             104 => Some(Syscall::Getgid),
             107 => Some(Syscall::Geteuid),
             108 => Some(Syscall::Getegid),
             110 => Some(Syscall::Getppid),
             158 => Some(Syscall::ArchPrctl),
+            165 => Some(Syscall::Mount),
+            166 => Some(Syscall::Umount2),
+            217 => Some(Syscall::Getdents64),
+            228 => Some(Syscall::ClockGettime),
+            229 => Some(Syscall::ClockGetres),
             231 => Some(Syscall::ExitGroup),
+            298 => Some(Syscall::PerfEventOpen),
+            314 => Some(Syscall::SchedSetattr),
+            315 => Some(Syscall::SchedGetattr),
+            425 => Some(Syscall::IoUringSetup),
+            426 => Some(Syscall::IoUringEnter),
             1000 => Some(Syscall::HelixDisStats),
             1001 => Some(Syscall::HelixHotReload),
             1002 => Some(Syscall::HelixSelfHeal),
             1003 => Some(Syscall::HelixBenchmark),
             1004 => Some(Syscall::HelixKernelInfo),
+            1005 => Some(Syscall::HelixCtl),
             _ => None,
         }
     }
@@ -245,6 +301,8 @@ pub enum SyscallError {
     EDOM = 33,
     /// Result too large
     ERANGE = 34,
+    /// File name too long
+    ENAMETOOLONG = 36,
     /// Function not implemented
     ENOSYS = 38,
 }
@@ -256,6 +314,12 @@ pub fn to_errno(self) -> i64 {
     }
 }
 
+impl From<UserFault> for SyscallError {
+    fn from(_: UserFault) -> Self {
+        SyscallError::EFAULT
+    }
+}
+
 /// Syscall arguments
 #[derive(Debug, Clone, Copy, Default)]
 pub struct SyscallArgs {
@@ -314,46 +378,98 @@ struct SyscallEntry {
     name: &'static str,
 }
 
+/// Number of standard syscall slots
+const TABLE_SIZE: usize = 512;
+
+/// Handler slots, covering the Helix-specific syscalls as well
+const HANDLER_SLOTS: usize = 1024;
+
+/// Calls per standard syscall
+///
+/// Kept outside [`SyscallTable`]: the global table becomes read-only at
+/// kernel lockdown.
+static CALL_COUNTS: [AtomicU64; TABLE_SIZE] = [const { AtomicU64::new(0) }; TABLE_SIZE];
+
+/// Table not initialized
+const TABLE_EMPTY: u8 = 0;
+/// `init` is filling in the handlers
+const TABLE_FILLING: u8 = 1;
+/// Handlers in place; the table is no longer written
+const TABLE_READY: u8 = 2;
+
 /// The syscall table
+///
+/// Handlers are written once, by [`init`](Self::init), and only read
+/// afterwards, so the table can live in memory that is made read-only
+/// once boot is over (see [`SYSCALL_TABLE`]).
 pub struct SyscallTable {
     /// Handlers indexed by syscall number
-    handlers: RwLock<Vec<Option<SyscallEntry>>>,
-    /// Statistics
-    call_counts: [AtomicU64; 256],
+    handlers: UnsafeCell<[Option<SyscallEntry>; HANDLER_SLOTS]>,
+    /// `TABLE_EMPTY`, `TABLE_FILLING` or `TABLE_READY`
+    state: AtomicU8,
 }
 
+// SAFETY: `handlers` is written only by the one `init` call that moves
+// `state` out of `TABLE_EMPTY`, and read only once `state` is `TABLE_READY`
+unsafe impl Sync for SyscallTable {}
+
 impl SyscallTable {
     /// Create new syscall table
     pub const fn new() -> Self {
-        const ZERO: AtomicU64 = AtomicU64::new(0);
         Self {
-            handlers: RwLock::new(Vec::new()),
-            call_counts: [ZERO; 256],
+            handlers: UnsafeCell::new([const { None }; HANDLER_SLOTS]),
+            state: AtomicU8::new(TABLE_EMPTY),
         }
     }
     
     /// Initialize table with default handlers
+    ///
+    /// Only the first call has an effect.
     pub fn init(&self) {
-        let mut handlers = self.handlers.write();
-        handlers.resize_with(256, || None);
+        // Plain load first: a locked-down table must not even see a failed
+        // compare-exchange, which still writes
+        if self.state.load(Ordering::Relaxed) != TABLE_EMPTY
+            || self
+                .state
+                .compare_exchange(TABLE_EMPTY, TABLE_FILLING, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+        // SAFETY: the state transition above makes this the only writer,
+        // and readers wait for `TABLE_READY`
+        let handlers = unsafe { &mut *self.handlers.get() };
         
         // Register standard syscalls
-        self.register_handler_internal(&mut handlers, Syscall::Read, sys_read, 3, "read");
-        self.register_handler_internal(&mut handlers, Syscall::Write, sys_write, 3, "write");
-        self.register_handler_internal(&mut handlers, Syscall::Open, sys_open, 3, "open");
-        self.register_handler_internal(&mut handlers, Syscall::Close, sys_close, 1, "close");
-        self.register_handler_internal(&mut handlers, Syscall::Getpid, sys_getpid, 0, "getpid");
-        self.register_handler_internal(&mut handlers, Syscall::Getppid, sys_getppid, 0, "getppid");
-        self.register_handler_internal(&mut handlers, Syscall::Fork, sys_fork, 0, "fork");
-        self.register_handler_internal(&mut handlers, Syscall::Exit, sys_exit, 1, "exit");
-        self.register_handler_internal(&mut handlers, Syscall::Brk, sys_brk, 1, "brk");
-        self.register_handler_internal(&mut handlers, Syscall::Mmap, sys_mmap, 6, "mmap");
-        self.register_handler_internal(&mut handlers, Syscall::Munmap, sys_munmap, 2, "munmap");
+        Self::register_handler_internal(handlers, Syscall::Read, sys_read, 3, "read");
+        Self::register_handler_internal(handlers, Syscall::Write, sys_write, 3, "write");
+        Self::register_handler_internal(handlers, Syscall::Open, sys_open, 3, "open");
+        Self::register_handler_internal(handlers, Syscall::Close, sys_close, 1, "close");
+        Self::register_handler_internal(handlers, Syscall::Getpid, sys_getpid, 0, "getpid");
+        Self::register_handler_internal(handlers, Syscall::Getppid, sys_getppid, 0, "getppid");
+        Self::register_handler_internal(handlers, Syscall::Fork, sys_fork, 0, "fork");
+        Self::register_handler_internal(handlers, Syscall::Exit, sys_exit, 1, "exit");
+        Self::register_handler_internal(handlers, Syscall::Wait4, sys_wait4, 4, "wait4");
+        Self::register_handler_internal(handlers, Syscall::Getrusage, sys_getrusage, 2, "getrusage");
+        Self::register_handler_internal(handlers, Syscall::Times, sys_times, 1, "times");
+        Self::register_handler_internal(handlers, Syscall::Brk, sys_brk, 1, "brk");
+        Self::register_handler_internal(handlers, Syscall::Mmap, sys_mmap, 6, "mmap");
+        Self::register_handler_internal(handlers, Syscall::Munmap, sys_munmap, 2, "munmap");
+        Self::register_handler_internal(handlers, Syscall::Ioctl, sys_ioctl, 3, "ioctl");
+        Self::register_handler_internal(handlers, Syscall::ClockGettime, sys_clock_gettime, 2, "clock_gettime");
+        Self::register_handler_internal(handlers, Syscall::ClockGetres, sys_clock_getres, 2, "clock_getres");
+        Self::register_handler_internal(handlers, Syscall::PerfEventOpen, sys_perf_event_open, 5, "perf_event_open");
+        Self::register_handler_internal(handlers, Syscall::SchedSetattr, sys_sched_setattr, 3, "sched_setattr");
+        Self::register_handler_internal(handlers, Syscall::SchedGetattr, sys_sched_getattr, 4, "sched_getattr");
+        Self::register_handler_internal(handlers, Syscall::IoUringSetup, sys_io_uring_setup, 2, "io_uring_setup");
+        Self::register_handler_internal(handlers, Syscall::IoUringEnter, sys_io_uring_enter, 4, "io_uring_enter");
+        Self::register_handler_internal(handlers, Syscall::HelixCtl, sys_helix_ctl, 5, "helix_ctl");
+        
+        self.state.store(TABLE_READY, Ordering::Release);
     }
     
     fn register_handler_internal(
-        &self,
-        handlers: &mut Vec<Option<SyscallEntry>>,
+        handlers: &mut [Option<SyscallEntry>],
         syscall: Syscall,
         handler: SyscallHandler,
         arg_count: u8,
@@ -374,16 +490,16 @@ fn register_handler_internal(
     pub fn handle(&self, num: u64, args: SyscallArgs) -> SyscallResult {
         STATS.syscall_made();
         
-        let handlers = self.handlers.read();
-        
-        if num >= handlers.len() as u64 {
+        if self.state.load(Ordering::Acquire) != TABLE_READY || num >= HANDLER_SLOTS as u64 {
             return Err(SyscallError::ENOSYS);
         }
+        // SAFETY: the table is ready, so no longer written
+        let handlers = unsafe { &*self.handlers.get() };
         
         if let Some(entry) = &handlers[num as usize] {
             // Update stats
-            if num < 256 {
-                self.call_counts[num as usize].fetch_add(1, Ordering::Relaxed);
+            if num < TABLE_SIZE as u64 {
+                CALL_COUNTS[num as usize].fetch_add(1, Ordering::Relaxed);
             }
             
             // Call handler
@@ -396,8 +512,8 @@ pub fn handle(&self, num: u64, args: SyscallArgs) -> SyscallResult {
     /// Get syscall count
     pub fn get_count(&self, syscall: Syscall) -> u64 {
         let num = syscall as usize;
-        if num < 256 {
-            self.call_counts[num].load(Ordering::Relaxed)
+        if num < TABLE_SIZE {
+            CALL_COUNTS[num].load(Ordering::Relaxed)
         } else {
             0
         }
@@ -416,9 +532,19 @@ fn default() -> Self {
 
 /// Read from file descriptor
 fn sys_read(args: SyscallArgs) -> SyscallResult {
-    let _fd = args.arg1 as i32;
-    let _buf = args.arg2 as *mut u8;
-    let _count = args.arg3 as usize;
+    let fd = args.arg1 as i32;
+    let buf = args.arg2;
+    let count = args.arg3 as usize;
+    
+    if let Some(perf_fd) = perf_fd(fd) {
+        return perf_read(perf_fd, buf, count);
+    }
+    if let Some(slot) = events_fd(fd) {
+        return read_to_user(buf, count, |buf| events::read(slot, buf));
+    }
+    if let Some(slot) = proc_fd(fd) {
+        return read_to_user(buf, count, |buf| procfs::read(slot, buf));
+    }
     
     // In real OS, would read from fd_table entry
     // For now, return 0 (EOF)
@@ -428,13 +554,22 @@ fn sys_read(args: SyscallArgs) -> SyscallResult {
 /// Write to file descriptor
 fn sys_write(args: SyscallArgs) -> SyscallResult {
     let fd = args.arg1 as i32;
-    let buf = args.arg2 as *const u8;
+    let buf = args.arg2;
     let count = args.arg3 as usize;
     
-    if buf.is_null() {
+    if buf == 0 {
         return Err(SyscallError::EFAULT);
     }
     
+    if let Some(slot) = events_fd(fd) {
+        let spec = read_user_buf(buf, count)?;
+        return events::write(slot, &spec).map(|len| len as u64);
+    }
+    if let Some(slot) = proc_fd(fd) {
+        let data = read_user_buf(buf, count)?;
+        return procfs::write(slot, &data).map(|len| len as u64);
+    }
+    
     // For stdout/stderr, would output to console
     match fd {
         1 | 2 => {
@@ -447,15 +582,104 @@ fn sys_write(args: SyscallArgs) -> SyscallResult {
 }
 
 /// Open file
-fn sys_open(_args: SyscallArgs) -> SyscallResult {
+fn sys_open(args: SyscallArgs) -> SyscallResult {
+    let path = user_path(args.arg1)?;
+    let path = path.as_str();
+    if let Some(reason) = lockdown_reason(path, args.arg2) {
+        lockdown::check(reason).map_err(|_| SyscallError::EPERM)?;
+    }
+    if path == EVENTS_PATH {
+        return events::open().map(|fd| fd as u64);
+    }
+    if let Some(file) = procfs::find(path) {
+        return procfs::open(file, path).map(|fd| fd as u64);
+    }
+    
     // Filesystem not implemented
     Err(SyscallError::ENOSYS)
 }
 
+/// Files giving raw access to physical memory or I/O ports
+const RAW_MEMORY_PATHS: [&str; 3] = ["/dev/mem", "/dev/kmem", "/dev/port"];
+
+/// Image of kernel memory
+const KCORE_PATH: &str = "/proc/kcore";
+
+/// debugfs mount point
+const DEBUGFS_DIR: &str = "/sys/kernel/debug";
+
+/// tracefs mount point
+const TRACEFS_DIR: &str = "/sys/kernel/tracing";
+
+/// Access mode bits of the `open` flags (0 = read-only)
+const O_ACCMODE: u64 = 0o3;
+
+/// `path` made absolute, with empty, `.` and `..` components resolved
+///
+/// Lexical only, as there are no symlinks to follow yet. Relative paths
+/// resolve from the root, the only working directory so far.
+fn normalize_path(path: &str) -> String {
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            name => components.push(name),
+        }
+    }
+
+    if components.is_empty() {
+        return String::from("/");
+    }
+    let mut normalized = String::with_capacity(path.len() + 1);
+    for component in components {
+        normalized.push('/');
+        normalized.push_str(component);
+    }
+    normalized
+}
+
+/// Lockdown restriction on opening `path` with `flags`, if any
+///
+/// `path` is normalized first, so `/dev//mem` and `/dev/../dev/mem` are
+/// caught as `/dev/mem`.
+fn lockdown_reason(path: &str, flags: u64) -> Option<LockdownReason> {
+    let path = normalize_path(path);
+    let path = path.as_str();
+    let under = |dir: &str| path.strip_prefix(dir).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+    let write = flags & O_ACCMODE != 0;
+    if RAW_MEMORY_PATHS.contains(&path) {
+        Some(LockdownReason::RawMemory)
+    } else if path == KCORE_PATH {
+        Some(LockdownReason::KernelRead)
+    } else if under(DEBUGFS_DIR) {
+        Some(if write { LockdownReason::DebugfsWrite } else { LockdownReason::Debugfs })
+    } else if under(TRACEFS_DIR) && write {
+        Some(LockdownReason::TracingWrite)
+    } else {
+        None
+    }
+}
+
 /// Close file descriptor
 fn sys_close(args: SyscallArgs) -> SyscallResult {
     let fd = args.arg1 as i32;
     
+    if let Some(perf_fd) = perf_fd(fd) {
+        return with_perf(|table| table.close(perf_fd, perf_now())).map(|()| 0);
+    }
+    if let Some(slot) = events_fd(fd) {
+        return events::close(slot).map(|()| 0);
+    }
+    if let Some(slot) = proc_fd(fd) {
+        return procfs::close(slot).map(|()| 0);
+    }
+    if let Some(slot) = uring_fd(fd) {
+        return uring::close(slot).map(|()| 0);
+    }
+    
     // Would close in fd_table
     if fd >= 0 {
         Ok(0)
@@ -464,8 +688,59 @@ fn sys_close(args: SyscallArgs) -> SyscallResult {
     }
 }
 
+// ============================================================================
+// User Memory
+// ============================================================================
+
+/// Maximum path length, including the terminating NUL
+const PATH_MAX: usize = 4096;
+
+/// Largest transfer through a kernel bounce buffer; longer reads and
+/// writes are short
+const USER_IO_MAX: usize = 64 * 1024;
+
+/// NUL-terminated user path
+fn user_path(path: u64) -> Result<String, SyscallError> {
+    if path == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    let mut bytes = vec![0; PATH_MAX];
+    let len = uaccess::strncpy_from_user(&mut bytes, path)?;
+    if len == PATH_MAX {
+        return Err(SyscallError::ENAMETOOLONG);
+    }
+    bytes.truncate(len);
+    String::from_utf8(bytes).map_err(|_| SyscallError::EINVAL)
+}
+
+/// Copy up to `count` bytes in from user buffer `buf`
+pub(crate) fn read_user_buf(buf: u64, count: usize) -> Result<Vec<u8>, SyscallError> {
+    if buf == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    let mut bytes = vec![0; count.min(USER_IO_MAX)];
+    uaccess::copy_from_user(&mut bytes, buf)?;
+    Ok(bytes)
+}
+
+/// Run `read` on a bounce buffer of up to `count` bytes and copy what it
+/// produced out to user buffer `buf`
+pub(crate) fn read_to_user(
+    buf: u64,
+    count: usize,
+    read: impl FnOnce(&mut [u8]) -> Result<usize, SyscallError>,
+) -> SyscallResult {
+    if buf == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    let mut bytes = vec![0; count.min(USER_IO_MAX)];
+    let len = read(&mut bytes)?;
+    uaccess::copy_to_user(buf, &bytes[..len])?;
+    Ok(len as u64)
+}
+
 /// Get process ID
-fn sys_getpid(_args: SyscallArgs) -> SyscallResult {
+pub(crate) fn sys_getpid(_args: SyscallArgs) -> SyscallResult {
     // Would return current process PID
     Ok(1)
 }
@@ -507,13 +782,20 @@ fn sys_mmap(args: SyscallArgs) -> SyscallResult {
     let len = args.arg2;
     let _prot = args.arg3 as i32;
     let _flags = args.arg4 as i32;
-    let _fd = args.arg5 as i32;
+    let fd = args.arg5 as i32;
     let _offset = args.arg6;
     
     if len == 0 {
         return Err(SyscallError::EINVAL);
     }
     
+    if let Some(perf_fd) = perf_fd(fd) {
+        return perf_mmap(perf_fd, len);
+    }
+    if let Some(slot) = uring_fd(fd) {
+        return uring::mmap(slot, len);
+    }
+    
     // Would allocate virtual memory
     // For now, return error
     if addr == 0 {
@@ -532,7 +814,121 @@ fn sys_munmap(args: SyscallArgs) -> SyscallResult {
     Ok(0)
 }
 
+/// Device control
+fn sys_ioctl(args: SyscallArgs) -> SyscallResult {
+    let fd = args.arg1 as i32;
+    let request = args.arg2;
+    
+    match perf_fd(fd) {
+        Some(perf_fd) => with_perf(|table| table.ioctl(perf_fd, request, perf_now())).map(|()| 0),
+        None if fd >= 0 => Err(SyscallError::ENOTTY),
+        None => Err(SyscallError::EBADF),
+    }
+}
+
+// ============================================================================
+// Performance Events
+// ============================================================================
+
+/// First descriptor number handed out for perf events
+pub const PERF_FD_BASE: i32 = 1 << 20;
+
+/// Open perf events (installed by [`init_perf`])
+static PERF_EVENTS: Mutex<Option<PerfFdTable>> = Mutex::new(None);
+
+/// Timestamp source for perf time accounting (ns)
+static PERF_CLOCK: RwLock<Option<fn() -> u64>> = RwLock::new(None);
+
+/// Enable `perf_event_open` over the PMUs registered with `manager`
+pub fn init_perf(manager: PerfManager, clock: fn() -> u64) {
+    *PERF_CLOCK.write() = Some(clock);
+    *PERF_EVENTS.lock() = Some(PerfFdTable::new(manager));
+}
+
+/// Run `f` on the perf descriptor table
+///
+/// Used by the scheduler (`sched_in` / `sched_out`), tracepoints and the
+/// PMU overflow handler (`account`) to drive the open events.
+pub fn with_perf<R>(f: impl FnOnce(&mut PerfFdTable) -> Result<R, PerfError>) -> Result<R, SyscallError> {
+    let mut table = PERF_EVENTS.lock();
+    let table = table.as_mut().ok_or(SyscallError::ENODEV)?;
+    f(table).map_err(perf_errno)
+}
+
+fn perf_now() -> u64 {
+    PERF_CLOCK.read().map(|clock| clock()).unwrap_or(0)
+}
+
+/// Perf table index of a user descriptor, if it is a perf descriptor
+fn perf_fd(fd: i32) -> Option<i32> {
+    fd.checked_sub(PERF_FD_BASE).filter(|fd| *fd >= 0)
+}
+
+fn perf_errno(error: PerfError) -> SyscallError {
+    match error {
+        PerfError::InvalidArgument => SyscallError::EINVAL,
+        PerfError::NoSuchEvent => SyscallError::ENOENT,
+        PerfError::NotSupported => SyscallError::ENODEV,
+        PerfError::BadDescriptor => SyscallError::EBADF,
+        PerfError::Busy => SyscallError::EBUSY,
+        PerfError::OutOfMemory => SyscallError::ENOMEM,
+    }
+}
+
+/// Open a performance counter
+///
+/// `perf_event_open(attr, pid, cpu, group_fd, flags)`: `pid` 0 is the
+/// calling process, -1 any process (with `cpu` >= 0); `cpu` -1 any CPU.
+/// Groups are not supported. Events that count kernel mode are refused
+/// under `confidentiality` lockdown.
+fn sys_perf_event_open(args: SyscallArgs) -> SyscallResult {
+    let attr = args.arg1;
+    let pid = args.arg2 as i32;
+    let cpu = args.arg3 as i32;
+    let group_fd = args.arg4 as i32;
+    let flags = args.arg5;
+    
+    if attr == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    if group_fd != -1 || flags != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    
+    // SAFETY: `PerfEventAttr` is plain integers
+    let attr: PerfEventAttr = unsafe { uaccess::read_user(attr) }?;
+    if attr.flags & attr_flags::EXCLUDE_KERNEL == 0 {
+        lockdown::check(LockdownReason::PerfKernel).map_err(|_| SyscallError::EPERM)?;
+    }
+    let pid = if pid == 0 { sys_getpid(args)? as i32 } else { pid };
+    let target = PerfTarget::from_args(pid, cpu).map_err(perf_errno)?;
+    
+    with_perf(|table| table.open(&attr, target, perf_now())).map(|fd| (fd + PERF_FD_BASE) as u64)
+}
+
+fn perf_read(fd: i32, buf: u64, count: usize) -> SyscallResult {
+    if buf == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    let value = with_perf(|table| table.read(fd, perf_now()))?;
+    read_to_user(buf, count, |buf| value.write_to(buf).ok_or(SyscallError::ENOSPC))
+}
+
+/// Map the ring of a sampling event: one control page plus a power of two
+/// data pages
+fn perf_mmap(fd: i32, len: u64) -> SyscallResult {
+    let pages = len as usize / PAGE_SIZE;
+    if len as usize % PAGE_SIZE != 0 || pages < 2 {
+        return Err(SyscallError::EINVAL);
+    }
+    with_perf(|table| table.mmap(fd, pages - 1).map(|ring| ring.as_ptr() as u64))
+}
+
 /// Global syscall table
+///
+/// Read-only once the kernel is locked down
+/// (`helix_hal::kernel_protect::lockdown`).
+#[link_section = ".data..ro_after_init"]
 pub static SYSCALL_TABLE: SyscallTable = SyscallTable::new();
 
 /// Initialize syscall subsystem
@@ -555,6 +951,10 @@ fn test_syscall_from_num() {
         assert_eq!(Syscall::from_num(0), Some(Syscall::Read));
         assert_eq!(Syscall::from_num(1), Some(Syscall::Write));
         assert_eq!(Syscall::from_num(60), Some(Syscall::Exit));
+        assert_eq!(Syscall::from_num(298), Some(Syscall::PerfEventOpen));
+        assert_eq!(Syscall::from_num(217), Some(Syscall::Getdents64));
+        assert_eq!(Syscall::from_num(1005), Some(Syscall::HelixCtl));
+        assert_eq!(Syscall::from_num(426), Some(Syscall::IoUringEnter));
         assert_eq!(Syscall::from_num(9999), None);
     }
 
@@ -563,4 +963,57 @@ fn test_syscall_error() {
         assert_eq!(SyscallError::ENOENT.to_errno(), -2);
         assert_eq!(SyscallError::EINVAL.to_errno(), -22);
     }
+
+    #[test]
+    fn test_perf_fd_range() {
+        assert_eq!(perf_fd(PERF_FD_BASE + 3), Some(3));
+        assert_eq!(perf_fd(3), None);
+        assert_eq!(perf_fd(-1), None);
+        assert_eq!(perf_errno(PerfError::BadDescriptor), SyscallError::EBADF);
+    }
+
+    #[test]
+    fn test_lockdown_reason() {
+        assert_eq!(lockdown_reason("/dev/mem", 0), Some(LockdownReason::RawMemory));
+        assert_eq!(lockdown_reason("/proc/kcore", 0), Some(LockdownReason::KernelRead));
+        assert_eq!(lockdown_reason("/sys/kernel/debug/gpio", 0), Some(LockdownReason::Debugfs));
+        assert_eq!(lockdown_reason("/sys/kernel/debug/gpio", 2), Some(LockdownReason::DebugfsWrite));
+        assert_eq!(lockdown_reason("/sys/kernel/tracing/trace", 0), None);
+        assert_eq!(lockdown_reason("/sys/kernel/tracing/tracing_on", 1), Some(LockdownReason::TracingWrite));
+        assert_eq!(lockdown_reason("/sys/kernel/debugger", 1), None);
+        assert_eq!(lockdown_reason(EVENTS_PATH, 2), None);
+
+        // Spellings of the same file
+        assert_eq!(lockdown_reason("/dev//mem", 0), Some(LockdownReason::RawMemory));
+        assert_eq!(lockdown_reason("/dev/./mem", 0), Some(LockdownReason::RawMemory));
+        assert_eq!(lockdown_reason("/dev/../dev/kmem", 0), Some(LockdownReason::RawMemory));
+        assert_eq!(lockdown_reason("//dev/port/", 0), Some(LockdownReason::RawMemory));
+        assert_eq!(lockdown_reason("/../../dev/mem", 0), Some(LockdownReason::RawMemory));
+        assert_eq!(lockdown_reason("dev/mem", 0), Some(LockdownReason::RawMemory));
+        assert_eq!(lockdown_reason("/proc/self/../kcore", 0), Some(LockdownReason::KernelRead));
+        assert_eq!(lockdown_reason("/sys/kernel//debug", 2), Some(LockdownReason::DebugfsWrite));
+        assert_eq!(lockdown_reason("/dev/mem/../null", 0), None);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/"), "/");
+        assert_eq!(normalize_path(""), "/");
+        assert_eq!(normalize_path("/.."), "/");
+        assert_eq!(normalize_path("/a/./b//c/../d/"), "/a/b/d");
+    }
+
+    #[test]
+    fn test_syscall_table_init() {
+        let table = SyscallTable::new();
+        let getppid = Syscall::Getppid as u64;
+        assert_eq!(table.handle(getppid, SyscallArgs::default()), Err(SyscallError::ENOSYS));
+
+        table.init();
+        table.init();
+        let before = table.get_count(Syscall::Getppid);
+        assert_eq!(table.handle(getppid, SyscallArgs::default()), Ok(0));
+        assert_eq!(table.get_count(Syscall::Getppid), before + 1);
+        assert_eq!(table.handle(HANDLER_SLOTS as u64, SyscallArgs::default()), Err(SyscallError::ENOSYS));
+    }
 }
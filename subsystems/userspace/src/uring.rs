@@ -0,0 +1,837 @@
+//! # Asynchronous I/O Rings
+//!
+//! An io_uring-style interface: a submission queue (SQ) and a completion
+//! queue (CQ) in memory shared with the process, so a server can keep many
+//! operations in flight and reap their results without a syscall each.
+//!
+//! ```text
+//! io_uring_setup(entries, params) -> fd
+//! mmap(0, params.ring_size, .., fd, 0) -> ring
+//! io_uring_enter(fd, to_submit, min_complete, flags) -> submitted
+//! ```
+//!
+//! ## Ring Layout
+//!
+//! One mapping holds everything; [`UringParams`] reports the offsets:
+//!
+//! ```text
+//! 0x00            Header: SQ head/tail/mask/entries/flags/dropped,
+//!                 CQ head/tail/mask/entries/overflow (u32 each)
+//! sq_off.sqes     Submission entries (64 bytes each)
+//! cq_off.cqes     Completion entries (16 bytes each)
+//! ```
+//!
+//! The process fills `sqes[tail & mask]` and then advances the SQ tail;
+//! the kernel advances the SQ head as it consumes entries. Completions are
+//! written at `cqes[tail & mask]` before the CQ tail moves; the process
+//! advances the CQ head. Unlike io_uring there is no SQ index array.
+//!
+//! ## Operations
+//!
+//! `NOP`, `READ`, `WRITE`, `FSYNC` and `ACCEPT`, with io_uring's opcode
+//! numbers. Files go through the registered [`RingFiles`] backend (the
+//! VFS descriptor table) and sockets through [`RingSockets`] (the network
+//! stack). An operation whose backend answers `EAGAIN` is parked and
+//! retried on every later pass, so a read on an idle socket or an accept
+//! without a pending connection completes when data arrives. Completions
+//! are posted in completion order, not submission order.
+//!
+//! ## Polling Mode
+//!
+//! With [`setup::SQPOLL`], a kernel thread calls [`poll_rings`] and
+//! submissions need no syscall at all. After `sq_thread_idle` idle passes
+//! the poller stops looking at the ring and sets [`sq_flags::NEED_WAKEUP`];
+//! the process then calls `io_uring_enter` with [`enter::SQ_WAKEUP`].
+//!
+//! Descriptors live above [`URING_FD_BASE`], below the event range.
+
+use alloc::alloc::{alloc_zeroed, dealloc, Layout};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicU32, Ordering};
+use helix_hal::uaccess::{self, UserCopy};
+use spin::{Mutex, RwLock};
+
+use super::events::EVENTS_FD_BASE;
+use super::syscalls::{read_to_user, read_user_buf, SyscallArgs, SyscallError, SyscallResult};
+
+/// First descriptor number handed out for rings
+pub const URING_FD_BASE: i32 = 1 << 16;
+
+/// Largest submission queue
+pub const MAX_ENTRIES: u32 = 4096;
+
+/// Page size of the ring mapping
+const PAGE_SIZE: usize = 4096;
+
+/// Default idle passes before an SQPOLL ring needs a wakeup
+const DEFAULT_SQ_THREAD_IDLE: u32 = 1000;
+
+// =============================================================================
+// ABI
+// =============================================================================
+
+/// Operation codes (io_uring numbering)
+pub mod op {
+    /// Complete immediately with 0
+    pub const NOP: u8 = 0;
+    /// Flush a file (`op_flags`: [`super::FSYNC_DATASYNC`])
+    pub const FSYNC: u8 = 3;
+    /// Accept a connection; the result is the new descriptor
+    pub const ACCEPT: u8 = 13;
+    /// Read `len` bytes at `off` into `addr`
+    pub const READ: u8 = 22;
+    /// Write `len` bytes from `addr` at `off`
+    pub const WRITE: u8 = 23;
+}
+
+/// `off` value meaning "at the current file position"
+pub const OFFSET_CURRENT: u64 = u64::MAX;
+
+/// `FSYNC` flag: data only, skip metadata not needed to read it back
+pub const FSYNC_DATASYNC: u32 = 1;
+
+/// `io_uring_setup` flags
+pub mod setup {
+    /// Kernel thread polls the submission queue
+    pub const SQPOLL: u32 = 1 << 1;
+}
+
+/// Submission queue flags (kernel-written)
+pub mod sq_flags {
+    /// The poller went idle; wake it with `io_uring_enter`
+    pub const NEED_WAKEUP: u32 = 1 << 0;
+}
+
+/// `io_uring_enter` flags
+pub mod enter {
+    /// Wait for `min_complete` completions
+    pub const GETEVENTS: u32 = 1 << 0;
+    /// Wake an idle poller
+    pub const SQ_WAKEUP: u32 = 1 << 1;
+}
+
+/// Submission queue entry
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sqe {
+    /// Operation ([`op`])
+    pub opcode: u8,
+    /// Entry flags (none supported yet; must be 0)
+    pub flags: u8,
+    /// I/O priority
+    pub ioprio: u16,
+    /// Target descriptor
+    pub fd: i32,
+    /// File offset, or [`OFFSET_CURRENT`]
+    pub off: u64,
+    /// Buffer address
+    pub addr: u64,
+    /// Buffer length
+    pub len: u32,
+    /// Operation flags (`fsync` / `accept` flags)
+    pub op_flags: u32,
+    /// Copied to the completion
+    pub user_data: u64,
+    /// Reserved
+    pub pad: [u64; 3],
+}
+
+/// Completion queue entry
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cqe {
+    /// From the submission
+    pub user_data: u64,
+    /// Result, or a negative errno
+    pub res: i32,
+    /// Reserved
+    pub flags: u32,
+}
+
+/// Submission queue offsets in the mapping
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SqOffsets {
+    /// Head (kernel-written)
+    pub head: u32,
+    /// Tail (process-written)
+    pub tail: u32,
+    /// Index mask
+    pub ring_mask: u32,
+    /// Entry count
+    pub ring_entries: u32,
+    /// [`sq_flags`]
+    pub flags: u32,
+    /// Invalid entries skipped
+    pub dropped: u32,
+    /// Entry array
+    pub sqes: u32,
+}
+
+/// Completion queue offsets in the mapping
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CqOffsets {
+    /// Head (process-written)
+    pub head: u32,
+    /// Tail (kernel-written)
+    pub tail: u32,
+    /// Index mask
+    pub ring_mask: u32,
+    /// Entry count
+    pub ring_entries: u32,
+    /// Completions that had to wait for CQ space
+    pub overflow: u32,
+    /// Entry array
+    pub cqes: u32,
+}
+
+/// `io_uring_setup` parameters; `flags` and `sq_thread_idle` are inputs,
+/// the rest is filled in by the kernel
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UringParams {
+    /// SQ entries
+    pub sq_entries: u32,
+    /// CQ entries
+    pub cq_entries: u32,
+    /// [`setup`] flags
+    pub flags: u32,
+    /// Idle poller passes before [`sq_flags::NEED_WAKEUP`] (0: default)
+    pub sq_thread_idle: u32,
+    /// Bytes to map
+    pub ring_size: u32,
+    /// Reserved
+    pub resv: u32,
+    /// Submission queue offsets
+    pub sq_off: SqOffsets,
+    /// Completion queue offsets
+    pub cq_off: CqOffsets,
+}
+
+// SAFETY: `repr(C)` integers without padding
+unsafe impl UserCopy for UringParams {}
+
+/// Header word offsets
+mod hdr {
+    pub const SQ_HEAD: usize = 0x00;
+    pub const SQ_TAIL: usize = 0x04;
+    pub const SQ_MASK: usize = 0x08;
+    pub const SQ_ENTRIES: usize = 0x0C;
+    pub const SQ_FLAGS: usize = 0x10;
+    pub const SQ_DROPPED: usize = 0x14;
+    pub const CQ_HEAD: usize = 0x18;
+    pub const CQ_TAIL: usize = 0x1C;
+    pub const CQ_MASK: usize = 0x20;
+    pub const CQ_ENTRIES: usize = 0x24;
+    pub const CQ_OVERFLOW: usize = 0x28;
+    /// Header size; the SQE array follows
+    pub const SIZE: usize = 0x40;
+}
+
+// =============================================================================
+// Backends
+// =============================================================================
+
+/// File operations, served by the VFS descriptor table
+///
+/// Return `EAGAIN` when the operation would block; it is retried later.
+pub trait RingFiles: Send + Sync {
+    /// Read into `buf` at `offset` (`None`: current position)
+    fn read(&self, fd: i32, buf: &mut [u8], offset: Option<u64>) -> Result<usize, SyscallError>;
+    /// Write `buf` at `offset` (`None`: current position)
+    fn write(&self, fd: i32, buf: &[u8], offset: Option<u64>) -> Result<usize, SyscallError>;
+    /// Flush the file to stable storage
+    fn fsync(&self, fd: i32, datasync: bool) -> Result<(), SyscallError>;
+}
+
+/// Socket operations, served by the network stack
+pub trait RingSockets: Send + Sync {
+    /// Accept a pending connection on listening socket `fd`; returns the
+    /// connected descriptor, `EAGAIN` if none is pending
+    fn accept(&self, fd: i32, flags: u32) -> Result<i32, SyscallError>;
+}
+
+/// Registered backends
+struct Backends {
+    files: Option<&'static dyn RingFiles>,
+    sockets: Option<&'static dyn RingSockets>,
+}
+
+static BACKENDS: RwLock<Backends> = RwLock::new(Backends {
+    files: None,
+    sockets: None,
+});
+
+/// Serve file operations with `backend`
+pub fn register_files(backend: &'static dyn RingFiles) {
+    BACKENDS.write().files = Some(backend);
+}
+
+/// Serve socket operations with `backend`
+pub fn register_sockets(backend: &'static dyn RingSockets) {
+    BACKENDS.write().sockets = Some(backend);
+}
+
+/// Lets `io_uring_enter` give up the CPU while waiting for completions
+static WAIT: RwLock<Option<fn()>> = RwLock::new(None);
+
+/// Yield with `wait` while `io_uring_enter` waits for completions
+///
+/// Without it, `GETEVENTS` retries parked operations once and returns.
+pub fn set_wait(wait: fn()) {
+    *WAIT.write() = Some(wait);
+}
+
+// =============================================================================
+// Ring
+// =============================================================================
+
+/// Zeroed, page-aligned ring mapping
+struct RingMemory {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// SAFETY: the memory is only accessed through atomics and volatile copies
+unsafe impl Send for RingMemory {}
+
+impl RingMemory {
+    fn new(size: usize) -> Result<Self, SyscallError> {
+        let layout = Layout::from_size_align(size, PAGE_SIZE).map_err(|_| SyscallError::EINVAL)?;
+        // SAFETY: `size` is non-zero
+        let ptr = unsafe { alloc_zeroed(layout) };
+        let ptr = NonNull::new(ptr).ok_or(SyscallError::ENOMEM)?;
+        Ok(Self { ptr, layout })
+    }
+
+    fn word(&self, offset: usize) -> &AtomicU32 {
+        debug_assert!(offset % 4 == 0 && offset + 4 <= hdr::SIZE);
+        // SAFETY: aligned and inside the header
+        unsafe { &*(self.ptr.as_ptr().add(offset) as *const AtomicU32) }
+    }
+}
+
+impl Drop for RingMemory {
+    fn drop(&mut self) {
+        // SAFETY: allocated in `new` with this layout
+        unsafe { dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+/// An open ring
+struct Ring {
+    mem: RingMemory,
+    params: UringParams,
+    /// Operations that answered `EAGAIN`
+    parked: VecDeque<Sqe>,
+    /// Completions waiting for CQ space
+    overflow: VecDeque<Cqe>,
+    /// Consecutive poller passes without work
+    idle: u32,
+}
+
+impl Ring {
+    fn new(entries: u32, flags: u32, sq_thread_idle: u32) -> Result<Self, SyscallError> {
+        if entries == 0 || entries > MAX_ENTRIES || flags & !setup::SQPOLL != 0 {
+            return Err(SyscallError::EINVAL);
+        }
+        let sq_entries = entries.next_power_of_two();
+        let cq_entries = sq_entries * 2;
+
+        let sqes = hdr::SIZE;
+        let cqes = sqes + sq_entries as usize * core::mem::size_of::<Sqe>();
+        let size = (cqes + cq_entries as usize * core::mem::size_of::<Cqe>()).next_multiple_of(PAGE_SIZE);
+
+        let params = UringParams {
+            sq_entries,
+            cq_entries,
+            flags,
+            sq_thread_idle: match sq_thread_idle {
+                0 => DEFAULT_SQ_THREAD_IDLE,
+                n => n,
+            },
+            ring_size: size as u32,
+            resv: 0,
+            sq_off: SqOffsets {
+                head: hdr::SQ_HEAD as u32,
+                tail: hdr::SQ_TAIL as u32,
+                ring_mask: hdr::SQ_MASK as u32,
+                ring_entries: hdr::SQ_ENTRIES as u32,
+                flags: hdr::SQ_FLAGS as u32,
+                dropped: hdr::SQ_DROPPED as u32,
+                sqes: sqes as u32,
+            },
+            cq_off: CqOffsets {
+                head: hdr::CQ_HEAD as u32,
+                tail: hdr::CQ_TAIL as u32,
+                ring_mask: hdr::CQ_MASK as u32,
+                ring_entries: hdr::CQ_ENTRIES as u32,
+                overflow: hdr::CQ_OVERFLOW as u32,
+                cqes: cqes as u32,
+            },
+        };
+
+        let mem = RingMemory::new(size)?;
+        mem.word(hdr::SQ_MASK).store(sq_entries - 1, Ordering::Relaxed);
+        mem.word(hdr::SQ_ENTRIES).store(sq_entries, Ordering::Relaxed);
+        mem.word(hdr::CQ_MASK).store(cq_entries - 1, Ordering::Relaxed);
+        mem.word(hdr::CQ_ENTRIES).store(cq_entries, Ordering::Relaxed);
+
+        Ok(Self {
+            mem,
+            params,
+            parked: VecDeque::new(),
+            overflow: VecDeque::new(),
+            idle: 0,
+        })
+    }
+
+    fn is_sqpoll(&self) -> bool {
+        self.params.flags & setup::SQPOLL != 0
+    }
+
+    /// Completions the process has not reaped yet
+    fn cq_ready(&self) -> u32 {
+        let head = self.mem.word(hdr::CQ_HEAD).load(Ordering::Acquire);
+        self.mem.word(hdr::CQ_TAIL).load(Ordering::Relaxed).wrapping_sub(head)
+    }
+
+    /// Post a completion, or hold it until the CQ has room
+    fn complete(&mut self, cqe: Cqe) {
+        if !self.overflow.is_empty() || !self.post(cqe) {
+            self.overflow.push_back(cqe);
+            self.mem.word(hdr::CQ_OVERFLOW).fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn post(&self, cqe: Cqe) -> bool {
+        if self.cq_ready() >= self.params.cq_entries {
+            return false;
+        }
+        let tail = self.mem.word(hdr::CQ_TAIL).load(Ordering::Relaxed);
+        let index = (tail & (self.params.cq_entries - 1)) as usize;
+        let offset = self.params.cq_off.cqes as usize + index * core::mem::size_of::<Cqe>();
+        // SAFETY: inside the CQE array
+        unsafe { (self.mem.ptr.as_ptr().add(offset) as *mut Cqe).write_volatile(cqe) };
+        self.mem.word(hdr::CQ_TAIL).store(tail.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    fn flush_overflow(&mut self) {
+        while let Some(&cqe) = self.overflow.front() {
+            if !self.post(cqe) {
+                break;
+            }
+            self.overflow.pop_front();
+        }
+    }
+
+    /// Consume up to `limit` submissions; returns how many were taken
+    ///
+    /// Stops early while too many operations are in flight to be sure of
+    /// CQ space, leaving the rest in the SQ.
+    fn submit(&mut self, limit: u32) -> u32 {
+        let head = self.mem.word(hdr::SQ_HEAD).load(Ordering::Relaxed);
+        let tail = self.mem.word(hdr::SQ_TAIL).load(Ordering::Acquire);
+        let available = tail.wrapping_sub(head);
+        if available > self.params.sq_entries {
+            // Corrupt tail: drop everything the process claims to have queued
+            self.mem.word(hdr::SQ_DROPPED).fetch_add(available, Ordering::Relaxed);
+            self.mem.word(hdr::SQ_HEAD).store(tail, Ordering::Release);
+            return 0;
+        }
+
+        let mut taken = 0;
+        while taken < available.min(limit) {
+            if self.parked.len() + self.overflow.len() >= self.params.cq_entries as usize {
+                break;
+            }
+            let index = (head.wrapping_add(taken) & (self.params.sq_entries - 1)) as usize;
+            let offset = self.params.sq_off.sqes as usize + index * core::mem::size_of::<Sqe>();
+            // SAFETY: inside the SQE array
+            let sqe = unsafe { (self.mem.ptr.as_ptr().add(offset) as *const Sqe).read_volatile() };
+            taken += 1;
+            self.mem.word(hdr::SQ_HEAD).store(head.wrapping_add(taken), Ordering::Release);
+
+            match execute(&sqe) {
+                Some(res) => self.complete(Cqe { user_data: sqe.user_data, res, flags: 0 }),
+                None => self.parked.push_back(sqe),
+            }
+        }
+        taken
+    }
+
+    /// Retry parked operations; returns how many completed
+    fn retry(&mut self) -> usize {
+        let mut done = 0;
+        for _ in 0..self.parked.len() {
+            let Some(sqe) = self.parked.pop_front() else { break };
+            match execute(&sqe) {
+                Some(res) => {
+                    self.complete(Cqe { user_data: sqe.user_data, res, flags: 0 });
+                    done += 1;
+                }
+                None => self.parked.push_back(sqe),
+            }
+        }
+        done
+    }
+}
+
+/// Run one operation; `None` if it would block
+fn execute(sqe: &Sqe) -> Option<i32> {
+    match run(sqe) {
+        Err(SyscallError::EAGAIN) => None,
+        Ok(value) => Some(value),
+        Err(error) => Some(-(error as i32)),
+    }
+}
+
+fn run(sqe: &Sqe) -> Result<i32, SyscallError> {
+    if sqe.flags != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let backends = BACKENDS.read();
+    let offset = (sqe.off != OFFSET_CURRENT).then_some(sqe.off);
+    let len = sqe.len.min(i32::MAX as u32) as usize;
+
+    match sqe.opcode {
+        op::NOP => Ok(0),
+        op::READ | op::WRITE if sqe.addr == 0 && len != 0 => Err(SyscallError::EFAULT),
+        op::READ => {
+            let files = backends.files.ok_or(SyscallError::ENOSYS)?;
+            read_to_user(sqe.addr, len, |buf| files.read(sqe.fd, buf, offset)).map(|n| n as i32)
+        }
+        op::WRITE => {
+            let files = backends.files.ok_or(SyscallError::ENOSYS)?;
+            let buf = read_user_buf(sqe.addr, len)?;
+            files.write(sqe.fd, &buf, offset).map(|n| n as i32)
+        }
+        op::FSYNC => {
+            let files = backends.files.ok_or(SyscallError::ENOSYS)?;
+            files.fsync(sqe.fd, sqe.op_flags & FSYNC_DATASYNC != 0).map(|()| 0)
+        }
+        op::ACCEPT => {
+            let sockets = backends.sockets.ok_or(SyscallError::ENOSYS)?;
+            sockets.accept(sqe.fd, sqe.op_flags)
+        }
+        _ => Err(SyscallError::EINVAL),
+    }
+}
+
+// =============================================================================
+// Descriptors
+// =============================================================================
+
+/// Open rings, indexed by `fd - URING_FD_BASE`
+static RINGS: Mutex<Vec<Option<Arc<Mutex<Ring>>>>> = Mutex::new(Vec::new());
+
+/// Slot of a user descriptor, if it is a ring descriptor
+pub(crate) fn uring_fd(fd: i32) -> Option<usize> {
+    (URING_FD_BASE..EVENTS_FD_BASE).contains(&fd).then(|| (fd - URING_FD_BASE) as usize)
+}
+
+fn lookup(slot: usize) -> Result<Arc<Mutex<Ring>>, SyscallError> {
+    RINGS.lock().get(slot).and_then(Option::clone).ok_or(SyscallError::EBADF)
+}
+
+/// Create a ring; returns its descriptor and parameters
+pub fn setup(entries: u32, params: &UringParams) -> Result<(i32, UringParams), SyscallError> {
+    let ring = Ring::new(entries, params.flags, params.sq_thread_idle)?;
+    let params = ring.params;
+
+    let mut rings = RINGS.lock();
+    let slot = match rings.iter().position(Option::is_none) {
+        Some(slot) => slot,
+        None => {
+            rings.push(None);
+            rings.len() - 1
+        }
+    };
+    if URING_FD_BASE + slot as i32 >= EVENTS_FD_BASE {
+        return Err(SyscallError::EMFILE);
+    }
+    rings[slot] = Some(Arc::new(Mutex::new(ring)));
+    Ok((URING_FD_BASE + slot as i32, params))
+}
+
+/// Submit and reap; returns the number of submissions consumed
+pub fn enter(slot: usize, to_submit: u32, min_complete: u32, flags: u32) -> Result<u32, SyscallError> {
+    if flags & !(enter::GETEVENTS | enter::SQ_WAKEUP) != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let shared = lookup(slot)?;
+    let mut ring = shared.lock();
+
+    let submitted = if ring.is_sqpoll() {
+        if flags & enter::SQ_WAKEUP != 0 {
+            ring.idle = 0;
+            ring.mem.word(hdr::SQ_FLAGS).fetch_and(!sq_flags::NEED_WAKEUP, Ordering::Release);
+        }
+        to_submit
+    } else {
+        ring.flush_overflow();
+        let submitted = ring.submit(to_submit);
+        ring.retry();
+        submitted
+    };
+
+    if flags & enter::GETEVENTS != 0 {
+        let wait = *WAIT.read();
+        while ring.cq_ready() < min_complete && !(ring.parked.is_empty() && ring.overflow.is_empty()) {
+            ring.flush_overflow();
+            if ring.retry() == 0 {
+                let Some(wait) = wait else { break };
+                drop(ring);
+                wait();
+                // Closed while waiting
+                if !lookup(slot).is_ok_and(|current| Arc::ptr_eq(&current, &shared)) {
+                    return Err(SyscallError::EBADF);
+                }
+                ring = shared.lock();
+            }
+        }
+    }
+    Ok(submitted)
+}
+
+/// Address of the ring mapping, checking the requested length
+pub(crate) fn mmap(slot: usize, len: u64) -> SyscallResult {
+    let ring = lookup(slot)?;
+    let ring = ring.lock();
+    if len == 0 || len > ring.params.ring_size as u64 {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(ring.mem.ptr.as_ptr() as u64)
+}
+
+/// Tear down a ring; parked operations are dropped
+pub(crate) fn close(slot: usize) -> Result<(), SyscallError> {
+    let mut rings = RINGS.lock();
+    rings.get_mut(slot).and_then(Option::take).map(drop).ok_or(SyscallError::EBADF)
+}
+
+/// One pass of the kernel submission poller over every `SQPOLL` ring
+///
+/// Called in a loop by the poller thread; returns the submissions and
+/// completions handled, so the thread can back off when it sees 0.
+pub fn poll_rings() -> usize {
+    let rings: Vec<_> = RINGS.lock().iter().flatten().cloned().collect();
+    let mut work = 0;
+    for ring in rings {
+        let mut ring = ring.lock();
+        let asleep = ring.mem.word(hdr::SQ_FLAGS).load(Ordering::Acquire) & sq_flags::NEED_WAKEUP != 0;
+        if !ring.is_sqpoll() || asleep {
+            continue;
+        }
+        ring.flush_overflow();
+        let done = ring.submit(u32::MAX) as usize + ring.retry();
+        if done == 0 {
+            ring.idle += 1;
+            if ring.idle >= ring.params.sq_thread_idle && ring.parked.is_empty() {
+                ring.mem.word(hdr::SQ_FLAGS).fetch_or(sq_flags::NEED_WAKEUP, Ordering::Release);
+            }
+        } else {
+            ring.idle = 0;
+        }
+        work += done;
+    }
+    work
+}
+
+// =============================================================================
+// Syscalls
+// =============================================================================
+
+/// `io_uring_setup(entries, params)`
+pub fn sys_io_uring_setup(args: SyscallArgs) -> SyscallResult {
+    let entries = args.arg1 as u32;
+    let params = args.arg2;
+    if params == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+
+    let input: UringParams = uaccess::get_user(params)?;
+    let (fd, output) = setup(entries, &input)?;
+    uaccess::put_user(params, &output)?;
+    Ok(fd as u64)
+}
+
+/// `io_uring_enter(fd, to_submit, min_complete, flags)`
+pub fn sys_io_uring_enter(args: SyscallArgs) -> SyscallResult {
+    let slot = uring_fd(args.arg1 as i32).ok_or(SyscallError::EBADF)?;
+    enter(slot, args.arg2 as u32, args.arg3 as u32, args.arg4 as u32).map(u64::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicBool;
+
+    /// Files backend over one in-memory file (fd 3); fd 4 is a pipe that
+    /// only has data once `READY` is set
+    struct MemFiles;
+
+    static FILE: Mutex<[u8; 64]> = Mutex::new([0; 64]);
+    static READY: AtomicBool = AtomicBool::new(false);
+
+    impl RingFiles for MemFiles {
+        fn read(&self, fd: i32, buf: &mut [u8], offset: Option<u64>) -> Result<usize, SyscallError> {
+            match fd {
+                3 => {
+                    let file = FILE.lock();
+                    let start = offset.unwrap_or(0) as usize;
+                    let n = buf.len().min(file.len().saturating_sub(start));
+                    buf[..n].copy_from_slice(&file[start..start + n]);
+                    Ok(n)
+                }
+                4 if READY.load(Ordering::Relaxed) => {
+                    buf[0] = b'!';
+                    Ok(1)
+                }
+                4 => Err(SyscallError::EAGAIN),
+                _ => Err(SyscallError::EBADF),
+            }
+        }
+
+        fn write(&self, fd: i32, buf: &[u8], offset: Option<u64>) -> Result<usize, SyscallError> {
+            if fd != 3 {
+                return Err(SyscallError::EBADF);
+            }
+            let mut file = FILE.lock();
+            let start = offset.unwrap_or(0) as usize;
+            file[start..start + buf.len()].copy_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn fsync(&self, _fd: i32, _datasync: bool) -> Result<(), SyscallError> {
+            Ok(())
+        }
+    }
+
+    static FILES: MemFiles = MemFiles;
+
+    /// Process-side view of a mapped ring
+    struct User {
+        base: *mut u8,
+        params: UringParams,
+    }
+
+    impl User {
+        fn word(&self, offset: u32) -> &AtomicU32 {
+            // SAFETY: header word of the live mapping
+            unsafe { &*(self.base.add(offset as usize) as *const AtomicU32) }
+        }
+
+        fn push(&self, sqe: Sqe) {
+            let tail = self.word(self.params.sq_off.tail).load(Ordering::Relaxed);
+            let index = tail & (self.params.sq_entries - 1);
+            let at = self.params.sq_off.sqes as usize + index as usize * 64;
+            // SAFETY: inside the SQE array
+            unsafe { (self.base.add(at) as *mut Sqe).write(sqe) };
+            self.word(self.params.sq_off.tail).store(tail + 1, Ordering::Release);
+        }
+
+        fn pop(&self) -> Option<Cqe> {
+            let head = self.word(self.params.cq_off.head).load(Ordering::Relaxed);
+            if head == self.word(self.params.cq_off.tail).load(Ordering::Acquire) {
+                return None;
+            }
+            let index = head & (self.params.cq_entries - 1);
+            let at = self.params.cq_off.cqes as usize + index as usize * 16;
+            // SAFETY: inside the CQE array
+            let cqe = unsafe { (self.base.add(at) as *const Cqe).read() };
+            self.word(self.params.cq_off.head).store(head + 1, Ordering::Release);
+            Some(cqe)
+        }
+    }
+
+    fn sqe(opcode: u8, fd: i32, off: u64, buf: &mut [u8], user_data: u64) -> Sqe {
+        Sqe {
+            opcode,
+            fd,
+            off,
+            addr: buf.as_mut_ptr() as u64,
+            len: buf.len() as u32,
+            user_data,
+            ..Default::default()
+        }
+    }
+
+    fn open(entries: u32, flags: u32) -> (usize, User) {
+        let input = UringParams { flags, sq_thread_idle: 2, ..Default::default() };
+        let (fd, params) = setup(entries, &input).unwrap();
+        let slot = uring_fd(fd).unwrap();
+        let base = mmap(slot, params.ring_size as u64).unwrap() as *mut u8;
+        (slot, User { base, params })
+    }
+
+    #[test]
+    fn test_setup() {
+        assert_eq!(core::mem::size_of::<Sqe>(), 64);
+        assert_eq!(core::mem::size_of::<Cqe>(), 16);
+        assert!(setup(0, &UringParams::default()).is_err());
+        assert!(setup(8, &UringParams { flags: 1, ..Default::default() }).is_err());
+
+        let (slot, user) = open(5, 0);
+        assert_eq!(user.params.sq_entries, 8);
+        assert_eq!(user.params.cq_entries, 16);
+        assert_eq!(user.word(user.params.sq_off.ring_mask).load(Ordering::Relaxed), 7);
+        assert_eq!(mmap(slot, user.params.ring_size as u64 + 1), Err(SyscallError::EINVAL));
+        assert_eq!(close(slot), Ok(()));
+        assert_eq!(close(slot), Err(SyscallError::EBADF));
+        assert_eq!(uring_fd(EVENTS_FD_BASE), None);
+    }
+
+    #[test]
+    fn test_submit_and_park() {
+        register_files(&FILES);
+        let (slot, user) = open(4, 0);
+
+        let mut data = *b"hello";
+        let mut back = [0u8; 5];
+        let mut pipe = [0u8; 1];
+        user.push(sqe(op::WRITE, 3, 8, &mut data, 1));
+        user.push(sqe(op::READ, 3, 8, &mut back, 2));
+        user.push(sqe(op::READ, 4, OFFSET_CURRENT, &mut pipe, 3));
+        user.push(sqe(0xEE, 0, 0, &mut [], 4));
+
+        assert_eq!(enter(slot, 4, 0, 0), Ok(4));
+        assert_eq!(user.pop(), Some(Cqe { user_data: 1, res: 5, flags: 0 }));
+        assert_eq!(user.pop(), Some(Cqe { user_data: 2, res: 5, flags: 0 }));
+        assert_eq!(user.pop(), Some(Cqe { user_data: 4, res: -22, flags: 0 }));
+        assert_eq!(user.pop(), None);
+        assert_eq!(&back, b"hello");
+
+        // The parked pipe read completes once data shows up
+        READY.store(true, Ordering::Relaxed);
+        assert_eq!(enter(slot, 0, 1, enter::GETEVENTS), Ok(0));
+        assert_eq!(user.pop(), Some(Cqe { user_data: 3, res: 1, flags: 0 }));
+        assert_eq!(pipe[0], b'!');
+        close(slot).unwrap();
+    }
+
+    #[test]
+    fn test_sqpoll() {
+        let (slot, user) = open(2, setup::SQPOLL);
+        user.push(sqe(op::NOP, 0, 0, &mut [], 7));
+        assert!(poll_rings() >= 1);
+        assert_eq!(user.pop(), Some(Cqe { user_data: 7, res: 0, flags: 0 }));
+
+        // Idle passes put the poller to sleep until woken
+        while user.word(user.params.sq_off.flags).load(Ordering::Relaxed) & sq_flags::NEED_WAKEUP == 0 {
+            poll_rings();
+        }
+        user.push(sqe(op::NOP, 0, 0, &mut [], 8));
+        poll_rings();
+        assert_eq!(user.pop(), None);
+        assert_eq!(enter(slot, 1, 0, enter::SQ_WAKEUP), Ok(1));
+        poll_rings();
+        assert_eq!(user.pop(), Some(Cqe { user_data: 8, res: 0, flags: 0 }));
+        close(slot).unwrap();
+    }
+}
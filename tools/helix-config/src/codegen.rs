@@ -0,0 +1,142 @@
+//! Profile code generation
+//!
+//! Turns a [`ProfileConfig`] into the Rust source included by the profile
+//! crate, and into the `cfg` flags that gate its features and subsystems.
+
+use crate::profile::ProfileConfig;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Crate name to the identifier it is imported as
+pub fn crate_ident(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+fn write_list(out: &mut String, doc: &str, name: &str, items: &[String]) {
+    let _ = writeln!(out, "/// {}", doc);
+    let _ = write!(out, "pub const {}: &[&str] = &[", name);
+    for (i, item) in items.iter().enumerate() {
+        let _ = write!(out, "{}{:?}", if i == 0 { "" } else { ", " }, item);
+    }
+    out.push_str("];\n\n");
+}
+
+/// Source of the profile's configuration module
+///
+/// `linked` holds the crates the profile depends on: static modules among
+/// them are linked in with `extern crate`, which brings their
+/// `#[helix_module]` registrations into the image. The generated
+/// `static_modules()` instantiates them, in `helix.toml` order.
+pub fn generate(config: &ProfileConfig, linked: &BTreeSet<String>) -> String {
+    let mut out = String::new();
+    out.push_str("// Generated by helix-config from helix.toml; do not edit.\n\n");
+
+    let strings = [
+        ("Profile name", "PROFILE_NAME", &config.name),
+        ("Profile version", "PROFILE_VERSION", &config.version),
+        ("Profile description", "PROFILE_DESCRIPTION", &config.description),
+        ("Target class", "TARGET", &config.target),
+        ("Primary architecture", "ARCH", &config.arch),
+        ("Default kernel command line, before the bootloader's", "DEFAULT_CMDLINE", &config.cmdline),
+    ];
+    for (doc, name, value) in strings {
+        let _ = writeln!(out, "/// {}\npub const {}: &str = {:?};\n", doc, name, value);
+    }
+
+    out.push_str("/// Feature switches, in name order\npub const FEATURES: &[(&str, bool)] = &[");
+    for (i, (feature, enabled)) in config.features.iter().enumerate() {
+        let _ = write!(out, "{}({:?}, {})", if i == 0 { "" } else { ", " }, feature, enabled);
+    }
+    out.push_str("];\n\n");
+
+    write_list(&mut out, "Enabled subsystems", "SUBSYSTEMS", &config.subsystems);
+    write_list(&mut out, "Modules linked into the kernel", "STATIC_MODULES", &config.static_modules);
+    write_list(&mut out, "Modules loaded at runtime", "DYNAMIC_MODULES", &config.dynamic_modules);
+    write_list(&mut out, "Early initialization steps", "EARLY_INIT", &config.early_init);
+    write_list(&mut out, "Late initialization steps", "LATE_INIT", &config.late_init);
+
+    for module in config.static_modules.iter().filter(|m| linked.contains(*m)) {
+        let _ = writeln!(out, "extern crate {} as _;", crate_ident(module));
+    }
+    out.push_str(
+        "\n/// Instantiate the linked static modules, in `helix.toml` order\n\
+         pub fn static_modules() -> alloc::vec::Vec<alloc::boxed::Box<dyn helix_modules::v2::ModuleTrait>> {\n    \
+         helix_modules::linked::instantiate(STATIC_MODULES)\n}\n",
+    );
+    out
+}
+
+/// Cargo directives declaring and setting the profile's `cfg` flags
+///
+/// Enabled features become `cfg(helix_feature = "...")` and enabled
+/// subsystems `cfg(helix_subsystem = "...")`; every declared feature is a
+/// known value, so a typo in a `cfg` is a warning.
+pub fn cfg_directives(config: &ProfileConfig) -> Vec<String> {
+    let values = |names: &mut dyn Iterator<Item = &String>| {
+        names.map(|n| format!("{:?}", n)).collect::<Vec<_>>().join(", ")
+    };
+    let mut directives = vec![
+        format!("cargo:rustc-check-cfg=cfg(helix_feature, values({}))", values(&mut config.features.keys())),
+        format!("cargo:rustc-check-cfg=cfg(helix_subsystem, values({}))", values(&mut config.subsystems.iter())),
+    ];
+    for feature in config.enabled_features() {
+        directives.push(format!("cargo:rustc-cfg=helix_feature={:?}", feature));
+    }
+    for subsystem in &config.subsystems {
+        directives.push(format!("cargo:rustc-cfg=helix_subsystem={:?}", subsystem));
+    }
+    directives
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ProfileConfig {
+        ProfileConfig::from_toml(
+            r#"
+[profile]
+name = "minimal"
+[profile.features]
+multicore = false
+serial = true
+[subsystems]
+enabled = ["execution"]
+[modules]
+static = ["helix-scheduler-round-robin", "helix-allocator-buddy"]
+[boot]
+cmdline = "quiet \"loglevel=3\""
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_generate() {
+        let linked = BTreeSet::from(["helix-scheduler-round-robin".to_string()]);
+        let code = generate(&config(), &linked);
+        assert!(code.contains("pub const PROFILE_NAME: &str = \"minimal\";\n"));
+        assert!(code.contains("pub const DEFAULT_CMDLINE: &str = \"quiet \\\"loglevel=3\\\"\";\n"));
+        assert!(code.contains("pub const FEATURES: &[(&str, bool)] = &[(\"multicore\", false), (\"serial\", true)];\n"));
+        assert!(code.contains(
+            "pub const STATIC_MODULES: &[&str] = &[\"helix-scheduler-round-robin\", \"helix-allocator-buddy\"];\n"
+        ));
+        assert!(code.contains("pub const LATE_INIT: &[&str] = &[];\n"));
+        assert!(code.contains("extern crate helix_scheduler_round_robin as _;\n"));
+        assert!(!code.contains("helix_allocator_buddy"));
+        assert!(code.contains("helix_modules::linked::instantiate(STATIC_MODULES)"));
+    }
+
+    #[test]
+    fn test_cfg_directives() {
+        assert_eq!(
+            cfg_directives(&config()),
+            [
+                "cargo:rustc-check-cfg=cfg(helix_feature, values(\"multicore\", \"serial\"))",
+                "cargo:rustc-check-cfg=cfg(helix_subsystem, values(\"execution\"))",
+                "cargo:rustc-cfg=helix_feature=\"serial\"",
+                "cargo:rustc-cfg=helix_subsystem=\"execution\"",
+            ]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! Configuration error types
+
+use std::fmt;
+
+/// Error reading a profile configuration
+#[derive(Debug)]
+pub enum ConfigError {
+    /// File could not be read (path, reason)
+    Io(String, std::io::Error),
+    /// Syntax error (line, message)
+    Parse(usize, String),
+    /// Required key is missing
+    Missing(&'static str),
+    /// Key has the wrong type (key, expected type)
+    Type(String, &'static str),
+    /// Value is not allowed (key, value)
+    Invalid(String, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "cannot read {}: {}", path, err),
+            Self::Parse(line, message) => write!(f, "line {}: {}", line, message),
+            Self::Missing(key) => write!(f, "missing required key '{}'", key),
+            Self::Type(key, expected) => write!(f, "'{}' must be {}", key, expected),
+            Self::Invalid(key, value) => write!(f, "invalid value '{}' for '{}'", value, key),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Configuration result type
+pub type ConfigResult<T> = Result<T, ConfigError>;
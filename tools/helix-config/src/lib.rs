@@ -0,0 +1,113 @@
+//! # Helix Profile Configuration
+//!
+//! Build-time generation of OS profile code from `helix.toml`, so a new
+//! profile is declared rather than hand-written.
+//!
+//! ## Overview
+//!
+//! - **TOML**: dependency-free reader for the subset manifests use
+//! - **Profile**: the `helix.toml` schema (subsystems, modules, features,
+//!   command-line defaults, init order)
+//! - **Codegen**: constants, static module linking and `cfg` flags
+//!
+//! ## Usage
+//!
+//! In the profile's `build.rs` `main`, with `helix-config` as a build
+//! dependency:
+//!
+//! ```rust,no_run
+//! if let Err(err) = helix_config::build_profile() {
+//!     panic!("helix.toml: {}", err);
+//! }
+//! ```
+//!
+//! and in the profile crate:
+//!
+//! ```rust,ignore
+//! mod config {
+//!     include!(concat!(env!("OUT_DIR"), "/helix_config.rs"));
+//! }
+//!
+//! #[cfg(helix_feature = "multicore")]
+//! fn start_aps() { /* ... */ }
+//! ```
+//!
+//! Static modules are linked in only if they are dependencies of the
+//! profile crate; the others are reported as build warnings. Each one
+//! registers itself with `#[helix_module]` (see `helix_modules::linked`).
+
+#![deny(unsafe_code)]
+
+// ============================================================================
+// MODULES
+// ============================================================================
+
+/// Error types
+pub mod error;
+
+/// Minimal TOML reader
+pub mod toml;
+
+/// `helix.toml` schema
+pub mod profile;
+
+/// Rust source and `cfg` generation
+pub mod codegen;
+
+// ============================================================================
+// RE-EXPORTS
+// ============================================================================
+
+pub use codegen::{cfg_directives, generate};
+pub use error::{ConfigError, ConfigResult};
+pub use profile::ProfileConfig;
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Profile configuration file, next to the profile's `Cargo.toml`
+pub const CONFIG_FILE: &str = "helix.toml";
+
+/// Generated file, in `OUT_DIR`
+pub const GENERATED_FILE: &str = "helix_config.rs";
+
+fn read(path: &Path) -> ConfigResult<String> {
+    std::fs::read_to_string(path).map_err(|err| ConfigError::Io(path.display().to_string(), err))
+}
+
+/// Crate names in a manifest's `[dependencies]`
+pub fn dependencies(manifest: &toml::Table) -> BTreeSet<String> {
+    toml::lookup(manifest, "dependencies")
+        .and_then(toml::Value::as_table)
+        .map(|deps| deps.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Generate the configuration of the profile being built
+///
+/// Reads `helix.toml` and `Cargo.toml` from `CARGO_MANIFEST_DIR`, writes
+/// [`GENERATED_FILE`] to `OUT_DIR` and prints the `cfg` directives. Must
+/// be called from a build script.
+pub fn build_profile() -> ConfigResult<ProfileConfig> {
+    let env = |name: &str| PathBuf::from(std::env::var_os(name).expect("helix-config runs from build scripts"));
+    let (dir, out_dir) = (env("CARGO_MANIFEST_DIR"), env("OUT_DIR"));
+    println!("cargo:rerun-if-changed={}", CONFIG_FILE);
+    println!("cargo:rerun-if-changed=Cargo.toml");
+
+    let config = ProfileConfig::from_toml(&read(&dir.join(CONFIG_FILE))?)?;
+    let linked = dependencies(&toml::parse(&read(&dir.join("Cargo.toml"))?)?);
+    for module in config.static_modules.iter().filter(|m| !linked.contains(*m)) {
+        println!(
+            "cargo:warning=static module `{}` is not a dependency of profile `{}`; not registered",
+            module, config.name
+        );
+    }
+    for directive in cfg_directives(&config) {
+        println!("{}", directive);
+    }
+
+    let path = out_dir.join(GENERATED_FILE);
+    std::fs::write(&path, generate(&config, &linked))
+        .map_err(|err| ConfigError::Io(path.display().to_string(), err))?;
+    Ok(config)
+}
@@ -0,0 +1,224 @@
+//! Profile configuration (`helix.toml`)
+//!
+//! The parts of `helix.toml` that shape the kernel image:
+//!
+//! ```toml
+//! [profile]
+//! name = "minimal"
+//! version = "1.0.0"
+//! target = "embedded"
+//!
+//! [profile.arch]
+//! primary = "x86_64"
+//!
+//! [profile.features]          # -> cfg(helix_feature = "...")
+//! multicore = false
+//!
+//! [subsystems]                # -> cfg(helix_subsystem = "...")
+//! enabled = ["execution", "memory"]
+//!
+//! [modules]
+//! static = ["helix-scheduler-round-robin"]
+//! dynamic = []
+//!
+//! [boot]
+//! cmdline = "quiet loglevel=3"
+//! early_init = ["console", "memory"]
+//! late_init = ["scheduler"]
+//! ```
+//!
+//! Modules may also be listed as `[modules.static] modules = [...]`.
+//! Other sections are left to the profile.
+
+use crate::error::{ConfigError, ConfigResult};
+use crate::toml::{self, lookup, Table, Value};
+use std::collections::BTreeMap;
+
+/// Configuration of an OS profile
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileConfig {
+    /// Profile name
+    pub name: String,
+    /// Profile version
+    pub version: String,
+    /// One-line description
+    pub description: String,
+    /// Target class (`embedded`, `desktop`, ...)
+    pub target: String,
+    /// Primary architecture
+    pub arch: String,
+    /// Feature switches, by name
+    pub features: BTreeMap<String, bool>,
+    /// Enabled subsystems
+    pub subsystems: Vec<String>,
+    /// Modules linked into the kernel (crate names)
+    pub static_modules: Vec<String>,
+    /// Modules loaded at runtime
+    pub dynamic_modules: Vec<String>,
+    /// Default kernel command line
+    pub cmdline: String,
+    /// Early initialization steps, in order
+    pub early_init: Vec<String>,
+    /// Late initialization steps, in order
+    pub late_init: Vec<String>,
+}
+
+/// Names usable in `cfg` values and generated identifiers
+fn is_config_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Crate names: lowercase, digits, `-` and `_`
+fn is_crate_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+fn string(doc: &Table, key: &'static str) -> ConfigResult<Option<String>> {
+    match lookup(doc, key) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(ConfigError::Type(key.to_string(), "a string")),
+    }
+}
+
+fn strings(doc: &Table, key: &str) -> ConfigResult<Vec<String>> {
+    let Some(value) = lookup(doc, key) else {
+        return Ok(Vec::new());
+    };
+    value
+        .as_array()
+        .and_then(|items| items.iter().map(|v| v.as_str().map(String::from)).collect())
+        .ok_or_else(|| ConfigError::Type(key.to_string(), "an array of strings"))
+}
+
+/// Check every name in `names` with `valid`
+fn validate(key: &str, names: &[String], valid: fn(&str) -> bool) -> ConfigResult<()> {
+    match names.iter().find(|n| !valid(n)) {
+        Some(name) => Err(ConfigError::Invalid(key.to_string(), name.clone())),
+        None => Ok(()),
+    }
+}
+
+impl ProfileConfig {
+    /// Parse `helix.toml` text
+    pub fn from_toml(text: &str) -> ConfigResult<Self> {
+        Self::from_table(&toml::parse(text)?)
+    }
+
+    /// Read the configuration from a parsed document
+    pub fn from_table(doc: &Table) -> ConfigResult<Self> {
+        let name = string(doc, "profile.name")?.ok_or(ConfigError::Missing("profile.name"))?;
+        if !is_config_name(&name) {
+            return Err(ConfigError::Invalid("profile.name".to_string(), name));
+        }
+
+        let mut features = BTreeMap::new();
+        if let Some(value) = lookup(doc, "profile.features") {
+            let table = value
+                .as_table()
+                .ok_or_else(|| ConfigError::Type("profile.features".to_string(), "a table"))?;
+            for (feature, enabled) in table {
+                let key = format!("profile.features.{}", feature);
+                if !is_config_name(feature) {
+                    return Err(ConfigError::Invalid("profile.features".to_string(), feature.clone()));
+                }
+                let enabled = enabled.as_bool().ok_or(ConfigError::Type(key, "a boolean"))?;
+                features.insert(feature.clone(), enabled);
+            }
+        }
+
+        // `static = [...]` or `[modules.static] modules = [...]`
+        let modules = |kind: &str| match lookup(doc, &format!("modules.{}", kind)) {
+            Some(Value::Table(_)) => strings(doc, &format!("modules.{}.modules", kind)),
+            _ => strings(doc, &format!("modules.{}", kind)),
+        };
+
+        let config = Self {
+            name,
+            version: string(doc, "profile.version")?.unwrap_or_else(|| "0.0.0".to_string()),
+            description: string(doc, "profile.description")?.unwrap_or_default(),
+            target: string(doc, "profile.target")?.unwrap_or_default(),
+            arch: string(doc, "profile.arch.primary")?.unwrap_or_else(|| "x86_64".to_string()),
+            features,
+            subsystems: strings(doc, "subsystems.enabled")?,
+            static_modules: modules("static")?,
+            dynamic_modules: modules("dynamic")?,
+            cmdline: string(doc, "boot.cmdline")?.unwrap_or_default(),
+            early_init: strings(doc, "boot.early_init")?,
+            late_init: strings(doc, "boot.late_init")?,
+        };
+        validate("subsystems.enabled", &config.subsystems, is_config_name)?;
+        validate("modules.static", &config.static_modules, is_crate_name)?;
+        validate("modules.dynamic", &config.dynamic_modules, is_crate_name)?;
+        Ok(config)
+    }
+
+    /// Features switched on, in name order
+    pub fn enabled_features(&self) -> impl Iterator<Item = &str> {
+        self.features.iter().filter(|(_, on)| **on).map(|(name, _)| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_config() {
+        let config = ProfileConfig::from_toml(
+            r#"
+[profile]
+name = "myos"
+version = "1.2.0"
+target = "desktop"
+
+[profile.features]
+multicore = true
+graphics = false
+userspace = true
+
+[subsystems]
+enabled = ["execution", "memory"]
+
+[modules.static]
+modules = ["helix-scheduler-cfs"]
+
+[boot]
+cmdline = "console=serial"
+late_init = ["scheduler"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.name, "myos");
+        assert_eq!(config.arch, "x86_64");
+        assert_eq!(config.enabled_features().collect::<Vec<_>>(), ["multicore", "userspace"]);
+        assert_eq!(config.subsystems, ["execution", "memory"]);
+        assert_eq!(config.static_modules, ["helix-scheduler-cfs"]);
+        assert!(config.dynamic_modules.is_empty());
+        assert_eq!(config.cmdline, "console=serial");
+        assert_eq!(config.late_init, ["scheduler"]);
+    }
+
+    #[test]
+    fn test_profile_errors() {
+        assert!(matches!(
+            ProfileConfig::from_toml("[profile]\nversion = \"1\"\n"),
+            Err(ConfigError::Missing("profile.name"))
+        ));
+        assert!(matches!(
+            ProfileConfig::from_toml("[profile]\nname = \"os\"\n[profile.features]\nsmp = 1\n"),
+            Err(ConfigError::Type(key, _)) if key == "profile.features.smp"
+        ));
+        assert!(matches!(
+            ProfileConfig::from_toml("[profile]\nname = \"os\"\n[subsystems]\nenabled = [\"Net\"]\n"),
+            Err(ConfigError::Invalid(_, name)) if name == "Net"
+        ));
+        assert!(matches!(
+            ProfileConfig::from_toml("[profile]\nname = \"os\"\n[modules]\nstatic = [1]\n"),
+            Err(ConfigError::Type(_, "an array of strings"))
+        ));
+    }
+}
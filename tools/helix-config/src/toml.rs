@@ -0,0 +1,433 @@
+//! Minimal TOML reader
+//!
+//! Covers what profile and crate manifests use: tables, dotted and quoted
+//! keys, basic and literal strings, integers, booleans, arrays (including
+//! multi-line ones) and inline tables. Floats, dates and multi-line strings
+//! are rejected. Arrays of tables (`[[bin]]`) are skipped with their keys.
+
+use crate::error::{ConfigError, ConfigResult};
+use std::collections::BTreeMap;
+
+/// A table: keys to values
+pub type Table = BTreeMap<String, Value>;
+
+/// A TOML value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// String
+    String(String),
+    /// Integer
+    Integer(i64),
+    /// Boolean
+    Boolean(bool),
+    /// Array
+    Array(Vec<Value>),
+    /// Table
+    Table(Table),
+}
+
+impl Value {
+    /// The string, if this is one
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The integer, if this is one
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Self::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The boolean, if this is one
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The items, if this is an array
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// The table, if this is one
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            Self::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+}
+
+/// Look up a dotted path (`"profile.features"`) in a table
+pub fn lookup<'a>(table: &'a Table, path: &str) -> Option<&'a Value> {
+    let mut keys = path.split('.');
+    let mut value = table.get(keys.next()?)?;
+    for key in keys {
+        value = value.as_table()?.get(key)?;
+    }
+    Some(value)
+}
+
+/// Parse a document into its root table
+pub fn parse(input: &str) -> ConfigResult<Table> {
+    Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+    }
+    .document()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, message: impl Into<String>) -> ConfigResult<T> {
+        Err(ConfigError::Parse(self.line, message.into()))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> ConfigResult<()> {
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => self.error(format!("expected '{}', found '{}'", expected, c)),
+            None => self.error(format!("expected '{}', found end of file", expected)),
+        }
+    }
+
+    /// Spaces and tabs
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    /// Comment up to (not including) the newline
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    /// Whitespace, newlines and comments
+    fn skip_trivia(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n' | '\r') => {
+                    self.bump();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> ConfigResult<()> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.peek() == Some('\r') {
+            self.bump();
+        }
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => self.error(format!("unexpected '{}' after value", c)),
+        }
+    }
+
+    fn document(mut self) -> ConfigResult<Table> {
+        let mut root = Table::new();
+        let mut current: Vec<String> = Vec::new();
+        let mut skipping = false;
+
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => return Ok(root),
+                Some('[') => {
+                    self.bump();
+                    let array = self.peek() == Some('[');
+                    if array {
+                        self.bump();
+                    }
+                    let path = self.key_path()?;
+                    self.expect(']')?;
+                    if array {
+                        self.expect(']')?;
+                    } else {
+                        self.table_at(&mut root, &path)?;
+                        current = path;
+                    }
+                    skipping = array;
+                    self.end_of_line()?;
+                }
+                Some(_) => {
+                    let mut path = self.key_path()?;
+                    self.skip_spaces();
+                    self.expect('=')?;
+                    self.skip_spaces();
+                    let value = self.value()?;
+                    if !skipping {
+                        let key = path.pop().unwrap_or_default();
+                        let mut full = current.clone();
+                        full.extend(path);
+                        let table = self.table_at(&mut root, &full)?;
+                        self.insert(table, key, value)?;
+                    }
+                    self.end_of_line()?;
+                }
+            }
+        }
+    }
+
+    /// Table at `path`, created if needed
+    fn table_at<'a>(&self, root: &'a mut Table, path: &[String]) -> ConfigResult<&'a mut Table> {
+        let mut table = root;
+        for key in path {
+            let entry = table.entry(key.clone()).or_insert_with(|| Value::Table(Table::new()));
+            table = match entry {
+                Value::Table(t) => t,
+                _ => return self.error(format!("'{}' is not a table", key)),
+            };
+        }
+        Ok(table)
+    }
+
+    fn insert(&self, table: &mut Table, key: String, value: Value) -> ConfigResult<()> {
+        if table.contains_key(&key) {
+            return self.error(format!("duplicate key '{}'", key));
+        }
+        table.insert(key, value);
+        Ok(())
+    }
+
+    /// `a.b."c"`
+    fn key_path(&mut self) -> ConfigResult<Vec<String>> {
+        let mut path = Vec::new();
+        loop {
+            self.skip_spaces();
+            let key = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => self.bare_word(),
+            };
+            if key.is_empty() {
+                return self.error("expected a key");
+            }
+            path.push(key);
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(path);
+            }
+            self.bump();
+        }
+    }
+
+    fn bare_word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+')) {
+                break;
+            }
+            word.push(c);
+            self.bump();
+        }
+        word
+    }
+
+    fn value(&mut self) -> ConfigResult<Value> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => {
+                let word = self.bare_word();
+                match word.as_str() {
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ => match word.replace('_', "").parse() {
+                        Ok(i) if !word.is_empty() && self.peek() != Some('.') => Ok(Value::Integer(i)),
+                        _ => self.error("unsupported value"),
+                    },
+                }
+            }
+            None => self.error("expected a value"),
+        }
+    }
+
+    fn basic_string(&mut self) -> ConfigResult<String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            if matches!(self.peek(), None | Some('\n')) {
+                return self.error("unterminated string");
+            }
+            match self.bump() {
+                Some('"') => return Ok(s),
+                Some('\\') => s.push(match self.bump() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    _ => return self.error("unsupported escape"),
+                }),
+                Some(c) => s.push(c),
+                None => return self.error("unterminated string"),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> ConfigResult<String> {
+        self.expect('\'')?;
+        let mut s = String::new();
+        loop {
+            if matches!(self.peek(), None | Some('\n')) {
+                return self.error("unterminated string");
+            }
+            match self.bump() {
+                Some('\'') => return Ok(s),
+                Some(c) => s.push(c),
+                None => return self.error("unterminated string"),
+            }
+        }
+    }
+
+    fn array(&mut self) -> ConfigResult<Value> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_trivia();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return self.error("expected ',' or ']' in array"),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> ConfigResult<Value> {
+        self.expect('{')?;
+        let mut table = Table::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some('}') {
+                self.bump();
+                return Ok(Value::Table(table));
+            }
+            let mut path = self.key_path()?;
+            self.skip_spaces();
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.value()?;
+            let key = path.pop().unwrap_or_default();
+            let inner = self.table_at(&mut table, &path)?;
+            self.insert(inner, key, value)?;
+            self.skip_spaces();
+            match self.bump() {
+                Some(',') => {}
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return self.error("expected ',' or '}' in inline table"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_document() {
+        let doc = parse(
+            r#"
+# Profile
+[profile]
+name = "minimal"   # trailing comment
+version = '1.0.0'
+
+[profile.features]
+multicore = false
+hot_reload = true
+
+[memory]
+heap_size_kb = 1_024
+
+[modules]
+static = [
+    "helix-scheduler-round-robin", # comment inside
+    "helix-allocator-buddy",
+]
+
+[dependencies]
+helix-hal = { workspace = true }
+helix-fs = { path = "../../fs", features = ["alloc"] }
+
+[[bin]]
+name = "skipped"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(lookup(&doc, "profile.name").and_then(Value::as_str), Some("minimal"));
+        assert_eq!(lookup(&doc, "profile.version").and_then(Value::as_str), Some("1.0.0"));
+        assert_eq!(lookup(&doc, "profile.features.hot_reload").and_then(Value::as_bool), Some(true));
+        assert_eq!(lookup(&doc, "memory.heap_size_kb").and_then(Value::as_integer), Some(1024));
+        assert_eq!(lookup(&doc, "modules.static").and_then(Value::as_array).map(<[_]>::len), Some(2));
+        assert_eq!(lookup(&doc, "dependencies.helix-hal.workspace"), Some(&Value::Boolean(true)));
+        assert_eq!(lookup(&doc, "dependencies.helix-fs.path").and_then(Value::as_str), Some("../../fs"));
+        assert!(lookup(&doc, "bin").is_none());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let line = |input: &str| match parse(input) {
+            Err(ConfigError::Parse(line, _)) => line,
+            other => panic!("expected a parse error, got {:?}", other),
+        };
+        assert_eq!(line("a = 1\na = 2\n"), 2);
+        assert_eq!(line("a = \"open\n"), 1);
+        assert_eq!(line("\n\npi = 3.14\n"), 3);
+        assert_eq!(line("a = [1, 2\nb = 3\n"), 2);
+        assert_eq!(line("a = 1 b = 2\n"), 1);
+        assert_eq!(line("a = 1\n[a]\n"), 2);
+    }
+}
@@ -0,0 +1,44 @@
+//! Kernel control interface
+//!
+//! `helix_ctl` takes a command code and space-separated operands and
+//! replies with text lines; the codes mirror the kernel's `CtlCommand`.
+
+use crate::syscall::{nr, result, syscall5};
+use crate::Result;
+
+/// Command codes
+#[allow(missing_docs)]
+pub mod cmd {
+    pub const MODULE_LIST: u32 = 0x100;
+    pub const MODULE_LOAD: u32 = 0x101;
+    pub const MODULE_UNLOAD: u32 = 0x102;
+    pub const MODULE_HOT_SWAP: u32 = 0x103;
+    pub const MODULE_VERIFY: u32 = 0x104;
+    pub const MODULE_ABI: u32 = 0x105;
+    pub const AI_GET_MODE: u32 = 0x200;
+    pub const AI_SET_MODE: u32 = 0x201;
+    pub const SNAPSHOT_LIST: u32 = 0x300;
+    pub const SNAPSHOT_CREATE: u32 = 0x301;
+    pub const SNAPSHOT_RESTORE: u32 = 0x302;
+    pub const SNAPSHOT_DELETE: u32 = 0x303;
+    pub const BOOT_SLOTS: u32 = 0x400;
+    pub const BOOT_SET_ACTIVE: u32 = 0x401;
+    pub const BOOT_MARK_GOOD: u32 = 0x402;
+}
+
+/// Run `command` with operands `arg`; the reply goes to `out`
+///
+/// Returns the reply length, or `ERANGE` if `out` is too small.
+pub fn helix_ctl(command: u32, arg: &str, out: &mut [u8]) -> Result<usize> {
+    // SAFETY: both buffers are valid for the lengths passed
+    result(unsafe {
+        syscall5(
+            nr::HELIX_CTL,
+            command as usize,
+            arg.as_ptr() as usize,
+            arg.len(),
+            out.as_mut_ptr() as usize,
+            out.len(),
+        )
+    })
+}
@@ -0,0 +1,88 @@
+//! Program arguments
+
+/// Command-line arguments, as passed on the initial stack
+#[derive(Clone, Copy)]
+pub struct Args {
+    argc: usize,
+    argv: *const *const u8,
+}
+
+impl Args {
+    /// Read `argc` / `argv` from the initial stack
+    ///
+    /// # Safety
+    ///
+    /// `sp` must point at `argc` followed by `argc` valid C strings.
+    pub unsafe fn from_stack(sp: *const usize) -> Self {
+        // SAFETY: guaranteed by the caller
+        unsafe {
+            Self {
+                argc: *sp,
+                argv: sp.add(1) as *const *const u8,
+            }
+        }
+    }
+
+    /// Number of arguments, program name included
+    pub fn len(&self) -> usize {
+        self.argc
+    }
+
+    /// No arguments at all (not even the program name)?
+    pub fn is_empty(&self) -> bool {
+        self.argc == 0
+    }
+
+    /// Argument `i` (0 = program name); non-UTF-8 arguments read as `""`
+    pub fn get(&self, i: usize) -> Option<&'static str> {
+        if i >= self.argc {
+            return None;
+        }
+        // SAFETY: `i < argc`, and the kernel passes NUL-terminated strings
+        // that live for the whole program
+        unsafe {
+            let arg = *self.argv.add(i);
+            let mut len = 0;
+            while *arg.add(len) != 0 {
+                len += 1;
+            }
+            Some(core::str::from_utf8(core::slice::from_raw_parts(arg, len)).unwrap_or(""))
+        }
+    }
+
+    /// Program name
+    pub fn program(&self) -> &'static str {
+        self.get(0).unwrap_or("")
+    }
+
+    /// Arguments after the program name
+    pub fn iter(&self) -> impl Iterator<Item = &'static str> + '_ {
+        (1..self.argc).filter_map(|i| self.get(i))
+    }
+
+    /// Split the arguments into option letters (`-la` → `l`, `a`) and operands
+    ///
+    /// Returns the option letters as a bitmask over `a..=z` and calls
+    /// `operand` for everything else. `--` ends option parsing.
+    pub fn parse(&self, mut operand: impl FnMut(&'static str)) -> u32 {
+        let mut flags = 0u32;
+        let mut options_done = false;
+        for arg in self.iter() {
+            if !options_done && arg == "--" {
+                options_done = true;
+            } else if !options_done && arg.len() > 1 && arg.starts_with('-') {
+                for c in arg[1..].bytes().filter(u8::is_ascii_lowercase) {
+                    flags |= 1 << (c - b'a');
+                }
+            } else {
+                operand(arg);
+            }
+        }
+        flags
+    }
+}
+
+/// Bit for option letter `c` in the mask returned by [`Args::parse`]
+pub const fn flag(c: u8) -> u32 {
+    1 << (c - b'a')
+}
@@ -0,0 +1,391 @@
+//! Files, directories and mounts
+
+use core::fmt;
+
+use crate::syscall::{nr, result, syscall3, syscall5, Errno};
+use crate::Result;
+
+/// Longest path accepted, terminating NUL included
+pub const PATH_MAX: usize = 256;
+
+/// `open` flags
+pub mod flags {
+    /// Read only
+    pub const O_RDONLY: usize = 0;
+    /// Write only
+    pub const O_WRONLY: usize = 1;
+    /// Create if missing
+    pub const O_CREAT: usize = 0o100;
+    /// Truncate to zero length
+    pub const O_TRUNC: usize = 0o1000;
+    /// Fail unless a directory
+    pub const O_DIRECTORY: usize = 0o200000;
+}
+
+/// File type bits of `st_mode`
+pub mod mode {
+    /// File type mask
+    pub const S_IFMT: u32 = 0o170000;
+    /// Directory
+    pub const S_IFDIR: u32 = 0o040000;
+    /// Regular file
+    pub const S_IFREG: u32 = 0o100000;
+    /// Symbolic link
+    pub const S_IFLNK: u32 = 0o120000;
+    /// Character device
+    pub const S_IFCHR: u32 = 0o020000;
+    /// Block device
+    pub const S_IFBLK: u32 = 0o060000;
+}
+
+/// `umount2` flag: detach even if busy
+pub const MNT_DETACH: usize = 2;
+
+// =============================================================================
+// Paths
+// =============================================================================
+
+/// NUL-terminated path for passing to the kernel
+pub struct CPath {
+    buf: [u8; PATH_MAX],
+    len: usize,
+}
+
+impl CPath {
+    /// Copy `path`, failing with `ENAMETOOLONG` if it does not fit
+    pub fn new(path: &str) -> Result<Self> {
+        let mut p = Self { buf: [0; PATH_MAX], len: 0 };
+        p.push_str(path)?;
+        Ok(p)
+    }
+
+    /// `dir/name`
+    pub fn join(dir: &str, name: &str) -> Result<Self> {
+        let mut p = Self::new(dir)?;
+        if !dir.ends_with('/') {
+            p.push_str("/")?;
+        }
+        p.push_str(name)?;
+        Ok(p)
+    }
+
+    fn push_str(&mut self, s: &str) -> Result<()> {
+        let end = self.len + s.len();
+        if end >= PATH_MAX || s.bytes().any(|b| b == 0) {
+            return Err(Errno::ENAMETOOLONG);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+
+    /// The path, without the NUL
+    pub fn as_str(&self) -> &str {
+        // SAFETY: built from `&str`s only
+        unsafe { core::str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+
+    fn as_ptr(&self) -> usize {
+        self.buf.as_ptr() as usize
+    }
+}
+
+/// Last component of `path`
+pub fn basename(path: &str) -> &str {
+    path.trim_end_matches('/').rsplit('/').next().unwrap_or(path)
+}
+
+// =============================================================================
+// File Descriptors
+// =============================================================================
+
+/// An open file descriptor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fd(pub i32);
+
+/// Open `path`
+pub fn open(path: &str, flags: usize, mode: u32) -> Result<Fd> {
+    let path = CPath::new(path)?;
+    // SAFETY: NUL-terminated path on our stack
+    let ret = unsafe { syscall3(nr::OPEN, path.as_ptr(), flags, mode as usize) };
+    result(ret).map(|fd| Fd(fd as i32))
+}
+
+/// Close `fd`
+pub fn close(fd: Fd) -> Result<()> {
+    // SAFETY: no pointers
+    result(unsafe { syscall3(nr::CLOSE, fd.0 as usize, 0, 0) }).map(|_| ())
+}
+
+/// Read into `buf`; returns the bytes read (0 at end of file)
+pub fn read(fd: Fd, buf: &mut [u8]) -> Result<usize> {
+    // SAFETY: `buf` is valid for `buf.len()` bytes
+    result(unsafe { syscall3(nr::READ, fd.0 as usize, buf.as_mut_ptr() as usize, buf.len()) })
+}
+
+/// Write from `buf`; returns the bytes written
+pub fn write(fd: Fd, buf: &[u8]) -> Result<usize> {
+    // SAFETY: `buf` is valid for `buf.len()` bytes
+    result(unsafe { syscall3(nr::WRITE, fd.0 as usize, buf.as_ptr() as usize, buf.len()) })
+}
+
+/// Write all of `buf`
+pub fn write_all(fd: Fd, mut buf: &[u8]) -> Result<()> {
+    while !buf.is_empty() {
+        match write(fd, buf)? {
+            0 => return Err(Errno::EIO),
+            n => buf = &buf[n..],
+        }
+    }
+    Ok(())
+}
+
+/// Copy everything readable from `from` to `to`; returns the bytes copied
+pub fn copy(from: Fd, to: Fd) -> Result<u64> {
+    let mut buf = [0u8; 4096];
+    let mut total = 0;
+    loop {
+        match read(from, &mut buf)? {
+            0 => return Ok(total),
+            n => {
+                write_all(to, &buf[..n])?;
+                total += n as u64;
+            }
+        }
+    }
+}
+
+/// Copy the regular file `from` to `to`, replacing `to`
+pub fn copy_file(from: &str, to: &str) -> Result<u64> {
+    let mode = stat(from)?.st_mode & 0o7777;
+    let src = open(from, flags::O_RDONLY, 0)?;
+    let copied = open(to, flags::O_WRONLY | flags::O_CREAT | flags::O_TRUNC, mode)
+        .and_then(|dst| {
+            let copied = copy(src, dst);
+            close(dst)?;
+            copied
+        });
+    close(src)?;
+    copied
+}
+
+// =============================================================================
+// Metadata
+// =============================================================================
+
+/// File status (`struct stat`, x86_64 layout)
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stat {
+    /// Device
+    pub st_dev: u64,
+    /// Inode number
+    pub st_ino: u64,
+    /// Hard links
+    pub st_nlink: u64,
+    /// Type and permissions
+    pub st_mode: u32,
+    /// Owner
+    pub st_uid: u32,
+    /// Group
+    pub st_gid: u32,
+    _pad0: u32,
+    /// Device (special files)
+    pub st_rdev: u64,
+    /// Size in bytes
+    pub st_size: i64,
+    /// Preferred I/O size
+    pub st_blksize: i64,
+    /// 512-byte blocks allocated
+    pub st_blocks: i64,
+    /// Access time (s, ns)
+    pub st_atime: [i64; 2],
+    /// Modification time (s, ns)
+    pub st_mtime: [i64; 2],
+    /// Status change time (s, ns)
+    pub st_ctime: [i64; 2],
+    _reserved: [i64; 3],
+}
+
+impl Stat {
+    /// Is a directory?
+    pub fn is_dir(&self) -> bool {
+        self.st_mode & mode::S_IFMT == mode::S_IFDIR
+    }
+
+    /// `ls -l` style mode string (`drwxr-xr-x`)
+    pub fn mode_string(&self) -> ModeString {
+        ModeString(self.st_mode)
+    }
+}
+
+/// Formats a mode as `ls -l` does
+pub struct ModeString(u32);
+
+impl fmt::Display for ModeString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.0 & mode::S_IFMT {
+            mode::S_IFDIR => 'd',
+            mode::S_IFLNK => 'l',
+            mode::S_IFCHR => 'c',
+            mode::S_IFBLK => 'b',
+            _ => '-',
+        };
+        write!(f, "{}", kind)?;
+        for shift in [6, 3, 0] {
+            let bits = self.0 >> shift;
+            write!(
+                f,
+                "{}{}{}",
+                if bits & 4 != 0 { 'r' } else { '-' },
+                if bits & 2 != 0 { 'w' } else { '-' },
+                if bits & 1 != 0 { 'x' } else { '-' },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn stat_with(n: usize, path: &str) -> Result<Stat> {
+    let path = CPath::new(path)?;
+    let mut st = Stat::default();
+    // SAFETY: NUL-terminated path and a `Stat`-sized buffer on our stack
+    let ret = unsafe { syscall3(n, path.as_ptr(), &mut st as *mut Stat as usize, 0) };
+    result(ret).map(|_| st)
+}
+
+/// Status of `path`, following symlinks
+pub fn stat(path: &str) -> Result<Stat> {
+    stat_with(nr::STAT, path)
+}
+
+/// Status of `path` itself
+pub fn lstat(path: &str) -> Result<Stat> {
+    stat_with(nr::LSTAT, path)
+}
+
+// =============================================================================
+// Directories
+// =============================================================================
+
+/// Directory entry
+#[derive(Debug, Clone, Copy)]
+pub struct DirEntry<'a> {
+    /// Inode number
+    pub ino: u64,
+    /// `DT_*` type
+    pub kind: u8,
+    /// Name
+    pub name: &'a str,
+}
+
+/// `DT_DIR`
+pub const DT_DIR: u8 = 4;
+
+/// Open directory, read with `getdents64`
+pub struct ReadDir {
+    fd: Fd,
+    buf: [u8; 2048],
+    len: usize,
+    pos: usize,
+}
+
+impl ReadDir {
+    /// Open `path` for listing
+    pub fn open(path: &str) -> Result<Self> {
+        Ok(Self {
+            fd: open(path, flags::O_RDONLY | flags::O_DIRECTORY, 0)?,
+            buf: [0; 2048],
+            len: 0,
+            pos: 0,
+        })
+    }
+
+    /// Next entry (`.` and `..` included), or `None` at the end
+    pub fn next_entry(&mut self) -> Result<Option<DirEntry<'_>>> {
+        if self.pos >= self.len {
+            // SAFETY: `buf` is valid for its length
+            let ret = unsafe {
+                syscall3(nr::GETDENTS64, self.fd.0 as usize, self.buf.as_mut_ptr() as usize, self.buf.len())
+            };
+            self.len = result(ret)?;
+            self.pos = 0;
+            if self.len == 0 {
+                return Ok(None);
+            }
+        }
+
+        // struct linux_dirent64 { u64 ino; i64 off; u16 reclen; u8 type; char name[]; }
+        let rec = &self.buf[self.pos..self.len];
+        if rec.len() < 19 {
+            return Err(Errno::EIO);
+        }
+        let ino = u64::from_ne_bytes(rec[0..8].try_into().unwrap());
+        let reclen = u16::from_ne_bytes([rec[16], rec[17]]) as usize;
+        if reclen < 19 || reclen > rec.len() {
+            return Err(Errno::EIO);
+        }
+        let name = &rec[19..reclen];
+        let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+        self.pos += reclen;
+
+        Ok(Some(DirEntry {
+            ino,
+            kind: rec[18],
+            name: core::str::from_utf8(name).unwrap_or("?"),
+        }))
+    }
+}
+
+impl Drop for ReadDir {
+    fn drop(&mut self) {
+        let _ = close(self.fd);
+    }
+}
+
+/// Create directory `path`
+pub fn mkdir(path: &str, mode: u32) -> Result<()> {
+    let path = CPath::new(path)?;
+    // SAFETY: NUL-terminated path on our stack
+    result(unsafe { syscall3(nr::MKDIR, path.as_ptr(), mode as usize, 0) }).map(|_| ())
+}
+
+/// Remove empty directory `path`
+pub fn rmdir(path: &str) -> Result<()> {
+    let path = CPath::new(path)?;
+    // SAFETY: NUL-terminated path on our stack
+    result(unsafe { syscall3(nr::RMDIR, path.as_ptr(), 0, 0) }).map(|_| ())
+}
+
+/// Remove file `path`
+pub fn unlink(path: &str) -> Result<()> {
+    let path = CPath::new(path)?;
+    // SAFETY: NUL-terminated path on our stack
+    result(unsafe { syscall3(nr::UNLINK, path.as_ptr(), 0, 0) }).map(|_| ())
+}
+
+/// Rename `from` to `to`
+pub fn rename(from: &str, to: &str) -> Result<()> {
+    let (from, to) = (CPath::new(from)?, CPath::new(to)?);
+    // SAFETY: NUL-terminated paths on our stack
+    result(unsafe { syscall3(nr::RENAME, from.as_ptr(), to.as_ptr(), 0) }).map(|_| ())
+}
+
+// =============================================================================
+// Mounts
+// =============================================================================
+
+/// Mount `source` of filesystem type `fstype` on `target`
+pub fn mount(source: &str, target: &str, fstype: &str, flags: usize) -> Result<()> {
+    let (source, target, fstype) = (CPath::new(source)?, CPath::new(target)?, CPath::new(fstype)?);
+    // SAFETY: NUL-terminated strings on our stack, no data argument
+    let ret = unsafe { syscall5(nr::MOUNT, source.as_ptr(), target.as_ptr(), fstype.as_ptr(), flags, 0) };
+    result(ret).map(|_| ())
+}
+
+/// Unmount `target`
+pub fn umount(target: &str, flags: usize) -> Result<()> {
+    let target = CPath::new(target)?;
+    // SAFETY: NUL-terminated path on our stack
+    result(unsafe { syscall3(nr::UMOUNT2, target.as_ptr(), flags, 0) }).map(|_| ())
+}
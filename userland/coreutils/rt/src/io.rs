@@ -0,0 +1,109 @@
+//! Standard streams and formatted output
+
+use core::fmt;
+
+use crate::fs::{self, Fd};
+
+/// Standard input
+pub const STDIN: Fd = Fd(0);
+/// Standard output
+pub const STDOUT: Fd = Fd(1);
+/// Standard error
+pub const STDERR: Fd = Fd(2);
+
+/// Formatting sink writing straight to a file descriptor
+pub struct Writer(pub Fd);
+
+impl fmt::Write for Writer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        fs::write_all(self.0, s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(fd: Fd, args: fmt::Arguments<'_>) {
+    let _ = fmt::Write::write_fmt(&mut Writer(fd), args);
+}
+
+/// Print to standard output
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => ($crate::io::_print($crate::io::STDOUT, format_args!($($arg)*)));
+}
+
+/// Print to standard output, with a newline
+#[macro_export]
+macro_rules! println {
+    () => ($crate::print!("\n"));
+    ($($arg:tt)*) => ($crate::io::_print($crate::io::STDOUT, format_args!("{}\n", format_args!($($arg)*))));
+}
+
+/// Print to standard error, with a newline
+#[macro_export]
+macro_rules! eprintln {
+    ($($arg:tt)*) => ($crate::io::_print($crate::io::STDERR, format_args!("{}\n", format_args!($($arg)*))));
+}
+
+/// Fixed-capacity string for formatting without an allocator
+pub struct StackString<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> StackString<N> {
+    /// Empty string
+    pub const fn new() -> Self {
+        Self { buf: [0; N], len: 0 }
+    }
+
+    /// Contents
+    pub fn as_str(&self) -> &str {
+        // SAFETY: only whole `&str`s are ever appended
+        unsafe { core::str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+
+    /// Contents as bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Length in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Is the string empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove and return the last character
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        self.len -= c.len_utf8();
+        Some(c)
+    }
+
+    /// Drop the contents
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for StackString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Write for StackString<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > N {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
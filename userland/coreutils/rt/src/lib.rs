@@ -0,0 +1,92 @@
+//! # Helix Userspace Runtime
+//!
+//! The smallest libc replacement that lets a `no_std` program run on Helix:
+//!
+//! - `_start`: reads `argc`/`argv` from the initial stack and calls the
+//!   program's `main` (declared with [`entry!`]), then exits with its status
+//! - Raw syscalls and thin wrappers for files, directories, mounts and
+//!   processes, returning [`Errno`] on failure
+//! - `print!`/`println!`/`eprintln!` over file descriptors 1 and 2
+//! - `helix_ctl`, the kernel control interface
+//! - Asynchronous I/O rings for servers that keep many operations in flight
+//! - Raw terminal mode, key decoding and cursor control for full-screen
+//!   programs
+//! - A panic handler that reports to stderr and exits with status 101
+//!
+//! Nothing allocates: buffers live on the stack and paths are limited to
+//! [`fs::PATH_MAX`] bytes.
+
+#![no_std]
+#![warn(missing_docs)]
+
+pub mod ctl;
+pub mod env;
+pub mod fs;
+pub mod io;
+pub mod process;
+pub mod syscall;
+pub mod tty;
+pub mod uring;
+
+pub use env::Args;
+pub use syscall::Errno;
+
+/// Result of a runtime call
+pub type Result<T> = core::result::Result<T, Errno>;
+
+/// Declare the program entry point
+///
+/// `main` takes the [`Args`] and returns the exit status.
+///
+/// ```ignore
+/// helix_rt::entry!(main);
+///
+/// fn main(args: helix_rt::Args) -> i32 { 0 }
+/// ```
+#[macro_export]
+macro_rules! entry {
+    ($main:path) => {
+        #[no_mangle]
+        fn __helix_main(args: $crate::Args) -> i32 {
+            let main: fn($crate::Args) -> i32 = $main;
+            main(args)
+        }
+    };
+}
+
+extern "Rust" {
+    fn __helix_main(args: Args) -> i32;
+}
+
+// The kernel enters with `rsp` pointing at `argc`, followed by the `argv`
+// pointers, a null, the `envp` pointers and another null.
+core::arch::global_asm!(
+    ".globl _start",
+    "_start:",
+    "xor rbp, rbp",
+    "mov rdi, rsp",
+    "and rsp, -16",
+    "call {start}",
+    "ud2",
+    start = sym start,
+);
+
+/// Rust side of `_start`
+///
+/// # Safety
+///
+/// `sp` must be the initial stack pointer set up by the kernel.
+unsafe extern "C" fn start(sp: *const usize) -> ! {
+    // SAFETY: guaranteed by the caller
+    let args = unsafe { Args::from_stack(sp) };
+    // SAFETY: provided by the program through `entry!`
+    let status = unsafe { __helix_main(args) };
+    process::exit(status)
+}
+
+#[cfg(target_os = "none")]
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo<'_>) -> ! {
+    eprintln!("panic: {}", info);
+    process::exit(101)
+}
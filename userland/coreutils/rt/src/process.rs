@@ -0,0 +1,29 @@
+//! Process control
+
+use crate::syscall::{nr, result, syscall3};
+use crate::Result;
+
+/// Terminate the process with `status`
+pub fn exit(status: i32) -> ! {
+    // SAFETY: no pointers; exit_group does not return
+    unsafe {
+        syscall3(nr::EXIT_GROUP, status as usize, 0, 0);
+        syscall3(nr::EXIT, status as usize, 0, 0);
+    }
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// Process ID of the caller
+pub fn getpid() -> u32 {
+    // SAFETY: no arguments
+    unsafe { syscall3(nr::GETPID, 0, 0, 0) as u32 }
+}
+
+/// Sleep for `ms` milliseconds
+pub fn sleep_ms(ms: u64) -> Result<()> {
+    let req = [(ms / 1000) as i64, ((ms % 1000) * 1_000_000) as i64];
+    // SAFETY: `req` is a valid `struct timespec`; no remaining time wanted
+    result(unsafe { syscall3(nr::NANOSLEEP, req.as_ptr() as usize, 0, 0) }).map(|_| ())
+}
@@ -0,0 +1,234 @@
+//! Terminal control
+//!
+//! Raw mode over `TCGETS`/`TCSETS`, window size, key decoding and the
+//! handful of ANSI sequences full-screen programs need. A console that
+//! does not implement the ioctls (`ENOTTY`) is driven as-is, at 80x24.
+
+use crate::fs::{self, Fd};
+use crate::io::STDOUT;
+use crate::syscall::{nr, result, syscall3};
+use crate::{print, Result};
+
+const TCGETS: usize = 0x5401;
+const TCSETS: usize = 0x5402;
+const TIOCGWINSZ: usize = 0x5413;
+
+// c_iflag
+const BRKINT: u32 = 0o2;
+const INPCK: u32 = 0o20;
+const ISTRIP: u32 = 0o40;
+const ICRNL: u32 = 0o400;
+const IXON: u32 = 0o2000;
+// c_oflag
+const OPOST: u32 = 0o1;
+// c_cflag
+const CS8: u32 = 0o60;
+// c_lflag
+const ISIG: u32 = 0o1;
+const ICANON: u32 = 0o2;
+const ECHO: u32 = 0o10;
+const IEXTEN: u32 = 0o100000;
+// c_cc
+const VTIME: usize = 5;
+const VMIN: usize = 6;
+
+/// Kernel `struct termios`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Termios {
+    /// Input modes
+    pub c_iflag: u32,
+    /// Output modes
+    pub c_oflag: u32,
+    /// Control modes
+    pub c_cflag: u32,
+    /// Local modes
+    pub c_lflag: u32,
+    /// Line discipline
+    pub c_line: u8,
+    /// Control characters
+    pub c_cc: [u8; 19],
+}
+
+/// Read the terminal attributes of `fd`
+pub fn get_attr(fd: Fd) -> Result<Termios> {
+    let mut termios = Termios::default();
+    // SAFETY: `termios` is a valid `struct termios` on our stack
+    result(unsafe { syscall3(nr::IOCTL, fd.0 as usize, TCGETS, &mut termios as *mut _ as usize) })?;
+    Ok(termios)
+}
+
+/// Set the terminal attributes of `fd`
+pub fn set_attr(fd: Fd, termios: &Termios) -> Result<()> {
+    // SAFETY: `termios` is a valid `struct termios`
+    result(unsafe { syscall3(nr::IOCTL, fd.0 as usize, TCSETS, termios as *const _ as usize) }).map(|_| ())
+}
+
+/// Terminal size as `(rows, columns)`, 24x80 if unknown
+pub fn window_size() -> (usize, usize) {
+    let mut ws = [0u16; 4];
+    // SAFETY: `ws` is a valid `struct winsize`
+    let ret = unsafe { syscall3(nr::IOCTL, STDOUT.0 as usize, TIOCGWINSZ, ws.as_mut_ptr() as usize) };
+    match result(ret) {
+        Ok(_) if ws[0] > 0 && ws[1] > 0 => (ws[0] as usize, ws[1] as usize),
+        _ => (24, 80),
+    }
+}
+
+/// A terminal in raw mode until dropped
+///
+/// No echo, no line buffering, no signal characters and no output
+/// post-processing; reads time out after 100ms so a lone `Esc` can be told
+/// apart from an escape sequence.
+pub struct RawMode {
+    fd: Fd,
+    saved: Option<Termios>,
+}
+
+impl RawMode {
+    /// Switch `fd` (normally [`STDIN`](crate::io::STDIN)) to raw mode
+    pub fn enable(fd: Fd) -> Self {
+        let Ok(saved) = get_attr(fd) else {
+            return Self { fd, saved: None };
+        };
+        let mut raw = saved;
+        raw.c_iflag &= !(BRKINT | ICRNL | INPCK | ISTRIP | IXON);
+        raw.c_oflag &= !OPOST;
+        raw.c_cflag |= CS8;
+        raw.c_lflag &= !(ECHO | ICANON | IEXTEN | ISIG);
+        raw.c_cc[VMIN] = 0;
+        raw.c_cc[VTIME] = 1;
+        let _ = set_attr(fd, &raw);
+        Self { fd, saved: Some(saved) }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Some(saved) = &self.saved {
+            let _ = set_attr(self.fd, saved);
+        }
+    }
+}
+
+/// A decoded key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// Printable character (or any byte without a special meaning)
+    Char(u8),
+    /// `Ctrl` plus a letter, as the lowercase letter
+    Ctrl(u8),
+    /// Return
+    Enter,
+    /// Backspace
+    Backspace,
+    /// Escape
+    Esc,
+    /// Arrow up
+    Up,
+    /// Arrow down
+    Down,
+    /// Arrow left
+    Left,
+    /// Arrow right
+    Right,
+    /// Home
+    Home,
+    /// End
+    End,
+    /// Page up
+    PageUp,
+    /// Page down
+    PageDown,
+    /// Delete
+    Delete,
+}
+
+fn read_byte(fd: Fd) -> Result<Option<u8>> {
+    let mut byte = [0u8];
+    match fs::read(fd, &mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+/// Wait for the next key on the terminal of `raw`
+///
+/// Returns `None` once the input is closed and not a terminal.
+pub fn read_key(raw: &RawMode) -> Result<Option<Key>> {
+    let byte = loop {
+        match read_byte(raw.fd)? {
+            Some(byte) => break byte,
+            // Without raw mode a zero-length read is end of file
+            None if raw.saved.is_none() => return Ok(None),
+            None => continue,
+        }
+    };
+
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => escape_sequence(raw.fd)?,
+        1..=26 => Key::Ctrl(byte - 1 + b'a'),
+        _ => Key::Char(byte),
+    };
+    Ok(Some(key))
+}
+
+/// Decode `ESC [ ...` / `ESC O ...`; a timeout means a bare `Esc`
+fn escape_sequence(fd: Fd) -> Result<Key> {
+    let Some(intro) = read_byte(fd)? else {
+        return Ok(Key::Esc);
+    };
+    let Some(code) = read_byte(fd)? else {
+        return Ok(Key::Esc);
+    };
+    let key = match (intro, code) {
+        (b'[' | b'O', b'A') => Key::Up,
+        (b'[' | b'O', b'B') => Key::Down,
+        (b'[' | b'O', b'C') => Key::Right,
+        (b'[' | b'O', b'D') => Key::Left,
+        (b'[' | b'O', b'H') => Key::Home,
+        (b'[' | b'O', b'F') => Key::End,
+        (b'[', b'0'..=b'9') => {
+            if read_byte(fd)? != Some(b'~') {
+                return Ok(Key::Esc);
+            }
+            match code {
+                b'1' | b'7' => Key::Home,
+                b'3' => Key::Delete,
+                b'4' | b'8' => Key::End,
+                b'5' => Key::PageUp,
+                b'6' => Key::PageDown,
+                _ => Key::Esc,
+            }
+        }
+        _ => Key::Esc,
+    };
+    Ok(key)
+}
+
+/// Clear the screen and home the cursor
+pub fn clear_screen() {
+    print!("\x1b[2J\x1b[H");
+}
+
+/// Move the cursor to `row`, `col` (0-based)
+pub fn move_to(row: usize, col: usize) {
+    print!("\x1b[{};{}H", row + 1, col + 1);
+}
+
+/// Clear from the cursor to the end of the line
+pub fn clear_line() {
+    print!("\x1b[K");
+}
+
+/// Switch reverse video on or off
+pub fn reverse(on: bool) {
+    print!("{}", if on { "\x1b[7m" } else { "\x1b[m" });
+}
+
+/// Show or hide the cursor
+pub fn show_cursor(on: bool) {
+    print!("{}", if on { "\x1b[?25h" } else { "\x1b[?25l" });
+}
@@ -0,0 +1,264 @@
+//! Asynchronous I/O rings
+//!
+//! A thin client for the kernel's io_uring-style interface: queue
+//! operations with [`Ring::push`], hand them to the kernel with
+//! [`Ring::submit`] and collect results with [`Ring::pop`]. The layout and
+//! codes mirror the kernel's `uring` module.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::fs::Fd;
+use crate::syscall::{nr, result, syscall3, syscall5, syscall6};
+use crate::Result;
+
+/// Operation codes
+#[allow(missing_docs)]
+pub mod op {
+    pub const NOP: u8 = 0;
+    pub const FSYNC: u8 = 3;
+    pub const ACCEPT: u8 = 13;
+    pub const READ: u8 = 22;
+    pub const WRITE: u8 = 23;
+}
+
+/// `off` value meaning "at the current file position"
+pub const OFFSET_CURRENT: u64 = u64::MAX;
+
+/// Setup flag: a kernel thread polls the submission queue
+pub const SETUP_SQPOLL: u32 = 1 << 1;
+
+const SQ_NEED_WAKEUP: u32 = 1 << 0;
+const ENTER_GETEVENTS: u32 = 1 << 0;
+const ENTER_SQ_WAKEUP: u32 = 1 << 1;
+
+/// Submission queue entry
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sqe {
+    /// Operation ([`op`])
+    pub opcode: u8,
+    /// Entry flags (must be 0)
+    pub flags: u8,
+    /// I/O priority
+    pub ioprio: u16,
+    /// Target descriptor
+    pub fd: i32,
+    /// File offset, or [`OFFSET_CURRENT`]
+    pub off: u64,
+    /// Buffer address
+    pub addr: u64,
+    /// Buffer length
+    pub len: u32,
+    /// Operation flags
+    pub op_flags: u32,
+    /// Copied to the completion
+    pub user_data: u64,
+    /// Reserved
+    pub pad: [u64; 3],
+}
+
+impl Sqe {
+    /// No-op
+    pub fn nop(user_data: u64) -> Self {
+        Self { opcode: op::NOP, user_data, ..Default::default() }
+    }
+
+    /// Read into `buf` at `off`
+    pub fn read(fd: Fd, buf: &mut [u8], off: u64, user_data: u64) -> Self {
+        Self {
+            opcode: op::READ,
+            fd: fd.0,
+            off,
+            addr: buf.as_mut_ptr() as u64,
+            len: buf.len() as u32,
+            user_data,
+            ..Default::default()
+        }
+    }
+
+    /// Write `buf` at `off`
+    pub fn write(fd: Fd, buf: &[u8], off: u64, user_data: u64) -> Self {
+        Self {
+            opcode: op::WRITE,
+            fd: fd.0,
+            off,
+            addr: buf.as_ptr() as u64,
+            len: buf.len() as u32,
+            user_data,
+            ..Default::default()
+        }
+    }
+
+    /// Flush `fd`; `datasync` skips metadata
+    pub fn fsync(fd: Fd, datasync: bool, user_data: u64) -> Self {
+        Self { opcode: op::FSYNC, fd: fd.0, op_flags: datasync as u32, user_data, ..Default::default() }
+    }
+
+    /// Accept a connection on listening socket `fd`
+    pub fn accept(fd: Fd, user_data: u64) -> Self {
+        Self { opcode: op::ACCEPT, fd: fd.0, user_data, ..Default::default() }
+    }
+}
+
+/// Completion queue entry
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cqe {
+    /// From the submission
+    pub user_data: u64,
+    /// Result, or a negative errno
+    pub res: i32,
+    /// Reserved
+    pub flags: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct SqOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    sqes: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct CqOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_idle: u32,
+    ring_size: u32,
+    resv: u32,
+    sq_off: SqOffsets,
+    cq_off: CqOffsets,
+}
+
+/// An async I/O ring
+pub struct Ring {
+    fd: Fd,
+    base: *mut u8,
+    params: Params,
+    /// Entries pushed since the last submit
+    queued: u32,
+}
+
+impl Ring {
+    /// Create a ring with at least `entries` submission slots
+    pub fn new(entries: u32, flags: u32) -> Result<Self> {
+        let mut params = Params { flags, ..Default::default() };
+        // SAFETY: `params` is valid for the kernel to fill in
+        let fd = result(unsafe {
+            syscall3(nr::IO_URING_SETUP, entries as usize, &mut params as *mut Params as usize, 0)
+        })?;
+        let fd = Fd(fd as i32);
+
+        // PROT_READ | PROT_WRITE, MAP_SHARED
+        // SAFETY: maps the ring; no user memory is passed
+        let base = result(unsafe {
+            syscall6(nr::MMAP, 0, params.ring_size as usize, 3, 1, fd.0 as usize, 0)
+        });
+        match base {
+            Ok(base) => Ok(Self { fd, base: base as *mut u8, params, queued: 0 }),
+            Err(e) => {
+                let _ = crate::fs::close(fd);
+                Err(e)
+            }
+        }
+    }
+
+    /// Ring descriptor
+    pub fn fd(&self) -> Fd {
+        self.fd
+    }
+
+    fn word(&self, offset: u32) -> &AtomicU32 {
+        // SAFETY: header word of the mapping, which lives as long as `self`
+        unsafe { &*(self.base.add(offset as usize) as *const AtomicU32) }
+    }
+
+    /// Queue `sqe`; false if the submission queue is full
+    ///
+    /// # Safety
+    ///
+    /// The buffer `sqe` points to must stay valid, and unused by anything
+    /// else, until its completion has been popped.
+    pub unsafe fn push(&mut self, sqe: Sqe) -> bool {
+        let head = self.word(self.params.sq_off.head).load(Ordering::Acquire);
+        let tail = self.word(self.params.sq_off.tail).load(Ordering::Relaxed);
+        if tail.wrapping_sub(head) >= self.params.sq_entries {
+            return false;
+        }
+        let index = (tail & (self.params.sq_entries - 1)) as usize;
+        let offset = self.params.sq_off.sqes as usize + index * core::mem::size_of::<Sqe>();
+        // SAFETY: inside the SQE array
+        unsafe { (self.base.add(offset) as *mut Sqe).write_volatile(sqe) };
+        self.word(self.params.sq_off.tail).store(tail.wrapping_add(1), Ordering::Release);
+        self.queued += 1;
+        true
+    }
+
+    /// Hand queued entries to the kernel, waiting until `min_complete`
+    /// completions are available; returns the entries submitted
+    ///
+    /// With [`SETUP_SQPOLL`] this only enters the kernel to wake an idle
+    /// poller or to wait.
+    pub fn submit(&mut self, min_complete: u32) -> Result<u32> {
+        let queued = core::mem::take(&mut self.queued);
+        let mut flags = if min_complete > 0 { ENTER_GETEVENTS } else { 0 };
+        if self.params.flags & SETUP_SQPOLL != 0 {
+            if self.word(self.params.sq_off.flags).load(Ordering::Acquire) & SQ_NEED_WAKEUP != 0 {
+                flags |= ENTER_SQ_WAKEUP;
+            }
+            if flags == 0 {
+                return Ok(queued);
+            }
+        }
+        // SAFETY: no pointers
+        let ret = unsafe {
+            syscall5(
+                nr::IO_URING_ENTER,
+                self.fd.0 as usize,
+                queued as usize,
+                min_complete as usize,
+                flags as usize,
+                0,
+            )
+        };
+        result(ret).map(|n| n as u32)
+    }
+
+    /// Next completion, if any
+    pub fn pop(&mut self) -> Option<Cqe> {
+        let head = self.word(self.params.cq_off.head).load(Ordering::Relaxed);
+        if head == self.word(self.params.cq_off.tail).load(Ordering::Acquire) {
+            return None;
+        }
+        let index = (head & (self.params.cq_entries - 1)) as usize;
+        let offset = self.params.cq_off.cqes as usize + index * core::mem::size_of::<Cqe>();
+        // SAFETY: inside the CQE array
+        let cqe = unsafe { (self.base.add(offset) as *const Cqe).read_volatile() };
+        self.word(self.params.cq_off.head).store(head.wrapping_add(1), Ordering::Release);
+        Some(cqe)
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        let _ = crate::fs::close(self.fd);
+    }
+}
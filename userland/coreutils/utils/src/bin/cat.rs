@@ -0,0 +1,27 @@
+//! cat - concatenate files to standard output
+//!
+//! `cat [FILE]...`; no file or `-` reads standard input.
+
+#![no_std]
+#![no_main]
+
+use helix_coreutils::{cat, report};
+use helix_rt::Args;
+
+helix_rt::entry!(main);
+
+fn main(args: Args) -> i32 {
+    let mut status = 0;
+    let mut any = false;
+    args.parse(|path| {
+        any = true;
+        if let Err(e) = cat(path) {
+            report(args.program(), path, e);
+            status = 1;
+        }
+    });
+    if !any && cat("-").is_err() {
+        status = 1;
+    }
+    status
+}
@@ -0,0 +1,38 @@
+//! cp - copy files
+//!
+//! `cp SOURCE DEST` or `cp SOURCE... DIRECTORY`. Directories are not
+//! copied.
+
+#![no_std]
+#![no_main]
+
+use helix_coreutils::{destination, is_dir, operands, report, sources_and_dest};
+use helix_rt::{eprintln, fs, Args, Errno};
+
+helix_rt::entry!(main);
+
+fn main(args: Args) -> i32 {
+    let mut flags = 0;
+    let (list, count) = operands::<32>(&args, &mut flags);
+    let (sources, dest, dest_is_dir) = match sources_and_dest(&list[..count]) {
+        Ok(split) => split,
+        Err(msg) => {
+            eprintln!("cp: {}", msg);
+            return 1;
+        }
+    };
+
+    let mut status = 0;
+    for source in sources {
+        let result = if is_dir(source) {
+            Err(Errno::EISDIR)
+        } else {
+            destination(source, dest, dest_is_dir).and_then(|to| fs::copy_file(source, to.as_str()))
+        };
+        if let Err(e) = result {
+            report(args.program(), source, e);
+            status = 1;
+        }
+    }
+    status
+}
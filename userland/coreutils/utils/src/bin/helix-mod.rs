@@ -0,0 +1,191 @@
+//! helix-mod - fetch and install kernel modules from a repository
+//!
+//! ```text
+//! helix-mod [-r REPO] fetch MODULE...
+//! helix-mod [-r REPO] install MODULE...
+//! ```
+//!
+//! `MODULE` is `NAME[>=MIN][<=MAX]`. The modules and their dependencies
+//! are resolved against `REPO/index` (default `/mnt/modules`), preferring
+//! builds for the running kernel's ABI. `fetch` copies the packages to
+//! `/var/cache/modules` and has the kernel check their signatures and ABI;
+//! packages that fail are deleted. `install` then stages them in
+//! `/lib/modules` as `NAME.hxm`, where `helixctl module load` finds them.
+//!
+//! Prints `name version` per package, dependencies first.
+
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+
+use helix_coreutils::modrepo::{Dependency, Entry, Index, Plan, Version, INDEX_FILE, MAX_PLAN};
+use helix_coreutils::procfs::read_file;
+use helix_coreutils::report;
+use helix_coreutils::text::Storage;
+use helix_rt::ctl::{cmd, helix_ctl};
+use helix_rt::fs::{self, CPath, PATH_MAX};
+use helix_rt::io::StackString;
+use helix_rt::{eprintln, println, Args, Errno, Result};
+
+helix_rt::entry!(main);
+
+const DEFAULT_REPO: &str = "/mnt/modules";
+const CACHE_DIRS: [&str; 3] = ["/var", "/var/cache", "/var/cache/modules"];
+const MODULE_DIR: &str = "/lib/modules";
+
+static INDEX: Storage<{ 64 * 1024 }> = Storage::new();
+
+fn usage() -> i32 {
+    eprintln!("usage: helix-mod [-r REPO] fetch|install NAME[>=MIN][<=MAX]...");
+    2
+}
+
+/// Create `dirs` in order, keeping existing ones
+fn make_dirs(dirs: &[&str]) -> Result<()> {
+    for dir in dirs {
+        match fs::mkdir(dir, 0o755) {
+            Ok(()) | Err(Errno::EEXIST) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Path of `entry` in the cache
+fn cached(entry: &Entry<'_>) -> Result<CPath> {
+    CPath::join(CACHE_DIRS[2], entry.file)
+}
+
+/// Copy `entry` from the repository to the cache
+fn fetch(repo: &str, entry: &Entry<'_>) -> Result<CPath> {
+    let to = cached(entry)?;
+    fs::copy_file(CPath::join(repo, entry.file)?.as_str(), to.as_str())?;
+    Ok(to)
+}
+
+/// Have the kernel check the package at `path`; is it `entry`?
+fn verify(path: &str, entry: &Entry<'_>) -> Result<bool> {
+    let mut reply = [0u8; 128];
+    let len = helix_ctl(cmd::MODULE_VERIFY, path, &mut reply)?;
+    let mut words = core::str::from_utf8(&reply[..len]).unwrap_or("").split_whitespace();
+    Ok(words.next() == Some(entry.name) && words.next().and_then(Version::parse) == Some(entry.version))
+}
+
+/// Stage a verified package as `MODULE_DIR/NAME.hxm`
+///
+/// The copy is renamed into place so the loader never sees a partial file.
+fn stage(entry: &Entry<'_>) -> Result<()> {
+    let (mut path, mut partial) = (StackString::<PATH_MAX>::new(), StackString::<PATH_MAX>::new());
+    write!(path, "{}/{}.hxm", MODULE_DIR, entry.name)
+        .and_then(|()| write!(partial, "{}.new", path.as_str()))
+        .map_err(|_| Errno::ENAMETOOLONG)?;
+    fs::copy_file(cached(entry)?.as_str(), partial.as_str())?;
+    fs::rename(partial.as_str(), path.as_str()).map_err(|e| {
+        let _ = fs::unlink(partial.as_str());
+        e
+    })
+}
+
+fn main(args: Args) -> i32 {
+    let (repo, verb_at) = match args.get(1) {
+        Some("-r") => match args.get(2) {
+            Some(repo) => (repo, 3),
+            None => return usage(),
+        },
+        _ => (DEFAULT_REPO, 1),
+    };
+    let install = match args.get(verb_at) {
+        Some("fetch") => false,
+        Some("install") => true,
+        _ => return usage(),
+    };
+
+    let mut roots = [Dependency::any(""); MAX_PLAN];
+    let mut count = 0;
+    for spec in args.iter().skip(verb_at) {
+        let Some(dep) = Dependency::parse(spec).filter(|_| count < MAX_PLAN) else {
+            eprintln!("helix-mod: invalid module: {}", spec);
+            return 2;
+        };
+        roots[count] = dep;
+        count += 1;
+    }
+    if count == 0 {
+        return usage();
+    }
+
+    let Some(mut storage) = INDEX.take() else {
+        return 1;
+    };
+    let buf = storage.buf();
+    let index = match CPath::join(repo, INDEX_FILE).and_then(|path| read_file(path.as_str(), buf)) {
+        Ok(len) if len < buf.len() => core::str::from_utf8(&buf[..len]).map_err(|_| Errno::EINVAL),
+        Ok(_) => Err(Errno::EFBIG),
+        Err(e) => Err(e),
+    };
+    let index = match index {
+        Ok(text) => Index::new(text),
+        Err(e) => {
+            report(args.program(), INDEX_FILE, e);
+            return 1;
+        }
+    };
+
+    // Without the module backend, resolve regardless of ABI
+    let mut abi = [0u8; 64];
+    let kernel_abi = helix_ctl(cmd::MODULE_ABI, "", &mut abi)
+        .ok()
+        .and_then(|len| core::str::from_utf8(&abi[..len]).ok())
+        .map(str::trim);
+
+    let plan = match Plan::resolve(&index, &roots[..count], kernel_abi) {
+        Ok(plan) => plan,
+        Err(e) => {
+            eprintln!("helix-mod: {}", e);
+            return 1;
+        }
+    };
+
+    if let Err(e) = make_dirs(&CACHE_DIRS) {
+        report(args.program(), CACHE_DIRS[2], e);
+        return 1;
+    }
+    for entry in plan.iter() {
+        let path = match fetch(repo, entry) {
+            Ok(path) => path,
+            Err(e) => {
+                report(args.program(), entry.file, e);
+                return 1;
+            }
+        };
+        let verified = verify(path.as_str(), entry);
+        if !matches!(verified, Ok(true)) {
+            let _ = fs::unlink(path.as_str());
+            match verified {
+                Err(e) => report(args.program(), entry.file, e),
+                _ => eprintln!("helix-mod: {}: does not match index entry {} {}", entry.file, entry.name, entry.version),
+            }
+            return 1;
+        }
+    }
+
+    // Stage only once every package has been verified
+    if install {
+        if let Err(e) = make_dirs(&["/lib", MODULE_DIR]) {
+            report(args.program(), MODULE_DIR, e);
+            return 1;
+        }
+        for entry in plan.iter() {
+            if let Err(e) = stage(entry) {
+                report(args.program(), entry.name, e);
+                return 1;
+            }
+        }
+    }
+
+    for entry in plan.iter() {
+        println!("{} {}", entry.name, entry.version);
+    }
+    0
+}
@@ -0,0 +1,86 @@
+//! helixctl - administer the running kernel
+//!
+//! ```text
+//! helixctl module list | load PATH | unload NAME | swap NAME PATH
+//! helixctl module verify PATH | abi
+//! helixctl ai mode [off|observe|assist|autonomous]
+//! helixctl snapshot list | create NAME | restore NAME | delete NAME
+//! helixctl boot slots | activate SLOT | mark-good
+//! ```
+//!
+//! Listings print one record per line with space-separated columns:
+//! modules as `id name version state`, snapshots as
+//! `id name created_ns size` and boot slots as
+//! `name active bootable successful tries version` (`-` for unset flags).
+
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+
+use helix_coreutils::report;
+use helix_rt::ctl::{cmd, helix_ctl};
+use helix_rt::fs;
+use helix_rt::io::{StackString, STDOUT};
+use helix_rt::{eprintln, Args};
+
+helix_rt::entry!(main);
+
+/// `(domain, verb, command, operands)`
+const COMMANDS: &[(&str, &str, u32, usize)] = &[
+    ("module", "list", cmd::MODULE_LIST, 0),
+    ("module", "load", cmd::MODULE_LOAD, 1),
+    ("module", "unload", cmd::MODULE_UNLOAD, 1),
+    ("module", "swap", cmd::MODULE_HOT_SWAP, 2),
+    ("module", "verify", cmd::MODULE_VERIFY, 1),
+    ("module", "abi", cmd::MODULE_ABI, 0),
+    ("ai", "mode", cmd::AI_GET_MODE, 0),
+    ("ai", "mode", cmd::AI_SET_MODE, 1),
+    ("snapshot", "list", cmd::SNAPSHOT_LIST, 0),
+    ("snapshot", "create", cmd::SNAPSHOT_CREATE, 1),
+    ("snapshot", "restore", cmd::SNAPSHOT_RESTORE, 1),
+    ("snapshot", "delete", cmd::SNAPSHOT_DELETE, 1),
+    ("boot", "slots", cmd::BOOT_SLOTS, 0),
+    ("boot", "activate", cmd::BOOT_SET_ACTIVE, 1),
+    ("boot", "mark-good", cmd::BOOT_MARK_GOOD, 0),
+];
+
+fn usage() -> i32 {
+    eprintln!("usage: helixctl module list | load PATH | unload NAME | swap NAME PATH");
+    eprintln!("       helixctl module verify PATH | abi");
+    eprintln!("       helixctl ai mode [off|observe|assist|autonomous]");
+    eprintln!("       helixctl snapshot list | create NAME | restore NAME | delete NAME");
+    eprintln!("       helixctl boot slots | activate SLOT | mark-good");
+    2
+}
+
+fn main(args: Args) -> i32 {
+    let (Some(domain), Some(verb)) = (args.get(1), args.get(2)) else {
+        return usage();
+    };
+    let operands = args.len() - 3;
+    let Some(&(_, _, command, _)) = COMMANDS
+        .iter()
+        .find(|&&(d, v, _, n)| d == domain && v == verb && n == operands)
+    else {
+        return usage();
+    };
+
+    let mut arg = StackString::<512>::new();
+    for (i, operand) in args.iter().skip(3).enumerate() {
+        let sep = if i > 0 { " " } else { "" };
+        if write!(arg, "{}{}", sep, operand).is_err() || operand.contains(' ') {
+            eprintln!("helixctl: invalid operand: {}", operand);
+            return 2;
+        }
+    }
+
+    let mut reply = [0u8; 8192];
+    match helix_ctl(command, arg.as_str(), &mut reply) {
+        Ok(len) => fs::write_all(STDOUT, &reply[..len]).map_or(1, |()| 0),
+        Err(e) => {
+            report(args.program(), verb, e);
+            1
+        }
+    }
+}
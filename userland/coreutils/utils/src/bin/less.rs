@@ -0,0 +1,180 @@
+//! less - page through text
+//!
+//! `less [FILE]`; without a file, pages standard input and reads keys from
+//! `/dev/tty`. `q` quits, `j`/`k`/arrows/`Enter` scroll a line,
+//! `Space`/`f`/`b`/`PageDown`/`PageUp` a page and `d`/`u` half a page,
+//! `g`/`G` jump to the start or end, `/PATTERN` searches and `n` repeats the
+//! search. Long lines are chopped; `Left`/`Right` scroll sideways.
+
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+
+use helix_coreutils::report;
+use helix_coreutils::text::{print_line, prompt, Storage, Text};
+use helix_rt::io::{StackString, STDIN, STDOUT};
+use helix_rt::tty::{self, Key, RawMode};
+use helix_rt::{fs, print, Args, Errno};
+
+helix_rt::entry!(main);
+
+static STORAGE: Storage<{ 256 * 1024 }> = Storage::new();
+
+/// Pager state
+struct Pager<'a> {
+    text: Text<'a>,
+    name: &'static str,
+    /// First line on screen
+    top: usize,
+    /// First screen column shown
+    left: usize,
+    /// Text rows (the last screen row is the prompt)
+    height: usize,
+    width: usize,
+    pattern: StackString<64>,
+    /// Line of the last match
+    found: Option<usize>,
+    message: StackString<96>,
+}
+
+fn main(args: Args) -> i32 {
+    let path = args.get(1);
+    let Some(mut storage) = STORAGE.take() else {
+        return 1;
+    };
+
+    let mut text = Text::new(storage.buf());
+    let loaded = match path {
+        Some(path) => text.load(path),
+        None => text.read_from(STDIN),
+    };
+    let mut message = StackString::new();
+    match loaded {
+        Ok(()) => {}
+        Err(Errno::EFBIG) => {
+            let _ = write!(message, "(truncated at {} KiB)", text.len() / 1024);
+        }
+        Err(e) => {
+            report(args.program(), path.unwrap_or("-"), e);
+            return 1;
+        }
+    }
+
+    // Standard input carried the text; keys come from the terminal
+    let keys = match path {
+        Some(_) => STDIN,
+        None => match fs::open("/dev/tty", fs::flags::O_RDONLY, 0) {
+            Ok(fd) => fd,
+            Err(_) => return fs::write_all(STDOUT, text.as_bytes()).map_or(1, |()| 0),
+        },
+    };
+
+    let (rows, cols) = tty::window_size();
+    let mut pager = Pager {
+        text,
+        name: path.unwrap_or("(standard input)"),
+        top: 0,
+        left: 0,
+        height: rows.saturating_sub(1).max(1),
+        width: cols,
+        pattern: StackString::new(),
+        found: None,
+        message,
+    };
+
+    let raw = RawMode::enable(keys);
+    loop {
+        pager.draw();
+        let Ok(Some(key)) = tty::read_key(&raw) else {
+            break;
+        };
+        if !pager.key(key, &raw) {
+            break;
+        }
+    }
+    tty::clear_screen();
+    0
+}
+
+impl Pager<'_> {
+    fn max_top(&self) -> usize {
+        self.text.line_count().saturating_sub(self.height)
+    }
+
+    fn draw(&mut self) {
+        self.top = self.top.min(self.max_top());
+        let lines = self.text.line_count();
+        tty::show_cursor(false);
+        for i in 0..self.height {
+            tty::move_to(i, 0);
+            let n = self.top + i;
+            if n < lines {
+                print_line(self.text.line(n), self.left, self.width);
+            } else {
+                print!("~");
+            }
+            tty::clear_line();
+        }
+
+        tty::move_to(self.height, 0);
+        tty::reverse(true);
+        let mut status = StackString::<128>::new();
+        if !self.message.is_empty() {
+            let _ = write!(status, "{}", self.message.as_str());
+        } else if self.top >= self.max_top() {
+            let _ = write!(status, "{} (END)", self.name);
+        } else {
+            let last = self.top + self.height;
+            let _ = write!(status, "{} lines {}-{}/{} {}%", self.name, self.top + 1, last, lines, last * 100 / lines);
+        }
+        print_line(status.as_bytes(), 0, self.width);
+        tty::reverse(false);
+        tty::clear_line();
+        tty::show_cursor(true);
+    }
+
+    /// Handle `key`; `false` to quit
+    fn key(&mut self, key: Key, raw: &RawMode) -> bool {
+        self.message.clear();
+        let half = (self.height / 2).max(1);
+        match key {
+            Key::Char(b'q' | b'Q') => return false,
+            Key::Char(b'j') | Key::Down | Key::Enter => self.top += 1,
+            Key::Char(b'k') | Key::Up => self.top = self.top.saturating_sub(1),
+            Key::Char(b' ' | b'f') | Key::Ctrl(b'f') | Key::PageDown => self.top += self.height,
+            Key::Char(b'b') | Key::Ctrl(b'b') | Key::PageUp => self.top = self.top.saturating_sub(self.height),
+            Key::Char(b'd') | Key::Ctrl(b'd') => self.top += half,
+            Key::Char(b'u') | Key::Ctrl(b'u') => self.top = self.top.saturating_sub(half),
+            Key::Char(b'g' | b'<') | Key::Home => self.top = 0,
+            Key::Char(b'G' | b'>') | Key::End => self.top = self.max_top(),
+            Key::Right => self.left += self.width / 2,
+            Key::Left => self.left = self.left.saturating_sub(self.width / 2),
+            Key::Char(b'/') => {
+                let mut pattern = StackString::new();
+                if prompt(raw, self.height, "/", &mut pattern) && !pattern.is_empty() {
+                    self.pattern = pattern;
+                    self.found = None;
+                    self.search();
+                }
+            }
+            Key::Char(b'n') if !self.pattern.is_empty() => self.search(),
+            _ => {}
+        }
+        true
+    }
+
+    /// Bring the next line matching the pattern to the top
+    fn search(&mut self) {
+        let from = self.found.map_or(self.top, |n| n + 1);
+        match self.text.find_line(from, self.pattern.as_bytes()) {
+            Some(n) => {
+                self.found = Some(n);
+                self.top = n;
+            }
+            None => {
+                let _ = write!(self.message, "Pattern not found: {}", self.pattern.as_str());
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! ls - list directory contents
+//!
+//! `ls [-a] [-l] [PATH]...`; `-a` shows dot files, `-l` mode, links and
+//! size.
+
+#![no_std]
+#![no_main]
+
+use helix_coreutils::{operands, report};
+use helix_rt::env::flag;
+use helix_rt::fs::{self, CPath, ReadDir, Stat, DT_DIR};
+use helix_rt::{print, println, Args, Result};
+
+helix_rt::entry!(main);
+
+fn main(args: Args) -> i32 {
+    let mut flags = 0;
+    let (paths, count) = operands::<32>(&args, &mut flags);
+    let paths = if count == 0 { &["."][..] } else { &paths[..count] };
+    let long = flags & flag(b'l') != 0;
+    let all = flags & flag(b'a') != 0;
+
+    let mut status = 0;
+    for (i, path) in paths.iter().enumerate() {
+        let st = match fs::lstat(path) {
+            Ok(st) => st,
+            Err(e) => {
+                report(args.program(), path, e);
+                status = 1;
+                continue;
+            }
+        };
+        if !st.is_dir() {
+            print_entry(path, &st, long, false);
+            continue;
+        }
+
+        if paths.len() > 1 {
+            if i > 0 {
+                println!();
+            }
+            println!("{}:", path);
+        }
+        if let Err(e) = list_dir(path, long, all) {
+            report(args.program(), path, e);
+            status = 1;
+        }
+    }
+    status
+}
+
+fn list_dir(path: &str, long: bool, all: bool) -> Result<()> {
+    let mut dir = ReadDir::open(path)?;
+    while let Some(entry) = dir.next_entry()? {
+        if entry.name.starts_with('.') && !all {
+            continue;
+        }
+        if long {
+            // Entries that vanish or cannot be stat'ed still get listed
+            let st = CPath::join(path, entry.name).and_then(|p| fs::lstat(p.as_str())).unwrap_or_default();
+            print_entry(entry.name, &st, true, entry.kind == DT_DIR);
+        } else {
+            print_entry(entry.name, &Stat::default(), false, entry.kind == DT_DIR);
+        }
+    }
+    Ok(())
+}
+
+fn print_entry(name: &str, st: &Stat, long: bool, dir: bool) {
+    if long {
+        print!("{} {:>3} {:>9} ", st.mode_string(), st.st_nlink, st.st_size);
+    }
+    println!("{}{}", name, if dir || st.is_dir() { "/" } else { "" });
+}
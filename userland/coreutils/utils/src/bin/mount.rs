@@ -0,0 +1,62 @@
+//! mount - mount a filesystem
+//!
+//! `mount` lists the mounted filesystems (`/proc/mounts`);
+//! `mount [-r] -t TYPE SOURCE TARGET` mounts `SOURCE` on `TARGET`,
+//! read-only with `-r`.
+
+#![no_std]
+#![no_main]
+
+use helix_coreutils::{cat, report};
+use helix_rt::{eprintln, fs, Args};
+
+helix_rt::entry!(main);
+
+/// `MS_RDONLY`
+const MS_RDONLY: usize = 1;
+
+fn main(args: Args) -> i32 {
+    if args.len() == 1 {
+        return match cat("/proc/mounts") {
+            Ok(()) => 0,
+            Err(e) => {
+                report(args.program(), "/proc/mounts", e);
+                1
+            }
+        };
+    }
+
+    let mut fstype = None;
+    let mut flags = 0;
+    let mut operands = [""; 2];
+    let mut count = 0;
+
+    let mut i = 1;
+    while let Some(arg) = args.get(i) {
+        match arg {
+            "-t" => {
+                fstype = args.get(i + 1);
+                i += 1;
+            }
+            "-r" => flags |= MS_RDONLY,
+            _ if count < operands.len() => {
+                operands[count] = arg;
+                count += 1;
+            }
+            _ => count += 1,
+        }
+        i += 1;
+    }
+
+    let (Some(fstype), 2) = (fstype, count) else {
+        eprintln!("usage: mount [-r] -t TYPE SOURCE TARGET");
+        return 1;
+    };
+    match fs::mount(operands[0], operands[1], fstype, flags) {
+        Ok(()) => 0,
+        Err(e) => {
+            report(args.program(), operands[1], e);
+            1
+        }
+    }
+}
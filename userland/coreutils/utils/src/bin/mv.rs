@@ -0,0 +1,44 @@
+//! mv - move (rename) files
+//!
+//! `mv SOURCE DEST` or `mv SOURCE... DIRECTORY`. Files crossing a mount
+//! are copied and then removed.
+
+#![no_std]
+#![no_main]
+
+use helix_coreutils::{destination, is_dir, operands, report, sources_and_dest};
+use helix_rt::{eprintln, fs, Args, Errno, Result};
+
+helix_rt::entry!(main);
+
+fn main(args: Args) -> i32 {
+    let mut flags = 0;
+    let (list, count) = operands::<32>(&args, &mut flags);
+    let (sources, dest, dest_is_dir) = match sources_and_dest(&list[..count]) {
+        Ok(split) => split,
+        Err(msg) => {
+            eprintln!("mv: {}", msg);
+            return 1;
+        }
+    };
+
+    let mut status = 0;
+    for source in sources {
+        let result = destination(source, dest, dest_is_dir).and_then(|to| move_file(source, to.as_str()));
+        if let Err(e) = result {
+            report(args.program(), source, e);
+            status = 1;
+        }
+    }
+    status
+}
+
+fn move_file(from: &str, to: &str) -> Result<()> {
+    match fs::rename(from, to) {
+        Err(Errno::EXDEV) if !is_dir(from) => {
+            fs::copy_file(from, to)?;
+            fs::unlink(from)
+        }
+        result => result,
+    }
+}
@@ -0,0 +1,23 @@
+//! ps - list processes
+//!
+//! Reads `/proc/<pid>/stat` for every process.
+
+#![no_std]
+#![no_main]
+
+use helix_coreutils::procfs::{for_each_process, print_header, print_process};
+use helix_coreutils::report;
+use helix_rt::Args;
+
+helix_rt::entry!(main);
+
+fn main(args: Args) -> i32 {
+    print_header();
+    match for_each_process(print_process) {
+        Ok(_) => 0,
+        Err(e) => {
+            report(args.program(), "/proc", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! rm - remove files
+//!
+//! `rm [-r] [-f] FILE...`; `-r` removes directories and their contents,
+//! `-f` ignores missing files.
+
+#![no_std]
+#![no_main]
+
+use helix_coreutils::report;
+use helix_rt::env::flag;
+use helix_rt::fs::{self, CPath, ReadDir};
+use helix_rt::{eprintln, Args, Errno, Result};
+
+helix_rt::entry!(main);
+
+fn main(args: Args) -> i32 {
+    let mut status = 0;
+    let mut any = false;
+    let flags = args.parse(|_| any = true);
+    let recursive = flags & flag(b'r') != 0;
+    let force = flags & flag(b'f') != 0;
+
+    if !any && !force {
+        eprintln!("rm: missing operand");
+        return 1;
+    }
+
+    args.parse(|path| {
+        let result = match fs::lstat(path) {
+            Ok(st) if st.is_dir() && !recursive => Err(Errno::EISDIR),
+            Ok(st) if st.is_dir() => remove_tree(path),
+            Ok(_) => fs::unlink(path),
+            Err(e) => Err(e),
+        };
+        match result {
+            Err(Errno::ENOENT) if force => {}
+            Err(e) => {
+                report(args.program(), path, e);
+                status = 1;
+            }
+            Ok(()) => {}
+        }
+    });
+    status
+}
+
+/// Remove `dir` and everything below it
+///
+/// The listing is reopened after every removal rather than kept across
+/// changes to the directory.
+fn remove_tree(dir: &str) -> Result<()> {
+    loop {
+        let child = {
+            let mut entries = ReadDir::open(dir)?;
+            let mut child = None;
+            while let Some(entry) = entries.next_entry()? {
+                if entry.name != "." && entry.name != ".." {
+                    child = Some(CPath::join(dir, entry.name)?);
+                    break;
+                }
+            }
+            child
+        };
+        let Some(child) = child else {
+            break;
+        };
+        match fs::lstat(child.as_str())? {
+            st if st.is_dir() => remove_tree(child.as_str())?,
+            _ => fs::unlink(child.as_str())?,
+        }
+    }
+    fs::rmdir(dir)
+}
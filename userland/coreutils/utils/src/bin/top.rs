@@ -0,0 +1,73 @@
+//! top - live process and scheduler view
+//!
+//! `top [-n ITERATIONS] [-d SECONDS]`. Redraws the process list and the
+//! per-CPU scheduler statistics from `/proc/sched_stats` until interrupted
+//! (^C) or `ITERATIONS` screens have been shown.
+
+#![no_std]
+#![no_main]
+
+use helix_coreutils::procfs::{for_each_process, print_header, print_process, read_file};
+use helix_coreutils::report;
+use helix_rt::{eprintln, print, println, process, Args};
+
+helix_rt::entry!(main);
+
+fn main(args: Args) -> i32 {
+    let mut iterations = u64::MAX;
+    let mut delay_ms = 2000;
+
+    let mut i = 1;
+    while let Some(arg) = args.get(i) {
+        let value = args.get(i + 1).and_then(|v| v.parse::<u64>().ok());
+        match (arg, value) {
+            ("-n", Some(n)) => iterations = n,
+            ("-d", Some(secs)) => delay_ms = secs * 1000,
+            _ => {
+                eprintln!("usage: top [-n ITERATIONS] [-d SECONDS]");
+                return 1;
+            }
+        }
+        i += 2;
+    }
+
+    for n in 0..iterations {
+        if n > 0 && process::sleep_ms(delay_ms).is_err() {
+            break;
+        }
+        // Clear the screen and home the cursor
+        print!("\x1b[2J\x1b[H");
+
+        let mut running = 0;
+        let mut listing = [None; 64];
+        let total = for_each_process(|p| {
+            if p.state == 'R' {
+                running += 1;
+            }
+            if let Some(slot) = listing.iter_mut().find(|slot| slot.is_none()) {
+                *slot = Some(*p);
+            }
+        });
+        let total = match total {
+            Ok(total) => total,
+            Err(e) => {
+                report(args.program(), "/proc", e);
+                return 1;
+            }
+        };
+
+        println!("top - {} processes, {} running\n", total, running);
+        let mut buf = [0u8; 2048];
+        match read_file("/proc/sched_stats", &mut buf) {
+            Ok(len) => print!("{}", core::str::from_utf8(&buf[..len]).unwrap_or("")),
+            Err(e) => report(args.program(), "/proc/sched_stats", e),
+        }
+        println!();
+
+        print_header();
+        for p in listing.iter().flatten() {
+            print_process(p);
+        }
+    }
+    0
+}
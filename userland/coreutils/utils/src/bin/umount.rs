@@ -0,0 +1,32 @@
+//! umount - unmount filesystems
+//!
+//! `umount [-l] TARGET...`; `-l` detaches busy filesystems lazily.
+
+#![no_std]
+#![no_main]
+
+use helix_coreutils::report;
+use helix_rt::env::flag;
+use helix_rt::fs::{self, MNT_DETACH};
+use helix_rt::{eprintln, Args};
+
+helix_rt::entry!(main);
+
+fn main(args: Args) -> i32 {
+    let mut any = false;
+    let flags = args.parse(|_| any = true);
+    if !any {
+        eprintln!("usage: umount [-l] TARGET...");
+        return 1;
+    }
+    let umount_flags = if flags & flag(b'l') != 0 { MNT_DETACH } else { 0 };
+
+    let mut status = 0;
+    args.parse(|target| {
+        if let Err(e) = fs::umount(target, umount_flags) {
+            report(args.program(), target, e);
+            status = 1;
+        }
+    });
+    status
+}
@@ -0,0 +1,413 @@
+//! vi - minimal screen editor
+//!
+//! `vi FILE`. In normal mode `h j k l` and the arrows move, `0 $ gg G`
+//! jump, `Ctrl-f`/`Ctrl-b` page, `x` deletes a character and `dd` a line,
+//! `i a I A o O` start inserting and `ZZ` saves and quits. The command line
+//! (`:`) takes `w`, `q`, `q!`, `wq`, `x` and a line number. `Esc` leaves
+//! insert mode.
+//!
+//! Files up to 64 KiB; bytes outside printable ASCII are shown as `?`.
+
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+
+use helix_coreutils::report;
+use helix_coreutils::text::{display_col, print_line, prompt, Storage, Text};
+use helix_rt::io::{StackString, STDIN};
+use helix_rt::tty::{self, Key, RawMode};
+use helix_rt::{eprintln, print, Args, Errno, Result};
+
+helix_rt::entry!(main);
+
+static STORAGE: Storage<{ 64 * 1024 }> = Storage::new();
+
+/// Replace the status line message
+macro_rules! message {
+    ($editor:expr, $($arg:tt)*) => {{
+        $editor.message.clear();
+        let _ = write!($editor.message, $($arg)*);
+    }};
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Insert,
+}
+
+/// Whether the editor keeps running after a key
+enum Action {
+    Continue,
+    Quit,
+}
+
+/// Editor state
+///
+/// The text is kept either empty or ending in `\n`, so every line, the
+/// last one included, is followed by a newline.
+struct Editor<'a> {
+    text: Text<'a>,
+    path: &'static str,
+    /// Cursor line
+    row: usize,
+    /// Cursor byte within the line
+    col: usize,
+    /// First line on screen
+    top: usize,
+    /// First screen column shown
+    left: usize,
+    /// Text rows (the last screen row is the status line)
+    height: usize,
+    width: usize,
+    mode: Mode,
+    /// First key of `dd`, `gg` or `ZZ`
+    pending: Option<u8>,
+    dirty: bool,
+    message: StackString<128>,
+}
+
+fn main(args: Args) -> i32 {
+    let Some(path) = args.get(1).filter(|_| args.len() == 2) else {
+        eprintln!("usage: vi FILE");
+        return 1;
+    };
+    let Some(mut storage) = STORAGE.take() else {
+        return 1;
+    };
+
+    let mut text = Text::new(storage.buf());
+    let loaded = match text.load(path) {
+        Err(Errno::ENOENT) => Ok(()),
+        loaded => loaded,
+    };
+    let terminated = match text.as_bytes().last() {
+        Some(b) if *b != b'\n' => text.insert(text.len(), b"\n").map_err(|_| Errno::EFBIG),
+        _ => Ok(()),
+    };
+    if let Err(e) = loaded.and(terminated) {
+        report(args.program(), path, e);
+        return 1;
+    }
+
+    let (rows, cols) = tty::window_size();
+    let mut editor = Editor {
+        text,
+        path,
+        row: 0,
+        col: 0,
+        top: 0,
+        left: 0,
+        height: rows.saturating_sub(1).max(1),
+        width: cols,
+        mode: Mode::Normal,
+        pending: None,
+        dirty: false,
+        message: StackString::new(),
+    };
+
+    let raw = RawMode::enable(STDIN);
+    loop {
+        editor.draw();
+        let Ok(Some(key)) = tty::read_key(&raw) else {
+            break;
+        };
+        if let Action::Quit = editor.key(key, &raw) {
+            break;
+        }
+    }
+    tty::clear_screen();
+    0
+}
+
+impl Editor<'_> {
+    fn lines(&self) -> usize {
+        self.text.line_count().max(1)
+    }
+
+    fn line_len(&self) -> usize {
+        self.text.line(self.row).len()
+    }
+
+    /// Byte offset of the cursor in the text
+    fn offset(&self) -> usize {
+        self.text.line_range(self.row).0 + self.col
+    }
+
+    fn insert(&mut self, pos: usize, bytes: &[u8]) -> bool {
+        // Keep the text newline-terminated
+        if self.text.is_empty() && self.text.insert(0, b"\n").is_err() {
+            return false;
+        }
+        match self.text.insert(pos, bytes) {
+            Ok(()) => {
+                self.dirty = true;
+                true
+            }
+            Err(e) => {
+                message!(self, "{}", e);
+                false
+            }
+        }
+    }
+
+    fn remove(&mut self, start: usize, end: usize) {
+        self.text.remove(start, end);
+        self.dirty = true;
+    }
+
+    /// Keep the cursor on the text and on screen
+    fn clamp(&mut self) {
+        self.row = self.row.min(self.lines() - 1);
+        let max_col = match self.mode {
+            Mode::Insert => self.line_len(),
+            Mode::Normal => self.line_len().saturating_sub(1),
+        };
+        self.col = self.col.min(max_col);
+
+        if self.row < self.top {
+            self.top = self.row;
+        } else if self.row >= self.top + self.height {
+            self.top = self.row + 1 - self.height;
+        }
+        let x = display_col(self.text.line(self.row), self.col);
+        if x < self.left {
+            self.left = x;
+        } else if x >= self.left + self.width {
+            self.left = x + 1 - self.width;
+        }
+    }
+
+    fn draw(&mut self) {
+        self.clamp();
+        tty::show_cursor(false);
+        for i in 0..self.height {
+            tty::move_to(i, 0);
+            let n = self.top + i;
+            if n < self.lines() {
+                print_line(self.text.line(n), self.left, self.width);
+            } else {
+                print!("~");
+            }
+            tty::clear_line();
+        }
+
+        tty::move_to(self.height, 0);
+        if self.message.is_empty() {
+            tty::reverse(true);
+            let mut status = StackString::<128>::new();
+            let _ = write!(
+                status,
+                "{}{} {},{}{}",
+                self.path,
+                if self.dirty { " [+]" } else { "" },
+                self.row + 1,
+                self.col + 1,
+                if self.mode == Mode::Insert { "  -- INSERT --" } else { "" }
+            );
+            print_line(status.as_bytes(), 0, self.width);
+            tty::clear_line();
+            tty::reverse(false);
+        } else {
+            print_line(self.message.as_bytes(), 0, self.width);
+            tty::clear_line();
+        }
+
+        let x = display_col(self.text.line(self.row), self.col);
+        tty::move_to(self.row - self.top, x - self.left);
+        tty::show_cursor(true);
+    }
+
+    fn key(&mut self, key: Key, raw: &RawMode) -> Action {
+        self.message.clear();
+        match self.mode {
+            Mode::Normal => self.normal(key, raw),
+            Mode::Insert => {
+                self.insert_mode(key);
+                Action::Continue
+            }
+        }
+    }
+
+    /// Shared cursor movement; `true` if `key` moved the cursor
+    fn movement(&mut self, key: Key) -> bool {
+        match key {
+            Key::Left => self.col = self.col.saturating_sub(1),
+            Key::Right => self.col += 1,
+            Key::Up => self.row = self.row.saturating_sub(1),
+            Key::Down => self.row += 1,
+            Key::Home => self.col = 0,
+            Key::End => self.col = usize::MAX,
+            Key::PageUp => self.row = self.row.saturating_sub(self.height),
+            Key::PageDown => self.row += self.height,
+            _ => return false,
+        }
+        true
+    }
+
+    fn normal(&mut self, key: Key, raw: &RawMode) -> Action {
+        if let Some(first) = self.pending.take() {
+            match (first, key) {
+                (b'd', Key::Char(b'd')) => self.delete_line(),
+                (b'g', Key::Char(b'g')) => self.row = 0,
+                (b'Z', Key::Char(b'Z')) => return self.write(true),
+                _ => {}
+            }
+            return Action::Continue;
+        }
+        if self.movement(key) {
+            return Action::Continue;
+        }
+
+        let Key::Char(c) = key else {
+            match key {
+                Key::Ctrl(b'f') => self.row += self.height,
+                Key::Ctrl(b'b') => self.row = self.row.saturating_sub(self.height),
+                Key::Delete => self.delete_char(),
+                _ => {}
+            }
+            return Action::Continue;
+        };
+        match c {
+            b'h' => self.col = self.col.saturating_sub(1),
+            b'l' => self.col += 1,
+            b'k' => self.row = self.row.saturating_sub(1),
+            b'j' => self.row += 1,
+            b'0' => self.col = 0,
+            b'$' => self.col = usize::MAX,
+            b'G' => self.row = self.lines() - 1,
+            b'x' => self.delete_char(),
+            b'i' => self.mode = Mode::Insert,
+            b'a' => {
+                self.mode = Mode::Insert;
+                if self.line_len() > 0 {
+                    self.col += 1;
+                }
+            }
+            b'I' => {
+                self.mode = Mode::Insert;
+                self.col = 0;
+            }
+            b'A' => {
+                self.mode = Mode::Insert;
+                self.col = self.line_len();
+            }
+            b'o' => {
+                let end = self.text.line_range(self.row).1;
+                if self.insert(end, b"\n") {
+                    self.row += 1;
+                    self.col = 0;
+                    self.mode = Mode::Insert;
+                }
+            }
+            b'O' => {
+                let start = self.text.line_range(self.row).0;
+                if self.insert(start, b"\n") {
+                    self.col = 0;
+                    self.mode = Mode::Insert;
+                }
+            }
+            b'd' | b'g' | b'Z' => self.pending = Some(c),
+            b':' => return self.command(raw),
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    fn insert_mode(&mut self, key: Key) {
+        if self.movement(key) {
+            return;
+        }
+        let offset = self.offset();
+        match key {
+            Key::Esc => {
+                self.mode = Mode::Normal;
+                self.col = self.col.saturating_sub(1);
+            }
+            Key::Char(c) => {
+                if self.insert(offset, &[c]) {
+                    self.col += 1;
+                }
+            }
+            Key::Ctrl(b'i') => {
+                if self.insert(offset, b"\t") {
+                    self.col += 1;
+                }
+            }
+            Key::Enter => {
+                if self.insert(offset, b"\n") {
+                    self.row += 1;
+                    self.col = 0;
+                }
+            }
+            Key::Backspace if self.col > 0 => {
+                self.remove(offset - 1, offset);
+                self.col -= 1;
+            }
+            Key::Backspace if self.row > 0 => {
+                // Join with the previous line
+                self.row -= 1;
+                self.col = self.line_len();
+                self.remove(offset - 1, offset);
+            }
+            Key::Delete if self.col < self.line_len() || self.row + 1 < self.lines() => {
+                self.remove(offset, offset + 1);
+            }
+            _ => {}
+        }
+    }
+
+    fn delete_char(&mut self) {
+        if self.col < self.line_len() {
+            let offset = self.offset();
+            self.remove(offset, offset + 1);
+        }
+    }
+
+    fn delete_line(&mut self) {
+        let (start, end) = self.text.line_range(self.row);
+        if start < self.text.len() {
+            self.remove(start, end + 1);
+        }
+    }
+
+    fn save(&mut self) -> Result<()> {
+        self.text.save(self.path)?;
+        self.dirty = false;
+        message!(self, "\"{}\" {}L, {}B written", self.path, self.text.line_count(), self.text.len());
+        Ok(())
+    }
+
+    /// Save, then quit if `quit` and the save succeeded
+    fn write(&mut self, quit: bool) -> Action {
+        match self.save() {
+            Ok(()) if quit => Action::Quit,
+            Ok(()) => Action::Continue,
+            Err(e) => {
+                message!(self, "\"{}\": {}", self.path, e);
+                Action::Continue
+            }
+        }
+    }
+
+    fn command(&mut self, raw: &RawMode) -> Action {
+        let mut input = StackString::<64>::new();
+        if !prompt(raw, self.height, ":", &mut input) {
+            return Action::Continue;
+        }
+        match input.as_str().trim() {
+            "" => {}
+            "w" => return self.write(false),
+            "wq" | "x" => return self.write(true),
+            "q!" => return Action::Quit,
+            "q" if self.dirty => message!(self, "No write since last change (add ! to override)"),
+            "q" => return Action::Quit,
+            cmd => match cmd.parse::<usize>() {
+                Ok(line) => self.row = line.saturating_sub(1),
+                Err(_) => message!(self, "Not an editor command: {}", cmd),
+            },
+        }
+        Action::Continue
+    }
+}
@@ -0,0 +1,76 @@
+//! # Helix Core Utilities
+//!
+//! Helpers shared by the programs in `src/bin`: error reporting, copy/move
+//! destination rules, `/proc` parsing, the text buffer behind `vi` and
+//! `less`, and the module repository index behind `helix-mod`.
+
+#![no_std]
+#![warn(missing_docs)]
+
+pub mod modrepo;
+pub mod procfs;
+pub mod text;
+
+use helix_rt::fs::{self, CPath};
+use helix_rt::io::{STDIN, STDOUT};
+use helix_rt::{eprintln, Errno, Result};
+
+/// Print `program: subject: error` to stderr
+pub fn report(program: &str, subject: &str, err: Errno) {
+    eprintln!("{}: {}: {}", fs::basename(program), subject, err);
+}
+
+/// Copy a file (`-` = standard input) to standard output
+pub fn cat(path: &str) -> Result<()> {
+    if path == "-" {
+        return fs::copy(STDIN, STDOUT).map(|_| ());
+    }
+    let fd = fs::open(path, fs::flags::O_RDONLY, 0)?;
+    let copied = fs::copy(fd, STDOUT);
+    fs::close(fd)?;
+    copied.map(|_| ())
+}
+
+/// Is `path` an existing directory?
+pub fn is_dir(path: &str) -> bool {
+    fs::stat(path).is_ok_and(|st| st.is_dir())
+}
+
+/// Where `cp`/`mv` put `source`: inside `dest` if it is a directory
+pub fn destination(source: &str, dest: &str, dest_is_dir: bool) -> Result<CPath> {
+    if dest_is_dir {
+        CPath::join(dest, fs::basename(source))
+    } else {
+        CPath::new(dest)
+    }
+}
+
+/// Split `cp`/`mv` operands into sources and destination
+///
+/// Several sources require the destination to be a directory.
+pub fn sources_and_dest<'a>(operands: &'a [&'a str]) -> core::result::Result<(&'a [&'a str], &'a str, bool), &'static str> {
+    let Some((dest, sources)) = operands.split_last() else {
+        return Err("missing file operand");
+    };
+    if sources.is_empty() {
+        return Err("missing destination file operand");
+    }
+    let dest_is_dir = is_dir(dest);
+    if sources.len() > 1 && !dest_is_dir {
+        return Err("target is not a directory");
+    }
+    Ok((sources, dest, dest_is_dir))
+}
+
+/// Collect operands into a fixed array
+pub fn operands<const N: usize>(args: &helix_rt::Args, flags: &mut u32) -> ([&'static str; N], usize) {
+    let mut list = [""; N];
+    let mut len = 0;
+    *flags = args.parse(|arg| {
+        if len < N {
+            list[len] = arg;
+            len += 1;
+        }
+    });
+    (list, len)
+}
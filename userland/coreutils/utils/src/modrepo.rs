@@ -0,0 +1,268 @@
+//! Module repository index and dependency resolution for `helix-mod`
+//!
+//! A repository is a directory holding module packages (`.hxm`) and an
+//! `index` file with one package version per line:
+//!
+//! ```text
+//! # name version abi file [dependency...]
+//! virtio-core 1.0.3 1.0-3fa2c4e19b0d7a55 virtio-core-1.0.3.hxm
+//! virtio-net 1.2.0 1.0-3fa2c4e19b0d7a55 virtio-net-1.2.0.hxm virtio-core>=1.0.0 ?trace>=0.2.0
+//! ```
+//!
+//! Dependencies use the package manifest syntax: `NAME[>=MIN][<=MAX]`,
+//! optional ones prefixed with `?`.
+
+use core::fmt;
+
+/// Index file name, relative to the repository
+pub const INDEX_FILE: &str = "index";
+
+/// Most packages one resolution may pull in
+pub const MAX_PLAN: usize = 32;
+
+/// Module version, `MAJOR.MINOR.PATCH`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(pub u16, pub u16, pub u16);
+
+impl Version {
+    /// Parse `MAJOR.MINOR.PATCH`
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.splitn(3, '.');
+        let mut next = || parts.next()?.parse().ok();
+        Some(Self(next()?, next()?, next()?))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// `MAJOR.MINOR-HASH` as its parts
+fn split_abi(abi: &str) -> Option<(u16, u16, &str)> {
+    let (version, hash) = abi.split_once('-')?;
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?, hash))
+}
+
+/// Can a module built for ABI `module` run on a kernel with ABI `kernel`?
+///
+/// The symbol hashes must match and the kernel minor must be at least the
+/// module's.
+pub fn abi_compatible(module: &str, kernel: &str) -> bool {
+    match (split_abi(module), split_abi(kernel)) {
+        (Some((major, minor, hash)), Some((k_major, k_minor, k_hash))) => {
+            hash == k_hash && major == k_major && k_minor >= minor
+        }
+        _ => false,
+    }
+}
+
+/// A dependency on another module
+#[derive(Debug, Clone, Copy)]
+pub struct Dependency<'a> {
+    /// Module name
+    pub name: &'a str,
+    /// Lowest acceptable version
+    pub min: Version,
+    /// Highest acceptable version
+    pub max: Option<Version>,
+    /// Skipped if no version is available
+    pub optional: bool,
+}
+
+impl<'a> Dependency<'a> {
+    /// Any version of `name`
+    pub fn any(name: &'a str) -> Self {
+        Self { name, min: Version(0, 0, 0), max: None, optional: false }
+    }
+
+    /// Parse `NAME[>=MIN][<=MAX]`, `?` prefix for optional
+    pub fn parse(spec: &'a str) -> Option<Self> {
+        let (optional, spec) = match spec.strip_prefix('?') {
+            Some(rest) => (true, rest),
+            None => (false, spec),
+        };
+        let (rest, max) = match spec.split_once("<=") {
+            Some((rest, max)) => (rest, Some(Version::parse(max)?)),
+            None => (spec, None),
+        };
+        let (name, min) = match rest.split_once(">=") {
+            Some((name, min)) => (name, Version::parse(min)?),
+            None => (rest, Version(0, 0, 0)),
+        };
+        if name.is_empty() || name.contains(['<', '>', '=', '?']) {
+            return None;
+        }
+        Some(Self { name, min, max, optional })
+    }
+
+    /// Does `version` satisfy the dependency?
+    pub fn allows(&self, version: Version) -> bool {
+        version >= self.min && !matches!(self.max, Some(max) if version > max)
+    }
+}
+
+/// One package version in the index
+#[derive(Debug, Clone, Copy)]
+pub struct Entry<'a> {
+    /// Module name
+    pub name: &'a str,
+    /// Version
+    pub version: Version,
+    /// ABI fingerprint it was built against
+    pub abi: &'a str,
+    /// Package file, relative to the repository
+    pub file: &'a str,
+    /// Dependency specs, space separated
+    depends: &'a str,
+}
+
+impl<'a> Entry<'a> {
+    /// Parse an index line
+    pub fn parse(line: &'a str) -> Option<Self> {
+        let mut words = line.splitn(5, ' ');
+        let name = words.next()?;
+        let version = Version::parse(words.next()?)?;
+        let abi = words.next()?;
+        let file = words.next()?;
+        if file.contains('/') {
+            return None;
+        }
+        Some(Self { name, version, abi, file, depends: words.next().unwrap_or("") })
+    }
+
+    /// Dependencies (`None` for a malformed spec)
+    pub fn dependencies(&self) -> impl Iterator<Item = Option<Dependency<'a>>> {
+        self.depends.split_whitespace().map(Dependency::parse)
+    }
+}
+
+/// Repository index
+pub struct Index<'a> {
+    text: &'a str,
+}
+
+impl<'a> Index<'a> {
+    /// Index over the contents of an index file
+    pub fn new(text: &'a str) -> Self {
+        Self { text }
+    }
+
+    /// Entries, skipping comments and malformed lines
+    pub fn entries(&self) -> impl Iterator<Item = Entry<'a>> {
+        self.text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(Entry::parse)
+    }
+
+    /// Newest version satisfying `dep`, built for `abi` if given
+    pub fn best(&self, dep: &Dependency<'_>, abi: Option<&str>) -> Option<Entry<'a>> {
+        self.entries()
+            .filter(|e| e.name == dep.name && dep.allows(e.version))
+            .filter(|e| !matches!(abi, Some(abi) if !abi_compatible(e.abi, abi)))
+            .max_by_key(|e| e.version)
+    }
+}
+
+/// Resolution failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolveError<'a> {
+    /// No version of the module satisfies the dependency
+    NotFound(&'a str),
+    /// The version already picked does not satisfy a later dependency
+    Conflict(&'a str),
+    /// The module depends on itself
+    Cycle(&'a str),
+    /// A dependency of the module is malformed
+    BadEntry(&'a str),
+    /// More than [`MAX_PLAN`] packages
+    TooMany,
+}
+
+impl fmt::Display for ResolveError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "{}: no matching version", name),
+            Self::Conflict(name) => write!(f, "{}: conflicting version requirements", name),
+            Self::Cycle(name) => write!(f, "{}: circular dependency", name),
+            Self::BadEntry(name) => write!(f, "{}: malformed index entry", name),
+            Self::TooMany => write!(f, "more than {} packages", MAX_PLAN),
+        }
+    }
+}
+
+/// Packages to fetch, dependencies first
+pub struct Plan<'a> {
+    entries: [Option<Entry<'a>>; MAX_PLAN],
+    /// Dependencies resolved (entries still being visited are on a cycle)
+    done: [bool; MAX_PLAN],
+    /// Indices into `entries`, in install order
+    order: [usize; MAX_PLAN],
+    len: usize,
+    ordered: usize,
+}
+
+impl<'a> Plan<'a> {
+    /// Resolve `roots` and their dependencies
+    ///
+    /// Each module gets the newest version satisfying the first dependency
+    /// on it; later dependencies must accept that version.
+    pub fn resolve(
+        index: &Index<'a>,
+        roots: &[Dependency<'a>],
+        abi: Option<&str>,
+    ) -> Result<Self, ResolveError<'a>> {
+        let mut plan = Self {
+            entries: [None; MAX_PLAN],
+            done: [false; MAX_PLAN],
+            order: [0; MAX_PLAN],
+            len: 0,
+            ordered: 0,
+        };
+        for root in roots {
+            plan.visit(index, root, abi)?;
+        }
+        Ok(plan)
+    }
+
+    fn visit(&mut self, index: &Index<'a>, dep: &Dependency<'a>, abi: Option<&str>) -> Result<(), ResolveError<'a>> {
+        if let Some(i) = self.entries[..self.len].iter().position(|e| e.is_some_and(|e| e.name == dep.name)) {
+            let entry = self.entries[i].unwrap();
+            if !dep.allows(entry.version) {
+                return Err(ResolveError::Conflict(dep.name));
+            }
+            if !self.done[i] {
+                return Err(ResolveError::Cycle(dep.name));
+            }
+            return Ok(());
+        }
+
+        let Some(entry) = index.best(dep, abi) else {
+            return if dep.optional { Ok(()) } else { Err(ResolveError::NotFound(dep.name)) };
+        };
+        if self.len == MAX_PLAN {
+            return Err(ResolveError::TooMany);
+        }
+        let i = self.len;
+        self.entries[i] = Some(entry);
+        self.len += 1;
+
+        for child in entry.dependencies() {
+            let child = child.ok_or(ResolveError::BadEntry(entry.name))?;
+            self.visit(index, &child, abi)?;
+        }
+        self.done[i] = true;
+        self.order[self.ordered] = i;
+        self.ordered += 1;
+        Ok(())
+    }
+
+    /// Packages in install order
+    pub fn iter(&self) -> impl Iterator<Item = &Entry<'a>> {
+        self.order[..self.ordered].iter().filter_map(|&i| self.entries[i].as_ref())
+    }
+}
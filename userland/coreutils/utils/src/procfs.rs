@@ -0,0 +1,183 @@
+//! `/proc` readers for `ps` and `top`
+
+use helix_rt::fs::{self, ReadDir};
+use helix_rt::io::StackString;
+use helix_rt::Result;
+
+/// One line of `/proc/<pid>/stat`
+#[derive(Debug, Clone, Copy)]
+pub struct ProcStat {
+    /// Process ID
+    pub pid: u32,
+    /// Command name (truncated)
+    pub comm: StackName,
+    /// State letter (`R`, `S`, `T`, `Z`, ...)
+    pub state: char,
+    /// Parent process ID
+    pub ppid: u32,
+    /// Process group
+    pub pgrp: u32,
+    /// User time (ticks)
+    pub utime: u64,
+    /// System time (ticks)
+    pub stime: u64,
+    /// Number of threads
+    pub threads: u32,
+}
+
+/// Command name buffer
+#[derive(Debug, Clone, Copy)]
+pub struct StackName {
+    buf: [u8; 16],
+    len: usize,
+}
+
+impl StackName {
+    fn new(name: &str) -> Self {
+        let mut buf = [0; 16];
+        let mut len = name.len().min(buf.len());
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        buf[..len].copy_from_slice(&name.as_bytes()[..len]);
+        Self { buf, len }
+    }
+
+    /// Name
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("?")
+    }
+}
+
+impl ProcStat {
+    /// Parse `pid (comm) state ppid pgrp session tty tpgid flags minflt
+    /// cminflt majflt cmajflt utime stime cutime cstime priority nice threads ...`
+    pub fn parse(line: &str) -> Option<Self> {
+        let open = line.find('(')?;
+        let close = line.rfind(')')?;
+        let pid = line[..open].trim().parse().ok()?;
+        let comm = StackName::new(&line[open + 1..close]);
+
+        let mut fields = line[close + 1..].split_whitespace();
+        let state = fields.next()?.chars().next()?;
+        let mut field = |skip: usize| fields.nth(skip);
+        let ppid = field(0)?.parse().ok()?;
+        let pgrp = field(0)?.parse().ok()?;
+        let utime = field(8)?.parse().ok()?;
+        let stime = field(0)?.parse().ok()?;
+        let threads = field(4)?.parse().ok()?;
+
+        Some(Self { pid, comm, state, ppid, pgrp, utime, stime, threads })
+    }
+
+    /// Read `/proc/<pid>/stat`
+    pub fn read(pid: u32) -> Result<Self> {
+        let mut path = StackString::<32>::new();
+        let _ = core::fmt::Write::write_fmt(&mut path, format_args!("/proc/{}/stat", pid));
+        let mut buf = [0u8; 512];
+        let len = read_file(path.as_str(), &mut buf)?;
+        core::str::from_utf8(&buf[..len])
+            .ok()
+            .and_then(Self::parse)
+            .ok_or(helix_rt::Errno::EIO)
+    }
+}
+
+/// Read up to `buf.len()` bytes of `path`; returns the length read
+pub fn read_file(path: &str, buf: &mut [u8]) -> Result<usize> {
+    let fd = fs::open(path, fs::flags::O_RDONLY, 0)?;
+    let mut len = 0;
+    let result = loop {
+        match fs::read(fd, &mut buf[len..]) {
+            Ok(0) => break Ok(len),
+            Ok(n) => {
+                len += n;
+                if len == buf.len() {
+                    break Ok(len);
+                }
+            }
+            Err(e) => break Err(e),
+        }
+    };
+    fs::close(fd)?;
+    result
+}
+
+/// Call `f` for every process listed in `/proc`
+pub fn for_each_process(mut f: impl FnMut(&ProcStat)) -> Result<usize> {
+    let mut dir = ReadDir::open("/proc")?;
+    let mut count = 0;
+    while let Some(entry) = dir.next_entry()? {
+        let Ok(pid) = entry.name.parse::<u32>() else {
+            continue;
+        };
+        // The process may have exited since the listing
+        if let Ok(stat) = ProcStat::read(pid) {
+            f(&stat);
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Print the `ps` header
+pub fn print_header() {
+    helix_rt::println!("  PID  PPID  PGRP S THR     TIME COMMAND");
+}
+
+/// Print one process in `ps` format (`TIME` in seconds at 100 ticks/s)
+pub fn print_process(p: &ProcStat) {
+    let secs = (p.utime + p.stime) / 100;
+    helix_rt::println!(
+        "{:>5} {:>5} {:>5} {} {:>3} {:>5}:{:02} {}",
+        p.pid,
+        p.ppid,
+        p.pgrp,
+        p.state,
+        p.threads,
+        secs / 60,
+        secs % 60,
+        p.comm.as_str()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat() {
+        let line = "42 (sh) S 1 42 42 0 -1 0 10 0 0 0 7 3 0 0 20 0 2 0 100 4096 12";
+        let stat = ProcStat::parse(line).unwrap();
+
+        assert_eq!(stat.pid, 42);
+        assert_eq!(stat.comm.as_str(), "sh");
+        assert_eq!(stat.state, 'S');
+        assert_eq!((stat.ppid, stat.pgrp), (1, 42));
+        assert_eq!((stat.utime, stat.stime), (7, 3));
+        assert_eq!(stat.threads, 2);
+    }
+
+    #[test]
+    fn test_parse_comm() {
+        // Parentheses and spaces inside the name
+        let line = "7 (a (b) c) R 1 7 7 0 -1 0 0 0 0 0 1 2 0 0 20 0 1 0";
+        let stat = ProcStat::parse(line).unwrap();
+        assert_eq!(stat.comm.as_str(), "a (b) c");
+        assert_eq!(stat.state, 'R');
+
+        // Truncated at 16 bytes, on a character boundary
+        let line = "8 (abcdefghijklmnopq) R 1 8 8 0 -1 0 0 0 0 0 1 2 0 0 20 0 1 0";
+        assert_eq!(ProcStat::parse(line).unwrap().comm.as_str(), "abcdefghijklmnop");
+        let line = "9 (abcdefghijklmnoö) R 1 9 9 0 -1 0 0 0 0 0 1 2 0 0 20 0 1 0";
+        assert_eq!(ProcStat::parse(line).unwrap().comm.as_str(), "abcdefghijklmno");
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        assert!(ProcStat::parse("").is_none());
+        assert!(ProcStat::parse("x (sh) S 1 1").is_none());
+        assert!(ProcStat::parse("1 (sh) S 1 1 1 0 -1 0 0 0 0 0 1").is_none());
+        assert!(ProcStat::parse("1 sh S 1 1 1 0 -1 0 0 0 0 0 1 2 0 0 20 0 1 0").is_none());
+    }
+}
@@ -0,0 +1,305 @@
+//! Line-oriented text buffer for `vi` and `less`
+//!
+//! Text lives in a fixed, statically allocated [`Storage`]; lines are
+//! found by scanning for `\n`, which is fast enough at these sizes.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use helix_rt::fs::{self, Fd};
+use helix_rt::io::StackString;
+use helix_rt::tty::{self, Key, RawMode};
+use helix_rt::{print, Errno, Result};
+
+/// Statically allocated backing store, handed out once
+pub struct Storage<const N: usize> {
+    taken: AtomicBool,
+    buf: UnsafeCell<[u8; N]>,
+}
+
+// SAFETY: `take` hands out the buffer at most once
+unsafe impl<const N: usize> Sync for Storage<N> {}
+
+impl<const N: usize> Storage<N> {
+    /// Zeroed storage
+    pub const fn new() -> Self {
+        Self { taken: AtomicBool::new(false), buf: UnsafeCell::new([0; N]) }
+    }
+
+    /// Sole owner of the buffer, or `None` if already taken
+    pub fn take(&'static self) -> Option<Taken<N>> {
+        if self.taken.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        Some(Taken(self))
+    }
+}
+
+impl<const N: usize> Default for Storage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The buffer of a [`Storage`], owned by whoever took it
+pub struct Taken<const N: usize>(&'static Storage<N>);
+
+impl<const N: usize> Taken<N> {
+    /// The buffer
+    pub fn buf(&mut self) -> &mut [u8] {
+        // SAFETY: only one `Taken` exists per storage, and it is borrowed
+        // mutably for as long as the reference lives
+        unsafe { &mut *self.0.buf.get() }
+    }
+}
+
+/// Text in a fixed buffer
+pub struct Text<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Text<'a> {
+    /// Empty text over `buf`
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    /// Append everything readable from `fd`; `EFBIG` if it does not fit
+    pub fn read_from(&mut self, fd: Fd) -> Result<()> {
+        loop {
+            if self.len == self.buf.len() {
+                let mut probe = [0u8];
+                return match fs::read(fd, &mut probe)? {
+                    0 => Ok(()),
+                    _ => Err(Errno::EFBIG),
+                };
+            }
+            match fs::read(fd, &mut self.buf[self.len..])? {
+                0 => return Ok(()),
+                n => self.len += n,
+            }
+        }
+    }
+
+    /// Append the contents of `path`
+    pub fn load(&mut self, path: &str) -> Result<()> {
+        let fd = fs::open(path, fs::flags::O_RDONLY, 0)?;
+        let loaded = self.read_from(fd);
+        fs::close(fd)?;
+        loaded
+    }
+
+    /// Replace `path` with the text
+    pub fn save(&self, path: &str) -> Result<()> {
+        let fd = fs::open(path, fs::flags::O_WRONLY | fs::flags::O_CREAT | fs::flags::O_TRUNC, 0o644)?;
+        let written = fs::write_all(fd, self.as_bytes());
+        fs::close(fd)?;
+        written
+    }
+
+    /// Contents
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Length in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Is the text empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of lines; a final `\n` does not start another line
+    pub fn line_count(&self) -> usize {
+        let newlines = self.as_bytes().iter().filter(|&&b| b == b'\n').count();
+        match self.as_bytes().last() {
+            None | Some(b'\n') => newlines,
+            Some(_) => newlines + 1,
+        }
+    }
+
+    /// Byte range of line `n`, without its `\n`
+    ///
+    /// The line after the last one is the empty range at the end of the text.
+    pub fn line_range(&self, n: usize) -> (usize, usize) {
+        let bytes = self.as_bytes();
+        let mut start = 0;
+        for _ in 0..n {
+            match bytes[start..].iter().position(|&b| b == b'\n') {
+                Some(i) => start += i + 1,
+                None => return (self.len, self.len),
+            }
+        }
+        let end = bytes[start..].iter().position(|&b| b == b'\n').map_or(self.len, |i| start + i);
+        (start, end)
+    }
+
+    /// Line `n`, without its `\n`
+    pub fn line(&self, n: usize) -> &[u8] {
+        let (start, end) = self.line_range(n);
+        &self.buf[start..end]
+    }
+
+    /// Insert `bytes` at `pos`; `ENOSPC` if the buffer is full
+    pub fn insert(&mut self, pos: usize, bytes: &[u8]) -> Result<()> {
+        let end = self.len + bytes.len();
+        if end > self.buf.len() {
+            return Err(Errno::ENOSPC);
+        }
+        self.buf.copy_within(pos..self.len, pos + bytes.len());
+        self.buf[pos..pos + bytes.len()].copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    /// Remove the bytes in `start..end`
+    pub fn remove(&mut self, start: usize, end: usize) {
+        self.buf.copy_within(end..self.len, start);
+        self.len -= end - start;
+    }
+
+    /// First line at or after `from` containing `pattern`, wrapping around
+    pub fn find_line(&self, from: usize, pattern: &[u8]) -> Option<usize> {
+        if pattern.is_empty() {
+            return None;
+        }
+        let lines = self.line_count();
+        (0..lines)
+            .map(|i| (from + i) % lines)
+            .find(|&n| self.line(n).windows(pattern.len()).any(|w| w == pattern))
+    }
+}
+
+/// Screen column of byte `col` in `line`, with tabs every 8 columns
+pub fn display_col(line: &[u8], col: usize) -> usize {
+    line.iter().take(col).fold(0, |x, &b| if b == b'\t' { (x / 8 + 1) * 8 } else { x + 1 })
+}
+
+/// Print the part of `line` between screen columns `left` and `left + width`
+///
+/// Tabs are expanded and other control characters shown as `?`.
+pub fn print_line(line: &[u8], left: usize, width: usize) {
+    let mut out = [0u8; 256];
+    let mut len = 0;
+    let mut x = 0;
+    for &b in line {
+        let (ch, count) = match b {
+            b'\t' => (b' ', (x / 8 + 1) * 8 - x),
+            0x20..=0x7e => (b, 1),
+            _ => (b'?', 1),
+        };
+        for _ in 0..count {
+            if x >= left && x < left + width && len < out.len() {
+                out[len] = ch;
+                len += 1;
+            }
+            x += 1;
+        }
+        if x >= left + width {
+            break;
+        }
+    }
+    print!("{}", core::str::from_utf8(&out[..len]).unwrap_or(""));
+}
+
+/// Read a line of input on screen row `row`, after `prefix`
+///
+/// Returns `false` if cancelled with `Esc` or by erasing past the start.
+pub fn prompt<const N: usize>(raw: &RawMode, row: usize, prefix: &str, input: &mut StackString<N>) -> bool {
+    input.clear();
+    loop {
+        tty::move_to(row, 0);
+        print!("{}{}", prefix, input.as_str());
+        tty::clear_line();
+        match tty::read_key(raw) {
+            Ok(Some(Key::Enter)) => return true,
+            Ok(Some(Key::Backspace)) => {
+                if input.pop().is_none() {
+                    return false;
+                }
+            }
+            Ok(Some(Key::Char(c))) if c.is_ascii_graphic() || c == b' ' => {
+                let _ = core::fmt::Write::write_char(input, c as char);
+            }
+            Ok(Some(Key::Esc) | None) | Err(_) => return false,
+            Ok(Some(_)) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text<'a>(buf: &'a mut [u8], contents: &[u8]) -> Text<'a> {
+        let mut text = Text::new(buf);
+        text.insert(0, contents).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_storage_taken_once() {
+        static STORAGE: Storage<16> = Storage::new();
+
+        let mut taken = STORAGE.take().unwrap();
+        assert_eq!(taken.buf().len(), 16);
+        assert!(STORAGE.take().is_none());
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut buf = [0u8; 64];
+        let text = text(&mut buf, b"one\ntwo\n\nfour");
+
+        assert_eq!(text.line_count(), 4);
+        assert_eq!(text.line(0), b"one");
+        assert_eq!(text.line(1), b"two");
+        assert_eq!(text.line(2), b"");
+        assert_eq!(text.line(3), b"four");
+        assert_eq!(text.line_range(4), (text.len(), text.len()));
+
+        let mut buf = [0u8; 64];
+        assert_eq!(self::text(&mut buf, b"one\n").line_count(), 1);
+        assert_eq!(Text::new(&mut [0u8; 4]).line_count(), 0);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut buf = [0u8; 8];
+        let mut text = text(&mut buf, b"ac\n");
+
+        text.insert(1, b"b").unwrap();
+        assert_eq!(text.as_bytes(), b"abc\n");
+        text.remove(0, 2);
+        assert_eq!(text.as_bytes(), b"c\n");
+
+        text.insert(2, b"123456").unwrap();
+        assert_eq!(text.insert(0, b"x"), Err(Errno::ENOSPC));
+        assert_eq!(text.as_bytes(), b"c\n123456");
+    }
+
+    #[test]
+    fn test_find_line() {
+        let mut buf = [0u8; 64];
+        let text = text(&mut buf, b"alpha\nbeta\ngamma\n");
+
+        assert_eq!(text.find_line(0, b"ta"), Some(1));
+        assert_eq!(text.find_line(2, b"al"), Some(0));
+        assert_eq!(text.find_line(1, b"a"), Some(1));
+        assert_eq!(text.find_line(0, b"delta"), None);
+        assert_eq!(text.find_line(0, b""), None);
+    }
+
+    #[test]
+    fn test_display_col() {
+        assert_eq!(display_col(b"abc", 2), 2);
+        assert_eq!(display_col(b"\tx", 1), 8);
+        assert_eq!(display_col(b"ab\tx", 3), 8);
+        assert_eq!(display_col(b"ab\tx", 4), 9);
+        assert_eq!(display_col(b"ab", 10), 2);
+    }
+}